@@ -1,14 +1,23 @@
 /// API request handlers
-use crate::agent::builder::AgentBuilder;
+use crate::agent::builder::{create_embeddings_client_from_config, AgentBuilder};
 use crate::agent::core::AgentCore;
 use crate::api::mesh::{MeshRegistry, MeshState};
+use crate::api::middleware::{bad_request, RequestLimits};
 use crate::api::models::*;
+use crate::api::session_queue::{SessionBusy, SessionQueues};
+use crate::api::triggers::TriggerJobSlots;
 use crate::config::{AgentRegistry, AppConfig};
+use crate::embeddings::EmbeddingsClient;
+use crate::mesh::CapabilityCatalog;
+use crate::persistence::memories::MemoryDeletion;
+use crate::persistence::search::SemanticSearchFilter;
 use crate::persistence::Persistence;
+use crate::quota::{QuotaExceeded, QuotaTracker};
 use crate::tools::ToolRegistry;
+use crate::tools::{CancellationToken, ChannelToolOutputSink};
 use async_stream::stream;
 use axum::{
-    extract::{Json, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
     response::{
         sse::{Event, Sse},
@@ -20,7 +29,21 @@ use serde_json::json;
 use std::convert::Infallible;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+
+/// Upper bound on `limit` accepted by `/v1/search`
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Memories returned by `/v1/sessions/:id/memory` when no `limit` is given
+const DEFAULT_MEMORY_LIMIT: usize = 50;
+
+/// Upper bound on `limit` accepted by `/v1/sessions/:id/memory`
+const MAX_MEMORY_LIMIT: usize = 500;
+
+/// Decisions returned by `/v1/policy/audit` when no `limit` is given
+const DEFAULT_POLICY_AUDIT_LIMIT: usize = 50;
+
+/// Upper bound on `limit` accepted by `/v1/policy/audit`
+const MAX_POLICY_AUDIT_LIMIT: usize = 1000;
 
 /// Shared application state
 #[derive(Clone)]
@@ -31,6 +54,18 @@ pub struct AppState {
     pub config: AppConfig,
     pub start_time: Instant,
     pub mesh_registry: MeshRegistry,
+    /// Embeds search queries; `None` when no embeddings model is configured
+    pub embeddings: Option<EmbeddingsClient>,
+    /// Id this instance is known by in the mesh
+    pub instance_id: String,
+    /// Enforces `[mesh.quotas]` on agent runs
+    pub quotas: Arc<QuotaTracker>,
+    /// Caps on request sizes and query fields
+    pub limits: RequestLimits,
+    /// Serializes agent steps per session
+    pub session_queues: SessionQueues,
+    /// Caps the webhook trigger jobs queued and running
+    pub trigger_jobs: TriggerJobSlots,
 }
 
 impl AppState {
@@ -40,38 +75,103 @@ impl AppState {
         tool_registry: Arc<ToolRegistry>,
         config: AppConfig,
     ) -> Self {
+        let embeddings = match create_embeddings_client_from_config(&config) {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!("Semantic search disabled: {}", err);
+                None
+            }
+        };
+        let instance_id = persistence.instance_id().to_string();
         Self {
+            quotas: Arc::new(QuotaTracker::new(
+                instance_id.clone(),
+                config.mesh.quotas.clone(),
+            )),
+            instance_id,
             persistence: persistence.clone(),
             agent_registry,
             tool_registry,
             config,
             start_time: Instant::now(),
             mesh_registry: MeshRegistry::with_persistence(persistence),
+            embeddings,
+            limits: RequestLimits::default(),
+            session_queues: SessionQueues::default(),
+            trigger_jobs: TriggerJobSlots::default(),
         }
     }
+
+    /// Catalog of this instance's agents, tools, providers and features
+    pub fn capability_catalog(&self) -> CapabilityCatalog {
+        CapabilityCatalog::build(
+            self.instance_id.clone(),
+            &self.config,
+            &self.agent_registry,
+            &self.tool_registry,
+            &self.config.mesh.shared_tools,
+        )
+    }
+
+    /// Replace the client used to embed search queries
+    pub fn with_embeddings(mut self, embeddings: EmbeddingsClient) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
 }
 
 impl MeshState for AppState {
     fn mesh_registry(&self) -> &MeshRegistry {
         &self.mesh_registry
     }
+
+    fn mesh_stale_after_secs(&self) -> u64 {
+        self.config.mesh.leader_timeout_secs
+    }
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "info",
+    responses((status = 200, description = "Server status and session queue depths", body = HealthResponse))
+)]
 pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
     let uptime = state.start_time.elapsed().as_secs();
 
+    let session_queues = state.session_queues.depths();
     let response = HealthResponse {
         status: "healthy".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         uptime_seconds: uptime,
-        active_sessions: 0, // TODO: Track active sessions
+        active_sessions: session_queues.len(),
+        queued_steps: state.session_queues.waiting(),
+        session_queues,
     };
 
     Json(response)
 }
 
+/// Agents, tools (with schemas), model providers, features and version of
+/// this instance
+#[utoipa::path(
+    get,
+    path = "/v1/capabilities",
+    tag = "info",
+    responses((status = 200, description = "Capability catalog of this instance", body = Object))
+)]
+pub async fn capabilities(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.capability_catalog())
+}
+
 /// List available agents
+#[utoipa::path(
+    get,
+    path = "/agents",
+    tag = "agents",
+    responses((status = 200, description = "Configured agent profiles", body = AgentListResponse))
+)]
 pub async fn list_agents(State(state): State<AppState>) -> impl IntoResponse {
     let agent_names = state.agent_registry.list();
     let mut agent_infos = Vec::new();
@@ -93,8 +193,334 @@ pub async fn list_agents(State(state): State<AppState>) -> impl IntoResponse {
     .into_response()
 }
 
+/// List sessions with their titles and metadata
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Sessions, most recently active first", body = SessionListResponse),
+        (status = 500, description = "Sessions could not be read", body = ErrorResponse)
+    )
+)]
+pub async fn list_sessions(State(state): State<AppState>) -> Response {
+    match state.persistence.list_session_records() {
+        Ok(records) => Json(SessionListResponse {
+            sessions: records.into_iter().map(SessionInfo::from).collect(),
+        })
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "session_error",
+                format!("Failed to list sessions: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Metadata of a single session
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}",
+    tag = "sessions",
+    params(("session_id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Session metadata", body = SessionInfo),
+        (status = 404, description = "No such session", body = ErrorResponse),
+        (status = 500, description = "Session could not be read", body = ErrorResponse)
+    )
+)]
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Response {
+    match state.persistence.get_session(&session_id) {
+        Ok(Some(record)) => Json(SessionInfo::from(record)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                "session_not_found",
+                format!("Session '{}' not found", session_id),
+            )),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "session_error",
+                format!("Failed to load session: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Semantic search over messages, transcriptions and graph nodes of all sessions
+#[utoipa::path(
+    post,
+    path = "/v1/search",
+    tag = "memory",
+    request_body = SearchRequest,
+    responses(
+        (status = 200, description = "Results, most similar first", body = SearchResponse),
+        (status = 400, description = "Empty query", body = ErrorResponse),
+        (status = 502, description = "The query could not be embedded", body = ErrorResponse),
+        (status = 503, description = "No embeddings model is configured", body = ErrorResponse)
+    )
+)]
+pub async fn semantic_search(
+    State(state): State<AppState>,
+    Json(request): Json<SearchRequest>,
+) -> Response {
+    if request.query.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "invalid_request",
+                "query must not be empty",
+            )),
+        )
+            .into_response();
+    }
+    let Some(embeddings) = state.embeddings.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                "embeddings_unavailable",
+                "Semantic search requires model.embeddings_model to be configured",
+            )),
+        )
+            .into_response();
+    };
+
+    let query_embedding = match embeddings.embed(&request.query).await {
+        Ok(embedding) => embedding,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse::new(
+                    "embeddings_error",
+                    format!("Failed to embed query: {}", e),
+                )),
+            )
+                .into_response()
+        }
+    };
+
+    let filter = SemanticSearchFilter {
+        namespace: request.namespace.clone(),
+        agent: request.agent.clone(),
+        since: request.from,
+        until: request.to,
+    };
+    let limit = request.limit.clamp(1, MAX_SEARCH_LIMIT);
+    match state
+        .persistence
+        .semantic_search(&query_embedding, &filter, limit)
+    {
+        Ok(hits) => Json(SearchResponse {
+            query: request.query,
+            results: hits.into_iter().map(SearchResult::from).collect(),
+        })
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "search_error",
+                format!("Search failed: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Recallable memories of a session, optionally ranked against `q`
+#[utoipa::path(
+    get,
+    path = "/v1/sessions/{session_id}/memory",
+    tag = "memory",
+    params(("session_id" = String, Path, description = "Session ID"), MemoryListQuery),
+    responses(
+        (status = 200, description = "Memories, newest or most relevant first", body = MemoryListResponse),
+        (status = 502, description = "`q` could not be embedded", body = ErrorResponse)
+    )
+)]
+pub async fn session_memory(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+    Query(params): Query<MemoryListQuery>,
+) -> Response {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_MEMORY_LIMIT)
+        .clamp(1, MAX_MEMORY_LIMIT);
+    let query = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let result = match query {
+        Some(query) => {
+            // Rank by similarity when queries can be embedded, else by text
+            let query_embedding = match state.embeddings.as_ref() {
+                Some(embeddings) => match embeddings.embed(query).await {
+                    Ok(embedding) => Some(embedding),
+                    Err(e) => {
+                        return (
+                            StatusCode::BAD_GATEWAY,
+                            Json(ErrorResponse::new(
+                                "embeddings_error",
+                                format!("Failed to embed query: {}", e),
+                            )),
+                        )
+                            .into_response()
+                    }
+                },
+                None => None,
+            };
+            state
+                .persistence
+                .search_memories(&session_id, query, query_embedding.as_deref(), limit)
+        }
+        None => state
+            .persistence
+            .list_memories(&session_id)
+            .map(|mut memories| {
+                memories.truncate(limit);
+                memories
+            }),
+    };
+
+    match result {
+        Ok(memories) => Json(MemoryListResponse {
+            session_id,
+            memories: memories.into_iter().map(MemoryInfo::from).collect(),
+        })
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "memory_error",
+                format!("Failed to list memories: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Delete one memory so recall stops returning it
+#[utoipa::path(
+    delete,
+    path = "/v1/memory/{vector_id}",
+    tag = "memory",
+    params(("vector_id" = i64, Path, description = "Memory ID from the memory listing")),
+    responses(
+        (status = 200, description = "The deleted memory", body = MemoryDeleteResponse),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 404, description = "No such memory", body = ErrorResponse),
+        (status = 409, description = "The memory belongs to a graph node", body = ErrorResponse)
+    )
+)]
+pub async fn delete_memory(State(state): State<AppState>, Path(vector_id): Path<i64>) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    match state.persistence.delete_memory(vector_id) {
+        Ok(MemoryDeletion::Deleted(entry)) => Json(MemoryDeleteResponse {
+            deleted: MemoryInfo::from(entry),
+        })
+        .into_response(),
+        Ok(MemoryDeletion::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                "memory_not_found",
+                format!("Memory {} not found", vector_id),
+            )),
+        )
+            .into_response(),
+        Ok(MemoryDeletion::LinkedToNode { node_id }) => (
+            StatusCode::CONFLICT,
+            Json(
+                ErrorResponse::new(
+                    "memory_linked_to_node",
+                    format!(
+                        "Memory {} belongs to graph node {}; delete the node instead",
+                        vector_id, node_id
+                    ),
+                )
+                .with_details(json!({ "node_id": node_id })),
+            ),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "memory_error",
+                format!("Failed to delete memory: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Latest policy decisions on tool calls, to see why one was denied
+#[utoipa::path(
+    get,
+    path = "/v1/policy/audit",
+    tag = "policy",
+    params(PolicyAuditQuery),
+    responses(
+        (status = 200, description = "Decisions, newest first", body = PolicyAuditResponse)
+    )
+)]
+pub async fn policy_audit(
+    State(state): State<AppState>,
+    Query(params): Query<PolicyAuditQuery>,
+) -> Response {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_POLICY_AUDIT_LIMIT)
+        .clamp(1, MAX_POLICY_AUDIT_LIMIT);
+    match state
+        .persistence
+        .policy_audit(params.agent.as_deref(), limit)
+    {
+        Ok(entries) => Json(PolicyAuditResponse {
+            decisions: entries.into_iter().map(PolicyDecisionInfo::from).collect(),
+        })
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "policy_error",
+                format!("Failed to list policy decisions: {}", e),
+            )),
+        )
+            .into_response(),
+    }
+}
+
 /// Query endpoint - process a message and return response
+#[utoipa::path(
+    post,
+    path = "/query",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "The agent's answer", body = QueryResponse),
+        (status = 400, description = "Invalid request or unknown agent", body = ErrorResponse),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 429, description = "The session is busy or a quota is used up", body = ErrorResponse),
+        (status = 500, description = "The step failed", body = ErrorResponse)
+    )
+)]
 pub async fn query(State(state): State<AppState>, Json(request): Json<QueryRequest>) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    if let Err(error) = request.validate(&state.limits) {
+        return bad_request(error);
+    }
+
     // If streaming requested, delegate to streaming handler
     if request.stream {
         return (
@@ -115,6 +541,12 @@ pub async fn query(State(state): State<AppState>, Json(request): Json<QueryReque
         .session_id
         .unwrap_or_else(|| format!("api_{}", uuid_v4()));
 
+    // Wait for earlier steps of this session to finish
+    let _turn = match state.session_queues.reserve(&session_id) {
+        Ok(ticket) => ticket.wait().await,
+        Err(busy) => return session_busy(&busy),
+    };
+
     // Create agent instance
     let agent_result = create_agent(&state, &agent_name, &session_id, request.temperature).await;
 
@@ -129,11 +561,19 @@ pub async fn query(State(state): State<AppState>, Json(request): Json<QueryReque
         }
     };
 
+    let permit = match state.quotas.begin_run(&agent_name) {
+        Ok(permit) => permit,
+        Err(err) => return quota_exceeded(&err),
+    };
+
     // Process the message
     let start = Instant::now();
 
     match agent.run_step(&request.message).await {
         Ok(output) => {
+            if let Some(usage) = &output.token_usage {
+                permit.record_tokens(u64::from(usage.total_tokens));
+            }
             let processing_time = start.elapsed().as_millis() as u64;
             let tool_calls: Vec<ToolCallInfo> = output
                 .tool_invocations
@@ -144,6 +584,7 @@ pub async fn query(State(state): State<AppState>, Json(request): Json<QueryReque
                     success: inv.success,
                     output: inv.output.clone(),
                     error: inv.error.clone(),
+                    cached: inv.cached,
                 })
                 .collect();
 
@@ -171,19 +612,43 @@ pub async fn query(State(state): State<AppState>, Json(request): Json<QueryReque
 }
 
 /// Streaming query endpoint
+#[utoipa::path(
+    post,
+    path = "/stream",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Server-sent events carrying one StreamChunk each", content_type = "text/event-stream", body = StreamChunk),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 429, description = "The session is busy or a quota is used up", body = ErrorResponse)
+    )
+)]
 pub async fn stream_query(
     State(state): State<AppState>,
     Json(request): Json<QueryRequest>,
 ) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    if let Err(error) = request.validate(&state.limits) {
+        return bad_request(error);
+    }
     let agent_name = request.agent.unwrap_or_else(|| "default".to_string());
     let session_id = request
         .session_id
         .unwrap_or_else(|| format!("api_{}", uuid_v4()));
 
+    // Held until the stream ends, so the step runs alone in its session
+    let turn = match state.session_queues.reserve(&session_id) {
+        Ok(ticket) => ticket.wait().await,
+        Err(busy) => return session_busy(&busy),
+    };
+
     // Create agent
     let agent_result = create_agent(&state, &agent_name, &session_id, request.temperature).await;
 
-    let agent = match agent_result {
+    let mut agent = match agent_result {
         Ok(agent) => agent,
         Err(e) => {
             return (
@@ -194,24 +659,57 @@ pub async fn stream_query(
         }
     };
 
-    // Create SSE stream
-    let agent = Arc::new(RwLock::new(agent));
+    let permit = match state.quotas.begin_run(&agent_name) {
+        Ok(permit) => permit,
+        Err(err) => return quota_exceeded(&err),
+    };
+
+    // The step runs on its own task, holding the session's turn. A client
+    // that disconnects cancels the tools still running, so the step ends
+    // soon after instead of running on unseen.
+    let cancel = CancellationToken::new();
+    agent.set_tool_cancellation(cancel.clone());
+    let disconnect = cancel.drop_guard();
+    let (sink, mut tool_output) = ChannelToolOutputSink::new();
+    agent.set_tool_output_sink(Arc::new(sink));
     let message = request.message.clone();
+    let start = Instant::now();
+    let mut step = tokio::spawn(async move {
+        let _turn = turn;
+        agent.run_step(&message).await
+    });
+
+    // Create SSE stream
     let session_id_clone = session_id.clone();
     let agent_name_clone = agent_name.clone();
     let model_id = state.config.model.provider.clone();
 
     let sse_stream = stream! {
+        let _disconnect = disconnect;
         yield StreamChunk::Start {
             session_id: session_id_clone.clone(),
             agent: agent_name_clone.clone(),
         };
 
-        let start = Instant::now();
-        let mut agent_lock = agent.write().await;
+        // Forward tools' output while the step runs
+        let result = loop {
+            let chunk = tokio::select! {
+                result = &mut step => break result,
+                Some(chunk) = tool_output.recv() => chunk,
+            };
+            yield StreamChunk::ToolOutput { name: chunk.tool, text: chunk.text };
+        };
+        while let Ok(chunk) = tool_output.try_recv() {
+            yield StreamChunk::ToolOutput { name: chunk.tool, text: chunk.text };
+        }
 
-        match agent_lock.run_step(&message).await {
+        let result =
+            result.unwrap_or_else(|err| Err(anyhow::anyhow!("agent step failed: {}", err)));
+        match result {
             Ok(output) => {
+                if let Some(usage) = &output.token_usage {
+                    permit.record_tokens(u64::from(usage.total_tokens));
+                }
                 yield StreamChunk::Content { text: output.response.clone() };
 
                 for invocation in output.tool_invocations {
@@ -253,8 +751,41 @@ pub async fn stream_query(
     .into_response()
 }
 
+/// Helper: 429 response for a step refused because its session is busy
+fn session_busy(busy: &SessionBusy) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(
+            ErrorResponse::new(SessionBusy::CODE, busy.to_string())
+                .with_details(serde_json::to_value(busy).unwrap_or_default()),
+        ),
+    )
+        .into_response()
+}
+
+/// Helper: 429 response for a run refused by a quota
+fn quota_exceeded(err: &QuotaExceeded) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ErrorResponse::quota_exceeded(err)),
+    )
+        .into_response()
+}
+
+/// Helper: 403 response for a run or write sent to a read replica
+pub(crate) fn read_only_replica() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse::new(
+            "read_only_replica",
+            "This instance is a read replica; send runs and writes to a worker or leader",
+        )),
+    )
+        .into_response()
+}
+
 /// Helper: Create agent instance
-async fn create_agent(
+pub(crate) async fn create_agent(
     state: &AppState,
     agent_name: &str,
     session_id: &str,
@@ -280,7 +811,7 @@ async fn create_agent(
 }
 
 /// Helper: Generate UUID v4
-fn uuid_v4() -> String {
+pub(crate) fn uuid_v4() -> String {
     let rng = std::collections::hash_map::RandomState::new();
     let hash = std::hash::BuildHasher::hash_one(&rng, SystemTime::now());
     format!("{:x}", hash)
@@ -1,4 +1,11 @@
+use crate::api::models::ErrorResponse;
+use crate::config::MeshRole;
+use crate::mesh::{
+    CapabilityCatalog, InstanceHealth, InstanceStats, MeshStatsResponse, MESH_STATS_RETENTION_SECS,
+};
+use crate::mesh_invite::{JoinTokenError, MeshKeyring};
 use crate::persistence::Persistence;
+use crate::quota::{check_run, QuotaExceeded, QuotaKind};
 use anyhow::Result;
 /// Mesh registry handlers and models
 use axum::{
@@ -8,7 +15,7 @@ use axum::{
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -23,6 +30,14 @@ pub struct MeshInstance {
     pub last_heartbeat: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub agent_profiles: Vec<String>,
+    /// Tools this instance runs for other members as `remote:<tool>`
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Full catalog, when the instance sent one at registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<CapabilityCatalog>,
+    #[serde(default)]
+    pub role: MeshRole,
 }
 
 /// Request to register a new instance
@@ -33,6 +48,17 @@ pub struct RegisterRequest {
     pub port: u16,
     pub capabilities: Vec<String>,
     pub agent_profiles: Vec<String>,
+    /// Tools offered for remote execution
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<CapabilityCatalog>,
+    /// Token from `spec-ai mesh invite`; limits the capabilities recorded
+    /// for this instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_token: Option<String>,
+    #[serde(default)]
+    pub role: MeshRole,
 }
 
 /// Response from registration
@@ -76,6 +102,8 @@ pub enum MessageType {
     TaskDelegation, // Delegate a task to another agent
     TaskResult,     // Result of a delegated task
     GraphSync,      // Knowledge graph synchronization
+    ToolCall,       // Run a tool on the target instance
+    ToolResult,     // Result of a remote tool call
     Custom(String), // Custom message type
 }
 
@@ -88,6 +116,8 @@ impl MessageType {
             MessageType::TaskDelegation => "task_delegation".to_string(),
             MessageType::TaskResult => "task_result".to_string(),
             MessageType::GraphSync => "graph_sync".to_string(),
+            MessageType::ToolCall => "tool_call".to_string(),
+            MessageType::ToolResult => "tool_result".to_string(),
             MessageType::Custom(s) => s.clone(),
         }
     }
@@ -100,6 +130,8 @@ impl MessageType {
             "task_delegation" => MessageType::TaskDelegation,
             "task_result" => MessageType::TaskResult,
             "graph_sync" => MessageType::GraphSync,
+            "tool_call" => MessageType::ToolCall,
+            "tool_result" => MessageType::ToolResult,
             custom => MessageType::Custom(custom.to_string()),
         }
     }
@@ -146,7 +178,15 @@ pub struct MeshRegistry {
     instances: Arc<RwLock<HashMap<String, MeshInstance>>>,
     leader_id: Arc<RwLock<Option<String>>>,
     message_queue: Arc<RwLock<Vec<AgentMessage>>>,
+    /// Heartbeat stats per instance, oldest first, covering the stats window
+    stats: Arc<RwLock<HashMap<String, VecDeque<(DateTime<Utc>, InstanceStats)>>>>,
+    /// Send times of each instance's messages in the last hour
+    sent: Arc<RwLock<HashMap<String, VecDeque<DateTime<Utc>>>>>,
     persistence: Option<Persistence>,
+    /// Checks join tokens of registering members
+    join_tokens: Option<Arc<MeshKeyring>>,
+    /// Refuse members that register without a join token
+    require_join_token: bool,
 }
 
 impl MeshRegistry {
@@ -155,26 +195,71 @@ impl MeshRegistry {
             instances: Arc::new(RwLock::new(HashMap::new())),
             leader_id: Arc::new(RwLock::new(None)),
             message_queue: Arc::new(RwLock::new(Vec::new())),
+            stats: Arc::new(RwLock::new(HashMap::new())),
+            sent: Arc::new(RwLock::new(HashMap::new())),
             persistence: None,
+            join_tokens: None,
+            require_join_token: false,
         }
     }
 
+    /// A registry that keeps stats samples in `persistence`, starting from
+    /// the samples stored there that are still inside the stats window
     pub fn with_persistence(persistence: Persistence) -> Self {
         Self {
             instances: Arc::new(RwLock::new(HashMap::new())),
             leader_id: Arc::new(RwLock::new(None)),
             message_queue: Arc::new(RwLock::new(Vec::new())),
+            stats: Arc::new(RwLock::new(stored_stats(&persistence))),
+            sent: Arc::new(RwLock::new(HashMap::new())),
             persistence: Some(persistence),
+            join_tokens: None,
+            require_join_token: false,
         }
     }
 
+    /// Accept join tokens minted with `keyring`; with `required`, members
+    /// that register without one are refused
+    pub fn with_join_tokens(mut self, keyring: MeshKeyring, required: bool) -> Self {
+        self.join_tokens = Some(Arc::new(keyring));
+        self.require_join_token = required;
+        self
+    }
+
+    /// Check the join token a member registers with and keep only the
+    /// capabilities it allows. Members without a token keep what they ask
+    /// for unless tokens are required.
+    pub fn admit(
+        &self,
+        instance: &mut MeshInstance,
+        join_token: Option<&str>,
+    ) -> Result<(), JoinTokenError> {
+        let Some(token) = join_token else {
+            return if self.require_join_token {
+                Err(JoinTokenError::Missing)
+            } else {
+                Ok(())
+            };
+        };
+        let keyring = self.join_tokens.as_ref().ok_or(JoinTokenError::Invalid)?;
+        let claims = keyring.verify(token, Utc::now())?;
+        instance.capabilities = claims.bind(std::mem::take(&mut instance.capabilities));
+        tracing::info!(
+            "Instance {} joined with token {} (capabilities: {})",
+            instance.instance_id,
+            claims.id,
+            instance.capabilities.join(", ")
+        );
+        Ok(())
+    }
+
     /// Register a new instance
     pub async fn register(&self, instance: MeshInstance) -> RegisterResponse {
         let mut instances = self.instances.write().await;
         let mut leader = self.leader_id.write().await;
 
-        // First instance becomes the leader
-        let is_leader = instances.is_empty();
+        // The first leader-capable instance becomes the leader
+        let is_leader = leader.is_none() && instance.role.can_lead();
         let mut new_instance = instance.clone();
         new_instance.is_leader = is_leader;
 
@@ -193,13 +278,20 @@ impl MeshRegistry {
         }
     }
 
-    /// Update heartbeat timestamp
-    pub async fn heartbeat(&self, instance_id: &str) -> HeartbeatResponse {
+    /// Update heartbeat timestamp and record the stats sent with it
+    pub async fn heartbeat(
+        &self,
+        instance_id: &str,
+        stats: Option<InstanceStats>,
+    ) -> HeartbeatResponse {
         let mut instances = self.instances.write().await;
         let leader = self.leader_id.read().await;
 
         if let Some(instance) = instances.get_mut(instance_id) {
             instance.last_heartbeat = Utc::now();
+            if let Some(stats) = stats {
+                self.record_stats(instance_id, stats).await;
+            }
             HeartbeatResponse {
                 acknowledged: true,
                 leader_id: leader.clone(),
@@ -214,21 +306,62 @@ impl MeshRegistry {
         }
     }
 
+    /// Keep a stats sample in the rolling window, in memory and (when the
+    /// registry has a database) on disk
+    async fn record_stats(&self, instance_id: &str, stats: InstanceStats) {
+        if let Some(ref persistence) = self.persistence {
+            if let Err(e) = persistence.mesh_stats_record(instance_id, &stats) {
+                tracing::warn!("Failed to persist mesh stats: {}", e);
+            }
+        }
+
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::seconds(MESH_STATS_RETENTION_SECS);
+        let mut all_stats = self.stats.write().await;
+        let samples = all_stats.entry(instance_id.to_string()).or_default();
+        samples.push_back((now, stats));
+        while samples.front().is_some_and(|(at, _)| *at < cutoff) {
+            samples.pop_front();
+        }
+    }
+
+    /// Cluster health: each instance's latest stats and window aggregates.
+    /// Instances silent for more than `stale_after_secs` are reported
+    /// unhealthy.
+    pub async fn stats(&self, stale_after_secs: u64) -> MeshStatsResponse {
+        let instances = self.instances.read().await;
+        let all_stats = self.stats.read().await;
+        let health = instances
+            .values()
+            .map(|instance| {
+                let samples: Vec<InstanceStats> = all_stats
+                    .get(&instance.instance_id)
+                    .map(|samples| samples.iter().map(|(_, s)| s.clone()).collect())
+                    .unwrap_or_default();
+                InstanceHealth::from_samples(
+                    &instance.instance_id,
+                    &instance.hostname,
+                    instance.is_leader,
+                    instance.last_heartbeat,
+                    &samples,
+                    stale_after_secs,
+                )
+            })
+            .collect();
+        MeshStatsResponse::new(self.leader_id.read().await.clone(), health)
+    }
+
     /// Remove an instance
     pub async fn deregister(&self, instance_id: &str) -> bool {
         let mut instances = self.instances.write().await;
         let mut leader = self.leader_id.write().await;
 
+        self.stats.write().await.remove(instance_id);
+        self.sent.write().await.remove(instance_id);
         if let Some(instance) = instances.remove(instance_id) {
             // If leader is leaving, elect a new one
-            if instance.is_leader && !instances.is_empty() {
-                // Simple election: first remaining instance becomes leader
-                if let Some((new_leader_id, new_leader)) = instances.iter_mut().next() {
-                    new_leader.is_leader = true;
-                    *leader = Some(new_leader_id.clone());
-                }
-            } else if instances.is_empty() {
-                *leader = None;
+            if instance.is_leader || instances.is_empty() {
+                *leader = elect_leader(&mut instances);
             }
             true
         } else {
@@ -261,13 +394,11 @@ impl MeshRegistry {
             .collect();
 
         for id in stale_ids {
+            self.stats.write().await.remove(&id);
             if let Some(instance) = instances.remove(&id) {
                 // Handle leader failover if needed
-                if instance.is_leader && !instances.is_empty() {
-                    if let Some((new_leader_id, new_leader)) = instances.iter_mut().next() {
-                        new_leader.is_leader = true;
-                        *leader = Some(new_leader_id.clone());
-                    }
+                if instance.is_leader {
+                    *leader = elect_leader(&mut instances);
                 }
             }
         }
@@ -292,6 +423,27 @@ impl MeshRegistry {
         payload: serde_json::Value,
         correlation_id: Option<String>,
     ) -> Result<SendMessageResponse> {
+        self.check_quotas(&source_instance, target_instance.as_deref(), &message_type)
+            .await?;
+        if matches!(
+            message_type,
+            MessageType::TaskDelegation | MessageType::ToolCall
+        ) {
+            if let Some(target) = &target_instance {
+                let instances = self.instances.read().await;
+                if let Some(instance) = instances.get(target) {
+                    if !instance.role.accepts_writes() {
+                        anyhow::bail!(
+                            "Instance '{}' is a {} and takes no {}",
+                            target,
+                            instance.role.as_str(),
+                            message_type.as_str()
+                        );
+                    }
+                }
+            }
+        }
+
         // Generate time-ordered UUID v7 for better database performance and distributed safety
         let message_id = uuid::Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext)).to_string();
 
@@ -348,6 +500,59 @@ impl MeshRegistry {
         })
     }
 
+    /// Refuse a message when its sender is over its hourly message limit,
+    /// or when it delegates a task to an instance at its run or token limit.
+    /// Limits and usage are the ones instances report in their heartbeats.
+    async fn check_quotas(
+        &self,
+        source: &str,
+        target: Option<&str>,
+        message_type: &MessageType,
+    ) -> Result<(), QuotaExceeded> {
+        let now = Utc::now();
+        let all_stats = self.stats.read().await;
+        let quota_of = |id: &str| {
+            all_stats
+                .get(id)
+                .and_then(|samples| samples.back())
+                .and_then(|(_, stats)| stats.quota.clone())
+        };
+
+        if *message_type == MessageType::TaskDelegation {
+            if let Some((target, quota)) = target.and_then(|t| quota_of(t).map(|q| (t, q))) {
+                check_run(
+                    &format!("instance {}", target),
+                    &quota.limits,
+                    quota.running,
+                    quota.tokens_today,
+                    now,
+                )?;
+            }
+        }
+
+        let mut sent = self.sent.write().await;
+        let log = sent.entry(source.to_string()).or_default();
+        let cutoff = now - chrono::Duration::hours(1);
+        while log.front().is_some_and(|at| *at <= cutoff) {
+            log.pop_front();
+        }
+        if let Some(max) = quota_of(source).and_then(|q| q.limits.max_messages_per_hour) {
+            if log.len() as u64 >= u64::from(max) {
+                return Err(QuotaExceeded {
+                    kind: QuotaKind::MessagesPerHour,
+                    scope: format!("instance {}", source),
+                    limit: u64::from(max),
+                    used: log.len() as u64,
+                    retry_after_secs: log
+                        .front()
+                        .map(|oldest| (*oldest - cutoff).num_seconds().max(0) as u64),
+                });
+            }
+        }
+        log.push_back(now);
+        Ok(())
+    }
+
     /// Get pending messages for an instance
     pub async fn get_pending_messages(&self, instance_id: &str) -> Vec<AgentMessage> {
         let queue = self.message_queue.read().await;
@@ -394,23 +599,9 @@ impl MeshClient {
         format!("{}-{}", hostname, uuid)
     }
 
-    /// Register this instance with a mesh registry
-    pub async fn register(
-        &self,
-        instance_id: String,
-        hostname: String,
-        port: u16,
-        capabilities: Vec<String>,
-        agent_profiles: Vec<String>,
-    ) -> Result<RegisterResponse> {
-        let request = RegisterRequest {
-            instance_id,
-            hostname,
-            port,
-            capabilities,
-            agent_profiles,
-        };
-
+    /// Register this instance with a mesh registry. The response lists the
+    /// peers, with the catalog each of them registered.
+    pub async fn register(&self, request: RegisterRequest) -> Result<RegisterResponse> {
         let response = self
             .client
             .post(format!("{}/registry/register", self.base_url))
@@ -421,7 +612,13 @@ impl MeshClient {
         if response.status().is_success() {
             Ok(response.json().await?)
         } else {
-            anyhow::bail!("Registration failed: {}", response.status())
+            let status = response.status();
+            let reason = response
+                .json::<ErrorResponse>()
+                .await
+                .map(|body| format!(" ({})", body.error))
+                .unwrap_or_default();
+            anyhow::bail!("Registration failed: {}{}", status, reason)
         }
     }
 
@@ -557,9 +754,47 @@ impl MeshClient {
     }
 }
 
+/// Simple election: the first remaining instance able to lead becomes
+/// leader. Workers and read replicas are never elected.
+fn elect_leader(instances: &mut HashMap<String, MeshInstance>) -> Option<String> {
+    let (id, instance) = instances
+        .iter_mut()
+        .find(|(_, instance)| instance.role.can_lead())?;
+    instance.is_leader = true;
+    Some(id.clone())
+}
+
+/// Stats samples stored before a restart, per instance and oldest first,
+/// that are still inside the stats window
+fn stored_stats(
+    persistence: &Persistence,
+) -> HashMap<String, VecDeque<(DateTime<Utc>, InstanceStats)>> {
+    let records = match persistence.mesh_stats_window(None) {
+        Ok(records) => records,
+        Err(e) => {
+            tracing::warn!("Failed to load stored mesh stats: {}", e);
+            return HashMap::new();
+        }
+    };
+    let cutoff = Utc::now() - chrono::Duration::seconds(MESH_STATS_RETENTION_SECS);
+    let mut stats: HashMap<String, VecDeque<_>> = HashMap::new();
+    for record in records.into_iter().filter(|r| r.created_at >= cutoff) {
+        stats
+            .entry(record.instance_id)
+            .or_default()
+            .push_back((record.created_at, record.stats));
+    }
+    stats
+}
+
 /// Extension trait to add mesh registry to app state
 pub trait MeshState {
     fn mesh_registry(&self) -> &MeshRegistry;
+
+    /// Seconds without a heartbeat after which an instance is unhealthy
+    fn mesh_stale_after_secs(&self) -> u64 {
+        15
+    }
 }
 
 /// Handler: Register a new instance
@@ -567,7 +802,7 @@ pub async fn register_instance<S: MeshState>(
     State(state): State<S>,
     Json(request): Json<RegisterRequest>,
 ) -> impl IntoResponse {
-    let instance = MeshInstance {
+    let mut instance = MeshInstance {
         instance_id: request.instance_id,
         hostname: request.hostname,
         port: request.port,
@@ -576,10 +811,24 @@ pub async fn register_instance<S: MeshState>(
         last_heartbeat: Utc::now(),
         created_at: Utc::now(),
         agent_profiles: request.agent_profiles,
+        tools: request.tools,
+        catalog: request.catalog,
+        role: request.role,
     };
+    if let Err(err) = state
+        .mesh_registry()
+        .admit(&mut instance, request.join_token.as_deref())
+    {
+        tracing::warn!("Refused to register {}: {}", instance.instance_id, err);
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new("join_token_refused", err.to_string())),
+        )
+            .into_response();
+    }
 
     let response = state.mesh_registry().register(instance).await;
-    (StatusCode::OK, Json(response))
+    (StatusCode::OK, Json(response)).into_response()
 }
 
 /// Handler: List all instances
@@ -600,9 +849,13 @@ pub async fn list_instances<S: MeshState>(State(state): State<S>) -> impl IntoRe
 pub async fn heartbeat<S: MeshState>(
     State(state): State<S>,
     Path(instance_id): Path<String>,
-    Json(_request): Json<HeartbeatRequest>,
+    Json(request): Json<HeartbeatRequest>,
 ) -> impl IntoResponse {
-    let response = state.mesh_registry().heartbeat(&instance_id).await;
+    let stats = request
+        .metrics
+        .as_ref()
+        .and_then(InstanceStats::from_metrics);
+    let response = state.mesh_registry().heartbeat(&instance_id, stats).await;
 
     if response.acknowledged {
         (StatusCode::OK, Json(response))
@@ -611,6 +864,12 @@ pub async fn heartbeat<S: MeshState>(
     }
 }
 
+/// Handler: Cluster health at a glance
+pub async fn mesh_stats<S: MeshState>(State(state): State<S>) -> impl IntoResponse {
+    let stale_after = state.mesh_stale_after_secs();
+    Json(state.mesh_registry().stats(stale_after).await)
+}
+
 /// Handler: Deregister an instance
 pub async fn deregister_instance<S: MeshState>(
     State(state): State<S>,
@@ -643,13 +902,20 @@ pub async fn send_message<S: MeshState>(
         .await
     {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": e.to_string()
-            })),
-        )
-            .into_response(),
+        Err(e) => match e.downcast_ref::<QuotaExceeded>() {
+            Some(err) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(ErrorResponse::quota_exceeded(err)),
+            )
+                .into_response(),
+            None => (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+                .into_response(),
+        },
     }
 }
 
@@ -685,3 +951,138 @@ pub async fn acknowledge_messages<S: MeshState>(
 
     StatusCode::NO_CONTENT
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn instance(id: &str, role: MeshRole) -> MeshInstance {
+        MeshInstance {
+            instance_id: id.to_string(),
+            hostname: "localhost".to_string(),
+            port: 3000,
+            capabilities: Vec::new(),
+            is_leader: false,
+            last_heartbeat: Utc::now(),
+            created_at: Utc::now(),
+            agent_profiles: Vec::new(),
+            tools: Vec::new(),
+            catalog: None,
+            role,
+        }
+    }
+
+    #[tokio::test]
+    async fn workers_and_replicas_are_never_elected() {
+        let registry = MeshRegistry::new();
+        assert!(
+            !registry
+                .register(instance("worker", MeshRole::Worker))
+                .await
+                .is_leader
+        );
+        registry
+            .register(instance("replica", MeshRole::ReadReplica))
+            .await;
+        assert_eq!(registry.get_leader().await, None);
+
+        registry
+            .register(instance("a", MeshRole::LeaderCapable))
+            .await;
+        registry
+            .register(instance("b", MeshRole::LeaderCapable))
+            .await;
+        assert_eq!(registry.get_leader().await.as_deref(), Some("a"));
+
+        // The leader leaves: the other leader-capable instance takes over
+        assert!(registry.deregister("a").await);
+        assert_eq!(registry.get_leader().await.as_deref(), Some("b"));
+
+        // The leader goes silent: only a worker and a replica remain
+        registry
+            .instances
+            .write()
+            .await
+            .get_mut("b")
+            .unwrap()
+            .last_heartbeat = Utc::now() - chrono::Duration::minutes(10);
+        registry.cleanup_stale(60).await;
+        assert_eq!(registry.get_leader().await, None);
+        let remaining = registry.list().await;
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|instance| !instance.is_leader));
+
+        // Same when the last leader-capable instance deregisters
+        registry
+            .register(instance("c", MeshRole::LeaderCapable))
+            .await;
+        assert_eq!(registry.get_leader().await.as_deref(), Some("c"));
+        assert!(registry.deregister("c").await);
+        assert_eq!(registry.get_leader().await, None);
+    }
+
+    #[tokio::test]
+    async fn replicas_take_no_delegated_tasks_or_tool_calls() {
+        let registry = MeshRegistry::new();
+        registry
+            .register(instance("leader", MeshRole::LeaderCapable))
+            .await;
+        registry
+            .register(instance("replica", MeshRole::ReadReplica))
+            .await;
+        registry
+            .register(instance("worker", MeshRole::Worker))
+            .await;
+        let send = |target: &str, message_type: MessageType| {
+            registry.send_message(
+                "leader".to_string(),
+                Some(target.to_string()),
+                message_type,
+                json!({}),
+                None,
+            )
+        };
+
+        for message_type in [MessageType::TaskDelegation, MessageType::ToolCall] {
+            let err = send("replica", message_type.clone()).await.unwrap_err();
+            assert!(
+                err.to_string().contains("is a read-replica and takes no"),
+                "{}",
+                err
+            );
+            assert!(send("worker", message_type).await.is_ok());
+        }
+        assert!(send("replica", MessageType::Query).await.is_ok());
+        let pending = registry.get_pending_messages("replica").await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].message_type, MessageType::Query);
+    }
+
+    #[tokio::test]
+    async fn stored_stats_outlive_a_restart() {
+        let persistence = Persistence::in_memory().unwrap();
+        let recent = InstanceStats {
+            tool_calls: 7,
+            ..InstanceStats::default()
+        };
+        persistence
+            .mesh_stats_record("node-a", &InstanceStats::default())
+            .unwrap();
+        persistence.mesh_stats_record("node-a", &recent).unwrap();
+        // A sample from before the window is not loaded
+        persistence
+            .conn()
+            .execute(
+                "UPDATE mesh_stats SET created_at = TIMESTAMP '2020-01-01 00:00:00' WHERE tool_calls = 0",
+                [],
+            )
+            .unwrap();
+
+        let registry = MeshRegistry::with_persistence(persistence);
+        let stats = registry.stats.read().await;
+        let samples = &stats["node-a"];
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].1, recent);
+    }
+}
@@ -1,10 +1,13 @@
 /// API authentication and middleware
+use crate::api::models::ErrorResponse;
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    body::Body,
+    extract::{Json, Request, State},
+    http::{header::CONTENT_LENGTH, HeaderMap, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use serde_json::json;
 
 /// API key authentication middleware
 pub struct ApiKeyAuth {
@@ -61,9 +64,192 @@ pub async fn auth_middleware(
     Ok(next.run(request).await)
 }
 
+/// Size caps on requests, so one client cannot make the server buffer
+/// arbitrarily large inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Largest request body, in bytes
+    pub max_body_bytes: usize,
+    /// Longest URL query string, in bytes
+    pub max_query_bytes: usize,
+    /// Longest `message` of a query, in characters
+    pub max_message_chars: usize,
+    /// Longest `session_id`, in characters
+    pub max_session_id_chars: usize,
+    /// Longest `agent` name, in characters
+    pub max_agent_chars: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            // Sync changesets are the largest bodies the server accepts
+            max_body_bytes: 8 * 1024 * 1024,
+            max_query_bytes: 4096,
+            max_message_chars: 100_000,
+            max_session_id_chars: 256,
+            max_agent_chars: 128,
+        }
+    }
+}
+
+/// A structured 400 response
+pub fn bad_request(error: ErrorResponse) -> Response {
+    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+}
+
+/// Reject requests whose query string or body is over [`RequestLimits`] or
+/// not valid UTF-8. Bodies are buffered here, up to the limit, and handed
+/// on unchanged.
+pub async fn limits_middleware(
+    State(limits): State<RequestLimits>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(query) = request.uri().query() {
+        if query.len() > limits.max_query_bytes {
+            return bad_request(
+                ErrorResponse::new(
+                    "query_too_long",
+                    format!(
+                        "Query string is longer than {} bytes",
+                        limits.max_query_bytes
+                    ),
+                )
+                .with_details(json!({"limit": limits.max_query_bytes, "actual": query.len()})),
+            );
+        }
+        if std::str::from_utf8(&percent_decode(query)).is_err() {
+            return bad_request(ErrorResponse::new(
+                "invalid_utf8",
+                "Query string is not valid UTF-8",
+            ));
+        }
+    }
+
+    let too_large = || {
+        bad_request(
+            ErrorResponse::new(
+                "body_too_large",
+                format!(
+                    "Request body is larger than {} bytes",
+                    limits.max_body_bytes
+                ),
+            )
+            .with_details(json!({"limit": limits.max_body_bytes})),
+        )
+    };
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limits.max_body_bytes) {
+        return too_large();
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, limits.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(_) => return too_large(),
+    };
+    if let Err(err) = std::str::from_utf8(&bytes) {
+        return bad_request(
+            ErrorResponse::new("invalid_utf8", "Request body is not valid UTF-8")
+                .with_details(json!({"valid_up_to": err.valid_up_to()})),
+        );
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+/// Bytes of a URL query string with `%XX` escapes decoded
+fn percent_decode(query: &str) -> Vec<u8> {
+    let bytes = query.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{routing::post, Router};
+    use tower::ServiceExt;
+
+    fn limited_router(limits: RequestLimits) -> Router {
+        Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .layer(axum::middleware::from_fn_with_state(
+                limits,
+                limits_middleware,
+            ))
+    }
+
+    async fn error_code(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        error.code
+    }
+
+    #[tokio::test]
+    async fn limits_reject_oversized_and_invalid_requests() {
+        let limits = RequestLimits {
+            max_body_bytes: 16,
+            max_query_bytes: 8,
+            ..RequestLimits::default()
+        };
+        let request = |uri: &str, body: Vec<u8>| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let ok = limited_router(limits.clone())
+            .oneshot(request("/echo?a=1", b"hello".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(ok.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"hello");
+
+        for (uri, body, code) in [
+            ("/echo", vec![b'x'; 17], "body_too_large"),
+            ("/echo?query=long", Vec::new(), "query_too_long"),
+            ("/echo?q=%FF", Vec::new(), "invalid_utf8"),
+            ("/echo", vec![b'a', 0xC3, 0x28], "invalid_utf8"),
+        ] {
+            let response = limited_router(limits.clone())
+                .oneshot(request(uri, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(error_code(response).await, code, "{}", uri);
+        }
+    }
 
     #[test]
     fn test_api_key_auth_disabled() {
@@ -2,6 +2,7 @@ pub mod handlers;
 pub mod mesh;
 pub mod middleware;
 pub mod models;
+pub mod openapi;
 /// REST API and WebSocket server for programmatic agent access
 ///
 /// This module provides:
@@ -10,7 +11,9 @@ pub mod models;
 /// - API key authentication
 /// - JSON request/response format
 pub mod server;
+pub mod session_queue;
 pub mod sync_handlers;
+pub mod triggers;
 pub use spec_ai_core::sync;
 
 pub use models::{ErrorResponse, QueryRequest, QueryResponse, StreamChunk};
@@ -1,8 +1,18 @@
 /// API request and response models
+use crate::api::middleware::RequestLimits;
+use crate::persistence::memories::MemoryEntry;
+use crate::persistence::policy_audit::PolicyAuditEntry;
+use crate::persistence::search::{SemanticHitKind, SemanticSearchHit};
+use crate::persistence::triggers::{TriggerJob, WebhookTrigger};
+use crate::persistence::SessionRecord;
+use crate::quota::QuotaExceeded;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 
 /// Request to query the agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryRequest {
     /// The user's message/query
     pub message: String,
@@ -19,8 +29,55 @@ pub struct QueryRequest {
     pub max_tokens: Option<usize>,
 }
 
+impl QueryRequest {
+    /// Check field lengths against `limits` and the temperature range
+    pub fn validate(&self, limits: &RequestLimits) -> Result<(), ErrorResponse> {
+        if self.message.trim().is_empty() {
+            return Err(ErrorResponse::new(
+                "invalid_request",
+                "message must not be empty",
+            ));
+        }
+        let fields = [
+            ("message", Some(&self.message), limits.max_message_chars),
+            (
+                "session_id",
+                self.session_id.as_ref(),
+                limits.max_session_id_chars,
+            ),
+            ("agent", self.agent.as_ref(), limits.max_agent_chars),
+        ];
+        for (field, value, limit) in fields {
+            let Some(chars) = value.map(|value| value.chars().count()) else {
+                continue;
+            };
+            if chars > limit {
+                return Err(ErrorResponse::new(
+                    "field_too_long",
+                    format!("{} is longer than {} characters", field, limit),
+                )
+                .with_details(serde_json::json!({
+                    "field": field,
+                    "limit": limit,
+                    "actual": chars,
+                })));
+            }
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(ErrorResponse::new(
+                    "invalid_request",
+                    "temperature must be between 0.0 and 2.0",
+                )
+                .with_details(serde_json::json!({"field": "temperature"})));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Response from the agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryResponse {
     /// The agent's response message
     pub response: String,
@@ -36,11 +93,12 @@ pub struct QueryResponse {
 }
 
 /// Information about a tool call
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ToolCallInfo {
     /// Tool name
     pub name: String,
     /// Tool arguments
+    #[schema(value_type = Object)]
     pub arguments: serde_json::Value,
     /// Execution status
     pub success: bool,
@@ -50,10 +108,13 @@ pub struct ToolCallInfo {
     /// Error message if the tool failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Result was served from the tool memoization cache
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
 }
 
 /// Response metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ResponseMetadata {
     /// Timestamp of response
     pub timestamp: String,
@@ -66,7 +127,7 @@ pub struct ResponseMetadata {
 }
 
 /// Streaming response chunk
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum StreamChunk {
     /// Initial metadata
@@ -79,12 +140,17 @@ pub enum StreamChunk {
     #[serde(rename = "tool_call")]
     ToolCall {
         name: String,
+        #[schema(value_type = Object)]
         arguments: serde_json::Value,
     },
+    /// Output a tool wrote while running, before its result
+    #[serde(rename = "tool_output")]
+    ToolOutput { name: String, text: String },
     /// Tool result
     #[serde(rename = "tool_result")]
     ToolResult {
         name: String,
+        #[schema(value_type = Object)]
         result: serde_json::Value,
     },
     /// End of stream
@@ -96,7 +162,7 @@ pub enum StreamChunk {
 }
 
 /// Error response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     /// Error message
     pub error: String,
@@ -104,6 +170,7 @@ pub struct ErrorResponse {
     pub code: String,
     /// Additional details
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
     pub details: Option<serde_json::Value>,
 }
 
@@ -120,10 +187,16 @@ impl ErrorResponse {
         self.details = Some(details);
         self
     }
+
+    /// A refused request, with the quota that refused it as details
+    pub fn quota_exceeded(err: &QuotaExceeded) -> Self {
+        Self::new(QuotaExceeded::CODE, err.to_string())
+            .with_details(serde_json::to_value(err).unwrap_or_default())
+    }
 }
 
 /// Health check response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
     /// Service status
     pub status: String,
@@ -131,19 +204,25 @@ pub struct HealthResponse {
     pub version: String,
     /// Uptime in seconds
     pub uptime_seconds: u64,
-    /// Active sessions count
+    /// Sessions with a step running or waiting
     pub active_sessions: usize,
+    /// Steps waiting for an earlier step of their session to finish
+    #[serde(default)]
+    pub queued_steps: usize,
+    /// Steps running or waiting, per active session
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub session_queues: BTreeMap<String, usize>,
 }
 
 /// Agent list response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AgentListResponse {
     /// Available agents
     pub agents: Vec<AgentInfo>,
 }
 
 /// Agent information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AgentInfo {
     /// Agent ID
     pub id: String,
@@ -157,6 +236,409 @@ pub struct AgentInfo {
     pub denied_tools: Vec<String>,
 }
 
+/// Session list response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionListResponse {
+    /// Sessions, most recently active first
+    pub sessions: Vec<SessionInfo>,
+}
+
+/// Session metadata
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionInfo {
+    /// Session ID
+    pub id: String,
+    /// Generated or user-set title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Agent that handled the session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Session tags
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    /// Number of stored messages
+    pub message_count: i64,
+    /// Creation timestamp (RFC3339)
+    pub created_at: String,
+    /// Last activity timestamp (RFC3339)
+    pub updated_at: String,
+    /// When the session's messages were folded into a summary (RFC3339);
+    /// querying the session thaws it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub archived_at: Option<String>,
+}
+
+/// Semantic search request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchRequest {
+    /// Text to search for
+    pub query: String,
+    /// Maximum number of results
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+    /// Only sessions whose ID starts with this prefix
+    pub namespace: Option<String>,
+    /// Only sessions handled by this agent
+    pub agent: Option<String>,
+    /// Only content created at or after this time (RFC3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only content created before this time (RFC3339)
+    pub to: Option<DateTime<Utc>>,
+}
+
+fn default_search_limit() -> usize {
+    10
+}
+
+/// Semantic search response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResponse {
+    /// The query as received
+    pub query: String,
+    /// Results, most similar first
+    pub results: Vec<SearchResult>,
+}
+
+/// A single semantic search result
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResult {
+    /// `message`, `transcription`, `graph_node` or `summary`
+    pub kind: String,
+    /// Cosine similarity to the query
+    pub score: f32,
+    /// Session the content belongs to
+    pub session_id: String,
+    /// Session title, if one has been set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_title: Option<String>,
+    /// Matched content
+    pub text: String,
+    /// Creation timestamp of the embedding (RFC3339)
+    pub created_at: String,
+    /// Where the content is stored
+    pub provenance: SearchProvenance,
+}
+
+/// Database rows behind a search result
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SearchProvenance {
+    /// Embedding row in `memory_vectors`
+    pub memory_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcription_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary_id: Option<i64>,
+}
+
+/// Filters for listing a session's memories
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MemoryListQuery {
+    /// Only memories relevant to this text, most relevant first
+    pub q: Option<String>,
+    /// Maximum number of memories returned
+    pub limit: Option<usize>,
+}
+
+/// Recallable memories of a session
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MemoryListResponse {
+    pub session_id: String,
+    /// Newest first, or most relevant first when `q` was given
+    pub memories: Vec<MemoryInfo>,
+}
+
+/// A recallable memory and the content it was embedded from
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MemoryInfo {
+    /// Embedding row in `memory_vectors`; pass to `DELETE /v1/memory/:id`
+    pub id: i64,
+    pub session_id: String,
+    /// `message`, `transcription`, `graph_node` or `summary`; absent once
+    /// the source record is gone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub text: String,
+    /// Times recall has returned the memory
+    pub access_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_accessed_at: Option<String>,
+    /// Creation timestamp of the embedding (RFC3339)
+    pub created_at: String,
+    /// Relevance to `q`, when searching
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+/// A deleted memory
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MemoryDeleteResponse {
+    pub deleted: MemoryInfo,
+}
+
+/// Filters for listing policy decisions
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PolicyAuditQuery {
+    /// Only decisions about this agent's tool calls
+    pub agent: Option<String>,
+    /// Maximum number of decisions returned
+    pub limit: Option<usize>,
+}
+
+/// Latest policy decisions on tool calls
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PolicyAuditResponse {
+    /// Newest first
+    pub decisions: Vec<PolicyDecisionInfo>,
+}
+
+/// Whether a tool call was allowed, and the rule that decided
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PolicyDecisionInfo {
+    pub id: i64,
+    pub session_id: String,
+    pub run_id: String,
+    pub agent: String,
+    /// `tool_call`, or the tool's own action such as `process`
+    pub action: String,
+    /// Tool name, or the resource shared by a family of tools
+    pub resource: String,
+    /// `allow` or `deny`
+    pub effect: String,
+    /// The rule that decided; absent when no rule matched and the default
+    /// deny applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// When the decision was made (RFC3339)
+    pub created_at: String,
+}
+
+/// Request to register a webhook that runs a spec
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateTriggerRequest {
+    /// Last segment of the webhook URL, `/v1/hooks/<name>`; letters,
+    /// digits, `-` and `_`
+    pub name: String,
+    /// Shared secret deliveries are signed (or authenticated) with
+    pub secret: String,
+    /// `.spec` file run on each delivery
+    pub spec_path: String,
+    /// Agent profile to run the spec with (default agent if not specified)
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Spec variable name to JSON Pointer into the payload, e.g.
+    /// `{"branch": "/ref"}`
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Shortest secret accepted for a trigger
+pub const MIN_TRIGGER_SECRET_CHARS: usize = 16;
+
+/// Longest trigger name
+const MAX_TRIGGER_NAME_CHARS: usize = 64;
+
+impl CreateTriggerRequest {
+    /// Check the request before the spec is looked at
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        let name_ok = !self.name.is_empty()
+            && self.name.len() <= MAX_TRIGGER_NAME_CHARS
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !name_ok {
+            return Err(ErrorResponse::new(
+                "invalid_request",
+                format!(
+                    "name must be 1 to {} letters, digits, '-' or '_'",
+                    MAX_TRIGGER_NAME_CHARS
+                ),
+            ));
+        }
+        if self.secret.chars().count() < MIN_TRIGGER_SECRET_CHARS {
+            return Err(ErrorResponse::new(
+                "invalid_request",
+                format!(
+                    "secret must be at least {} characters",
+                    MIN_TRIGGER_SECRET_CHARS
+                ),
+            ));
+        }
+        if let Some((variable, pointer)) = self
+            .variables
+            .iter()
+            .find(|(_, pointer)| !pointer.is_empty() && !pointer.starts_with('/'))
+        {
+            return Err(ErrorResponse::new(
+                "invalid_request",
+                format!(
+                    "variable '{}' maps to '{}', which is not a JSON Pointer (use e.g. '/{}')",
+                    variable, pointer, pointer
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A registered webhook; its secret is never returned
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TriggerInfo {
+    pub id: i64,
+    pub name: String,
+    /// Path deliveries are posted to
+    pub url: String,
+    pub spec_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    pub variables: BTreeMap<String, String>,
+    /// Registration timestamp (RFC3339)
+    pub created_at: String,
+}
+
+/// Registered webhooks
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TriggerListResponse {
+    pub triggers: Vec<TriggerInfo>,
+}
+
+/// A spec run started by a webhook delivery
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TriggerJobInfo {
+    pub id: i64,
+    pub trigger_id: i64,
+    /// Session the spec runs in
+    pub session_id: String,
+    /// `queued`, `running`, `succeeded` or `failed`
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Agent's answer, once the run succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+impl From<WebhookTrigger> for TriggerInfo {
+    fn from(trigger: WebhookTrigger) -> Self {
+        Self {
+            id: trigger.id,
+            url: format!("/v1/hooks/{}", trigger.name),
+            name: trigger.name,
+            spec_path: trigger.spec_path,
+            agent: trigger.agent,
+            variables: trigger.variables,
+            created_at: trigger.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<TriggerJob> for TriggerJobInfo {
+    fn from(job: TriggerJob) -> Self {
+        Self {
+            id: job.id,
+            trigger_id: job.trigger_id,
+            session_id: job.session_id,
+            status: job.status.as_str().to_string(),
+            run_id: job.run_id,
+            error: job.error,
+            response: None,
+            created_at: job.created_at.to_rfc3339(),
+            finished_at: job.finished_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+impl From<MemoryEntry> for MemoryInfo {
+    fn from(entry: MemoryEntry) -> Self {
+        Self {
+            id: entry.id,
+            session_id: entry.session_id,
+            kind: entry.kind.map(|kind| kind.as_str().to_string()),
+            source_id: entry.source_id,
+            role: entry.role,
+            text: entry.text,
+            access_count: entry.access_count,
+            last_accessed_at: entry.last_accessed_at.map(|at| at.to_rfc3339()),
+            created_at: entry.created_at.to_rfc3339(),
+            score: entry.score,
+        }
+    }
+}
+
+impl From<PolicyAuditEntry> for PolicyDecisionInfo {
+    fn from(entry: PolicyAuditEntry) -> Self {
+        Self {
+            effect: entry.effect().to_string(),
+            id: entry.id,
+            session_id: entry.session_id,
+            run_id: entry.run_id,
+            agent: entry.agent,
+            action: entry.action,
+            resource: entry.resource,
+            rule: entry.rule,
+            created_at: entry.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<SemanticSearchHit> for SearchResult {
+    fn from(hit: SemanticSearchHit) -> Self {
+        let mut provenance = SearchProvenance {
+            memory_id: hit.memory_id,
+            role: hit.role,
+            ..Default::default()
+        };
+        match hit.kind {
+            SemanticHitKind::Message => provenance.message_id = Some(hit.source_id),
+            SemanticHitKind::Transcription => provenance.transcription_id = Some(hit.source_id),
+            SemanticHitKind::GraphNode => provenance.node_id = Some(hit.source_id),
+            SemanticHitKind::Summary => provenance.summary_id = Some(hit.source_id),
+        }
+        Self {
+            kind: hit.kind.as_str().to_string(),
+            score: hit.score,
+            session_id: hit.session_id,
+            session_title: hit.session_title,
+            text: hit.text,
+            created_at: hit.created_at.to_rfc3339(),
+            provenance,
+        }
+    }
+}
+
+impl From<SessionRecord> for SessionInfo {
+    fn from(record: SessionRecord) -> Self {
+        Self {
+            id: record.session_id,
+            title: record.title,
+            agent: record.agent,
+            tags: record.tags,
+            message_count: record.message_count,
+            created_at: record.created_at.to_rfc3339(),
+            updated_at: record.updated_at.to_rfc3339(),
+            archived_at: record.archived_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +719,74 @@ mod tests {
         assert!(err.details.is_some());
     }
 
+    #[test]
+    fn query_request_validation_caps_fields() {
+        let limits = RequestLimits {
+            max_message_chars: 5,
+            max_session_id_chars: 3,
+            ..RequestLimits::default()
+        };
+        let request = QueryRequest {
+            message: "héllo".to_string(),
+            session_id: Some("abc".to_string()),
+            agent: None,
+            stream: false,
+            temperature: Some(0.5),
+            max_tokens: None,
+        };
+        assert!(request.validate(&limits).is_ok());
+
+        let long_session = QueryRequest {
+            session_id: Some("abcd".to_string()),
+            ..request.clone()
+        };
+        let err = long_session.validate(&limits).unwrap_err();
+        assert_eq!(err.code, "field_too_long");
+        assert_eq!(err.details.unwrap()["field"], "session_id");
+
+        let hot = QueryRequest {
+            temperature: Some(3.0),
+            ..request.clone()
+        };
+        assert_eq!(hot.validate(&limits).unwrap_err().code, "invalid_request");
+
+        let empty = QueryRequest {
+            message: "  ".to_string(),
+            ..request
+        };
+        assert!(empty.validate(&limits).is_err());
+    }
+
+    #[test]
+    fn trigger_requests_need_a_url_safe_name_and_a_real_secret() {
+        let request = CreateTriggerRequest {
+            name: "deploy-prod".to_string(),
+            secret: "0123456789abcdef".to_string(),
+            spec_path: "specs/deploy.spec".to_string(),
+            agent: None,
+            variables: BTreeMap::from([("branch".to_string(), "/ref".to_string())]),
+        };
+        assert!(request.validate().is_ok());
+
+        for name in ["", "a/b", "deploy prod"] {
+            let bad = CreateTriggerRequest {
+                name: name.to_string(),
+                ..request.clone()
+            };
+            assert!(bad.validate().is_err(), "{:?} accepted", name);
+        }
+        let short = CreateTriggerRequest {
+            secret: "hunter2".to_string(),
+            ..request.clone()
+        };
+        assert!(short.validate().is_err());
+        let dotted = CreateTriggerRequest {
+            variables: BTreeMap::from([("branch".to_string(), "ref".to_string())]),
+            ..request
+        };
+        assert!(dotted.validate().unwrap_err().error.contains("'/ref'"));
+    }
+
     #[test]
     fn test_health_response() {
         let health = HealthResponse {
@@ -244,6 +794,8 @@ mod tests {
             version: "0.1.0".to_string(),
             uptime_seconds: 3600,
             active_sessions: 5,
+            queued_steps: 2,
+            session_queues: BTreeMap::from([("s1".to_string(), 3)]),
         };
 
         let json = serde_json::to_string(&health).unwrap();
@@ -0,0 +1,118 @@
+/// OpenAPI document of the REST API
+///
+/// Generated from the handler and model annotations, so it cannot drift from
+/// what the server accepts. Served at `/openapi.json`, with Swagger UI at
+/// `/swagger-ui`. The mesh and sync routes are left out: they are spoken
+/// between instances rather than by API clients.
+use crate::api::handlers;
+use crate::api::models::*;
+use crate::api::triggers;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Path of the OpenAPI document
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Path of the Swagger UI
+pub const SWAGGER_UI_PATH: &str = "/swagger-ui";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "spec-ai API",
+        description = "Query agents, inspect sessions and search memories of a spec-ai instance"
+    ),
+    paths(
+        handlers::health_check,
+        handlers::capabilities,
+        handlers::list_agents,
+        handlers::list_sessions,
+        handlers::get_session,
+        handlers::session_memory,
+        handlers::delete_memory,
+        handlers::policy_audit,
+        handlers::query,
+        handlers::stream_query,
+        handlers::semantic_search,
+        triggers::create_trigger,
+        triggers::list_triggers,
+        triggers::delete_trigger,
+        triggers::receive_webhook,
+        triggers::get_job,
+    ),
+    components(schemas(
+        QueryRequest,
+        QueryResponse,
+        ToolCallInfo,
+        ResponseMetadata,
+        StreamChunk,
+        ErrorResponse,
+        HealthResponse,
+        AgentListResponse,
+        AgentInfo,
+        SessionListResponse,
+        SessionInfo,
+        SearchRequest,
+        SearchResponse,
+        SearchResult,
+        SearchProvenance,
+        MemoryListResponse,
+        MemoryInfo,
+        MemoryDeleteResponse,
+        PolicyAuditResponse,
+        PolicyDecisionInfo,
+        CreateTriggerRequest,
+        TriggerInfo,
+        TriggerListResponse,
+        TriggerJobInfo,
+    )),
+    tags(
+        (name = "info", description = "Server health and capabilities"),
+        (name = "agents", description = "Configured agent profiles"),
+        (name = "sessions", description = "Conversation sessions"),
+        (name = "memory", description = "Recallable memories and semantic search"),
+        (name = "policy", description = "Decisions of the tool policy"),
+        (name = "query", description = "Running agent steps"),
+        (name = "triggers", description = "Webhooks that run specs as background jobs")
+    )
+)]
+pub struct ApiDoc;
+
+/// Swagger UI serving the document at [`OPENAPI_PATH`]
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_the_client_routes() {
+        let doc = ApiDoc::openapi();
+        for path in [
+            "/health",
+            "/query",
+            "/stream",
+            "/v1/search",
+            "/sessions/{session_id}",
+            "/v1/sessions/{session_id}/memory",
+            "/v1/memory/{vector_id}",
+            "/v1/policy/audit",
+            "/v1/triggers",
+            "/v1/hooks/{name}",
+            "/v1/jobs/{id}",
+        ] {
+            assert!(doc.paths.paths.contains_key(path), "missing {}", path);
+        }
+        assert!(!doc.paths.paths.keys().any(|path| path.starts_with("/sync")));
+
+        let schemas = &doc.components.as_ref().unwrap().schemas;
+        assert!(schemas.contains_key("QueryRequest"));
+        assert!(schemas.contains_key("ErrorResponse"));
+
+        let json = serde_json::to_value(&doc).unwrap();
+        assert_eq!(json["info"]["title"], "spec-ai API");
+        assert!(json["openapi"].as_str().unwrap().starts_with("3."));
+    }
+}
@@ -1,18 +1,28 @@
 /// HTTP server implementation
-use crate::api::handlers::{health_check, list_agents, query, stream_query, AppState};
+use crate::api::handlers::{
+    capabilities, delete_memory, get_session, health_check, list_agents, list_sessions,
+    policy_audit, query, semantic_search, session_memory, stream_query, AppState,
+};
 use crate::api::mesh::{
-    acknowledge_messages, deregister_instance, get_messages, heartbeat, list_instances,
+    acknowledge_messages, deregister_instance, get_messages, heartbeat, list_instances, mesh_stats,
     register_instance, send_message,
 };
+use crate::api::middleware::{limits_middleware, RequestLimits};
+use crate::api::openapi::swagger_ui;
+use crate::api::session_queue::{SessionQueues, DEFAULT_MAX_QUEUED_STEPS};
 use crate::api::sync_handlers::{
     bulk_toggle_sync, configure_sync, get_sync_status, handle_sync_apply, handle_sync_request,
     list_conflicts, list_sync_configs, toggle_sync,
 };
+use crate::api::triggers::{
+    create_trigger, delete_trigger, get_job, list_triggers, receive_webhook,
+};
 use crate::config::{AgentRegistry, AppConfig};
 use crate::persistence::Persistence;
 use crate::tools::ToolRegistry;
 use anyhow::Result;
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{delete, get, post},
     Router,
 };
@@ -31,6 +41,11 @@ pub struct ApiConfig {
     pub api_key: Option<String>,
     /// Enable CORS
     pub enable_cors: bool,
+    /// Caps on request sizes and query fields
+    pub limits: RequestLimits,
+    /// Steps that may wait behind a running step of the same session
+    /// before new ones get a 429
+    pub max_queued_steps: usize,
 }
 
 impl Default for ApiConfig {
@@ -40,6 +55,8 @@ impl Default for ApiConfig {
             port: 3000,
             api_key: None,
             enable_cors: true,
+            limits: RequestLimits::default(),
+            max_queued_steps: DEFAULT_MAX_QUEUED_STEPS,
         }
     }
 }
@@ -69,6 +86,16 @@ impl ApiConfig {
         self
     }
 
+    pub fn with_limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_max_queued_steps(mut self, steps: usize) -> Self {
+        self.max_queued_steps = steps;
+        self
+    }
+
     pub fn bind_address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
@@ -89,11 +116,47 @@ impl ApiServer {
         tool_registry: Arc<ToolRegistry>,
         app_config: AppConfig,
     ) -> Self {
-        let state = AppState::new(persistence, agent_registry, tool_registry, app_config);
+        let mut state = AppState::new(persistence, agent_registry, tool_registry, app_config);
+        state.limits = config.limits.clone();
+        state.session_queues = SessionQueues::new(config.max_queued_steps);
 
         Self { config, state }
     }
 
+    /// Use `instance_id` as this instance's mesh id (defaults to the
+    /// database instance id)
+    pub fn with_instance_id(mut self, instance_id: impl Into<String>) -> Self {
+        self.state.instance_id = instance_id.into();
+        self.state.quotas = Arc::new(crate::quota::QuotaTracker::new(
+            self.state.instance_id.clone(),
+            self.state.config.mesh.quotas.clone(),
+        ));
+        self
+    }
+
+    /// Catalog this server advertises at `/v1/capabilities`
+    pub fn capability_catalog(&self) -> crate::mesh::CapabilityCatalog {
+        self.state.capability_catalog()
+    }
+
+    /// Enforce quotas with `quotas`, e.g. one shared with the mesh tool
+    /// executor (defaults to a tracker over `[mesh.quotas]`)
+    pub fn with_quotas(mut self, quotas: Arc<crate::quota::QuotaTracker>) -> Self {
+        self.state.quotas = quotas;
+        self
+    }
+
+    /// Check members' join tokens against `keyring` (see
+    /// [`MeshRegistry::with_join_tokens`](crate::api::mesh::MeshRegistry::with_join_tokens))
+    pub fn with_join_tokens(
+        mut self,
+        keyring: crate::mesh_invite::MeshKeyring,
+        required: bool,
+    ) -> Self {
+        self.state.mesh_registry = self.state.mesh_registry.with_join_tokens(keyring, required);
+        self
+    }
+
     /// Get the mesh registry for self-registration
     pub fn mesh_registry(&self) -> &crate::api::mesh::MeshRegistry {
         &self.state.mesh_registry
@@ -105,9 +168,22 @@ impl ApiServer {
             // Health and info endpoints
             .route("/health", get(health_check))
             .route("/agents", get(list_agents))
+            .route("/sessions", get(list_sessions))
+            .route("/sessions/:session_id", get(get_session))
+            // Memory inspection endpoints
+            .route("/v1/sessions/:session_id/memory", get(session_memory))
+            .route("/v1/memory/:vector_id", delete(delete_memory))
+            .route("/v1/policy/audit", get(policy_audit))
             // Query endpoints
             .route("/query", post(query))
             .route("/stream", post(stream_query))
+            .route("/v1/search", post(semantic_search))
+            .route("/v1/capabilities", get(capabilities))
+            // Webhook triggers
+            .route("/v1/triggers", get(list_triggers).post(create_trigger))
+            .route("/v1/triggers/:id", delete(delete_trigger))
+            .route("/v1/hooks/:name", post(receive_webhook))
+            .route("/v1/jobs/:id", get(get_job))
             // Mesh registry endpoints
             .route("/registry/register", post(register_instance::<AppState>))
             .route("/registry/agents", get(list_instances::<AppState>))
@@ -119,6 +195,7 @@ impl ApiServer {
                 "/registry/deregister/:instance_id",
                 delete(deregister_instance::<AppState>),
             )
+            .route("/mesh/stats", get(mesh_stats::<AppState>))
             // Message routing endpoints
             .route(
                 "/messages/send/:source_instance",
@@ -141,8 +218,15 @@ impl ApiServer {
                 post(configure_sync),
             )
             .route("/sync/conflicts", get(list_conflicts))
+            // OpenAPI document and Swagger UI
+            .merge(swagger_ui())
             // Add state
-            .with_state(self.state.clone());
+            .with_state(self.state.clone())
+            .layer(axum::middleware::from_fn_with_state(
+                self.config.limits.clone(),
+                limits_middleware,
+            ))
+            .layer(DefaultBodyLimit::max(self.config.limits.max_body_bytes));
 
         // Add CORS if enabled
         if self.config.enable_cors {
@@ -229,4 +313,53 @@ mod tests {
 
         assert_eq!(config.bind_address(), "localhost:5000");
     }
+
+    #[tokio::test]
+    async fn read_replicas_refuse_runs_and_writes() {
+        use crate::api::models::ErrorResponse;
+        use crate::config::MeshRole;
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let persistence = Persistence::in_memory().unwrap();
+        let mut app_config = AppConfig::default();
+        app_config.mesh.role = MeshRole::ReadReplica;
+        let server = ApiServer::new(
+            ApiConfig::default(),
+            persistence.clone(),
+            Arc::new(AgentRegistry::new(
+                std::collections::HashMap::new(),
+                persistence,
+            )),
+            Arc::new(ToolRegistry::new()),
+            app_config,
+        );
+
+        let run = r#"{"message": "hello"}"#;
+        for (method, uri, body) in [
+            ("POST", "/query", run),
+            ("POST", "/stream", run),
+            ("DELETE", "/v1/memory/1", ""),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = server.build_router().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(error.code, "read_only_replica", "{}", uri);
+        }
+
+        // Reads are still served
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        let response = server.build_router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,179 @@
+/// Per-session serialization of agent steps
+///
+/// Two requests for the same session would otherwise load the same history
+/// and interleave their message and memory writes. Each session gets an
+/// async lock: steps for one session run one at a time, in arrival order,
+/// while different sessions run in parallel. A session with too many steps
+/// waiting refuses new ones.
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Steps that may wait behind the running one, per session
+pub const DEFAULT_MAX_QUEUED_STEPS: usize = 8;
+
+#[derive(Default)]
+struct Slot {
+    lock: Arc<AsyncMutex<()>>,
+    /// Steps holding a ticket: the running one plus those waiting
+    depth: usize,
+}
+
+/// Locks and queue depths of the sessions with steps in flight
+#[derive(Clone)]
+pub struct SessionQueues {
+    slots: Arc<Mutex<HashMap<String, Slot>>>,
+    max_queued: usize,
+}
+
+/// Refusal of a step because its session's queue is full
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionBusy {
+    pub session_id: String,
+    /// Steps already running or waiting for the session
+    pub queue_depth: usize,
+    pub max_queued: usize,
+}
+
+impl SessionBusy {
+    pub const CODE: &'static str = "session_busy";
+}
+
+impl std::fmt::Display for SessionBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Session '{}' already has {} step(s) in flight; retry once they finish",
+            self.session_id, self.queue_depth
+        )
+    }
+}
+
+/// A place in a session's queue; [`wait`](Self::wait) for the session's turn
+pub struct SessionTicket {
+    queues: SessionQueues,
+    session_id: String,
+    lock: Arc<AsyncMutex<()>>,
+}
+
+/// The session's turn to run a step, held until dropped. Field order makes
+/// the lock release before the queue depth drops.
+pub struct SessionTurn {
+    _guard: OwnedMutexGuard<()>,
+    _ticket: SessionTicket,
+}
+
+impl SessionQueues {
+    pub fn new(max_queued: usize) -> Self {
+        Self {
+            slots: Arc::new(Mutex::new(HashMap::new())),
+            max_queued,
+        }
+    }
+
+    /// Join `session_id`'s queue, or refuse when `max_queued` steps are
+    /// already waiting behind the running one
+    pub fn reserve(&self, session_id: &str) -> Result<SessionTicket, SessionBusy> {
+        let mut slots = self.slots.lock().expect("session queue mutex poisoned");
+        let slot = slots.entry(session_id.to_string()).or_default();
+        if slot.depth > self.max_queued {
+            return Err(SessionBusy {
+                session_id: session_id.to_string(),
+                queue_depth: slot.depth,
+                max_queued: self.max_queued,
+            });
+        }
+        slot.depth += 1;
+        Ok(SessionTicket {
+            queues: self.clone(),
+            session_id: session_id.to_string(),
+            lock: slot.lock.clone(),
+        })
+    }
+
+    /// Steps running or waiting, per session with any
+    pub fn depths(&self) -> BTreeMap<String, usize> {
+        self.slots
+            .lock()
+            .expect("session queue mutex poisoned")
+            .iter()
+            .map(|(session_id, slot)| (session_id.clone(), slot.depth))
+            .collect()
+    }
+
+    /// Steps waiting for their session's turn, across all sessions
+    pub fn waiting(&self) -> usize {
+        self.depths()
+            .values()
+            .map(|depth| depth.saturating_sub(1))
+            .sum()
+    }
+}
+
+impl Default for SessionQueues {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_QUEUED_STEPS)
+    }
+}
+
+impl SessionTicket {
+    /// Wait until the steps ahead of this one have finished
+    pub async fn wait(self) -> SessionTurn {
+        let guard = self.lock.clone().lock_owned().await;
+        SessionTurn {
+            _guard: guard,
+            _ticket: self,
+        }
+    }
+}
+
+impl Drop for SessionTicket {
+    fn drop(&mut self) {
+        let mut slots = self
+            .queues
+            .slots
+            .lock()
+            .expect("session queue mutex poisoned");
+        if let Some(slot) = slots.get_mut(&self.session_id) {
+            slot.depth = slot.depth.saturating_sub(1);
+            if slot.depth == 0 {
+                slots.remove(&self.session_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn steps_for_a_session_run_one_at_a_time() {
+        let queues = SessionQueues::new(1);
+        let first = queues.reserve("s1").unwrap().wait().await;
+
+        // Another session is not held up
+        let other = queues.reserve("s2").unwrap().wait().await;
+        drop(other);
+
+        let second = queues.reserve("s1").unwrap();
+        let busy = queues.reserve("s1").err().unwrap();
+        assert_eq!(busy.queue_depth, 2);
+        assert_eq!(queues.waiting(), 1);
+
+        let waiting = tokio::spawn(second.wait());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let turn = tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("second step gets its turn")
+            .unwrap();
+        assert_eq!(queues.depths().get("s1"), Some(&1));
+        drop(turn);
+        assert!(queues.depths().is_empty());
+    }
+}
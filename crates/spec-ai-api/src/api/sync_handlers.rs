@@ -3,7 +3,9 @@ use axum::extract::{Json, Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
-use spec_ai_core::sync::{GraphSyncPayload, SyncEngine, SyncType, VectorClock};
+use spec_ai_core::sync::{
+    GraphSyncPayload, SyncAcl, SyncDenied, SyncEngine, SyncPeer, SyncType, VectorClock,
+};
 
 /// Request to initiate a sync
 #[derive(Debug, Deserialize)]
@@ -50,6 +52,29 @@ pub struct ConflictInfo {
     pub detected_at: String,
 }
 
+/// A mesh instance as the sync ACL sees it, with the capabilities it
+/// registered; instances not in the registry have none
+async fn sync_peer(state: &AppState, instance_id: &str) -> SyncPeer {
+    let capabilities = state
+        .mesh_registry
+        .list()
+        .await
+        .into_iter()
+        .find(|instance| instance.instance_id == instance_id)
+        .map(|instance| instance.capabilities)
+        .unwrap_or_default();
+    SyncPeer::new(instance_id, capabilities)
+}
+
+/// 403 for syncs the ACL refuses, 500 for anything else
+fn sync_error_status(err: &anyhow::Error) -> StatusCode {
+    if err.downcast_ref::<SyncDenied>().is_some() {
+        StatusCode::FORBIDDEN
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
 /// Handle sync request from a peer
 pub async fn handle_sync_request(
     State(state): State<AppState>,
@@ -57,7 +82,9 @@ pub async fn handle_sync_request(
 ) -> impl IntoResponse {
     let persistence = state.persistence.clone();
     let instance_id = crate::api::mesh::MeshClient::generate_instance_id();
-    let sync_engine = SyncEngine::new(persistence.clone(), instance_id);
+    let sync_engine = SyncEngine::new(persistence.clone(), instance_id)
+        .with_acl(SyncAcl::from_config(&state.config.sync))
+        .for_peer(sync_peer(&state, &request.requesting_instance).await);
 
     // Parse their vector clock
     let their_vc = if let Some(ref vc_str) = request.vector_clock {
@@ -113,7 +140,7 @@ pub async fn handle_sync_request(
                 Ok(p) => p,
                 Err(e) => {
                     return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
+                        sync_error_status(&e),
                         Json(SyncResponse {
                             success: false,
                             message: format!("Full sync failed: {}", e),
@@ -135,7 +162,7 @@ pub async fn handle_sync_request(
                 Ok(p) => p,
                 Err(e) => {
                     return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
+                        sync_error_status(&e),
                         Json(SyncResponse {
                             success: false,
                             message: format!("Incremental sync failed: {}", e),
@@ -174,7 +201,13 @@ pub async fn handle_sync_apply(
 ) -> impl IntoResponse {
     let persistence = state.persistence.clone();
     let instance_id = crate::api::mesh::MeshClient::generate_instance_id();
-    let sync_engine = SyncEngine::new(persistence.clone(), instance_id);
+    let peer = match payload.source_instance.as_deref() {
+        Some(source) => sync_peer(&state, source).await,
+        None => SyncPeer::default(),
+    };
+    let sync_engine = SyncEngine::new(persistence.clone(), instance_id)
+        .with_acl(SyncAcl::from_config(&state.config.sync))
+        .for_peer(peer);
 
     let graph_name = payload.graph_name.as_deref().unwrap_or("default");
 
@@ -195,7 +228,7 @@ pub async fn handle_sync_apply(
             })),
         ),
         Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            sync_error_status(&e),
             Json(serde_json::json!({
                 "success": false,
                 "message": format!("Failed to apply sync: {}", e)
@@ -0,0 +1,555 @@
+/// Webhook triggers: inbound webhooks that run a spec as a background job
+///
+/// `POST /v1/triggers` registers a webhook name, its secret and the spec it
+/// runs. Deliveries to `/v1/hooks/:name` must carry a GitHub-style
+/// `X-Hub-Signature-256` HMAC of the body, or the secret itself in
+/// `X-Webhook-Token` / `X-Gitlab-Token`. Accepted deliveries fill the spec's
+/// variables from the JSON payload and are answered with `202` and a job,
+/// whose progress and answer `/v1/jobs/:id` reports. A few jobs run at a
+/// time and a bounded number wait their turn; deliveries beyond that get
+/// `429` so the sender can redeliver later.
+use crate::api::handlers::{create_agent, read_only_replica, uuid_v4, AppState};
+use crate::api::middleware::bad_request;
+use crate::api::models::*;
+use crate::persistence::triggers::{TriggerJobStatus, WebhookTrigger};
+use crate::spec::AgentSpec;
+use crate::types::MessageRole;
+use anyhow::{anyhow, Context};
+use axum::{
+    body::Bytes,
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use ring::hmac;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+
+/// Headers carrying the secret itself, for senders that cannot sign
+const TOKEN_HEADERS: [&str; 2] = ["x-webhook-token", "x-gitlab-token"];
+
+/// Messages read back when looking for a job's answer
+const JOB_MESSAGES: i64 = 20;
+
+/// Trigger jobs that run at once
+pub const DEFAULT_MAX_RUNNING_JOBS: usize = 4;
+
+/// Trigger jobs that may wait for a running one to finish
+pub const DEFAULT_MAX_QUEUED_JOBS: usize = 64;
+
+/// Caps on the trigger jobs in flight: `accepted` bounds jobs queued or
+/// running, `running` those past the queue
+#[derive(Clone)]
+pub struct TriggerJobSlots {
+    accepted: Arc<Semaphore>,
+    running: Arc<Semaphore>,
+}
+
+impl TriggerJobSlots {
+    pub fn new(max_running: usize, max_queued: usize) -> Self {
+        Self {
+            accepted: Arc::new(Semaphore::new(max_running + max_queued)),
+            running: Arc::new(Semaphore::new(max_running)),
+        }
+    }
+
+    /// A place for one more job, or `None` when the queue is full
+    fn admit(&self) -> Option<OwnedSemaphorePermit> {
+        self.accepted.clone().try_acquire_owned().ok()
+    }
+
+    /// Wait until fewer than the maximum jobs are running
+    async fn turn(&self) -> Option<OwnedSemaphorePermit> {
+        self.running.clone().acquire_owned().await.ok()
+    }
+}
+
+impl Default for TriggerJobSlots {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RUNNING_JOBS, DEFAULT_MAX_QUEUED_JOBS)
+    }
+}
+
+/// Register a webhook that runs a spec
+#[utoipa::path(
+    post,
+    path = "/v1/triggers",
+    tag = "triggers",
+    request_body = CreateTriggerRequest,
+    responses(
+        (status = 201, description = "The registered trigger", body = TriggerInfo),
+        (status = 400, description = "Invalid request, spec or agent", body = ErrorResponse),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 409, description = "A trigger with this name exists", body = ErrorResponse)
+    )
+)]
+pub async fn create_trigger(
+    State(state): State<AppState>,
+    Json(request): Json<CreateTriggerRequest>,
+) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    if let Err(error) = request.validate() {
+        return bad_request(error);
+    }
+    if let Some(agent) = &request.agent {
+        if state.agent_registry.get(agent).is_none() {
+            return bad_request(ErrorResponse::new(
+                "agent_error",
+                format!("Agent '{}' not found", agent),
+            ));
+        }
+    }
+
+    // Check the spec now rather than on the first delivery
+    let spec = match AgentSpec::from_file_unresolved(&request.spec_path) {
+        Ok(spec) => spec,
+        Err(e) => return bad_request(ErrorResponse::new("invalid_spec", format!("{:#}", e))),
+    };
+    if let Some(variable) = request
+        .variables
+        .keys()
+        .find(|name| !spec.variables.contains_key(*name))
+    {
+        return bad_request(ErrorResponse::new(
+            "invalid_request",
+            format!("spec does not declare a variable named '{}'", variable),
+        ));
+    }
+    let spec_path = std::fs::canonicalize(&request.spec_path)
+        .map(|path| path.display().to_string())
+        .unwrap_or(request.spec_path);
+
+    match state.persistence.webhook_trigger(&request.name) {
+        Ok(Some(_)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(
+                    "trigger_exists",
+                    format!("A trigger named '{}' already exists", request.name),
+                )),
+            )
+                .into_response();
+        }
+        Ok(None) => {}
+        Err(e) => return trigger_error(e),
+    }
+
+    match state.persistence.add_webhook_trigger(
+        &request.name,
+        &request.secret,
+        &spec_path,
+        request.agent.as_deref(),
+        &request.variables,
+    ) {
+        Ok(trigger) => (StatusCode::CREATED, Json(TriggerInfo::from(trigger))).into_response(),
+        Err(e) => trigger_error(e),
+    }
+}
+
+/// List registered webhooks
+#[utoipa::path(
+    get,
+    path = "/v1/triggers",
+    tag = "triggers",
+    responses((status = 200, description = "Registered triggers, oldest first", body = TriggerListResponse))
+)]
+pub async fn list_triggers(State(state): State<AppState>) -> Response {
+    match state.persistence.list_webhook_triggers() {
+        Ok(triggers) => Json(TriggerListResponse {
+            triggers: triggers.into_iter().map(TriggerInfo::from).collect(),
+        })
+        .into_response(),
+        Err(e) => trigger_error(e),
+    }
+}
+
+/// Remove a webhook; jobs it started are kept
+#[utoipa::path(
+    delete,
+    path = "/v1/triggers/{id}",
+    tag = "triggers",
+    params(("id" = i64, Path, description = "Trigger ID")),
+    responses(
+        (status = 204, description = "The trigger was removed"),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 404, description = "No such trigger", body = ErrorResponse)
+    )
+)]
+pub async fn delete_trigger(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    match state.persistence.delete_webhook_trigger(id) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => not_found("trigger_not_found", format!("Trigger {} not found", id)),
+        Err(e) => trigger_error(e),
+    }
+}
+
+/// Receive a webhook delivery and queue its spec run
+#[utoipa::path(
+    post,
+    path = "/v1/hooks/{name}",
+    tag = "triggers",
+    params(("name" = String, Path, description = "Trigger name")),
+    request_body(content = Object, description = "JSON payload the spec variables are read from"),
+    responses(
+        (status = 202, description = "The queued job", body = TriggerJobInfo),
+        (status = 400, description = "The payload is not JSON", body = ErrorResponse),
+        (status = 401, description = "Missing or wrong signature", body = ErrorResponse),
+        (status = 403, description = "This instance is a read replica", body = ErrorResponse),
+        (status = 404, description = "No such trigger", body = ErrorResponse),
+        (status = 429, description = "Too many jobs are queued", body = ErrorResponse)
+    )
+)]
+pub async fn receive_webhook(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !state.config.mesh.role.accepts_writes() {
+        return read_only_replica();
+    }
+    let trigger = match state.persistence.webhook_trigger(&name) {
+        Ok(Some(trigger)) => trigger,
+        Ok(None) => return not_found("trigger_not_found", format!("Trigger '{}' not found", name)),
+        Err(e) => return trigger_error(e),
+    };
+    if !verify_delivery(&trigger.secret, &headers, &body) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "invalid_signature",
+                format!(
+                    "Delivery must be signed with {} or carry the secret in {}",
+                    SIGNATURE_HEADER, TOKEN_HEADERS[0]
+                ),
+            )),
+        )
+            .into_response();
+    }
+    let payload: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return bad_request(ErrorResponse::new(
+                    "invalid_payload",
+                    format!("Payload is not JSON: {}", e),
+                ))
+            }
+        }
+    };
+    let variables = payload_variables(&trigger, &payload);
+
+    let Some(slot) = state.trigger_jobs.admit() else {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse::new(
+                "too_many_jobs",
+                "Too many trigger jobs are queued; redeliver later",
+            )),
+        )
+            .into_response();
+    };
+    let session_id = format!("trigger_{}_{}", trigger.name, uuid_v4());
+    let job_id = match state.persistence.add_trigger_job(trigger.id, &session_id) {
+        Ok(id) => id,
+        Err(e) => return trigger_error(e),
+    };
+    let job = match state.persistence.trigger_job(job_id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return trigger_error(anyhow!("job {} vanished", job_id)),
+        Err(e) => return trigger_error(e),
+    };
+
+    tokio::spawn(async move {
+        let _slot = slot;
+        let _turn = state.trigger_jobs.turn().await;
+        run_job(state, trigger, job_id, session_id, variables).await
+    });
+
+    (StatusCode::ACCEPTED, Json(TriggerJobInfo::from(job))).into_response()
+}
+
+/// Status of a triggered run, with the agent's answer once it succeeded
+#[utoipa::path(
+    get,
+    path = "/v1/jobs/{id}",
+    tag = "triggers",
+    params(("id" = i64, Path, description = "Job ID from the webhook response")),
+    responses(
+        (status = 200, description = "The job", body = TriggerJobInfo),
+        (status = 404, description = "No such job", body = ErrorResponse)
+    )
+)]
+pub async fn get_job(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    let job = match state.persistence.trigger_job(id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return not_found("job_not_found", format!("Job {} not found", id)),
+        Err(e) => return trigger_error(e),
+    };
+    let response = if job.status == TriggerJobStatus::Succeeded {
+        state
+            .persistence
+            .list_messages(&job.session_id, JOB_MESSAGES)
+            .ok()
+            .and_then(|messages| {
+                messages
+                    .into_iter()
+                    .rev()
+                    .find(|m| m.role == MessageRole::Assistant)
+            })
+            .map(|m| m.content)
+    } else {
+        None
+    };
+    Json(TriggerJobInfo {
+        response,
+        ..TriggerJobInfo::from(job)
+    })
+    .into_response()
+}
+
+/// Run a delivery's spec and record how it went
+async fn run_job(
+    state: AppState,
+    trigger: WebhookTrigger,
+    job_id: i64,
+    session_id: String,
+    variables: HashMap<String, String>,
+) {
+    let persistence = state.persistence.clone();
+    if let Err(e) =
+        persistence.set_trigger_job_status(job_id, TriggerJobStatus::Running, None, None)
+    {
+        tracing::warn!("Failed to mark trigger job {} running: {}", job_id, e);
+    }
+    let result = run_spec(&state, &trigger, &session_id, &variables).await;
+    let recorded = match &result {
+        Ok(run_id) => persistence.set_trigger_job_status(
+            job_id,
+            TriggerJobStatus::Succeeded,
+            Some(run_id),
+            None,
+        ),
+        Err(e) => {
+            tracing::warn!("Trigger '{}' job {} failed: {:#}", trigger.name, job_id, e);
+            persistence.set_trigger_job_status(
+                job_id,
+                TriggerJobStatus::Failed,
+                None,
+                Some(&format!("{:#}", e)),
+            )
+        }
+    };
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record trigger job {}: {}", job_id, e);
+    }
+}
+
+/// Run the spec unattended: the spec's own `[[permissions]]` are not
+/// granted, as there is no operator to approve them
+async fn run_spec(
+    state: &AppState,
+    trigger: &WebhookTrigger,
+    session_id: &str,
+    variables: &HashMap<String, String>,
+) -> anyhow::Result<String> {
+    let spec = AgentSpec::from_file_with_vars(&trigger.spec_path, variables)?;
+    let agent_name = trigger.agent.as_deref().unwrap_or("default");
+    let mut agent = create_agent(state, agent_name, session_id, None).await?;
+    let permit = state.quotas.begin_run(agent_name)?;
+    let output = agent
+        .run_spec(&spec)
+        .await
+        .with_context(|| format!("running {}", trigger.spec_path))?;
+    if let Some(usage) = &output.token_usage {
+        permit.record_tokens(u64::from(usage.total_tokens));
+    }
+    Ok(output.run_id)
+}
+
+/// Whether a delivery carries a valid signature, or the secret itself
+fn verify_delivery(secret: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    if let Some(signature) = headers.get(SIGNATURE_HEADER) {
+        return signature
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().strip_prefix("sha256="))
+            .and_then(decode_hex)
+            .is_some_and(|tag| hmac::verify(&key, body, &tag).is_ok());
+    }
+    // Compare through the HMAC so the comparison takes constant time
+    let expected = hmac::sign(&key, secret.as_bytes());
+    TOKEN_HEADERS
+        .iter()
+        .filter_map(|name| headers.get(*name))
+        .any(|token| hmac::verify(&key, token.as_bytes(), expected.as_ref()).is_ok())
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(text.get(at..at + 2)?, 16).ok())
+        .collect()
+}
+
+/// Spec variables read from the payload; strings are used as they are,
+/// other values as JSON, and missing ones are left to the spec's defaults
+fn payload_variables(trigger: &WebhookTrigger, payload: &Value) -> HashMap<String, String> {
+    trigger
+        .variables
+        .iter()
+        .filter_map(|(name, pointer)| {
+            let value = match payload.pointer(pointer)? {
+                Value::Null => return None,
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            Some((name.clone(), value))
+        })
+        .collect()
+}
+
+fn not_found(code: &str, message: String) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new(code, message)),
+    )
+        .into_response()
+}
+
+fn trigger_error(e: anyhow::Error) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(
+            "trigger_error",
+            format!("Trigger storage failed: {}", e),
+        )),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    const SECRET: &str = "0123456789abcdef";
+
+    fn trigger(variables: &[(&str, &str)]) -> WebhookTrigger {
+        WebhookTrigger {
+            id: 1,
+            name: "deploy".to_string(),
+            secret: SECRET.to_string(),
+            spec_path: "deploy.spec".to_string(),
+            agent: None,
+            variables: variables
+                .iter()
+                .map(|(name, pointer)| (name.to_string(), pointer.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn signed(body: &[u8], secret: &str) -> HeaderMap {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let hex: String = hmac::sign(&key, body)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!("sha256={}", hex)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn deliveries_must_be_signed_with_the_secret() {
+        let body = br#"{"ref":"main"}"#;
+        assert!(verify_delivery(SECRET, &signed(body, SECRET), body));
+        assert!(!verify_delivery(
+            SECRET,
+            &signed(body, "another secret!!"),
+            body
+        ));
+        assert!(!verify_delivery(SECRET, &signed(b"{}", SECRET), body));
+        assert!(!verify_delivery(SECRET, &HeaderMap::new(), body));
+
+        let mut garbled = HeaderMap::new();
+        garbled.insert(SIGNATURE_HEADER, HeaderValue::from_static("sha256=zz"));
+        assert!(!verify_delivery(SECRET, &garbled, body));
+
+        let mut token = HeaderMap::new();
+        token.insert("x-gitlab-token", HeaderValue::from_static(SECRET));
+        assert!(verify_delivery(SECRET, &token, body));
+        token.insert(
+            "x-gitlab-token",
+            HeaderValue::from_static("0123456789abcdeX"),
+        );
+        assert!(!verify_delivery(SECRET, &token, body));
+    }
+
+    #[tokio::test]
+    async fn jobs_beyond_the_queue_are_refused() {
+        let slots = TriggerJobSlots::new(1, 1);
+        let first = slots.admit().unwrap();
+        let second = slots.admit().unwrap();
+        assert!(slots.admit().is_none());
+
+        let turn = slots.turn().await.unwrap();
+        assert!(slots.running.clone().try_acquire_owned().is_err());
+        drop(turn);
+        drop(first);
+        assert!(slots.admit().is_some());
+        drop(second);
+    }
+
+    #[test]
+    fn variables_are_read_from_the_payload() {
+        let payload = json!({
+            "ref": "refs/heads/main",
+            "pusher": {"name": "ada"},
+            "commits": [{"id": "abc"}],
+            "forced": false,
+            "base": null
+        });
+        let variables = payload_variables(
+            &trigger(&[
+                ("branch", "/ref"),
+                ("who", "/pusher/name"),
+                ("first_commit", "/commits/0/id"),
+                ("forced", "/forced"),
+                ("base", "/base"),
+                ("missing", "/nope"),
+            ]),
+            &payload,
+        );
+        assert_eq!(variables["branch"], "refs/heads/main");
+        assert_eq!(variables["who"], "ada");
+        assert_eq!(variables["first_commit"], "abc");
+        assert_eq!(variables["forced"], "false");
+        assert!(!variables.contains_key("base"));
+        assert!(!variables.contains_key("missing"));
+    }
+}
@@ -1,4 +1,4 @@
 pub mod api;
-pub use spec_ai_config::{config, persistence};
-pub use spec_ai_core::{agent, embeddings, mesh, spec, tools};
+pub use spec_ai_config::{config, persistence, types};
+pub use spec_ai_core::{agent, embeddings, mesh, mesh_invite, quota, spec, tools};
 pub use spec_ai_policy::{plugin, policy};
@@ -7,7 +7,7 @@ use tracing::{debug, error, info, warn};
 
 use crate::api::mesh::{MeshClient, MeshRegistry};
 use crate::persistence::Persistence;
-use spec_ai_core::sync::{GraphSyncPayload, SyncEngine};
+use spec_ai_core::sync::{GraphSyncPayload, SyncAccess, SyncAcl, SyncEngine, SyncPeer};
 
 /// Configuration for the sync coordinator
 #[derive(Debug, Clone)]
@@ -20,6 +20,9 @@ pub struct SyncCoordinatorConfig {
     pub retry_interval_secs: u64,
     /// Maximum number of retry attempts
     pub max_retries: usize,
+    /// `[[sync.acl]]` rules; graphs are only pulled from peers allowed to
+    /// write them
+    pub acl: SyncAcl,
 }
 
 impl Default for SyncCoordinatorConfig {
@@ -29,6 +32,7 @@ impl Default for SyncCoordinatorConfig {
             max_concurrent_syncs: 3,  // Up to 3 concurrent syncs
             retry_interval_secs: 300, // Retry after 5 minutes
             max_retries: 3,           // Max 3 retry attempts
+            acl: SyncAcl::default(),
         }
     }
 }
@@ -117,6 +121,19 @@ impl SyncCoordinator {
                 if peer.instance_id == self.instance_id {
                     continue; // Skip self
                 }
+                let sync_peer = SyncPeer::new(&peer.instance_id, peer.capabilities.clone());
+                if !self.config.acl.allows(
+                    &sync_peer,
+                    SyncAccess::Write,
+                    &session_id,
+                    &graph_name,
+                ) {
+                    debug!(
+                        "Skipping {}/{} for peer {}: not allowed by sync ACL",
+                        session_id, graph_name, peer.instance_id
+                    );
+                    continue;
+                }
 
                 let permit = semaphore.clone().acquire_owned().await?;
                 let self_clone = self.clone();
@@ -130,7 +147,7 @@ impl SyncCoordinator {
                     let _permit = permit; // Hold permit until task completes
 
                     match self_clone
-                        .sync_with_peer(&session_id, &graph_name, &peer_id, &peer_url)
+                        .sync_with_peer(&session_id, &graph_name, sync_peer, &peer_url)
                         .await
                     {
                         Ok(_) => {
@@ -204,16 +221,19 @@ impl SyncCoordinator {
         &self,
         session_id: &str,
         graph_name: &str,
-        peer_id: &str,
+        peer: SyncPeer,
         peer_url: &str,
     ) -> Result<()> {
+        let peer_id = peer.instance_id.clone();
         debug!(
             "Syncing {}/{} with peer {} at {}",
             session_id, graph_name, peer_id, peer_url
         );
 
         // Create sync engine
-        let sync_engine = SyncEngine::new((*self.persistence).clone(), self.instance_id.clone());
+        let sync_engine = SyncEngine::new((*self.persistence).clone(), self.instance_id.clone())
+            .with_acl(self.config.acl.clone())
+            .for_peer(peer);
 
         // Get our current vector clock
         let our_vc = self
@@ -1,8 +1,15 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use spec_ai_core::cli::CliState;
-use spec_ai_core::spec::AgentSpec;
-use std::path::PathBuf;
+use spec_ai_core::cli::{dialog, CliState};
+use spec_ai_core::mesh_invite::MeshKeyring;
+use spec_ai_core::spec::{AgentSpec, SpecPermission};
+use spec_ai_core::spec_library::SpecLibrary;
+use spec_ai_core::spec_source::{
+    self, Checksum, RemoteSpec, SpecCache, SpecProvenance, SpecSource,
+};
+use spec_ai_core::spec_vars;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 #[cfg(feature = "api")]
@@ -10,6 +17,7 @@ use {
     spec_ai_api::api::server::{ApiConfig, ApiServer},
     spec_ai_config::config::AgentRegistry,
     spec_ai_config::persistence::Persistence,
+    spec_ai_core::clock::Clock,
     spec_ai_core::tools::ToolRegistry,
     std::sync::Arc,
 };
@@ -22,6 +30,10 @@ struct Cli {
     #[arg(short, long, global = true)]
     config: Option<PathBuf>,
 
+    /// Reproducible runs: fixed seed, zero temperature, pinned tool timestamps
+    #[arg(long, global = true)]
+    deterministic: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -30,9 +42,16 @@ struct Cli {
 enum Commands {
     /// Run one or more spec files
     Run {
-        /// Spec files or directories to run. If not provided, uses spec/smoke.spec
+        /// Spec files, directories, `github:org/repo/path/to.spec` or
+        /// `https://` URLs to run. If not provided, uses spec/smoke.spec
         #[arg(value_name = "SPEC_OR_DIR")]
-        specs: Vec<PathBuf>,
+        specs: Vec<String>,
+        /// Expected checksum of the remote spec, as sha256:<hex> or blake3:<hex>
+        #[arg(long)]
+        checksum: Option<Checksum>,
+        /// Value for a spec variable; repeat for several
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
     },
     /// Start the API server for agent mesh functionality
     Server {
@@ -45,9 +64,195 @@ enum Commands {
         /// Join existing mesh at specified address
         #[arg(long)]
         join: Option<String>,
+        /// Token from `spec-ai mesh invite` on the leader
+        #[arg(long, value_name = "TOKEN")]
+        join_token: Option<String>,
+    },
+    /// Mesh administration
+    Mesh {
+        #[command(subcommand)]
+        command: MeshCommand,
+    },
+    /// Serve editor extensions over JSON-RPC (LSP-style framing) on stdio,
+    /// or on a unix socket with `--socket`
+    Daemon {
+        /// Unix socket to listen on instead of stdio
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+        /// Workspace to serve (default: the repository containing the
+        /// current directory)
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Seconds between workspace scans for changed files
+        #[arg(long, default_value = "2")]
+        watch_interval: u64,
+    },
+    /// Inspect stored conversation sessions
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommand,
+    },
+    /// Manage models in the local Ollama instance
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
+    },
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Load a repository's structure into a session's knowledge graph, as
+    /// `/init` does in the REPL
+    Bootstrap {
+        /// Repository to bootstrap (default: the repository containing the
+        /// current directory)
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Plugins to run, comma separated (default: auto-detect)
+        #[arg(long, value_delimiter = ',')]
+        plugins: Option<Vec<String>>,
+        /// Session to store the graph in (default: `bootstrap-<repo name>`)
+        #[arg(long)]
+        session: Option<String>,
+        /// Reuse cached plugin results, as `/refresh` does
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Draft specs for components that no test or spec exercises, using
+    /// the bootstrap graph and recent git history
+    SuggestSpecs {
+        /// Repository to inspect (default: the repository containing the
+        /// current directory)
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Session holding the bootstrap graph (default: `bootstrap-<repo name>`)
+        #[arg(long)]
+        session: Option<String>,
+        /// Maximum number of specs to draft
+        #[arg(short, long, default_value = "5")]
+        limit: usize,
+        /// Directory for the drafts, relative to the repository root
+        #[arg(long, default_value = spec_ai_core::spec_suggest::DEFAULT_SUGGESTION_DIR)]
+        out: PathBuf,
+        /// Replace drafts that already exist
+        #[arg(long)]
+        force: bool,
+        /// List the uncovered components without drafting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Manage the local spec library used by `/spec <name>`
+    Specs {
+        #[command(subcommand)]
+        command: SpecsCommand,
     },
 }
 
+#[derive(Subcommand)]
+enum SessionsCommand {
+    /// Search message content, titles and tags across all sessions
+    Search {
+        /// Words to look for
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+        /// Maximum number of results
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Archive and delete sessions left inactive, as configured under
+    /// `[sessions]`
+    Sweep {
+        /// Report what would be archived or deleted without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Delete blobs that no message or tool log entry refers to any more
+    Prune {
+        /// Report what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Copy a session's messages, embeddings, graph, transcriptions and
+    /// tool log into another database file
+    CopySession {
+        /// Session to copy
+        session_id: String,
+        /// Database to copy from (default: the configured database)
+        #[arg(long, value_name = "DB")]
+        from: Option<PathBuf>,
+        /// Database to copy into; created if it does not exist
+        #[arg(long, value_name = "DB")]
+        to: PathBuf,
+        /// Store the copy under another session ID
+        #[arg(long = "as", value_name = "SESSION_ID")]
+        as_session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MeshCommand {
+    /// Mint a time-limited join token for a new member (run on the leader)
+    Invite {
+        /// Capabilities the member may register, comma separated; `*`
+        /// allows any
+        #[arg(long, value_delimiter = ',', default_value = "query")]
+        capabilities: Vec<String>,
+        /// Hours until the token expires
+        #[arg(long, default_value = "24")]
+        ttl_hours: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum SpecsCommand {
+    /// Show library specs with their descriptions
+    List,
+    /// Copy a spec file, `github:` source or `https://` URL into the library
+    Add {
+        /// Spec to add
+        source: String,
+        /// Library name (default: the spec's file name without `.spec`)
+        #[arg(long)]
+        name: Option<String>,
+        /// Expected checksum of a remote spec, as sha256:<hex> or blake3:<hex>
+        #[arg(long)]
+        checksum: Option<Checksum>,
+        /// Replace an existing spec of the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Delete a spec from the library
+    Remove { name: String },
+    /// Show a library spec's metadata and requirements
+    Info { name: String },
+}
+
+#[derive(Subcommand)]
+#[cfg_attr(not(feature = "ollama"), allow(dead_code))]
+enum ModelsCommand {
+    /// Show installed models with their sizes
+    List,
+    /// Download a model
+    Pull {
+        /// Model to pull, e.g. `llama3` or `mistral:7b` (default: the configured Ollama model)
+        name: Option<String>,
+    },
+    /// Remove an installed model
+    Rm {
+        /// Model to remove
+        name: String,
+    },
+}
+
+fn parse_var(arg: &str) -> Result<(String, String)> {
+    spec_vars::parse_var_arg(arg)
+}
+
 fn collect_spec_files(path: &PathBuf) -> Result<Vec<PathBuf>> {
     let mut specs = Vec::new();
 
@@ -82,7 +287,12 @@ fn collect_spec_files(path: &PathBuf) -> Result<Vec<PathBuf>> {
     Ok(specs)
 }
 
-async fn run_spec_file(cli: &mut CliState, spec_path: &PathBuf) -> Result<bool> {
+async fn run_spec_file(
+    cli: &mut CliState,
+    spec_path: &PathBuf,
+    provenance: Option<&SpecProvenance>,
+    vars: &HashMap<String, String>,
+) -> Result<bool> {
     if !spec_path.exists() {
         eprintln!("Error: Spec file '{}' not found", spec_path.display());
         return Ok(false);
@@ -95,10 +305,18 @@ async fn run_spec_file(cli: &mut CliState, spec_path: &PathBuf) -> Result<bool>
         )
     })?;
 
-    println!("=== Running spec: {} ===", abs_path.display());
+    match provenance {
+        Some(provenance) => {
+            println!("=== Running spec: {} ===", provenance.source);
+            println!("    from {}", provenance.summary());
+        }
+        None => println!("=== Running spec: {} ===", abs_path.display()),
+    }
 
-    let spec = AgentSpec::from_file(&abs_path)?;
-    let output = cli.agent.run_spec(&spec).await?;
+    let spec = AgentSpec::from_file_with_vars(&abs_path, vars)?;
+    let granted =
+        approve_spec_permissions(&spec, &abs_path, provenance, &cli.config.specs.trusted_keys)?;
+    let output = cli.agent.run_spec_with_permissions(&spec, granted).await?;
 
     // Print the response
     println!("{}", output.response);
@@ -108,22 +326,131 @@ async fn run_spec_file(cli: &mut CliState, spec_path: &PathBuf) -> Result<bool>
     Ok(true)
 }
 
+/// The `[[permissions]]` of a spec granted for this run. Specs signed by a
+/// trusted key get them without asking; otherwise the operator is asked on
+/// the terminal, and without a terminal the spec runs without them.
+fn approve_spec_permissions(
+    spec: &AgentSpec,
+    path: &Path,
+    provenance: Option<&SpecProvenance>,
+    trusted_keys: &[String],
+) -> Result<Vec<SpecPermission>> {
+    if spec.permissions.is_empty() {
+        return Ok(Vec::new());
+    }
+    println!("    requests for this run:");
+    for permission in &spec.permissions {
+        println!("      - {}", permission);
+    }
+
+    // A cached remote spec may have been signed by a key trusted back then
+    let signer = match provenance {
+        Some(provenance) => provenance
+            .signed_by
+            .clone()
+            .filter(|key| trusted_keys.iter().any(|trusted| trusted.trim() == key)),
+        None => spec_source::local_signer(path, trusted_keys),
+    };
+    if let Some(key) = signer {
+        println!("    approved: signed by {}", &key[..12.min(key.len())]);
+        return Ok(spec.permissions.clone());
+    }
+
+    if !dialog::is_interactive() {
+        println!("    not approved (spec is unsigned and there is no terminal to ask)");
+        return Ok(Vec::new());
+    }
+    let mut confirm =
+        dialog::ConfirmDialog::new("Spec permissions", "Grant these for this run only?");
+    if matches!(
+        dialog::run_in_terminal(&mut confirm)?,
+        dialog::DialogOutcome::Submitted(true)
+    ) {
+        Ok(spec.permissions.clone())
+    } else {
+        println!("    not approved; running without them");
+        Ok(Vec::new())
+    }
+}
+
+/// Download a remote spec (and its signature when keys are trusted), verify
+/// it and cache it. A cached copy is used when it matches a pinned checksum,
+/// or when the download fails.
+async fn fetch_remote_spec(
+    spec: &RemoteSpec,
+    cache: &SpecCache,
+    trusted_keys: &[String],
+) -> Result<(PathBuf, SpecProvenance)> {
+    let cached = cache.load(spec)?;
+    if let (Some(checksum), Some((path, provenance))) = (&spec.checksum, &cached) {
+        if std::fs::read(path).is_ok_and(|bytes| checksum.matches(&bytes)) {
+            return Ok((path.clone(), provenance.clone()));
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let download = async {
+        let response = client.get(&spec.url).send().await?.error_for_status()?;
+        let bytes = response.bytes().await?;
+        let signature = if trusted_keys.is_empty() {
+            None
+        } else {
+            let response = client.get(format!("{}.sig", spec.url)).send().await?;
+            if response.status().is_success() {
+                Some(response.text().await?)
+            } else {
+                None
+            }
+        };
+        anyhow::Ok((bytes, signature))
+    };
+
+    match download.await {
+        Ok((bytes, signature)) => cache.admit(spec, &bytes, signature.as_deref(), trusted_keys),
+        Err(err) => match cached {
+            Some((path, provenance)) if spec.checksum.is_none() => {
+                eprintln!(
+                    "Warning: could not fetch {} ({}); using the copy cached at {}",
+                    spec.reference,
+                    err,
+                    provenance.fetched_at.format("%Y-%m-%d %H:%M UTC")
+                );
+                Ok((path, provenance))
+            }
+            _ => Err(err).with_context(|| format!("fetching {}", spec.url)),
+        },
+    }
+}
+
 #[cfg(feature = "api")]
 async fn start_server(
     config_path: Option<PathBuf>,
     host: String,
     port: u16,
     join: Option<String>,
+    join_token: Option<String>,
 ) -> Result<()> {
     use spec_ai_api::api::mesh::MeshClient;
+    use spec_ai_api::mesh::{
+        collect_instance_stats, serve_tool_calls, MeshClient as CoreMeshClient, MeshToolExecutor,
+    };
+    use spec_ai_api::quota::QuotaTracker;
     use spec_ai_config::config::AppConfig;
     use spec_ai_core::embeddings::EmbeddingsClient;
     use std::net::TcpListener;
+    use std::time::Duration;
 
     // Generate unique instance ID
     let instance_id = MeshClient::generate_instance_id();
     println!("Instance ID: {}", instance_id);
 
+    // Load configuration
+    let app_config = match &config_path {
+        Some(path) => AppConfig::load_from_file(path)?,
+        None => AppConfig::load()?,
+    };
+    let role = app_config.mesh.role;
+
     // Determine if we should join an existing mesh or start as leader
     if let Some(ref registry_addr) = join {
         // Explicit join - find an available port for ourselves
@@ -138,6 +465,7 @@ async fn start_server(
                     test_port,
                     registry_addr.clone(),
                     instance_id,
+                    join_token,
                 )
                 .await;
             }
@@ -151,6 +479,15 @@ async fn start_server(
 
     // Check if port is available
     match TcpListener::bind(format!("{}:{}", host, port)) {
+        Ok(_) if !role.can_lead() => {
+            anyhow::bail!(
+                "[mesh] role = \"{}\" never hosts the registry, and none is running on {}:{}; \
+                 start a leader first or pass --join",
+                role.as_str(),
+                host,
+                port
+            );
+        }
         Ok(_listener) => {
             // Port is available, we'll be the mesh leader/registry
             println!(
@@ -182,6 +519,7 @@ async fn start_server(
                                 test_port,
                                 registry_url,
                                 instance_id,
+                                join_token,
                             )
                             .await;
                         }
@@ -201,15 +539,14 @@ async fn start_server(
         }
     }
 
-    // Load configuration
-    let app_config = if let Some(path) = config_path {
-        AppConfig::load_from_file(&path)?
-    } else {
-        AppConfig::load()?
-    };
-
     // Initialize persistence
-    let persistence = Persistence::new(&app_config.database.path)?;
+    let persistence = Persistence::new(&app_config.database.path)?
+        .with_privacy(&app_config.privacy)?
+        .with_events(&app_config.events);
+    spec_ai_core::events::spawn_publisher(persistence.clone(), &app_config.events);
+    if persistence.is_in_memory() {
+        println!("Database: in memory, sessions are discarded on shutdown");
+    }
 
     // Initialize embeddings client if configured
     let embeddings = if let Some(embeddings_model) = &app_config.model.embeddings_model {
@@ -235,15 +572,47 @@ async fn start_server(
         None
     };
 
+    let quotas = Arc::new(QuotaTracker::new(
+        instance_id.clone(),
+        app_config.mesh.quotas.clone(),
+    ));
+
+    // Remote tools are run by whichever member shares them
+    let remote_tools = Arc::new(
+        MeshToolExecutor::new(CoreMeshClient::new(&host, port), instance_id.clone())
+            .with_timeout(Duration::from_secs(
+                app_config.mesh.remote_tool_timeout_secs,
+            ))
+            .with_quotas(quotas.clone()),
+    );
+    tokio::spawn(
+        remote_tools
+            .clone()
+            .keep_fresh(Duration::from_secs(app_config.mesh.heartbeat_interval_secs)),
+    );
+
     // Create registries
     let agent_registry = Arc::new(AgentRegistry::new(
         app_config.agents.clone(),
         persistence.clone(),
     ));
-    let tool_registry = Arc::new(ToolRegistry::with_builtin_tools(
-        Some(Arc::new(persistence.clone())),
-        embeddings,
-    ));
+    spec_ai_core::agent::session_lifecycle::spawn_sweeper(&agent_registry, &app_config);
+    // Read replicas leave reminders to the members that accept writes
+    if app_config.mesh.role.accepts_writes() {
+        spec_ai_core::agent::reminders::spawn_delivery(persistence.clone());
+    }
+    let tool_registry = Arc::new(
+        ToolRegistry::with_builtin_tools(Some(Arc::new(persistence.clone())), embeddings)
+            .with_clock(
+                Clock::from_config(&app_config.time).context("Invalid [time] configuration")?,
+            )
+            .with_composite_tools(&app_config.composite_tools)
+            .context("Invalid composite tool configuration")?
+            .with_settings(&app_config.tools)
+            .with_graph_types(&app_config.graph)
+            .with_remote_executor(remote_tools.clone()),
+    );
+    let shared_tools = shared_mesh_tools(&app_config.mesh.shared_tools, &tool_registry);
 
     // Configure and start API server
     let api_config = ApiConfig::new()
@@ -251,13 +620,25 @@ async fn start_server(
         .with_port(port)
         .with_cors(true);
 
-    let server = ApiServer::new(
+    let mut server = ApiServer::new(
         api_config.clone(),
         persistence.clone(),
         agent_registry.clone(),
         tool_registry.clone(),
         app_config.clone(),
-    );
+    )
+    .with_instance_id(instance_id.clone())
+    .with_quotas(quotas.clone());
+
+    // Members joining with a token from `spec-ai mesh invite`
+    let require_join_token = app_config.mesh.require_join_token;
+    match MeshKeyring::from_config(&app_config.mesh) {
+        Ok(keyring) => server = server.with_join_tokens(keyring, require_join_token),
+        Err(err) if !require_join_token => {
+            eprintln!("Warning: join tokens are disabled: {:#}", err)
+        }
+        Err(err) => return Err(err.context("loading the mesh key")),
+    }
 
     println!("Server running at http://{}", api_config.bind_address());
     println!("Health check: http://{}/health", api_config.bind_address());
@@ -274,19 +655,39 @@ async fn start_server(
         last_heartbeat: chrono::Utc::now(),
         created_at: chrono::Utc::now(),
         agent_profiles: agent_registry.list(),
+        tools: shared_tools.clone(),
+        catalog: Some(server.capability_catalog()),
+        role,
     };
     mesh_registry.register(self_instance).await;
 
+    // Run tool calls other members send us
+    if !shared_tools.is_empty() {
+        println!("Sharing tools with the mesh: {}", shared_tools.join(", "));
+        tokio::spawn(serve_tool_calls(
+            CoreMeshClient::new(&host, port),
+            instance_id.clone(),
+            tool_registry.clone(),
+            shared_tools,
+            Duration::from_secs(app_config.mesh.remote_tool_timeout_secs),
+        ));
+    }
+
     // Start background heartbeat for self (keeps our own timestamp fresh)
     let heartbeat_instance_id = instance_id.clone();
     let heartbeat_registry = mesh_registry.clone();
     let heartbeat_interval = app_config.mesh.heartbeat_interval_secs;
+    let stats_persistence = persistence.clone();
+    let stats_quotas = quotas.clone();
     tokio::spawn(async move {
         let mut interval =
             tokio::time::interval(tokio::time::Duration::from_secs(heartbeat_interval));
         loop {
             interval.tick().await;
-            let _ = heartbeat_registry.heartbeat(&heartbeat_instance_id).await;
+            let stats = collect_instance_stats(&stats_persistence, None, Some(&stats_quotas));
+            let _ = heartbeat_registry
+                .heartbeat(&heartbeat_instance_id, Some(stats))
+                .await;
         }
     });
 
@@ -305,11 +706,13 @@ async fn start_server(
     // Setup shutdown signal
     let shutdown_instance_id = instance_id.clone();
     let shutdown_registry = mesh_registry.clone();
+    let shutdown_tools = tool_registry.clone();
     let shutdown = async move {
         tokio::signal::ctrl_c()
             .await
             .expect("Failed to install Ctrl+C handler");
         println!("\nShutting down server...");
+        shutdown_tools.processes().stop_all().await;
         // Deregister from mesh
         let _ = shutdown_registry.deregister(&shutdown_instance_id).await;
     };
@@ -321,6 +724,23 @@ async fn start_server(
     Ok(())
 }
 
+/// Tools from `[mesh] shared_tools` that exist locally; unknown names are
+/// skipped with a warning
+#[cfg(feature = "api")]
+fn shared_mesh_tools(configured: &[String], registry: &ToolRegistry) -> Vec<String> {
+    configured
+        .iter()
+        .filter(|name| {
+            let known = registry.get(name.as_str()).is_some() && !name.starts_with("remote:");
+            if !known {
+                eprintln!("Warning: [mesh] shared_tools names unknown tool '{}'", name);
+            }
+            known
+        })
+        .cloned()
+        .collect()
+}
+
 #[cfg(feature = "api")]
 async fn start_mesh_member(
     config_path: Option<PathBuf>,
@@ -328,13 +748,24 @@ async fn start_mesh_member(
     port: u16,
     registry_url: String,
     instance_id: String,
+    join_token: Option<String>,
 ) -> Result<()> {
-    use spec_ai_api::api::mesh::MeshClient;
+    use spec_ai_api::api::mesh::{MeshClient, RegisterRequest};
+    use spec_ai_api::mesh::{
+        collect_instance_stats, serve_tool_calls, CapabilityCatalog, MeshClient as CoreMeshClient,
+        MeshToolExecutor,
+    };
+    use spec_ai_api::quota::QuotaTracker;
     use spec_ai_config::config::AppConfig;
     use spec_ai_core::embeddings::EmbeddingsClient;
+    use std::time::Duration;
 
     println!("Starting as mesh member on {}:{}", host, port);
     println!("Registry at: {}", registry_url);
+    let (registry_host, registry_port) = registry_url
+        .split_once(':')
+        .context("Registry address must be host:port")?;
+    let registry_port: u16 = registry_port.parse().context("Invalid registry port")?;
 
     // Load configuration
     let app_config = if let Some(path) = config_path {
@@ -344,7 +775,13 @@ async fn start_mesh_member(
     };
 
     // Initialize persistence
-    let persistence = Persistence::new(&app_config.database.path)?;
+    let persistence = Persistence::new(&app_config.database.path)?
+        .with_privacy(&app_config.privacy)?
+        .with_events(&app_config.events);
+    spec_ai_core::events::spawn_publisher(persistence.clone(), &app_config.events);
+    if persistence.is_in_memory() {
+        println!("Database: in memory, sessions are discarded on shutdown");
+    }
 
     // Initialize embeddings client if configured
     let embeddings = if let Some(embeddings_model) = &app_config.model.embeddings_model {
@@ -369,38 +806,108 @@ async fn start_mesh_member(
         None
     };
 
+    let quotas = Arc::new(QuotaTracker::new(
+        instance_id.clone(),
+        app_config.mesh.quotas.clone(),
+    ));
+
+    // Remote tools are run by whichever member shares them
+    let remote_tools = Arc::new(
+        MeshToolExecutor::new(
+            CoreMeshClient::new(registry_host, registry_port),
+            instance_id.clone(),
+        )
+        .with_timeout(Duration::from_secs(
+            app_config.mesh.remote_tool_timeout_secs,
+        ))
+        .with_quotas(quotas.clone()),
+    );
+    tokio::spawn(
+        remote_tools
+            .clone()
+            .keep_fresh(Duration::from_secs(app_config.mesh.heartbeat_interval_secs)),
+    );
+
     // Create registries
     let agent_registry = Arc::new(AgentRegistry::new(
         app_config.agents.clone(),
         persistence.clone(),
     ));
-    let tool_registry = Arc::new(ToolRegistry::with_builtin_tools(
-        Some(Arc::new(persistence.clone())),
-        embeddings,
-    ));
+    spec_ai_core::agent::session_lifecycle::spawn_sweeper(&agent_registry, &app_config);
+    // Read replicas leave reminders to the members that accept writes
+    if app_config.mesh.role.accepts_writes() {
+        spec_ai_core::agent::reminders::spawn_delivery(persistence.clone());
+    }
+    let tool_registry = Arc::new(
+        ToolRegistry::with_builtin_tools(Some(Arc::new(persistence.clone())), embeddings)
+            .with_clock(
+                Clock::from_config(&app_config.time).context("Invalid [time] configuration")?,
+            )
+            .with_composite_tools(&app_config.composite_tools)
+            .context("Invalid composite tool configuration")?
+            .with_settings(&app_config.tools)
+            .with_graph_types(&app_config.graph)
+            .with_remote_executor(remote_tools.clone()),
+    );
+    let role = app_config.mesh.role;
+    println!("Mesh role: {}", role.as_str());
+
+    // Read replicas run nothing on behalf of other members
+    let shared_tools = if role.accepts_writes() {
+        shared_mesh_tools(&app_config.mesh.shared_tools, &tool_registry)
+    } else {
+        if !app_config.mesh.shared_tools.is_empty() {
+            eprintln!("Warning: [mesh] shared_tools is ignored on a read replica");
+        }
+        Vec::new()
+    };
+    let capabilities = if role.accepts_writes() {
+        vec!["query".to_string()]
+    } else {
+        Vec::new()
+    };
 
     // Get agent profiles for registration
     let agent_profiles: Vec<String> = agent_registry.list();
 
     // Register with the mesh
-    let mesh_client = MeshClient::new(
-        &registry_url.split(':').next().unwrap(),
-        registry_url.split(':').nth(1).unwrap().parse()?,
-    );
+    let mesh_client = MeshClient::new(registry_host, registry_port);
 
+    let catalog = CapabilityCatalog::build(
+        instance_id.clone(),
+        &app_config,
+        &agent_registry,
+        &tool_registry,
+        &shared_tools,
+    );
     let register_response = mesh_client
-        .register(
-            instance_id.clone(),
-            host.clone(),
+        .register(RegisterRequest {
+            instance_id: instance_id.clone(),
+            hostname: host.clone(),
             port,
-            vec!["query".to_string()],
+            capabilities,
             agent_profiles,
-        )
+            tools: shared_tools.clone(),
+            catalog: Some(catalog),
+            join_token,
+            role,
+        })
         .await?;
 
     println!("Registered with mesh:");
     println!("  Leader: {}", register_response.is_leader);
     println!("  Peers: {}", register_response.peers.len());
+    for peer in &register_response.peers {
+        if let Some(catalog) = &peer.catalog {
+            println!(
+                "    {}: {} agents, {} tools, version {}",
+                peer.instance_id,
+                catalog.agents.len(),
+                catalog.tools.len(),
+                catalog.version
+            );
+        }
+    }
 
     // Start our API server
     let api_config = ApiConfig::new()
@@ -410,28 +917,51 @@ async fn start_mesh_member(
 
     let server = ApiServer::new(
         api_config.clone(),
-        persistence,
+        persistence.clone(),
         agent_registry,
-        tool_registry,
+        tool_registry.clone(),
         app_config.clone(),
-    );
+    )
+    .with_instance_id(instance_id.clone())
+    .with_quotas(quotas.clone());
 
     println!("Server running at http://{}", api_config.bind_address());
+    let shutdown_tools = tool_registry.clone();
+
+    // Run tool calls other members send us
+    if !shared_tools.is_empty() {
+        println!("Sharing tools with the mesh: {}", shared_tools.join(", "));
+        tokio::spawn(serve_tool_calls(
+            CoreMeshClient::new(registry_host, registry_port),
+            instance_id.clone(),
+            tool_registry,
+            shared_tools,
+            Duration::from_secs(app_config.mesh.remote_tool_timeout_secs),
+        ));
+    }
 
     // Start background heartbeat to registry
     let heartbeat_instance_id = instance_id.clone();
     let heartbeat_client = mesh_client.clone();
     let heartbeat_interval = app_config.mesh.heartbeat_interval_secs;
+    let stats_persistence = persistence.clone();
+    let stats_quotas = quotas.clone();
     tokio::spawn(async move {
         let mut interval =
             tokio::time::interval(tokio::time::Duration::from_secs(heartbeat_interval));
+        // Each heartbeat reports how long the previous one took
+        let mut last_latency = None;
         loop {
             interval.tick().await;
-            if let Err(e) = heartbeat_client
-                .heartbeat(&heartbeat_instance_id, None)
+            let stats =
+                collect_instance_stats(&stats_persistence, last_latency, Some(&stats_quotas));
+            let sent = std::time::Instant::now();
+            match heartbeat_client
+                .heartbeat(&heartbeat_instance_id, Some(stats.to_metrics()))
                 .await
             {
-                eprintln!("Heartbeat failed: {}", e);
+                Ok(_) => last_latency = Some(sent.elapsed()),
+                Err(e) => eprintln!("Heartbeat failed: {}", e),
             }
         }
     });
@@ -444,6 +974,7 @@ async fn start_mesh_member(
             .await
             .expect("Failed to install Ctrl+C handler");
         println!("\nShutting down server...");
+        shutdown_tools.processes().stop_all().await;
         // Deregister from mesh
         if let Err(e) = shutdown_client.deregister(&shutdown_instance_id).await {
             eprintln!("Failed to deregister: {}", e);
@@ -457,30 +988,56 @@ async fn start_mesh_member(
     Ok(())
 }
 
-async fn run_specs_command(config_path: Option<PathBuf>, spec_paths: Vec<PathBuf>) -> Result<i32> {
-    // Determine which spec to run
-    let specs_to_run = if spec_paths.is_empty() {
+async fn run_specs_command(
+    config_path: Option<PathBuf>,
+    spec_args: Vec<String>,
+    checksum: Option<Checksum>,
+    vars: HashMap<String, String>,
+    deterministic: bool,
+) -> Result<i32> {
+    let mut sources = spec_args
+        .iter()
+        .map(|arg| SpecSource::parse(arg))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(checksum) = checksum {
+        let mut remote = sources.iter_mut().filter_map(|source| match source {
+            SpecSource::Remote(spec) => Some(spec),
+            SpecSource::Local(_) => None,
+        });
+        match (remote.next(), remote.next()) {
+            (Some(spec), None) => spec.checksum = Some(checksum),
+            _ => anyhow::bail!("--checksum applies to exactly one remote spec"),
+        }
+    }
+
+    // Determine which local specs to run; remote ones are fetched once the
+    // configuration is loaded
+    let mut specs_to_run: Vec<(PathBuf, Option<SpecProvenance>)> = Vec::new();
+    let mut remote_specs = Vec::new();
+    if sources.is_empty() {
         let default_spec = PathBuf::from("../../../spec/smoke.spec");
         if !default_spec.exists() {
             eprintln!("Error: Default spec not found at 'spec/smoke.spec'.");
             eprintln!("Please provide explicit spec files or create the default spec.");
             return Ok(1);
         }
-        vec![default_spec]
+        specs_to_run.push((default_spec, None));
     } else {
-        let mut all_specs = Vec::new();
-        for path in &spec_paths {
-            let specs = collect_spec_files(path)?;
-            all_specs.extend(specs);
+        for source in sources {
+            match source {
+                SpecSource::Local(path) => {
+                    let specs = collect_spec_files(&path)?;
+                    specs_to_run.extend(specs.into_iter().map(|spec| (spec, None)));
+                }
+                SpecSource::Remote(spec) => remote_specs.push(spec),
+            }
         }
 
-        if all_specs.is_empty() {
+        if specs_to_run.is_empty() && remote_specs.is_empty() {
             eprintln!("Error: No .spec files found in provided paths.");
             return Ok(1);
         }
-
-        all_specs
-    };
+    }
 
     // Initialize CLI state
     let mut cli = match CliState::initialize_with_path(config_path) {
@@ -499,18 +1056,32 @@ async fn run_specs_command(config_path: Option<PathBuf>, spec_paths: Vec<PathBuf
             return Err(e);
         }
     };
+    cli.set_deterministic(deterministic);
 
-    // Run each spec file
     let mut all_success = true;
-    for spec_path in specs_to_run {
-        match run_spec_file(&mut cli, &spec_path).await {
+    if !remote_specs.is_empty() {
+        let cache = SpecCache::from_config(&cli.config.specs)?;
+        for spec in &remote_specs {
+            match fetch_remote_spec(spec, &cache, &cli.config.specs.trusted_keys).await {
+                Ok((path, provenance)) => specs_to_run.push((path, Some(provenance))),
+                Err(e) => {
+                    eprintln!("Error fetching spec '{}': {:#}", spec.reference, e);
+                    all_success = false;
+                }
+            }
+        }
+    }
+
+    // Run each spec file
+    for (spec_path, provenance) in specs_to_run {
+        match run_spec_file(&mut cli, &spec_path, provenance.as_ref(), &vars).await {
             Ok(success) => {
                 if !success {
                     all_success = false;
                 }
             }
             Err(e) => {
-                eprintln!("Error running spec '{}': {}", spec_path.display(), e);
+                eprintln!("Error running spec '{}': {:#}", spec_path.display(), e);
                 all_success = false;
             }
         }
@@ -519,18 +1090,615 @@ async fn run_specs_command(config_path: Option<PathBuf>, spec_paths: Vec<PathBuf
     Ok(if all_success { 0 } else { 1 })
 }
 
+async fn run_sessions_command(
+    config_path: Option<PathBuf>,
+    command: SessionsCommand,
+) -> Result<()> {
+    use spec_ai_config::config::{AgentRegistry, AppConfig};
+    use spec_ai_core::agent::session_lifecycle;
+    use spec_ai_core::cli::formatting;
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+    let persistence = spec_ai_config::persistence::Persistence::new(&app_config.database.path)
+        .context("opening the session database")?;
+
+    match command {
+        SessionsCommand::Search { query, limit } => {
+            let query = query.join(" ");
+            let hits = persistence.search_sessions(&query, limit)?;
+            println!("{}", formatting::render_session_search(&query, &hits));
+        }
+        SessionsCommand::Sweep { dry_run } => {
+            if !app_config.sessions.is_enabled() {
+                anyhow::bail!(
+                    "set archive_after_days or delete_after_days under [sessions] to sweep sessions"
+                );
+            }
+            let registry = AgentRegistry::new(app_config.agents.clone(), persistence);
+            registry.init()?;
+            let agent = session_lifecycle::sweeper_agent(&registry, &app_config)?;
+            let report =
+                session_lifecycle::sweep(&agent, &app_config.sessions, chrono::Utc::now(), dry_run)
+                    .await?;
+            let (archive, delete) = if dry_run {
+                ("Would archive", "Would delete")
+            } else {
+                ("Archived", "Deleted")
+            };
+            for session_id in &report.archived {
+                println!("{} {}", archive, session_id);
+            }
+            for session_id in &report.deleted {
+                println!("{} {}", delete, session_id);
+            }
+            for (session_id, err) in &report.failed {
+                eprintln!("Failed to sweep {}: {}", session_id, err);
+            }
+            println!(
+                "{} {} and {} {} session(s)",
+                archive,
+                report.archived.len(),
+                delete.to_lowercase(),
+                report.deleted.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_mesh_command(config_path: Option<PathBuf>, command: MeshCommand) -> Result<()> {
+    use spec_ai_config::config::AppConfig;
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+    match command {
+        MeshCommand::Invite {
+            capabilities,
+            ttl_hours,
+        } => {
+            let keyring = MeshKeyring::from_config(&app_config.mesh)?;
+            let capabilities: Vec<String> = capabilities
+                .into_iter()
+                .map(|capability| capability.trim().to_string())
+                .filter(|capability| !capability.is_empty())
+                .collect();
+            if capabilities.is_empty() {
+                anyhow::bail!("an invite must allow at least one capability");
+            }
+            let (token, claims) =
+                keyring.mint(capabilities, chrono::Duration::hours(ttl_hours as i64))?;
+            println!("{}", token);
+            eprintln!(
+                "Allows {} until {}; join with `spec-ai server --join <leader> --join-token <token>`",
+                claims.capabilities.join(", "),
+                claims.expires_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            Ok(())
+        }
+    }
+}
+
+fn run_db_command(config_path: Option<PathBuf>, command: DbCommand) -> Result<()> {
+    use spec_ai_config::config::AppConfig;
+    use spec_ai_config::persistence::Persistence;
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+    let open = |path: &std::path::Path| -> Result<Persistence> {
+        Persistence::new(path)
+            .with_context(|| format!("opening the database at {}", path.display()))?
+            .with_privacy(&app_config.privacy)
+    };
+
+    match command {
+        DbCommand::Prune { dry_run } => {
+            let persistence = Persistence::new(&app_config.database.path)
+                .context("opening the session database")?;
+            let report = persistence.gc_blobs(dry_run)?;
+            let verb = if dry_run { "Would remove" } else { "Removed" };
+            println!(
+                "{} {} unreferenced blob(s), {} bytes; {} still referenced",
+                verb, report.removed, report.bytes_freed, report.kept
+            );
+        }
+        DbCommand::CopySession {
+            session_id,
+            from,
+            to,
+            as_session,
+        } => {
+            let from = from.unwrap_or_else(|| app_config.database.path.clone());
+            let source = open(&from)?;
+            // One database can hold a copy under another ID, but DuckDB
+            // locks the file, so it is opened only once
+            let same_file =
+                std::fs::canonicalize(&from).ok() == std::fs::canonicalize(&to).ok() && to.exists();
+            if same_file && as_session.is_none() {
+                anyhow::bail!("copying a session within one database needs --as <SESSION_ID>");
+            }
+            let target = if same_file {
+                source.clone()
+            } else {
+                open(&to)?
+            };
+            let report = source
+                .copy_session_to(&session_id, &target, as_session.as_deref())
+                .with_context(|| format!("copying session '{}'", session_id))?;
+            println!(
+                "Copied session '{}' to '{}' in {}: {}",
+                session_id,
+                report.session_id,
+                to.display(),
+                report.summary()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_bootstrap_command(
+    config_path: Option<PathBuf>,
+    path: Option<PathBuf>,
+    plugins: Option<Vec<String>>,
+    session: Option<String>,
+    refresh: bool,
+) -> Result<()> {
+    use spec_ai_config::config::AppConfig;
+    use spec_ai_core::bootstrap_self::{resolve_repo_root, BootstrapSelf};
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+    let repo_root = match path {
+        Some(path) => path
+            .canonicalize()
+            .with_context(|| format!("repository path '{}' not found", path.display()))?,
+        None => resolve_repo_root()?,
+    };
+    let session = session.unwrap_or_else(|| {
+        let name = repo_root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("repo");
+        format!("bootstrap-{}", name)
+    });
+    let persistence = spec_ai_config::persistence::Persistence::new(&app_config.database.path)
+        .context("opening the session database")?
+        .with_privacy(&app_config.privacy)?
+        .with_events(&app_config.events);
+
+    let bootstrapper = BootstrapSelf::new(&persistence, &session, repo_root.clone());
+    let outcome = if refresh {
+        bootstrapper.refresh_with_plugins(plugins)?
+    } else {
+        bootstrapper.run_with_plugins(plugins)?
+    };
+    println!(
+        "Bootstrapped '{}' from {} into session '{}': {} nodes and {} edges ({} components, {} documents)",
+        outcome.repository_name,
+        repo_root.display(),
+        session,
+        outcome.nodes_created,
+        outcome.edges_created,
+        outcome.component_count,
+        outcome.document_count
+    );
+    Ok(())
+}
+
+async fn run_daemon_command(
+    config_path: Option<PathBuf>,
+    socket: Option<PathBuf>,
+    workspace: Option<PathBuf>,
+    watch_interval: u64,
+) -> Result<()> {
+    use spec_ai_config::config::{AgentProfile, AgentRegistry, AppConfig};
+    use spec_ai_config::persistence::Persistence;
+    use spec_ai_core::bootstrap_self::resolve_repo_root;
+    use spec_ai_core::daemon::Daemon;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+
+    // stdout carries the protocol, so logs go to stderr
+    let default_directive = format!("spec_ai={}", app_config.logging.level.to_lowercase());
+    let filter = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or(default_directive);
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let workspace = match workspace {
+        Some(path) => path
+            .canonicalize()
+            .with_context(|| format!("workspace '{}' not found", path.display()))?,
+        None => resolve_repo_root()?,
+    };
+    let persistence = Persistence::new(&app_config.database.path)
+        .context("opening the session database")?
+        .with_privacy(&app_config.privacy)?
+        .with_events(&app_config.events);
+    spec_ai_core::events::spawn_publisher(persistence.clone(), &app_config.events);
+
+    let registry = AgentRegistry::new(app_config.agents.clone(), persistence);
+    registry.init()?;
+    if registry.active_name().is_none() {
+        let name = app_config
+            .default_agent
+            .clone()
+            .filter(|name| registry.get(name).is_some())
+            .or_else(|| registry.list().first().cloned());
+        match name {
+            Some(name) => registry.set_active(&name)?,
+            None => {
+                registry.upsert("default".to_string(), AgentProfile::default())?;
+                registry.set_active("default")?;
+            }
+        }
+    }
+
+    let daemon = Daemon::new(app_config, Arc::new(registry), workspace)
+        .with_watch_interval(Duration::from_secs(watch_interval.max(1)));
+    match socket {
+        #[cfg(unix)]
+        Some(path) => daemon.serve_unix(&path).await,
+        #[cfg(not(unix))]
+        Some(_) => anyhow::bail!("--socket needs a unix platform; use stdio instead"),
+        None => daemon.serve_stdio().await,
+    }
+}
+
+async fn run_suggest_specs_command(
+    config_path: Option<PathBuf>,
+    path: Option<PathBuf>,
+    session: Option<String>,
+    limit: usize,
+    out: PathBuf,
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    use spec_ai_core::bootstrap_self::{resolve_repo_root, BootstrapSelf};
+    use spec_ai_core::spec_suggest::{
+        component_count, extract_spec, find_coverage_gaps, suggestion_prompt, write_draft,
+    };
+
+    let repo_root = match path {
+        Some(path) => path
+            .canonicalize()
+            .with_context(|| format!("repository path '{}' not found", path.display()))?,
+        None => resolve_repo_root()?,
+    };
+    let repo_name = repo_root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("repo")
+        .to_string();
+    let session = session.unwrap_or_else(|| format!("bootstrap-{}", repo_name));
+    // One state for the model and the graph: DuckDB allows a single writer
+    let cli = CliState::initialize_with_path(config_path)?;
+
+    if component_count(&cli.persistence, &session)? == 0 {
+        println!(
+            "Session '{}' has no bootstrap graph; bootstrapping {} first",
+            session,
+            repo_root.display()
+        );
+        BootstrapSelf::new(&cli.persistence, &session, repo_root.clone()).run_with_plugins(None)?;
+    }
+
+    let gaps = find_coverage_gaps(&cli.persistence, &session, &repo_root)?;
+    if gaps.is_empty() {
+        println!("Every component has tests or a spec; nothing to suggest.");
+        return Ok(());
+    }
+    let gaps = &gaps[..gaps.len().min(limit)];
+    if dry_run {
+        for gap in gaps {
+            println!("{}: {}", gap.path, gap.reasons().join(", "));
+        }
+        return Ok(());
+    }
+
+    let out_dir = repo_root.join(&out);
+    let mut written = 0;
+    for gap in gaps {
+        let prompt = suggestion_prompt(&repo_name, gap);
+        let draft = cli
+            .agent
+            .complete("suggest_specs", &prompt)
+            .await
+            .and_then(|answer| extract_spec(&answer))
+            .and_then(|spec| write_draft(&out_dir, gap, &spec, force));
+        match draft {
+            Ok(file) => {
+                written += 1;
+                println!("{} ({})", file.display(), gap.reasons().join(", "));
+            }
+            Err(err) => eprintln!("Skipped {}: {:#}", gap.path, err),
+        }
+    }
+    println!(
+        "Drafted {} of {} spec(s) in {}; review them before running.",
+        written,
+        gaps.len(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+async fn run_specs_library_command(
+    config_path: Option<PathBuf>,
+    command: SpecsCommand,
+) -> Result<()> {
+    use spec_ai_config::config::AppConfig;
+
+    let app_config = if let Some(path) = config_path {
+        AppConfig::load_from_file(&path)?
+    } else {
+        AppConfig::load()?
+    };
+    let library = SpecLibrary::from_config(&app_config.specs)?;
+
+    match command {
+        SpecsCommand::List => {
+            let names = library.names()?;
+            if names.is_empty() {
+                println!(
+                    "No specs in {}. Add one with: spec-ai specs add <file>",
+                    library.root().display()
+                );
+            }
+            for name in names {
+                match library.get(&name) {
+                    Ok(entry) => println!(
+                        "{:<24} {}",
+                        name,
+                        entry
+                            .spec
+                            .description()
+                            .unwrap_or_else(|| entry.spec.display_name())
+                    ),
+                    Err(e) => println!("{:<24} (invalid: {:#})", name, e),
+                }
+            }
+        }
+        SpecsCommand::Add {
+            source,
+            name,
+            checksum,
+            force,
+        } => {
+            let (path, provenance) = match SpecSource::parse(&source)? {
+                SpecSource::Local(path) => {
+                    if checksum.is_some() {
+                        anyhow::bail!("--checksum applies to remote specs only");
+                    }
+                    (path, None)
+                }
+                SpecSource::Remote(mut spec) => {
+                    if checksum.is_some() {
+                        spec.checksum = checksum;
+                    }
+                    let cache = SpecCache::from_config(&app_config.specs)?;
+                    let (path, provenance) =
+                        fetch_remote_spec(&spec, &cache, &app_config.specs.trusted_keys).await?;
+                    (path, Some(provenance))
+                }
+            };
+            let name = match name.or_else(|| SpecLibrary::name_for(&path)) {
+                Some(name) => name,
+                None => anyhow::bail!(
+                    "cannot derive a library name from '{}'; pass --name",
+                    path.display()
+                ),
+            };
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let entry = library.add(&name, &contents, provenance.as_ref(), force)?;
+            println!("Added '{}' to {}", entry.name, library.root().display());
+        }
+        SpecsCommand::Remove { name } => {
+            if library.remove(&name)? {
+                println!("Removed '{}'", name);
+            } else {
+                anyhow::bail!("no spec named '{}' in {}", name, library.root().display());
+            }
+        }
+        SpecsCommand::Info { name } => {
+            let entry = library.get(&name)?;
+            println!("Name:        {}", entry.name);
+            println!("Title:       {}", entry.spec.display_name());
+            if let Some(description) = entry.spec.description() {
+                println!("Description: {}", description);
+            }
+            println!("Path:        {}", entry.path.display());
+            if let Some(provenance) = &entry.provenance {
+                println!("Source:      {}", provenance.summary());
+            }
+            let requires = &entry.spec.requires;
+            if !requires.tools.is_empty() {
+                println!("Tools:       {}", requires.tools.join(", "));
+            }
+            if !requires.features.is_empty() {
+                println!("Features:    {}", requires.features.join(", "));
+            }
+            if let Some(tokens) = requires.min_context_window {
+                println!("Context:     at least {} tokens", tokens);
+            }
+            for (name, variable) in &entry.spec.variables {
+                let default = variable
+                    .default
+                    .as_ref()
+                    .map(|value| format!(" = {}", value))
+                    .unwrap_or_default();
+                println!(
+                    "Variable:    {} ({}){}{}",
+                    name,
+                    variable.kind.as_str(),
+                    default,
+                    variable
+                        .description
+                        .as_deref()
+                        .map(|text| format!(" - {}", text))
+                        .unwrap_or_default()
+                );
+            }
+            println!();
+            println!("{}", entry.spec.preview());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ollama")]
+async fn run_models_command(config_path: Option<PathBuf>, command: ModelsCommand) -> Result<()> {
+    use spec_ai_config::config::AppConfig;
+    use spec_ai_core::agent::providers::OllamaProvider;
+
+    let provider = OllamaProvider::from_env();
+    match command {
+        ModelsCommand::List => {
+            let models = provider.list_models().await?;
+            if models.is_empty() {
+                println!(
+                    "No models installed in Ollama at {}. Pull one with: spec-ai models pull <name>",
+                    provider.base_url()
+                );
+            }
+            for model in models {
+                println!(
+                    "{:<40} {:>9}  {}",
+                    model.name,
+                    format_size(model.size),
+                    model.modified_at.as_deref().unwrap_or("")
+                );
+            }
+        }
+        ModelsCommand::Pull { name } => {
+            let name = match name {
+                Some(name) => name,
+                None => {
+                    let app_config = match &config_path {
+                        Some(path) => AppConfig::load_from_file(path)?,
+                        None => AppConfig::load()?,
+                    };
+                    match app_config.model.model_name {
+                        Some(model) if app_config.model.provider.eq_ignore_ascii_case("ollama") => {
+                            model
+                        }
+                        _ => anyhow::bail!(
+                            "No model given and [model] does not name an Ollama model; run: spec-ai models pull <name>"
+                        ),
+                    }
+                }
+            };
+            println!("Pulling {} (this can take a while)...", name);
+            provider.pull_model(&name).await?;
+            println!("Pulled {}", name);
+        }
+        ModelsCommand::Rm { name } => {
+            provider.delete_model(&name).await?;
+            println!("Removed {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable size in decimal units, as Ollama reports them
+#[cfg(feature = "ollama")]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1000.0 {
+            break;
+        }
+        size /= 1000.0;
+        unit = next;
+    }
+    format!("{:.1} {}", size, unit)
+}
+
 #[tokio::main]
 pub async fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Run { specs }) => {
-            let exit_code = run_specs_command(cli.config, specs).await?;
+        Some(Commands::Run {
+            specs,
+            checksum,
+            vars,
+        }) => {
+            let vars = vars.into_iter().collect();
+            let exit_code =
+                run_specs_command(cli.config, specs, checksum, vars, cli.deterministic).await?;
             std::process::exit(exit_code);
         }
+        Some(Commands::Sessions { command }) => run_sessions_command(cli.config, command).await,
+        Some(Commands::Db { command }) => run_db_command(cli.config, command),
+        Some(Commands::Mesh { command }) => run_mesh_command(cli.config, command),
+        Some(Commands::Daemon {
+            socket,
+            workspace,
+            watch_interval,
+        }) => run_daemon_command(cli.config, socket, workspace, watch_interval).await,
+        Some(Commands::Bootstrap {
+            path,
+            plugins,
+            session,
+            refresh,
+        }) => run_bootstrap_command(cli.config, path, plugins, session, refresh),
+        Some(Commands::SuggestSpecs {
+            path,
+            session,
+            limit,
+            out,
+            force,
+            dry_run,
+        }) => {
+            run_suggest_specs_command(cli.config, path, session, limit, out, force, dry_run).await
+        }
+        Some(Commands::Specs { command }) => run_specs_library_command(cli.config, command).await,
+        #[cfg(feature = "ollama")]
+        Some(Commands::Models { command }) => run_models_command(cli.config, command).await,
+        #[cfg(not(feature = "ollama"))]
+        Some(Commands::Models { .. }) => {
+            eprintln!("Error: Model management requires the 'ollama' feature");
+            eprintln!("Please rebuild with: cargo build --features ollama");
+            std::process::exit(1);
+        }
         #[cfg(feature = "api")]
-        Some(Commands::Server { port, host, join }) => {
-            start_server(cli.config, host, port, join).await?;
+        Some(Commands::Server {
+            port,
+            host,
+            join,
+            join_token,
+        }) => {
+            start_server(cli.config, host, port, join, join_token).await?;
             Ok(())
         }
         #[cfg(not(feature = "api"))]
@@ -557,6 +1725,7 @@ pub async fn run() -> Result<()> {
                     return Err(e);
                 }
             };
+            cli_state.set_deterministic(cli.deterministic);
 
             // Initialize logging based on config
             let log_level = cli_state.config.logging.level.to_uppercase();
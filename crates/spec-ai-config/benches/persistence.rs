@@ -0,0 +1,110 @@
+//! Recall and graph traversal over in-memory databases of growing size
+//!
+//! `cargo bench -p spec-ai-config`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spec_ai_config::persistence::Persistence;
+use spec_ai_config::types::{EdgeType, NodeType, TraversalDirection};
+
+const SESSION: &str = "bench";
+const DIMENSIONS: usize = 384;
+
+/// Deterministic pseudo-random vector, so runs are comparable
+fn embedding(seed: usize) -> Vec<f32> {
+    let mut state = seed as u64;
+    (0..DIMENSIONS)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / u32::MAX as f32) - 0.25
+        })
+        .collect()
+}
+
+fn in_transaction(persistence: &Persistence, insert: impl FnOnce(&Persistence)) {
+    persistence
+        .conn()
+        .execute_batch("BEGIN TRANSACTION")
+        .unwrap();
+    insert(persistence);
+    persistence.conn().execute_batch("COMMIT").unwrap();
+}
+
+fn recall_top_k(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recall_top_k");
+    group.sample_size(10);
+    for vectors in [1_000, 10_000, 100_000] {
+        let persistence = Persistence::in_memory().unwrap();
+        in_transaction(&persistence, |persistence| {
+            for i in 0..vectors {
+                persistence
+                    .insert_memory_vector(SESSION, None, &embedding(i))
+                    .unwrap();
+            }
+        });
+        let query = embedding(usize::MAX / 2);
+
+        group.throughput(Throughput::Elements(vectors as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(vectors), &vectors, |b, _| {
+            b.iter(|| persistence.recall_top_k(SESSION, &query, 10).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// A tree where every node has `FANOUT` children, `LEVELS` deep
+fn graph_traversal(c: &mut Criterion) {
+    const FANOUT: usize = 4;
+    const LEVELS: usize = 5;
+
+    let persistence = Persistence::in_memory().unwrap();
+    let props = serde_json::json!({});
+    let mut root = 0;
+    in_transaction(&persistence, |persistence| {
+        root = persistence
+            .insert_graph_node(SESSION, NodeType::Entity, "root", &props, None)
+            .unwrap();
+        let mut level = vec![root];
+        for depth in 1..=LEVELS {
+            let mut next = Vec::new();
+            for parent in &level {
+                for child in 0..FANOUT {
+                    let label = format!("node-{}-{}-{}", depth, parent, child);
+                    let id = persistence
+                        .insert_graph_node(SESSION, NodeType::Concept, &label, &props, None)
+                        .unwrap();
+                    persistence
+                        .insert_graph_edge(
+                            SESSION,
+                            *parent,
+                            id,
+                            EdgeType::RelatesTo,
+                            None,
+                            None,
+                            1.0,
+                        )
+                        .unwrap();
+                    next.push(id);
+                }
+            }
+            level = next;
+        }
+    });
+
+    let mut group = c.benchmark_group("traverse_neighbors");
+    group.sample_size(10);
+    for depth in 1..=LEVELS {
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
+            b.iter(|| {
+                persistence
+                    .traverse_neighbors(SESSION, root, TraversalDirection::Outgoing, depth)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, recall_top_k, graph_traversal);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -40,6 +40,12 @@ pub struct AgentProfile {
     #[serde(default)]
     pub denied_tools: Option<Vec<String>>,
 
+    /// Built-in policy rules layered under the stored policy rules: `safe`
+    /// (no shell, file writes or web), `standard` (shell and file writes
+    /// need approval) or `yolo` (everything allowed)
+    #[serde(default)]
+    pub policy_preset: Option<String>,
+
     /// Memory parameters: number of messages to recall (k for top-k)
     #[serde(default = "AgentProfile::default_memory_k")]
     pub memory_k: usize,
@@ -52,6 +58,60 @@ pub struct AgentProfile {
     #[serde(default)]
     pub max_context_tokens: Option<usize>,
 
+    /// Retries after a context-length error, each dropping more low-priority
+    /// context (old history, then recalled memories, then graph context)
+    #[serde(default = "AgentProfile::default_context_overflow_retries")]
+    pub context_overflow_retries: usize,
+
+    // ========== Generation Parameters ==========
+    /// Maximum tokens to generate per response (falls back to max_context_tokens)
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// Sequences that stop generation when produced
+    #[serde(default)]
+    pub stop_sequences: Option<Vec<String>>,
+
+    /// Frequency penalty (-2.0 to 2.0)
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+
+    /// Presence penalty (-2.0 to 2.0)
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+
+    /// Top-k sampling (providers that support it)
+    #[serde(default)]
+    pub top_k: Option<u32>,
+
+    /// Sampling seed for reproducible output (providers that support it)
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Reproducible runs: fixed seed, zero temperature, pinned tool-result
+    /// timestamps and a request hash recorded for every model call
+    #[serde(default)]
+    pub deterministic: bool,
+
+    /// Weight of memory importance (recency, recall frequency, graph centrality)
+    /// versus raw similarity when ranking recalled memories (0.0 to 1.0, 0 disables)
+    #[serde(default)]
+    pub memory_importance_weight: f32,
+
+    /// Hours after which the recency component of memory importance halves
+    #[serde(default = "AgentProfile::default_memory_decay_half_life_hours")]
+    pub memory_decay_half_life_hours: f32,
+
+    /// Sessions semantic recall draws memories from: this one (`session`),
+    /// every session of this agent (`agent`) or all sessions (`global`)
+    #[serde(default)]
+    pub recall_scope: RecallScope,
+
+    /// Condense old messages into recallable summaries in the background,
+    /// see [`MemoryCompactionConfig`]; off while unset
+    #[serde(default)]
+    pub memory_compaction: Option<MemoryCompactionConfig>,
+
     // ========== Knowledge Graph Configuration ==========
     /// Enable knowledge graph features for this agent
     #[serde(default)]
@@ -106,10 +166,75 @@ pub struct AgentProfile {
     #[serde(default = "AgentProfile::default_escalation_threshold")]
     pub escalation_threshold: f32,
 
+    /// Provider and model per task class (`code`, `summarize`, `extract`,
+    /// `chat`, `plan`); steps of an unlisted class use the main model
+    #[serde(default)]
+    pub model_routes: HashMap<String, ModelRouteConfig>,
+
     /// Display reasoning summary to user (requires fast model for summarization)
     #[serde(default)]
     pub show_reasoning: bool,
 
+    /// After specs and long runs, ask the fast model for lessons learned and
+    /// store them as `Lesson` graph nodes that later prompts can surface
+    #[serde(default)]
+    pub enable_reflection: bool,
+
+    /// Have a critic model check the final answer against recalled context and
+    /// tool output, with one automatic revision when it finds unsupported claims
+    #[serde(default)]
+    pub verify_responses: bool,
+
+    /// Agent profile whose model acts as the critic (defaults to the fast model)
+    #[serde(default)]
+    pub critic_profile: Option<String>,
+
+    /// Formatters applied in order to every final response before it is
+    /// stored and shown
+    #[serde(default)]
+    pub post_processors: Vec<PostProcessor>,
+
+    /// Language for answers: `auto` to mirror the user's language, or a
+    /// language code/name such as `es` or `Spanish`
+    #[serde(default)]
+    pub response_language: Option<String>,
+
+    /// Translate answers with the fast model when they come back in a
+    /// language other than `response_language`
+    #[serde(default)]
+    pub translate_responses: bool,
+
+    /// Put the current date, time and upcoming calendar events (see
+    /// `[time]`) in the prompt
+    #[serde(default)]
+    pub time_context: bool,
+
+    /// Context blocks (OS, working directory, git status, ...) added to
+    /// every prompt, in order
+    #[serde(default)]
+    pub context_providers: Vec<ContextProviderConfig>,
+
+    /// Shrink recalled messages and tool output that exceed a token
+    /// threshold before they go into the prompt
+    #[serde(default)]
+    pub context_compression: Option<ContextCompressionConfig>,
+
+    /// How tools are offered to the model: `auto` uses native function
+    /// calling when the provider supports it and the text protocol
+    /// (`Action:` / `Action Input:` lines) otherwise
+    #[serde(default)]
+    pub tool_calling: ToolCallingMode,
+
+    /// Keep offering tools that have failed every call this agent made to
+    /// them; by default they drop out of the tool list and schema
+    #[serde(default)]
+    pub advertise_failing_tools: bool,
+
+    /// Seconds a call of the named tool may run before it is abandoned and
+    /// reported as failed, overriding the global `tool_timeout_secs`
+    #[serde(default)]
+    pub tool_timeouts: HashMap<String, u64>,
+
     // ========== Audio Transcription Configuration ==========
     /// Enable audio transcription for this agent
     #[serde(default)]
@@ -126,6 +251,10 @@ pub struct AgentProfile {
 
 impl AgentProfile {
     const ALWAYS_ALLOWED_TOOLS: [&'static str; 1] = ["prompt_user"];
+    const VALID_PROVIDERS: [&'static str; 6] =
+        ["mock", "openai", "anthropic", "ollama", "mlx", "lmstudio"];
+    pub const POLICY_PRESETS: [&'static str; 3] = ["safe", "standard", "yolo"];
+
     fn default_memory_k() -> usize {
         10
     }
@@ -134,6 +263,14 @@ impl AgentProfile {
         0.9
     }
 
+    fn default_context_overflow_retries() -> usize {
+        3
+    }
+
+    fn default_memory_decay_half_life_hours() -> f32 {
+        168.0 // One week
+    }
+
     fn default_graph_depth() -> usize {
         3
     }
@@ -190,6 +327,49 @@ impl AgentProfile {
             .into());
         }
 
+        // Validate generation parameters
+        if self.max_tokens == Some(0) {
+            return Err(AgentError::Invalid("max_tokens must be greater than 0".into()).into());
+        }
+        if self.top_k == Some(0) {
+            return Err(AgentError::Invalid("top_k must be greater than 0".into()).into());
+        }
+        for (name, penalty) in [
+            ("frequency_penalty", self.frequency_penalty),
+            ("presence_penalty", self.presence_penalty),
+        ] {
+            if let Some(value) = penalty {
+                if !(-2.0..=2.0).contains(&value) {
+                    return Err(AgentError::Invalid(format!(
+                        "{} must be between -2.0 and 2.0, got {}",
+                        name, value
+                    ))
+                    .into());
+                }
+            }
+        }
+        if let Some(stops) = &self.stop_sequences {
+            if stops.iter().any(|s| s.is_empty()) {
+                return Err(AgentError::Invalid("stop_sequences must not be empty".into()).into());
+            }
+        }
+
+        // Validate memory importance settings
+        if !(0.0..=1.0).contains(&self.memory_importance_weight) {
+            return Err(AgentError::Invalid(format!(
+                "memory_importance_weight must be between 0.0 and 1.0, got {}",
+                self.memory_importance_weight
+            ))
+            .into());
+        }
+        if self.memory_decay_half_life_hours <= 0.0 {
+            return Err(AgentError::Invalid(format!(
+                "memory_decay_half_life_hours must be positive, got {}",
+                self.memory_decay_half_life_hours
+            ))
+            .into());
+        }
+
         // Validate graph_weight
         if self.graph_weight < 0.0 || self.graph_weight > 1.0 {
             return Err(AgentError::Invalid(format!(
@@ -223,19 +403,99 @@ impl AgentProfile {
             }
         }
 
+        if let Some((tool, _)) = self.tool_timeouts.iter().find(|(_, secs)| **secs == 0) {
+            return Err(AgentError::Invalid(format!(
+                "tool_timeouts.{}: timeout must be at least 1 second",
+                tool
+            ))
+            .into());
+        }
+
+        if matches!(&self.response_language, Some(language) if language.trim().is_empty()) {
+            return Err(AgentError::Invalid("response_language must not be empty".into()).into());
+        }
+
+        for processor in &self.post_processors {
+            processor.validate()?;
+        }
+
+        for provider in &self.context_providers {
+            if provider.name().trim().is_empty() {
+                return Err(AgentError::Invalid(
+                    "context_providers: name must not be empty".into(),
+                )
+                .into());
+            }
+            if provider.max_tokens() == Some(0) {
+                return Err(AgentError::Invalid(format!(
+                    "context_providers: max_tokens of '{}' must be greater than 0",
+                    provider.name()
+                ))
+                .into());
+            }
+        }
+
+        if let Some(compression) = &self.context_compression {
+            compression.validate()?;
+        }
+
+        if let Some(compaction) = &self.memory_compaction {
+            compaction.validate()?;
+        }
+
+        if let Some(preset) = &self.policy_preset {
+            if !Self::POLICY_PRESETS.contains(&preset.trim().to_ascii_lowercase().as_str()) {
+                return Err(AgentError::Invalid(format!(
+                    "policy_preset must be one of: {}. Got: {}",
+                    Self::POLICY_PRESETS.join(", "),
+                    preset
+                ))
+                .into());
+            }
+        }
+
         // Validate model provider if specified
         if let Some(provider) = &self.model_provider {
-            let valid_providers = ["mock", "openai", "anthropic", "ollama", "mlx", "lmstudio"];
-            if !valid_providers.contains(&provider.as_str()) {
+            if !Self::VALID_PROVIDERS.contains(&provider.as_str()) {
                 return Err(AgentError::Invalid(format!(
                     "model_provider must be one of: {}. Got: {}",
-                    valid_providers.join(", "),
+                    Self::VALID_PROVIDERS.join(", "),
                     provider
                 ))
                 .into());
             }
         }
 
+        for (task, route) in &self.model_routes {
+            if TaskClass::parse(task).is_none() {
+                let classes: Vec<&str> = TaskClass::ALL.iter().map(|c| c.as_str()).collect();
+                return Err(AgentError::Invalid(format!(
+                    "model_routes: unknown task class '{}' (expected one of: {})",
+                    task,
+                    classes.join(", ")
+                ))
+                .into());
+            }
+            if !Self::VALID_PROVIDERS.contains(&route.provider.as_str()) {
+                return Err(AgentError::Invalid(format!(
+                    "model_routes.{}: provider must be one of: {}. Got: {}",
+                    task,
+                    Self::VALID_PROVIDERS.join(", "),
+                    route.provider
+                ))
+                .into());
+            }
+            if let Some(temp) = route.temperature {
+                if !(0.0..=2.0).contains(&temp) {
+                    return Err(AgentError::Invalid(format!(
+                        "model_routes.{}: temperature must be between 0.0 and 2.0, got {}",
+                        task, temp
+                    ))
+                    .into());
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -277,6 +537,287 @@ impl AgentProfile {
     }
 }
 
+/// Kind of work a step asks for, used to pick a `model_routes` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskClass {
+    Code,
+    Summarize,
+    Extract,
+    Chat,
+    Plan,
+}
+
+impl TaskClass {
+    pub const ALL: [TaskClass; 5] = [
+        TaskClass::Code,
+        TaskClass::Summarize,
+        TaskClass::Extract,
+        TaskClass::Chat,
+        TaskClass::Plan,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskClass::Code => "code",
+            TaskClass::Summarize => "summarize",
+            TaskClass::Extract => "extract",
+            TaskClass::Chat => "chat",
+            TaskClass::Plan => "plan",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL
+            .into_iter()
+            .find(|class| class.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+/// Model a task class is routed to (`model_routes` in a profile)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelRouteConfig {
+    /// Model provider (e.g., "ollama", "openai")
+    pub provider: String,
+    /// Model name; the provider's default when unset
+    #[serde(default)]
+    pub model_name: Option<String>,
+    /// Temperature for this route (defaults to the main model's)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+/// A formatter applied to final responses (`post_processors` in a profile)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PostProcessor {
+    /// Tidy whitespace, bullet markers and unterminated code fences
+    NormalizeMarkdown,
+    /// Add a language to code fences that lack one, guessed from the code
+    InferCodeLanguage,
+    /// Replace the `from` prefix of matching URLs with `to`
+    RewriteLinks { from: String, to: String },
+    /// Embed the response in a template containing `{{response}}`
+    Wrap { template: String },
+    /// Append a section with this heading unless the response already has
+    /// one. Without `content` the section lists the response's links.
+    EnsureSection {
+        heading: String,
+        #[serde(default)]
+        content: Option<String>,
+    },
+}
+
+impl PostProcessor {
+    fn validate(&self) -> Result<()> {
+        let problem = match self {
+            PostProcessor::RewriteLinks { from, .. } if from.trim().is_empty() => {
+                "rewrite_links needs a non-empty `from`"
+            }
+            PostProcessor::Wrap { template } if !template.contains("{{response}}") => {
+                "wrap template must contain {{response}}"
+            }
+            PostProcessor::EnsureSection { heading, .. } if heading.trim().is_empty() => {
+                "ensure_section needs a heading"
+            }
+            _ => return Ok(()),
+        };
+        Err(AgentError::Invalid(format!("post_processors: {}", problem)).into())
+    }
+}
+
+/// How oversized context is compressed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionMode {
+    /// Drop filler words, repeated lines and, if still too long, the middle
+    #[default]
+    Heuristic,
+    /// Ask the fast model for a dense rewrite (heuristic when unavailable)
+    Summarize,
+}
+
+impl CompressionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionMode::Heuristic => "heuristic",
+            CompressionMode::Summarize => "summarize",
+        }
+    }
+}
+
+/// Sessions memory recall searches (`recall_scope` in a profile)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecallScope {
+    /// Only the current session
+    #[default]
+    Session,
+    /// The current session and every other session of the same agent
+    Agent,
+    /// Every session in the database
+    Global,
+}
+
+impl RecallScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecallScope::Session => "session",
+            RecallScope::Agent => "agent",
+            RecallScope::Global => "global",
+        }
+    }
+}
+
+/// How the model asks for tools (`tool_calling` in a profile)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCallingMode {
+    /// Native when the provider reports function calling support
+    #[default]
+    Auto,
+    /// Always rely on the provider's native tool calls
+    Native,
+    /// Always describe tools in the prompt and parse text actions
+    Text,
+}
+
+impl ToolCallingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolCallingMode::Auto => "auto",
+            ToolCallingMode::Native => "native",
+            ToolCallingMode::Text => "text",
+        }
+    }
+}
+
+/// Context compression settings (`context_compression` in a profile)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextCompressionConfig {
+    #[serde(default)]
+    pub mode: CompressionMode,
+    /// Items over this many tokens are compressed
+    #[serde(default = "ContextCompressionConfig::default_threshold_tokens")]
+    pub threshold_tokens: usize,
+    /// Size to aim for, as a fraction of the original
+    #[serde(default = "ContextCompressionConfig::default_target_ratio")]
+    pub target_ratio: f32,
+}
+
+impl Default for ContextCompressionConfig {
+    fn default() -> Self {
+        Self {
+            mode: CompressionMode::default(),
+            threshold_tokens: Self::default_threshold_tokens(),
+            target_ratio: Self::default_target_ratio(),
+        }
+    }
+}
+
+impl ContextCompressionConfig {
+    fn default_threshold_tokens() -> usize {
+        800
+    }
+
+    fn default_target_ratio() -> f32 {
+        0.5
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.threshold_tokens == 0 {
+            return Err(AgentError::Invalid(
+                "context_compression: threshold_tokens must be greater than 0".into(),
+            )
+            .into());
+        }
+        if !(self.target_ratio > 0.0 && self.target_ratio < 1.0) {
+            return Err(AgentError::Invalid(format!(
+                "context_compression: target_ratio must be between 0.0 and 1.0 (exclusive), got {}",
+                self.target_ratio
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Memory compaction settings (`memory_compaction` in a profile)
+///
+/// Messages older than the newest `keep_recent` are summarized
+/// `chunk_messages` at a time; each summary replaces the embeddings of the
+/// messages it covers in recall.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryCompactionConfig {
+    /// Newest messages of a session left as they are
+    #[serde(default = "MemoryCompactionConfig::default_keep_recent")]
+    pub keep_recent: usize,
+    /// Messages condensed into one summary
+    #[serde(default = "MemoryCompactionConfig::default_chunk_messages")]
+    pub chunk_messages: usize,
+}
+
+impl Default for MemoryCompactionConfig {
+    fn default() -> Self {
+        Self {
+            keep_recent: Self::default_keep_recent(),
+            chunk_messages: Self::default_chunk_messages(),
+        }
+    }
+}
+
+impl MemoryCompactionConfig {
+    fn default_keep_recent() -> usize {
+        100
+    }
+
+    fn default_chunk_messages() -> usize {
+        40
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.chunk_messages < 2 {
+            return Err(AgentError::Invalid(
+                "memory_compaction: chunk_messages must be at least 2".into(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// A context provider enabled for a profile: either its name, or a table
+/// with the name and a token budget
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ContextProviderConfig {
+    Name(String),
+    Settings {
+        name: String,
+        /// Upper bound on the block's size (defaults to the provider's own)
+        #[serde(default)]
+        max_tokens: Option<usize>,
+    },
+}
+
+impl ContextProviderConfig {
+    pub fn name(&self) -> &str {
+        match self {
+            ContextProviderConfig::Name(name) | ContextProviderConfig::Settings { name, .. } => {
+                name
+            }
+        }
+    }
+
+    pub fn max_tokens(&self) -> Option<usize> {
+        match self {
+            ContextProviderConfig::Name(_) => None,
+            ContextProviderConfig::Settings { max_tokens, .. } => *max_tokens,
+        }
+    }
+}
+
 impl Default for AgentProfile {
     fn default() -> Self {
         Self {
@@ -287,9 +828,22 @@ impl Default for AgentProfile {
             model_name: None,
             allowed_tools: None,
             denied_tools: None,
+            policy_preset: None,
             memory_k: Self::default_memory_k(),
             top_p: Self::default_top_p(),
             max_context_tokens: None,
+            context_overflow_retries: Self::default_context_overflow_retries(),
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: Self::default_memory_decay_half_life_hours(),
+            recall_scope: RecallScope::Session,
+            memory_compaction: None,
             enable_graph: true, // Enable by default
             graph_memory: true, // Enable by default
             graph_depth: Self::default_graph_depth(),
@@ -303,7 +857,20 @@ impl Default for AgentProfile {
             fast_model_temperature: Self::default_fast_temperature(),
             fast_model_tasks: Self::default_fast_tasks(),
             escalation_threshold: Self::default_escalation_threshold(),
-            show_reasoning: false,             // Disabled by default
+            model_routes: HashMap::new(),
+            show_reasoning: false,    // Disabled by default
+            enable_reflection: false, // Disabled by default
+            verify_responses: false,  // Disabled by default
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            context_compression: None,
+            tool_calling: ToolCallingMode::Auto,
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
             enable_audio_transcription: false, // Disabled by default
             audio_response_mode: Self::default_audio_response_mode(),
             audio_scenario: None,
@@ -340,6 +907,130 @@ mod tests {
         assert!(profile.validate().is_ok());
     }
 
+    #[test]
+    fn test_post_processors_parse_and_validate() {
+        let profile: AgentProfile = toml::from_str(
+            r#"
+post_processors = [
+  { kind = "normalize_markdown" },
+  { kind = "rewrite_links", from = "http://wiki.internal", to = "https://wiki.example.com" },
+  { kind = "ensure_section", heading = "Sources" },
+]
+"#,
+        )
+        .unwrap();
+        assert_eq!(profile.post_processors.len(), 3);
+        assert_eq!(profile.post_processors[0], PostProcessor::NormalizeMarkdown);
+        assert!(profile.validate().is_ok());
+
+        let mut invalid = AgentProfile::default();
+        invalid.post_processors = vec![PostProcessor::Wrap {
+            template: "no placeholder".into(),
+        }];
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_context_providers_parse() {
+        let profile: AgentProfile = toml::from_str(
+            r#"
+context_providers = ["os", { name = "git", max_tokens = 40 }]
+"#,
+        )
+        .unwrap();
+        let names: Vec<&str> = profile.context_providers.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["os", "git"]);
+        assert_eq!(profile.context_providers[0].max_tokens(), None);
+        assert_eq!(profile.context_providers[1].max_tokens(), Some(40));
+        assert!(profile.validate().is_ok());
+
+        let mut invalid = AgentProfile::default();
+        invalid.context_providers = vec![ContextProviderConfig::Settings {
+            name: "cwd".into(),
+            max_tokens: Some(0),
+        }];
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_context_compression_parse_and_validate() {
+        let profile: AgentProfile = toml::from_str(
+            r#"
+[context_compression]
+mode = "summarize"
+threshold_tokens = 400
+"#,
+        )
+        .unwrap();
+        let compression = profile.context_compression.clone().unwrap();
+        assert_eq!(compression.mode, CompressionMode::Summarize);
+        assert_eq!(compression.threshold_tokens, 400);
+        assert_eq!(compression.target_ratio, 0.5);
+        assert!(profile.validate().is_ok());
+
+        let mut invalid = profile;
+        invalid.context_compression = Some(ContextCompressionConfig {
+            target_ratio: 1.5,
+            ..ContextCompressionConfig::default()
+        });
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_memory_compaction_parse_and_validate() {
+        let profile: AgentProfile = toml::from_str(
+            r#"
+[memory_compaction]
+keep_recent = 20
+"#,
+        )
+        .unwrap();
+        let compaction = profile.memory_compaction.clone().unwrap();
+        assert_eq!(compaction.keep_recent, 20);
+        assert_eq!(compaction.chunk_messages, 40);
+        assert!(profile.validate().is_ok());
+
+        let mut invalid = profile;
+        invalid.memory_compaction = Some(MemoryCompactionConfig {
+            chunk_messages: 1,
+            ..MemoryCompactionConfig::default()
+        });
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_model_routes_parse_and_validate() {
+        let profile: AgentProfile = toml::from_str(
+            r#"
+[model_routes]
+summarize = { provider = "ollama", model_name = "llama3.2:3b" }
+code = { provider = "openai", model_name = "gpt-4o", temperature = 0.2 }
+"#,
+        )
+        .unwrap();
+        assert_eq!(profile.model_routes.len(), 2);
+        assert_eq!(
+            profile.model_routes["summarize"].model_name.as_deref(),
+            Some("llama3.2:3b")
+        );
+        assert!(profile.validate().is_ok());
+
+        let mut invalid = profile.clone();
+        invalid.model_routes.insert(
+            "poetry".into(),
+            ModelRouteConfig {
+                provider: "ollama".into(),
+                model_name: None,
+                temperature: None,
+            },
+        );
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = profile;
+        invalid.model_routes.get_mut("code").unwrap().provider = "nowhere".into();
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_validate_invalid_temperature() {
         let mut profile = AgentProfile::default();
@@ -354,6 +1045,22 @@ mod tests {
         assert!(profile.validate().is_err());
     }
 
+    #[test]
+    fn test_validate_generation_parameters() {
+        let mut profile = AgentProfile::default();
+        profile.frequency_penalty = Some(2.5);
+        assert!(profile.validate().is_err());
+
+        profile.frequency_penalty = Some(0.5);
+        profile.top_k = Some(0);
+        assert!(profile.validate().is_err());
+
+        profile.top_k = Some(40);
+        profile.seed = Some(7);
+        profile.stop_sequences = Some(vec!["\n\nUser:".to_string()]);
+        assert!(profile.validate().is_ok());
+    }
+
     #[test]
     fn test_validate_tool_overlap() {
         let mut profile = AgentProfile::default();
@@ -4,11 +4,12 @@
 //! database configuration, UI preferences, and logging.
 
 use crate::config::agent::AgentProfile;
+use crate::types::{EdgeType, NodeType};
 use anyhow::{Context, Result};
 use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 /// Embedded default configuration file
 const DEFAULT_CONFIG: &str =
@@ -38,9 +39,51 @@ pub struct AppConfig {
     /// Mesh networking configuration
     #[serde(default)]
     pub mesh: MeshConfig,
+    /// Knowledge graph sync between mesh peers
+    #[serde(default)]
+    pub sync: SyncConfig,
     /// Plugin configuration for custom tools
     #[serde(default)]
     pub plugins: PluginConfig,
+    /// Per-tool execution settings, keyed by tool name
+    #[serde(default)]
+    pub tools: HashMap<String, ToolSettings>,
+    /// Seconds any tool call may run before it is abandoned and reported as
+    /// failed; agent profiles override it per tool with `tool_timeouts`.
+    /// Unbounded when unset.
+    #[serde(default)]
+    pub tool_timeout_secs: Option<u64>,
+    /// Tools assembled from other tools, keyed by the name the model sees
+    #[serde(default)]
+    pub composite_tools: HashMap<String, CompositeToolConfig>,
+    /// Multi-agent round-table configuration
+    #[serde(default)]
+    pub roundtable: RoundtableConfig,
+    /// Date, time and calendar settings
+    #[serde(default)]
+    pub time: TimeConfig,
+    /// Fetching and verifying remote specs
+    #[serde(default)]
+    pub specs: SpecsConfig,
+    /// Long-term memory about the user, shared by every session
+    #[serde(default)]
+    pub user_profile: UserProfileConfig,
+    /// Keeping personal data out of the database
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    /// Streaming graph changes and finished runs to an external system
+    #[serde(default)]
+    pub events: EventsConfig,
+    /// Archiving and deleting sessions left inactive
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+    /// Knowledge graph node and edge types beyond the built-in ones
+    #[serde(default)]
+    pub graph: GraphConfig,
+    /// TOML or YAML file of policy rules, checked after the rules stored in
+    /// the database and read again by `/policy reload`
+    #[serde(default)]
+    pub policy_file: Option<PathBuf>,
     /// Available agent profiles
     #[serde(default)]
     pub agents: HashMap<String, AgentProfile>,
@@ -174,6 +217,29 @@ impl AppConfig {
             _ => return Err(anyhow::anyhow!("Invalid log level: {}", self.logging.level)),
         }
 
+        self.roundtable.validate(&self.agents)?;
+        self.events.validate()?;
+        self.sessions.validate()?;
+        self.graph.validate()?;
+        if self.tool_timeout_secs == Some(0) {
+            return Err(anyhow::anyhow!("tool_timeout_secs must be at least 1"));
+        }
+        self.time
+            .utc_offset_seconds()
+            .context("Invalid [time] configuration")?;
+
+        for (name, profile) in &self.agents {
+            if let Some(critic) = &profile.critic_profile {
+                if !self.agents.contains_key(critic) {
+                    return Err(anyhow::anyhow!(
+                        "Critic profile '{}' of agent '{}' not found in agents map",
+                        critic,
+                        name
+                    ));
+                }
+            }
+        }
+
         // If a default agent is specified, it must exist in the agents map
         if let Some(default_agent) = &self.default_agent {
             if !self.agents.contains_key(default_agent) {
@@ -226,7 +292,11 @@ impl AppConfig {
     pub fn summary(&self) -> String {
         let mut summary = String::new();
         summary.push_str("Configuration loaded:\n");
-        summary.push_str(&format!("Database: {}\n", self.database.path.display()));
+        if self.database.is_in_memory() {
+            summary.push_str("Database: in memory (discarded on exit)\n");
+        } else {
+            summary.push_str(&format!("Database: {}\n", self.database.path.display()));
+        }
         summary.push_str(&format!("Model Provider: {}\n", self.model.provider));
         if let Some(model) = &self.model.model_name {
             summary.push_str(&format!("Model Name: {}\n", model));
@@ -238,6 +308,9 @@ impl AppConfig {
         if let Some(default) = &self.default_agent {
             summary.push_str(&format!("Default Agent: {}\n", default));
         }
+        if let Some(policy_file) = &self.policy_file {
+            summary.push_str(&format!("Policy File: {}\n", policy_file.display()));
+        }
         summary
     }
 }
@@ -245,10 +318,18 @@ impl AppConfig {
 /// Database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
-    /// Path to the database file
+    /// Path to the database file, or `:memory:` for a database that is
+    /// discarded on exit
     pub path: PathBuf,
 }
 
+impl DatabaseConfig {
+    /// Whether the configured database lives only in memory
+    pub fn is_in_memory(&self) -> bool {
+        self.path == Path::new(crate::persistence::IN_MEMORY_PATH)
+    }
+}
+
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
@@ -315,16 +396,28 @@ impl Default for UiConfig {
 pub struct LoggingConfig {
     /// Log level (trace, debug, info, warn, error)
     pub level: String,
+    /// Record every provider request and response in the `model_log` table
+    #[serde(default)]
+    pub model_log: bool,
+    /// Prompt and response text beyond this many bytes is cut from the log
+    #[serde(default = "default_model_log_max_bytes")]
+    pub model_log_max_bytes: usize,
 }
 
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
             level: "info".to_string(),
+            model_log: false,
+            model_log_max_bytes: default_model_log_max_bytes(),
         }
     }
 }
 
+fn default_model_log_max_bytes() -> usize {
+    16 * 1024
+}
+
 /// Mesh networking configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshConfig {
@@ -346,6 +439,465 @@ pub struct MeshConfig {
     /// Auto-join mesh on startup
     #[serde(default)]
     pub auto_join: bool,
+    /// Local tools other members may run on this instance as `remote:<tool>`
+    #[serde(default)]
+    pub shared_tools: Vec<String>,
+    /// Seconds a remote tool call may take before it is abandoned
+    #[serde(default = "default_remote_tool_timeout")]
+    pub remote_tool_timeout_secs: u64,
+    /// Limits this instance enforces locally and advertises to the leader
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    /// Key sealing join tokens (default: `~/.spec-ai/mesh.key`, created on
+    /// first use). `SPEC_AI_MESH_KEY` (base64) takes precedence.
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// Refuse members that register without a join token from
+    /// `spec-ai mesh invite`
+    #[serde(default)]
+    pub require_join_token: bool,
+    /// What this instance does in the mesh
+    #[serde(default)]
+    pub role: MeshRole,
+}
+
+/// `[mesh] role`: which subsystems an instance starts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MeshRole {
+    /// Runs agents and hosts the registry when no leader is reachable
+    #[default]
+    LeaderCapable,
+    /// Runs agents and delegated tasks, but only ever joins a registry
+    Worker,
+    /// Syncs graphs from peers and serves reads; refuses runs, writes,
+    /// delegated tasks and tool calls, and never hosts the registry
+    ReadReplica,
+}
+
+impl MeshRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MeshRole::LeaderCapable => "leader-capable",
+            MeshRole::Worker => "worker",
+            MeshRole::ReadReplica => "read-replica",
+        }
+    }
+
+    /// Whether the instance may host the registry and be elected leader
+    pub fn can_lead(&self) -> bool {
+        *self == MeshRole::LeaderCapable
+    }
+
+    /// Whether the instance runs agents and changes its data on request
+    pub fn accepts_writes(&self) -> bool {
+        *self != MeshRole::ReadReplica
+    }
+}
+
+/// Usage limits; a limit that is not set is unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    /// Agent runs allowed at the same time
+    #[serde(default)]
+    pub max_concurrent_runs: Option<usize>,
+    /// Provider tokens allowed per UTC day
+    #[serde(default)]
+    pub daily_token_budget: Option<u64>,
+    /// Mesh messages that may be sent in any hour (instance-wide only)
+    #[serde(default)]
+    pub max_messages_per_hour: Option<u32>,
+}
+
+impl QuotaLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// `[mesh.quotas]`: limits for the whole instance, with
+/// `[mesh.quotas.agents.<name>]` tables narrowing them for single agents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    #[serde(flatten)]
+    pub instance: QuotaLimits,
+    #[serde(default)]
+    pub agents: HashMap<String, QuotaLimits>,
+}
+
+/// `[specs]`: remote spec sources for `spec-ai run` and the local spec library
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpecsConfig {
+    /// Base64 Ed25519 public keys; when set, remote specs must come with a
+    /// `<url>.sig` signature from one of them
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+    /// Where fetched specs are cached (default: `~/.spec-ai/specs-cache`)
+    #[serde(default)]
+    pub cache_dir: Option<String>,
+    /// Spec library managed by `spec-ai specs` (default: `~/.spec-ai/specs`)
+    #[serde(default)]
+    pub library_dir: Option<String>,
+}
+
+/// `[user_profile]`: facts about the user (preferences, writing style,
+/// recurring projects) kept across sessions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserProfileConfig {
+    /// Extract facts when a session ends and show them in every prompt
+    #[serde(default = "default_user_profile_enabled")]
+    pub enabled: bool,
+    /// Most facts put in the prompt, newest first
+    #[serde(default = "default_user_profile_prompt_facts")]
+    pub prompt_facts: usize,
+}
+
+fn default_user_profile_enabled() -> bool {
+    true
+}
+
+fn default_user_profile_prompt_facts() -> usize {
+    20
+}
+
+impl Default for UserProfileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_user_profile_enabled(),
+            prompt_facts: default_user_profile_prompt_facts(),
+        }
+    }
+}
+
+/// `[privacy]`: personal data kept out of the database
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PrivacyConfig {
+    /// Replace emails, phone numbers and names with tokens before they are
+    /// stored; the originals go to an encrypted vault and are put back only
+    /// when a prompt is assembled
+    #[serde(default)]
+    pub redact_at_rest: bool,
+    /// Key for the vault (default: `~/.spec-ai/vault.key`, created on first
+    /// use). `SPEC_AI_VAULT_KEY` (64 hex digits) takes precedence.
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// Names always treated as personal data, in addition to those found
+    /// after phrases like "my name is"
+    #[serde(default)]
+    pub known_names: Vec<String>,
+}
+
+/// `[events]`: graph changes and finished runs published to NATS, Kafka or
+/// a webhook. Events are written to an outbox in the database together with
+/// the change they describe and delivered in order, at least once.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventsConfig {
+    /// `webhook`, `nats` or `kafka`; events are off while unset
+    #[serde(default)]
+    pub sink: Option<String>,
+    /// Webhook URL, `nats://host:port`, or the base URL of a Kafka REST proxy
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Kafka topic, or NATS subject prefix (events go to `<topic>.<kind>`)
+    #[serde(default = "default_events_topic")]
+    pub topic: String,
+    /// Event kinds to publish: `graph` and `run`
+    #[serde(default = "default_event_kinds")]
+    pub include: Vec<String>,
+    /// Events sent per request or NATS flush
+    #[serde(default = "default_events_batch_size")]
+    pub batch_size: usize,
+    /// How often the outbox is checked for new events
+    #[serde(default = "default_events_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+impl EventsConfig {
+    pub const SINKS: [&'static str; 3] = ["webhook", "nats", "kafka"];
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Whether events of `kind` are recorded
+    pub fn includes(&self, kind: &str) -> bool {
+        self.is_enabled() && self.include.iter().any(|included| included == kind)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+        if !Self::SINKS.contains(&sink.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Invalid [events] sink '{}': expected one of {}",
+                sink,
+                Self::SINKS.join(", ")
+            ));
+        }
+        if self.url.as_deref().is_none_or(|url| url.trim().is_empty()) {
+            return Err(anyhow::anyhow!("[events] sink '{}' needs a url", sink));
+        }
+        if let Some(kind) = self
+            .include
+            .iter()
+            .find(|kind| !matches!(kind.as_str(), "graph" | "run"))
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid [events] kind '{}': expected graph or run",
+                kind
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            url: None,
+            topic: default_events_topic(),
+            include: default_event_kinds(),
+            batch_size: default_events_batch_size(),
+            poll_interval_ms: default_events_poll_interval_ms(),
+        }
+    }
+}
+
+fn default_events_topic() -> String {
+    "spec-ai.events".to_string()
+}
+
+fn default_event_kinds() -> Vec<String> {
+    vec!["graph".to_string(), "run".to_string()]
+}
+
+fn default_events_batch_size() -> usize {
+    100
+}
+
+fn default_events_poll_interval_ms() -> u64 {
+    500
+}
+
+/// `[sessions]`: what happens to sessions nobody has used for a while.
+/// Archiving replaces a session's messages with a summary and drops their
+/// embeddings, keeping the graph; an archived session is thawed when it is
+/// opened again and continues from the summary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SessionsConfig {
+    /// Days without activity after which a session is archived; never
+    /// while unset
+    #[serde(default)]
+    pub archive_after_days: Option<u32>,
+    /// Days without activity after which a session is deleted with
+    /// everything recorded for it; never while unset
+    #[serde(default)]
+    pub delete_after_days: Option<u32>,
+}
+
+impl SessionsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.archive_after_days.is_some() || self.delete_after_days.is_some()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.archive_after_days == Some(0) || self.delete_after_days == Some(0) {
+            return Err(anyhow::anyhow!(
+                "[sessions] archive_after_days and delete_after_days must be at least 1"
+            ));
+        }
+        if let (Some(archive), Some(delete)) = (self.archive_after_days, self.delete_after_days) {
+            if delete <= archive {
+                return Err(anyhow::anyhow!(
+                    "[sessions] delete_after_days ({}) must be greater than archive_after_days ({})",
+                    delete,
+                    archive
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `[graph]`: node and edge types of the knowledge graph beyond the
+/// built-in ones, and how any type is shown. The graph tool creates nodes
+/// of a built-in or declared type only; edges may still carry any custom
+/// type, declared or not.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GraphConfig {
+    /// `[graph.node_types.<name>]`, keyed by lowercase name
+    #[serde(default)]
+    pub node_types: HashMap<String, GraphTypeStyle>,
+    /// `[graph.edge_types.<NAME>]`, keyed by uppercase name
+    #[serde(default)]
+    pub edge_types: HashMap<String, GraphTypeStyle>,
+}
+
+/// How a node or edge type is shown by `/graph show` and in Mermaid charts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GraphTypeStyle {
+    /// Name shown in place of the type's own
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Color as `#rrggbb`
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl GraphTypeStyle {
+    /// Red, green and blue components of the color
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let hex = self.color.as_deref()?.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl GraphConfig {
+    /// Parse a node type the graph may be given; names neither built in nor
+    /// declared are refused
+    pub fn node_type(&self, name: &str) -> Result<NodeType> {
+        let node_type = NodeType::from_str(name.trim());
+        if node_type.is_builtin() || self.node_types.contains_key(node_type.as_str()) {
+            Ok(node_type)
+        } else {
+            Err(anyhow::anyhow!(
+                "Unknown node type '{}': expected one of {}",
+                name,
+                self.node_type_names().join(", ")
+            ))
+        }
+    }
+
+    /// Built-in node types, then the declared ones in name order
+    pub fn node_type_names(&self) -> Vec<String> {
+        let builtin: Vec<String> = NodeType::BUILTIN
+            .iter()
+            .map(|t| t.as_str().to_string())
+            .collect();
+        let mut declared: Vec<String> = self
+            .node_types
+            .keys()
+            .filter(|name| !builtin.contains(name))
+            .cloned()
+            .collect();
+        declared.sort();
+        builtin.into_iter().chain(declared).collect()
+    }
+
+    /// Built-in edge types, then the declared ones in name order
+    pub fn edge_type_names(&self) -> Vec<String> {
+        let builtin: Vec<String> = EdgeType::BUILTIN.iter().map(EdgeType::as_str).collect();
+        let mut declared: Vec<String> = self
+            .edge_types
+            .keys()
+            .filter(|name| !builtin.contains(name))
+            .cloned()
+            .collect();
+        declared.sort();
+        builtin.into_iter().chain(declared).collect()
+    }
+
+    pub fn node_style(&self, node_type: &NodeType) -> Option<&GraphTypeStyle> {
+        self.node_types.get(node_type.as_str())
+    }
+
+    pub fn edge_style(&self, edge_type: &EdgeType) -> Option<&GraphTypeStyle> {
+        self.edge_types.get(&edge_type.as_str())
+    }
+
+    /// Name a node type is shown by
+    pub fn node_label(&self, node_type: &NodeType) -> String {
+        self.node_style(node_type)
+            .and_then(|style| style.label.clone())
+            .unwrap_or_else(|| node_type.as_str().to_string())
+    }
+
+    /// Name an edge type is shown by
+    pub fn edge_label(&self, edge_type: &EdgeType) -> String {
+        self.edge_style(edge_type)
+            .and_then(|style| style.label.clone())
+            .unwrap_or_else(|| edge_type.as_str())
+    }
+
+    fn validate(&self) -> Result<()> {
+        let is_name = |name: &str, upper: bool| {
+            name.starts_with(|c: char| c.is_ascii_alphabetic())
+                && name.chars().all(|c| {
+                    c == '_'
+                        || c.is_ascii_digit()
+                        || (c.is_ascii_alphabetic() && c.is_ascii_uppercase() == upper)
+                })
+        };
+        for (kind, types, upper) in [
+            ("node", &self.node_types, false),
+            ("edge", &self.edge_types, true),
+        ] {
+            for (name, style) in types {
+                if !is_name(name, upper) {
+                    return Err(anyhow::anyhow!(
+                        "Invalid [graph] {} type '{}': use {} letters, digits and underscores",
+                        kind,
+                        name,
+                        if upper { "uppercase" } else { "lowercase" }
+                    ));
+                }
+                if style.label.as_deref().is_some_and(|l| l.trim().is_empty()) {
+                    return Err(anyhow::anyhow!(
+                        "[graph] {} type '{}' has an empty label",
+                        kind,
+                        name
+                    ));
+                }
+                if style.color.is_some() && style.rgb().is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Invalid [graph] color '{}' of {} type '{}': expected #rrggbb",
+                        style.color.as_deref().unwrap_or_default(),
+                        kind,
+                        name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `[sync]`: which mesh peers may replicate which graphs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncConfig {
+    /// `[[sync.acl]]` rules; the first rule matching a graph decides, and
+    /// graphs no rule matches sync with every peer
+    #[serde(default)]
+    pub acl: Vec<SyncAclRule>,
+}
+
+/// Peers allowed to read or write the graphs a rule matches. Peers are
+/// instance IDs, `tag:<capability>` for instances advertising that
+/// capability, or `*` for any instance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncAclRule {
+    /// Session ID, optionally with `*` wildcards
+    #[serde(default = "default_acl_pattern")]
+    pub session: String,
+    /// Graph name, optionally with `*` wildcards
+    #[serde(default = "default_acl_pattern")]
+    pub graph: String,
+    /// Peers that may pull these graphs
+    #[serde(default)]
+    pub read: Vec<String>,
+    /// Peers whose changes to these graphs are applied here
+    #[serde(default)]
+    pub write: Vec<String>,
+}
+
+fn default_acl_pattern() -> String {
+    "*".to_string()
 }
 
 fn default_registry_port() -> u16 {
@@ -364,6 +916,10 @@ fn default_replication_factor() -> usize {
     2
 }
 
+fn default_remote_tool_timeout() -> u64 {
+    60
+}
+
 impl Default for MeshConfig {
     fn default() -> Self {
         Self {
@@ -373,6 +929,12 @@ impl Default for MeshConfig {
             leader_timeout_secs: default_leader_timeout(),
             replication_factor: default_replication_factor(),
             auto_join: true,
+            shared_tools: Vec::new(),
+            remote_tool_timeout_secs: default_remote_tool_timeout(),
+            quotas: QuotaConfig::default(),
+            key_path: None,
+            require_join_token: false,
+            role: MeshRole::default(),
         }
     }
 }
@@ -422,6 +984,18 @@ pub struct AudioConfig {
     /// Delay between mock transcription events in milliseconds
     #[serde(default = "default_event_delay_ms")]
     pub event_delay_ms: u64,
+    /// Phrases that start a hands-free request (matched case- and
+    /// punctuation-insensitively in the transcription)
+    #[serde(default = "default_wake_words")]
+    pub wake_words: Vec<String>,
+    /// Longest hands-free request in seconds before it is sent anyway
+    #[serde(default = "default_max_utterance_secs")]
+    pub max_utterance_secs: u64,
+    /// Command that speaks hands-free responses; the text is passed as its
+    /// last argument. Unset picks `say`, `espeak-ng`, `espeak` or `spd-say`
+    /// from PATH; `"none"` keeps responses silent.
+    #[serde(default)]
+    pub tts_command: Option<String>,
 }
 
 fn default_transcription_provider() -> String {
@@ -444,6 +1018,14 @@ fn default_event_delay_ms() -> u64 {
     500
 }
 
+fn default_wake_words() -> Vec<String> {
+    vec!["hey spec".to_string()]
+}
+
+fn default_max_utterance_secs() -> u64 {
+    15
+}
+
 impl Default for AudioConfig {
     fn default() -> Self {
         Self {
@@ -461,6 +1043,9 @@ impl Default for AudioConfig {
             auto_respond: false,
             mock_scenario: default_mock_scenario(),
             event_delay_ms: default_event_delay_ms(),
+            wake_words: default_wake_words(),
+            max_utterance_secs: default_max_utterance_secs(),
+            tts_command: None,
         }
     }
 }
@@ -503,3 +1088,265 @@ impl Default for PluginConfig {
         }
     }
 }
+
+/// Clock and calendar used for time-aware prompts and tools
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeConfig {
+    /// `local`, `UTC`, or a fixed offset such as `+02:00` / `UTC-5`
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// iCalendar (.ics) file with the user's events
+    #[serde(default)]
+    pub calendar_file: Option<PathBuf>,
+    /// How many days of upcoming events to put in the prompt
+    #[serde(default = "default_calendar_days")]
+    pub calendar_days: u32,
+}
+
+fn default_timezone() -> String {
+    "local".to_string()
+}
+
+fn default_calendar_days() -> u32 {
+    7
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            timezone: default_timezone(),
+            calendar_file: None,
+            calendar_days: default_calendar_days(),
+        }
+    }
+}
+
+impl TimeConfig {
+    /// Offset from UTC in seconds, `None` for the system's local zone.
+    ///
+    /// Named IANA zones are not supported; use a fixed offset instead.
+    pub fn utc_offset_seconds(&self) -> Result<Option<i32>> {
+        parse_utc_offset(&self.timezone)
+    }
+}
+
+/// Parse a `[time].timezone` value into seconds east of UTC (`None` = local)
+pub fn parse_utc_offset(value: &str) -> Result<Option<i32>> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("local") {
+        return Ok(None);
+    }
+    let upper = value.to_ascii_uppercase();
+    let offset = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if offset.is_empty() || offset == "Z" {
+        return Ok(Some(0));
+    }
+
+    let invalid = || {
+        anyhow::anyhow!(
+            "Unsupported timezone '{}': use 'local', 'UTC' or an offset like '+02:00'",
+            value
+        )
+    };
+    let (sign, digits) = match offset.as_bytes()[0] {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes >= 60 {
+        return Err(invalid());
+    }
+    Ok(Some(sign * (hours * 3600 + minutes * 60)))
+}
+
+/// Round-table mode: several profiles draft answers, a moderator synthesizes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RoundtableConfig {
+    /// Profiles that each draft an answer (2 to 4)
+    #[serde(default)]
+    pub participants: Vec<String>,
+
+    /// Profile that synthesizes the final response (defaults to the active agent)
+    #[serde(default)]
+    pub moderator: Option<String>,
+
+    /// Print every draft before the synthesized answer
+    #[serde(default)]
+    pub show_drafts: bool,
+}
+
+impl RoundtableConfig {
+    pub const MIN_PARTICIPANTS: usize = 2;
+    pub const MAX_PARTICIPANTS: usize = 4;
+
+    /// Whether any participants are configured
+    pub fn is_configured(&self) -> bool {
+        !self.participants.is_empty()
+    }
+
+    fn validate(&self, agents: &HashMap<String, AgentProfile>) -> Result<()> {
+        if !self.is_configured() && self.moderator.is_none() {
+            return Ok(());
+        }
+        let count = self.participants.len();
+        if !(Self::MIN_PARTICIPANTS..=Self::MAX_PARTICIPANTS).contains(&count) {
+            return Err(anyhow::anyhow!(
+                "roundtable needs {} to {} participants, got {}",
+                Self::MIN_PARTICIPANTS,
+                Self::MAX_PARTICIPANTS,
+                count
+            ));
+        }
+        for name in self.participants.iter().chain(self.moderator.iter()) {
+            if !agents.contains_key(name) {
+                return Err(anyhow::anyhow!(
+                    "roundtable agent '{}' not found in agents map",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Execution settings for a single tool (`[tools.<name>]`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolSettings {
+    /// Cache successful results of identical calls
+    #[serde(default)]
+    pub memoize: bool,
+
+    /// Seconds a memoized result stays valid
+    #[serde(default = "default_memo_ttl_secs")]
+    pub memo_ttl_secs: u64,
+
+    /// Expected latency tier advertised to the model until enough calls
+    /// have been observed to measure it
+    #[serde(default)]
+    pub latency_tier: Option<ToolTier>,
+
+    /// Relative cost tier advertised to the model (API spend, rate limits)
+    #[serde(default)]
+    pub cost_tier: Option<ToolTier>,
+}
+
+/// Coarse low/medium/high rating used for tool latency and cost hints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolTier {
+    Low,
+    Medium,
+    High,
+}
+
+impl ToolTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolTier::Low => "low",
+            ToolTier::Medium => "medium",
+            ToolTier::High => "high",
+        }
+    }
+}
+
+/// A tool that runs other tools in sequence (`[composite_tools]`)
+///
+/// The short form lists tool names, e.g.
+/// `fetch_and_summarize = ["web_scraper", "summarize"]`: the first tool gets
+/// the composite's arguments and each later tool receives the previous
+/// output. The long form spells out the steps and their argument templates.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum CompositeToolConfig {
+    Pipeline(Vec<String>),
+    Definition(CompositeToolDefinition),
+}
+
+/// Long form of a composite tool
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompositeToolDefinition {
+    /// Description shown to the model (defaults to a summary of the steps)
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Arguments the composite accepts, mapped to their descriptions. When
+    /// empty and the first step has no template, the first tool's schema is
+    /// advertised instead.
+    #[serde(default)]
+    pub parameters: BTreeMap<String, String>,
+
+    /// Tool calls, run in order
+    pub steps: Vec<CompositeStep>,
+}
+
+/// One tool call inside a composite tool
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompositeStep {
+    /// Tool to call
+    pub tool: String,
+
+    /// Argument template. Strings may contain `{{name}}` (a composite
+    /// argument), `{{previous}}` (the prior step's output) or `{{steps.N}}`
+    /// (output of step N, counting from 0); a dotted suffix such as
+    /// `{{previous.title}}` reads a field of JSON output. When omitted the
+    /// first step gets the composite's arguments and later steps the
+    /// previous output.
+    #[serde(default)]
+    pub args: Option<serde_json::Value>,
+}
+
+impl CompositeToolConfig {
+    /// Steps in execution order
+    pub fn steps(&self) -> Vec<CompositeStep> {
+        match self {
+            CompositeToolConfig::Pipeline(tools) => tools
+                .iter()
+                .map(|tool| CompositeStep {
+                    tool: tool.clone(),
+                    args: None,
+                })
+                .collect(),
+            CompositeToolConfig::Definition(definition) => definition.steps.clone(),
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            CompositeToolConfig::Pipeline(_) => None,
+            CompositeToolConfig::Definition(definition) => definition.description.as_deref(),
+        }
+    }
+
+    /// Declared arguments; `None` for the short form
+    pub fn parameters(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            CompositeToolConfig::Pipeline(_) => None,
+            CompositeToolConfig::Definition(definition) => Some(&definition.parameters),
+        }
+    }
+}
+
+fn default_memo_ttl_secs() -> u64 {
+    300
+}
+
+impl Default for ToolSettings {
+    fn default() -> Self {
+        Self {
+            memoize: false,
+            memo_ttl_secs: default_memo_ttl_secs(),
+            latency_tier: None,
+            cost_tier: None,
+        }
+    }
+}
@@ -166,10 +166,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents: HashMap::new(),
             default_agent: None,
         }
@@ -4,9 +4,16 @@ pub mod cache;
 pub mod registry;
 
 // Re-export common types for convenience
-pub use agent::AgentProfile;
+pub use agent::{
+    AgentProfile, CompressionMode, ContextCompressionConfig, ContextProviderConfig,
+    MemoryCompactionConfig, ModelRouteConfig, PostProcessor, RecallScope, TaskClass,
+    ToolCallingMode,
+};
 pub use agent_config::{
-    AppConfig, AudioConfig, DatabaseConfig, LoggingConfig, MeshConfig, ModelConfig, PluginConfig,
-    UiConfig,
+    parse_utc_offset, AppConfig, AudioConfig, CompositeStep, CompositeToolConfig,
+    CompositeToolDefinition, DatabaseConfig, EventsConfig, GraphConfig, GraphTypeStyle,
+    LoggingConfig, MeshConfig, MeshRole, ModelConfig, PluginConfig, PrivacyConfig, QuotaConfig,
+    QuotaLimits, RoundtableConfig, SessionsConfig, SpecsConfig, SyncAclRule, SyncConfig,
+    TimeConfig, ToolSettings, ToolTier, UiConfig, UserProfileConfig,
 };
 pub use registry::AgentRegistry;
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod persistence;
+pub mod secret_key;
 pub mod sync;
 pub mod test_utils;
 pub mod types;
@@ -0,0 +1,132 @@
+//! Content-addressed blob storage
+//!
+//! Large payloads (scraped pages, file extracts, long tool output) are kept
+//! out of the `messages` and `tool_log` tables. Each payload is written once
+//! to `<root>/<first two hex digits>/<blake3 hash>`, and the row stores a
+//! short preview followed by a [`reference`] to the blob. The `blobs` table
+//! indexes what has been written so `db prune` can delete blobs no row
+//! refers to any more.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Payloads larger than this many bytes are moved to the blob store
+pub const DEFAULT_BLOB_THRESHOLD: usize = 64 * 1024;
+
+/// Characters of a payload kept inline ahead of its blob reference
+pub const PREVIEW_CHARS: usize = 2_000;
+
+const REFERENCE_PREFIX: &str = "[blob:";
+
+/// Blob files under one root directory
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Hex blake3 digest identifying `bytes`
+    pub fn hash(bytes: &[u8]) -> String {
+        blake3::hash(bytes).to_hex().to_string()
+    }
+
+    /// Where the blob with `hash` lives
+    pub fn path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2.min(hash.len())]).join(hash)
+    }
+
+    /// Write `bytes` unless an identical blob already exists, returning its
+    /// hash. Files are written to a temporary name and renamed into place so
+    /// readers never see a partial blob.
+    pub fn put(&self, bytes: &[u8]) -> Result<String> {
+        let hash = Self::hash(bytes);
+        let path = self.path(&hash);
+        if path.exists() {
+            return Ok(hash);
+        }
+        let dir = path.parent().unwrap_or(&self.root);
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating blob directory {}", dir.display()))?;
+        let mut file = tempfile::NamedTempFile::new_in(dir).context("creating blob file")?;
+        file.write_all(bytes).context("writing blob")?;
+        file.persist(&path)
+            .with_context(|| format!("storing blob {}", path.display()))?;
+        Ok(hash)
+    }
+
+    /// Contents of the blob, or `None` if it is not on disk
+    pub fn get(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(hash);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes =
+            std::fs::read(&path).with_context(|| format!("reading blob {}", path.display()))?;
+        Ok(Some(bytes))
+    }
+
+    /// Delete the blob file; missing files are not an error
+    pub fn remove(&self, hash: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(hash)) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("deleting blob {}", hash)),
+        }
+    }
+}
+
+/// Marker stored in place of a payload, e.g. `[blob:3f2a… 81234 bytes]`
+pub fn reference(hash: &str, size: usize) -> String {
+    format!("{}{} {} bytes]", REFERENCE_PREFIX, hash, size)
+}
+
+/// Preview of `text` followed by the blob reference
+pub fn preview_with_reference(text: &str, hash: &str) -> String {
+    let preview: String = text.chars().take(PREVIEW_CHARS).collect();
+    format!("{}\n…\n{}", preview.trim_end(), reference(hash, text.len()))
+}
+
+/// Hash of the blob referenced at the end of `text`, if any
+pub fn referenced_hash(text: &str) -> Option<&str> {
+    let start = text.rfind(REFERENCE_PREFIX)? + REFERENCE_PREFIX.len();
+    let rest = text[start..].strip_suffix(']')?;
+    let (hash, size) = rest.split_once(' ')?;
+    let valid = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+    (valid && size.ends_with(" bytes")).then_some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_blobs_by_content_and_references_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+
+        let hash = store.put(b"scraped page").unwrap();
+        assert_eq!(hash, BlobStore::hash(b"scraped page"));
+        assert!(store.path(&hash).starts_with(dir.path().join(&hash[..2])));
+        assert_eq!(store.put(b"scraped page").unwrap(), hash);
+        assert_eq!(store.get(&hash).unwrap().unwrap(), b"scraped page");
+
+        let text = "x".repeat(PREVIEW_CHARS + 10);
+        let stored = preview_with_reference(&text, &hash);
+        assert!(stored.len() < text.len() + 100);
+        assert_eq!(referenced_hash(&stored), Some(hash.as_str()));
+        assert_eq!(referenced_hash("see [blob:nothere]"), None);
+
+        store.remove(&hash).unwrap();
+        store.remove(&hash).unwrap();
+        assert!(store.get(&hash).unwrap().is_none());
+    }
+}
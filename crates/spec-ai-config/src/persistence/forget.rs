@@ -0,0 +1,125 @@
+//! Erasing what the user asked to be forgotten
+//!
+//! `/forget` takes a message ID or a piece of text. A message ID erases that
+//! message; text erases every message, graph node, tool call, model call,
+//! transcription, profile fact, `/compact` summary, memory summary and
+//! `/retry` answer containing it (ignoring case). Derived data goes too:
+//! embeddings of erased messages, edges of erased nodes, graph nodes recorded
+//! for an erased message, memory summaries covering it and other answers to
+//! it. Changelog snapshots of erased graph entities are dropped, and synced
+//! deletions leave a delete entry without content so peers erase their
+//! copies.
+
+use super::search;
+
+/// What to forget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgetTarget {
+    /// A single message, written as `42` or `#42`
+    Message(i64),
+    /// Everything containing this text, ignoring case
+    Text(String),
+}
+
+impl ForgetTarget {
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+        match query.trim_start_matches('#').parse::<i64>() {
+            Ok(id) => Some(Self::Message(id)),
+            Err(_) => Some(Self::Text(query.to_string())),
+        }
+    }
+
+    /// Lowercased text to look for, `None` for a message ID
+    pub(crate) fn needle(&self) -> Option<String> {
+        match self {
+            Self::Message(_) => None,
+            Self::Text(text) => Some(text.to_lowercase()),
+        }
+    }
+
+    /// Whether `text` contains the target text
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        self.needle()
+            .is_some_and(|needle| text.to_lowercase().contains(&needle))
+    }
+
+    /// Short excerpt of `text` around the target text
+    pub(crate) fn preview(&self, text: &str) -> String {
+        let terms: Vec<String> = self.needle().into_iter().collect();
+        search::snippet(text, &terms)
+    }
+}
+
+/// Something erased (or, in a dry run, that would be)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForgottenItem {
+    pub id: i64,
+    pub session_id: Option<String>,
+    pub preview: String,
+}
+
+/// What a `/forget` erased, or would erase in a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForgetReport {
+    pub dry_run: bool,
+    pub messages: Vec<ForgottenItem>,
+    pub graph_nodes: Vec<ForgottenItem>,
+    pub profile_facts: Vec<ForgottenItem>,
+    pub memory_vectors: usize,
+    pub graph_edges: usize,
+    pub tool_calls: usize,
+    pub model_calls: usize,
+    pub transcriptions: usize,
+    /// `/compact` summaries, whose sessions go back to their raw messages
+    pub context_anchors: usize,
+    /// Memory summaries with the text or covering an erased message
+    pub memory_summaries: usize,
+    /// `/retry` answers, whether picked or not
+    pub response_variants: usize,
+    /// Changelog entries dropped or stripped of their snapshot
+    pub changelog_entries: usize,
+    /// Graph deletions recorded for sync peers
+    pub synced_deletions: usize,
+}
+
+impl ForgetReport {
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+            && self.graph_nodes.is_empty()
+            && self.profile_facts.is_empty()
+            && self.memory_vectors == 0
+            && self.graph_edges == 0
+            && self.tool_calls == 0
+            && self.model_calls == 0
+            && self.transcriptions == 0
+            && self.context_anchors == 0
+            && self.memory_summaries == 0
+            && self.response_variants == 0
+            && self.changelog_entries == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(ForgetTarget::parse("#42"), Some(ForgetTarget::Message(42)));
+        assert_eq!(ForgetTarget::parse(" 7 "), Some(ForgetTarget::Message(7)));
+        assert_eq!(
+            ForgetTarget::parse("my address"),
+            Some(ForgetTarget::Text("my address".into()))
+        );
+        assert_eq!(ForgetTarget::parse("  "), None);
+
+        let target = ForgetTarget::parse("Elm Street").unwrap();
+        assert!(target.matches("I live on elm street 5"));
+        assert!(!target.matches("I live elsewhere"));
+        assert!(!ForgetTarget::Message(1).matches("anything"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Importance and decay scoring for memory vectors
+//!
+//! A memory's importance blends three signals, each normalized to `[0, 1]`:
+//! recency (exponential decay from the last access, or creation if never
+//! recalled), access frequency (how often it has been recalled), and graph
+//! centrality (degree of the graph nodes linked to the memory). Recall blends
+//! importance with similarity; pruning and consolidation pick the least
+//! important memories first.
+
+use chrono::{DateTime, Utc};
+
+/// Relative contribution of each importance signal
+const RECENCY_SHARE: f32 = 0.5;
+const FREQUENCY_SHARE: f32 = 0.3;
+const CENTRALITY_SHARE: f32 = 0.2;
+
+/// Degree at which centrality reaches half of its maximum contribution
+const CENTRALITY_HALF_DEGREE: f32 = 4.0;
+
+/// Per-profile knobs for importance scoring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryImportanceParams {
+    /// Weight of importance vs similarity in recall ranking (0.0 = similarity only)
+    pub weight: f32,
+    /// Hours after which the recency signal has halved
+    pub half_life_hours: f32,
+}
+
+impl Default for MemoryImportanceParams {
+    fn default() -> Self {
+        Self {
+            weight: 0.0,
+            half_life_hours: 168.0,
+        }
+    }
+}
+
+/// Recall bookkeeping for a single memory vector
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryAccessStats {
+    pub access_count: i64,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+/// Importance of a memory in `[0, 1]`
+pub fn importance_score(
+    created_at: DateTime<Utc>,
+    stats: &MemoryAccessStats,
+    graph_degree: usize,
+    params: &MemoryImportanceParams,
+    now: DateTime<Utc>,
+) -> f32 {
+    let anchor = stats.last_accessed_at.unwrap_or(created_at).max(created_at);
+    let age_hours = (now - anchor).num_seconds().max(0) as f32 / 3600.0;
+    let half_life = params.half_life_hours.max(f32::EPSILON);
+    let recency = 0.5f32.powf(age_hours / half_life);
+
+    let accesses = stats.access_count.max(0) as f32;
+    let frequency = accesses / (accesses + 1.0);
+
+    let degree = graph_degree as f32;
+    let centrality = degree / (degree + CENTRALITY_HALF_DEGREE);
+
+    (RECENCY_SHARE * recency + FREQUENCY_SHARE * frequency + CENTRALITY_SHARE * centrality)
+        .clamp(0.0, 1.0)
+}
+
+/// Blend similarity with importance according to `weight`
+pub fn blended_score(similarity: f32, importance: f32, weight: f32) -> f32 {
+    let weight = weight.clamp(0.0, 1.0);
+    (1.0 - weight) * similarity + weight * importance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn recency_decays_with_half_life() {
+        let now = Utc::now();
+        let params = MemoryImportanceParams {
+            weight: 1.0,
+            half_life_hours: 24.0,
+        };
+        let fresh = importance_score(now, &MemoryAccessStats::default(), 0, &params, now);
+        let day_old = importance_score(
+            now - Duration::hours(24),
+            &MemoryAccessStats::default(),
+            0,
+            &params,
+            now,
+        );
+        assert!((fresh - RECENCY_SHARE).abs() < 1e-4);
+        assert!((day_old - RECENCY_SHARE / 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn access_and_centrality_raise_importance() {
+        let now = Utc::now();
+        let created = now - Duration::days(30);
+        let params = MemoryImportanceParams::default();
+        let baseline = importance_score(created, &MemoryAccessStats::default(), 0, &params, now);
+        let recalled = importance_score(
+            created,
+            &MemoryAccessStats {
+                access_count: 5,
+                last_accessed_at: Some(now),
+            },
+            0,
+            &params,
+            now,
+        );
+        let central = importance_score(created, &MemoryAccessStats::default(), 8, &params, now);
+        assert!(recalled > baseline);
+        assert!(central > baseline);
+    }
+
+    #[test]
+    fn zero_weight_keeps_similarity() {
+        assert_eq!(blended_score(0.8, 0.1, 0.0), 0.8);
+        assert!((blended_score(0.8, 0.2, 0.5) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,46 @@
+//! Inspecting and editing recallable memories
+//!
+//! Every row of `memory_vectors` is something semantic recall can bring
+//! back into context. These types describe a memory together with the text
+//! it stands for (a message, a transcription chunk, a graph node or a
+//! summary of older messages) so users can see what is recallable, and what
+//! happened when they delete one. Deleting a memory removes only the
+//! embedding: the message, transcription, node or summary it was computed
+//! from stays in the session.
+
+use super::search::SemanticHitKind;
+use chrono::{DateTime, Utc};
+
+/// A recallable memory and what it was embedded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryEntry {
+    /// Row in `memory_vectors`
+    pub id: i64,
+    pub session_id: String,
+    /// What the embedding was computed from; `None` once that record is gone
+    pub kind: Option<SemanticHitKind>,
+    /// Row in `messages`, `transcriptions`, `graph_nodes` or
+    /// `memory_summaries`, depending on `kind`
+    pub source_id: Option<i64>,
+    /// Message role for message memories
+    pub role: Option<String>,
+    pub text: String,
+    /// Times the memory was recalled
+    pub access_count: i64,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Relevance to a search query; `None` when listing
+    pub score: Option<f32>,
+}
+
+/// Result of deleting a single memory
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryDeletion {
+    Deleted(MemoryEntry),
+    NotFound,
+    /// The embedding belongs to a graph node, which is deleted together
+    /// with its memory through the graph tools instead
+    LinkedToNode {
+        node_id: i64,
+    },
+}
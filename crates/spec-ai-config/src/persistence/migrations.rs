@@ -64,6 +64,162 @@ pub fn run(conn: &Connection) -> Result<()> {
         migrations_applied = true;
     }
 
+    if current < 9 {
+        apply_v9(conn)?;
+        set_version(conn, 9)?;
+        migrations_applied = true;
+    }
+
+    if current < 10 {
+        apply_v10(conn)?;
+        set_version(conn, 10)?;
+        migrations_applied = true;
+    }
+
+    if current < 11 {
+        apply_v11(conn)?;
+        set_version(conn, 11)?;
+        migrations_applied = true;
+    }
+
+    if current < 12 {
+        apply_v12(conn)?;
+        set_version(conn, 12)?;
+        migrations_applied = true;
+    }
+
+    if current < 13 {
+        apply_v13(conn)?;
+        set_version(conn, 13)?;
+        migrations_applied = true;
+    }
+
+    if current < 14 {
+        apply_v14(conn)?;
+        set_version(conn, 14)?;
+        migrations_applied = true;
+    }
+
+    if current < 15 {
+        apply_v15(conn)?;
+        set_version(conn, 15)?;
+        migrations_applied = true;
+    }
+
+    if current < 16 {
+        apply_v16(conn)?;
+        set_version(conn, 16)?;
+        migrations_applied = true;
+    }
+
+    if current < 17 {
+        apply_v17(conn)?;
+        set_version(conn, 17)?;
+        migrations_applied = true;
+    }
+
+    if current < 18 {
+        apply_v18(conn)?;
+        set_version(conn, 18)?;
+        migrations_applied = true;
+    }
+
+    if current < 19 {
+        apply_v19(conn)?;
+        set_version(conn, 19)?;
+        migrations_applied = true;
+    }
+
+    if current < 20 {
+        apply_v20(conn)?;
+        set_version(conn, 20)?;
+        migrations_applied = true;
+    }
+
+    if current < 21 {
+        apply_v21(conn)?;
+        set_version(conn, 21)?;
+        migrations_applied = true;
+    }
+
+    if current < 22 {
+        apply_v22(conn)?;
+        set_version(conn, 22)?;
+        migrations_applied = true;
+    }
+
+    if current < 23 {
+        apply_v23(conn)?;
+        set_version(conn, 23)?;
+        migrations_applied = true;
+    }
+
+    if current < 24 {
+        apply_v24(conn)?;
+        set_version(conn, 24)?;
+        migrations_applied = true;
+    }
+
+    if current < 25 {
+        apply_v25(conn)?;
+        set_version(conn, 25)?;
+        migrations_applied = true;
+    }
+
+    if current < 26 {
+        apply_v26(conn)?;
+        set_version(conn, 26)?;
+        migrations_applied = true;
+    }
+
+    if current < 27 {
+        apply_v27(conn)?;
+        set_version(conn, 27)?;
+        migrations_applied = true;
+    }
+
+    if current < 28 {
+        apply_v28(conn)?;
+        set_version(conn, 28)?;
+        migrations_applied = true;
+    }
+
+    if current < 29 {
+        apply_v29(conn)?;
+        set_version(conn, 29)?;
+        migrations_applied = true;
+    }
+
+    if current < 30 {
+        apply_v30(conn)?;
+        set_version(conn, 30)?;
+        migrations_applied = true;
+    }
+
+    if current < 31 {
+        apply_v31(conn)?;
+        set_version(conn, 31)?;
+        migrations_applied = true;
+    }
+
+    if current < 32 {
+        apply_v32(conn)?;
+        set_version(conn, 32)?;
+        migrations_applied = true;
+    }
+
+    if current < 33 {
+        apply_v33(conn)?;
+        set_version(conn, 33)?;
+        migrations_applied = true;
+    }
+
+    if current < 34 {
+        apply_v34(conn)?;
+        set_version(conn, 34)?;
+        migrations_applied = true;
+    }
+
     // Force checkpoint after migrations to ensure WAL is merged into the database file.
     // This prevents ALTER TABLE operations from being stuck in the WAL, which can cause
     // "no default database set" errors during WAL replay on subsequent startups.
@@ -446,3 +602,538 @@ fn apply_v8(conn: &Connection) -> Result<()> {
 
     Ok(())
 }
+
+fn apply_v9(conn: &Connection) -> Result<()> {
+    // Recall bookkeeping for memory importance/decay scoring.
+    // Kept in a side table because memory_vectors is referenced by foreign keys,
+    // which prevents DuckDB from altering it in place.
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS memory_access (
+            memory_id BIGINT PRIMARY KEY,
+            access_count BIGINT NOT NULL DEFAULT 0,
+            last_accessed_at TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v9 schema (memory access tracking)")
+}
+
+fn apply_v10(conn: &Connection) -> Result<()> {
+    // Round-table drafts: each participant's answer plus the moderator's synthesis
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS roundtable_drafts_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS roundtable_drafts (
+            id BIGINT PRIMARY KEY DEFAULT nextval('roundtable_drafts_id_seq'),
+            session_id TEXT NOT NULL,
+            roundtable_id TEXT NOT NULL,
+            agent_name TEXT NOT NULL,
+            is_synthesis BOOLEAN NOT NULL DEFAULT FALSE,
+            content TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_roundtable_drafts_session
+            ON roundtable_drafts(session_id, roundtable_id);
+        "#,
+    )
+    .context("applying v10 schema (roundtable drafts)")
+}
+
+fn apply_v11(conn: &Connection) -> Result<()> {
+    // Session metadata; message_count caches COUNT(*) over messages so
+    // session listings do not scan the whole table
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS sessions (
+            session_id TEXT PRIMARY KEY,
+            title TEXT,
+            agent TEXT,
+            tags TEXT NOT NULL DEFAULT '[]',  -- JSON array of strings
+            message_count BIGINT NOT NULL DEFAULT 0,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        INSERT INTO sessions (session_id, message_count, created_at, updated_at)
+        SELECT session_id, COUNT(*), MIN(created_at), MAX(created_at)
+        FROM messages
+        WHERE session_id NOT IN (SELECT session_id FROM sessions)
+        GROUP BY session_id;
+        "#,
+    )
+    .context("applying v11 schema (session metadata)")
+}
+
+fn apply_v12(conn: &Connection) -> Result<()> {
+    // Per-stage step timings; the stage `run_step.total` holds each step's
+    // wall-clock total, category is NULL for stages nested in another
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS step_timings_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS step_timings (
+            id BIGINT PRIMARY KEY DEFAULT nextval('step_timings_id_seq'),
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            agent TEXT,
+            stage TEXT NOT NULL,
+            category TEXT,
+            duration_ms DOUBLE NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_step_timings_session ON step_timings(session_id);
+        "#,
+    )
+    .context("applying v12 schema (step timings)")
+}
+
+fn apply_v13(conn: &Connection) -> Result<()> {
+    // Provider request/response capture (written only when logging.model_log
+    // is enabled); prompt and response are stored truncated, the digest and
+    // byte count describe the full prompt
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS model_log_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS model_log (
+            id BIGINT PRIMARY KEY DEFAULT nextval('model_log_id_seq'),
+            session_id TEXT NOT NULL,
+            run_id TEXT,
+            agent TEXT,
+            stage TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT,
+            prompt_digest TEXT NOT NULL,
+            prompt_bytes BIGINT NOT NULL,
+            prompt TEXT NOT NULL,
+            params TEXT NOT NULL,  -- JSON generation config
+            response TEXT,
+            error TEXT,
+            truncated BOOLEAN NOT NULL DEFAULT FALSE,
+            prompt_tokens BIGINT,
+            completion_tokens BIGINT,
+            latency_ms DOUBLE NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_model_log_run ON model_log(run_id);
+        "#,
+    )
+    .context("applying v13 schema (model log)")
+}
+
+fn apply_v14(conn: &Connection) -> Result<()> {
+    // Index of content-addressed blob files; messages and tool_log rows
+    // refer to blobs by hash, which is what garbage collection checks
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS blobs (
+            hash TEXT PRIMARY KEY,
+            size BIGINT NOT NULL,
+            media_type TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v14 schema (blob index)")
+}
+
+fn apply_v15(conn: &Connection) -> Result<()> {
+    // Per-instance stats reported in mesh heartbeats; the leader keeps a
+    // rolling window and prunes older samples as new ones arrive
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS mesh_stats_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS mesh_stats (
+            id BIGINT PRIMARY KEY DEFAULT nextval('mesh_stats_id_seq'),
+            instance_id TEXT NOT NULL,
+            active_sessions BIGINT NOT NULL,
+            tokens_used BIGINT NOT NULL,
+            tool_calls BIGINT NOT NULL,
+            sync_lag_secs BIGINT,
+            heartbeat_latency_ms DOUBLE,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_mesh_stats_instance ON mesh_stats(instance_id, created_at);
+        "#,
+    )
+    .context("applying v15 schema (mesh stats)")
+}
+
+fn apply_v16(conn: &Connection) -> Result<()> {
+    // Repository roots registered with `/workspace add`; at most one per
+    // session is focused, and file tools resolve relative paths against it
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS session_workspaces (
+            session_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            root TEXT NOT NULL,
+            focused BOOLEAN NOT NULL DEFAULT FALSE,
+            graph_node_id BIGINT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (session_id, name)
+        );
+        "#,
+    )
+    .context("applying v16 schema (session workspaces)")
+}
+
+fn apply_v17(conn: &Connection) -> Result<()> {
+    // Facts about the user shared by every session, and how far each
+    // session's messages have been mined for them
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS user_profile_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS user_profile (
+            id BIGINT PRIMARY KEY DEFAULT nextval('user_profile_id_seq'),
+            category TEXT NOT NULL,
+            fact TEXT NOT NULL,
+            source_session TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS user_profile_extractions (
+            session_id TEXT PRIMARY KEY,
+            last_message_id BIGINT NOT NULL,
+            extracted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v17 schema (user profile)")
+}
+
+fn apply_v18(conn: &Connection) -> Result<()> {
+    // Deletions of synced graph entities, written by delete_graph_node and
+    // delete_graph_edge alongside their changelog entries
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS graph_tombstones (
+            session_id TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id BIGINT NOT NULL,
+            deleted_by TEXT NOT NULL,
+            vector_clock TEXT NOT NULL,
+            deleted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_graph_tombstones_entity ON graph_tombstones(entity_type, entity_id);
+        "#,
+    )
+    .context("applying v18 schema (graph tombstones)")
+}
+
+fn apply_v19(conn: &Connection) -> Result<()> {
+    // Encrypted originals of the personal data replaced by tokens when
+    // privacy.redact_at_rest is on; the key is kept outside the database
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pii_vault (
+            token TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            nonce TEXT NOT NULL,
+            ciphertext TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v19 schema (PII vault)")
+}
+
+fn apply_v20(conn: &Connection) -> Result<()> {
+    // Offsets of each transcribed chunk in the recording and the speaker
+    // proposed for it, for subtitle and meeting-notes export
+    conn.execute_batch(
+        r#"
+        ALTER TABLE transcriptions ADD COLUMN start_ms BIGINT;
+        ALTER TABLE transcriptions ADD COLUMN end_ms BIGINT;
+        ALTER TABLE transcriptions ADD COLUMN speaker TEXT;
+        "#,
+    )
+    .context("applying v20 schema (transcription timing and speakers)")
+}
+
+fn apply_v21(conn: &Connection) -> Result<()> {
+    // The audio file a chunk was transcribed from; NULL for live capture
+    conn.execute_batch(
+        r#"
+        ALTER TABLE transcriptions ADD COLUMN source_file TEXT;
+        "#,
+    )
+    .context("applying v21 schema (transcription source files)")
+}
+
+fn apply_v22(conn: &Connection) -> Result<()> {
+    // Graph nodes pinned with `/graph pin`, whose neighborhoods are always
+    // recalled. A side table because graph_nodes is referenced by foreign
+    // keys and cannot be altered in place.
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS graph_pins (
+            node_id BIGINT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            pinned_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_graph_pins_session ON graph_pins(session_id);
+        "#,
+    )
+    .context("applying v22 schema (graph pins)")
+}
+
+fn apply_v23(conn: &Connection) -> Result<()> {
+    // Named graphs: nodes outside the "default" graph of their session are
+    // listed in graph_node_graphs (a side table, like graph_pins), and each
+    // session remembers the graph chosen with `/graph use`.
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS graph_node_graphs (
+            node_id BIGINT PRIMARY KEY,
+            graph_name TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_graph_node_graphs_name ON graph_node_graphs(graph_name);
+
+        CREATE TABLE IF NOT EXISTS graph_active (
+            session_id TEXT PRIMARY KEY,
+            graph_name TEXT NOT NULL
+        );
+        "#,
+    )
+    .context("applying v23 schema (named graphs)")
+}
+
+fn apply_v24(conn: &Connection) -> Result<()> {
+    // Outbox of graph changes and finished runs waiting to be published to
+    // the `[events]` sink; rows are deleted once delivered
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS event_outbox_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS event_outbox (
+            id BIGINT PRIMARY KEY DEFAULT nextval('event_outbox_id_seq'),
+            kind TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v24 schema (event outbox)")
+}
+
+fn apply_v25(conn: &Connection) -> Result<()> {
+    // Messages waiting for their embedding, so the background worker picks
+    // them up again after a restart; rows are deleted once the vector is
+    // stored. `node_id` is the message's graph node, linked to the vector.
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS embedding_queue (
+            message_id BIGINT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            node_id BIGINT,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            enqueued_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_embedding_queue_session ON embedding_queue(session_id);
+        "#,
+    )
+    .context("applying v25 schema (embedding queue)")
+}
+
+fn apply_v26(conn: &Connection) -> Result<()> {
+    // Summary written by `/compact`, standing in for a session's messages up
+    // to `through_message_id` in later prompts; the messages themselves stay
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS context_anchors (
+            session_id TEXT PRIMARY KEY,
+            through_message_id BIGINT NOT NULL,
+            summary TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .context("applying v26 schema (context anchors)")
+}
+
+fn apply_v27(conn: &Connection) -> Result<()> {
+    // Answers produced by `/retry`, next to the original, for the assistant
+    // message `message_id`; the canonical one is copied into that message
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS response_variants_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS response_variants (
+            id BIGINT PRIMARY KEY DEFAULT nextval('response_variants_id_seq'),
+            session_id TEXT NOT NULL,
+            user_message_id BIGINT NOT NULL,
+            message_id BIGINT NOT NULL,
+            content TEXT NOT NULL,
+            model TEXT,
+            temperature REAL,
+            canonical BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_response_variants_message ON response_variants(message_id);
+        "#,
+    )
+    .context("applying v27 schema (response variants)")
+}
+
+fn apply_v28(conn: &Connection) -> Result<()> {
+    // Running totals of tool calls per agent; no arguments or output, so
+    // nothing here needs forgetting
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_usage (
+            agent TEXT NOT NULL,
+            tool_name TEXT NOT NULL,
+            calls BIGINT NOT NULL DEFAULT 0,
+            successes BIGINT NOT NULL DEFAULT 0,
+            total_latency_ms BIGINT NOT NULL DEFAULT 0,
+            output_tokens BIGINT NOT NULL DEFAULT 0,
+            last_used_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (agent, tool_name)
+        );
+        "#,
+    )
+    .context("applying v28 schema (tool usage)")
+}
+
+fn apply_v29(conn: &Connection) -> Result<()> {
+    // Webhooks that run a spec, and the runs they started
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS webhook_triggers_id_seq START 1;
+        CREATE SEQUENCE IF NOT EXISTS trigger_jobs_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS webhook_triggers (
+            id BIGINT PRIMARY KEY DEFAULT nextval('webhook_triggers_id_seq'),
+            name TEXT NOT NULL UNIQUE,
+            secret TEXT NOT NULL,
+            spec_path TEXT NOT NULL,
+            agent TEXT,
+            variables TEXT NOT NULL DEFAULT '{}',
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS trigger_jobs (
+            id BIGINT PRIMARY KEY DEFAULT nextval('trigger_jobs_id_seq'),
+            trigger_id BIGINT NOT NULL,
+            session_id TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'queued',
+            run_id TEXT,
+            error TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            finished_at TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_trigger_jobs_trigger ON trigger_jobs(trigger_id);
+        "#,
+    )
+    .context("applying v29 schema (webhook triggers)")
+}
+
+fn apply_v30(conn: &Connection) -> Result<()> {
+    // When a session's messages were folded into its summary, NULL while
+    // they are still stored
+    conn.execute_batch(
+        r#"
+        ALTER TABLE sessions ADD COLUMN archived_at TIMESTAMP;
+        "#,
+    )
+    .context("applying v30 schema (session archival)")
+}
+
+fn apply_v31(conn: &Connection) -> Result<()> {
+    // Policy decisions on tool calls and the rule behind each, NULL for the
+    // default deny
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS policy_audit_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS policy_audit (
+            id BIGINT PRIMARY KEY DEFAULT nextval('policy_audit_id_seq'),
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            agent TEXT NOT NULL,
+            action TEXT NOT NULL,
+            resource TEXT NOT NULL,
+            allowed BOOLEAN NOT NULL,
+            rule TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_policy_audit_agent ON policy_audit(agent);
+        "#,
+    )
+    .context("applying v31 schema (policy audit)")
+}
+
+fn apply_v32(conn: &Connection) -> Result<()> {
+    // Follow-up messages to post into a session once due; delivered_at is
+    // NULL while pending
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS reminders_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS reminders (
+            id BIGINT PRIMARY KEY DEFAULT nextval('reminders_id_seq'),
+            session_id TEXT NOT NULL,
+            message TEXT NOT NULL,
+            due_at TIMESTAMP NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            delivered_at TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(due_at);
+        "#,
+    )
+    .context("applying v32 schema (reminders)")
+}
+
+fn apply_v33(conn: &Connection) -> Result<()> {
+    // Runs of old messages condensed into one recallable summary; the
+    // messages stay, their embeddings give way to the summary's
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS memory_summaries_id_seq START 1;
+
+        CREATE TABLE IF NOT EXISTS memory_summaries (
+            id BIGINT PRIMARY KEY DEFAULT nextval('memory_summaries_id_seq'),
+            session_id TEXT NOT NULL,
+            first_message_id BIGINT NOT NULL,
+            last_message_id BIGINT NOT NULL,
+            message_count BIGINT NOT NULL,
+            summary TEXT NOT NULL,
+            embedding_id BIGINT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_memory_summaries_session ON memory_summaries(session_id);
+        "#,
+    )
+    .context("applying v33 schema (memory summaries)")
+}
+
+fn apply_v34(conn: &Connection) -> Result<()> {
+    // Counts embedding updates, so recall indexes notice vectors replaced
+    // behind their back
+    conn.execute_batch(
+        r#"
+        ALTER TABLE memory_vectors ADD COLUMN revision BIGINT DEFAULT 0;
+        "#,
+    )
+    .context("applying v34 schema (memory vector revisions)")
+}
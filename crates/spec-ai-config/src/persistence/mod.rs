@@ -1,44 +1,176 @@
+pub mod blobs;
+pub mod forget;
+pub mod importance;
+pub mod memories;
 pub mod migrations;
-
-use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+pub mod outbox;
+pub mod policy_audit;
+pub mod privacy;
+pub mod reminders;
+pub mod search;
+pub mod tool_usage;
+pub mod transfer;
+pub mod triggers;
+pub mod variants;
+pub mod vector_index;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use directories::BaseDirs;
 use duckdb::{params, Connection};
 use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+use blobs::BlobStore;
+use forget::{ForgetReport, ForgetTarget, ForgottenItem};
+use importance::{MemoryAccessStats, MemoryImportanceParams};
+use memories::{MemoryDeletion, MemoryEntry};
+use outbox::OutboxEvent;
+use policy_audit::{PolicyAuditEntry, PolicyDecisionRecord};
+use privacy::{PiiVault, Redaction};
+use reminders::Reminder;
+use search::{SemanticHitKind, SemanticSearchFilter, SemanticSearchHit, SessionSearchHit};
+use tool_usage::ToolUsageStats;
+use transfer::{SessionCopyReport, SessionSnapshot};
+use triggers::{TriggerJob, TriggerJobStatus, WebhookTrigger};
+use variants::ResponseVariant;
+use vector_index::{HnswIndex, IndexCache};
+
+use crate::config::RecallScope;
 use crate::types::{
-    EdgeType, GraphEdge, GraphNode, GraphPath, MemoryVector, Message, MessageRole, NodeType,
-    PolicyEntry, TraversalDirection,
+    EdgeType, GraphEdge, GraphNeighbor, GraphNode, GraphPath, MemoryVector, Message, MessageRole,
+    NodeType, PolicyEntry, TraversalDirection,
 };
 
 #[derive(Clone)]
 pub struct Persistence {
     conn: Arc<Mutex<Connection>>,
     instance_id: String,
+    blobs: BlobStore,
+    /// Message and tool payloads over this many bytes go to the blob store
+    blob_threshold: usize,
+    in_memory: bool,
+    /// Set when personal data is tokenized before storage
+    vault: Option<Arc<PiiVault>>,
+    /// Set when changes are recorded in the event outbox
+    events: Option<Arc<crate::config::EventsConfig>>,
+    /// Recall index of each session recalled so far, built on first use
+    vector_indexes: Arc<Mutex<IndexCache>>,
 }
 
+/// Database path that selects an in-memory database
+pub const IN_MEMORY_PATH: &str = ":memory:";
+
+/// Graph that a session's nodes belong to unless placed in a named one
+pub const DEFAULT_GRAPH: &str = "default";
+
+/// Condition on `memory_vectors mv` for the vectors recall ranks. Vectors of
+/// graph nodes are found through `search_graph_nodes_semantic` instead.
+const RECALLED_VECTORS: &str = "(mv.message_id IS NOT NULL \
+     OR NOT EXISTS (SELECT 1 FROM graph_nodes g WHERE g.embedding_id = mv.id))";
+
+/// Candidates read per memory asked for when ranking by more than
+/// similarity, or by importance alone
+const RECALL_OVERSAMPLE: usize = 4;
+
 impl Persistence {
     /// Create or open the database at the provided path and run migrations.
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
         Self::with_instance_id(db_path, generate_instance_id())
     }
 
+    /// Create a database that lives only in memory: nothing is written to
+    /// disk, no file lock is taken, and everything is discarded when the last
+    /// clone is dropped. Same as opening the path `:memory:`.
+    pub fn in_memory() -> Result<Self> {
+        Self::with_instance_id(IN_MEMORY_PATH, generate_instance_id())
+    }
+
     /// Create with a specific instance_id
     pub fn with_instance_id<P: AsRef<Path>>(db_path: P, instance_id: String) -> Result<Self> {
+        if db_path.as_ref() == Path::new(IN_MEMORY_PATH) {
+            let conn = Connection::open_in_memory().context("opening in-memory DuckDB")?;
+            migrations::run(&conn).context("running migrations")?;
+            return Ok(Self {
+                conn: Arc::new(Mutex::new(conn)),
+                instance_id,
+                blobs: BlobStore::new(PathBuf::new()),
+                blob_threshold: blobs::DEFAULT_BLOB_THRESHOLD,
+                in_memory: true,
+                vault: None,
+                events: None,
+                vector_indexes: Arc::default(),
+            });
+        }
+
         let db_path = expand_tilde(db_path.as_ref())?;
         if let Some(dir) = db_path.parent() {
             std::fs::create_dir_all(dir).context("creating DB directory")?;
         }
         let conn = Connection::open(&db_path).context("opening DuckDB")?;
         migrations::run(&conn).context("running migrations")?;
+        let blob_root = db_path
+            .parent()
+            .map(|dir| dir.join("blobs"))
+            .unwrap_or_else(|| PathBuf::from("blobs"));
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             instance_id,
+            blobs: BlobStore::new(blob_root),
+            blob_threshold: blobs::DEFAULT_BLOB_THRESHOLD,
+            in_memory: false,
+            vault: None,
+            events: None,
+            vector_indexes: Arc::default(),
+        })
+    }
+
+    /// Whether this database is discarded on shutdown
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    /// Store blobs under `dir` instead of `blobs/` next to the database
+    pub fn with_blob_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.blobs = BlobStore::new(dir);
+        self
+    }
+
+    /// Size in bytes above which payloads are moved to the blob store
+    pub fn with_blob_threshold(mut self, bytes: usize) -> Self {
+        self.blob_threshold = bytes;
+        self
+    }
+
+    /// Tokenize emails, phone numbers and names with `vault` before storing
+    /// messages, graph nodes and profile facts
+    pub fn with_vault(mut self, vault: PiiVault) -> Self {
+        self.vault = Some(Arc::new(vault));
+        self
+    }
+
+    /// Attach a vault when `privacy.redact_at_rest` is on
+    pub fn with_privacy(self, config: &crate::config::PrivacyConfig) -> Result<Self> {
+        Ok(match PiiVault::from_config(config)? {
+            Some(vault) => self.with_vault(vault),
+            None => self,
         })
     }
 
+    /// Record graph changes and finished runs in the event outbox when an
+    /// `[events]` sink is configured
+    pub fn with_events(mut self, config: &crate::config::EventsConfig) -> Self {
+        self.events = config.is_enabled().then(|| Arc::new(config.clone()));
+        self
+    }
+
+    /// Whether personal data is tokenized before storage
+    pub fn redacts_at_rest(&self) -> bool {
+        self.vault.is_some()
+    }
+
     /// Get the instance ID for this persistence instance
     pub fn instance_id(&self) -> &str {
         &self.instance_id
@@ -75,6 +207,9 @@ impl Persistence {
         role: MessageRole,
         content: &str,
     ) -> Result<i64> {
+        let content = self.redact(content)?;
+        let offloaded = self.offload(&content, "text/plain")?;
+        let content = offloaded.as_deref().unwrap_or(content.as_str());
         let conn = self.conn();
         let mut stmt = conn.prepare(
             "INSERT INTO messages (session_id, role, content) VALUES (?, ?, ?) RETURNING id",
@@ -82,6 +217,16 @@ impl Persistence {
         let id: i64 = stmt.query_row(params![session_id, role.as_str(), content], |row| {
             row.get(0)
         })?;
+        let updated = conn.execute(
+            "UPDATE sessions SET message_count = message_count + 1, updated_at = CURRENT_TIMESTAMP, archived_at = NULL WHERE session_id = ?",
+            params![session_id],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO sessions (session_id, message_count) VALUES (?, 1)",
+                params![session_id],
+            )?;
+        }
         Ok(id)
     }
 
@@ -137,6 +282,10 @@ impl Persistence {
         let conn = self.conn();
         let mut stmt = conn.prepare("DELETE FROM messages WHERE session_id = ? AND id NOT IN (SELECT id FROM messages WHERE session_id = ? ORDER BY id DESC LIMIT ?)")?;
         let changed = stmt.execute(params![session_id, session_id, keep_latest])? as u64;
+        conn.execute(
+            "UPDATE sessions SET message_count = (SELECT COUNT(*) FROM messages WHERE session_id = ?) WHERE session_id = ?",
+            params![session_id, session_id],
+        )?;
         Ok(changed)
     }
 
@@ -148,837 +297,3199 @@ impl Persistence {
         message_id: Option<i64>,
         embedding: &[f32],
     ) -> Result<i64> {
-        let conn = self.conn();
         let embedding_json = serde_json::to_string(embedding)?;
-        let mut stmt = conn.prepare("INSERT INTO memory_vectors (session_id, message_id, embedding) VALUES (?, ?, ?) RETURNING id")?;
-        let id: i64 = stmt.query_row(params![session_id, message_id, embedding_json], |row| {
-            row.get(0)
-        })?;
+        let (id, created_at): (i64, String) = self.conn().query_row(
+            "INSERT INTO memory_vectors (session_id, message_id, embedding) VALUES (?, ?, ?) \
+             RETURNING id, CAST(created_at AS TEXT)",
+            params![session_id, message_id, embedding_json],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if let Some(index) = self.vector_indexes().get_mut(session_id) {
+            index.insert(MemoryVector {
+                id,
+                session_id: session_id.to_string(),
+                message_id,
+                embedding: embedding.to_vec(),
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
         Ok(id)
     }
 
+    /// The `k` memories of a session most similar to `query_embedding`,
+    /// with their cosine similarity, best first. Ranked with the session's
+    /// [`HnswIndex`], which is first brought up to date with the table.
     pub fn recall_top_k(
         &self,
         session_id: &str,
         query_embedding: &[f32],
         k: usize,
     ) -> Result<Vec<(MemoryVector, f32)>> {
+        let mut indexes = self.vector_indexes();
+        let index = indexes.get_or_insert(session_id);
+        self.sync_vector_index(session_id, index)?;
+        Ok(index.search(query_embedding, k))
+    }
+
+    fn vector_indexes(&self) -> std::sync::MutexGuard<'_, IndexCache> {
+        self.vector_indexes
+            .lock()
+            .expect("vector index mutex poisoned")
+    }
+
+    /// Make `index` hold the session's recalled vectors. Vectors added since
+    /// it was last synced are inserted; any other change made behind its
+    /// back, such as a deletion or a new embedding stored by another
+    /// process, rebuilds it.
+    fn sync_vector_index(&self, session_id: &str, index: &mut HnswIndex) -> Result<()> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT id, session_id, message_id, embedding, CAST(created_at AS TEXT) as created_at FROM memory_vectors WHERE session_id = ?")?;
-        let mut rows = stmt.query(params![session_id])?;
-        let mut scored: Vec<(MemoryVector, f32)> = Vec::new();
+        let (count, max_id, revision): (i64, i64, i64) = conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(MAX(id), 0), CAST(COALESCE(SUM(revision), 0) AS BIGINT) \
+                 FROM memory_vectors mv WHERE session_id = ? AND {}",
+                RECALLED_VECTORS
+            ),
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let stored = (count as usize, max_id);
+        if index.fingerprint() == stored && index.revision() == revision {
+            return Ok(());
+        }
+        if index.revision() == revision {
+            let (_, indexed_max_id) = index.fingerprint();
+            for memory in Self::recalled_vectors(&conn, session_id, indexed_max_id)? {
+                index.insert(memory);
+            }
+        }
+        if index.fingerprint() != stored
+            || index.revision() != revision
+            || index.removed_count() > index.len()
+        {
+            let mut rebuilt = HnswIndex::new();
+            for memory in Self::recalled_vectors(&conn, session_id, 0)? {
+                rebuilt.insert(memory);
+            }
+            rebuilt.set_revision(revision);
+            *index = rebuilt;
+        }
+        Ok(())
+    }
+
+    /// Recalled vectors of a session with ids above `after_id`, oldest first
+    fn recalled_vectors(
+        conn: &Connection,
+        session_id: &str,
+        after_id: i64,
+    ) -> Result<Vec<MemoryVector>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, message_id, embedding, CAST(created_at AS TEXT) as created_at \
+             FROM memory_vectors mv WHERE session_id = ? AND id > ? AND {} ORDER BY id",
+            RECALLED_VECTORS
+        ))?;
+        let rows = stmt.query(params![session_id, after_id])?;
+        Self::collect_memory_vectors(rows)
+    }
+
+    /// Up to `limit` recalled vectors of a session, those created or last
+    /// recalled longest ago first
+    fn least_recently_used_memories(
+        &self,
+        session_id: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryVector>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT mv.id, mv.session_id, mv.message_id, mv.embedding, CAST(mv.created_at AS TEXT) \
+             FROM memory_vectors mv LEFT JOIN memory_access a ON a.memory_id = mv.id \
+             WHERE mv.session_id = ? AND {} \
+             ORDER BY GREATEST(mv.created_at, COALESCE(a.last_accessed_at, mv.created_at)), \
+                 COALESCE(a.access_count, 0), mv.id \
+             LIMIT ?",
+            RECALLED_VECTORS
+        ))?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = stmt.query(params![session_id, limit])?;
+        Self::collect_memory_vectors(rows)
+    }
+
+    /// Read `id, session_id, message_id, embedding, created_at` rows
+    fn collect_memory_vectors(mut rows: duckdb::Rows) -> Result<Vec<MemoryVector>> {
+        let mut memories = Vec::new();
         while let Some(row) = rows.next()? {
-            let id: i64 = row.get(0)?;
-            let sid: String = row.get(1)?;
-            let message_id: Option<i64> = row.get(2)?;
-            let embedding_text: String = row.get(3)?;
             let created_at: String = row.get(4)?;
-            let created_at: DateTime<Utc> = created_at.parse().unwrap_or_else(|_| Utc::now());
-            let embedding: Vec<f32> = serde_json::from_str(&embedding_text).unwrap_or_default();
-            let score = cosine_similarity(query_embedding, &embedding);
-            scored.push((
-                MemoryVector {
-                    id,
-                    session_id: sid,
-                    message_id,
-                    embedding,
-                    created_at,
-                },
-                score,
-            ));
+            let embedding_text: String = row.get(3)?;
+            memories.push(MemoryVector {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                message_id: row.get(2)?,
+                embedding: serde_json::from_str(&embedding_text).unwrap_or_default(),
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
+        Ok(memories)
+    }
+
+    /// Take a memory out of the recall indexes
+    fn unindex_memory(&self, memory_id: i64) {
+        for index in self.vector_indexes().values_mut() {
+            index.remove(memory_id);
+        }
+    }
+
+    /// Recall the top-k memories ranked by similarity blended with importance.
+    ///
+    /// With `params.weight == 0.0` this is equivalent to [`Self::recall_top_k`].
+    pub fn recall_top_k_weighted(
+        &self,
+        session_id: &str,
+        query_embedding: &[f32],
+        k: usize,
+        params: &MemoryImportanceParams,
+    ) -> Result<Vec<(MemoryVector, f32)>> {
+        if params.weight <= 0.0 {
+            return self.recall_top_k(session_id, query_embedding, k);
+        }
+
+        let candidates = k.saturating_mul(RECALL_OVERSAMPLE);
+        let mut scored = self.recall_top_k(session_id, query_embedding, candidates)?;
+        let stats = self.memory_access_stats(session_id)?;
+        let degrees = self.memory_graph_degrees(session_id)?;
+        let now = Utc::now();
+        for (memory, score) in scored.iter_mut() {
+            let importance = importance::importance_score(
+                memory.created_at,
+                stats
+                    .get(&memory.id)
+                    .unwrap_or(&MemoryAccessStats::default()),
+                degrees.get(&memory.id).copied().unwrap_or(0),
+                params,
+                now,
+            );
+            *score = importance::blended_score(*score, importance, params.weight);
         }
         scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
         scored.truncate(k);
         Ok(scored)
     }
 
-    /// List known session IDs ordered by most recent activity
-    pub fn list_sessions(&self) -> Result<Vec<String>> {
+    /// [`Self::recall_top_k_weighted`] over every session in `scope`: the
+    /// sessions run by `agent` (those recorded without an agent when it is
+    /// `None`) or all of them. Only the most recently recalled sessions keep
+    /// their index in memory, see [`IndexCache`].
+    pub fn recall_top_k_in_scope(
+        &self,
+        session_id: &str,
+        scope: RecallScope,
+        agent: Option<&str>,
+        query_embedding: &[f32],
+        k: usize,
+        params: &MemoryImportanceParams,
+    ) -> Result<Vec<(MemoryVector, f32)>> {
+        let mut sessions = vec![session_id.to_string()];
+        let others = {
+            let conn = self.conn();
+            match scope {
+                RecallScope::Session => Vec::new(),
+                RecallScope::Agent => {
+                    let mut stmt = conn.prepare(
+                        "SELECT DISTINCT mv.session_id FROM memory_vectors mv
+                         LEFT JOIN sessions s ON s.session_id = mv.session_id
+                         WHERE s.agent IS NOT DISTINCT FROM ? ORDER BY mv.session_id",
+                    )?;
+                    let rows = stmt.query_map(params![agent], |row| row.get::<_, String>(0))?;
+                    rows.collect::<Result<Vec<_>, _>>()?
+                }
+                RecallScope::Global => {
+                    let mut stmt = conn.prepare(
+                        "SELECT DISTINCT session_id FROM memory_vectors ORDER BY session_id",
+                    )?;
+                    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                    rows.collect::<Result<Vec<_>, _>>()?
+                }
+            }
+        };
+        sessions.extend(others.into_iter().filter(|other| other != session_id));
+
+        let mut recalled = Vec::new();
+        for session in &sessions {
+            recalled.extend(self.recall_top_k_weighted(session, query_embedding, k, params)?);
+        }
+        recalled.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        recalled.truncate(k);
+        Ok(recalled)
+    }
+
+    /// Id of the vector stored for a message, if any
+    pub fn message_embedding_id(&self, message_id: i64) -> Result<Option<i64>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT id FROM memory_vectors WHERE message_id = ? ORDER BY id LIMIT 1")?;
+        let mut rows = stmt.query(params![message_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    // ---------- Embedding Queue ----------
+
+    /// Queue a message to be embedded in the background. `node_id` is the
+    /// message's graph node, linked to the vector once it is stored.
+    pub fn embedding_queue_push(
+        &self,
+        session_id: &str,
+        message_id: i64,
+        node_id: Option<i64>,
+    ) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO embedding_queue (message_id, session_id, node_id) VALUES (?, ?, ?)
+             ON CONFLICT DO NOTHING",
+            params![message_id, session_id, node_id],
+        )?;
+        Ok(())
+    }
+
+    /// Oldest queued messages of a session that failed fewer than
+    /// `max_attempts` times
+    pub fn embedding_queue_pending(
+        &self,
+        session_id: &str,
+        max_attempts: i64,
+        limit: usize,
+    ) -> Result<Vec<QueuedEmbedding>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT session_id, MAX(created_at) as last FROM messages GROUP BY session_id ORDER BY last DESC"
+            "SELECT message_id, session_id, node_id, attempts FROM embedding_queue
+             WHERE session_id = ? AND attempts < ? ORDER BY message_id ASC LIMIT ?",
         )?;
-        let mut rows = stmt.query([])?;
+        let mut rows = stmt.query(params![session_id, max_attempts, limit as i64])?;
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
-            let sid: String = row.get(0)?;
-            out.push(sid);
+            out.push(QueuedEmbedding {
+                message_id: row.get(0)?,
+                session_id: row.get(1)?,
+                node_id: row.get(2)?,
+                attempts: row.get(3)?,
+            });
         }
         Ok(out)
     }
 
-    // ---------- Tool Log ----------
+    /// Take a message off the queue once its vector is stored
+    pub fn embedding_queue_complete(&self, message_id: i64) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM embedding_queue WHERE message_id = ?",
+            params![message_id],
+        )?;
+        Ok(())
+    }
 
-    pub fn log_tool(
-        &self,
-        session_id: &str,
-        agent_name: &str,
-        run_id: &str,
-        tool_name: &str,
-        arguments: &JsonValue,
-        result: &JsonValue,
-        success: bool,
-        error: Option<&str>,
-    ) -> Result<i64> {
+    /// Count a failed attempt to embed a queued message
+    pub fn embedding_queue_failed(&self, message_id: i64) -> Result<()> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("INSERT INTO tool_log (session_id, agent, run_id, tool_name, arguments, result, success, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id")?;
-        let id: i64 = stmt.query_row(
-            params![
-                session_id,
-                agent_name,
-                run_id,
-                tool_name,
-                arguments.to_string(),
-                result.to_string(),
-                success,
-                error.unwrap_or("")
-            ],
-            |row| row.get(0),
+        conn.execute(
+            "UPDATE embedding_queue SET attempts = attempts + 1 WHERE message_id = ?",
+            params![message_id],
         )?;
-        Ok(id)
+        Ok(())
     }
 
-    // ---------- Policy Cache ----------
+    /// Most recent messages of a session still waiting for their embedding,
+    /// oldest first; semantic recall cannot find them yet
+    pub fn unembedded_messages(&self, session_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.session_id, m.role, m.content, CAST(m.created_at AS TEXT)
+             FROM embedding_queue q JOIN messages m ON m.id = q.message_id
+             WHERE q.session_id = ? ORDER BY m.id DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![session_id, limit as i64])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let role: String = row.get(2)?;
+            let created_at: String = row.get(4)?;
+            out.push(Message {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: MessageRole::from_str(&role),
+                content: row.get(3)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
+        out.reverse();
+        Ok(out)
+    }
 
-    pub fn policy_upsert(&self, key: &str, value: &JsonValue) -> Result<()> {
+    /// Counts and latest ids of everything recall reads for a session. Two
+    /// equal revisions mean no message, vector, graph node, edge or pin of
+    /// the session was added, changed or removed in between.
+    pub fn session_revision(&self, session_id: &str) -> Result<SessionRevision> {
+        let conn = self.conn();
+        let revision = conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM messages WHERE session_id = ?),
+                (SELECT COALESCE(MAX(id), 0) FROM messages WHERE session_id = ?),
+                (SELECT COUNT(*) FROM memory_vectors WHERE session_id = ?),
+                (SELECT COALESCE(MAX(id), 0) FROM memory_vectors WHERE session_id = ?),
+                (SELECT COUNT(*) FROM graph_nodes WHERE session_id = ?),
+                (SELECT CAST(MAX(updated_at) AS TEXT) FROM graph_nodes WHERE session_id = ?),
+                (SELECT COUNT(*) FROM graph_edges WHERE session_id = ?),
+                (SELECT COALESCE(MAX(id), 0) FROM graph_edges WHERE session_id = ?),
+                (SELECT COUNT(*) FROM graph_pins WHERE session_id = ?)",
+            duckdb::params_from_iter(std::iter::repeat_n(session_id, 9)),
+            |row| {
+                Ok(SessionRevision {
+                    counts: [
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(6)?,
+                        row.get(7)?,
+                        row.get(8)?,
+                    ],
+                    nodes_updated_at: row.get(5)?,
+                })
+            },
+        )?;
+        Ok(revision)
+    }
+
+    /// Increment the access counter and refresh the last-access time of recalled memories.
+    pub fn record_memory_access(&self, memory_ids: &[i64]) -> Result<()> {
+        if memory_ids.is_empty() {
+            return Ok(());
+        }
         let conn = self.conn();
-        // DuckDB upsert workaround: delete then insert atomically within a transaction.
         conn.execute_batch("BEGIN TRANSACTION;")?;
         {
-            let mut del = conn.prepare("DELETE FROM policy_cache WHERE key = ?")?;
-            let _ = del.execute(params![key])?;
-            let mut ins = conn.prepare("INSERT INTO policy_cache (key, value, updated_at) VALUES (?, ?, CURRENT_TIMESTAMP)")?;
-            let _ = ins.execute(params![key, value.to_string()])?;
+            let mut update = conn.prepare(
+                "UPDATE memory_access SET access_count = access_count + 1, last_accessed_at = CURRENT_TIMESTAMP WHERE memory_id = ?",
+            )?;
+            let mut insert = conn.prepare(
+                "INSERT INTO memory_access (memory_id, access_count, last_accessed_at) VALUES (?, 1, CURRENT_TIMESTAMP)",
+            )?;
+            for &id in memory_ids {
+                if update.execute(params![id])? == 0 {
+                    insert.execute(params![id])?;
+                }
+            }
         }
         conn.execute_batch("COMMIT;")?;
         Ok(())
     }
 
-    pub fn policy_get(&self, key: &str) -> Result<Option<PolicyEntry>> {
+    /// Access statistics for all memories in a session, keyed by memory id.
+    pub fn memory_access_stats(&self, session_id: &str) -> Result<HashMap<i64, MemoryAccessStats>> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT key, value, CAST(updated_at AS TEXT) as updated_at FROM policy_cache WHERE key = ?")?;
-        let mut rows = stmt.query(params![key])?;
-        if let Some(row) = rows.next()? {
-            let key: String = row.get(0)?;
-            let value_text: String = row.get(1)?;
-            let updated_at: String = row.get(2)?;
-            let updated_at: DateTime<Utc> = updated_at.parse().unwrap_or_else(|_| Utc::now());
-            let value: JsonValue = serde_json::from_str(&value_text).unwrap_or(JsonValue::Null);
-            Ok(Some(PolicyEntry {
-                key,
-                value,
-                updated_at,
-            }))
-        } else {
-            Ok(None)
+        let mut stmt = conn.prepare(
+            "SELECT a.memory_id, a.access_count, CAST(a.last_accessed_at AS TEXT)
+             FROM memory_access a JOIN memory_vectors m ON m.id = a.memory_id
+             WHERE m.session_id = ?",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut out = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let access_count: i64 = row.get(1)?;
+            let last_accessed_at: Option<String> = row.get(2)?;
+            out.insert(
+                id,
+                MemoryAccessStats {
+                    access_count,
+                    last_accessed_at: last_accessed_at.as_deref().and_then(parse_timestamp),
+                },
+            );
         }
+        Ok(out)
     }
-}
 
-fn generate_instance_id() -> String {
-    let hostname = hostname::get()
-        .ok()
-        .and_then(|h| h.into_string().ok())
-        .unwrap_or_else(|| "unknown".to_string());
-    let uuid = uuid::Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext));
-    format!("{}-{}", hostname, uuid)
-}
+    /// Graph degree of the nodes linked to each memory, keyed by memory id.
+    ///
+    /// A memory is linked to a node either through the node's `embedding_id` or,
+    /// for message nodes, through the `message_id` property.
+    pub fn memory_graph_degrees(&self, session_id: &str) -> Result<HashMap<i64, usize>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT n.id, n.node_type, n.properties, n.embedding_id, COUNT(e.id)
+             FROM graph_nodes n
+             LEFT JOIN graph_edges e ON e.source_id = n.id OR e.target_id = n.id
+             WHERE n.session_id = ?
+             GROUP BY n.id, n.node_type, n.properties, n.embedding_id",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut by_embedding: HashMap<i64, usize> = HashMap::new();
+        let mut by_message: HashMap<i64, usize> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let node_type: String = row.get(1)?;
+            let properties: String = row.get(2)?;
+            let embedding_id: Option<i64> = row.get(3)?;
+            let degree: i64 = row.get(4)?;
+            let degree = degree.max(0) as usize;
+            if let Some(embedding_id) = embedding_id {
+                let entry = by_embedding.entry(embedding_id).or_default();
+                *entry = (*entry).max(degree);
+            }
+            if NodeType::from_str(&node_type) == NodeType::Message {
+                if let Some(message_id) = serde_json::from_str::<JsonValue>(&properties)
+                    .ok()
+                    .and_then(|p| p["message_id"].as_i64())
+                {
+                    let entry = by_message.entry(message_id).or_default();
+                    *entry = (*entry).max(degree);
+                }
+            }
+        }
 
-fn expand_tilde(path: &Path) -> Result<PathBuf> {
-    let path_str = path.to_string_lossy();
-    if path_str == "~" {
-        let base = BaseDirs::new().context("base directories not available")?;
-        Ok(base.home_dir().to_path_buf())
-    } else if let Some(stripped) = path_str.strip_prefix("~/") {
-        let base = BaseDirs::new().context("base directories not available")?;
-        Ok(base.home_dir().join(stripped))
-    } else {
-        Ok(path.to_path_buf())
+        let mut stmt =
+            conn.prepare("SELECT id, message_id FROM memory_vectors WHERE session_id = ?")?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut out = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let message_id: Option<i64> = row.get(1)?;
+            let via_embedding = by_embedding.get(&id).copied().unwrap_or(0);
+            let via_message = message_id
+                .and_then(|m| by_message.get(&m).copied())
+                .unwrap_or(0);
+            let degree = via_embedding.max(via_message);
+            if degree > 0 {
+                out.insert(id, degree);
+            }
+        }
+        Ok(out)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
-
-    #[test]
-    fn expands_home_directory_prefix() {
-        let base = BaseDirs::new().expect("home directory available");
-        let expected = base.home_dir().join("demo.db");
-        let result = expand_tilde(Path::new("~/demo.db")).expect("path expansion succeeds");
-        assert_eq!(result, expected);
+    /// Least important memories of a session, lowest first, as candidates for
+    /// consolidation or pruning. Recency weighs most in importance, so only
+    /// the memories longest without use are scored, several times `limit`.
+    pub fn memory_prune_candidates(
+        &self,
+        session_id: &str,
+        limit: usize,
+        params: &MemoryImportanceParams,
+    ) -> Result<Vec<(MemoryVector, f32)>> {
+        let mut memories: Vec<(MemoryVector, f32)> = self
+            .least_recently_used_memories(session_id, limit.saturating_mul(RECALL_OVERSAMPLE))?
+            .into_iter()
+            .map(|memory| (memory, 0.0))
+            .collect();
+        let stats = self.memory_access_stats(session_id)?;
+        let degrees = self.memory_graph_degrees(session_id)?;
+        let now = Utc::now();
+        for (memory, score) in memories.iter_mut() {
+            *score = importance::importance_score(
+                memory.created_at,
+                stats
+                    .get(&memory.id)
+                    .unwrap_or(&MemoryAccessStats::default()),
+                degrees.get(&memory.id).copied().unwrap_or(0),
+                params,
+                now,
+            );
+        }
+        memories.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        memories.truncate(limit);
+        Ok(memories)
     }
 
-    #[test]
-    fn leaves_regular_paths_unchanged() {
-        let input = Path::new("relative/path.db");
-        let result = expand_tilde(input).expect("path expansion succeeds");
-        assert_eq!(result, input);
+    /// Recallable memories of a session with the text each one stands for,
+    /// newest first
+    pub fn list_memories(&self, session_id: &str) -> Result<Vec<MemoryEntry>> {
+        Ok(self
+            .memory_rows(Some(session_id), None)?
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect())
     }
-}
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.is_empty() || b.is_empty() || a.len() != b.len() {
-        return 0.0;
-    }
-    let mut dot = 0.0f32;
-    let mut na = 0.0f32;
-    let mut nb = 0.0f32;
-    for i in 0..a.len() {
-        dot += a[i] * b[i];
-        na += a[i] * a[i];
-        nb += b[i] * b[i];
-    }
-    if na == 0.0 || nb == 0.0 {
-        return 0.0;
+    /// Memories of a session ranked by similarity to `query_embedding`, or
+    /// by how well their text matches `query` when there is no embedding
+    pub fn search_memories(
+        &self,
+        session_id: &str,
+        query: &str,
+        query_embedding: Option<&[f32]>,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>> {
+        let terms = search::query_terms(query);
+        let mut hits: Vec<MemoryEntry> = self
+            .memory_rows(Some(session_id), None)?
+            .into_iter()
+            .filter_map(|(mut entry, embedding)| {
+                let score = match query_embedding {
+                    Some(query_embedding) => cosine_similarity(query_embedding, &embedding),
+                    None => search::score_text(&entry.text, &terms),
+                };
+                if query_embedding.is_none() && score <= 0.0 {
+                    return None;
+                }
+                entry.score = Some(score);
+                Some(entry)
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        hits.truncate(limit);
+        Ok(hits)
     }
-    dot / (na.sqrt() * nb.sqrt())
-}
 
-// ========== Knowledge Graph Methods ==========
+    pub fn get_memory(&self, memory_id: i64) -> Result<Option<MemoryEntry>> {
+        Ok(self
+            .memory_rows(None, Some(memory_id))?
+            .into_iter()
+            .next()
+            .map(|(entry, _)| entry))
+    }
 
-impl Persistence {
-    // ---------- Graph Node Operations ----------
+    /// Delete one memory so recall no longer returns it. The message or
+    /// transcription it was embedded from stays; memories of graph nodes
+    /// are left to node deletion.
+    pub fn delete_memory(&self, memory_id: i64) -> Result<MemoryDeletion> {
+        let Some(entry) = self.get_memory(memory_id)? else {
+            return Ok(MemoryDeletion::NotFound);
+        };
+        let conn = self.conn();
+        {
+            let mut stmt =
+                conn.prepare("SELECT id FROM graph_nodes WHERE embedding_id = ? LIMIT 1")?;
+            let mut rows = stmt.query(params![memory_id])?;
+            if let Some(row) = rows.next()? {
+                return Ok(MemoryDeletion::LinkedToNode {
+                    node_id: row.get(0)?,
+                });
+            }
+        }
+        conn.execute(
+            "UPDATE transcriptions SET embedding_id = NULL WHERE embedding_id = ?",
+            params![memory_id],
+        )?;
+        conn.execute(
+            "UPDATE tokenized_files SET embedding_id = NULL WHERE embedding_id = ?",
+            params![memory_id],
+        )?;
+        conn.execute(
+            "UPDATE memory_summaries SET embedding_id = NULL WHERE embedding_id = ?",
+            params![memory_id],
+        )?;
+        conn.execute(
+            "DELETE FROM memory_access WHERE memory_id = ?",
+            params![memory_id],
+        )?;
+        conn.execute(
+            "DELETE FROM memory_vectors WHERE id = ?",
+            params![memory_id],
+        )?;
+        drop(conn);
+        self.unindex_memory(memory_id);
+        Ok(MemoryDeletion::Deleted(entry))
+    }
 
-    pub fn insert_graph_node(
+    /// Memories with their source text and embedding, newest first
+    fn memory_rows(
         &self,
-        session_id: &str,
-        node_type: NodeType,
-        label: &str,
-        properties: &JsonValue,
-        embedding_id: Option<i64>,
-    ) -> Result<i64> {
-        use crate::sync::VectorClock;
+        session_id: Option<&str>,
+        memory_id: Option<i64>,
+    ) -> Result<Vec<(MemoryEntry, Vec<f32>)>> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<duckdb::types::Value> = Vec::new();
+        if let Some(session_id) = session_id {
+            clauses.push("mv.session_id = ?");
+            values.push(session_id.to_string().into());
+        }
+        if let Some(memory_id) = memory_id {
+            clauses.push("mv.id = ?");
+            values.push(memory_id.into());
+        }
+        let filter_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
 
-        // Check if sync is enabled BEFORE locking the connection to avoid deadlock
-        let sync_enabled = self
-            .graph_get_sync_enabled(session_id, "default")
-            .unwrap_or(false);
+        let rows: Vec<(MemoryEntry, String)> = {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT mv.id, mv.session_id, mv.embedding, CAST(mv.created_at AS TEXT), \
+                 m.id, m.role, m.content, t.id, t.text, g.id, g.label, CAST(g.properties AS TEXT), \
+                 COALESCE(a.access_count, 0), CAST(a.last_accessed_at AS TEXT), ms.id, ms.summary \
+                 FROM memory_vectors mv \
+                 LEFT JOIN messages m ON m.id = mv.message_id \
+                 LEFT JOIN transcriptions t ON t.embedding_id = mv.id \
+                 LEFT JOIN graph_nodes g ON g.embedding_id = mv.id \
+                 LEFT JOIN memory_access a ON a.memory_id = mv.id \
+                 LEFT JOIN memory_summaries ms ON ms.embedding_id = mv.id \
+                 {} ORDER BY mv.id DESC",
+                filter_sql
+            ))?;
+            let mut rows = stmt.query(duckdb::params_from_iter(values))?;
+            let mut out = Vec::new();
+            while let Some(row) = rows.next()? {
+                let message_id: Option<i64> = row.get(4)?;
+                let transcription_id: Option<i64> = row.get(7)?;
+                let node_id: Option<i64> = row.get(9)?;
+                let summary_id: Option<i64> = row.get(14)?;
+                let (kind, source_id, text) = match (message_id, transcription_id, node_id) {
+                    (Some(id), _, _) => (
+                        Some(SemanticHitKind::Message),
+                        Some(id),
+                        row.get::<_, String>(6)?,
+                    ),
+                    (None, Some(id), _) => (
+                        Some(SemanticHitKind::Transcription),
+                        Some(id),
+                        row.get::<_, String>(8)?,
+                    ),
+                    (None, None, Some(id)) => {
+                        let label: String = row.get(10)?;
+                        let properties: Option<String> = row.get(11)?;
+                        let text = match properties {
+                            Some(properties) => format!("{} {}", label, properties),
+                            None => label,
+                        };
+                        (Some(SemanticHitKind::GraphNode), Some(id), text)
+                    }
+                    (None, None, None) => match summary_id {
+                        Some(id) => (
+                            Some(SemanticHitKind::Summary),
+                            Some(id),
+                            row.get::<_, String>(15)?,
+                        ),
+                        None => (None, None, String::new()),
+                    },
+                };
+                let created_at: String = row.get(3)?;
+                let last_accessed_at: Option<String> = row.get(13)?;
+                out.push((
+                    MemoryEntry {
+                        id: row.get(0)?,
+                        session_id: row.get(1)?,
+                        kind,
+                        source_id,
+                        role: row.get(5)?,
+                        text,
+                        access_count: row.get(12)?,
+                        last_accessed_at: last_accessed_at.as_deref().and_then(parse_timestamp),
+                        created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+                        score: None,
+                    },
+                    row.get::<_, String>(2)?,
+                ));
+            }
+            out
+        };
 
-        // Create initial vector clock for this node
-        let mut vector_clock = VectorClock::new();
-        vector_clock.increment(&self.instance_id);
-        let vc_json = vector_clock.to_json()?;
+        rows.into_iter()
+            .map(|(mut entry, embedding_text)| {
+                // Long messages are stored as blob references
+                if entry.kind == Some(SemanticHitKind::Message) {
+                    entry.text = self.resolve_blob(&entry.text)?;
+                }
+                let embedding: Vec<f32> = serde_json::from_str(&embedding_text).unwrap_or_default();
+                Ok((entry, embedding))
+            })
+            .collect()
+    }
 
+    /// List known session IDs ordered by most recent activity
+    pub fn list_sessions(&self) -> Result<Vec<String>> {
         let conn = self.conn();
-
-        // Insert the node with sync metadata
         let mut stmt = conn.prepare(
-            "INSERT INTO graph_nodes (session_id, node_type, label, properties, embedding_id,
-                                     vector_clock, last_modified_by, sync_enabled)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
-        )?;
-        let id: i64 = stmt.query_row(
-            params![
-                session_id,
-                node_type.as_str(),
-                label,
-                properties.to_string(),
-                embedding_id,
-                vc_json,
-                self.instance_id,
-                sync_enabled,
-            ],
-            |row| row.get(0),
+            "SELECT session_id, MAX(created_at) as last FROM messages GROUP BY session_id ORDER BY last DESC"
         )?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let sid: String = row.get(0)?;
+            out.push(sid);
+        }
+        Ok(out)
+    }
 
-        // If sync is enabled, append to changelog
-        if sync_enabled {
-            let node_data = serde_json::json!({
-                "id": id,
-                "session_id": session_id,
-                "node_type": node_type.as_str(),
-                "label": label,
-                "properties": properties,
-                "embedding_id": embedding_id,
-            });
+    /// Metadata of one session, if it has any messages or metadata
+    pub fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("{} WHERE session_id = ?", SESSION_RECORD_SELECT))?;
+        let mut rows = stmt.query(params![session_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(SessionRecord::from_row(row)?)),
+            None => Ok(None),
+        }
+    }
 
-            self.graph_changelog_append(
-                session_id,
-                &self.instance_id,
-                "node",
-                id,
-                "create",
-                &vc_json,
-                Some(&node_data.to_string()),
-            )?;
+    /// Metadata of all sessions ordered by most recent activity
+    pub fn list_session_records(&self) -> Result<Vec<SessionRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "{} ORDER BY updated_at DESC",
+            SESSION_RECORD_SELECT
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(SessionRecord::from_row(row)?);
         }
+        Ok(out)
+    }
 
-        Ok(id)
+    pub fn set_session_title(&self, session_id: &str, title: &str) -> Result<()> {
+        self.update_session_column(session_id, "title", title)
     }
 
-    pub fn get_graph_node(&self, node_id: i64) -> Result<Option<GraphNode>> {
-        let conn = self.conn();
-        let mut stmt = conn.prepare(
-            "SELECT id, session_id, node_type, label, properties, embedding_id,
-                    CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
-             FROM graph_nodes WHERE id = ?",
-        )?;
-        let mut rows = stmt.query(params![node_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Self::row_to_graph_node(row)?))
-        } else {
-            Ok(None)
+    pub fn set_session_agent(&self, session_id: &str, agent: &str) -> Result<()> {
+        self.update_session_column(session_id, "agent", agent)
+    }
+
+    /// Add a tag to a session, returning its tags afterwards. Tags are
+    /// trimmed and lowercased; adding an existing tag is a no-op.
+    pub fn add_session_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>> {
+        let tag = tag.trim().to_lowercase();
+        let mut tags = self
+            .get_session(session_id)?
+            .map(|session| session.tags)
+            .unwrap_or_default();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+            tags.sort();
+            self.update_session_column(session_id, "tags", &serde_json::to_string(&tags)?)?;
         }
+        Ok(tags)
     }
 
-    pub fn list_graph_nodes(
+    /// Remove a tag from a session, returning its remaining tags
+    pub fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>> {
+        let tag = tag.trim().to_lowercase();
+        let Some(session) = self.get_session(session_id)? else {
+            return Ok(Vec::new());
+        };
+        let tags: Vec<String> = session.tags.into_iter().filter(|t| *t != tag).collect();
+        self.update_session_column(session_id, "tags", &serde_json::to_string(&tags)?)?;
+        Ok(tags)
+    }
+
+    /// Archive a session: `summary` becomes its context anchor, covering
+    /// every stored message, and the messages are deleted with their
+    /// embeddings and answer variants. The graph stays; nodes recorded for
+    /// a message lose its embedding. Without a summary, the anchor the
+    /// session already has is kept.
+    pub fn archive_session(
         &self,
         session_id: &str,
-        node_type: Option<NodeType>,
-        limit: Option<i64>,
-    ) -> Result<Vec<GraphNode>> {
+        summary: Option<&str>,
+    ) -> Result<SessionArchive> {
+        let mut last_message_id = None;
+        let mut blob_hashes: Vec<String> = Vec::new();
+        {
+            let conn = self.conn();
+            let mut stmt =
+                conn.prepare("SELECT id, content FROM messages WHERE session_id = ? ORDER BY id")?;
+            let mut rows = stmt.query(params![session_id])?;
+            while let Some(row) = rows.next()? {
+                last_message_id = Some(row.get::<_, i64>(0)?);
+                let content: String = row.get(1)?;
+                blob_hashes.extend(blobs::referenced_hash(&content).map(str::to_string));
+            }
+        }
+        if let (Some(summary), Some(through_message_id)) = (summary, last_message_id) {
+            self.set_context_anchor(session_id, through_message_id, summary)?;
+        }
+
         let conn = self.conn();
+        let message_vectors = "SELECT id FROM memory_vectors WHERE message_id IN (SELECT id FROM messages WHERE session_id = ?)";
+        conn.execute(
+            &format!(
+                "UPDATE graph_nodes SET embedding_id = NULL WHERE embedding_id IN ({})",
+                message_vectors
+            ),
+            params![session_id],
+        )?;
+        conn.execute(
+            &format!(
+                "DELETE FROM memory_access WHERE memory_id IN ({})",
+                message_vectors
+            ),
+            params![session_id],
+        )?;
+        let memory_vectors = conn.execute(
+            "DELETE FROM memory_vectors WHERE message_id IN (SELECT id FROM messages WHERE session_id = ?)",
+            params![session_id],
+        )?;
+        conn.execute(
+            "DELETE FROM embedding_queue WHERE session_id = ?",
+            params![session_id],
+        )?;
+        conn.execute(
+            "DELETE FROM response_variants WHERE session_id = ?",
+            params![session_id],
+        )?;
+        let messages = conn.execute(
+            "DELETE FROM messages WHERE session_id = ?",
+            params![session_id],
+        )?;
+        conn.execute(
+            "UPDATE sessions SET message_count = 0, archived_at = CURRENT_TIMESTAMP WHERE session_id = ?",
+            params![session_id],
+        )?;
+        drop(conn);
+        self.vector_indexes().remove(session_id);
 
-        let nodes = if let Some(nt) = node_type {
+        self.release_blobs(blob_hashes)?;
+        Ok(SessionArchive {
+            messages,
+            memory_vectors,
+        })
+    }
+
+    /// Mark an archived session as in use again, returning whether it was
+    /// archived. The conversation continues from its summary.
+    pub fn thaw_session(&self, session_id: &str) -> Result<bool> {
+        let thawed = self.conn().execute(
+            "UPDATE sessions SET archived_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE session_id = ? AND archived_at IS NOT NULL",
+            params![session_id],
+        )?;
+        Ok(thawed > 0)
+    }
+
+    /// Delete a session with everything recorded for it: messages,
+    /// memories, graph, transcriptions, logs and metadata. Deletions of
+    /// synced graph entities are still recorded for peers. Returns whether
+    /// the session existed.
+    pub fn delete_session(&self, session_id: &str) -> Result<bool> {
+        let (node_ids, edge_ids) = {
+            let conn = self.conn();
+            let mut stmt = conn.prepare("SELECT id FROM graph_nodes WHERE session_id = ?")?;
+            let node_ids = stmt
+                .query_map(params![session_id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
             let mut stmt = conn.prepare(
-                "SELECT id, session_id, node_type, label, properties, embedding_id,
-                        CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
-                 FROM graph_nodes WHERE session_id = ? AND node_type = ?
-                 ORDER BY id DESC LIMIT ?",
+                "SELECT id FROM graph_edges WHERE session_id = ?
+                    OR source_id IN (SELECT id FROM graph_nodes WHERE session_id = ?)
+                    OR target_id IN (SELECT id FROM graph_nodes WHERE session_id = ?)",
             )?;
-            let query = stmt.query(params![session_id, nt.as_str(), limit.unwrap_or(100)])?;
-            Self::collect_graph_nodes(query)?
-        } else {
+            let edge_ids = stmt
+                .query_map(params![session_id, session_id, session_id], |row| {
+                    row.get::<_, i64>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            (node_ids, edge_ids)
+        };
+        for id in edge_ids {
+            self.delete_graph_edge(id)?;
+        }
+        for id in node_ids {
+            self.delete_graph_node(id)?;
+        }
+
+        let mut blob_hashes: Vec<String> = Vec::new();
+        {
+            let conn = self.conn();
             let mut stmt = conn.prepare(
-                "SELECT id, session_id, node_type, label, properties, embedding_id,
-                        CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
-                 FROM graph_nodes WHERE session_id = ?
-                 ORDER BY id DESC LIMIT ?",
+                "SELECT content FROM messages WHERE session_id = ?
+                 UNION ALL SELECT CAST(result AS TEXT) FROM tool_log WHERE session_id = ?",
             )?;
-            let query = stmt.query(params![session_id, limit.unwrap_or(100)])?;
-            Self::collect_graph_nodes(query)?
-        };
+            let mut rows = stmt.query(params![session_id, session_id])?;
+            while let Some(row) = rows.next()? {
+                let stored: String = row.get(0)?;
+                blob_hashes.extend(blobs::referenced_hash(&stored).map(str::to_string));
+            }
+        }
 
-        Ok(nodes)
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM memory_access WHERE memory_id IN (SELECT id FROM memory_vectors WHERE session_id = ?)",
+            params![session_id],
+        )?;
+        let mut existed = false;
+        // Tables referring to others come before the tables they refer to
+        for table in [
+            "tokenized_files",
+            "transcriptions",
+            "memory_vectors",
+            "embedding_queue",
+            "response_variants",
+            "messages",
+            "context_anchors",
+            "memory_summaries",
+            "roundtable_drafts",
+            "step_timings",
+            "model_log",
+            "tool_log",
+            "session_workspaces",
+            "user_profile_extractions",
+            "graph_active",
+            "graph_metadata",
+            "trigger_jobs",
+            "policy_audit",
+            "reminders",
+            "sessions",
+        ] {
+            let deleted = conn.execute(
+                &format!("DELETE FROM {} WHERE session_id = ?", table),
+                params![session_id],
+            )?;
+            existed |= deleted > 0;
+        }
+        drop(conn);
+        self.vector_indexes().remove(session_id);
+
+        self.release_blobs(blob_hashes)?;
+        Ok(existed)
     }
 
-    pub fn count_graph_nodes(&self, session_id: &str) -> Result<i64> {
+    /// The summary standing in for the session's older messages, if the
+    /// session was compacted
+    pub fn context_anchor(&self, session_id: &str) -> Result<Option<ContextAnchor>> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT COUNT(*) FROM graph_nodes WHERE session_id = ?")?;
-        let count: i64 = stmt.query_row(params![session_id], |row| row.get(0))?;
-        Ok(count)
+        let mut stmt = conn.prepare(
+            "SELECT through_message_id, summary, CAST(created_at AS TEXT) FROM context_anchors WHERE session_id = ?",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let created_at: String = row.get(2)?;
+        Ok(Some(ContextAnchor {
+            through_message_id: row.get(0)?,
+            summary: row.get(1)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        }))
     }
 
-    pub fn update_graph_node(&self, node_id: i64, properties: &JsonValue) -> Result<()> {
-        use crate::sync::VectorClock;
-
+    /// Replace the session's context anchor with `summary`, covering every
+    /// message up to and including `through_message_id`
+    pub fn set_context_anchor(
+        &self,
+        session_id: &str,
+        through_message_id: i64,
+        summary: &str,
+    ) -> Result<()> {
         let conn = self.conn();
+        conn.execute(
+            "DELETE FROM context_anchors WHERE session_id = ?",
+            params![session_id],
+        )?;
+        conn.execute(
+            "INSERT INTO context_anchors (session_id, through_message_id, summary) VALUES (?, ?, ?)",
+            params![session_id, through_message_id, summary],
+        )?;
+        Ok(())
+    }
 
-        // First get the current node data and vector clock
-        let mut stmt = conn.prepare(
-            "SELECT session_id, node_type, label, vector_clock, sync_enabled
-             FROM graph_nodes WHERE id = ?",
+    // ---------- Memory Summaries ----------
+
+    /// Last message of a session covered by a memory summary; 0 when none is
+    pub fn summarized_through(&self, session_id: &str) -> Result<i64> {
+        let conn = self.conn();
+        let through = conn.query_row(
+            "SELECT COALESCE(MAX(last_message_id), 0) FROM memory_summaries WHERE session_id = ?",
+            params![session_id],
+            |row| row.get(0),
         )?;
+        Ok(through)
+    }
 
-        let (session_id, node_type, label, current_vc_json, sync_enabled): (
-            String,
-            String,
-            String,
-            Option<String>,
-            bool,
-        ) = stmt.query_row(params![node_id], |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get(4).unwrap_or(false),
-            ))
-        })?;
-
-        // Update the vector clock
-        let mut vector_clock = if let Some(vc_json) = current_vc_json {
-            VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
-        } else {
-            VectorClock::new()
-        };
-        vector_clock.increment(&self.instance_id);
-        let vc_json = vector_clock.to_json()?;
-
-        // Update the node with new properties and sync metadata
-        conn.execute(
-            "UPDATE graph_nodes
-             SET properties = ?,
-                 vector_clock = ?,
-                 last_modified_by = ?,
-                 updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?",
-            params![properties.to_string(), vc_json, self.instance_id, node_id],
+    /// Oldest messages no memory summary covers yet, at most `limit`,
+    /// leaving out the newest `keep_recent` messages of the session and
+    /// everything from `before` on
+    pub fn messages_to_summarize(
+        &self,
+        session_id: &str,
+        keep_recent: usize,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Message>> {
+        let through = self.summarized_through(session_id)?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, role, content, CAST(created_at AS TEXT) FROM messages
+             WHERE session_id = ? AND id > ? AND (? IS NULL OR id < ?)
+               AND id NOT IN (SELECT id FROM messages WHERE session_id = ? ORDER BY id DESC LIMIT ?)
+             ORDER BY id LIMIT ?",
         )?;
-
-        // If sync is enabled, append to changelog
-        if sync_enabled {
-            let node_data = serde_json::json!({
-                "id": node_id,
-                "session_id": session_id,
-                "node_type": node_type,
-                "label": label,
-                "properties": properties,
+        let mut rows = stmt.query(params![
+            session_id,
+            through,
+            before,
+            before,
+            session_id,
+            keep_recent as i64,
+            limit as i64
+        ])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let role: String = row.get(2)?;
+            let created_at: String = row.get(4)?;
+            out.push(Message {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: MessageRole::from_str(&role),
+                content: row.get(3)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
             });
-
-            self.graph_changelog_append(
-                &session_id,
-                &self.instance_id,
-                "node",
-                node_id,
-                "update",
-                &vc_json,
-                Some(&node_data.to_string()),
-            )?;
         }
-
-        Ok(())
+        Ok(out)
     }
 
-    pub fn delete_graph_node(&self, node_id: i64) -> Result<()> {
-        use crate::sync::VectorClock;
+    /// Store `summary` in place of `messages` (consecutive messages of
+    /// `session_id`, oldest first) for recall: the summary gets `embedding`
+    /// as its memory and the messages' embeddings are deleted. The messages
+    /// themselves stay. Returns `None` when a summary stored meanwhile
+    /// already covers them.
+    pub fn store_memory_summary(
+        &self,
+        session_id: &str,
+        messages: &[Message],
+        summary: &str,
+        embedding: Option<&[f32]>,
+    ) -> Result<Option<MemorySummary>> {
+        let (Some(first), Some(last)) = (messages.first(), messages.last()) else {
+            return Ok(None);
+        };
+        let summary = self.redact(summary.trim())?;
 
         let conn = self.conn();
-
-        // First get the node data before deletion
-        let mut stmt = conn.prepare(
-            "SELECT session_id, node_type, label, properties, vector_clock, sync_enabled
-             FROM graph_nodes WHERE id = ?",
+        let through: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(last_message_id), 0) FROM memory_summaries WHERE session_id = ?",
+            params![session_id],
+            |row| row.get(0),
         )?;
-
-        let result = stmt.query_row(params![node_id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, Option<String>>(4)?,
-                row.get::<_, bool>(5).unwrap_or(false),
-            ))
-        });
-
-        // If node exists, handle sync tracking
-        if let Ok((session_id, node_type, label, properties, current_vc_json, sync_enabled)) =
-            result
+        if first.id <= through {
+            return Ok(None);
+        }
+        let covered =
+            "SELECT id FROM memory_vectors WHERE session_id = ? AND message_id BETWEEN ? AND ?";
+        let mut replaced: Vec<i64> = Vec::new();
         {
-            if sync_enabled {
-                // Update the vector clock for the deletion
-                let mut vector_clock = if let Some(vc_json) = current_vc_json {
-                    VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
-                } else {
-                    VectorClock::new()
-                };
-                vector_clock.increment(&self.instance_id);
-                let vc_json = vector_clock.to_json()?;
+            let mut stmt = conn.prepare(covered)?;
+            let mut rows = stmt.query(params![session_id, first.id, last.id])?;
+            while let Some(row) = rows.next()? {
+                replaced.push(row.get(0)?);
+            }
+        }
+        conn.execute(
+            &format!(
+                "UPDATE graph_nodes SET embedding_id = NULL WHERE embedding_id IN ({})",
+                covered
+            ),
+            params![session_id, first.id, last.id],
+        )?;
+        conn.execute(
+            &format!("DELETE FROM memory_access WHERE memory_id IN ({})", covered),
+            params![session_id, first.id, last.id],
+        )?;
+        conn.execute(
+            "DELETE FROM memory_vectors WHERE session_id = ? AND message_id BETWEEN ? AND ?",
+            params![session_id, first.id, last.id],
+        )?;
+        let (id, created_at): (i64, String) = conn.query_row(
+            "INSERT INTO memory_summaries (session_id, first_message_id, last_message_id, message_count, summary) \
+             VALUES (?, ?, ?, ?, ?) RETURNING id, CAST(created_at AS TEXT)",
+            params![
+                session_id,
+                first.id,
+                last.id,
+                messages.len() as i64,
+                summary
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        drop(conn);
+        for memory_id in replaced {
+            self.unindex_memory(memory_id);
+        }
 
-                // Create a tombstone entry for sync
-                conn.execute(
-                    "INSERT INTO graph_tombstones
-                     (session_id, entity_type, entity_id, deleted_by, vector_clock)
-                     VALUES (?, ?, ?, ?, ?)",
-                    params![session_id, "node", node_id, self.instance_id, vc_json],
+        let embedding_id = match embedding {
+            Some(embedding) => {
+                let embedding_id = self.insert_memory_vector(session_id, None, embedding)?;
+                self.conn().execute(
+                    "UPDATE memory_summaries SET embedding_id = ? WHERE id = ?",
+                    params![embedding_id, id],
                 )?;
+                Some(embedding_id)
+            }
+            None => None,
+        };
+        Ok(Some(MemorySummary {
+            id,
+            session_id: session_id.to_string(),
+            first_message_id: first.id,
+            last_message_id: last.id,
+            message_count: messages.len() as i64,
+            summary,
+            embedding_id,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        }))
+    }
 
-                // Append deletion to changelog
-                let node_data = serde_json::json!({
-                    "id": node_id,
-                    "session_id": session_id,
-                    "node_type": node_type,
-                    "label": label,
-                    "properties": properties,
-                });
+    /// Memory summaries of a session, oldest first
+    pub fn list_memory_summaries(&self, session_id: &str) -> Result<Vec<MemorySummary>> {
+        self.memory_summaries_where("session_id = ?", params![session_id])
+    }
 
-                self.graph_changelog_append(
-                    &session_id,
-                    &self.instance_id,
-                    "node",
-                    node_id,
-                    "delete",
-                    &vc_json,
-                    Some(&node_data.to_string()),
-                )?;
-            }
+    /// The memory summary whose embedding is `embedding_id`, if any
+    pub fn memory_summary_by_embedding(&self, embedding_id: i64) -> Result<Option<MemorySummary>> {
+        Ok(self
+            .memory_summaries_where("embedding_id = ?", params![embedding_id])?
+            .pop())
+    }
+
+    fn memory_summaries_where(
+        &self,
+        condition: &str,
+        params: &[&dyn duckdb::ToSql],
+    ) -> Result<Vec<MemorySummary>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, first_message_id, last_message_id, message_count, summary, embedding_id, CAST(created_at AS TEXT)
+             FROM memory_summaries WHERE {} ORDER BY first_message_id",
+            condition
+        ))?;
+        let mut rows = stmt.query(params)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(7)?;
+            out.push(MemorySummary {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                first_message_id: row.get(2)?,
+                last_message_id: row.get(3)?,
+                message_count: row.get(4)?,
+                summary: row.get(5)?,
+                embedding_id: row.get(6)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
         }
+        Ok(out)
+    }
 
-        // Now delete the node
-        conn.execute("DELETE FROM graph_nodes WHERE id = ?", params![node_id])?;
+    /// Replace the content of a stored message, as when another answer
+    /// variant is picked
+    pub fn set_message_content(&self, message_id: i64, content: &str) -> Result<()> {
+        let content = self.redact(content)?;
+        let offloaded = self.offload(&content, "text/plain")?;
+        let content = offloaded.as_deref().unwrap_or(content.as_str());
+        self.conn().execute(
+            "UPDATE messages SET content = ? WHERE id = ?",
+            params![content, message_id],
+        )?;
         Ok(())
     }
 
-    // ---------- Graph Edge Operations ----------
+    /// Replace the embedding of a message's memories, keeping their IDs and
+    /// links; returns how many memories the message had
+    pub fn update_message_embedding(&self, message_id: i64, embedding: &[f32]) -> Result<usize> {
+        let embedding_json = serde_json::to_string(embedding)?;
+        let updated = self.conn().execute(
+            "UPDATE memory_vectors SET embedding = ?, revision = revision + 1 WHERE message_id = ?",
+            params![embedding_json, message_id],
+        )?;
+        for index in self.vector_indexes().values_mut() {
+            let memories = index.memories_of_message(message_id);
+            index.set_revision(index.revision() + memories.len() as i64);
+            for memory in memories {
+                index.remove(memory.id);
+                index.insert(MemoryVector {
+                    embedding: embedding.to_vec(),
+                    ..memory
+                });
+            }
+        }
+        Ok(updated)
+    }
 
-    pub fn insert_graph_edge(
+    /// Record an answer to `user_message_id` as a variant of the assistant
+    /// message `message_id`, not canonical until picked
+    pub fn add_response_variant(
         &self,
         session_id: &str,
-        source_id: i64,
-        target_id: i64,
-        edge_type: EdgeType,
-        predicate: Option<&str>,
-        properties: Option<&JsonValue>,
-        weight: f32,
+        user_message_id: i64,
+        message_id: i64,
+        content: &str,
+        model: Option<&str>,
+        temperature: Option<f32>,
     ) -> Result<i64> {
-        use crate::sync::VectorClock;
-
-        // Check if sync is enabled BEFORE locking the connection to avoid deadlock
-        let sync_enabled = self
-            .graph_get_sync_enabled(session_id, "default")
-            .unwrap_or(false);
-
-        // Create initial vector clock for this edge
-        let mut vector_clock = VectorClock::new();
-        vector_clock.increment(&self.instance_id);
-        let vc_json = vector_clock.to_json()?;
-
+        let content = self.redact(content)?;
         let conn = self.conn();
-
-        // Insert the edge with sync metadata
         let mut stmt = conn.prepare(
-            "INSERT INTO graph_edges (session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                                     vector_clock, last_modified_by, sync_enabled)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+            "INSERT INTO response_variants (session_id, user_message_id, message_id, content, model, temperature)
+             VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
         )?;
-        let props_str = properties.map(|p| p.to_string());
         let id: i64 = stmt.query_row(
             params![
                 session_id,
-                source_id,
-                target_id,
-                edge_type.as_str(),
-                predicate,
-                props_str,
-                weight,
-                vc_json,
-                self.instance_id,
-                sync_enabled,
+                user_message_id,
+                message_id,
+                content,
+                model,
+                temperature
             ],
             |row| row.get(0),
         )?;
-
-        // If sync is enabled, append to changelog
-        if sync_enabled {
-            let edge_data = serde_json::json!({
-                "id": id,
-                "session_id": session_id,
-                "source_id": source_id,
-                "target_id": target_id,
-                "edge_type": edge_type.as_str(),
-                "predicate": predicate,
-                "properties": properties,
-                "weight": weight,
-            });
-
-            self.graph_changelog_append(
-                session_id,
-                &self.instance_id,
-                "edge",
-                id,
-                "insert",
-                &vc_json,
-                Some(&edge_data.to_string()),
-            )?;
-        }
-
         Ok(id)
     }
 
-    pub fn get_graph_edge(&self, edge_id: i64) -> Result<Option<GraphEdge>> {
+    /// Variants of an assistant message, oldest first
+    pub fn list_response_variants(&self, message_id: i64) -> Result<Vec<ResponseVariant>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                    CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
-             FROM graph_edges WHERE id = ?",
+            "SELECT id, session_id, user_message_id, message_id, content, model, temperature, canonical, CAST(created_at AS TEXT)
+             FROM response_variants WHERE message_id = ? ORDER BY id",
         )?;
-        let mut rows = stmt.query(params![edge_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Self::row_to_graph_edge(row)?))
-        } else {
-            Ok(None)
+        let mut rows = stmt.query(params![message_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(8)?;
+            out.push(ResponseVariant {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                user_message_id: row.get(2)?,
+                message_id: row.get(3)?,
+                content: row.get(4)?,
+                model: row.get(5)?,
+                temperature: row.get(6)?,
+                canonical: row.get(7)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
         }
+        Ok(out)
     }
 
-    pub fn list_graph_edges(
+    /// Make a variant the canonical answer: its content replaces the
+    /// assistant message's. Returns the variant, or `None` if it is not a
+    /// variant of that message.
+    pub fn pick_response_variant(
         &self,
-        session_id: &str,
-        source_id: Option<i64>,
-        target_id: Option<i64>,
-    ) -> Result<Vec<GraphEdge>> {
+        message_id: i64,
+        variant_id: i64,
+    ) -> Result<Option<ResponseVariant>> {
+        let Some(mut variant) = self
+            .list_response_variants(message_id)?
+            .into_iter()
+            .find(|variant| variant.id == variant_id)
+        else {
+            return Ok(None);
+        };
+        self.set_message_content(message_id, &variant.content)?;
         let conn = self.conn();
+        conn.execute(
+            "UPDATE response_variants SET canonical = (id = ?) WHERE message_id = ?",
+            params![variant_id, message_id],
+        )?;
+        variant.canonical = true;
+        Ok(Some(variant))
+    }
 
-        let edges = match (source_id, target_id) {
-            (Some(src), Some(tgt)) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
-                     FROM graph_edges WHERE session_id = ? AND source_id = ? AND target_id = ?",
-                )?;
-                let query = stmt.query(params![session_id, src, tgt])?;
-                Self::collect_graph_edges(query)?
-            }
-            (Some(src), None) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
-                     FROM graph_edges WHERE session_id = ? AND source_id = ?",
-                )?;
-                let query = stmt.query(params![session_id, src])?;
-                Self::collect_graph_edges(query)?
+    /// Search message content and session titles/tags across all sessions,
+    /// best matches first
+    pub fn search_sessions(&self, query: &str, limit: usize) -> Result<Vec<SessionSearchHit>> {
+        let terms = search::query_terms(query);
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut hits = Vec::new();
+        {
+            let conn = self.conn();
+            let filter = vec!["m.content ILIKE ?"; terms.len()].join(" OR ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT m.id, m.session_id, m.role, m.content, CAST(m.created_at AS TEXT), s.title FROM messages m LEFT JOIN sessions s ON s.session_id = m.session_id WHERE {}",
+                filter
+            ))?;
+            let patterns = terms.iter().map(|term| format!("%{}%", term));
+            let mut rows = stmt.query(duckdb::params_from_iter(patterns))?;
+            while let Some(row) = rows.next()? {
+                let content: String = row.get(3)?;
+                let score = search::score_text(&content, &terms);
+                if score <= 0.0 {
+                    continue;
+                }
+                let created_at: String = row.get(4)?;
+                hits.push(SessionSearchHit {
+                    session_id: row.get(1)?,
+                    session_title: row.get(5)?,
+                    message_id: Some(row.get(0)?),
+                    role: Some(row.get(2)?),
+                    snippet: search::snippet(&content, &terms),
+                    score,
+                    created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+                });
             }
-            (None, Some(tgt)) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
-                     FROM graph_edges WHERE session_id = ? AND target_id = ?",
-                )?;
-                let query = stmt.query(params![session_id, tgt])?;
-                Self::collect_graph_edges(query)?
+        }
+
+        for session in self.list_session_records()? {
+            let score = search::score_metadata(session.title.as_deref(), &session.tags, &terms);
+            if score <= 0.0 {
+                continue;
             }
-            (None, None) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
-                     FROM graph_edges WHERE session_id = ?",
-                )?;
-                let query = stmt.query(params![session_id])?;
-                Self::collect_graph_edges(query)?
+            let mut snippet = session.display_title().to_string();
+            if !session.tags.is_empty() {
+                snippet.push_str(&format!(" [{}]", session.tags.join(", ")));
             }
-        };
+            hits.push(SessionSearchHit {
+                session_id: session.session_id,
+                session_title: session.title,
+                message_id: None,
+                role: None,
+                snippet,
+                score,
+                created_at: session.updated_at,
+            });
+        }
 
-        Ok(edges)
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        hits.truncate(limit);
+        Ok(hits)
     }
 
-    pub fn count_graph_edges(&self, session_id: &str) -> Result<i64> {
-        let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT COUNT(*) FROM graph_edges WHERE session_id = ?")?;
-        let count: i64 = stmt.query_row(params![session_id], |row| row.get(0))?;
-        Ok(count)
-    }
+    /// Rank stored embeddings of all sessions by similarity to
+    /// `query_embedding`, resolving each to the message, transcription or
+    /// graph node it was computed from. Each matching session is searched
+    /// through its recall [`HnswIndex`] and its graph nodes, so only the best
+    /// candidates are read back. With a time window more candidates are
+    /// taken per session, as some of the best may fall outside it.
+    pub fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        filter: &SemanticSearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SemanticSearchHit>> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        if let Some(namespace) = &filter.namespace {
+            clauses.push("starts_with(mv.session_id, ?)");
+            values.push(namespace.clone());
+        }
+        if let Some(agent) = &filter.agent {
+            clauses.push("s.agent = ?");
+            values.push(agent.clone());
+        }
+        let sessions = {
+            let conn = self.conn();
+            let filter_sql = if clauses.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", clauses.join(" AND "))
+            };
+            let mut stmt = conn.prepare(&format!(
+                "SELECT DISTINCT mv.session_id FROM memory_vectors mv \
+                 LEFT JOIN sessions s ON s.session_id = mv.session_id \
+                 {} ORDER BY mv.session_id",
+                filter_sql
+            ))?;
+            let rows = stmt.query_map(duckdb::params_from_iter(values), |row| {
+                row.get::<_, String>(0)
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
 
-    pub fn delete_graph_edge(&self, edge_id: i64) -> Result<()> {
-        use crate::sync::VectorClock;
+        let candidates = if filter.since.is_some() || filter.until.is_some() {
+            limit.saturating_mul(RECALL_OVERSAMPLE)
+        } else {
+            limit
+        };
+        let mut scored: Vec<(i64, f32)> = Vec::new();
+        for session in &sessions {
+            for (memory, score) in self.recall_top_k(session, query_embedding, candidates)? {
+                scored.push((memory.id, score));
+            }
+            for (node, score) in
+                self.search_graph_nodes_semantic(session, query_embedding, candidates)?
+            {
+                scored.extend(node.embedding_id.map(|id| (id, score)));
+            }
+        }
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(candidates);
+        if scored.is_empty() {
+            return Ok(Vec::new());
+        }
+        let scores: HashMap<i64, f32> = scored.into_iter().collect();
+
+        let ids = scores
+            .keys()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut clauses = vec![format!("mv.id IN ({})", ids)];
+        let mut values: Vec<String> = Vec::new();
+        if let Some(since) = filter.since {
+            clauses.push("mv.created_at >= CAST(? AS TIMESTAMP)".to_string());
+            values.push(since.naive_utc().to_string());
+        }
+        if let Some(until) = filter.until {
+            clauses.push("mv.created_at < CAST(? AS TIMESTAMP)".to_string());
+            values.push(until.naive_utc().to_string());
+        }
 
         let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT mv.id, mv.session_id, CAST(mv.created_at AS TEXT), s.title, \
+             m.id, m.role, m.content, t.id, t.text, g.id, g.label, CAST(g.properties AS TEXT), \
+             ms.id, ms.summary \
+             FROM memory_vectors mv \
+             LEFT JOIN sessions s ON s.session_id = mv.session_id \
+             LEFT JOIN messages m ON m.id = mv.message_id \
+             LEFT JOIN transcriptions t ON t.embedding_id = mv.id \
+             LEFT JOIN graph_nodes g ON g.embedding_id = mv.id \
+             LEFT JOIN memory_summaries ms ON ms.embedding_id = mv.id \
+             WHERE {}",
+            clauses.join(" AND ")
+        ))?;
+        let mut rows = stmt.query(duckdb::params_from_iter(values))?;
+        let mut hits = Vec::new();
+        while let Some(row) = rows.next()? {
+            let message_id: Option<i64> = row.get(4)?;
+            let transcription_id: Option<i64> = row.get(7)?;
+            let node_id: Option<i64> = row.get(9)?;
+            let summary_id: Option<i64> = row.get(12)?;
+            let (kind, source_id, text) = match (message_id, transcription_id, node_id) {
+                (Some(id), _, _) => (SemanticHitKind::Message, id, row.get::<_, String>(6)?),
+                (None, Some(id), _) => {
+                    (SemanticHitKind::Transcription, id, row.get::<_, String>(8)?)
+                }
+                (None, None, Some(id)) => {
+                    let label: String = row.get(10)?;
+                    let properties: Option<String> = row.get(11)?;
+                    let text = match properties {
+                        Some(properties) => format!("{} {}", label, properties),
+                        None => label,
+                    };
+                    (SemanticHitKind::GraphNode, id, text)
+                }
+                (None, None, None) => match summary_id {
+                    Some(id) => (SemanticHitKind::Summary, id, row.get::<_, String>(13)?),
+                    // Embedding no longer linked to anything searchable
+                    None => continue,
+                },
+            };
 
-        // First get the edge data before deletion
-        let mut stmt = conn.prepare(
-            "SELECT session_id, source_id, target_id, edge_type, predicate, properties, weight,
-                    vector_clock, sync_enabled
-             FROM graph_edges WHERE id = ?",
-        )?;
+            let memory_id: i64 = row.get(0)?;
+            let created_at: String = row.get(2)?;
+            hits.push(SemanticSearchHit {
+                kind,
+                score: scores.get(&memory_id).copied().unwrap_or_default(),
+                session_id: row.get(1)?,
+                session_title: row.get(3)?,
+                text,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+                memory_id,
+                source_id,
+                role: row.get(5)?,
+            });
+        }
 
-        let result = stmt.query_row(params![edge_id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, i64>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, Option<String>>(4)?,
-                row.get::<_, Option<String>>(5)?,
-                row.get::<_, f32>(6)?,
-                row.get::<_, Option<String>>(7)?,
-                row.get::<_, bool>(8).unwrap_or(false),
-            ))
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
         });
+        hits.truncate(limit);
+        Ok(hits)
+    }
 
-        // If edge exists, handle sync tracking
-        if let Ok((
-            session_id,
-            source_id,
-            target_id,
-            edge_type,
-            predicate,
-            properties,
-            weight,
-            current_vc_json,
-            sync_enabled,
-        )) = result
-        {
-            if sync_enabled {
-                // Update the vector clock for the deletion
-                let mut vector_clock = if let Some(vc_json) = current_vc_json {
-                    VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
-                } else {
-                    VectorClock::new()
-                };
-                vector_clock.increment(&self.instance_id);
-                let vc_json = vector_clock.to_json()?;
-
-                // Create a tombstone entry for sync
-                conn.execute(
-                    "INSERT INTO graph_tombstones
-                     (session_id, entity_type, entity_id, deleted_by, vector_clock)
-                     VALUES (?, ?, ?, ?, ?)",
-                    params![session_id, "edge", edge_id, self.instance_id, vc_json],
-                )?;
-
-                // Append deletion to changelog
-                let edge_data = serde_json::json!({
-                    "id": edge_id,
-                    "session_id": session_id,
-                    "source_id": source_id,
-                    "target_id": target_id,
-                    "edge_type": edge_type,
-                    "predicate": predicate,
-                    "properties": properties,
-                    "weight": weight,
-                });
-
-                self.graph_changelog_append(
-                    &session_id,
-                    &self.instance_id,
-                    "edge",
-                    edge_id,
-                    "delete",
-                    &vc_json,
-                    Some(&edge_data.to_string()),
-                )?;
-            }
+    /// Set one text column of a session's metadata, creating the row if needed.
+    /// `column` is always a literal from this module, never user input.
+    fn update_session_column(&self, session_id: &str, column: &str, value: &str) -> Result<()> {
+        let conn = self.conn();
+        let updated = conn.execute(
+            &format!(
+                "UPDATE sessions SET {} = ?, updated_at = CURRENT_TIMESTAMP WHERE session_id = ?",
+                column
+            ),
+            params![value, session_id],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                &format!(
+                    "INSERT INTO sessions (session_id, {}) VALUES (?, ?)",
+                    column
+                ),
+                params![session_id, value],
+            )?;
         }
-
-        // Now delete the edge
-        conn.execute("DELETE FROM graph_edges WHERE id = ?", params![edge_id])?;
         Ok(())
     }
 
-    // ---------- Graph Traversal Operations ----------
+    // ---------- Session Transfer ----------
 
-    pub fn find_shortest_path(
+    /// Whether the database holds anything for a session
+    pub fn session_exists(&self, session_id: &str) -> Result<bool> {
+        let conn = self.conn();
+        let found: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sessions WHERE session_id = ?)
+                 OR EXISTS (SELECT 1 FROM messages WHERE session_id = ?)
+                 OR EXISTS (SELECT 1 FROM graph_nodes WHERE session_id = ?)
+                 OR EXISTS (SELECT 1 FROM transcriptions WHERE session_id = ?)
+                 OR EXISTS (SELECT 1 FROM tool_log WHERE session_id = ?)",
+            duckdb::params_from_iter(std::iter::repeat_n(session_id, 5)),
+            |row| row.get(0),
+        )?;
+        Ok(found)
+    }
+
+    /// Copy a session's messages, embeddings, graph, transcriptions and tool
+    /// log into `target`, as `target_session` or under the same ID. Rows get
+    /// new IDs in `target` with the references between them remapped, and
+    /// text is stored the way `target` stores it: personal data tokenized
+    /// with its vault, large payloads in its blob store. Nothing is written
+    /// when the session is missing here or already exists in `target`.
+    pub fn copy_session_to(
         &self,
         session_id: &str,
-        source_id: i64,
-        target_id: i64,
-        max_hops: Option<usize>,
-    ) -> Result<Option<GraphPath>> {
-        // Simple BFS implementation for finding shortest path
-        // In production, this would use DuckPGQ's ANY SHORTEST functionality
-        let max_depth = max_hops.unwrap_or(10);
-
-        let mut visited = std::collections::HashSet::new();
-        let mut queue = std::collections::VecDeque::new();
-        let mut parent_map = std::collections::HashMap::new();
-
-        queue.push_back((source_id, 0));
-        visited.insert(source_id);
+        target: &Persistence,
+        target_session: Option<&str>,
+    ) -> Result<SessionCopyReport> {
+        let target_session = target_session.unwrap_or(session_id);
+        if !self.session_exists(session_id)? {
+            bail!("session '{}' not found", session_id);
+        }
+        if target.session_exists(target_session)? {
+            bail!(
+                "session '{}' already exists in the target database",
+                target_session
+            );
+        }
 
-        while let Some((current_id, depth)) = queue.pop_front() {
-            if current_id == target_id {
-                // Reconstruct path
-                let path = self.reconstruct_path(&parent_map, source_id, target_id)?;
-                return Ok(Some(path));
+        let mut snapshot = SessionSnapshot::load(&self.conn(), session_id)
+            .with_context(|| format!("reading session '{}'", session_id))?;
+        for message in &mut snapshot.messages {
+            let text = self.detokenize(&self.resolve_blob(&message.content)?)?;
+            let text = target.redact(&text)?;
+            message.content = target.offload(&text, "text/plain")?.unwrap_or(text);
+        }
+        for node in &mut snapshot.nodes {
+            node.label = target.redact(&self.detokenize(&node.label)?)?;
+            node.properties = target.redact_json(&self.detokenize_json(&node.properties)?)?;
+        }
+        for call in &mut snapshot.tool_log {
+            // Offloaded results are stored as {"blob": <preview and reference>}
+            let stored = serde_json::from_str::<JsonValue>(&call.result)
+                .ok()
+                .and_then(|value| value["blob"].as_str().map(str::to_string));
+            if let Some(stored) = stored {
+                let result = self.resolve_blob(&stored)?;
+                call.result = match target.offload(&result, "application/json")? {
+                    Some(stored) => serde_json::json!({ "blob": stored }).to_string(),
+                    None => result,
+                };
             }
+        }
 
-            if depth >= max_depth {
-                continue;
+        let conn = target.conn();
+        conn.execute_batch("BEGIN TRANSACTION;")?;
+        match snapshot.insert(&conn, target_session) {
+            Ok(report) => {
+                conn.execute_batch("COMMIT;")?;
+                Ok(report)
             }
-
-            // Get outgoing edges
-            let edges = self.list_graph_edges(session_id, Some(current_id), None)?;
-            for edge in edges {
-                let target = edge.target_id;
-                if !visited.contains(&target) {
-                    visited.insert(target);
-                    parent_map.insert(target, (current_id, edge));
-                    queue.push_back((target, depth + 1));
-                }
+            Err(err) => {
+                let _ = conn.execute_batch("ROLLBACK;");
+                Err(err.context(format!("writing session '{}'", target_session)))
             }
         }
-
-        Ok(None)
     }
 
-    pub fn traverse_neighbors(
+    // ---------- Tool Log ----------
+
+    pub fn log_tool(
         &self,
         session_id: &str,
-        node_id: i64,
-        direction: TraversalDirection,
-        depth: usize,
-    ) -> Result<Vec<GraphNode>> {
-        if depth == 0 {
-            return Ok(vec![]);
+        agent_name: &str,
+        run_id: &str,
+        tool_name: &str,
+        arguments: &JsonValue,
+        result: &JsonValue,
+        success: bool,
+        error: Option<&str>,
+    ) -> Result<i64> {
+        let mut result_json = result.to_string();
+        if let Some(stored) = self.offload(&result_json, "application/json")? {
+            result_json = serde_json::json!({ "blob": stored }).to_string();
         }
+        let conn = self.conn();
+        let mut stmt = conn.prepare("INSERT INTO tool_log (session_id, agent, run_id, tool_name, arguments, result, success, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id")?;
+        let id: i64 = stmt.query_row(
+            params![
+                session_id,
+                agent_name,
+                run_id,
+                tool_name,
+                arguments.to_string(),
+                result_json,
+                success,
+                error.unwrap_or("")
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
 
-        let mut visited = std::collections::HashSet::new();
-        let mut result = Vec::new();
-        let mut queue = std::collections::VecDeque::new();
-
-        queue.push_back((node_id, 0));
-        visited.insert(node_id);
-
-        while let Some((current_id, current_depth)) = queue.pop_front() {
-            if current_depth > 0 {
-                if let Some(node) = self.get_graph_node(current_id)? {
-                    result.push(node);
-                }
-            }
+    /// Add a call to the agent's totals for `tool_name`
+    pub fn record_tool_usage(
+        &self,
+        agent: &str,
+        tool_name: &str,
+        success: bool,
+        latency_ms: u64,
+        output_tokens: usize,
+    ) -> Result<()> {
+        let successes = i64::from(success);
+        let latency_ms = latency_ms as i64;
+        let output_tokens = output_tokens as i64;
+        let conn = self.conn();
+        let updated = conn.execute(
+            "UPDATE tool_usage SET calls = calls + 1, successes = successes + ?, total_latency_ms = total_latency_ms + ?, output_tokens = output_tokens + ?, last_used_at = CURRENT_TIMESTAMP WHERE agent = ? AND tool_name = ?",
+            params![successes, latency_ms, output_tokens, agent, tool_name],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO tool_usage (agent, tool_name, calls, successes, total_latency_ms, output_tokens) VALUES (?, ?, 1, ?, ?, ?)",
+                params![agent, tool_name, successes, latency_ms, output_tokens],
+            )?;
+        }
+        Ok(())
+    }
 
-            if current_depth >= depth {
-                continue;
-            }
+    /// Usage totals of one agent's tools, or of every agent's, most called first
+    pub fn tool_usage_stats(&self, agent: Option<&str>) -> Result<Vec<ToolUsageStats>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT agent, tool_name, calls, successes, total_latency_ms, output_tokens, CAST(last_used_at AS TEXT)
+             FROM tool_usage WHERE (? IS NULL OR agent = ?)
+             ORDER BY calls DESC, agent, tool_name",
+        )?;
+        let mut rows = stmt.query(params![agent, agent])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let last_used_at: Option<String> = row.get(6)?;
+            out.push(ToolUsageStats {
+                agent: row.get(0)?,
+                tool_name: row.get(1)?,
+                calls: row.get(2)?,
+                successes: row.get(3)?,
+                total_latency_ms: row.get(4)?,
+                output_tokens: row.get(5)?,
+                last_used_at: last_used_at.as_deref().and_then(parse_timestamp),
+            });
+        }
+        Ok(out)
+    }
 
-            // Get edges based on direction
-            let edges = match direction {
-                TraversalDirection::Outgoing => {
-                    self.list_graph_edges(session_id, Some(current_id), None)?
-                }
-                TraversalDirection::Incoming => {
-                    self.list_graph_edges(session_id, None, Some(current_id))?
-                }
-                TraversalDirection::Both => {
-                    let mut out_edges =
-                        self.list_graph_edges(session_id, Some(current_id), None)?;
-                    let in_edges = self.list_graph_edges(session_id, None, Some(current_id))?;
-                    out_edges.extend(in_edges);
-                    out_edges
-                }
-            };
+    /// Tools that have failed every call the agent made to them, often
+    /// enough that they should no longer be advertised, and recently enough
+    /// that they are not yet due for another try
+    pub fn failing_tools(&self, agent: &str) -> Result<HashSet<String>> {
+        let now = Utc::now();
+        Ok(self
+            .tool_usage_stats(Some(agent))?
+            .into_iter()
+            .filter(|stats| stats.is_hidden(now))
+            .map(|stats| stats.tool_name)
+            .collect())
+    }
 
-            for edge in edges {
-                let next_id = match direction {
-                    TraversalDirection::Outgoing => edge.target_id,
-                    TraversalDirection::Incoming => edge.source_id,
-                    TraversalDirection::Both => {
-                        if edge.source_id == current_id {
-                            edge.target_id
-                        } else {
-                            edge.source_id
-                        }
-                    }
-                };
+    // ---------- Webhook Triggers ----------
 
-                if !visited.contains(&next_id) {
-                    visited.insert(next_id);
-                    queue.push_back((next_id, current_depth + 1));
-                }
-            }
+    /// Register a webhook that runs `spec_path`; names are unique
+    pub fn add_webhook_trigger(
+        &self,
+        name: &str,
+        secret: &str,
+        spec_path: &str,
+        agent: Option<&str>,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<WebhookTrigger> {
+        if self.webhook_trigger(name)?.is_some() {
+            bail!("a trigger named '{}' already exists", name);
         }
+        let id: i64 = self.conn().query_row(
+            "INSERT INTO webhook_triggers (name, secret, spec_path, agent, variables) VALUES (?, ?, ?, ?, ?) RETURNING id",
+            params![name, secret, spec_path, agent, serde_json::to_string(variables)?],
+            |row| row.get(0),
+        )?;
+        self.webhook_triggers_where("id = ?", params![id])?
+            .pop()
+            .context("reading back the new trigger")
+    }
 
-        Ok(result)
+    /// Every registered webhook, oldest first
+    pub fn list_webhook_triggers(&self) -> Result<Vec<WebhookTrigger>> {
+        self.webhook_triggers_where("TRUE", params![])
     }
 
-    // ---------- Helper Methods ----------
+    pub fn webhook_trigger(&self, name: &str) -> Result<Option<WebhookTrigger>> {
+        Ok(self
+            .webhook_triggers_where("name = ?", params![name])?
+            .pop())
+    }
 
-    fn row_to_graph_node(row: &duckdb::Row) -> Result<GraphNode> {
-        let id: i64 = row.get(0)?;
-        let session_id: String = row.get(1)?;
-        let node_type: String = row.get(2)?;
-        let label: String = row.get(3)?;
-        let properties: String = row.get(4)?;
-        let embedding_id: Option<i64> = row.get(5)?;
-        let created_at: String = row.get(6)?;
-        let updated_at: String = row.get(7)?;
+    /// Remove a webhook; its jobs stay for their history
+    pub fn delete_webhook_trigger(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn()
+            .execute("DELETE FROM webhook_triggers WHERE id = ?", params![id])?;
+        Ok(deleted > 0)
+    }
 
-        Ok(GraphNode {
-            id,
-            session_id,
-            node_type: NodeType::from_str(&node_type),
-            label,
-            properties: serde_json::from_str(&properties).unwrap_or(JsonValue::Null),
-            embedding_id,
-            created_at: created_at.parse().unwrap_or_else(|_| Utc::now()),
-            updated_at: updated_at.parse().unwrap_or_else(|_| Utc::now()),
-        })
+    fn webhook_triggers_where(
+        &self,
+        condition: &str,
+        params: &[&dyn duckdb::ToSql],
+    ) -> Result<Vec<WebhookTrigger>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, secret, spec_path, agent, variables, CAST(created_at AS TEXT) FROM webhook_triggers WHERE {} ORDER BY id",
+            condition
+        ))?;
+        let mut rows = stmt.query(params)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let variables: String = row.get(5)?;
+            let created_at: String = row.get(6)?;
+            out.push(WebhookTrigger {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                secret: row.get(2)?,
+                spec_path: row.get(3)?,
+                agent: row.get(4)?,
+                variables: serde_json::from_str(&variables).unwrap_or_default(),
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
+        Ok(out)
     }
 
-    fn row_to_graph_edge(row: &duckdb::Row) -> Result<GraphEdge> {
-        let id: i64 = row.get(0)?;
-        let session_id: String = row.get(1)?;
-        let source_id: i64 = row.get(2)?;
+    /// Queue a run of `trigger_id` in `session_id`
+    pub fn add_trigger_job(&self, trigger_id: i64, session_id: &str) -> Result<i64> {
+        let id = self.conn().query_row(
+            "INSERT INTO trigger_jobs (trigger_id, session_id) VALUES (?, ?) RETURNING id",
+            params![trigger_id, session_id],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Move a job along; finished jobs get their finish time
+    pub fn set_trigger_job_status(
+        &self,
+        job_id: i64,
+        status: TriggerJobStatus,
+        run_id: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let finished = if status.is_finished() {
+            "CURRENT_TIMESTAMP"
+        } else {
+            "NULL"
+        };
+        self.conn().execute(
+            &format!(
+                "UPDATE trigger_jobs SET status = ?, run_id = COALESCE(?, run_id), error = ?, finished_at = {} WHERE id = ?",
+                finished
+            ),
+            params![status.as_str(), run_id, error, job_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn trigger_job(&self, job_id: i64) -> Result<Option<TriggerJob>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, trigger_id, session_id, status, run_id, error, CAST(created_at AS TEXT), CAST(finished_at AS TEXT)
+             FROM trigger_jobs WHERE id = ?",
+        )?;
+        let mut rows = stmt.query(params![job_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let status: String = row.get(3)?;
+        let created_at: String = row.get(6)?;
+        let finished_at: Option<String> = row.get(7)?;
+        Ok(Some(TriggerJob {
+            id: row.get(0)?,
+            trigger_id: row.get(1)?,
+            session_id: row.get(2)?,
+            status: TriggerJobStatus::parse(&status).unwrap_or(TriggerJobStatus::Failed),
+            run_id: row.get(4)?,
+            error: row.get(5)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            finished_at: finished_at.as_deref().and_then(parse_timestamp),
+        }))
+    }
+
+    // ---------- Policy Cache ----------
+
+    pub fn policy_upsert(&self, key: &str, value: &JsonValue) -> Result<()> {
+        let conn = self.conn();
+        // DuckDB upsert workaround: delete then insert atomically within a transaction.
+        conn.execute_batch("BEGIN TRANSACTION;")?;
+        {
+            let mut del = conn.prepare("DELETE FROM policy_cache WHERE key = ?")?;
+            let _ = del.execute(params![key])?;
+            let mut ins = conn.prepare("INSERT INTO policy_cache (key, value, updated_at) VALUES (?, ?, CURRENT_TIMESTAMP)")?;
+            let _ = ins.execute(params![key, value.to_string()])?;
+        }
+        conn.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+
+    pub fn policy_get(&self, key: &str) -> Result<Option<PolicyEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT key, value, CAST(updated_at AS TEXT) as updated_at FROM policy_cache WHERE key = ?")?;
+        let mut rows = stmt.query(params![key])?;
+        if let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            let value_text: String = row.get(1)?;
+            let updated_at: String = row.get(2)?;
+            let updated_at: DateTime<Utc> = updated_at.parse().unwrap_or_else(|_| Utc::now());
+            let value: JsonValue = serde_json::from_str(&value_text).unwrap_or(JsonValue::Null);
+            Ok(Some(PolicyEntry {
+                key,
+                value,
+                updated_at,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // ---------- Policy Audit ----------
+
+    /// Store a policy decision in the audit log
+    pub fn record_policy_decision(&self, record: &PolicyDecisionRecord) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO policy_audit (session_id, run_id, agent, action, resource, allowed, rule) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                record.session_id,
+                record.run_id,
+                record.agent,
+                record.action,
+                record.resource,
+                record.allowed,
+                record.rule
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The latest `limit` policy decisions, of one agent or of every agent,
+    /// newest first
+    pub fn policy_audit(&self, agent: Option<&str>, limit: usize) -> Result<Vec<PolicyAuditEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, run_id, agent, action, resource, allowed, rule, CAST(created_at AS TEXT)
+             FROM policy_audit WHERE (? IS NULL OR agent = ?)
+             ORDER BY id DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![agent, agent, limit as i64])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(8)?;
+            out.push(PolicyAuditEntry {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                run_id: row.get(2)?,
+                agent: row.get(3)?,
+                action: row.get(4)?,
+                resource: row.get(5)?,
+                allowed: row.get(6)?,
+                rule: row.get(7)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
+        Ok(out)
+    }
+
+    // ---------- Reminders ----------
+
+    /// Schedule `message` to be posted into `session_id` at `due_at`
+    pub fn add_reminder(
+        &self,
+        session_id: &str,
+        message: &str,
+        due_at: DateTime<Utc>,
+    ) -> Result<Reminder> {
+        let id: i64 = self.conn().query_row(
+            "INSERT INTO reminders (session_id, message, due_at) VALUES (?, ?, CAST(? AS TIMESTAMP)) RETURNING id",
+            params![session_id, message, due_at.naive_utc().to_string()],
+            |row| row.get(0),
+        )?;
+        self.reminders_where("id = ?", params![id])?
+            .pop()
+            .context("reading back the new reminder")
+    }
+
+    /// Reminders not delivered yet, of one session or of every session,
+    /// soonest first
+    pub fn pending_reminders(&self, session_id: Option<&str>) -> Result<Vec<Reminder>> {
+        self.reminders_where(
+            "delivered_at IS NULL AND (? IS NULL OR session_id = ?)",
+            params![session_id, session_id],
+        )
+    }
+
+    /// Undelivered reminders due at `now`, soonest first
+    pub fn due_reminders(&self, now: DateTime<Utc>) -> Result<Vec<Reminder>> {
+        self.reminders_where(
+            "delivered_at IS NULL AND due_at <= CAST(? AS TIMESTAMP)",
+            params![now.naive_utc().to_string()],
+        )
+    }
+
+    /// Mark a reminder delivered. Returns `false` when it already was, so
+    /// that only one caller posts it.
+    pub fn mark_reminder_delivered(&self, id: i64) -> Result<bool> {
+        let updated = self.conn().execute(
+            "UPDATE reminders SET delivered_at = CURRENT_TIMESTAMP WHERE id = ? AND delivered_at IS NULL",
+            params![id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Drop a pending reminder of `session_id`
+    pub fn cancel_reminder(&self, session_id: &str, id: i64) -> Result<bool> {
+        let deleted = self.conn().execute(
+            "DELETE FROM reminders WHERE id = ? AND session_id = ? AND delivered_at IS NULL",
+            params![id, session_id],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    fn reminders_where(
+        &self,
+        condition: &str,
+        params: &[&dyn duckdb::ToSql],
+    ) -> Result<Vec<Reminder>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, message, CAST(epoch(due_at) AS BIGINT), CAST(created_at AS TEXT), CAST(epoch(delivered_at) AS BIGINT)
+             FROM reminders WHERE {} ORDER BY due_at, id",
+            condition
+        ))?;
+        let mut rows = stmt.query(params)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let due_at: i64 = row.get(3)?;
+            let created_at: String = row.get(4)?;
+            let delivered_at: Option<i64> = row.get(5)?;
+            out.push(Reminder {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                message: row.get(2)?,
+                due_at: DateTime::from_timestamp(due_at, 0).unwrap_or_else(Utc::now),
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+                delivered_at: delivered_at.and_then(|at| DateTime::from_timestamp(at, 0)),
+            });
+        }
+        Ok(out)
+    }
+}
+
+fn generate_instance_id() -> String {
+    let hostname = hostname::get()
+        .ok()
+        .and_then(|h| h.into_string().ok())
+        .unwrap_or_else(|| "unknown".to_string());
+    let uuid = uuid::Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext));
+    format!("{}-{}", hostname, uuid)
+}
+
+/// Read a timestamp selected with `CAST(col AS TEXT)`. DuckDB writes
+/// `TIMESTAMP` values without an offset, as `2026-10-16 18:23:47.123`;
+/// they hold UTC.
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    text.parse::<DateTime<Utc>>()
+        .or_else(|_| {
+            DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z")
+                .map(|at| at.with_timezone(&Utc))
+        })
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .map(|at| at.and_utc())
+        })
+}
+
+fn expand_tilde(path: &Path) -> Result<PathBuf> {
+    let path_str = path.to_string_lossy();
+    if path_str == "~" {
+        let base = BaseDirs::new().context("base directories not available")?;
+        Ok(base.home_dir().to_path_buf())
+    } else if let Some(stripped) = path_str.strip_prefix("~/") {
+        let base = BaseDirs::new().context("base directories not available")?;
+        Ok(base.home_dir().join(stripped))
+    } else {
+        Ok(path.to_path_buf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn expands_home_directory_prefix() {
+        let base = BaseDirs::new().expect("home directory available");
+        let expected = base.home_dir().join("demo.db");
+        let result = expand_tilde(Path::new("~/demo.db")).expect("path expansion succeeds");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn leaves_regular_paths_unchanged() {
+        let input = Path::new("relative/path.db");
+        let result = expand_tilde(input).expect("path expansion succeeds");
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn parses_duckdb_timestamp_text() {
+        let expected: DateTime<Utc> = "2026-10-16T18:23:47.123Z".parse().unwrap();
+        assert_eq!(parse_timestamp("2026-10-16 18:23:47.123"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2026-10-16 20:23:47.123+02"),
+            Some(expected)
+        );
+        assert_eq!(parse_timestamp("2026-10-16T18:23:47.123Z"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2026-10-16 18:23:47"),
+            Some(expected - chrono::Duration::milliseconds(123))
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.is_empty() || b.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let mut dot = 0.0f32;
+    let mut na = 0.0f32;
+    let mut nb = 0.0f32;
+    for i in 0..a.len() {
+        dot += a[i] * b[i];
+        na += a[i] * a[i];
+        nb += b[i] * b[i];
+    }
+    if na == 0.0 || nb == 0.0 {
+        return 0.0;
+    }
+    dot / (na.sqrt() * nb.sqrt())
+}
+
+// ========== Knowledge Graph Methods ==========
+
+impl Persistence {
+    // ---------- Graph Node Operations ----------
+
+    pub fn insert_graph_node(
+        &self,
+        session_id: &str,
+        node_type: NodeType,
+        label: &str,
+        properties: &JsonValue,
+        embedding_id: Option<i64>,
+    ) -> Result<i64> {
+        self.insert_graph_node_in_graph(
+            session_id,
+            DEFAULT_GRAPH,
+            node_type,
+            label,
+            properties,
+            embedding_id,
+        )
+    }
+
+    /// Insert a node into one of the session's named graphs, creating the
+    /// graph when it does not exist yet
+    pub fn insert_graph_node_in_graph(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+        node_type: NodeType,
+        label: &str,
+        properties: &JsonValue,
+        embedding_id: Option<i64>,
+    ) -> Result<i64> {
+        use crate::sync::VectorClock;
+
+        let graph_name = graph_name.trim();
+        if graph_name.is_empty() {
+            bail!("graph name must not be empty");
+        }
+        if graph_name != DEFAULT_GRAPH {
+            self.graph_create(session_id, graph_name)?;
+        }
+
+        // Check if sync is enabled BEFORE locking the connection to avoid deadlock
+        let sync_enabled = self
+            .graph_get_sync_enabled(session_id, graph_name)
+            .unwrap_or(false);
+
+        // Create initial vector clock for this node
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(&self.instance_id);
+        let vc_json = vector_clock.to_json()?;
+
+        let label = self.redact(label)?;
+        let properties = self.redact_json(properties)?;
+        let conn = self.conn();
+
+        // Insert the node with sync metadata
+        let mut stmt = conn.prepare(
+            "INSERT INTO graph_nodes (session_id, node_type, label, properties, embedding_id,
+                                     vector_clock, last_modified_by, sync_enabled)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+        )?;
+        let id: i64 = stmt.query_row(
+            params![
+                session_id,
+                node_type.as_str(),
+                label,
+                properties.to_string(),
+                embedding_id,
+                vc_json,
+                self.instance_id,
+                sync_enabled,
+            ],
+            |row| row.get(0),
+        )?;
+        if graph_name != DEFAULT_GRAPH {
+            conn.execute(
+                "INSERT INTO graph_node_graphs (node_id, graph_name) VALUES (?, ?)",
+                params![id, graph_name],
+            )?;
+        }
+        // The changelog takes the connection lock itself
+        drop(stmt);
+        drop(conn);
+
+        let node_data = serde_json::json!({
+            "id": id,
+            "session_id": session_id,
+            "node_type": node_type.as_str(),
+            "label": label,
+            "properties": properties,
+            "embedding_id": embedding_id,
+        });
+
+        // If sync is enabled, append to changelog
+        if sync_enabled {
+            self.graph_changelog_append(
+                session_id,
+                &self.instance_id,
+                "node",
+                id,
+                "create",
+                &vc_json,
+                Some(&node_data.to_string()),
+            )?;
+        }
+        self.record_graph_event(session_id, "node", id, "create", &node_data)?;
+
+        Ok(id)
+    }
+
+    pub fn get_graph_node(&self, node_id: i64) -> Result<Option<GraphNode>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, node_type, label, properties, embedding_id,
+                    CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+             FROM graph_nodes WHERE id = ?",
+        )?;
+        let mut rows = stmt.query(params![node_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_graph_node(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_graph_nodes(
+        &self,
+        session_id: &str,
+        node_type: Option<NodeType>,
+        limit: Option<i64>,
+    ) -> Result<Vec<GraphNode>> {
+        let conn = self.conn();
+
+        let nodes = if let Some(nt) = node_type {
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, node_type, label, properties, embedding_id,
+                        CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+                 FROM graph_nodes WHERE session_id = ? AND node_type = ?
+                 ORDER BY id DESC LIMIT ?",
+            )?;
+            let query = stmt.query(params![session_id, nt.as_str(), limit.unwrap_or(100)])?;
+            Self::collect_graph_nodes(query)?
+        } else {
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, node_type, label, properties, embedding_id,
+                        CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+                 FROM graph_nodes WHERE session_id = ?
+                 ORDER BY id DESC LIMIT ?",
+            )?;
+            let query = stmt.query(params![session_id, limit.unwrap_or(100)])?;
+            Self::collect_graph_nodes(query)?
+        };
+
+        Ok(nodes)
+    }
+
+    /// Nodes of one of the session's graphs, newest first. Unlike
+    /// [`Self::list_graph_nodes`], which spans every graph of the session.
+    pub fn list_graph_nodes_in_graph(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+        node_type: Option<NodeType>,
+        limit: Option<i64>,
+    ) -> Result<Vec<GraphNode>> {
+        let conn = self.conn();
+        let mut sql = String::from(
+            "SELECT n.id, n.session_id, n.node_type, n.label, n.properties, n.embedding_id,
+                    CAST(n.created_at AS TEXT), CAST(n.updated_at AS TEXT)
+             FROM graph_nodes n LEFT JOIN graph_node_graphs g ON g.node_id = n.id
+             WHERE n.session_id = ? AND COALESCE(g.graph_name, ?) = ?",
+        );
+        let mut values: Vec<duckdb::types::Value> = vec![
+            session_id.to_string().into(),
+            DEFAULT_GRAPH.to_string().into(),
+            graph_name.to_string().into(),
+        ];
+        if let Some(node_type) = node_type {
+            sql.push_str(" AND n.node_type = ?");
+            values.push(node_type.as_str().into());
+        }
+        sql.push_str(" ORDER BY n.id DESC LIMIT ?");
+        values.push(limit.unwrap_or(100).into());
+
+        let mut stmt = conn.prepare(&sql)?;
+        let query = stmt.query(duckdb::params_from_iter(values))?;
+        Self::collect_graph_nodes(query)
+    }
+
+    /// Name of the graph a node belongs to
+    pub fn graph_node_graph_name(&self, node_id: i64) -> Result<String> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            "SELECT graph_name FROM graph_node_graphs WHERE node_id = ?",
+            params![node_id],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(graph_name) => Ok(graph_name),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(DEFAULT_GRAPH.to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn count_graph_nodes(&self, session_id: &str) -> Result<i64> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT COUNT(*) FROM graph_nodes WHERE session_id = ?")?;
+        let count: i64 = stmt.query_row(params![session_id], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Pin a node so recall always includes its neighborhood. Returns
+    /// `false` when the node does not exist.
+    pub fn pin_graph_node(&self, node_id: i64) -> Result<bool> {
+        let Some(node) = self.get_graph_node(node_id)? else {
+            return Ok(false);
+        };
+        let conn = self.conn();
+        conn.execute("DELETE FROM graph_pins WHERE node_id = ?", params![node_id])?;
+        conn.execute(
+            "INSERT INTO graph_pins (node_id, session_id) VALUES (?, ?)",
+            params![node_id, node.session_id],
+        )?;
+        Ok(true)
+    }
+
+    /// Returns `false` when the node was not pinned
+    pub fn unpin_graph_node(&self, node_id: i64) -> Result<bool> {
+        let removed = self
+            .conn()
+            .execute("DELETE FROM graph_pins WHERE node_id = ?", params![node_id])?;
+        Ok(removed > 0)
+    }
+
+    /// Pinned nodes of a session, in the order they were pinned
+    pub fn list_pinned_graph_nodes(&self, session_id: &str) -> Result<Vec<GraphNode>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT n.id, n.session_id, n.node_type, n.label, n.properties, n.embedding_id,
+                    CAST(n.created_at AS TEXT), CAST(n.updated_at AS TEXT)
+             FROM graph_pins p JOIN graph_nodes n ON n.id = p.node_id
+             WHERE p.session_id = ?
+             ORDER BY p.pinned_at, n.id",
+        )?;
+        let query = stmt.query(params![session_id])?;
+        Self::collect_graph_nodes(query)
+    }
+
+    /// Link a node to its embedding once it is computed. Embedding ids are
+    /// local to this database, so the change is not synced.
+    pub fn set_graph_node_embedding(&self, node_id: i64, embedding_id: i64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE graph_nodes SET embedding_id = ? WHERE id = ?",
+            params![embedding_id, node_id],
+        )?;
+        // A vector of the node's own text is no longer recalled
+        for index in self.vector_indexes().values_mut() {
+            if index
+                .get(embedding_id)
+                .is_some_and(|memory| memory.message_id.is_none())
+            {
+                index.remove(embedding_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Nodes of a session that have no embedding yet, oldest first. Message
+    /// nodes are left out; they share the vector of their message.
+    pub fn graph_nodes_without_embedding(
+        &self,
+        session_id: &str,
+        limit: usize,
+    ) -> Result<Vec<GraphNode>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, node_type, label, properties, embedding_id,
+                    CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+             FROM graph_nodes
+             WHERE session_id = ? AND embedding_id IS NULL AND node_type <> ?
+             ORDER BY id LIMIT ?",
+        )?;
+        let query = stmt.query(params![
+            session_id,
+            NodeType::Message.as_str(),
+            limit as i64
+        ])?;
+        Self::collect_graph_nodes(query)
+    }
+
+    /// Store the embedding of a node's own text (see
+    /// [`GraphNode::embedding_text`]) and link the node to it
+    pub fn insert_graph_node_embedding(
+        &self,
+        node_id: i64,
+        session_id: &str,
+        embedding: &[f32],
+    ) -> Result<i64> {
+        let embedding_id = self.insert_memory_vector(session_id, None, embedding)?;
+        self.set_graph_node_embedding(node_id, embedding_id)?;
+        Ok(embedding_id)
+    }
+
+    /// Unlink a node from the embedding of its own text and delete it, so
+    /// the node is embedded again. Vectors of messages and transcriptions
+    /// stay.
+    fn drop_graph_node_embedding(&self, node_id: i64) -> Result<()> {
+        let conn = self.conn();
+        let embedding_id = match conn.query_row(
+            "SELECT mv.id FROM graph_nodes n JOIN memory_vectors mv ON mv.id = n.embedding_id
+             WHERE n.id = ? AND mv.message_id IS NULL
+               AND NOT EXISTS (SELECT 1 FROM transcriptions t WHERE t.embedding_id = mv.id)",
+            params![node_id],
+            |row| row.get::<_, i64>(0),
+        ) {
+            Ok(id) => id,
+            Err(duckdb::Error::QueryReturnedNoRows) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        conn.execute(
+            "UPDATE graph_nodes SET embedding_id = NULL WHERE embedding_id = ?",
+            params![embedding_id],
+        )?;
+        conn.execute(
+            "DELETE FROM memory_access WHERE memory_id = ?",
+            params![embedding_id],
+        )?;
+        conn.execute(
+            "DELETE FROM memory_vectors WHERE id = ?",
+            params![embedding_id],
+        )?;
+        Ok(())
+    }
+
+    /// The `k` nodes of a session whose embeddings are most similar to
+    /// `query_embedding`, with their cosine similarity, best first. Nodes
+    /// without an embedding are not considered.
+    pub fn search_graph_nodes_semantic(
+        &self,
+        session_id: &str,
+        query_embedding: &[f32],
+        k: usize,
+    ) -> Result<Vec<(GraphNode, f32)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT n.id, n.session_id, n.node_type, n.label, n.properties, n.embedding_id,
+                    CAST(n.created_at AS TEXT), CAST(n.updated_at AS TEXT), mv.embedding
+             FROM graph_nodes n JOIN memory_vectors mv ON mv.id = n.embedding_id
+             WHERE n.session_id = ?",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut scored = Vec::new();
+        while let Some(row) = rows.next()? {
+            let node = Self::row_to_graph_node(row)?;
+            let embedding_text: String = row.get(8)?;
+            let embedding: Vec<f32> = serde_json::from_str(&embedding_text).unwrap_or_default();
+            let score = cosine_similarity(query_embedding, &embedding);
+            scored.push((node, score));
+        }
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+        Ok(scored)
+    }
+
+    pub fn update_graph_node(&self, node_id: i64, properties: &JsonValue) -> Result<()> {
+        use crate::sync::VectorClock;
+
+        let properties = &self.redact_json(properties)?;
+        // The old embedding no longer describes the node
+        self.drop_graph_node_embedding(node_id)?;
+        let conn = self.conn();
+
+        // First get the current node data and vector clock
+        let mut stmt = conn.prepare(
+            "SELECT session_id, node_type, label, vector_clock, sync_enabled
+             FROM graph_nodes WHERE id = ?",
+        )?;
+
+        let (session_id, node_type, label, current_vc_json, sync_enabled): (
+            String,
+            String,
+            String,
+            Option<String>,
+            bool,
+        ) = stmt.query_row(params![node_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4).unwrap_or(false),
+            ))
+        })?;
+
+        // Update the vector clock
+        let mut vector_clock = if let Some(vc_json) = current_vc_json {
+            VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
+        } else {
+            VectorClock::new()
+        };
+        vector_clock.increment(&self.instance_id);
+        let vc_json = vector_clock.to_json()?;
+
+        // Update the node with new properties and sync metadata
+        conn.execute(
+            "UPDATE graph_nodes
+             SET properties = ?,
+                 vector_clock = ?,
+                 last_modified_by = ?,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+            params![properties.to_string(), vc_json, self.instance_id, node_id],
+        )?;
+        drop(stmt);
+        drop(conn);
+
+        let node_data = serde_json::json!({
+            "id": node_id,
+            "session_id": session_id,
+            "node_type": node_type,
+            "label": label,
+            "properties": properties,
+        });
+
+        // If sync is enabled, append to changelog
+        if sync_enabled {
+            self.graph_changelog_append(
+                &session_id,
+                &self.instance_id,
+                "node",
+                node_id,
+                "update",
+                &vc_json,
+                Some(&node_data.to_string()),
+            )?;
+        }
+        self.record_graph_event(&session_id, "node", node_id, "update", &node_data)?;
+
+        Ok(())
+    }
+
+    /// Update the CRDT property `key` of a node, starting from `initial`
+    /// when the node does not have it yet, and save the node like
+    /// [`Self::update_graph_node`] does
+    fn graph_update_crdt_property(
+        &self,
+        node_id: i64,
+        key: &str,
+        initial: crate::sync::CrdtValue,
+        update: impl FnOnce(&mut crate::sync::CrdtValue),
+    ) -> Result<crate::sync::CrdtValue> {
+        use crate::sync::CrdtValue;
+
+        let node = self
+            .get_graph_node(node_id)?
+            .with_context(|| format!("graph node {} not found", node_id))?;
+        let mut properties = match node.properties {
+            JsonValue::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        let mut value = match properties.get(key) {
+            None => initial,
+            Some(existing) => CrdtValue::from_json(existing)
+                .filter(|crdt| std::mem::discriminant(crdt) == std::mem::discriminant(&initial))
+                .with_context(|| {
+                    format!(
+                        "property '{}' of node {} is not a {}",
+                        key,
+                        node_id,
+                        match initial {
+                            CrdtValue::GCounter(_) => "counter",
+                            CrdtValue::OrSet(_) => "set",
+                        }
+                    )
+                })?,
+        };
+        update(&mut value);
+        properties.insert(key.to_string(), value.to_json());
+        self.update_graph_node(node_id, &JsonValue::Object(properties))?;
+        Ok(value)
+    }
+
+    /// Add `by` to the grow-only counter property `key`, returning the new
+    /// total across all instances
+    pub fn graph_counter_increment(&self, node_id: i64, key: &str, by: u64) -> Result<u64> {
+        use crate::sync::{CrdtValue, GCounter};
+
+        let instance_id = self.instance_id.clone();
+        let value = self.graph_update_crdt_property(
+            node_id,
+            key,
+            CrdtValue::GCounter(GCounter::default()),
+            |value| {
+                if let CrdtValue::GCounter(counter) = value {
+                    counter.increment(&instance_id, by);
+                }
+            },
+        )?;
+        Ok(value.value().as_u64().unwrap_or_default())
+    }
+
+    /// Add `element` to the observed-remove set property `key`, returning
+    /// the set's elements
+    pub fn graph_set_add(
+        &self,
+        node_id: i64,
+        key: &str,
+        element: &JsonValue,
+    ) -> Result<Vec<JsonValue>> {
+        use crate::sync::{CrdtValue, OrSet};
+
+        let instance_id = self.instance_id.clone();
+        let value = self.graph_update_crdt_property(
+            node_id,
+            key,
+            CrdtValue::OrSet(OrSet::default()),
+            |value| {
+                if let CrdtValue::OrSet(set) = value {
+                    set.add(&instance_id, element.clone());
+                }
+            },
+        )?;
+        Ok(Self::crdt_set_values(&value))
+    }
+
+    /// Remove `element` from the observed-remove set property `key`,
+    /// returning the set's elements
+    pub fn graph_set_remove(
+        &self,
+        node_id: i64,
+        key: &str,
+        element: &JsonValue,
+    ) -> Result<Vec<JsonValue>> {
+        use crate::sync::{CrdtValue, OrSet};
+
+        let value = self.graph_update_crdt_property(
+            node_id,
+            key,
+            CrdtValue::OrSet(OrSet::default()),
+            |value| {
+                if let CrdtValue::OrSet(set) = value {
+                    set.remove(element);
+                }
+            },
+        )?;
+        Ok(Self::crdt_set_values(&value))
+    }
+
+    fn crdt_set_values(value: &crate::sync::CrdtValue) -> Vec<JsonValue> {
+        match value {
+            crate::sync::CrdtValue::OrSet(set) => set.values(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn delete_graph_node(&self, node_id: i64) -> Result<()> {
+        use crate::sync::VectorClock;
+
+        self.drop_graph_node_embedding(node_id)?;
+        let conn = self.conn();
+        conn.execute("DELETE FROM graph_pins WHERE node_id = ?", params![node_id])?;
+        conn.execute(
+            "DELETE FROM graph_node_graphs WHERE node_id = ?",
+            params![node_id],
+        )?;
+
+        // First get the node data before deletion
+        let mut stmt = conn.prepare(
+            "SELECT session_id, node_type, label, properties, vector_clock, sync_enabled
+             FROM graph_nodes WHERE id = ?",
+        )?;
+
+        let result = stmt.query_row(params![node_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, bool>(5).unwrap_or(false),
+            ))
+        });
+
+        drop(stmt);
+        let Ok((session_id, node_type, label, properties, current_vc_json, sync_enabled)) = result
+        else {
+            // Nothing to track for a node that does not exist
+            conn.execute("DELETE FROM graph_nodes WHERE id = ?", params![node_id])?;
+            return Ok(());
+        };
+        let node_data = serde_json::json!({
+            "id": node_id,
+            "session_id": session_id,
+            "node_type": node_type,
+            "label": label,
+            "properties": properties,
+        });
+
+        if sync_enabled {
+            // Update the vector clock for the deletion
+            let mut vector_clock = if let Some(vc_json) = current_vc_json {
+                VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
+            } else {
+                VectorClock::new()
+            };
+            vector_clock.increment(&self.instance_id);
+            let vc_json = vector_clock.to_json()?;
+
+            // Create a tombstone entry for sync
+            conn.execute(
+                "INSERT INTO graph_tombstones
+                 (session_id, entity_type, entity_id, deleted_by, vector_clock)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![session_id, "node", node_id, self.instance_id, vc_json],
+            )?;
+            // The changelog takes the connection lock itself
+            drop(conn);
+
+            // Append deletion to changelog
+            self.graph_changelog_append(
+                &session_id,
+                &self.instance_id,
+                "node",
+                node_id,
+                "delete",
+                &vc_json,
+                Some(&node_data.to_string()),
+            )?;
+            self.conn()
+                .execute("DELETE FROM graph_nodes WHERE id = ?", params![node_id])?;
+        } else {
+            conn.execute("DELETE FROM graph_nodes WHERE id = ?", params![node_id])?;
+            drop(conn);
+        }
+
+        self.record_graph_event(&session_id, "node", node_id, "delete", &node_data)
+    }
+
+    // ---------- Graph Edge Operations ----------
+
+    pub fn insert_graph_edge(
+        &self,
+        session_id: &str,
+        source_id: i64,
+        target_id: i64,
+        edge_type: EdgeType,
+        predicate: Option<&str>,
+        properties: Option<&JsonValue>,
+        weight: f32,
+    ) -> Result<i64> {
+        use crate::sync::VectorClock;
+
+        // Edges stay inside one graph, so traversals never leave it
+        let graph_name = self.graph_node_graph_name(source_id)?;
+        let target_graph = self.graph_node_graph_name(target_id)?;
+        if graph_name != target_graph {
+            bail!(
+                "cannot link node {} in graph '{}' to node {} in graph '{}'",
+                source_id,
+                graph_name,
+                target_id,
+                target_graph
+            );
+        }
+
+        // Check if sync is enabled BEFORE locking the connection to avoid deadlock
+        let sync_enabled = self
+            .graph_get_sync_enabled(session_id, &graph_name)
+            .unwrap_or(false);
+
+        // Create initial vector clock for this edge
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(&self.instance_id);
+        let vc_json = vector_clock.to_json()?;
+
+        let conn = self.conn();
+
+        // Insert the edge with sync metadata
+        let mut stmt = conn.prepare(
+            "INSERT INTO graph_edges (session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                                     vector_clock, last_modified_by, sync_enabled)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+        )?;
+        let props_str = properties.map(|p| p.to_string());
+        let id: i64 = stmt.query_row(
+            params![
+                session_id,
+                source_id,
+                target_id,
+                edge_type.as_str(),
+                predicate,
+                props_str,
+                weight,
+                vc_json,
+                self.instance_id,
+                sync_enabled,
+            ],
+            |row| row.get(0),
+        )?;
+        // The changelog takes the connection lock itself
+        drop(stmt);
+        drop(conn);
+
+        let edge_data = serde_json::json!({
+            "id": id,
+            "session_id": session_id,
+            "source_id": source_id,
+            "target_id": target_id,
+            "edge_type": edge_type.as_str(),
+            "predicate": predicate,
+            "properties": properties,
+            "weight": weight,
+        });
+
+        // If sync is enabled, append to changelog
+        if sync_enabled {
+            self.graph_changelog_append(
+                session_id,
+                &self.instance_id,
+                "edge",
+                id,
+                "insert",
+                &vc_json,
+                Some(&edge_data.to_string()),
+            )?;
+        }
+        self.record_graph_event(session_id, "edge", id, "insert", &edge_data)?;
+
+        Ok(id)
+    }
+
+    pub fn get_graph_edge(&self, edge_id: i64) -> Result<Option<GraphEdge>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                    CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
+             FROM graph_edges WHERE id = ?",
+        )?;
+        let mut rows = stmt.query(params![edge_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_graph_edge(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_graph_edges(
+        &self,
+        session_id: &str,
+        source_id: Option<i64>,
+        target_id: Option<i64>,
+    ) -> Result<Vec<GraphEdge>> {
+        let conn = self.conn();
+
+        let edges = match (source_id, target_id) {
+            (Some(src), Some(tgt)) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
+                     FROM graph_edges WHERE session_id = ? AND source_id = ? AND target_id = ?",
+                )?;
+                let query = stmt.query(params![session_id, src, tgt])?;
+                Self::collect_graph_edges(query)?
+            }
+            (Some(src), None) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
+                     FROM graph_edges WHERE session_id = ? AND source_id = ?",
+                )?;
+                let query = stmt.query(params![session_id, src])?;
+                Self::collect_graph_edges(query)?
+            }
+            (None, Some(tgt)) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
+                     FROM graph_edges WHERE session_id = ? AND target_id = ?",
+                )?;
+                let query = stmt.query(params![session_id, tgt])?;
+                Self::collect_graph_edges(query)?
+            }
+            (None, None) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                            CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT), CAST(created_at AS TEXT)
+                     FROM graph_edges WHERE session_id = ?",
+                )?;
+                let query = stmt.query(params![session_id])?;
+                Self::collect_graph_edges(query)?
+            }
+        };
+
+        Ok(edges)
+    }
+
+    /// Edges of one of the session's graphs; an edge belongs to the graph
+    /// of the nodes it joins
+    pub fn list_graph_edges_in_graph(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+        source_id: Option<i64>,
+        target_id: Option<i64>,
+    ) -> Result<Vec<GraphEdge>> {
+        let members = self.graph_node_ids_in_graph(session_id, graph_name)?;
+        Ok(self
+            .list_graph_edges(session_id, source_id, target_id)?
+            .into_iter()
+            .filter(|edge| members.contains(&edge.source_id))
+            .collect())
+    }
+
+    /// Ids of the nodes in one of the session's graphs
+    pub fn graph_node_ids_in_graph(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+    ) -> Result<HashSet<i64>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT n.id FROM graph_nodes n
+             LEFT JOIN graph_node_graphs g ON g.node_id = n.id
+             WHERE n.session_id = ? AND COALESCE(g.graph_name, ?) = ?",
+        )?;
+        let ids = stmt.query_map(params![session_id, DEFAULT_GRAPH, graph_name], |row| {
+            row.get::<_, i64>(0)
+        })?;
+        Ok(ids.collect::<Result<_, _>>()?)
+    }
+
+    pub fn count_graph_edges(&self, session_id: &str) -> Result<i64> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT COUNT(*) FROM graph_edges WHERE session_id = ?")?;
+        let count: i64 = stmt.query_row(params![session_id], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    pub fn delete_graph_edge(&self, edge_id: i64) -> Result<()> {
+        use crate::sync::VectorClock;
+
+        let conn = self.conn();
+
+        // First get the edge data before deletion
+        let mut stmt = conn.prepare(
+            "SELECT session_id, source_id, target_id, edge_type, predicate, properties, weight,
+                    vector_clock, sync_enabled
+             FROM graph_edges WHERE id = ?",
+        )?;
+
+        let result = stmt.query_row(params![edge_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, f32>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, bool>(8).unwrap_or(false),
+            ))
+        });
+
+        drop(stmt);
+        let Ok((
+            session_id,
+            source_id,
+            target_id,
+            edge_type,
+            predicate,
+            properties,
+            weight,
+            current_vc_json,
+            sync_enabled,
+        )) = result
+        else {
+            // Nothing to track for an edge that does not exist
+            conn.execute("DELETE FROM graph_edges WHERE id = ?", params![edge_id])?;
+            return Ok(());
+        };
+        let edge_data = serde_json::json!({
+            "id": edge_id,
+            "session_id": session_id,
+            "source_id": source_id,
+            "target_id": target_id,
+            "edge_type": edge_type,
+            "predicate": predicate,
+            "properties": properties,
+            "weight": weight,
+        });
+
+        if sync_enabled {
+            // Update the vector clock for the deletion
+            let mut vector_clock = if let Some(vc_json) = current_vc_json {
+                VectorClock::from_json(&vc_json).unwrap_or_else(|_| VectorClock::new())
+            } else {
+                VectorClock::new()
+            };
+            vector_clock.increment(&self.instance_id);
+            let vc_json = vector_clock.to_json()?;
+
+            // Create a tombstone entry for sync
+            conn.execute(
+                "INSERT INTO graph_tombstones
+                 (session_id, entity_type, entity_id, deleted_by, vector_clock)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![session_id, "edge", edge_id, self.instance_id, vc_json],
+            )?;
+            // The changelog takes the connection lock itself
+            drop(conn);
+
+            // Append deletion to changelog
+            self.graph_changelog_append(
+                &session_id,
+                &self.instance_id,
+                "edge",
+                edge_id,
+                "delete",
+                &vc_json,
+                Some(&edge_data.to_string()),
+            )?;
+            self.conn()
+                .execute("DELETE FROM graph_edges WHERE id = ?", params![edge_id])?;
+        } else {
+            conn.execute("DELETE FROM graph_edges WHERE id = ?", params![edge_id])?;
+            drop(conn);
+        }
+
+        self.record_graph_event(&session_id, "edge", edge_id, "delete", &edge_data)
+    }
+
+    // ---------- Graph Traversal Operations ----------
+
+    pub fn find_shortest_path(
+        &self,
+        session_id: &str,
+        source_id: i64,
+        target_id: i64,
+        max_hops: Option<usize>,
+    ) -> Result<Option<GraphPath>> {
+        // Simple BFS implementation for finding shortest path
+        // In production, this would use DuckPGQ's ANY SHORTEST functionality
+        let max_depth = max_hops.unwrap_or(10);
+
+        let mut visited = std::collections::HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        let mut parent_map = std::collections::HashMap::new();
+
+        queue.push_back((source_id, 0));
+        visited.insert(source_id);
+
+        while let Some((current_id, depth)) = queue.pop_front() {
+            if current_id == target_id {
+                // Reconstruct path
+                let path = self.reconstruct_path(&parent_map, source_id, target_id)?;
+                return Ok(Some(path));
+            }
+
+            if depth >= max_depth {
+                continue;
+            }
+
+            // Get outgoing edges
+            let edges = self.list_graph_edges(session_id, Some(current_id), None)?;
+            for edge in edges {
+                let target = edge.target_id;
+                if !visited.contains(&target) {
+                    visited.insert(target);
+                    parent_map.insert(target, (current_id, edge));
+                    queue.push_back((target, depth + 1));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn traverse_neighbors(
+        &self,
+        session_id: &str,
+        node_id: i64,
+        direction: TraversalDirection,
+        depth: usize,
+    ) -> Result<Vec<GraphNode>> {
+        Ok(self
+            .traverse_neighbors_with_edges(session_id, node_id, direction, depth)?
+            .into_iter()
+            .map(|neighbor| neighbor.node)
+            .collect())
+    }
+
+    /// Breadth-first traversal like [`Self::traverse_neighbors`], also
+    /// reporting each node's depth and the edge that reached it
+    pub fn traverse_neighbors_with_edges(
+        &self,
+        session_id: &str,
+        node_id: i64,
+        direction: TraversalDirection,
+        depth: usize,
+    ) -> Result<Vec<GraphNeighbor>> {
+        if depth == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut visited = std::collections::HashSet::new();
+        let mut result = Vec::new();
+        let mut queue = std::collections::VecDeque::new();
+
+        queue.push_back((node_id, 0, None));
+        visited.insert(node_id);
+
+        while let Some((current_id, current_depth, via)) = queue.pop_front() {
+            if let Some(edge) = via {
+                if let Some(node) = self.get_graph_node(current_id)? {
+                    result.push(GraphNeighbor {
+                        node,
+                        depth: current_depth,
+                        edge,
+                    });
+                }
+            }
+
+            if current_depth >= depth {
+                continue;
+            }
+
+            // Get edges based on direction
+            let edges = match direction {
+                TraversalDirection::Outgoing => {
+                    self.list_graph_edges(session_id, Some(current_id), None)?
+                }
+                TraversalDirection::Incoming => {
+                    self.list_graph_edges(session_id, None, Some(current_id))?
+                }
+                TraversalDirection::Both => {
+                    let mut out_edges =
+                        self.list_graph_edges(session_id, Some(current_id), None)?;
+                    let in_edges = self.list_graph_edges(session_id, None, Some(current_id))?;
+                    out_edges.extend(in_edges);
+                    out_edges
+                }
+            };
+
+            for edge in edges {
+                let next_id = match direction {
+                    TraversalDirection::Outgoing => edge.target_id,
+                    TraversalDirection::Incoming => edge.source_id,
+                    TraversalDirection::Both => {
+                        if edge.source_id == current_id {
+                            edge.target_id
+                        } else {
+                            edge.source_id
+                        }
+                    }
+                };
+
+                if !visited.contains(&next_id) {
+                    visited.insert(next_id);
+                    queue.push_back((next_id, current_depth + 1, Some(edge)));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    // ---------- Helper Methods ----------
+
+    fn row_to_graph_node(row: &duckdb::Row) -> Result<GraphNode> {
+        let id: i64 = row.get(0)?;
+        let session_id: String = row.get(1)?;
+        let node_type: String = row.get(2)?;
+        let label: String = row.get(3)?;
+        let properties: String = row.get(4)?;
+        let embedding_id: Option<i64> = row.get(5)?;
+        let created_at: String = row.get(6)?;
+        let updated_at: String = row.get(7)?;
+
+        Ok(GraphNode {
+            id,
+            session_id,
+            node_type: NodeType::from_str(&node_type),
+            label,
+            properties: serde_json::from_str(&properties).unwrap_or(JsonValue::Null),
+            embedding_id,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            updated_at: parse_timestamp(&updated_at).unwrap_or_else(Utc::now),
+        })
+    }
+
+    fn row_to_graph_edge(row: &duckdb::Row) -> Result<GraphEdge> {
+        let id: i64 = row.get(0)?;
+        let session_id: String = row.get(1)?;
+        let source_id: i64 = row.get(2)?;
         let target_id: i64 = row.get(3)?;
         let edge_type: String = row.get(4)?;
         let predicate: Option<String> = row.get(5)?;
@@ -988,235 +3499,1266 @@ impl Persistence {
         let temporal_end: Option<String> = row.get(9)?;
         let created_at: String = row.get(10)?;
 
-        Ok(GraphEdge {
-            id,
-            session_id,
-            source_id,
-            target_id,
-            edge_type: EdgeType::from_str(&edge_type),
-            predicate,
-            properties: properties.and_then(|p| serde_json::from_str(&p).ok()),
-            weight,
-            temporal_start: temporal_start.and_then(|s| s.parse().ok()),
-            temporal_end: temporal_end.and_then(|s| s.parse().ok()),
-            created_at: created_at.parse().unwrap_or_else(|_| Utc::now()),
+        Ok(GraphEdge {
+            id,
+            session_id,
+            source_id,
+            target_id,
+            edge_type: EdgeType::from_str(&edge_type),
+            predicate,
+            properties: properties.and_then(|p| serde_json::from_str(&p).ok()),
+            weight,
+            temporal_start: temporal_start.as_deref().and_then(parse_timestamp),
+            temporal_end: temporal_end.as_deref().and_then(parse_timestamp),
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        })
+    }
+
+    fn collect_graph_nodes(mut rows: duckdb::Rows) -> Result<Vec<GraphNode>> {
+        let mut nodes = Vec::new();
+        while let Some(row) = rows.next()? {
+            nodes.push(Self::row_to_graph_node(row)?);
+        }
+        Ok(nodes)
+    }
+
+    fn collect_graph_edges(mut rows: duckdb::Rows) -> Result<Vec<GraphEdge>> {
+        let mut edges = Vec::new();
+        while let Some(row) = rows.next()? {
+            edges.push(Self::row_to_graph_edge(row)?);
+        }
+        Ok(edges)
+    }
+
+    fn reconstruct_path(
+        &self,
+        parent_map: &std::collections::HashMap<i64, (i64, GraphEdge)>,
+        source_id: i64,
+        target_id: i64,
+    ) -> Result<GraphPath> {
+        let mut path_edges = Vec::new();
+        let mut path_nodes = Vec::new();
+        let mut current = target_id;
+        let mut total_weight = 0.0;
+
+        // Collect edges in reverse order
+        while current != source_id {
+            if let Some((parent, edge)) = parent_map.get(&current) {
+                path_edges.push(edge.clone());
+                total_weight += edge.weight;
+                current = *parent;
+            } else {
+                break;
+            }
+        }
+
+        // Reverse to get correct order
+        path_edges.reverse();
+
+        // Collect nodes
+        if let Some(node) = self.get_graph_node(source_id)? {
+            path_nodes.push(node);
+        }
+        for edge in &path_edges {
+            if let Some(node) = self.get_graph_node(edge.target_id)? {
+                path_nodes.push(node);
+            }
+        }
+
+        Ok(GraphPath {
+            length: path_edges.len(),
+            weight: total_weight,
+            nodes: path_nodes,
+            edges: path_edges,
+        })
+    }
+
+    // ---------- Transcriptions ----------
+
+    pub fn insert_transcription(
+        &self,
+        session_id: &str,
+        chunk_id: i64,
+        text: &str,
+        timestamp: chrono::DateTime<Utc>,
+    ) -> Result<i64> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "INSERT INTO transcriptions (session_id, chunk_id, text, timestamp, embedding_id) VALUES (?, ?, ?, ?, NULL) RETURNING id",
+        )?;
+        let id: i64 = stmt.query_row(
+            params![session_id, chunk_id, text, timestamp.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn update_transcription_embedding(
+        &self,
+        transcription_id: i64,
+        embedding_id: i64,
+    ) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE transcriptions SET embedding_id = ? WHERE id = ?",
+            params![embedding_id, transcription_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_transcriptions(
+        &self,
+        session_id: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<(i64, i64, String, DateTime<Utc>)>> {
+        let conn = self.conn();
+        let query = if let Some(lim) = limit {
+            format!(
+                "SELECT id, chunk_id, text, CAST(timestamp AS TEXT) FROM transcriptions WHERE session_id = ? ORDER BY chunk_id ASC LIMIT {}",
+                lim
+            )
+        } else {
+            "SELECT id, chunk_id, text, CAST(timestamp AS TEXT) FROM transcriptions WHERE session_id = ? ORDER BY chunk_id ASC".to_string()
+        };
+
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut out = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let chunk_id: i64 = row.get(1)?;
+            let text: String = row.get(2)?;
+            let timestamp_str: String = row.get(3)?;
+            let timestamp = parse_timestamp(&timestamp_str).unwrap_or_else(Utc::now);
+            out.push((id, chunk_id, text, timestamp));
+        }
+
+        Ok(out)
+    }
+
+    /// Record where a transcribed chunk sits in the recording, who is
+    /// proposed as its speaker and, for recorded files, which file it came
+    /// from
+    pub fn update_transcription_segment(
+        &self,
+        transcription_id: i64,
+        start_ms: i64,
+        end_ms: i64,
+        speaker: Option<&str>,
+        source_file: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE transcriptions SET start_ms = ?, end_ms = ?, speaker = ?, source_file = ? WHERE id = ?",
+            params![start_ms, end_ms, speaker, source_file, transcription_id],
+        )?;
+        Ok(())
+    }
+
+    /// A session's transcribed chunks in recording order, with their
+    /// offsets and speakers where known
+    pub fn list_transcript_segments(&self, session_id: &str) -> Result<Vec<TranscriptSegment>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, chunk_id, text, CAST(timestamp AS TEXT), start_ms, end_ms, speaker, source_file
+             FROM transcriptions WHERE session_id = ? ORDER BY chunk_id ASC, id ASC",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let timestamp: String = row.get(3)?;
+            out.push(TranscriptSegment {
+                id: row.get(0)?,
+                chunk_id: row.get(1)?,
+                text: row.get(2)?,
+                timestamp: parse_timestamp(&timestamp).unwrap_or_else(Utc::now),
+                start_ms: row.get(4)?,
+                end_ms: row.get(5)?,
+                speaker: row.get(6)?,
+                source_file: row.get(7)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn get_full_transcription(&self, session_id: &str) -> Result<String> {
+        let transcriptions = self.list_transcriptions(session_id, None)?;
+        Ok(transcriptions
+            .into_iter()
+            .map(|(_, _, text, _)| text)
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    pub fn delete_transcriptions(&self, session_id: &str) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM transcriptions WHERE session_id = ?",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_transcription_by_embedding(&self, embedding_id: i64) -> Result<Option<String>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT text FROM transcriptions WHERE embedding_id = ? LIMIT 1")?;
+        let result: Result<String, _> = stmt.query_row(params![embedding_id], |row| row.get(0));
+        match result {
+            Ok(text) => Ok(Some(text)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // ---------- Tokenized Files Cache ----------
+
+    /// Persist tokenization metadata for a file, replacing any existing entry for the path.
+    pub fn upsert_tokenized_file(
+        &self,
+        session_id: &str,
+        path: &str,
+        file_hash: &str,
+        raw_tokens: usize,
+        cleaned_tokens: usize,
+        bytes_captured: usize,
+        truncated: bool,
+        embedding_id: Option<i64>,
+    ) -> Result<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM tokenized_files WHERE session_id = ? AND path = ?",
+            params![session_id, path],
+        )?;
+        let mut stmt = conn.prepare("INSERT INTO tokenized_files (session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id")?;
+        let id: i64 = stmt.query_row(
+            params![
+                session_id,
+                path,
+                file_hash,
+                raw_tokens as i64,
+                cleaned_tokens as i64,
+                bytes_captured as i64,
+                truncated,
+                embedding_id
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn get_tokenized_file(
+        &self,
+        session_id: &str,
+        path: &str,
+    ) -> Result<Option<TokenizedFileRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT id, session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id, CAST(updated_at AS TEXT) FROM tokenized_files WHERE session_id = ? AND path = ? LIMIT 1")?;
+        let mut rows = stmt.query(params![session_id, path])?;
+        if let Some(row) = rows.next()? {
+            let record = TokenizedFileRecord::from_row(row)?;
+            Ok(Some(record))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_tokenized_files(&self, session_id: &str) -> Result<Vec<TokenizedFileRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT id, session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id, CAST(updated_at AS TEXT) FROM tokenized_files WHERE session_id = ? ORDER BY path")?;
+        let mut rows = stmt.query(params![session_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(TokenizedFileRecord::from_row(row)?);
+        }
+        Ok(out)
+    }
+
+    // ---------- Round-table Drafts ----------
+
+    /// Record one participant's draft (or the moderator's synthesis)
+    pub fn insert_roundtable_draft(
+        &self,
+        session_id: &str,
+        roundtable_id: &str,
+        agent_name: &str,
+        is_synthesis: bool,
+        content: &str,
+    ) -> Result<i64> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("INSERT INTO roundtable_drafts (session_id, roundtable_id, agent_name, is_synthesis, content) VALUES (?, ?, ?, ?, ?) RETURNING id")?;
+        let id: i64 = stmt.query_row(
+            params![session_id, roundtable_id, agent_name, is_synthesis, content],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Drafts of a round-table in the order they were written
+    pub fn list_roundtable_drafts(
+        &self,
+        session_id: &str,
+        roundtable_id: &str,
+    ) -> Result<Vec<RoundtableDraftRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT id, session_id, roundtable_id, agent_name, is_synthesis, content, CAST(created_at AS TEXT) FROM roundtable_drafts WHERE session_id = ? AND roundtable_id = ? ORDER BY id")?;
+        let mut rows = stmt.query(params![session_id, roundtable_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(6)?;
+            out.push(RoundtableDraftRecord {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                roundtable_id: row.get(2)?,
+                agent_name: row.get(3)?,
+                is_synthesis: row.get(4)?,
+                content: row.get(5)?,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            });
+        }
+        Ok(out)
+    }
+
+    // ---------- Step Timings ----------
+
+    /// Record the stage timings of one step. Each entry is
+    /// `(stage, category, duration_ms)`.
+    pub fn insert_step_timings(
+        &self,
+        session_id: &str,
+        run_id: &str,
+        agent: Option<&str>,
+        stages: &[(&str, Option<&str>, f64)],
+    ) -> Result<()> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("INSERT INTO step_timings (session_id, run_id, agent, stage, category, duration_ms) VALUES (?, ?, ?, ?, ?, ?)")?;
+        for &(stage, category, duration_ms) in stages {
+            stmt.execute(params![
+                session_id,
+                run_id,
+                agent,
+                stage,
+                category,
+                duration_ms
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Timings aggregated per stage, slowest in total first; all sessions
+    /// when `session_id` is `None`
+    pub fn step_timing_report(&self, session_id: Option<&str>) -> Result<Vec<StageTimingSummary>> {
+        let conn = self.conn();
+        let mut sql = String::from("SELECT stage, category, COUNT(*), SUM(duration_ms), AVG(duration_ms), MAX(duration_ms) FROM step_timings");
+        if session_id.is_some() {
+            sql.push_str(" WHERE session_id = ?");
+        }
+        sql.push_str(" GROUP BY stage, category ORDER BY SUM(duration_ms) DESC, stage");
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = match session_id {
+            Some(session_id) => stmt.query(params![session_id])?,
+            None => stmt.query([])?,
+        };
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let count: i64 = row.get(2)?;
+            out.push(StageTimingSummary {
+                stage: row.get(0)?,
+                category: row.get(1)?,
+                count: count.max(0) as usize,
+                total_ms: row.get(3)?,
+                avg_ms: row.get(4)?,
+                max_ms: row.get(5)?,
+            });
+        }
+        Ok(out)
+    }
+
+    // ---------- Model Log ----------
+
+    /// Record one provider call
+    pub fn insert_model_log(&self, entry: &ModelLogEntry) -> Result<i64> {
+        let params_json = serde_json::to_string(&entry.params)?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare("INSERT INTO model_log (session_id, run_id, agent, stage, provider, model, prompt_digest, prompt_bytes, prompt, params, response, error, truncated, prompt_tokens, completion_tokens, latency_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id")?;
+        let id: i64 = stmt.query_row(
+            params![
+                entry.session_id,
+                entry.run_id,
+                entry.agent,
+                entry.stage,
+                entry.provider,
+                entry.model,
+                entry.prompt_digest,
+                entry.prompt_bytes as i64,
+                entry.prompt,
+                params_json,
+                entry.response,
+                entry.error,
+                entry.truncated,
+                entry.prompt_tokens.map(i64::from),
+                entry.completion_tokens.map(i64::from),
+                entry.latency_ms,
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Provider calls made during a run, in order
+    pub fn list_model_log(&self, run_id: &str) -> Result<Vec<ModelLogRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT id, session_id, run_id, agent, stage, provider, model, prompt_digest, prompt_bytes, prompt, params, response, error, truncated, prompt_tokens, completion_tokens, latency_ms, CAST(created_at AS TEXT) FROM model_log WHERE run_id = ? ORDER BY id")?;
+        let mut rows = stmt.query(params![run_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(ModelLogRecord::from_row(row)?);
+        }
+        Ok(out)
+    }
+
+    // ---------- Privacy ----------
+
+    /// `text` with its personal data replaced by tokens, the originals
+    /// sealed into the vault. Unchanged unless redacting at rest.
+    pub fn redact(&self, text: &str) -> Result<String> {
+        let Some(vault) = &self.vault else {
+            return Ok(text.to_string());
+        };
+        let (redacted, found) = vault.redact(text);
+        self.store_redactions(vault, &found)?;
+        Ok(redacted)
+    }
+
+    fn redact_json(&self, value: &JsonValue) -> Result<JsonValue> {
+        let Some(vault) = &self.vault else {
+            return Ok(value.clone());
+        };
+        let mut found = Vec::new();
+        let redacted = vault.redact_json(value, &mut found);
+        self.store_redactions(vault, &found)?;
+        Ok(redacted)
+    }
+
+    fn store_redactions(&self, vault: &PiiVault, found: &[Redaction]) -> Result<()> {
+        if found.is_empty() {
+            return Ok(());
+        }
+        let conn = self.conn();
+        for redaction in found {
+            let exists: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM pii_vault WHERE token = ?",
+                params![redaction.token],
+                |row| row.get(0),
+            )?;
+            if exists == 0 {
+                let (nonce, ciphertext) = vault.seal(&redaction.token, &redaction.value)?;
+                conn.execute(
+                    "INSERT INTO pii_vault (token, kind, nonce, ciphertext) VALUES (?, ?, ?, ?)",
+                    params![redaction.token, redaction.kind.as_str(), nonce, ciphertext],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `text` with tokens replaced by the personal data they stand for.
+    /// Tokens missing from the vault, or sealed under another key, are left
+    /// in place; without a vault the text is returned unchanged.
+    pub fn detokenize(&self, text: &str) -> Result<String> {
+        let Some(vault) = &self.vault else {
+            return Ok(text.to_string());
+        };
+        let tokens = privacy::find_tokens(text);
+        if tokens.is_empty() {
+            return Ok(text.to_string());
+        }
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT nonce, ciphertext FROM pii_vault WHERE token = ?")?;
+        let mut out = text.to_string();
+        for token in tokens {
+            let mut rows = stmt.query(params![token])?;
+            let Some(row) = rows.next()? else {
+                continue;
+            };
+            let nonce: String = row.get(0)?;
+            let ciphertext: String = row.get(1)?;
+            match vault.open(&token, &nonce, &ciphertext) {
+                Ok(value) => out = out.replace(&token, &value),
+                Err(err) => tracing::warn!("{}", err),
+            }
+        }
+        Ok(out)
+    }
+
+    /// [`Self::detokenize`] applied to every string in `value`
+    fn detokenize_json(&self, value: &JsonValue) -> Result<JsonValue> {
+        Ok(match value {
+            JsonValue::String(text) => JsonValue::String(self.detokenize(text)?),
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| self.detokenize_json(item))
+                    .collect::<Result<_>>()?,
+            ),
+            JsonValue::Object(map) => JsonValue::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), self.detokenize_json(item)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
         })
     }
 
-    fn collect_graph_nodes(mut rows: duckdb::Rows) -> Result<Vec<GraphNode>> {
-        let mut nodes = Vec::new();
+    // ---------- Blobs ----------
+
+    /// Store `bytes` in the blob store and index it, returning the hash
+    pub fn put_blob(&self, bytes: &[u8], media_type: Option<&str>) -> Result<String> {
+        let hash = self.blobs.put(bytes)?;
+        let conn = self.conn();
+        let exists: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM blobs WHERE hash = ?",
+            params![hash],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            conn.execute(
+                "INSERT INTO blobs (hash, size, media_type) VALUES (?, ?, ?)",
+                params![hash, bytes.len() as i64, media_type],
+            )?;
+        }
+        Ok(hash)
+    }
+
+    /// Contents of a stored blob
+    pub fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        self.blobs.get(hash)
+    }
+
+    /// Full text of a message or tool result that was moved to the blob
+    /// store; other text is returned unchanged
+    pub fn resolve_blob(&self, text: &str) -> Result<String> {
+        let Some(hash) = blobs::referenced_hash(text) else {
+            return Ok(text.to_string());
+        };
+        match self.blobs.get(hash)? {
+            Some(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            None => {
+                tracing::warn!(
+                    "Blob {} referenced but missing from {}",
+                    hash,
+                    self.blobs.root().display()
+                );
+                Ok(text.to_string())
+            }
+        }
+    }
+
+    pub fn list_blobs(&self) -> Result<Vec<BlobRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT hash, size, media_type, CAST(created_at AS TEXT) FROM blobs ORDER BY created_at",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
         while let Some(row) = rows.next()? {
-            nodes.push(Self::row_to_graph_node(row)?);
+            out.push(BlobRecord::from_row(row)?);
         }
-        Ok(nodes)
+        Ok(out)
     }
 
-    fn collect_graph_edges(mut rows: duckdb::Rows) -> Result<Vec<GraphEdge>> {
-        let mut edges = Vec::new();
-        while let Some(row) = rows.next()? {
-            edges.push(Self::row_to_graph_edge(row)?);
+    /// Delete indexed blobs that no message or tool log entry refers to.
+    /// With `dry_run` nothing is deleted and the report lists what would be.
+    pub fn gc_blobs(&self, dry_run: bool) -> Result<BlobGcReport> {
+        let mut report = BlobGcReport::default();
+        for blob in self.list_blobs()? {
+            if self.blob_referenced(&blob.hash)? {
+                report.kept += 1;
+                continue;
+            }
+            if !dry_run {
+                self.blobs.remove(&blob.hash)?;
+                self.conn()
+                    .execute("DELETE FROM blobs WHERE hash = ?", params![blob.hash])?;
+            }
+            report.removed += 1;
+            report.bytes_freed += blob.size;
         }
-        Ok(edges)
+        Ok(report)
     }
 
-    fn reconstruct_path(
-        &self,
-        parent_map: &std::collections::HashMap<i64, (i64, GraphEdge)>,
-        source_id: i64,
-        target_id: i64,
-    ) -> Result<GraphPath> {
-        let mut path_edges = Vec::new();
-        let mut path_nodes = Vec::new();
-        let mut current = target_id;
-        let mut total_weight = 0.0;
-
-        // Collect edges in reverse order
-        while current != source_id {
-            if let Some((parent, edge)) = parent_map.get(&current) {
-                path_edges.push(edge.clone());
-                total_weight += edge.weight;
-                current = *parent;
-            } else {
-                break;
+    /// Whether a message or tool log entry still refers to a blob
+    /// Remove the blobs among `hashes` that nothing refers to any more
+    fn release_blobs(&self, hashes: Vec<String>) -> Result<()> {
+        for hash in hashes {
+            if !self.blob_referenced(&hash)? {
+                self.blobs.remove(&hash)?;
+                self.conn()
+                    .execute("DELETE FROM blobs WHERE hash = ?", params![hash])?;
             }
         }
+        Ok(())
+    }
 
-        // Reverse to get correct order
-        path_edges.reverse();
+    fn blob_referenced(&self, hash: &str) -> Result<bool> {
+        let references: i64 = self.conn().query_row(
+            "SELECT (SELECT COUNT(*) FROM messages WHERE contains(content, ?)) + (SELECT COUNT(*) FROM tool_log WHERE contains(CAST(result AS TEXT), ?))",
+            params![hash, hash],
+            |row| row.get(0),
+        )?;
+        Ok(references > 0)
+    }
 
-        // Collect nodes
-        if let Some(node) = self.get_graph_node(source_id)? {
-            path_nodes.push(node);
-        }
-        for edge in &path_edges {
-            if let Some(node) = self.get_graph_node(edge.target_id)? {
-                path_nodes.push(node);
-            }
+    /// Move `text` to the blob store when it is over the threshold, returning
+    /// the preview-and-reference text to store in its place
+    fn offload(&self, text: &str, media_type: &str) -> Result<Option<String>> {
+        // In-memory databases keep payloads inline: there is no file to keep
+        // small, and blob files would outlive the session
+        if self.in_memory || self.blob_threshold == 0 || text.len() <= self.blob_threshold {
+            return Ok(None);
         }
-
-        Ok(GraphPath {
-            length: path_edges.len(),
-            weight: total_weight,
-            nodes: path_nodes,
-            edges: path_edges,
-        })
+        let hash = self.put_blob(text.as_bytes(), Some(media_type))?;
+        Ok(Some(blobs::preview_with_reference(text, &hash)))
     }
 
-    // ---------- Transcriptions ----------
+    // ---------- Mesh Stats ----------
 
-    pub fn insert_transcription(
-        &self,
-        session_id: &str,
-        chunk_id: i64,
-        text: &str,
-        timestamp: chrono::DateTime<Utc>,
-    ) -> Result<i64> {
+    /// This instance's activity as reported in mesh heartbeats. Sessions
+    /// count as active if they changed within `active_window`; token use and
+    /// heartbeat latency are not stored here and are left at zero / `None`.
+    pub fn local_instance_stats(&self, active_window: chrono::Duration) -> Result<InstanceStats> {
+        let cutoff = (Utc::now() - active_window).naive_utc().to_string();
         let conn = self.conn();
-        let mut stmt = conn.prepare(
-            "INSERT INTO transcriptions (session_id, chunk_id, text, timestamp, embedding_id) VALUES (?, ?, ?, ?, NULL) RETURNING id",
+        let active_sessions: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sessions WHERE updated_at >= CAST(? AS TIMESTAMP)",
+            params![cutoff],
+            |row| row.get(0),
         )?;
-        let id: i64 = stmt.query_row(
-            params![session_id, chunk_id, text, timestamp.to_rfc3339()],
+        let tool_calls: i64 =
+            conn.query_row("SELECT COUNT(*) FROM tool_log", [], |row| row.get(0))?;
+        let last_sync: Option<i64> = conn.query_row(
+            "SELECT CAST(epoch(MAX(last_sync_at)) AS BIGINT) FROM graph_sync_state",
+            [],
             |row| row.get(0),
         )?;
-        Ok(id)
+        Ok(InstanceStats {
+            active_sessions: active_sessions.max(0) as u64,
+            tokens_used: 0,
+            tool_calls: tool_calls.max(0) as u64,
+            sync_lag_secs: last_sync.map(|at| (Utc::now().timestamp() - at).max(0)),
+            heartbeat_latency_ms: None,
+            quota: None,
+        })
     }
 
-    pub fn update_transcription_embedding(
-        &self,
-        transcription_id: i64,
-        embedding_id: i64,
-    ) -> Result<()> {
+    /// Store a stats sample reported by `instance_id` and drop samples older
+    /// than [`MESH_STATS_RETENTION_SECS`]
+    pub fn mesh_stats_record(&self, instance_id: &str, stats: &InstanceStats) -> Result<()> {
+        let cutoff = (Utc::now() - chrono::Duration::seconds(MESH_STATS_RETENTION_SECS))
+            .naive_utc()
+            .to_string();
         let conn = self.conn();
         conn.execute(
-            "UPDATE transcriptions SET embedding_id = ? WHERE id = ?",
-            params![embedding_id, transcription_id],
+            "INSERT INTO mesh_stats (instance_id, active_sessions, tokens_used, tool_calls, sync_lag_secs, heartbeat_latency_ms) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                instance_id,
+                stats.active_sessions as i64,
+                stats.tokens_used as i64,
+                stats.tool_calls as i64,
+                stats.sync_lag_secs,
+                stats.heartbeat_latency_ms
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM mesh_stats WHERE created_at < CAST(? AS TIMESTAMP)",
+            params![cutoff],
         )?;
         Ok(())
     }
 
-    pub fn list_transcriptions(
+    /// Stored stats samples, oldest first; all instances when `instance_id`
+    /// is `None`
+    pub fn mesh_stats_window(&self, instance_id: Option<&str>) -> Result<Vec<MeshStatsRecord>> {
+        let conn = self.conn();
+        let mut sql = String::from("SELECT instance_id, active_sessions, tokens_used, tool_calls, sync_lag_secs, heartbeat_latency_ms, CAST(created_at AS TEXT) FROM mesh_stats");
+        if instance_id.is_some() {
+            sql.push_str(" WHERE instance_id = ?");
+        }
+        sql.push_str(" ORDER BY created_at, id");
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = match instance_id {
+            Some(instance_id) => stmt.query(params![instance_id])?,
+            None => stmt.query([])?,
+        };
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(MeshStatsRecord::from_row(row)?);
+        }
+        Ok(out)
+    }
+
+    // ========== Session Workspaces ==========
+
+    /// Register a workspace root for a session. The first workspace of a
+    /// session is focused.
+    pub fn workspace_add(
         &self,
         session_id: &str,
-        limit: Option<i64>,
-    ) -> Result<Vec<(i64, i64, String, DateTime<Utc>)>> {
+        name: &str,
+        root: &Path,
+        graph_node_id: Option<i64>,
+    ) -> Result<SessionWorkspace> {
+        let focused = self.workspace_list(session_id)?.is_empty();
         let conn = self.conn();
-        let query = if let Some(lim) = limit {
-            format!(
-                "SELECT id, chunk_id, text, CAST(timestamp AS TEXT) FROM transcriptions WHERE session_id = ? ORDER BY chunk_id ASC LIMIT {}",
-                lim
-            )
-        } else {
-            "SELECT id, chunk_id, text, CAST(timestamp AS TEXT) FROM transcriptions WHERE session_id = ? ORDER BY chunk_id ASC".to_string()
-        };
+        conn.execute(
+            "INSERT INTO session_workspaces (session_id, name, root, focused, graph_node_id) VALUES (?, ?, ?, ?, ?)",
+            params![
+                session_id,
+                name,
+                root.to_string_lossy().to_string(),
+                focused,
+                graph_node_id
+            ],
+        )
+        .with_context(|| format!("workspace '{}' already exists in this session", name))?;
+        drop(conn);
+        self.workspace_get(session_id, name)?
+            .context("workspace missing after insert")
+    }
 
-        let mut stmt = conn.prepare(&query)?;
+    pub fn workspace_get(&self, session_id: &str, name: &str) -> Result<Option<SessionWorkspace>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT session_id, name, root, focused, graph_node_id, CAST(created_at AS TEXT)
+             FROM session_workspaces WHERE session_id = ? AND name = ?",
+        )?;
+        let mut rows = stmt.query(params![session_id, name])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(SessionWorkspace::from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Workspaces of a session in the order they were added
+    pub fn workspace_list(&self, session_id: &str) -> Result<Vec<SessionWorkspace>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT session_id, name, root, focused, graph_node_id, CAST(created_at AS TEXT)
+             FROM session_workspaces WHERE session_id = ? ORDER BY created_at, name",
+        )?;
         let mut rows = stmt.query(params![session_id])?;
         let mut out = Vec::new();
-
         while let Some(row) = rows.next()? {
-            let id: i64 = row.get(0)?;
-            let chunk_id: i64 = row.get(1)?;
-            let text: String = row.get(2)?;
-            let timestamp_str: String = row.get(3)?;
-            let timestamp: DateTime<Utc> = timestamp_str.parse().unwrap_or_else(|_| Utc::now());
-            out.push((id, chunk_id, text, timestamp));
+            out.push(SessionWorkspace::from_row(row)?);
         }
-
         Ok(out)
     }
 
-    pub fn get_full_transcription(&self, session_id: &str) -> Result<String> {
-        let transcriptions = self.list_transcriptions(session_id, None)?;
-        Ok(transcriptions
+    /// The workspace tools are currently scoped to, if any
+    pub fn workspace_focused(&self, session_id: &str) -> Result<Option<SessionWorkspace>> {
+        Ok(self
+            .workspace_list(session_id)?
             .into_iter()
-            .map(|(_, _, text, _)| text)
-            .collect::<Vec<_>>()
-            .join(" "))
+            .find(|workspace| workspace.focused))
     }
 
-    pub fn delete_transcriptions(&self, session_id: &str) -> Result<()> {
+    /// Focus `name`, unfocusing the session's other workspaces. Returns
+    /// `false` if the session has no such workspace.
+    pub fn workspace_focus(&self, session_id: &str, name: &str) -> Result<bool> {
+        if self.workspace_get(session_id, name)?.is_none() {
+            return Ok(false);
+        }
         let conn = self.conn();
         conn.execute(
-            "DELETE FROM transcriptions WHERE session_id = ?",
-            params![session_id],
+            "UPDATE session_workspaces SET focused = (name = ?) WHERE session_id = ?",
+            params![name, session_id],
         )?;
-        Ok(())
+        Ok(true)
     }
 
-    pub fn get_transcription_by_embedding(&self, embedding_id: i64) -> Result<Option<String>> {
+    /// Unregister a workspace. Its graph nodes are kept.
+    pub fn workspace_remove(&self, session_id: &str, name: &str) -> Result<bool> {
         let conn = self.conn();
-        let mut stmt =
-            conn.prepare("SELECT text FROM transcriptions WHERE embedding_id = ? LIMIT 1")?;
-        let result: Result<String, _> = stmt.query_row(params![embedding_id], |row| row.get(0));
-        match result {
-            Ok(text) => Ok(Some(text)),
-            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+        let removed = conn.execute(
+            "DELETE FROM session_workspaces WHERE session_id = ? AND name = ?",
+            params![session_id, name],
+        )?;
+        Ok(removed > 0)
+    }
+
+    // ========== Forget ==========
+
+    /// Erase what `target` names, in `session_id` or in every session; see
+    /// [`forget`] for what that covers. Profile facts are shared by all
+    /// sessions and are matched either way. With `dry_run` nothing changes
+    /// and the report lists what would be erased.
+    pub fn forget(
+        &self,
+        target: &ForgetTarget,
+        session_id: Option<&str>,
+        dry_run: bool,
+    ) -> Result<ForgetReport> {
+        // Stored text holds tokens in place of personal data, so look for those
+        let redacted_target;
+        let target = match (&self.vault, target) {
+            (Some(vault), ForgetTarget::Text(text)) => {
+                redacted_target = ForgetTarget::Text(vault.redact(text).0);
+                &redacted_target
+            }
+            _ => target,
+        };
+        let in_scope = |sid: &str| session_id.is_none_or(|scope| scope == sid);
+        let mut report = ForgetReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut blob_hashes: Vec<String> = Vec::new();
+
+        // Messages, read in full when their content was moved to a blob
+        let messages: Vec<(i64, String, String)> = {
+            let conn = self.conn();
+            let mut out = Vec::new();
+            let mut stmt = conn.prepare("SELECT id, session_id, content FROM messages")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                out.push((row.get(0)?, row.get(1)?, row.get(2)?));
+            }
+            out
+        };
+        for (id, sid, stored) in messages {
+            if !in_scope(&sid) {
+                continue;
+            }
+            let content = self.resolve_blob(&stored)?;
+            let hit = match target {
+                ForgetTarget::Message(wanted) => id == *wanted,
+                ForgetTarget::Text(_) => target.matches(&content),
+            };
+            if hit {
+                blob_hashes.extend(blobs::referenced_hash(&stored).map(str::to_string));
+                report.messages.push(ForgottenItem {
+                    id,
+                    session_id: Some(sid),
+                    preview: target.preview(&content),
+                });
+            }
+        }
+        let message_ids: HashSet<i64> = report.messages.iter().map(|m| m.id).collect();
+
+        // Graph nodes mentioning the text or recorded for an erased message
+        let mut vector_ids: HashSet<i64> = HashSet::new();
+        let mut synced: HashSet<(&str, i64)> = HashSet::new();
+        let nodes: Vec<(i64, String, String, String, Option<i64>, bool)> = {
+            let conn = self.conn();
+            let mut out = Vec::new();
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, label, properties, embedding_id, COALESCE(sync_enabled, FALSE) FROM graph_nodes",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                out.push((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ));
+            }
+            out
+        };
+        for (id, sid, label, properties, embedding_id, sync_enabled) in nodes {
+            if !in_scope(&sid) {
+                continue;
+            }
+            let for_message = serde_json::from_str::<JsonValue>(&properties)
+                .ok()
+                .and_then(|props| props["message_id"].as_i64())
+                .is_some_and(|message_id| message_ids.contains(&message_id));
+            if for_message || target.matches(&label) || target.matches(&properties) {
+                vector_ids.extend(embedding_id);
+                if sync_enabled {
+                    synced.insert(("node", id));
+                }
+                report.graph_nodes.push(ForgottenItem {
+                    id,
+                    session_id: Some(sid),
+                    preview: target.preview(&format!("{} {}", label, properties)),
+                });
+            }
+        }
+        let node_ids: HashSet<i64> = report.graph_nodes.iter().map(|n| n.id).collect();
+
+        let mut edge_ids: Vec<i64> = Vec::new();
+        {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, source_id, target_id, predicate, properties, COALESCE(sync_enabled, FALSE) FROM graph_edges",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let source: i64 = row.get(2)?;
+                let dest: i64 = row.get(3)?;
+                let predicate: Option<String> = row.get(4)?;
+                let properties: Option<String> = row.get(5)?;
+                let touches = node_ids.contains(&source) || node_ids.contains(&dest);
+                let mentions = predicate
+                    .iter()
+                    .chain(&properties)
+                    .any(|t| target.matches(t));
+                if touches || (in_scope(&sid) && mentions) {
+                    let id: i64 = row.get(0)?;
+                    if row.get::<_, bool>(6)? {
+                        synced.insert(("edge", id));
+                    }
+                    edge_ids.push(id);
+                }
+            }
+        }
+        report.graph_edges = edge_ids.len();
+        report.synced_deletions = synced.len();
+
+        // Changelog snapshots of erased entities, or containing the text
+        let mut changelog_ids: Vec<i64> = Vec::new();
+        {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, entity_type, entity_id, data FROM graph_changelog",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let entity_type: String = row.get(2)?;
+                let entity_id: i64 = row.get(3)?;
+                let data: Option<String> = row.get(4)?;
+                let erased = match entity_type.as_str() {
+                    "node" => node_ids.contains(&entity_id),
+                    "edge" => edge_ids.contains(&entity_id),
+                    _ => false,
+                };
+                if erased || (in_scope(&sid) && data.is_some_and(|d| target.matches(&d))) {
+                    changelog_ids.push(row.get(0)?);
+                }
+            }
+        }
+        report.changelog_entries = changelog_ids.len();
+
+        // Embeddings of erased messages, and transcriptions with the text
+        {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id, message_id FROM memory_vectors WHERE message_id IS NOT NULL",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let message_id: i64 = row.get(1)?;
+                if message_ids.contains(&message_id) {
+                    vector_ids.insert(row.get(0)?);
+                }
+            }
+        }
+        // Answer variants of erased messages, or with the text
+        let mut variant_ids: Vec<i64> = Vec::new();
+        {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, user_message_id, message_id, content FROM response_variants",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let content: String = row.get(4)?;
+                if message_ids.contains(&row.get::<_, i64>(2)?)
+                    || message_ids.contains(&row.get::<_, i64>(3)?)
+                    || (in_scope(&sid) && target.matches(&content))
+                {
+                    variant_ids.push(row.get(0)?);
+                }
+            }
+        }
+        report.response_variants = variant_ids.len();
+        // Memory summaries covering an erased message, or with the text
+        let mut summary_ids: Vec<i64> = Vec::new();
+        {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id, session_id, first_message_id, last_message_id, summary, embedding_id FROM memory_summaries",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let first: i64 = row.get(2)?;
+                let last: i64 = row.get(3)?;
+                let summary: String = row.get(4)?;
+                let covers_erased = report.messages.iter().any(|m| {
+                    m.session_id.as_deref() == Some(sid.as_str()) && (first..=last).contains(&m.id)
+                });
+                if covers_erased || (in_scope(&sid) && target.matches(&summary)) {
+                    summary_ids.push(row.get(0)?);
+                    vector_ids.extend(row.get::<_, Option<i64>>(5)?);
+                }
+            }
+        }
+        report.memory_summaries = summary_ids.len();
+        let mut transcription_ids: Vec<i64> = Vec::new();
+        let mut anchor_sessions: Vec<String> = Vec::new();
+        let mut tool_ids: Vec<i64> = Vec::new();
+        let mut model_ids: Vec<i64> = Vec::new();
+        if matches!(target, ForgetTarget::Text(_)) {
+            let conn = self.conn();
+            let mut stmt =
+                conn.prepare("SELECT id, session_id, text, embedding_id FROM transcriptions")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let text: String = row.get(2)?;
+                if in_scope(&sid) && target.matches(&text) {
+                    transcription_ids.push(row.get(0)?);
+                    vector_ids.extend(row.get::<_, Option<i64>>(3)?);
+                }
+            }
+            drop(rows);
+            drop(stmt);
+
+            let mut stmt = conn.prepare("SELECT session_id, summary FROM context_anchors")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(0)?;
+                let summary: String = row.get(1)?;
+                if in_scope(&sid) && target.matches(&summary) {
+                    anchor_sessions.push(sid);
+                }
+            }
+            drop(rows);
+            drop(stmt);
+
+            let mut stmt = conn
+                .prepare("SELECT id, session_id, arguments, CAST(result AS TEXT) FROM tool_log")?;
+            let mut rows = stmt.query([])?;
+            let mut tool_rows: Vec<(i64, Option<String>, String, String)> = Vec::new();
+            while let Some(row) = rows.next()? {
+                tool_rows.push((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?));
+            }
+            drop(rows);
+            drop(stmt);
+
+            let mut stmt = conn
+                .prepare("SELECT id, session_id, prompt, COALESCE(response, '') FROM model_log")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let sid: String = row.get(1)?;
+                let prompt: String = row.get(2)?;
+                let response: String = row.get(3)?;
+                if in_scope(&sid) && (target.matches(&prompt) || target.matches(&response)) {
+                    model_ids.push(row.get(0)?);
+                }
+            }
+            drop(rows);
+            drop(stmt);
+            drop(conn);
+
+            for (id, sid, arguments, result) in tool_rows {
+                if !sid.as_deref().is_none_or(&in_scope) {
+                    continue;
+                }
+                let full_result = self.resolve_blob(&result)?;
+                if target.matches(&arguments) || target.matches(&full_result) {
+                    blob_hashes.extend(blobs::referenced_hash(&result).map(str::to_string));
+                    tool_ids.push(id);
+                }
+            }
+
+            for fact in self.user_profile_list()? {
+                if target.matches(&fact.fact) {
+                    report.profile_facts.push(ForgottenItem {
+                        id: fact.id,
+                        session_id: fact.source_session,
+                        preview: fact.fact,
+                    });
+                }
+            }
+        }
+        report.memory_vectors = vector_ids.len();
+        report.transcriptions = transcription_ids.len();
+        report.context_anchors = anchor_sessions.len();
+        report.tool_calls = tool_ids.len();
+        report.model_calls = model_ids.len();
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        // Edges before nodes and dependants before what they reference, so
+        // foreign keys hold throughout. Synced deletions are recorded by
+        // delete_graph_edge/delete_graph_node; their new changelog entries
+        // keep the deletion for peers but not the content.
+        for id in &edge_ids {
+            self.delete_graph_edge(*id)?;
+        }
+        for id in &node_ids {
+            self.delete_graph_node(*id)?;
+        }
+        let conn = self.conn();
+        for id in &changelog_ids {
+            conn.execute("DELETE FROM graph_changelog WHERE id = ?", params![id])?;
+        }
+        for (entity_type, id) in &synced {
+            conn.execute(
+                "UPDATE graph_changelog SET data = NULL WHERE entity_type = ? AND entity_id = ? AND operation = 'delete'",
+                params![entity_type, id],
+            )?;
+        }
+        for id in &transcription_ids {
+            conn.execute("DELETE FROM transcriptions WHERE id = ?", params![id])?;
+        }
+        for id in &variant_ids {
+            conn.execute("DELETE FROM response_variants WHERE id = ?", params![id])?;
+        }
+        for sid in &anchor_sessions {
+            conn.execute(
+                "DELETE FROM context_anchors WHERE session_id = ?",
+                params![sid],
+            )?;
+        }
+        for id in &summary_ids {
+            conn.execute("DELETE FROM memory_summaries WHERE id = ?", params![id])?;
+        }
+        for id in &vector_ids {
+            conn.execute("DELETE FROM memory_access WHERE memory_id = ?", params![id])?;
+            conn.execute("DELETE FROM memory_vectors WHERE id = ?", params![id])?;
+        }
+        for id in &message_ids {
+            conn.execute("DELETE FROM messages WHERE id = ?", params![id])?;
         }
+        let sessions: HashSet<&str> = report
+            .messages
+            .iter()
+            .filter_map(|m| m.session_id.as_deref())
+            .collect();
+        for sid in sessions {
+            conn.execute(
+                "UPDATE sessions SET message_count = (SELECT COUNT(*) FROM messages WHERE session_id = ?) WHERE session_id = ?",
+                params![sid, sid],
+            )?;
+        }
+        for id in &tool_ids {
+            conn.execute("DELETE FROM tool_log WHERE id = ?", params![id])?;
+        }
+        for id in &model_ids {
+            conn.execute("DELETE FROM model_log WHERE id = ?", params![id])?;
+        }
+        for fact in &report.profile_facts {
+            conn.execute("DELETE FROM user_profile WHERE id = ?", params![fact.id])?;
+        }
+        drop(conn);
+
+        self.release_blobs(blob_hashes)?;
+        Ok(report)
     }
 
-    // ---------- Tokenized Files Cache ----------
+    // ========== User Profile ==========
+
+    /// Facts about the user, most recently confirmed first
+    pub fn user_profile_list(&self) -> Result<Vec<UserProfileFact>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, category, fact, source_session, CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+             FROM user_profile ORDER BY updated_at DESC, id DESC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(UserProfileFact::from_row(row)?);
+        }
+        Ok(out)
+    }
 
-    /// Persist tokenization metadata for a file, replacing any existing entry for the path.
-    pub fn upsert_tokenized_file(
+    /// Store a fact, or refresh it when the same fact (ignoring case) is
+    /// already known. Returns its ID.
+    pub fn user_profile_upsert(
         &self,
-        session_id: &str,
-        path: &str,
-        file_hash: &str,
-        raw_tokens: usize,
-        cleaned_tokens: usize,
-        bytes_captured: usize,
-        truncated: bool,
-        embedding_id: Option<i64>,
+        category: &str,
+        fact: &str,
+        source_session: Option<&str>,
     ) -> Result<i64> {
+        let fact = self.redact(fact.trim())?;
         let conn = self.conn();
-        conn.execute(
-            "DELETE FROM tokenized_files WHERE session_id = ? AND path = ?",
-            params![session_id, path],
-        )?;
-        let mut stmt = conn.prepare("INSERT INTO tokenized_files (session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id")?;
-        let id: i64 = stmt.query_row(
-            params![
-                session_id,
-                path,
-                file_hash,
-                raw_tokens as i64,
-                cleaned_tokens as i64,
-                bytes_captured as i64,
-                truncated,
-                embedding_id
-            ],
+        let existing: Option<i64> = conn
+            .prepare("SELECT id FROM user_profile WHERE lower(fact) = lower(?) LIMIT 1")?
+            .query_row(params![fact], |row| row.get(0))
+            .ok();
+        if let Some(id) = existing {
+            conn.execute(
+                "UPDATE user_profile SET category = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+                params![category, id],
+            )?;
+            return Ok(id);
+        }
+        let id: i64 = conn.query_row(
+            "INSERT INTO user_profile (category, fact, source_session) VALUES (?, ?, ?) RETURNING id",
+            params![category, fact, source_session],
             |row| row.get(0),
         )?;
         Ok(id)
     }
 
-    pub fn get_tokenized_file(
-        &self,
-        session_id: &str,
-        path: &str,
-    ) -> Result<Option<TokenizedFileRecord>> {
+    /// Delete the facts matching `query`: a fact ID, or text contained in
+    /// the fact (ignoring case). Returns the deleted facts.
+    pub fn user_profile_forget(&self, query: &str) -> Result<Vec<UserProfileFact>> {
+        let query = query.trim();
+        let matching: Vec<UserProfileFact> = self
+            .user_profile_list()?
+            .into_iter()
+            .filter(|fact| match query.parse::<i64>() {
+                Ok(id) => fact.id == id,
+                Err(_) => fact.fact.to_lowercase().contains(&query.to_lowercase()),
+            })
+            .collect();
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT id, session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id, CAST(updated_at AS TEXT) FROM tokenized_files WHERE session_id = ? AND path = ? LIMIT 1")?;
-        let mut rows = stmt.query(params![session_id, path])?;
-        if let Some(row) = rows.next()? {
-            let record = TokenizedFileRecord::from_row(row)?;
-            Ok(Some(record))
-        } else {
-            Ok(None)
+        for fact in &matching {
+            conn.execute("DELETE FROM user_profile WHERE id = ?", params![fact.id])?;
         }
+        Ok(matching)
     }
 
-    pub fn list_tokenized_files(&self, session_id: &str) -> Result<Vec<TokenizedFileRecord>> {
+    /// Replace the whole profile with `facts` (category, fact), as written
+    /// by `/profile edit`
+    pub fn user_profile_replace(&self, facts: &[(String, String)]) -> Result<()> {
+        {
+            let conn = self.conn();
+            conn.execute("DELETE FROM user_profile", [])?;
+        }
+        for (category, fact) in facts {
+            self.user_profile_upsert(category, fact, None)?;
+        }
+        Ok(())
+    }
+
+    /// Last message of a session already mined for profile facts
+    pub fn user_profile_extracted_through(&self, session_id: &str) -> Result<Option<i64>> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT id, session_id, path, file_hash, raw_tokens, cleaned_tokens, bytes_captured, truncated, embedding_id, CAST(updated_at AS TEXT) FROM tokenized_files WHERE session_id = ? ORDER BY path")?;
+        let mut stmt = conn
+            .prepare("SELECT last_message_id FROM user_profile_extractions WHERE session_id = ?")?;
         let mut rows = stmt.query(params![session_id])?;
-        let mut out = Vec::new();
-        while let Some(row) = rows.next()? {
-            out.push(TokenizedFileRecord::from_row(row)?);
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
         }
-        Ok(out)
+    }
+
+    pub fn user_profile_mark_extracted(&self, session_id: &str, message_id: i64) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM user_profile_extractions WHERE session_id = ?",
+            params![session_id],
+        )?;
+        conn.execute(
+            "INSERT INTO user_profile_extractions (session_id, last_message_id) VALUES (?, ?)",
+            params![session_id, message_id],
+        )?;
+        Ok(())
     }
 
     // ========== Mesh Message Persistence ==========
@@ -1321,6 +4863,75 @@ impl Persistence {
         Ok(out)
     }
 
+    // ===== Event Outbox =====
+
+    /// Queue an event of `kind` for the `[events]` sink. Does nothing when
+    /// no sink is configured or the kind is not included. Takes the
+    /// connection lock, so call it after releasing yours.
+    pub fn event_outbox_append(
+        &self,
+        kind: &str,
+        session_id: &str,
+        payload: &JsonValue,
+    ) -> Result<Option<i64>> {
+        if !self
+            .events
+            .as_ref()
+            .is_some_and(|events| events.includes(kind))
+        {
+            return Ok(None);
+        }
+        let conn = self.conn();
+        let id: i64 = conn.query_row(
+            "INSERT INTO event_outbox (kind, session_id, payload) VALUES (?, ?, ?) RETURNING id",
+            params![kind, session_id, payload.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(Some(id))
+    }
+
+    /// Oldest undelivered events, at most `limit`
+    pub fn event_outbox_pending(&self, limit: usize) -> Result<Vec<OutboxEvent>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, session_id, payload, CAST(created_at AS TEXT)
+             FROM event_outbox ORDER BY id ASC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![limit as i64])?;
+        let mut events = Vec::new();
+        while let Some(row) = rows.next()? {
+            events.push(OutboxEvent::from_row(row)?);
+        }
+        Ok(events)
+    }
+
+    /// Mark every event up to and including `up_to_id` as delivered
+    pub fn event_outbox_ack(&self, up_to_id: i64) -> Result<usize> {
+        let conn = self.conn();
+        let deleted = conn.execute("DELETE FROM event_outbox WHERE id <= ?", params![up_to_id])?;
+        Ok(deleted)
+    }
+
+    /// Queue a graph change for the `[events]` sink
+    fn record_graph_event(
+        &self,
+        session_id: &str,
+        entity_type: &str,
+        entity_id: i64,
+        operation: &str,
+        data: &JsonValue,
+    ) -> Result<()> {
+        let payload = serde_json::json!({
+            "instance_id": self.instance_id,
+            "entity_type": entity_type,
+            "entity_id": entity_id,
+            "operation": operation,
+            "data": data,
+        });
+        self.event_outbox_append(outbox::GRAPH_EVENT, session_id, &payload)?;
+        Ok(())
+    }
+
     // ===== Graph Synchronization Methods =====
 
     /// Append an entry to the graph changelog
@@ -1335,12 +4946,12 @@ impl Persistence {
         data: Option<&str>,
     ) -> Result<i64> {
         let conn = self.conn();
-        conn.execute(
+        let id: i64 = conn.query_row(
             "INSERT INTO graph_changelog (session_id, instance_id, entity_type, entity_id, operation, vector_clock, data)
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+             VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING id",
             params![session_id, instance_id, entity_type, entity_id, operation, vector_clock, data],
+            |row| row.get(0),
         )?;
-        let id: i64 = conn.query_row("SELECT last_insert_rowid()", params![], |row| row.get(0))?;
         Ok(id)
     }
 
@@ -1420,21 +5031,89 @@ impl Persistence {
         Ok(())
     }
 
-    /// Enable or disable sync for a graph
+    /// Record a named graph of a session; does nothing when it exists
+    pub fn graph_create(&self, session_id: &str, graph_name: &str) -> Result<()> {
+        let conn = self.conn();
+        let exists: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM graph_metadata WHERE session_id = ? AND graph_name = ?",
+            params![session_id, graph_name],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            conn.execute(
+                "INSERT INTO graph_metadata (session_id, graph_name) VALUES (?, ?)",
+                params![session_id, graph_name],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Enable or disable sync for a graph. Only nodes and edges written
+    /// while sync is enabled are sent to peers.
     pub fn graph_set_sync_enabled(
         &self,
         session_id: &str,
         graph_name: &str,
         enabled: bool,
     ) -> Result<()> {
+        self.graph_create(session_id, graph_name)?;
         let conn = self.conn();
+        if enabled {
+            // Changelog entries reference the instance that wrote them
+            let registered: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM mesh_registry WHERE instance_id = ?",
+                params![self.instance_id],
+                |row| row.get(0),
+            )?;
+            if registered == 0 {
+                conn.execute(
+                    "INSERT INTO mesh_registry (instance_id, hostname, port) VALUES (?, 'localhost', 0)",
+                    params![self.instance_id],
+                )?;
+            }
+        }
         conn.execute(
-            "UPDATE graph_metadata SET sync_enabled = ? WHERE session_id = ? AND graph_name = ?",
+            "UPDATE graph_metadata SET sync_enabled = ?, updated_at = CURRENT_TIMESTAMP
+             WHERE session_id = ? AND graph_name = ?",
             params![enabled, session_id, graph_name],
         )?;
         Ok(())
     }
 
+    /// Graph chosen for the session with `/graph use`, `default` until then
+    pub fn graph_get_active(&self, session_id: &str) -> Result<String> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            "SELECT graph_name FROM graph_active WHERE session_id = ?",
+            params![session_id],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(graph_name) => Ok(graph_name),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(DEFAULT_GRAPH.to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Make `graph_name` the session's active graph, creating it if needed
+    pub fn graph_set_active(&self, session_id: &str, graph_name: &str) -> Result<()> {
+        let graph_name = graph_name.trim();
+        if graph_name.is_empty() {
+            bail!("graph name must not be empty");
+        }
+        self.graph_create(session_id, graph_name)?;
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM graph_active WHERE session_id = ?",
+            params![session_id],
+        )?;
+        conn.execute(
+            "INSERT INTO graph_active (session_id, graph_name) VALUES (?, ?)",
+            params![session_id, graph_name],
+        )?;
+        Ok(())
+    }
+
     /// Check if sync is enabled for a graph
     pub fn graph_get_sync_enabled(&self, session_id: &str, graph_name: &str) -> Result<bool> {
         let conn = self.conn();
@@ -1450,36 +5129,26 @@ impl Persistence {
         }
     }
 
-    /// List all graphs for a session
+    /// List all graphs for a session: those created or synced explicitly,
+    /// those holding nodes, and `default` whenever the session has nodes
     pub fn graph_list(&self, session_id: &str) -> Result<Vec<String>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT DISTINCT graph_name FROM graph_metadata WHERE session_id = ?
+            "SELECT graph_name FROM graph_metadata WHERE session_id = ?
              UNION
-             SELECT DISTINCT 'default' as graph_name
-             FROM graph_nodes WHERE session_id = ?
+             SELECT COALESCE(g.graph_name, ?) AS graph_name
+             FROM graph_nodes n LEFT JOIN graph_node_graphs g ON g.node_id = n.id
+             WHERE n.session_id = ?
              ORDER BY graph_name",
         )?;
 
         let mut graphs = Vec::new();
-        let mut rows = stmt.query(params![session_id, session_id])?;
+        let mut rows = stmt.query(params![session_id, DEFAULT_GRAPH, session_id])?;
         while let Some(row) = rows.next()? {
             let graph_name: String = row.get(0)?;
             graphs.push(graph_name);
         }
 
-        // Always include "default" if we have any nodes
-        if graphs.is_empty() {
-            let node_count: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM graph_nodes WHERE session_id = ?",
-                params![session_id],
-                |row| row.get(0),
-            )?;
-            if node_count > 0 {
-                graphs.push("default".to_string());
-            }
-        }
-
         Ok(graphs)
     }
 
@@ -1625,8 +5294,9 @@ impl Persistence {
         deleted_by: &str,
     ) -> Result<()> {
         let conn = self.conn();
+        // Deleted nodes keep their ID for conflict resolution, not their content
         conn.execute(
-            "UPDATE graph_nodes SET is_deleted = TRUE, vector_clock = ?, last_modified_by = ?, updated_at = CURRENT_TIMESTAMP
+            "UPDATE graph_nodes SET is_deleted = TRUE, properties = '{}', vector_clock = ?, last_modified_by = ?, updated_at = CURRENT_TIMESTAMP
              WHERE id = ?",
             params![vector_clock, deleted_by, node_id],
         )?;
@@ -1642,7 +5312,7 @@ impl Persistence {
     ) -> Result<()> {
         let conn = self.conn();
         conn.execute(
-            "UPDATE graph_edges SET is_deleted = TRUE, vector_clock = ?, last_modified_by = ?
+            "UPDATE graph_edges SET is_deleted = TRUE, properties = NULL, vector_clock = ?, last_modified_by = ?
              WHERE id = ?",
             params![vector_clock, deleted_by, edge_id],
         )?;
@@ -1692,6 +5362,384 @@ impl TokenizedFileRecord {
     }
 }
 
+/// A message waiting in the embedding queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedEmbedding {
+    pub message_id: i64,
+    pub session_id: String,
+    /// Graph node of the message, if one was created
+    pub node_id: Option<i64>,
+    /// Failed attempts so far
+    pub attempts: i64,
+}
+
+/// State of a session's recall inputs, see [`Persistence::session_revision`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRevision {
+    counts: [i64; 8],
+    nodes_updated_at: Option<String>,
+}
+
+const SESSION_RECORD_SELECT: &str = "SELECT session_id, title, agent, tags, message_count, CAST(created_at AS TEXT), CAST(updated_at AS TEXT), CAST(archived_at AS TEXT) FROM sessions";
+
+/// Summary of a session's earlier messages, see
+/// [`Persistence::context_anchor`]
+#[derive(Debug, Clone)]
+pub struct ContextAnchor {
+    /// Last message the summary covers
+    pub through_message_id: i64,
+    pub summary: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Messages condensed into one recallable memory, see
+/// [`Persistence::store_memory_summary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySummary {
+    pub id: i64,
+    pub session_id: String,
+    /// First and last message the summary covers
+    pub first_message_id: i64,
+    pub last_message_id: i64,
+    pub message_count: i64,
+    pub summary: String,
+    /// Row in `memory_vectors`; `None` when the summary was not embedded or
+    /// its memory was deleted
+    pub embedding_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What [`Persistence::archive_session`] deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionArchive {
+    pub messages: usize,
+    pub memory_vectors: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionRecord {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub agent: Option<String>,
+    pub tags: Vec<String>,
+    /// Cached number of stored messages
+    pub message_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the session's messages were folded into its summary, see
+    /// [`Persistence::archive_session`]
+    pub archived_at: Option<DateTime<Utc>>,
+}
+
+impl SessionRecord {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let tags: String = row.get(3)?;
+        let created_at: String = row.get(5)?;
+        let updated_at: String = row.get(6)?;
+        let archived_at: Option<String> = row.get(7)?;
+        Ok(Self {
+            session_id: row.get(0)?,
+            title: row.get(1)?,
+            agent: row.get(2)?,
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+            message_count: row.get(4)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            updated_at: parse_timestamp(&updated_at).unwrap_or_else(Utc::now),
+            archived_at: archived_at.as_deref().and_then(parse_timestamp),
+        })
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Title for display, falling back to the session ID
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.session_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundtableDraftRecord {
+    pub id: i64,
+    pub session_id: String,
+    pub roundtable_id: String,
+    pub agent_name: String,
+    pub is_synthesis: bool,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Timings of one stage aggregated over the recorded steps
+#[derive(Debug, Clone)]
+pub struct StageTimingSummary {
+    pub stage: String,
+    pub category: Option<String>,
+    /// Number of times the stage ran
+    pub count: usize,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+/// An entry in the blob index
+#[derive(Debug, Clone)]
+pub struct BlobRecord {
+    pub hash: String,
+    pub size: u64,
+    pub media_type: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlobRecord {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let size: i64 = row.get(1)?;
+        let created_at: String = row.get(3)?;
+        Ok(Self {
+            hash: row.get(0)?,
+            size: size.max(0) as u64,
+            media_type: row.get(2)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// Outcome of [`Persistence::gc_blobs`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobGcReport {
+    /// Blobs deleted (or that would be, on a dry run)
+    pub removed: usize,
+    pub bytes_freed: u64,
+    /// Blobs still referenced
+    pub kept: usize,
+}
+
+/// How long the mesh leader keeps heartbeat stats samples
+pub const MESH_STATS_RETENTION_SECS: i64 = 60 * 60;
+
+/// Activity of one mesh instance, sent with each heartbeat
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct InstanceStats {
+    /// Sessions with activity in the last few minutes
+    pub active_sessions: u64,
+    /// Provider tokens used since the instance started
+    pub tokens_used: u64,
+    /// Tool calls recorded in the instance's tool log
+    pub tool_calls: u64,
+    /// Seconds since the instance last synced a graph with a peer; `None`
+    /// if it never has
+    pub sync_lag_secs: Option<i64>,
+    /// Round trip of the instance's previous heartbeat
+    pub heartbeat_latency_ms: Option<f64>,
+    /// Usage against the instance's quotas; not kept in `mesh_stats`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<QuotaUsage>,
+}
+
+/// An instance's usage against its `[mesh.quotas]` limits
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct QuotaUsage {
+    /// Agent runs in progress
+    pub running: usize,
+    /// Provider tokens used since UTC midnight
+    pub tokens_today: u64,
+    /// Mesh messages sent in the last hour
+    pub messages_last_hour: u32,
+    pub limits: crate::config::QuotaLimits,
+}
+
+impl InstanceStats {
+    /// The stats as heartbeat `metrics`
+    pub fn to_metrics(&self) -> HashMap<String, JsonValue> {
+        match serde_json::to_value(self) {
+            Ok(JsonValue::Object(map)) => map.into_iter().collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Stats read back from heartbeat `metrics`; unknown keys are ignored
+    pub fn from_metrics(metrics: &HashMap<String, JsonValue>) -> Option<Self> {
+        let map: serde_json::Map<String, JsonValue> = metrics
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        serde_json::from_value(JsonValue::Object(map)).ok()
+    }
+}
+
+/// A stored heartbeat stats sample
+#[derive(Debug, Clone)]
+pub struct MeshStatsRecord {
+    pub instance_id: String,
+    pub stats: InstanceStats,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MeshStatsRecord {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let active_sessions: i64 = row.get(1)?;
+        let tokens_used: i64 = row.get(2)?;
+        let tool_calls: i64 = row.get(3)?;
+        let created_at: String = row.get(6)?;
+        Ok(Self {
+            instance_id: row.get(0)?,
+            stats: InstanceStats {
+                active_sessions: active_sessions.max(0) as u64,
+                tokens_used: tokens_used.max(0) as u64,
+                tool_calls: tool_calls.max(0) as u64,
+                sync_lag_secs: row.get(4)?,
+                heartbeat_latency_ms: row.get(5)?,
+                quota: None,
+            },
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// A repository root registered for a session with `/workspace add`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionWorkspace {
+    pub session_id: String,
+    pub name: String,
+    pub root: PathBuf,
+    /// Whether file tools and code search are scoped to this workspace
+    pub focused: bool,
+    /// Workspace node the bootstrapped graph hangs off
+    pub graph_node_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SessionWorkspace {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let root: String = row.get(2)?;
+        let created_at: String = row.get(5)?;
+        Ok(Self {
+            session_id: row.get(0)?,
+            name: row.get(1)?,
+            root: PathBuf::from(root),
+            focused: row.get(3)?,
+            graph_node_id: row.get(4)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// One transcribed chunk of a recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptSegment {
+    pub id: i64,
+    pub chunk_id: i64,
+    pub text: String,
+    /// When the chunk was transcribed
+    pub timestamp: DateTime<Utc>,
+    /// Offsets into the recording; `None` for chunks stored before they
+    /// were recorded
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    /// Speaker proposed for the chunk, e.g. from a `Name:` prefix
+    pub speaker: Option<String>,
+    /// Audio file the chunk was transcribed from; `None` for live capture
+    pub source_file: Option<String>,
+}
+
+/// Something known about the user across sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserProfileFact {
+    pub id: i64,
+    /// `preference`, `style`, `project` or `other`
+    pub category: String,
+    pub fact: String,
+    /// Session the fact was extracted from; `None` when written by hand
+    pub source_session: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserProfileFact {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let created_at: String = row.get(4)?;
+        let updated_at: String = row.get(5)?;
+        Ok(Self {
+            id: row.get(0)?,
+            category: row.get(1)?,
+            fact: row.get(2)?,
+            source_session: row.get(3)?,
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+            updated_at: parse_timestamp(&updated_at).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// A provider call to record in `model_log`
+#[derive(Debug, Clone)]
+pub struct ModelLogEntry {
+    pub session_id: String,
+    pub run_id: Option<String>,
+    pub agent: Option<String>,
+    /// Step stage that made the call, e.g. `run_step.main_model_call`
+    pub stage: String,
+    pub provider: String,
+    /// Model reported by the response
+    pub model: Option<String>,
+    /// Digest of the full prompt
+    pub prompt_digest: String,
+    /// Size of the full prompt
+    pub prompt_bytes: usize,
+    /// Prompt, possibly truncated
+    pub prompt: String,
+    /// Generation parameters sent with the request
+    pub params: JsonValue,
+    /// Response text, possibly truncated
+    pub response: Option<String>,
+    pub error: Option<String>,
+    /// Whether the prompt or response was cut to the size cap
+    pub truncated: bool,
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub latency_ms: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelLogRecord {
+    pub id: i64,
+    pub entry: ModelLogEntry,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ModelLogRecord {
+    fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let prompt_bytes: i64 = row.get(8)?;
+        let params: String = row.get(10)?;
+        let prompt_tokens: Option<i64> = row.get(14)?;
+        let completion_tokens: Option<i64> = row.get(15)?;
+        let created_at: String = row.get(17)?;
+        Ok(Self {
+            id: row.get(0)?,
+            entry: ModelLogEntry {
+                session_id: row.get(1)?,
+                run_id: row.get(2)?,
+                agent: row.get(3)?,
+                stage: row.get(4)?,
+                provider: row.get(5)?,
+                model: row.get(6)?,
+                prompt_digest: row.get(7)?,
+                prompt_bytes: prompt_bytes.max(0) as usize,
+                prompt: row.get(9)?,
+                params: serde_json::from_str(&params).unwrap_or(JsonValue::Null),
+                response: row.get(11)?,
+                error: row.get(12)?,
+                truncated: row.get(13)?,
+                prompt_tokens: prompt_tokens.map(|n| n.max(0) as u32),
+                completion_tokens: completion_tokens.map(|n| n.max(0) as u32),
+                latency_ms: row.get(16)?,
+            },
+            created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MeshMessageRecord {
     pub id: i64,
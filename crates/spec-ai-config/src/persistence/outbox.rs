@@ -0,0 +1,41 @@
+//! Events waiting to be published to the `[events]` sink
+//!
+//! Graph changes and finished runs are appended to the `event_outbox` table
+//! right after the change itself is written. A publisher reads the outbox in
+//! id order and acknowledges what it delivered, which deletes those rows, so
+//! events survive restarts and reach the sink at least once.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Graph nodes and edges created, updated or deleted
+pub const GRAPH_EVENT: &str = "graph";
+/// An agent run that finished
+pub const RUN_EVENT: &str = "run";
+
+/// One outbox row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEvent {
+    /// Increases with every event, so consumers can drop redeliveries
+    pub id: i64,
+    pub kind: String,
+    pub session_id: String,
+    pub payload: JsonValue,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OutboxEvent {
+    pub(crate) fn from_row(row: &duckdb::Row) -> Result<Self> {
+        let payload: String = row.get(3)?;
+        let created_at: String = row.get(4)?;
+        Ok(Self {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            session_id: row.get(2)?,
+            payload: serde_json::from_str(&payload).unwrap_or(JsonValue::Null),
+            created_at: created_at.parse().unwrap_or_else(|_| Utc::now()),
+        })
+    }
+}
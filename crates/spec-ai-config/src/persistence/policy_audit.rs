@@ -0,0 +1,47 @@
+//! Audit log of policy decisions
+//!
+//! Every tool call an agent is about to make is checked against its policy,
+//! and the outcome lands in `policy_audit`: who asked, the action and
+//! resource checked, whether it was allowed and the rule that decided. Tool
+//! arguments are not stored. `/policy audit` and `GET /v1/policy/audit`
+//! list the latest decisions.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A decision to store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolicyDecisionRecord<'a> {
+    pub session_id: &'a str,
+    pub run_id: &'a str,
+    pub agent: &'a str,
+    pub action: &'a str,
+    pub resource: &'a str,
+    pub allowed: bool,
+    /// The rule that decided; `None` for the engine's default deny
+    pub rule: Option<&'a str>,
+}
+
+/// A stored decision
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyAuditEntry {
+    pub id: i64,
+    pub session_id: String,
+    pub run_id: String,
+    pub agent: String,
+    pub action: String,
+    pub resource: String,
+    pub allowed: bool,
+    pub rule: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PolicyAuditEntry {
+    pub fn effect(&self) -> &'static str {
+        if self.allowed {
+            "allow"
+        } else {
+            "deny"
+        }
+    }
+}
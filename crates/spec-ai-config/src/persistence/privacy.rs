@@ -0,0 +1,430 @@
+//! Personal data kept out of the database
+//!
+//! With `privacy.redact_at_rest` on, emails, phone numbers and names are
+//! replaced by tokens like `[[pii:email:3f9a0c1d2b4e5f60]]` before messages,
+//! graph nodes and profile facts are stored. Each original is encrypted
+//! (ChaCha20-Poly1305) into the `pii_vault` table under a key kept outside
+//! the database, so the file can be shared or synced without exposing it.
+//! Prompts are detokenized just before they go to the model.
+//!
+//! Tokens are a keyed hash of the value, so the same address always gets
+//! the same token and dedup, `/forget` and sync keep working on redacted text.
+//! Names are recognized after cue phrases ("my name is", "call me") and
+//! titles ("Dr.", "Ms."), plus any configured `known_names`; there is no
+//! statistical name recognizer.
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::PrivacyConfig;
+
+/// Environment variable holding the vault key as 64 hex digits
+pub const KEY_ENV: &str = "SPEC_AI_VAULT_KEY";
+
+/// Key file used when `privacy.key_path` is not set
+pub const DEFAULT_KEY_PATH: &str = "~/.spec-ai/vault.key";
+
+/// Hex digits of the keyed hash kept in a token
+const TOKEN_HASH_LEN: usize = 16;
+
+/// Kind of personal data a token stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Name,
+}
+
+impl PiiKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::Name => "name",
+        }
+    }
+
+    /// Form of the value that decides its token, so `Bob@Example.com` and
+    /// `bob@example.com`, or two spellings of one phone number, match
+    fn normalize(&self, value: &str) -> String {
+        match self {
+            Self::Email | Self::Name => value.to_lowercase(),
+            Self::Phone => value.chars().filter(char::is_ascii_digit).collect(),
+        }
+    }
+}
+
+/// Personal data found in a text, by byte range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiMatch {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A value replaced by a token, to be sealed into the vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    pub token: String,
+    pub kind: PiiKind,
+    pub value: String,
+}
+
+fn email_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+    })
+}
+
+fn phone_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\+?\(?\d{1,4}\)?(?:[ .-]?\(?\d{2,4}\)?){2,4}").unwrap())
+}
+
+fn name_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?:(?i:\bmy name is|\bname's|\bcall me|\bsigned,?)\s+|\b(?:Mr|Mrs|Ms|Miss|Dr|Prof)\.?\s+)([A-Z][a-z]+(?:[ -][A-Z][a-z]+){0,2})",
+        )
+        .unwrap()
+    })
+}
+
+fn token_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\[\[pii:(?:email|phone|name):[0-9a-f]{16}\]\]").unwrap())
+}
+
+fn date_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\d{4}[-.]\d{2}[-.]\d{2}$").unwrap())
+}
+
+/// Whether a phone-number candidate stands on its own and looks like one:
+/// 7 to 15 digits, written with a leading `+` or with separators, and not
+/// a date
+fn plausible_phone(text: &str, start: usize, end: usize) -> bool {
+    let candidate = &text[start..end];
+    if date_re().is_match(candidate) {
+        return false;
+    }
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    let separated = candidate.starts_with('+') || candidate.contains([' ', '.', '-', '(']);
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    let isolated = !before.is_some_and(|c| c.is_alphanumeric() || c == '-' || c == ':')
+        && !after.is_some_and(|c| c.is_alphanumeric() || c == '-' || c == ':');
+    (7..=15).contains(&digits) && separated && isolated
+}
+
+/// Personal data in `text`, in order and without overlaps. Existing tokens
+/// are never matched again.
+pub fn detect(text: &str, known_names: &[String]) -> Vec<PiiMatch> {
+    let mut found: Vec<PiiMatch> = Vec::new();
+    for m in email_re().find_iter(text) {
+        found.push(PiiMatch {
+            kind: PiiKind::Email,
+            start: m.start(),
+            end: m.end(),
+        });
+    }
+    for m in phone_re().find_iter(text) {
+        if plausible_phone(text, m.start(), m.end()) {
+            found.push(PiiMatch {
+                kind: PiiKind::Phone,
+                start: m.start(),
+                end: m.end(),
+            });
+        }
+    }
+
+    // Names found after a cue are matched everywhere else in the text too
+    let mut names: Vec<String> = name_re()
+        .captures_iter(text)
+        .filter_map(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+        .collect();
+    names.extend(
+        known_names
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty()),
+    );
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name.to_lowercase()) {
+            continue;
+        }
+        let pattern = format!(r"(?i)\b{}\b", regex::escape(&name));
+        if let Ok(re) = Regex::new(&pattern) {
+            for m in re.find_iter(text) {
+                found.push(PiiMatch {
+                    kind: PiiKind::Name,
+                    start: m.start(),
+                    end: m.end(),
+                });
+            }
+        }
+    }
+
+    let tokens: Vec<(usize, usize)> = token_re()
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    let overlaps = |a: (usize, usize), b: (usize, usize)| a.0 < b.1 && b.0 < a.1;
+
+    // Earliest first, and the longer match where two start together
+    found.sort_by_key(|m| (m.start, std::cmp::Reverse(m.end)));
+    let mut out: Vec<PiiMatch> = Vec::new();
+    for m in found {
+        let span = (m.start, m.end);
+        if tokens.iter().any(|t| overlaps(*t, span)) {
+            continue;
+        }
+        if out.last().is_some_and(|prev| prev.end > m.start) {
+            continue;
+        }
+        out.push(m);
+    }
+    out
+}
+
+/// Tokens in `text`, each listed once
+pub fn find_tokens(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    token_re()
+        .find_iter(text)
+        .map(|m| m.as_str().to_string())
+        .filter(|token| seen.insert(token.clone()))
+        .collect()
+}
+
+/// Keys for tokenizing and sealing personal data
+pub struct PiiVault {
+    token_key: [u8; 32],
+    sealing_key: LessSafeKey,
+    known_names: Vec<String>,
+}
+
+impl std::fmt::Debug for PiiVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PiiVault")
+            .field("known_names", &self.known_names.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PiiVault {
+    /// The vault for `config`, or `None` when redaction is off
+    pub fn from_config(config: &PrivacyConfig) -> Result<Option<Self>> {
+        if !config.redact_at_rest {
+            return Ok(None);
+        }
+        let key = load_key(config.key_path.as_deref().unwrap_or(DEFAULT_KEY_PATH))?;
+        Ok(Some(Self::with_key(key, config.known_names.clone())))
+    }
+
+    pub fn with_key(key: [u8; 32], known_names: Vec<String>) -> Self {
+        let token_key = blake3::derive_key("spec-ai pii vault token", &key);
+        let sealing_key = blake3::derive_key("spec-ai pii vault sealing", &key);
+        let sealing_key = LessSafeKey::new(
+            UnboundKey::new(&CHACHA20_POLY1305, &sealing_key).expect("32-byte ChaCha20 key"),
+        );
+        Self {
+            token_key,
+            sealing_key,
+            known_names,
+        }
+    }
+
+    /// Token standing for `value`
+    pub fn token_for(&self, kind: PiiKind, value: &str) -> String {
+        let input = format!("{}:{}", kind.as_str(), kind.normalize(value));
+        let hash = blake3::keyed_hash(&self.token_key, input.as_bytes()).to_hex();
+        format!("[[pii:{}:{}]]", kind.as_str(), &hash[..TOKEN_HASH_LEN])
+    }
+
+    /// `text` with its personal data replaced by tokens, and what was replaced
+    pub fn redact(&self, text: &str) -> (String, Vec<Redaction>) {
+        let matches = detect(text, &self.known_names);
+        if matches.is_empty() {
+            return (text.to_string(), Vec::new());
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut redactions = Vec::new();
+        let mut last = 0;
+        for m in matches {
+            let value = &text[m.start..m.end];
+            let token = self.token_for(m.kind, value);
+            out.push_str(&text[last..m.start]);
+            out.push_str(&token);
+            last = m.end;
+            if !redactions.iter().any(|r: &Redaction| r.token == token) {
+                redactions.push(Redaction {
+                    token,
+                    kind: m.kind,
+                    value: value.to_string(),
+                });
+            }
+        }
+        out.push_str(&text[last..]);
+        (out, redactions)
+    }
+
+    /// [`redact`](Self::redact) applied to every string inside `value`
+    pub fn redact_json(&self, value: &JsonValue, redactions: &mut Vec<Redaction>) -> JsonValue {
+        match value {
+            JsonValue::String(text) => {
+                let (text, found) = self.redact(text);
+                for r in found {
+                    if !redactions.iter().any(|existing| existing.token == r.token) {
+                        redactions.push(r);
+                    }
+                }
+                JsonValue::String(text)
+            }
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| self.redact_json(item, redactions))
+                    .collect(),
+            ),
+            JsonValue::Object(map) => JsonValue::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.redact_json(v, redactions)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Encrypt `value`, bound to its token. Returns (nonce, ciphertext) as hex.
+    pub fn seal(&self, token: &str, value: &str) -> Result<(String, String)> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("no randomness available for the PII vault"))?;
+        let mut in_out = value.as_bytes().to_vec();
+        self.sealing_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(token.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("sealing PII vault entry failed"))?;
+        Ok((to_hex(&nonce), to_hex(&in_out)))
+    }
+
+    /// Decrypt a vault entry; fails when it was sealed under another key
+    pub fn open(&self, token: &str, nonce: &str, ciphertext: &str) -> Result<String> {
+        let nonce = Nonce::try_assume_unique_for_key(&from_hex(nonce)?)
+            .map_err(|_| anyhow!("bad nonce in PII vault entry {}", token))?;
+        let mut in_out = from_hex(ciphertext)?;
+        let plain = self
+            .sealing_key
+            .open_in_place(nonce, Aad::from(token.as_bytes()), &mut in_out)
+            .map_err(|_| anyhow!("PII vault entry {} does not open with this key", token))?;
+        String::from_utf8(plain.to_vec()).context("PII vault entry is not UTF-8")
+    }
+}
+
+/// Read the vault key from [`KEY_ENV`] or `path`, creating a new random key
+/// file when neither exists
+fn load_key(path: &str) -> Result<[u8; 32]> {
+    if let Ok(hex) = std::env::var(KEY_ENV) {
+        return parse_key(&hex).with_context(|| format!("reading {}", KEY_ENV));
+    }
+    let path = super::expand_tilde(Path::new(path))?;
+    crate::secret_key::load_or_create(&path, "PII vault key", parse_key, |key| to_hex(key))
+}
+
+fn parse_key(hex: &str) -> Result<[u8; 32]> {
+    let bytes = from_hex(hex.trim())?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("vault key must be 64 hex digits"))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        bail!("invalid hex");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("invalid hex")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str, known: &[String]) -> Vec<(PiiKind, String)> {
+        detect(text, known)
+            .into_iter()
+            .map(|m| (m.kind, text[m.start..m.end].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_emails_phones_and_names() {
+        let found = kinds(
+            "Hi, my name is Jane Doe. Mail jane.doe@example.com or call +1 555-123-4567; Jane is out on 2024-01-15.",
+            &[],
+        );
+        assert_eq!(
+            found,
+            vec![
+                (PiiKind::Name, "Jane Doe".to_string()),
+                (PiiKind::Email, "jane.doe@example.com".to_string()),
+                (PiiKind::Phone, "+1 555-123-4567".to_string()),
+            ]
+        );
+
+        let found = kinds("Ask Dr. Smith, or ping bob about it", &["Bob".to_string()]);
+        assert_eq!(
+            found,
+            vec![
+                (PiiKind::Name, "Smith".to_string()),
+                (PiiKind::Name, "bob".to_string()),
+            ]
+        );
+        assert!(kinds("version 1.2.3 built at 10:30, order 1234567", &[]).is_empty());
+    }
+
+    #[test]
+    fn tokens_are_stable_and_round_trip() {
+        let vault = PiiVault::with_key([7u8; 32], Vec::new());
+        let (redacted, found) = vault.redact("Write to Bob@Example.com, bob@example.com");
+        assert_eq!(found.len(), 1);
+        assert!(!redacted.contains("example.com"));
+        assert_eq!(find_tokens(&redacted), vec![found[0].token.clone()]);
+
+        // Already-redacted text is left alone
+        assert_eq!(vault.redact(&redacted).0, redacted);
+
+        let (nonce, sealed) = vault.seal(&found[0].token, &found[0].value).unwrap();
+        assert_eq!(
+            vault.open(&found[0].token, &nonce, &sealed).unwrap(),
+            "Bob@Example.com"
+        );
+        let other = PiiVault::with_key([8u8; 32], Vec::new());
+        assert!(other.open(&found[0].token, &nonce, &sealed).is_err());
+        assert_ne!(
+            other.token_for(PiiKind::Email, "bob@example.com"),
+            found[0].token
+        );
+    }
+}
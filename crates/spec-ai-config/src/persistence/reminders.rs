@@ -0,0 +1,21 @@
+//! Follow-ups scheduled with `remind_me`
+//!
+//! A reminder is a message an agent asked to be posted into its session at
+//! a later time ("check the deploy in 30 minutes"). Reminders are stored
+//! until they are due; whichever process is running then (the API server,
+//! or the REPL when it next starts) posts them and marks them delivered.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A scheduled follow-up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub session_id: String,
+    pub message: String,
+    pub due_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// When it was posted into the session; `None` while pending
+    pub delivered_at: Option<DateTime<Utc>>,
+}
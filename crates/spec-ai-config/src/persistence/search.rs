@@ -0,0 +1,190 @@
+//! Ranking and snippets for cross-session search
+//!
+//! The database narrows candidates with case-insensitive substring matches;
+//! this module scores them. Each query term contributes `1 + ln(tf)` when it
+//! occurs, the sum is scaled by the share of terms that matched (so messages
+//! containing every term outrank ones repeating a single term), and matches
+//! in a session's title or tags count double.
+//!
+//! Semantic search instead ranks every stored embedding by cosine similarity
+//! and reports what the embedding belongs to ([`SemanticSearchHit`]).
+
+use chrono::{DateTime, Utc};
+
+/// Characters shown around the first match in a snippet
+const SNIPPET_CHARS: usize = 160;
+
+/// Boost for matches in session titles and tags
+const METADATA_BOOST: f32 = 2.0;
+
+/// One search result: a message, or the session itself when only its
+/// metadata matched
+#[derive(Debug, Clone)]
+pub struct SessionSearchHit {
+    pub session_id: String,
+    pub session_title: Option<String>,
+    /// Matching message, `None` for title/tag matches
+    pub message_id: Option<i64>,
+    pub role: Option<String>,
+    pub snippet: String,
+    pub score: f32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a semantic search hit was embedded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticHitKind {
+    Message,
+    Transcription,
+    GraphNode,
+    /// Messages condensed by memory compaction
+    Summary,
+}
+
+impl SemanticHitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SemanticHitKind::Message => "message",
+            SemanticHitKind::Transcription => "transcription",
+            SemanticHitKind::GraphNode => "graph_node",
+            SemanticHitKind::Summary => "summary",
+        }
+    }
+}
+
+/// Restricts semantic search; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct SemanticSearchFilter {
+    /// Session ID prefix, e.g. `project-x-` for all sessions of a project
+    pub namespace: Option<String>,
+    /// Agent recorded in the session metadata
+    pub agent: Option<String>,
+    /// Only embeddings created at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only embeddings created before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// One semantic search result with the record it came from
+#[derive(Debug, Clone)]
+pub struct SemanticSearchHit {
+    pub kind: SemanticHitKind,
+    /// Cosine similarity to the query
+    pub score: f32,
+    pub session_id: String,
+    pub session_title: Option<String>,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// Row in `memory_vectors` holding the embedding
+    pub memory_id: i64,
+    /// Row in `messages`, `transcriptions`, `graph_nodes` or
+    /// `memory_summaries`, depending on `kind`
+    pub source_id: i64,
+    /// Message role for message hits
+    pub role: Option<String>,
+}
+
+/// Lowercased, de-duplicated query terms
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+        .map(str::to_lowercase)
+        .filter(|term| !term.is_empty())
+    {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Relevance of `text` for `terms`, `0.0` when no term occurs
+pub fn score_text(text: &str, terms: &[String]) -> f32 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let haystack = text.to_lowercase();
+    let mut matched = 0usize;
+    let mut score = 0.0f32;
+    for term in terms {
+        let tf = haystack.matches(term.as_str()).count();
+        if tf > 0 {
+            matched += 1;
+            score += 1.0 + (tf as f32).ln();
+        }
+    }
+    score * matched as f32 / terms.len() as f32
+}
+
+/// Relevance of a session's title and tags, boosted over message matches
+pub fn score_metadata(title: Option<&str>, tags: &[String], terms: &[String]) -> f32 {
+    let text = format!("{} {}", title.unwrap_or_default(), tags.join(" "));
+    score_text(&text, terms) * METADATA_BOOST
+}
+
+/// Excerpt of `text` centred on the first occurrence of any term
+pub fn snippet(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text.to_lowercase().chars().collect();
+    // Lowercasing can change the length of some characters; fall back to the
+    // start of the text rather than misplacing the window
+    let first = if lower.len() == chars.len() {
+        let lower: String = lower.into_iter().collect();
+        terms
+            .iter()
+            .filter_map(|term| lower.find(term.as_str()))
+            .min()
+            .map(|byte| lower[..byte].chars().count())
+            .unwrap_or(0)
+    } else {
+        0
+    };
+
+    let start = first.saturating_sub(SNIPPET_CHARS / 3);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    let start = end.saturating_sub(SNIPPET_CHARS).min(start);
+    let mut out: String = chars[start..end].iter().collect();
+    out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        out.insert(0, '…');
+    }
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terms_are_normalized_and_deduplicated() {
+        assert_eq!(
+            query_terms("Graph-sync, graph-SYNC  conflicts?"),
+            vec!["graph-sync".to_string(), "conflicts".to_string()]
+        );
+        assert!(query_terms("  ?! ").is_empty());
+    }
+
+    #[test]
+    fn full_coverage_outranks_repetition() {
+        let terms = query_terms("duckdb migration");
+        let both = score_text("The DuckDB migration failed", &terms);
+        let repeated = score_text("duckdb duckdb duckdb duckdb", &terms);
+        assert!(both > repeated);
+        assert_eq!(score_text("nothing relevant", &terms), 0.0);
+        assert!(score_metadata(Some("DuckDB migration"), &[], &terms) > both);
+    }
+
+    #[test]
+    fn snippet_centres_on_first_match() {
+        let text = format!("{} needle here {}", "a ".repeat(200), "b ".repeat(200));
+        let excerpt = snippet(&text, &query_terms("needle"));
+        assert!(excerpt.starts_with('…'));
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.contains("needle here"));
+        assert_eq!(snippet("short text", &query_terms("text")), "short text");
+    }
+}
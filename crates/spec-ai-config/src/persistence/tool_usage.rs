@@ -0,0 +1,107 @@
+//! Per-agent tool usage statistics
+//!
+//! Every tool call adds to its agent's running totals in `tool_usage`: calls,
+//! successes, latency and the tokens of the output the model had to read.
+//! `/tools stats` shows them, and agents stop advertising tools that have
+//! never succeeded once they have failed often enough to be sure. A hidden
+//! tool is offered again once it has gone unused for a cool-down, since the
+//! cause of its failures may have been fixed; failing again hides it anew.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Calls a tool must have failed, without any success, before it stops being
+/// advertised to the agent
+pub const MIN_CALLS_BEFORE_HIDING: i64 = 3;
+
+/// Hours a failing tool stays hidden after its last call before it is
+/// offered again
+pub const RETRY_HIDDEN_AFTER_HOURS: i64 = 24;
+
+/// Totals of one tool for one agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolUsageStats {
+    pub agent: String,
+    pub tool_name: String,
+    pub calls: i64,
+    pub successes: i64,
+    pub total_latency_ms: i64,
+    /// Tokens of all outputs, as counted for the agent's model
+    pub output_tokens: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ToolUsageStats {
+    /// Share of calls that succeeded, in `[0, 1]`
+    pub fn success_rate(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.successes as f64 / self.calls as f64
+    }
+
+    pub fn average_latency_ms(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.total_latency_ms as f64 / self.calls as f64
+    }
+
+    pub fn average_output_tokens(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.output_tokens as f64 / self.calls as f64
+    }
+
+    /// Whether the tool has failed every call, enough times to stop offering it
+    pub fn never_succeeds(&self) -> bool {
+        self.successes == 0 && self.calls >= MIN_CALLS_BEFORE_HIDING
+    }
+
+    /// Whether the tool is kept out of the agent's tool list at `now`: it
+    /// never succeeds and was last called within the cool-down
+    pub fn is_hidden(&self, now: DateTime<Utc>) -> bool {
+        self.never_succeeds()
+            && self
+                .last_used_at
+                .is_some_and(|at| now - at < Duration::hours(RETRY_HIDDEN_AFTER_HOURS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(calls: i64, successes: i64) -> ToolUsageStats {
+        ToolUsageStats {
+            agent: "coder".to_string(),
+            tool_name: "bash".to_string(),
+            calls,
+            successes,
+            total_latency_ms: calls * 40,
+            output_tokens: calls * 100,
+            last_used_at: None,
+        }
+    }
+
+    #[test]
+    fn tools_are_hidden_only_after_repeated_failures() {
+        assert!(!stats(0, 0).never_succeeds());
+        assert!(!stats(MIN_CALLS_BEFORE_HIDING - 1, 0).never_succeeds());
+        assert!(stats(MIN_CALLS_BEFORE_HIDING, 0).never_succeeds());
+        assert!(!stats(10, 1).never_succeeds());
+
+        // Hidden only until the cool-down after the last call has passed
+        let now = Utc::now();
+        let mut failing = stats(MIN_CALLS_BEFORE_HIDING, 0);
+        failing.last_used_at = Some(now - Duration::hours(1));
+        assert!(failing.is_hidden(now));
+        failing.last_used_at = Some(now - Duration::hours(RETRY_HIDDEN_AFTER_HOURS + 1));
+        assert!(!failing.is_hidden(now));
+
+        let stats = stats(4, 3);
+        assert_eq!(stats.success_rate(), 0.75);
+        assert_eq!(stats.average_latency_ms(), 40.0);
+        assert_eq!(stats.average_output_tokens(), 100.0);
+    }
+}
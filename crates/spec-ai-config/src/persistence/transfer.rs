@@ -0,0 +1,538 @@
+//! Copying a session from one database into another
+//!
+//! `spec-ai db copy-session` reads everything a session owns from the
+//! source database into a [`SessionSnapshot`], then writes it to the target
+//! in one transaction. Rows get fresh IDs from the target's sequences, and
+//! every reference between them (a vector's message, a node's embedding, an
+//! edge's endpoints, `message_id` in graph properties) is remapped. Deleted
+//! graph entities are left behind, and the copied graph starts unsynced:
+//! the target has its own history to sync from.
+
+use anyhow::Result;
+use duckdb::{params, Connection};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// What `copy-session` wrote to the target database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionCopyReport {
+    /// ID of the session in the target database
+    pub session_id: String,
+    pub messages: usize,
+    pub memory_vectors: usize,
+    pub graph_nodes: usize,
+    pub graph_edges: usize,
+    pub transcriptions: usize,
+    pub tool_calls: usize,
+}
+
+impl SessionCopyReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} message(s), {} embedding(s), {} graph node(s), {} edge(s), {} transcription(s), {} tool call(s)",
+            self.messages,
+            self.memory_vectors,
+            self.graph_nodes,
+            self.graph_edges,
+            self.transcriptions,
+            self.tool_calls
+        )
+    }
+}
+
+pub(crate) struct SessionMetaRow {
+    pub title: Option<String>,
+    pub agent: Option<String>,
+    pub tags: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+pub(crate) struct MessageRow {
+    pub id: i64,
+    pub role: String,
+    pub content: String,
+    pub created_at: Option<String>,
+}
+
+pub(crate) struct VectorRow {
+    pub id: i64,
+    pub message_id: Option<i64>,
+    pub embedding: String,
+    pub created_at: Option<String>,
+}
+
+pub(crate) struct NodeRow {
+    pub id: i64,
+    pub node_type: String,
+    pub label: String,
+    pub properties: JsonValue,
+    pub embedding_id: Option<i64>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// Named graph holding the node, `None` for the default graph
+    pub graph_name: Option<String>,
+    pub pinned: bool,
+}
+
+pub(crate) struct EdgeRow {
+    pub source_id: i64,
+    pub target_id: i64,
+    pub edge_type: String,
+    pub predicate: Option<String>,
+    pub properties: Option<JsonValue>,
+    pub weight: Option<f32>,
+    pub temporal_start: Option<String>,
+    pub temporal_end: Option<String>,
+    pub created_at: Option<String>,
+}
+
+pub(crate) struct TranscriptionRow {
+    pub chunk_id: i64,
+    pub text: String,
+    pub timestamp: String,
+    pub embedding_id: Option<i64>,
+    pub created_at: Option<String>,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    pub speaker: Option<String>,
+    pub source_file: Option<String>,
+}
+
+pub(crate) struct ToolLogRow {
+    pub agent: Option<String>,
+    pub run_id: Option<String>,
+    pub tool_name: String,
+    pub arguments: String,
+    pub result: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// Everything a session owns, as stored in the source database
+pub(crate) struct SessionSnapshot {
+    pub meta: Option<SessionMetaRow>,
+    pub messages: Vec<MessageRow>,
+    pub vectors: Vec<VectorRow>,
+    /// Graphs registered for the session, with or without nodes
+    pub graphs: Vec<String>,
+    pub active_graph: Option<String>,
+    pub nodes: Vec<NodeRow>,
+    pub edges: Vec<EdgeRow>,
+    pub transcriptions: Vec<TranscriptionRow>,
+    pub tool_log: Vec<ToolLogRow>,
+}
+
+impl SessionSnapshot {
+    pub fn load(conn: &Connection, session_id: &str) -> Result<Self> {
+        let meta = {
+            let mut stmt = conn.prepare(
+                "SELECT title, agent, tags, CAST(created_at AS TEXT), CAST(updated_at AS TEXT)
+                 FROM sessions WHERE session_id = ?",
+            )?;
+            let mut rows = stmt.query(params![session_id])?;
+            match rows.next()? {
+                Some(row) => Some(SessionMetaRow {
+                    title: row.get(0)?,
+                    agent: row.get(1)?,
+                    tags: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                }),
+                None => None,
+            }
+        };
+
+        let messages = query_rows(
+            conn,
+            "SELECT id, role, content, CAST(created_at AS TEXT)
+             FROM messages WHERE session_id = ? ORDER BY id",
+            session_id,
+            |row| {
+                Ok(MessageRow {
+                    id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )?;
+
+        let vectors = query_rows(
+            conn,
+            "SELECT id, message_id, embedding, CAST(created_at AS TEXT)
+             FROM memory_vectors WHERE session_id = ? ORDER BY id",
+            session_id,
+            |row| {
+                Ok(VectorRow {
+                    id: row.get(0)?,
+                    message_id: row.get(1)?,
+                    embedding: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )?;
+
+        let graphs = query_rows(
+            conn,
+            "SELECT graph_name FROM graph_metadata WHERE session_id = ? ORDER BY id",
+            session_id,
+            |row| Ok(row.get(0)?),
+        )?;
+        let active_graph = query_rows(
+            conn,
+            "SELECT graph_name FROM graph_active WHERE session_id = ?",
+            session_id,
+            |row| Ok(row.get(0)?),
+        )?
+        .into_iter()
+        .next();
+
+        let nodes = query_rows(
+            conn,
+            "SELECT n.id, n.node_type, n.label, n.properties, n.embedding_id,
+                    CAST(n.created_at AS TEXT), CAST(n.updated_at AS TEXT), g.graph_name,
+                    p.node_id IS NOT NULL
+             FROM graph_nodes n
+             LEFT JOIN graph_node_graphs g ON g.node_id = n.id
+             LEFT JOIN graph_pins p ON p.node_id = n.id
+             WHERE n.session_id = ? AND NOT COALESCE(n.is_deleted, FALSE)
+             ORDER BY n.id",
+            session_id,
+            |row| {
+                let properties: String = row.get(3)?;
+                Ok(NodeRow {
+                    id: row.get(0)?,
+                    node_type: row.get(1)?,
+                    label: row.get(2)?,
+                    properties: serde_json::from_str(&properties).unwrap_or(JsonValue::Null),
+                    embedding_id: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    graph_name: row.get(7)?,
+                    pinned: row.get(8)?,
+                })
+            },
+        )?;
+
+        let edges = query_rows(
+            conn,
+            "SELECT source_id, target_id, edge_type, predicate, properties, weight,
+                    CAST(temporal_start AS TEXT), CAST(temporal_end AS TEXT),
+                    CAST(created_at AS TEXT)
+             FROM graph_edges
+             WHERE session_id = ? AND NOT COALESCE(is_deleted, FALSE)
+             ORDER BY id",
+            session_id,
+            |row| {
+                let properties: Option<String> = row.get(4)?;
+                Ok(EdgeRow {
+                    source_id: row.get(0)?,
+                    target_id: row.get(1)?,
+                    edge_type: row.get(2)?,
+                    predicate: row.get(3)?,
+                    properties: properties.and_then(|p| serde_json::from_str(&p).ok()),
+                    weight: row.get(5)?,
+                    temporal_start: row.get(6)?,
+                    temporal_end: row.get(7)?,
+                    created_at: row.get(8)?,
+                })
+            },
+        )?;
+
+        let transcriptions = query_rows(
+            conn,
+            "SELECT chunk_id, text, CAST(timestamp AS TEXT), embedding_id,
+                    CAST(created_at AS TEXT), start_ms, end_ms, speaker, source_file
+             FROM transcriptions WHERE session_id = ? ORDER BY id",
+            session_id,
+            |row| {
+                Ok(TranscriptionRow {
+                    chunk_id: row.get(0)?,
+                    text: row.get(1)?,
+                    timestamp: row.get(2)?,
+                    embedding_id: row.get(3)?,
+                    created_at: row.get(4)?,
+                    start_ms: row.get(5)?,
+                    end_ms: row.get(6)?,
+                    speaker: row.get(7)?,
+                    source_file: row.get(8)?,
+                })
+            },
+        )?;
+
+        let tool_log = query_rows(
+            conn,
+            "SELECT agent, run_id, tool_name, arguments, result, success, error,
+                    CAST(created_at AS TEXT)
+             FROM tool_log WHERE session_id = ? ORDER BY id",
+            session_id,
+            |row| {
+                Ok(ToolLogRow {
+                    agent: row.get(0)?,
+                    run_id: row.get(1)?,
+                    tool_name: row.get(2)?,
+                    arguments: row.get(3)?,
+                    result: row.get(4)?,
+                    success: row.get(5)?,
+                    error: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            },
+        )?;
+
+        Ok(Self {
+            meta,
+            messages,
+            vectors,
+            graphs,
+            active_graph,
+            nodes,
+            edges,
+            transcriptions,
+            tool_log,
+        })
+    }
+
+    /// Write the snapshot to `conn` as `session_id`, remapping every ID.
+    /// The caller wraps this in a transaction.
+    pub fn insert(&self, conn: &Connection, session_id: &str) -> Result<SessionCopyReport> {
+        let mut report = SessionCopyReport {
+            session_id: session_id.to_string(),
+            ..Default::default()
+        };
+
+        match &self.meta {
+            Some(meta) => conn.execute(
+                "INSERT INTO sessions (session_id, title, agent, tags, message_count, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP),
+                         COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP))",
+                params![
+                    session_id,
+                    meta.title,
+                    meta.agent,
+                    meta.tags,
+                    self.messages.len() as i64,
+                    meta.created_at,
+                    meta.updated_at
+                ],
+            )?,
+            None => conn.execute(
+                "INSERT INTO sessions (session_id, message_count) VALUES (?, ?)",
+                params![session_id, self.messages.len() as i64],
+            )?,
+        };
+
+        let mut message_ids = HashMap::new();
+        for message in &self.messages {
+            let id: i64 = conn.query_row(
+                "INSERT INTO messages (session_id, role, content, created_at)
+                 VALUES (?, ?, ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP)) RETURNING id",
+                params![
+                    session_id,
+                    message.role,
+                    message.content,
+                    message.created_at
+                ],
+                |row| row.get(0),
+            )?;
+            message_ids.insert(message.id, id);
+        }
+        report.messages = message_ids.len();
+
+        let mut vector_ids = HashMap::new();
+        for vector in &self.vectors {
+            let message_id = vector
+                .message_id
+                .and_then(|id| message_ids.get(&id))
+                .copied();
+            let id: i64 = conn.query_row(
+                "INSERT INTO memory_vectors (session_id, message_id, embedding, created_at)
+                 VALUES (?, ?, ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP)) RETURNING id",
+                params![session_id, message_id, vector.embedding, vector.created_at],
+                |row| row.get(0),
+            )?;
+            vector_ids.insert(vector.id, id);
+        }
+        report.memory_vectors = vector_ids.len();
+
+        for graph_name in &self.graphs {
+            conn.execute(
+                "INSERT INTO graph_metadata (session_id, graph_name) VALUES (?, ?)",
+                params![session_id, graph_name],
+            )?;
+        }
+        if let Some(graph_name) = &self.active_graph {
+            conn.execute(
+                "INSERT INTO graph_active (session_id, graph_name) VALUES (?, ?)",
+                params![session_id, graph_name],
+            )?;
+        }
+
+        let mut node_ids = HashMap::new();
+        for node in &self.nodes {
+            let embedding_id = node
+                .embedding_id
+                .and_then(|id| vector_ids.get(&id))
+                .copied();
+            let properties = remap_message_id(&node.properties, &message_ids);
+            let id: i64 = conn.query_row(
+                "INSERT INTO graph_nodes (session_id, node_type, label, properties, embedding_id, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP),
+                         COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP)) RETURNING id",
+                params![
+                    session_id,
+                    node.node_type,
+                    node.label,
+                    properties.to_string(),
+                    embedding_id,
+                    node.created_at,
+                    node.updated_at
+                ],
+                |row| row.get(0),
+            )?;
+            if let Some(graph_name) = &node.graph_name {
+                conn.execute(
+                    "INSERT INTO graph_node_graphs (node_id, graph_name) VALUES (?, ?)",
+                    params![id, graph_name],
+                )?;
+            }
+            if node.pinned {
+                conn.execute(
+                    "INSERT INTO graph_pins (node_id, session_id) VALUES (?, ?)",
+                    params![id, session_id],
+                )?;
+            }
+            node_ids.insert(node.id, id);
+        }
+        report.graph_nodes = node_ids.len();
+
+        for edge in &self.edges {
+            // Edges to nodes of another session, or to deleted ones, stay behind
+            let (Some(&source_id), Some(&target_id)) =
+                (node_ids.get(&edge.source_id), node_ids.get(&edge.target_id))
+            else {
+                continue;
+            };
+            let properties = edge
+                .properties
+                .as_ref()
+                .map(|p| remap_message_id(p, &message_ids).to_string());
+            conn.execute(
+                "INSERT INTO graph_edges (session_id, source_id, target_id, edge_type, predicate, properties,
+                                          weight, temporal_start, temporal_end, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, COALESCE(?, 1.0), CAST(? AS TIMESTAMP), CAST(? AS TIMESTAMP),
+                         COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP))",
+                params![
+                    session_id,
+                    source_id,
+                    target_id,
+                    edge.edge_type,
+                    edge.predicate,
+                    properties,
+                    edge.weight,
+                    edge.temporal_start,
+                    edge.temporal_end,
+                    edge.created_at
+                ],
+            )?;
+            report.graph_edges += 1;
+        }
+
+        for transcription in &self.transcriptions {
+            let embedding_id = transcription
+                .embedding_id
+                .and_then(|id| vector_ids.get(&id))
+                .copied();
+            conn.execute(
+                "INSERT INTO transcriptions (session_id, chunk_id, text, timestamp, embedding_id, created_at,
+                                             start_ms, end_ms, speaker, source_file)
+                 VALUES (?, ?, ?, CAST(? AS TIMESTAMP), ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP),
+                         ?, ?, ?, ?)",
+                params![
+                    session_id,
+                    transcription.chunk_id,
+                    transcription.text,
+                    transcription.timestamp,
+                    embedding_id,
+                    transcription.created_at,
+                    transcription.start_ms,
+                    transcription.end_ms,
+                    transcription.speaker,
+                    transcription.source_file
+                ],
+            )?;
+            report.transcriptions += 1;
+        }
+
+        for call in &self.tool_log {
+            conn.execute(
+                "INSERT INTO tool_log (session_id, agent, run_id, tool_name, arguments, result, success, error, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, COALESCE(CAST(? AS TIMESTAMP), CURRENT_TIMESTAMP))",
+                params![
+                    session_id,
+                    call.agent,
+                    call.run_id,
+                    call.tool_name,
+                    call.arguments,
+                    call.result,
+                    call.success,
+                    call.error,
+                    call.created_at
+                ],
+            )?;
+            report.tool_calls += 1;
+        }
+
+        Ok(report)
+    }
+}
+
+fn query_rows<T>(
+    conn: &Connection,
+    sql: &str,
+    session_id: &str,
+    map: impl Fn(&duckdb::Row) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params![session_id])?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next()? {
+        out.push(map(row)?);
+    }
+    Ok(out)
+}
+
+/// `properties` with a top-level `message_id` pointing at the copied message
+fn remap_message_id(properties: &JsonValue, message_ids: &HashMap<i64, i64>) -> JsonValue {
+    let mut properties = properties.clone();
+    if let Some(new_id) = properties["message_id"]
+        .as_i64()
+        .and_then(|id| message_ids.get(&id))
+    {
+        properties["message_id"] = JsonValue::from(*new_id);
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn remaps_message_ids_in_properties() {
+        let ids = HashMap::from([(3, 40)]);
+        assert_eq!(
+            remap_message_id(&json!({"message_id": 3, "role": "user"}), &ids),
+            json!({"message_id": 40, "role": "user"})
+        );
+        // Unknown and missing IDs are left alone
+        assert_eq!(
+            remap_message_id(&json!({"message_id": 9}), &ids),
+            json!({"message_id": 9})
+        );
+        assert_eq!(remap_message_id(&json!("text"), &ids), json!("text"));
+    }
+}
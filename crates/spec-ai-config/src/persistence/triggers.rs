@@ -0,0 +1,98 @@
+//! Webhook triggers and the spec runs they start
+//!
+//! A trigger maps an inbound webhook name and its shared secret to a spec
+//! file, with the spec variables to fill from the webhook's JSON payload.
+//! Each accepted webhook becomes a job that runs the spec in a session of
+//! its own. Payloads are not stored; a job's answer is read back from its
+//! session, so `/forget` covers it like any other message.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A registered webhook
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookTrigger {
+    pub id: i64,
+    /// Last segment of the webhook URL, `/v1/hooks/<name>`
+    pub name: String,
+    /// Key of the HMAC-SHA256 signature expected on each delivery; never
+    /// serialized
+    #[serde(skip)]
+    pub secret: String,
+    pub spec_path: String,
+    /// Agent profile to run the spec with; the default agent when `None`
+    pub agent: Option<String>,
+    /// Spec variable name to JSON Pointer (RFC 6901) into the payload
+    pub variables: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a triggered run is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerJobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl TriggerJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(Self::Queued),
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+/// One spec run started by a webhook
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TriggerJob {
+    pub id: i64,
+    pub trigger_id: i64,
+    /// Session the spec runs in
+    pub session_id: String,
+    pub status: TriggerJobStatus,
+    /// Run ID of the finished step
+    pub run_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_statuses_round_trip() {
+        for status in [
+            TriggerJobStatus::Queued,
+            TriggerJobStatus::Running,
+            TriggerJobStatus::Succeeded,
+            TriggerJobStatus::Failed,
+        ] {
+            assert_eq!(TriggerJobStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(TriggerJobStatus::parse("done"), None);
+        assert!(!TriggerJobStatus::Running.is_finished());
+    }
+}
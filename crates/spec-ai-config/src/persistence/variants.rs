@@ -0,0 +1,28 @@
+//! Alternative answers to one user message
+//!
+//! `/retry` asks the model again for the answer to the last user message.
+//! Every answer, the original included, is kept as a variant of that
+//! message's assistant reply; the chosen one is copied into the reply in
+//! `messages`, which is what history, recall and search see. The others stay
+//! in `response_variants` until another pick brings them back.
+
+use chrono::{DateTime, Utc};
+
+/// One answer to a user message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseVariant {
+    /// Row in `response_variants`
+    pub id: i64,
+    pub session_id: String,
+    pub user_message_id: i64,
+    /// Assistant reply in `messages` the variants are answers for
+    pub message_id: i64,
+    pub content: String,
+    /// Model that wrote the answer; `None` for answers recorded before the
+    /// first retry
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    /// Whether this is the answer in the conversation history
+    pub canonical: bool,
+    pub created_at: DateTime<Utc>,
+}
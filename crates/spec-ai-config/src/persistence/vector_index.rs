@@ -0,0 +1,516 @@
+//! Approximate nearest-neighbor index over memory vectors
+//!
+//! [`HnswIndex`] is a hierarchical navigable small world graph over one
+//! session's memory vectors, so recall does not compare the query with every
+//! memory. Vectors stay in `memory_vectors`, which is what persists: the
+//! index is kept in memory, built from the table the first time a session is
+//! recalled in a process and extended as vectors are stored. Rebuilding it
+//! is cheap next to embedding, so it is not written to disk. Small sessions
+//! are searched exactly. [`IndexCache`] keeps the indexes of the sessions
+//! recalled most recently, so recall across many sessions does not hold
+//! every one of them in memory.
+
+use crate::types::MemoryVector;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Links per node on the upper layers
+const M: usize = 16;
+/// Links per node on the bottom layer
+const M0: usize = 2 * M;
+/// Candidates considered when linking a new node
+const EF_CONSTRUCTION: usize = 100;
+/// Candidates considered per query on the bottom layer, or `k` if larger
+const EF_SEARCH: usize = 64;
+/// Highest layer a node can be placed on
+const MAX_LEVEL: usize = 16;
+/// Indexes of up to this many vectors are searched exactly
+pub const EXACT_SEARCH_LIMIT: usize = 1024;
+/// Session indexes an [`IndexCache`] keeps by default
+pub const DEFAULT_CACHED_INDEXES: usize = 64;
+
+struct Node {
+    memory: MemoryVector,
+    /// Embedding scaled to unit length; empty for a zero vector
+    unit: Vec<f32>,
+    /// Neighbors on each layer the node is on, bottom layer first
+    links: Vec<Vec<usize>>,
+    /// Removed nodes still route searches but are never returned
+    removed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Scored {
+    similarity: f32,
+    node: usize,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Memory vectors of one session, ranked by cosine similarity
+pub struct HnswIndex {
+    nodes: Vec<Node>,
+    /// Position in `nodes` of each memory still in the index
+    by_id: HashMap<i64, usize>,
+    entry_point: Option<usize>,
+    rng: StdRng,
+    /// Embedding updates of the indexed memories the index reflects
+    revision: i64,
+}
+
+impl Default for HnswIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HnswIndex {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            by_id: HashMap::new(),
+            entry_point: None,
+            rng: StdRng::seed_from_u64(0x5eed),
+            revision: 0,
+        }
+    }
+
+    /// Number of memories in the index
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// Removed memories still taking up space in the graph
+    pub fn removed_count(&self) -> usize {
+        self.nodes.len() - self.by_id.len()
+    }
+
+    /// Count and highest id of the memories in the index, to compare with
+    /// the table
+    pub fn fingerprint(&self) -> (usize, i64) {
+        (
+            self.by_id.len(),
+            self.by_id.keys().max().copied().unwrap_or(0),
+        )
+    }
+
+    /// Embedding updates the index reflects, to compare with the table's
+    /// `revision` column
+    pub fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    pub fn set_revision(&mut self, revision: i64) {
+        self.revision = revision;
+    }
+
+    pub fn get(&self, id: i64) -> Option<&MemoryVector> {
+        self.by_id.get(&id).map(|&node| &self.nodes[node].memory)
+    }
+
+    /// Memories of the index embedded from `message_id`
+    pub fn memories_of_message(&self, message_id: i64) -> Vec<MemoryVector> {
+        self.by_id
+            .values()
+            .map(|&node| &self.nodes[node].memory)
+            .filter(|memory| memory.message_id == Some(message_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Add a memory; one already in the index is left as it is
+    pub fn insert(&mut self, memory: MemoryVector) {
+        if self.by_id.contains_key(&memory.id) {
+            return;
+        }
+        let level = self.random_level();
+        let unit = unit_vector(&memory.embedding);
+        let new = self.nodes.len();
+        self.by_id.insert(memory.id, new);
+        self.nodes.push(Node {
+            memory,
+            unit: unit.clone(),
+            links: vec![Vec::new(); level + 1],
+            removed: false,
+        });
+
+        let Some(mut entry) = self.entry_point else {
+            self.entry_point = Some(new);
+            return;
+        };
+        let top = self.nodes[entry].links.len() - 1;
+        for layer in (level + 1..=top).rev() {
+            entry = self.greedy_closest(&unit, entry, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&unit, entry, EF_CONSTRUCTION, layer);
+            let neighbors: Vec<usize> = candidates
+                .iter()
+                .map(|scored| scored.node)
+                .filter(|&node| node != new)
+                .take(M)
+                .collect();
+            let max_links = if layer == 0 { M0 } else { M };
+            for &neighbor in &neighbors {
+                self.nodes[neighbor].links[layer].push(new);
+                if self.nodes[neighbor].links[layer].len() > max_links {
+                    self.prune_links(neighbor, layer, max_links);
+                }
+            }
+            self.nodes[new].links[layer] = neighbors;
+            if let Some(closest) = candidates.first() {
+                entry = closest.node;
+            }
+        }
+        if level > top {
+            self.entry_point = Some(new);
+        }
+    }
+
+    /// Take a memory out of the results; returns whether it was in the index
+    pub fn remove(&mut self, id: i64) -> bool {
+        match self.by_id.remove(&id) {
+            Some(node) => {
+                self.nodes[node].removed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `k` memories most similar to `query`, best first
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(MemoryVector, f32)> {
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+        let query = unit_vector(query);
+        let found = if self.len() <= EXACT_SEARCH_LIMIT || k >= self.len() {
+            self.search_exact(&query, k)
+        } else {
+            self.search_approximate(&query, k)
+        };
+        found
+            .into_iter()
+            .map(|scored| (self.nodes[scored.node].memory.clone(), scored.similarity))
+            .collect()
+    }
+
+    fn search_exact(&self, query: &[f32], k: usize) -> Vec<Scored> {
+        let mut scored: Vec<Scored> = self
+            .by_id
+            .values()
+            .map(|&node| Scored {
+                similarity: self.similarity(query, node),
+                node,
+            })
+            .collect();
+        scored.sort_by(|a, b| b.cmp(a));
+        scored.truncate(k);
+        scored
+    }
+
+    fn search_approximate(&self, query: &[f32], k: usize) -> Vec<Scored> {
+        let Some(mut entry) = self.entry_point else {
+            return Vec::new();
+        };
+        for layer in (1..self.nodes[entry].links.len()).rev() {
+            entry = self.greedy_closest(query, entry, layer);
+        }
+        self.search_layer(query, entry, EF_SEARCH.max(k), 0)
+            .into_iter()
+            .filter(|scored| !self.nodes[scored.node].removed)
+            .take(k)
+            .collect()
+    }
+
+    /// Follow links on `layer` towards `query` while that gets closer
+    fn greedy_closest(&self, query: &[f32], mut current: usize, layer: usize) -> usize {
+        let mut best = self.similarity(query, current);
+        loop {
+            let mut moved = false;
+            for &neighbor in &self.nodes[current].links[layer] {
+                let similarity = self.similarity(query, neighbor);
+                if similarity > best {
+                    best = similarity;
+                    current = neighbor;
+                    moved = true;
+                }
+            }
+            if !moved {
+                return current;
+            }
+        }
+    }
+
+    /// Up to `ef` nodes of `layer` closest to `query`, best first
+    fn search_layer(&self, query: &[f32], entry: usize, ef: usize, layer: usize) -> Vec<Scored> {
+        let start = Scored {
+            similarity: self.similarity(query, entry),
+            node: entry,
+        };
+        let mut visited = HashSet::from([entry]);
+        let mut candidates = BinaryHeap::from([start]);
+        let mut found = BinaryHeap::from([Reverse(start)]);
+        while let Some(candidate) = candidates.pop() {
+            let worst = found.peek().map_or(f32::MIN, |w| w.0.similarity);
+            if candidate.similarity < worst && found.len() >= ef {
+                break;
+            }
+            let Some(links) = self.nodes[candidate.node].links.get(layer) else {
+                continue;
+            };
+            for &neighbor in links {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let scored = Scored {
+                    similarity: self.similarity(query, neighbor),
+                    node: neighbor,
+                };
+                let worst = found.peek().map_or(f32::MIN, |w| w.0.similarity);
+                if found.len() < ef || scored.similarity > worst {
+                    candidates.push(scored);
+                    found.push(Reverse(scored));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found.into_sorted_vec().into_iter().map(|r| r.0).collect()
+    }
+
+    /// Keep the `max_links` links of `node` on `layer` closest to it
+    fn prune_links(&mut self, node: usize, layer: usize, max_links: usize) {
+        let unit = self.nodes[node].unit.clone();
+        let mut links: Vec<Scored> = self.nodes[node].links[layer]
+            .iter()
+            .map(|&neighbor| Scored {
+                similarity: self.similarity(&unit, neighbor),
+                node: neighbor,
+            })
+            .collect();
+        links.sort_by(|a, b| b.cmp(a));
+        links.truncate(max_links);
+        self.nodes[node].links[layer] = links.into_iter().map(|scored| scored.node).collect();
+    }
+
+    fn similarity(&self, query: &[f32], node: usize) -> f32 {
+        let unit = &self.nodes[node].unit;
+        if query.is_empty() || query.len() != unit.len() {
+            return 0.0;
+        }
+        query.iter().zip(unit).map(|(a, b)| a * b).sum()
+    }
+
+    /// Layers decay exponentially: a node is on layer `l` with
+    /// probability `M^-l`
+    fn random_level(&mut self) -> usize {
+        let uniform: f64 = 1.0 - self.rng.gen::<f64>();
+        let level = -uniform.ln() / (M as f64).ln();
+        (level as usize).min(MAX_LEVEL)
+    }
+}
+
+/// Indexes of the sessions recalled most recently, by session id. Past
+/// `capacity`, the least recently used index is dropped; it is rebuilt from
+/// the table if its session is recalled again.
+pub struct IndexCache {
+    /// Each index with the tick it was last used at
+    indexes: HashMap<String, (HnswIndex, u64)>,
+    capacity: usize,
+    tick: u64,
+}
+
+impl Default for IndexCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHED_INDEXES)
+    }
+}
+
+impl IndexCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            indexes: HashMap::new(),
+            capacity: capacity.max(1),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    /// Index of `session_id`, empty if it was not cached, as the most
+    /// recently used one
+    pub fn get_or_insert(&mut self, session_id: &str) -> &mut HnswIndex {
+        self.tick += 1;
+        if !self.indexes.contains_key(session_id) && self.indexes.len() >= self.capacity {
+            let oldest = self
+                .indexes
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(session, _)| session.clone());
+            if let Some(oldest) = oldest {
+                self.indexes.remove(&oldest);
+            }
+        }
+        let (index, used) = self
+            .indexes
+            .entry(session_id.to_string())
+            .or_insert_with(|| (HnswIndex::new(), 0));
+        *used = self.tick;
+        index
+    }
+
+    /// Index of `session_id` if it is cached, leaving its recency as it is
+    pub fn get_mut(&mut self, session_id: &str) -> Option<&mut HnswIndex> {
+        self.indexes.get_mut(session_id).map(|(index, _)| index)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut HnswIndex> {
+        self.indexes.values_mut().map(|(index, _)| index)
+    }
+
+    pub fn remove(&mut self, session_id: &str) -> Option<HnswIndex> {
+        self.indexes.remove(session_id).map(|(index, _)| index)
+    }
+}
+
+/// `vector` scaled to unit length, or empty when it has no length
+fn unit_vector(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return Vec::new();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn memory(id: i64, embedding: Vec<f32>) -> MemoryVector {
+        MemoryVector {
+            id,
+            session_id: "s".to_string(),
+            message_id: Some(id),
+            embedding,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn random_vectors(count: usize, dims: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| (0..dims).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn small_indexes_rank_exactly() {
+        let mut index = HnswIndex::new();
+        index.insert(memory(1, vec![1.0, 0.0]));
+        index.insert(memory(2, vec![0.6, 0.8]));
+        index.insert(memory(3, vec![0.0, 0.0]));
+        index.insert(memory(4, vec![1.0, 0.0, 0.0]));
+        // Inserting an id twice keeps the first
+        index.insert(memory(1, vec![0.0, 1.0]));
+        assert_eq!(index.len(), 4);
+
+        let found = index.search(&[2.0, 0.0], 3);
+        let ids: Vec<i64> = found.iter().map(|(m, _)| m.id).collect();
+        assert_eq!(ids[..2], [1, 2]);
+        assert!((found[0].1 - 1.0).abs() < 1e-6);
+        assert!((found[1].1 - 0.6).abs() < 1e-6);
+        // Zero vectors and other dimensions are not similar to anything
+        assert_eq!(found[2].1, 0.0);
+        assert!(index.search(&[1.0, 0.0], 0).is_empty());
+
+        assert_eq!(index.fingerprint(), (4, 4));
+        assert!(index.remove(4));
+        assert!(!index.remove(4));
+        assert_eq!(index.fingerprint(), (3, 3));
+        assert_eq!(index.removed_count(), 1);
+        assert!(index
+            .search(&[1.0, 0.0, 0.0], 10)
+            .iter()
+            .all(|(m, _)| m.id != 4));
+    }
+
+    #[test]
+    fn approximate_search_finds_most_true_neighbors() {
+        let mut index = HnswIndex::new();
+        for (id, vector) in random_vectors(2000, 16, 7).into_iter().enumerate() {
+            index.insert(memory(id as i64 + 1, vector));
+        }
+        // Removed memories are skipped by the graph search too
+        for id in (1..=2000).step_by(10) {
+            index.remove(id);
+        }
+
+        let mut hits = 0;
+        let queries = random_vectors(50, 16, 11);
+        for query in &queries {
+            let query = unit_vector(query);
+            let exact: HashSet<usize> = index
+                .search_exact(&query, 10)
+                .into_iter()
+                .map(|s| s.node)
+                .collect();
+            let approximate = index.search_approximate(&query, 10);
+            assert_eq!(approximate.len(), 10);
+            assert!(approximate.iter().all(|s| !index.nodes[s.node].removed));
+            hits += approximate
+                .iter()
+                .filter(|s| exact.contains(&s.node))
+                .count();
+        }
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall >= 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn caches_drop_the_least_recently_used_index() {
+        let mut cache = IndexCache::new(2);
+        cache.get_or_insert("a").insert(memory(1, vec![1.0, 0.0]));
+        cache.get_or_insert("b").insert(memory(2, vec![0.0, 1.0]));
+        // Using "a" again makes "b" the one to go
+        assert_eq!(cache.get_or_insert("a").len(), 1);
+        cache.get_or_insert("c");
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_mut("b").is_none());
+        assert_eq!(cache.get_mut("a").map(|index| index.len()), Some(1));
+
+        // An evicted session starts over empty
+        assert!(cache.get_or_insert("b").is_empty());
+        assert!(cache.get_mut("a").is_none());
+        assert!(cache.remove("b").is_some());
+        assert_eq!(cache.values_mut().count(), 1);
+    }
+}
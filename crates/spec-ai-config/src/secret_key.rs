@@ -0,0 +1,94 @@
+//! 32-byte secret keys kept in files only their owner can read
+//!
+//! A missing key file is created with `create_new` and mode `0600` in one
+//! step, so the key is never readable by others, not even briefly, and when
+//! two processes create it at once both end up using the same key.
+
+use anyhow::{anyhow, Context, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// Read the key stored at `path`, or create it with a fresh random key.
+/// `what` names the key in errors and logs, `decode` and `encode` convert
+/// between the key and the file's text.
+pub fn load_or_create(
+    path: &Path,
+    what: &str,
+    decode: impl Fn(&str) -> Result<[u8; 32]>,
+    encode: impl Fn(&[u8; 32]) -> String,
+) -> Result<[u8; 32]> {
+    let read = |path: &Path| -> Result<[u8; 32]> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {} {}", what, path.display()))?;
+        decode(&text).with_context(|| format!("reading {} {}", what, path.display()))
+    };
+    if path.exists() {
+        return read(path);
+    }
+
+    let mut key = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| anyhow!("no randomness available for the {}", what))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(path) {
+        Ok(file) => file,
+        // Another process created it first; use theirs
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => return read(path),
+        Err(err) => {
+            return Err(err).with_context(|| format!("creating {} {}", what, path.display()))
+        }
+    };
+    file.write_all(format!("{}\n", encode(&key)).as_bytes())
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("writing {} {}", what, path.display()))?;
+    tracing::info!("Created {} {}", what, path.display());
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(text: &str) -> Result<[u8; 32]> {
+        let bytes: Vec<u8> = text
+            .trim()
+            .split(',')
+            .map(|b| b.parse::<u8>())
+            .collect::<std::result::Result<_, _>>()?;
+        bytes.try_into().map_err(|_| anyhow!("wrong length"))
+    }
+
+    fn encode(key: &[u8; 32]) -> String {
+        key.iter().map(u8::to_string).collect::<Vec<_>>().join(",")
+    }
+
+    #[test]
+    fn creates_a_private_key_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("test.key");
+
+        let created = load_or_create(&path, "test key", decode, encode).unwrap();
+        let loaded = load_or_create(&path, "test key", decode, encode).unwrap();
+        assert_eq!(created, loaded);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Conflict-free replicated property values
+//!
+//! Node properties that several instances update at once, such as mention
+//! counts or tag sets, can be stored as CRDTs. A CRDT property is a JSON
+//! object tagged with `"$crdt"` inside the node's ordinary properties:
+//!
+//! ```json
+//! {"mentions": {"$crdt": "g_counter", "counts": {"node-a": 3, "node-b": 1}},
+//!  "tags": {"$crdt": "or_set", "elements": {"node-a:4f1c…": "rust"}, "removed": []}}
+//! ```
+//!
+//! Two replicas of such a property always merge to the same value, so sync
+//! merges them instead of reporting a conflict.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Key marking a JSON object as a CRDT value
+pub const CRDT_TAG: &str = "$crdt";
+
+/// Grow-only counter: one count per instance, the value is their sum
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GCounter {
+    #[serde(default)]
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl GCounter {
+    pub fn increment(&mut self, instance_id: &str, by: u64) {
+        *self.counts.entry(instance_id.to_string()).or_default() += by;
+    }
+
+    pub fn value(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Keep the higher count seen for each instance
+    pub fn merge(&mut self, other: &GCounter) {
+        for (instance, count) in &other.counts {
+            let entry = self.counts.entry(instance.clone()).or_default();
+            *entry = (*entry).max(*count);
+        }
+    }
+}
+
+/// Observed-remove set: every add gets a unique tag, and a remove only
+/// cancels the tags it has seen, so an add concurrent with a remove wins
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrSet {
+    /// Live elements by add tag
+    #[serde(default)]
+    pub elements: BTreeMap<String, JsonValue>,
+    /// Tags of removed adds, kept so a replica that has not seen the remove
+    /// cannot bring the element back
+    #[serde(default)]
+    pub removed: BTreeSet<String>,
+}
+
+impl OrSet {
+    pub fn add(&mut self, instance_id: &str, value: JsonValue) {
+        let tag = format!("{}:{}", instance_id, uuid::Uuid::new_v4());
+        self.elements.insert(tag, value);
+    }
+
+    /// Remove `value`, returning whether it was present
+    pub fn remove(&mut self, value: &JsonValue) -> bool {
+        let tags: Vec<String> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| *element == value)
+            .map(|(tag, _)| tag.clone())
+            .collect();
+        for tag in &tags {
+            self.elements.remove(tag);
+            self.removed.insert(tag.clone());
+        }
+        !tags.is_empty()
+    }
+
+    pub fn contains(&self, value: &JsonValue) -> bool {
+        self.elements.values().any(|element| element == value)
+    }
+
+    /// Distinct elements in tag order
+    pub fn values(&self) -> Vec<JsonValue> {
+        let mut values: Vec<JsonValue> = Vec::new();
+        for element in self.elements.values() {
+            if !values.contains(element) {
+                values.push(element.clone());
+            }
+        }
+        values
+    }
+
+    pub fn merge(&mut self, other: &OrSet) {
+        self.removed.extend(other.removed.iter().cloned());
+        for (tag, element) in &other.elements {
+            self.elements
+                .entry(tag.clone())
+                .or_insert_with(|| element.clone());
+        }
+        let removed = &self.removed;
+        self.elements.retain(|tag, _| !removed.contains(tag));
+    }
+}
+
+/// A CRDT property value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "$crdt", rename_all = "snake_case")]
+pub enum CrdtValue {
+    GCounter(GCounter),
+    OrSet(OrSet),
+}
+
+impl CrdtValue {
+    /// Parse a property value, if it is a CRDT
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        if value.get(CRDT_TAG).is_none() {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        serde_json::to_value(self).unwrap_or(JsonValue::Null)
+    }
+
+    /// The plain value: the total for a counter, the elements for a set
+    pub fn value(&self) -> JsonValue {
+        match self {
+            CrdtValue::GCounter(counter) => JsonValue::from(counter.value()),
+            CrdtValue::OrSet(set) => JsonValue::Array(set.values()),
+        }
+    }
+
+    /// Merge `other` into this value; `false` if they are different kinds
+    pub fn merge(&mut self, other: &CrdtValue) -> bool {
+        match (self, other) {
+            (CrdtValue::GCounter(ours), CrdtValue::GCounter(theirs)) => ours.merge(theirs),
+            (CrdtValue::OrSet(ours), CrdtValue::OrSet(theirs)) => ours.merge(theirs),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Merge of one property present on either side, if it is a CRDT there
+fn merge_property(local: Option<&JsonValue>, remote: Option<&JsonValue>) -> Option<CrdtValue> {
+    let local_crdt = local.and_then(CrdtValue::from_json);
+    let remote_crdt = remote.and_then(CrdtValue::from_json);
+    match (local_crdt, remote_crdt) {
+        (Some(mut ours), Some(theirs)) => ours.merge(&theirs).then_some(ours),
+        (Some(ours), None) if remote.is_none() => Some(ours),
+        (None, Some(theirs)) if local.is_none() => Some(theirs),
+        _ => None,
+    }
+}
+
+fn property_keys<'a>(
+    local: &'a Map<String, JsonValue>,
+    remote: &'a Map<String, JsonValue>,
+) -> BTreeSet<&'a String> {
+    local.keys().chain(remote.keys()).collect()
+}
+
+/// Write the merge of every CRDT property of `local` and `remote` into
+/// `merged`, leaving other properties as they are
+pub fn merge_crdt_properties(local: &JsonValue, remote: &JsonValue, merged: &mut JsonValue) {
+    let (Some(local_map), Some(remote_map)) = (local.as_object(), remote.as_object()) else {
+        return;
+    };
+    let Some(merged_map) = merged.as_object_mut() else {
+        return;
+    };
+    for key in property_keys(local_map, remote_map) {
+        if let Some(value) = merge_property(local_map.get(key), remote_map.get(key)) {
+            merged_map.insert(key.clone(), value.to_json());
+        }
+    }
+}
+
+/// Whether two versions of a node's properties differ only in CRDT values,
+/// in which case merging them resolves the difference without a conflict
+pub fn only_crdt_properties_differ(local: &JsonValue, remote: &JsonValue) -> bool {
+    let (Some(local_map), Some(remote_map)) = (local.as_object(), remote.as_object()) else {
+        return local == remote;
+    };
+    property_keys(local_map, remote_map).into_iter().all(|key| {
+        let (ours, theirs) = (local_map.get(key), remote_map.get(key));
+        ours == theirs || merge_property(ours, theirs).is_some()
+    })
+}
+
+/// Properties with each CRDT value replaced by its plain value
+pub fn plain_properties(properties: &JsonValue) -> JsonValue {
+    match properties {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let plain = CrdtValue::from_json(value)
+                        .map(|crdt| crdt.value())
+                        .unwrap_or_else(|| value.clone());
+                    (key.clone(), plain)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counters_merge_without_losing_increments() {
+        let mut a = GCounter::default();
+        let mut b = GCounter::default();
+        a.increment("node-a", 3);
+        b.increment("node-b", 2);
+        b.increment("node-a", 1); // stale view of node-a
+
+        let mut merged = a.clone();
+        merged.merge(&b);
+        assert_eq!(merged.value(), 5);
+        let mut other_way = b.clone();
+        other_way.merge(&a);
+        assert_eq!(other_way, merged);
+    }
+
+    #[test]
+    fn concurrent_add_survives_remove() {
+        let mut a = OrSet::default();
+        a.add("node-a", json!("rust"));
+        let mut b = a.clone();
+
+        assert!(a.remove(&json!("rust")));
+        b.add("node-b", json!("rust"));
+        b.add("node-b", json!("sync"));
+
+        let mut merged = a.clone();
+        merged.merge(&b);
+        assert_eq!(merged.values().len(), 2);
+        assert!(merged.contains(&json!("rust")) && merged.contains(&json!("sync")));
+        let mut other_way = b.clone();
+        other_way.merge(&a);
+        assert_eq!(other_way, merged);
+
+        // A remove that has seen every add sticks
+        merged.remove(&json!("rust"));
+        b.merge(&merged);
+        assert!(!b.contains(&json!("rust")));
+    }
+
+    #[test]
+    fn properties_round_trip_and_merge() {
+        let mut counter = GCounter::default();
+        counter.increment("node-a", 2);
+        let local = json!({
+            "name": "Alice",
+            "mentions": CrdtValue::GCounter(counter.clone()).to_json(),
+        });
+        assert_eq!(local["mentions"]["$crdt"], "g_counter");
+        assert_eq!(
+            CrdtValue::from_json(&local["mentions"]),
+            Some(CrdtValue::GCounter(counter))
+        );
+        assert_eq!(CrdtValue::from_json(&json!({"counts": {}})), None);
+
+        let mut remote_counter = GCounter::default();
+        remote_counter.increment("node-b", 5);
+        let remote = json!({
+            "name": "Alice",
+            "mentions": CrdtValue::GCounter(remote_counter).to_json(),
+        });
+        assert!(only_crdt_properties_differ(&local, &remote));
+
+        let mut merged = local.clone();
+        merge_crdt_properties(&local, &remote, &mut merged);
+        assert_eq!(
+            plain_properties(&merged),
+            json!({"name": "Alice", "mentions": 7})
+        );
+
+        let renamed = json!({"name": "Bob", "mentions": remote["mentions"].clone()});
+        assert!(!only_crdt_properties_differ(&local, &renamed));
+    }
+}
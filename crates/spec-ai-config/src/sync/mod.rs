@@ -1,3 +1,5 @@
+pub mod crdt;
 pub mod vector_clock;
 
+pub use crdt::{CrdtValue, GCounter, OrSet};
 pub use vector_clock::{ClockOrder, VectorClock};
@@ -282,4 +282,76 @@ mod tests {
         assert!(display.contains("a: 1"));
         assert!(display.contains("b: 2"));
     }
+
+    mod laws {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Clocks over a few shared instances, so they overlap often
+        fn clock() -> impl Strategy<Value = VectorClock> {
+            prop::collection::hash_map("[a-d]", 1i64..20, 0..4)
+                .prop_map(|versions| VectorClock { versions })
+        }
+
+        fn merged(a: &VectorClock, b: &VectorClock) -> VectorClock {
+            let mut merged = a.clone();
+            merged.merge(b);
+            merged
+        }
+
+        fn reversed(order: ClockOrder) -> ClockOrder {
+            match order {
+                ClockOrder::Before => ClockOrder::After,
+                ClockOrder::After => ClockOrder::Before,
+                other => other,
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn merge_is_commutative(a in clock(), b in clock()) {
+                prop_assert_eq!(merged(&a, &b), merged(&b, &a));
+            }
+
+            #[test]
+            fn merge_is_associative(a in clock(), b in clock(), c in clock()) {
+                prop_assert_eq!(merged(&merged(&a, &b), &c), merged(&a, &merged(&b, &c)));
+            }
+
+            #[test]
+            fn merge_is_idempotent(a in clock(), b in clock()) {
+                prop_assert_eq!(merged(&a, &a), a.clone());
+                let once = merged(&a, &b);
+                prop_assert_eq!(merged(&once, &b), once);
+            }
+
+            #[test]
+            fn merge_dominates_both_sides(a in clock(), b in clock()) {
+                let m = merged(&a, &b);
+                prop_assert!(matches!(m.compare(&a), ClockOrder::After | ClockOrder::Equal));
+                prop_assert!(matches!(m.compare(&b), ClockOrder::After | ClockOrder::Equal));
+            }
+
+            #[test]
+            fn compare_is_antisymmetric(a in clock(), b in clock()) {
+                prop_assert_eq!(a.compare(&b), reversed(b.compare(&a)));
+                prop_assert_eq!(a.compare(&b) == ClockOrder::Equal, a == b);
+            }
+
+            #[test]
+            fn before_is_transitive(a in clock(), b in clock(), c in clock()) {
+                let (ab, bc) = (merged(&a, &b), merged(&merged(&a, &b), &c));
+                if a.happens_before(&ab) && ab.happens_before(&bc) {
+                    prop_assert!(a.happens_before(&bc));
+                }
+            }
+
+            #[test]
+            fn increment_moves_forward(a in clock(), instance in "[a-e]") {
+                let mut next = a.clone();
+                next.increment(&instance);
+                prop_assert_eq!(a.compare(&next), ClockOrder::Before);
+            }
+        }
+    }
 }
@@ -88,6 +88,13 @@ pub struct GraphNode {
     pub updated_at: DateTime<Utc>,
 }
 
+impl GraphNode {
+    /// Text the node's own embedding is computed from
+    pub fn embedding_text(&self) -> String {
+        format!("{} {}", self.label, self.properties)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NodeType {
     Entity,     // Person, place, thing
@@ -97,10 +104,25 @@ pub enum NodeType {
     ToolResult, // Linked to tool_log
     Event,      // Temporal events
     Goal,       // User goals / tasks
+    Lesson,     // Lessons learned from past runs
+    /// Type declared in the `[graph]` configuration, by its lowercase name
+    Other(String),
 }
 
 impl NodeType {
-    pub fn as_str(&self) -> &'static str {
+    /// Types every graph knows, whatever is configured
+    pub const BUILTIN: [NodeType; 8] = [
+        NodeType::Entity,
+        NodeType::Concept,
+        NodeType::Fact,
+        NodeType::Message,
+        NodeType::ToolResult,
+        NodeType::Event,
+        NodeType::Goal,
+        NodeType::Lesson,
+    ];
+
+    pub fn as_str(&self) -> &str {
         match self {
             NodeType::Entity => "entity",
             NodeType::Concept => "concept",
@@ -109,9 +131,13 @@ impl NodeType {
             NodeType::ToolResult => "tool_result",
             NodeType::Event => "event",
             NodeType::Goal => "goal",
+            NodeType::Lesson => "lesson",
+            NodeType::Other(name) => name,
         }
     }
 
+    /// Parse a stored or requested type; names that are not built in are
+    /// kept as [`NodeType::Other`] rather than guessed at
     pub fn from_str(s: &str) -> Self {
         match s.to_ascii_lowercase().as_str() {
             "entity" => NodeType::Entity,
@@ -121,9 +147,14 @@ impl NodeType {
             "tool_result" => NodeType::ToolResult,
             "event" => NodeType::Event,
             "goal" => NodeType::Goal,
-            _ => NodeType::Entity,
+            "lesson" => NodeType::Lesson,
+            other => NodeType::Other(other.to_string()),
         }
     }
+
+    pub fn is_builtin(&self) -> bool {
+        !matches!(self, NodeType::Other(_))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,6 +186,18 @@ pub enum EdgeType {
 }
 
 impl EdgeType {
+    /// Types every graph knows, whatever is configured
+    pub const BUILTIN: [EdgeType; 8] = [
+        EdgeType::RelatesTo,
+        EdgeType::CausedBy,
+        EdgeType::PartOf,
+        EdgeType::Mentions,
+        EdgeType::FollowsFrom,
+        EdgeType::Uses,
+        EdgeType::Produces,
+        EdgeType::DependsOn,
+    ];
+
     pub fn as_str(&self) -> String {
         match self {
             EdgeType::RelatesTo => "RELATES_TO".to_string(),
@@ -208,6 +251,15 @@ pub struct GraphPath {
     pub weight: f32,
 }
 
+/// A node reached during traversal, with the edge that led to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNeighbor {
+    pub node: GraphNode,
+    /// Hops from the starting node
+    pub depth: usize,
+    pub edge: GraphEdge,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphQueryResult {
     pub nodes: Vec<GraphNode>,
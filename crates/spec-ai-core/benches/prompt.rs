@@ -0,0 +1,63 @@
+//! Prompt assembly with growing conversation context
+//!
+//! `cargo bench -p spec-ai-core --bench prompt`
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use spec_ai_core::agent::providers::MockProvider;
+use spec_ai_core::agent::AgentBuilder;
+use spec_ai_core::config::AgentProfile;
+use spec_ai_core::persistence::Persistence;
+use spec_ai_core::types::{Message, MessageRole};
+use std::sync::Arc;
+
+fn context(messages: usize) -> Vec<Message> {
+    (0..messages)
+        .map(|i| Message {
+            id: i as i64,
+            session_id: "bench".to_string(),
+            role: if i % 2 == 0 {
+                MessageRole::User
+            } else {
+                MessageRole::Assistant
+            },
+            content: format!(
+                "Message {}: the build failed in crates/spec-ai-core because a test \
+                 expected three tool calls and saw two; rerun with --nocapture.",
+                i
+            ),
+            created_at: Utc::now(),
+        })
+        .collect()
+}
+
+fn build_prompt(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let agent = AgentBuilder::new()
+        .with_profile(AgentProfile {
+            prompt: Some("You are a careful coding assistant.".to_string()),
+            ..AgentProfile::default()
+        })
+        .with_provider(Arc::new(MockProvider::default()))
+        .with_persistence(Persistence::in_memory().unwrap())
+        .with_session_id("bench")
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("build_prompt");
+    for messages in [0, 10, 100, 1_000] {
+        let context = context(messages);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(messages),
+            &context,
+            |b, context| {
+                b.to_async(&runtime)
+                    .iter(|| agent.build_prompt("Why did the build fail?", context))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build_prompt);
+criterion_main!(benches);
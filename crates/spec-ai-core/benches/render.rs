@@ -0,0 +1,75 @@
+//! Terminal rendering of the REPL's scrollback and agent responses
+//!
+//! `cargo bench -p spec-ai-core --bench render`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use spec_ai_core::cli::formatting::render_markdown;
+use spec_ai_core::cli::scrollback::{LineKind, Scrollback};
+
+const WIDTH: usize = 100;
+const HEIGHT: usize = 40;
+
+fn entry(i: usize) -> String {
+    format!(
+        "Entry {}: the agent read src/main.rs, found the panic in parse_args and \
+         suggested returning an error instead of unwrapping the missing value.",
+        i
+    )
+}
+
+fn scrollback(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scrollback");
+    for entries in [100, 1_000, 5_000] {
+        let mut scrollback = Scrollback::new(WIDTH);
+        for i in 0..entries {
+            scrollback.push(LineKind::Assistant, &entry(i));
+        }
+        group.bench_with_input(
+            BenchmarkId::new("render", entries),
+            &scrollback,
+            |b, scrollback| b.iter(|| scrollback.render(HEIGHT, ("[", "]"))),
+        );
+
+        let mut searched = Scrollback::new(WIDTH);
+        for i in 0..entries {
+            searched.push(LineKind::Assistant, &entry(i));
+        }
+        searched.search("panic", HEIGHT);
+        group.bench_with_input(
+            BenchmarkId::new("render_search", entries),
+            &searched,
+            |b, searched| b.iter(|| searched.render(HEIGHT, ("[", "]"))),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("push", entries),
+            &entries,
+            |b, &entries| {
+                b.iter(|| {
+                    let mut scrollback = Scrollback::new(WIDTH);
+                    for i in 0..entries {
+                        scrollback.push(LineKind::Assistant, &entry(i));
+                    }
+                    scrollback
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn markdown(c: &mut Criterion) {
+    let response: String = (0..50)
+        .map(|i| {
+            format!(
+                "## Step {}\n\nRun `cargo test` and check **every** failure:\n\n\
+                 ```rust\nfn main() {{ println!(\"{}\"); }}\n```\n\n- one\n- two\n\n",
+                i, i
+            )
+        })
+        .collect();
+    c.bench_function("render_markdown", |b| b.iter(|| render_markdown(&response)));
+}
+
+criterion_group!(benches, scrollback, markdown);
+criterion_main!(benches);
@@ -0,0 +1,426 @@
+//! Transcribing recorded audio files
+//!
+//! Files are decoded with symphonia, mixed down to mono, resampled to
+//! 16 kHz and cut into WAV chunks of the configured length. Chunks are
+//! decoded on a blocking thread and handed to the transcription provider
+//! one at a time, so long recordings are never held in memory whole.
+
+use crate::agent::transcript_export;
+use crate::agent::transcription::{TranscriptionConfig, TranscriptionProvider};
+use crate::persistence::Persistence;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tokio::sync::mpsc;
+
+/// Sample rate of the chunks sent to providers (what Whisper expects)
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// File extensions `/listen file` and `audio_transcribe` accept
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "mp3", "m4a"];
+
+/// Chunks decoded ahead of the provider
+const CHUNK_BUFFER: usize = 2;
+
+/// A slice of a recording, encoded as 16-bit mono WAV
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    pub index: usize,
+    /// Offsets of the chunk in the recording
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub wav: Vec<u8>,
+}
+
+/// What the provider heard in one chunk of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscribedChunk {
+    pub index: usize,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+/// Fail early, with a readable message, for files that cannot be decoded
+pub fn check_supported(path: &Path) -> Result<()> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+        bail!(
+            "unsupported audio file {}; expected one of: {}",
+            path.display(),
+            SUPPORTED_EXTENSIONS.join(", ")
+        );
+    }
+    if !path.is_file() {
+        bail!("{} does not exist", path.display());
+    }
+    Ok(())
+}
+
+/// Transcribe `path` chunk by chunk with `provider`. Chunks where nothing
+/// was heard are left out.
+pub async fn transcribe_file(
+    provider: &dyn TranscriptionProvider,
+    path: &Path,
+    config: &TranscriptionConfig,
+) -> Result<Vec<TranscribedChunk>> {
+    check_supported(path)?;
+    let (tx, mut rx) = mpsc::channel::<AudioChunk>(CHUNK_BUFFER);
+    let owned_path = path.to_path_buf();
+    let chunk_secs = config.chunk_duration_secs;
+    let decoder = tokio::task::spawn_blocking(move || {
+        decode_chunks(&owned_path, chunk_secs, |chunk| {
+            tx.blocking_send(chunk)
+                .map_err(|_| anyhow!("transcription stopped"))
+        })
+    });
+
+    let mut transcribed = Vec::new();
+    while let Some(chunk) = rx.recv().await {
+        let text = provider
+            .transcribe_chunk(&chunk, config)
+            .await
+            .with_context(|| {
+                format!(
+                    "transcribing {} at {}s",
+                    path.display(),
+                    chunk.start_ms / 1000
+                )
+            })?;
+        let text = text.trim();
+        if !text.is_empty() {
+            transcribed.push(TranscribedChunk {
+                index: chunk.index,
+                start_ms: chunk.start_ms,
+                end_ms: chunk.end_ms,
+                text: text.to_string(),
+            });
+        }
+    }
+    decoder
+        .await
+        .context("audio decoder panicked")?
+        .with_context(|| format!("decoding {}", path.display()))?;
+    Ok(transcribed)
+}
+
+/// Store `chunks` in the session's transcriptions after its earlier
+/// recordings, with their offsets, proposed speakers and source file.
+/// `chunk_ms` places them when earlier chunks have no recorded offsets.
+/// Returns the new rows' ids, in order.
+pub fn save_chunks(
+    persistence: &Persistence,
+    session_id: &str,
+    chunks: &[TranscribedChunk],
+    source_file: Option<&str>,
+    chunk_ms: i64,
+) -> Result<Vec<i64>> {
+    let earlier = persistence.list_transcript_segments(session_id)?;
+    let first_chunk = earlier
+        .iter()
+        .map(|segment| segment.chunk_id + 1)
+        .max()
+        .unwrap_or(0);
+    let offset_ms = earlier
+        .iter()
+        .filter_map(|segment| segment.end_ms)
+        .max()
+        .unwrap_or(first_chunk * chunk_ms);
+
+    let mut ids = Vec::with_capacity(chunks.len());
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let id = persistence.insert_transcription(
+            session_id,
+            first_chunk + idx as i64,
+            &chunk.text,
+            chrono::Utc::now(),
+        )?;
+        persistence.update_transcription_segment(
+            id,
+            offset_ms + chunk.start_ms,
+            offset_ms + chunk.end_ms,
+            transcript_export::propose_speaker(&chunk.text).as_deref(),
+            source_file,
+        )?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Decode `path` and pass each chunk of `chunk_secs` to `emit` in order.
+/// The last chunk holds whatever is left and may be shorter.
+pub fn decode_chunks(
+    path: &Path,
+    chunk_secs: f64,
+    mut emit: impl FnMut(AudioChunk) -> Result<()>,
+) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .context("unrecognised audio format")?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("no audio track found"))?;
+    let track_id = track.id;
+    let source_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| anyhow!("audio track has no sample rate"))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("unsupported audio codec")?;
+
+    let chunk_samples = ((chunk_secs.max(0.1) * SAMPLE_RATE as f64) as usize).max(1);
+    let mut chunker = Chunker::new(chunk_samples);
+    let mut resampler = Resampler::new(source_rate);
+    let mut resampled = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(err) => return Err(err).context("reading audio packet"),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame; the rest of the file is usually fine
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(err) => return Err(err).context("decoding audio packet"),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        samples.copy_interleaved_ref(decoded);
+
+        resampled.clear();
+        for frame in samples.samples().chunks(channels) {
+            let mono = frame.iter().sum::<f32>() / frame.len() as f32;
+            resampler.push(mono, &mut resampled);
+        }
+        for chunk in chunker.push(&resampled) {
+            emit(chunk)?;
+        }
+    }
+    if let Some(chunk) = chunker.finish() {
+        emit(chunk)?;
+    }
+    Ok(())
+}
+
+/// 16-bit mono WAV at [`SAMPLE_RATE`]
+pub fn encode_wav(samples: &[f32]) -> Vec<u8> {
+    let pcm: Vec<i16> = samples
+        .iter()
+        .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect();
+    wav_bytes(&pcm, SAMPLE_RATE, 1)
+}
+
+fn wav_bytes(pcm: &[i16], sample_rate: u32, channels: u16) -> Vec<u8> {
+    let data_len = (pcm.len() * 2) as u32;
+    let block_align = channels * 2;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in pcm {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
+
+/// Linear interpolation from the file's rate to [`SAMPLE_RATE`], fed one
+/// sample at a time so it works across packet boundaries
+struct Resampler {
+    /// Source samples per output sample
+    step: f64,
+    /// Source position of the next output sample
+    next: f64,
+    seen: u64,
+    last: f32,
+}
+
+impl Resampler {
+    fn new(source_rate: u32) -> Self {
+        Self {
+            step: source_rate as f64 / SAMPLE_RATE as f64,
+            next: 0.0,
+            seen: 0,
+            last: 0.0,
+        }
+    }
+
+    fn push(&mut self, sample: f32, out: &mut Vec<f32>) {
+        let position = self.seen as f64;
+        while self.next <= position {
+            if self.seen == 0 {
+                out.push(sample);
+            } else {
+                let t = (self.next - (position - 1.0)) as f32;
+                out.push(self.last + (sample - self.last) * t);
+            }
+            self.next += self.step;
+        }
+        self.last = sample;
+        self.seen += 1;
+    }
+}
+
+/// Cuts the resampled signal into chunks and tracks their offsets
+struct Chunker {
+    chunk_samples: usize,
+    pending: Vec<f32>,
+    emitted_samples: u64,
+    index: usize,
+}
+
+impl Chunker {
+    fn new(chunk_samples: usize) -> Self {
+        Self {
+            chunk_samples,
+            pending: Vec::with_capacity(chunk_samples),
+            emitted_samples: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) -> Vec<AudioChunk> {
+        self.pending.extend_from_slice(samples);
+        let mut ready = Vec::new();
+        while self.pending.len() >= self.chunk_samples {
+            let rest = self.pending.split_off(self.chunk_samples);
+            let full = std::mem::replace(&mut self.pending, rest);
+            ready.push(self.chunk(&full));
+        }
+        ready
+    }
+
+    fn finish(&mut self) -> Option<AudioChunk> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let rest = std::mem::take(&mut self.pending);
+        Some(self.chunk(&rest))
+    }
+
+    fn chunk(&mut self, samples: &[f32]) -> AudioChunk {
+        let to_ms = |samples: u64| (samples * 1000 / SAMPLE_RATE as u64) as i64;
+        let start = self.emitted_samples;
+        self.emitted_samples += samples.len() as u64;
+        let chunk = AudioChunk {
+            index: self.index,
+            start_ms: to_ms(start),
+            end_ms: to_ms(self.emitted_samples),
+            wav: encode_wav(samples),
+        };
+        self.index += 1;
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::transcription_providers::MockTranscriptionProvider;
+    use std::path::PathBuf;
+
+    fn write_tone(dir: &Path, name: &str, secs: f64, rate: u32, channels: u16) -> PathBuf {
+        let frames = (secs * rate as f64) as usize;
+        let pcm: Vec<i16> = (0..frames)
+            .flat_map(|i| {
+                let t = i as f64 / rate as f64;
+                let value = ((t * 440.0 * std::f64::consts::TAU).sin() * 8000.0) as i16;
+                std::iter::repeat_n(value, channels as usize)
+            })
+            .collect();
+        let path = dir.join(name);
+        std::fs::write(&path, wav_bytes(&pcm, rate, channels)).unwrap();
+        path
+    }
+
+    #[test]
+    fn decodes_files_into_timed_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_tone(dir.path(), "stereo.wav", 2.5, 44_100, 2);
+
+        let mut chunks = Vec::new();
+        decode_chunks(&path, 1.0, |chunk| {
+            chunks.push(chunk);
+            Ok(())
+        })
+        .unwrap();
+
+        let spans: Vec<(usize, i64, i64)> = chunks
+            .iter()
+            .map(|chunk| (chunk.index, chunk.start_ms, chunk.end_ms))
+            .collect();
+        assert_eq!(spans, vec![(0, 0, 1000), (1, 1000, 2000), (2, 2000, 2500)]);
+        // One second of 16 kHz mono PCM plus the header
+        assert_eq!(chunks[0].wav.len(), 44 + 2 * SAMPLE_RATE as usize);
+        assert_eq!(&chunks[0].wav[..4], b"RIFF");
+    }
+
+    #[tokio::test]
+    async fn transcribes_files_through_the_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_tone(dir.path(), "standup.wav", 2.2, 16_000, 1);
+        let provider = MockTranscriptionProvider::with_transcriptions(vec![
+            "Alice: ship it on Friday".to_string(),
+            " ".to_string(),
+        ]);
+        let config = TranscriptionConfig {
+            chunk_duration_secs: 1.0,
+            ..Default::default()
+        };
+
+        let chunks = transcribe_file(&provider, &path, &config).await.unwrap();
+        // The second chunk heard nothing and is dropped
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "Alice: ship it on Friday");
+        assert_eq!((chunks[1].index, chunks[1].start_ms), (2, 2000));
+
+        let missing = transcribe_file(&provider, &dir.path().join("notes.txt"), &config).await;
+        assert!(missing.unwrap_err().to_string().contains("unsupported"));
+    }
+}
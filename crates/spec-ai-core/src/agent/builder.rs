@@ -11,15 +11,23 @@ use crate::agent::providers::openai::OpenAIProvider;
 use crate::agent::providers::LMStudioProvider;
 #[cfg(feature = "mlx")]
 use crate::agent::providers::MLXProvider;
-use crate::config::{AgentProfile, AgentRegistry, AppConfig, ModelConfig};
+use crate::agent::routing::ModelRoute;
+use crate::agent::transcription::TranscriptionConfig;
+use crate::agent::transcription_factory::{
+    create_transcription_provider, TranscriptionProviderConfig,
+};
+use crate::clock::Clock;
+use crate::config::{AgentProfile, AgentRegistry, AppConfig, ModelConfig, TaskClass};
 use crate::embeddings::EmbeddingsClient;
 use crate::persistence::Persistence;
-use crate::policy::PolicyEngine;
+use crate::policy::{PolicyEngine, PolicyPreset};
+use crate::tools::builtin::AudioTranscriptionTool;
 use crate::tools::ToolRegistry;
 use anyhow::{anyhow, Context, Result};
 #[cfg(any(feature = "mlx", feature = "lmstudio"))]
 use async_openai::config::OpenAIConfig;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{info, warn};
 
 /// Builder for constructing AgentCore instances
@@ -126,7 +134,10 @@ impl AgentBuilder {
         let persistence = if let Some(persistence) = self.persistence {
             persistence
         } else if let Some(ref config) = self.config {
-            Persistence::new(&config.database.path).context("Failed to create persistence layer")?
+            Persistence::new(&config.database.path)
+                .context("Failed to create persistence layer")?
+                .with_privacy(&config.privacy)?
+                .with_events(&config.events)
         } else {
             return Err(anyhow!(
                 "Either persistence or config must be provided to build agent"
@@ -142,14 +153,42 @@ impl AgentBuilder {
             None
         };
 
+        let clock = match &self.config {
+            Some(config) => {
+                Clock::from_config(&config.time).context("Invalid [time] configuration")?
+            }
+            None => Clock::default(),
+        };
+
         // Get or create tool registry (defaults to built-in tools)
         // Create this before the provider so OpenAI can be configured with tools
         let tool_registry = if let Some(registry) = self.tool_registry {
             registry
         } else {
             let persistence_arc = Arc::new(persistence.clone());
-            let mut registry =
-                ToolRegistry::with_builtin_tools(Some(persistence_arc), embeddings_client.clone());
+            let mut registry = ToolRegistry::with_builtin_tools(
+                Some(persistence_arc.clone()),
+                embeddings_client.clone(),
+            );
+            // Recorded files go through the configured transcription provider
+            // rather than the mock one
+            if let Some(ref config) = self.config {
+                match create_transcription_provider(&TranscriptionProviderConfig::from(
+                    &config.audio,
+                )) {
+                    Ok(provider) => registry.register(Arc::new(
+                        AudioTranscriptionTool::with_persistence(persistence_arc)
+                            .with_transcription_provider(
+                                provider,
+                                TranscriptionConfig::from(&config.audio),
+                            ),
+                    )),
+                    Err(e) => warn!(
+                        "Audio files will use the mock transcription provider: {}",
+                        e
+                    ),
+                }
+            }
             info!(
                 "Created tool registry with {} builtin tools",
                 registry.len()
@@ -190,9 +229,29 @@ impl AgentBuilder {
                 }
             }
 
+            if let Some(ref config) = self.config {
+                registry = registry
+                    .with_clock(clock.clone())
+                    .with_composite_tools(&config.composite_tools)
+                    .context("Invalid composite tool configuration")?
+                    .with_settings(&config.tools)
+                    .with_graph_types(&config.graph);
+            }
+
             Arc::new(registry)
         };
 
+        // Tools that failed every call this agent made stay out of the
+        // native function-calling schema
+        #[cfg(any(feature = "openai", feature = "mlx", feature = "lmstudio"))]
+        let hidden_tools = if profile.advertise_failing_tools {
+            Default::default()
+        } else {
+            persistence
+                .failing_tools(self.agent_name.as_deref().unwrap_or("unknown"))
+                .unwrap_or_default()
+        };
+
         // Get or create provider with tools configured (for OpenAI-compatible providers)
         let provider = if let Some(provider) = self.provider {
             provider
@@ -204,7 +263,7 @@ impl AgentBuilder {
             #[cfg(feature = "openai")]
             {
                 if base_provider.kind() == ProviderKind::OpenAI {
-                    let tools = tool_registry.to_openai_tools();
+                    let tools = tool_registry.to_openai_tools(&hidden_tools);
                     if !tools.is_empty() {
                         info!(
                             "Configuring OpenAI provider with {} tools for native function calling",
@@ -237,7 +296,7 @@ impl AgentBuilder {
             #[cfg(feature = "mlx")]
             {
                 if base_provider.kind() == ProviderKind::MLX {
-                    let tools = tool_registry.to_openai_tools();
+                    let tools = tool_registry.to_openai_tools(&hidden_tools);
                     if !tools.is_empty() {
                         info!(
                             "Configuring MLX provider with {} tools for native function calling",
@@ -268,7 +327,7 @@ impl AgentBuilder {
             #[cfg(feature = "lmstudio")]
             {
                 if base_provider.kind() == ProviderKind::LMStudio {
-                    let tools = tool_registry.to_openai_tools();
+                    let tools = tool_registry.to_openai_tools(&hidden_tools);
                     if !tools.is_empty() {
                         info!(
                             "Configuring LM Studio provider with {} tools for native function calling",
@@ -307,6 +366,10 @@ impl AgentBuilder {
         let session_id = self
             .session_id
             .unwrap_or_else(|| format!("session-{}", chrono::Utc::now().timestamp_millis()));
+        // Opening an archived session thaws it; it continues from its summary
+        if persistence.thaw_session(&session_id)? {
+            info!("Thawed archived session '{}'", session_id);
+        }
 
         // Get or create policy engine (defaults to empty policy engine, or load from persistence)
         let policy_engine = if let Some(engine) = self.policy_engine {
@@ -315,21 +378,10 @@ impl AgentBuilder {
             // Try to load from persistence, or create empty engine with default allow rule
             let mut engine = PolicyEngine::load_from_persistence(&persistence)
                 .unwrap_or_else(|_| PolicyEngine::new());
-
-            // If the policy engine has no rules at all, add a default allow-all for tools
-            if engine.rule_count() == 0 {
-                tracing::debug!(
-                    "Empty policy engine detected, adding default allow-all rule for tools"
-                );
-                engine.add_rule(crate::policy::PolicyRule {
-                    agent: "*".to_string(),
-                    action: "tool_call".to_string(),
-                    resource: "*".to_string(),
-                    effect: crate::policy::PolicyEffect::Allow,
-                });
+            if let Some(path) = self.config.as_ref().and_then(|c| c.policy_file.as_deref()) {
+                engine.merge_file(path)?;
             }
-
-            Arc::new(engine)
+            Arc::new(with_profile_policy(engine, &profile))
         };
 
         let fast_provider = if profile.fast_reasoning {
@@ -359,6 +411,80 @@ impl AgentBuilder {
             None
         };
 
+        // A configured critic profile reviews answers with its own model;
+        // without one, verification falls back to the fast provider
+        let critic_provider = match (&profile.critic_profile, &self.config) {
+            (Some(critic_name), Some(config)) if profile.verify_responses => {
+                match config.agents.get(critic_name) {
+                    Some(critic) => {
+                        let critic_config = ModelConfig {
+                            provider: critic
+                                .model_provider
+                                .clone()
+                                .unwrap_or_else(|| config.model.provider.clone()),
+                            model_name: critic
+                                .model_name
+                                .clone()
+                                .or_else(|| config.model.model_name.clone()),
+                            embeddings_model: None,
+                            api_key_source: None,
+                            temperature: critic.temperature.unwrap_or(config.model.temperature),
+                        };
+                        match create_provider(&critic_config) {
+                            Ok(provider) => Some(provider),
+                            Err(err) => {
+                                warn!(
+                                    "Failed to create critic provider for '{}' - {}",
+                                    critic_name, err
+                                );
+                                None
+                            }
+                        }
+                    }
+                    None => {
+                        warn!("Critic profile '{}' not found", critic_name);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // Routes that fail to build are skipped; their task class falls back
+        // to the main model
+        let main_temperature = profile
+            .temperature
+            .or(self.config.as_ref().map(|config| config.model.temperature))
+            .unwrap_or(0.7);
+        let mut routes = Vec::new();
+        for (task, route) in &profile.model_routes {
+            let Some(class) = TaskClass::parse(task) else {
+                warn!("Unknown task class '{}' in model_routes", task);
+                continue;
+            };
+            let route_config = ModelConfig {
+                provider: route.provider.clone(),
+                model_name: route.model_name.clone(),
+                embeddings_model: None,
+                api_key_source: None,
+                temperature: route.temperature.unwrap_or(main_temperature),
+            };
+            let label = match &route.model_name {
+                Some(model_name) => format!("{}/{}", route.provider, model_name),
+                None => route.provider.clone(),
+            };
+            match create_provider(&route_config) {
+                Ok(provider) => routes.push((class, ModelRoute { provider, label })),
+                Err(err) => warn!("Failed to create {} route {} - {}", task, label, err),
+            }
+        }
+
+        let model_name = profile.model_name.clone().or_else(|| {
+            self.config
+                .as_ref()
+                .and_then(|config| config.model.model_name.clone())
+        });
+
         let mut agent = AgentCore::new(
             profile,
             provider,
@@ -373,6 +499,28 @@ impl AgentBuilder {
         if let Some(fast_provider) = fast_provider {
             agent = agent.with_fast_provider(fast_provider);
         }
+        if let Some(critic_provider) = critic_provider {
+            agent = agent.with_critic_provider(critic_provider);
+        }
+        if let Some(model_name) = model_name {
+            agent = agent.with_model_name(model_name);
+        }
+        for (class, route) in routes {
+            agent = agent.with_route(class, route);
+        }
+        agent = agent.with_clock(clock);
+        if let Some(config) = self.config.as_ref().filter(|c| c.logging.model_log) {
+            agent = agent.with_model_log(config.logging.model_log_max_bytes);
+        }
+        if let Some(config) = self.config.as_ref().filter(|c| c.user_profile.enabled) {
+            agent = agent.with_user_profile(config.user_profile.prompt_facts);
+        }
+        if let Some(secs) = self.config.as_ref().and_then(|c| c.tool_timeout_secs) {
+            agent = agent.with_tool_timeout(Duration::from_secs(secs));
+        }
+        // Replies no longer wait for their messages to be embedded
+        agent = agent.with_background_embeddings();
+        agent = agent.with_background_compaction();
 
         Ok(agent)
     }
@@ -384,6 +532,35 @@ impl Default for AgentBuilder {
     }
 }
 
+/// Layer the profile's `policy_preset` under the stored policy rules. Without
+/// a preset, an engine with no rules at all allows every tool call.
+pub fn with_profile_policy(mut engine: PolicyEngine, profile: &AgentProfile) -> PolicyEngine {
+    match profile
+        .policy_preset
+        .as_deref()
+        .and_then(PolicyPreset::parse)
+    {
+        Some(preset) => {
+            tracing::debug!("Applying the '{}' policy preset", preset.as_str());
+            engine.apply_preset(preset);
+        }
+        None if engine.rule_count() == 0 => {
+            tracing::debug!(
+                "Empty policy engine detected, adding default allow-all rule for tools"
+            );
+            engine.add_rule(crate::policy::PolicyRule {
+                agent: "*".to_string(),
+                action: "tool_call".to_string(),
+                resource: "*".to_string(),
+                effect: crate::policy::PolicyEffect::Allow,
+                alternative: None,
+            });
+        }
+        None => {}
+    }
+    engine
+}
+
 /// Create an agent from the active profile in the registry
 pub fn create_agent_from_registry(
     registry: &AgentRegistry,
@@ -395,11 +572,41 @@ pub fn create_agent_from_registry(
         .context("No active agent profile in registry")?
         .ok_or_else(|| anyhow!("No active agent set in registry"))?;
 
+    build_named_agent(registry, config, agent_name, profile, session_id)
+}
+
+/// Create an agent from a named profile in the registry, active or not
+pub fn create_agent_for_profile(
+    registry: &AgentRegistry,
+    config: &AppConfig,
+    agent_name: &str,
+    session_id: Option<String>,
+) -> Result<AgentCore> {
+    let profile = registry
+        .get(agent_name)
+        .ok_or_else(|| anyhow!("Agent profile '{}' not found", agent_name))?;
+
+    build_named_agent(
+        registry,
+        config,
+        agent_name.to_string(),
+        profile,
+        session_id,
+    )
+}
+
+fn build_named_agent(
+    registry: &AgentRegistry,
+    config: &AppConfig,
+    agent_name: String,
+    profile: AgentProfile,
+    session_id: Option<String>,
+) -> Result<AgentCore> {
     let mut builder = AgentBuilder::new()
         .with_profile(profile)
         .with_config(config.clone())
         .with_persistence(registry.persistence().clone())
-        .with_agent_name(agent_name.clone());
+        .with_agent_name(agent_name);
 
     if let Some(sid) = session_id {
         builder = builder.with_session_id(sid);
@@ -408,7 +615,10 @@ pub fn create_agent_from_registry(
     builder.build()
 }
 
-fn create_embeddings_client_from_config(config: &AppConfig) -> Result<Option<EmbeddingsClient>> {
+/// Embeddings client for the configured `embeddings_model`, if any
+pub fn create_embeddings_client_from_config(
+    config: &AppConfig,
+) -> Result<Option<EmbeddingsClient>> {
     let model = &config.model;
     let Some(model_name) = &model.embeddings_model else {
         return Ok(None);
@@ -502,10 +712,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents: HashMap::new(),
             default_agent: None,
         }
@@ -520,6 +744,7 @@ mod tests {
             model_name: None,
             allowed_tools: None,
             denied_tools: None,
+            policy_preset: None,
             memory_k: 10,
             top_p: 0.95,
             max_context_tokens: Some(4096),
@@ -540,6 +765,31 @@ mod tests {
             enable_audio_transcription: false,
             audio_response_mode: "immediate".to_string(),
             audio_scenario: None,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: 168.0,
+            recall_scope: Default::default(),
+            memory_compaction: None,
+            enable_reflection: false,
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            context_overflow_retries: 3,
+            verify_responses: false,
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            model_routes: HashMap::new(),
+            context_compression: None,
+            tool_calling: Default::default(),
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
         }
     }
 
@@ -0,0 +1,122 @@
+//! Conversation compaction
+//!
+//! `/compact` asks the fast model for a summary of the session's messages
+//! since the previous compaction, folding in the previous summary. The
+//! summary becomes the session's context anchor: prompts carry it in place
+//! of the messages it covers, which stay stored for search and recall.
+//! Memory compaction ([`super::memory_compaction`]) summarizes runs of old
+//! messages for recall with [`build_memory_prompt`].
+
+use crate::agent::reflection::truncate;
+use crate::types::{Message, MessageRole};
+
+/// Most messages summarized in one compaction
+pub const MAX_MESSAGES: i64 = 500;
+
+/// Characters of a single message shown to the summarizer
+const MESSAGE_CHARS: usize = 4000;
+
+/// Heading of the summary in prompts
+pub const SUMMARY_HEADING: &str = "Summary of the conversation so far:";
+
+/// Prompt asking for a summary the conversation can continue from
+pub fn build_summary_prompt(previous: Option<&str>, messages: &[Message]) -> String {
+    let mut prompt = String::from(
+        "Summarize the conversation below so that it can be continued from the \
+         summary alone. Keep the user's goals, decisions made, facts established, \
+         file names, commands, numbers and open questions; drop greetings and \
+         repetition. Write plain prose or short bullet points, without a preamble.\n\n",
+    );
+    if let Some(previous) = previous {
+        prompt.push_str(&format!(
+            "Summary of earlier messages:\n{}\n\n",
+            previous.trim()
+        ));
+    }
+    prompt.push_str(&format!(
+        "Conversation:\n{}\nSummary:",
+        transcript(messages)
+    ));
+    prompt
+}
+
+/// Prompt asking for a summary of part of a conversation, recalled later in
+/// place of its messages
+pub fn build_memory_prompt(messages: &[Message]) -> String {
+    format!(
+        "Summarize this part of a conversation as a memory that will be recalled \
+         later in place of its messages. Keep the topics, decisions made, facts \
+         established, names, file names, commands and numbers, so that the summary \
+         makes sense on its own. Write plain prose or short bullet points, without \
+         a preamble.\n\nConversation:\n{}\nSummary:",
+        transcript(messages)
+    )
+}
+
+/// What the summarized messages look like in a prompt, for counting tokens
+pub fn prompt_text(previous: Option<&str>, messages: &[Message]) -> String {
+    let mut text = String::new();
+    if let Some(previous) = previous {
+        text.push_str(&format!("{}\n{}\n\n", SUMMARY_HEADING, previous));
+    }
+    for message in messages {
+        text.push_str(&format!("{}: {}\n", message.role.as_str(), message.content));
+    }
+    text
+}
+
+fn transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .filter(|m| matches!(m.role, MessageRole::User | MessageRole::Assistant))
+        .map(|m| {
+            format!(
+                "{}: {}\n",
+                m.role.as_str(),
+                truncate(m.content.trim(), MESSAGE_CHARS)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn message(id: i64, role: MessageRole, content: &str) -> Message {
+        Message {
+            id,
+            session_id: "s".to_string(),
+            role,
+            content: content.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn prompts_fold_in_the_previous_summary() {
+        let messages = vec![
+            message(3, MessageRole::User, "Use port 8080"),
+            message(4, MessageRole::System, "[Graph Context] noise"),
+            message(
+                5,
+                MessageRole::Assistant,
+                "Done, the server listens on 8080.",
+            ),
+        ];
+        let prompt = build_summary_prompt(Some("The user is setting up a server."), &messages);
+        assert!(prompt.contains("Summary of earlier messages:\nThe user is setting up a server."));
+        assert!(prompt.contains("user: Use port 8080\nassistant: Done"));
+        assert!(!prompt.contains("noise"));
+        assert!(prompt.ends_with("Summary:"));
+
+        let text = prompt_text(None, &messages);
+        assert!(text.starts_with("user: Use port 8080\n"));
+
+        let memory = build_memory_prompt(&messages);
+        assert!(memory.contains("Conversation:\nuser: Use port 8080\nassistant: Done"));
+        assert!(!memory.contains("noise"));
+        assert!(memory.ends_with("Summary:"));
+    }
+}
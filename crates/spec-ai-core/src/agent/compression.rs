@@ -0,0 +1,272 @@
+//! Context compression
+//!
+//! Long recalled messages and tool output crowd everything else out of the
+//! prompt. Items over `context_compression.threshold_tokens` are shrunk
+//! towards `target_ratio` of their size before prompt assembly, either with
+//! the heuristic here or by the fast model (see [`build_summary_prompt`]).
+//!
+//! The heuristic follows the LLMLingua idea of dropping the tokens that carry
+//! the least information: repeated lines are folded, filler words are removed
+//! from prose (code blocks are left alone), and if that is not enough the
+//! middle of the text is cut, keeping the head and tail. Every compression is
+//! scored by how many key terms (numbers, identifiers, paths, names) survive,
+//! which flags compressions that probably lost something that mattered.
+
+use std::collections::HashSet;
+
+/// Key-term retention below which a compression is flagged as degraded
+pub const MIN_KEY_TERM_RETENTION: f32 = 0.8;
+
+/// Words dropped from prose; they rarely change what a sentence says
+const FILLER_WORDS: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "is",
+    "are",
+    "was",
+    "were",
+    "be",
+    "been",
+    "being",
+    "that",
+    "which",
+    "very",
+    "really",
+    "just",
+    "quite",
+    "actually",
+    "basically",
+    "simply",
+    "also",
+    "so",
+    "then",
+    "there",
+    "here",
+    "of",
+    "some",
+    "such",
+    "it",
+    "its",
+    "this",
+    "these",
+    "those",
+    "please",
+    "would",
+    "could",
+    "should",
+    "might",
+    "can",
+    "will",
+    "do",
+    "does",
+    "did",
+];
+
+/// Compress `text` heuristically towards `target_ratio` of its length
+pub fn compress(text: &str, target_ratio: f32) -> String {
+    let target_chars = ((text.chars().count() as f32) * target_ratio).ceil() as usize;
+    let lines = fold_repeats(&prune_filler(text));
+    let compressed = lines.join("\n");
+    if compressed.chars().count() <= target_chars {
+        return compressed;
+    }
+    keep_head_and_tail(&lines, target_chars)
+}
+
+/// Prose lines with filler words removed; fenced code is kept verbatim
+fn prune_filler(text: &str) -> Vec<String> {
+    let mut in_code = false;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().starts_with("```") {
+            in_code = !in_code;
+            lines.push(trimmed.to_string());
+            continue;
+        }
+        if in_code || !is_prose(trimmed) {
+            lines.push(trimmed.to_string());
+            continue;
+        }
+        let kept: Vec<&str> = trimmed
+            .split_whitespace()
+            .filter(|word| {
+                let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+                !FILLER_WORDS.contains(&bare.to_lowercase().as_str())
+            })
+            .collect();
+        lines.push(kept.join(" "));
+    }
+    lines
+}
+
+/// Whether a line reads as sentences rather than code, JSON or a table
+fn is_prose(line: &str) -> bool {
+    let total = line.chars().filter(|c| !c.is_whitespace()).count();
+    if total == 0 {
+        return false;
+    }
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    letters * 10 >= total * 8 && line.split_whitespace().count() >= 4
+}
+
+/// Drop blank runs and fold consecutive identical lines into one
+fn fold_repeats(lines: &[String]) -> Vec<String> {
+    let mut folded: Vec<String> = Vec::new();
+    let mut repeats = 0usize;
+    let mut last: Option<&str> = None;
+    for line in lines {
+        if line.trim().is_empty() {
+            if folded.last().is_some_and(|prev| !prev.is_empty()) {
+                flush_repeats(&mut folded, &mut repeats);
+                folded.push(String::new());
+                last = None;
+            }
+            continue;
+        }
+        if last == Some(line.as_str()) {
+            repeats += 1;
+            continue;
+        }
+        flush_repeats(&mut folded, &mut repeats);
+        folded.push(line.clone());
+        last = Some(line.as_str());
+    }
+    flush_repeats(&mut folded, &mut repeats);
+    while folded.last().is_some_and(|line| line.is_empty()) {
+        folded.pop();
+    }
+    folded
+}
+
+fn flush_repeats(folded: &mut [String], repeats: &mut usize) {
+    if *repeats > 0 {
+        if let Some(line) = folded.last_mut() {
+            line.push_str(&format!(" [repeated {}x]", *repeats + 1));
+        }
+        *repeats = 0;
+    }
+}
+
+/// First and last lines within `budget` characters, split 60/40, with a
+/// marker where lines were cut
+fn keep_head_and_tail(lines: &[String], budget: usize) -> String {
+    let head_budget = budget * 3 / 5;
+    let tail_budget = budget - head_budget;
+
+    let mut head = 0;
+    let mut used = 0;
+    while head < lines.len() && used + lines[head].chars().count() < head_budget {
+        used += lines[head].chars().count() + 1;
+        head += 1;
+    }
+    let mut tail = lines.len();
+    used = 0;
+    while tail > head && used + lines[tail - 1].chars().count() < tail_budget {
+        used += lines[tail - 1].chars().count() + 1;
+        tail -= 1;
+    }
+
+    if head == 0 && tail == lines.len() {
+        // Not even one line fits, so cut inside the text instead
+        let chars: Vec<char> = lines.join("\n").chars().collect();
+        let head: String = chars[..head_budget.min(chars.len())].iter().collect();
+        let tail: String = chars[chars.len().saturating_sub(tail_budget)..]
+            .iter()
+            .collect();
+        return format!("{} [...] {}", head.trim_end(), tail.trim_start());
+    }
+
+    let mut kept: Vec<String> = lines[..head].to_vec();
+    if tail > head {
+        kept.push(format!("[... {} lines omitted ...]", tail - head));
+    }
+    kept.extend_from_slice(&lines[tail..]);
+    kept.join("\n")
+}
+
+/// Numbers, identifiers, paths and proper names in `text`
+pub fn key_terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '"' | '\''))
+        .map(|word| word.trim_matches(|c: char| matches!(c, '.' | ':' | '!' | '?' | '`')))
+        .filter(|word| word.chars().count() >= 2)
+        .filter(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap_or_default();
+            word.chars().any(|c| c.is_ascii_digit())
+                || word.contains(['_', '/', '.', ':'])
+                || (first.is_uppercase() && !FILLER_WORDS.contains(&word.to_lowercase().as_str()))
+                || chars.any(|c| c.is_uppercase())
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Share of `original`'s key terms still present in `compressed` (1.0 when
+/// there were none)
+pub fn key_term_retention(original: &str, compressed: &str) -> f32 {
+    let terms = key_terms(original);
+    if terms.is_empty() {
+        return 1.0;
+    }
+    let kept = terms
+        .iter()
+        .filter(|term| compressed.contains(term.as_str()))
+        .count();
+    kept as f32 / terms.len() as f32
+}
+
+/// Prompt asking the fast model to compress `text` to about `target_tokens`
+pub fn build_summary_prompt(text: &str, target_tokens: usize) -> String {
+    format!(
+        "Compress the following context to at most about {} tokens. Keep every name, \
+         number, identifier, file path, error message and decision exactly as written; \
+         drop filler, repetition and pleasantries. Reply with the compressed text only.\n\n{}",
+        target_tokens, text
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristic_drops_filler_and_repeats_but_keeps_key_terms() {
+        let text =
+            "The deploy of version 2.4.1 was actually blocked by the flaky test in ci/run.sh\n\
+                    retrying connection\n\
+                    retrying connection\n\
+                    retrying connection\n\
+                    \n\n\n\
+                    ```\n\
+                    let the = a;\n\
+                    ```";
+        let compressed = compress(text, 0.9);
+        assert!(compressed.starts_with("deploy version 2.4.1 blocked by flaky test in ci/run.sh"));
+        assert!(compressed.contains("retrying connection [repeated 3x]"));
+        assert!(compressed.contains("let the = a;"));
+        assert!(compressed.len() < text.len());
+        assert_eq!(key_term_retention(text, &compressed), 1.0);
+    }
+
+    #[test]
+    fn cuts_the_middle_when_pruning_is_not_enough() {
+        let lines: Vec<String> = (1..=40).map(|n| format!("row_{} = {}", n, n * 7)).collect();
+        let text = lines.join("\n");
+        let compressed = compress(&text, 0.3);
+        assert!(compressed.starts_with("row_1 = 7"));
+        assert!(compressed.ends_with("row_40 = 280"));
+        assert!(compressed.contains("lines omitted"));
+        assert!(compressed.chars().count() <= (text.chars().count() as f32 * 0.3) as usize + 30);
+
+        let retention = key_term_retention(&text, &compressed);
+        assert!(retention < MIN_KEY_TERM_RETENTION);
+
+        let long_line = "alpha beta gamma delta ".repeat(20);
+        let compressed = compress(&long_line, 0.5);
+        assert!(compressed.starts_with("alpha beta"));
+        assert!(compressed.contains(" [...] "));
+        assert!(compressed.len() < long_line.len() / 2 + 10);
+    }
+}
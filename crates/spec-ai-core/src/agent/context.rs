@@ -0,0 +1,290 @@
+//! Context providers
+//!
+//! A context provider contributes a short block of ambient facts (operating
+//! system, working directory, git state, the spec being run, the clock) to
+//! the prompt. Profiles pick providers by name in `context_providers`, each
+//! with an optional token budget; blocks over budget are cut short.
+//! Additional providers can be registered on [`ContextProviders`].
+
+use crate::clock::Clock;
+use crate::config::ContextProviderConfig;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+/// Rough characters-per-token ratio used for budgets
+const CHARS_PER_TOKEN: usize = 4;
+
+/// What providers may draw on when building their block
+pub struct ContextRequest<'a> {
+    pub clock: &'a Clock,
+    /// Display name of the spec being run, if any
+    pub spec: Option<&'a str>,
+    pub working_dir: Option<PathBuf>,
+}
+
+/// Source of one prompt context block
+pub trait ContextProvider: Send + Sync {
+    /// Name used in `context_providers`
+    fn name(&self) -> &str;
+
+    /// Budget applied when the profile does not set `max_tokens`
+    fn default_max_tokens(&self) -> usize {
+        100
+    }
+
+    /// The block's text, or `None` when there is nothing to report
+    fn provide(&self, request: &ContextRequest<'_>) -> Option<String>;
+}
+
+/// Operating system and architecture
+pub struct OsProvider;
+
+impl ContextProvider for OsProvider {
+    fn name(&self) -> &str {
+        "os"
+    }
+
+    fn default_max_tokens(&self) -> usize {
+        30
+    }
+
+    fn provide(&self, _request: &ContextRequest<'_>) -> Option<String> {
+        Some(format!(
+            "Operating system: {} ({}, {})",
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            std::env::consts::FAMILY
+        ))
+    }
+}
+
+/// Current working directory
+pub struct WorkingDirProvider;
+
+impl ContextProvider for WorkingDirProvider {
+    fn name(&self) -> &str {
+        "cwd"
+    }
+
+    fn default_max_tokens(&self) -> usize {
+        60
+    }
+
+    fn provide(&self, request: &ContextRequest<'_>) -> Option<String> {
+        let dir = request.working_dir.as_ref()?;
+        Some(format!("Working directory: {}", dir.display()))
+    }
+}
+
+/// Branch and uncommitted changes of the git repository in the working directory
+pub struct GitProvider;
+
+impl GitProvider {
+    fn git(dir: &Path, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+impl ContextProvider for GitProvider {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn default_max_tokens(&self) -> usize {
+        60
+    }
+
+    fn provide(&self, request: &ContextRequest<'_>) -> Option<String> {
+        let dir = request.working_dir.as_ref()?;
+        let branch = Self::git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let changes = Self::git(dir, &["status", "--porcelain"])?.lines().count();
+        let status = match changes {
+            0 => "clean".to_string(),
+            1 => "1 uncommitted change".to_string(),
+            n => format!("{} uncommitted changes", n),
+        };
+        Some(format!("Git: branch {}, {}", branch, status))
+    }
+}
+
+/// Name of the spec being executed
+pub struct SpecProvider;
+
+impl ContextProvider for SpecProvider {
+    fn name(&self) -> &str {
+        "spec"
+    }
+
+    fn default_max_tokens(&self) -> usize {
+        40
+    }
+
+    fn provide(&self, request: &ContextRequest<'_>) -> Option<String> {
+        request.spec.map(|spec| format!("Active spec: {}", spec))
+    }
+}
+
+/// Current date, time and upcoming calendar events
+pub struct TimeProvider;
+
+impl ContextProvider for TimeProvider {
+    fn name(&self) -> &str {
+        "time"
+    }
+
+    fn default_max_tokens(&self) -> usize {
+        300
+    }
+
+    fn provide(&self, request: &ContextRequest<'_>) -> Option<String> {
+        Some(request.clock.context_block())
+    }
+}
+
+/// The providers an agent can draw on, looked up by name
+#[derive(Clone)]
+pub struct ContextProviders {
+    providers: Vec<Arc<dyn ContextProvider>>,
+}
+
+impl Default for ContextProviders {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ContextProviders {
+    /// `os`, `cwd`, `git`, `spec` and `time`
+    pub fn builtin() -> Self {
+        Self {
+            providers: vec![
+                Arc::new(OsProvider),
+                Arc::new(WorkingDirProvider),
+                Arc::new(GitProvider),
+                Arc::new(SpecProvider),
+                Arc::new(TimeProvider),
+            ],
+        }
+    }
+
+    /// Add a provider, replacing any existing one with the same name
+    pub fn register(&mut self, provider: Arc<dyn ContextProvider>) {
+        self.providers
+            .retain(|existing| existing.name() != provider.name());
+        self.providers.push(provider);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ContextProvider>> {
+        self.providers
+            .iter()
+            .find(|provider| provider.name() == name)
+    }
+
+    /// Blocks of the `enabled` providers, in order, each within its budget.
+    /// Unknown names are skipped with a warning.
+    pub fn render(
+        &self,
+        enabled: &[ContextProviderConfig],
+        request: &ContextRequest<'_>,
+    ) -> String {
+        let mut blocks = Vec::new();
+        for config in enabled {
+            let Some(provider) = self.get(config.name()) else {
+                tracing::warn!("Unknown context provider '{}'", config.name());
+                continue;
+            };
+            let budget = config
+                .max_tokens()
+                .unwrap_or_else(|| provider.default_max_tokens());
+            if let Some(block) = provider.provide(request) {
+                let block = truncate_to_tokens(block.trim(), budget);
+                if !block.is_empty() {
+                    blocks.push(block);
+                }
+            }
+        }
+        blocks.join("\n")
+    }
+}
+
+/// Rough token count of `text` at the same ratio budgets use
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Cut `text` to roughly `max_tokens`, marking the cut with an ellipsis
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let max_chars = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, &'static str);
+
+    impl ContextProvider for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn provide(&self, _request: &ContextRequest<'_>) -> Option<String> {
+            Some(self.1.to_string())
+        }
+    }
+
+    #[test]
+    fn renders_enabled_providers_in_order_within_budget() {
+        let clock = Clock::default();
+        let request = ContextRequest {
+            clock: &clock,
+            spec: Some("Release notes"),
+            working_dir: Some(PathBuf::from("/work/project")),
+        };
+        let mut providers = ContextProviders::builtin();
+        providers.register(Arc::new(Fixed("notes", "Remember the freeze")));
+        providers.register(Arc::new(Fixed("long", "abcdefghijklmnopqrstuvwxyz")));
+
+        let enabled = vec![
+            ContextProviderConfig::Name("spec".into()),
+            ContextProviderConfig::Name("missing".into()),
+            ContextProviderConfig::Name("cwd".into()),
+            ContextProviderConfig::Name("notes".into()),
+            ContextProviderConfig::Settings {
+                name: "long".into(),
+                max_tokens: Some(2),
+            },
+        ];
+        assert_eq!(
+            providers.render(&enabled, &request),
+            "Active spec: Release notes\nWorking directory: /work/project\nRemember the freeze\nabcdefg…"
+        );
+
+        let no_spec = ContextRequest {
+            spec: None,
+            ..request
+        };
+        assert_eq!(
+            providers.render(&[ContextProviderConfig::Name("spec".into())], &no_spec),
+            ""
+        );
+        assert!(providers
+            .render(&[ContextProviderConfig::Name("os".into())], &no_spec)
+            .starts_with(&format!("Operating system: {}", std::env::consts::OS)));
+    }
+}
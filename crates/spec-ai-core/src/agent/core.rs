@@ -2,25 +2,61 @@
 //!
 //! The heart of the agent system - orchestrates reasoning, memory, and model interaction.
 
-use crate::agent::model::{GenerationConfig, ModelProvider};
+use crate::agent::compaction;
+use crate::agent::compression;
+use crate::agent::context::{estimate_tokens, ContextProvider, ContextProviders, ContextRequest};
+use crate::agent::determinism;
+use crate::agent::embedding_queue::{self, EmbeddingQueue};
+use crate::agent::language::{self, LanguageTarget};
+use crate::agent::memory_compaction::{self, MemoryCompactor};
+use crate::agent::model::{
+    record_token_usage, GenerationConfig, ModelProvider, ModelResponse, TokenUsage,
+};
+use crate::agent::output::ContextSection;
 pub use crate::agent::output::{
-    AgentOutput, GraphDebugInfo, GraphDebugNode, MemoryRecallMatch, MemoryRecallStats,
-    MemoryRecallStrategy, ToolInvocation,
+    AgentOutput, CompactionReport, CompressionReport, ContextDegradation, GraphDebugInfo,
+    GraphDebugNode, GraphInfluence, GraphInfluenceKind, LanguageReport, MemoryRecallMatch,
+    MemoryRecallStats, MemoryRecallStrategy, RouteReport, StageTiming, StepTimings, TimingCategory,
+    ToolInvocation, VerificationReport,
 };
+use crate::agent::overflow::{self, PromptContext};
+use crate::agent::postprocess;
+use crate::agent::react;
+use crate::agent::recall_cache::{RecallCache, DEFAULT_RECALL_CACHE_CAPACITY};
+use crate::agent::reflection;
+use crate::agent::routing::{self, ModelRoute, ModelRouter};
+use crate::agent::titles;
+use crate::agent::transcript_export::{self, ActionItem};
+use crate::agent::user_profile;
+use crate::agent::verification;
+use crate::clock::Clock;
 use crate::config::agent::AgentProfile;
+use crate::config::{
+    CompressionMode, ContextProviderConfig, RecallScope, TaskClass, ToolCallingMode,
+};
 use crate::embeddings::EmbeddingsClient;
-use crate::persistence::Persistence;
-use crate::policy::{PolicyDecision, PolicyEngine};
-use crate::spec::AgentSpec;
-use crate::tools::{ToolRegistry, ToolResult};
-use crate::types::{EdgeType, Message, MessageRole, NodeType, TraversalDirection};
-use anyhow::{Context, Result};
+use crate::panel::PanelSpec;
+use crate::persistence::importance::MemoryImportanceParams;
+use crate::persistence::outbox::RUN_EVENT;
+use crate::persistence::policy_audit::PolicyDecisionRecord;
+use crate::persistence::variants::ResponseVariant;
+use crate::persistence::{MemorySummary, ModelLogEntry, Persistence, SessionArchive};
+use crate::policy::{PolicyDecision, PolicyDenial, PolicyEngine};
+use crate::progress::ProgressReporter;
+use crate::spec::{AgentSpec, SpecPermission};
+use crate::tokenizer;
+use crate::tools::builtin::KnowledgeGraphStore;
+use crate::tools::{CancellationToken, ToolLimits, ToolOutputSink, ToolRegistry, ToolResult};
+use crate::types::{
+    EdgeType, GraphNeighbor, GraphNode, Message, MessageRole, NodeType, TraversalDirection,
+};
+use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use std::sync::Arc;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -29,9 +65,54 @@ const DEFAULT_TOP_P: f32 = 0.9;
 const DEFAULT_FAST_TEMPERATURE: f32 = 0.3;
 const DEFAULT_ESCALATION_THRESHOLD: f32 = 0.6;
 
+#[derive(Clone)]
 struct RecallResult {
     messages: Vec<Message>,
     stats: Option<MemoryRecallStats>,
+    graph: GraphRecallTrace,
+}
+
+/// Graph nodes consulted while recalling context
+#[derive(Clone, Default)]
+struct GraphRecallTrace {
+    influences: Vec<GraphInfluence>,
+    /// Estimated tokens of graph-derived text added to the context
+    tokens: usize,
+}
+
+impl GraphRecallTrace {
+    fn record(&mut self, influence: GraphInfluence, content: &str) {
+        if influence.included {
+            self.tokens += estimate_tokens(content);
+        }
+        self.influences.push(influence);
+    }
+}
+
+fn graph_influence(
+    kind: GraphInfluenceKind,
+    seed_node_id: i64,
+    neighbor: &GraphNeighbor,
+    score: Option<f32>,
+) -> GraphInfluence {
+    GraphInfluence {
+        kind,
+        node_id: neighbor.node.id,
+        node_type: neighbor.node.node_type.as_str().to_string(),
+        label: neighbor.node.label.clone(),
+        seed_node_id: Some(seed_node_id),
+        depth: neighbor.depth,
+        edge: Some(
+            neighbor
+                .edge
+                .predicate
+                .clone()
+                .unwrap_or_else(|| neighbor.edge.edge_type.as_str().to_lowercase()),
+        ),
+        edge_weight: Some(neighbor.edge.weight),
+        score,
+        included: true,
+    }
 }
 
 // Entity extracted from text
@@ -74,8 +155,14 @@ pub struct AgentCore {
     profile: AgentProfile,
     /// Model provider
     provider: Arc<dyn ModelProvider>,
+    /// Main model name, used to pick a tokenizer
+    model_name: Option<String>,
     /// Optional fast model provider for hierarchical reasoning
     fast_provider: Option<Arc<dyn ModelProvider>>,
+    /// Optional critic provider for verifying answers (falls back to the fast provider)
+    critic_provider: Option<Arc<dyn ModelProvider>>,
+    /// Providers for task classes listed in `model_routes`
+    router: ModelRouter,
     /// Optional embeddings client for semantic recall
     embeddings_client: Option<EmbeddingsClient>,
     /// Persistence layer
@@ -92,6 +179,43 @@ pub struct AgentCore {
     policy_engine: Arc<PolicyEngine>,
     /// Cache for tool permission checks to avoid repeated lookups
     tool_permission_cache: Arc<RwLock<HashMap<String, bool>>>,
+    /// Progress reporter for model calls and tool executions
+    progress: ProgressReporter,
+    /// Receives tools' partial output while they run; without one, tools
+    /// run to completion before anything is shown
+    tool_output: Option<Arc<dyn ToolOutputSink>>,
+    /// How long a tool call may run unless the profile's `tool_timeouts`
+    /// say otherwise; unbounded when `None`
+    tool_timeout: Option<Duration>,
+    /// Abandons the tool calls in progress when cancelled
+    tool_cancel: CancellationToken,
+    /// `response_language` of the spec being run, overriding the profile
+    language_override: Option<String>,
+    /// Current time and calendar for `time_context` prompts
+    clock: Clock,
+    /// Sources of the context blocks named in `context_providers`
+    context_providers: ContextProviders,
+    /// Display name of the spec being run, if any
+    active_spec: Option<String>,
+    /// Stages timed by `log_timing` since the current step began
+    stage_timings: Mutex<Vec<StageTiming>>,
+    /// Payload size cap for the model log; `None` disables logging
+    model_log_max_bytes: Option<usize>,
+    /// Run the current step belongs to, for model log entries
+    current_run_id: Option<String>,
+    /// Facts from the user profile put in each prompt; `None` leaves the
+    /// profile out of prompts and skips extraction
+    user_profile_facts: Option<usize>,
+    /// Recent input embeddings and recall results of this session
+    recall_cache: Mutex<RecallCache<RecallResult>>,
+    /// Worker embedding stored messages in the background; without one,
+    /// messages are embedded before `store_message` returns
+    embedding_queue: Option<EmbeddingQueue>,
+    /// Worker condensing old messages into summaries, when the profile sets
+    /// `memory_compaction`
+    memory_compactor: Option<MemoryCompactor>,
+    /// Permissions granted to the spec being run; dropped when its run ends
+    spec_permissions: Vec<SpecPermission>,
 }
 
 impl AgentCore {
@@ -109,7 +233,10 @@ impl AgentCore {
         Self {
             profile,
             provider,
+            model_name: None,
             fast_provider: None,
+            critic_provider: None,
+            router: ModelRouter::default(),
             embeddings_client,
             persistence,
             session_id,
@@ -118,39 +245,186 @@ impl AgentCore {
             tool_registry,
             policy_engine,
             tool_permission_cache: Arc::new(RwLock::new(HashMap::new())),
+            progress: ProgressReporter::noop(),
+            tool_output: None,
+            tool_timeout: None,
+            tool_cancel: CancellationToken::new(),
+            language_override: None,
+            clock: Clock::default(),
+            context_providers: ContextProviders::builtin(),
+            active_spec: None,
+            stage_timings: Mutex::new(Vec::new()),
+            model_log_max_bytes: None,
+            current_run_id: None,
+            user_profile_facts: None,
+            recall_cache: Mutex::new(RecallCache::new(DEFAULT_RECALL_CACHE_CAPACITY)),
+            embedding_queue: None,
+            memory_compactor: None,
+            spec_permissions: Vec::new(),
         }
     }
 
+    /// Set the main model's name so prompts are counted with its tokenizer
+    pub fn with_model_name(mut self, model_name: impl Into<String>) -> Self {
+        self.model_name = Some(model_name.into());
+        self
+    }
+
     /// Set the fast model provider for hierarchical reasoning
     pub fn with_fast_provider(mut self, fast_provider: Arc<dyn ModelProvider>) -> Self {
         self.fast_provider = Some(fast_provider);
         self
     }
 
+    /// Set the provider that reviews draft answers when verification is enabled
+    pub fn with_critic_provider(mut self, critic_provider: Arc<dyn ModelProvider>) -> Self {
+        self.critic_provider = Some(critic_provider);
+        self
+    }
+
+    /// Answer steps of `class` with `route` instead of the main provider
+    pub fn with_route(mut self, class: TaskClass, route: ModelRoute) -> Self {
+        self.router.insert(class, route);
+        self
+    }
+
+    /// Set the clock used for time-aware prompts
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record provider requests and responses in the model log, cutting
+    /// prompts and responses to `max_bytes`
+    pub fn with_model_log(mut self, max_bytes: usize) -> Self {
+        self.model_log_max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Show up to `prompt_facts` user profile facts in prompts and extract
+    /// new ones in `update_user_profile`
+    pub fn with_user_profile(mut self, prompt_facts: usize) -> Self {
+        self.user_profile_facts = Some(prompt_facts);
+        self
+    }
+
+    /// Keep up to `capacity` recall results for repeated inputs; 0 turns
+    /// the recall cache and embedding reuse off
+    pub fn with_recall_cache(mut self, capacity: usize) -> Self {
+        self.recall_cache = Mutex::new(RecallCache::new(capacity));
+        self
+    }
+
+    /// Give up on tool calls that run longer than `timeout`, unless the
+    /// profile's `tool_timeouts` name the tool
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Embed stored messages in a background task instead of before the step
+    /// goes on. Needs an embeddings client and an async runtime.
+    pub fn with_background_embeddings(mut self) -> Self {
+        self.embedding_queue = self.spawn_embedding_queue();
+        self
+    }
+
+    fn spawn_embedding_queue(&self) -> Option<EmbeddingQueue> {
+        EmbeddingQueue::spawn(
+            self.persistence.clone(),
+            self.embeddings_client.clone()?,
+            self.session_id.clone(),
+        )
+    }
+
+    /// Condense old messages in a background task as the profile's
+    /// `memory_compaction` says. Needs an embeddings client and an async
+    /// runtime; summaries come from the fast model when there is one.
+    pub fn with_background_compaction(mut self) -> Self {
+        self.memory_compactor = self.spawn_memory_compactor();
+        self
+    }
+
+    fn spawn_memory_compactor(&self) -> Option<MemoryCompactor> {
+        MemoryCompactor::spawn(
+            self.persistence.clone(),
+            self.embeddings_client.clone()?,
+            self.fast_provider
+                .clone()
+                .unwrap_or_else(|| self.provider.clone()),
+            self.session_id.clone(),
+            self.profile.memory_compaction.clone()?,
+        )
+    }
+
+    /// Make an additional context provider available to `context_providers`
+    pub fn with_context_provider(mut self, provider: Arc<dyn ContextProvider>) -> Self {
+        self.context_providers.register(provider);
+        self
+    }
+
     /// Set a new session ID and clear conversation history
     pub fn with_session(mut self, session_id: String) -> Self {
         self.session_id = session_id;
         self.conversation_history.clear();
         self.tool_permission_cache = Arc::new(RwLock::new(HashMap::new()));
+        if let Ok(cache) = self.recall_cache.get_mut() {
+            cache.clear();
+        }
+        if self.embedding_queue.is_some() {
+            self.embedding_queue = self.spawn_embedding_queue();
+        }
+        if self.memory_compactor.is_some() {
+            self.memory_compactor = self.spawn_memory_compactor();
+        }
         self
     }
 
     /// Execute a single interaction step
     pub async fn run_step(&mut self, input: &str) -> Result<AgentOutput> {
+        self.execute_step(input, false).await
+    }
+
+    /// Run one step; `spec_run` marks structured spec executions, which are
+    /// always eligible for reflection regardless of length.
+    async fn execute_step(&mut self, input: &str, spec_run: bool) -> Result<AgentOutput> {
         let run_id = format!("run-{}", Utc::now().timestamp_micros());
         let total_timer = Instant::now();
+        self.take_stage_timings();
+        self.current_run_id = Some(run_id.clone());
 
         // Step 1: Recall relevant memories
         let recall_timer = Instant::now();
         let recall_result = self.recall_memories(input).await?;
         self.log_timing("run_step.recall_memories", recall_timer);
-        let recalled_messages = recall_result.messages;
         let recall_stats = recall_result.stats;
+        let mut graph_trace = recall_result.graph;
+        let mut prompt_context = PromptContext::new(recall_result.messages, recall_stats.as_ref());
+        // Messages a `/compact` summary covers are left to the summary
+        if let Some(anchor) = self.persistence.context_anchor(&self.session_id)? {
+            prompt_context.drop_history_through(anchor.through_message_id);
+        }
+
+        let mut compression = None;
+        for section in [ContextSection::History, ContextSection::Memories] {
+            for message in prompt_context.messages_mut(section) {
+                if let Some(compressed) = self
+                    .compress_context_item(&message.content, &mut compression)
+                    .await
+                {
+                    message.content = compressed;
+                }
+            }
+        }
 
         // Step 2: Build prompt with context
         let prompt_timer = Instant::now();
-        let mut prompt = self.build_prompt(input, &recalled_messages).await?;
+        let mut prompt = self.build_prompt(input, &prompt_context.messages()).await?;
         self.log_timing("run_step.build_prompt", prompt_timer);
+        // Text appended past this point (hints, tool results) survives
+        // prompt rebuilds after a context overflow
+        let mut base_prompt_len = prompt.len();
+        let mut context_degradation: Vec<ContextDegradation> = Vec::new();
 
         // Step 3: Store user message
         let store_user_timer = Instant::now();
@@ -169,6 +443,8 @@ impl AgentCore {
         let mut auto_response: Option<String> = None;
         let mut reasoning: Option<String> = None;
         let mut reasoning_summary: Option<String> = None;
+        let mut request_hashes = Vec::new();
+        let mut verification = None;
 
         // Attempt to auto-satisfy simple goals before invoking the model
         if let Some(goal) = goal_context.as_mut() {
@@ -176,17 +452,21 @@ impl AgentCore {
                 if let Some((tool_name, tool_args)) =
                     Self::infer_goal_tool_action(goal.text.as_str())
                 {
-                    if self.is_tool_allowed(&tool_name).await {
+                    if self.authorize_tool_call(&run_id, &tool_name).await {
                         let tool_timer = Instant::now();
                         let tool_result = self.execute_tool(&run_id, &tool_name, &tool_args).await;
-                        self.log_timing("run_step.tool_execution.auto", tool_timer);
+                        self.log_timing(
+                            &format!("run_step.tool_execution.{}", tool_name),
+                            tool_timer,
+                        );
                         match tool_result {
-                            Ok(result) => {
+                            Ok((result, cached)) => {
                                 let invocation = ToolInvocation::from_result(
                                     &tool_name,
                                     tool_args.clone(),
                                     &result,
-                                );
+                                )
+                                .with_cached(cached);
                                 if let Err(err) = self
                                     .record_goal_tool_result(goal, &tool_name, &tool_args, &result)
                                 {
@@ -222,35 +502,40 @@ impl AgentCore {
             .unwrap_or(false);
 
         // Fast-model routing (when enabled) happens only if we still need a model response
+        let fast_task = if skip_model {
+            None
+        } else {
+            self.detect_task_type(input)
+        };
         let mut fast_model_final: Option<(String, f32)> = None;
-        if !skip_model {
-            if let Some(task_type) = self.detect_task_type(input) {
-                let complexity = self.estimate_task_complexity(input);
-                if self.should_use_fast_model(&task_type, complexity) {
-                    let fast_timer = Instant::now();
-                    let fast_result = self.fast_reasoning(&task_type, input).await;
-                    self.log_timing("run_step.fast_reasoning_attempt", fast_timer);
-                    match fast_result {
-                        Ok((fast_text, confidence)) => {
-                            if confidence >= self.escalation_threshold() {
-                                fast_model_final = Some((fast_text, confidence));
-                            } else {
-                                prompt.push_str(&format!(
-                                    "\n\nFAST_MODEL_HINT (task={} confidence={:.0}%):\n{}\n\nRefine this hint and produce a complete answer.",
-                                    task_type,
-                                    (confidence * 100.0).round(),
-                                    fast_text
-                                ));
-                            }
-                        }
-                        Err(err) => {
-                            warn!("Fast reasoning failed for task {}: {}", task_type, err);
+        if let Some(task_type) = fast_task.as_deref() {
+            let complexity = self.estimate_task_complexity(input);
+            if self.should_use_fast_model(task_type, complexity) {
+                let fast_timer = Instant::now();
+                let fast_result = self.fast_reasoning(task_type, input).await;
+                self.log_timing("run_step.fast_reasoning_attempt", fast_timer);
+                match fast_result {
+                    Ok((fast_text, confidence)) => {
+                        if confidence >= self.escalation_threshold() {
+                            fast_model_final = Some((fast_text, confidence));
+                        } else {
+                            prompt.push_str(&format!(
+                                "\n\nFAST_MODEL_HINT (task={} confidence={:.0}%):\n{}\n\nRefine this hint and produce a complete answer.",
+                                task_type,
+                                (confidence * 100.0).round(),
+                                fast_text
+                            ));
                         }
                     }
+                    Err(err) => {
+                        warn!("Fast reasoning failed for task {}: {}", task_type, err);
+                    }
                 }
             }
         }
 
+        let mut route = None;
+
         if skip_model {
             final_response = auto_response.unwrap_or_else(|| "Task completed.".to_string());
             finish_reason = Some("auto_tool".to_string());
@@ -258,14 +543,114 @@ impl AgentCore {
             final_response = fast_text;
             finish_reason = Some(format!("fast_model ({:.0}%)", (confidence * 100.0).round()));
         } else {
+            let task_class = fast_task
+                .as_deref()
+                .and_then(routing::class_of_fast_task)
+                .unwrap_or_else(|| routing::classify(input));
+            let (provider, report) = self.select_route(task_class);
+            let token_model = if report.routed {
+                report.model.clone()
+            } else {
+                self.tokenizer_model()
+            };
+            info!(
+                "Routing {} task to {}{}",
+                report.task,
+                report.model,
+                if report.routed { "" } else { " (main model)" }
+            );
+            route = Some(report);
+
+            // Providers without function calling get the tools described in
+            // the prompt and answer with text actions instead
+            let text_tools = self.uses_text_tool_calls(provider.as_ref());
+            if text_tools {
+                let tools = self.text_tool_parameters().await;
+                if !tools.is_empty() {
+                    prompt.push_str("\n\n");
+                    prompt.push_str(&react::instructions(&tools));
+                }
+            }
+
             // Allow up to 5 iterations to handle tool calls
-            for _iteration in 0..5 {
+            for iteration in 0..5 {
                 // Generate response using model
                 let generation_config = self.build_generation_config();
-                let model_timer = Instant::now();
-                let response_result = self.provider.generate(&prompt, &generation_config).await;
-                self.log_timing("run_step.main_model_call", model_timer);
-                let response = response_result.context("Failed to generate response from model")?;
+                let response = loop {
+                    let prompt_tokens = tokenizer::count_tokens(&prompt, &token_model);
+                    self.progress.step(
+                        "model",
+                        format!(
+                            "waiting for {} (~{} prompt tokens)",
+                            provider.metadata().name,
+                            prompt_tokens
+                        ),
+                        Some(iteration as u64 + 1),
+                        None,
+                    );
+                    // A prompt over max_context_tokens sheds context before it
+                    // is sent, the same way a provider's overflow error would
+                    let over_budget = self
+                        .profile
+                        .max_context_tokens
+                        .is_some_and(|budget| prompt_tokens > budget)
+                        && !prompt_context.is_empty()
+                        && context_degradation.len() < self.profile.context_overflow_retries;
+                    if self.profile.deterministic && !over_budget {
+                        let hash = determinism::request_hash(&prompt, &generation_config);
+                        debug!("Model request {} hash: {}", iteration + 1, hash);
+                        request_hashes.push(hash);
+                    }
+                    let response_result = if over_budget {
+                        Err(anyhow!(
+                            "prompt of ~{} tokens exceeds max_context_tokens",
+                            prompt_tokens
+                        ))
+                    } else {
+                        self.call_model(
+                            &provider,
+                            "run_step.main_model_call",
+                            &prompt,
+                            &generation_config,
+                        )
+                        .await
+                    };
+
+                    let err = match response_result {
+                        Ok(response) => break response,
+                        Err(err) => err,
+                    };
+                    let recoverable = context_degradation.len()
+                        < self.profile.context_overflow_retries
+                        && (over_budget || overflow::is_context_overflow(provider.kind(), &err));
+                    let shed = if recoverable {
+                        prompt_context.shed()
+                    } else {
+                        None
+                    };
+                    let Some((section, dropped)) = shed else {
+                        return Err(err.context("Failed to generate response from model"));
+                    };
+
+                    let degradation = ContextDegradation {
+                        attempt: context_degradation.len() + 1,
+                        section,
+                        dropped,
+                        remaining: prompt_context.count(section),
+                    };
+                    warn!(
+                        "Context window exceeded; dropping {} {} message(s) before retry {}",
+                        dropped,
+                        section.as_str(),
+                        degradation.attempt
+                    );
+                    context_degradation.push(degradation);
+
+                    let tail = prompt.split_off(base_prompt_len);
+                    prompt = self.build_prompt(input, &prompt_context.messages()).await?;
+                    base_prompt_len = prompt.len();
+                    prompt.push_str(&tail);
+                };
 
                 token_usage = response.usage;
                 finish_reason = response.finish_reason.clone();
@@ -278,7 +663,18 @@ impl AgentCore {
                 }
 
                 // Check for SDK-native tool calls (function calling)
-                let sdk_tool_calls = response.tool_calls.clone().unwrap_or_default();
+                let mut sdk_tool_calls = response.tool_calls.clone().unwrap_or_default();
+                if text_tools && sdk_tool_calls.is_empty() {
+                    match react::parse_action(&response.content) {
+                        Some(action) => {
+                            debug!("Text tool call: {}", action.call.function_name);
+                            prompt.push_str("\n\n");
+                            prompt.push_str(&action.transcript());
+                            sdk_tool_calls.push(action.call);
+                        }
+                        None => final_response = react::final_answer(&final_response),
+                    }
+                }
 
                 // Early termination: if no tool calls and response is complete, break immediately
                 if sdk_tool_calls.is_empty() {
@@ -309,7 +705,9 @@ impl AgentCore {
                         let tool_args = &tool_call.arguments;
 
                         // Check if tool is allowed
-                        if !self.is_tool_allowed(tool_name).await {
+                        if !self.authorize_tool_call(&run_id, tool_name).await
+                            && !self.spec_permits(&run_id, tool_name, tool_args)
+                        {
                             warn!(
                                 "Tool '{}' is not allowed by agent policy - prompting user",
                                 tool_name
@@ -322,8 +720,10 @@ impl AgentCore {
                                     // Permission granted, continue to execute the tool below
                                 }
                                 Ok(false) => {
-                                    let error_msg =
-                                        format!("Tool '{}' was denied by user", tool_name);
+                                    let error_msg = self.denied_tool_error(
+                                        tool_name,
+                                        format!("Tool '{}' was denied by user", tool_name),
+                                    );
                                     warn!("{}", error_msg);
                                     tool_invocations.push(ToolInvocation {
                                         name: tool_name.clone(),
@@ -331,13 +731,17 @@ impl AgentCore {
                                         success: false,
                                         output: None,
                                         error: Some(error_msg),
+                                        cached: false,
                                     });
                                     continue;
                                 }
                                 Err(e) => {
-                                    let error_msg = format!(
-                                        "Failed to get user permission for tool '{}': {}",
-                                        tool_name, e
+                                    let error_msg = self.denied_tool_error(
+                                        tool_name,
+                                        format!(
+                                            "Failed to get user permission for tool '{}': {}",
+                                            tool_name, e
+                                        ),
                                     );
                                     warn!("{}", error_msg);
                                     tool_invocations.push(ToolInvocation {
@@ -346,6 +750,7 @@ impl AgentCore {
                                         success: false,
                                         output: None,
                                         error: Some(error_msg),
+                                        cached: false,
                                     });
                                     continue;
                                 }
@@ -355,14 +760,18 @@ impl AgentCore {
                         // Execute tool
                         let tool_timer = Instant::now();
                         let exec_result = self.execute_tool(&run_id, tool_name, tool_args).await;
-                        self.log_timing("run_step.tool_execution.sdk", tool_timer);
+                        self.log_timing(
+                            &format!("run_step.tool_execution.{}", tool_name),
+                            tool_timer,
+                        );
                         match exec_result {
-                            Ok(result) => {
+                            Ok((result, cached)) => {
                                 let invocation = ToolInvocation::from_result(
                                     tool_name,
                                     tool_args.clone(),
                                     &result,
-                                );
+                                )
+                                .with_cached(cached);
                                 let tool_output = invocation.output.clone().unwrap_or_default();
                                 let was_success = invocation.success;
                                 let error_message = invocation
@@ -387,6 +796,18 @@ impl AgentCore {
                                 }
 
                                 if was_success {
+                                    let tool_output = if self.profile.deterministic {
+                                        determinism::pin_timestamps(&tool_output).into_owned()
+                                    } else {
+                                        tool_output
+                                    };
+                                    let tool_output = match self
+                                        .compress_context_item(&tool_output, &mut compression)
+                                        .await
+                                    {
+                                        Some(compressed) => compressed,
+                                        None => tool_output,
+                                    };
                                     // Add tool result to prompt for next iteration
                                     prompt.push_str(&format!(
                                         "\n\nTOOL_RESULT from {}:\n{}\n\nBased on this result, please continue.",
@@ -413,6 +834,7 @@ impl AgentCore {
                                     success: false,
                                     output: None,
                                     error: Some(error_msg),
+                                    cached: false,
                                 });
                             }
                         }
@@ -434,6 +856,33 @@ impl AgentCore {
                 // No tool calls found or response includes final answer, break
                 break;
             }
+
+            if self.profile.verify_responses && !final_response.trim().is_empty() {
+                let verify_timer = Instant::now();
+                verification = self
+                    .verify_response(
+                        input,
+                        &prompt,
+                        &prompt_context.messages(),
+                        &tool_invocations,
+                        &mut final_response,
+                        &mut request_hashes,
+                    )
+                    .await;
+                self.log_timing("run_step.verification", verify_timer);
+            }
+        }
+
+        let language_report = match self.response_language_setting() {
+            Some(setting) => Some(
+                self.enforce_response_language(&setting, input, &mut final_response)
+                    .await,
+            ),
+            None => None,
+        };
+
+        if !self.profile.post_processors.is_empty() {
+            final_response = postprocess::apply(&self.profile.post_processors, &final_response);
         }
 
         // Step 5: Store assistant response with reasoning if available
@@ -467,6 +916,20 @@ impl AgentCore {
             }
         }
 
+        // Optional reflection: distill lessons from specs and long runs into the graph
+        if self.should_reflect(spec_run, &tool_invocations) {
+            let reflection_timer = Instant::now();
+            let goal_node_id = goal_context.as_ref().and_then(|goal| goal.node_id);
+            match self
+                .reflect_on_run(input, &final_response, &tool_invocations, goal_node_id)
+                .await
+            {
+                Ok(count) => debug!("Reflection stored {} lesson(s)", count),
+                Err(err) => warn!("Reflection pass failed: {}", err),
+            }
+            self.log_timing("run_step.reflection", reflection_timer);
+        }
+
         // Step 6: Update conversation history
         self.conversation_history.push(Message {
             id: user_message_id,
@@ -494,6 +957,7 @@ impl AgentCore {
                     response_message_id,
                     &final_response,
                     &tool_invocations,
+                    &mut graph_trace.influences,
                 )?;
                 self.log_timing("run_step.evaluate_graph_for_next_action", graph_timer);
                 recommendation
@@ -520,15 +984,32 @@ impl AgentCore {
             });
         }
 
-        let graph_debug = match self.snapshot_graph_debug_info() {
-            Ok(info) => Some(info),
-            Err(err) => {
-                warn!("Failed to capture graph debug info: {}", err);
-                None
-            }
-        };
+        let graph_debug =
+            match self.snapshot_graph_debug_info(graph_trace, next_action_recommendation.clone()) {
+                Ok(info) => Some(info),
+                Err(err) => {
+                    warn!("Failed to capture graph debug info: {}", err);
+                    None
+                }
+            };
+
+        if let Err(err) = self.ensure_session_title(input, &final_response).await {
+            warn!("Failed to title session {}: {}", self.session_id, err);
+        }
 
+        let timings = StepTimings {
+            total_ms: elapsed_ms(total_timer),
+            stages: self.take_stage_timings(),
+        };
         self.log_timing("run_step.total", total_timer);
+        self.persist_timings(&run_id, &timings);
+        self.publish_run_event(
+            &run_id,
+            &timings,
+            token_usage.as_ref(),
+            &tool_invocations,
+            finish_reason.as_deref(),
+        );
 
         Ok(AgentOutput {
             response: final_response,
@@ -542,18 +1023,168 @@ impl AgentCore {
             reasoning,
             reasoning_summary,
             graph_debug,
+            request_hashes,
+            context_degradation,
+            verification,
+            language: language_report,
+            timings: Some(timings),
+            route,
+            compression,
         })
     }
 
     /// Execute a structured spec by converting it into a single prompt.
     pub async fn run_spec(&mut self, spec: &AgentSpec) -> Result<AgentOutput> {
+        self.run_spec_with_permissions(spec, Vec::new()).await
+    }
+
+    /// Execute a spec with the `[[permissions]]` the operator approved.
+    /// They allow their tools for this run only, whatever the profile and
+    /// policy engine say, and are never remembered as tool decisions.
+    pub async fn run_spec_with_permissions(
+        &mut self,
+        spec: &AgentSpec,
+        granted: Vec<SpecPermission>,
+    ) -> Result<AgentOutput> {
+        self.spec_permissions = granted;
+        let result = self.execute_spec(spec).await;
+        self.spec_permissions.clear();
+        result
+    }
+
+    async fn execute_spec(&mut self, spec: &AgentSpec) -> Result<AgentOutput> {
         debug!(
             "Executing structured spec '{}' (source: {:?})",
             spec.display_name(),
             spec.source_path()
         );
+        self.check_spec_requirements(spec)?;
+        self.progress
+            .panels(spec.display_name(), spec.panels.clone());
         let prompt = spec.to_prompt();
-        self.run_step(&prompt).await
+
+        // The spec prompt wraps the goal in English boilerplate, so `auto`
+        // is resolved against the spec's own text instead
+        let setting = spec
+            .response_language
+            .clone()
+            .or_else(|| self.profile.response_language.clone());
+        self.language_override = setting.map(|setting| {
+            if setting.trim().eq_ignore_ascii_case("auto") {
+                language::detect(&spec.language_sample())
+                    .map(|detected| detected.code.to_string())
+                    .unwrap_or(setting)
+            } else {
+                setting
+            }
+        });
+        self.active_spec = Some(spec.display_name().to_string());
+        let result = self.execute_step(&prompt, true).await;
+        self.language_override = None;
+        self.active_spec = None;
+        result
+    }
+
+    /// Fail before any model call when the spec's `[requires]` section
+    /// asks for tools, provider features or context the agent lacks
+    fn check_spec_requirements(&self, spec: &AgentSpec) -> Result<()> {
+        if spec.requires.is_empty() {
+            return Ok(());
+        }
+        let metadata = self.provider.metadata();
+        // The profile's limit narrows whatever the provider offers
+        let context_window = match (
+            metadata.context_window,
+            self.profile.max_context_tokens.map(|t| t as u32),
+        ) {
+            (Some(provider), Some(profile)) => Some(provider.min(profile)),
+            (provider, profile) => provider.or(profile),
+        };
+        spec.requires
+            .check(
+                |tool| {
+                    self.tool_registry.has(tool)
+                        && (self.profile.is_tool_allowed(tool)
+                            || self.spec_permissions.iter().any(|p| p.tool == tool))
+                },
+                &metadata,
+                context_window,
+            )
+            .with_context(|| format!("cannot run spec '{}'", spec.display_name()))
+    }
+
+    /// `response_language` for the current run: the spec's while a spec
+    /// runs, otherwise the profile's
+    fn response_language_setting(&self) -> Option<String> {
+        self.language_override
+            .clone()
+            .or_else(|| self.profile.response_language.clone())
+    }
+
+    /// Check the answer's language against `response_language` and, when it
+    /// is wrong and `translate_responses` is on, have the fast model
+    /// translate it
+    async fn enforce_response_language(
+        &self,
+        setting: &str,
+        input: &str,
+        response: &mut String,
+    ) -> LanguageReport {
+        let target = language::resolve_target(setting, input);
+        let detected_response = language::detect(response);
+        let mut report = LanguageReport {
+            target: target.as_ref().map(LanguageTarget::label),
+            detected_input: language::detect(input).map(|l| l.code.to_string()),
+            detected_response: detected_response.map(|l| l.code.to_string()),
+            translated: false,
+        };
+
+        let (Some(target), Some(detected)) = (target, detected_response) else {
+            return report;
+        };
+        match target.language {
+            Some(wanted) if wanted != detected => {}
+            _ => return report,
+        }
+        info!(
+            "Answer is in {} but {} was requested",
+            detected.name, target.name
+        );
+        if !self.profile.translate_responses {
+            return report;
+        }
+        let Some(fast_provider) = self.fast_provider.as_ref() else {
+            debug!("translate_responses is enabled but no fast provider is configured");
+            return report;
+        };
+
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.0),
+            max_tokens: None,
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let translated = self
+            .call_model(
+                fast_provider,
+                "run_step.translate_response",
+                &language::build_translation_prompt(response, &target),
+                &config,
+            )
+            .await;
+        match translated {
+            Ok(reply) if !reply.content.trim().is_empty() => {
+                *response = reply.content.trim().to_string();
+                report.translated = true;
+            }
+            Ok(_) => warn!("Translation returned an empty answer; keeping the original"),
+            Err(err) => warn!("Translating the answer failed: {}", err),
+        }
+        report
     }
 
     /// Build generation configuration from profile
@@ -580,17 +1211,40 @@ impl AgentCore {
             Some(DEFAULT_TOP_P)
         };
 
-        GenerationConfig {
+        let config = GenerationConfig {
             temperature,
-            max_tokens: self.profile.max_context_tokens.map(|t| t as u32),
-            stop_sequences: None,
+            max_tokens: self
+                .profile
+                .max_tokens
+                .or(self.profile.max_context_tokens.map(|t| t as u32)),
+            stop_sequences: self
+                .profile
+                .stop_sequences
+                .clone()
+                .filter(|stops| !stops.is_empty()),
             top_p,
-            frequency_penalty: None,
-            presence_penalty: None,
+            frequency_penalty: self.profile.frequency_penalty,
+            presence_penalty: self.profile.presence_penalty,
+            top_k: self.profile.top_k,
+            seed: self.profile.seed,
+        };
+        self.pin_if_deterministic(config)
+    }
+
+    /// Apply deterministic sampling settings when the profile asks for them
+    fn pin_if_deterministic(&self, config: GenerationConfig) -> GenerationConfig {
+        if self.profile.deterministic {
+            determinism::pin_generation_config(config)
+        } else {
+            config
         }
     }
 
-    fn snapshot_graph_debug_info(&self) -> Result<GraphDebugInfo> {
+    fn snapshot_graph_debug_info(
+        &self,
+        trace: GraphRecallTrace,
+        recommendation: Option<String>,
+    ) -> Result<GraphDebugInfo> {
         let mut info = GraphDebugInfo {
             enabled: self.profile.enable_graph,
             graph_memory_enabled: self.profile.graph_memory,
@@ -599,6 +1253,10 @@ impl AgentCore {
             node_count: 0,
             edge_count: 0,
             recent_nodes: Vec::new(),
+            traversal_depth: self.profile.graph_depth,
+            influences: trace.influences,
+            context_tokens: trace.tokens,
+            recommendation,
         };
 
         if !self.profile.enable_graph {
@@ -624,6 +1282,93 @@ impl AgentCore {
     }
 
     /// Summarize reasoning using the fast model
+    /// Compress `text` when it is over the `context_compression` threshold,
+    /// adding it to `report`. `None` means the text is used as it is.
+    async fn compress_context_item(
+        &self,
+        text: &str,
+        report: &mut Option<CompressionReport>,
+    ) -> Option<String> {
+        let settings = self.profile.context_compression.as_ref()?;
+        let model = self.tokenizer_model();
+        let original_tokens = tokenizer::count_tokens(text, &model);
+        if original_tokens <= settings.threshold_tokens {
+            return None;
+        }
+
+        let timer = Instant::now();
+        let summary = match settings.mode {
+            CompressionMode::Summarize => {
+                let target_tokens =
+                    ((original_tokens as f32) * settings.target_ratio).ceil() as usize;
+                self.summarize_for_compression(text, target_tokens).await
+            }
+            CompressionMode::Heuristic => None,
+        };
+        let compressed =
+            summary.unwrap_or_else(|| compression::compress(text, settings.target_ratio));
+        self.log_timing("run_step.compress_context", timer);
+
+        let compressed_tokens = tokenizer::count_tokens(&compressed, &model);
+        if compressed_tokens >= original_tokens {
+            return None;
+        }
+        let retention = compression::key_term_retention(text, &compressed);
+        debug!(
+            "Compressed context from {} to {} tokens (key terms kept: {:.0}%)",
+            original_tokens,
+            compressed_tokens,
+            retention * 100.0
+        );
+        report
+            .get_or_insert_with(|| CompressionReport::new(settings.mode.as_str()))
+            .record(
+                original_tokens,
+                compressed_tokens,
+                retention,
+                compression::MIN_KEY_TERM_RETENTION,
+            );
+        Some(compressed)
+    }
+
+    /// Fast-model rewrite of `text` in about `target_tokens`; `None` falls
+    /// back to the heuristic
+    async fn summarize_for_compression(&self, text: &str, target_tokens: usize) -> Option<String> {
+        let Some(fast_provider) = self.fast_provider.as_ref() else {
+            debug!("No fast model for summarize compression; using the heuristic");
+            return None;
+        };
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.2),
+            max_tokens: Some((target_tokens as u32).saturating_mul(2)),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let prompt = compression::build_summary_prompt(text, target_tokens);
+        match self
+            .call_model(
+                fast_provider,
+                "compress_context.summarize",
+                &prompt,
+                &config,
+            )
+            .await
+        {
+            Ok(response) if !response.content.trim().is_empty() => {
+                Some(response.content.trim().to_string())
+            }
+            Ok(_) => None,
+            Err(err) => {
+                warn!("Context compression by the fast model failed: {}", err);
+                None
+            }
+        }
+    }
+
     async fn summarize_reasoning(&self, reasoning: &str) -> Option<String> {
         // Only summarize if we have a fast provider and reasoning is substantial
         let fast_provider = self.fast_provider.as_ref()?;
@@ -638,18 +1383,25 @@ impl AgentCore {
             reasoning
         );
 
-        let config = GenerationConfig {
+        let config = self.pin_if_deterministic(GenerationConfig {
             temperature: Some(0.3),
             max_tokens: Some(100),
             stop_sequences: None,
             top_p: Some(0.9),
             frequency_penalty: None,
             presence_penalty: None,
-        };
+            top_k: None,
+            seed: None,
+        });
 
-        let timer = Instant::now();
-        let response = fast_provider.generate(&summary_prompt, &config).await;
-        self.log_timing("summarize_reasoning.generate", timer);
+        let response = self
+            .call_model(
+                fast_provider,
+                "summarize_reasoning.generate",
+                &summary_prompt,
+                &config,
+            )
+            .await;
         match response {
             Ok(response) => {
                 let summary = response.content.trim().to_string();
@@ -667,34 +1419,870 @@ impl AgentCore {
         }
     }
 
-    /// Recall relevant memories for the given input
-    async fn recall_memories(&self, query: &str) -> Result<RecallResult> {
-        const RECENT_CONTEXT: i64 = 2;
-        // const MIN_MESSAGES_FOR_SEMANTIC_RECALL: usize = 3;
-        let mut context = Vec::new();
-        let mut seen_ids = HashSet::new();
+    /// Have the critic check `draft` against the recalled context and tool
+    /// output; when it reports unsupported claims, ask the main model for one
+    /// revision and replace the draft with it. Returns `None` when no critic is
+    /// available or its reply could not be parsed.
+    async fn verify_response(
+        &self,
+        input: &str,
+        prompt: &str,
+        context: &[Message],
+        tool_invocations: &[ToolInvocation],
+        draft: &mut String,
+        request_hashes: &mut Vec<String>,
+    ) -> Option<VerificationReport> {
+        let Some(critic) = self
+            .critic_provider
+            .as_ref()
+            .or(self.fast_provider.as_ref())
+        else {
+            debug!("Verification enabled but no critic or fast provider is configured");
+            return None;
+        };
 
-        let recent_messages = self
-            .persistence
-            .list_messages(&self.session_id, RECENT_CONTEXT)?;
+        let critic_prompt =
+            verification::build_verification_prompt(input, draft, context, tool_invocations);
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.0),
+            max_tokens: Some(400),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let reviewed = self
+            .call_model(critic, "verify_response.critic", &critic_prompt, &config)
+            .await;
+        let issues = match reviewed {
+            Ok(response) => match verification::parse_findings(&response.content) {
+                Some(issues) => issues,
+                None => {
+                    warn!("Critic reply did not contain findings; keeping the draft");
+                    return None;
+                }
+            },
+            Err(err) => {
+                warn!("Verification pass failed: {}", err);
+                return None;
+            }
+        };
 
-        // Optimization: Skip semantic recall for very new sessions (first interaction only)
-        // This saves embedding generation time when there's insufficient history
-        if self.conversation_history.is_empty() && recent_messages.is_empty() {
-            return Ok(RecallResult {
-                messages: Vec::new(),
-                stats: Some(MemoryRecallStats {
-                    strategy: MemoryRecallStrategy::RecentContext {
-                        limit: RECENT_CONTEXT as usize,
-                    },
-                    matches: Vec::new(),
-                }),
-            });
+        let mut report = VerificationReport {
+            verifier: critic.metadata().name,
+            issues,
+            revised: false,
+        };
+        if report.passed() {
+            return Some(report);
         }
 
-        for message in recent_messages {
-            seen_ids.insert(message.id);
-            context.push(message);
+        info!(
+            "Critic found {} unsupported claim(s); revising the answer",
+            report.issues.len()
+        );
+        let revision_prompt = format!(
+            "{}{}",
+            prompt,
+            verification::build_revision_request(draft, &report.issues)
+        );
+        let generation_config = self.build_generation_config();
+        if self.profile.deterministic {
+            request_hashes.push(determinism::request_hash(
+                &revision_prompt,
+                &generation_config,
+            ));
+        }
+        let revised = self
+            .call_model(
+                &self.provider,
+                "verify_response.revision",
+                &revision_prompt,
+                &generation_config,
+            )
+            .await;
+        match revised {
+            Ok(response) if !response.content.trim().is_empty() => {
+                *draft = response.content;
+                report.revised = true;
+            }
+            Ok(_) => warn!("Revision returned an empty answer; keeping the draft"),
+            Err(err) => warn!("Revision after verification failed: {}", err),
+        }
+        Some(report)
+    }
+
+    /// Record the agent and a short title for the session after its first
+    /// exchange. The fast model writes the title when available; otherwise
+    /// the opening words of the first message are used.
+    async fn ensure_session_title(&self, input: &str, response: &str) -> Result<()> {
+        let session = self.persistence.get_session(&self.session_id)?;
+        if let Some(agent) = self.agent_name.as_deref() {
+            if !matches!(&session, Some(s) if s.agent.is_some()) {
+                self.persistence
+                    .set_session_agent(&self.session_id, agent)?;
+            }
+        }
+        if session.is_some_and(|s| s.title.is_some()) {
+            return Ok(());
+        }
+
+        let mut title = None;
+        if let Some(fast_provider) = self.fast_provider.as_ref() {
+            let config = self.pin_if_deterministic(GenerationConfig {
+                temperature: Some(0.2),
+                max_tokens: Some(24),
+                stop_sequences: None,
+                top_p: Some(0.9),
+                frequency_penalty: None,
+                presence_penalty: None,
+                top_k: None,
+                seed: None,
+            });
+            let generated = self
+                .call_model(
+                    fast_provider,
+                    "session_title.generate",
+                    &titles::build_title_prompt(input, response),
+                    &config,
+                )
+                .await;
+            match generated {
+                Ok(reply) => title = titles::clean_title(&reply.content),
+                Err(err) => debug!("Fast model could not title session: {}", err),
+            }
+        }
+
+        let title = title.unwrap_or_else(|| titles::fallback_title(input));
+        if title.is_empty() {
+            return Ok(());
+        }
+        self.persistence.set_session_title(&self.session_id, &title)
+    }
+
+    /// Mine the session's messages since the last extraction for facts about
+    /// the user and add them to the profile. Called when a session ends;
+    /// uses the fast model when there is one. Returns the facts stored.
+    pub async fn update_user_profile(&self) -> Result<usize> {
+        if self.user_profile_facts.is_none() {
+            return Ok(0);
+        }
+        let since = self
+            .persistence
+            .user_profile_extracted_through(&self.session_id)?
+            .unwrap_or(0);
+        let messages: Vec<Message> = self
+            .persistence
+            .list_messages(&self.session_id, 200)?
+            .into_iter()
+            .filter(|message| message.id > since)
+            .collect();
+        let Some(last_id) = messages.last().map(|message| message.id) else {
+            return Ok(0);
+        };
+        if !messages.iter().any(|m| m.role == MessageRole::User) {
+            return Ok(0);
+        }
+
+        let known = self.persistence.user_profile_list()?;
+        let prompt = user_profile::build_extraction_prompt(&known, &messages);
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.2),
+            max_tokens: Some(400),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let provider = self.fast_provider.as_ref().unwrap_or(&self.provider);
+        let reply = self
+            .call_model(provider, "user_profile.extract", &prompt, &config)
+            .await?;
+        let facts = user_profile::parse_facts(&reply.content);
+        for fact in &facts {
+            self.persistence.user_profile_upsert(
+                &fact.category,
+                &fact.fact,
+                Some(&self.session_id),
+            )?;
+        }
+        self.persistence
+            .user_profile_mark_extracted(&self.session_id, last_id)?;
+        Ok(facts.len())
+    }
+
+    /// Summarize the session's messages since the last compaction, together
+    /// with the previous summary, into a new context anchor. Later prompts
+    /// carry the summary instead of those messages, which stay stored. Uses
+    /// the fast model when there is one.
+    pub async fn compact(&self) -> Result<CompactionReport> {
+        let report = self
+            .summarize_session(&self.session_id)
+            .await?
+            .ok_or_else(|| anyhow!("Nothing to compact since the last summary"))?;
+        self.persistence.set_context_anchor(
+            &self.session_id,
+            report.through_message_id,
+            &report.summary,
+        )?;
+        Ok(report)
+    }
+
+    /// Condense the session's messages older than the newest
+    /// `memory_compaction.keep_recent` into summaries now, including a last
+    /// chunk shorter than `chunk_messages`. Queued messages are embedded
+    /// first so that they can be included.
+    pub async fn compact_memories(&self) -> Result<Vec<MemorySummary>> {
+        let client = self
+            .embeddings_client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Memory compaction needs an embeddings model"))?;
+        self.flush_embeddings().await?;
+        let settings = self.profile.memory_compaction.clone().unwrap_or_default();
+        let provider = self.fast_provider.as_ref().unwrap_or(&self.provider);
+        memory_compaction::compact(
+            &self.persistence,
+            client,
+            provider.as_ref(),
+            &self.session_id,
+            &settings,
+            true,
+        )
+        .await
+    }
+
+    /// Archive a session: summarize what its last summary does not cover,
+    /// then delete its messages and their embeddings, keeping the summary
+    /// and the graph. See [`Persistence::archive_session`].
+    pub async fn archive_session(&self, session_id: &str) -> Result<SessionArchive> {
+        let summary = self
+            .summarize_session(session_id)
+            .await?
+            .map(|report| report.summary);
+        self.persistence
+            .archive_session(session_id, summary.as_deref())
+    }
+
+    /// Summary of the session's messages since its last summary, folding
+    /// that summary in; `None` when there is nothing new to summarize.
+    /// Nothing is stored.
+    async fn summarize_session(&self, session_id: &str) -> Result<Option<CompactionReport>> {
+        let previous = self.persistence.context_anchor(session_id)?;
+        let previous_summary = previous.as_ref().map(|anchor| anchor.summary.as_str());
+        let since = previous
+            .as_ref()
+            .map_or(0, |anchor| anchor.through_message_id);
+        let messages: Vec<Message> = self
+            .persistence
+            .list_messages(session_id, compaction::MAX_MESSAGES)?
+            .into_iter()
+            .filter(|message| message.id > since)
+            .collect();
+        let Some(through_message_id) = messages.last().map(|message| message.id) else {
+            return Ok(None);
+        };
+
+        let model = self.tokenizer_model();
+        let tokens_before = tokenizer::count_tokens(
+            &compaction::prompt_text(previous_summary, &messages),
+            &model,
+        );
+        let prompt = compaction::build_summary_prompt(previous_summary, &messages);
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.2),
+            max_tokens: Some(800),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let provider = self.fast_provider.as_ref().unwrap_or(&self.provider);
+        let reply = self
+            .call_model(provider, "compact.summarize", &prompt, &config)
+            .await?;
+        let summary = reply.content.trim().to_string();
+        if summary.is_empty() {
+            return Err(anyhow!("The model returned an empty summary"));
+        }
+
+        let tokens_after = tokenizer::count_tokens(
+            &format!("{}\n{}\n\n", compaction::SUMMARY_HEADING, summary),
+            &model,
+        );
+        Ok(Some(CompactionReport {
+            messages: messages.len(),
+            through_message_id,
+            tokens_before,
+            tokens_after,
+            summary,
+        }))
+    }
+
+    /// The last user message of the session and the answer to it
+    fn last_exchange(&self) -> Result<Option<(Message, Message)>> {
+        let messages = self.persistence.list_messages(&self.session_id, 50)?;
+        let Some(answer_at) = messages
+            .iter()
+            .rposition(|message| message.role == MessageRole::Assistant)
+        else {
+            return Ok(None);
+        };
+        let question = messages[..answer_at]
+            .iter()
+            .rev()
+            .find(|message| message.role == MessageRole::User);
+        Ok(question.map(|question| (question.clone(), messages[answer_at].clone())))
+    }
+
+    /// Ask again for the answer to the last user message, with `temperature`
+    /// and `provider` in place of the profile's when given. The original
+    /// answer and every retry are kept as variants of the reply, and the new
+    /// answer becomes the one in the history. Tools are not called again;
+    /// the model gets the context the original answer had.
+    pub async fn regenerate(
+        &mut self,
+        temperature: Option<f32>,
+        provider: Option<Arc<dyn ModelProvider>>,
+    ) -> Result<ResponseVariant> {
+        let Some((question, answer)) = self.last_exchange()? else {
+            return Err(anyhow!("No answer to retry yet"));
+        };
+        if self
+            .persistence
+            .list_response_variants(answer.id)?
+            .is_empty()
+        {
+            let original = self.persistence.add_response_variant(
+                &self.session_id,
+                question.id,
+                answer.id,
+                &answer.content,
+                None,
+                None,
+            )?;
+            self.persistence
+                .pick_response_variant(answer.id, original)?;
+        }
+
+        let mut recalled = self.recall_memories(&question.content).await?;
+        recalled.messages.retain(|message| message.id < question.id);
+        let mut context = PromptContext::new(recalled.messages, recalled.stats.as_ref());
+        if let Some(anchor) = self.persistence.context_anchor(&self.session_id)? {
+            context.drop_history_through(anchor.through_message_id);
+        }
+        let prompt = self
+            .build_prompt(&question.content, &context.messages())
+            .await?;
+
+        let mut config = self.build_generation_config();
+        if temperature.is_some() {
+            config.temperature = temperature;
+        }
+        let provider =
+            provider.unwrap_or_else(|| self.select_route(routing::classify(&question.content)).0);
+        let response = self
+            .call_model(&provider, "retry.generate", &prompt, &config)
+            .await?;
+        let content = postprocess::apply(&self.profile.post_processors, &response.content);
+        if content.trim().is_empty() {
+            return Err(anyhow!("The model returned an empty answer"));
+        }
+
+        let variant_id = self.persistence.add_response_variant(
+            &self.session_id,
+            question.id,
+            answer.id,
+            &content,
+            Some(&response.model),
+            config.temperature,
+        )?;
+        self.apply_response_variant(answer.id, variant_id).await
+    }
+
+    /// Variants of the last answer, oldest first; empty until it is retried
+    pub fn response_variants(&self) -> Result<Vec<ResponseVariant>> {
+        match self.last_exchange()? {
+            Some((_, answer)) => self.persistence.list_response_variants(answer.id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Put variant `number` (1-based, in [`Self::response_variants`] order)
+    /// of the last answer into the history
+    pub async fn pick_response_variant(&mut self, number: usize) -> Result<ResponseVariant> {
+        let variants = self.response_variants()?;
+        let Some(variant) = number.checked_sub(1).and_then(|index| variants.get(index)) else {
+            return Err(anyhow!(
+                "No variant {}; the last answer has {}",
+                number,
+                variants.len()
+            ));
+        };
+        self.apply_response_variant(variant.message_id, variant.id)
+            .await
+    }
+
+    async fn apply_response_variant(
+        &mut self,
+        message_id: i64,
+        variant_id: i64,
+    ) -> Result<ResponseVariant> {
+        let variant = self
+            .persistence
+            .pick_response_variant(message_id, variant_id)?
+            .ok_or_else(|| anyhow!("Variant {} not found", variant_id))?;
+        for message in self
+            .conversation_history
+            .iter_mut()
+            .filter(|message| message.id == message_id)
+        {
+            message.content = variant.content.clone();
+        }
+        if let Ok(cache) = self.recall_cache.get_mut() {
+            cache.clear();
+        }
+        // Semantic recall should find the answer now in the history
+        if let Some(client) = &self.embeddings_client {
+            match self
+                .embed_text(client, &variant.content, "embeddings.message_content")
+                .await
+            {
+                Ok(embedding) if !embedding.is_empty() => {
+                    if self
+                        .persistence
+                        .update_message_embedding(message_id, &embedding)?
+                        == 0
+                    {
+                        self.persistence.insert_memory_vector(
+                            &self.session_id,
+                            Some(message_id),
+                            &embedding,
+                        )?;
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => warn!(
+                    "Failed to embed the picked answer for message {}: {}",
+                    message_id, err
+                ),
+            }
+        }
+        Ok(variant)
+    }
+
+    /// Ask the model for the action items in the session's transcription
+    /// and store the new ones as `Task` nodes linked to a `Meeting` event.
+    /// Uses the fast model when there is one. Returns the items stored.
+    pub async fn extract_action_items(&self) -> Result<Vec<ActionItem>> {
+        if !self.profile.enable_graph {
+            return Ok(Vec::new());
+        }
+        let segments = self
+            .persistence
+            .list_transcript_segments(&self.session_id)?;
+        if segments.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prompt = transcript_export::build_action_items_prompt(&segments);
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.2),
+            max_tokens: Some(600),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let provider = self.fast_provider.as_ref().unwrap_or(&self.provider);
+        let reply = self
+            .call_model(provider, "transcription.action_items", &prompt, &config)
+            .await?;
+
+        let existing = transcript_export::action_items_from_nodes(
+            &self
+                .persistence
+                .list_graph_nodes(&self.session_id, Some(NodeType::Goal), None)?,
+        );
+        let items: Vec<ActionItem> = transcript_export::parse_action_items(&reply.content)
+            .into_iter()
+            .filter(|item| {
+                !existing
+                    .iter()
+                    .any(|known| known.task.eq_ignore_ascii_case(&item.task))
+            })
+            .collect();
+        if items.is_empty() {
+            return Ok(items);
+        }
+
+        let meeting_id = self.persistence.insert_graph_node(
+            &self.session_id,
+            NodeType::Event,
+            "Meeting",
+            &json!({
+                "source": transcript_export::TRANSCRIPTION_SOURCE,
+                "chunks": segments.len(),
+                "recorded_at": segments[0].timestamp.to_rfc3339(),
+            }),
+            None,
+        )?;
+        for item in &items {
+            let task_id = self.persistence.insert_graph_node(
+                &self.session_id,
+                NodeType::Goal,
+                transcript_export::TASK_LABEL,
+                &json!({
+                    "task": item.task,
+                    "owner": item.owner,
+                    "due": item.due,
+                    "status": "open",
+                    "source": transcript_export::TRANSCRIPTION_SOURCE,
+                }),
+                None,
+            )?;
+            self.persistence.insert_graph_edge(
+                &self.session_id,
+                task_id,
+                meeting_id,
+                EdgeType::PartOf,
+                Some("agreed_in"),
+                None,
+                1.0,
+            )?;
+        }
+        Ok(items)
+    }
+
+    fn should_reflect(&self, spec_run: bool, tool_invocations: &[ToolInvocation]) -> bool {
+        self.profile.enable_reflection
+            && self.profile.enable_graph
+            && self.fast_provider.is_some()
+            && (spec_run || tool_invocations.len() >= reflection::LONG_RUN_TOOL_CALLS)
+    }
+
+    /// Ask the fast model for lessons learned and store them as `Lesson` nodes
+    /// linked to the run's goal and the tool results the lessons mention.
+    async fn reflect_on_run(
+        &self,
+        task: &str,
+        response: &str,
+        tool_invocations: &[ToolInvocation],
+        goal_node_id: Option<i64>,
+    ) -> Result<usize> {
+        let Some(fast_provider) = self.fast_provider.as_ref() else {
+            return Ok(0);
+        };
+
+        let prompt = reflection::build_reflection_prompt(task, response, tool_invocations);
+        let config = self.pin_if_deterministic(GenerationConfig {
+            temperature: Some(0.2),
+            max_tokens: Some(400),
+            stop_sequences: None,
+            top_p: Some(0.9),
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+        });
+        let generated = self
+            .call_model(fast_provider, "reflect_on_run.generate", &prompt, &config)
+            .await;
+        let lessons = reflection::parse_lessons(&generated?.content);
+        if lessons.is_empty() {
+            return Ok(0);
+        }
+
+        let tool_nodes = match goal_node_id {
+            Some(goal_id) => self
+                .persistence
+                .traverse_neighbors(&self.session_id, goal_id, TraversalDirection::Incoming, 1)?
+                .into_iter()
+                .filter(|node| node.node_type == NodeType::ToolResult)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let task_excerpt = reflection::truncate(task.trim(), 500);
+        for lesson in &lessons {
+            let properties = json!({
+                "lesson": lesson.lesson,
+                "cause": lesson.cause,
+                "tools": lesson.tools,
+                "success": lesson.success,
+                "task": task_excerpt,
+                "created_at": Utc::now().to_rfc3339(),
+            });
+            let lesson_id = self.persistence.insert_graph_node(
+                &self.session_id,
+                NodeType::Lesson,
+                "Lesson",
+                &properties,
+                None,
+            )?;
+
+            if let Some(goal_id) = goal_node_id {
+                self.persistence.insert_graph_edge(
+                    &self.session_id,
+                    lesson_id,
+                    goal_id,
+                    EdgeType::RelatesTo,
+                    Some("learned_from"),
+                    None,
+                    1.0,
+                )?;
+            }
+            for tool_node in tool_nodes
+                .iter()
+                .filter(|node| lesson.tools.iter().any(|t| t == &node.label))
+            {
+                self.persistence.insert_graph_edge(
+                    &self.session_id,
+                    lesson_id,
+                    tool_node.id,
+                    EdgeType::RelatesTo,
+                    Some("about_tool"),
+                    None,
+                    if lesson.success { 0.8 } else { 1.0 },
+                )?;
+            }
+        }
+
+        Ok(lessons.len())
+    }
+
+    /// Recall relevant memories for the given input, led by the
+    /// neighborhoods of pinned graph nodes whatever their similarity
+    ///
+    /// A repeated input reuses the earlier result while the session's
+    /// revision is unchanged; such a hit does not count as another access
+    /// of the recalled memories.
+    async fn recall_memories(&self, query: &str) -> Result<RecallResult> {
+        // Recall reaching into other sessions changes with them, so only
+        // recall from this session alone is cached
+        let revision = if self.profile.recall_scope != RecallScope::Session {
+            None
+        } else {
+            match self.persistence.session_revision(&self.session_id) {
+                Ok(revision) => Some(revision),
+                Err(err) => {
+                    warn!("Failed to read session revision: {}", err);
+                    None
+                }
+            }
+        };
+        if let Some(revision) = &revision {
+            let cached = self
+                .recall_cache
+                .lock()
+                .ok()
+                .and_then(|mut cache| cache.recall(query, revision));
+            if let Some(cached) = cached {
+                debug!(
+                    "Reusing recall for an unchanged session {}",
+                    self.session_id
+                );
+                return Ok(cached);
+            }
+        }
+
+        let mut recalled = self.recall_relevant_memories(query).await?;
+        if self.profile.enable_graph {
+            let pinned = self.pinned_graph_context(&recalled.messages, &mut recalled.graph)?;
+            recalled.messages.splice(0..0, pinned);
+        }
+        if let (Some(revision), Ok(mut cache)) = (revision, self.recall_cache.lock()) {
+            cache.store_recall(query, revision, recalled.clone());
+        }
+        Ok(recalled)
+    }
+
+    fn has_cached_embedding(&self, text: &str) -> bool {
+        self.recall_cache
+            .lock()
+            .is_ok_and(|cache| cache.has_embedding(text))
+    }
+
+    /// Embed the session's queued messages now rather than waiting for the
+    /// background worker; returns how many were embedded
+    pub async fn flush_embeddings(&self) -> Result<usize> {
+        match (&self.embedding_queue, &self.embeddings_client) {
+            (Some(_), Some(client)) => {
+                embedding_queue::drain(&self.persistence, client, &self.session_id).await
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// Embed `text`, reusing the embedding of the same text from earlier in
+    /// the step; `stage` names the timing of a fresh embedding
+    async fn embed_text(
+        &self,
+        client: &EmbeddingsClient,
+        text: &str,
+        stage: &str,
+    ) -> Result<Vec<f32>> {
+        let cached = self
+            .recall_cache
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.embedding(text));
+        if let Some(embedding) = cached {
+            return Ok(embedding);
+        }
+
+        let embed_timer = Instant::now();
+        let result = client.embed_batch(&[text]).await;
+        self.log_timing(stage, embed_timer);
+        let embedding = result?.pop().unwrap_or_default();
+        if let Ok(mut cache) = self.recall_cache.lock() {
+            cache.store_embedding(text, &embedding);
+        }
+        Ok(embedding)
+    }
+
+    /// Context for the session's pinned graph nodes and their neighbors,
+    /// leaving out messages already in `recalled`
+    fn pinned_graph_context(
+        &self,
+        recalled: &[Message],
+        trace: &mut GraphRecallTrace,
+    ) -> Result<Vec<Message>> {
+        const NEIGHBORS_PER_PIN: usize = 20;
+
+        let mut seen_messages: HashSet<i64> = recalled.iter().map(|m| m.id).collect();
+        let mut seen_nodes = HashSet::new();
+        let mut context = Vec::new();
+        for pinned in self.persistence.list_pinned_graph_nodes(&self.session_id)? {
+            if !seen_nodes.insert(pinned.id) {
+                continue;
+            }
+            if let Some(message) = self.graph_node_message(&pinned, &mut seen_messages)? {
+                trace.record(
+                    GraphInfluence {
+                        kind: GraphInfluenceKind::Pinned,
+                        node_id: pinned.id,
+                        node_type: pinned.node_type.as_str().to_string(),
+                        label: pinned.label.clone(),
+                        seed_node_id: None,
+                        depth: 0,
+                        edge: None,
+                        edge_weight: None,
+                        score: None,
+                        included: true,
+                    },
+                    &message.content,
+                );
+                context.push(message);
+            }
+
+            let neighbors = self.persistence.traverse_neighbors_with_edges(
+                &self.session_id,
+                pinned.id,
+                TraversalDirection::Both,
+                self.profile.graph_depth,
+            )?;
+            let mut added = 0;
+            for neighbor in neighbors {
+                if !seen_nodes.insert(neighbor.node.id) {
+                    continue;
+                }
+                let influence =
+                    graph_influence(GraphInfluenceKind::Pinned, pinned.id, &neighbor, None);
+                if added == NEIGHBORS_PER_PIN {
+                    trace.record(
+                        GraphInfluence {
+                            included: false,
+                            ..influence
+                        },
+                        "",
+                    );
+                    continue;
+                }
+                if let Some(message) =
+                    self.graph_node_message(&neighbor.node, &mut seen_messages)?
+                {
+                    trace.record(influence, &message.content);
+                    context.push(message);
+                    added += 1;
+                }
+            }
+        }
+        Ok(context)
+    }
+
+    /// A graph node as a context message: the message a message node stands
+    /// for (unless already in `seen_messages`), else a synthetic system note
+    fn graph_node_message(
+        &self,
+        node: &GraphNode,
+        seen_messages: &mut HashSet<i64>,
+    ) -> Result<Option<Message>> {
+        if node.node_type == NodeType::Message {
+            let Some(message_id) = node.properties["message_id"].as_i64() else {
+                return Ok(None);
+            };
+            if !seen_messages.insert(message_id) {
+                return Ok(None);
+            }
+            return self.persistence.get_message(message_id);
+        }
+        Ok(Some(Message {
+            id: -1, // Synthetic ID
+            session_id: self.session_id.clone(),
+            role: MessageRole::System,
+            content: format!(
+                "[Pinned Graph Context - {} {}]: {}",
+                node.node_type.as_str(),
+                node.label,
+                node.properties
+            ),
+            created_at: Utc::now(),
+        }))
+    }
+
+    /// Recall memories by recency, graph links and similarity to the input
+    async fn recall_relevant_memories(&self, query: &str) -> Result<RecallResult> {
+        const RECENT_CONTEXT: i64 = 2;
+        // const MIN_MESSAGES_FOR_SEMANTIC_RECALL: usize = 3;
+        let mut context = Vec::new();
+        let mut seen_ids = HashSet::new();
+        let mut graph_trace = GraphRecallTrace::default();
+
+        let recent_messages = self
+            .persistence
+            .list_messages(&self.session_id, RECENT_CONTEXT)?;
+
+        // Optimization: Skip semantic recall for very new sessions (first interaction only)
+        // This saves embedding generation time when there's insufficient history
+        // and nothing to recall from other sessions
+        if self.conversation_history.is_empty()
+            && recent_messages.is_empty()
+            && self.profile.recall_scope == RecallScope::Session
+        {
+            return Ok(RecallResult {
+                messages: Vec::new(),
+                stats: Some(MemoryRecallStats {
+                    strategy: MemoryRecallStrategy::RecentContext {
+                        limit: RECENT_CONTEXT as usize,
+                    },
+                    scope: RecallScope::Session,
+                    matches: Vec::new(),
+                }),
+                graph: graph_trace,
+            });
+        }
+
+        for message in recent_messages {
+            seen_ids.insert(message.id);
+            context.push(message);
         }
 
         // If graph memory is enabled, expand context with graph-connected nodes
@@ -714,7 +2302,7 @@ impl AgentCore {
                     if let Some(msg_id) = node.properties["message_id"].as_i64() {
                         if msg_id == msg.id {
                             // Traverse graph to find related nodes
-                            let neighbors = self.persistence.traverse_neighbors(
+                            let neighbors = self.persistence.traverse_neighbors_with_edges(
                                 &self.session_id,
                                 node.id,
                                 TraversalDirection::Both,
@@ -723,15 +2311,24 @@ impl AgentCore {
 
                             // Add messages from related nodes
                             for neighbor in neighbors {
-                                if neighbor.node_type == NodeType::Message {
+                                if neighbor.node.node_type == NodeType::Message {
                                     if let Some(related_msg_id) =
-                                        neighbor.properties["message_id"].as_i64()
+                                        neighbor.node.properties["message_id"].as_i64()
                                     {
                                         if !seen_ids.contains(&related_msg_id) {
                                             if let Some(related_msg) =
                                                 self.persistence.get_message(related_msg_id)?
                                             {
                                                 seen_ids.insert(related_msg.id);
+                                                graph_trace.record(
+                                                    graph_influence(
+                                                        GraphInfluenceKind::RecentContext,
+                                                        node.id,
+                                                        &neighbor,
+                                                        None,
+                                                    ),
+                                                    &related_msg.content,
+                                                );
                                                 graph_messages.push(related_msg);
                                             }
                                         }
@@ -752,181 +2349,307 @@ impl AgentCore {
                 return Ok(RecallResult {
                     messages: context,
                     stats: None,
+                    graph: graph_trace,
                 });
             }
 
-            let embed_timer = Instant::now();
-            let embed_result = client.embed_batch(&[query]).await;
-            self.log_timing("recall_memories.embed_batch", embed_timer);
+            // Messages still waiting for their embedding cannot match yet
+            for message in self
+                .persistence
+                .unembedded_messages(&self.session_id, self.profile.memory_k)?
+            {
+                if seen_ids.insert(message.id) {
+                    context.push(message);
+                }
+            }
+
+            let embed_result = self
+                .embed_text(client, query, "recall_memories.embed_batch")
+                .await;
             match embed_result {
-                Ok(mut embeddings) => match embeddings.pop() {
-                    Some(query_embedding) if !query_embedding.is_empty() => {
-                        let recalled = self.persistence.recall_top_k(
-                            &self.session_id,
-                            &query_embedding,
-                            self.profile.memory_k,
-                        )?;
+                Ok(query_embedding) if !query_embedding.is_empty() => {
+                    let importance = MemoryImportanceParams {
+                        weight: self.profile.memory_importance_weight,
+                        half_life_hours: self.profile.memory_decay_half_life_hours,
+                    };
+                    let recalled = self.persistence.recall_top_k_in_scope(
+                        &self.session_id,
+                        self.profile.recall_scope,
+                        self.agent_name.as_deref(),
+                        &query_embedding,
+                        self.profile.memory_k,
+                        &importance,
+                    )?;
+
+                    // Recall counts feed the frequency component of importance
+                    let recalled_ids: Vec<i64> = recalled.iter().map(|(m, _)| m.id).collect();
+                    if let Err(err) = self.persistence.record_memory_access(&recalled_ids) {
+                        warn!("Failed to record memory access: {}", err);
+                    }
 
-                        let mut matches = Vec::new();
-                        let mut semantic_context = Vec::new();
+                    let mut matches = Vec::new();
+                    let mut semantic_context = Vec::new();
 
-                        for (memory, score) in recalled {
-                            if let Some(message_id) = memory.message_id {
-                                if seen_ids.contains(&message_id) {
-                                    continue;
-                                }
+                    for (memory, score) in recalled {
+                        if let Some(message_id) = memory.message_id {
+                            if seen_ids.contains(&message_id) {
+                                continue;
+                            }
 
-                                if let Some(message) = self.persistence.get_message(message_id)? {
-                                    seen_ids.insert(message.id);
-                                    matches.push(MemoryRecallMatch {
-                                        message_id: Some(message.id),
-                                        score,
-                                        role: message.role.clone(),
-                                        preview: preview_text(&message.content),
-                                    });
-                                    semantic_context.push(message);
-                                }
-                            } else {
-                                // Check if this is a transcription embedding
-                                if let Some(transcription_text) =
-                                    self.persistence.get_transcription_by_embedding(memory.id)?
-                                {
-                                    // Create a synthetic message for the transcription
-                                    let transcription_message = Message {
-                                        id: memory.id, // Use embedding ID as synthetic message ID
-                                        session_id: memory.session_id.clone(),
-                                        role: MessageRole::User, // Transcriptions are user input
-                                        content: format!("[Transcription] {}", transcription_text),
-                                        created_at: memory.created_at,
-                                    };
+                            if let Some(message) = self.persistence.get_message(message_id)? {
+                                seen_ids.insert(message.id);
+                                matches.push(MemoryRecallMatch {
+                                    message_id: Some(message.id),
+                                    session_id: (message.session_id != self.session_id)
+                                        .then(|| message.session_id.clone()),
+                                    score,
+                                    role: message.role.clone(),
+                                    preview: preview_text(&message.content),
+                                });
+                                semantic_context.push(message);
+                            }
+                        } else {
+                            // Check if this is a transcription embedding
+                            if let Some(transcription_text) =
+                                self.persistence.get_transcription_by_embedding(memory.id)?
+                            {
+                                // Create a synthetic message for the transcription
+                                let transcription_message = Message {
+                                    id: memory.id, // Use embedding ID as synthetic message ID
+                                    session_id: memory.session_id.clone(),
+                                    role: MessageRole::User, // Transcriptions are user input
+                                    content: format!("[Transcription] {}", transcription_text),
+                                    created_at: memory.created_at,
+                                };
+
+                                matches.push(MemoryRecallMatch {
+                                    message_id: None, // No actual message ID
+                                    session_id: (memory.session_id != self.session_id)
+                                        .then(|| memory.session_id.clone()),
+                                    score,
+                                    role: MessageRole::User,
+                                    preview: preview_text(&transcription_text),
+                                });
+                                semantic_context.push(transcription_message);
+                            } else if let Some(summary) =
+                                self.persistence.memory_summary_by_embedding(memory.id)?
+                            {
+                                // Stands in for the messages it condensed
+                                matches.push(MemoryRecallMatch {
+                                    message_id: None,
+                                    session_id: (memory.session_id != self.session_id)
+                                        .then(|| memory.session_id.clone()),
+                                    score,
+                                    role: MessageRole::System,
+                                    preview: preview_text(&summary.summary),
+                                });
+                                semantic_context.push(Message {
+                                    id: memory.id,
+                                    session_id: memory.session_id.clone(),
+                                    role: MessageRole::System,
+                                    content: format!(
+                                        "[Summary of messages #{}-#{}] {}",
+                                        summary.first_message_id,
+                                        summary.last_message_id,
+                                        summary.summary
+                                    ),
+                                    created_at: summary.created_at,
+                                });
+                            }
+                        }
+                    }
 
-                                    matches.push(MemoryRecallMatch {
-                                        message_id: None, // No actual message ID
-                                        score,
-                                        role: MessageRole::User,
-                                        preview: preview_text(&transcription_text),
+                    // If graph memory enabled, expand semantic matches with graph connections
+                    if self.profile.enable_graph && self.profile.graph_memory {
+                        let mut graph_expanded = Vec::new();
+                        let mut expanded_influences = Vec::new();
+
+                        // Facts, concepts and entities close to the input in their own right
+                        let mut matched_nodes = HashSet::new();
+                        let graph = KnowledgeGraphStore::new(Arc::new(self.persistence.clone()))
+                            .with_embeddings(Some(client.clone()));
+                        match graph
+                            .search_nodes_semantic(
+                                &self.session_id,
+                                query_embedding.clone(),
+                                self.profile.memory_k,
+                            )
+                            .await
+                        {
+                            Ok(hits) => {
+                                for (node, score) in hits {
+                                    if score <= 0.0
+                                        || !matches!(
+                                            node.node_type,
+                                            NodeType::Fact | NodeType::Concept | NodeType::Entity
+                                        )
+                                    {
+                                        continue;
+                                    }
+                                    matched_nodes.insert(node.id);
+                                    expanded_influences.push(GraphInfluence {
+                                        kind: GraphInfluenceKind::SemanticMatch,
+                                        node_id: node.id,
+                                        node_type: node.node_type.as_str().to_string(),
+                                        label: node.label.clone(),
+                                        seed_node_id: None,
+                                        depth: 0,
+                                        edge: None,
+                                        edge_weight: None,
+                                        score: Some(score),
+                                        included: true,
+                                    });
+                                    graph_expanded.push(Message {
+                                        id: -1, // Synthetic ID
+                                        session_id: self.session_id.clone(),
+                                        role: MessageRole::System,
+                                        content: format!(
+                                            "[Graph Context - {} {}]: {}",
+                                            node.node_type.as_str(),
+                                            node.label,
+                                            node.properties
+                                        ),
+                                        created_at: Utc::now(),
                                     });
-                                    semantic_context.push(transcription_message);
                                 }
                             }
+                            Err(err) => warn!("Failed to search graph nodes: {:#}", err),
                         }
 
-                        // If graph memory enabled, expand semantic matches with graph connections
-                        if self.profile.enable_graph && self.profile.graph_memory {
-                            let mut graph_expanded = Vec::new();
+                        for (msg, seed) in semantic_context.iter().zip(&matches) {
+                            // Find message node in graph
+                            let nodes = self.persistence.list_graph_nodes(
+                                &self.session_id,
+                                Some(NodeType::Message),
+                                Some(100),
+                            )?;
 
-                            for msg in &semantic_context {
-                                // Find message node in graph
-                                let nodes = self.persistence.list_graph_nodes(
-                                    &self.session_id,
-                                    Some(NodeType::Message),
-                                    Some(100),
-                                )?;
-
-                                for node in nodes {
-                                    if let Some(msg_id) = node.properties["message_id"].as_i64() {
-                                        if msg_id == msg.id {
-                                            // Traverse to find related information
-                                            let neighbors = self.persistence.traverse_neighbors(
+                            for node in nodes {
+                                if let Some(msg_id) = node.properties["message_id"].as_i64() {
+                                    if msg_id == msg.id {
+                                        // Traverse to find related information
+                                        let neighbors =
+                                            self.persistence.traverse_neighbors_with_edges(
                                                 &self.session_id,
                                                 node.id,
                                                 TraversalDirection::Both,
                                                 self.profile.graph_depth,
                                             )?;
 
-                                            for neighbor in neighbors {
-                                                // Include related facts, concepts, and entities
-                                                if matches!(
-                                                    neighbor.node_type,
-                                                    NodeType::Fact
-                                                        | NodeType::Concept
-                                                        | NodeType::Entity
-                                                ) {
-                                                    // Create a synthetic message for graph context
-                                                    let graph_content = format!(
-                                                        "[Graph Context - {} {}]: {}",
-                                                        neighbor.node_type.as_str(),
-                                                        neighbor.label,
-                                                        neighbor.properties
-                                                    );
-
-                                                    // Add as system message for context
-                                                    let graph_msg = Message {
-                                                        id: -1, // Synthetic ID
-                                                        session_id: self.session_id.clone(),
-                                                        role: MessageRole::System,
-                                                        content: graph_content,
-                                                        created_at: Utc::now(),
-                                                    };
-
-                                                    graph_expanded.push(graph_msg);
-                                                }
+                                        for neighbor in neighbors {
+                                            // Include related facts, concepts, and entities
+                                            if matches!(
+                                                neighbor.node.node_type,
+                                                NodeType::Fact
+                                                    | NodeType::Concept
+                                                    | NodeType::Entity
+                                            ) && !matched_nodes.contains(&neighbor.node.id)
+                                            {
+                                                // Create a synthetic message for graph context
+                                                let graph_content = format!(
+                                                    "[Graph Context - {} {}]: {}",
+                                                    neighbor.node.node_type.as_str(),
+                                                    neighbor.node.label,
+                                                    neighbor.node.properties
+                                                );
+                                                expanded_influences.push(graph_influence(
+                                                    GraphInfluenceKind::SemanticExpansion,
+                                                    node.id,
+                                                    &neighbor,
+                                                    Some(seed.score),
+                                                ));
+
+                                                // Add as system message for context
+                                                let graph_msg = Message {
+                                                    id: -1, // Synthetic ID
+                                                    session_id: self.session_id.clone(),
+                                                    role: MessageRole::System,
+                                                    content: graph_content,
+                                                    created_at: Utc::now(),
+                                                };
+
+                                                graph_expanded.push(graph_msg);
                                             }
                                         }
                                     }
                                 }
                             }
+                        }
 
-                            // Combine semantic and graph-expanded context with weighted limits
-                            let total_slots = self.profile.memory_k.max(1);
-                            let mut graph_limit =
-                                ((total_slots as f32) * self.profile.graph_weight).round() as usize;
-                            graph_limit = graph_limit.min(total_slots);
-                            if graph_limit == 0 && !graph_expanded.is_empty() {
-                                graph_limit = 1;
-                            }
-
-                            let mut semantic_limit = total_slots.saturating_sub(graph_limit);
-                            if semantic_limit == 0 && !semantic_context.is_empty() {
-                                semantic_limit = 1;
-                                graph_limit = graph_limit.saturating_sub(1);
-                            }
+                        // Combine semantic and graph-expanded context with weighted limits
+                        let total_slots = self.profile.memory_k.max(1);
+                        let mut graph_limit =
+                            ((total_slots as f32) * self.profile.graph_weight).round() as usize;
+                        graph_limit = graph_limit.min(total_slots);
+                        if graph_limit == 0 && !graph_expanded.is_empty() {
+                            graph_limit = 1;
+                        }
 
-                            let mut limited_semantic = semantic_context;
-                            if limited_semantic.len() > semantic_limit && semantic_limit > 0 {
-                                limited_semantic.truncate(semantic_limit);
-                            }
+                        let mut semantic_limit = total_slots.saturating_sub(graph_limit);
+                        if semantic_limit == 0 && !semantic_context.is_empty() {
+                            semantic_limit = 1;
+                            graph_limit = graph_limit.saturating_sub(1);
+                        }
 
-                            let mut limited_graph = graph_expanded;
-                            if limited_graph.len() > graph_limit && graph_limit > 0 {
-                                limited_graph.truncate(graph_limit);
-                            }
+                        let mut limited_semantic = semantic_context;
+                        if limited_semantic.len() > semantic_limit && semantic_limit > 0 {
+                            limited_semantic.truncate(semantic_limit);
+                        }
 
-                            context.extend(limited_semantic);
-                            context.extend(limited_graph);
-                        } else {
-                            context.extend(semantic_context);
+                        let mut limited_graph = graph_expanded;
+                        if limited_graph.len() > graph_limit && graph_limit > 0 {
+                            limited_graph.truncate(graph_limit);
+                        }
+                        for (index, mut influence) in expanded_influences.into_iter().enumerate() {
+                            let content = match limited_graph.get(index) {
+                                Some(message) => message.content.as_str(),
+                                None => {
+                                    influence.included = false;
+                                    ""
+                                }
+                            };
+                            graph_trace.record(influence, content);
                         }
 
-                        return Ok(RecallResult {
-                            messages: context,
-                            stats: Some(MemoryRecallStats {
-                                strategy: MemoryRecallStrategy::Semantic {
-                                    requested: self.profile.memory_k,
-                                    returned: matches.len(),
-                                },
-                                matches,
-                            }),
-                        });
-                    }
-                    _ => {
-                        return Ok(RecallResult {
-                            messages: context,
-                            stats: Some(MemoryRecallStats {
-                                strategy: MemoryRecallStrategy::Semantic {
-                                    requested: self.profile.memory_k,
-                                    returned: 0,
-                                },
-                                matches: Vec::new(),
-                            }),
-                        });
+                        context.extend(limited_semantic);
+                        context.extend(limited_graph);
+                    } else {
+                        context.extend(semantic_context);
                     }
-                },
+
+                    return Ok(RecallResult {
+                        messages: context,
+                        stats: Some(MemoryRecallStats {
+                            strategy: MemoryRecallStrategy::Semantic {
+                                requested: self.profile.memory_k,
+                                returned: matches.len(),
+                            },
+                            scope: self.profile.recall_scope,
+                            matches,
+                        }),
+                        graph: graph_trace,
+                    });
+                }
+                Ok(_) => {
+                    return Ok(RecallResult {
+                        messages: context,
+                        stats: Some(MemoryRecallStats {
+                            strategy: MemoryRecallStrategy::Semantic {
+                                requested: self.profile.memory_k,
+                                returned: 0,
+                            },
+                            scope: self.profile.recall_scope,
+                            matches: Vec::new(),
+                        }),
+                        graph: graph_trace,
+                    });
+                }
                 Err(err) => {
                     warn!("Failed to embed recall query: {}", err);
                     return Ok(RecallResult {
                         messages: context,
                         stats: None,
+                        graph: graph_trace,
                     });
                 }
             }
@@ -941,17 +2664,47 @@ impl AgentCore {
                 strategy: MemoryRecallStrategy::RecentContext {
                     limit: self.profile.memory_k,
                 },
+                scope: RecallScope::Session,
                 matches: Vec::new(),
             })
         } else {
             None
         };
 
-        Ok(RecallResult { messages, stats })
+        Ok(RecallResult {
+            messages,
+            stats,
+            graph: graph_trace,
+        })
+    }
+
+    /// The profile's `context_providers`, plus `time` when `time_context` is on.
+    /// Deterministic runs leave the clock out so the prompt does not change
+    /// from one run to the next.
+    fn enabled_context_providers(&self) -> Vec<ContextProviderConfig> {
+        let mut enabled = self.profile.context_providers.clone();
+        if self.profile.time_context && !enabled.iter().any(|provider| provider.name() == "time") {
+            enabled.push(ContextProviderConfig::Name("time".to_string()));
+        }
+        if self.profile.deterministic {
+            enabled.retain(|provider| provider.name() != "time");
+        }
+        enabled
+    }
+
+    /// Tool description for the prompt; deterministic runs use configured
+    /// hints only because measured latency tiers can differ between runs
+    fn prompt_tool_description(&self, name: &str) -> Option<String> {
+        if self.profile.deterministic {
+            self.tool_registry.describe_configured(name)
+        } else {
+            self.tool_registry.describe(name)
+        }
     }
 
-    /// Build the prompt from system prompt, context, and user input
-    async fn build_prompt(&self, input: &str, context_messages: &[Message]) -> Result<String> {
+    /// Build the prompt from system prompt, context, and user input, as a
+    /// step sends it before any tool results
+    pub async fn build_prompt(&self, input: &str, context_messages: &[Message]) -> Result<String> {
         let mut prompt = String::new();
 
         // Add system prompt if configured
@@ -961,26 +2714,101 @@ impl AgentCore {
             prompt.push_str("\n\n");
         }
 
+        if let Some(target) = self
+            .response_language_setting()
+            .and_then(|setting| language::resolve_target(&setting, input))
+        {
+            prompt.push_str(&language::instruction(&target));
+            prompt.push_str("\n\n");
+        }
+
+        let context = self.context_providers.render(
+            &self.enabled_context_providers(),
+            &ContextRequest {
+                clock: &self.clock,
+                spec: self.active_spec.as_deref(),
+                working_dir: std::env::current_dir().ok(),
+            },
+        );
+        if !context.is_empty() {
+            prompt.push_str(&context);
+            prompt.push_str("\n\n");
+        }
+
+        if let Some(limit) = self.user_profile_facts {
+            let block = user_profile::render_block(&self.persistence.user_profile_list()?, limit);
+            if !block.is_empty() {
+                prompt.push_str(&block);
+                prompt.push('\n');
+            }
+        }
+
         // Add tool instructions
         let available_tools = self.tool_registry.list();
         tracing::debug!("Tool registry has {} tools", available_tools.len());
         if !available_tools.is_empty() {
             prompt.push_str("Available tools:\n");
+            let hidden = self.hidden_tools();
+            let mut has_hints = false;
             for tool_name in &available_tools {
-                info!(
-                    "Checking tool: {} - allowed: {}",
-                    tool_name,
-                    self.is_tool_allowed(tool_name).await
-                );
-                if self.is_tool_allowed(tool_name).await {
-                    if let Some(tool) = self.tool_registry.get(tool_name) {
-                        prompt.push_str(&format!("- {}: {}\n", tool_name, tool.description()));
+                if hidden.contains(*tool_name) {
+                    continue;
+                }
+                let offered = self.is_tool_offered(tool_name).await;
+                info!("Checking tool: {} - allowed: {}", tool_name, offered);
+                if offered {
+                    if let Some(description) = self.prompt_tool_description(tool_name) {
+                        has_hints |= if self.profile.deterministic {
+                            self.tool_registry
+                                .hints()
+                                .configured_hint(tool_name)
+                                .is_some()
+                        } else {
+                            self.tool_registry.hints().hint(tool_name).is_some()
+                        };
+                        prompt.push_str(&format!("- {}: {}\n", tool_name, description));
+                    }
+                }
+            }
+            for tool_name in self.tool_registry.remote_tools() {
+                if !hidden.contains(&tool_name) && self.is_tool_offered(&tool_name).await {
+                    if let Some(description) = self.prompt_tool_description(&tool_name) {
+                        prompt.push_str(&format!("- {}: {}\n", tool_name, description));
                     }
                 }
             }
+            if has_hints {
+                prompt.push_str(
+                    "Bracketed hints give relative latency and cost; when tools would give equivalent results, prefer the cheaper and faster one.\n",
+                );
+            }
             prompt.push('\n');
         }
 
+        // Surface lessons learned from similar earlier tasks
+        if self.profile.enable_graph {
+            let lesson_nodes = self.persistence.list_graph_nodes(
+                &self.session_id,
+                Some(NodeType::Lesson),
+                Some(100),
+            )?;
+            let relevant = reflection::relevant_lessons(input, &lesson_nodes, 3);
+            if !relevant.is_empty() {
+                prompt.push_str("Lessons from previous runs:\n");
+                for node in relevant {
+                    prompt.push_str(&format!("- {}\n", reflection::format_lesson(node)));
+                }
+                prompt.push('\n');
+            }
+        }
+
+        if let Some(anchor) = self.persistence.context_anchor(&self.session_id)? {
+            prompt.push_str(compaction::SUMMARY_HEADING);
+            prompt.push('\n');
+            prompt.push_str(anchor.summary.trim());
+            prompt.push_str("\n\n");
+        }
+
         // Add conversation context
         if !context_messages.is_empty() {
             prompt.push_str("Previous conversation:\n");
@@ -995,7 +2823,9 @@ impl AgentCore {
 
         prompt.push_str("assistant:");
 
-        Ok(prompt)
+        // Stored text carries tokens for personal data; the model gets the
+        // originals
+        self.persistence.detokenize(&prompt)
     }
 
     /// Store a message in persistence
@@ -1016,30 +2846,36 @@ impl AgentCore {
             .context("Failed to store message")?;
 
         let mut embedding_id = None;
+        // Left to the background worker unless the embedding is at hand
+        let mut queue_embedding = false;
 
         if let Some(client) = &self.embeddings_client {
-            if !content.trim().is_empty() {
-                let embed_timer = Instant::now();
-                let embed_result = client.embed_batch(&[content]).await;
-                self.log_timing("embeddings.message_content", embed_timer);
+            if !content.trim().is_empty()
+                && self.embedding_queue.is_some()
+                && !self.has_cached_embedding(content)
+            {
+                queue_embedding = true;
+            } else if !content.trim().is_empty() {
+                // The user's input was usually embedded for recall already
+                let embed_result = self
+                    .embed_text(client, content, "embeddings.message_content")
+                    .await;
                 match embed_result {
-                    Ok(mut embeddings) => {
-                        if let Some(embedding) = embeddings.pop() {
-                            if !embedding.is_empty() {
-                                match self.persistence.insert_memory_vector(
-                                    &self.session_id,
-                                    Some(message_id),
-                                    &embedding,
-                                ) {
-                                    Ok(emb_id) => {
-                                        embedding_id = Some(emb_id);
-                                    }
-                                    Err(err) => {
-                                        warn!(
-                                            "Failed to persist embedding for message {}: {}",
-                                            message_id, err
-                                        );
-                                    }
+                    Ok(embedding) => {
+                        if !embedding.is_empty() {
+                            match self.persistence.insert_memory_vector(
+                                &self.session_id,
+                                Some(message_id),
+                                &embedding,
+                            ) {
+                                Ok(emb_id) => {
+                                    embedding_id = Some(emb_id);
+                                }
+                                Err(err) => {
+                                    warn!(
+                                        "Failed to persist embedding for message {}: {}",
+                                        message_id, err
+                                    );
                                 }
                             }
                         }
@@ -1055,14 +2891,31 @@ impl AgentCore {
         }
 
         // If auto_graph is enabled, create graph nodes and edges
+        let mut node_id = None;
         if self.profile.enable_graph && self.profile.auto_graph {
-            self.build_graph_for_message(message_id, role, content, embedding_id, reasoning)?;
+            node_id = Some(self.build_graph_for_message(
+                message_id,
+                role,
+                content,
+                embedding_id,
+                reasoning,
+            )?);
+        }
+
+        if let Some(queue) = self.embedding_queue.as_ref().filter(|_| queue_embedding) {
+            self.persistence
+                .embedding_queue_push(&self.session_id, message_id, node_id)?;
+            queue.notify();
+        }
+        if let Some(compactor) = &self.memory_compactor {
+            compactor.notify();
         }
 
         Ok(message_id)
     }
 
-    /// Build graph nodes and edges for a new message
+    /// Build graph nodes and edges for a new message, returning the
+    /// message's node
     fn build_graph_for_message(
         &self,
         message_id: i64,
@@ -1070,7 +2923,7 @@ impl AgentCore {
         content: &str,
         embedding_id: Option<i64>,
         reasoning: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<i64> {
         use serde_json::json;
 
         // Create a node for the message
@@ -1225,7 +3078,7 @@ impl AgentCore {
             }
         }
 
-        Ok(())
+        Ok(message_node_id)
     }
 
     fn create_goal_context(
@@ -1482,6 +3335,71 @@ impl AgentCore {
         None
     }
 
+    /// Name the main model's tokens are counted for: the model name when
+    /// known, otherwise the provider's
+    pub fn tokenizer_model(&self) -> String {
+        self.model_name
+            .clone()
+            .unwrap_or_else(|| self.provider.metadata().name)
+    }
+
+    /// Whether tools go through the text protocol for `provider` rather than
+    /// native function calling
+    fn uses_text_tool_calls(&self, provider: &dyn ModelProvider) -> bool {
+        match self.profile.tool_calling {
+            ToolCallingMode::Native => false,
+            ToolCallingMode::Text => true,
+            ToolCallingMode::Auto => !provider.metadata().supports_function_calling,
+        }
+    }
+
+    /// Name and parameter schema of every tool this agent may call, sorted
+    /// so the prompt is stable
+    async fn text_tool_parameters(&self) -> Vec<(String, Value)> {
+        let mut names: Vec<String> = self
+            .tool_registry
+            .list()
+            .into_iter()
+            .map(str::to_string)
+            .chain(self.tool_registry.remote_tools())
+            .collect();
+        names.sort();
+        let hidden = self.hidden_tools();
+        let mut tools = Vec::new();
+        for name in names {
+            if hidden.contains(&name) || !self.is_tool_offered(&name).await {
+                continue;
+            }
+            if let Some(tool) = self.tool_registry.get(&name) {
+                tools.push((name, tool.parameters()));
+            }
+        }
+        tools
+    }
+
+    /// Provider for a step of `class`: its `model_routes` entry, or the main
+    /// provider when the class has none
+    fn select_route(&self, class: TaskClass) -> (Arc<dyn ModelProvider>, RouteReport) {
+        match self.router.get(class) {
+            Some(route) => (
+                route.provider.clone(),
+                RouteReport {
+                    task: class.as_str().to_string(),
+                    model: route.label.clone(),
+                    routed: true,
+                },
+            ),
+            None => (
+                self.provider.clone(),
+                RouteReport {
+                    task: class.as_str().to_string(),
+                    model: self.provider.metadata().name,
+                    routed: false,
+                },
+            ),
+        }
+    }
+
     fn estimate_task_complexity(&self, input: &str) -> f32 {
         let words = input.split_whitespace().count() as f32;
         let clauses =
@@ -1779,18 +3697,20 @@ impl AgentCore {
                 DEFAULT_FAST_TEMPERATURE
             };
 
-            let config = GenerationConfig {
+            let config = self.pin_if_deterministic(GenerationConfig {
                 temperature: Some(fast_temperature),
                 max_tokens: Some(256), // Keep responses short for speed
                 stop_sequences: None,
                 top_p: Some(DEFAULT_TOP_P),
                 frequency_penalty: None,
                 presence_penalty: None,
-            };
+                top_k: None,
+                seed: None,
+            });
 
-            let call_timer = Instant::now();
-            let response_result = fast_provider.generate(&prompt, &config).await;
-            self.log_timing("fast_reasoning.generate", call_timer);
+            let response_result = self
+                .call_model(fast_provider, "fast_reasoning.generate", &prompt, &config)
+                .await;
             let response = response_result?;
 
             let confidence = Self::parse_confidence(&response.content).unwrap_or(0.7);
@@ -1865,6 +3785,16 @@ impl AgentCore {
         concepts
     }
 
+    /// One-off completion outside the conversation: no history, memory or
+    /// tools, and nothing is persisted. Timed and logged as `stage`.
+    pub async fn complete(&self, stage: &str, prompt: &str) -> Result<String> {
+        let config = self.build_generation_config();
+        let response = self
+            .call_model(&self.provider, stage, prompt, &config)
+            .await?;
+        Ok(response.content)
+    }
+
     /// Get the current session ID
     pub fn session_id(&self) -> &str {
         &self.session_id
@@ -1880,6 +3810,11 @@ impl AgentCore {
         self.agent_name.as_deref()
     }
 
+    /// Client that embeds memories and recall queries, if configured
+    pub fn embeddings_client(&self) -> Option<&EmbeddingsClient> {
+        self.embeddings_client.as_ref()
+    }
+
     /// Get conversation history
     pub fn conversation_history(&self) -> &[Message] {
         &self.conversation_history
@@ -1916,19 +3851,147 @@ impl AgentCore {
         }
 
         // Then check policy engine
-        let agent_name = self.agent_name.as_deref().unwrap_or("agent");
-        let decision = self.policy_engine.check(agent_name, "tool_call", tool_name);
+        let decision = self.check_tool_policy(tool_name);
         debug!(
             "Policy check for tool '{}': decision={:?}",
             tool_name, decision
         );
 
-        let allowed = matches!(decision, PolicyDecision::Allow);
-        self.tool_permission_cache
-            .write()
-            .await
-            .insert(tool_name.to_string(), allowed);
-        allowed
+        let allowed = matches!(decision, PolicyDecision::Allow);
+        self.tool_permission_cache
+            .write()
+            .await
+            .insert(tool_name.to_string(), allowed);
+        allowed
+    }
+
+    /// Tools left out of the prompt and schema because every call this
+    /// agent made to them failed; see `advertise_failing_tools`
+    fn hidden_tools(&self) -> HashSet<String> {
+        if self.profile.advertise_failing_tools {
+            return HashSet::new();
+        }
+        let agent_name = self.agent_name.as_deref().unwrap_or("unknown");
+        match self.persistence.failing_tools(agent_name) {
+            Ok(hidden) => hidden,
+            Err(err) => {
+                warn!("Failed to load tool usage of '{}': {}", agent_name, err);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Policy decision for calling a tool. Tools sharing a backend (e.g. the
+    /// graph tools) are checked against their common resource instead of
+    /// their name, and some tools against an action other than `tool_call`.
+    fn check_tool_policy(&self, tool_name: &str) -> PolicyDecision {
+        let agent_name = self.agent_name.as_deref().unwrap_or("agent");
+        let (action, resource) = self.tool_policy_target(tool_name);
+        self.policy_engine.check(agent_name, &action, &resource)
+    }
+
+    /// Action and resource a call to the tool is checked against
+    fn tool_policy_target(&self, tool_name: &str) -> (String, String) {
+        match self.tool_registry.get(tool_name) {
+            Some(tool) => (
+                tool.policy_action().to_string(),
+                tool.policy_resource().to_string(),
+            ),
+            None => ("tool_call".to_string(), tool_name.to_string()),
+        }
+    }
+
+    /// Whether the profile and policy allow a tool call, recording the
+    /// decision in the policy audit log. A tool the profile refuses is
+    /// logged as denied by the profile, since the policy is never asked.
+    async fn authorize_tool_call(&self, run_id: &str, tool_name: &str) -> bool {
+        let allowed = self.is_tool_allowed(tool_name).await;
+        let agent_name = self.agent_name.as_deref().unwrap_or("agent");
+        let (action, resource) = self.tool_policy_target(tool_name);
+        let rule = if !self.profile.is_tool_allowed(tool_name) {
+            Some("agent profile (allowed_tools/denied_tools)".to_string())
+        } else {
+            self.policy_engine
+                .matching_rule(agent_name, &action, &resource)
+                .map(|(index, rule)| format!("#{} {}", index + 1, rule))
+        };
+        let record = PolicyDecisionRecord {
+            session_id: &self.session_id,
+            run_id,
+            agent: agent_name,
+            action: &action,
+            resource: &resource,
+            allowed,
+            rule: rule.as_deref(),
+        };
+        if let Err(err) = self.persistence.record_policy_decision(&record) {
+            warn!(
+                "Failed to record policy decision for '{}': {}",
+                tool_name, err
+            );
+        }
+        allowed
+    }
+
+    /// Whether the model is told about a tool: the profile and policy allow
+    /// it, or the running spec was granted a permission for it. Grants are
+    /// still checked per call by [`spec_permits`](Self::spec_permits).
+    async fn is_tool_offered(&self, tool_name: &str) -> bool {
+        self.spec_permissions
+            .iter()
+            .any(|permission| permission.tool == tool_name)
+            || self.is_tool_allowed(tool_name).await
+    }
+
+    /// Whether a permission granted to the running spec covers this call.
+    /// Checked per call and never cached, since path-scoped permissions
+    /// depend on the arguments.
+    fn spec_permits(&self, run_id: &str, tool_name: &str, args: &Value) -> bool {
+        if self.spec_permissions.is_empty() {
+            return false;
+        }
+        let Some(tool) = self.tool_registry.get(tool_name) else {
+            return false;
+        };
+        let Ok(base) = std::env::current_dir() else {
+            return false;
+        };
+        let args = self.scope_tool_args(tool_name, args);
+        // A missing path argument means the working directory
+        let paths: Vec<PathBuf> = tool
+            .workspace_args()
+            .iter()
+            .map(|name| match args.get(*name) {
+                Some(Value::String(path)) if !path.is_empty() => PathBuf::from(path),
+                _ => base.clone(),
+            })
+            .collect();
+        match self
+            .spec_permissions
+            .iter()
+            .find(|permission| permission.permits(tool_name, &paths, &base))
+        {
+            Some(permission) => {
+                info!(
+                    "Spec permission '{}' allows '{}' in {}",
+                    permission, tool_name, run_id
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Error shown to the model for a tool call that was not allowed, with
+    /// the alternative suggested by the denying policy rule, if any
+    fn denied_tool_error(&self, tool_name: &str, error: String) -> String {
+        match self.check_tool_policy(tool_name) {
+            PolicyDecision::Deny(PolicyDenial {
+                alternative: Some(alternative),
+                ..
+            }) => format!("{}. Policy suggests instead: {}", error, alternative),
+            _ => error,
+        }
     }
 
     /// Prompt user for permission to use a tool
@@ -2024,31 +4087,127 @@ impl AgentCore {
     }
 
     /// Execute a tool and log the result
+    /// Fill in the current session for session-scoped tools; the model
+    /// never sees the session id, so it cannot supply it itself. Path
+    /// arguments are resolved against the session's focused workspace.
+    fn scope_tool_args(&self, tool_name: &str, args: &Value) -> Value {
+        let mut args = args.clone();
+        let Some(tool) = self.tool_registry.get(tool_name) else {
+            return args;
+        };
+        let Some(object) = args.as_object_mut() else {
+            return args;
+        };
+        if tool.session_scoped() {
+            let present =
+                matches!(object.get("session_id"), Some(Value::String(id)) if !id.is_empty());
+            if !present {
+                object.insert(
+                    "session_id".to_string(),
+                    Value::String(self.session_id.clone()),
+                );
+            }
+        }
+        if tool.workspace_args().is_empty() && !tool.confined() {
+            return args;
+        }
+        let workspace = self
+            .persistence
+            .workspace_focused(&self.session_id)
+            .ok()
+            .flatten();
+        if let Some(workspace) = &workspace {
+            for name in tool.workspace_args() {
+                let resolved = match object.get(*name) {
+                    Some(Value::String(path)) if !path.is_empty() => {
+                        let path = Path::new(path);
+                        if path.is_absolute() {
+                            continue;
+                        }
+                        workspace.root.join(path)
+                    }
+                    None | Some(Value::Null) => workspace.root.clone(),
+                    Some(_) => continue,
+                };
+                object.insert(
+                    name.to_string(),
+                    Value::String(resolved.display().to_string()),
+                );
+            }
+        }
+        // Confined tools check paths against this root, so the model
+        // cannot pick its own
+        if tool.confined() {
+            object.remove("workspace_root");
+            let root = workspace
+                .map(|workspace| workspace.root)
+                .or_else(|| std::env::current_dir().ok());
+            if let Some(root) = root {
+                object.insert(
+                    "workspace_root".to_string(),
+                    Value::String(root.display().to_string()),
+                );
+            }
+        }
+        args
+    }
+
     async fn execute_tool(
         &self,
         run_id: &str,
         tool_name: &str,
         args: &Value,
-    ) -> Result<ToolResult> {
+    ) -> Result<(ToolResult, bool)> {
         // Execute the tool (convert execution failures into ToolResult failures)
-        let exec_result = self.tool_registry.execute(tool_name, args.clone()).await;
-        let result = match exec_result {
+        self.progress
+            .step("tool", format!("running {}", tool_name), None, None);
+        let args = &self.scope_tool_args(tool_name, args);
+        let limits = self.tool_limits(tool_name);
+        let timer = Instant::now();
+        let exec_result = match &self.tool_output {
+            Some(sink) => {
+                let on_output = |text: &str| sink.output(tool_name, text);
+                self.tool_registry
+                    .execute_streaming(tool_name, args.clone(), &limits, on_output)
+                    .await
+            }
+            None => {
+                self.tool_registry
+                    .execute_memoized(tool_name, args.clone(), &limits)
+                    .await
+            }
+        };
+        let (result, cached) = match exec_result {
             Ok(res) => res,
-            Err(err) => ToolResult::failure(err.to_string()),
+            Err(err) => (ToolResult::failure(err.to_string()), false),
         };
+        self.progress
+            .panels(tool_name, PanelSpec::from_tool_output(&result.output));
+        let agent_name = self.agent_name.as_deref().unwrap_or("unknown");
+        let output_tokens = tokenizer::count_tokens(&result.output, &self.tokenizer_model());
+        if let Err(err) = self.persistence.record_tool_usage(
+            agent_name,
+            tool_name,
+            result.success,
+            timer.elapsed().as_millis() as u64,
+            output_tokens,
+        ) {
+            warn!("Failed to record usage of tool '{}': {}", tool_name, err);
+        }
 
         // Log to persistence
         let result_json = serde_json::json!({
             "output": result.output,
             "success": result.success,
             "error": result.error,
+            "cached": cached,
         });
 
         let error_str = result.error.as_deref();
         self.persistence
             .log_tool(
                 &self.session_id,
-                self.agent_name.as_deref().unwrap_or("unknown"),
+                agent_name,
                 run_id,
                 tool_name,
                 args,
@@ -2058,6 +4217,24 @@ impl AgentCore {
             )
             .context("Failed to log tool execution")?;
 
+        Ok((result, cached))
+    }
+
+    /// Run a tool by hand, the way a model's call would run: the profile
+    /// and policy must allow it, arguments are scoped to the session and
+    /// the call is logged. Nobody is asked for permission.
+    pub async fn run_tool(&self, tool_name: &str, args: Value) -> Result<ToolResult> {
+        if !self.tool_registry.has(tool_name) {
+            return Err(anyhow!("Unknown tool '{}'", tool_name));
+        }
+        let run_id = format!("repl-{}", Utc::now().timestamp_micros());
+        if !self.authorize_tool_call(&run_id, tool_name).await {
+            return Err(anyhow!(self.denied_tool_error(
+                tool_name,
+                format!("Tool '{}' is not allowed for this agent", tool_name),
+            )));
+        }
+        let (result, _) = self.execute_tool(&run_id, tool_name, &args).await?;
         Ok(result)
     }
 
@@ -2071,9 +4248,57 @@ impl AgentCore {
         &self.policy_engine
     }
 
+    /// Get the persistence layer
+    pub fn persistence(&self) -> &Persistence {
+        &self.persistence
+    }
+
     /// Set a new policy engine (useful for reloading policies)
     pub fn set_policy_engine(&mut self, policy_engine: Arc<PolicyEngine>) {
         self.policy_engine = policy_engine;
+        // Permissions cached under the old rules no longer hold
+        self.tool_permission_cache = Arc::new(RwLock::new(HashMap::new()));
+    }
+
+    /// Turn deterministic mode on or off for subsequent runs
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.profile.deterministic = enabled;
+    }
+
+    /// Attach a progress reporter for model calls and tool executions
+    pub fn set_progress_reporter(&mut self, progress: ProgressReporter) {
+        self.progress = progress;
+    }
+
+    /// Stream tools' partial output to `sink` while they run
+    pub fn set_tool_output_sink(&mut self, sink: Arc<dyn ToolOutputSink>) {
+        self.tool_output = Some(sink);
+    }
+
+    /// Token whose cancellation abandons the tool calls in progress, each
+    /// reported to the model as a failed call. A cancelled token stays
+    /// cancelled; install a fresh one with
+    /// [`set_tool_cancellation`](Self::set_tool_cancellation) before the
+    /// next run.
+    pub fn tool_cancellation(&self) -> CancellationToken {
+        self.tool_cancel.clone()
+    }
+
+    /// Abandon tool calls when `token` is cancelled
+    pub fn set_tool_cancellation(&mut self, token: CancellationToken) {
+        self.tool_cancel = token;
+    }
+
+    /// Bounds on a call of `tool_name`
+    fn tool_limits(&self, tool_name: &str) -> ToolLimits {
+        let timeout = match self.profile.tool_timeouts.get(tool_name) {
+            Some(secs) => Some(Duration::from_secs(*secs)),
+            None => self.tool_timeout,
+        };
+        ToolLimits {
+            timeout,
+            cancel: self.tool_cancel.clone(),
+        }
     }
 
     /// Generate and store an embedding for arbitrary text (e.g., transcriptions)
@@ -2114,6 +4339,7 @@ impl AgentCore {
         assistant_message_id: i64,
         response_content: &str,
         tool_invocations: &[ToolInvocation],
+        steering: &mut Vec<GraphInfluence>,
     ) -> Result<Option<String>> {
         // Find the assistant message node in the graph
         let nodes = self.persistence.list_graph_nodes(
@@ -2143,7 +4369,7 @@ impl AgentCore {
         let assistant_node_id = assistant_node_id.unwrap();
 
         // Analyze the graph context around the current conversation
-        let neighbors = self.persistence.traverse_neighbors(
+        let neighbors = self.persistence.traverse_neighbors_with_edges(
             &self.session_id,
             assistant_node_id,
             TraversalDirection::Both,
@@ -2164,6 +4390,18 @@ impl AgentCore {
                     "pending" | "in_progress" => {
                         if let Some(goal_text) = goal.properties["goal_text"].as_str() {
                             pending_goals.push(goal_text.to_string());
+                            steering.push(GraphInfluence {
+                                kind: GraphInfluenceKind::Steering,
+                                node_id: goal.id,
+                                node_type: goal.node_type.as_str().to_string(),
+                                label: goal.label.clone(),
+                                seed_node_id: None,
+                                depth: 0,
+                                edge: None,
+                                edge_weight: None,
+                                score: None,
+                                included: false,
+                            });
                         }
                     }
                     "completed" => {
@@ -2202,18 +4440,48 @@ impl AgentCore {
         let mut key_concepts = HashSet::new();
 
         for neighbor in &neighbors {
-            match neighbor.node_type {
-                NodeType::Entity => {
-                    if let Some(name) = neighbor.properties["name"].as_str() {
-                        key_entities.insert(name.to_string());
-                    }
-                }
-                NodeType::Concept => {
-                    if let Some(name) = neighbor.properties["name"].as_str() {
-                        key_concepts.insert(name.to_string());
-                    }
+            let names = match neighbor.node.node_type {
+                NodeType::Entity => &mut key_entities,
+                NodeType::Concept => &mut key_concepts,
+                _ => continue,
+            };
+            if let Some(name) = neighbor.node.properties["name"].as_str() {
+                names.insert(name.to_string());
+                steering.push(GraphInfluence {
+                    included: false,
+                    ..graph_influence(
+                        GraphInfluenceKind::Steering,
+                        assistant_node_id,
+                        neighbor,
+                        None,
+                    )
+                });
+            }
+        }
+
+        // Pinned entities and concepts steer every step, however far they
+        // are from this exchange
+        for pinned in self.persistence.list_pinned_graph_nodes(&self.session_id)? {
+            let names = match pinned.node_type {
+                NodeType::Entity => &mut key_entities,
+                NodeType::Concept => &mut key_concepts,
+                _ => continue,
+            };
+            if let Some(name) = pinned.properties["name"].as_str() {
+                if names.insert(name.to_string()) {
+                    steering.push(GraphInfluence {
+                        kind: GraphInfluenceKind::Steering,
+                        node_id: pinned.id,
+                        node_type: pinned.node_type.as_str().to_string(),
+                        label: pinned.label.clone(),
+                        seed_node_id: None,
+                        depth: 0,
+                        edge: None,
+                        edge_weight: None,
+                        score: None,
+                        included: false,
+                    });
                 }
-                _ => {}
             }
         }
 
@@ -2362,8 +4630,123 @@ impl AgentCore {
         }
     }
 
+    /// Generate with `provider`, timing the call as `stage` and recording it
+    /// in the model log when enabled
+    async fn call_model(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        stage: &str,
+        prompt: &str,
+        config: &GenerationConfig,
+    ) -> Result<ModelResponse> {
+        let timer = Instant::now();
+        let result = provider.generate(prompt, config).await;
+        let latency_ms = elapsed_ms(timer);
+        self.log_timing(stage, timer);
+        if let Some(usage) = result.as_ref().ok().and_then(|r| r.usage.as_ref()) {
+            record_token_usage(usage);
+        }
+        if let Some(max_bytes) = self.model_log_max_bytes {
+            let (logged_prompt, prompt_cut) = truncate_bytes(prompt, max_bytes);
+            let (response, response_cut, error) = match &result {
+                Ok(response) => {
+                    let (text, cut) = truncate_bytes(&response.content, max_bytes);
+                    (Some(text.to_string()), cut, None)
+                }
+                Err(err) => (None, false, Some(err.to_string())),
+            };
+            let usage = result.as_ref().ok().and_then(|r| r.usage.as_ref());
+            let entry = ModelLogEntry {
+                session_id: self.session_id.clone(),
+                run_id: self.current_run_id.clone(),
+                agent: self.agent_name.clone(),
+                stage: stage.to_string(),
+                provider: provider.kind().as_str().to_string(),
+                model: result.as_ref().ok().map(|r| r.model.clone()),
+                prompt_digest: blake3::hash(prompt.as_bytes()).to_hex().to_string(),
+                prompt_bytes: prompt.len(),
+                prompt: logged_prompt.to_string(),
+                params: serde_json::to_value(config).unwrap_or(Value::Null),
+                response,
+                error,
+                truncated: prompt_cut || response_cut,
+                prompt_tokens: usage.map(|u| u.prompt_tokens),
+                completion_tokens: usage.map(|u| u.completion_tokens),
+                latency_ms,
+            };
+            if let Err(err) = self.persistence.insert_model_log(&entry) {
+                warn!("Failed to write model log entry: {}", err);
+            }
+        }
+        result
+    }
+
+    /// Drain the stages timed so far
+    fn take_stage_timings(&mut self) -> Vec<StageTiming> {
+        let timings = self
+            .stage_timings
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::mem::take(timings)
+    }
+
+    /// Store a step's timings for usage reporting
+    fn persist_timings(&self, run_id: &str, timings: &StepTimings) {
+        let mut rows: Vec<(&str, Option<&str>, f64)> = timings
+            .stages
+            .iter()
+            .map(|stage| {
+                (
+                    stage.stage.as_str(),
+                    stage.category.as_ref().map(TimingCategory::as_str),
+                    stage.duration_ms,
+                )
+            })
+            .collect();
+        rows.push(("run_step.total", None, timings.total_ms));
+        if let Err(err) = self.persistence.insert_step_timings(
+            &self.session_id,
+            run_id,
+            self.agent_name.as_deref(),
+            &rows,
+        ) {
+            warn!("Failed to persist step timings: {}", err);
+        }
+    }
+
+    /// Queue the finished run for the `[events]` sink, if one is configured
+    fn publish_run_event(
+        &self,
+        run_id: &str,
+        timings: &StepTimings,
+        token_usage: Option<&TokenUsage>,
+        tool_invocations: &[ToolInvocation],
+        finish_reason: Option<&str>,
+    ) {
+        let payload = json!({
+            "run_id": run_id,
+            "agent": self.agent_name,
+            "total_ms": timings.total_ms,
+            "token_usage": token_usage,
+            "tools": tool_invocations
+                .iter()
+                .map(|invocation| json!({ "name": invocation.name, "success": invocation.success }))
+                .collect::<Vec<_>>(),
+            "finish_reason": finish_reason,
+        });
+        if let Err(err) =
+            self.persistence
+                .event_outbox_append(RUN_EVENT, &self.session_id, &payload)
+        {
+            warn!("Failed to queue run event: {}", err);
+        }
+    }
+
     fn log_timing(&self, stage: &str, start: Instant) {
-        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let duration_ms = elapsed_ms(start);
+        if let Ok(mut timings) = self.stage_timings.lock() {
+            timings.push(StageTiming::new(stage, duration_ms));
+        }
         let agent_label = self.agent_name.as_deref().unwrap_or("unnamed");
         info!(
             target: "agent_timing",
@@ -2376,6 +4759,22 @@ impl AgentCore {
     }
 }
 
+/// Longest prefix of `text` within `max_bytes`, and whether it was cut
+fn truncate_bytes(text: &str, max_bytes: usize) -> (&str, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
 fn preview_text(content: &str) -> String {
     const MAX_CHARS: usize = 80;
     let trimmed = content.trim();
@@ -2416,6 +4815,8 @@ fn payload_field(payload: &Value, key: &str) -> Value {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::model::{ModelResponse, ProviderKind, ProviderMetadata};
+    use crate::agent::output::ContextSection;
     use crate::agent::providers::MockProvider;
     use crate::config::AgentProfile;
     use crate::embeddings::{EmbeddingsClient, EmbeddingsService};
@@ -2442,6 +4843,7 @@ mod tests {
             model_name: None,
             allowed_tools: None,
             denied_tools: None,
+            policy_preset: None,
             memory_k: 5,
             top_p: 0.9,
             max_context_tokens: Some(2048),
@@ -2462,6 +4864,31 @@ mod tests {
             enable_audio_transcription: false,
             audio_response_mode: "immediate".to_string(),
             audio_scenario: None,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: 168.0,
+            recall_scope: Default::default(),
+            memory_compaction: None,
+            enable_reflection: false,
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            context_overflow_retries: 3,
+            verify_responses: false,
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            model_routes: HashMap::new(),
+            context_compression: None,
+            tool_calling: Default::default(),
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
         };
 
         let provider = Arc::new(MockProvider::new("This is a test response."));
@@ -2499,6 +4926,7 @@ mod tests {
             model_name: None,
             allowed_tools: None,
             denied_tools: None,
+            policy_preset: None,
             memory_k: 5,
             top_p: 0.9,
             max_context_tokens: Some(2048),
@@ -2519,6 +4947,31 @@ mod tests {
             enable_audio_transcription: false,
             audio_response_mode: "immediate".to_string(),
             audio_scenario: None,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: 168.0,
+            recall_scope: Default::default(),
+            memory_compaction: None,
+            enable_reflection: false,
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            context_overflow_retries: 3,
+            verify_responses: false,
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            model_routes: HashMap::new(),
+            context_compression: None,
+            tool_calling: Default::default(),
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
         };
 
         profile.validate().unwrap();
@@ -2586,6 +5039,93 @@ mod tests {
         assert_eq!(output.tool_invocations.len(), 0);
     }
 
+    #[tokio::test]
+    async fn model_log_records_provider_calls_per_run() {
+        let (agent, _dir) = create_test_agent("model-log");
+        let mut agent = agent.with_model_log(4);
+
+        let output = agent.run_step("Hello, how are you?").await.unwrap();
+        let timings = output.timings.expect("timings recorded");
+        assert!(timings.category_ms(TimingCategory::Model) <= timings.total_ms);
+
+        let records = agent.persistence.list_model_log(&output.run_id).unwrap();
+        assert_eq!(records.len(), 1);
+        let entry = &records[0].entry;
+        assert_eq!(entry.stage, "run_step.main_model_call");
+        assert_eq!(entry.provider, "mock");
+        assert_eq!(entry.session_id, "model-log");
+        assert!(entry.prompt_bytes > 4);
+        assert_eq!(entry.prompt.len(), 4);
+        assert!(entry.truncated);
+        assert!(entry.params.get("temperature").is_some());
+
+        // Logging is off unless enabled
+        let (mut agent, _dir) = create_test_agent("no-model-log");
+        let output = agent.run_step("Hello").await.unwrap();
+        assert!(agent
+            .persistence
+            .list_model_log(&output.run_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn routes_steps_by_task_class() {
+        let (agent, _dir) = create_test_agent("routed");
+        let mut agent = agent.with_route(
+            TaskClass::Code,
+            ModelRoute {
+                provider: Arc::new(MockProvider::new("fn main() {}")),
+                label: "ollama/qwen2.5-coder".to_string(),
+            },
+        );
+
+        let output = agent
+            .run_step("Refactor this function to avoid the clone")
+            .await
+            .unwrap();
+        assert_eq!(output.response, "fn main() {}");
+        let route = output.route.expect("route recorded");
+        assert_eq!(route.task, "code");
+        assert_eq!(route.model, "ollama/qwen2.5-coder");
+        assert!(route.routed);
+
+        // Classes without a route stay on the main model
+        let output = agent.run_step("Hello, how are you?").await.unwrap();
+        assert_eq!(output.response, "This is a test response.");
+        let route = output.route.expect("route recorded");
+        assert_eq!(route.task, "chat");
+        assert!(!route.routed);
+    }
+
+    #[tokio::test]
+    async fn first_exchange_titles_the_session() {
+        let (mut agent, _dir) =
+            create_fast_reasoning_agent("titled", "Title: \"Greeting the assistant\"");
+        agent.run_step("Hello there").await.unwrap();
+
+        let session = agent.persistence.get_session("titled").unwrap().unwrap();
+        assert_eq!(session.title.as_deref(), Some("Greeting the assistant"));
+        assert_eq!(session.agent.as_deref(), Some("titled"));
+        assert_eq!(session.message_count, 2);
+
+        // Later exchanges keep whatever title the session already has
+        agent
+            .persistence
+            .set_session_title("titled", "Renamed")
+            .unwrap();
+        agent.run_step("Another question").await.unwrap();
+        let session = agent.persistence.get_session("titled").unwrap().unwrap();
+        assert_eq!(session.title.as_deref(), Some("Renamed"));
+        assert!(session.message_count >= 4);
+
+        // Without a fast model the first message becomes the title
+        let (mut agent, _dir) = create_test_agent("untitled");
+        agent.run_step("Hello, how are you?").await.unwrap();
+        let session = agent.persistence.get_session("untitled").unwrap().unwrap();
+        assert_eq!(session.title.as_deref(), Some("Hello, how are you?"));
+    }
+
     #[tokio::test]
     async fn fast_model_short_circuits_when_confident() {
         let (mut agent, _dir) = create_fast_reasoning_agent(
@@ -2619,6 +5159,464 @@ mod tests {
         assert_eq!(output.response, "This is a test response.");
     }
 
+    #[tokio::test]
+    async fn reflection_lessons_surface_in_later_prompts() {
+        let (mut agent, _dir) = create_fast_reasoning_agent(
+            "reflection",
+            r#"[{"lesson": "Run cargo fmt before cargo clippy", "cause": null, "tools": ["shell"], "success": true}]"#,
+        );
+        agent.profile.enable_graph = true;
+        agent.profile.enable_reflection = true;
+        assert!(agent.should_reflect(true, &[]));
+        assert!(!agent.should_reflect(false, &[]));
+
+        let stored = agent
+            .reflect_on_run("Run cargo clippy on the workspace", "Done.", &[], None)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+
+        let lessons = agent
+            .persistence
+            .list_graph_nodes("reflection", Some(NodeType::Lesson), None)
+            .unwrap();
+        assert_eq!(lessons.len(), 1);
+
+        let prompt = agent
+            .build_prompt("please run cargo clippy again", &[])
+            .await
+            .unwrap();
+        assert!(prompt.contains("Lessons from previous runs:"));
+        assert!(prompt.contains("Run cargo fmt before cargo clippy"));
+    }
+
+    #[tokio::test]
+    async fn user_profile_facts_are_extracted_once_and_shown_in_prompts() {
+        let (agent, _dir) = create_fast_reasoning_agent(
+            "profile",
+            r#"[{"category": "style", "fact": "Prefers answers without emoji"}]"#,
+        );
+        // Disabled until the builder turns it on from `[user_profile]`
+        assert_eq!(agent.update_user_profile().await.unwrap(), 0);
+        let agent = agent.with_user_profile(5);
+        assert_eq!(agent.update_user_profile().await.unwrap(), 0);
+
+        agent
+            .persistence
+            .insert_message("profile", MessageRole::User, "No emoji in replies, please.")
+            .unwrap();
+        assert_eq!(agent.update_user_profile().await.unwrap(), 1);
+        // Nothing new since the last pass
+        assert_eq!(agent.update_user_profile().await.unwrap(), 0);
+
+        let prompt = agent.build_prompt("hello", &[]).await.unwrap();
+        assert!(prompt.contains("What you know about the user:\n- Prefers answers without emoji"));
+    }
+
+    #[tokio::test]
+    async fn compaction_replaces_history_with_a_summary() {
+        let (agent, _dir) = create_test_agent("compact");
+        let mut agent =
+            agent.with_fast_provider(Arc::new(MockProvider::new("The user picked port 8080.")));
+        agent.provider = Arc::new(MockProvider::new(
+            "There are several ports to choose from, and each has trade-offs worth \
+             weighing before the server is configured for production use.",
+        ));
+        assert!(agent.compact().await.is_err());
+
+        agent
+            .run_step("Which port should the server use?")
+            .await
+            .unwrap();
+        agent.run_step("Then use 8080.").await.unwrap();
+        let report = agent.compact().await.unwrap();
+        assert_eq!(report.messages, 4);
+        assert_eq!(report.summary, "The user picked port 8080.");
+        assert!(report.tokens_saved() > 0);
+
+        // Messages stay stored; prompts get the summary instead
+        assert_eq!(
+            agent
+                .persistence
+                .list_messages("compact", 10)
+                .unwrap()
+                .len(),
+            4
+        );
+        let prompt = agent.build_prompt("And the host?", &[]).await.unwrap();
+        assert!(prompt.contains("Summary of the conversation so far:\nThe user picked port 8080."));
+        assert!(agent.compact().await.is_err());
+
+        agent.run_step("And the host?").await.unwrap();
+        let report = agent.compact().await.unwrap();
+        assert_eq!(report.messages, 2);
+    }
+
+    #[tokio::test]
+    async fn memory_compaction_recalls_summaries_in_place_of_old_messages() {
+        let (agent, _dir) =
+            create_test_agent_with_embeddings("condense", Some(test_embeddings_client()));
+        let mut agent =
+            agent.with_fast_provider(Arc::new(MockProvider::new("Alpha ships on Fridays.")));
+        agent.profile.memory_compaction = Some(crate::config::MemoryCompactionConfig {
+            keep_recent: 2,
+            chunk_messages: 3,
+        });
+        let persistence = agent.persistence.clone();
+        for text in [
+            "When does alpha ship?",
+            "Alpha ships on Fridays.",
+            "Even in December?",
+            "Yes, alpha too.",
+            "What about beta?",
+            "Beta ships daily.",
+        ] {
+            let id = persistence
+                .insert_message("condense", MessageRole::User, text)
+                .unwrap();
+            persistence
+                .insert_memory_vector("condense", Some(id), &keyword_embedding(text))
+                .unwrap();
+        }
+
+        let summaries = agent.compact_memories().await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].message_count, 3);
+        assert_eq!(summaries[1].message_count, 1);
+        assert!(agent.compact_memories().await.unwrap().is_empty());
+
+        // The newest messages keep their own embeddings
+        let recall = agent.recall_memories("alpha release").await.unwrap();
+        assert!(recall.messages.iter().any(|m| m.role == MessageRole::System
+            && m.content.starts_with("[Summary of messages #")
+            && m.content.ends_with("Alpha ships on Fridays.")));
+        assert!(!recall
+            .messages
+            .iter()
+            .any(|m| m.content == "Yes, alpha too."));
+        assert_eq!(persistence.list_messages("condense", 10).unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn archived_sessions_continue_from_their_summary() {
+        let (agent, _dir) = create_test_agent("archive");
+        let mut agent =
+            agent.with_fast_provider(Arc::new(MockProvider::new("The user picked port 8080.")));
+        agent
+            .run_step("Which port should the server use?")
+            .await
+            .unwrap();
+
+        let archive = agent.archive_session("archive").await.unwrap();
+        assert_eq!(archive.messages, 2);
+        let session = agent.persistence.get_session("archive").unwrap().unwrap();
+        assert!(session.is_archived());
+        assert!(agent
+            .persistence
+            .list_messages("archive", 10)
+            .unwrap()
+            .is_empty());
+        let prompt = agent.build_prompt("And the host?", &[]).await.unwrap();
+        assert!(prompt.contains("Summary of the conversation so far:\nThe user picked port 8080."));
+
+        // Nothing new to summarize: the summary is kept as it is
+        agent.archive_session("archive").await.unwrap();
+        let anchor = agent.persistence.context_anchor("archive").unwrap();
+        assert_eq!(anchor.unwrap().summary, "The user picked port 8080.");
+
+        agent.run_step("And the host?").await.unwrap();
+        let session = agent.persistence.get_session("archive").unwrap().unwrap();
+        assert!(!session.is_archived());
+    }
+
+    #[tokio::test]
+    async fn sweeps_follow_the_stored_activity_times() {
+        use crate::agent::session_lifecycle::sweep;
+        use crate::config::SessionsConfig;
+
+        let (agent, _dir) = create_test_agent("sweeper");
+        let agent = agent.with_fast_provider(Arc::new(MockProvider::new("Summary.")));
+        for session_id in ["fresh", "idle", "abandoned"] {
+            agent
+                .persistence
+                .insert_message(session_id, MessageRole::User, "Hello")
+                .unwrap();
+        }
+        for (session_id, updated_at) in [("idle", "2026-01-01"), ("abandoned", "2020-01-01")] {
+            agent
+                .persistence
+                .conn()
+                .execute(
+                    &format!(
+                        "UPDATE sessions SET updated_at = TIMESTAMP '{} 00:00:00' WHERE session_id = '{}'",
+                        updated_at, session_id
+                    ),
+                    [],
+                )
+                .unwrap();
+        }
+
+        let config = SessionsConfig {
+            archive_after_days: Some(30),
+            delete_after_days: Some(365),
+        };
+        let now = "2026-03-01T00:00:00Z".parse().unwrap();
+        let report = sweep(&agent, &config, now, false).await.unwrap();
+        assert_eq!(report.archived, vec!["idle".to_string()]);
+        assert_eq!(report.deleted, vec!["abandoned".to_string()]);
+        assert!(report.failed.is_empty());
+
+        let idle = agent.persistence.get_session("idle").unwrap().unwrap();
+        assert!(idle.is_archived());
+        assert!(!agent
+            .persistence
+            .get_session("fresh")
+            .unwrap()
+            .unwrap()
+            .is_archived());
+        assert!(agent
+            .persistence
+            .get_session("abandoned")
+            .unwrap()
+            .is_none());
+
+        // Archived sessions are not archived again
+        let report = sweep(&agent, &config, now, false).await.unwrap();
+        assert!(report.archived.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_keep_every_answer_as_a_variant() {
+        let (mut agent, _dir) = create_test_agent("retry");
+        assert!(agent.regenerate(None, None).await.is_err());
+        agent.provider = Arc::new(MockProvider::new("Use port 8080."));
+        agent
+            .run_step("Which port should the server use?")
+            .await
+            .unwrap();
+        assert!(agent.response_variants().unwrap().is_empty());
+
+        let retry = agent
+            .regenerate(
+                Some(1.2),
+                Some(Arc::new(MockProvider::new(
+                    "Port 3000 is the usual choice.",
+                ))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(retry.content, "Port 3000 is the usual choice.");
+        assert_eq!(retry.temperature, Some(1.2));
+
+        let variants = agent.response_variants().unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].content, "Use port 8080.");
+        assert!(!variants[0].canonical);
+        assert!(variants[1].canonical);
+        let last_answer = |agent: &AgentCore| {
+            agent
+                .persistence
+                .list_messages("retry", 10)
+                .unwrap()
+                .pop()
+                .unwrap()
+                .content
+        };
+        assert_eq!(last_answer(&agent), "Port 3000 is the usual choice.");
+
+        let picked = agent.pick_response_variant(1).await.unwrap();
+        assert_eq!(picked.content, "Use port 8080.");
+        assert_eq!(last_answer(&agent), "Use port 8080.");
+        assert!(agent.response_variants().unwrap()[0].canonical);
+        assert!(agent.pick_response_variant(3).await.is_err());
+    }
+
+    /// Provider that rejects prompts longer than `limit` bytes the way local
+    /// servers report context overflows
+    struct ContextLimitedProvider {
+        limit: usize,
+    }
+
+    #[async_trait]
+    impl ModelProvider for ContextLimitedProvider {
+        async fn generate(
+            &self,
+            prompt: &str,
+            _config: &GenerationConfig,
+        ) -> Result<ModelResponse> {
+            if prompt.len() > self.limit {
+                anyhow::bail!(
+                    "request exceeds the context window ({} bytes)",
+                    prompt.len()
+                );
+            }
+            Ok(ModelResponse {
+                content: "fits now".to_string(),
+                model: "limited".to_string(),
+                usage: None,
+                finish_reason: Some("stop".to_string()),
+                tool_calls: None,
+                reasoning: None,
+            })
+        }
+
+        async fn stream(
+            &self,
+            _prompt: &str,
+            _config: &GenerationConfig,
+        ) -> Result<std::pin::Pin<Box<dyn futures::Stream<Item = Result<String>> + Send>>> {
+            anyhow::bail!("streaming not supported")
+        }
+
+        fn metadata(&self) -> ProviderMetadata {
+            ProviderMetadata {
+                name: "Limited".to_string(),
+                supported_models: vec!["limited".to_string()],
+                supports_streaming: false,
+                supports_function_calling: false,
+                supports_vision: false,
+                supports_json_mode: false,
+                context_window: None,
+            }
+        }
+
+        fn kind(&self) -> ProviderKind {
+            ProviderKind::Mock
+        }
+    }
+
+    #[tokio::test]
+    async fn context_overflow_drops_old_history_and_retries() {
+        let (mut agent, _dir) = create_test_agent("overflow");
+        agent.run_step(&"first ".repeat(80)).await.unwrap();
+        agent.run_step(&"second ".repeat(80)).await.unwrap();
+
+        agent.provider = Arc::new(ContextLimitedProvider { limit: 400 });
+        let output = agent.run_step("short question").await.unwrap();
+        assert_eq!(output.response, "fits now");
+        assert_eq!(output.context_degradation.len(), 2);
+        assert!(output
+            .context_degradation
+            .iter()
+            .all(|d| d.section == ContextSection::History));
+        assert_eq!(output.context_degradation[1].attempt, 2);
+
+        agent.profile.context_overflow_retries = 0;
+        agent.run_step("another short question").await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn prompts_over_max_context_tokens_shed_context_before_sending() {
+        let (mut agent, _dir) = create_test_agent("budget");
+        agent.run_step(&"first ".repeat(80)).await.unwrap();
+        agent.run_step(&"second ".repeat(80)).await.unwrap();
+
+        // Room for the bare prompt but not the recalled history
+        let bare = agent.build_prompt("short question", &[]).await.unwrap();
+        let bare_tokens = tokenizer::count_tokens(&bare, &agent.tokenizer_model());
+        agent.profile.max_context_tokens = Some(bare_tokens + 50);
+        agent.provider = Arc::new(ContextLimitedProvider { limit: usize::MAX });
+
+        let output = agent.run_step("short question").await.unwrap();
+        assert_eq!(output.response, "fits now");
+        assert!(!output.context_degradation.is_empty());
+        assert!(output
+            .context_degradation
+            .iter()
+            .all(|d| d.section == ContextSection::History));
+    }
+
+    #[tokio::test]
+    async fn context_compression_shrinks_long_recalled_messages() {
+        let (mut agent, _dir) = create_test_agent("compress");
+        let long_message = "The build of release 2.4.1 was really very slow today. ".repeat(30);
+        agent.run_step(&long_message).await.unwrap();
+
+        agent.profile.context_compression = Some(crate::config::ContextCompressionConfig {
+            threshold_tokens: 50,
+            ..Default::default()
+        });
+        let output = agent.run_step("What happened?").await.unwrap();
+        let report = output.compression.expect("compression recorded");
+        assert_eq!(report.mode, "heuristic");
+        assert_eq!(report.items, 1);
+        assert!(report.compressed_tokens < report.original_tokens);
+        assert!(report.ratio <= 0.6);
+        assert!(!report.degraded);
+
+        // Short context is left alone
+        agent.profile.context_compression = Some(Default::default());
+        let output = agent.run_step("And now?").await.unwrap();
+        assert!(output.compression.is_none());
+    }
+
+    #[tokio::test]
+    async fn verification_revises_unsupported_answers_once() {
+        let (agent, _dir) = create_test_agent("verify");
+        let mut agent = agent.with_critic_provider(Arc::new(MockProvider::with_responses(vec![
+            r#"{"issues": ["The module count is not in the evidence"]}"#.to_string(),
+            r#"{"issues": []}"#.to_string(),
+        ])));
+        agent.profile.verify_responses = true;
+        agent.provider = Arc::new(MockProvider::with_responses(vec![
+            "The crate has 12 modules.".to_string(),
+            "The crate has several modules.".to_string(),
+            "Rust is a programming language.".to_string(),
+        ]));
+
+        let output = agent.run_step("How many modules?").await.unwrap();
+        assert_eq!(output.response, "The crate has several modules.");
+        let report = output.verification.unwrap();
+        assert!(report.revised);
+        assert_eq!(
+            report.issues,
+            vec!["The module count is not in the evidence"]
+        );
+
+        let output = agent.run_step("What is Rust?").await.unwrap();
+        assert_eq!(output.response, "Rust is a programming language.");
+        let report = output.verification.unwrap();
+        assert!(report.passed());
+        assert!(!report.revised);
+    }
+
+    #[tokio::test]
+    async fn response_language_translates_answers_in_the_wrong_language() {
+        let (agent, _dir) = create_test_agent("language");
+        let mut agent = agent.with_fast_provider(Arc::new(MockProvider::with_responses(vec![
+            "Puedes configurar la base de datos en el archivo de configuración.".to_string(),
+        ])));
+        agent.profile.response_language = Some("auto".to_string());
+        agent.profile.translate_responses = true;
+        agent.provider = Arc::new(MockProvider::new(
+            "You can configure the database in the config file.",
+        ));
+
+        let question = "¿Cómo puedo configurar la base de datos para el proyecto?";
+        let prompt = agent.build_prompt(question, &[]).await.unwrap();
+        assert!(prompt.contains("final answer in Spanish"));
+
+        let output = agent.run_step(question).await.unwrap();
+        assert!(output.response.starts_with("Puedes configurar"));
+        let report = output.language.unwrap();
+        assert_eq!(report.target.as_deref(), Some("es"));
+        assert_eq!(report.detected_input.as_deref(), Some("es"));
+        assert_eq!(report.detected_response.as_deref(), Some("en"));
+        assert!(report.translated);
+
+        // Answers already in the right language are left alone
+        agent.profile.response_language = Some("en".to_string());
+        let output = agent
+            .run_step("What is the config file for?")
+            .await
+            .unwrap();
+        assert_eq!(
+            output.response,
+            "You can configure the database in the config file."
+        );
+        assert!(!output.language.unwrap().translated);
+    }
+
     #[tokio::test]
     async fn test_agent_core_conversation_history() {
         let (mut agent, _dir) = create_test_agent("test-session-2");
@@ -2760,6 +5758,307 @@ mod tests {
         assert!(tail.contains(&"Alpha answer"));
     }
 
+    #[tokio::test]
+    async fn background_embeddings_are_recalled_verbatim_until_embedded() {
+        let (agent, _dir) = create_test_agent_with_embeddings(
+            "background-embeddings",
+            Some(test_embeddings_client()),
+        );
+        let agent = agent.with_background_embeddings();
+
+        let question = agent
+            .store_message(MessageRole::User, "Alpha question")
+            .await
+            .unwrap();
+        for (role, content) in [
+            (MessageRole::Assistant, "Alpha answer"),
+            (MessageRole::User, "Beta prompt"),
+            (MessageRole::Assistant, "Beta reply"),
+        ] {
+            agent.store_message(role, content).await.unwrap();
+        }
+        // Nothing yielded to the worker yet
+        assert_eq!(
+            agent.persistence.message_embedding_id(question).unwrap(),
+            None
+        );
+
+        let recall = agent.recall_memories("alpha follow up").await.unwrap();
+        let contents: Vec<_> = recall.messages.iter().map(|m| m.content.as_str()).collect();
+        assert!(contents.contains(&"Alpha question"));
+        assert!(contents.contains(&"Alpha answer"));
+        assert_eq!(recall.stats.map(|s| s.matches.len()), Some(0));
+
+        assert_eq!(agent.flush_embeddings().await.unwrap(), 4);
+        assert!(agent
+            .persistence
+            .message_embedding_id(question)
+            .unwrap()
+            .is_some());
+        let recall = agent.recall_memories("alpha follow up").await.unwrap();
+        assert_eq!(recall.stats.map(|s| s.matches.len()), Some(2));
+    }
+
+    /// Keyword embeddings that take `delay` each, counting the calls
+    #[derive(Clone)]
+    struct SlowEmbeddingsService {
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+        delay: std::time::Duration,
+    }
+
+    #[async_trait]
+    impl EmbeddingsService for SlowEmbeddingsService {
+        async fn create_embeddings(
+            &self,
+            _model: &str,
+            inputs: Vec<String>,
+        ) -> Result<Vec<Vec<f32>>> {
+            tokio::time::sleep(self.delay).await;
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(inputs
+                .into_iter()
+                .map(|input| keyword_embedding(&input))
+                .collect())
+        }
+    }
+
+    fn slow_embeddings_client(
+        delay: std::time::Duration,
+    ) -> (EmbeddingsClient, Arc<std::sync::atomic::AtomicUsize>) {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let service = SlowEmbeddingsService {
+            calls: calls.clone(),
+            delay,
+        };
+        (
+            EmbeddingsClient::with_service("test", Arc::new(service) as Arc<dyn EmbeddingsService>),
+            calls,
+        )
+    }
+
+    #[tokio::test]
+    async fn recall_cache_reuses_input_embedding_and_unchanged_recall() {
+        use std::sync::atomic::Ordering;
+
+        let (client, calls) = slow_embeddings_client(std::time::Duration::ZERO);
+        let (mut agent, _dir) = create_test_agent_with_embeddings("recall-cache", Some(client));
+
+        // A new session skips recall; the input and the reply are embedded
+        agent.run_step("Alpha question").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // The input embedded for recall is stored without embedding it
+        // again, and the mock's unchanged reply reuses its embedding too
+        agent.run_step("Alpha follow up").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let accesses = |agent: &AgentCore| -> i64 {
+            agent
+                .persistence
+                .memory_access_stats(&agent.session_id)
+                .unwrap()
+                .values()
+                .map(|stats| stats.access_count)
+                .sum()
+        };
+        let first = agent.recall_memories("alpha").await.unwrap();
+        let recorded = accesses(&agent);
+        let again = agent.recall_memories("alpha").await.unwrap();
+        assert_eq!(again.messages.len(), first.messages.len());
+        assert_eq!(accesses(&agent), recorded, "served from the cache");
+
+        agent
+            .persistence
+            .insert_message(&agent.session_id, MessageRole::User, "Alpha note")
+            .unwrap();
+        let changed = agent.recall_memories("alpha").await.unwrap();
+        assert!(changed.messages.iter().any(|m| m.content == "Alpha note"));
+    }
+
+    /// Step latency with a 20ms embeddings service, with and without the
+    /// recall cache; run with `--nocapture` to see the timings
+    #[tokio::test]
+    async fn recall_cache_cuts_step_latency() {
+        use std::sync::atomic::Ordering;
+
+        const STEPS: usize = 5;
+        let delay = std::time::Duration::from_millis(20);
+        let mut results = Vec::new();
+        for capacity in [DEFAULT_RECALL_CACHE_CAPACITY, 0] {
+            let (client, calls) = slow_embeddings_client(delay);
+            let (agent, _dir) = create_test_agent_with_embeddings("recall-bench", Some(client));
+            let mut agent = agent.with_recall_cache(capacity);
+            let started = Instant::now();
+            for step in 0..STEPS {
+                agent
+                    .run_step(&format!("Alpha question {}", step))
+                    .await
+                    .unwrap();
+            }
+            let per_step_ms = elapsed_ms(started) / STEPS as f64;
+            println!(
+                "recall cache capacity {:>2}: {:.1} ms per step, {} embedding calls",
+                capacity,
+                per_step_ms,
+                calls.load(Ordering::SeqCst)
+            );
+            results.push(calls.load(Ordering::SeqCst));
+        }
+        // Cached, each step after the first embeds only its input for
+        // recall; uncached, it embeds the input twice and the reply
+        assert_eq!(results, [STEPS + 1, 3 * STEPS - 1]);
+    }
+
+    #[tokio::test]
+    async fn graph_memory_recalls_nodes_matching_the_input() {
+        let (mut agent, _dir) =
+            create_test_agent_with_embeddings("graph-rag", Some(test_embeddings_client()));
+        agent.profile.enable_graph = true;
+        agent.profile.graph_memory = true;
+        let persistence = agent.persistence.clone();
+        persistence
+            .insert_graph_node(
+                "graph-rag",
+                NodeType::Fact,
+                "Fact",
+                &json!({"text": "alpha ships on Fridays"}),
+                None,
+            )
+            .unwrap();
+        persistence
+            .insert_graph_node(
+                "graph-rag",
+                NodeType::Fact,
+                "Fact",
+                &json!({"text": "beta is frozen"}),
+                None,
+            )
+            .unwrap();
+        agent
+            .store_message(MessageRole::User, "Hello")
+            .await
+            .unwrap();
+
+        let recall = agent.recall_memories("when does alpha ship").await.unwrap();
+        assert!(recall
+            .messages
+            .iter()
+            .any(|m| m.content.contains("alpha ships on Fridays")));
+        assert!(!recall
+            .messages
+            .iter()
+            .any(|m| m.content.contains("beta is frozen")));
+        let matched: Vec<_> = recall
+            .graph
+            .influences
+            .iter()
+            .filter(|i| i.kind == GraphInfluenceKind::SemanticMatch)
+            .collect();
+        assert_eq!(matched.len(), 1);
+        assert!(matched[0].included);
+        // Both facts were embedded on the way
+        assert!(persistence
+            .graph_nodes_without_embedding("graph-rag", 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn recall_scope_reaches_earlier_sessions() {
+        let (mut agent, _dir) =
+            create_test_agent_with_embeddings("scope-new", Some(test_embeddings_client()));
+        let persistence = agent.persistence.clone();
+        let earlier = persistence
+            .insert_message("scope-old", MessageRole::User, "alpha ships on Fridays")
+            .unwrap();
+        persistence
+            .insert_memory_vector("scope-old", Some(earlier), &[1.0, 0.0])
+            .unwrap();
+        let reviewer = persistence
+            .insert_message("scope-review", MessageRole::User, "alpha is in review")
+            .unwrap();
+        persistence
+            .insert_memory_vector("scope-review", Some(reviewer), &[1.0, 0.0])
+            .unwrap();
+        persistence
+            .set_session_agent("scope-review", "reviewer")
+            .unwrap();
+        let recalled =
+            |recall: &RecallResult, text: &str| recall.messages.iter().any(|m| m.content == text);
+
+        let recall = agent.recall_memories("when does alpha ship").await.unwrap();
+        assert!(recall.messages.is_empty());
+
+        // The test agent has no name, so it shares sessions recorded without one
+        agent.profile.recall_scope = RecallScope::Agent;
+        let recall = agent.recall_memories("when does alpha ship").await.unwrap();
+        assert!(recalled(&recall, "alpha ships on Fridays"));
+        assert!(!recalled(&recall, "alpha is in review"));
+        let stats = recall.stats.expect("recall stats");
+        assert_eq!(stats.scope, RecallScope::Agent);
+        assert_eq!(stats.matches[0].session_id.as_deref(), Some("scope-old"));
+
+        agent.profile.recall_scope = RecallScope::Global;
+        let recall = agent.recall_memories("when does alpha ship").await.unwrap();
+        assert!(recalled(&recall, "alpha ships on Fridays"));
+        assert!(recalled(&recall, "alpha is in review"));
+    }
+
+    #[tokio::test]
+    async fn recall_memories_leads_with_pinned_neighborhoods() {
+        let (mut agent, _dir) = create_test_agent("pinned-recall");
+        agent.profile.enable_graph = true;
+        let persistence = agent.persistence.clone();
+        let architecture = persistence
+            .insert_graph_node(
+                "pinned-recall",
+                NodeType::Concept,
+                "Architecture",
+                &json!({"name": "event sourcing"}),
+                None,
+            )
+            .unwrap();
+        let store = persistence
+            .insert_graph_node(
+                "pinned-recall",
+                NodeType::Entity,
+                "Store",
+                &json!({"name": "DuckDB"}),
+                None,
+            )
+            .unwrap();
+        persistence
+            .insert_graph_edge(
+                "pinned-recall",
+                architecture,
+                store,
+                EdgeType::RelatesTo,
+                None,
+                None,
+                1.0,
+            )
+            .unwrap();
+
+        let recall = agent
+            .recall_memories("an unrelated question")
+            .await
+            .unwrap();
+        assert!(recall.messages.is_empty());
+
+        assert!(persistence.pin_graph_node(architecture).unwrap());
+        let recall = agent
+            .recall_memories("an unrelated question")
+            .await
+            .unwrap();
+        assert_eq!(recall.messages.len(), 2);
+        assert!(recall.messages[0].content.contains("event sourcing"));
+        assert!(recall.messages[1].content.contains("DuckDB"));
+        assert!(recall
+            .graph
+            .influences
+            .iter()
+            .all(|i| i.kind == GraphInfluenceKind::Pinned && i.included));
+    }
+
     #[tokio::test]
     async fn test_agent_tool_permission_allowed() {
         let dir = tempdir().unwrap();
@@ -2774,6 +6073,7 @@ mod tests {
             model_name: None,
             allowed_tools: Some(vec!["echo".to_string()]),
             denied_tools: None,
+            policy_preset: None,
             memory_k: 5,
             top_p: 0.9,
             max_context_tokens: Some(2048),
@@ -2794,6 +6094,31 @@ mod tests {
             enable_audio_transcription: false,
             audio_response_mode: "immediate".to_string(),
             audio_scenario: None,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: 168.0,
+            recall_scope: Default::default(),
+            memory_compaction: None,
+            enable_reflection: false,
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            context_overflow_retries: 3,
+            verify_responses: false,
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            model_routes: HashMap::new(),
+            context_compression: None,
+            tool_calling: Default::default(),
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
         };
 
         let provider = Arc::new(MockProvider::new("Test"));
@@ -2806,6 +6131,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "*".to_string(),
             effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
         });
         let policy_engine = Arc::new(policy_engine);
 
@@ -2842,6 +6168,30 @@ mod tests {
         assert!(!agent.is_tool_allowed("calculator").await);
     }
 
+    #[test]
+    fn denied_tool_errors_carry_the_policy_alternative() {
+        let (mut agent, _dir) = create_test_agent("deny-with-alternative");
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "web_*".to_string(),
+            effect: crate::policy::PolicyEffect::Deny,
+            alternative: Some("ask the user to paste the page".to_string()),
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+
+        assert_eq!(
+            agent.denied_tool_error("web_scraper", "Tool 'web_scraper' was denied by user".into()),
+            "Tool 'web_scraper' was denied by user. Policy suggests instead: ask the user to paste the page"
+        );
+        // Denied by the default rule, which suggests nothing
+        assert_eq!(
+            agent.denied_tool_error("bash", "Tool 'bash' was denied by user".into()),
+            "Tool 'bash' was denied by user"
+        );
+    }
+
     #[tokio::test]
     async fn test_agent_tool_execution_with_logging() {
         let dir = tempdir().unwrap();
@@ -2856,6 +6206,7 @@ mod tests {
             model_name: None,
             allowed_tools: Some(vec!["echo".to_string()]),
             denied_tools: None,
+            policy_preset: None,
             memory_k: 5,
             top_p: 0.9,
             max_context_tokens: Some(2048),
@@ -2876,6 +6227,31 @@ mod tests {
             enable_audio_transcription: false,
             audio_response_mode: "immediate".to_string(),
             audio_scenario: None,
+            memory_importance_weight: 0.0,
+            memory_decay_half_life_hours: 168.0,
+            recall_scope: Default::default(),
+            memory_compaction: None,
+            enable_reflection: false,
+            max_tokens: None,
+            stop_sequences: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            top_k: None,
+            seed: None,
+            deterministic: false,
+            context_overflow_retries: 3,
+            verify_responses: false,
+            critic_profile: None,
+            post_processors: Vec::new(),
+            response_language: None,
+            translate_responses: false,
+            time_context: false,
+            context_providers: Vec::new(),
+            model_routes: HashMap::new(),
+            context_compression: None,
+            tool_calling: Default::default(),
+            advertise_failing_tools: false,
+            tool_timeouts: HashMap::new(),
         };
 
         let provider = Arc::new(MockProvider::new("Test"));
@@ -2899,17 +6275,377 @@ mod tests {
 
         // Execute tool directly
         let args = serde_json::json!({"message": "test message"});
-        let result = agent
+        let (result, cached) = agent
             .execute_tool("run-tool-test", "echo", &args)
             .await
             .unwrap();
 
         assert!(result.success);
+        assert!(!cached);
         assert_eq!(result.output, "test message");
 
         // Verify tool execution was logged (we can't easily check DB here without more setup)
     }
 
+    #[tokio::test]
+    async fn tools_run_by_hand_are_subject_to_policy() {
+        let (mut agent, _dir) = create_test_agent("run-tool");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        registry.register(Arc::new(crate::tools::builtin::FileReadTool::new()));
+        agent.tool_registry = Arc::new(registry);
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "echo".to_string(),
+            effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+
+        let result = agent
+            .run_tool("echo", json!({"message": "hello"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "hello");
+
+        let err = agent
+            .run_tool("file_read", json!({"path": "Cargo.toml"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not allowed"));
+        assert!(agent.run_tool("missing", json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn deterministic_runs_send_identical_requests() {
+        let mut hashes = Vec::new();
+        for measured_ms in [10, 5_000] {
+            let (mut agent, _dir) = create_test_agent("deterministic");
+            agent.set_deterministic(true);
+            agent.profile.time_context = true;
+            let mut settings = HashMap::new();
+            settings.insert(
+                "echo".to_string(),
+                crate::config::ToolSettings {
+                    latency_tier: Some(crate::config::ToolTier::Medium),
+                    ..Default::default()
+                },
+            );
+            let mut registry = crate::tools::ToolRegistry::new().with_settings(&settings);
+            registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+            // Each run measures the tool differently
+            for _ in 0..3 {
+                registry
+                    .hints()
+                    .record_latency("echo", std::time::Duration::from_millis(measured_ms));
+            }
+            agent.tool_registry = Arc::new(registry);
+            let mut policy_engine = PolicyEngine::new();
+            policy_engine.add_rule(crate::policy::PolicyRule {
+                agent: "*".to_string(),
+                action: "tool_call".to_string(),
+                resource: "*".to_string(),
+                effect: crate::policy::PolicyEffect::Allow,
+                alternative: None,
+            });
+            agent.set_policy_engine(Arc::new(policy_engine));
+
+            let prompt = agent.build_prompt("hello", &[]).await.unwrap();
+            assert!(prompt.contains("[latency: medium]"));
+            assert!(!prompt.contains("Current date and time"));
+
+            let output = agent.run_step("hello").await.unwrap();
+            assert!(!output.request_hashes.is_empty());
+            hashes.push(output.request_hashes);
+        }
+        assert_eq!(hashes[0], hashes[1]);
+    }
+
+    #[tokio::test]
+    async fn partial_tool_output_reaches_the_sink() {
+        let (mut agent, _dir) = create_test_agent("tool-output");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::BashTool::new()));
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        agent.tool_registry = Arc::new(registry);
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "*".to_string(),
+            effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+        let (sink, mut rx) = crate::tools::ChannelToolOutputSink::new();
+        agent.set_tool_output_sink(Arc::new(sink));
+
+        let result = agent
+            .run_tool("bash", json!({"command": "echo first; echo second"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = rx.try_recv() {
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].tool, "bash");
+        assert_eq!(chunks[0].text, "first\n");
+        assert_eq!(chunks[1].text, "second\n");
+
+        // Tools that do not stream still run, with nothing partial to show
+        let result = agent
+            .run_tool("echo", json!({"message": "hello"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "hello");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn hung_tools_fail_instead_of_blocking_the_run() {
+        let (agent, _dir) = create_test_agent("tool-timeout");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::BashTool::new()));
+        let mut agent = agent.with_tool_timeout(Duration::from_secs(600));
+        agent.tool_registry = Arc::new(registry);
+        agent.profile.tool_timeouts.insert("bash".to_string(), 1);
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "*".to_string(),
+            effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+
+        let started = Instant::now();
+        let result = agent
+            .run_tool("bash", json!({"command": "sleep 30"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Tool 'bash' timed out after 1s")
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        agent.tool_cancellation().cancel();
+        let result = agent
+            .run_tool("bash", json!({"command": "sleep 30"}))
+            .await
+            .unwrap();
+        assert_eq!(result.error.as_deref(), Some("Tool 'bash' was cancelled"));
+
+        agent.set_tool_cancellation(CancellationToken::new());
+        let result = agent
+            .run_tool("bash", json!({"command": "echo done"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn tools_that_always_fail_stop_being_advertised() {
+        let (mut agent, _dir) = create_test_agent("tool-usage");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        agent.tool_registry = Arc::new(registry);
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "*".to_string(),
+            effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+
+        // Other agents' failures do not count
+        for _ in 0..3 {
+            agent
+                .persistence
+                .record_tool_usage("other-agent", "echo", false, 5, 10)
+                .unwrap();
+        }
+        let prompt = agent.build_prompt("hi", &[]).await.unwrap();
+        assert!(prompt.contains("- echo:"));
+
+        for _ in 0..3 {
+            agent
+                .persistence
+                .record_tool_usage("tool-usage", "echo", false, 5, 10)
+                .unwrap();
+        }
+        let prompt = agent.build_prompt("hi", &[]).await.unwrap();
+        assert!(!prompt.contains("- echo:"));
+        agent.profile.advertise_failing_tools = true;
+        let prompt = agent.build_prompt("hi", &[]).await.unwrap();
+        assert!(prompt.contains("- echo:"));
+        agent.profile.advertise_failing_tools = false;
+
+        // Once the cool-down has passed the tool is offered again, and
+        // another failure hides it anew
+        agent
+            .persistence
+            .conn()
+            .execute(
+                "UPDATE tool_usage SET last_used_at = TIMESTAMP '2020-01-01 00:00:00' WHERE agent = 'tool-usage'",
+                [],
+            )
+            .unwrap();
+        let prompt = agent.build_prompt("hi", &[]).await.unwrap();
+        assert!(prompt.contains("- echo:"));
+        agent
+            .persistence
+            .record_tool_usage("tool-usage", "echo", false, 5, 10)
+            .unwrap();
+        let prompt = agent.build_prompt("hi", &[]).await.unwrap();
+        assert!(!prompt.contains("- echo:"));
+
+        // Hidden tools can still be called, and one success brings them back
+        agent
+            .run_tool("echo", json!({"message": "hello"}))
+            .await
+            .unwrap();
+        let stats = agent
+            .persistence
+            .tool_usage_stats(Some("tool-usage"))
+            .unwrap();
+        assert_eq!((stats[0].calls, stats[0].successes), (5, 1));
+        assert!(stats[0].output_tokens > 30);
+        assert!(agent.hidden_tools().is_empty());
+    }
+
+    #[test]
+    fn tool_paths_resolve_against_focused_workspace() {
+        let (mut agent, dir) = create_test_agent("workspace-test");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::FileReadTool::new()));
+        registry.register(Arc::new(crate::tools::builtin::TailFileTool::new()));
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        agent.tool_registry = Arc::new(registry);
+
+        let args = serde_json::json!({"path": "src/lib.rs"});
+        assert_eq!(agent.scope_tool_args("file_read", &args), args);
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            agent.scope_tool_args("tail_file", &args)["workspace_root"],
+            cwd.display().to_string()
+        );
+
+        let root = dir.path().join("api");
+        agent
+            .persistence
+            .workspace_add("workspace-test", "api", &root, None)
+            .unwrap();
+        let scoped = agent.scope_tool_args("file_read", &args);
+        assert_eq!(
+            scoped["path"],
+            root.join("src/lib.rs").display().to_string()
+        );
+        let absolute = serde_json::json!({"path": "/etc/hosts"});
+        assert_eq!(agent.scope_tool_args("file_read", &absolute), absolute);
+        let echo = serde_json::json!({"message": "src/lib.rs"});
+        assert_eq!(agent.scope_tool_args("echo", &echo), echo);
+
+        // Confined tools get the workspace as their root, whatever the model says
+        let escape = serde_json::json!({"path": "app.log", "workspace_root": "/"});
+        let scoped = agent.scope_tool_args("tail_file", &escape);
+        assert_eq!(scoped["workspace_root"], root.display().to_string());
+        assert_eq!(scoped["path"], root.join("app.log").display().to_string());
+    }
+
+    #[tokio::test]
+    async fn spec_permissions_allow_their_paths_for_one_run() {
+        let (mut agent, _dir) = create_test_agent("spec-permissions");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::FileWriteTool::new()));
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        agent.tool_registry = Arc::new(registry);
+        agent.set_policy_engine(Arc::new(PolicyEngine::new()));
+
+        let inside = json!({"path": "out/report.md", "content": "done"});
+        let outside = json!({"path": "src/main.rs", "content": "oops"});
+        assert!(!agent.is_tool_allowed("file_write").await);
+        assert!(!agent.spec_permits("run-1", "file_write", &inside));
+        let prompt = agent.build_prompt("Write the report", &[]).await.unwrap();
+        assert!(!prompt.contains("- file_write:"));
+
+        agent.spec_permissions = vec![SpecPermission {
+            tool: "file_write".to_string(),
+            paths: vec!["./out/".to_string()],
+            reason: None,
+        }];
+        assert!(agent.spec_permits("run-1", "file_write", &inside));
+        assert!(!agent.spec_permits("run-1", "file_write", &outside));
+        assert!(!agent.spec_permits("run-1", "echo", &json!({"message": "hi"})));
+        // The grant never lands in the permission cache
+        assert!(!agent.is_tool_allowed("file_write").await);
+        // but the model is told it may use the tool
+        let prompt = agent.build_prompt("Write the report", &[]).await.unwrap();
+        assert!(prompt.contains("- file_write:"));
+        assert!(agent
+            .text_tool_parameters()
+            .await
+            .iter()
+            .any(|(name, _)| name == "file_write"));
+
+        let spec = AgentSpec::from_str(
+            r#"
+goal = "Say hi"
+tasks = ["Greet"]
+"#,
+        )
+        .unwrap();
+        agent
+            .run_spec_with_permissions(&spec, agent.spec_permissions.clone())
+            .await
+            .unwrap();
+        assert!(!agent.spec_permits("run-2", "file_write", &inside));
+    }
+
+    #[tokio::test]
+    async fn text_tool_calls_run_for_providers_without_function_calling() {
+        let (mut agent, _dir) = create_test_agent("text-tools");
+        let mut registry = crate::tools::ToolRegistry::new();
+        registry.register(Arc::new(crate::tools::builtin::EchoTool::new()));
+        agent.tool_registry = Arc::new(registry);
+        let mut policy_engine = PolicyEngine::new();
+        policy_engine.add_rule(crate::policy::PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "echo".to_string(),
+            effect: crate::policy::PolicyEffect::Allow,
+            alternative: None,
+        });
+        agent.set_policy_engine(Arc::new(policy_engine));
+        agent.provider = Arc::new(MockProvider::with_responses(vec![
+            "Thought: I should echo it.\nAction: echo\nAction Input: {\"message\": \"ping\"}"
+                .to_string(),
+            "Final Answer: The tool said ping.".to_string(),
+        ]));
+
+        let output = agent.run_step("Echo ping").await.unwrap();
+        assert_eq!(output.tool_invocations.len(), 1);
+        assert_eq!(output.tool_invocations[0].name, "echo");
+        assert_eq!(output.tool_invocations[0].output.as_deref(), Some("ping"));
+        assert_eq!(output.response, "The tool said ping.");
+
+        // Forcing native calls leaves text actions alone
+        agent.profile.tool_calling = ToolCallingMode::Native;
+        agent.provider = Arc::new(MockProvider::new("Action: echo"));
+        let output = agent.run_step("Echo again").await.unwrap();
+        assert!(output.tool_invocations.is_empty());
+        assert_eq!(output.response, "Action: echo");
+    }
+
     #[tokio::test]
     async fn test_agent_tool_registry_access() {
         let (agent, _dir) = create_test_agent("registry-test");
@@ -2951,4 +6687,27 @@ mod tests {
             assert!(query.contains("Cargo.toml") || query.contains("package.json"));
         }
     }
+
+    #[tokio::test]
+    async fn specs_with_unmet_requirements_fail_before_running() {
+        let (mut agent, _dir) = create_test_agent("spec-requires");
+        let spec = AgentSpec::from_str(
+            r#"
+goal = "Review the PR"
+tasks = ["Read the diff"]
+
+[requires]
+tools = ["no_such_tool"]
+min_context_window = 100000
+"#,
+        )
+        .unwrap();
+
+        let err = agent.run_spec(&spec).await.unwrap_err();
+        let report = format!("{:#}", err);
+        assert!(report.contains("cannot run spec 'Review the PR'"));
+        assert!(report.contains("no_such_tool"));
+        assert!(report.contains("2048 tokens is below the required 100000"));
+        assert!(agent.conversation_history().is_empty());
+    }
 }
@@ -0,0 +1,89 @@
+//! Deterministic mode for reproducible runs
+//!
+//! With `deterministic = true` (or `--deterministic` on the command line) every
+//! model request is sent with zero temperature and a fixed seed, timestamps in
+//! tool results are replaced before they reach the prompt, and a hash of each
+//! request is recorded so two runs can be compared step by step. The prompt
+//! leaves out the time context and advertises configured tool hints only,
+//! since the clock and measured latencies differ between runs.
+
+use crate::agent::model::GenerationConfig;
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+/// Seed used when the profile does not set one
+pub const DETERMINISTIC_SEED: u64 = 42;
+
+/// Placeholder substituted for timestamps found in tool output
+pub const PINNED_TIMESTAMP: &str = "1970-01-01T00:00:00Z";
+
+/// Force zero temperature and a fixed seed, keeping an explicit profile seed
+pub fn pin_generation_config(mut config: GenerationConfig) -> GenerationConfig {
+    config.temperature = Some(0.0);
+    config.seed = Some(config.seed.unwrap_or(DETERMINISTIC_SEED));
+    config
+}
+
+/// Replace ISO 8601 / RFC 3339 timestamps with [`PINNED_TIMESTAMP`]
+pub fn pin_timestamps(text: &str) -> Cow<'_, str> {
+    static TIMESTAMP: OnceLock<Regex> = OnceLock::new();
+    let pattern = TIMESTAMP.get_or_init(|| {
+        Regex::new(
+            r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2}|\s?UTC)?",
+        )
+        .unwrap()
+    });
+    pattern.replace_all(text, PINNED_TIMESTAMP)
+}
+
+/// Hex blake3 hash of the prompt together with its generation settings
+pub fn request_hash(prompt: &str, config: &GenerationConfig) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(prompt.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(serde_json::to_string(config).unwrap_or_default().as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_common_timestamp_formats() {
+        let output = "created 2024-05-01T12:30:45.123Z, modified 2024-05-02 08:00:00 UTC, \
+                      due 2024-06-01T00:00:00+02:00 on 2024-06-01";
+        assert_eq!(
+            pin_timestamps(output),
+            "created 1970-01-01T00:00:00Z, modified 1970-01-01T00:00:00Z, \
+             due 1970-01-01T00:00:00Z on 2024-06-01"
+        );
+        assert!(matches!(pin_timestamps("no dates"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn pinned_config_keeps_explicit_seed() {
+        let pinned = pin_generation_config(GenerationConfig {
+            temperature: Some(0.9),
+            ..Default::default()
+        });
+        assert_eq!(pinned.temperature, Some(0.0));
+        assert_eq!(pinned.seed, Some(DETERMINISTIC_SEED));
+
+        let seeded = pin_generation_config(GenerationConfig {
+            seed: Some(7),
+            ..Default::default()
+        });
+        assert_eq!(seeded.seed, Some(7));
+    }
+
+    #[test]
+    fn request_hash_covers_prompt_and_settings() {
+        let config = pin_generation_config(GenerationConfig::default());
+        let a = request_hash("hello", &config);
+        assert_eq!(a, request_hash("hello", &config));
+        assert_ne!(a, request_hash("hello!", &config));
+        assert_ne!(a, request_hash("hello", &GenerationConfig::default()));
+    }
+}
@@ -0,0 +1,144 @@
+//! Embedding messages off the response path
+//!
+//! With background embeddings on, storing a message only queues it in the
+//! `embedding_queue` table, and a worker per agent embeds the session's
+//! queue in batches. Each vector is stored and linked to the message's graph
+//! node before the message leaves the queue, so a restart resumes where the
+//! last run stopped: a message may be embedded twice, but never skipped.
+//! Until its vector exists, recall includes a queued message verbatim.
+
+use crate::embeddings::EmbeddingsClient;
+use crate::persistence::Persistence;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Failed attempts after which a message is left unembedded
+pub const MAX_ATTEMPTS: i64 = 5;
+
+/// Messages embedded per request
+const BATCH_SIZE: usize = 16;
+
+/// How often the worker retries failed messages while nothing new arrives
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Background embedding worker of one session; stopped when dropped
+pub struct EmbeddingQueue {
+    wake: Arc<Notify>,
+    worker: JoinHandle<()>,
+}
+
+impl EmbeddingQueue {
+    /// Start a worker for `session_id`, which first picks up messages left
+    /// queued by an earlier run. `None` outside an async runtime.
+    pub fn spawn(
+        persistence: Persistence,
+        client: EmbeddingsClient,
+        session_id: String,
+    ) -> Option<Self> {
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let wake = Arc::new(Notify::new());
+        let worker = runtime.spawn(run_worker(persistence, client, session_id, wake.clone()));
+        Some(Self { wake, worker })
+    }
+
+    /// Tell the worker that a message was queued
+    pub fn notify(&self) {
+        self.wake.notify_one();
+    }
+}
+
+impl Drop for EmbeddingQueue {
+    fn drop(&mut self) {
+        // Queued messages stay in the table for the next worker
+        self.worker.abort();
+    }
+}
+
+async fn run_worker(
+    persistence: Persistence,
+    client: EmbeddingsClient,
+    session_id: String,
+    wake: Arc<Notify>,
+) {
+    loop {
+        match drain(&persistence, &client, &session_id).await {
+            Ok(0) => {}
+            Ok(stored) => debug!("Embedded {} queued messages of {}", stored, session_id),
+            Err(err) => warn!("Failed to embed queued messages: {:#}", err),
+        }
+        let _ = tokio::time::timeout(RETRY_INTERVAL, wake.notified()).await;
+    }
+}
+
+/// Embed every queued message of a session; returns how many vectors were
+/// stored
+pub async fn drain(
+    persistence: &Persistence,
+    client: &EmbeddingsClient,
+    session_id: &str,
+) -> Result<usize> {
+    let mut stored = 0;
+    loop {
+        let queued = persistence.embedding_queue_pending(session_id, MAX_ATTEMPTS, BATCH_SIZE)?;
+        if queued.is_empty() {
+            return Ok(stored);
+        }
+
+        let mut batch = Vec::new();
+        let mut texts = Vec::new();
+        for item in queued {
+            // Embedded before a restart, or erased since it was queued
+            let message = persistence.get_message(item.message_id)?;
+            let done = persistence.message_embedding_id(item.message_id)?.is_some();
+            match message {
+                Some(message) if !done && !message.content.trim().is_empty() => {
+                    let content = persistence.resolve_blob(&message.content)?;
+                    texts.push(persistence.detokenize(&content)?);
+                    batch.push(item);
+                }
+                _ => persistence.embedding_queue_complete(item.message_id)?,
+            }
+        }
+        if batch.is_empty() {
+            continue;
+        }
+
+        let embeddings = match client.embed_batch(&texts).await {
+            Ok(embeddings) if embeddings.len() == batch.len() => embeddings,
+            result => {
+                for item in &batch {
+                    persistence.embedding_queue_failed(item.message_id)?;
+                }
+                return match result {
+                    Err(err) => Err(err.context("embedding queued messages")),
+                    Ok(embeddings) => Err(anyhow::anyhow!(
+                        "expected {} embeddings, got {}",
+                        batch.len(),
+                        embeddings.len()
+                    )),
+                };
+            }
+        };
+
+        for (item, embedding) in batch.iter().zip(embeddings) {
+            if embedding.is_empty() {
+                persistence.embedding_queue_failed(item.message_id)?;
+                continue;
+            }
+            let embedding_id = persistence.insert_memory_vector(
+                &item.session_id,
+                Some(item.message_id),
+                &embedding,
+            )?;
+            if let Some(node_id) = item.node_id {
+                persistence.set_graph_node_embedding(node_id, embedding_id)?;
+            }
+            persistence.embedding_queue_complete(item.message_id)?;
+            stored += 1;
+        }
+    }
+}
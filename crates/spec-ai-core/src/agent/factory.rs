@@ -78,12 +78,8 @@ pub fn create_provider(config: &ModelConfig) -> Result<Arc<dyn ModelProvider>> {
 
         #[cfg(feature = "ollama")]
         ProviderKind::Ollama => {
-            // Create Ollama provider with optional custom base URL
-            let mut provider = if let Ok(base_url) = std::env::var("OLLAMA_BASE_URL") {
-                OllamaProvider::with_base_url(base_url)
-            } else {
-                OllamaProvider::new()
-            };
+            // Create Ollama provider with optional custom base URL (OLLAMA_BASE_URL)
+            let mut provider = OllamaProvider::from_env();
 
             // Set model if specified in config
             if let Some(model_name) = &config.model_name {
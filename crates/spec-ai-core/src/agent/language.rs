@@ -0,0 +1,351 @@
+//! Response language handling
+//!
+//! `response_language` is either `auto` (answer in the language the user
+//! wrote in), a language code or name such as `es` / `Spanish`, or any other
+//! label passed through to the model verbatim. Detection is a lightweight
+//! heuristic: the dominant script for non-Latin text, common function words
+//! for Latin-script languages. It abstains on short or ambiguous text.
+
+use std::collections::HashMap;
+
+/// A language the detector can recognise
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    /// ISO 639-1 code
+    pub code: &'static str,
+    /// English name used in instructions to the model
+    pub name: &'static str,
+}
+
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        code: "en",
+        name: "English",
+    },
+    Language {
+        code: "es",
+        name: "Spanish",
+    },
+    Language {
+        code: "fr",
+        name: "French",
+    },
+    Language {
+        code: "de",
+        name: "German",
+    },
+    Language {
+        code: "it",
+        name: "Italian",
+    },
+    Language {
+        code: "pt",
+        name: "Portuguese",
+    },
+    Language {
+        code: "nl",
+        name: "Dutch",
+    },
+    Language {
+        code: "ru",
+        name: "Russian",
+    },
+    Language {
+        code: "el",
+        name: "Greek",
+    },
+    Language {
+        code: "he",
+        name: "Hebrew",
+    },
+    Language {
+        code: "ar",
+        name: "Arabic",
+    },
+    Language {
+        code: "hi",
+        name: "Hindi",
+    },
+    Language {
+        code: "th",
+        name: "Thai",
+    },
+    Language {
+        code: "zh",
+        name: "Chinese",
+    },
+    Language {
+        code: "ja",
+        name: "Japanese",
+    },
+    Language {
+        code: "ko",
+        name: "Korean",
+    },
+];
+
+/// Frequent function words per Latin-script language
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "it", "with", "for", "this",
+            "what", "how", "you", "can", "please",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "es", "y", "de", "que", "en", "por", "para", "una", "con",
+            "cómo", "qué", "puedes", "está",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "est", "et", "des", "que", "une", "dans", "pour", "avec", "je",
+            "vous", "comment", "pas", "ce", "sont",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "ich", "mit", "für", "wie",
+            "sie", "zu", "auf", "den", "kannst",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "gli", "è", "di", "che", "una", "per", "con", "sono", "come", "non",
+            "della", "questo", "puoi", "del",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "é", "não", "uma", "para", "com", "que", "em", "do", "da", "você",
+            "como", "está", "isso",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "van", "niet", "dat", "ik", "je", "met", "voor",
+            "zijn", "hoe", "wat", "kun",
+        ],
+    ),
+];
+
+/// Words needed before a Latin-script guess is trusted
+const MIN_WORDS: usize = 3;
+
+/// Look up a language by code or English name (case-insensitive)
+pub fn find(value: &str) -> Option<&'static Language> {
+    let value = value.trim();
+    LANGUAGES.iter().find(|language| {
+        language.code.eq_ignore_ascii_case(value) || language.name.eq_ignore_ascii_case(value)
+    })
+}
+
+fn by_code(code: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|language| language.code == code)
+}
+
+/// Most likely language of `text`, ignoring fenced code blocks
+pub fn detect(text: &str) -> Option<&'static Language> {
+    let prose = strip_code(text);
+    if let Some(language) = detect_script(&prose) {
+        return Some(language);
+    }
+
+    let words: Vec<String> = prose
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < MIN_WORDS {
+        return None;
+    }
+
+    let mut scores: Vec<(&str, usize)> = FUNCTION_WORDS
+        .iter()
+        .map(|(code, common)| {
+            let hits = words
+                .iter()
+                .filter(|word| common.contains(&word.as_str()))
+                .count();
+            (*code, hits)
+        })
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+    let (best, hits) = scores[0];
+    // Require a clear winner; ties and single incidental matches abstain
+    if hits < 2 || hits == scores[1].1 {
+        return None;
+    }
+    by_code(best)
+}
+
+/// Language of the dominant non-Latin script, if any
+fn detect_script(text: &str) -> Option<&'static Language> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut letters = 0usize;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let code = match c as u32 {
+            0x0400..=0x04FF => "ru",
+            0x0370..=0x03FF => "el",
+            0x0590..=0x05FF => "he",
+            0x0600..=0x06FF => "ar",
+            0x0900..=0x097F => "hi",
+            0x0E00..=0x0E7F => "th",
+            0x3040..=0x30FF => "ja",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x4E00..=0x9FFF => "zh",
+            _ => continue,
+        };
+        *counts.entry(code).or_default() += 1;
+    }
+    if letters == 0 {
+        return None;
+    }
+    // Japanese mixes kanji with kana; any kana means Japanese
+    if counts.get("ja").is_some_and(|kana| *kana > 0) {
+        let cjk = counts.get("ja").unwrap_or(&0) + counts.get("zh").unwrap_or(&0);
+        return (cjk * 3 >= letters).then(|| by_code("ja")).flatten();
+    }
+    let (code, count) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+    (count * 3 >= letters).then(|| by_code(code)).flatten()
+}
+
+fn strip_code(text: &str) -> String {
+    let mut in_code = false;
+    text.lines()
+        .filter(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                return false;
+            }
+            !in_code
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Language an answer should be written in
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageTarget {
+    /// Name used in instructions to the model
+    pub name: String,
+    /// Recognised language, `None` for labels the detector does not know
+    pub language: Option<&'static Language>,
+}
+
+impl LanguageTarget {
+    /// Code when known, otherwise the configured label
+    pub fn label(&self) -> String {
+        self.language
+            .map(|language| language.code.to_string())
+            .unwrap_or_else(|| self.name.clone())
+    }
+}
+
+/// Resolve a `response_language` setting for `input`. `None` means no
+/// language instruction applies (empty setting, or `auto` with an input
+/// whose language could not be detected).
+pub fn resolve_target(setting: &str, input: &str) -> Option<LanguageTarget> {
+    let setting = setting.trim();
+    if setting.is_empty() {
+        return None;
+    }
+    let language = if setting.eq_ignore_ascii_case("auto") {
+        Some(detect(input)?)
+    } else {
+        find(setting)
+    };
+    Some(LanguageTarget {
+        name: language
+            .map(|language| language.name.to_string())
+            .unwrap_or_else(|| setting.to_string()),
+        language,
+    })
+}
+
+/// Instruction added to the prompt
+pub fn instruction(target: &LanguageTarget) -> String {
+    format!(
+        "Language: Write your final answer in {}, even if tool output or earlier messages use another language. Keep code, identifiers and quoted text unchanged.",
+        target.name
+    )
+}
+
+/// Prompt asking the fast model to translate an answer
+pub fn build_translation_prompt(text: &str, target: &LanguageTarget) -> String {
+    format!(
+        "Translate the following answer into {}. Keep Markdown formatting, code blocks, \
+         URLs and identifiers exactly as they are. Reply with the translation only.\n\n{}",
+        target.name, text
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(text: &str) -> Option<&'static str> {
+        detect(text).map(|language| language.code)
+    }
+
+    #[test]
+    fn detects_common_languages() {
+        assert_eq!(
+            code("How can I configure the database for this project?"),
+            Some("en")
+        );
+        assert_eq!(
+            code("¿Cómo puedo configurar la base de datos para el proyecto?"),
+            Some("es")
+        );
+        assert_eq!(
+            code("Comment est-ce que je configure la base de données pour le projet ?"),
+            Some("fr")
+        );
+        assert_eq!(
+            code("Wie kann ich die Datenbank für das Projekt einrichten?"),
+            Some("de")
+        );
+        assert_eq!(code("Как настроить базу данных для проекта?"), Some("ru"));
+        assert_eq!(
+            code("このプロジェクトのデータベースを設定する方法は？"),
+            Some("ja")
+        );
+        assert_eq!(code("如何为这个项目配置数据库？"), Some("zh"));
+        // Too short to tell, and code blocks are ignored
+        assert_eq!(code("ok"), None);
+        assert_eq!(code("```\nlet the = and;\n```"), None);
+    }
+
+    #[test]
+    fn resolves_settings() {
+        let auto = resolve_target(
+            "auto",
+            "Wie kann ich die Datenbank für das Projekt einrichten?",
+        )
+        .unwrap();
+        assert_eq!(auto.label(), "de");
+        assert_eq!(auto.name, "German");
+        assert!(resolve_target("auto", "ok").is_none());
+        assert!(resolve_target("  ", "anything").is_none());
+
+        assert_eq!(
+            resolve_target("Spanish", "hello there friend")
+                .unwrap()
+                .label(),
+            "es"
+        );
+        let custom = resolve_target("Klingon", "hello").unwrap();
+        assert_eq!(custom.language, None);
+        assert_eq!(custom.label(), "Klingon");
+    }
+}
@@ -0,0 +1,180 @@
+//! Condensing old messages into recallable summaries
+//!
+//! Recall searches one embedding per message, so a long session fills its
+//! memory with turns that only matter together. Memory compaction asks the
+//! fast model to summarize runs of old messages, stores each summary with an
+//! embedding of its own and deletes the embeddings of the messages it
+//! covers, so that recall brings back the summary in their place. The
+//! messages themselves stay stored for history and search.
+//!
+//! With `memory_compaction` set in a profile, a worker per agent compacts
+//! whenever a full chunk of messages has aged past `keep_recent`;
+//! `/memory compact` compacts everything that old right away. Messages still
+//! waiting in the embedding queue are left for a later pass.
+
+use crate::agent::compaction;
+use crate::agent::embedding_queue;
+use crate::agent::model::{GenerationConfig, ModelProvider};
+use crate::config::MemoryCompactionConfig;
+use crate::embeddings::EmbeddingsClient;
+use crate::persistence::{MemorySummary, Persistence};
+use crate::types::{Message, MessageRole};
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Background memory compaction of one session; stopped when dropped
+pub struct MemoryCompactor {
+    wake: Arc<Notify>,
+    worker: JoinHandle<()>,
+}
+
+impl MemoryCompactor {
+    /// Start a worker for `session_id`, which first compacts what earlier
+    /// runs left. `None` outside an async runtime.
+    pub fn spawn(
+        persistence: Persistence,
+        client: EmbeddingsClient,
+        provider: Arc<dyn ModelProvider>,
+        session_id: String,
+        settings: MemoryCompactionConfig,
+    ) -> Option<Self> {
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let wake = Arc::new(Notify::new());
+        let worker = runtime.spawn(run_worker(
+            persistence,
+            client,
+            provider,
+            session_id,
+            settings,
+            wake.clone(),
+        ));
+        Some(Self { wake, worker })
+    }
+
+    /// Tell the worker that a message was stored
+    pub fn notify(&self) {
+        self.wake.notify_one();
+    }
+}
+
+impl Drop for MemoryCompactor {
+    fn drop(&mut self) {
+        // A summary is stored in one go, so nothing is left half done
+        self.worker.abort();
+    }
+}
+
+async fn run_worker(
+    persistence: Persistence,
+    client: EmbeddingsClient,
+    provider: Arc<dyn ModelProvider>,
+    session_id: String,
+    settings: MemoryCompactionConfig,
+    wake: Arc<Notify>,
+) {
+    loop {
+        match compact(
+            &persistence,
+            &client,
+            provider.as_ref(),
+            &session_id,
+            &settings,
+            false,
+        )
+        .await
+        {
+            Ok(summaries) if summaries.is_empty() => {}
+            Ok(summaries) => debug!(
+                "Condensed {} chunk(s) of old messages of {}",
+                summaries.len(),
+                session_id
+            ),
+            Err(err) => warn!("Failed to compact memories of {}: {:#}", session_id, err),
+        }
+        wake.notified().await;
+    }
+}
+
+/// Summarize the session's messages older than the newest `keep_recent`,
+/// `chunk_messages` at a time; with `partial`, a last shorter chunk too.
+/// Returns the summaries stored.
+pub async fn compact(
+    persistence: &Persistence,
+    client: &EmbeddingsClient,
+    provider: &dyn ModelProvider,
+    session_id: &str,
+    settings: &MemoryCompactionConfig,
+    partial: bool,
+) -> Result<Vec<MemorySummary>> {
+    // A message still queued would get its embedding after the summary
+    let before = persistence
+        .embedding_queue_pending(session_id, embedding_queue::MAX_ATTEMPTS, 1)?
+        .first()
+        .map(|queued| queued.message_id);
+    let mut stored = Vec::new();
+    loop {
+        let chunk = persistence.messages_to_summarize(
+            session_id,
+            settings.keep_recent,
+            before,
+            settings.chunk_messages,
+        )?;
+        if chunk.is_empty() || (chunk.len() < settings.chunk_messages && !partial) {
+            return Ok(stored);
+        }
+        let Some(summary) = summarize(persistence, client, provider, session_id, &chunk).await?
+        else {
+            // Compacted meanwhile by another pass
+            return Ok(stored);
+        };
+        stored.push(summary);
+    }
+}
+
+/// Summarize and store one chunk
+async fn summarize(
+    persistence: &Persistence,
+    client: &EmbeddingsClient,
+    provider: &dyn ModelProvider,
+    session_id: &str,
+    chunk: &[Message],
+) -> Result<Option<MemorySummary>> {
+    let mut messages = Vec::with_capacity(chunk.len());
+    for message in chunk {
+        let mut message = message.clone();
+        message.content = persistence.resolve_blob(&message.content)?;
+        messages.push(message);
+    }
+    // Only tool output and context notes: nothing worth recalling
+    if !messages
+        .iter()
+        .any(|m| matches!(m.role, MessageRole::User | MessageRole::Assistant))
+    {
+        return persistence.store_memory_summary(session_id, chunk, "", None);
+    }
+
+    // Stored text carries tokens for personal data; the model gets the
+    // originals
+    let prompt = persistence.detokenize(&compaction::build_memory_prompt(&messages))?;
+    let config = GenerationConfig {
+        temperature: Some(0.2),
+        max_tokens: Some(400),
+        stop_sequences: None,
+        top_p: Some(0.9),
+        frequency_penalty: None,
+        presence_penalty: None,
+        top_k: None,
+        seed: None,
+    };
+    let reply = provider.generate(&prompt, &config).await?;
+    let summary = reply.content.trim();
+    if summary.is_empty() {
+        return Err(anyhow!("The model returned an empty summary"));
+    }
+    let embedding = client.embed(summary).await?;
+    let embedding = (!embedding.is_empty()).then_some(embedding.as_slice());
+    persistence.store_memory_summary(session_id, chunk, summary, embedding)
+}
@@ -1,18 +1,44 @@
+pub mod audio_file;
 pub mod builder;
+pub mod compaction;
+pub mod compression;
+pub mod context;
 pub mod core;
+pub mod determinism;
+pub mod embedding_queue;
 pub mod factory;
 pub mod function_calling;
+pub mod language;
+pub mod memory_compaction;
 pub mod model;
 pub mod output;
+pub mod overflow;
+pub mod postprocess;
+pub mod recall_cache;
 pub mod providers;
+pub mod react;
+pub mod reflection;
+pub mod reminders;
+pub mod roundtable;
+pub mod routing;
+pub mod session_lifecycle;
+pub mod speech;
+pub mod titles;
+pub mod transcript_export;
 pub mod transcription;
 pub mod transcription_factory;
 pub mod transcription_providers;
+pub mod user_profile;
+pub mod verification;
+pub mod wake_word;
 
 pub use builder::AgentBuilder;
+pub use context::{ContextProvider, ContextProviders, ContextRequest};
 pub use core::AgentCore;
 pub use factory::create_provider;
-pub use model::{GenerationConfig, ModelProvider, ModelResponse, ProviderKind, ProviderMetadata};
+pub use model::{
+    GenerationConfig, GenerationParam, ModelProvider, ModelResponse, ProviderKind, ProviderMetadata,
+};
 pub use output::AgentOutput;
 pub use transcription::{
     TranscriptionConfig, TranscriptionEvent, TranscriptionProvider, TranscriptionProviderKind,
@@ -8,6 +8,7 @@ use async_trait::async_trait;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Configuration for model generation requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +25,104 @@ pub struct GenerationConfig {
     pub frequency_penalty: Option<f32>,
     /// Presence penalty
     pub presence_penalty: Option<f32>,
+    /// Top-k sampling
+    #[serde(default)]
+    pub top_k: Option<u32>,
+    /// Sampling seed
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Individual generation parameters, used to report which ones a provider ignores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationParam {
+    Temperature,
+    MaxTokens,
+    StopSequences,
+    TopP,
+    TopK,
+    FrequencyPenalty,
+    PresencePenalty,
+    Seed,
+}
+
+impl GenerationParam {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GenerationParam::Temperature => "temperature",
+            GenerationParam::MaxTokens => "max_tokens",
+            GenerationParam::StopSequences => "stop_sequences",
+            GenerationParam::TopP => "top_p",
+            GenerationParam::TopK => "top_k",
+            GenerationParam::FrequencyPenalty => "frequency_penalty",
+            GenerationParam::PresencePenalty => "presence_penalty",
+            GenerationParam::Seed => "seed",
+        }
+    }
+}
+
+/// Parameters accepted by OpenAI-compatible chat completion APIs
+pub const OPENAI_COMPATIBLE_PARAMS: &[GenerationParam] = &[
+    GenerationParam::Temperature,
+    GenerationParam::MaxTokens,
+    GenerationParam::StopSequences,
+    GenerationParam::TopP,
+    GenerationParam::FrequencyPenalty,
+    GenerationParam::PresencePenalty,
+    GenerationParam::Seed,
+];
+
+impl GenerationConfig {
+    /// Parameters that are set in this config
+    pub fn configured_params(&self) -> Vec<GenerationParam> {
+        let mut params = Vec::new();
+        let flags = [
+            (self.temperature.is_some(), GenerationParam::Temperature),
+            (self.max_tokens.is_some(), GenerationParam::MaxTokens),
+            (
+                self.stop_sequences.is_some(),
+                GenerationParam::StopSequences,
+            ),
+            (self.top_p.is_some(), GenerationParam::TopP),
+            (self.top_k.is_some(), GenerationParam::TopK),
+            (
+                self.frequency_penalty.is_some(),
+                GenerationParam::FrequencyPenalty,
+            ),
+            (
+                self.presence_penalty.is_some(),
+                GenerationParam::PresencePenalty,
+            ),
+            (self.seed.is_some(), GenerationParam::Seed),
+        ];
+        for (set, param) in flags {
+            if set {
+                params.push(param);
+            }
+        }
+        params
+    }
+
+    /// Parameters that are set but not in `supported`
+    pub fn unsupported_params(&self, supported: &[GenerationParam]) -> Vec<GenerationParam> {
+        self.configured_params()
+            .into_iter()
+            .filter(|p| !supported.contains(p))
+            .collect()
+    }
+
+    /// Warn about parameters the provider will not send
+    pub fn warn_unsupported(&self, provider: &str, supported: &[GenerationParam]) {
+        let ignored = self.unsupported_params(supported);
+        if !ignored.is_empty() {
+            let names: Vec<&str> = ignored.iter().map(GenerationParam::as_str).collect();
+            tracing::warn!(
+                "Provider '{}' does not support {}; ignoring",
+                provider,
+                names.join(", ")
+            );
+        }
+    }
 }
 
 impl Default for GenerationConfig {
@@ -35,6 +134,8 @@ impl Default for GenerationConfig {
             top_p: Some(1.0),
             frequency_penalty: None,
             presence_penalty: None,
+            top_k: None,
+            seed: None,
         }
     }
 }
@@ -123,6 +224,19 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+static TOKENS_USED: AtomicU64 = AtomicU64::new(0);
+
+/// Add a response's tokens to this process's running total
+pub fn record_token_usage(usage: &TokenUsage) {
+    TOKENS_USED.fetch_add(u64::from(usage.total_tokens), Ordering::Relaxed);
+}
+
+/// Provider tokens used by this process so far, as reported in mesh
+/// heartbeats
+pub fn tokens_used() -> u64 {
+    TOKENS_USED.load(Ordering::Relaxed)
+}
+
 /// Provider metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderMetadata {
@@ -132,6 +246,32 @@ pub struct ProviderMetadata {
     pub supported_models: Vec<String>,
     /// Supports streaming
     pub supports_streaming: bool,
+    /// Supports native function calling
+    #[serde(default)]
+    pub supports_function_calling: bool,
+    /// Accepts image inputs
+    #[serde(default)]
+    pub supports_vision: bool,
+    /// Can be asked to reply with a JSON object only
+    #[serde(default)]
+    pub supports_json_mode: bool,
+    /// Context window in tokens, when the provider reports one
+    #[serde(default)]
+    pub context_window: Option<u32>,
+}
+
+impl ProviderMetadata {
+    /// Whether the provider has a feature named in a spec's `[requires]`
+    /// section; `None` for names this check does not know
+    pub fn supports_feature(&self, feature: &str) -> Option<bool> {
+        match feature.trim().to_lowercase().replace('-', "_").as_str() {
+            "streaming" => Some(self.supports_streaming),
+            "function_calling" | "tools" => Some(self.supports_function_calling),
+            "vision" => Some(self.supports_vision),
+            "json_mode" | "json" => Some(self.supports_json_mode),
+            _ => None,
+        }
+    }
 }
 
 /// Types of model providers
@@ -240,6 +380,8 @@ mod tests {
             top_p: Some(0.95),
             frequency_penalty: None,
             presence_penalty: None,
+            top_k: None,
+            seed: None,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -249,6 +391,22 @@ mod tests {
         assert_eq!(config.max_tokens, deserialized.max_tokens);
     }
 
+    #[test]
+    fn test_generation_config_unsupported_params() {
+        let config = GenerationConfig {
+            top_k: Some(40),
+            seed: Some(1),
+            ..GenerationConfig::default()
+        };
+        let unsupported = config.unsupported_params(&[
+            GenerationParam::Temperature,
+            GenerationParam::MaxTokens,
+            GenerationParam::TopP,
+            GenerationParam::Seed,
+        ]);
+        assert_eq!(unsupported, vec![GenerationParam::TopK]);
+    }
+
     #[test]
     fn test_parse_thinking_tokens_with_tags() {
         let response = "<think>Let me consider this carefully...</think>Here's my final answer.";
@@ -1,6 +1,7 @@
 //! Shared agent output data types used by the core loop and CLI
 
 use crate::agent::model::TokenUsage;
+use crate::config::RecallScope;
 use crate::tools::ToolResult;
 use crate::types::MessageRole;
 use serde::{Deserialize, Serialize};
@@ -34,6 +35,293 @@ pub struct AgentOutput {
     /// Snapshot of graph state for debugging purposes
     #[serde(skip_serializing_if = "Option::is_none")]
     pub graph_debug: Option<GraphDebugInfo>,
+    /// Hash of every model request sent during this step (deterministic mode)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_hashes: Vec<String>,
+    /// Context dropped to recover from context-window overflows, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_degradation: Vec<ContextDegradation>,
+    /// Critic findings for the final answer (when verification is enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationReport>,
+    /// Language detection and translation outcome (when `response_language` is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<LanguageReport>,
+    /// Wall-clock time spent in each stage of the step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<StepTimings>,
+    /// Model the step's task class was routed to (when the main model ran)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<RouteReport>,
+    /// Context compressed before prompt assembly (when `context_compression` is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionReport>,
+}
+
+/// Prompt context sections, lowest priority first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextSection {
+    History,
+    Memories,
+    Graph,
+}
+
+impl ContextSection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContextSection::History => "history",
+            ContextSection::Memories => "memories",
+            ContextSection::Graph => "graph",
+        }
+    }
+}
+
+/// Context removed before one retry after a context-length error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextDegradation {
+    /// Retry number (1-based)
+    pub attempt: usize,
+    pub section: ContextSection,
+    /// Messages dropped from the section
+    pub dropped: usize,
+    /// Messages left in the section
+    pub remaining: usize,
+}
+
+/// Outcome of the critic pass over a draft answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Provider that reviewed the draft
+    pub verifier: String,
+    /// Claims not supported by the recalled context or tool output
+    pub issues: Vec<String>,
+    /// Whether the draft was rewritten to address the issues
+    pub revised: bool,
+}
+
+impl VerificationReport {
+    /// True when the critic found nothing to correct
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// How the answer's language was checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageReport {
+    /// Language the answer should use (code, or the configured label);
+    /// `None` when `auto` could not tell the user's language
+    pub target: Option<String>,
+    /// Language detected in the user's input
+    pub detected_input: Option<String>,
+    /// Language detected in the answer, before any translation
+    pub detected_response: Option<String>,
+    /// Whether the answer was translated into the target language
+    pub translated: bool,
+}
+
+/// Which model answered a step and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteReport {
+    /// Task class the input was classified as
+    pub task: String,
+    /// `provider/model` of the route, or the main provider's name
+    pub model: String,
+    /// Whether a `model_routes` entry matched; `false` means the main model
+    pub routed: bool,
+}
+
+/// Recalled messages and tool output compressed during a step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompressionReport {
+    /// `heuristic` or `summarize`
+    pub mode: String,
+    /// Number of items compressed
+    pub items: usize,
+    pub original_tokens: usize,
+    pub compressed_tokens: usize,
+    /// `compressed_tokens / original_tokens`
+    pub ratio: f32,
+    /// Lowest share of key terms (numbers, identifiers, names) kept by any item
+    pub key_term_retention: f32,
+    /// Whether some item kept too few key terms to be trusted
+    pub degraded: bool,
+}
+
+impl CompressionReport {
+    pub fn new(mode: impl Into<String>) -> Self {
+        Self {
+            mode: mode.into(),
+            items: 0,
+            original_tokens: 0,
+            compressed_tokens: 0,
+            ratio: 1.0,
+            key_term_retention: 1.0,
+            degraded: false,
+        }
+    }
+
+    /// Add one compressed item
+    pub fn record(
+        &mut self,
+        original_tokens: usize,
+        compressed_tokens: usize,
+        key_term_retention: f32,
+        min_retention: f32,
+    ) {
+        self.items += 1;
+        self.original_tokens += original_tokens;
+        self.compressed_tokens += compressed_tokens;
+        self.ratio = self.compressed_tokens as f32 / self.original_tokens.max(1) as f32;
+        self.key_term_retention = self.key_term_retention.min(key_term_retention);
+        self.degraded = self.key_term_retention < min_retention;
+    }
+}
+
+/// Outcome of `/compact`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactionReport {
+    /// Messages folded into the summary
+    pub messages: usize,
+    /// Last message the summary covers
+    pub through_message_id: i64,
+    /// Tokens the previous summary and the messages took in prompts
+    pub tokens_before: usize,
+    /// Tokens the new summary takes
+    pub tokens_after: usize,
+    pub summary: String,
+}
+
+impl CompactionReport {
+    pub fn tokens_saved(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+}
+
+/// Kind of work a timed stage performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingCategory {
+    Recall,
+    Prompt,
+    Model,
+    Tool,
+    Persistence,
+    Graph,
+}
+
+impl TimingCategory {
+    pub const ALL: [TimingCategory; 6] = [
+        TimingCategory::Recall,
+        TimingCategory::Prompt,
+        TimingCategory::Model,
+        TimingCategory::Tool,
+        TimingCategory::Persistence,
+        TimingCategory::Graph,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimingCategory::Recall => "recall",
+            TimingCategory::Prompt => "prompt",
+            TimingCategory::Model => "model",
+            TimingCategory::Tool => "tool",
+            TimingCategory::Persistence => "persistence",
+            TimingCategory::Graph => "graph",
+        }
+    }
+
+    /// Category of a top-level step stage; `None` for stages that run
+    /// inside another timed stage, so totals do not count them twice
+    pub fn of_stage(stage: &str) -> Option<Self> {
+        match stage {
+            "run_step.recall_memories" => Some(TimingCategory::Recall),
+            "run_step.build_prompt" | "run_step.compress_context" => Some(TimingCategory::Prompt),
+            "run_step.fast_reasoning_attempt"
+            | "run_step.main_model_call"
+            | "run_step.verification"
+            | "run_step.reflection"
+            | "run_step.translate_response"
+            | "summarize_reasoning.generate"
+            | "session_title.generate" => Some(TimingCategory::Model),
+            "run_step.evaluate_graph_for_next_action" => Some(TimingCategory::Graph),
+            _ if stage.starts_with("run_step.tool_execution.") => Some(TimingCategory::Tool),
+            _ if stage.starts_with("run_step.store_") => Some(TimingCategory::Persistence),
+            _ => None,
+        }
+    }
+}
+
+/// Duration of one timed stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub stage: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<TimingCategory>,
+    pub duration_ms: f64,
+}
+
+impl StageTiming {
+    pub fn new(stage: impl Into<String>, duration_ms: f64) -> Self {
+        let stage = stage.into();
+        Self {
+            category: TimingCategory::of_stage(&stage),
+            stage,
+            duration_ms,
+        }
+    }
+}
+
+/// Per-stage timings of one step, in the order the stages finished
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepTimings {
+    pub total_ms: f64,
+    pub stages: Vec<StageTiming>,
+}
+
+impl StepTimings {
+    /// Time spent in stages of `category`
+    pub fn category_ms(&self, category: TimingCategory) -> f64 {
+        self.stages
+            .iter()
+            .filter(|stage| stage.category == Some(category))
+            .map(|stage| stage.duration_ms)
+            .sum()
+    }
+
+    /// One line such as `Timing: 1.42s total · recall 12ms · model 1.30s · tool 95ms (2)`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("Timing: {} total", format_ms(self.total_ms))];
+        for category in TimingCategory::ALL {
+            let count = self
+                .stages
+                .iter()
+                .filter(|stage| stage.category == Some(category))
+                .count();
+            if count == 0 {
+                continue;
+            }
+            let mut part = format!(
+                "{} {}",
+                category.as_str(),
+                format_ms(self.category_ms(category))
+            );
+            if count > 1 {
+                part.push_str(&format!(" ({})", count));
+            }
+            parts.push(part);
+        }
+        parts.join(" · ")
+    }
+}
+
+fn format_ms(ms: f64) -> String {
+    if ms >= 1000.0 {
+        format!("{:.2}s", ms / 1000.0)
+    } else {
+        format!("{:.0}ms", ms)
+    }
 }
 
 /// Minimal snapshot of a recent graph node for debugging output
@@ -54,6 +342,81 @@ pub struct GraphDebugInfo {
     pub node_count: usize,
     pub edge_count: usize,
     pub recent_nodes: Vec<GraphDebugNode>,
+    /// `graph_depth` used to expand recalled context
+    #[serde(default)]
+    pub traversal_depth: usize,
+    /// Nodes that shaped the prompt or the next-action recommendation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub influences: Vec<GraphInfluence>,
+    /// Estimated tokens of prompt context contributed by the graph
+    #[serde(default)]
+    pub context_tokens: usize,
+    /// Recommendation produced by graph steering this step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+impl GraphDebugInfo {
+    /// Influences that reached the prompt
+    pub fn included(&self) -> impl Iterator<Item = &GraphInfluence> {
+        self.influences
+            .iter()
+            .filter(|influence| influence.included)
+    }
+}
+
+/// How a graph node came to influence a step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphInfluenceKind {
+    /// Message linked to the recent conversation
+    RecentContext,
+    /// Fact, concept or entity linked to a semantically recalled message
+    SemanticExpansion,
+    /// Fact, concept or entity whose own embedding matched the input
+    SemanticMatch,
+    /// Node weighed when recommending the next action
+    Steering,
+    /// Node pinned with `/graph pin`, or a neighbor of one
+    Pinned,
+}
+
+impl GraphInfluenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphInfluenceKind::RecentContext => "recent context",
+            GraphInfluenceKind::SemanticExpansion => "semantic expansion",
+            GraphInfluenceKind::SemanticMatch => "semantic match",
+            GraphInfluenceKind::Steering => "steering",
+            GraphInfluenceKind::Pinned => "pinned",
+        }
+    }
+}
+
+/// A graph node that influenced a step, and the path that led to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphInfluence {
+    pub kind: GraphInfluenceKind,
+    pub node_id: i64,
+    pub node_type: String,
+    pub label: String,
+    /// Node the traversal started from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed_node_id: Option<i64>,
+    /// Hops from the seed node
+    pub depth: usize,
+    /// Type (or predicate) of the edge followed into the node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_weight: Option<f32>,
+    /// Recall similarity of the message the traversal started from, or of
+    /// the node itself for a semantic match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// Whether the node's content went into the prompt (false when cut by
+    /// the `graph_weight` slot limit or only used for steering)
+    pub included: bool,
 }
 
 /// A single tool invocation, including arguments and outcome metadata
@@ -66,6 +429,9 @@ pub struct ToolInvocation {
     pub output: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Result was served from the memoization cache
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
 }
 
 impl ToolInvocation {
@@ -82,14 +448,24 @@ impl ToolInvocation {
             success: result.success,
             output,
             error: result.error.clone(),
+            cached: false,
         }
     }
+
+    /// Mark whether the result came from the memoization cache
+    pub fn with_cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
 }
 
 /// Telemetry about memory recall for a single turn
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryRecallStats {
     pub strategy: MemoryRecallStrategy,
+    /// Sessions the memories were drawn from
+    #[serde(default)]
+    pub scope: RecallScope,
     pub matches: Vec<MemoryRecallMatch>,
 }
 
@@ -104,6 +480,9 @@ pub enum MemoryRecallStrategy {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryRecallMatch {
     pub message_id: Option<i64>,
+    /// Session the memory was recorded in, when not the current one
+    #[serde(default)]
+    pub session_id: Option<String>,
     pub score: f32,
     pub role: MessageRole,
     pub preview: String,
@@ -0,0 +1,225 @@
+//! Recovery from context-window overflow errors
+//!
+//! Providers report an over-long prompt in different ways; [`is_context_overflow`]
+//! recognizes each provider's wording. [`PromptContext`] splits the recalled
+//! context into sections so that a retry can shed the least valuable part
+//! first: old conversation history, then recalled memories, then graph context.
+
+use crate::agent::model::ProviderKind;
+use crate::agent::output::{ContextSection, MemoryRecallStats};
+use crate::types::{Message, MessageRole};
+use std::collections::HashSet;
+
+/// Wording shared by several servers, checked for every provider
+const GENERIC_MARKERS: [&str; 3] = [
+    "context window",
+    "too many tokens",
+    "exceeds the available context size",
+];
+
+/// Whether `error` is the provider telling us the prompt no longer fits
+pub fn is_context_overflow(kind: ProviderKind, error: &anyhow::Error) -> bool {
+    let text = format!("{:#}", error).to_lowercase();
+    provider_markers(kind)
+        .iter()
+        .chain(GENERIC_MARKERS.iter())
+        .any(|marker| text.contains(marker))
+}
+
+fn provider_markers(kind: ProviderKind) -> &'static [&'static str] {
+    match kind {
+        ProviderKind::Mock => &[],
+        #[cfg(feature = "openai")]
+        ProviderKind::OpenAI => OPENAI_COMPATIBLE_MARKERS,
+        #[cfg(feature = "anthropic")]
+        ProviderKind::Anthropic => &[
+            "prompt is too long",
+            "exceed context limit",
+            "input is too long",
+        ],
+        #[cfg(feature = "ollama")]
+        ProviderKind::Ollama => &["exceeds the context length", "context length exceeded"],
+        #[cfg(feature = "mlx")]
+        ProviderKind::MLX => OPENAI_COMPATIBLE_MARKERS,
+        #[cfg(feature = "lmstudio")]
+        ProviderKind::LMStudio => OPENAI_COMPATIBLE_MARKERS,
+    }
+}
+
+/// Wording of OpenAI and servers mimicking its API (LM Studio, MLX)
+#[cfg(any(feature = "openai", feature = "mlx", feature = "lmstudio"))]
+const OPENAI_COMPATIBLE_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "reduce the length of the messages",
+    "greater than the context length",
+];
+
+/// Recalled context messages tagged with the section they belong to
+#[derive(Debug, Clone)]
+pub struct PromptContext {
+    entries: Vec<(ContextSection, Message)>,
+}
+
+impl PromptContext {
+    /// Classify recalled messages: synthetic graph context, semantic recall
+    /// matches (including transcriptions), and everything else as history
+    pub fn new(messages: Vec<Message>, stats: Option<&MemoryRecallStats>) -> Self {
+        let recalled: HashSet<i64> = stats
+            .map(|s| s.matches.iter().filter_map(|m| m.message_id).collect())
+            .unwrap_or_default();
+
+        let entries = messages
+            .into_iter()
+            .map(|message| {
+                let section = if message.role == MessageRole::System
+                    && message.content.starts_with("[Graph Context")
+                {
+                    ContextSection::Graph
+                } else if recalled.contains(&message.id)
+                    || message.content.starts_with("[Transcription]")
+                {
+                    ContextSection::Memories
+                } else {
+                    ContextSection::History
+                };
+                (section, message)
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Messages still included, in their original order
+    pub fn messages(&self) -> Vec<Message> {
+        self.entries.iter().map(|(_, m)| m.clone()).collect()
+    }
+
+    /// Messages of `section`, for rewriting in place
+    pub fn messages_mut(&mut self, section: ContextSection) -> impl Iterator<Item = &mut Message> {
+        self.entries
+            .iter_mut()
+            .filter(move |(s, _)| *s == section)
+            .map(|(_, message)| message)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn count(&self, section: ContextSection) -> usize {
+        self.entries.iter().filter(|(s, _)| *s == section).count()
+    }
+
+    /// Drop history messages up to and including `message_id`, which a
+    /// context anchor summarizes. Returns how many were removed.
+    pub fn drop_history_through(&mut self, message_id: i64) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|(s, m)| *s != ContextSection::History || m.id > message_id);
+        before - self.entries.len()
+    }
+
+    /// Drop the older half (at least one message) of the lowest-priority
+    /// section that still has content. Returns the section and how many
+    /// messages were removed, or `None` when nothing is left to drop.
+    pub fn shed(&mut self) -> Option<(ContextSection, usize)> {
+        let section = [
+            ContextSection::History,
+            ContextSection::Memories,
+            ContextSection::Graph,
+        ]
+        .into_iter()
+        .find(|section| self.count(*section) > 0)?;
+
+        let mut to_drop = self.count(section).div_ceil(2);
+        let dropped = to_drop;
+        // History is chronological, so the first entries are the oldest; for
+        // recalled sections the first entries are kept as the best matches.
+        if section == ContextSection::History {
+            self.entries.retain(|(s, _)| {
+                if *s == section && to_drop > 0 {
+                    to_drop -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        } else {
+            let keep = self.count(section) - to_drop;
+            let mut seen = 0;
+            self.entries.retain(|(s, _)| {
+                if *s != section {
+                    return true;
+                }
+                seen += 1;
+                seen <= keep
+            });
+        }
+        Some((section, dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::output::{MemoryRecallMatch, MemoryRecallStrategy};
+    use chrono::Utc;
+
+    fn message(id: i64, role: MessageRole, content: &str) -> Message {
+        Message {
+            id,
+            session_id: "s".to_string(),
+            role,
+            content: content.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn detects_overflow_wording() {
+        let err =
+            anyhow::anyhow!("OpenAI API error: This model's maximum context length is 8192 tokens");
+        assert!(!is_context_overflow(ProviderKind::Mock, &err));
+        let err = anyhow::anyhow!("model failed: prompt exceeds the context window");
+        assert!(is_context_overflow(ProviderKind::Mock, &err));
+        let err = anyhow::anyhow!("connection refused");
+        assert!(!is_context_overflow(ProviderKind::Mock, &err));
+    }
+
+    #[test]
+    fn sheds_history_then_memories_then_graph() {
+        let stats = MemoryRecallStats {
+            strategy: MemoryRecallStrategy::Semantic {
+                requested: 2,
+                returned: 1,
+            },
+            scope: Default::default(),
+            matches: vec![MemoryRecallMatch {
+                message_id: Some(10),
+                session_id: None,
+                score: 0.9,
+                role: MessageRole::User,
+                preview: "old".to_string(),
+            }],
+        };
+        let mut context = PromptContext::new(
+            vec![
+                message(1, MessageRole::User, "first"),
+                message(2, MessageRole::Assistant, "second"),
+                message(3, MessageRole::User, "third"),
+                message(10, MessageRole::User, "recalled"),
+                message(-1, MessageRole::System, "[Graph Context - fact x]: {}"),
+            ],
+            Some(&stats),
+        );
+
+        assert_eq!(context.shed(), Some((ContextSection::History, 2)));
+        let remaining: Vec<i64> = context.messages().iter().map(|m| m.id).collect();
+        assert_eq!(remaining, vec![3, 10, -1]);
+        assert_eq!(context.shed(), Some((ContextSection::History, 1)));
+        assert_eq!(context.shed(), Some((ContextSection::Memories, 1)));
+        assert_eq!(context.shed(), Some((ContextSection::Graph, 1)));
+        assert_eq!(context.shed(), None);
+        assert!(context.messages().is_empty());
+    }
+}
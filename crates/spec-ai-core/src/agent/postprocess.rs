@@ -0,0 +1,402 @@
+//! Response post-processing
+//!
+//! Profiles can list [`PostProcessor`]s that reshape the final answer before
+//! it is persisted and returned. Each formatter is a plain string transform;
+//! those that edit prose leave fenced code blocks untouched.
+
+use crate::config::PostProcessor;
+use regex::Regex;
+use std::sync::OnceLock;
+
+fn url_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"https?://[^\s)<>\]"'`]+"#).expect("valid regex"))
+}
+
+/// Apply `processors` to `response` in order
+pub fn apply(processors: &[PostProcessor], response: &str) -> String {
+    processors
+        .iter()
+        .fold(response.to_string(), |text, processor| match processor {
+            PostProcessor::NormalizeMarkdown => normalize_markdown(&text),
+            PostProcessor::InferCodeLanguage => infer_code_languages(&text),
+            PostProcessor::RewriteLinks { from, to } => rewrite_links(&text, from, to),
+            PostProcessor::Wrap { template } => template.replace("{{response}}", &text),
+            PostProcessor::EnsureSection { heading, content } => {
+                ensure_section(&text, heading, content.as_deref())
+            }
+        })
+}
+
+/// A line of the response, tagged with whether it sits inside a code fence
+enum Line<'a> {
+    Prose(&'a str),
+    Fence(&'a str),
+    Code(&'a str),
+}
+
+fn lines(text: &str) -> Vec<Line<'_>> {
+    let mut in_code = false;
+    text.lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                Line::Fence(line)
+            } else if in_code {
+                Line::Code(line)
+            } else {
+                Line::Prose(line)
+            }
+        })
+        .collect()
+}
+
+/// Trim trailing whitespace, collapse runs of blank lines, use `-` for
+/// bullets, separate headings from preceding text and close a dangling fence
+pub fn normalize_markdown(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    let mut out: Vec<String> = Vec::new();
+    let mut open_fences = 0usize;
+
+    for line in lines(&text) {
+        match line {
+            Line::Code(code) => out.push(code.to_string()),
+            Line::Fence(fence) => {
+                open_fences ^= 1;
+                if open_fences == 1 && out.last().is_some_and(|prev| !prev.is_empty()) {
+                    out.push(String::new());
+                }
+                out.push(fence.trim_end().to_string());
+            }
+            Line::Prose(prose) => {
+                let trimmed = prose.trim_end();
+                if trimmed.is_empty() {
+                    if out.last().is_some_and(|prev| !prev.is_empty()) {
+                        out.push(String::new());
+                    }
+                    continue;
+                }
+                let indent = trimmed.len() - trimmed.trim_start().len();
+                let body = &trimmed[indent..];
+                let is_rule = body.chars().all(|c| c == '*' || c == ' ');
+                let body = match body.strip_prefix("* ").or_else(|| body.strip_prefix("+ ")) {
+                    Some(item) if !is_rule => format!("- {}", item),
+                    _ => body.to_string(),
+                };
+                if body.starts_with('#') && out.last().is_some_and(|prev| !prev.is_empty()) {
+                    out.push(String::new());
+                }
+                out.push(format!("{}{}", &trimmed[..indent], body));
+            }
+        }
+    }
+    if open_fences == 1 {
+        out.push("```".to_string());
+    }
+
+    let start = out.iter().position(|line| !line.is_empty()).unwrap_or(0);
+    out[start..].join("\n").trim_end().to_string()
+}
+
+/// Add a language tag to bare ```` ``` ```` fences when the code makes it
+/// reasonably clear
+pub fn infer_code_languages(text: &str) -> String {
+    let parsed = lines(text);
+    let mut out: Vec<String> = Vec::with_capacity(parsed.len());
+    let mut index = 0;
+    while index < parsed.len() {
+        match parsed[index] {
+            // Always an opening fence: blocks are consumed up to their closing fence
+            Line::Fence(fence) => {
+                let body: Vec<&str> = parsed[index + 1..]
+                    .iter()
+                    .map_while(|line| match line {
+                        Line::Code(code) => Some(*code),
+                        _ => None,
+                    })
+                    .collect();
+                let language = if fence.trim() == "```" {
+                    guess_language(&body.join("\n"))
+                } else {
+                    None
+                };
+                match language {
+                    Some(language) => {
+                        out.push(fence.replacen("```", &format!("```{}", language), 1))
+                    }
+                    None => out.push(fence.to_string()),
+                }
+                out.extend(body.iter().map(|line| line.to_string()));
+                index += body.len() + 1;
+                if let Some(Line::Fence(close)) = parsed.get(index) {
+                    out.push(close.to_string());
+                    index += 1;
+                }
+            }
+            Line::Prose(line) | Line::Code(line) => {
+                out.push(line.to_string());
+                index += 1;
+            }
+        }
+    }
+    let mut result = out.join("\n");
+    if text.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Best guess at the language of a code block, `None` when unsure
+pub fn guess_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    if trimmed.starts_with("#!") {
+        return Some(if trimmed.contains("python") {
+            "python"
+        } else {
+            "bash"
+        });
+    }
+
+    const SIGNALS: &[(&str, &[&str])] = &[
+        (
+            "rust",
+            &[
+                "fn ",
+                "let mut ",
+                "impl ",
+                "pub struct",
+                "use std::",
+                "-> Result<",
+                "println!",
+                "&mut ",
+            ],
+        ),
+        (
+            "python",
+            &[
+                "def ", "import ", "elif ", "self.", "print(", "None:", "__init__",
+            ],
+        ),
+        (
+            "typescript",
+            &[
+                "interface ",
+                ": string",
+                ": number",
+                "export type ",
+                "readonly ",
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                "const ",
+                "function ",
+                "=> {",
+                "console.log",
+                "require(",
+                "document.",
+            ],
+        ),
+        ("go", &["package ", "func ", ":= ", "fmt.", "go func"]),
+        (
+            "sql",
+            &[
+                "SELECT ",
+                "INSERT INTO",
+                "CREATE TABLE",
+                "UPDATE ",
+                " FROM ",
+                " WHERE ",
+            ],
+        ),
+        (
+            "bash",
+            &[
+                "$ ", "sudo ", "cargo ", "npm ", "git ", "cd ", "export ", "echo ",
+            ],
+        ),
+        ("html", &["<div", "<html", "<span", "</", "<!DOCTYPE"]),
+    ];
+
+    let mut best: Option<(&'static str, usize)> = None;
+    for (language, needles) in SIGNALS {
+        let hits = needles
+            .iter()
+            .filter(|needle| trimmed.contains(**needle))
+            .count();
+        if hits > best.map_or(0, |(_, count)| count) {
+            best = Some((language, hits));
+        }
+    }
+    if let Some((language, hits)) = best {
+        if hits >= 2 || trimmed.lines().count() == 1 {
+            return Some(language);
+        }
+    }
+
+    let rows: Vec<&str> = trimmed.lines().map(str::trim).collect();
+    if rows
+        .iter()
+        .any(|row| row.starts_with('[') && row.ends_with(']'))
+        && rows.iter().any(|row| row.contains(" = "))
+    {
+        return Some("toml");
+    }
+    if rows.iter().all(|row| {
+        row.is_empty() || row.starts_with('#') || row.starts_with("- ") || row.contains(": ")
+    }) && rows.iter().any(|row| row.contains(": "))
+    {
+        return Some("yaml");
+    }
+    best.map(|(language, _)| language)
+}
+
+/// Replace the `from` prefix of every URL outside code blocks that starts
+/// with it
+pub fn rewrite_links(text: &str, from: &str, to: &str) -> String {
+    map_prose(text, |line| {
+        url_re()
+            .replace_all(line, |captures: &regex::Captures| {
+                let url = &captures[0];
+                match url.strip_prefix(from) {
+                    Some(rest) => format!("{}{}", to, rest),
+                    None => url.to_string(),
+                }
+            })
+            .into_owned()
+    })
+}
+
+/// Append a `## heading` section unless a heading with that title exists.
+/// The default content lists the URLs the response links to.
+pub fn ensure_section(text: &str, heading: &str, content: Option<&str>) -> String {
+    let heading = heading.trim();
+    let wanted = heading.to_lowercase();
+    let present = lines(text).iter().any(|line| match line {
+        Line::Prose(line) => {
+            let title = line
+                .trim()
+                .trim_start_matches('#')
+                .trim()
+                .trim_matches('*')
+                .trim_end_matches(':')
+                .trim();
+            (line.trim_start().starts_with('#') || line.trim_start().starts_with("**"))
+                && title.to_lowercase() == wanted
+        }
+        _ => false,
+    });
+    if present {
+        return text.to_string();
+    }
+
+    let body = match content {
+        Some(content) => content.trim().to_string(),
+        None => {
+            let mut urls: Vec<&str> = Vec::new();
+            for line in lines(text) {
+                if let Line::Prose(line) = line {
+                    for found in url_re().find_iter(line) {
+                        let url = found.as_str().trim_end_matches(['.', ',', ';', ':']);
+                        if !urls.contains(&url) {
+                            urls.push(url);
+                        }
+                    }
+                }
+            }
+            if urls.is_empty() {
+                "None.".to_string()
+            } else {
+                urls.iter()
+                    .map(|url| format!("- {}", url))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+    };
+    format!("{}\n\n## {}\n{}", text.trim_end(), heading, body)
+}
+
+/// Transform prose lines, leaving fenced code untouched
+fn map_prose(text: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut result = lines(text)
+        .into_iter()
+        .map(|line| match line {
+            Line::Prose(prose) => f(prose),
+            Line::Fence(line) | Line::Code(line) => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_markdown_outside_code() {
+        let input = "Intro  \r\n\r\n\r\n* one\n+ two\n## Heading\n```\n*  keep   \n\n\n";
+        assert_eq!(
+            normalize_markdown(input),
+            "Intro\n\n- one\n- two\n\n## Heading\n\n```\n*  keep   \n\n\n```"
+        );
+    }
+
+    #[test]
+    fn infers_fence_languages() {
+        let input = "Run:\n```\ncargo build --release\n```\n\n```\nfn main() {\n    let mut x = 1;\n}\n```\n```python\nx = 1\n```";
+        let output = infer_code_languages(input);
+        assert!(output.contains("```bash\ncargo build"));
+        assert!(output.contains("```rust\nfn main"));
+        assert!(output.contains("```python\nx = 1"));
+        assert_eq!(guess_language("{\"a\": 1}"), Some("json"));
+        assert_eq!(guess_language("[server]\nport = 80"), Some("toml"));
+        assert_eq!(guess_language("hello world"), None);
+    }
+
+    #[test]
+    fn rewrites_links_and_appends_sources() {
+        let processors = vec![
+            PostProcessor::RewriteLinks {
+                from: "http://wiki.internal".into(),
+                to: "https://wiki.example.com".into(),
+            },
+            PostProcessor::EnsureSection {
+                heading: "Sources".into(),
+                content: None,
+            },
+        ];
+        let output = apply(
+            &processors,
+            "See [docs](http://wiki.internal/setup) and https://rust-lang.org.\n```\ncurl http://wiki.internal/raw\n```",
+        );
+        assert!(output.contains("[docs](https://wiki.example.com/setup)"));
+        assert!(output.contains("curl http://wiki.internal/raw"));
+        assert!(output
+            .ends_with("## Sources\n- https://wiki.example.com/setup\n- https://rust-lang.org"));
+
+        // An existing section is left alone
+        assert_eq!(apply(&processors, &output), output);
+
+        let wrapped = apply(
+            &[PostProcessor::Wrap {
+                template: "> {{response}}".into(),
+            }],
+            "hi",
+        );
+        assert_eq!(wrapped, "> hi");
+    }
+}
@@ -4,8 +4,8 @@
 //! Supports Claude 3 family models including Opus, Sonnet, and Haiku.
 
 use crate::agent::model::{
-    parse_thinking_tokens, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
-    ProviderMetadata, TokenUsage, ToolCall,
+    parse_thinking_tokens, GenerationConfig, GenerationParam, ModelProvider, ModelResponse,
+    ProviderKind, ProviderMetadata, TokenUsage, ToolCall,
 };
 use anyhow::{anyhow, Result};
 use async_stream::stream;
@@ -45,6 +45,8 @@ struct AnthropicRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<Tool>>,
@@ -206,6 +208,17 @@ impl AnthropicProvider {
             content: prompt.to_string(),
         }];
 
+        config.warn_unsupported(
+            "anthropic",
+            &[
+                GenerationParam::Temperature,
+                GenerationParam::MaxTokens,
+                GenerationParam::StopSequences,
+                GenerationParam::TopP,
+                GenerationParam::TopK,
+            ],
+        );
+
         AnthropicRequest {
             model: self.model.clone(),
             messages,
@@ -213,6 +226,7 @@ impl AnthropicProvider {
             system: self.system_message.clone(),
             temperature: config.temperature,
             top_p: config.top_p,
+            top_k: config.top_k,
             stop_sequences: config.stop_sequences.clone(),
             tools: self.tools.clone(),
             stream: if stream { Some(true) } else { None },
@@ -433,6 +447,10 @@ impl ModelProvider for AnthropicProvider {
                 "claude-3-haiku-20240307".to_string(),
             ],
             supports_streaming: true,
+            supports_function_calling: true,
+            supports_vision: true,
+            supports_json_mode: false,
+            context_window: Some(200_000),
         }
     }
 
@@ -491,6 +509,19 @@ mod tests {
             .contains(&"claude-3-opus-20240229".to_string()));
     }
 
+    #[test]
+    fn test_build_request_passes_top_k() {
+        let provider = AnthropicProvider::with_api_key("test-key");
+        let config = GenerationConfig {
+            top_k: Some(40),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let request = provider.build_request("Hello", &config, false);
+        assert_eq!(request.top_k, Some(40));
+    }
+
     #[test]
     fn test_anthropic_provider_kind() {
         let provider = AnthropicProvider::with_api_key("test-key");
@@ -6,7 +6,7 @@
 
 use crate::agent::model::{
     parse_thinking_tokens, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
-    ProviderMetadata, TokenUsage, ToolCall,
+    ProviderMetadata, TokenUsage, ToolCall, OPENAI_COMPATIBLE_PARAMS,
 };
 use anyhow::{anyhow, Result};
 use async_openai::{
@@ -146,6 +146,10 @@ impl ModelProvider for LMStudioProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("lmstudio", OPENAI_COMPATIBLE_PARAMS);
 
         if let Some(ref tools) = self.tools {
             request_builder.tools(tools.clone());
@@ -236,6 +240,10 @@ impl ModelProvider for LMStudioProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("lmstudio", OPENAI_COMPATIBLE_PARAMS);
 
         let request = request_builder
             .build()
@@ -308,6 +316,10 @@ impl ModelProvider for LMStudioProvider {
                 "lmstudio-community/phi-3-medium-4k-instruct".to_string(),
             ],
             supports_streaming: true,
+            supports_function_calling: true,
+            supports_vision: false,
+            supports_json_mode: true,
+            context_window: None,
         }
     }
 
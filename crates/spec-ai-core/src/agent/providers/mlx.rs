@@ -5,7 +5,7 @@
 
 use crate::agent::model::{
     parse_thinking_tokens, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
-    ProviderMetadata, TokenUsage, ToolCall,
+    ProviderMetadata, TokenUsage, ToolCall, OPENAI_COMPATIBLE_PARAMS,
 };
 use anyhow::{anyhow, Result};
 use async_openai::{
@@ -156,6 +156,10 @@ impl ModelProvider for MLXProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("mlx", OPENAI_COMPATIBLE_PARAMS);
 
         // Add tools to the request if available (native function calling)
         if let Some(ref tools) = self.tools {
@@ -253,6 +257,10 @@ impl ModelProvider for MLXProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("mlx", OPENAI_COMPATIBLE_PARAMS);
 
         let request = request_builder
             .build()
@@ -336,6 +344,10 @@ impl ModelProvider for MLXProvider {
                 // MLX supports many models - these are just examples
             ],
             supports_streaming: true,
+            supports_function_calling: true,
+            supports_vision: false,
+            supports_json_mode: false,
+            context_window: None,
         }
     }
 
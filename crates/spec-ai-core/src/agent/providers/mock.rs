@@ -111,6 +111,10 @@ impl ModelProvider for MockProvider {
                 "mock-claude-3".to_string(),
             ],
             supports_streaming: true,
+            supports_function_calling: false,
+            supports_vision: false,
+            supports_json_mode: false,
+            context_window: None,
         }
     }
 
@@ -30,4 +30,4 @@ pub use lmstudio::LMStudioProvider;
 pub use anthropic::AnthropicProvider;
 
 #[cfg(feature = "ollama")]
-pub use ollama::OllamaProvider;
+pub use ollama::{OllamaModel, OllamaProvider};
@@ -2,6 +2,8 @@
 //!
 //! Integration with Ollama for running local LLMs.
 //! Supports any model available through your local Ollama instance.
+//! The provider can also list, pull and delete models, and pulls the
+//! configured model on first use when it is not installed yet.
 
 use crate::agent::model::{
     parse_thinking_tokens, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
@@ -13,9 +15,48 @@ use async_trait::async_trait;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
 
 const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
 
+/// A model installed in the local Ollama instance
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaModel {
+    /// Name including tag, e.g. `llama3:latest`
+    pub name: String,
+    /// Size on disk in bytes
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl OllamaModel {
+    /// Whether this is the model `name` refers to; a name without a tag
+    /// means `:latest`
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || (!name.contains(':') && self.name == format!("{}:latest", name))
+    }
+}
+
+/// Ollama `/api/tags` response
+#[derive(Debug, Deserialize)]
+struct OllamaTagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+/// Body of `/api/pull` and `/api/delete` requests
+#[derive(Debug, Serialize)]
+struct OllamaModelRequest<'a> {
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
 /// Message in an Ollama chat conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Message {
@@ -45,6 +86,14 @@ struct OllamaOptions {
     top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
 }
 
 /// Ollama chat API response
@@ -82,6 +131,10 @@ pub struct OllamaProvider {
     model: String,
     /// Optional system message for all requests
     system_message: Option<String>,
+    /// Pull the model before the first request if it is not installed
+    auto_pull: bool,
+    /// Set once the model is known to be installed (shared between clones)
+    model_ready: Arc<OnceCell<()>>,
 }
 
 impl OllamaProvider {
@@ -90,12 +143,7 @@ impl OllamaProvider {
     /// This will use http://localhost:11434 as the base URL and
     /// "llama2" as the default model.
     pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            base_url: DEFAULT_OLLAMA_URL.to_string(),
-            model: "llama2".to_string(),
-            system_message: None,
-        }
+        Self::with_base_url(DEFAULT_OLLAMA_URL)
     }
 
     /// Create a new Ollama provider with a custom base URL
@@ -105,15 +153,146 @@ impl OllamaProvider {
             base_url: base_url.into(),
             model: "llama2".to_string(),
             system_message: None,
+            auto_pull: true,
+            model_ready: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Use `OLLAMA_BASE_URL` when set, otherwise the default local address
+    pub fn from_env() -> Self {
+        match std::env::var("OLLAMA_BASE_URL") {
+            Ok(base_url) => Self::with_base_url(base_url),
+            Err(_) => Self::new(),
         }
     }
 
     /// Set the model to use
     pub fn with_model(mut self, model: impl Into<String>) -> Self {
         self.model = model.into();
+        self.model_ready = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// Whether to pull a missing model before the first request (default: on)
+    pub fn with_auto_pull(mut self, auto_pull: bool) -> Self {
+        self.auto_pull = auto_pull;
         self
     }
 
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Models installed in the local Ollama instance
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>> {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await
+            .map_err(|e| self.request_error("listing models", e))?;
+        let response = Self::check_status(response, "listing models").await?;
+        let tags: OllamaTagsResponse = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Ollama model list: {}", e))?;
+        Ok(tags.models)
+    }
+
+    /// Download `name` from the Ollama library; returns once the pull finishes
+    pub async fn pull_model(&self, name: &str) -> Result<()> {
+        let response = self
+            .client
+            .post(format!("{}/api/pull", self.base_url))
+            .json(&OllamaModelRequest {
+                model: name,
+                stream: Some(false),
+            })
+            .send()
+            .await
+            .map_err(|e| self.request_error("pulling a model", e))?;
+        Self::check_status(response, &format!("pulling '{}'", name)).await?;
+        Ok(())
+    }
+
+    /// Remove an installed model
+    pub async fn delete_model(&self, name: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(format!("{}/api/delete", self.base_url))
+            .json(&OllamaModelRequest {
+                model: name,
+                stream: None,
+            })
+            .send()
+            .await
+            .map_err(|e| self.request_error("deleting a model", e))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(anyhow!("Model '{}' is not installed in Ollama", name));
+        }
+        Self::check_status(response, &format!("deleting '{}'", name)).await?;
+        Ok(())
+    }
+
+    /// Pull the configured model if it is not installed yet. Checked once
+    /// per provider; does nothing when auto-pull is off.
+    pub async fn ensure_model(&self) -> Result<()> {
+        if !self.auto_pull {
+            return Ok(());
+        }
+        self.model_ready
+            .get_or_try_init(|| async {
+                let installed = self.list_models().await?;
+                if !installed.iter().any(|model| model.matches(&self.model)) {
+                    tracing::info!(
+                        "Ollama model '{}' not found locally; pulling it",
+                        self.model
+                    );
+                    self.pull_model(&self.model).await.map_err(|e| {
+                        anyhow!(
+                            "Model '{}' is not installed and pulling it failed: {}",
+                            self.model,
+                            e
+                        )
+                    })?;
+                }
+                Ok::<(), anyhow::Error>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Turn a transport error into a message that says what to do about it
+    fn request_error(&self, action: &str, err: reqwest::Error) -> anyhow::Error {
+        if err.is_connect() {
+            anyhow!(
+                "Could not connect to Ollama at {} while {}. Is the Ollama daemon running? Start it with `ollama serve` or set OLLAMA_BASE_URL.",
+                self.base_url,
+                action
+            )
+        } else {
+            anyhow!("Ollama request failed while {}: {}", action, err)
+        }
+    }
+
+    async fn check_status(response: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        Err(anyhow!(
+            "Ollama API error while {} ({}): {}",
+            action,
+            status,
+            error_text
+        ))
+    }
+
     /// Set a system message to be included in all requests
     pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
         self.system_message = Some(message.into());
@@ -143,20 +322,20 @@ impl OllamaProvider {
             content: prompt.to_string(),
         });
 
-        // Build options from config
-        let options = if config.temperature.is_some()
-            || config.max_tokens.is_some()
-            || config.top_p.is_some()
-            || config.stop_sequences.is_some()
-        {
+        // Build options from config (Ollama accepts every generation parameter)
+        let options = if config.configured_params().is_empty() {
+            None
+        } else {
             Some(OllamaOptions {
                 temperature: config.temperature,
                 num_predict: config.max_tokens,
                 top_p: config.top_p,
                 stop: config.stop_sequences.clone(),
+                top_k: config.top_k,
+                seed: config.seed,
+                frequency_penalty: config.frequency_penalty,
+                presence_penalty: config.presence_penalty,
             })
-        } else {
-            None
         };
 
         OllamaChatRequest {
@@ -182,6 +361,7 @@ impl Default for OllamaProvider {
 #[async_trait]
 impl ModelProvider for OllamaProvider {
     async fn generate(&self, prompt: &str, config: &GenerationConfig) -> Result<ModelResponse> {
+        self.ensure_model().await?;
         let request = self.build_request(prompt, config, false);
 
         // Make the API call
@@ -192,7 +372,7 @@ impl ModelProvider for OllamaProvider {
             .json(&request)
             .send()
             .await
-            .map_err(|e| anyhow!("Ollama API request failed: {}", e))?;
+            .map_err(|e| self.request_error("generating a response", e))?;
 
         // Check for HTTP errors
         if !response.status().is_success() {
@@ -246,6 +426,7 @@ impl ModelProvider for OllamaProvider {
         prompt: &str,
         config: &GenerationConfig,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<String>> + Send>>> {
+        self.ensure_model().await?;
         let request = self.build_request(prompt, config, true);
 
         // Make the streaming API call
@@ -256,7 +437,7 @@ impl ModelProvider for OllamaProvider {
             .json(&request)
             .send()
             .await
-            .map_err(|e| anyhow!("Ollama streaming API request failed: {}", e))?;
+            .map_err(|e| self.request_error("streaming a response", e))?;
 
         // Check for HTTP errors
         if !response.status().is_success() {
@@ -367,6 +548,10 @@ impl ModelProvider for OllamaProvider {
                 "gemma".to_string(),
             ],
             supports_streaming: true,
+            supports_function_calling: false,
+            supports_vision: false,
+            supports_json_mode: true,
+            context_window: None,
         }
     }
 
@@ -459,6 +644,32 @@ mod tests {
         assert_eq!(request.stream, Some(true));
     }
 
+    #[test]
+    fn test_model_matching_and_tags_parsing() {
+        let tags: OllamaTagsResponse = serde_json::from_str(
+            r#"{"models":[{"name":"llama3:latest","model":"llama3:latest","size":4661224676,"digest":"365c0bd3c000"},{"name":"mistral:7b","size":4109865159}]}"#,
+        )
+        .unwrap();
+        assert_eq!(tags.models.len(), 2);
+        assert_eq!(tags.models[0].size, 4_661_224_676);
+
+        let llama = &tags.models[0];
+        assert!(llama.matches("llama3"));
+        assert!(llama.matches("llama3:latest"));
+        assert!(!llama.matches("llama3:8b"));
+        assert!(!tags.models[1].matches("mistral"));
+        assert!(tags.models[1].matches("mistral:7b"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_daemon_error_is_actionable() {
+        // Nothing listens on port 9 (discard) in test environments
+        let provider = OllamaProvider::with_base_url("http://127.0.0.1:9");
+        let err = provider.list_models().await.unwrap_err().to_string();
+        assert!(err.contains("Is the Ollama daemon running?"), "{}", err);
+        assert!(err.contains("http://127.0.0.1:9"));
+    }
+
     #[test]
     fn test_chat_url() {
         let provider = OllamaProvider::new();
@@ -5,7 +5,7 @@
 
 use crate::agent::model::{
     parse_thinking_tokens, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
-    ProviderMetadata, TokenUsage, ToolCall,
+    ProviderMetadata, TokenUsage, ToolCall, OPENAI_COMPATIBLE_PARAMS,
 };
 use anyhow::{anyhow, Result};
 use async_openai::{
@@ -145,6 +145,10 @@ impl ModelProvider for OpenAIProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("openai", OPENAI_COMPATIBLE_PARAMS);
 
         // Add tools to the request if available (native function calling)
         if let Some(ref tools) = self.tools {
@@ -242,6 +246,10 @@ impl ModelProvider for OpenAIProvider {
         if let Some(stop) = &config.stop_sequences {
             request_builder.stop(stop.clone());
         }
+        if let Some(seed) = config.seed {
+            request_builder.seed(seed as i64);
+        }
+        config.warn_unsupported("openai", OPENAI_COMPATIBLE_PARAMS);
 
         let request = request_builder
             .build()
@@ -325,6 +333,10 @@ impl ModelProvider for OpenAIProvider {
                 "gpt-4.1-mini-16k".to_string(),
             ],
             supports_streaming: true,
+            supports_function_calling: true,
+            supports_vision: true,
+            supports_json_mode: true,
+            context_window: Some(128_000),
         }
     }
 
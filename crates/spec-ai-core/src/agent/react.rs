@@ -0,0 +1,197 @@
+//! Text tool-calling protocol for models without native function calling
+//!
+//! The prompt describes each allowed tool's arguments and asks the model to
+//! reply with ReAct-style `Action:` / `Action Input:` lines, which are parsed
+//! back into [`ToolCall`]s for the regular tool loop. Tool results go back to
+//! the model the same way as for native calls. A reply without an action is
+//! the answer, minus any `Final Answer:` label.
+
+use crate::agent::model::ToolCall;
+use serde_json::{Map, Value};
+
+const ACTION: &str = "Action:";
+const ACTION_INPUT: &str = "Action Input:";
+const FINAL_ANSWER: &str = "Final Answer:";
+
+/// A tool call written as text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextAction {
+    /// Reasoning the model wrote before the action
+    pub thought: String,
+    pub call: ToolCall,
+}
+
+impl TextAction {
+    /// The action as the model should see it in the transcript
+    pub fn transcript(&self) -> String {
+        format!(
+            "{} {}\n{} {}",
+            ACTION, self.call.function_name, ACTION_INPUT, self.call.arguments
+        )
+    }
+}
+
+/// Prompt section teaching the protocol, with the argument schema of each
+/// `(name, parameters)` tool
+pub fn instructions(tools: &[(String, Value)]) -> String {
+    let mut text = format!(
+        "TOOL_PROTOCOL: To use a tool, reply with exactly these two lines and nothing after them:\n\
+         {} <tool name>\n\
+         {} <JSON object with the tool's arguments>\n\
+         The result comes back as TOOL_RESULT. When no tool is needed, reply with\n\
+         {} <your answer>\n\
+         Tool arguments:\n",
+        ACTION, ACTION_INPUT, FINAL_ANSWER
+    );
+    for (name, parameters) in tools {
+        text.push_str(&format!(
+            "- {}: {}\n",
+            name,
+            describe_parameters(parameters)
+        ));
+    }
+    text
+}
+
+/// `{"path": string (required), "limit": integer}`
+fn describe_parameters(parameters: &Value) -> String {
+    let required: Vec<&str> = parameters
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = parameters.get("properties").and_then(Value::as_object) else {
+        return "{}".to_string();
+    };
+    let fields: Vec<String> = properties
+        .iter()
+        .map(|(name, schema)| {
+            let kind = schema.get("type").and_then(Value::as_str).unwrap_or("any");
+            if required.contains(&name.as_str()) {
+                format!("\"{}\": {} (required)", name, kind)
+            } else {
+                format!("\"{}\": {}", name, kind)
+            }
+        })
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// The first action in a reply, if the model asked for a tool
+pub fn parse_action(response: &str) -> Option<TextAction> {
+    let mut offset = 0;
+    let mut action = None;
+    for line in response.split_inclusive('\n') {
+        let label = strip_markup(line);
+        if let Some(rest) = label.strip_prefix(ACTION) {
+            let name = rest
+                .trim()
+                .trim_matches(|c: char| c.is_whitespace() || matches!(c, '`' | '"' | '\'' | '*'))
+                .split_whitespace()
+                .next()
+                .unwrap_or_default();
+            if !name.is_empty() {
+                action = Some((offset, offset + line.len(), name.to_string()));
+                break;
+            }
+        }
+        offset += line.len();
+    }
+    let (start, end, name) = action?;
+
+    let thought = strip_markup(&response[..start]);
+    let thought = thought.strip_prefix("Thought:").unwrap_or(thought).trim();
+    let rest = &response[end..];
+    let arguments = rest
+        .find(ACTION_INPUT)
+        .and_then(|at| first_json_object(&rest[at + ACTION_INPUT.len()..]))
+        .unwrap_or_else(|| Value::Object(Map::new()));
+
+    Some(TextAction {
+        thought: thought.to_string(),
+        call: ToolCall {
+            id: format!("text-{}", name),
+            function_name: name,
+            arguments,
+        },
+    })
+}
+
+/// The answer in a reply without an action
+pub fn final_answer(response: &str) -> String {
+    match response.rfind(FINAL_ANSWER) {
+        Some(at) => response[at + FINAL_ANSWER.len()..].trim().to_string(),
+        None => response.to_string(),
+    }
+}
+
+/// Leading whitespace and markdown emphasis, as in `**Action:** x`
+fn strip_markup(text: &str) -> &str {
+    text.trim_start_matches(|c: char| c.is_whitespace() || c == '*' || c == '#')
+}
+
+/// The first JSON object in `text`, ignoring code fences and trailing text
+fn first_json_object(text: &str) -> Option<Value> {
+    let start = text.find('{')?;
+    let mut values = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+    match values.next() {
+        Some(Ok(value @ Value::Object(_))) => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn actions_parse_with_their_arguments() {
+        let reply = "Thought: I need the file.\nAction: file_read\nAction Input: {\"path\": \"src/main.rs\"}\n";
+        let action = parse_action(reply).unwrap();
+        assert_eq!(action.thought, "I need the file.");
+        assert_eq!(action.call.function_name, "file_read");
+        assert_eq!(action.call.arguments, json!({"path": "src/main.rs"}));
+        assert_eq!(
+            action.transcript(),
+            "Action: file_read\nAction Input: {\"path\":\"src/main.rs\"}"
+        );
+    }
+
+    #[test]
+    fn markdown_and_trailing_text_are_tolerated() {
+        let reply = "**Action:** `search`\n**Action Input:**\n```json\n{\"query\": \"rust\", \"limit\": 3}\n```\nObservation: (pending)";
+        let action = parse_action(reply).unwrap();
+        assert_eq!(action.call.function_name, "search");
+        assert_eq!(action.call.arguments, json!({"query": "rust", "limit": 3}));
+
+        let action = parse_action("Action: list_files").unwrap();
+        assert_eq!(action.call.arguments, json!({}));
+    }
+
+    #[test]
+    fn replies_without_actions_are_answers() {
+        assert!(parse_action("The answer is 42.").is_none());
+        assert!(parse_action("Action Input: {}").is_none());
+        assert_eq!(
+            final_answer("Thought: done.\nFinal Answer: It is 42."),
+            "It is 42."
+        );
+        assert_eq!(final_answer("It is 42."), "It is 42.");
+    }
+
+    #[test]
+    fn instructions_describe_arguments() {
+        let tools = vec![(
+            "file_read".to_string(),
+            json!({
+                "type": "object",
+                "properties": {"path": {"type": "string"}},
+                "required": ["path"]
+            }),
+        )];
+        let text = instructions(&tools);
+        assert!(text.contains("- file_read: {\"path\": string (required)}"));
+        assert!(text.contains("Final Answer:"));
+    }
+}
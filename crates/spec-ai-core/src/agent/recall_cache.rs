@@ -0,0 +1,134 @@
+//! Per-session cache of input embeddings and recall results
+//!
+//! Every step embeds the user's input to recall memories, then embeds it
+//! again when storing the message. Embeddings are kept by text hash, so the
+//! input is embedded once per step. Recall results are kept by input hash
+//! together with the session's [`SessionRevision`]: asking the same thing
+//! again (a retry, a rerun spec) reuses the earlier result as long as
+//! nothing recall reads has changed since. Both halves evict the least
+//! recently used entry when full.
+
+use crate::persistence::SessionRevision;
+use std::collections::VecDeque;
+
+/// Recall results kept per session by default
+pub const DEFAULT_RECALL_CACHE_CAPACITY: usize = 32;
+
+/// Embeddings kept alongside; only the latest few inputs are ever reused
+const EMBEDDING_CAPACITY: usize = 8;
+
+#[derive(Debug)]
+pub(crate) struct RecallCache<T> {
+    capacity: usize,
+    /// Least recently used first
+    embeddings: VecDeque<(blake3::Hash, Vec<f32>)>,
+    recalls: VecDeque<(blake3::Hash, SessionRevision, T)>,
+}
+
+impl<T: Clone> RecallCache<T> {
+    /// Cache up to `capacity` recall results; 0 turns the cache off
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            embeddings: VecDeque::new(),
+            recalls: VecDeque::new(),
+        }
+    }
+
+    pub fn embedding(&mut self, text: &str) -> Option<Vec<f32>> {
+        let key = blake3::hash(text.as_bytes());
+        let index = self.embeddings.iter().position(|(k, _)| *k == key)?;
+        let entry = self.embeddings.remove(index)?;
+        let embedding = entry.1.clone();
+        self.embeddings.push_back(entry);
+        Some(embedding)
+    }
+
+    pub fn has_embedding(&self, text: &str) -> bool {
+        let key = blake3::hash(text.as_bytes());
+        self.embeddings.iter().any(|(k, _)| *k == key)
+    }
+
+    pub fn store_embedding(&mut self, text: &str, embedding: &[f32]) {
+        if self.capacity == 0 || embedding.is_empty() {
+            return;
+        }
+        let key = blake3::hash(text.as_bytes());
+        self.embeddings.retain(|(k, _)| *k != key);
+        if self.embeddings.len() >= EMBEDDING_CAPACITY.min(self.capacity) {
+            self.embeddings.pop_front();
+        }
+        self.embeddings.push_back((key, embedding.to_vec()));
+    }
+
+    /// Result recalled for `input` while the session was at `revision`
+    pub fn recall(&mut self, input: &str, revision: &SessionRevision) -> Option<T> {
+        let key = blake3::hash(input.as_bytes());
+        let index = self
+            .recalls
+            .iter()
+            .position(|(k, r, _)| *k == key && r == revision)?;
+        let entry = self.recalls.remove(index)?;
+        let result = entry.2.clone();
+        self.recalls.push_back(entry);
+        Some(result)
+    }
+
+    pub fn store_recall(&mut self, input: &str, revision: SessionRevision, result: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = blake3::hash(input.as_bytes());
+        // Results for older revisions of this input can never match again
+        self.recalls.retain(|(k, _, _)| *k != key);
+        if self.recalls.len() >= self.capacity {
+            self.recalls.pop_front();
+        }
+        self.recalls.push_back((key, revision, result));
+    }
+
+    pub fn clear(&mut self) {
+        self.embeddings.clear();
+        self.recalls.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Persistence;
+    use crate::types::MessageRole;
+
+    #[test]
+    fn least_recently_used_results_are_evicted() {
+        let persistence = Persistence::in_memory().unwrap();
+        let revision = persistence.session_revision("s").unwrap();
+        let mut cache = RecallCache::new(2);
+        cache.store_recall("a", revision.clone(), 1);
+        cache.store_recall("b", revision.clone(), 2);
+        assert_eq!(cache.recall("a", &revision), Some(1));
+        cache.store_recall("c", revision.clone(), 3);
+        assert_eq!(cache.recall("b", &revision), None);
+        assert_eq!(cache.recall("a", &revision), Some(1));
+
+        persistence
+            .insert_message("s", MessageRole::User, "hello")
+            .unwrap();
+        let changed = persistence.session_revision("s").unwrap();
+        assert_ne!(changed, revision);
+        assert_eq!(cache.recall("a", &changed), None);
+    }
+
+    #[test]
+    fn zero_capacity_caches_nothing() {
+        let revision = Persistence::in_memory()
+            .unwrap()
+            .session_revision("s")
+            .unwrap();
+        let mut cache = RecallCache::new(0);
+        cache.store_recall("a", revision.clone(), 1);
+        cache.store_embedding("a", &[1.0]);
+        assert_eq!(cache.recall("a", &revision), None);
+        assert_eq!(cache.embedding("a"), None);
+    }
+}
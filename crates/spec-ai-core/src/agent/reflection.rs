@@ -0,0 +1,224 @@
+//! Post-run reflection and lesson recall
+//!
+//! After a spec or a long tool-heavy run the fast model is asked what worked,
+//! what failed and why. Each answer becomes a `Lesson` graph node linked to the
+//! run's goal and tool nodes; later prompts include the lessons whose task text
+//! overlaps with the new input.
+
+use crate::agent::output::ToolInvocation;
+use crate::types::GraphNode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Tool calls after which a plain run counts as "long" and is reflected on
+pub const LONG_RUN_TOOL_CALLS: usize = 3;
+
+/// Upper bound on lessons stored per reflection
+const MAX_LESSONS_PER_RUN: usize = 3;
+
+/// Minimum keyword overlap for a stored lesson to be considered relevant
+const MIN_RELEVANCE: f32 = 0.15;
+
+/// A lesson extracted by the reflection pass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lesson {
+    pub lesson: String,
+    /// Root cause when the lesson comes from a failure
+    #[serde(default)]
+    pub cause: Option<String>,
+    /// Tools the lesson is about
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default = "default_success")]
+    pub success: bool,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// Prompt asking the fast model to reflect on a finished run
+pub fn build_reflection_prompt(
+    task: &str,
+    response: &str,
+    tool_invocations: &[ToolInvocation],
+) -> String {
+    let mut prompt = String::from(
+        "Review the finished task below and extract up to 3 reusable lessons learned. \
+         For failures, state the root cause. Respond with only a JSON array of objects \
+         with the keys \"lesson\" (string), \"cause\" (string or null), \"tools\" \
+         (array of tool names) and \"success\" (boolean). Respond with [] if nothing \
+         is worth remembering.\n\n",
+    );
+    prompt.push_str(&format!("Task:\n{}\n\n", truncate(task, 1500)));
+    if !tool_invocations.is_empty() {
+        prompt.push_str("Tool calls:\n");
+        for invocation in tool_invocations {
+            let outcome = if invocation.success {
+                "ok".to_string()
+            } else {
+                format!(
+                    "failed: {}",
+                    invocation.error.as_deref().unwrap_or("unknown error")
+                )
+            };
+            prompt.push_str(&format!("- {} ({})\n", invocation.name, outcome));
+        }
+        prompt.push('\n');
+    }
+    prompt.push_str(&format!(
+        "Final response:\n{}\n\nLessons:",
+        truncate(response, 1500)
+    ));
+    prompt
+}
+
+/// Parse the model's reflection, tolerating prose around the JSON array
+pub fn parse_lessons(text: &str) -> Vec<Lesson> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return Vec::new();
+    };
+    if end <= start {
+        return Vec::new();
+    }
+    serde_json::from_str::<Vec<Lesson>>(&text[start..=end])
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|l| !l.lesson.trim().is_empty())
+        .take(MAX_LESSONS_PER_RUN)
+        .collect()
+}
+
+/// Lesson nodes relevant to `input`, best match first
+pub fn relevant_lessons<'a>(
+    input: &str,
+    nodes: &'a [GraphNode],
+    limit: usize,
+) -> Vec<&'a GraphNode> {
+    let query = keywords(input);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(&GraphNode, f32)> = nodes
+        .iter()
+        .filter_map(|node| {
+            let text = format!(
+                "{} {} {}",
+                property(&node.properties, "task"),
+                property(&node.properties, "lesson"),
+                tool_names(&node.properties).join(" ")
+            );
+            let candidate = keywords(&text);
+            let overlap = query.intersection(&candidate).count() as f32;
+            let score = overlap / query.len().min(candidate.len()).max(1) as f32;
+            (score >= MIN_RELEVANCE).then_some((node, score))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scored.into_iter().take(limit).map(|(n, _)| n).collect()
+}
+
+/// One-line rendering of a lesson node for the prompt
+pub fn format_lesson(node: &GraphNode) -> String {
+    let mut line = property(&node.properties, "lesson").to_string();
+    let cause = property(&node.properties, "cause");
+    if !cause.is_empty() {
+        line.push_str(&format!(" (cause: {})", cause));
+    }
+    line
+}
+
+/// Tool names recorded on a lesson node
+pub fn tool_names(properties: &Value) -> Vec<String> {
+    properties["tools"]
+        .as_array()
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn property<'a>(properties: &'a Value, key: &str) -> &'a str {
+    properties[key].as_str().unwrap_or("")
+}
+
+fn keywords(text: &str) -> HashSet<String> {
+    const STOPWORDS: [&str; 16] = [
+        "the", "and", "for", "with", "that", "this", "from", "into", "then", "when", "what",
+        "have", "will", "your", "are", "was",
+    ];
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+pub(crate) fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let mut out: String = text.chars().take(max_chars).collect();
+        out.push('…');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::NodeType;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn lesson_node(id: i64, task: &str, lesson: &str) -> GraphNode {
+        GraphNode {
+            id,
+            session_id: "s".to_string(),
+            node_type: NodeType::Lesson,
+            label: "Lesson".to_string(),
+            properties: json!({"task": task, "lesson": lesson, "tools": ["shell"]}),
+            embedding_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn parses_lessons_wrapped_in_prose() {
+        let text = r#"Sure! Here you go:
+[{"lesson": "Run cargo check before tests", "cause": null, "tools": ["shell"], "success": true},
+ {"lesson": "", "tools": []},
+ {"lesson": "Paths must be absolute", "cause": "relative path resolved against wrong dir", "success": false}]
+Hope that helps."#;
+        let lessons = parse_lessons(text);
+        assert_eq!(lessons.len(), 2);
+        assert_eq!(lessons[0].tools, vec!["shell".to_string()]);
+        assert!(!lessons[1].success);
+        assert!(parse_lessons("nothing to report").is_empty());
+    }
+
+    #[test]
+    fn ranks_lessons_by_task_overlap() {
+        let nodes = vec![
+            lesson_node(1, "write release notes", "Group changes by crate"),
+            lesson_node(
+                2,
+                "run the cargo test suite",
+                "Build once before running tests",
+            ),
+        ];
+        let relevant = relevant_lessons("please run cargo test for the workspace", &nodes, 3);
+        assert_eq!(relevant.len(), 1);
+        assert_eq!(relevant[0].id, 2);
+        assert_eq!(
+            format_lesson(relevant[0]),
+            "Build once before running tests"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Posting due reminders into their sessions
+//!
+//! Reminders set with the `remind_me` tool wait in the database. Once one
+//! is due, [`deliver_due`] posts it into its session as an assistant
+//! message, where it shows up in the history and the next turn's context.
+//! The API server delivers every [`DELIVERY_INTERVAL`]; the REPL delivers
+//! when it starts and while it waits for input, so reminders that fell due
+//! while nothing was running arrive at the next launch.
+
+use crate::persistence::reminders::Reminder;
+use crate::persistence::Persistence;
+use crate::types::MessageRole;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Time between checks for due reminders
+pub const DELIVERY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The message a reminder is posted as
+pub fn reminder_text(reminder: &Reminder) -> String {
+    format!("Reminder: {}", reminder.message)
+}
+
+/// Post every reminder due at `now` into its session and return them
+pub fn deliver_due(persistence: &Persistence, now: DateTime<Utc>) -> Result<Vec<Reminder>> {
+    let mut delivered = Vec::new();
+    for reminder in persistence.due_reminders(now)? {
+        // Another process may have got to it first
+        if !persistence.mark_reminder_delivered(reminder.id)? {
+            continue;
+        }
+        persistence.insert_message(
+            &reminder.session_id,
+            MessageRole::Assistant,
+            &reminder_text(&reminder),
+        )?;
+        delivered.push(reminder);
+    }
+    Ok(delivered)
+}
+
+/// Deliver now and every [`DELIVERY_INTERVAL`]. Returns `None` when there is
+/// no async runtime.
+pub fn spawn_delivery(persistence: Persistence) -> Option<JoinHandle<()>> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    Some(runtime.spawn(async move {
+        loop {
+            let store = persistence.clone();
+            match tokio::task::spawn_blocking(move || deliver_due(&store, Utc::now())).await {
+                Ok(Ok(delivered)) if !delivered.is_empty() => {
+                    info!("Delivered {} reminder(s)", delivered.len())
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => warn!("Failed to deliver reminders: {:#}", err),
+                Err(err) => warn!("Reminder delivery task failed: {}", err),
+            }
+            tokio::time::sleep(DELIVERY_INTERVAL).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_db;
+    use chrono::Duration;
+
+    #[test]
+    fn due_reminders_are_posted_once() {
+        let persistence = create_test_db();
+        let now = Utc::now();
+        persistence
+            .add_reminder("s1", "check the deploy", now - Duration::minutes(1))
+            .unwrap();
+        persistence
+            .add_reminder("s1", "rotate the keys", now + Duration::hours(1))
+            .unwrap();
+
+        let delivered = deliver_due(&persistence, now).unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].message, "check the deploy");
+        let messages = persistence.list_messages("s1", 10).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, MessageRole::Assistant);
+        assert_eq!(messages[0].content, "Reminder: check the deploy");
+
+        assert!(deliver_due(&persistence, now).unwrap().is_empty());
+        let pending = persistence.pending_reminders(Some("s1")).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].message, "rotate the keys");
+
+        persistence
+            .conn()
+            .execute(
+                "UPDATE reminders SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+                [],
+            )
+            .unwrap();
+        let pending = persistence.pending_reminders(Some("s1")).unwrap();
+        assert_eq!(
+            pending[0].created_at.to_rfc3339(),
+            "2020-01-01T00:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Round-table mode: several agent profiles answer, a moderator synthesizes
+//!
+//! Each participant drafts an answer in its own sub-session so the drafts do
+//! not leak into each other's (or the main session's) history. The moderator
+//! then runs in the caller's session with every draft in its prompt. Drafts
+//! and the synthesis are stored in `roundtable_drafts` for later inspection.
+
+use crate::agent::builder::create_agent_for_profile;
+use crate::agent::core::AgentCore;
+use crate::agent::output::AgentOutput;
+use crate::config::{AgentRegistry, AppConfig, RoundtableConfig};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// One participant's answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundtableDraft {
+    pub agent: String,
+    pub response: String,
+}
+
+/// Result of a round-table: the drafts plus the moderator's run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundtableOutput {
+    pub roundtable_id: String,
+    pub drafts: Vec<RoundtableDraft>,
+    pub moderator: String,
+    pub output: AgentOutput,
+}
+
+/// Session a participant drafts in, derived from the caller's session
+pub fn participant_session(session_id: &str, agent: &str) -> String {
+    format!("{}:roundtable:{}", session_id, agent)
+}
+
+/// Prompt asking the moderator to merge the drafts into one answer
+pub fn build_moderator_prompt(question: &str, drafts: &[RoundtableDraft]) -> String {
+    let mut prompt = format!(
+        "You are moderating a round-table. {} agents answered the question below \
+         independently. Compare their drafts, resolve disagreements in favor of the \
+         best-supported reasoning, and write a single final answer. Mention a \
+         disagreement only if the user needs to know about it.\n\nQuestion:\n{}\n",
+        drafts.len(),
+        question.trim()
+    );
+    for draft in drafts {
+        prompt.push_str(&format!(
+            "\nDraft from {}:\n{}\n",
+            draft.agent,
+            draft.response.trim()
+        ));
+    }
+    prompt.push_str("\nFinal answer:");
+    prompt
+}
+
+/// Run `question` past the configured participants, then have `moderator`
+/// synthesize the final response in its own session.
+pub async fn run_roundtable(
+    registry: &AgentRegistry,
+    config: &AppConfig,
+    moderator: &mut AgentCore,
+    question: &str,
+) -> Result<RoundtableOutput> {
+    let settings = &config.roundtable;
+    let count = settings.participants.len();
+    if !(RoundtableConfig::MIN_PARTICIPANTS..=RoundtableConfig::MAX_PARTICIPANTS).contains(&count) {
+        return Err(anyhow!(
+            "roundtable needs {} to {} participants in [roundtable] config, got {}",
+            RoundtableConfig::MIN_PARTICIPANTS,
+            RoundtableConfig::MAX_PARTICIPANTS,
+            count
+        ));
+    }
+
+    let session_id = moderator.session_id().to_string();
+    let roundtable_id = format!("roundtable-{}", Utc::now().timestamp_micros());
+    let persistence = registry.persistence();
+
+    let mut participants = Vec::with_capacity(count);
+    for name in &settings.participants {
+        let agent = create_agent_for_profile(
+            registry,
+            config,
+            name,
+            Some(participant_session(&session_id, name)),
+        )?;
+        participants.push((name.clone(), agent));
+    }
+
+    let results = join_all(
+        participants
+            .iter_mut()
+            .map(|(_, agent)| agent.run_step(question)),
+    )
+    .await;
+
+    let mut drafts = Vec::with_capacity(count);
+    for ((name, _), result) in participants.iter().zip(results) {
+        match result {
+            Ok(output) => {
+                persistence.insert_roundtable_draft(
+                    &session_id,
+                    &roundtable_id,
+                    name,
+                    false,
+                    &output.response,
+                )?;
+                drafts.push(RoundtableDraft {
+                    agent: name.clone(),
+                    response: output.response,
+                });
+            }
+            Err(err) => warn!("Round-table participant '{}' failed: {:#}", name, err),
+        }
+    }
+    if drafts.is_empty() {
+        return Err(anyhow!("every round-table participant failed"));
+    }
+
+    let moderator_name = moderator.agent_name().unwrap_or("moderator").to_string();
+    let output = moderator
+        .run_step(&build_moderator_prompt(question, &drafts))
+        .await?;
+    persistence.insert_roundtable_draft(
+        &session_id,
+        &roundtable_id,
+        &moderator_name,
+        true,
+        &output.response,
+    )?;
+
+    Ok(RoundtableOutput {
+        roundtable_id,
+        drafts,
+        moderator: moderator_name,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentProfile;
+    use crate::persistence::Persistence;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn moderator_prompt_lists_every_draft() {
+        let drafts = vec![
+            RoundtableDraft {
+                agent: "skeptic".to_string(),
+                response: "Probably not.\n".to_string(),
+            },
+            RoundtableDraft {
+                agent: "optimist".to_string(),
+                response: "Yes, if tests pass.".to_string(),
+            },
+        ];
+        let prompt = build_moderator_prompt("Ship on Friday?", &drafts);
+        assert!(prompt.contains("2 agents answered"));
+        assert!(prompt.contains("Question:\nShip on Friday?"));
+        assert!(prompt.contains("Draft from skeptic:\nProbably not.\n"));
+        assert!(prompt.contains("Draft from optimist:\nYes, if tests pass."));
+        assert!(prompt.ends_with("Final answer:"));
+        assert_eq!(
+            participant_session("s1", "skeptic"),
+            "s1:roundtable:skeptic"
+        );
+    }
+
+    #[tokio::test]
+    async fn drafts_and_synthesis_are_persisted() {
+        let dir = tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.database.path = dir.path().join("roundtable.duckdb");
+        config.model.provider = "mock".to_string();
+        config.roundtable.participants = vec!["skeptic".to_string(), "optimist".to_string()];
+
+        let profile = AgentProfile {
+            enable_graph: false,
+            fast_reasoning: false,
+            ..AgentProfile::default()
+        };
+        let agents: HashMap<String, AgentProfile> = ["skeptic", "optimist", "judge"]
+            .into_iter()
+            .map(|name| (name.to_string(), profile.clone()))
+            .collect();
+        let persistence = Persistence::new(&config.database.path).unwrap();
+        let registry = AgentRegistry::new(agents, persistence.clone());
+
+        let mut moderator =
+            create_agent_for_profile(&registry, &config, "judge", Some("main".to_string()))
+                .unwrap();
+        let result = run_roundtable(&registry, &config, &mut moderator, "Ship on Friday?")
+            .await
+            .unwrap();
+
+        assert_eq!(result.drafts.len(), 2);
+        assert_eq!(result.moderator, "judge");
+        let stored = persistence
+            .list_roundtable_drafts("main", &result.roundtable_id)
+            .unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored[2].is_synthesis);
+        assert!(persistence
+            .list_messages(&participant_session("main", "skeptic"), 10)
+            .unwrap()
+            .iter()
+            .any(|m| m.content == "Ship on Friday?"));
+    }
+}
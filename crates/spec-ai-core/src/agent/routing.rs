@@ -0,0 +1,157 @@
+//! Model routing by task class
+//!
+//! A profile's `model_routes` sends each class of task (`code`,
+//! `summarize`, `extract`, `chat`, `plan`) to its own provider, so cheap
+//! models can take the cheap work. Steps are classified from the fast-model
+//! task type when one was detected, otherwise from keywords in the input;
+//! classes without a route use the main model.
+
+use crate::agent::model::ModelProvider;
+use crate::config::TaskClass;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Keywords per class, matched at word starts in the lowercased input
+const KEYWORDS: &[(TaskClass, &[&str])] = &[
+    (
+        TaskClass::Summarize,
+        &[
+            "summarize",
+            "summarise",
+            "summary",
+            "tl;dr",
+            "tldr",
+            "recap",
+        ],
+    ),
+    (
+        TaskClass::Extract,
+        &[
+            "extract",
+            "list all",
+            "pull out",
+            "entities",
+            "parse",
+            "fields from",
+        ],
+    ),
+    (
+        TaskClass::Code,
+        &[
+            "```",
+            "code",
+            "function",
+            "compile",
+            "refactor",
+            "implement",
+            "bug",
+            "debug",
+            "stack trace",
+            "unit test",
+            "regex",
+            "script",
+        ],
+    ),
+    (
+        TaskClass::Plan,
+        &[
+            "plan",
+            "roadmap",
+            "steps to",
+            "strategy",
+            "break down",
+            "milestones",
+            "how should i",
+        ],
+    ),
+];
+
+/// Class of a fast-model task type from `detect_task_type`
+pub fn class_of_fast_task(task: &str) -> Option<TaskClass> {
+    match task {
+        "summarization" => Some(TaskClass::Summarize),
+        "entity_extraction" => Some(TaskClass::Extract),
+        _ => None,
+    }
+}
+
+/// Most likely class of `input`; `Chat` when no class's keywords appear.
+/// Ties go to the class listed first in [`KEYWORDS`].
+pub fn classify(input: &str) -> TaskClass {
+    let text = input.to_lowercase();
+    let mut best = (TaskClass::Chat, 0);
+    for (class, keywords) in KEYWORDS {
+        let hits = keywords.iter().filter(|kw| mentions(&text, kw)).count();
+        if hits > best.1 {
+            best = (*class, hits);
+        }
+    }
+    best.0
+}
+
+/// Whether `keyword` occurs in `text` at the start of a word, so `plan`
+/// matches "planning" but not "explanation"
+fn mentions(text: &str, keyword: &str) -> bool {
+    text.match_indices(keyword)
+        .any(|(idx, _)| !matches!(text[..idx].chars().next_back(), Some(c) if c.is_alphanumeric()))
+}
+
+/// A provider serving one task class
+#[derive(Clone)]
+pub struct ModelRoute {
+    pub provider: Arc<dyn ModelProvider>,
+    /// `provider/model` as configured, for logs and run stats
+    pub label: String,
+}
+
+/// Routes configured for an agent
+#[derive(Clone, Default)]
+pub struct ModelRouter {
+    routes: HashMap<TaskClass, ModelRoute>,
+}
+
+impl ModelRouter {
+    pub fn insert(&mut self, class: TaskClass, route: ModelRoute) {
+        self.routes.insert(class, route);
+    }
+
+    pub fn get(&self, class: TaskClass) -> Option<&ModelRoute> {
+        self.routes.get(&class)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_keywords() {
+        assert_eq!(
+            classify("Summarize this thread for me"),
+            TaskClass::Summarize
+        );
+        assert_eq!(
+            classify("Extract the invoice fields from this email"),
+            TaskClass::Extract
+        );
+        assert_eq!(
+            classify("Refactor this function so it compiles"),
+            TaskClass::Code
+        );
+        assert_eq!(
+            classify("Draft a roadmap with milestones for Q3"),
+            TaskClass::Plan
+        );
+        assert_eq!(classify("Hi, how are you today?"), TaskClass::Chat);
+        assert_eq!(classify("Thanks for the explanation"), TaskClass::Chat);
+        assert_eq!(
+            class_of_fast_task("entity_extraction"),
+            Some(TaskClass::Extract)
+        );
+        assert_eq!(class_of_fast_task("tool_selection"), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! Archiving and deleting inactive sessions
+//!
+//! With `[sessions]` configured, a sweep archives every session nobody has
+//! used for `archive_after_days` (see [`AgentCore::archive_session`]) and
+//! deletes those left alone for `delete_after_days`. The REPL and the API
+//! server sweep when they start and every [`SWEEP_INTERVAL`] after that;
+//! `spec-ai sessions sweep` runs a single sweep.
+
+use crate::agent::builder::create_agent_for_profile;
+use crate::agent::core::AgentCore;
+use crate::config::{AgentRegistry, AppConfig, SessionsConfig};
+use crate::persistence::SessionRecord;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Time between sweeps of a long-running process
+pub const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(6 * 60 * 60);
+
+/// What a sweep does to a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionAction {
+    Archive,
+    Delete,
+}
+
+/// What is due for `session` at `now`, if anything
+pub fn due_action(
+    config: &SessionsConfig,
+    session: &SessionRecord,
+    now: DateTime<Utc>,
+) -> Option<SessionAction> {
+    let idle = now - session.updated_at;
+    let past = |days: Option<u32>| days.is_some_and(|days| idle >= Duration::days(days.into()));
+    if past(config.delete_after_days) {
+        Some(SessionAction::Delete)
+    } else if past(config.archive_after_days) && !session.is_archived() {
+        Some(SessionAction::Archive)
+    } else {
+        None
+    }
+}
+
+/// Sessions a sweep archived or deleted, or would in a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepReport {
+    pub dry_run: bool,
+    pub archived: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Sessions left as they were, with the error
+    pub failed: Vec<(String, String)>,
+}
+
+/// Archive and delete the sessions due at `now`, except the agent's own.
+/// The agent's fast model, or its main model, writes the summaries.
+pub async fn sweep(
+    agent: &AgentCore,
+    config: &SessionsConfig,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<SweepReport> {
+    let mut report = SweepReport {
+        dry_run,
+        ..Default::default()
+    };
+    for session in agent.persistence().list_session_records()? {
+        if session.session_id == agent.session_id() {
+            continue;
+        }
+        let Some(action) = due_action(config, &session, now) else {
+            continue;
+        };
+        let result = match action {
+            _ if dry_run => Ok(()),
+            SessionAction::Archive => agent.archive_session(&session.session_id).await.map(drop),
+            SessionAction::Delete => agent
+                .persistence()
+                .delete_session(&session.session_id)
+                .map(drop),
+        };
+        match (result, action) {
+            (Err(err), _) => report
+                .failed
+                .push((session.session_id, format!("{:#}", err))),
+            (Ok(()), SessionAction::Archive) => report.archived.push(session.session_id),
+            (Ok(()), SessionAction::Delete) => report.deleted.push(session.session_id),
+        }
+    }
+    Ok(report)
+}
+
+/// Agent to sweep with, of the active profile or else the default one, in
+/// a session of its own
+pub fn sweeper_agent(registry: &AgentRegistry, config: &AppConfig) -> Result<AgentCore> {
+    let name = registry
+        .active_name()
+        .or_else(|| config.default_agent.clone())
+        .or_else(|| registry.list().first().cloned())
+        .ok_or_else(|| anyhow!("no agent profile to write summaries with"))?;
+    create_agent_for_profile(registry, config, &name, None)
+}
+
+/// Sweep now and every [`SWEEP_INTERVAL`] with a [`sweeper_agent`].
+/// Returns `None` when `[sessions]` is not configured or there is no async
+/// runtime.
+pub fn spawn_sweeper(registry: &AgentRegistry, config: &AppConfig) -> Option<JoinHandle<()>> {
+    if !config.sessions.is_enabled() {
+        return None;
+    }
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let agent = match sweeper_agent(registry, config) {
+        Ok(agent) => agent,
+        Err(err) => {
+            warn!("Not archiving inactive sessions: {:#}", err);
+            return None;
+        }
+    };
+    let config = config.sessions.clone();
+    Some(runtime.spawn(async move {
+        loop {
+            match sweep(&agent, &config, Utc::now(), false).await {
+                Ok(report) => {
+                    if !report.archived.is_empty() || !report.deleted.is_empty() {
+                        info!(
+                            "Archived {} and deleted {} inactive sessions",
+                            report.archived.len(),
+                            report.deleted.len()
+                        );
+                    }
+                    for (session_id, err) in &report.failed {
+                        warn!(
+                            "Failed to archive or delete session '{}': {}",
+                            session_id, err
+                        );
+                    }
+                }
+                Err(err) => warn!("Failed to sweep inactive sessions: {:#}", err),
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(idle_days: i64, archived: bool, now: DateTime<Utc>) -> SessionRecord {
+        let updated_at = now - Duration::days(idle_days);
+        SessionRecord {
+            session_id: "s".to_string(),
+            title: None,
+            agent: None,
+            tags: Vec::new(),
+            message_count: 3,
+            created_at: updated_at,
+            updated_at,
+            archived_at: archived.then_some(updated_at),
+        }
+    }
+
+    #[test]
+    fn sessions_are_archived_then_deleted() {
+        let now = Utc::now();
+        let config = SessionsConfig {
+            archive_after_days: Some(30),
+            delete_after_days: Some(180),
+        };
+        assert_eq!(due_action(&config, &session(29, false, now), now), None);
+        assert_eq!(
+            due_action(&config, &session(30, false, now), now),
+            Some(SessionAction::Archive)
+        );
+        assert_eq!(due_action(&config, &session(90, true, now), now), None);
+        assert_eq!(
+            due_action(&config, &session(180, true, now), now),
+            Some(SessionAction::Delete)
+        );
+
+        let keep_forever = SessionsConfig::default();
+        assert_eq!(
+            due_action(&keep_forever, &session(999, false, now), now),
+            None
+        );
+    }
+}
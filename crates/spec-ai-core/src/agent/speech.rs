@@ -0,0 +1,212 @@
+//! Speaking responses aloud
+//!
+//! Hands-free mode reads the agent's answers back through a text-to-speech
+//! command (`say` on macOS, `espeak`/`spd-say` elsewhere, or whatever
+//! `audio.tts_command` names). Markdown is flattened and code left out
+//! first, since neither reads well aloud.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+
+/// Characters of a response read aloud; the rest is left on screen
+const MAX_SPOKEN_CHARS: usize = 600;
+
+/// Built-in speech commands tried in order when none is configured
+const CANDIDATES: &[(&str, &[&str])] = &[
+    ("say", &[]),
+    ("espeak-ng", &[]),
+    ("espeak", &[]),
+    ("spd-say", &["--wait"]),
+];
+
+#[async_trait]
+pub trait SpeechSynthesizer: Send + Sync {
+    /// Name shown when hands-free mode starts
+    fn name(&self) -> &str;
+
+    /// Speak `text`, returning once it has been said
+    async fn speak(&self, text: &str) -> Result<()>;
+}
+
+/// Runs a speech command with the text as its last argument
+#[derive(Debug, Clone)]
+pub struct CommandSynthesizer {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandSynthesizer {
+    /// `command` is split on whitespace: the program, then fixed arguments
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts.next()?;
+        Some(Self {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    /// The first built-in speech command found on PATH
+    pub fn detect() -> Option<Self> {
+        let path = std::env::var_os("PATH")?;
+        let dirs: Vec<_> = std::env::split_paths(&path).collect();
+        CANDIDATES.iter().find_map(|(program, args)| {
+            dirs.iter()
+                .any(|dir| dir.join(program).is_file())
+                .then(|| Self {
+                    program: program.to_string(),
+                    args: args.iter().map(|arg| arg.to_string()).collect(),
+                })
+        })
+    }
+}
+
+#[async_trait]
+impl SpeechSynthesizer for CommandSynthesizer {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    async fn speak(&self, text: &str) -> Result<()> {
+        let output = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .arg(text)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .with_context(|| format!("running {}", self.program))?;
+        if !output.status.success() {
+            bail!(
+                "{} exited with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Records what it was asked to say instead of speaking
+#[derive(Debug, Default)]
+pub struct MockSpeechSynthesizer {
+    spoken: Mutex<Vec<String>>,
+}
+
+impl MockSpeechSynthesizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spoken(&self) -> Vec<String> {
+        self.spoken.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SpeechSynthesizer for MockSpeechSynthesizer {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn speak(&self, text: &str) -> Result<()> {
+        if let Ok(mut spoken) = self.spoken.lock() {
+            spoken.push(text.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The configured speech command, else a detected one. `None` when
+/// responses should stay silent (`"none"`, or nothing found).
+pub fn create_speech_synthesizer(command: Option<&str>) -> Option<Arc<dyn SpeechSynthesizer>> {
+    let synthesizer = match command.map(str::trim) {
+        Some(command) if command.is_empty() || command.eq_ignore_ascii_case("none") => None,
+        Some(command) => CommandSynthesizer::parse(command),
+        None => CommandSynthesizer::detect(),
+    }?;
+    Some(Arc::new(synthesizer))
+}
+
+/// `text` as it should be read aloud: code blocks left out, Markdown
+/// markup and link targets dropped, shortened at a sentence end
+pub fn speakable(text: &str) -> String {
+    let mut prose = String::new();
+    let mut in_code = false;
+    let mut skipped_code = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            if in_code && !skipped_code {
+                prose.push_str(" The code is on screen. ");
+                skipped_code = true;
+            }
+            continue;
+        }
+        if !in_code {
+            prose.push_str(line.trim_start_matches(['#', '>', '-', '*', ' ']));
+            prose.push(' ');
+        }
+    }
+
+    let mut plain = String::with_capacity(prose.len());
+    let mut chars = prose.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '_' | '`' | '[' => {}
+            // `[label](url)`: keep the label, drop the target
+            ']' if chars.peek() == Some(&'(') => {
+                for skipped in chars.by_ref() {
+                    if skipped == ')' {
+                        break;
+                    }
+                }
+            }
+            ']' => {}
+            _ => plain.push(c),
+        }
+    }
+    let plain = plain.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if plain.chars().count() <= MAX_SPOKEN_CHARS {
+        return plain;
+    }
+    let cut: String = plain.chars().take(MAX_SPOKEN_CHARS).collect();
+    match cut.rfind(['.', '?', '!']) {
+        Some(end) => cut[..=end].to_string(),
+        None => format!("{}...", cut.trim_end()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattens_markdown_for_speech() {
+        let response = "## Result\n\nThe **build** failed in [ci.yml](https://x/ci.yml).\n\n```rust\nfn main() {}\n```\n- Fix the `lint` step";
+        assert_eq!(
+            speakable(response),
+            "Result The build failed in ci.yml. The code is on screen. Fix the lint step"
+        );
+
+        let long = "This sentence is spoken. ".repeat(40);
+        let spoken = speakable(&long);
+        assert!(spoken.len() <= MAX_SPOKEN_CHARS);
+        assert!(spoken.ends_with("spoken."));
+    }
+
+    #[test]
+    fn parses_configured_commands() {
+        assert!(create_speech_synthesizer(Some("none")).is_none());
+        assert!(create_speech_synthesizer(Some("  ")).is_none());
+
+        let command = CommandSynthesizer::parse("espeak -s 170").unwrap();
+        assert_eq!(command.name(), "espeak");
+        assert_eq!(command.args, vec!["-s", "170"]);
+    }
+}
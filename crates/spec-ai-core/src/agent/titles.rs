@@ -0,0 +1,88 @@
+//! Session titles
+//!
+//! After the first exchange of a session the fast model is asked for a short
+//! title; without a fast model (or when its reply is unusable) the opening
+//! words of the user's first message stand in.
+
+use crate::agent::reflection::truncate;
+
+/// Longest title kept, in characters
+pub const MAX_TITLE_CHARS: usize = 60;
+
+/// Words taken from the first message for the fallback title
+const FALLBACK_WORDS: usize = 8;
+
+/// Prompt asking the fast model to name a conversation
+pub fn build_title_prompt(input: &str, response: &str) -> String {
+    format!(
+        "Write a title of at most six words for the conversation below. \
+         Reply with the title only, without quotes or punctuation at the end.\n\n\
+         User: {}\n\nAssistant: {}\n\nTitle:",
+        truncate(input.trim(), 1000),
+        truncate(response.trim(), 1000)
+    )
+}
+
+/// Reduce a model reply to a single clean title line
+pub fn clean_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line);
+    let title = line
+        .trim()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '*' | '`' | '#'))
+        .trim_end_matches(['.', ':'])
+        .trim();
+    (!title.is_empty()).then(|| limit_chars(title))
+}
+
+/// Title made from the opening words of the first user message
+pub fn fallback_title(input: &str) -> String {
+    let words: Vec<&str> = input.split_whitespace().take(FALLBACK_WORDS).collect();
+    let mut title = limit_chars(&words.join(" "));
+    if input.split_whitespace().count() > FALLBACK_WORDS {
+        title.push('…');
+    }
+    title
+}
+
+fn limit_chars(text: &str) -> String {
+    text.chars()
+        .take(MAX_TITLE_CHARS)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_model_replies() {
+        assert_eq!(
+            clean_title("\n\"Fixing the CI cache.\"\nSome explanation"),
+            Some("Fixing the CI cache".to_string())
+        );
+        assert_eq!(
+            clean_title("Title: **Rust borrow checker help**"),
+            Some("Rust borrow checker help".to_string())
+        );
+        assert_eq!(clean_title("  \n\"\"\n"), None);
+        assert_eq!(
+            clean_title(&"x".repeat(200)).unwrap().len(),
+            MAX_TITLE_CHARS
+        );
+    }
+
+    #[test]
+    fn fallback_uses_opening_words() {
+        assert_eq!(fallback_title("  hello   there "), "hello there");
+        assert_eq!(
+            fallback_title("please explain how the graph sync protocol resolves conflicts"),
+            "please explain how the graph sync protocol resolves…"
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Transcript export and action items
+//!
+//! `/listen export` turns a session's stored transcription into SubRip or
+//! WebVTT subtitles, or into Markdown meeting notes. When a recording is
+//! saved, the model is asked for the action items it contains; those are
+//! kept as `Task` graph nodes and listed at the top of the notes.
+
+use crate::agent::reflection::truncate;
+use crate::persistence::TranscriptSegment;
+use crate::types::GraphNode;
+use serde::{Deserialize, Serialize};
+
+/// Graph label of action item nodes
+pub const TASK_LABEL: &str = "Task";
+
+/// `source` property of nodes created from transcriptions
+pub const TRANSCRIPTION_SOURCE: &str = "transcription";
+
+/// Upper bound on action items stored per recording
+const MAX_ACTION_ITEMS: usize = 20;
+
+/// Characters of transcript given to the action item pass
+const TRANSCRIPT_CHARS: usize = 12_000;
+
+/// Longest prefix accepted as a speaker name
+const MAX_SPEAKER_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Srt,
+    Vtt,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "srt" => Some(ExportFormat::Srt),
+            "vtt" | "webvtt" => Some(ExportFormat::Vtt),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Something someone agreed to do, as found in a transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+}
+
+/// Speaker named at the start of a chunk, as `Alice: ...`, `[Bob] ...` or
+/// `Speaker 2: ...`. Transcription providers do not separate speakers, so
+/// this is only a proposal; `None` when the chunk names nobody.
+pub fn propose_speaker(text: &str) -> Option<String> {
+    split_speaker(text).map(|(speaker, _)| speaker.to_string())
+}
+
+/// `(speaker, rest)` when `text` opens with a speaker label
+fn split_speaker(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    let (name, rest) = if let Some(bracketed) = text.strip_prefix('[') {
+        let (name, rest) = bracketed.split_once(']')?;
+        (name, rest.trim_start_matches(':'))
+    } else {
+        text.split_once(':')?
+    };
+    let name = name.trim();
+    let looks_like_name = !name.is_empty()
+        && name.chars().count() <= MAX_SPEAKER_CHARS
+        && name.split_whitespace().count() <= 3
+        && name
+            .split_whitespace()
+            .all(|word| word.starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit()))
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '.' | '\''));
+    // "Note: ..." and "12:30" are not speakers; a name is followed by speech
+    let rest = rest.trim_start();
+    (looks_like_name && !rest.is_empty() && !rest.starts_with(|c: char| c.is_ascii_digit()))
+        .then_some((name, rest))
+}
+
+/// A chunk's text without the speaker label it opened with
+fn speech(segment: &TranscriptSegment) -> &str {
+    let text = segment.text.trim();
+    match (&segment.speaker, split_speaker(text)) {
+        (Some(speaker), Some((named, rest))) if named == speaker => rest,
+        _ => text,
+    }
+}
+
+/// Start and end of each segment in milliseconds. Chunks stored without
+/// offsets are placed by their chunk number, `chunk_ms` apart.
+fn spans(segments: &[TranscriptSegment], chunk_ms: i64) -> Vec<(i64, i64)> {
+    segments
+        .iter()
+        .map(|segment| match (segment.start_ms, segment.end_ms) {
+            (Some(start), Some(end)) if end > start => (start, end),
+            _ => {
+                let start = segment.chunk_id.max(0) * chunk_ms;
+                (start, start + chunk_ms)
+            }
+        })
+        .collect()
+}
+
+fn timestamp(ms: i64, fraction_separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        fraction_separator,
+        ms % 1000
+    )
+}
+
+fn clock(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Render `segments` in `format`. `title` heads the Markdown notes and
+/// `action_items` are listed there; subtitles ignore both.
+pub fn render(
+    format: ExportFormat,
+    segments: &[TranscriptSegment],
+    action_items: &[ActionItem],
+    chunk_ms: i64,
+    title: &str,
+) -> String {
+    match format {
+        ExportFormat::Srt => to_srt(segments, chunk_ms),
+        ExportFormat::Vtt => to_vtt(segments, chunk_ms),
+        ExportFormat::Markdown => to_markdown(segments, action_items, chunk_ms, title),
+    }
+}
+
+fn to_srt(segments: &[TranscriptSegment], chunk_ms: i64) -> String {
+    let mut out = String::new();
+    for (index, (segment, (start, end))) in
+        segments.iter().zip(spans(segments, chunk_ms)).enumerate()
+    {
+        let line = match &segment.speaker {
+            Some(speaker) => format!("{}: {}", speaker, speech(segment)),
+            None => speech(segment).to_string(),
+        };
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(start, ','),
+            timestamp(end, ','),
+            line
+        ));
+    }
+    out
+}
+
+fn to_vtt(segments: &[TranscriptSegment], chunk_ms: i64) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let mut out = String::from("WEBVTT\n\n");
+    for (segment, (start, end)) in segments.iter().zip(spans(segments, chunk_ms)) {
+        let line = match &segment.speaker {
+            Some(speaker) => format!("<v {}>{}", escape(speaker), escape(speech(segment))),
+            None => escape(speech(segment)),
+        };
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(start, '.'),
+            timestamp(end, '.'),
+            line
+        ));
+    }
+    out
+}
+
+fn to_markdown(
+    segments: &[TranscriptSegment],
+    action_items: &[ActionItem],
+    chunk_ms: i64,
+    title: &str,
+) -> String {
+    let spans = spans(segments, chunk_ms);
+    let mut out = format!("# {}\n\n", title);
+    if let Some(first) = segments.first() {
+        let length = spans.last().map(|(_, end)| *end).unwrap_or_default();
+        out.push_str(&format!(
+            "_Recorded {} · {}_\n\n",
+            first.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            clock(length)
+        ));
+    }
+
+    let mut speakers: Vec<&str> = Vec::new();
+    for speaker in segments.iter().filter_map(|s| s.speaker.as_deref()) {
+        if !speakers.contains(&speaker) {
+            speakers.push(speaker);
+        }
+    }
+    if !speakers.is_empty() {
+        out.push_str(&format!("**Speakers:** {}\n\n", speakers.join(", ")));
+    }
+
+    let mut sources: Vec<&str> = Vec::new();
+    for source in segments.iter().filter_map(|s| s.source_file.as_deref()) {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    if !sources.is_empty() {
+        out.push_str(&format!("**Source:** {}\n\n", sources.join(", ")));
+    }
+
+    out.push_str("## Action items\n\n");
+    if action_items.is_empty() {
+        out.push_str("_None recorded._\n");
+    }
+    for item in action_items {
+        out.push_str(&format!("- [ ] {}", item.task));
+        let details: Vec<String> = [
+            item.owner.as_ref().map(|owner| format!("owner: {}", owner)),
+            item.due.as_ref().map(|due| format!("due: {}", due)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !details.is_empty() {
+            out.push_str(&format!(" ({})", details.join(", ")));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("\n## Transcript\n\n");
+    for (segment, (start, _)) in segments.iter().zip(&spans) {
+        match &segment.speaker {
+            Some(speaker) => out.push_str(&format!(
+                "**[{}] {}:** {}\n\n",
+                clock(*start),
+                speaker,
+                speech(segment)
+            )),
+            None => out.push_str(&format!("**[{}]** {}\n\n", clock(*start), speech(segment))),
+        }
+    }
+    out
+}
+
+/// Prompt asking the model for the action items in a transcript
+pub fn build_action_items_prompt(segments: &[TranscriptSegment]) -> String {
+    let transcript: String = segments
+        .iter()
+        .map(|segment| match &segment.speaker {
+            Some(speaker) => format!("{}: {}\n", speaker, speech(segment)),
+            None => format!("{}\n", speech(segment)),
+        })
+        .collect();
+    format!(
+        "Read the meeting transcript below and list its action items: tasks someone \
+         agreed or was asked to do. Skip general discussion and decisions that need no \
+         follow-up. Respond with only a JSON array of objects with the keys \"task\" (a \
+         short imperative sentence), \"owner\" (the person responsible, or null) and \
+         \"due\" (the deadline as said, or null). Respond with [] if there are none.\n\n\
+         Transcript:\n{}\nAction items:",
+        truncate(&transcript, TRANSCRIPT_CHARS)
+    )
+}
+
+/// Parse the model's answer, tolerating prose around the JSON array
+pub fn parse_action_items(text: &str) -> Vec<ActionItem> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return Vec::new();
+    };
+    if end <= start {
+        return Vec::new();
+    }
+    let clean = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty() && !value.eq_ignore_ascii_case("null"))
+    };
+    let mut items: Vec<ActionItem> = Vec::new();
+    for item in serde_json::from_str::<Vec<ActionItem>>(&text[start..=end]).unwrap_or_default() {
+        let task = item.task.trim().to_string();
+        if task.is_empty()
+            || items
+                .iter()
+                .any(|seen| seen.task.eq_ignore_ascii_case(&task))
+        {
+            continue;
+        }
+        items.push(ActionItem {
+            task,
+            owner: clean(item.owner),
+            due: clean(item.due),
+        });
+    }
+    items.truncate(MAX_ACTION_ITEMS);
+    items
+}
+
+/// Action items stored from transcriptions among `nodes`, oldest first
+pub fn action_items_from_nodes(nodes: &[GraphNode]) -> Vec<ActionItem> {
+    let mut nodes: Vec<&GraphNode> = nodes
+        .iter()
+        .filter(|node| {
+            node.label == TASK_LABEL
+                && node.properties["source"].as_str() == Some(TRANSCRIPTION_SOURCE)
+        })
+        .collect();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+        .into_iter()
+        .filter_map(|node| serde_json::from_value(node.properties.clone()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn segment(chunk_id: i64, text: &str, span: Option<(i64, i64)>) -> TranscriptSegment {
+        TranscriptSegment {
+            id: chunk_id + 1,
+            chunk_id,
+            text: text.into(),
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 2, 15, 4, 0).unwrap(),
+            start_ms: span.map(|(start, _)| start),
+            end_ms: span.map(|(_, end)| end),
+            speaker: propose_speaker(text),
+            source_file: None,
+        }
+    }
+
+    #[test]
+    fn proposes_speakers_from_labels() {
+        assert_eq!(
+            propose_speaker("Alice: let's ship it").as_deref(),
+            Some("Alice")
+        );
+        assert_eq!(
+            propose_speaker("[Speaker 2] agreed").as_deref(),
+            Some("Speaker 2")
+        );
+        assert_eq!(
+            propose_speaker("Mary Ann: fine").as_deref(),
+            Some("Mary Ann")
+        );
+        assert_eq!(propose_speaker("the plan: ship it"), None);
+        assert_eq!(propose_speaker("Meet at 12:30 tomorrow"), None);
+        assert_eq!(propose_speaker("Hello, this is a test."), None);
+    }
+
+    #[test]
+    fn renders_subtitles() {
+        let segments = vec![
+            segment(0, "Alice: Hello <everyone>", Some((0, 4_500))),
+            segment(1, "No label here", None),
+        ];
+        assert_eq!(
+            render(ExportFormat::Srt, &segments, &[], 5_000, "Meeting"),
+            "1\n00:00:00,000 --> 00:00:04,500\nAlice: Hello <everyone>\n\n\
+             2\n00:00:05,000 --> 00:00:10,000\nNo label here\n\n"
+        );
+        assert_eq!(
+            render(ExportFormat::Vtt, &segments, &[], 5_000, "Meeting"),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:04.500\n<v Alice>Hello &lt;everyone&gt;\n\n\
+             00:00:05.000 --> 00:00:10.000\nNo label here\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_meeting_notes_with_action_items() {
+        let mut segments = vec![
+            segment(0, "Alice: Bob, can you send the report?", Some((0, 30_000))),
+            segment(1, "Bob: Sure, by Friday.", Some((30_000, 65_000))),
+        ];
+        segments[1].source_file = Some("sync.m4a".into());
+        let items = vec![ActionItem {
+            task: "Send the report".into(),
+            owner: Some("Bob".into()),
+            due: Some("Friday".into()),
+        }];
+        let notes = render(
+            ExportFormat::Markdown,
+            &segments,
+            &items,
+            30_000,
+            "Weekly sync",
+        );
+        assert!(notes.starts_with("# Weekly sync\n\n_Recorded 2026-03-02 15:04 UTC · 01:05_"));
+        assert!(notes.contains("**Speakers:** Alice, Bob"));
+        assert!(notes.contains("**Source:** sync.m4a\n"));
+        assert!(notes.contains("- [ ] Send the report (owner: Bob, due: Friday)\n"));
+        assert!(notes.contains("**[00:30] Bob:** Sure, by Friday.\n"));
+    }
+
+    #[test]
+    fn parses_action_items() {
+        let reply = "Here you go:\n[{\"task\": \"Send the report\", \"owner\": \"Bob\", \"due\": null},\
+                     {\"task\": \"send the report\"}, {\"task\": \" \"}, {\"task\": \"Book a room\", \"owner\": \"\"}]";
+        let items = parse_action_items(reply);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].owner.as_deref(), Some("Bob"));
+        assert_eq!(items[0].due, None);
+        assert_eq!(items[1].owner, None);
+        assert!(parse_action_items("nothing to do").is_empty());
+    }
+}
@@ -3,7 +3,9 @@
 //! This module defines the core traits and types for integrating with various transcription providers.
 //! It provides a unified interface that abstracts away provider-specific details.
 
-use anyhow::Result;
+use crate::agent::audio_file::AudioChunk;
+use crate::config::AudioConfig;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
@@ -39,6 +41,22 @@ impl Default for TranscriptionConfig {
     }
 }
 
+impl From<&AudioConfig> for TranscriptionConfig {
+    fn from(audio: &AudioConfig) -> Self {
+        Self {
+            duration_secs: Some(audio.default_duration_secs),
+            chunk_duration_secs: audio.chunk_duration_secs,
+            model: audio
+                .model
+                .clone()
+                .unwrap_or_else(|| "whisper-1".to_string()),
+            out_file: audio.out_file.clone(),
+            language: audio.language.clone(),
+            endpoint: audio.endpoint.clone(),
+        }
+    }
+}
+
 /// Event emitted during transcription
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TranscriptionEvent {
@@ -137,6 +155,16 @@ pub trait TranscriptionProvider: Send + Sync {
         config: &TranscriptionConfig,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<TranscriptionEvent>> + Send>>>;
 
+    /// Transcribe one chunk of a recorded file (16 kHz mono WAV). Providers
+    /// that only handle live capture keep the default, which refuses.
+    async fn transcribe_chunk(
+        &self,
+        _chunk: &AudioChunk,
+        _config: &TranscriptionConfig,
+    ) -> Result<String> {
+        bail!("{} cannot transcribe audio files", self.metadata().name)
+    }
+
     /// Get provider metadata
     fn metadata(&self) -> TranscriptionProviderMetadata;
 
@@ -6,6 +6,7 @@ use crate::agent::transcription::{TranscriptionProvider, TranscriptionProviderKi
 use crate::agent::transcription_providers::MockTranscriptionProvider;
 #[cfg(feature = "vttrs")]
 use crate::agent::transcription_providers::VttRsProvider;
+use crate::config::AudioConfig;
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -39,6 +40,18 @@ impl Default for TranscriptionProviderConfig {
     }
 }
 
+impl From<&AudioConfig> for TranscriptionProviderConfig {
+    fn from(audio: &AudioConfig) -> Self {
+        Self {
+            provider: audio.provider.clone(),
+            api_key_source: audio.api_key_source.clone(),
+            endpoint: audio.endpoint.clone(),
+            on_device: audio.on_device,
+            settings: serde_json::Value::Null,
+        }
+    }
+}
+
 /// Create a transcription provider from configuration
 pub fn create_transcription_provider(
     config: &TranscriptionProviderConfig,
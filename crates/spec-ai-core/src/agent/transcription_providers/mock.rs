@@ -1,5 +1,6 @@
 //! Mock Transcription Provider for Testing
 
+use crate::agent::audio_file::AudioChunk;
 use crate::agent::transcription::{
     TranscriptionConfig, TranscriptionEvent, TranscriptionProvider, TranscriptionProviderKind,
     TranscriptionProviderMetadata,
@@ -100,6 +101,17 @@ impl TranscriptionProvider for MockTranscriptionProvider {
         Ok(Box::pin(stream))
     }
 
+    async fn transcribe_chunk(
+        &self,
+        chunk: &AudioChunk,
+        _config: &TranscriptionConfig,
+    ) -> Result<String> {
+        if self.transcriptions.is_empty() {
+            return Ok(String::new());
+        }
+        Ok(self.transcriptions[chunk.index % self.transcriptions.len()].clone())
+    }
+
     fn metadata(&self) -> TranscriptionProviderMetadata {
         TranscriptionProviderMetadata {
             name: self.name.clone(),
@@ -2,17 +2,21 @@
 //!
 //! Real-time audio transcription using the vtt-rs crate and OpenAI-compatible APIs.
 
+use crate::agent::audio_file::AudioChunk;
 use crate::agent::transcription::{
     TranscriptionConfig, TranscriptionEvent, TranscriptionProvider, TranscriptionProviderKind,
     TranscriptionProviderMetadata,
 };
-use anyhow::{Context as _, Result};
+use anyhow::{bail, Context as _, Result};
 use async_stream::stream;
 use async_trait::async_trait;
 use futures::Stream;
 use std::pin::Pin;
 use vtt_rs::{Config as VttConfig, TranscriptionService};
 
+/// OpenAI-compatible transcription endpoint used when none is configured
+const DEFAULT_ENDPOINT: &str = "https://api.openai.com/v1/audio/transcriptions";
+
 /// VTT-RS based transcription provider
 #[derive(Debug)]
 pub struct VttRsProvider {
@@ -64,6 +68,14 @@ impl VttRsProvider {
         self
     }
 
+    fn endpoint(&self, config: &TranscriptionConfig) -> String {
+        config
+            .endpoint
+            .clone()
+            .or(self.endpoint.clone())
+            .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string())
+    }
+
     /// Build VTT-RS config from transcription config
     fn build_vtt_config(&self, config: &TranscriptionConfig) -> VttConfig {
         use std::path::PathBuf;
@@ -72,11 +84,7 @@ impl VttRsProvider {
         VttConfig {
             chunk_duration_secs: config.chunk_duration_secs as usize,
             model: config.model.clone(),
-            endpoint: config
-                .endpoint
-                .clone()
-                .or(self.endpoint.clone())
-                .unwrap_or_else(|| "https://api.openai.com/v1/audio/transcriptions".to_string()),
+            endpoint: self.endpoint(config),
             out_file: config.out_file.clone().map(PathBuf::from),
             on_device: if self.on_device {
                 Some(vtt_rs::OnDeviceConfig::default())
@@ -191,6 +199,51 @@ impl TranscriptionProvider for VttRsProvider {
         Ok(Box::pin(stream))
     }
 
+    async fn transcribe_chunk(
+        &self,
+        chunk: &AudioChunk,
+        config: &TranscriptionConfig,
+    ) -> Result<String> {
+        use reqwest::multipart::{Form, Part};
+
+        if self.on_device {
+            bail!("on-device mode only transcribes live capture; configure an endpoint to transcribe files");
+        }
+        let file = Part::bytes(chunk.wav.clone())
+            .file_name(format!("chunk-{}.wav", chunk.index))
+            .mime_str("audio/wav")?;
+        let mut form = Form::new()
+            .text("model", config.model.clone())
+            .text("response_format", "json")
+            .part("file", file);
+        if let Some(language) = &config.language {
+            form = form.text("language", language.clone());
+        }
+
+        let endpoint = self.endpoint(config);
+        let response = reqwest::Client::new()
+            .post(&endpoint)
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| format!("sending audio to {}", endpoint))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                "transcription endpoint returned {}: {}",
+                status,
+                body.trim()
+            );
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("parsing transcription response")?;
+        Ok(body["text"].as_str().unwrap_or_default().trim().to_string())
+    }
+
     fn metadata(&self) -> TranscriptionProviderMetadata {
         TranscriptionProviderMetadata {
             name: self.name.clone(),
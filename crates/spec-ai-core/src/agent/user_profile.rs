@@ -0,0 +1,181 @@
+//! Long-term user profile
+//!
+//! When a session ends, its new messages are given to the model together
+//! with what is already known about the user, and the durable facts it
+//! finds (preferences, writing style, recurring projects) are stored in a
+//! profile shared by every session. Each prompt then opens with a compact
+//! block of the newest facts. `/profile` shows, edits and forgets them.
+
+use crate::agent::reflection::truncate;
+use crate::persistence::UserProfileFact;
+use crate::types::{Message, MessageRole};
+use serde::Deserialize;
+
+/// Categories a fact may be filed under; anything else becomes `other`
+pub const CATEGORIES: [&str; 4] = ["preference", "style", "project", "other"];
+
+/// Upper bound on facts stored per extraction
+const MAX_FACTS_PER_SESSION: usize = 5;
+
+/// Characters of conversation given to the extraction pass
+const TRANSCRIPT_CHARS: usize = 6000;
+
+/// A fact proposed by the extraction pass
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExtractedFact {
+    #[serde(default)]
+    pub category: String,
+    pub fact: String,
+}
+
+/// Prompt asking the model for durable facts about the user in `messages`
+pub fn build_extraction_prompt(known: &[UserProfileFact], messages: &[Message]) -> String {
+    let mut prompt = String::from(
+        "Read the conversation below and list durable facts about the user worth \
+         remembering in future conversations: preferences, writing style and \
+         recurring projects. Skip one-off requests, secrets and anything already \
+         known. Respond with only a JSON array of objects with the keys \"category\" \
+         (one of \"preference\", \"style\", \"project\", \"other\") and \"fact\" (a \
+         short sentence about the user). Respond with [] if there is nothing new.\n\n",
+    );
+    if !known.is_empty() {
+        prompt.push_str("Already known:\n");
+        for fact in known {
+            prompt.push_str(&format!("- {}\n", fact.fact));
+        }
+        prompt.push('\n');
+    }
+    let transcript: String = messages
+        .iter()
+        .filter(|m| matches!(m.role, MessageRole::User | MessageRole::Assistant))
+        .map(|m| format!("{}: {}\n", m.role.as_str(), truncate(m.content.trim(), 800)))
+        .collect();
+    prompt.push_str(&format!(
+        "Conversation:\n{}\nFacts:",
+        truncate(&transcript, TRANSCRIPT_CHARS)
+    ));
+    prompt
+}
+
+/// Parse the model's answer, tolerating prose around the JSON array
+pub fn parse_facts(text: &str) -> Vec<ExtractedFact> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return Vec::new();
+    };
+    if end <= start {
+        return Vec::new();
+    }
+    serde_json::from_str::<Vec<ExtractedFact>>(&text[start..=end])
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|f| !f.fact.trim().is_empty())
+        .map(|f| ExtractedFact {
+            category: normalize_category(&f.category),
+            fact: f.fact.trim().to_string(),
+        })
+        .take(MAX_FACTS_PER_SESSION)
+        .collect()
+}
+
+/// Lowercased category, or `other` when it is not one of [`CATEGORIES`]
+pub fn normalize_category(category: &str) -> String {
+    let category = category.trim().to_lowercase();
+    if CATEGORIES.contains(&category.as_str()) {
+        category
+    } else {
+        "other".to_string()
+    }
+}
+
+/// Prompt block listing up to `limit` facts, empty when there are none
+pub fn render_block(facts: &[UserProfileFact], limit: usize) -> String {
+    if facts.is_empty() || limit == 0 {
+        return String::new();
+    }
+    let mut block = String::from("What you know about the user:\n");
+    for fact in facts.iter().take(limit) {
+        block.push_str(&format!("- {}\n", fact.fact));
+    }
+    block
+}
+
+/// The profile as editable text: one `category: fact` line per fact
+pub fn to_text(facts: &[UserProfileFact]) -> String {
+    facts
+        .iter()
+        .map(|fact| format!("{}: {}\n", fact.category, fact.fact))
+        .collect()
+}
+
+/// Read text written by [`to_text`] back into (category, fact) pairs.
+/// Blank lines and lines starting with `#` are skipped; a line without a
+/// known category prefix is filed under `other`.
+pub fn from_text(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(':') {
+            Some((category, fact))
+                if CATEGORIES.contains(&category.trim().to_lowercase().as_str())
+                    && !fact.trim().is_empty() =>
+            {
+                (category.trim().to_lowercase(), fact.trim().to_string())
+            }
+            _ => ("other".to_string(), line.to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn fact(category: &str, text: &str) -> UserProfileFact {
+        UserProfileFact {
+            id: 1,
+            category: category.into(),
+            fact: text.into(),
+            source_session: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn parses_facts_and_normalizes_categories() {
+        let reply = "Sure:\n[{\"category\": \"Style\", \"fact\": \"Writes in British English\"}, \
+                     {\"category\": \"hobby\", \"fact\": \"Plays chess\"}, {\"fact\": \"  \"}]";
+        let facts = parse_facts(reply);
+        assert_eq!(facts.len(), 2);
+        assert_eq!(facts[0].category, "style");
+        assert_eq!(facts[1].category, "other");
+        assert!(parse_facts("nothing new").is_empty());
+    }
+
+    #[test]
+    fn renders_and_round_trips_profile_text() {
+        let facts = vec![
+            fact("project", "Maintains the billing service"),
+            fact("style", "Prefers short answers"),
+        ];
+        assert_eq!(
+            render_block(&facts, 1),
+            "What you know about the user:\n- Maintains the billing service\n"
+        );
+        assert!(render_block(&[], 5).is_empty());
+
+        let mut text = to_text(&facts);
+        text.push_str("# comment\n\nUses vim\n");
+        let parsed = from_text(&text);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(
+            parsed[0],
+            (
+                "project".to_string(),
+                "Maintains the billing service".to_string()
+            )
+        );
+        assert_eq!(parsed[2], ("other".to_string(), "Uses vim".to_string()));
+    }
+}
@@ -0,0 +1,175 @@
+//! Critic pass over draft answers
+//!
+//! With `verify_responses = true` a second model (the profile's
+//! `critic_profile`, or the fast model) reads the draft answer next to the
+//! recalled context and tool output and lists claims that nothing in the
+//! evidence supports. When it finds any, the main model gets one chance to
+//! revise the draft before the answer is stored.
+
+use crate::agent::output::ToolInvocation;
+use crate::agent::reflection::truncate;
+use crate::types::Message;
+use serde::Deserialize;
+
+/// Upper bound on issues carried into the revision request
+const MAX_ISSUES: usize = 5;
+
+/// Characters kept from each context message and tool output
+const EVIDENCE_CHARS: usize = 800;
+
+#[derive(Debug, Deserialize)]
+struct Findings {
+    #[serde(default)]
+    issues: Vec<String>,
+}
+
+/// Prompt asking the critic to check `draft` against the available evidence
+pub fn build_verification_prompt(
+    question: &str,
+    draft: &str,
+    context: &[Message],
+    tool_invocations: &[ToolInvocation],
+) -> String {
+    let mut prompt = String::from(
+        "You are a strict reviewer. Check the draft answer below against the evidence \
+         (recalled context and tool output) and list every factual claim that the \
+         evidence does not support or contradicts. General knowledge and reasoning \
+         steps are fine; invented file contents, command results, numbers or quotes \
+         are not. Respond with only a JSON object of the form \
+         {\"issues\": [\"...\"]}, using an empty array when the draft is supported.\n\n",
+    );
+    prompt.push_str(&format!(
+        "Question:\n{}\n\n",
+        truncate(question.trim(), 1500)
+    ));
+
+    prompt.push_str("Evidence:\n");
+    if context.is_empty() && tool_invocations.is_empty() {
+        prompt.push_str("(none)\n");
+    }
+    for message in context {
+        prompt.push_str(&format!(
+            "- {}: {}\n",
+            message.role.as_str(),
+            truncate(message.content.trim(), EVIDENCE_CHARS)
+        ));
+    }
+    for invocation in tool_invocations {
+        let result = match (&invocation.output, &invocation.error) {
+            (Some(output), _) => truncate(output.trim(), EVIDENCE_CHARS),
+            (None, Some(error)) => format!("error: {}", error),
+            (None, None) => "(no output)".to_string(),
+        };
+        prompt.push_str(&format!("- tool {}: {}\n", invocation.name, result));
+    }
+
+    prompt.push_str(&format!(
+        "\nDraft answer:\n{}\n\nFindings:",
+        truncate(draft.trim(), 3000)
+    ));
+    prompt
+}
+
+/// Parse the critic's findings, tolerating prose around the JSON object.
+/// Returns `None` when the reply contains no usable verdict.
+pub fn parse_findings(text: &str) -> Option<Vec<String>> {
+    let (start, end) = (text.find('{')?, text.rfind('}')?);
+    if end <= start {
+        return None;
+    }
+    let findings: Findings = serde_json::from_str(&text[start..=end]).ok()?;
+    Some(
+        findings
+            .issues
+            .into_iter()
+            .map(|issue| issue.trim().to_string())
+            .filter(|issue| !issue.is_empty())
+            .take(MAX_ISSUES)
+            .collect(),
+    )
+}
+
+/// Text appended to the main prompt asking for one revision of the draft
+pub fn build_revision_request(draft: &str, issues: &[String]) -> String {
+    let mut request = format!(
+        "\n\nDRAFT_ANSWER:\n{}\n\nVERIFICATION_ISSUES (claims not supported by the context or tool results):\n",
+        draft.trim()
+    );
+    for issue in issues {
+        request.push_str(&format!("- {}\n", issue));
+    }
+    request.push_str(
+        "\nRewrite the draft as the final answer, removing or clearly qualifying these \
+         claims. Reply with the revised answer only.",
+    );
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MessageRole;
+    use chrono::Utc;
+    use serde_json::json;
+
+    #[test]
+    fn prompt_includes_context_tools_and_draft() {
+        let context = vec![Message {
+            id: 1,
+            session_id: "s".to_string(),
+            role: MessageRole::User,
+            content: "The build uses Rust 1.80".to_string(),
+            created_at: Utc::now(),
+        }];
+        let tools = vec![
+            ToolInvocation {
+                name: "file_read".to_string(),
+                arguments: json!({"path": "Cargo.toml"}),
+                success: true,
+                output: Some("edition = \"2021\"".to_string()),
+                error: None,
+                cached: false,
+            },
+            ToolInvocation {
+                name: "bash".to_string(),
+                arguments: json!({}),
+                success: false,
+                output: None,
+                error: Some("timed out".to_string()),
+                cached: false,
+            },
+        ];
+        let prompt = build_verification_prompt("Which edition?", "Edition 2021.", &context, &tools);
+        assert!(prompt.contains("Question:\nWhich edition?"));
+        assert!(prompt.contains("- user: The build uses Rust 1.80"));
+        assert!(prompt.contains("- tool file_read: edition = \"2021\""));
+        assert!(prompt.contains("- tool bash: error: timed out"));
+        assert!(prompt.ends_with("Draft answer:\nEdition 2021.\n\nFindings:"));
+
+        let empty = build_verification_prompt("q", "a", &[], &[]);
+        assert!(empty.contains("Evidence:\n(none)\n"));
+    }
+
+    #[test]
+    fn parses_findings_with_surrounding_prose() {
+        let reply = "Sure:\n{\"issues\": [\"Claims 3 tests failed\", \"  \"]}\nDone.";
+        assert_eq!(
+            parse_findings(reply),
+            Some(vec!["Claims 3 tests failed".to_string()])
+        );
+        assert_eq!(parse_findings("{\"issues\": []}"), Some(Vec::new()));
+        assert_eq!(parse_findings("looks fine"), None);
+        assert_eq!(parse_findings("{not json}"), None);
+    }
+
+    #[test]
+    fn revision_request_lists_issues() {
+        let request = build_revision_request(
+            "It has 12 modules.",
+            &["Module count unverified".to_string()],
+        );
+        assert!(request.contains("DRAFT_ANSWER:\nIt has 12 modules."));
+        assert!(request.contains("- Module count unverified\n"));
+        assert!(request.ends_with("Reply with the revised answer only."));
+    }
+}
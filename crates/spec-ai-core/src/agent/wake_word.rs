@@ -0,0 +1,191 @@
+//! Wake-word detection over live transcription
+//!
+//! Hands-free mode keeps a transcription stream running and watches its
+//! chunks for a wake phrase such as "hey spec". What follows the phrase is
+//! collected into one request until the speaker pauses (an empty chunk or
+//! no chunk for a while), a chunk ends a sentence, or the request grows
+//! past its length limit.
+
+/// Watches transcribed chunks for a wake phrase and collects the request
+/// that follows it
+#[derive(Debug, Clone)]
+pub struct WakeWordDetector {
+    /// Each wake phrase as normalized words
+    wake_words: Vec<Vec<String>>,
+    /// Chunks (including pauses) a request may span
+    max_chunks: usize,
+    capture: Option<Capture>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Capture {
+    pieces: Vec<String>,
+    chunks: usize,
+}
+
+impl WakeWordDetector {
+    pub fn new(wake_words: &[String], max_chunks: usize) -> Self {
+        Self {
+            wake_words: wake_words
+                .iter()
+                .map(|phrase| words(phrase).into_iter().map(|(_, _, w)| w).collect())
+                .filter(|phrase: &Vec<String>| !phrase.is_empty())
+                .collect(),
+            max_chunks: max_chunks.max(1),
+            capture: None,
+        }
+    }
+
+    /// Whether a wake phrase was heard and its request is still open
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Feed one transcribed chunk; returns the request once it is complete
+    pub fn feed(&mut self, text: &str) -> Option<String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return self.silence();
+        }
+
+        let piece = match self.capture {
+            Some(_) => text,
+            None => {
+                let rest = self.after_wake_word(text)?;
+                self.capture = Some(Capture::default());
+                rest
+            }
+        };
+        let capture = self.capture.as_mut()?;
+        capture.chunks += 1;
+        if !piece.is_empty() {
+            capture.pieces.push(piece.to_string());
+        }
+        if ends_sentence(piece) || capture.chunks >= self.max_chunks {
+            return self.finish();
+        }
+        None
+    }
+
+    /// Nothing was heard for a chunk's length: a pause ends the request
+    /// unless the wake phrase is still waiting for one
+    pub fn silence(&mut self) -> Option<String> {
+        let capture = self.capture.as_mut()?;
+        capture.chunks += 1;
+        if !capture.pieces.is_empty() || capture.chunks >= self.max_chunks {
+            return self.finish();
+        }
+        None
+    }
+
+    /// Drop any request in progress, e.g. while the response is spoken
+    pub fn reset(&mut self) {
+        self.capture = None;
+    }
+
+    fn finish(&mut self) -> Option<String> {
+        let capture = self.capture.take()?;
+        let request = capture.pieces.join(" ");
+        let request = request.trim();
+        (!request.is_empty()).then(|| request.to_string())
+    }
+
+    /// The text after the first wake phrase in `text`, if there is one
+    fn after_wake_word<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let tokens = words(text);
+        for start in 0..tokens.len() {
+            for phrase in &self.wake_words {
+                let matches = phrase.len() <= tokens.len() - start
+                    && phrase
+                        .iter()
+                        .zip(&tokens[start..])
+                        .all(|(want, (_, _, have))| want == have);
+                if matches {
+                    let end = tokens[start + phrase.len() - 1].1;
+                    return Some(text[end..].trim_start_matches(|c: char| {
+                        c.is_whitespace() || matches!(c, ',' | '.' | '!' | '?' | ':' | '-')
+                    }));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Lowercased words of `text` with their byte ranges
+fn words(text: &str) -> Vec<(usize, usize, String)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        let in_word = c.is_alphanumeric() || c == '\'';
+        match (in_word, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(begin)) => {
+                out.push((begin, idx, text[begin..idx].to_lowercase()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        out.push((begin, text.len(), text[begin..].to_lowercase()));
+    }
+    out
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end().ends_with(['.', '?', '!'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> WakeWordDetector {
+        WakeWordDetector::new(&["hey spec".to_string(), "computer".to_string()], 4)
+    }
+
+    #[test]
+    fn captures_the_request_after_the_wake_phrase() {
+        let mut detector = detector();
+        assert_eq!(detector.feed("so the build was red again"), None);
+        assert!(!detector.is_capturing());
+
+        assert_eq!(
+            detector.feed("Hey, Spec! What failed in the last CI run?"),
+            Some("What failed in the last CI run?".to_string())
+        );
+
+        // The request continues over chunks until a pause
+        assert_eq!(detector.feed("okay computer"), None);
+        assert_eq!(detector.feed("remind me to call Sam"), None);
+        assert_eq!(detector.feed("at three"), None);
+        assert_eq!(
+            detector.silence(),
+            Some("remind me to call Sam at three".to_string())
+        );
+    }
+
+    #[test]
+    fn gives_up_after_the_length_limit() {
+        let mut detector = detector();
+        assert_eq!(detector.feed("hey spec"), None);
+        assert!(detector.is_capturing());
+        assert_eq!(detector.silence(), None);
+        assert_eq!(detector.silence(), None);
+        assert_eq!(detector.silence(), None);
+        assert!(!detector.is_capturing());
+
+        detector.feed("hey spec list");
+        detector.feed("every");
+        detector.feed("open");
+        assert_eq!(
+            detector.feed("ticket"),
+            Some("list every open ticket".into())
+        );
+
+        detector.feed("hey spec stop");
+        detector.reset();
+        assert_eq!(detector.silence(), None);
+    }
+}
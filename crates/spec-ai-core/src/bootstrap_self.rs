@@ -3,7 +3,11 @@ pub mod plugins;
 pub mod registry;
 
 use crate::persistence::Persistence;
+use crate::progress::ProgressReporter;
+use crate::types::{EdgeType, NodeType};
 use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -11,6 +15,9 @@ use plugin::BootstrapMode;
 use plugins::{RustCargoPlugin, ToakTokenizerPlugin, UniversalCodePlugin};
 use registry::PluginRegistry;
 
+/// Label of the node a workspace's repository graph hangs off
+pub const WORKSPACE_LABEL: &str = "Workspace";
+
 #[derive(Debug)]
 pub struct BootstrapOutcome {
     pub repository_node_id: i64,
@@ -20,6 +27,8 @@ pub struct BootstrapOutcome {
     pub component_count: usize,
     pub document_count: usize,
     pub phases: Vec<String>,
+    /// Workspace node the run's graph was attached to, for workspace runs
+    pub workspace_node_id: Option<i64>,
 }
 
 pub struct BootstrapSelf<'a> {
@@ -27,6 +36,8 @@ pub struct BootstrapSelf<'a> {
     session_id: &'a str,
     repo_root: PathBuf,
     plugins: PluginRegistry,
+    progress: ProgressReporter,
+    workspace: Option<String>,
 }
 
 impl<'a> BootstrapSelf<'a> {
@@ -36,9 +47,25 @@ impl<'a> BootstrapSelf<'a> {
             session_id,
             repo_root,
             plugins: PluginRegistry::new(),
+            progress: ProgressReporter::noop(),
+            workspace: None,
         }
     }
 
+    /// Namespace the run under a workspace: every node it creates gets a
+    /// `workspace` property, and the repository nodes are linked to a
+    /// `Workspace` node, so several repositories can share a session graph
+    pub fn with_workspace(mut self, name: impl Into<String>) -> Self {
+        self.workspace = Some(name.into());
+        self
+    }
+
+    /// Report per-plugin progress through the given reporter
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = progress;
+        self
+    }
+
     pub fn from_environment(persistence: &'a Persistence, session_id: &'a str) -> Result<Self> {
         let repo_root = resolve_repo_root()?;
         Ok(Self::new(persistence, session_id, repo_root))
@@ -75,6 +102,11 @@ impl<'a> BootstrapSelf<'a> {
             ));
         }
 
+        let existing_nodes = match &self.workspace {
+            Some(_) => self.node_ids()?,
+            None => HashSet::new(),
+        };
+
         let context = plugin::PluginContext {
             persistence: self.persistence,
             session_id: self.session_id,
@@ -89,9 +121,30 @@ impl<'a> BootstrapSelf<'a> {
         let mut component_count = 0;
         let mut document_count = 0;
         let mut root_node_id = None;
+        let mut plugin_roots = Vec::new();
+
+        let plugin_total = active_plugins.len() as u64;
+        self.progress.started(
+            "bootstrap",
+            format!("running {} plugin(s)", plugin_total),
+            Some(plugin_total),
+        );
 
-        for plugin in active_plugins {
-            let outcome = plugin.run(context.clone())?;
+        for (idx, plugin) in active_plugins.into_iter().enumerate() {
+            self.progress.step(
+                "bootstrap",
+                format!("plugin {}", plugin.name()),
+                Some(idx as u64),
+                Some(plugin_total),
+            );
+            let outcome = match plugin.run(context.clone()) {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    self.progress
+                        .failed("bootstrap", format!("plugin {} failed", plugin.name()));
+                    return Err(err);
+                }
+            };
 
             total_nodes += outcome.nodes_created;
             total_edges += outcome.edges_created;
@@ -101,6 +154,7 @@ impl<'a> BootstrapSelf<'a> {
             if root_node_id.is_none() {
                 root_node_id = outcome.root_node_id;
             }
+            plugin_roots.extend(outcome.root_node_id);
 
             // Extract metadata from first plugin that provides it
             if let Some(name) = outcome
@@ -129,6 +183,16 @@ impl<'a> BootstrapSelf<'a> {
         let repository_node_id =
             root_node_id.ok_or_else(|| anyhow!("No repository node created by plugins"))?;
 
+        let workspace_node_id = match &self.workspace {
+            Some(name) => Some(self.attach_to_workspace(name, &existing_nodes, &plugin_roots)?),
+            None => None,
+        };
+
+        self.progress.finished(
+            "bootstrap",
+            format!("{} nodes, {} edges", total_nodes, total_edges),
+        );
+
         Ok(BootstrapOutcome {
             repository_node_id,
             nodes_created: total_nodes,
@@ -137,9 +201,78 @@ impl<'a> BootstrapSelf<'a> {
             component_count,
             document_count,
             phases: all_phases,
+            workspace_node_id,
         })
     }
 
+    fn node_ids(&self) -> Result<HashSet<i64>> {
+        Ok(self
+            .persistence
+            .list_graph_nodes(self.session_id, None, None)?
+            .into_iter()
+            .map(|node| node.id)
+            .collect())
+    }
+
+    /// Tag the nodes created since `existing` with the workspace name and
+    /// link the plugins' repository nodes to the workspace node, creating
+    /// it on the first run
+    fn attach_to_workspace(
+        &self,
+        name: &str,
+        existing: &HashSet<i64>,
+        roots: &[i64],
+    ) -> Result<i64> {
+        let workspace_node = self
+            .persistence
+            .list_graph_nodes(self.session_id, Some(NodeType::Entity), None)?
+            .into_iter()
+            .find(|node| {
+                node.label == WORKSPACE_LABEL
+                    && node.properties.get("workspace").and_then(|v| v.as_str()) == Some(name)
+            });
+        let workspace_node_id = match workspace_node {
+            Some(node) => node.id,
+            None => self.persistence.insert_graph_node(
+                self.session_id,
+                NodeType::Entity,
+                WORKSPACE_LABEL,
+                &json!({
+                    "workspace": name,
+                    "root": self.repo_root.display().to_string(),
+                }),
+                None,
+            )?,
+        };
+
+        for node in self
+            .persistence
+            .list_graph_nodes(self.session_id, None, None)?
+        {
+            if existing.contains(&node.id) || node.id == workspace_node_id {
+                continue;
+            }
+            let mut properties = node.properties.clone();
+            if let Some(map) = properties.as_object_mut() {
+                map.insert("workspace".to_string(), json!(name));
+                self.persistence.update_graph_node(node.id, &properties)?;
+            }
+        }
+        // Repository nodes a refresh reused are linked already
+        for root in roots.iter().filter(|root| !existing.contains(root)) {
+            self.persistence.insert_graph_edge(
+                self.session_id,
+                *root,
+                workspace_node_id,
+                EdgeType::PartOf,
+                Some("in_workspace"),
+                None,
+                1.0,
+            )?;
+        }
+        Ok(workspace_node_id)
+    }
+
     /// Run bootstrap with auto-detection (backward compatibility)
     pub fn run(&self) -> Result<BootstrapOutcome> {
         self.run_with_plugins(None)
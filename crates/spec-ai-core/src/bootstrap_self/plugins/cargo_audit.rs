@@ -0,0 +1,554 @@
+//! Dependency audit for the rust-cargo plugin
+//!
+//! Reads the packages pinned in `Cargo.lock` and checks each against a local
+//! checkout of the RustSec advisory database
+//! (<https://github.com/rustsec/advisory-db>), the same one `cargo audit`
+//! uses. The checkout lives at `$CARGO_HOME/advisory-db` unless
+//! `SPEC_AI_ADVISORY_DB` points elsewhere. A missing checkout is cloned and
+//! an existing one updated on fresh bootstraps; with
+//! `SPEC_AI_ADVISORY_OFFLINE=1` the database is only read, never fetched.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::bootstrap_self::plugin::BootstrapMode;
+use crate::spec_source::config_dir;
+
+const ADVISORY_DB_URL: &str = "https://github.com/rustsec/advisory-db.git";
+const ADVISORY_DB_ENV: &str = "SPEC_AI_ADVISORY_DB";
+const ADVISORY_OFFLINE_ENV: &str = "SPEC_AI_ADVISORY_OFFLINE";
+
+/// A package pinned in `Cargo.lock`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+impl LockedPackage {
+    /// Packages without a source are workspace members or path dependencies
+    pub fn is_external(&self) -> bool {
+        self.source.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct LockFile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+/// Packages of the `Cargo.lock` at the repository root, if there is one
+pub fn read_lockfile(repo_root: &Path) -> Result<Option<Vec<LockedPackage>>> {
+    let path = repo_root.join("Cargo.lock");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let lock: LockFile = toml::from_str(&raw).context("parsing Cargo.lock")?;
+    Ok(Some(lock.package))
+}
+
+/// License declared by a package, from its sources in the local cargo
+/// registry cache
+pub fn registry_license(package: &LockedPackage) -> Option<String> {
+    let registry = cargo_home()?.join("registry").join("src");
+    let dir_name = format!("{}-{}", package.name, package.version);
+    for index in fs::read_dir(registry).ok()?.flatten() {
+        let manifest = index.path().join(&dir_name).join("Cargo.toml");
+        let Ok(raw) = fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let value: toml::Value = toml::from_str(&raw).ok()?;
+        return value
+            .get("package")
+            .and_then(|package| package.get("license"))
+            .and_then(|license| license.as_str())
+            .map(str::to_string);
+    }
+    None
+}
+
+/// A RustSec advisory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    pub title: String,
+    pub date: Option<String>,
+    /// CVE and GHSA identifiers of the same issue
+    pub aliases: Vec<String>,
+    pub url: Option<String>,
+    pub cvss: Option<String>,
+    /// `unmaintained`, `unsound` or `notice` for advisories that are not
+    /// vulnerabilities
+    pub informational: Option<String>,
+    pub patched: Vec<String>,
+    pub unaffected: Vec<String>,
+}
+
+impl Advisory {
+    /// Whether `version` is neither patched nor unaffected
+    pub fn affects(&self, version: &str) -> bool {
+        let Some(version) = Version::parse(version) else {
+            return false;
+        };
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|req| requirement_matches(req, &version))
+    }
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMeta,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMeta {
+    id: String,
+    package: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    date: Option<toml::Value>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    cvss: Option<String>,
+    #[serde(default)]
+    informational: Option<String>,
+    #[serde(default)]
+    withdrawn: Option<toml::Value>,
+}
+
+#[derive(Default, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+/// Parse an advisory file: Markdown with a fenced TOML front matter block,
+/// or plain TOML in older checkouts. Withdrawn advisories yield `None`.
+pub fn parse_advisory(contents: &str) -> Result<Option<Advisory>> {
+    let (front_matter, body) = match contents.trim_start().strip_prefix("```toml") {
+        Some(rest) => rest
+            .split_once("\n```")
+            .context("unterminated advisory front matter")?,
+        None => (contents, ""),
+    };
+    let file: AdvisoryFile = toml::from_str(front_matter).context("parsing advisory")?;
+    let meta = file.advisory;
+    if meta.withdrawn.is_some() {
+        return Ok(None);
+    }
+    let title = meta
+        .title
+        .or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string())
+        })
+        .unwrap_or_else(|| meta.id.clone());
+    Ok(Some(Advisory {
+        id: meta.id,
+        package: meta.package,
+        title,
+        date: meta.date.map(|date| match date {
+            toml::Value::String(text) => text,
+            other => other.to_string(),
+        }),
+        aliases: meta.aliases,
+        url: meta.url,
+        cvss: meta.cvss,
+        informational: meta.informational,
+        patched: file.versions.patched,
+        unaffected: file.versions.unaffected,
+    }))
+}
+
+/// State of the advisory database after [`AdvisoryDb::open`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvisoryDbStatus {
+    /// Fetched or updated for this run
+    Fetched,
+    /// Read as found on disk
+    Local,
+    /// Not on disk and not fetched
+    Unavailable,
+}
+
+impl AdvisoryDbStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdvisoryDbStatus::Fetched => "fetched",
+            AdvisoryDbStatus::Local => "local",
+            AdvisoryDbStatus::Unavailable => "unavailable",
+        }
+    }
+}
+
+/// Advisories of the RustSec database, by package name
+#[derive(Debug, Clone)]
+pub struct AdvisoryDb {
+    pub path: PathBuf,
+    pub status: AdvisoryDbStatus,
+    advisories: HashMap<String, Vec<Advisory>>,
+}
+
+impl AdvisoryDb {
+    /// Where the database is read from
+    pub fn default_path() -> Option<PathBuf> {
+        match std::env::var(ADVISORY_DB_ENV) {
+            Ok(path) if !path.trim().is_empty() => config_dir(path.trim()).ok(),
+            _ => cargo_home().map(|home| home.join("advisory-db")),
+        }
+    }
+
+    fn offline() -> bool {
+        std::env::var(ADVISORY_OFFLINE_ENV)
+            .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+    }
+
+    /// Open the database at its default location, fetching it first unless
+    /// offline. Refresh runs reuse the local copy.
+    pub fn open(mode: BootstrapMode) -> Self {
+        let path = Self::default_path().unwrap_or_else(|| PathBuf::from("advisory-db"));
+        let fetch = !Self::offline() && (matches!(mode, BootstrapMode::Fresh) || !path.exists());
+        let fetched = fetch && fetch_advisory_db(&path).is_ok();
+        Self::load(&path, fetched)
+    }
+
+    /// Read the advisories of a checkout at `path`
+    pub fn load(path: &Path, fetched: bool) -> Self {
+        let mut advisories: HashMap<String, Vec<Advisory>> = HashMap::new();
+        let crates_dir = path.join("crates");
+        let status = if !crates_dir.is_dir() {
+            AdvisoryDbStatus::Unavailable
+        } else if fetched {
+            AdvisoryDbStatus::Fetched
+        } else {
+            AdvisoryDbStatus::Local
+        };
+        for entry in WalkDir::new(&crates_dir)
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(Result::ok)
+        {
+            let is_advisory = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext == "md" || ext == "toml");
+            if !is_advisory {
+                continue;
+            }
+            let Ok(contents) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            if let Ok(Some(advisory)) = parse_advisory(&contents) {
+                advisories
+                    .entry(advisory.package.clone())
+                    .or_default()
+                    .push(advisory);
+            }
+        }
+        Self {
+            path: path.to_path_buf(),
+            status,
+            advisories,
+        }
+    }
+
+    /// Advisories affecting a locked package
+    pub fn advisories_for(&self, package: &LockedPackage) -> Vec<&Advisory> {
+        self.advisories
+            .get(&package.name)
+            .map(|list| {
+                list.iter()
+                    .filter(|advisory| advisory.affects(&package.version))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn advisory_count(&self) -> usize {
+        self.advisories.values().map(Vec::len).sum()
+    }
+}
+
+fn fetch_advisory_db(path: &Path) -> Result<()> {
+    let status = if path.join(".git").exists() {
+        Command::new("git")
+            .arg("-C")
+            .arg(path)
+            .args(["pull", "--ff-only", "--quiet"])
+            .status()
+    } else {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", ADVISORY_DB_URL])
+            .arg(path)
+            .status()
+    }
+    .context("running git")?;
+    anyhow::ensure!(status.success(), "git exited with {}", status);
+    Ok(())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    if let Some(home) = std::env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(home));
+    }
+    directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".cargo"))
+}
+
+/// A semantic version; pre-release versions sort before their release
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: String,
+}
+
+impl Version {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().split('+').next()?;
+        let (core, pre) = text.split_once('-').unwrap_or((text, ""));
+        let mut parts = core.split('.').map(|part| part.parse::<u64>());
+        Some(Self {
+            major: parts.next()?.ok()?,
+            minor: parts.next().unwrap_or(Ok(0)).ok()?,
+            patch: parts.next().unwrap_or(Ok(0)).ok()?,
+            pre: pre.to_string(),
+        })
+    }
+
+    fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: String::new(),
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A version in a requirement, which may leave out minor and patch
+struct Partial {
+    version: Version,
+    /// Number of components given, 1 to 3
+    given: usize,
+}
+
+impl Partial {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_end_matches(".*");
+        let core = text.split(['-', '+']).next()?;
+        Some(Self {
+            version: Version::parse(text)?,
+            given: core.split('.').count().min(3),
+        })
+    }
+
+    /// Smallest version past every version this one stands for
+    fn next(&self) -> Version {
+        let v = &self.version;
+        match self.given {
+            1 => Version::new(v.major + 1, 0, 0),
+            2 => Version::new(v.major, v.minor + 1, 0),
+            _ => Version::new(v.major, v.minor, v.patch + 1),
+        }
+    }
+
+    /// Upper bound of a caret requirement
+    fn caret_limit(&self) -> Version {
+        let v = &self.version;
+        if v.major > 0 || self.given == 1 {
+            Version::new(v.major + 1, 0, 0)
+        } else if v.minor > 0 || self.given == 2 {
+            Version::new(0, v.minor + 1, 0)
+        } else {
+            Version::new(0, 0, v.patch + 1)
+        }
+    }
+
+    /// Upper bound of a tilde requirement
+    fn tilde_limit(&self) -> Version {
+        let v = &self.version;
+        if self.given == 1 {
+            Version::new(v.major + 1, 0, 0)
+        } else {
+            Version::new(v.major, v.minor + 1, 0)
+        }
+    }
+}
+
+/// Whether `version` satisfies a Cargo-style requirement such as
+/// `>= 1.2.3, < 2` or `^0.4`
+fn requirement_matches(requirement: &str, version: &Version) -> bool {
+    requirement
+        .split(',')
+        .all(|comparator| comparator_matches(comparator.trim(), version))
+}
+
+fn comparator_matches(comparator: &str, version: &Version) -> bool {
+    if comparator.is_empty() || comparator == "*" {
+        return true;
+    }
+    let (op, rest) = [">=", "<=", ">", "<", "=", "^", "~"]
+        .iter()
+        .find_map(|op| comparator.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("^", comparator));
+    let Some(bound) = Partial::parse(rest) else {
+        return false;
+    };
+    let lower = &bound.version;
+    match op {
+        ">=" => version >= lower,
+        ">" => version >= &bound.next() || (bound.given == 3 && version > lower),
+        "<" => version < lower,
+        "<=" => version < &bound.next(),
+        "=" => version >= lower && version < &bound.next(),
+        "~" => version >= lower && version < &bound.tilde_limit(),
+        _ => version >= lower && version < &bound.caret_limit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2021-0001"
+package = "leaky"
+date = "2021-01-04"
+aliases = ["CVE-2021-1234"]
+url = "https://example.com/leaky/issues/1"
+
+[versions]
+patched = [">= 0.4.2, < 0.5.0", ">= 0.5.1"]
+unaffected = ["< 0.3"]
+```
+
+# Heap overflow in `Leaky::grow`
+
+Details.
+"#;
+
+    #[test]
+    fn parses_advisories_and_matches_affected_versions() {
+        let advisory = parse_advisory(ADVISORY).unwrap().unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2021-0001");
+        assert_eq!(advisory.title, "Heap overflow in `Leaky::grow`");
+        assert_eq!(advisory.date.as_deref(), Some("2021-01-04"));
+        assert_eq!(advisory.aliases, vec!["CVE-2021-1234"]);
+
+        assert!(advisory.affects("0.4.1"));
+        assert!(advisory.affects("0.5.0"));
+        assert!(!advisory.affects("0.4.2"));
+        assert!(!advisory.affects("0.5.1"));
+        assert!(!advisory.affects("0.2.9"));
+        assert!(!advisory.affects("not-a-version"));
+
+        let withdrawn = ADVISORY.replace("[versions]", "withdrawn = \"2021-02-01\"\n\n[versions]");
+        assert!(parse_advisory(&withdrawn).unwrap().is_none());
+    }
+
+    #[test]
+    fn matches_requirement_operators() {
+        let v = |text| Version::parse(text).unwrap();
+        assert!(requirement_matches("^1.2.3", &v("1.9.0")));
+        assert!(!requirement_matches("^1.2.3", &v("2.0.0")));
+        assert!(!requirement_matches("^0.2.3", &v("0.3.0")));
+        assert!(requirement_matches("0.2", &v("0.2.9")));
+        assert!(requirement_matches("~1.2", &v("1.2.7")));
+        assert!(!requirement_matches("~1.2", &v("1.3.0")));
+        assert!(requirement_matches("<= 1.2", &v("1.2.5")));
+        assert!(!requirement_matches("> 1.2", &v("1.2.5")));
+        assert!(requirement_matches("> 1.2.4", &v("1.2.5")));
+        assert!(!requirement_matches(">= 1.0.0", &v("1.0.0-rc.1")));
+        assert!(requirement_matches("=1.0", &v("1.0.3")));
+    }
+
+    #[test]
+    fn reads_lockfile_and_local_database() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.lock"),
+            r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+
+[[package]]
+name = "leaky"
+version = "0.4.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "abc"
+"#,
+        )
+        .unwrap();
+        let packages = read_lockfile(dir.path()).unwrap().unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(!packages[0].is_external());
+
+        let db_root = dir.path().join("advisory-db");
+        fs::create_dir_all(db_root.join("crates/leaky")).unwrap();
+        fs::write(db_root.join("crates/leaky/RUSTSEC-2021-0001.md"), ADVISORY).unwrap();
+        let db = AdvisoryDb::load(&db_root, false);
+        assert_eq!(db.status, AdvisoryDbStatus::Local);
+        assert_eq!(db.advisory_count(), 1);
+        assert_eq!(db.advisories_for(&packages[1]).len(), 1);
+        assert!(db.advisories_for(&packages[0]).is_empty());
+
+        let missing = AdvisoryDb::load(&dir.path().join("nowhere"), false);
+        assert_eq!(missing.status, AdvisoryDbStatus::Unavailable);
+        assert!(read_lockfile(&db_root).unwrap().is_none());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cargo_audit;
 pub mod rust_cargo;
 pub mod toak_tokenizer;
 pub mod universal_code;
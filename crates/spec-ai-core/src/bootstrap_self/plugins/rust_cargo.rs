@@ -1,3 +1,4 @@
+use super::cargo_audit::{self, AdvisoryDb, LockedPackage};
 use crate::bootstrap_self::plugin::{BootstrapPlugin, PluginContext, PluginOutcome};
 use crate::types::{EdgeType, NodeType};
 use anyhow::{Context, Result};
@@ -27,6 +28,7 @@ static BOOTSTRAP_PHASES: &[&str] = &[
     "Survey the repository layout and capture component stats",
     "Index canonical documents and spec for semantic recall",
     "Extract dependency and build surfaces from Cargo manifests",
+    "Audit locked dependencies against the RustSec advisory database",
     "Link every artifact into the session knowledge graph",
 ];
 
@@ -53,6 +55,26 @@ impl BootstrapPlugin for RustCargoPlugin {
         let components = self.collect_components(context.repo_root)?;
         let documents = self.collect_documents(context.repo_root);
         let manifest = metadata.manifest.clone();
+        let locked = cargo_audit::read_lockfile(context.repo_root)?
+            .map(|packages| {
+                packages
+                    .into_iter()
+                    .filter(LockedPackage::is_external)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let advisory_db = (!locked.is_empty()).then(|| AdvisoryDb::open(context.mode));
+        let audited: Vec<(&LockedPackage, Vec<&cargo_audit::Advisory>)> = locked
+            .iter()
+            .map(|package| {
+                let advisories = advisory_db
+                    .as_ref()
+                    .map(|db| db.advisories_for(package))
+                    .unwrap_or_default();
+                (package, advisories)
+            })
+            .collect();
+        let vulnerability_count: usize = audited.iter().map(|(_, found)| found.len()).sum();
 
         let repo_props = json!({
             "name": metadata.name,
@@ -67,6 +89,13 @@ impl BootstrapPlugin for RustCargoPlugin {
                 "dev": manifest.dev_dependencies.len(),
                 "build": manifest.build_dependencies.len()
             },
+            "locked_dependency_count": locked.len(),
+            "vulnerability_count": vulnerability_count,
+            "advisory_db": advisory_db.as_ref().map(|db| json!({
+                "path": db.path.display().to_string(),
+                "status": db.status.as_str(),
+                "advisories": db.advisory_count(),
+            })),
             "component_catalog": components.iter().map(|c| c.name.clone()).collect::<Vec<_>>(),
             "document_catalog": documents.iter().map(|d| d.relative_path.clone()).collect::<Vec<_>>(),
             "phases": outcome.phases.clone(),
@@ -185,10 +214,98 @@ impl BootstrapPlugin for RustCargoPlugin {
             outcome.edges_created += 1;
         }
 
+        let direct: Vec<&str> = manifest
+            .dependencies
+            .iter()
+            .chain(&manifest.dev_dependencies)
+            .chain(&manifest.build_dependencies)
+            .filter_map(|entry| entry.split([' ', '=']).next())
+            .collect();
+        let mut advisory_nodes = std::collections::HashMap::new();
+        for (package, advisories) in &audited {
+            let dependency_props = json!({
+                "name": package.name,
+                "version": package.version,
+                "source": package.source,
+                "checksum": package.checksum,
+                "license": cargo_audit::registry_license(package),
+                "direct": direct.contains(&package.name.as_str()),
+                "vulnerable": !advisories.is_empty(),
+                "bootstrap_source": "rust-cargo-plugin",
+            });
+
+            let dependency_node_id = context.persistence.insert_graph_node(
+                context.session_id,
+                NodeType::Entity,
+                "Dependency",
+                &dependency_props,
+                None,
+            )?;
+            outcome.nodes_created += 1;
+
+            context.persistence.insert_graph_edge(
+                context.session_id,
+                repo_node_id,
+                dependency_node_id,
+                EdgeType::DependsOn,
+                Some("depends_on"),
+                Some(&json!({"bootstrap_source": "rust-cargo-plugin"})),
+                0.95,
+            )?;
+            outcome.edges_created += 1;
+
+            for advisory in advisories {
+                // One node per advisory, shared by every affected version
+                let advisory_node_id = match advisory_nodes.get(&advisory.id) {
+                    Some(id) => *id,
+                    None => {
+                        let advisory_props = json!({
+                            "id": advisory.id,
+                            "package": advisory.package,
+                            "title": advisory.title,
+                            "date": advisory.date,
+                            "aliases": advisory.aliases,
+                            "url": advisory.url,
+                            "cvss": advisory.cvss,
+                            "informational": advisory.informational,
+                            "patched": advisory.patched,
+                            "unaffected": advisory.unaffected,
+                            "bootstrap_source": "rust-cargo-plugin",
+                        });
+                        let id = context.persistence.insert_graph_node(
+                            context.session_id,
+                            NodeType::Fact,
+                            "Vulnerability",
+                            &advisory_props,
+                            None,
+                        )?;
+                        outcome.nodes_created += 1;
+                        advisory_nodes.insert(advisory.id.clone(), id);
+                        id
+                    }
+                };
+
+                context.persistence.insert_graph_edge(
+                    context.session_id,
+                    dependency_node_id,
+                    advisory_node_id,
+                    EdgeType::Custom("VULNERABLE_TO".to_string()),
+                    Some("vulnerable_to"),
+                    Some(&json!({
+                        "version": package.version,
+                        "bootstrap_source": "rust-cargo-plugin",
+                    })),
+                    1.0,
+                )?;
+                outcome.edges_created += 1;
+            }
+        }
+
         outcome.metadata = json!({
             "repository_name": metadata.name,
             "component_count": components.len(),
             "document_count": documents.len(),
+            "vulnerability_count": vulnerability_count,
         });
 
         Ok(outcome)
@@ -0,0 +1,560 @@
+//! Modal dialog primitives: confirm, single-select and text-input forms
+//!
+//! Dialogs are pure state machines driven by [`DialogKey`] events and rendered
+//! as a framed block of lines, so any front-end that can read keys and print
+//! text can host them. While a dialog is open it owns all input: keys never
+//! leak to the surrounding view, Tab/Shift-Tab cycle focus only within the
+//! dialog, and Esc always cancels. [`run_in_terminal`] hosts a dialog on the
+//! REPL's terminal for confirmations, agent selection and rule entry.
+
+use super::text_utils::{display_width, pad_to_width, truncate_with_ellipsis, wrap_text};
+use anyhow::Result;
+use crossterm::cursor::{MoveToColumn, MoveUp};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType};
+use std::io::{IsTerminal, Write};
+
+/// Widest a dialog is drawn, however wide the terminal
+const MAX_DIALOG_WIDTH: usize = 72;
+
+/// Keyboard input understood by dialogs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogKey {
+    Up,
+    Down,
+    Left,
+    Right,
+    Tab,
+    BackTab,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+}
+
+impl DialogKey {
+    /// Map a terminal key press; keys dialogs do not use map to `None`
+    pub fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+        Some(match event.code {
+            KeyCode::Up => DialogKey::Up,
+            KeyCode::Down => DialogKey::Down,
+            KeyCode::Left => DialogKey::Left,
+            KeyCode::Right => DialogKey::Right,
+            KeyCode::Tab if event.modifiers.contains(KeyModifiers::SHIFT) => DialogKey::BackTab,
+            KeyCode::Tab => DialogKey::Tab,
+            KeyCode::BackTab => DialogKey::BackTab,
+            KeyCode::Enter => DialogKey::Enter,
+            KeyCode::Esc => DialogKey::Esc,
+            KeyCode::Backspace => DialogKey::Backspace,
+            // Raw mode swallows the interrupt, so Ctrl-C cancels instead
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => DialogKey::Esc,
+            KeyCode::Char(c) => DialogKey::Char(c),
+            _ => return None,
+        })
+    }
+}
+
+/// Result of feeding a key into a dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogOutcome<T> {
+    /// Dialog is still open
+    Pending,
+    /// User confirmed with a value
+    Submitted(T),
+    /// User dismissed the dialog
+    Cancelled,
+}
+
+/// Common interface for modal dialogs
+pub trait Dialog {
+    type Output;
+
+    fn title(&self) -> &str;
+    fn handle_key(&mut self, key: DialogKey) -> DialogOutcome<Self::Output>;
+    /// Body lines, without the frame
+    fn body(&self, inner_width: usize) -> Vec<String>;
+
+    /// Render the dialog framed in a box `width` cells wide
+    fn render(&self, width: usize) -> Vec<String> {
+        let inner = width.saturating_sub(4).max(1);
+        let title = truncate_with_ellipsis(self.title(), inner.saturating_sub(2), "…");
+        let title_fill = inner.saturating_sub(display_width(&title) + 2);
+        let mut lines = vec![format!("┌─ {} {}─┐", title, "─".repeat(title_fill))];
+        for line in self.body(inner) {
+            lines.push(format!("│ {} │", pad_to_width(&line, inner)));
+        }
+        lines.push(format!("└{}┘", "─".repeat(inner + 2)));
+        lines
+    }
+}
+
+/// Yes/No confirmation with a focused default button
+#[derive(Debug, Clone)]
+pub struct ConfirmDialog {
+    title: String,
+    message: String,
+    yes_focused: bool,
+}
+
+impl ConfirmDialog {
+    pub fn new(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            yes_focused: false,
+        }
+    }
+
+    /// Focus the "Yes" button initially (defaults to "No")
+    pub fn default_yes(mut self) -> Self {
+        self.yes_focused = true;
+        self
+    }
+}
+
+impl Dialog for ConfirmDialog {
+    type Output = bool;
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_key(&mut self, key: DialogKey) -> DialogOutcome<bool> {
+        match key {
+            DialogKey::Left
+            | DialogKey::Right
+            | DialogKey::Tab
+            | DialogKey::BackTab
+            | DialogKey::Up
+            | DialogKey::Down => {
+                self.yes_focused = !self.yes_focused;
+                DialogOutcome::Pending
+            }
+            DialogKey::Char('y') | DialogKey::Char('Y') => DialogOutcome::Submitted(true),
+            DialogKey::Char('n') | DialogKey::Char('N') => DialogOutcome::Submitted(false),
+            DialogKey::Enter => DialogOutcome::Submitted(self.yes_focused),
+            DialogKey::Esc => DialogOutcome::Cancelled,
+            _ => DialogOutcome::Pending,
+        }
+    }
+
+    fn body(&self, inner_width: usize) -> Vec<String> {
+        let mut lines = wrap_text(&self.message, inner_width);
+        lines.push(String::new());
+        let (yes, no) = if self.yes_focused {
+            ("[ Yes ]", "  No  ")
+        } else {
+            ("  Yes  ", "[ No ]")
+        };
+        lines.push(format!("{}   {}", yes, no));
+        lines
+    }
+}
+
+/// Pick exactly one option from a list
+#[derive(Debug, Clone)]
+pub struct SelectDialog {
+    title: String,
+    options: Vec<String>,
+    selected: usize,
+}
+
+impl SelectDialog {
+    pub fn new(title: impl Into<String>, options: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            options,
+            selected: 0,
+        }
+    }
+
+    /// Preselect the option at `index` (clamped to the list)
+    pub fn with_selected(mut self, index: usize) -> Self {
+        self.selected = index.min(self.options.len().saturating_sub(1));
+        self
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.options.get(self.selected).map(String::as_str)
+    }
+}
+
+impl Dialog for SelectDialog {
+    type Output = (usize, String);
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_key(&mut self, key: DialogKey) -> DialogOutcome<(usize, String)> {
+        if self.options.is_empty() {
+            return match key {
+                DialogKey::Esc | DialogKey::Enter => DialogOutcome::Cancelled,
+                _ => DialogOutcome::Pending,
+            };
+        }
+        let count = self.options.len();
+        match key {
+            DialogKey::Up | DialogKey::BackTab | DialogKey::Char('k') => {
+                self.selected = (self.selected + count - 1) % count;
+                DialogOutcome::Pending
+            }
+            DialogKey::Down | DialogKey::Tab | DialogKey::Char('j') => {
+                self.selected = (self.selected + 1) % count;
+                DialogOutcome::Pending
+            }
+            DialogKey::Char(c) if c.is_ascii_digit() => {
+                let idx = c.to_digit(10).unwrap_or(0) as usize;
+                if (1..=count).contains(&idx) {
+                    self.selected = idx - 1;
+                }
+                DialogOutcome::Pending
+            }
+            DialogKey::Enter => {
+                DialogOutcome::Submitted((self.selected, self.options[self.selected].clone()))
+            }
+            DialogKey::Esc => DialogOutcome::Cancelled,
+            _ => DialogOutcome::Pending,
+        }
+    }
+
+    fn body(&self, inner_width: usize) -> Vec<String> {
+        if self.options.is_empty() {
+            return vec!["(no options)".to_string()];
+        }
+        self.options
+            .iter()
+            .enumerate()
+            .map(|(idx, option)| {
+                let marker = if idx == self.selected { "▸" } else { " " };
+                let line = format!("{} {}. {}", marker, idx + 1, option);
+                truncate_with_ellipsis(&line, inner_width, "…")
+            })
+            .collect()
+    }
+}
+
+/// A single labelled text field inside a [`FormDialog`]
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub label: String,
+    pub value: String,
+    pub required: bool,
+}
+
+impl FormField {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: String::new(),
+            required: false,
+        }
+    }
+
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = value.into();
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+}
+
+/// Text input form; focus cycles through the fields and a submit button
+#[derive(Debug, Clone)]
+pub struct FormDialog {
+    title: String,
+    fields: Vec<FormField>,
+    /// Index into fields, or `fields.len()` for the submit button
+    focus: usize,
+    error: Option<String>,
+}
+
+impl FormDialog {
+    pub fn new(title: impl Into<String>, fields: Vec<FormField>) -> Self {
+        Self {
+            title: title.into(),
+            fields,
+            focus: 0,
+            error: None,
+        }
+    }
+
+    fn focus_count(&self) -> usize {
+        self.fields.len() + 1
+    }
+
+    fn on_submit_button(&self) -> bool {
+        self.focus == self.fields.len()
+    }
+
+    fn try_submit(&mut self) -> DialogOutcome<Vec<(String, String)>> {
+        if let Some(missing) = self
+            .fields
+            .iter()
+            .position(|f| f.required && f.value.trim().is_empty())
+        {
+            self.error = Some(format!("'{}' is required", self.fields[missing].label));
+            self.focus = missing;
+            return DialogOutcome::Pending;
+        }
+        DialogOutcome::Submitted(
+            self.fields
+                .iter()
+                .map(|f| (f.label.clone(), f.value.clone()))
+                .collect(),
+        )
+    }
+}
+
+impl Dialog for FormDialog {
+    type Output = Vec<(String, String)>;
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_key(&mut self, key: DialogKey) -> DialogOutcome<Vec<(String, String)>> {
+        match key {
+            DialogKey::Tab | DialogKey::Down => {
+                self.focus = (self.focus + 1) % self.focus_count();
+                DialogOutcome::Pending
+            }
+            DialogKey::BackTab | DialogKey::Up => {
+                self.focus = (self.focus + self.focus_count() - 1) % self.focus_count();
+                DialogOutcome::Pending
+            }
+            DialogKey::Enter => {
+                if self.on_submit_button() {
+                    self.try_submit()
+                } else {
+                    // Enter in a field advances like Tab
+                    self.focus += 1;
+                    DialogOutcome::Pending
+                }
+            }
+            DialogKey::Esc => DialogOutcome::Cancelled,
+            DialogKey::Backspace => {
+                if let Some(field) = self.fields.get_mut(self.focus) {
+                    field.value.pop();
+                }
+                DialogOutcome::Pending
+            }
+            DialogKey::Char(c) => {
+                if let Some(field) = self.fields.get_mut(self.focus) {
+                    field.value.push(c);
+                    self.error = None;
+                }
+                DialogOutcome::Pending
+            }
+            DialogKey::Left | DialogKey::Right => DialogOutcome::Pending,
+        }
+    }
+
+    fn body(&self, inner_width: usize) -> Vec<String> {
+        let label_width = self
+            .fields
+            .iter()
+            .map(|f| display_width(&f.label) + usize::from(f.required))
+            .max()
+            .unwrap_or(0);
+        let mut lines = Vec::with_capacity(self.fields.len() + 3);
+        for (idx, field) in self.fields.iter().enumerate() {
+            let label = if field.required {
+                format!("{}*", field.label)
+            } else {
+                field.label.clone()
+            };
+            let cursor = if idx == self.focus { "_" } else { "" };
+            let marker = if idx == self.focus { "▸" } else { " " };
+            let line = format!(
+                "{} {}: {}{}",
+                marker,
+                pad_to_width(&label, label_width),
+                field.value,
+                cursor
+            );
+            lines.push(truncate_with_ellipsis(&line, inner_width, "…"));
+        }
+        lines.push(String::new());
+        if let Some(error) = &self.error {
+            lines.push(truncate_with_ellipsis(
+                &format!("! {}", error),
+                inner_width,
+                "…",
+            ));
+        }
+        lines.push(if self.on_submit_button() {
+            "[ Submit ]".to_string()
+        } else {
+            "  Submit  ".to_string()
+        });
+        lines
+    }
+}
+
+/// Whether dialogs can be shown: both stdin and stdout are terminals and
+/// plain text mode is off
+pub fn is_interactive() -> bool {
+    super::formatting::is_terminal()
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+}
+
+/// Draw `dialog` below the cursor and feed it key presses until it is
+/// submitted or cancelled, then erase it
+pub fn run_in_terminal<D: Dialog>(dialog: &mut D) -> Result<DialogOutcome<D::Output>> {
+    let width = terminal_size::terminal_size()
+        .map(|(w, _)| w.0 as usize)
+        .unwrap_or(80)
+        .min(MAX_DIALOG_WIDTH);
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    let outcome = drive(dialog, width, &mut stdout);
+    terminal::disable_raw_mode()?;
+    outcome
+}
+
+fn drive<D: Dialog>(
+    dialog: &mut D,
+    width: usize,
+    out: &mut impl Write,
+) -> Result<DialogOutcome<D::Output>> {
+    let mut drawn = 0u16;
+    loop {
+        erase(out, drawn)?;
+        let lines = dialog.render(width);
+        for line in &lines {
+            queue!(out, Print(line), Print("\r\n"))?;
+        }
+        out.flush()?;
+        drawn = lines.len() as u16;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        let Some(key) = DialogKey::from_key_event(&key) else {
+            continue;
+        };
+        match dialog.handle_key(key) {
+            DialogOutcome::Pending => {}
+            outcome => {
+                erase(out, drawn)?;
+                out.flush()?;
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// Clear the `lines` rows drawn above the cursor
+fn erase(out: &mut impl Write, lines: u16) -> Result<()> {
+    if lines > 0 {
+        queue!(out, MoveUp(lines))?;
+    }
+    queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirm_dialog_toggles_and_submits() {
+        let mut dialog = ConfirmDialog::new("Allow tool", "Run `bash`?");
+        assert_eq!(
+            dialog.handle_key(DialogKey::Enter),
+            DialogOutcome::Submitted(false)
+        );
+        dialog.handle_key(DialogKey::Tab);
+        assert_eq!(
+            dialog.handle_key(DialogKey::Enter),
+            DialogOutcome::Submitted(true)
+        );
+        assert_eq!(
+            dialog.handle_key(DialogKey::Char('n')),
+            DialogOutcome::Submitted(false)
+        );
+        assert_eq!(dialog.handle_key(DialogKey::Esc), DialogOutcome::Cancelled);
+    }
+
+    #[test]
+    fn select_dialog_wraps_and_accepts_digits() {
+        let options = vec!["coder".to_string(), "researcher".to_string()];
+        let mut dialog = SelectDialog::new("Agent", options);
+        dialog.handle_key(DialogKey::Up);
+        assert_eq!(dialog.selected(), Some("researcher"));
+        dialog.handle_key(DialogKey::Char('1'));
+        assert_eq!(
+            dialog.handle_key(DialogKey::Enter),
+            DialogOutcome::Submitted((0, "coder".to_string()))
+        );
+    }
+
+    #[test]
+    fn form_dialog_traps_focus_and_validates() {
+        let mut form = FormDialog::new(
+            "New agent",
+            vec![FormField::new("name").required(), FormField::new("model")],
+        );
+        // Focus cycles name -> model -> submit -> name
+        form.handle_key(DialogKey::BackTab);
+        assert_eq!(
+            form.handle_key(DialogKey::Enter),
+            DialogOutcome::Pending,
+            "submit with empty required field stays open"
+        );
+        assert!(form
+            .render(40)
+            .iter()
+            .any(|l| l.contains("'name' is required")));
+
+        for c in "ops".chars() {
+            form.handle_key(DialogKey::Char(c));
+        }
+        form.handle_key(DialogKey::Tab);
+        form.handle_key(DialogKey::Tab);
+        assert_eq!(
+            form.handle_key(DialogKey::Enter),
+            DialogOutcome::Submitted(vec![
+                ("name".to_string(), "ops".to_string()),
+                ("model".to_string(), String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn terminal_keys_map_to_dialog_keys() {
+        let key = |code, modifiers| DialogKey::from_key_event(&KeyEvent::new(code, modifiers));
+        assert_eq!(
+            key(KeyCode::Tab, KeyModifiers::SHIFT),
+            Some(DialogKey::BackTab)
+        );
+        assert_eq!(key(KeyCode::Tab, KeyModifiers::NONE), Some(DialogKey::Tab));
+        assert_eq!(
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(DialogKey::Esc)
+        );
+        assert_eq!(
+            key(KeyCode::Char('y'), KeyModifiers::NONE),
+            Some(DialogKey::Char('y'))
+        );
+        assert_eq!(key(KeyCode::F(1), KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn rendered_frame_has_uniform_width() {
+        let dialog = ConfirmDialog::new("確認", "Delete session 日本語 history?");
+        let lines = dialog.render(30);
+        let widths: Vec<usize> = lines.iter().map(|l| display_width(l)).collect();
+        assert!(widths.iter().all(|w| *w == 30), "widths: {:?}", widths);
+    }
+}
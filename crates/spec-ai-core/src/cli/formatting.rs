@@ -1,6 +1,25 @@
 //! Terminal formatting utilities using termimad for rich markdown rendering
 
-use crate::agent::core::{AgentOutput, MemoryRecallStrategy};
+use super::text_utils::{display_width, truncate_to_width, truncate_with_ellipsis};
+use crate::agent::core::{
+    AgentOutput, CompactionReport, GraphDebugInfo, GraphInfluence, GraphInfluenceKind,
+    MemoryRecallStrategy,
+};
+use crate::config::{GraphConfig, GraphTypeStyle, RecallScope};
+use crate::persistence::forget::ForgetReport;
+use crate::persistence::memories::MemoryEntry;
+use crate::persistence::policy_audit::PolicyAuditEntry;
+use crate::persistence::reminders::Reminder;
+use crate::persistence::search::SessionSearchHit;
+use crate::persistence::tool_usage::ToolUsageStats;
+use crate::persistence::variants::ResponseVariant;
+use crate::persistence::{MemorySummary, ModelLogRecord};
+use crate::policy::PolicyRule;
+use crate::tools::builtin::graph_diff::{DiffEdge, GraphDiff, GraphSnapshot};
+use crate::tools::builtin::graph_store::node_summary;
+use crate::tools::builtin::graph_subgraph::{Subgraph, MAX_SUBGRAPH_NODES};
+use crate::tools::ToolResult;
+use crate::types::{GraphNode, NodeType};
 use serde_json::to_string;
 use std::cell::Cell;
 use termimad::*;
@@ -116,6 +135,12 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
                     "- Strategy: semantic (requested top {}, returned {})\n",
                     requested, returned
                 ));
+                if stats.scope != RecallScope::Session {
+                    section.push_str(&format!(
+                        "- Scope: {} (memories from other sessions are marked)\n",
+                        stats.scope.as_str()
+                    ));
+                }
             }
             MemoryRecallStrategy::RecentContext { limit } => {
                 section.push_str(&format!(
@@ -130,11 +155,17 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
         } else {
             section.push_str("- Matches:\n");
             for (idx, m) in stats.matches.iter().take(3).enumerate() {
+                let origin = m
+                    .session_id
+                    .as_deref()
+                    .map(|session| format!(" | session {}", session))
+                    .unwrap_or_default();
                 section.push_str(&format!(
-                    "  {}. [{} | score {:.2}] {}\n",
+                    "  {}. [{} | score {:.2}{}] {}\n",
                     idx + 1,
                     m.role.as_str(),
                     m.score,
+                    origin,
                     m.preview
                 ));
             }
@@ -152,7 +183,11 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
         let mut section = String::from("## Tool Calls\n\n");
         for (idx, inv) in output.tool_invocations.iter().enumerate() {
             // Status symbol
-            let status_symbol = if inv.success { "✓" } else { "✗" };
+            let status_symbol = match (inv.success, inv.cached) {
+                (true, true) => "✓ cached",
+                (true, false) => "✓",
+                (false, _) => "✗",
+            };
 
             // Tool header
             section.push_str(&format!(
@@ -168,13 +203,7 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
             ) {
                 for (key, value) in args_map.iter() {
                     let formatted_value = match value {
-                        serde_json::Value::String(s) => {
-                            if s.len() > 80 {
-                                format!("{}...", &s[..77])
-                            } else {
-                                s.clone()
-                            }
-                        }
+                        serde_json::Value::String(s) => truncate_with_ellipsis(s, 80, "..."),
                         serde_json::Value::Number(n) => n.to_string(),
                         serde_json::Value::Bool(b) => b.to_string(),
                         _ => to_string(value).unwrap_or_else(|_| "...".to_string()),
@@ -200,8 +229,7 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
                                     // Show first few lines if not too many
                                     if lines.len() <= 5 {
                                         for line in lines.iter().take(5) {
-                                            let trimmed =
-                                                if line.len() > 60 { &line[..60] } else { line };
+                                            let trimmed = truncate_to_width(line, 60);
                                             section.push_str(&format!("    `{}`\n", trimmed));
                                         }
                                     }
@@ -221,8 +249,12 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
                         }
                     } else {
                         // Plain text output
-                        let trimmed = if out.len() > 200 {
-                            format!("{}... ({} chars)", &out[..197], out.len())
+                        let trimmed = if display_width(out) > 200 {
+                            format!(
+                                "{}... ({} chars)",
+                                truncate_to_width(out, 197),
+                                out.chars().count()
+                            )
                         } else {
                             out.clone()
                         };
@@ -268,6 +300,13 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
                 "- Node Count: {}\n- Edge Count: {}\n",
                 graph_debug.node_count, graph_debug.edge_count
             ));
+            section.push_str(&format!(
+                "- Traversal Depth: {}\n- Influencing Nodes: {} in prompt, {} considered\n- Graph Context: ~{} tokens\n",
+                graph_debug.traversal_depth,
+                graph_debug.included().count(),
+                graph_debug.influences.len(),
+                graph_debug.context_tokens
+            ));
 
             if graph_debug.recent_nodes.is_empty() {
                 section.push_str("- Recent Nodes: none recorded yet\n");
@@ -287,6 +326,90 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
         sections.push(section);
     }
 
+    if !output.context_degradation.is_empty() {
+        let mut section = String::from("## Context Overflow Recovery\n");
+        for step in &output.context_degradation {
+            section.push_str(&format!(
+                "- Retry {}: dropped {} {} message(s), {} left\n",
+                step.attempt,
+                step.dropped,
+                step.section.as_str(),
+                step.remaining
+            ));
+        }
+        sections.push(section);
+    }
+
+    if let Some(report) = &output.compression {
+        let mut section = String::from("## Context Compression\n");
+        section.push_str(&format!(
+            "- Compressed {} item(s) with {}: {} → {} tokens ({:.0}%)\n",
+            report.items,
+            report.mode,
+            report.original_tokens,
+            report.compressed_tokens,
+            report.ratio * 100.0
+        ));
+        section.push_str(&format!(
+            "- Key terms kept: {:.0}%{}\n",
+            report.key_term_retention * 100.0,
+            if report.degraded {
+                " (degraded: details may be missing)"
+            } else {
+                ""
+            }
+        ));
+        sections.push(section);
+    }
+
+    if let Some(report) = &output.verification {
+        let mut section = String::from("## Verification\n");
+        if report.passed() {
+            section.push_str(&format!(
+                "- Verified by {}: no unsupported claims\n",
+                report.verifier
+            ));
+        } else {
+            let outcome = if report.revised {
+                "answer revised"
+            } else {
+                "revision failed, draft kept"
+            };
+            section.push_str(&format!(
+                "- Checked by {}: {} issue(s), {}\n",
+                report.verifier,
+                report.issues.len(),
+                outcome
+            ));
+            for issue in &report.issues {
+                section.push_str(&format!("  - {}\n", issue));
+            }
+        }
+        sections.push(section);
+    }
+
+    if !output.request_hashes.is_empty() {
+        let mut section = String::from("## Request Hashes\n");
+        for (idx, hash) in output.request_hashes.iter().enumerate() {
+            section.push_str(&format!("- Step {}: {}\n", idx + 1, hash));
+        }
+        sections.push(section);
+    }
+
+    if show_reasoning {
+        if let Some(timings) = &output.timings {
+            sections.push(format!("{}\n", timings.summary()));
+        }
+        if let Some(route) = &output.route {
+            let target = if route.routed {
+                route.model.clone()
+            } else {
+                format!("main model ({})", route.model)
+            };
+            sections.push(format!("Route: {} → {}\n", route.task, target));
+        }
+    }
+
     // Display reasoning summary if enabled and available
     if show_reasoning {
         // Display reasoning summary if available (more user-friendly)
@@ -301,11 +424,11 @@ pub fn render_run_stats(output: &AgentOutput, show_reasoning: bool) -> Option<St
             if !reasoning.is_empty() {
                 let mut section = String::from("## Reasoning\n\n");
                 // Truncate long reasoning for display
-                let preview = if reasoning.len() > 200 {
+                let preview = if display_width(reasoning) > 200 {
                     format!(
                         "💭 {}... ({} chars total)",
-                        &reasoning[..197],
-                        reasoning.len()
+                        truncate_to_width(reasoning, 197),
+                        reasoning.chars().count()
                     )
                 } else {
                     format!("💭 {}", reasoning)
@@ -346,7 +469,7 @@ pub fn render_help() -> String {
 Manage your AI agent profiles and sessions:
 
 - **`/agents`** or **`/list`** — List all available agent profiles
-- **`/switch <name>`** — Switch to a different agent profile
+- **`/switch <name>`** — Switch to a different agent profile (without a name, pick it from a list)
 - **`/new <name>`** — Create new conversation session
 
 ## Configuration
@@ -356,20 +479,40 @@ Control your SpecAI configuration:
   - Shows model provider, temperature, and other settings
 - **`/config reload`** — Reload configuration from file
   - Useful after editing spec-ai.config.toml
+- **`/policy reload`** — Reload policy rules from the database and the policy file
+- **`/policy list`** — Show the rules tool calls are checked against, first match wins
+- **`/policy add <agent> <action> <resource> <allow|deny>`** — Store a rule after the stored ones (`*` matches anything; without arguments, fill in a form)
+- **`/policy remove <N>`** — Delete stored rule N
+- **`/policy audit [N]`** — Show the last N policy decisions on tool calls (default: 20) and the rule behind each
 
 ## Memory & History
 Access conversation memory:
 
 - **`/memory show [N]`** — Show last N messages (default: 10)
   - Displays color-coded conversation history
+- **`/memory search <query>`** — List this session's recallable memories matching a query, with their IDs
+- **`/memory delete <id>`** — Delete a wrong or stale memory so it is no longer recalled
+- **`/memory compact`** — Condense messages older than the `memory_compaction` window into summaries that recall brings back in their place
 - **`/memory clear`** — Clear conversation history
+- **`/compact`** — Summarize the conversation so far with the fast model; prompts carry the summary instead of the older messages, which stay stored
+- **`/retry [--temperature X] [--provider name[/model]]`** — Ask again for the last answer; every answer is kept as a variant
+- **`/variants`** — List the variants of the last answer
+- **`/variants pick <N>`** — Make variant N the answer in the history
+- **`/forget [--dry-run] [--session] <text|message_id>`** — Erase matching messages, memories, graph nodes and logs in every session (or only this one)
+- **`/profile show`** — Show what is remembered about you across sessions
+- **`/profile edit`** — Edit the profile in `$EDITOR`
+- **`/profile forget <fact>`** — Forget facts by ID or matching text
 
 ## Session Management
 Manage multiple conversation sessions:
 
-- **`/session list`** — List all conversation sessions
+- **`/session list`** — List sessions with their titles and message counts
+- **`/session tag <tag>`** / **`/session untag <tag>`** — Tag or untag the current session
+- **`/session search <query>`** — Search messages, titles and tags across all sessions
 - **`/session load <id>`** — Load a specific session
 - **`/session delete <id>`** — Delete a session
+- **`/reminders`** — List this session's pending reminders, set by the agent with `remind_me`
+- **`/reminders cancel <id>`** — Drop a pending reminder
 
 ## Knowledge Graph
 AI reasoning with graph-based memory:
@@ -377,15 +520,24 @@ AI reasoning with graph-based memory:
 - **`/graph enable`** — Enable knowledge graph features
   - Activates graph memory and automatic entity extraction
 - **`/graph disable`** — Disable knowledge graph features
-- **`/graph status`** — Show current graph configuration
+- **`/graph status`** — Show current graph configuration, the active graph and the session's graphs
+- **`/graph use <name>`** — Switch `/graph show` and the graph tools to a named graph, creating it if needed (`default` is the implicit one)
 - **`/graph show [N]`** — Display last N graph nodes (default: 10)
+- **`/graph why`** — Explain which graph nodes steered the last step
+- **`/graph pin <node_id>`** — Always recall a node's neighborhood, however unrelated the current message (`/graph unpin`, `/graph pins` to list)
+- **`/graph diff <from> [to]`** — Report nodes and edges added, removed or changed between two snapshots, each `session[@time]` (`/graph diff @2026-01-31` compares this session then and now)
+- **`/graph subgraph <query> [--depth N] [--mermaid]`** — Show the nodes closest in meaning to the query and everything within N hops of them (default 1), optionally as a Mermaid flowchart
 - **`/graph clear`** — Clear graph for current session
 
 ## Repository Bootstrap
 Prime the knowledge graph with source facts before the first prompt:
 
 - **`/init`** — Run the bootstrap-self pipeline against the repo (only valid as the first message)
+- **`/init --force`** — Bootstrap a session that already has history (asks for confirmation; `/init --force --yes` skips it)
 - **`/refresh`** — Re-run the bootstrap-self pipeline with caching enabled (safe after `/init`)
+- **`/workspace add <path> [name]`** — Bootstrap another repository into this session as a named workspace
+- **`/workspace list|focus <name>|remove <name>`** — List workspaces, or choose the one file tools and code search resolve paths against
+- **`/ci ingest <file> [cargo_test|pytest|junit]`** — Record the failures of a test log as `TestFailure` graph nodes linked to code components
 
 ## Audio Transcription
 Mock audio input transcription for testing:
@@ -394,14 +546,42 @@ Mock audio input transcription for testing:
   - **Scenarios:** `simple_conversation`, `command_sequence`, `noisy_environment`, `emotional_context`, `multi_speaker`
   - **Duration:** Time in seconds (default: 30)
   - Example: `/listen simple_conversation 60`
+- **`/listen stop|status`** — Stop or check a recording; saved chunks get offsets and proposed speakers (`Name:` prefixes), and their action items are stored as `Task` graph nodes
+- **`/listen file <path>`** — Transcribe a recorded wav, mp3 or m4a file with the configured provider; chunks are stored like a recording's, with the file as their source
+- **`/listen export <srt|vtt|md> <path>`** — Write the session's transcription as SubRip or WebVTT subtitles, or as Markdown meeting notes with the action items
+- **`/handsfree on|off|status`** — Listen for the wake word (`audio.wake_words`), run the request spoken after it and read the answer aloud
 
 ## Spec Runs
 Execute structured `.spec` files with clear goals:
 
 - **`/spec run <file>`** — Load and execute a TOML spec (extension must be `.spec`)
 - **`/spec <file>`** — Shorthand for `/spec run <file>`
+- **`/spec <name>`** — Run a spec from the library managed by `spec-ai specs`
+  - **`/spec`** lists the library; type part of a name and press Tab, then Enter, to complete it
   - Specs must define a `goal` and at least one `tasks` or `deliverables` entry
 
+## Round-table
+Ask several agent profiles at once for high-stakes answers:
+
+- **`/roundtable <question>`** — Each `[roundtable] participants` profile drafts an answer, then the moderator synthesizes one
+- **`/roundtable --drafts <question>`** — Also print every draft before the final answer
+
+## Scrollback
+Page through and search the conversation history:
+
+- **`/scroll up|down`** (or **`/pgup`**, **`/pgdn`**) — Page through earlier output
+- **`/scroll bottom`** — Return to the latest output
+- **`/scroll prev-tool|next-tool`** — Jump between tool invocations
+- **`/find <text>`** — Highlight matches in history; **`/find next|prev|clear`** to navigate
+
+## Debugging
+- **`/tool schema <name>`** — Show a tool's description and JSON parameters
+- **`/tool run <name> [json-args]`** — Call a tool directly, as the model would (the agent's tool policy applies), and show its result
+- **`/tools stats [all]`** — Calls, success rate, average latency and output tokens per tool, for this agent (or every agent)
+- **`/tokens <text|file>`** — Count tokens with the current model's tokenizer (estimated for models without a local tokenizer)
+- **`/model-log show [run_id]`** — Show provider requests and responses of a run (default: the last one); needs `model_log = true` under `[logging]`
+- **`/mesh status`** — Cluster health reported by the mesh leader: instances, sessions, tokens, tool calls, heartbeat latency and sync lag
+
 ## General Commands
 - **`/help`** — Show this help message
 - **`/quit`** or **`/exit`** — Exit the REPL
@@ -497,6 +677,674 @@ pub fn render_config(config_text: &str) -> String {
     skin.text(&formatted, Some(terminal_width)).to_string()
 }
 
+/// Render cross-session search results, one line per hit
+pub fn render_session_search(query: &str, hits: &[SessionSearchHit]) -> String {
+    if hits.is_empty() {
+        return format!("No matches for \"{}\".", query);
+    }
+    let items = hits
+        .iter()
+        .map(|hit| {
+            let title = hit.session_title.as_deref().unwrap_or(&hit.session_id);
+            let location = match (hit.message_id, hit.role.as_deref()) {
+                (Some(id), Some(role)) => format!("{} #{} {}", hit.session_id, id, role),
+                (Some(id), None) => format!("{} #{}", hit.session_id, id),
+                _ => format!("{} (title/tags)", hit.session_id),
+            };
+            format!(
+                "[{}] {} — {} (score {:.2}): {}",
+                hit.created_at.format("%Y-%m-%d"),
+                title,
+                location,
+                hit.score,
+                hit.snippet
+            )
+        })
+        .collect();
+    render_list(&format!("Results for \"{}\"", query), items)
+}
+
+/// Render memories with their source and recall count, one line each
+pub fn render_memories(title: &str, memories: &[MemoryEntry]) -> String {
+    let items = memories
+        .iter()
+        .map(|memory| {
+            let source = match (memory.kind, memory.source_id, memory.role.as_deref()) {
+                (Some(kind), Some(id), Some(role)) => {
+                    format!("{} #{} {}", kind.as_str(), id, role)
+                }
+                (Some(kind), Some(id), None) => format!("{} #{}", kind.as_str(), id),
+                _ => "unlinked".to_string(),
+            };
+            let mut line = format!("#{} [{}]", memory.id, source);
+            if let Some(score) = memory.score {
+                line.push_str(&format!(" (score {:.2})", score));
+            }
+            if memory.access_count > 0 {
+                line.push_str(&format!(" recalled {}x", memory.access_count));
+            }
+            let text = memory.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            line.push_str(&format!(": {}", truncate_with_ellipsis(&text, 120, "...")));
+            line
+        })
+        .collect();
+    render_list(title, items)
+}
+
+/// Render what `/forget` erased, or would erase in a dry run
+pub fn render_forget_report(query: &str, report: &ForgetReport) -> String {
+    let mut items = Vec::new();
+    for message in &report.messages {
+        items.push(format!(
+            "message #{} in {}: {}",
+            message.id,
+            message.session_id.as_deref().unwrap_or("?"),
+            message.preview
+        ));
+    }
+    for node in &report.graph_nodes {
+        items.push(format!(
+            "graph node #{} in {}: {}",
+            node.id,
+            node.session_id.as_deref().unwrap_or("?"),
+            node.preview
+        ));
+    }
+    for fact in &report.profile_facts {
+        items.push(format!("profile fact #{}: {}", fact.id, fact.preview));
+    }
+    let counts = [
+        (report.memory_vectors, "memory embeddings"),
+        (report.graph_edges, "graph edges"),
+        (report.tool_calls, "tool log entries"),
+        (report.model_calls, "model log entries"),
+        (report.transcriptions, "transcriptions"),
+        (report.context_anchors, "conversation summaries"),
+        (report.memory_summaries, "memory summaries"),
+        (report.response_variants, "answer variants"),
+        (report.changelog_entries, "sync changelog entries"),
+    ];
+    for (count, what) in counts.iter().filter(|(count, _)| *count > 0) {
+        items.push(format!("{} {}", count, what));
+    }
+    if report.synced_deletions > 0 && !report.dry_run {
+        items.push(format!(
+            "{} deletion(s) queued for sync peers",
+            report.synced_deletions
+        ));
+    }
+    let title = if report.dry_run {
+        format!(
+            "Would forget for \"{}\" (run without --dry-run to erase)",
+            query
+        )
+    } else {
+        format!("Forgot for \"{}\"", query)
+    };
+    render_list(&title, items)
+}
+
+/// Render the outcome of `/compact`
+pub fn render_compaction(report: &CompactionReport) -> String {
+    let items = vec![
+        format!(
+            "{} message(s) summarized, through message #{}",
+            report.messages, report.through_message_id
+        ),
+        format!(
+            "~{} tokens saved ({} → {})",
+            report.tokens_saved(),
+            report.tokens_before,
+            report.tokens_after
+        ),
+        format!("Summary: {}", report.summary),
+    ];
+    render_list("Compacted conversation", items)
+}
+
+/// Render the summaries `/memory compact` stored
+pub fn render_memory_summaries(summaries: &[MemorySummary]) -> String {
+    let items = summaries
+        .iter()
+        .map(|summary| {
+            let text = summary
+                .summary
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "messages #{}-#{} ({}): {}",
+                summary.first_message_id,
+                summary.last_message_id,
+                summary.message_count,
+                truncate_with_ellipsis(&text, 120, "...")
+            )
+        })
+        .collect();
+    render_list("Compacted memories", items)
+}
+
+/// Render the answer `/retry` got, the newest of `count` variants
+pub fn render_retry(variant: &ResponseVariant, count: usize) -> String {
+    format!(
+        "{}\nVariant {} of {}. /variants pick N keeps another one.",
+        render_markdown(&variant.content),
+        count,
+        count
+    )
+}
+
+/// Render the variants of the last answer, marking the one in the history
+pub fn render_variants(variants: &[ResponseVariant]) -> String {
+    let items = variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let mut line = format!("{}.", index + 1);
+            if variant.canonical {
+                line.push_str(" (current)");
+            }
+            match (&variant.model, variant.temperature) {
+                (Some(model), Some(temperature)) => {
+                    line.push_str(&format!(" [{}, temperature {:.2}]", model, temperature))
+                }
+                (Some(model), None) => line.push_str(&format!(" [{}]", model)),
+                (None, _) => line.push_str(" [original]"),
+            }
+            line.push_str(&format!(
+                " {}",
+                truncate_with_ellipsis(&variant.content.replace('\n', " "), 120, "...")
+            ));
+            line
+        })
+        .collect();
+    render_list("Answer variants", items)
+}
+
+fn describe_influence(influence: &GraphInfluence) -> String {
+    let mut line = format!(
+        "#{} [{}] {}",
+        influence.node_id, influence.node_type, influence.label
+    );
+    if let Some(edge) = &influence.edge {
+        line.push_str(&format!(" — via {}", edge));
+        if let Some(weight) = influence.edge_weight {
+            line.push_str(&format!(" ({:.2})", weight));
+        }
+    }
+    if let Some(seed) = influence.seed_node_id {
+        line.push_str(&format!(" from #{}", seed));
+    }
+    if influence.depth > 0 {
+        line.push_str(&format!(", depth {}", influence.depth));
+    }
+    if let Some(score) = influence.score {
+        line.push_str(&format!(", recall score {:.2}", score));
+    }
+    line
+}
+
+/// Explain which graph nodes shaped a step's prompt and recommendation
+pub fn render_graph_explanation(info: &GraphDebugInfo) -> String {
+    if !info.enabled {
+        return render_markdown("Knowledge graph was disabled for the last step.");
+    }
+
+    let mut text = format!(
+        "# Graph Steering\n\nTraversal depth {}; graph context added ~{} tokens to the prompt.\n",
+        info.traversal_depth, info.context_tokens
+    );
+
+    let groups = [
+        (
+            "In prompt",
+            info.included().collect::<Vec<_>>(),
+            "No graph nodes were added to the prompt.",
+        ),
+        (
+            "Dropped by graph_weight limit",
+            info.influences
+                .iter()
+                .filter(|i| !i.included && i.kind != GraphInfluenceKind::Steering)
+                .collect(),
+            "",
+        ),
+        (
+            "Weighed for next action",
+            info.influences
+                .iter()
+                .filter(|i| i.kind == GraphInfluenceKind::Steering)
+                .collect(),
+            "",
+        ),
+    ];
+    for (title, influences, empty) in groups {
+        if influences.is_empty() {
+            if !empty.is_empty() {
+                text.push_str(&format!("\n{}\n", empty));
+            }
+            continue;
+        }
+        text.push_str(&format!("\n## {}\n", title));
+        for influence in influences {
+            let kind = match influence.kind {
+                GraphInfluenceKind::Steering => String::new(),
+                kind => format!("({}) ", kind.as_str()),
+            };
+            text.push_str(&format!("- {}{}\n", kind, describe_influence(influence)));
+        }
+    }
+
+    match &info.recommendation {
+        Some(recommendation) => {
+            text.push_str(&format!("\n## Recommendation\n{}\n", recommendation))
+        }
+        None if info.graph_steering_enabled => {
+            text.push_str("\nNo next-action recommendation was produced.\n")
+        }
+        None => text.push_str("\nGraph steering is disabled.\n"),
+    }
+
+    render_markdown(&text)
+}
+
+/// Render the nodes and edges that differ between two graph snapshots
+pub fn render_graph_diff(from: &GraphSnapshot, to: &GraphSnapshot, diff: &GraphDiff) -> String {
+    if diff.is_empty() {
+        return format!("No graph differences between {} and {}.", from, to);
+    }
+
+    let describe_node = |node: &GraphNode| {
+        format!(
+            "#{} [{}] {}",
+            node.id,
+            node.node_type.as_str(),
+            node_summary(node)
+        )
+    };
+    let describe_edge = |edge: &DiffEdge| {
+        format!(
+            "{} -{}-> {}",
+            edge.source,
+            edge.edge
+                .predicate
+                .as_deref()
+                .unwrap_or(&edge.edge.edge_type.as_str()),
+            edge.target
+        )
+    };
+
+    let mut text = format!("# Graph Diff\n\n{} → {}\n", from, to);
+    let groups = [
+        (
+            "Added nodes",
+            diff.added_nodes
+                .iter()
+                .map(describe_node)
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "Removed nodes",
+            diff.removed_nodes.iter().map(describe_node).collect(),
+        ),
+        (
+            "Changed nodes",
+            diff.changed_nodes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{}: {} → {}",
+                        describe_node(&change.after),
+                        change.before.properties,
+                        change.after.properties
+                    )
+                })
+                .collect(),
+        ),
+        (
+            "Added edges",
+            diff.added_edges.iter().map(describe_edge).collect(),
+        ),
+        (
+            "Removed edges",
+            diff.removed_edges.iter().map(describe_edge).collect(),
+        ),
+        (
+            "Changed edges",
+            diff.changed_edges
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} (weight {:.2} → {:.2})",
+                        describe_edge(&change.after),
+                        change.before.edge.weight,
+                        change.after.edge.weight
+                    )
+                })
+                .collect(),
+        ),
+    ];
+    for (title, lines) in groups {
+        if lines.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n## {} ({})\n", title, lines.len()));
+        for line in lines {
+            text.push_str(&format!("- {}\n", line));
+        }
+    }
+
+    render_markdown(&text)
+}
+
+/// `[type]` tag of a graph node, named as configured in `[graph]` and, in a
+/// terminal, drawn in the type's color
+pub fn render_node_type(types: &GraphConfig, node_type: &NodeType) -> String {
+    let tag = format!("[{}]", types.node_label(node_type));
+    match types.node_style(node_type).and_then(GraphTypeStyle::rgb) {
+        Some((r, g, b)) if is_terminal() => {
+            format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, tag)
+        }
+        _ => tag,
+    }
+}
+
+/// Render a topic's subgraph as node and edge lists, with a Mermaid
+/// flowchart when `mermaid` is set
+pub fn render_subgraph(
+    query: &str,
+    depth: usize,
+    subgraph: &Subgraph,
+    mermaid: bool,
+    types: &GraphConfig,
+) -> String {
+    if subgraph.nodes.is_empty() {
+        return format!("No graph nodes match '{}'.", query);
+    }
+
+    let mut text = format!(
+        "# Subgraph: {}\n\n{} nodes and {} edges within {} hop(s) of {} matching node(s){}\n",
+        query,
+        subgraph.nodes.len(),
+        subgraph.edges.len(),
+        depth,
+        subgraph.seeds.len(),
+        if subgraph.truncated {
+            format!(", cut off at {} nodes", MAX_SUBGRAPH_NODES)
+        } else {
+            String::new()
+        }
+    );
+    text.push_str("\n## Nodes\n");
+    for node in &subgraph.nodes {
+        let score = subgraph
+            .seed_score(node.id)
+            .map(|score| format!(" (match {:.2})", score))
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "- #{} [{}] {}{}\n",
+            node.id,
+            types.node_label(&node.node_type),
+            node_summary(node),
+            score
+        ));
+    }
+    if !subgraph.edges.is_empty() {
+        text.push_str("\n## Edges\n");
+        for edge in &subgraph.edges {
+            text.push_str(&format!(
+                "- #{} -{}-> #{}\n",
+                edge.source_id,
+                edge.predicate
+                    .clone()
+                    .unwrap_or_else(|| types.edge_label(&edge.edge_type)),
+                edge.target_id
+            ));
+        }
+    }
+    if mermaid {
+        text.push_str(&format!(
+            "\n```mermaid\n{}```\n",
+            subgraph.to_mermaid(types)
+        ));
+    }
+
+    render_markdown(&text)
+}
+
+/// Render the provider calls logged for a run
+pub fn render_model_log(run_id: &str, records: &[ModelLogRecord]) -> String {
+    if records.is_empty() {
+        return format!("No provider calls logged for {}.", run_id);
+    }
+    let mut text = format!("# Model Log: {}\n", run_id);
+    for (index, record) in records.iter().enumerate() {
+        let entry = &record.entry;
+        text.push_str(&format!(
+            "\n## {}. {} — {}{} ({:.0}ms)\n",
+            index + 1,
+            entry.stage,
+            entry.provider,
+            entry
+                .model
+                .as_deref()
+                .map(|model| format!("/{}", model))
+                .unwrap_or_default(),
+            entry.latency_ms
+        ));
+        text.push_str(&format!(
+            "- Prompt: {} bytes, blake3 {}\n- Params: {}\n",
+            entry.prompt_bytes,
+            &entry.prompt_digest[..entry.prompt_digest.len().min(16)],
+            entry.params
+        ));
+        if entry.prompt_tokens.is_some() || entry.completion_tokens.is_some() {
+            text.push_str(&format!(
+                "- Tokens: {} prompt, {} completion\n",
+                entry
+                    .prompt_tokens
+                    .map_or("?".to_string(), |n| n.to_string()),
+                entry
+                    .completion_tokens
+                    .map_or("?".to_string(), |n| n.to_string())
+            ));
+        }
+        if entry.truncated {
+            text.push_str("- Payloads truncated to the model_log_max_bytes cap\n");
+        }
+        if let Some(error) = &entry.error {
+            text.push_str(&format!("- Error: {}\n", error));
+        }
+        if let Some(response) = &entry.response {
+            text.push_str(&format!(
+                "- Response: {}\n",
+                truncate_with_ellipsis(&response.replace('\n', " "), 300, "...")
+            ));
+        }
+    }
+    render_markdown(&text)
+}
+
+/// Render a tool's description and parameter schema
+pub fn render_tool_schema(name: &str, description: &str, parameters: &serde_json::Value) -> String {
+    let schema =
+        serde_json::to_string_pretty(parameters).unwrap_or_else(|_| parameters.to_string());
+    render_markdown(&format!(
+        "# Tool: {}\n\n{}\n\n```json\n{}\n```\n",
+        name, description, schema
+    ))
+}
+
+/// Render the result of `/tool run`, pretty-printing JSON output
+pub fn render_tool_result(name: &str, result: &ToolResult) -> String {
+    let status = if result.success {
+        "succeeded"
+    } else {
+        "failed"
+    };
+    let mut text = format!("# {} {}\n", name, status);
+    if let Some(error) = &result.error {
+        text.push_str(&format!("\n- Error: {}\n", error));
+    }
+    if !result.output.is_empty() {
+        match serde_json::from_str::<serde_json::Value>(&result.output) {
+            Ok(json) => text.push_str(&format!(
+                "\n```json\n{}\n```\n",
+                serde_json::to_string_pretty(&json).unwrap_or_else(|_| result.output.clone())
+            )),
+            Err(_) => text.push_str(&format!("\n```\n{}\n```\n", result.output.trim_end())),
+        }
+    }
+    render_markdown(&text)
+}
+
+/// Render tool usage totals; `hides` marks tools that are no longer
+/// advertised to the current agent
+pub fn render_tool_stats(stats: &[ToolUsageStats], all_agents: bool, hides: bool) -> String {
+    let items = stats
+        .iter()
+        .map(|stats| {
+            let mut line = if all_agents {
+                format!("{} ({})", stats.tool_name, stats.agent)
+            } else {
+                stats.tool_name.clone()
+            };
+            line.push_str(&format!(
+                " — {} call(s), {:.0}% succeeded, {:.0}ms avg, ~{:.0} output tokens/call",
+                stats.calls,
+                stats.success_rate() * 100.0,
+                stats.average_latency_ms(),
+                stats.average_output_tokens()
+            ));
+            if hides && !all_agents && stats.is_hidden(chrono::Utc::now()) {
+                line.push_str(" [not advertised]");
+            }
+            line
+        })
+        .collect();
+    render_list("Tool usage", items)
+}
+
+/// Render policy rules in the order they are checked; the first `stored`
+/// come from the database, the next `from_file` from the policy file and
+/// the rest from the profile
+pub fn render_policy_rules(rules: &[PolicyRule], stored: usize, from_file: usize) -> String {
+    if rules.is_empty() {
+        return "No policy rules; every tool call needs approval.".to_string();
+    }
+    let items = rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let mut line = format!("#{} {}", index + 1, rule);
+            if let Some(alternative) = &rule.alternative {
+                line.push_str(&format!(" (instead: {})", alternative));
+            }
+            if index >= stored + from_file {
+                line.push_str(" [built in]");
+            } else if index >= stored {
+                line.push_str(" [policy file]");
+            }
+            line
+        })
+        .collect();
+    render_list("Policy rules", items)
+}
+
+/// Render policy decisions, newest first
+pub fn render_policy_audit(entries: &[PolicyAuditEntry]) -> String {
+    let items = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {} — {} {} on {} ({}, {}), rule: {}",
+                entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+                entry.effect().to_uppercase(),
+                entry.agent,
+                entry.action,
+                entry.resource,
+                entry.run_id,
+                entry.session_id,
+                entry
+                    .rule
+                    .as_deref()
+                    .unwrap_or("none matched (default deny)")
+            )
+        })
+        .collect();
+    render_list("Policy decisions", items)
+}
+
+pub fn render_reminders(reminders: &[Reminder]) -> String {
+    let items = reminders
+        .iter()
+        .map(|reminder| {
+            format!(
+                "#{} {} — {}",
+                reminder.id,
+                reminder.due_at.format("%Y-%m-%d %H:%M UTC"),
+                reminder.message
+            )
+        })
+        .collect();
+    render_list("Pending reminders", items)
+}
+
+/// Render the leader's view of cluster health
+#[cfg(feature = "api")]
+pub fn render_mesh_stats(stats: &crate::mesh::MeshStatsResponse) -> String {
+    let mut text = format!(
+        "# Mesh: {}/{} instances healthy\n\n- Leader: {}\n- Active sessions: {}\n- Last {} min: {} tokens, {} tool calls\n",
+        stats.healthy,
+        stats.instances.len(),
+        stats.leader_id.as_deref().unwrap_or("none"),
+        stats.active_sessions,
+        stats.window_secs / 60,
+        stats.tokens_in_window,
+        stats.tool_calls_in_window
+    );
+    for instance in &stats.instances {
+        text.push_str(&format!(
+            "\n## {} {}{}\n- Host: {}, last heartbeat {}s ago\n",
+            if instance.healthy { "✓" } else { "✗" },
+            instance.instance_id,
+            if instance.is_leader { " (leader)" } else { "" },
+            instance.hostname,
+            instance.seconds_since_heartbeat
+        ));
+        let Some(latest) = &instance.latest else {
+            text.push_str("- No stats reported yet\n");
+            continue;
+        };
+        text.push_str(&format!(
+            "- Sessions: {} active · Tokens: {} in window · Tool calls: {} in window\n",
+            latest.active_sessions, instance.tokens_in_window, instance.tool_calls_in_window
+        ));
+        text.push_str(&format!(
+            "- Heartbeat latency: {} · Sync lag: {}\n",
+            instance
+                .avg_heartbeat_latency_ms
+                .map_or("n/a".to_string(), |ms| format!("{:.0}ms avg", ms)),
+            instance
+                .max_sync_lag_secs
+                .map_or("never synced".to_string(), |secs| format!("{}s max", secs))
+        ));
+        if let Some(quota) = &latest.quota {
+            let limit = |limit: Option<u64>| limit.map_or("∞".to_string(), |n| n.to_string());
+            text.push_str(&format!(
+                "- Quota: {}/{} runs · {}/{} tokens today · {}/{} messages this hour\n",
+                quota.running,
+                limit(quota.limits.max_concurrent_runs.map(|n| n as u64)),
+                quota.tokens_today,
+                limit(quota.limits.daily_token_budget),
+                quota.messages_last_hour,
+                limit(quota.limits.max_messages_per_hour.map(u64::from))
+            ));
+        }
+    }
+    render_markdown(&text)
+}
+
 /// Render a formatted list with custom bullet styling
 pub fn render_list(title: &str, items: Vec<String>) -> String {
     if !is_terminal() {
@@ -546,4 +1394,131 @@ mod tests {
         assert!(result.contains("default"));
         assert!(result.contains("researcher"));
     }
+
+    #[test]
+    fn test_run_stats_timing_summary() {
+        use crate::agent::core::{CompressionReport, RouteReport, StageTiming, StepTimings};
+
+        set_plain_text_mode(true);
+        let mut output = AgentOutput {
+            response: String::new(),
+            response_message_id: None,
+            token_usage: None,
+            tool_invocations: Vec::new(),
+            finish_reason: None,
+            recall_stats: None,
+            run_id: "run-1".to_string(),
+            next_action: None,
+            reasoning: None,
+            reasoning_summary: None,
+            graph_debug: None,
+            request_hashes: Vec::new(),
+            context_degradation: Vec::new(),
+            verification: None,
+            language: None,
+            timings: Some(StepTimings {
+                total_ms: 1420.0,
+                stages: vec![
+                    StageTiming::new("run_step.recall_memories", 12.4),
+                    StageTiming::new("recall_memories.embed_batch", 10.0),
+                    StageTiming::new("run_step.main_model_call", 1300.0),
+                    StageTiming::new("run_step.tool_execution.grep", 60.0),
+                    StageTiming::new("run_step.tool_execution.file_read", 35.0),
+                    StageTiming::new("run_step.store_user_message", 8.0),
+                ],
+            }),
+            route: Some(RouteReport {
+                task: "code".to_string(),
+                model: "ollama/qwen2.5-coder".to_string(),
+                routed: true,
+            }),
+            compression: None,
+        };
+
+        let stats = render_run_stats(&output, true).unwrap();
+        assert!(stats.contains(
+            "Timing: 1.42s total · recall 12ms · model 1.30s · tool 95ms (2) · persistence 8ms"
+        ));
+        assert!(stats.contains("Route: code → ollama/qwen2.5-coder"));
+        assert!(render_run_stats(&output, false).is_none());
+
+        output.timings = None;
+        output.route = None;
+        assert!(render_run_stats(&output, true).is_none());
+
+        output.compression = Some(CompressionReport {
+            mode: "heuristic".to_string(),
+            items: 2,
+            original_tokens: 2400,
+            compressed_tokens: 1000,
+            ratio: 1000.0 / 2400.0,
+            key_term_retention: 0.7,
+            degraded: true,
+        });
+        let stats = render_run_stats(&output, false).unwrap();
+        assert!(stats.contains("Compressed 2 item(s) with heuristic: 2400 → 1000 tokens (42%)"));
+        assert!(stats.contains("Key terms kept: 70% (degraded"));
+        set_plain_text_mode(false);
+    }
+
+    #[test]
+    fn test_render_graph_explanation() {
+        set_plain_text_mode(true);
+        let influence = |kind, node_id, included| GraphInfluence {
+            kind,
+            node_id,
+            node_type: "Concept".to_string(),
+            label: format!("node {}", node_id),
+            seed_node_id: Some(1),
+            depth: 1,
+            edge: Some("relates_to".to_string()),
+            edge_weight: Some(0.5),
+            score: Some(0.82),
+            included,
+        };
+        let info = GraphDebugInfo {
+            enabled: true,
+            graph_memory_enabled: true,
+            auto_graph_enabled: true,
+            graph_steering_enabled: true,
+            node_count: 4,
+            edge_count: 3,
+            recent_nodes: Vec::new(),
+            traversal_depth: 2,
+            influences: vec![
+                influence(GraphInfluenceKind::SemanticExpansion, 2, true),
+                influence(GraphInfluenceKind::SemanticExpansion, 3, false),
+                influence(GraphInfluenceKind::Steering, 4, false),
+            ],
+            context_tokens: 37,
+            recommendation: Some("Follow up on the pending goal".to_string()),
+        };
+
+        let text = render_graph_explanation(&info);
+        assert!(text.contains("Traversal depth 2; graph context added ~37 tokens"));
+        assert!(text.contains(
+            "- (semantic expansion) #2 [Concept] node 2 — via relates_to (0.50) from #1, depth 1, recall score 0.82"
+        ));
+        assert!(text.contains("## Dropped by graph_weight limit\n- (semantic expansion) #3"));
+        assert!(text.contains("## Weighed for next action\n- #4 [Concept]"));
+        assert!(text.contains("Follow up on the pending goal"));
+        set_plain_text_mode(false);
+    }
+
+    #[test]
+    fn test_render_node_type_uses_configured_label() {
+        set_plain_text_mode(true);
+        let mut types = GraphConfig::default();
+        types.node_types.insert(
+            "incident".to_string(),
+            GraphTypeStyle {
+                label: Some("Incident".to_string()),
+                color: Some("#d62728".to_string()),
+            },
+        );
+        let incident = NodeType::from_str("Incident");
+        assert_eq!(render_node_type(&types, &incident), "[Incident]");
+        assert_eq!(render_node_type(&types, &NodeType::Fact), "[fact]");
+        set_plain_text_mode(false);
+    }
 }
@@ -0,0 +1,217 @@
+//! Hands-free mode for the REPL
+//!
+//! A background transcription stream is watched for the wake phrase; each
+//! request heard after it is handed to the REPL, which runs it like a typed
+//! message and speaks the answer. The detector ignores the microphone while
+//! an answer is being spoken so the agent does not hear itself.
+
+use crate::agent::speech::{speakable, SpeechSynthesizer};
+use crate::agent::wake_word::WakeWordDetector;
+use crate::agent::{TranscriptionConfig, TranscriptionEvent, TranscriptionProvider};
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<TranscriptionEvent>> + Send>>;
+
+/// A running hands-free session
+pub(crate) struct HandsFree {
+    handle: std::thread::JoinHandle<()>,
+    stop_tx: mpsc::UnboundedSender<()>,
+    requests_rx: mpsc::UnboundedReceiver<String>,
+    /// Set while an answer is spoken
+    speaking: Arc<AtomicBool>,
+    speech: Option<Arc<dyn SpeechSynthesizer>>,
+    started_at: SystemTime,
+}
+
+impl HandsFree {
+    /// Start listening with `provider`; `config.duration_secs` should be
+    /// `None` so the stream runs until stopped
+    pub(crate) fn start(
+        provider: Arc<dyn TranscriptionProvider>,
+        config: TranscriptionConfig,
+        detector: WakeWordDetector,
+        speech: Option<Arc<dyn SpeechSynthesizer>>,
+    ) -> Self {
+        let (stop_tx, stop_rx) = mpsc::unbounded_channel::<()>();
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel::<String>();
+        let speaking = Arc::new(AtomicBool::new(false));
+        let pause = Duration::from_secs_f64(config.chunk_duration_secs.max(0.5) * 2.0);
+
+        let thread_speaking = Arc::clone(&speaking);
+        // Same runtime setup as /listen: providers may hold non-Send audio
+        // handles and rely on spawn_local
+        let handle = std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to create runtime");
+            let local = tokio::task::LocalSet::new();
+            local.block_on(&rt, async move {
+                match provider.start_transcription(&config).await {
+                    Ok(events) => {
+                        detect_requests(
+                            events,
+                            detector,
+                            requests_tx,
+                            stop_rx,
+                            thread_speaking,
+                            pause,
+                        )
+                        .await
+                    }
+                    Err(e) => eprintln!("[Hands-free] Failed to start: {}", e),
+                }
+            })
+        });
+
+        Self {
+            handle,
+            stop_tx,
+            requests_rx,
+            speaking,
+            speech,
+            started_at: SystemTime::now(),
+        }
+    }
+
+    pub(crate) fn stop(self) {
+        let _ = self.stop_tx.send(());
+    }
+
+    /// False once the transcription stream has ended
+    pub(crate) fn is_listening(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    pub(crate) fn elapsed_secs(&self) -> u64 {
+        self.started_at.elapsed().map(|d| d.as_secs()).unwrap_or(0)
+    }
+
+    /// The next request heard; `None` once listening has stopped
+    pub(crate) async fn next_request(&mut self) -> Option<String> {
+        self.requests_rx.recv().await
+    }
+
+    /// Speak `response`, if a speech command is available
+    pub(crate) async fn say(&self, response: &str) -> Result<()> {
+        let Some(speech) = &self.speech else {
+            return Ok(());
+        };
+        let text = speakable(response);
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.speaking.store(true, Ordering::SeqCst);
+        let result = speech.speak(&text).await;
+        self.speaking.store(false, Ordering::SeqCst);
+        result
+    }
+}
+
+/// Feed transcription `events` through `detector` until the stream ends or
+/// a stop arrives, sending each complete request. No chunk for `pause`
+/// counts as the speaker pausing.
+async fn detect_requests(
+    mut events: EventStream,
+    mut detector: WakeWordDetector,
+    requests: mpsc::UnboundedSender<String>,
+    mut stop_rx: mpsc::UnboundedReceiver<()>,
+    speaking: Arc<AtomicBool>,
+    pause: Duration,
+) {
+    loop {
+        let next = tokio::select! {
+            _ = stop_rx.recv() => break,
+            next = tokio::time::timeout(pause, events.next()) => next,
+        };
+        let ended = matches!(
+            next,
+            Ok(Some(Ok(TranscriptionEvent::Completed { .. }))) | Ok(None)
+        );
+        let request = match next {
+            Err(_) => detector.silence(),
+            Ok(Some(Ok(TranscriptionEvent::Transcription { text, .. }))) => {
+                if speaking.load(Ordering::SeqCst) {
+                    detector.reset();
+                    continue;
+                }
+                detector.feed(&text)
+            }
+            Ok(Some(Ok(TranscriptionEvent::Error { chunk_id, message }))) => {
+                eprintln!("[Hands-free] Error in chunk {}: {}", chunk_id, message);
+                None
+            }
+            Ok(Some(Ok(TranscriptionEvent::Started { .. }))) => None,
+            Ok(Some(Ok(TranscriptionEvent::Completed { .. }))) | Ok(None) => detector.silence(),
+            Ok(Some(Err(e))) => {
+                eprintln!("[Hands-free] Error: {}", e);
+                break;
+            }
+        };
+        if let Some(request) = request {
+            if requests.send(request).is_err() {
+                break;
+            }
+        }
+        if ended {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::transcription_providers::MockTranscriptionProvider;
+
+    async fn requests_heard(speaking: bool) -> Vec<String> {
+        let provider = MockTranscriptionProvider::with_transcriptions(vec![
+            "the standup moved to ten".to_string(),
+            "Hey spec, what's on my calendar today?".to_string(),
+        ]);
+        let config = TranscriptionConfig {
+            duration_secs: Some(1),
+            chunk_duration_secs: 0.25,
+            ..Default::default()
+        };
+        let events = provider.start_transcription(&config).await.unwrap();
+        let detector = WakeWordDetector::new(&["hey spec".to_string()], 3);
+        let (requests_tx, mut requests_rx) = mpsc::unbounded_channel();
+        let (_stop_tx, stop_rx) = mpsc::unbounded_channel();
+
+        detect_requests(
+            events,
+            detector,
+            requests_tx,
+            stop_rx,
+            Arc::new(AtomicBool::new(speaking)),
+            Duration::from_secs(1),
+        )
+        .await;
+
+        let mut heard = Vec::new();
+        while let Ok(request) = requests_rx.try_recv() {
+            heard.push(request);
+        }
+        heard
+    }
+
+    #[tokio::test]
+    async fn hands_requests_after_the_wake_word_to_the_repl() {
+        assert_eq!(
+            requests_heard(false).await,
+            vec![
+                "what's on my calendar today?".to_string(),
+                "what's on my calendar today?".to_string()
+            ]
+        );
+        // Nothing is picked up while an answer is being spoken
+        assert!(requests_heard(true).await.is_empty());
+    }
+}
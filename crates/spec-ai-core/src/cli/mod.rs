@@ -1,57 +1,245 @@
 //! CLI module for Epic 4 — minimal REPL and command parser
 
+pub mod dialog;
 pub mod formatting;
+mod handsfree;
+pub mod panel;
+pub mod progress;
+pub mod scrollback;
+pub mod text_utils;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::mpsc;
 
-use crate::agent::core::MemoryRecallStrategy;
+use crate::agent::audio_file::{self, TranscribedChunk};
+use crate::agent::builder::{create_agent_for_profile, with_profile_policy};
+use crate::agent::core::{GraphDebugInfo, MemoryRecallStrategy};
+use crate::agent::reminders;
+use crate::agent::roundtable::run_roundtable;
+use crate::agent::speech::create_speech_synthesizer;
+use crate::agent::transcript_export::{self, ExportFormat};
+use crate::agent::transcription_factory::TranscriptionProviderConfig;
+use crate::agent::user_profile;
+use crate::agent::wake_word::WakeWordDetector;
+use crate::agent::{create_provider, AgentBuilder, AgentCore, AgentOutput};
 use crate::agent::{
-    create_transcription_provider, create_transcription_provider_simple, TranscriptionProvider,
+    create_transcription_provider, create_transcription_provider_simple, TranscriptionConfig,
+    TranscriptionProvider,
 };
-use crate::agent::{AgentBuilder, AgentCore, AgentOutput};
 use crate::bootstrap_self::BootstrapSelf;
-use crate::config::{AgentProfile, AgentRegistry, AppConfig};
+use crate::config::{AgentProfile, AgentRegistry, AppConfig, ModelConfig, RecallScope};
+use crate::persistence::forget::ForgetTarget;
+use crate::persistence::memories::MemoryDeletion;
 use crate::persistence::Persistence;
-use crate::policy::PolicyEngine;
-use crate::spec::AgentSpec;
+use crate::policy::{load_policy_file, PolicyEffect, PolicyEngine, PolicyRule};
+use crate::progress::ProgressReporter;
+use crate::spec::{AgentSpec, UnmetRequirements};
+use crate::spec_library::SpecLibrary;
+use crate::spec_source;
+use crate::tokenizer::Tokenizer;
+use crate::tools::builtin::ci_log::{parse_ci_log, record_ci_report, CiFormat};
+use crate::tools::builtin::{GraphSnapshot, KnowledgeGraphStore};
+use crate::tools::{CancellationToken, ToolOutputSink};
+use crate::types::NodeType;
+use scrollback::{LineKind, Scrollback};
 use terminal_size::terminal_size;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Hits shown by `/session search`
+const SESSION_SEARCH_LIMIT: usize = 20;
+
+/// Memories shown by `/memory search`
+const MEMORY_SEARCH_LIMIT: usize = 20;
+
+/// Decisions shown by `/policy audit` without a count
+const POLICY_AUDIT_LIMIT: usize = 20;
+
+/// Matching nodes `/graph subgraph` expands from
+const SUBGRAPH_SEEDS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
     Help,
     Quit,
     ConfigReload,
     ConfigShow,
     PolicyReload,
+    /// Rules the agent's tool calls are checked against
+    PolicyList,
+    /// `/policy add` without arguments: enter the rule in a form
+    PolicyAddForm,
+    /// Store a rule after the stored ones
+    PolicyAdd {
+        agent: String,
+        action: String,
+        resource: String,
+        effect: PolicyEffect,
+    },
+    /// Delete a stored rule by its number in `/policy list`
+    PolicyRemove(usize),
+    /// Latest policy decisions on tool calls
+    PolicyAudit(Option<usize>),
     SwitchAgent(String),
+    /// `/switch` without a name: choose the agent from a list
+    PickAgent,
     ListAgents,
     MemoryShow(Option<usize>),
+    /// Recallable memories of the session relevant to a query
+    MemorySearch(String),
+    /// Delete one memory by its `memory_vectors` id
+    MemoryDelete(i64),
+    /// Condense old messages into recallable summaries now
+    MemoryCompact,
     SessionNew(Option<String>),
     SessionList,
     SessionSwitch(String),
+    SessionTag(String),
+    SessionUntag(String),
+    SessionSearch(String),
+    /// Pending reminders of the session
+    Reminders,
+    /// Drop a pending reminder by its ID
+    RemindersCancel(i64),
     // Graph commands
     GraphEnable,
     GraphDisable,
     GraphStatus,
     GraphShow(Option<usize>),
+    /// Make a named graph the session's active one, creating it if needed
+    GraphUse(String),
+    GraphWhy,
     GraphClear,
+    /// Always recall a node's neighborhood, whatever its similarity
+    GraphPin(i64),
+    GraphUnpin(i64),
+    GraphPins,
+    /// Compare two graph snapshots, each `session[@time]`; `to` defaults
+    /// to the current session's graph
+    GraphDiff {
+        from: String,
+        to: Option<String>,
+    },
+    /// Nodes matching a topic and everything within `depth` hops of them
+    GraphSubgraph {
+        query: String,
+        depth: usize,
+        mermaid: bool,
+    },
+    /// Provider calls of a run (the last one when no run ID is given)
+    ModelLogShow(Option<String>),
+    /// Count the tokens of some text, or of a file's contents
+    Tokens(String),
+    /// Call a tool directly with JSON arguments
+    ToolRun {
+        name: String,
+        args: String,
+    },
+    /// Parameters a tool accepts
+    ToolSchema(String),
+    /// Call counts, success rates, latency and output size of tools, for
+    /// the current agent or every agent
+    ToolsStats {
+        all_agents: bool,
+    },
+    /// Cluster health from the mesh leader
+    MeshStatus,
     // Audio commands
     ListenStart(Option<u64>), // duration in seconds
     ListenStop,
     ListenStatus,
+    /// Transcribe a recorded wav/mp3/m4a file into the session
+    ListenFile(PathBuf),
+    /// Write the session's transcription as `srt`, `vtt` or `md`
+    ListenExport {
+        format: String,
+        path: PathBuf,
+    },
     Listen(Option<String>, Option<u64>), // Deprecated: kept for backward compatibility
+    /// Listen for the wake word and answer spoken requests aloud
+    HandsFreeOn,
+    HandsFreeOff,
+    HandsFreeStatus,
     PasteStart,
     RunSpec(PathBuf),
-    Init(Option<Vec<String>>),    // optional plugins list
+    /// Library specs matching a partial `/spec` name
+    CompleteSpec(String),
+    Roundtable {
+        question: String,
+        show_drafts: bool,
+    },
+    Init(Option<Vec<String>>), // optional plugins list
+    /// `/init --force`: bootstrap a session that already has history once
+    /// confirmed with `--yes`
+    InitForce {
+        plugins: Option<Vec<String>>,
+        confirmed: bool,
+    },
     Refresh(Option<Vec<String>>), // rerun bootstrap with caching
+    // Workspace commands
+    WorkspaceAdd {
+        path: PathBuf,
+        name: Option<String>,
+    },
+    WorkspaceList,
+    WorkspaceFocus(String),
+    WorkspaceRemove(String),
+    /// Record the failures of a test log in the session graph
+    CiIngest {
+        path: PathBuf,
+        format: Option<String>,
+    },
+    /// Facts kept about the user across sessions
+    ProfileShow,
+    ProfileEdit,
+    ProfileForget(String),
+    /// Summarize the conversation into a context anchor
+    Compact,
+    /// Ask again for the last answer, optionally with another temperature
+    /// or `provider[/model]`
+    Retry {
+        temperature: Option<f32>,
+        provider: Option<String>,
+    },
+    /// Answers of the last reply kept by /retry
+    Variants,
+    /// Make variant N (1-based) the answer in the history
+    VariantsPick(usize),
+    /// Erase messages, memories and graph data matching a query
+    Forget {
+        query: String,
+        dry_run: bool,
+        /// Only the current session instead of every session
+        session_only: bool,
+    },
+    // Scrollback commands
+    Scroll(ScrollCommand),
+    Find(FindCommand),
     Message(String),
     Empty,
 }
 
+/// Viewport movement within the conversation scrollback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollCommand {
+    PageUp,
+    PageDown,
+    Bottom,
+    PreviousTool,
+    NextTool,
+}
+
+/// Search within the conversation scrollback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FindCommand {
+    Query(String),
+    Next,
+    Previous,
+    Clear,
+}
+
 pub fn parse_command(input: &str) -> Command {
     let line = input.trim();
     if line.is_empty() {
@@ -71,13 +259,43 @@ pub fn parse_command(input: &str) -> Command {
             },
             "policy" => match parts.next() {
                 Some("reload") => Command::PolicyReload,
+                Some("list") => Command::PolicyList,
+                Some("add") => {
+                    let fields: Vec<&str> = parts.collect();
+                    let effect = match fields.get(3).map(|e| e.to_lowercase()).as_deref() {
+                        Some("allow") => Some(PolicyEffect::Allow),
+                        Some("deny") => Some(PolicyEffect::Deny),
+                        _ => None,
+                    };
+                    match (fields.as_slice(), effect) {
+                        ([], _) => Command::PolicyAddForm,
+                        ([agent, action, resource, _], Some(effect)) => Command::PolicyAdd {
+                            agent: agent.to_string(),
+                            action: action.to_string(),
+                            resource: resource.to_string(),
+                            effect,
+                        },
+                        _ => Command::Help,
+                    }
+                }
+                Some("remove") => match parts
+                    .next()
+                    .and_then(|n| n.trim_start_matches('#').parse::<usize>().ok())
+                {
+                    Some(n) if n > 0 => Command::PolicyRemove(n),
+                    _ => Command::Help,
+                },
+                Some("audit") => {
+                    let n = parts.next().and_then(|s| s.parse::<usize>().ok());
+                    Command::PolicyAudit(n)
+                }
                 _ => Command::Help,
             },
             "agents" | "list" => Command::ListAgents,
             "switch" => {
                 let name = parts.next().unwrap_or("").to_string();
                 if name.is_empty() {
-                    Command::Help
+                    Command::PickAgent
                 } else {
                     Command::SwitchAgent(name)
                 }
@@ -87,6 +305,22 @@ pub fn parse_command(input: &str) -> Command {
                     let n = parts.next().and_then(|s| s.parse::<usize>().ok());
                     Command::MemoryShow(n)
                 }
+                Some("search") => {
+                    let query = parts.collect::<Vec<_>>().join(" ");
+                    if query.is_empty() {
+                        Command::Help
+                    } else {
+                        Command::MemorySearch(query)
+                    }
+                }
+                Some("delete") => match parts
+                    .next()
+                    .and_then(|id| id.trim_start_matches('#').parse::<i64>().ok())
+                {
+                    Some(id) => Command::MemoryDelete(id),
+                    None => Command::Help,
+                },
+                Some("compact") => Command::MemoryCompact,
                 _ => Command::Help,
             },
             "session" => match parts.next() {
@@ -103,6 +337,22 @@ pub fn parse_command(input: &str) -> Command {
                         Command::SessionSwitch(id)
                     }
                 }
+                Some("tag") => match parts.next() {
+                    Some(tag) => Command::SessionTag(tag.to_string()),
+                    None => Command::Help,
+                },
+                Some("untag") => match parts.next() {
+                    Some(tag) => Command::SessionUntag(tag.to_string()),
+                    None => Command::Help,
+                },
+                Some("search") => {
+                    let query = parts.collect::<Vec<_>>().join(" ");
+                    if query.is_empty() {
+                        Command::Help
+                    } else {
+                        Command::SessionSearch(query)
+                    }
+                }
                 _ => Command::Help,
             },
             "graph" => match parts.next() {
@@ -113,13 +363,119 @@ pub fn parse_command(input: &str) -> Command {
                     let n = parts.next().and_then(|s| s.parse::<usize>().ok());
                     Command::GraphShow(n)
                 }
+                Some("use") => match parts.next() {
+                    Some(name) => Command::GraphUse(name.to_string()),
+                    None => Command::Help,
+                },
                 Some("clear") => Command::GraphClear,
+                Some("why") => Command::GraphWhy,
+                Some(action @ ("pin" | "unpin")) => match parts
+                    .next()
+                    .and_then(|id| id.trim_start_matches('#').parse::<i64>().ok())
+                {
+                    Some(id) if action == "pin" => Command::GraphPin(id),
+                    Some(id) => Command::GraphUnpin(id),
+                    None => Command::Help,
+                },
+                Some("pins") => Command::GraphPins,
+                Some("diff") => match (parts.next(), parts.next()) {
+                    (Some(from), to) => Command::GraphDiff {
+                        from: from.to_string(),
+                        to: to.map(str::to_string),
+                    },
+                    (None, _) => Command::Help,
+                },
+                Some("subgraph") => {
+                    let mut query = Vec::new();
+                    let mut depth = 1;
+                    let mut mermaid = false;
+                    while let Some(word) = parts.next() {
+                        match word {
+                            "--mermaid" => mermaid = true,
+                            "--depth" => match parts.next().and_then(|n| n.parse().ok()) {
+                                Some(n) => depth = n,
+                                None => return Command::Help,
+                            },
+                            word => query.push(word),
+                        }
+                    }
+                    if query.is_empty() || !(1..=10).contains(&depth) {
+                        Command::Help
+                    } else {
+                        Command::GraphSubgraph {
+                            query: query.join(" "),
+                            depth,
+                            mermaid,
+                        }
+                    }
+                }
+                _ => Command::Help,
+            },
+            "tokens" => {
+                let arg = rest[cmd.len()..].trim();
+                if arg.is_empty() {
+                    Command::Help
+                } else {
+                    Command::Tokens(arg.to_string())
+                }
+            }
+            "tool" => match parts.next() {
+                Some("schema") => match parts.next() {
+                    Some(name) => Command::ToolSchema(name.to_string()),
+                    None => Command::Help,
+                },
+                Some("run") => match parts.next() {
+                    Some(name) => {
+                        // Arguments are the raw rest of the line, so spacing
+                        // inside JSON strings survives
+                        let after_run = rest[cmd.len()..].trim_start()["run".len()..].trim_start();
+                        Command::ToolRun {
+                            name: name.to_string(),
+                            args: after_run[name.len()..].trim().to_string(),
+                        }
+                    }
+                    None => Command::Help,
+                },
+                _ => Command::Help,
+            },
+            "tools" => match (parts.next(), parts.next()) {
+                (Some("stats"), None) => Command::ToolsStats { all_agents: false },
+                (Some("stats"), Some("all")) => Command::ToolsStats { all_agents: true },
+                _ => Command::Help,
+            },
+            "mesh" => match parts.next() {
+                Some("status") => Command::MeshStatus,
+                _ => Command::Help,
+            },
+            "model-log" => match parts.next() {
+                Some("show") => Command::ModelLogShow(parts.next().map(str::to_string)),
+                _ => Command::Help,
+            },
+            "handsfree" => match parts.next() {
+                Some("on") => Command::HandsFreeOn,
+                Some("off") => Command::HandsFreeOff,
+                Some("status") | None => Command::HandsFreeStatus,
                 _ => Command::Help,
             },
             "listen" => {
                 match parts.next() {
                     Some("stop") => Command::ListenStop,
                     Some("status") => Command::ListenStatus,
+                    Some("file") => {
+                        let path = parts.collect::<Vec<_>>().join(" ");
+                        if path.is_empty() {
+                            Command::Help
+                        } else {
+                            Command::ListenFile(PathBuf::from(path))
+                        }
+                    }
+                    Some("export") => match (parts.next(), parts.next()) {
+                        (Some(format), Some(path)) => Command::ListenExport {
+                            format: format.to_string(),
+                            path: PathBuf::from(path),
+                        },
+                        _ => Command::Help,
+                    },
                     Some("start") => {
                         let duration = parts.next().and_then(|s| s.parse::<u64>().ok());
                         Command::ListenStart(duration)
@@ -132,24 +488,50 @@ pub fn parse_command(input: &str) -> Command {
                     None => Command::ListenStart(None),
                 }
             }
+            "scroll" | "pgup" | "pgdn" => {
+                let direction = match cmd.as_str() {
+                    "pgup" => Some("up"),
+                    "pgdn" => Some("down"),
+                    _ => parts.next(),
+                };
+                match direction {
+                    Some("up") => Command::Scroll(ScrollCommand::PageUp),
+                    Some("down") => Command::Scroll(ScrollCommand::PageDown),
+                    Some("bottom") => Command::Scroll(ScrollCommand::Bottom),
+                    Some("prev-tool") => Command::Scroll(ScrollCommand::PreviousTool),
+                    Some("next-tool") => Command::Scroll(ScrollCommand::NextTool),
+                    _ => Command::Help,
+                }
+            }
+            "find" => {
+                let args: Vec<&str> = parts.collect();
+                match args.as_slice() {
+                    [] => Command::Help,
+                    ["next"] => Command::Find(FindCommand::Next),
+                    ["prev"] => Command::Find(FindCommand::Previous),
+                    ["clear"] => Command::Find(FindCommand::Clear),
+                    terms => Command::Find(FindCommand::Query(terms.join(" "))),
+                }
+            }
             "paste" => Command::PasteStart,
             "init" => {
-                let plugins = if let Some(arg) = parts.next() {
-                    if arg.starts_with("--plugins=") {
-                        Some(
-                            arg.strip_prefix("--plugins=")
-                                .unwrap_or("")
-                                .split(',')
-                                .map(|p| p.trim().to_string())
-                                .collect(),
-                        )
-                    } else {
-                        None
+                let mut plugins = None;
+                let mut force = false;
+                let mut confirmed = false;
+                for arg in parts {
+                    if let Some(list) = arg.strip_prefix("--plugins=") {
+                        plugins = Some(list.split(',').map(|p| p.trim().to_string()).collect());
+                    } else if arg == "--force" {
+                        force = true;
+                    } else if arg == "--yes" || arg == "-y" {
+                        confirmed = true;
                     }
+                }
+                if force {
+                    Command::InitForce { plugins, confirmed }
                 } else {
-                    None
-                };
-                Command::Init(plugins)
+                    Command::Init(plugins)
+                }
             }
             "refresh" => {
                 let plugins = if let Some(arg) = parts.next() {
@@ -169,10 +551,134 @@ pub fn parse_command(input: &str) -> Command {
                 };
                 Command::Refresh(plugins)
             }
+            "workspace" | "ws" => {
+                let args: Vec<&str> = parts.collect();
+                match args.as_slice() {
+                    [] | ["list"] => Command::WorkspaceList,
+                    ["add", path] => Command::WorkspaceAdd {
+                        path: PathBuf::from(path),
+                        name: None,
+                    },
+                    ["add", path, name] => Command::WorkspaceAdd {
+                        path: PathBuf::from(path),
+                        name: Some(name.to_string()),
+                    },
+                    ["focus", name] => Command::WorkspaceFocus(name.to_string()),
+                    ["remove", name] => Command::WorkspaceRemove(name.to_string()),
+                    _ => Command::Help,
+                }
+            }
+            "ci" => {
+                let args: Vec<&str> = parts.collect();
+                match args.as_slice() {
+                    ["ingest", path] => Command::CiIngest {
+                        path: PathBuf::from(path),
+                        format: None,
+                    },
+                    ["ingest", path, format] => Command::CiIngest {
+                        path: PathBuf::from(path),
+                        format: Some(format.to_string()),
+                    },
+                    _ => Command::Help,
+                }
+            }
+            "compact" => Command::Compact,
+            "retry" => {
+                let mut temperature = None;
+                let mut provider = None;
+                let mut valid = true;
+                while let Some(arg) = parts.next() {
+                    match arg {
+                        "--temperature" => match parts.next().and_then(|t| t.parse().ok()) {
+                            Some(value) => temperature = Some(value),
+                            None => valid = false,
+                        },
+                        "--provider" => match parts.next() {
+                            Some(label) => provider = Some(label.to_string()),
+                            None => valid = false,
+                        },
+                        _ => valid = false,
+                    }
+                }
+                if valid {
+                    Command::Retry {
+                        temperature,
+                        provider,
+                    }
+                } else {
+                    Command::Help
+                }
+            }
+            "variants" => {
+                let args: Vec<&str> = parts.collect();
+                match args.as_slice() {
+                    [] | ["list"] => Command::Variants,
+                    ["pick", number] => number
+                        .parse()
+                        .map(Command::VariantsPick)
+                        .unwrap_or(Command::Help),
+                    _ => Command::Help,
+                }
+            }
+            "forget" => {
+                let mut dry_run = false;
+                let mut session_only = false;
+                let mut words = Vec::new();
+                for part in parts {
+                    match part {
+                        "--dry-run" if words.is_empty() => dry_run = true,
+                        "--session" if words.is_empty() => session_only = true,
+                        _ => words.push(part),
+                    }
+                }
+                if words.is_empty() {
+                    Command::Help
+                } else {
+                    Command::Forget {
+                        query: words.join(" "),
+                        dry_run,
+                        session_only,
+                    }
+                }
+            }
+            "reminders" => match parts.next() {
+                None | Some("list") => Command::Reminders,
+                Some("cancel") => match parts
+                    .next()
+                    .and_then(|id| id.trim_start_matches('#').parse::<i64>().ok())
+                {
+                    Some(id) => Command::RemindersCancel(id),
+                    None => Command::Help,
+                },
+                _ => Command::Help,
+            },
+            "profile" => match parts.next() {
+                None | Some("show") => Command::ProfileShow,
+                Some("edit") => Command::ProfileEdit,
+                Some("forget") => {
+                    let fact = parts.collect::<Vec<_>>().join(" ");
+                    if fact.is_empty() {
+                        Command::Help
+                    } else {
+                        Command::ProfileForget(fact)
+                    }
+                }
+                _ => Command::Help,
+            },
             "spec" => {
                 let args: Vec<&str> = parts.collect();
+                // The REPL reads whole lines, so a Tab typed after a partial
+                // name arrives as trailing whitespace and asks for completion
+                let completing = input.trim_end_matches(['\n', '\r']).ends_with('\t');
                 if args.is_empty() {
-                    Command::Help
+                    Command::CompleteSpec(String::new())
+                } else if completing {
+                    let names = if args[0].eq_ignore_ascii_case("run") {
+                        &args[1..]
+                    } else {
+                        &args[..]
+                    };
+                    Command::CompleteSpec(names.first().copied().unwrap_or("").to_string())
                 } else {
                     let (path_parts, _explicit_run) = if args[0].eq_ignore_ascii_case("run") {
                         (args[1..].to_vec(), true)
@@ -187,6 +693,21 @@ pub fn parse_command(input: &str) -> Command {
                     }
                 }
             }
+            "roundtable" => {
+                let mut args: Vec<&str> = parts.collect();
+                let show_drafts = args.first() == Some(&"--drafts");
+                if show_drafts {
+                    args.remove(0);
+                }
+                if args.is_empty() {
+                    Command::Help
+                } else {
+                    Command::Roundtable {
+                        question: args.join(" "),
+                        show_drafts,
+                    }
+                }
+            }
             _ => Command::Help,
         }
     } else {
@@ -194,13 +715,53 @@ pub fn parse_command(input: &str) -> Command {
     }
 }
 
+/// Parse a `/graph diff` snapshot: `session`, `session@time` or `@time` for
+/// the current session, with an RFC 3339 time or a `YYYY-MM-DD` date (end of
+/// that day, UTC)
+fn parse_graph_snapshot(spec: &str, current_session: &str) -> Option<GraphSnapshot> {
+    let Some((session, time)) = spec.rsplit_once('@') else {
+        return Some(GraphSnapshot::current(spec));
+    };
+    let session = if session.is_empty() {
+        current_session
+    } else {
+        session
+    };
+    let as_of = match chrono::DateTime::parse_from_rfc3339(time) {
+        Ok(time) => time.with_timezone(&chrono::Utc),
+        Err(_) => chrono::NaiveDate::parse_from_str(time, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(23, 59, 59)?
+            .and_utc(),
+    };
+    Some(GraphSnapshot::as_of(session, as_of))
+}
+
 /// Transcription task handle for background listening
 struct TranscriptionTask {
     handle: std::thread::JoinHandle<()>,
     stop_tx: mpsc::UnboundedSender<()>,
     started_at: std::time::SystemTime,
     duration_secs: Option<u64>,
-    chunks_rx: mpsc::UnboundedReceiver<String>,
+    /// Length of each audio chunk, for the chunks' offsets
+    chunk_ms: i64,
+    chunks_rx: mpsc::UnboundedReceiver<(usize, String)>,
+}
+
+/// The next request heard in hands-free mode; never resolves while it is off
+async fn next_spoken_request(handsfree: &mut Option<handsfree::HandsFree>) -> Option<String> {
+    match handsfree {
+        Some(handsfree) => handsfree.next_request().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves when the user presses Ctrl-C; never if the handler cannot be
+/// installed
+async fn ctrl_c() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
 }
 
 pub struct CliState {
@@ -215,6 +776,19 @@ pub struct CliState {
     paste_buffer: String,
     init_allowed: bool,
     transcription_task: Option<TranscriptionTask>,
+    handsfree: Option<handsfree::HandsFree>,
+    progress: ProgressReporter,
+    /// Prints tools' output while they run
+    tool_output: Arc<dyn ToolOutputSink>,
+    scrollback: Scrollback,
+    /// Panels of the current run, redrawn as progress events publish them
+    panels: Arc<std::sync::Mutex<panel::PanelPane>>,
+    /// Force deterministic mode on every agent this session builds
+    deterministic: bool,
+    /// Graph snapshot from the most recent step, for `/graph why`
+    last_graph_debug: Option<GraphDebugInfo>,
+    /// Run ID of the most recent step, for `/model-log show`
+    last_run_id: Option<String>,
 }
 
 impl CliState {
@@ -236,8 +810,11 @@ impl CliState {
 
     /// Create a CLI state from a provided config
     pub fn new_with_config(config: AppConfig) -> Result<Self> {
-        let persistence =
-            Persistence::new(&config.database.path).context("initializing persistence")?;
+        let persistence = Persistence::new(&config.database.path)
+            .context("initializing persistence")?
+            .with_privacy(&config.privacy)?
+            .with_events(&config.events);
+        crate::events::spawn_publisher(persistence.clone(), &config.events);
 
         // Build registry and ensure an active agent exists
         let initial_agents = config.agents.clone();
@@ -268,21 +845,21 @@ impl CliState {
 
         // Create the AgentCore from registry + config
         let agent = AgentBuilder::new_with_registry(&registry, &config, None)?;
+        crate::agent::session_lifecycle::spawn_sweeper(&registry, &config);
 
         // Create transcription provider from config
-        let transcription_provider = {
-            use crate::agent::transcription_factory::TranscriptionProviderConfig;
-            let provider_config = TranscriptionProviderConfig {
-                provider: config.audio.provider.clone(),
-                api_key_source: config.audio.api_key_source.clone(),
-                endpoint: config.audio.endpoint.clone(),
-                on_device: config.audio.on_device,
-                settings: serde_json::Value::Null,
-            };
-            create_transcription_provider(&provider_config)
+        let transcription_provider =
+            create_transcription_provider(&TranscriptionProviderConfig::from(&config.audio))
                 .or_else(|_| create_transcription_provider_simple("mock"))
-                .context("Failed to create transcription provider")?
-        };
+                .context("Failed to create transcription provider")?;
+
+        let terminal_width = terminal_size().map(|(w, _)| w.0 as usize).unwrap_or(80);
+        let panels = Arc::new(std::sync::Mutex::new(panel::PanelPane::new()));
+        let progress = ProgressReporter::new(Arc::new(panel::PanelProgressSink::new(
+            panels.clone(),
+            terminal_width,
+            Arc::new(progress::PrintProgressSink::new(terminal_width)),
+        )));
 
         let mut state = Self {
             config,
@@ -296,6 +873,14 @@ impl CliState {
             paste_buffer: String::new(),
             init_allowed: true,
             transcription_task: None,
+            handsfree: None,
+            progress,
+            tool_output: Arc::new(progress::PrintToolOutputSink::new(terminal_width)),
+            scrollback: Scrollback::new(terminal_width),
+            panels,
+            deterministic: false,
+            last_graph_debug: None,
+            last_run_id: None,
         };
 
         state.refresh_init_gate()?;
@@ -304,39 +889,162 @@ impl CliState {
     }
 
     /// Save transcription chunks to database with embeddings
-    async fn save_transcription_chunks(&self, chunks: &[String]) -> usize {
+    /// Store transcribed `(chunk id, text)` pairs from live capture
+    async fn save_transcription_chunks(&self, chunks: &[(usize, String)], chunk_ms: i64) -> usize {
+        let chunks: Vec<TranscribedChunk> = chunks
+            .iter()
+            .map(|(chunk_id, text)| TranscribedChunk {
+                index: *chunk_id,
+                start_ms: *chunk_id as i64 * chunk_ms,
+                end_ms: (*chunk_id as i64 + 1) * chunk_ms,
+                text: text.clone(),
+            })
+            .collect();
+        self.save_transcript_segments(&chunks, None, chunk_ms).await
+    }
+
+    /// Store transcribed chunks after the session's earlier recordings and
+    /// link their embeddings
+    async fn save_transcript_segments(
+        &self,
+        chunks: &[TranscribedChunk],
+        source_file: Option<&str>,
+        chunk_ms: i64,
+    ) -> usize {
         let session_id = self.agent.session_id();
-        let mut chunk_count = 0;
-        for (idx, text) in chunks.iter().enumerate() {
-            let timestamp = chrono::Utc::now();
+        let ids = match audio_file::save_chunks(
+            &self.persistence,
+            session_id,
+            chunks,
+            source_file,
+            chunk_ms,
+        ) {
+            Ok(ids) => ids,
+            Err(e) => {
+                eprintln!("[Transcription] Failed to save chunks: {:#}", e);
+                return 0;
+            }
+        };
 
-            // Insert transcription
-            match self
-                .persistence
-                .insert_transcription(session_id, idx as i64, text, timestamp)
-            {
-                Ok(transcription_id) => {
-                    chunk_count += 1;
-
-                    // Generate and link embedding
-                    if let Some(embedding_id) = self.agent.generate_embedding(text).await {
-                        if let Err(e) = self
-                            .persistence
-                            .update_transcription_embedding(transcription_id, embedding_id)
-                        {
-                            eprintln!(
-                                "[Transcription] Failed to link embedding for chunk {}: {}",
-                                idx, e
-                            );
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("[Transcription] Failed to save chunk {}: {}", idx, e);
+        for (idx, (transcription_id, chunk)) in ids.iter().zip(chunks).enumerate() {
+            // Generate and link embedding
+            if let Some(embedding_id) = self.agent.generate_embedding(&chunk.text).await {
+                if let Err(e) = self
+                    .persistence
+                    .update_transcription_embedding(*transcription_id, embedding_id)
+                {
+                    eprintln!(
+                        "[Transcription] Failed to link embedding for chunk {}: {}",
+                        idx, e
+                    );
                 }
             }
         }
-        chunk_count
+        ids.len()
+    }
+
+    /// Summary of the action items found in the saved transcription
+    async fn extract_action_items_note(&self) -> String {
+        match self.agent.extract_action_items().await {
+            Ok(items) if items.is_empty() => String::new(),
+            Ok(items) => {
+                let tasks = items.into_iter().map(|item| item.task).collect();
+                format!(
+                    "\n{}",
+                    formatting::render_list("Action items (stored as Task nodes)", tasks)
+                )
+            }
+            Err(err) => format!("\nCould not extract action items: {:#}", err),
+        }
+    }
+
+    async fn transcribe_file_command(&self, path: &Path) -> Result<String> {
+        let config = TranscriptionConfig::from(&self.config.audio);
+        let chunks =
+            audio_file::transcribe_file(self.transcription_provider.as_ref(), path, &config)
+                .await?;
+        if chunks.is_empty() {
+            return Ok(format!("No speech found in {}", path.display()));
+        }
+
+        let source = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let source = source.display().to_string();
+        let chunk_ms = (config.chunk_duration_secs * 1000.0) as i64;
+        let saved = self
+            .save_transcript_segments(&chunks, Some(&source), chunk_ms)
+            .await;
+        let action_items = if saved > 0 {
+            self.extract_action_items_note().await
+        } else {
+            String::new()
+        };
+        let length_secs = chunks.last().map(|chunk| chunk.end_ms).unwrap_or_default() / 1000;
+        Ok(format!(
+            "Transcribed {} ({} seconds) with {}, saved {} chunks to database{}",
+            path.display(),
+            length_secs,
+            self.transcription_provider.metadata().name,
+            saved,
+            action_items
+        ))
+    }
+
+    fn export_transcription_command(&self, format: &str, path: &Path) -> Result<String> {
+        let export_format = ExportFormat::parse(format)
+            .with_context(|| format!("unknown format '{}'; use srt, vtt or md", format))?;
+        let session_id = self.agent.session_id();
+        let segments = self.persistence.list_transcript_segments(session_id)?;
+        if segments.is_empty() {
+            return Ok(
+                "No transcription stored for this session. Record one with /listen start or /listen file."
+                    .to_string(),
+            );
+        }
+        let action_items = transcript_export::action_items_from_nodes(
+            &self
+                .persistence
+                .list_graph_nodes(session_id, Some(NodeType::Goal), None)?,
+        );
+        let title = self
+            .persistence
+            .get_session(session_id)?
+            .and_then(|session| session.title)
+            .unwrap_or_else(|| "Meeting notes".to_string());
+        let chunk_ms = (self.config.audio.chunk_duration_secs * 1000.0) as i64;
+        let rendered =
+            transcript_export::render(export_format, &segments, &action_items, chunk_ms, &title);
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        std::fs::write(path, rendered).with_context(|| format!("writing {}", path.display()))?;
+        Ok(format!(
+            "Exported {} transcribed chunk(s) to {} as {}",
+            segments.len(),
+            path.display(),
+            export_format.as_str()
+        ))
+    }
+
+    /// [`Self::handle_line`], abandoning the tool calls in progress once
+    /// `interrupt` resolves; the REPL interrupts on Ctrl-C. The agent then
+    /// gets a fresh cancellation token for the next line.
+    pub async fn handle_line_until<F>(&mut self, line: &str, interrupt: F) -> Result<Option<String>>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let cancel = self.agent.tool_cancellation();
+        let watcher = tokio::spawn(async move {
+            interrupt.await;
+            cancel.cancel();
+        });
+        let out = self.handle_line(line).await;
+        watcher.abort();
+        if self.agent.tool_cancellation().is_cancelled() {
+            self.agent.set_tool_cancellation(CancellationToken::new());
+        }
+        out
     }
 
     /// Handle a single line of input. Returns an optional output string.
@@ -370,8 +1078,13 @@ impl CliState {
             Command::ConfigReload => {
                 let current_session = self.agent.session_id().to_string();
                 self.config = AppConfig::load()?;
-                // rebuild persistence (path may have changed)
-                self.persistence = Persistence::new(&self.config.database.path)?;
+                // rebuild persistence (path may have changed); an in-memory
+                // database is kept, since reopening it would start empty
+                if !(self.config.database.is_in_memory() && self.persistence.is_in_memory()) {
+                    self.persistence = Persistence::new(&self.config.database.path)?
+                        .with_privacy(&self.config.privacy)?
+                        .with_events(&self.config.events);
+                }
                 // rebuild registry with new agents
                 self.registry =
                     AgentRegistry::new(self.config.agents.clone(), self.persistence.clone());
@@ -389,27 +1102,52 @@ impl CliState {
                 Ok(Some("Configuration reloaded.".to_string()))
             }
             Command::PolicyReload => {
-                // Load policies from persistence
-                let policy_engine = PolicyEngine::load_from_persistence(&self.persistence)
-                    .context("Failed to load policies from persistence")?;
-                let rule_count = policy_engine.rule_count();
-
-                // Update the agent's policy engine
-                self.agent
-                    .set_policy_engine(std::sync::Arc::new(policy_engine));
-
+                let rule_count = self.reload_policies()?;
                 Ok(Some(format!(
                     "Policies reloaded. {} rule(s) active.",
                     rule_count
                 )))
             }
-            Command::SwitchAgent(name) => {
-                self.registry.set_active(&name)?;
-                let session = self.agent.session_id().to_string();
-                self.agent =
-                    AgentBuilder::new_with_registry(&self.registry, &self.config, Some(session))?;
-                Ok(Some(format!("Switched active agent to '{}'.", name)))
+            Command::PolicyList => {
+                let stored = PolicyEngine::load_from_persistence(&self.persistence)
+                    .context("Failed to load policies from persistence")?
+                    .rule_count();
+                let from_file = match &self.config.policy_file {
+                    Some(path) => load_policy_file(path)?.rules.len(),
+                    None => 0,
+                };
+                Ok(Some(formatting::render_policy_rules(
+                    &self.agent.policy_engine().policy_set().rules,
+                    stored,
+                    from_file,
+                )))
+            }
+            Command::PolicyAdd {
+                agent,
+                action,
+                resource,
+                effect,
+            } => Ok(Some(self.policy_add_command(PolicyRule {
+                agent,
+                action,
+                resource,
+                effect,
+                alternative: None,
+            })?)),
+            Command::PolicyAddForm => Ok(Some(self.policy_add_form_command()?)),
+            Command::PolicyRemove(number) => Ok(Some(self.policy_remove_command(number)?)),
+            Command::PolicyAudit(n) => {
+                let entries = self
+                    .persistence
+                    .policy_audit(None, n.unwrap_or(POLICY_AUDIT_LIMIT))?;
+                Ok(Some(if entries.is_empty() {
+                    "No policy decisions recorded yet.".to_string()
+                } else {
+                    formatting::render_policy_audit(&entries)
+                }))
             }
+            Command::SwitchAgent(name) => Ok(Some(self.switch_agent_command(&name)?)),
+            Command::PickAgent => Ok(Some(self.pick_agent_command()?)),
             Command::MemoryShow(n) => {
                 let limit = n.unwrap_or(10) as i64;
                 let sid = self.agent.session_id().to_string();
@@ -424,7 +1162,18 @@ impl CliState {
                     Ok(Some(formatting::render_memory(messages)))
                 }
             }
+            Command::MemorySearch(query) => Ok(Some(self.memory_search_command(&query).await?)),
+            Command::MemoryDelete(id) => Ok(Some(self.memory_delete_command(id)?)),
+            Command::MemoryCompact => match self.agent.compact_memories().await {
+                Ok(summaries) if summaries.is_empty() => Ok(Some(
+                    "Nothing to compact: no messages older than the memory_compaction window."
+                        .to_string(),
+                )),
+                Ok(summaries) => Ok(Some(formatting::render_memory_summaries(&summaries))),
+                Err(err) => Ok(Some(format!("Nothing compacted: {:#}", err))),
+            },
             Command::SessionNew(id_opt) => {
+                self.end_session().await;
                 let new_id = id_opt.unwrap_or_else(|| {
                     format!("session-{}", chrono::Utc::now().timestamp_millis())
                 });
@@ -437,16 +1186,63 @@ impl CliState {
                 Ok(Some(format!("Started new session '{}'.", new_id)))
             }
             Command::SessionList => {
-                let sessions = self.persistence.list_sessions()?;
+                let sessions = self.persistence.list_session_records()?;
                 if sessions.is_empty() {
                     return Ok(Some("No sessions yet.".to_string()));
                 }
+                let items = sessions
+                    .iter()
+                    .map(|session| {
+                        let count = if session.is_archived() {
+                            "archived".to_string()
+                        } else {
+                            format!("{} messages", session.message_count)
+                        };
+                        match session.title.as_deref() {
+                            Some(title) => {
+                                format!("{} — {} ({})", session.session_id, title, count)
+                            }
+                            None => format!("{} ({})", session.session_id, count),
+                        }
+                    })
+                    .collect();
                 Ok(Some(formatting::render_list(
                     "Sessions (most recent first)",
-                    sessions,
+                    items,
+                )))
+            }
+            Command::SessionTag(tag) => {
+                let tags = self
+                    .persistence
+                    .add_session_tag(self.agent.session_id(), &tag)?;
+                Ok(Some(format!(
+                    "Session '{}' tags: {}",
+                    self.agent.session_id(),
+                    tags.join(", ")
                 )))
             }
+            Command::SessionUntag(tag) => {
+                let tags = self
+                    .persistence
+                    .remove_session_tag(self.agent.session_id(), &tag)?;
+                Ok(Some(if tags.is_empty() {
+                    format!("Session '{}' has no tags.", self.agent.session_id())
+                } else {
+                    format!(
+                        "Session '{}' tags: {}",
+                        self.agent.session_id(),
+                        tags.join(", ")
+                    )
+                }))
+            }
+            Command::SessionSearch(query) => {
+                let hits = self
+                    .persistence
+                    .search_sessions(&query, SESSION_SEARCH_LIMIT)?;
+                Ok(Some(formatting::render_session_search(&query, &hits)))
+            }
             Command::SessionSwitch(id) => {
+                self.end_session().await;
                 self.agent = AgentBuilder::new_with_registry(
                     &self.registry,
                     &self.config,
@@ -485,6 +1281,16 @@ impl CliState {
             }
             Command::GraphStatus => {
                 let profile = self.agent.profile();
+                let session_id = self.agent.session_id();
+                let active = self.persistence.graph_get_active(session_id)?;
+                let mut graphs = Vec::new();
+                for name in self.persistence.graph_list(session_id)? {
+                    if self.persistence.graph_get_sync_enabled(session_id, &name)? {
+                        graphs.push(format!("{} (synced)", name));
+                    } else {
+                        graphs.push(name);
+                    }
+                }
                 let status = format!(
                     "Knowledge Graph Configuration:\n  \
                     Enabled: {}\n  \
@@ -493,7 +1299,9 @@ impl CliState {
                     Graph Steering: {}\n  \
                     Traversal Depth: {}\n  \
                     Graph Weight: {:.2}\n  \
-                    Tool Threshold: {:.2}",
+                    Tool Threshold: {:.2}\n  \
+                    Active Graph: {}\n  \
+                    Graphs: {}",
                     profile.enable_graph,
                     profile.graph_memory,
                     profile.auto_graph,
@@ -501,40 +1309,263 @@ impl CliState {
                     profile.graph_depth,
                     profile.graph_weight,
                     profile.graph_threshold,
+                    active,
+                    if graphs.is_empty() {
+                        "none".to_string()
+                    } else {
+                        graphs.join(", ")
+                    },
                 );
                 Ok(Some(status))
             }
+            Command::GraphUse(name) => {
+                self.persistence
+                    .graph_set_active(self.agent.session_id(), &name)?;
+                Ok(Some(format!(
+                    "Using graph '{}'; /graph show and the graph tools now work in it.",
+                    name.trim()
+                )))
+            }
             Command::GraphShow(limit) => {
                 let limit_val = limit.unwrap_or(10) as i64;
                 let session_id = self.agent.session_id();
-                let nodes = self
-                    .persistence
-                    .list_graph_nodes(session_id, None, Some(limit_val))?;
+                let graph_name = self.persistence.graph_get_active(session_id)?;
+                let nodes = self.persistence.list_graph_nodes_in_graph(
+                    session_id,
+                    &graph_name,
+                    None,
+                    Some(limit_val),
+                )?;
 
                 if nodes.is_empty() {
-                    Ok(Some("No graph nodes in current session.".to_string()))
+                    Ok(Some(format!(
+                        "No nodes in graph '{}' of the current session.",
+                        graph_name
+                    )))
                 } else {
                     let mut output = format!(
-                        "Graph Nodes (showing {} of {}):\n",
+                        "Graph Nodes in '{}' (showing {} of {}):\n",
+                        graph_name,
                         nodes.len(),
                         nodes.len()
                     );
+                    let pinned: HashSet<i64> = self
+                        .persistence
+                        .list_pinned_graph_nodes(session_id)?
+                        .iter()
+                        .map(|node| node.id)
+                        .collect();
                     for node in &nodes {
                         output.push_str(&format!(
-                            "  [{:?}] {} - {}\n",
-                            node.node_type,
+                            "  #{} {} {} - {}{}\n",
+                            node.id,
+                            formatting::render_node_type(&self.config.graph, &node.node_type),
                             node.label,
-                            node.properties["name"].as_str().unwrap_or("unnamed")
+                            node.properties["name"].as_str().unwrap_or("unnamed"),
+                            if pinned.contains(&node.id) {
+                                " (pinned)"
+                            } else {
+                                ""
+                            }
                         ));
                     }
 
                     // Also show edge count
-                    let edges = self.persistence.list_graph_edges(session_id, None, None)?;
+                    let edges = self.persistence.list_graph_edges_in_graph(
+                        session_id,
+                        &graph_name,
+                        None,
+                        None,
+                    )?;
                     output.push_str(&format!("\nTotal edges: {}", edges.len()));
 
                     Ok(Some(output))
                 }
             }
+            Command::GraphPin(node_id) => {
+                let in_session = self
+                    .persistence
+                    .get_graph_node(node_id)?
+                    .is_some_and(|node| node.session_id == self.agent.session_id());
+                if !in_session || !self.persistence.pin_graph_node(node_id)? {
+                    return Ok(Some(format!(
+                        "No graph node #{} in this session. See /graph show.",
+                        node_id
+                    )));
+                }
+                let mut reply = format!(
+                    "Pinned node #{}; its neighborhood is recalled on every step.",
+                    node_id
+                );
+                if !self.agent.profile().enable_graph {
+                    reply.push_str(" The knowledge graph is disabled; use /graph enable.");
+                }
+                Ok(Some(reply))
+            }
+            Command::GraphUnpin(node_id) => {
+                let reply = if self.persistence.unpin_graph_node(node_id)? {
+                    format!("Unpinned node #{}.", node_id)
+                } else {
+                    format!("Node #{} is not pinned.", node_id)
+                };
+                Ok(Some(reply))
+            }
+            Command::GraphPins => {
+                let pinned = self
+                    .persistence
+                    .list_pinned_graph_nodes(self.agent.session_id())?;
+                if pinned.is_empty() {
+                    return Ok(Some(
+                        "No pinned graph nodes. Pin one with /graph pin <node_id>.".to_string(),
+                    ));
+                }
+                let items = pinned
+                    .iter()
+                    .map(|node| {
+                        format!(
+                            "#{} [{}] {}",
+                            node.id,
+                            self.config.graph.node_label(&node.node_type),
+                            node.label
+                        )
+                    })
+                    .collect();
+                Ok(Some(formatting::render_list("Pinned graph nodes", items)))
+            }
+            Command::GraphDiff { from, to } => {
+                let session_id = self.agent.session_id();
+                let to = to.as_deref().unwrap_or(session_id);
+                let (Some(from), Some(to)) = (
+                    parse_graph_snapshot(&from, session_id),
+                    parse_graph_snapshot(to, session_id),
+                ) else {
+                    return Ok(Some(
+                        "Snapshots are session[@time], with RFC 3339 or YYYY-MM-DD times, e.g. /graph diff @2026-01-31 or /graph diff run-1 run-2".to_string(),
+                    ));
+                };
+                let store = KnowledgeGraphStore::new(Arc::new(self.persistence.clone()));
+                let diff = store.diff(from.clone(), to.clone()).await?;
+                Ok(Some(formatting::render_graph_diff(&from, &to, &diff)))
+            }
+            Command::GraphSubgraph {
+                query,
+                depth,
+                mermaid,
+            } => {
+                let Some(client) = self.agent.embeddings_client().cloned() else {
+                    return Ok(Some(
+                        "Finding nodes by topic needs an embeddings model; set model.embeddings_model."
+                            .to_string(),
+                    ));
+                };
+                let query_embedding = client.embed(&query).await?;
+                let store = KnowledgeGraphStore::new(Arc::new(self.persistence.clone()))
+                    .with_embeddings(Some(client))
+                    .with_types(self.config.graph.clone());
+                let subgraph = store
+                    .subgraph(
+                        self.agent.session_id(),
+                        query_embedding,
+                        SUBGRAPH_SEEDS,
+                        depth,
+                    )
+                    .await?;
+                Ok(Some(formatting::render_subgraph(
+                    &query,
+                    depth,
+                    &subgraph,
+                    mermaid,
+                    store.types(),
+                )))
+            }
+            Command::GraphWhy => match &self.last_graph_debug {
+                Some(info) => Ok(Some(formatting::render_graph_explanation(info))),
+                None => Ok(Some(
+                    "No graph steering recorded yet; send a message first.".to_string(),
+                )),
+            },
+            Command::ModelLogShow(run_id) => {
+                let Some(run_id) = run_id.or_else(|| self.last_run_id.clone()) else {
+                    return Ok(Some(
+                        "No run yet; pass a run ID: /model-log show <run_id>".to_string(),
+                    ));
+                };
+                let records = self.persistence.list_model_log(&run_id)?;
+                if records.is_empty() && !self.config.logging.model_log {
+                    return Ok(Some(format!(
+                        "No provider calls logged for {}. Enable them with model_log = true under [logging].",
+                        run_id
+                    )));
+                }
+                Ok(Some(formatting::render_model_log(&run_id, &records)))
+            }
+            Command::ToolSchema(name) => Ok(Some(match self.agent.tool_registry().get(&name) {
+                Some(tool) => {
+                    formatting::render_tool_schema(&name, tool.description(), &tool.parameters())
+                }
+                None => format!(
+                    "Unknown tool '{}'. Available: {}",
+                    name,
+                    self.agent.tool_registry().list().join(", ")
+                ),
+            })),
+            Command::ToolsStats { all_agents } => {
+                let agent = self.agent.agent_name().unwrap_or("unknown");
+                let stats = self
+                    .persistence
+                    .tool_usage_stats((!all_agents).then_some(agent))?;
+                Ok(Some(if stats.is_empty() {
+                    "No tool calls recorded yet.".to_string()
+                } else {
+                    let hides = !self.agent.profile().advertise_failing_tools;
+                    formatting::render_tool_stats(&stats, all_agents, hides)
+                }))
+            }
+            Command::ToolRun { name, args } => {
+                let args = if args.is_empty() {
+                    serde_json::json!({})
+                } else {
+                    match serde_json::from_str(&args) {
+                        Ok(args) => args,
+                        Err(err) => {
+                            return Ok(Some(format!(
+                                "Tool arguments must be JSON, e.g. /tool run {} {{\"key\": \"value\"}}: {}",
+                                name, err
+                            )))
+                        }
+                    }
+                };
+                match self.agent.run_tool(&name, args).await {
+                    Ok(result) => Ok(Some(formatting::render_tool_result(&name, &result))),
+                    Err(err) => Ok(Some(format!("{:#}", err))),
+                }
+            }
+            Command::Tokens(arg) => {
+                let path = Path::new(&arg);
+                let (label, text) = if path.is_file() {
+                    let text = std::fs::read_to_string(path)
+                        .with_context(|| format!("reading {}", path.display()))?;
+                    (arg.clone(), text)
+                } else {
+                    ("text".to_string(), arg)
+                };
+                let model = self.agent.tokenizer_model();
+                let tokenizer = Tokenizer::for_model(&model);
+                let count = tokenizer.count(&text);
+                let tokens = if tokenizer.is_exact() {
+                    format!("{} tokens ({} for {})", count, tokenizer.name(), model)
+                } else {
+                    format!("~{} tokens (estimated for {})", count, model)
+                };
+                Ok(Some(format!(
+                    "{}: {} · {} characters · {} lines",
+                    label,
+                    tokens,
+                    text.chars().count(),
+                    text.lines().count()
+                )))
+            }
+            Command::MeshStatus => Ok(Some(self.mesh_status().await)),
             Command::GraphClear => {
                 let session_id = self.agent.session_id();
 
@@ -552,7 +1583,7 @@ impl CliState {
                 )))
             }
             Command::ListenStart(duration) => {
-                use crate::agent::{TranscriptionConfig, TranscriptionEvent};
+                use crate::agent::TranscriptionEvent;
                 use futures::StreamExt;
 
                 // Check if already running
@@ -562,25 +1593,23 @@ impl CliState {
                             .to_string(),
                     ));
                 }
+                if self.handsfree.is_some() {
+                    return Ok(Some(
+                        "Hands-free mode is using the microphone. Use /handsfree off first."
+                            .to_string(),
+                    ));
+                }
 
                 // Build transcription config from app config
-                let config = TranscriptionConfig {
-                    duration_secs: duration.or(Some(self.config.audio.default_duration_secs)),
-                    chunk_duration_secs: self.config.audio.chunk_duration_secs,
-                    model: self
-                        .config
-                        .audio
-                        .model
-                        .clone()
-                        .unwrap_or_else(|| "whisper-1".to_string()),
-                    out_file: self.config.audio.out_file.clone(),
-                    language: self.config.audio.language.clone(),
-                    endpoint: self.config.audio.endpoint.clone(),
-                };
+                let mut config = TranscriptionConfig::from(&self.config.audio);
+                if duration.is_some() {
+                    config.duration_secs = duration;
+                }
 
                 // Create stop channel and chunks channel
                 let (stop_tx, mut stop_rx) = mpsc::unbounded_channel::<()>();
-                let (chunks_tx, chunks_rx) = mpsc::unbounded_channel::<String>();
+                let (chunks_tx, chunks_rx) = mpsc::unbounded_channel::<(usize, String)>();
+                let chunk_ms = (config.chunk_duration_secs * 1000.0) as i64;
 
                 // Clone provider for background task
                 let provider = Arc::clone(&self.transcription_provider);
@@ -621,7 +1650,7 @@ impl CliState {
                                                 }
                                                 Some(Ok(TranscriptionEvent::Transcription { chunk_id, text, .. })) => {
                                                     println!("[Transcription] Chunk {}: {}", chunk_id, text);
-                                                    let _ = chunks_tx.send(text);
+                                                    let _ = chunks_tx.send((chunk_id, text));
                                                 }
                                                 Some(Ok(TranscriptionEvent::Error { chunk_id, message })) => {
                                                     eprintln!("[Transcription] Error in chunk {}: {}", chunk_id, message);
@@ -655,6 +1684,7 @@ impl CliState {
                     stop_tx,
                     started_at,
                     duration_secs: duration.or(Some(self.config.audio.default_duration_secs)),
+                    chunk_ms,
                     chunks_rx,
                 });
 
@@ -671,18 +1701,23 @@ impl CliState {
 
                     // Collect any remaining chunks
                     let mut chunks = Vec::new();
-                    while let Ok(text) = task.chunks_rx.try_recv() {
-                        chunks.push(text);
+                    while let Ok(chunk) = task.chunks_rx.try_recv() {
+                        chunks.push(chunk);
                     }
 
                     // Save to database
-                    let chunk_count = self.save_transcription_chunks(&chunks).await;
+                    let chunk_count = self.save_transcription_chunks(&chunks, task.chunk_ms).await;
+                    let action_items = if chunk_count > 0 {
+                        self.extract_action_items_note().await
+                    } else {
+                        String::new()
+                    };
 
                     let elapsed = task.started_at.elapsed().map(|d| d.as_secs()).unwrap_or(0);
 
                     Ok(Some(format!(
-                        "Stopped transcription (ran for {} seconds, saved {} chunks to database)",
-                        elapsed, chunk_count
+                        "Stopped transcription (ran for {} seconds, saved {} chunks to database){}",
+                        elapsed, chunk_count, action_items
                     )))
                 } else {
                     Ok(Some("No transcription is currently running.".to_string()))
@@ -695,18 +1730,24 @@ impl CliState {
                         // Collect chunks
                         let mut chunks = Vec::new();
                         let mut chunks_rx = task.chunks_rx;
-                        while let Ok(text) = chunks_rx.try_recv() {
-                            chunks.push(text);
+                        while let Ok(chunk) = chunks_rx.try_recv() {
+                            chunks.push(chunk);
                         }
 
                         // Save to database
-                        let chunk_count = self.save_transcription_chunks(&chunks).await;
+                        let chunk_count =
+                            self.save_transcription_chunks(&chunks, task.chunk_ms).await;
+                        let action_items = if chunk_count > 0 {
+                            self.extract_action_items_note().await
+                        } else {
+                            String::new()
+                        };
 
                         let elapsed = task.started_at.elapsed().map(|d| d.as_secs()).unwrap_or(0);
 
                         return Ok(Some(format!(
-                            "Transcription completed (ran for {} seconds, saved {} chunks to database)",
-                            elapsed, chunk_count
+                            "Transcription completed (ran for {} seconds, saved {} chunks to database){}",
+                            elapsed, chunk_count, action_items
                         )));
                     } else {
                         // Put it back since it's still running
@@ -732,6 +1773,24 @@ impl CliState {
                     Ok(Some("No transcription is currently running.\nUse /listen start [duration] to start.".to_string()))
                 }
             }
+            Command::ListenFile(path) => match self.transcribe_file_command(&path).await {
+                Ok(output) => Ok(Some(output)),
+                Err(err) => Ok(Some(format!(
+                    "Could not transcribe {}: {:#}",
+                    path.display(),
+                    err
+                ))),
+            },
+            Command::ListenExport { format, path } => {
+                match self.export_transcription_command(&format, &path) {
+                    Ok(output) => Ok(Some(output)),
+                    Err(err) => Ok(Some(format!(
+                        "Could not export the transcription to {}: {:#}",
+                        path.display(),
+                        err
+                    ))),
+                }
+            }
             Command::Listen(_scenario, duration) => {
                 // Redirect to new command
                 Ok(Some(format!(
@@ -746,33 +1805,44 @@ impl CliState {
                         .to_string(),
                 ))
             }
-            Command::RunSpec(path) => {
-                let output = self.run_spec_command(&path).await?;
+            Command::RunSpec(path) => match self.run_spec_command(&path).await {
+                Ok(output) => Ok(Some(output)),
+                // Unmet requirements are reported without leaving the REPL
+                Err(err) if err.downcast_ref::<UnmetRequirements>().is_some() => {
+                    Ok(Some(format!("{:#}", err)))
+                }
+                Err(err) => Err(err),
+            },
+            Command::CompleteSpec(prefix) => Ok(Some(self.complete_spec_command(&prefix))),
+            Command::Roundtable {
+                question,
+                show_drafts,
+            } => {
+                let output = self.run_roundtable_command(&question, show_drafts).await?;
                 Ok(Some(output))
             }
             Command::Init(plugins) => {
                 if !self.init_allowed {
                     return Ok(Some(
-                        "The /init command must be the first action in a session. Start a new session to run it again."
+                        "The /init command must be the first action in a session. Start a new session, or use /init --force to bootstrap this one."
                             .to_string(),
                     ));
                 }
-                let bootstrapper =
-                    BootstrapSelf::from_environment(&self.persistence, self.agent.session_id())?;
-                let outcome = bootstrapper.run_with_plugins(plugins.clone())?;
-                self.init_allowed = false;
-                Ok(Some(format!(
-                    "Knowledge graph bootstrap complete for '{}': {} nodes and {} edges captured ({} components, {} documents).",
-                    outcome.repository_name,
-                    outcome.nodes_created,
-                    outcome.edges_created,
-                    outcome.component_count,
-                    outcome.document_count
-                )))
+                Ok(Some(self.run_init_command(plugins)?))
+            }
+            Command::InitForce { plugins, confirmed } => {
+                if !self.init_allowed && !confirmed && !self.confirm_force_init()? {
+                    return Ok(Some(
+                        "This session already has history; bootstrapping adds the repository's facts to its knowledge graph alongside it. Run /init --force --yes to continue."
+                            .to_string(),
+                    ));
+                }
+                Ok(Some(self.run_init_command(plugins)?))
             }
             Command::Refresh(plugins) => {
                 let bootstrapper =
-                    BootstrapSelf::from_environment(&self.persistence, self.agent.session_id())?;
+                    BootstrapSelf::from_environment(&self.persistence, self.agent.session_id())?
+                        .with_progress(self.progress.clone());
                 let outcome = bootstrapper.refresh_with_plugins(plugins.clone())?;
                 self.init_allowed = false;
                 Ok(Some(format!(
@@ -784,27 +1854,310 @@ impl CliState {
                     outcome.document_count
                 )))
             }
-            Command::Message(text) => {
-                self.init_allowed = false;
-                let output = self.agent.run_step(&text).await?;
-                self.update_reasoning_messages(&output);
-                let mut formatted =
-                    formatting::render_agent_response("assistant", &output.response);
-                let show_reasoning = self.agent.profile().show_reasoning;
-                if let Some(stats) = formatting::render_run_stats(&output, show_reasoning) {
-                    formatted.push('\n');
-                    formatted.push_str(&stats);
+            Command::WorkspaceAdd { path, name } => match self.add_workspace_command(&path, name) {
+                Ok(output) => Ok(Some(output)),
+                Err(err) => Ok(Some(format!("Could not add workspace: {:#}", err))),
+            },
+            Command::WorkspaceList => Ok(Some(self.list_workspaces_command()?)),
+            Command::WorkspaceFocus(name) => {
+                let session_id = self.agent.session_id().to_string();
+                if self.persistence.workspace_focus(&session_id, &name)? {
+                    Ok(Some(format!(
+                        "Focused workspace '{}'. File tools and code search now resolve paths against it.",
+                        name
+                    )))
+                } else {
+                    Ok(Some(format!(
+                        "No workspace named '{}'. See /workspace list.",
+                        name
+                    )))
                 }
+            }
+            Command::WorkspaceRemove(name) => {
+                let session_id = self.agent.session_id().to_string();
+                if self.persistence.workspace_remove(&session_id, &name)? {
+                    Ok(Some(format!(
+                        "Removed workspace '{}'. Its graph nodes are kept.",
+                        name
+                    )))
+                } else {
+                    Ok(Some(format!("No workspace named '{}'.", name)))
+                }
+            }
+            Command::CiIngest { path, format } => {
+                match self.ingest_ci_command(&path, format.as_deref()) {
+                    Ok(output) => Ok(Some(output)),
+                    Err(err) => Ok(Some(format!(
+                        "Could not ingest {}: {:#}",
+                        path.display(),
+                        err
+                    ))),
+                }
+            }
+            Command::ProfileShow => Ok(Some(self.show_profile_command()?)),
+            Command::ProfileEdit => match self.edit_profile_command() {
+                Ok(output) => Ok(Some(output)),
+                Err(err) => Ok(Some(format!("Profile not changed: {:#}", err))),
+            },
+            Command::ProfileForget(fact) => {
+                let forgotten = self.persistence.user_profile_forget(&fact)?;
+                Ok(Some(if forgotten.is_empty() {
+                    format!("No profile fact matches '{}'. See /profile show.", fact)
+                } else {
+                    let items = forgotten.into_iter().map(|f| f.fact).collect();
+                    formatting::render_list("Forgotten", items)
+                }))
+            }
+            Command::Compact => match self.agent.compact().await {
+                Ok(report) => Ok(Some(formatting::render_compaction(&report))),
+                Err(err) => Ok(Some(format!("Nothing compacted: {:#}", err))),
+            },
+            Command::Retry {
+                temperature,
+                provider,
+            } => {
+                let provider = match provider {
+                    Some(label) => {
+                        let (provider, model_name) = match label.split_once('/') {
+                            Some((provider, model)) => (provider.to_string(), Some(model.to_string())),
+                            None => (label.clone(), None),
+                        };
+                        let model_config = ModelConfig {
+                            provider,
+                            model_name,
+                            embeddings_model: None,
+                            api_key_source: None,
+                            temperature: temperature.unwrap_or(self.config.model.temperature),
+                        };
+                        match create_provider(&model_config) {
+                            Ok(provider) => Some(provider),
+                            Err(err) => {
+                                return Ok(Some(format!(
+                                    "Cannot retry with '{}': {:#}",
+                                    label, err
+                                )))
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                match self.agent.regenerate(temperature, provider).await {
+                    Ok(variant) => {
+                        let count = self.agent.response_variants()?.len();
+                        Ok(Some(formatting::render_retry(&variant, count)))
+                    }
+                    Err(err) => Ok(Some(format!("Nothing retried: {:#}", err))),
+                }
+            }
+            Command::Reminders => {
+                let reminders = self
+                    .persistence
+                    .pending_reminders(Some(self.agent.session_id()))?;
+                Ok(Some(if reminders.is_empty() {
+                    "No pending reminders in this session.".to_string()
+                } else {
+                    formatting::render_reminders(&reminders)
+                }))
+            }
+            Command::RemindersCancel(id) => {
+                let cancelled = self
+                    .persistence
+                    .cancel_reminder(self.agent.session_id(), id)?;
+                Ok(Some(if cancelled {
+                    format!("Cancelled reminder #{}.", id)
+                } else {
+                    format!("No pending reminder #{} in this session.", id)
+                }))
+            }
+            Command::Variants => {
+                let variants = self.agent.response_variants()?;
+                Ok(Some(if variants.is_empty() {
+                    "The last answer has no variants. /retry asks for another.".to_string()
+                } else {
+                    formatting::render_variants(&variants)
+                }))
+            }
+            Command::VariantsPick(number) => {
+                match self.agent.pick_response_variant(number).await {
+                    Ok(variant) => Ok(Some(format!(
+                        "Variant {} is now the answer in the history.\n\n{}",
+                        number,
+                        formatting::render_markdown(&variant.content)
+                    ))),
+                    Err(err) => Ok(Some(format!("{:#}. See /variants.", err))),
+                }
+            }
+            Command::Forget {
+                query,
+                dry_run,
+                session_only,
+            } => Ok(Some(self.forget_command(&query, dry_run, session_only)?)),
+            Command::Scroll(action) => {
+                let height = Self::scrollback_height();
+                match action {
+                    ScrollCommand::PageUp => self.scrollback.page_up(height),
+                    ScrollCommand::PageDown => self.scrollback.page_down(height),
+                    ScrollCommand::Bottom => self.scrollback.scroll_to_bottom(),
+                    ScrollCommand::PreviousTool => {
+                        if !self.scrollback.previous_tool(height) {
+                            return Ok(Some("No earlier tool invocations.".to_string()));
+                        }
+                    }
+                    ScrollCommand::NextTool => {
+                        if !self.scrollback.next_tool(height) {
+                            return Ok(Some("No later tool invocations.".to_string()));
+                        }
+                    }
+                }
+                Ok(Some(self.render_scrollback(height)))
+            }
+            Command::Find(action) => {
+                let height = Self::scrollback_height();
+                match action {
+                    FindCommand::Query(query) => {
+                        if self.scrollback.search(&query, height) == 0 {
+                            return Ok(Some(format!("No matches for '{}'.", query)));
+                        }
+                    }
+                    FindCommand::Next => {
+                        if self.scrollback.next_match(height).is_none() {
+                            return Ok(Some("No active search. Use /find <text>.".to_string()));
+                        }
+                    }
+                    FindCommand::Previous => {
+                        if self.scrollback.previous_match(height).is_none() {
+                            return Ok(Some("No active search. Use /find <text>.".to_string()));
+                        }
+                    }
+                    FindCommand::Clear => {
+                        self.scrollback.clear_search();
+                        return Ok(Some("Search cleared.".to_string()));
+                    }
+                }
+                Ok(Some(self.render_scrollback(height)))
+            }
+            Command::HandsFreeOn => Ok(Some(self.handsfree_on())),
+            Command::HandsFreeOff => Ok(Some(match self.handsfree.take() {
+                Some(handsfree) => {
+                    let elapsed = handsfree.elapsed_secs();
+                    handsfree.stop();
+                    format!("Hands-free mode off (was on for {} seconds).", elapsed)
+                }
+                None => "Hands-free mode is not on.".to_string(),
+            })),
+            Command::HandsFreeStatus => Ok(Some(match &self.handsfree {
+                Some(handsfree) if handsfree.is_listening() => format!(
+                    "Hands-free mode: on for {} seconds, listening for {}\nUse /handsfree off to stop.",
+                    handsfree.elapsed_secs(),
+                    self.wake_words_display()
+                ),
+                Some(_) => "Hands-free mode: on, but transcription has ended. Use /handsfree off, then /handsfree on to restart.".to_string(),
+                None => "Hands-free mode: off\nUse /handsfree on to start.".to_string(),
+            })),
+            Command::Message(text) => {
+                let (formatted, _) = self.run_message(&text).await?;
                 Ok(Some(formatted))
             }
         }
     }
 
+    /// Run one agent step for `text`; returns the rendered output and the
+    /// plain response
+    async fn run_message(&mut self, text: &str) -> Result<(String, String)> {
+        self.init_allowed = false;
+        self.agent.set_progress_reporter(self.progress.clone());
+        self.agent.set_tool_output_sink(self.tool_output.clone());
+        self.apply_deterministic();
+        let output = self.agent.run_step(text).await?;
+        self.record_exchange(text, &output);
+        self.update_reasoning_messages(&output);
+        let mut formatted = formatting::render_agent_response("assistant", &output.response);
+        let show_reasoning = self.agent.profile().show_reasoning;
+        if let Some(stats) = formatting::render_run_stats(&output, show_reasoning) {
+            formatted.push('\n');
+            formatted.push_str(&stats);
+        }
+        Ok((formatted, output.response))
+    }
+
+    fn wake_words_display(&self) -> String {
+        self.config
+            .audio
+            .wake_words
+            .iter()
+            .map(|phrase| format!("\"{}\"", phrase))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
+    fn handsfree_on(&mut self) -> String {
+        if self.handsfree.is_some() {
+            return "Hands-free mode is already on. Use /handsfree off to stop it.".to_string();
+        }
+        if self.transcription_task.is_some() {
+            return "A /listen recording is using the microphone. Use /listen stop first."
+                .to_string();
+        }
+        if self
+            .config
+            .audio
+            .wake_words
+            .iter()
+            .all(|w| w.trim().is_empty())
+        {
+            return "No wake words configured. Set audio.wake_words first.".to_string();
+        }
+
+        let mut config = TranscriptionConfig::from(&self.config.audio);
+        config.duration_secs = None;
+        let max_chunks = (self.config.audio.max_utterance_secs as f64
+            / config.chunk_duration_secs.max(0.1))
+        .ceil() as usize;
+        let detector = WakeWordDetector::new(&self.config.audio.wake_words, max_chunks);
+        let speech = create_speech_synthesizer(self.config.audio.tts_command.as_deref());
+        let voice = match &speech {
+            Some(speech) => format!("Answers are spoken with {}.", speech.name()),
+            None => "No speech command found; answers are only shown. Set audio.tts_command to speak them.".to_string(),
+        };
+        let provider_name = self.transcription_provider.metadata().name;
+        self.handsfree = Some(handsfree::HandsFree::start(
+            Arc::clone(&self.transcription_provider),
+            config,
+            detector,
+            speech,
+        ));
+        format!(
+            "Hands-free mode on using {}. Say {} followed by your request.\n{}\nUse /handsfree off to stop.",
+            provider_name,
+            self.wake_words_display(),
+            voice
+        )
+    }
+
+    /// Run a request heard in hands-free mode and speak the answer
+    async fn handle_spoken_request(&mut self, request: &str) -> String {
+        let mut out = format!("[Hands-free] {}\n", request);
+        match self.run_message(request).await {
+            Ok((formatted, response)) => {
+                out.push_str(&formatted);
+                if let Some(handsfree) = &self.handsfree {
+                    if let Err(e) = handsfree.say(&response).await {
+                        out.push_str(&format!(
+                            "\n[Hands-free] Could not speak the answer: {:#}",
+                            e
+                        ));
+                    }
+                }
+            }
+            Err(e) => out.push_str(&format!("Error: {:#}", e)),
+        }
+        out
+    }
+
     /// Run interactive REPL on stdin/stdout
     pub async fn run_repl(&mut self) -> Result<()> {
         let stdin = io::stdin();
-        let mut reader = BufReader::new(stdin);
-        let mut line = String::new();
+        let mut lines = BufReader::new(stdin).lines();
         let mut stdout = tokio::io::stdout();
 
         // Print welcome and summary
@@ -813,13 +2166,49 @@ impl CliState {
         stdout.flush().await?;
 
         self.set_status_idle();
+        // Reminders are posted when the REPL starts and while it waits
+        let mut reminder_ticks = tokio::time::interval(reminders::DELIVERY_INTERVAL);
+        reminder_ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut redraw = true;
         loop {
-            self.render_reasoning_prompt(&mut stdout).await?;
-            line.clear();
-            let n = reader.read_line(&mut line).await?;
-            if n == 0 {
-                break;
-            } // EOF
+            if redraw {
+                self.render_reasoning_prompt(&mut stdout).await?;
+            }
+            redraw = true;
+            // Typed input, a request heard in hands-free mode, or a reminder.
+            // Ctrl-C at the prompt quits; while a line runs it cancels tools.
+            let line = tokio::select! {
+                read = lines.next_line() => match read? {
+                    Some(line) => line,
+                    None => break, // EOF
+                },
+                _ = ctrl_c() => break,
+                _ = reminder_ticks.tick() => {
+                    match self.deliver_reminders() {
+                        Some(out) => {
+                            stdout.write_all(b"\n").await?;
+                            stdout.write_all(out.as_bytes()).await?;
+                            stdout.write_all(b"\n").await?;
+                            stdout.flush().await?;
+                        }
+                        // Nothing to show; keep the prompt as it is
+                        None => redraw = false,
+                    }
+                    continue;
+                }
+                Some(request) = next_spoken_request(&mut self.handsfree) => {
+                    self.status_message = "Status: answering a spoken request".to_string();
+                    self.render_status_line(&mut stdout).await?;
+                    let out = self.handle_spoken_request(&request).await;
+                    stdout.write_all(out.as_bytes()).await?;
+                    if !out.ends_with('\n') {
+                        stdout.write_all(b"\n").await?;
+                    }
+                    stdout.flush().await?;
+                    self.set_status_idle();
+                    continue;
+                }
+            };
 
             let trimmed = line.trim_end_matches(&['\n', '\r'][..]);
 
@@ -836,7 +2225,7 @@ impl CliState {
                     if !matches!(command_preview, Command::Empty) {
                         self.render_status_line(&mut stdout).await?;
                     }
-                    if let Some(out) = self.handle_line(&full_input).await? {
+                    if let Some(out) = self.handle_line_until(&full_input, ctrl_c()).await? {
                         if out == "__QUIT__" {
                             break;
                         }
@@ -872,7 +2261,7 @@ impl CliState {
             if !matches!(command_preview, Command::Empty) {
                 self.render_status_line(&mut stdout).await?;
             }
-            if let Some(out) = self.handle_line(&line).await? {
+            if let Some(out) = self.handle_line_until(&line, ctrl_c()).await? {
                 if out == "__QUIT__" {
                     break;
                 }
@@ -885,14 +2274,493 @@ impl CliState {
             self.set_status_idle();
         }
 
+        self.end_session().await;
+
         // Checkpoint database before exiting to ensure all WAL data is written
         let _ = self.persistence.checkpoint();
 
         Ok(())
     }
 
+    fn run_init_command(&mut self, plugins: Option<Vec<String>>) -> Result<String> {
+        let bootstrapper =
+            BootstrapSelf::from_environment(&self.persistence, self.agent.session_id())?
+                .with_progress(self.progress.clone());
+        let outcome = bootstrapper.run_with_plugins(plugins)?;
+        self.init_allowed = false;
+        Ok(format!(
+            "Knowledge graph bootstrap complete for '{}': {} nodes and {} edges captured ({} components, {} documents).",
+            outcome.repository_name,
+            outcome.nodes_created,
+            outcome.edges_created,
+            outcome.component_count,
+            outcome.document_count
+        ))
+    }
+
+    /// Bootstrap `path` into the session graph under its own workspace node
+    /// and register it, named after its directory unless `name` is given
+    fn add_workspace_command(&mut self, path: &Path, name: Option<String>) -> Result<String> {
+        let root = path
+            .canonicalize()
+            .with_context(|| format!("resolving {}", path.display()))?;
+        if !root.is_dir() {
+            bail!("{} is not a directory", root.display());
+        }
+        let name = match name {
+            Some(name) => name,
+            None => root
+                .file_name()
+                .and_then(|name| name.to_str())
+                .context("cannot derive a workspace name; pass one after the path")?
+                .to_string(),
+        };
+        let session_id = self.agent.session_id().to_string();
+        if self
+            .persistence
+            .workspace_get(&session_id, &name)?
+            .is_some()
+        {
+            bail!("workspace '{}' already exists in this session", name);
+        }
+        let outcome = BootstrapSelf::new(&self.persistence, &session_id, root.clone())
+            .with_workspace(name.clone())
+            .with_progress(self.progress.clone())
+            .run_with_plugins(None)?;
+        let workspace =
+            self.persistence
+                .workspace_add(&session_id, &name, &root, outcome.workspace_node_id)?;
+        self.init_allowed = false;
+        Ok(format!(
+            "Added workspace '{}' at {}: {} nodes and {} edges captured ({} components, {} documents).{}",
+            workspace.name,
+            workspace.root.display(),
+            outcome.nodes_created,
+            outcome.edges_created,
+            outcome.component_count,
+            outcome.document_count,
+            if workspace.focused {
+                " It is now focused."
+            } else {
+                ""
+            }
+        ))
+    }
+
+    fn list_workspaces_command(&self) -> Result<String> {
+        let workspaces = self.persistence.workspace_list(self.agent.session_id())?;
+        if workspaces.is_empty() {
+            return Ok(
+                "No workspaces in this session. Add one with /workspace add <path>.".to_string(),
+            );
+        }
+        let mut out = String::from("Workspaces:");
+        for workspace in workspaces {
+            out.push_str(&format!(
+                "\n  {} {} ({})",
+                if workspace.focused { "*" } else { " " },
+                workspace.name,
+                workspace.root.display()
+            ));
+        }
+        Ok(out)
+    }
+
+    fn forget_command(&mut self, query: &str, dry_run: bool, session_only: bool) -> Result<String> {
+        let Some(target) = ForgetTarget::parse(query) else {
+            return Ok("Usage: /forget [--dry-run] [--session] <text|message_id>".to_string());
+        };
+        let session_id = self.agent.session_id().to_string();
+        let scope = session_only.then_some(session_id.as_str());
+        let report = self.persistence.forget(&target, scope, dry_run)?;
+        if report.is_empty() {
+            return Ok(format!("Nothing matches '{}'.", query));
+        }
+        if !dry_run
+            && report
+                .messages
+                .iter()
+                .any(|m| m.session_id.as_deref() == Some(&session_id))
+        {
+            // Reload the session so erased messages leave the conversation
+            self.agent = AgentBuilder::new_with_registry(
+                &self.registry,
+                &self.config,
+                Some(session_id.clone()),
+            )?;
+        }
+        Ok(formatting::render_forget_report(query, &report))
+    }
+
+    async fn memory_search_command(&self, query: &str) -> Result<String> {
+        let session_id = self.agent.session_id().to_string();
+        // Rank like recall does when queries can be embedded, else by text
+        let query_embedding = match self.agent.embeddings_client() {
+            Some(client) => Some(client.embed(query).await?),
+            None => None,
+        };
+        let memories = self.persistence.search_memories(
+            &session_id,
+            query,
+            query_embedding.as_deref(),
+            MEMORY_SEARCH_LIMIT,
+        )?;
+        if memories.is_empty() {
+            return Ok(format!("No memories in this session match '{}'.", query));
+        }
+        Ok(formatting::render_memories(
+            &format!(
+                "Memories for \"{}\" (remove one with /memory delete <id>)",
+                query
+            ),
+            &memories,
+        ))
+    }
+
+    /// Give the agent the stored rules, the policy file's rules and its
+    /// profile's preset again; returns the number of active rules
+    fn reload_policies(&mut self) -> Result<usize> {
+        let mut policy_engine = PolicyEngine::load_from_persistence(&self.persistence)
+            .context("Failed to load policies from persistence")?;
+        if let Some(path) = &self.config.policy_file {
+            policy_engine.merge_file(path)?;
+        }
+        let policy_engine = with_profile_policy(policy_engine, self.agent.profile());
+        let rule_count = policy_engine.rule_count();
+        self.agent
+            .set_policy_engine(std::sync::Arc::new(policy_engine));
+        Ok(rule_count)
+    }
+
+    /// Ask for a rule in a form; without a terminal, explain the arguments
+    fn policy_add_form_command(&mut self) -> Result<String> {
+        if !dialog::is_interactive() {
+            return Ok("Usage: /policy add <agent> <action> <resource> <allow|deny>".to_string());
+        }
+        let mut form = dialog::FormDialog::new(
+            "New policy rule",
+            vec![
+                dialog::FormField::new("agent").with_value("*").required(),
+                dialog::FormField::new("action")
+                    .with_value("tool_call")
+                    .required(),
+                dialog::FormField::new("resource").required(),
+                dialog::FormField::new("effect")
+                    .with_value("allow")
+                    .required(),
+            ],
+        );
+        let values = match dialog::run_in_terminal(&mut form)? {
+            dialog::DialogOutcome::Submitted(values) => values,
+            _ => return Ok("No rule added.".to_string()),
+        };
+        let field = |name: &str| {
+            values
+                .iter()
+                .find(|(label, _)| label == name)
+                .map(|(_, value)| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        let effect = match field("effect").to_lowercase().as_str() {
+            "allow" => PolicyEffect::Allow,
+            "deny" => PolicyEffect::Deny,
+            other => {
+                return Ok(format!(
+                    "Unknown effect '{}'; use allow or deny. No rule added.",
+                    other
+                ))
+            }
+        };
+        self.policy_add_command(PolicyRule {
+            agent: field("agent"),
+            action: field("action"),
+            resource: field("resource"),
+            effect,
+            alternative: None,
+        })
+    }
+
+    fn policy_add_command(&mut self, rule: PolicyRule) -> Result<String> {
+        let mut stored = PolicyEngine::load_from_persistence(&self.persistence)
+            .context("Failed to load policies from persistence")?;
+        let description = rule.to_string();
+        stored.add_rule(rule);
+        stored.save_to_persistence(&self.persistence)?;
+        let number = stored.rule_count();
+        self.reload_policies()?;
+        let mut message = format!("Added policy rule #{}: {}.", number, description);
+        if number == 1
+            && self.agent.profile().policy_preset.is_none()
+            && self.config.policy_file.is_none()
+        {
+            message.push_str(
+                " Tools no rule allows now need approval, as the default allow-all applies only without stored rules.",
+            );
+        }
+        Ok(message)
+    }
+
+    fn policy_remove_command(&mut self, number: usize) -> Result<String> {
+        let mut stored = PolicyEngine::load_from_persistence(&self.persistence)
+            .context("Failed to load policies from persistence")?;
+        let Some(rule) = stored.remove_rule(number - 1) else {
+            return Ok(format!(
+                "No stored policy rule #{}; /policy list shows the {} stored rule(s) first.",
+                number,
+                stored.rule_count()
+            ));
+        };
+        stored.save_to_persistence(&self.persistence)?;
+        self.reload_policies()?;
+        Ok(format!("Removed policy rule #{}: {}.", number, rule))
+    }
+
+    fn memory_delete_command(&self, id: i64) -> Result<String> {
+        Ok(match self.persistence.delete_memory(id)? {
+            MemoryDeletion::Deleted(entry) => format!(
+                "Deleted memory #{} of session '{}'; it will no longer be recalled. The {} it came from is kept.",
+                entry.id,
+                entry.session_id,
+                entry.kind.map(|kind| kind.as_str()).unwrap_or("record")
+            ),
+            MemoryDeletion::NotFound => format!("No memory #{}.", id),
+            MemoryDeletion::LinkedToNode { node_id } => format!(
+                "Memory #{} belongs to graph node #{}; delete the node to remove it.",
+                id, node_id
+            ),
+        })
+    }
+
+    fn show_profile_command(&self) -> Result<String> {
+        let facts = self.persistence.user_profile_list()?;
+        if facts.is_empty() {
+            return Ok(if self.config.user_profile.enabled {
+                "No profile facts yet. They are collected when a session ends.".to_string()
+            } else {
+                "No profile facts. The user profile is disabled ([user_profile] enabled = false)."
+                    .to_string()
+            });
+        }
+        let items = facts
+            .iter()
+            .map(|fact| format!("#{} [{}] {}", fact.id, fact.category, fact.fact))
+            .collect();
+        Ok(formatting::render_list("User profile", items))
+    }
+
+    /// Open the profile in `$VISUAL`/`$EDITOR` as `category: fact` lines and
+    /// store what the editor leaves behind
+    fn switch_agent_command(&mut self, name: &str) -> Result<String> {
+        self.registry.set_active(name)?;
+        let session = self.agent.session_id().to_string();
+        self.agent = AgentBuilder::new_with_registry(&self.registry, &self.config, Some(session))?;
+        Ok(format!("Switched active agent to '{}'.", name))
+    }
+
+    /// Choose the agent to switch to from a list; without a terminal, explain
+    /// how to name it instead
+    fn pick_agent_command(&mut self) -> Result<String> {
+        let agents = self.registry.list();
+        if agents.is_empty() {
+            return Ok("No agents configured.".to_string());
+        }
+        if !dialog::is_interactive() {
+            return Ok("Usage: /switch <agent>. See /agents for the names.".to_string());
+        }
+        let active = self.registry.active_name();
+        let current = agents
+            .iter()
+            .position(|name| Some(name) == active.as_ref())
+            .unwrap_or(0);
+        let mut select = dialog::SelectDialog::new("Switch agent", agents).with_selected(current);
+        match dialog::run_in_terminal(&mut select)? {
+            dialog::DialogOutcome::Submitted((_, name)) => self.switch_agent_command(&name),
+            _ => Ok("Agent unchanged.".to_string()),
+        }
+    }
+
+    /// Ask whether to grant an unsigned spec's permissions for this run;
+    /// only called on a terminal
+    fn confirm_spec_permissions(&self, spec: &AgentSpec) -> Result<bool> {
+        let requested = spec
+            .permissions
+            .iter()
+            .map(|permission| format!("- {}", permission))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut confirm = dialog::ConfirmDialog::new(
+            "Spec permissions",
+            format!(
+                "`{}` is unsigned and requests for this run:\n{}\nGrant these for this run only?",
+                spec.display_name(),
+                requested
+            ),
+        );
+        Ok(matches!(
+            dialog::run_in_terminal(&mut confirm)?,
+            dialog::DialogOutcome::Submitted(true)
+        ))
+    }
+
+    /// Ask before bootstrapping a session that already has history; only
+    /// possible on a terminal, otherwise `--yes` is required
+    fn confirm_force_init(&self) -> Result<bool> {
+        if !dialog::is_interactive() {
+            return Ok(false);
+        }
+        let mut confirm = dialog::ConfirmDialog::new(
+            "Bootstrap this session?",
+            "This session already has history; bootstrapping adds the repository's facts to its knowledge graph alongside it.",
+        );
+        Ok(matches!(
+            dialog::run_in_terminal(&mut confirm)?,
+            dialog::DialogOutcome::Submitted(true)
+        ))
+    }
+
+    fn edit_profile_command(&self) -> Result<String> {
+        let facts = self.persistence.user_profile_list()?;
+        let mut file = tempfile::Builder::new()
+            .prefix("spec-ai-profile-")
+            .suffix(".txt")
+            .tempfile()?;
+        let header = format!(
+            "# One fact per line as `category: fact` ({}).\n\
+             # Delete a line to forget it; save an empty file to clear the profile.\n",
+            user_profile::CATEGORIES.join(", ")
+        );
+        std::io::Write::write_all(
+            &mut file,
+            (header + &user_profile::to_text(&facts)).as_bytes(),
+        )?;
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let status = std::process::Command::new(&editor)
+            .arg(file.path())
+            .status()
+            .with_context(|| format!("starting editor '{}'", editor))?;
+        if !status.success() {
+            bail!("editor exited with {}", status);
+        }
+        let edited = user_profile::from_text(&std::fs::read_to_string(file.path())?);
+        self.persistence.user_profile_replace(&edited)?;
+        Ok(format!("User profile saved: {} fact(s).", edited.len()))
+    }
+
+    /// Collect profile facts from the session being left and stop the
+    /// processes it started. Failures are logged rather than returned so
+    /// they never block leaving a session.
+    /// Post due reminders into their sessions; the text to show for those
+    /// of the current session
+    fn deliver_reminders(&self) -> Option<String> {
+        let delivered = match reminders::deliver_due(&self.persistence, chrono::Utc::now()) {
+            Ok(delivered) => delivered,
+            Err(err) => {
+                tracing::warn!("Failed to deliver reminders: {:#}", err);
+                return None;
+            }
+        };
+        let session_id = self.agent.session_id();
+        let (here, elsewhere): (Vec<_>, Vec<_>) = delivered
+            .iter()
+            .partition(|reminder| reminder.session_id == session_id);
+        let mut lines: Vec<String> = here
+            .into_iter()
+            .map(|reminder| {
+                format!(
+                    "{} (set {})",
+                    reminders::reminder_text(reminder),
+                    reminder.created_at.format("%Y-%m-%d %H:%M UTC")
+                )
+            })
+            .collect();
+        if !elsewhere.is_empty() {
+            lines.push(format!(
+                "{} reminder(s) posted into other sessions.",
+                elsewhere.len()
+            ));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    async fn end_session(&self) {
+        match self.agent.update_user_profile().await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!(
+                "Stored {} user profile fact(s) from session '{}'",
+                count,
+                self.agent.session_id()
+            ),
+            Err(err) => tracing::warn!("Could not update the user profile: {:#}", err),
+        }
+        // Whatever is left stays queued for the next run of this session
+        match self.agent.flush_embeddings().await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!("Embedded {} queued message(s)", count),
+            Err(err) => tracing::warn!("Could not embed queued messages: {:#}", err),
+        }
+        let stopped = self
+            .agent
+            .tool_registry()
+            .processes()
+            .stop_session(self.agent.session_id())
+            .await;
+        if stopped > 0 {
+            tracing::debug!("Stopped {} background process(es)", stopped);
+        }
+    }
+
+    fn ingest_ci_command(&self, path: &Path, format: Option<&str>) -> Result<String> {
+        let format = match format {
+            Some(name) => {
+                Some(CiFormat::parse(name).with_context(|| format!("unknown format '{}'", name))?)
+            }
+            None => None,
+        };
+        let log =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let report = parse_ci_log(&log, format)?;
+        let outcome = record_ci_report(
+            &self.persistence,
+            self.agent.session_id(),
+            &report,
+            &path.display().to_string(),
+        )?;
+        Ok(format!(
+            "{}\nRecorded {} TestFailure node(s), {} linked to code components.",
+            report.summary(),
+            outcome.failure_node_ids.len(),
+            outcome.linked
+        ))
+    }
+
+    fn spec_library(&self) -> Option<SpecLibrary> {
+        SpecLibrary::from_config(&self.config.specs).ok()
+    }
+
+    fn complete_spec_command(&self, prefix: &str) -> String {
+        let matches = self
+            .spec_library()
+            .map(|library| library.complete(prefix))
+            .unwrap_or_default();
+        match matches.as_slice() {
+            [] if prefix.is_empty() => {
+                "The spec library is empty. Add specs with `spec-ai specs add <file>`, or run a file with `/spec <file>`.".to_string()
+            }
+            [] => format!("No library spec starts with '{}'.", prefix),
+            [only] => format!("/spec {}", only),
+            many => format!("Library specs:\n  {}", many.join("\n  ")),
+        }
+    }
+
     async fn run_spec_command(&mut self, path: &Path) -> Result<String> {
-        let spec = AgentSpec::from_file(path)?;
+        // A bare name that is not a file refers to a spec in the library
+        let library_path = match (path.exists(), path.to_str(), self.spec_library()) {
+            (false, Some(name), Some(library)) => library.resolve(name),
+            _ => None,
+        };
+        let spec = AgentSpec::from_file(library_path.as_deref().unwrap_or(path))?;
         let mut intro = format!("Executing spec `{}`", spec.display_name());
         if let Some(source) = spec.source_path() {
             intro.push_str(&format!(" ({})", source.display()));
@@ -906,7 +2774,46 @@ impl CliState {
             intro.push_str("\n\n");
         }
 
-        let output = self.agent.run_spec(&spec).await?;
+        // Each spec run starts from its own declared panels, which the agent
+        // publishes when the run begins
+        if let Ok(mut panels) = self.panels.lock() {
+            panels.clear();
+        }
+
+        // Specs signed by a trusted key get their permissions; for others
+        // the user is asked, and without a terminal they are not granted
+        let mut granted = Vec::new();
+        if !spec.permissions.is_empty() {
+            let signer = spec.source_path().and_then(|source| {
+                spec_source::local_signer(source, &self.config.specs.trusted_keys)
+            });
+            let list = spec
+                .permissions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
+            if signer.is_some() {
+                intro.push_str(&format!("Granted for this run (signed spec): {}\n\n", list));
+                granted = spec.permissions.clone();
+            } else if !dialog::is_interactive() {
+                intro.push_str(&format!(
+                    "Not granted (unsigned spec and no terminal to ask): {}\n\n",
+                    list
+                ));
+            } else if self.confirm_spec_permissions(&spec)? {
+                intro.push_str(&format!("Granted for this run (approved): {}\n\n", list));
+                granted = spec.permissions.clone();
+            } else {
+                intro.push_str(&format!("Not granted (declined): {}\n\n", list));
+            }
+        }
+
+        self.agent.set_progress_reporter(self.progress.clone());
+        self.agent.set_tool_output_sink(self.tool_output.clone());
+        self.apply_deterministic();
+        let output = self.agent.run_spec_with_permissions(&spec, granted).await?;
+        self.record_exchange(&format!("/spec {}", path.display()), &output);
         self.update_reasoning_messages(&output);
         intro.push_str(&formatting::render_agent_response(
             "assistant",
@@ -921,8 +2828,136 @@ impl CliState {
         Ok(intro)
     }
 
+    /// Ask the mesh leader on this machine for cluster health
+    #[cfg(feature = "api")]
+    async fn mesh_status(&self) -> String {
+        let port = self.config.mesh.registry_port;
+        match crate::mesh::MeshClient::new("127.0.0.1", port)
+            .stats()
+            .await
+        {
+            Ok(stats) => formatting::render_mesh_stats(&stats),
+            Err(err) => format!(
+                "No mesh leader reachable on port {} ({}). Start one with `spec-ai server`.",
+                port, err
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "api"))]
+    async fn mesh_status(&self) -> String {
+        "Mesh support is not part of this build; rebuild with the `api` feature.".to_string()
+    }
+
+    async fn run_roundtable_command(
+        &mut self,
+        question: &str,
+        show_drafts: bool,
+    ) -> Result<String> {
+        if !self.config.roundtable.is_configured() {
+            return Ok(
+                "Round-table mode needs 2-4 agent profiles under [roundtable] participants in spec-ai.config.toml."
+                    .to_string(),
+            );
+        }
+        self.init_allowed = false;
+        self.agent.set_progress_reporter(self.progress.clone());
+        self.agent.set_tool_output_sink(self.tool_output.clone());
+        self.apply_deterministic();
+
+        let result = match self.config.roundtable.moderator.clone() {
+            Some(name) => {
+                let mut moderator = create_agent_for_profile(
+                    &self.registry,
+                    &self.config,
+                    &name,
+                    Some(self.agent.session_id().to_string()),
+                )?;
+                if self.deterministic {
+                    moderator.set_deterministic(true);
+                }
+                run_roundtable(&self.registry, &self.config, &mut moderator, question).await?
+            }
+            None => run_roundtable(&self.registry, &self.config, &mut self.agent, question).await?,
+        };
+
+        self.record_exchange(&format!("/roundtable {}", question), &result.output);
+        self.update_reasoning_messages(&result.output);
+
+        let mut rendered = String::new();
+        if show_drafts || self.config.roundtable.show_drafts {
+            for draft in &result.drafts {
+                rendered.push_str(&formatting::render_agent_response(
+                    &format!("draft from {}", draft.agent),
+                    &draft.response,
+                ));
+                rendered.push_str("\n\n");
+            }
+        }
+        rendered.push_str(&formatting::render_agent_response(
+            &result.moderator,
+            &result.output.response,
+        ));
+        let show_reasoning = self.agent.profile().show_reasoning;
+        if let Some(stats) = formatting::render_run_stats(&result.output, show_reasoning) {
+            rendered.push('\n');
+            rendered.push_str(&stats);
+        }
+        Ok(rendered)
+    }
+
+    /// Append a completed exchange to the scrollback buffer
+    fn record_exchange(&mut self, input: &str, output: &AgentOutput) {
+        self.scrollback
+            .push(LineKind::User, &format!("user: {}", input));
+        for invocation in &output.tool_invocations {
+            let status = match (invocation.success, invocation.cached) {
+                (true, true) => "ok, cached",
+                (true, false) => "ok",
+                (false, _) => "err",
+            };
+            self.scrollback.push(
+                LineKind::Tool,
+                &format!("tool: {} ({})", invocation.name, status),
+            );
+        }
+        self.scrollback.push(
+            LineKind::Assistant,
+            &format!("assistant: {}", output.response),
+        );
+    }
+
+    /// Rows available for the scrollback view (terminal height minus REPL chrome)
+    fn scrollback_height() -> usize {
+        terminal_size()
+            .map(|(_, h)| (h.0 as usize).saturating_sub(6))
+            .unwrap_or(20)
+            .max(5)
+    }
+
+    fn render_scrollback(&self, height: usize) -> String {
+        if self.scrollback.is_empty() {
+            return "Conversation history is empty.".to_string();
+        }
+        let highlight = if formatting::is_terminal() {
+            ("\x1b[7m", "\x1b[0m")
+        } else {
+            ("[[", "]]")
+        };
+        let mut out = self.scrollback.render(height, highlight).join("\n");
+        out.push_str(&format!(
+            "\n-- {} --",
+            self.scrollback.position_summary(height)
+        ));
+        out
+    }
+
     fn update_reasoning_messages(&mut self, output: &AgentOutput) {
         self.reasoning_messages = Self::format_reasoning_messages(output);
+        if output.graph_debug.is_some() {
+            self.last_graph_debug = output.graph_debug.clone();
+        }
+        self.last_run_id = Some(output.run_id.clone());
     }
 
     fn format_reasoning_messages(output: &AgentOutput) -> Vec<String> {
@@ -930,6 +2965,15 @@ impl CliState {
 
         if let Some(stats) = &output.recall_stats {
             match &stats.strategy {
+                MemoryRecallStrategy::Semantic {
+                    requested,
+                    returned,
+                } if stats.scope != RecallScope::Session => lines.push(format!(
+                    "Recall: semantic, {} scope (requested {}, returned {})",
+                    stats.scope.as_str(),
+                    requested,
+                    returned
+                )),
                 MemoryRecallStrategy::Semantic {
                     requested,
                     returned,
@@ -982,14 +3026,24 @@ impl CliState {
             Command::ConfigReload => "Status: reloading configuration".to_string(),
             Command::ConfigShow => "Status: displaying configuration".to_string(),
             Command::PolicyReload => "Status: reloading policies".to_string(),
+            Command::PolicyList => "Status: listing policy rules".to_string(),
+            Command::PolicyAdd { .. } | Command::PolicyAddForm => {
+                "Status: adding a policy rule".to_string()
+            }
+            Command::PolicyRemove(number) => format!("Status: removing policy rule #{}", number),
+            Command::PolicyAudit(_) => "Status: showing policy decisions".to_string(),
             Command::SwitchAgent(name) => {
                 format!("Status: switching to agent '{}'", name)
             }
+            Command::PickAgent => "Status: choosing an agent".to_string(),
             Command::ListAgents => "Status: listing agents".to_string(),
             Command::MemoryShow(Some(limit)) => {
                 format!("Status: showing last {} messages", limit)
             }
             Command::MemoryShow(None) => "Status: showing recent messages".to_string(),
+            Command::MemorySearch(query) => format!("Status: searching memories for '{}'", query),
+            Command::MemoryDelete(id) => format!("Status: deleting memory #{}", id),
+            Command::MemoryCompact => "Status: compacting memories".to_string(),
             Command::SessionNew(Some(id)) => {
                 format!("Status: starting session '{}'", id)
             }
@@ -1001,12 +3055,29 @@ impl CliState {
             Command::GraphEnable => "Status: showing graph enable instructions".to_string(),
             Command::GraphDisable => "Status: showing graph disable instructions".to_string(),
             Command::GraphStatus => "Status: showing graph status".to_string(),
+            Command::GraphUse(name) => format!("Status: switching to graph {}", name),
             Command::GraphShow(Some(limit)) => {
                 format!("Status: inspecting graph (limit {})", limit)
             }
             Command::GraphShow(None) => "Status: inspecting graph".to_string(),
+            Command::GraphWhy => "Status: explaining last graph steering".to_string(),
+            Command::GraphDiff { .. } => "Status: comparing graph snapshots".to_string(),
+            Command::GraphSubgraph { query, .. } => {
+                format!("Status: extracting the subgraph for '{}'", query)
+            }
+            Command::ModelLogShow(_) => "Status: showing model log".to_string(),
+            Command::Tokens(_) => "Status: counting tokens".to_string(),
+            Command::ToolRun { name, .. } => format!("Status: running tool '{}'", name),
+            Command::ToolSchema(name) => format!("Status: showing schema of '{}'", name),
+            Command::ToolsStats { .. } => "Status: showing tool usage".to_string(),
+            Command::MeshStatus => "Status: fetching mesh stats".to_string(),
             Command::GraphClear => "Status: clearing session graph".to_string(),
-            Command::Init(_) => "Status: bootstrapping repository graph".to_string(),
+            Command::GraphPin(id) => format!("Status: pinning graph node #{}", id),
+            Command::GraphUnpin(id) => format!("Status: unpinning graph node #{}", id),
+            Command::GraphPins => "Status: listing pinned graph nodes".to_string(),
+            Command::Init(_) | Command::InitForce { .. } => {
+                "Status: bootstrapping repository graph".to_string()
+            }
             Command::ListenStart(duration) => {
                 let mut status = "Status: starting background transcription".to_string();
                 if let Some(d) = duration {
@@ -1016,6 +3087,15 @@ impl CliState {
             }
             Command::ListenStop => "Status: stopping transcription".to_string(),
             Command::ListenStatus => "Status: checking transcription status".to_string(),
+            Command::ListenFile(path) => {
+                format!("Status: transcribing {}", path.display())
+            }
+            Command::HandsFreeOn => "Status: starting hands-free mode".to_string(),
+            Command::HandsFreeOff => "Status: stopping hands-free mode".to_string(),
+            Command::HandsFreeStatus => "Status: checking hands-free mode".to_string(),
+            Command::ListenExport { path, .. } => {
+                format!("Status: exporting transcription to {}", path.display())
+            }
             Command::Listen(scenario, duration) => {
                 let mut status = "Status: starting audio transcription".to_string();
                 if let Some(s) = scenario {
@@ -1029,26 +3109,40 @@ impl CliState {
             Command::RunSpec(path) => {
                 format!("Status: executing spec '{}'", path.display())
             }
+            Command::CompleteSpec(_) => "Status: listing library specs".to_string(),
+            Command::Roundtable { .. } => "Status: collecting round-table drafts".to_string(),
             Command::PasteStart => {
                 "Status: entering paste mode (end with /end on its own line)".to_string()
             }
             Command::Message(_) => "Status: running agent step".to_string(),
             Command::Refresh(_) => "Status: refreshing internal knowledge graph".to_string(),
+            Command::WorkspaceAdd { path, .. } => {
+                format!("Status: bootstrapping workspace '{}'", path.display())
+            }
+            Command::WorkspaceList => "Status: listing workspaces".to_string(),
+            Command::CiIngest { path, .. } => {
+                format!("Status: ingesting test log '{}'", path.display())
+            }
+            Command::WorkspaceFocus(name) => format!("Status: focusing workspace '{}'", name),
+            Command::WorkspaceRemove(name) => format!("Status: removing workspace '{}'", name),
+            Command::ProfileShow => "Status: showing user profile".to_string(),
+            Command::ProfileEdit => "Status: editing user profile".to_string(),
+            Command::ProfileForget(_) => "Status: forgetting profile facts".to_string(),
+            Command::Compact => "Status: compacting conversation".to_string(),
+            Command::Retry { .. } => "Status: retrying the last answer".to_string(),
+            Command::Reminders => "Status: listing reminders".to_string(),
+            Command::RemindersCancel(id) => format!("Status: cancelling reminder #{}", id),
+            Command::Variants => "Status: listing answer variants".to_string(),
+            Command::VariantsPick(n) => format!("Status: picking answer variant {}", n),
+            Command::Forget { dry_run: true, .. } => "Status: finding what to forget".to_string(),
+            Command::Forget { .. } => "Status: forgetting".to_string(),
+            Command::Scroll(_) => "Status: scrolling conversation history".to_string(),
+            Command::Find(_) => "Status: searching conversation history".to_string(),
         }
     }
 
     fn pad_line_to_width(line: &str, width: usize) -> String {
-        if width == 0 {
-            return String::new();
-        }
-        let truncated: String = line.chars().take(width).collect();
-        let truncated_len = truncated.chars().count();
-        if truncated_len >= width {
-            return truncated;
-        }
-        let mut padded = truncated;
-        padded.push_str(&" ".repeat(width - truncated_len));
-        padded
+        text_utils::pad_to_width(line, width)
     }
 
     fn reasoning_display_lines(&self, width: usize) -> Vec<String> {
@@ -1070,7 +3164,7 @@ impl CliState {
 
     fn input_display_width(&self) -> usize {
         let terminal_width = terminal_size().map(|(w, _)| w.0 as usize).unwrap_or(80);
-        let prompt_len = self.config.ui.prompt.chars().count();
+        let prompt_len = text_utils::display_width(&self.config.ui.prompt);
         if terminal_width <= prompt_len {
             1
         } else {
@@ -1102,6 +3196,21 @@ impl CliState {
         Ok(())
     }
 
+    /// Run every agent in deterministic mode, regardless of its profile
+    /// (the `--deterministic` command line flag)
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.deterministic = enabled;
+        self.apply_deterministic();
+    }
+
+    /// Agents are rebuilt when switching sessions or profiles, so the
+    /// override is re-applied before each run
+    fn apply_deterministic(&mut self) {
+        if self.deterministic {
+            self.agent.set_deterministic(true);
+        }
+    }
+
     fn refresh_init_gate(&mut self) -> Result<()> {
         let messages = self.persistence.list_messages(self.agent.session_id(), 1)?;
         self.init_allowed = messages.is_empty();
@@ -1140,15 +3249,128 @@ mod tests {
             parse_command("/init --plugins=rust-cargo,python"),
             Command::Init(Some(vec!["rust-cargo".to_string(), "python".to_string()]))
         );
+        assert_eq!(
+            parse_command("/init --force"),
+            Command::InitForce {
+                plugins: None,
+                confirmed: false
+            }
+        );
+        assert_eq!(
+            parse_command("/init --force --plugins=rust-cargo --yes"),
+            Command::InitForce {
+                plugins: Some(vec!["rust-cargo".to_string()]),
+                confirmed: true
+            }
+        );
         assert_eq!(
             parse_command("/switch coder"),
             Command::SwitchAgent("coder".into())
         );
+        assert_eq!(parse_command("/switch"), Command::PickAgent);
         assert_eq!(
             parse_command("/memory show 5"),
             Command::MemoryShow(Some(5))
         );
+        assert_eq!(parse_command("/policy list"), Command::PolicyList);
+        assert_eq!(
+            parse_command("/policy add coder tool_call bash Deny"),
+            Command::PolicyAdd {
+                agent: "coder".into(),
+                action: "tool_call".into(),
+                resource: "bash".into(),
+                effect: PolicyEffect::Deny,
+            }
+        );
+        assert_eq!(
+            parse_command("/policy add * tool_call bash maybe"),
+            Command::Help
+        );
+        assert_eq!(parse_command("/policy add * tool_call"), Command::Help);
+        assert_eq!(parse_command("/policy add"), Command::PolicyAddForm);
+        assert_eq!(parse_command("/policy remove #2"), Command::PolicyRemove(2));
+        assert_eq!(parse_command("/policy remove 0"), Command::Help);
+        assert_eq!(parse_command("/policy audit"), Command::PolicyAudit(None));
+        assert_eq!(
+            parse_command("/policy audit 50"),
+            Command::PolicyAudit(Some(50))
+        );
+        assert_eq!(
+            parse_command("/memory search deploy window"),
+            Command::MemorySearch("deploy window".into())
+        );
+        assert_eq!(
+            parse_command("/memory delete #12"),
+            Command::MemoryDelete(12)
+        );
+        assert_eq!(parse_command("/memory delete stale"), Command::Help);
+        assert_eq!(parse_command("/memory compact"), Command::MemoryCompact);
+        assert_eq!(parse_command("/graph pin #7"), Command::GraphPin(7));
+        assert_eq!(parse_command("/graph unpin 7"), Command::GraphUnpin(7));
+        assert_eq!(parse_command("/graph pins"), Command::GraphPins);
+        assert_eq!(
+            parse_command("/graph use incidents"),
+            Command::GraphUse("incidents".into())
+        );
+        assert_eq!(parse_command("/graph use"), Command::Help);
+        assert_eq!(parse_command("/graph pin"), Command::Help);
+        assert_eq!(
+            parse_command("/graph diff run-1 run-2"),
+            Command::GraphDiff {
+                from: "run-1".into(),
+                to: Some("run-2".into())
+            }
+        );
+        assert_eq!(
+            parse_command("/graph diff @2026-01-31"),
+            Command::GraphDiff {
+                from: "@2026-01-31".into(),
+                to: None
+            }
+        );
+        assert_eq!(parse_command("/graph diff"), Command::Help);
+        assert_eq!(
+            parse_command("/graph subgraph billing --depth 2 service --mermaid"),
+            Command::GraphSubgraph {
+                query: "billing service".into(),
+                depth: 2,
+                mermaid: true
+            }
+        );
+        assert_eq!(
+            parse_command("/graph subgraph billing"),
+            Command::GraphSubgraph {
+                query: "billing".into(),
+                depth: 1,
+                mermaid: false
+            }
+        );
+        assert_eq!(parse_command("/graph subgraph --depth 2"), Command::Help);
+        assert_eq!(
+            parse_command("/graph subgraph billing --depth x"),
+            Command::Help
+        );
+        assert_eq!(
+            parse_graph_snapshot("run-1", "current"),
+            Some(GraphSnapshot::current("run-1"))
+        );
+        assert_eq!(
+            parse_graph_snapshot("@2026-01-31", "current")
+                .and_then(|snapshot| snapshot.as_of)
+                .map(|as_of| as_of.to_rfc3339()),
+            Some("2026-01-31T23:59:59+00:00".to_string())
+        );
+        assert_eq!(parse_graph_snapshot("run-1@yesterday", "current"), None);
         assert_eq!(parse_command("/session list"), Command::SessionList);
+        assert_eq!(
+            parse_command("/session tag Billing"),
+            Command::SessionTag("Billing".into())
+        );
+        assert_eq!(parse_command("/session tag"), Command::Help);
+        assert_eq!(
+            parse_command("/session search duckdb  migration"),
+            Command::SessionSearch("duckdb migration".into())
+        );
         assert_eq!(parse_command("/session new"), Command::SessionNew(None));
         assert_eq!(
             parse_command("/session new s2"),
@@ -1166,6 +3388,172 @@ mod tests {
             parse_command("/spec nested/path/my.spec"),
             Command::RunSpec(PathBuf::from("nested/path/my.spec"))
         );
+        assert_eq!(parse_command("/spec"), Command::CompleteSpec(String::new()));
+        assert_eq!(
+            parse_command("/spec rev\t\n"),
+            Command::CompleteSpec("rev".into())
+        );
+        assert_eq!(
+            parse_command("/spec run rev\t"),
+            Command::CompleteSpec("rev".into())
+        );
+        assert_eq!(
+            parse_command("/spec review"),
+            Command::RunSpec("review".into())
+        );
+        assert_eq!(parse_command("/workspace"), Command::WorkspaceList);
+        assert_eq!(
+            parse_command("/workspace add ../api backend"),
+            Command::WorkspaceAdd {
+                path: PathBuf::from("../api"),
+                name: Some("backend".into()),
+            }
+        );
+        assert_eq!(
+            parse_command("/ws focus backend"),
+            Command::WorkspaceFocus("backend".into())
+        );
+        assert_eq!(
+            parse_command("/workspace remove backend"),
+            Command::WorkspaceRemove("backend".into())
+        );
+        assert_eq!(parse_command("/workspace focus"), Command::Help);
+        assert_eq!(
+            parse_command("/listen export srt notes/standup.srt"),
+            Command::ListenExport {
+                format: "srt".into(),
+                path: PathBuf::from("notes/standup.srt"),
+            }
+        );
+        assert_eq!(parse_command("/listen export md"), Command::Help);
+        assert_eq!(
+            parse_command("/listen file recordings/weekly sync.m4a"),
+            Command::ListenFile(PathBuf::from("recordings/weekly sync.m4a"))
+        );
+        assert_eq!(parse_command("/listen file"), Command::Help);
+        assert_eq!(parse_command("/handsfree on"), Command::HandsFreeOn);
+        assert_eq!(parse_command("/handsfree off"), Command::HandsFreeOff);
+        assert_eq!(parse_command("/handsfree"), Command::HandsFreeStatus);
+        assert_eq!(parse_command("/handsfree maybe"), Command::Help);
+        assert_eq!(
+            parse_command("/ci ingest target/junit.xml junit"),
+            Command::CiIngest {
+                path: PathBuf::from("target/junit.xml"),
+                format: Some("junit".into()),
+            }
+        );
+        assert_eq!(
+            parse_command("/forget --dry-run --session my old address"),
+            Command::Forget {
+                query: "my old address".into(),
+                dry_run: true,
+                session_only: true,
+            }
+        );
+        assert_eq!(
+            parse_command("/forget #42"),
+            Command::Forget {
+                query: "#42".into(),
+                dry_run: false,
+                session_only: false,
+            }
+        );
+        assert_eq!(parse_command("/forget --dry-run"), Command::Help);
+        assert_eq!(parse_command("/compact"), Command::Compact);
+        assert_eq!(
+            parse_command("/retry"),
+            Command::Retry {
+                temperature: None,
+                provider: None,
+            }
+        );
+        assert_eq!(
+            parse_command("/retry --temperature 1.1 --provider openai/gpt-4o"),
+            Command::Retry {
+                temperature: Some(1.1),
+                provider: Some("openai/gpt-4o".into()),
+            }
+        );
+        assert_eq!(parse_command("/retry --temperature hot"), Command::Help);
+        assert_eq!(parse_command("/variants"), Command::Variants);
+        assert_eq!(parse_command("/variants pick 2"), Command::VariantsPick(2));
+        assert_eq!(parse_command("/variants pick two"), Command::Help);
+        assert_eq!(parse_command("/reminders"), Command::Reminders);
+        assert_eq!(
+            parse_command("/reminders cancel #3"),
+            Command::RemindersCancel(3)
+        );
+        assert_eq!(parse_command("/reminders cancel"), Command::Help);
+        assert_eq!(parse_command("/profile"), Command::ProfileShow);
+        assert_eq!(parse_command("/profile edit"), Command::ProfileEdit);
+        assert_eq!(
+            parse_command("/profile forget prefers tabs"),
+            Command::ProfileForget("prefers tabs".into())
+        );
+        assert_eq!(parse_command("/profile forget"), Command::Help);
+        assert_eq!(
+            parse_command("/roundtable --drafts Ship on Friday?"),
+            Command::Roundtable {
+                question: "Ship on Friday?".into(),
+                show_drafts: true,
+            }
+        );
+        assert_eq!(parse_command("/roundtable"), Command::Help);
+        assert_eq!(
+            parse_command("/tokens  Hello,  world"),
+            Command::Tokens("Hello,  world".into())
+        );
+        assert_eq!(parse_command("/tokens"), Command::Help);
+        assert_eq!(
+            parse_command("/tool run  echo {\"message\": \"a  b\"}"),
+            Command::ToolRun {
+                name: "echo".into(),
+                args: "{\"message\": \"a  b\"}".into(),
+            }
+        );
+        assert_eq!(
+            parse_command("/tool run list_files"),
+            Command::ToolRun {
+                name: "list_files".into(),
+                args: String::new(),
+            }
+        );
+        assert_eq!(
+            parse_command("/tool schema file_read"),
+            Command::ToolSchema("file_read".into())
+        );
+        assert_eq!(parse_command("/tool run"), Command::Help);
+        assert_eq!(
+            parse_command("/tools stats"),
+            Command::ToolsStats { all_agents: false }
+        );
+        assert_eq!(
+            parse_command("/tools stats all"),
+            Command::ToolsStats { all_agents: true }
+        );
+        assert_eq!(parse_command("/tools"), Command::Help);
+        assert_eq!(parse_command("/mesh status"), Command::MeshStatus);
+        assert_eq!(parse_command("/mesh"), Command::Help);
+        assert_eq!(
+            parse_command("/scroll up"),
+            Command::Scroll(ScrollCommand::PageUp)
+        );
+        assert_eq!(
+            parse_command("/pgdn"),
+            Command::Scroll(ScrollCommand::PageDown)
+        );
+        assert_eq!(
+            parse_command("/scroll prev-tool"),
+            Command::Scroll(ScrollCommand::PreviousTool)
+        );
+        assert_eq!(
+            parse_command("/find deploy step"),
+            Command::Find(FindCommand::Query("deploy step".into()))
+        );
+        assert_eq!(
+            parse_command("/find next"),
+            Command::Find(FindCommand::Next)
+        );
         assert_eq!(parse_command("hello"), Command::Message("hello".into()));
         assert_eq!(parse_command("   "), Command::Empty);
     }
@@ -1184,6 +3572,13 @@ mod tests {
             reasoning: None,
             reasoning_summary: None,
             graph_debug: None,
+            request_hashes: Vec::new(),
+            context_degradation: Vec::new(),
+            verification: None,
+            language: None,
+            timings: None,
+            route: None,
+            compression: None,
         };
         let lines = CliState::format_reasoning_messages(&output);
         assert_eq!(
@@ -1203,6 +3598,7 @@ mod tests {
                 requested: 5,
                 returned: 2,
             },
+            scope: Default::default(),
             matches: Vec::new(),
         };
         let invocation = ToolInvocation {
@@ -1211,6 +3607,7 @@ mod tests {
             success: true,
             output: Some("ok".to_string()),
             error: None,
+            cached: false,
         };
         let output = AgentOutput {
             response: String::new(),
@@ -1224,6 +3621,13 @@ mod tests {
             reasoning: None,
             reasoning_summary: None,
             graph_debug: None,
+            request_hashes: Vec::new(),
+            context_degradation: Vec::new(),
+            verification: None,
+            language: None,
+            timings: None,
+            route: None,
+            compression: None,
         };
         let lines = CliState::format_reasoning_messages(&output);
         assert!(lines[0].starts_with("Recall: semantic"));
@@ -1250,6 +3654,13 @@ mod tests {
             reasoning: None,
             reasoning_summary: None,
             graph_debug: None,
+            request_hashes: Vec::new(),
+            context_degradation: Vec::new(),
+            verification: None,
+            language: None,
+            timings: None,
+            route: None,
+            compression: None,
         };
         let lines = CliState::format_reasoning_messages(&output);
         assert_eq!(lines[2], "Tokens: P 4 C 6 T 10");
@@ -1282,10 +3693,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "info".into(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents,
             default_agent: Some("test".into()),
         };
@@ -1311,6 +3736,7 @@ mod tests {
         // List sessions should include s2
         let out4 = cli.handle_line("/session list").await.unwrap().unwrap();
         assert!(out4.contains("s2"));
+        assert!(out4.contains("messages)"));
     }
 
     #[cfg_attr(
@@ -1345,10 +3771,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "info".into(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents,
             default_agent: Some("coder".into()),
         };
@@ -1396,10 +3836,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "debug".into(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents,
             default_agent: Some("test".into()),
         };
@@ -1443,10 +3897,24 @@ mod tests {
             },
             logging: LoggingConfig {
                 level: "info".into(),
+                ..LoggingConfig::default()
             },
             audio: AudioConfig::default(),
             mesh: crate::config::MeshConfig::default(),
+            sync: crate::config::SyncConfig::default(),
             plugins: PluginConfig::default(),
+            tools: HashMap::new(),
+            composite_tools: HashMap::new(),
+            roundtable: crate::config::RoundtableConfig::default(),
+            time: crate::config::TimeConfig::default(),
+            specs: crate::config::SpecsConfig::default(),
+            user_profile: crate::config::UserProfileConfig::default(),
+            privacy: crate::config::PrivacyConfig::default(),
+            events: crate::config::EventsConfig::default(),
+            sessions: crate::config::SessionsConfig::default(),
+            graph: crate::config::GraphConfig::default(),
+            policy_file: None,
+            tool_timeout_secs: None,
             agents,
             default_agent: Some("test".into()),
         };
@@ -1460,4 +3928,252 @@ mod tests {
         assert!(out.contains("/agents") || out.contains("agents"));
         assert!(out.contains("/list") || out.contains("list"));
     }
+
+    #[cfg_attr(
+        target_os = "macos",
+        ignore = "SystemConfiguration unavailable in sandboxed macOS runners"
+    )]
+    #[tokio::test]
+    async fn test_policy_rules_are_edited_from_the_repl() {
+        formatting::set_plain_text_mode(true);
+        let dir = tempdir().unwrap();
+        let mut agents = HashMap::new();
+        agents.insert("test".to_string(), AgentProfile::default());
+        let config = AppConfig {
+            database: DatabaseConfig {
+                path: dir.path().join("cli_policy.duckdb"),
+            },
+            model: ModelConfig {
+                provider: "mock".into(),
+                model_name: None,
+                embeddings_model: None,
+                api_key_source: None,
+                temperature: 0.7,
+            },
+            agents,
+            default_agent: Some("test".into()),
+            ..AppConfig::default()
+        };
+        let mut cli = CliState::new_with_config(config).unwrap();
+
+        // Without stored rules everything is allowed by a built-in rule
+        let out = cli.handle_line("/policy list").await.unwrap().unwrap();
+        assert!(out.contains("#1 allow tool_call on * for * [built in]"));
+
+        let out = cli
+            .handle_line("/policy add * tool_call echo allow")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(out.contains("Added policy rule #1: allow tool_call on echo for *"));
+        assert!(out.contains("need approval"));
+        cli.handle_line("/policy add * tool_call bash deny")
+            .await
+            .unwrap();
+        assert_eq!(cli.agent.policy_engine().rule_count(), 2);
+
+        let out = cli.handle_line("/policy remove 1").await.unwrap().unwrap();
+        assert!(out.contains("Removed policy rule #1"));
+        let out = cli.handle_line("/policy list").await.unwrap().unwrap();
+        assert!(out.contains("#1 deny tool_call on bash for *"));
+        assert!(!out.contains("echo"));
+        assert!(!out.contains("[built in]"));
+
+        let out = cli.handle_line("/policy remove 5").await.unwrap().unwrap();
+        assert!(out.contains("No stored policy rule #5"));
+
+        // The rule form needs a terminal; otherwise the arguments are explained
+        let out = cli.handle_line("/policy add").await.unwrap().unwrap();
+        assert!(out.starts_with("Usage: /policy add"));
+        let out = cli.handle_line("/switch").await.unwrap().unwrap();
+        assert!(out.starts_with("Usage: /switch"));
+
+        let stored = PolicyEngine::load_from_persistence(&cli.persistence).unwrap();
+        assert_eq!(stored.rule_count(), 1);
+    }
+
+    #[cfg_attr(
+        target_os = "macos",
+        ignore = "SystemConfiguration unavailable in sandboxed macOS runners"
+    )]
+    #[tokio::test]
+    async fn test_policy_file_is_merged_and_reloaded() {
+        formatting::set_plain_text_mode(true);
+        let dir = tempdir().unwrap();
+        let policy_path = dir.path().join("spec-ai.policy.toml");
+        std::fs::write(
+            &policy_path,
+            "[[rules]]\nagent = \"*\"\naction = \"tool_call\"\nresource = \"echo\"\neffect = \"allow\"\n",
+        )
+        .unwrap();
+        let mut agents = HashMap::new();
+        agents.insert("test".to_string(), AgentProfile::default());
+        let config = AppConfig {
+            database: DatabaseConfig {
+                path: dir.path().join("cli_policy_file.duckdb"),
+            },
+            model: ModelConfig {
+                provider: "mock".into(),
+                model_name: None,
+                embeddings_model: None,
+                api_key_source: None,
+                temperature: 0.7,
+            },
+            agents,
+            default_agent: Some("test".into()),
+            policy_file: Some(policy_path.clone()),
+            ..AppConfig::default()
+        };
+        let mut cli = CliState::new_with_config(config).unwrap();
+
+        // The file's rules replace the default allow-all
+        let out = cli.handle_line("/policy list").await.unwrap().unwrap();
+        assert!(out.contains("#1 allow tool_call on echo for * [policy file]"));
+        assert!(!out.contains("[built in]"));
+
+        cli.handle_line("/policy add * tool_call bash deny")
+            .await
+            .unwrap();
+        let out = cli.handle_line("/policy list").await.unwrap().unwrap();
+        assert!(out.contains("#1 deny tool_call on bash for *"));
+        assert!(out.contains("#2 allow tool_call on echo for * [policy file]"));
+
+        std::fs::write(
+            &policy_path,
+            "[[rules]]\nagent = \"*\"\naction = \"tool_call\"\nresource = \"*\"\neffect = \"allow\"\n",
+        )
+        .unwrap();
+        let out = cli.handle_line("/policy reload").await.unwrap().unwrap();
+        assert!(out.contains("2 rule(s) active"));
+        let out = cli.handle_line("/policy list").await.unwrap().unwrap();
+        assert!(out.contains("#2 allow tool_call on * for * [policy file]"));
+
+        // Only the database rules are stored
+        let stored = PolicyEngine::load_from_persistence(&cli.persistence).unwrap();
+        assert_eq!(stored.rule_count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(
+        target_os = "macos",
+        ignore = "SystemConfiguration unavailable in sandboxed macOS runners"
+    )]
+    #[tokio::test]
+    async fn test_interrupt_cancels_the_running_tool() {
+        formatting::set_plain_text_mode(true);
+        let dir = tempdir().unwrap();
+        let mut agents = HashMap::new();
+        agents.insert("test".to_string(), AgentProfile::default());
+        let config = AppConfig {
+            database: DatabaseConfig {
+                path: dir.path().join("cli_interrupt.duckdb"),
+            },
+            model: ModelConfig {
+                provider: "mock".into(),
+                model_name: None,
+                embeddings_model: None,
+                api_key_source: None,
+                temperature: 0.7,
+            },
+            agents,
+            default_agent: Some("test".into()),
+            ..AppConfig::default()
+        };
+        let mut cli = CliState::new_with_config(config).unwrap();
+
+        let started = std::time::Instant::now();
+        let out = cli
+            .handle_line_until(
+                "/tool run bash {\"command\": \"sleep 30\"}",
+                tokio::time::sleep(std::time::Duration::from_millis(200)),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(out.contains("Tool 'bash' was cancelled"), "{}", out);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // The next line is not cancelled by the earlier interrupt
+        let out = cli
+            .handle_line_until(
+                "/tool run bash {\"command\": \"echo done\"}",
+                std::future::pending(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(out.contains("bash succeeded"), "{}", out);
+    }
+
+    #[cfg_attr(
+        target_os = "macos",
+        ignore = "SystemConfiguration unavailable in sandboxed macOS runners"
+    )]
+    #[tokio::test]
+    async fn test_reminders_are_listed_delivered_and_cancelled() {
+        formatting::set_plain_text_mode(true);
+        let dir = tempdir().unwrap();
+        let mut agents = HashMap::new();
+        agents.insert("test".to_string(), AgentProfile::default());
+        let config = AppConfig {
+            database: DatabaseConfig {
+                path: dir.path().join("cli_reminders.duckdb"),
+            },
+            model: ModelConfig {
+                provider: "mock".into(),
+                model_name: None,
+                embeddings_model: None,
+                api_key_source: None,
+                temperature: 0.7,
+            },
+            agents,
+            default_agent: Some("test".into()),
+            ..AppConfig::default()
+        };
+        let mut cli = CliState::new_with_config(config).unwrap();
+        let session = cli.agent.session_id().to_string();
+        let now = chrono::Utc::now();
+
+        let out = cli.handle_line("/reminders").await.unwrap().unwrap();
+        assert!(out.contains("No pending reminders"));
+
+        cli.persistence
+            .add_reminder(
+                &session,
+                "check the deploy",
+                now - chrono::Duration::minutes(1),
+            )
+            .unwrap();
+        cli.persistence
+            .add_reminder(
+                "other",
+                "water the plants",
+                now - chrono::Duration::minutes(1),
+            )
+            .unwrap();
+        let later = cli
+            .persistence
+            .add_reminder(
+                &session,
+                "rotate the keys",
+                now + chrono::Duration::hours(1),
+            )
+            .unwrap();
+
+        let out = cli.deliver_reminders().unwrap();
+        assert!(out.contains("Reminder: check the deploy"));
+        assert!(out.contains("1 reminder(s) posted into other sessions."));
+        assert!(cli.deliver_reminders().is_none());
+
+        let out = cli.handle_line("/reminders").await.unwrap().unwrap();
+        assert!(out.contains(&format!("#{}", later.id)));
+        assert!(out.contains("rotate the keys"));
+        assert!(!out.contains("check the deploy"));
+
+        let cancel = format!("/reminders cancel {}", later.id);
+        let out = cli.handle_line(&cancel).await.unwrap().unwrap();
+        assert!(out.contains("Cancelled reminder"));
+        let out = cli.handle_line(&cancel).await.unwrap().unwrap();
+        assert!(out.contains("No pending reminder"));
+    }
 }
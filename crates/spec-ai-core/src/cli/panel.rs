@@ -0,0 +1,291 @@
+//! Rendering for spec- and tool-declared panels
+//!
+//! `DynamicPanel` interprets a [`PanelSpec`] into text lines; `PanelPane` keeps
+//! the panels of the current run in declaration order and applies updates by id.
+//! `PanelProgressSink` feeds it from the progress stream, so the REPL reprints the
+//! pane as soon as a spec or tool publishes a panel, in place of a side pane.
+
+use super::text_utils::{display_width, pad_to_width, truncate_with_ellipsis};
+use crate::panel::{PanelContent, PanelSpec, StepStatus};
+use crate::progress::{ProgressEvent, ProgressSink};
+use std::sync::{Arc, Mutex};
+
+/// Widget that renders a single declarative panel
+#[derive(Debug, Clone)]
+pub struct DynamicPanel {
+    spec: PanelSpec,
+}
+
+impl DynamicPanel {
+    pub fn new(spec: PanelSpec) -> Self {
+        Self { spec }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.spec.id
+    }
+
+    pub fn spec(&self) -> &PanelSpec {
+        &self.spec
+    }
+
+    /// Render the panel into lines no wider than `width` cells
+    pub fn render(&self, width: usize) -> Vec<String> {
+        let width = width.max(8);
+        let mut lines = vec![truncate_with_ellipsis(
+            &format!("▌{}", self.spec.display_title()),
+            width,
+            "…",
+        )];
+
+        match &self.spec.content {
+            PanelContent::KeyValue { entries } => {
+                let key_width = entries
+                    .iter()
+                    .map(|e| display_width(&e.key))
+                    .max()
+                    .unwrap_or(0)
+                    .min(width / 2);
+                for entry in entries {
+                    let line =
+                        format!("  {}  {}", pad_to_width(&entry.key, key_width), entry.value);
+                    lines.push(truncate_with_ellipsis(&line, width, "…"));
+                }
+            }
+            PanelContent::Table { columns, rows } => {
+                lines.extend(render_table(columns, rows, width));
+            }
+            PanelContent::Progress { steps } => {
+                let done = steps
+                    .iter()
+                    .filter(|s| matches!(s.status, StepStatus::Done | StepStatus::Skipped))
+                    .count();
+                for step in steps {
+                    let marker = match step.status {
+                        StepStatus::Pending => "○",
+                        StepStatus::Running => "◐",
+                        StepStatus::Done => "●",
+                        StepStatus::Failed => "✗",
+                        StepStatus::Skipped => "–",
+                    };
+                    let mut line = format!("  {} {}", marker, step.label);
+                    if let Some(detail) = step.detail.as_deref().filter(|d| !d.is_empty()) {
+                        line.push_str(&format!(" — {}", detail));
+                    }
+                    lines.push(truncate_with_ellipsis(&line, width, "…"));
+                }
+                lines.push(format!("  {}/{} complete", done, steps.len()));
+            }
+        }
+
+        lines
+    }
+}
+
+/// Shrink column widths proportionally until the table fits
+fn render_table(columns: &[String], rows: &[Vec<String>], width: usize) -> Vec<String> {
+    let mut widths: Vec<usize> = columns.iter().map(|c| display_width(c)).collect();
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate().take(widths.len()) {
+            widths[idx] = widths[idx].max(display_width(cell));
+        }
+    }
+
+    // Two leading spaces plus a " │ " separator between columns
+    let chrome = 2 + 3 * columns.len().saturating_sub(1);
+    let available = width.saturating_sub(chrome).max(columns.len());
+    while widths.iter().sum::<usize>() > available {
+        if let Some(widest) = widths.iter_mut().max() {
+            *widest -= 1;
+        }
+    }
+
+    let format_row = |cells: &[String]| {
+        let rendered: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(idx, w)| {
+                let cell = cells.get(idx).map(String::as_str).unwrap_or("");
+                pad_to_width(&truncate_with_ellipsis(cell, *w, "…"), *w)
+            })
+            .collect();
+        format!("  {}", rendered.join(" │ ")).trim_end().to_string()
+    };
+
+    let mut lines = vec![format_row(columns)];
+    let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+    lines.push(format!("  {}", rule.join("─┼─")));
+    for row in rows {
+        lines.push(format_row(row));
+    }
+    lines
+}
+
+/// Ordered collection of panels for the current run
+#[derive(Debug, Clone, Default)]
+pub struct PanelPane {
+    panels: Vec<DynamicPanel>,
+}
+
+impl PanelPane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panels.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.panels.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.panels.clear();
+    }
+
+    /// Insert a panel, replacing any existing panel with the same id in place
+    pub fn upsert(&mut self, spec: PanelSpec) {
+        match self.panels.iter_mut().find(|p| p.id() == spec.id) {
+            Some(existing) => *existing = DynamicPanel::new(spec),
+            None => self.panels.push(DynamicPanel::new(spec)),
+        }
+    }
+
+    /// Render all panels separated by blank lines
+    pub fn render(&self, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (idx, panel) in self.panels.iter().enumerate() {
+            if idx > 0 {
+                lines.push(String::new());
+            }
+            lines.extend(panel.render(width));
+        }
+        lines
+    }
+}
+
+/// Progress sink that applies the panels an event carries to a shared pane
+/// and prints the pane; events without panels go to `inner`
+pub struct PanelProgressSink {
+    pane: Arc<Mutex<PanelPane>>,
+    width: usize,
+    inner: Arc<dyn ProgressSink>,
+}
+
+impl PanelProgressSink {
+    pub fn new(pane: Arc<Mutex<PanelPane>>, width: usize, inner: Arc<dyn ProgressSink>) -> Self {
+        Self { pane, width, inner }
+    }
+}
+
+impl ProgressSink for PanelProgressSink {
+    fn report(&self, event: ProgressEvent) {
+        if event.panels.is_empty() {
+            self.inner.report(event);
+            return;
+        }
+        let Ok(mut pane) = self.pane.lock() else {
+            return;
+        };
+        for panel in event.panels {
+            pane.upsert(panel);
+        }
+        println!("{}", pane.render(self.width).join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panel::{PanelEntry, PanelStep};
+    use crate::progress::{ChannelProgressSink, ProgressReporter};
+
+    fn progress(id: &str, statuses: &[StepStatus]) -> PanelSpec {
+        PanelSpec {
+            id: id.to_string(),
+            title: Some("Deploy".to_string()),
+            content: PanelContent::Progress {
+                steps: statuses
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| PanelStep {
+                        label: format!("step {}", i + 1),
+                        status: *s,
+                        detail: None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn pane_updates_panels_by_id() {
+        let mut pane = PanelPane::new();
+        pane.upsert(progress(
+            "deploy",
+            &[StepStatus::Running, StepStatus::Pending],
+        ));
+        pane.upsert(PanelSpec {
+            id: "env".to_string(),
+            title: None,
+            content: PanelContent::KeyValue {
+                entries: vec![PanelEntry {
+                    key: "region".to_string(),
+                    value: "eu-west".to_string(),
+                }],
+            },
+        });
+        pane.upsert(progress("deploy", &[StepStatus::Done, StepStatus::Running]));
+
+        assert_eq!(pane.len(), 2);
+        let rendered = pane.render(60);
+        assert_eq!(rendered[0], "▌Deploy");
+        assert!(rendered.iter().any(|l| l == "  1/2 complete"));
+        assert!(rendered
+            .iter()
+            .any(|l| l.contains("region") && l.contains("eu-west")));
+    }
+
+    #[test]
+    fn progress_events_update_the_pane_during_a_run() {
+        let pane = Arc::new(Mutex::new(PanelPane::new()));
+        let (inner, mut rx) = ChannelProgressSink::new();
+        let reporter = ProgressReporter::new(Arc::new(PanelProgressSink::new(
+            pane.clone(),
+            60,
+            Arc::new(inner),
+        )));
+
+        reporter.panels(
+            "deploy.spec",
+            vec![progress("deploy", &[StepStatus::Pending])],
+        );
+        reporter.step("tool", "running deploy", None, None);
+        reporter.panels("deploy", vec![progress("deploy", &[StepStatus::Done])]);
+
+        let rendered = pane.lock().unwrap().render(60);
+        assert!(rendered.iter().any(|l| l == "  1/1 complete"));
+        // Only the ordinary event reaches the inner sink
+        assert_eq!(rx.try_recv().unwrap().operation, "tool");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn tables_fit_requested_width() {
+        let panel = DynamicPanel::new(PanelSpec {
+            id: "hosts".to_string(),
+            title: None,
+            content: PanelContent::Table {
+                columns: vec!["host".to_string(), "status".to_string()],
+                rows: vec![vec![
+                    "a-very-long-hostname.internal.example".to_string(),
+                    "healthy".to_string(),
+                ]],
+            },
+        });
+        for line in panel.render(30) {
+            assert!(display_width(&line) <= 30, "too wide: {:?}", line);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Text progress widgets for the REPL
+//!
+//! `Spinner`, `Throbber` and `ProgressBar` render to plain strings so the same
+//! widgets work in a redrawing terminal and in piped output. `render_progress_line`
+//! turns a [`ProgressEvent`] into the single textual line the REPL prints, and
+//! `render_tool_output` prefixes what a running tool writes with its name.
+//! Progress lines go to stderr, and only when it is a terminal, so they never
+//! mix with answers piped from stdout.
+
+use super::text_utils::{display_width, truncate_with_ellipsis};
+use crate::progress::{ProgressEvent, ProgressSink, ProgressState};
+use crate::tools::ToolOutputSink;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const THROBBER_FRAMES: &[&str] = &["[=   ]", "[ =  ]", "[  = ]", "[   =]", "[  = ]", "[ =  ]"];
+
+/// Rotating single-cell indicator for operations without a known total
+#[derive(Debug, Default)]
+pub struct Spinner {
+    frame: usize,
+}
+
+impl Spinner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance one frame and return it
+    pub fn tick(&mut self) -> &'static str {
+        let frame = SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()];
+        self.frame = self.frame.wrapping_add(1);
+        frame
+    }
+}
+
+/// Bouncing indicator, wider than a spinner, for indeterminate progress bars
+#[derive(Debug, Default)]
+pub struct Throbber {
+    frame: usize,
+}
+
+impl Throbber {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance one frame and return it
+    pub fn tick(&mut self) -> &'static str {
+        let frame = THROBBER_FRAMES[self.frame % THROBBER_FRAMES.len()];
+        self.frame = self.frame.wrapping_add(1);
+        frame
+    }
+}
+
+/// Fixed-width determinate progress bar
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    width: usize,
+}
+
+impl ProgressBar {
+    /// Create a bar whose filled area spans `width` cells (brackets excluded)
+    pub fn new(width: usize) -> Self {
+        Self {
+            width: width.max(1),
+        }
+    }
+
+    /// Render the bar for `fraction` in `[0.0, 1.0]`
+    pub fn render(&self, fraction: f32) -> String {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let filled = (fraction * self.width as f32).round() as usize;
+        format!(
+            "[{}{}] {:>3}%",
+            "#".repeat(filled),
+            "-".repeat(self.width - filled),
+            (fraction * 100.0).round() as u32
+        )
+    }
+}
+
+/// Render a progress event as one line of plain text, at most `width` cells wide
+pub fn render_progress_line(event: &ProgressEvent, width: usize) -> String {
+    let indicator = match event.state {
+        ProgressState::Started => "…".to_string(),
+        ProgressState::Running => match event.fraction() {
+            Some(fraction) => ProgressBar::new(20).render(fraction),
+            None => match (event.current, event.total) {
+                (Some(current), None) => format!("({})", current),
+                _ => "…".to_string(),
+            },
+        },
+        ProgressState::Finished => "✓".to_string(),
+        ProgressState::Failed => "✗".to_string(),
+    };
+
+    let prefix = format!("[{}] {} ", event.operation, indicator);
+    let remaining = width.saturating_sub(display_width(&prefix));
+    format!(
+        "{}{}",
+        prefix,
+        truncate_with_ellipsis(&event.message, remaining, "...")
+    )
+}
+
+/// Sink that prints each event as a progress line on stderr, when stderr is
+/// a terminal
+pub struct PrintProgressSink {
+    width: usize,
+    enabled: bool,
+    printed: AtomicUsize,
+}
+
+impl PrintProgressSink {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            enabled: std::io::stderr().is_terminal(),
+            printed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of lines printed so far
+    pub fn printed(&self) -> usize {
+        self.printed.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressSink for PrintProgressSink {
+    fn report(&self, event: ProgressEvent) {
+        if !self.enabled {
+            return;
+        }
+        let line = render_progress_line(&event, self.width);
+        if writeln!(std::io::stderr().lock(), "{}", line).is_ok() {
+            self.printed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Render a tool's partial output as lines at most `width` cells wide,
+/// each prefixed with the tool's name
+pub fn render_tool_output(tool: &str, text: &str, width: usize) -> Vec<String> {
+    let prefix = format!("[{}] │ ", tool);
+    let remaining = width.saturating_sub(display_width(&prefix));
+    text.lines()
+        .map(|line| {
+            format!(
+                "{}{}",
+                prefix,
+                truncate_with_ellipsis(line.trim_end(), remaining, "...")
+            )
+        })
+        .collect()
+}
+
+/// Sink that prints tools' output on stdout as it is written
+pub struct PrintToolOutputSink {
+    width: usize,
+}
+
+impl PrintToolOutputSink {
+    pub fn new(width: usize) -> Self {
+        Self { width }
+    }
+}
+
+impl ToolOutputSink for PrintToolOutputSink {
+    fn output(&self, tool: &str, text: &str) {
+        for line in render_tool_output(tool, text, self.width) {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(state: ProgressState, current: Option<u64>, total: Option<u64>) -> ProgressEvent {
+        ProgressEvent {
+            operation: "bootstrap".to_string(),
+            state,
+            message: "scanning plugins".to_string(),
+            current,
+            total,
+            panels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn progress_bar_renders_fraction() {
+        let bar = ProgressBar::new(10);
+        assert_eq!(bar.render(0.0), "[----------]   0%");
+        assert_eq!(bar.render(0.5), "[#####-----]  50%");
+        assert_eq!(bar.render(2.0), "[##########] 100%");
+    }
+
+    #[test]
+    fn spinner_cycles_frames() {
+        let mut spinner = Spinner::new();
+        let first = spinner.tick();
+        for _ in 1..SPINNER_FRAMES.len() {
+            spinner.tick();
+        }
+        assert_eq!(spinner.tick(), first);
+        assert_eq!(Throbber::new().tick(), THROBBER_FRAMES[0]);
+    }
+
+    #[test]
+    fn progress_lines_fit_width() {
+        let line = render_progress_line(&event(ProgressState::Running, Some(1), Some(2)), 80);
+        assert!(line.starts_with("[bootstrap] [##########----------]  50%"));
+        assert!(line.ends_with("scanning plugins"));
+
+        let narrow = render_progress_line(&event(ProgressState::Finished, None, None), 20);
+        assert!(display_width(&narrow) <= 20);
+        assert!(narrow.contains('✓'));
+    }
+
+    #[test]
+    fn progress_is_silent_off_a_terminal() {
+        let sink = PrintProgressSink {
+            width: 80,
+            enabled: false,
+            printed: AtomicUsize::new(0),
+        };
+        sink.report(event(ProgressState::Started, None, None));
+        assert_eq!(sink.printed(), 0);
+    }
+
+    #[test]
+    fn tool_output_is_prefixed_per_line() {
+        let lines = render_tool_output("bash", "compiling a\ncompiling b\n", 80);
+        assert_eq!(lines, vec!["[bash] │ compiling a", "[bash] │ compiling b"]);
+
+        let long = "x".repeat(100);
+        let narrow = render_tool_output("bash", &long, 20);
+        assert!(display_width(&narrow[0]) <= 20);
+        assert!(narrow[0].ends_with("..."));
+    }
+}
@@ -0,0 +1,358 @@
+//! Conversation scrollback with paging, search and tool-call navigation
+//!
+//! The REPL records every rendered exchange here as wrapped lines. A viewport
+//! offset (counted from the bottom) supports paging back through history, and
+//! searches highlight matches and move the viewport to them.
+
+use super::text_utils::wrap_text;
+
+/// Lines kept before the oldest history is discarded
+const DEFAULT_CAPACITY: usize = 5_000;
+
+/// Kind of a scrollback line, used for navigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    User,
+    Assistant,
+    Tool,
+    System,
+}
+
+#[derive(Debug, Clone)]
+struct ScrollbackLine {
+    text: String,
+    kind: LineKind,
+    /// True for the first line of an entry (navigation targets)
+    entry_start: bool,
+}
+
+/// Scroll/search state for the conversation pane
+#[derive(Debug)]
+pub struct Scrollback {
+    lines: Vec<ScrollbackLine>,
+    capacity: usize,
+    width: usize,
+    /// Index of the first visible line, or None to follow the tail
+    top: Option<usize>,
+    query: Option<String>,
+    matches: Vec<usize>,
+    current_match: Option<usize>,
+}
+
+impl Scrollback {
+    pub fn new(width: usize) -> Self {
+        Self::with_capacity(width, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(width: usize, capacity: usize) -> Self {
+        Self {
+            lines: Vec::new(),
+            capacity: capacity.max(1),
+            width: width.max(1),
+            top: None,
+            query: None,
+            matches: Vec::new(),
+            current_match: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Append an entry, wrapping it to the pane width
+    pub fn push(&mut self, kind: LineKind, text: &str) {
+        for (idx, line) in wrap_text(text, self.width).into_iter().enumerate() {
+            self.lines.push(ScrollbackLine {
+                text: line,
+                kind,
+                entry_start: idx == 0,
+            });
+        }
+
+        if self.lines.len() > self.capacity {
+            let overflow = self.lines.len() - self.capacity;
+            self.lines.drain(..overflow);
+            self.top = self.top.map(|top| top.saturating_sub(overflow));
+        }
+
+        if let Some(query) = self.query.clone() {
+            self.recompute_matches(&query);
+        }
+    }
+
+    /// Scroll one page towards older lines
+    pub fn page_up(&mut self, height: usize) {
+        let current = self.top_for(height);
+        self.top = Some(current.saturating_sub(height.max(1)));
+    }
+
+    /// Scroll one page towards newer lines; reaching the end resumes following
+    pub fn page_down(&mut self, height: usize) {
+        let current = self.top_for(height);
+        let next = current + height.max(1);
+        if next >= self.tail_top(height) {
+            self.top = None;
+        } else {
+            self.top = Some(next);
+        }
+    }
+
+    /// Jump back to the newest lines
+    pub fn scroll_to_bottom(&mut self) {
+        self.top = None;
+    }
+
+    /// Whether the viewport is following new output
+    pub fn is_following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    /// Start a case-insensitive search and jump to the most recent match.
+    /// Returns the number of matching lines.
+    pub fn search(&mut self, query: &str, height: usize) -> usize {
+        let query = query.trim();
+        if query.is_empty() {
+            self.clear_search();
+            return 0;
+        }
+        self.recompute_matches(query);
+        self.query = Some(query.to_string());
+        self.current_match = self.matches.len().checked_sub(1);
+        self.reveal_current_match(height);
+        self.matches.len()
+    }
+
+    /// Move to the next older match, wrapping around
+    pub fn previous_match(&mut self, height: usize) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let idx = match self.current_match {
+            Some(0) | None => self.matches.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.current_match = Some(idx);
+        self.reveal_current_match(height);
+        Some(idx)
+    }
+
+    /// Move to the next newer match, wrapping around
+    pub fn next_match(&mut self, height: usize) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let idx = match self.current_match {
+            Some(i) if i + 1 < self.matches.len() => i + 1,
+            _ => 0,
+        };
+        self.current_match = Some(idx);
+        self.reveal_current_match(height);
+        Some(idx)
+    }
+
+    pub fn clear_search(&mut self) {
+        self.query = None;
+        self.matches.clear();
+        self.current_match = None;
+    }
+
+    /// Move the viewport to the closest tool invocation above the current view
+    pub fn previous_tool(&mut self, height: usize) -> bool {
+        let top = self.top_for(height);
+        let target = self.lines[..top]
+            .iter()
+            .rposition(|l| l.entry_start && l.kind == LineKind::Tool);
+        self.jump_to(target, height)
+    }
+
+    /// Move the viewport to the closest tool invocation below the current view top
+    pub fn next_tool(&mut self, height: usize) -> bool {
+        let top = self.top_for(height);
+        let target = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(top + 1)
+            .find(|(_, l)| l.entry_start && l.kind == LineKind::Tool)
+            .map(|(idx, _)| idx);
+        self.jump_to(target, height)
+    }
+
+    /// Render the visible window. Search matches are wrapped in `highlight`.
+    pub fn render(&self, height: usize, highlight: (&str, &str)) -> Vec<String> {
+        let top = self.top_for(height);
+        let end = (top + height).min(self.lines.len());
+        self.lines[top..end]
+            .iter()
+            .map(|line| match &self.query {
+                Some(query) => highlight_matches(&line.text, query, highlight),
+                None => line.text.clone(),
+            })
+            .collect()
+    }
+
+    /// Status summary such as "lines 10-30 of 120 | match 2/5"
+    pub fn position_summary(&self, height: usize) -> String {
+        let top = self.top_for(height);
+        let end = (top + height).min(self.lines.len());
+        let mut summary = format!("lines {}-{} of {}", top + 1, end, self.lines.len());
+        if let Some(query) = &self.query {
+            match self.current_match {
+                Some(idx) => summary.push_str(&format!(
+                    " | match {}/{} for '{}'",
+                    idx + 1,
+                    self.matches.len(),
+                    query
+                )),
+                None => summary.push_str(&format!(" | no matches for '{}'", query)),
+            }
+        }
+        summary
+    }
+
+    fn tail_top(&self, height: usize) -> usize {
+        self.lines.len().saturating_sub(height)
+    }
+
+    fn top_for(&self, height: usize) -> usize {
+        self.top
+            .unwrap_or_else(|| self.tail_top(height))
+            .min(self.tail_top(height))
+    }
+
+    fn jump_to(&mut self, target: Option<usize>, height: usize) -> bool {
+        match target {
+            Some(idx) => {
+                self.top = Some(idx.min(self.tail_top(height)));
+                if self.top == Some(self.tail_top(height)) {
+                    self.top = None;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reveal_current_match(&mut self, height: usize) {
+        let target = self.current_match.map(|i| self.matches[i]);
+        let centered = target.map(|line| line.saturating_sub(height / 2));
+        self.jump_to(centered, height);
+    }
+
+    fn recompute_matches(&mut self, query: &str) {
+        let needle = query.to_lowercase();
+        self.matches = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.text.to_lowercase().contains(&needle))
+            .map(|(idx, _)| idx)
+            .collect();
+        if let Some(current) = self.current_match {
+            if current >= self.matches.len() {
+                self.current_match = self.matches.len().checked_sub(1);
+            }
+        }
+    }
+}
+
+/// Wrap every case-insensitive occurrence of `query` in the given markers
+fn highlight_matches(text: &str, query: &str, (open, close): (&str, &str)) -> String {
+    let lower = text.to_lowercase();
+    let needle = query.to_lowercase();
+    // Lowercasing can change byte lengths for some scripts; skip highlighting then
+    if needle.is_empty() || lower.len() != text.len() {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (start, _) in lower.match_indices(&needle) {
+        if start < cursor {
+            continue;
+        }
+        let end = start + needle.len();
+        out.push_str(&text[cursor..start]);
+        out.push_str(open);
+        out.push_str(&text[start..end]);
+        out.push_str(close);
+        cursor = end;
+    }
+    out.push_str(&text[cursor..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(count: usize) -> Scrollback {
+        let mut sb = Scrollback::new(40);
+        for i in 0..count {
+            let kind = if i % 5 == 0 {
+                LineKind::Tool
+            } else {
+                LineKind::Assistant
+            };
+            sb.push(kind, &format!("line {}", i));
+        }
+        sb
+    }
+
+    #[test]
+    fn paging_moves_viewport_and_resumes_following() {
+        let mut sb = filled(30);
+        assert_eq!(sb.render(10, ("", ""))[0], "line 20");
+
+        sb.page_up(10);
+        assert!(!sb.is_following());
+        assert_eq!(sb.render(10, ("", ""))[0], "line 10");
+
+        sb.page_down(10);
+        assert!(sb.is_following());
+        assert_eq!(sb.render(10, ("", ""))[0], "line 20");
+    }
+
+    #[test]
+    fn search_highlights_and_cycles_matches() {
+        let mut sb = Scrollback::new(40);
+        sb.push(LineKind::User, "Deploy the service");
+        sb.push(LineKind::Assistant, "deploying now");
+        sb.push(LineKind::Assistant, "done");
+
+        assert_eq!(sb.search("deploy", 2), 2);
+        let rendered = sb.render(3, ("[", "]"));
+        assert!(rendered.iter().any(|l| l == "[Deploy] the service"));
+        assert!(rendered.iter().any(|l| l == "[deploy]ing now"));
+
+        assert_eq!(sb.next_match(2), Some(0));
+        assert_eq!(sb.previous_match(2), Some(1));
+        assert!(sb.position_summary(2).contains("match 2/2"));
+    }
+
+    #[test]
+    fn tool_navigation_jumps_between_invocations() {
+        let mut sb = filled(30);
+        assert!(sb.previous_tool(5));
+        assert_eq!(sb.render(5, ("", ""))[0], "line 20");
+        assert!(sb.previous_tool(5));
+        assert_eq!(sb.render(5, ("", ""))[0], "line 15");
+        assert!(sb.next_tool(5));
+        assert_eq!(sb.render(5, ("", ""))[0], "line 20");
+    }
+
+    #[test]
+    fn capacity_discards_oldest_lines() {
+        let mut sb = Scrollback::with_capacity(40, 3);
+        for i in 0..5 {
+            sb.push(LineKind::User, &format!("msg {}", i));
+        }
+        assert_eq!(sb.len(), 3);
+        assert_eq!(sb.render(3, ("", ""))[0], "msg 2");
+    }
+}
@@ -0,0 +1,159 @@
+//! Display-width aware text helpers for terminal output
+//!
+//! Terminal cells are not characters: CJK ideographs and most emoji occupy two
+//! columns, combining marks occupy none, and a single user-perceived character
+//! (grapheme cluster) may span several `char`s. All measurement, truncation and
+//! wrapping here operates on grapheme clusters and their cell width so output
+//! never splits a cluster or overflows the available columns.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Number of terminal cells a string occupies
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Cell width of a single grapheme cluster
+fn grapheme_width(grapheme: &str) -> usize {
+    UnicodeWidthStr::width(grapheme)
+}
+
+/// Truncate `text` so it fits in `width` cells without splitting a grapheme.
+///
+/// A wide grapheme that would straddle the boundary is dropped entirely.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let w = grapheme_width(grapheme);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        out.push_str(grapheme);
+    }
+    out
+}
+
+/// Truncate `text` to `width` cells, appending `ellipsis` when anything was cut.
+///
+/// The ellipsis counts against the width budget.
+pub fn truncate_with_ellipsis(text: &str, width: usize, ellipsis: &str) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let ellipsis_width = display_width(ellipsis);
+    if ellipsis_width >= width {
+        return truncate_to_width(ellipsis, width);
+    }
+    let mut out = truncate_to_width(text, width - ellipsis_width);
+    out.push_str(ellipsis);
+    out
+}
+
+/// Truncate or right-pad `text` with spaces so it occupies exactly `width` cells.
+///
+/// When a wide grapheme cannot fit at the boundary, the remaining cell is
+/// filled with a space so the line width stays exact.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let mut out = truncate_to_width(text, width);
+    let used = display_width(&out);
+    if used < width {
+        out.push_str(&" ".repeat(width - used));
+    }
+    out
+}
+
+/// Wrap `text` into lines no wider than `width` cells.
+///
+/// Breaks prefer whitespace; words longer than a full line are split on
+/// grapheme boundaries. Explicit newlines are preserved.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut current = String::new();
+        let mut current_width = 0;
+
+        for word in paragraph.split_word_bounds() {
+            let word_width = display_width(word);
+            let is_space = word.chars().all(char::is_whitespace);
+
+            if current_width + word_width <= width {
+                current.push_str(word);
+                current_width += word_width;
+                continue;
+            }
+
+            if is_space {
+                // Break at whitespace; do not carry it onto the next line
+                lines.push(current.trim_end().to_string());
+                current.clear();
+                current_width = 0;
+                continue;
+            }
+
+            if current_width > 0 && word_width <= width {
+                lines.push(current.trim_end().to_string());
+                current = word.to_string();
+                current_width = word_width;
+                continue;
+            }
+
+            // Word wider than a line: split it across lines by grapheme
+            for grapheme in word.graphemes(true) {
+                let w = grapheme_width(grapheme);
+                if current_width + w > width && current_width > 0 {
+                    lines.push(std::mem::take(&mut current));
+                    current_width = 0;
+                }
+                current.push_str(grapheme);
+                current_width += w;
+            }
+        }
+
+        lines.push(current.trim_end().to_string());
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_wide_and_combining_characters() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("👍"), 2);
+    }
+
+    #[test]
+    fn truncation_never_splits_graphemes() {
+        assert_eq!(truncate_to_width("日本語", 5), "日本");
+        assert_eq!(truncate_to_width("e\u{301}e\u{301}", 1), "e\u{301}");
+        assert_eq!(truncate_with_ellipsis("hello world", 8, "..."), "hello...");
+        assert_eq!(truncate_with_ellipsis("short", 8, "..."), "short");
+    }
+
+    #[test]
+    fn padding_fills_exact_width() {
+        assert_eq!(pad_to_width("ab", 4), "ab  ");
+        assert_eq!(pad_to_width("日本語", 5), "日本 ");
+        assert_eq!(display_width(&pad_to_width("👍x", 6)), 6);
+    }
+
+    #[test]
+    fn wraps_on_words_and_splits_long_runs() {
+        assert_eq!(wrap_text("hello big world", 9), vec!["hello big", "world"]);
+        assert_eq!(wrap_text("日本語日本語", 4), vec!["日本", "語日", "本語"]);
+        assert_eq!(wrap_text("a\nb", 10), vec!["a", "b"]);
+        for line in wrap_text("漢字 and emoji 👍👍👍 mixed together", 7) {
+            assert!(display_width(&line) <= 7, "line too wide: {:?}", line);
+        }
+    }
+}
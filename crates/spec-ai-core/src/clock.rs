@@ -0,0 +1,552 @@
+//! Date, time and calendar awareness
+//!
+//! Models have no idea what day it is, so questions like "what's next
+//! Tuesday" get hallucinated answers. [`Clock`] supplies the current time in
+//! the configured zone, resolves relative date expressions and reads upcoming
+//! events from an iCalendar file. It backs the `time_context` prompt block
+//! and the `get_time` / `schedule_lookup` tools.
+//!
+//! Zones are the system's local zone or a fixed UTC offset. The ICS reader
+//! understands single events (`VEVENT` with `DTSTART`/`DTEND`); recurring
+//! rules are not expanded and `TZID` times are read in the configured zone.
+
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc, Weekday,
+};
+use std::path::{Path, PathBuf};
+
+use crate::config::{parse_utc_offset, TimeConfig};
+
+/// Working hours used when listing free time, as (start hour, end hour)
+pub const WORKING_HOURS: (u32, u32) = (9, 17);
+
+/// Timezone the clock reports in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    /// The system's local zone
+    Local,
+    /// A fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Parse `local`, `UTC` or an offset such as `+02:00`
+    pub fn parse(value: &str) -> Result<Self> {
+        match parse_utc_offset(value)? {
+            None => Ok(Zone::Local),
+            Some(seconds) => FixedOffset::east_opt(seconds)
+                .map(Zone::Fixed)
+                .context("UTC offset out of range"),
+        }
+    }
+
+    /// Convert an instant into this zone
+    pub fn convert(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => instant.with_timezone(&Local).fixed_offset(),
+            Zone::Fixed(offset) => instant.with_timezone(offset),
+        }
+    }
+
+    /// Interpret a wall-clock time in this zone
+    pub fn localize(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Zone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+            Zone::Fixed(offset) => offset.from_local_datetime(&naive).single(),
+        }
+    }
+
+    /// Display name such as `UTC+02:00`, or `local time, UTC+02:00`
+    pub fn label(&self, at: &DateTime<FixedOffset>) -> String {
+        let offset = format_offset(at.offset());
+        match self {
+            Zone::Local => format!("local time, {}", offset),
+            Zone::Fixed(_) => offset,
+        }
+    }
+}
+
+fn format_offset(offset: &FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    if seconds == 0 {
+        return "UTC".to_string();
+    }
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!(
+        "UTC{}{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// A single calendar entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: DateTime<FixedOffset>,
+    /// Exclusive end; equal to `start` for events without a duration
+    pub end: DateTime<FixedOffset>,
+    pub all_day: bool,
+    pub location: Option<String>,
+}
+
+impl CalendarEvent {
+    /// Whether the event takes up any time in `[from, to)`
+    pub fn overlaps(&self, from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> bool {
+        if self.start == self.end {
+            return self.start >= from && self.start < to;
+        }
+        self.start < to && self.end > from
+    }
+
+    /// One-line description, e.g. `Tue 20 Oct 09:00-09:30 Standup (Room 4)`
+    pub fn describe(&self) -> String {
+        let when = if self.all_day {
+            format!("{} (all day)", self.start.format("%a %d %b"))
+        } else if self.end > self.start {
+            format!(
+                "{}-{}",
+                self.start.format("%a %d %b %H:%M"),
+                self.end.format("%H:%M")
+            )
+        } else {
+            self.start.format("%a %d %b %H:%M").to_string()
+        };
+        match &self.location {
+            Some(location) => format!("{} {} ({})", when, self.summary, location),
+            None => format!("{} {}", when, self.summary),
+        }
+    }
+}
+
+/// Parse the events of an iCalendar document, sorted by start time.
+/// Cancelled events and entries without a usable `DTSTART` are skipped.
+pub fn parse_ics(contents: &str, zone: &Zone) -> Vec<CalendarEvent> {
+    // Unfold continuation lines (RFC 5545 §3.1)
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines().map(|line| line.trim_end_matches('\r')) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String, String)>> = None;
+    for line in &lines {
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            current = Some(Vec::new());
+        } else if line.eq_ignore_ascii_case("END:VEVENT") {
+            if let Some(properties) = current.take() {
+                events.extend(build_event(&properties, zone));
+            }
+        } else if let Some(properties) = current.as_mut() {
+            let Some((head, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (name, params) = head.split_once(';').unwrap_or((head, ""));
+            properties.push((
+                name.to_ascii_uppercase(),
+                params.to_ascii_uppercase(),
+                value.to_string(),
+            ));
+        }
+    }
+    events.sort_by_key(|event| event.start);
+    events
+}
+
+fn build_event(properties: &[(String, String, String)], zone: &Zone) -> Option<CalendarEvent> {
+    let property = |name: &str| {
+        properties
+            .iter()
+            .find(|(key, _, _)| key == name)
+            .map(|(_, params, value)| (params.as_str(), value.as_str()))
+    };
+    if property("STATUS").is_some_and(|(_, status)| status.eq_ignore_ascii_case("CANCELLED")) {
+        return None;
+    }
+
+    let (params, value) = property("DTSTART")?;
+    let (start, all_day) = parse_ics_time(params, value, zone)?;
+    let end = property("DTEND")
+        .and_then(|(params, value)| parse_ics_time(params, value, zone))
+        .map(|(end, _)| end)
+        .unwrap_or(if all_day {
+            start + Duration::days(1)
+        } else {
+            start
+        });
+
+    Some(CalendarEvent {
+        summary: property("SUMMARY")
+            .map(|(_, summary)| unescape(summary))
+            .unwrap_or_else(|| "(untitled)".to_string()),
+        start,
+        end: end.max(start),
+        all_day,
+        location: property("LOCATION")
+            .map(|(_, location)| unescape(location))
+            .filter(|location| !location.is_empty()),
+    })
+}
+
+/// Parse a `DATE` or `DATE-TIME` value; the flag marks all-day dates
+fn parse_ics_time(params: &str, value: &str, zone: &Zone) -> Option<(DateTime<FixedOffset>, bool)> {
+    let value = value.trim();
+    if (params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME")) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((zone.localize(date.and_time(NaiveTime::MIN))?, true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((zone.convert(naive.and_utc()), false));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((zone.localize(naive)?, false))
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Resolve a date expression such as `today`, `next friday`, `in 3 days`,
+/// `2 weeks ago` or `2025-03-01` relative to `today`.
+///
+/// A bare weekday (or `this <weekday>`) is the next occurrence including
+/// today; `next <weekday>` is the first one strictly after today.
+pub fn resolve_date(expression: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let normalized = expression
+        .trim()
+        .trim_end_matches(['?', '.', '!'])
+        .to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&normalized, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["today"] | ["now"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["day", "after", "tomorrow"] | ["the", "day", "after", "tomorrow"] => {
+            today.checked_add_signed(Duration::days(2))
+        }
+        ["next", unit] => shift(today, 1, unit),
+        ["last", unit] if unit.parse::<Weekday>().is_err() => shift(today, -1, unit),
+        ["in", count, unit] => shift(today, parse_count(count)?, unit),
+        [count, unit, "ago"] => shift(today, -parse_count(count)?, unit),
+        [count, unit, "from", "now"] => shift(today, parse_count(count)?, unit),
+        [day] => Some(weekday_on_or_after(today, day.parse().ok()?)),
+        ["this", day] => Some(weekday_on_or_after(today, day.parse().ok()?)),
+        ["last", day] => {
+            let weekday: Weekday = day.parse().ok()?;
+            let back =
+                (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            let back = if back == 0 { 7 } else { back };
+            today.checked_sub_signed(Duration::days(back as i64))
+        }
+        _ => None,
+    }
+}
+
+fn weekday_on_or_after(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(ahead as i64)
+}
+
+/// Move `today` by `count` units; a weekday unit means the matching day
+fn shift(today: NaiveDate, count: i64, unit: &str) -> Option<NaiveDate> {
+    if let Ok(weekday) = unit.parse::<Weekday>() {
+        // "next <weekday>": strictly after today
+        let next = weekday_on_or_after(today + Duration::days(1), weekday);
+        return (count == 1).then_some(next);
+    }
+    match unit.trim_end_matches('s') {
+        "day" => today.checked_add_signed(Duration::days(count)),
+        "week" => today.checked_add_signed(Duration::weeks(count)),
+        "month" if count >= 0 => today.checked_add_months(Months::new(count as u32)),
+        "month" => today.checked_sub_months(Months::new(count.unsigned_abs() as u32)),
+        "year" if count >= 0 => today.checked_add_months(Months::new(count as u32 * 12)),
+        "year" => today.checked_sub_months(Months::new(count.unsigned_abs() as u32 * 12)),
+        _ => None,
+    }
+}
+
+fn parse_count(word: &str) -> Option<i64> {
+    const WORDS: &[&str] = &[
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ => word
+            .parse()
+            .ok()
+            .or_else(|| WORDS.iter().position(|w| *w == word).map(|n| n as i64)),
+    }
+}
+
+/// Free stretches within working hours on `date`, given that day's events
+pub fn free_slots(
+    zone: &Zone,
+    date: NaiveDate,
+    events: &[CalendarEvent],
+) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+    let at = |hour| zone.localize(date.and_hms_opt(hour, 0, 0)?);
+    let (Some(day_start), Some(day_end)) = (at(WORKING_HOURS.0), at(WORKING_HOURS.1)) else {
+        return Vec::new();
+    };
+
+    let mut busy: Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> = events
+        .iter()
+        .filter(|event| !event.all_day && event.overlaps(day_start, day_end))
+        .map(|event| (event.start.max(day_start), event.end.min(day_end)))
+        .collect();
+    busy.sort();
+
+    let mut slots = Vec::new();
+    let mut cursor = day_start;
+    for (start, end) in busy {
+        if start > cursor {
+            slots.push((cursor, start));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < day_end {
+        slots.push((cursor, day_end));
+    }
+    slots
+}
+
+/// Source of the current time and calendar for prompts and tools
+#[derive(Debug, Clone)]
+pub struct Clock {
+    zone: Zone,
+    calendar_file: Option<PathBuf>,
+    calendar_days: u32,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(Zone::Local)
+    }
+}
+
+impl Clock {
+    pub fn new(zone: Zone) -> Self {
+        Self {
+            zone,
+            calendar_file: None,
+            calendar_days: 7,
+        }
+    }
+
+    /// Build a clock from the `[time]` section
+    pub fn from_config(config: &TimeConfig) -> Result<Self> {
+        let mut clock = Self::new(Zone::parse(&config.timezone)?);
+        clock.calendar_file = config.calendar_file.clone();
+        clock.calendar_days = config.calendar_days;
+        Ok(clock)
+    }
+
+    /// Read events from an iCalendar file
+    pub fn with_calendar(mut self, path: impl Into<PathBuf>) -> Self {
+        self.calendar_file = Some(path.into());
+        self
+    }
+
+    pub fn zone(&self) -> Zone {
+        self.zone
+    }
+
+    pub fn calendar_file(&self) -> Option<&Path> {
+        self.calendar_file.as_deref()
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.zone.convert(Utc::now())
+    }
+
+    /// Midnight at the start of `date` and of the following day
+    pub fn day_bounds(
+        &self,
+        date: NaiveDate,
+    ) -> Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let start = self.zone.localize(date.and_time(NaiveTime::MIN))?;
+        let end = self
+            .zone
+            .localize(date.succ_opt()?.and_time(NaiveTime::MIN))?;
+        Some((start, end))
+    }
+
+    /// Events overlapping `[from, to)`; empty when no calendar is configured
+    pub fn events_between(
+        &self,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+    ) -> Result<Vec<CalendarEvent>> {
+        let Some(path) = &self.calendar_file else {
+            return Ok(Vec::new());
+        };
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read calendar {}", path.display()))?;
+        Ok(parse_ics(&contents, &self.zone)
+            .into_iter()
+            .filter(|event| event.overlaps(from, to))
+            .collect())
+    }
+
+    /// Prompt block with the current time and, when a calendar is
+    /// configured, the events of the next `calendar_days` days
+    pub fn context_block(&self) -> String {
+        let now = self.now();
+        let upcoming = match &self.calendar_file {
+            Some(_) => {
+                let until = now + Duration::days(self.calendar_days as i64);
+                match self.events_between(now, until) {
+                    Ok(events) => Some(events),
+                    Err(err) => {
+                        tracing::warn!("Calendar unavailable for prompt context: {:#}", err);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        render_context(&self.zone, now, upcoming.as_deref(), self.calendar_days)
+    }
+}
+
+fn render_context(
+    zone: &Zone,
+    now: DateTime<FixedOffset>,
+    upcoming: Option<&[CalendarEvent]>,
+    days: u32,
+) -> String {
+    let mut block = format!(
+        "Current date and time: {} ({}). Resolve relative dates such as \"next Tuesday\" from this, or call get_time.",
+        now.format("%A, %-d %B %Y, %H:%M"),
+        zone.label(&now)
+    );
+    if let Some(events) = upcoming {
+        if events.is_empty() {
+            block.push_str(&format!("\nNo calendar events in the next {} days.", days));
+        } else {
+            block.push_str(&format!("\nCalendar for the next {} days:", days));
+            for event in events {
+                block.push_str("\n- ");
+                block.push_str(&event.describe());
+            }
+        }
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc() -> Zone {
+        Zone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn resolves_relative_dates() {
+        // A Friday
+        let today = date("2025-03-07");
+        let cases = [
+            ("today", "2025-03-07"),
+            ("Tomorrow?", "2025-03-08"),
+            ("friday", "2025-03-07"),
+            ("next friday", "2025-03-14"),
+            ("next tuesday", "2025-03-11"),
+            ("this monday", "2025-03-10"),
+            ("last friday", "2025-02-28"),
+            ("in 3 days", "2025-03-10"),
+            ("two weeks ago", "2025-02-21"),
+            ("next month", "2025-04-07"),
+            ("a year from now", "2026-03-07"),
+            ("2025-12-25", "2025-12-25"),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(
+                resolve_date(expression, today),
+                Some(date(expected)),
+                "{}",
+                expression
+            );
+        }
+        assert_eq!(resolve_date("sometime soon", today), None);
+    }
+
+    #[test]
+    fn parses_ics_events() {
+        let zone = Zone::parse("+02:00").unwrap();
+        let ics = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\nSUMMARY:Design review\\, part 2\r\nDTSTART:20250310T080000Z\r\nDTEND:20250310T090000Z\r\nLOCATION:Room 4\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:Offsite\r\nDTSTART;VALUE=DATE:20250311\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:Long\r\n  title\r\nDTSTART;TZID=Europe/Paris:20250310T140000\r\nDTEND;TZID=Europe/Paris:20250310T150000\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:Dropped\r\nSTATUS:CANCELLED\r\nDTSTART:20250310T120000Z\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+        let events = parse_ics(ics, &zone);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "Design review, part 2");
+        assert_eq!(
+            events[0].describe(),
+            "Mon 10 Mar 10:00-11:00 Design review, part 2 (Room 4)"
+        );
+        assert_eq!(events[1].summary, "Long title");
+        assert_eq!(events[1].start.format("%H:%M").to_string(), "14:00");
+        assert!(events[2].all_day);
+        assert_eq!(events[2].describe(), "Tue 11 Mar (all day) Offsite");
+
+        let slots = free_slots(&zone, date("2025-03-10"), &events);
+        let slots: Vec<String> = slots
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")))
+            .collect();
+        assert_eq!(slots, vec!["09:00-10:00", "11:00-14:00", "15:00-17:00"]);
+    }
+
+    #[test]
+    fn renders_prompt_context() {
+        let zone = utc();
+        let now = zone.convert(date("2025-03-07").and_hms_opt(16, 30, 0).unwrap().and_utc());
+        let block = render_context(&zone, now, None, 7);
+        assert!(block.starts_with("Current date and time: Friday, 7 March 2025, 16:30 (UTC)."));
+        assert!(!block.contains("Calendar"));
+
+        let block = render_context(&zone, now, Some(&[]), 3);
+        assert!(block.ends_with("No calendar events in the next 3 days."));
+        let india = Zone::parse("UTC+05:30").unwrap();
+        assert_eq!(india.label(&india.convert(Utc::now())), "UTC+05:30");
+        assert!(Zone::Local
+            .label(&Zone::Local.convert(Utc::now()))
+            .starts_with("local time, UTC"));
+    }
+}
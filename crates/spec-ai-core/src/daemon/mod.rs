@@ -0,0 +1,692 @@
+//! Long-running daemon for editor extensions
+//!
+//! `spec-ai daemon` serves JSON-RPC 2.0 with LSP-style framing over stdio
+//! or a unix socket. Requests:
+//!
+//! - `initialize`: server name, version, workspace and supported methods
+//! - `query` `{message, session?, agent?}`: one agent step; the result is
+//!   the same run object the HTTP API returns
+//! - `search/code` `{query, top_n?}`: semantic code search over the
+//!   workspace
+//! - `graph/lookup` `{session?, query?, node_type?, node_id?, depth?, limit?}`:
+//!   knowledge graph nodes by label, or one node and its neighbors
+//! - `patch/preview` `{patch}`: each file's content after a unified diff,
+//!   without writing anything
+//! - `shutdown`, then the `exit` notification
+//!
+//! Any request can be cancelled with `$/cancelRequest {id}`; it then fails
+//! with code -32800. While files in the workspace change, the daemon sends
+//! `workspace/changed {changes: [{path, kind}], truncated}` notifications
+//! and rebuilds the code search index on the next search.
+
+pub mod patch;
+pub mod rpc;
+pub mod watch;
+
+use crate::agent::builder::{create_agent_for_profile, create_agent_from_registry};
+use crate::agent::AgentCore;
+use crate::config::{AgentRegistry, AppConfig};
+use crate::tools::builtin::CodeSearchTool;
+use crate::tools::Tool;
+use crate::types::{NodeType, TraversalDirection};
+use anyhow::{Context, Result};
+use rpc::{Incoming, Outgoing, RequestId, RpcError};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::{broadcast, mpsc, Mutex as AsyncMutex};
+use tokio::task::AbortHandle;
+use tracing::debug;
+use watch::{FileChange, Snapshot};
+
+/// How often the workspace is rescanned by default
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Changes reported per `workspace/changed` notification at most; the rest
+/// follow in later notifications
+const MAX_CHANGES_PER_NOTIFICATION: usize = 500;
+
+const METHODS: &[&str] = &[
+    "initialize",
+    "query",
+    "search/code",
+    "graph/lookup",
+    "patch/preview",
+    "shutdown",
+];
+
+/// State shared by every connection
+struct Shared {
+    config: AppConfig,
+    registry: Arc<AgentRegistry>,
+    workspace: PathBuf,
+    default_session: String,
+    watch_interval: Duration,
+    /// One agent per session and profile; its steps run one at a time
+    agents: Mutex<HashMap<AgentKey, Arc<AsyncMutex<AgentCore>>>>,
+    changes: broadcast::Sender<Arc<WorkspaceChanges>>,
+    /// Files changed since the code search index was built
+    index_stale: AtomicBool,
+}
+
+/// Session id and profile name (`None` for the active profile)
+type AgentKey = (String, Option<String>);
+
+#[derive(Debug)]
+struct WorkspaceChanges {
+    changes: Vec<FileChange>,
+    truncated: bool,
+}
+
+/// The daemon; cheap to clone, all clones share agents and the watcher
+#[derive(Clone)]
+pub struct Daemon {
+    shared: Arc<Shared>,
+}
+
+impl Daemon {
+    /// Serve `workspace` with agents from `registry`. The default session
+    /// is the one `spec-ai bootstrap` fills for the workspace, so queries
+    /// and graph lookups see the repository's structure.
+    pub fn new(config: AppConfig, registry: Arc<AgentRegistry>, workspace: PathBuf) -> Self {
+        let name = workspace
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("repo");
+        let default_session = format!("bootstrap-{}", name);
+        let (changes, _) = broadcast::channel(16);
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                registry,
+                default_session,
+                workspace,
+                watch_interval: DEFAULT_WATCH_INTERVAL,
+                agents: Mutex::new(HashMap::new()),
+                changes,
+                index_stale: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Session used when a request names none
+    pub fn with_default_session(self, session: impl Into<String>) -> Self {
+        self.reconfigure(|shared| shared.default_session = session.into())
+    }
+
+    pub fn with_watch_interval(self, interval: Duration) -> Self {
+        self.reconfigure(|shared| shared.watch_interval = interval)
+    }
+
+    fn reconfigure(mut self, change: impl FnOnce(&mut Shared)) -> Self {
+        let shared =
+            Arc::get_mut(&mut self.shared).expect("daemon is configured before it is shared");
+        change(shared);
+        self
+    }
+
+    /// Serve one client on stdin and stdout until it sends `exit` or
+    /// closes stdin
+    pub async fn serve_stdio(self) -> Result<()> {
+        let watcher = tokio::spawn(self.clone().watch());
+        let result = self
+            .serve_connection(tokio::io::stdin(), tokio::io::stdout())
+            .await;
+        watcher.abort();
+        result
+    }
+
+    /// Accept clients on a unix socket at `path` until the process stops.
+    /// A stale socket file from an earlier run is replaced; any other file
+    /// at `path` is left alone. Only the owner may connect.
+    #[cfg(unix)]
+    pub async fn serve_unix(self, path: &std::path::Path) -> Result<()> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        use tokio::net::{UnixListener, UnixStream};
+
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) => {
+                if !metadata.file_type().is_socket() {
+                    anyhow::bail!("{} exists and is not a socket", path.display());
+                }
+                if UnixStream::connect(path).await.is_ok() {
+                    anyhow::bail!("another daemon is listening on {}", path.display());
+                }
+                std::fs::remove_file(path)
+                    .with_context(|| format!("removing stale socket {}", path.display()))?;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("inspecting {}", path.display()));
+            }
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("binding unix socket {}", path.display()))?;
+        let _cleanup = SocketFile(path.to_path_buf());
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("restricting unix socket {}", path.display()))?;
+        let watcher = tokio::spawn(self.clone().watch());
+
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(client) => client,
+                Err(err) => {
+                    watcher.abort();
+                    return Err(err).context("accepting a daemon client");
+                }
+            };
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                let (reader, writer) = stream.into_split();
+                if let Err(err) = daemon.serve_connection(reader, writer).await {
+                    tracing::warn!("daemon client disconnected: {:#}", err);
+                }
+            });
+        }
+    }
+
+    /// Handle one client's requests until `exit` or end of input
+    pub async fn serve_connection<R, W>(&self, reader: R, writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outgoing, mut queue) = mpsc::unbounded_channel::<Outgoing>();
+        let writer_task = tokio::spawn(async move {
+            let mut writer = writer;
+            while let Some(message) = queue.recv().await {
+                rpc::write_message(&mut writer, &message).await?;
+            }
+            anyhow::Ok(())
+        });
+        let notifier = tokio::spawn(forward_changes(
+            self.shared.changes.subscribe(),
+            outgoing.clone(),
+        ));
+
+        let inflight: Arc<Mutex<HashMap<RequestId, AbortHandle>>> = Arc::default();
+        let mut shutting_down = false;
+        let mut reader = BufReader::new(reader);
+        let result = loop {
+            let body = match rpc::read_message(&mut reader).await {
+                Ok(Some(body)) => body,
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err),
+            };
+            let message = match rpc::parse(&body) {
+                Ok(message) => message,
+                Err((id, error)) => {
+                    let _ = outgoing.send(Outgoing::response(id, Err(error)));
+                    continue;
+                }
+            };
+
+            let Incoming { id, method, params } = message;
+            match (method.as_str(), id) {
+                ("exit", _) => break Ok(()),
+                ("$/cancelRequest", _) => {
+                    if let Ok(id) = serde_json::from_value::<RequestId>(params["id"].clone()) {
+                        if let Some(task) = inflight.lock().expect("inflight lock").get(&id) {
+                            debug!("cancelling daemon request {}", id);
+                            task.abort();
+                        }
+                    }
+                }
+                (method, None) => debug!("ignoring daemon notification {}", method),
+                (_, Some(id)) if shutting_down => {
+                    let _ = outgoing.send(Outgoing::response(
+                        Some(id),
+                        Err(RpcError::new(
+                            rpc::INVALID_REQUEST,
+                            "the daemon is shutting down",
+                        )),
+                    ));
+                }
+                ("shutdown", Some(id)) => {
+                    shutting_down = true;
+                    let _ = outgoing.send(Outgoing::response(Some(id), Ok(Value::Null)));
+                }
+                (_, Some(id)) => self.spawn_request(id, method, params, &inflight, &outgoing),
+            }
+        };
+
+        for task in inflight.lock().expect("inflight lock").values() {
+            task.abort();
+        }
+        notifier.abort();
+        drop(outgoing);
+        writer_task.await.context("daemon writer stopped")??;
+        result
+    }
+
+    /// Run a request in its own task so it can be cancelled and others
+    /// are not held up
+    fn spawn_request(
+        &self,
+        id: RequestId,
+        method: String,
+        params: Value,
+        inflight: &Arc<Mutex<HashMap<RequestId, AbortHandle>>>,
+        outgoing: &mpsc::UnboundedSender<Outgoing>,
+    ) {
+        let daemon = self.clone();
+        let work = tokio::spawn(async move { daemon.dispatch(&method, params).await });
+        inflight
+            .lock()
+            .expect("inflight lock")
+            .insert(id.clone(), work.abort_handle());
+
+        let inflight = inflight.clone();
+        let outgoing = outgoing.clone();
+        tokio::spawn(async move {
+            let result = match work.await {
+                Ok(result) => result,
+                Err(err) if err.is_cancelled() => Err(RpcError::cancelled()),
+                Err(err) => Err(RpcError::new(rpc::INTERNAL_ERROR, err.to_string())),
+            };
+            inflight.lock().expect("inflight lock").remove(&id);
+            let _ = outgoing.send(Outgoing::response(Some(id), result));
+        });
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(self.initialize()),
+            "query" => self.query(parse_params(params)?).await,
+            "search/code" => self.search_code(parse_params(params)?).await,
+            "graph/lookup" => self.graph_lookup(parse_params(params)?).await,
+            "patch/preview" => self.patch_preview(parse_params(params)?).await,
+            other => Err(RpcError::new(
+                rpc::METHOD_NOT_FOUND,
+                format!("Unknown method: {}", other),
+            )),
+        }
+    }
+
+    fn initialize(&self) -> Value {
+        json!({
+            "server_info": {
+                "name": "spec-ai",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "workspace": self.shared.workspace.display().to_string(),
+            "default_session": self.shared.default_session,
+            "capabilities": {
+                "methods": METHODS,
+                "notifications": ["workspace/changed"],
+                "cancellation": true,
+            },
+        })
+    }
+
+    async fn query(&self, params: QueryParams) -> Result<Value, RpcError> {
+        if params.message.trim().is_empty() {
+            return Err(RpcError::invalid_params("message is empty"));
+        }
+        let session = params
+            .session
+            .unwrap_or_else(|| self.shared.default_session.clone());
+        let agent = self.agent(&session, params.agent.as_deref())?;
+        let output = agent.lock().await.run_step(&params.message).await?;
+        Ok(serde_json::to_value(output).context("serializing the run")?)
+    }
+
+    /// The agent for `session`, created on first use
+    fn agent(&self, session: &str, agent_name: Option<&str>) -> Result<Arc<AsyncMutex<AgentCore>>> {
+        let key = (session.to_string(), agent_name.map(str::to_string));
+        let mut agents = self.shared.agents.lock().expect("daemon agents lock");
+        if let Some(agent) = agents.get(&key) {
+            return Ok(agent.clone());
+        }
+        let shared = &self.shared;
+        let agent = match agent_name {
+            Some(name) => create_agent_for_profile(
+                &shared.registry,
+                &shared.config,
+                name,
+                Some(session.to_string()),
+            ),
+            None => create_agent_from_registry(
+                &shared.registry,
+                &shared.config,
+                Some(session.to_string()),
+            ),
+        }
+        .with_context(|| format!("creating an agent for session '{}'", session))?;
+        let agent = Arc::new(AsyncMutex::new(agent));
+        agents.insert(key, agent.clone());
+        Ok(agent)
+    }
+
+    async fn search_code(&self, params: CodeSearchParams) -> Result<Value, RpcError> {
+        let refresh = self.shared.index_stale.swap(false, Ordering::SeqCst);
+        let result = CodeSearchTool::new()
+            .execute(json!({
+                "query": params.query,
+                "top_n": params.top_n,
+                "root": self.shared.workspace.display().to_string(),
+                "refresh": refresh,
+            }))
+            .await;
+        let result = match result {
+            Ok(result) if result.success => result,
+            Ok(result) => {
+                self.shared.index_stale.fetch_or(refresh, Ordering::SeqCst);
+                return Err(anyhow::anyhow!(result.error.unwrap_or_default()).into());
+            }
+            Err(err) => {
+                self.shared.index_stale.fetch_or(refresh, Ordering::SeqCst);
+                return Err(err.into());
+            }
+        };
+        Ok(serde_json::from_str(&result.output).context("reading code search results")?)
+    }
+
+    async fn graph_lookup(&self, params: GraphLookupParams) -> Result<Value, RpcError> {
+        let persistence = self.shared.registry.persistence().clone();
+        let session = params
+            .session
+            .unwrap_or_else(|| self.shared.default_session.clone());
+        let lookup = tokio::task::spawn_blocking(move || -> Result<Value> {
+            if let Some(node_id) = params.node_id {
+                let node = persistence
+                    .get_graph_node(node_id)?
+                    .filter(|node| node.session_id == session)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no node {} in session '{}'", node_id, session)
+                    })?;
+                let neighbors = persistence.traverse_neighbors_with_edges(
+                    &session,
+                    node_id,
+                    TraversalDirection::Both,
+                    params.depth.unwrap_or(1),
+                )?;
+                return Ok(json!({ "session": session, "node": node, "neighbors": neighbors }));
+            }
+
+            let node_type = params.node_type.as_deref().map(NodeType::from_str);
+            let needle = params.query.as_deref().map(str::to_lowercase);
+            let limit = params.limit.unwrap_or(50);
+            let nodes: Vec<_> = persistence
+                .list_graph_nodes(&session, node_type, None)?
+                .into_iter()
+                .filter(|node| {
+                    needle
+                        .as_deref()
+                        .is_none_or(|needle| node.label.to_lowercase().contains(needle))
+                })
+                .take(limit)
+                .collect();
+            Ok(json!({ "session": session, "nodes": nodes }))
+        });
+        let result = lookup
+            .await
+            .map_err(|err| RpcError::new(rpc::INTERNAL_ERROR, err.to_string()))??;
+        Ok(result)
+    }
+
+    async fn patch_preview(&self, params: PatchPreviewParams) -> Result<Value, RpcError> {
+        let workspace = self.shared.workspace.clone();
+        let previews = tokio::task::spawn_blocking(move || {
+            patch::preview(&params.patch, |path| {
+                std::fs::read_to_string(workspace.join(path)).ok()
+            })
+        })
+        .await
+        .map_err(|err| RpcError::new(rpc::INTERNAL_ERROR, err.to_string()))??;
+        Ok(json!({ "files": previews }))
+    }
+
+    /// Rescan the workspace at the watch interval and publish what changed
+    async fn watch(self) {
+        let root = self.shared.workspace.clone();
+        let scan = move || {
+            let root = root.clone();
+            async move {
+                tokio::task::spawn_blocking(move || Snapshot::take(&root))
+                    .await
+                    .unwrap_or_default()
+            }
+        };
+        let mut previous = scan().await;
+        debug!(
+            "watching {} files in {}",
+            previous.len(),
+            self.shared.workspace.display()
+        );
+        let mut ticker = tokio::time::interval(self.shared.watch_interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let current = scan().await;
+            let changes = current.changes_since(&previous);
+            if !changes.is_empty() {
+                self.shared.index_stale.store(true, Ordering::SeqCst);
+                for batch in changes.chunks(MAX_CHANGES_PER_NOTIFICATION) {
+                    let _ = self.shared.changes.send(Arc::new(WorkspaceChanges {
+                        changes: batch.to_vec(),
+                        truncated: current.truncated,
+                    }));
+                }
+            }
+            previous = current;
+        }
+    }
+}
+
+async fn forward_changes(
+    mut changes: broadcast::Receiver<Arc<WorkspaceChanges>>,
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+) {
+    loop {
+        match changes.recv().await {
+            Ok(batch) => {
+                let notification = Outgoing::notification(
+                    "workspace/changed",
+                    json!({ "changes": batch.changes, "truncated": batch.truncated }),
+                );
+                if outgoing.send(notification).is_err() {
+                    return;
+                }
+            }
+            // A slow client misses batches; tell it to rescan everything
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                let notification = Outgoing::notification(
+                    "workspace/changed",
+                    json!({ "changes": [], "truncated": true }),
+                );
+                if outgoing.send(notification).is_err() {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Removes the daemon's socket file when the listener stops
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    message: String,
+    #[serde(default)]
+    session: Option<String>,
+    #[serde(default)]
+    agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeSearchParams {
+    query: String,
+    #[serde(default)]
+    top_n: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphLookupParams {
+    #[serde(default)]
+    session: Option<String>,
+    /// Case-insensitive substring of node labels
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    node_type: Option<String>,
+    #[serde(default)]
+    node_id: Option<i64>,
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchPreviewParams {
+    patch: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Persistence;
+    use tokio::io::{duplex, AsyncWriteExt};
+
+    async fn send<W: AsyncWrite + Unpin>(writer: &mut W, message: Value) {
+        let body = message.to_string();
+        writer
+            .write_all(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn answers_requests_over_a_connection() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("a.txt"), "one\ntwo\n").unwrap();
+        let registry = Arc::new(AgentRegistry::new(
+            HashMap::new(),
+            Persistence::in_memory().unwrap(),
+        ));
+        let daemon = Daemon::new(
+            AppConfig::default(),
+            registry,
+            workspace.path().to_path_buf(),
+        )
+        .with_default_session("daemon-test");
+
+        let (client, server) = duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let serving =
+            tokio::spawn(async move { daemon.serve_connection(server_read, server_write).await });
+        let (client_read, mut client_write) = tokio::io::split(client);
+        let mut client_read = BufReader::new(client_read);
+
+        send(
+            &mut client_write,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+        )
+        .await;
+        send(
+            &mut client_write,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "patch/preview",
+                "params": { "patch": "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n" },
+            }),
+        )
+        .await;
+        send(
+            &mut client_write,
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "nope" }),
+        )
+        .await;
+        send(
+            &mut client_write,
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "query", "params": {} }),
+        )
+        .await;
+
+        let mut responses = HashMap::new();
+        while responses.len() < 4 {
+            let body = rpc::read_message(&mut client_read).await.unwrap().unwrap();
+            let message: Value = serde_json::from_slice(&body).unwrap();
+            if let Some(id) = message.get("id").and_then(Value::as_i64) {
+                responses.insert(id, message);
+            }
+        }
+        assert_eq!(responses[&1]["result"]["default_session"], "daemon-test");
+        assert_eq!(
+            responses[&2]["result"]["files"][0]["content"],
+            "one\nthree\n"
+        );
+        assert_eq!(responses[&3]["error"]["code"], rpc::METHOD_NOT_FOUND);
+        assert_eq!(responses[&4]["error"]["code"], rpc::INVALID_PARAMS);
+
+        send(
+            &mut client_write,
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        )
+        .await;
+        serving.await.unwrap().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_is_private_and_never_replaces_other_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = || {
+            let registry = Arc::new(AgentRegistry::new(
+                HashMap::new(),
+                Persistence::in_memory().unwrap(),
+            ));
+            Daemon::new(AppConfig::default(), registry, dir.path().to_path_buf())
+        };
+
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "keep me").unwrap();
+        assert!(daemon().serve_unix(&notes).await.is_err());
+        assert_eq!(std::fs::read_to_string(&notes).unwrap(), "keep me");
+
+        let socket = dir.path().join("daemon.sock");
+        let serving = tokio::spawn({
+            let daemon = daemon();
+            let socket = socket.clone();
+            async move { daemon.serve_unix(&socket).await }
+        });
+        let mode = || {
+            std::fs::metadata(&socket)
+                .map(|metadata| metadata.permissions().mode() & 0o777)
+                .ok()
+        };
+        for _ in 0..200 {
+            if mode() == Some(0o600) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(mode(), Some(0o600));
+        assert!(daemon().serve_unix(&socket).await.is_err());
+        serving.abort();
+    }
+}
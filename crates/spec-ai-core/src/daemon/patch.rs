@@ -0,0 +1,343 @@
+//! Previewing unified diffs without writing them
+//!
+//! Editors send a patch (typically one the agent proposed) and get back
+//! each file's content after the patch, or the hunk that does not apply,
+//! so they can show a diff view before the user accepts anything.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::path::{Component, Path};
+
+/// Lines a hunk may have moved from where its header places it
+const MAX_OFFSET: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewStatus {
+    /// The patch applies; `content` holds the result
+    Modified,
+    Created,
+    Deleted,
+    /// A hunk's context is not in the file; `error` says which
+    Conflict,
+}
+
+/// The outcome of a patch for one file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilePreview {
+    pub path: String,
+    pub status: PreviewStatus,
+    pub hunks: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Content after the patch; absent for deletions and conflicts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    /// Lines as they appear in the diff, prefix included
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// Apply `patch` to the files `read` returns (by workspace-relative path;
+/// `None` for a missing file) and report each file's result
+pub fn preview(patch: &str, read: impl Fn(&str) -> Option<String>) -> Result<Vec<FilePreview>> {
+    let files = parse(patch)?;
+    if files.is_empty() {
+        bail!("no file changes found in the patch");
+    }
+    Ok(files.iter().map(|file| preview_file(file, &read)).collect())
+}
+
+fn preview_file(file: &FilePatch, read: &impl Fn(&str) -> Option<String>) -> FilePreview {
+    let path = file
+        .new_path
+        .clone()
+        .or_else(|| file.old_path.clone())
+        .unwrap_or_default();
+    let added = count_lines(file, '+');
+    let removed = count_lines(file, '-');
+    let mut result = FilePreview {
+        path: path.clone(),
+        status: PreviewStatus::Conflict,
+        hunks: file.hunks.len(),
+        added,
+        removed,
+        content: None,
+        error: None,
+    };
+
+    let status = match (&file.old_path, &file.new_path) {
+        (None, Some(_)) => PreviewStatus::Created,
+        (Some(_), None) => PreviewStatus::Deleted,
+        _ => PreviewStatus::Modified,
+    };
+    let original = match (&status, &file.old_path) {
+        (PreviewStatus::Created, _) => String::new(),
+        (_, Some(old_path)) => match read(old_path) {
+            Some(content) => content,
+            None => {
+                result.error = Some(format!("{} does not exist", old_path));
+                return result;
+            }
+        },
+        (_, None) => String::new(),
+    };
+
+    match apply(&original, &file.hunks) {
+        Ok(content) => {
+            if status != PreviewStatus::Deleted {
+                result.content = Some(content);
+            }
+            result.status = status;
+        }
+        Err(err) => result.error = Some(err.to_string()),
+    }
+    result
+}
+
+fn count_lines(file: &FilePatch, prefix: char) -> usize {
+    file.hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| line.starts_with(prefix))
+        .count()
+}
+
+fn parse(patch: &str) -> Result<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = patch.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(old) = line.strip_prefix("--- ") {
+            let Some(new) = lines.next().and_then(|next| next.strip_prefix("+++ ")) else {
+                bail!("'--- ' line without a following '+++ ' line");
+            };
+            files.push(FilePatch {
+                old_path: patch_path(old, "a/")?,
+                new_path: patch_path(new, "b/")?,
+                hunks: Vec::new(),
+            });
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| anyhow!("hunk before any file header"))?;
+            let (old_start, old_len, new_len) = parse_hunk_header(header)?;
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+            };
+            let (mut old_seen, mut new_seen) = (0, 0);
+            while old_seen < old_len || new_seen < new_len {
+                let Some(body) = lines.next() else {
+                    bail!("hunk at line {} is cut short", old_start);
+                };
+                match body.chars().next() {
+                    Some('+') => new_seen += 1,
+                    Some('-') => old_seen += 1,
+                    Some(' ') | None => {
+                        old_seen += 1;
+                        new_seen += 1;
+                    }
+                    Some('\\') => continue,
+                    Some(_) => bail!("unexpected line in hunk: {}", body),
+                }
+                hunk.lines.push(if body.is_empty() {
+                    " ".to_string()
+                } else {
+                    body.to_string()
+                });
+            }
+            // "\ No newline at end of file" markers after the last line
+            while lines.peek().is_some_and(|next| next.starts_with('\\')) {
+                lines.next();
+            }
+            file.hunks.push(hunk);
+        }
+    }
+    Ok(files)
+}
+
+/// `path` without its `a/` or `b/` prefix and timestamp; `None` for
+/// `/dev/null`. Paths must stay inside the workspace.
+fn patch_path(raw: &str, prefix: &str) -> Result<Option<String>> {
+    let raw = raw.split('\t').next().unwrap_or_default().trim();
+    if raw == "/dev/null" {
+        return Ok(None);
+    }
+    let path = raw.strip_prefix(prefix).unwrap_or(raw);
+    let escapes = Path::new(path)
+        .components()
+        .any(|part| !matches!(part, Component::Normal(_) | Component::CurDir));
+    if path.is_empty() || escapes {
+        bail!("patch path '{}' is outside the workspace", raw);
+    }
+    Ok(Some(path.to_string()))
+}
+
+/// `-12,5 +12,7 @@ fn name` -> (12, 5, 7)
+fn parse_hunk_header(header: &str) -> Result<(usize, usize, usize)> {
+    let mut ranges = header.split_whitespace();
+    let old = ranges.next().and_then(|range| range.strip_prefix('-'));
+    let new = ranges.next().and_then(|range| range.strip_prefix('+'));
+    let (Some(old), Some(new)) = (old, new) else {
+        bail!("malformed hunk header: @@ {}", header);
+    };
+    let range = |text: &str| -> Result<(usize, usize)> {
+        let (start, len) = text.split_once(',').unwrap_or((text, "1"));
+        Ok((start.parse()?, len.parse()?))
+    };
+    let (old_start, old_len) =
+        range(old).map_err(|_| anyhow!("malformed hunk header: @@ {}", header))?;
+    let (_, new_len) = range(new).map_err(|_| anyhow!("malformed hunk header: @@ {}", header))?;
+    Ok((old_start, old_len, new_len))
+}
+
+fn apply(original: &str, hunks: &[Hunk]) -> Result<String> {
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let trailing_newline = original.is_empty() || original.ends_with('\n');
+    // Line count change from the hunks applied so far
+    let mut shift: isize = 0;
+
+    for hunk in hunks {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|line| !line.starts_with('+'))
+            .map(|line| &line[1..])
+            .collect();
+        let new: Vec<String> = hunk
+            .lines
+            .iter()
+            .filter(|line| !line.starts_with('-'))
+            .map(|line| line[1..].to_string())
+            .collect();
+
+        // Unified diffs number lines from 1; an empty old range names the
+        // line before the insertion
+        let expected = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (expected as isize + shift).max(0) as usize;
+        let at = find(&lines, &old, expected).ok_or_else(|| {
+            anyhow!(
+                "hunk at line {} does not match the file (expected: {:?})",
+                hunk.old_start,
+                old.first().copied().unwrap_or_default()
+            )
+        })?;
+        let replaced = old.len();
+        shift += new.len() as isize - replaced as isize;
+        lines.splice(at..at + replaced, new);
+    }
+
+    let mut content = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Where `needle` occurs in `lines`, preferring the spot nearest `expected`
+fn find(lines: &[String], needle: &[&str], expected: usize) -> Option<usize> {
+    let matches_at = |at: usize| {
+        at + needle.len() <= lines.len()
+            && needle
+                .iter()
+                .zip(&lines[at..])
+                .all(|(want, have)| *want == have.as_str())
+    };
+    if needle.is_empty() {
+        return Some(expected.min(lines.len()));
+    }
+    (0..=MAX_OFFSET).find_map(|offset| {
+        [expected.checked_sub(offset), expected.checked_add(offset)]
+            .into_iter()
+            .flatten()
+            .find(|&at| matches_at(at))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn files(entries: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = entries
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.to_string()))
+            .collect();
+        move |path| map.get(path).cloned()
+    }
+
+    #[test]
+    fn previews_modified_created_and_deleted_files() {
+        let patch = "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn one() {}
+-fn two() {}
++fn two() { 2 }
+ fn three() {}
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1,2 @@
++# Notes
++first
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let read = files(&[
+            (
+                "src/lib.rs",
+                "// header\nfn one() {}\nfn two() {}\nfn three() {}\n",
+            ),
+            ("old.txt", "bye\n"),
+        ]);
+        let previews = preview(patch, read).unwrap();
+
+        assert_eq!(previews[0].status, PreviewStatus::Modified);
+        assert_eq!(
+            previews[0].content.as_deref(),
+            Some("// header\nfn one() {}\nfn two() { 2 }\nfn three() {}\n")
+        );
+        assert_eq!((previews[0].added, previews[0].removed), (1, 1));
+
+        assert_eq!(previews[1].path, "NOTES.md");
+        assert_eq!(previews[1].status, PreviewStatus::Created);
+        assert_eq!(previews[1].content.as_deref(), Some("# Notes\nfirst\n"));
+
+        assert_eq!(previews[2].path, "old.txt");
+        assert_eq!(previews[2].status, PreviewStatus::Deleted);
+        assert!(previews[2].content.is_none());
+    }
+
+    #[test]
+    fn reports_conflicts_and_rejects_escaping_paths() {
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-missing\n+new\n";
+        let previews = preview(patch, files(&[("a.txt", "present\n")])).unwrap();
+        assert_eq!(previews[0].status, PreviewStatus::Conflict);
+        assert!(previews[0].error.as_deref().unwrap().contains("line 1"));
+
+        let escaping = "--- a/../etc/passwd\n+++ b/../etc/passwd\n@@ -1 +1 @@\n-x\n+y\n";
+        assert!(preview(escaping, files(&[])).is_err());
+        assert!(preview("just text", files(&[])).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! JSON-RPC 2.0 messages and LSP-style framing
+//!
+//! Each message is a JSON body preceded by a `Content-Length` header and a
+//! blank line, as in the Language Server Protocol, so editor extensions can
+//! reuse their LSP transport code.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest message body accepted from a client
+pub const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The request failed for a reason the client can act on, e.g. an unknown
+/// session or a patch that does not apply
+pub const REQUEST_FAILED: i64 = -32803;
+/// The client cancelled the request with `$/cancelRequest`
+pub const REQUEST_CANCELLED: i64 = -32800;
+
+/// Request identifier: a number or a string, as the client chose
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i64),
+    String(String),
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestId::Number(id) => write!(f, "{}", id),
+            RequestId::String(id) => write!(f, "{:?}", id),
+        }
+    }
+}
+
+/// A request (with `id`) or notification (without) from the client
+#[derive(Debug, Clone, Deserialize)]
+pub struct Incoming {
+    #[serde(default)]
+    pub id: Option<RequestId>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn invalid_params(err: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, format!("Invalid params: {}", err))
+    }
+
+    pub fn cancelled() -> Self {
+        Self::new(REQUEST_CANCELLED, "Request cancelled")
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(REQUEST_FAILED, format!("{:#}", err))
+    }
+}
+
+/// A message to the client: a response or a server notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Outgoing {
+    Response {
+        jsonrpc: &'static str,
+        id: Option<RequestId>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        result: Option<Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<RpcError>,
+    },
+    Notification {
+        jsonrpc: &'static str,
+        method: String,
+        params: Value,
+    },
+}
+
+impl Outgoing {
+    pub fn response(id: Option<RequestId>, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            // A null result must still be present for the response to be valid
+            Ok(value) => (Some(value), None),
+            Err(error) => (None, Some(error)),
+        };
+        Outgoing::Response {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+
+    pub fn notification(method: impl Into<String>, params: Value) -> Self {
+        Outgoing::Notification {
+            jsonrpc: "2.0",
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// Parse a message body. A body that is not a valid request yields the
+/// error to answer it with, and its id if one could be read.
+pub fn parse(body: &[u8]) -> Result<Incoming, (Option<RequestId>, RpcError)> {
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|err| (None, RpcError::new(PARSE_ERROR, err.to_string())))?;
+    let id = value
+        .get("id")
+        .cloned()
+        .and_then(|id| serde_json::from_value(id).ok());
+    serde_json::from_value(value)
+        .map_err(|err| (id, RpcError::new(INVALID_REQUEST, err.to_string())))
+}
+
+/// Read one framed message body; `None` at a clean end of stream
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            if content_length.is_none() {
+                return Ok(None);
+            }
+            bail!("stream ended inside a message header");
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            if content_length.is_none() {
+                // Tolerate blank lines between messages
+                continue;
+            }
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| anyhow!("malformed header line: {}", header))?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            let length: usize = value
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid Content-Length: {}", value.trim()))?;
+            content_length = Some(length);
+        }
+    }
+
+    let length = content_length.unwrap_or_default();
+    if length > MAX_MESSAGE_BYTES {
+        bail!(
+            "message of {} bytes exceeds the {} byte limit",
+            length,
+            MAX_MESSAGE_BYTES
+        );
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .context("reading message body")?;
+    Ok(Some(body))
+}
+
+/// Write one message with its `Content-Length` header
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Outgoing,
+) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn frames_round_trip() {
+        let mut wire = Vec::new();
+        write_message(
+            &mut wire,
+            &Outgoing::response(Some(RequestId::Number(1)), Ok(json!(null))),
+        )
+        .await
+        .unwrap();
+        write_message(
+            &mut wire,
+            &Outgoing::notification("workspace/changed", json!({ "changes": [] })),
+        )
+        .await
+        .unwrap();
+
+        let mut reader = BufReader::new(wire.as_slice());
+        let first = read_message(&mut reader).await.unwrap().unwrap();
+        let first: Value = serde_json::from_slice(&first).unwrap();
+        assert_eq!(first, json!({ "jsonrpc": "2.0", "id": 1, "result": null }));
+        let second = read_message(&mut reader).await.unwrap().unwrap();
+        let second: Value = serde_json::from_slice(&second).unwrap();
+        assert_eq!(second["method"], "workspace/changed");
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_requests_and_reports_bad_ones() {
+        let request =
+            parse(br#"{"jsonrpc":"2.0","id":"a","method":"query","params":{"message":"hi"}}"#)
+                .unwrap();
+        assert_eq!(request.id, Some(RequestId::String("a".into())));
+        assert_eq!(request.params["message"], "hi");
+
+        let notification = parse(br#"{"jsonrpc":"2.0","method":"exit"}"#).unwrap();
+        assert!(notification.id.is_none());
+
+        let (id, error) = parse(br#"{"id": 3}"#).unwrap_err();
+        assert_eq!(id, Some(RequestId::Number(3)));
+        assert_eq!(error.code, INVALID_REQUEST);
+        assert!(parse(b"not json").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Polling workspace watcher
+//!
+//! The daemon snapshots file sizes and modification times at an interval
+//! and diffs consecutive snapshots, which needs no platform file-event API
+//! and behaves the same over network mounts and containers.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Files tracked at most; larger trees are only partly watched
+pub const MAX_WATCHED_FILES: usize = 20_000;
+
+/// Directories never watched: VCS data, build output, dependencies and
+/// spec-ai's own caches
+const SKIPPED_DIRS: &[&str] = &[".git", ".hg", ".svn", ".spec-ai", "target", "node_modules"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Sizes and modification times of the workspace's files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    files: BTreeMap<String, Stamp>,
+    /// Whether `MAX_WATCHED_FILES` cut the walk short
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Changed,
+    Deleted,
+}
+
+/// One file that differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    /// Path relative to the workspace root, with `/` separators
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+impl Snapshot {
+    /// Walk `root`, skipping hidden and generated directories
+    pub fn take(root: &Path) -> Self {
+        let mut snapshot = Snapshot::default();
+        let walker = WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !entry.file_type().is_dir()
+                    || !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+            });
+        for entry in walker.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if snapshot.files.len() >= MAX_WATCHED_FILES {
+                snapshot.truncated = true;
+                break;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let Some(path) = relative_path(root, entry.path()) else {
+                continue;
+            };
+            snapshot.files.insert(
+                path,
+                Stamp {
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                },
+            );
+        }
+        snapshot
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Files created, changed or deleted since `previous`, sorted by path
+    pub fn changes_since(&self, previous: &Snapshot) -> Vec<FileChange> {
+        let mut changes = Vec::new();
+        for (path, stamp) in &self.files {
+            match previous.files.get(path) {
+                None => changes.push(FileChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Created,
+                }),
+                Some(before) if before != stamp => changes.push(FileChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Changed,
+                }),
+                Some(_) => {}
+            }
+        }
+        for path in previous.files.keys() {
+            if !self.files.contains_key(path) {
+                changes.push(FileChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Deleted,
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative: PathBuf = path.strip_prefix(root).ok()?.to_path_buf();
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn diffs_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn a() {}").unwrap();
+        fs::write(root.join("README.md"), "hello").unwrap();
+        fs::write(root.join("target/debug/out"), "ignored").unwrap();
+
+        let before = Snapshot::take(root);
+        assert_eq!(before.len(), 2);
+
+        fs::write(root.join("src/lib.rs"), "fn a() { changed }").unwrap();
+        fs::remove_file(root.join("README.md")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("target/debug/out"), "still ignored").unwrap();
+
+        let after = Snapshot::take(root);
+        assert_eq!(
+            after.changes_since(&before),
+            vec![
+                FileChange {
+                    path: "README.md".into(),
+                    kind: ChangeKind::Deleted
+                },
+                FileChange {
+                    path: "src/lib.rs".into(),
+                    kind: ChangeKind::Changed
+                },
+                FileChange {
+                    path: "src/main.rs".into(),
+                    kind: ChangeKind::Created
+                },
+            ]
+        );
+        assert!(after.changes_since(&after).is_empty());
+    }
+}
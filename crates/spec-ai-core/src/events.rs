@@ -0,0 +1,324 @@
+//! Publishing graph changes and finished runs to an external system
+//!
+//! With an `[events]` sink configured, the persistence layer queues every
+//! graph change and finished run in its event outbox. [`spawn_publisher`]
+//! drains the outbox in order and removes what the sink accepted, so events
+//! reach the sink at least once, even across restarts. Consumers can drop
+//! redeliveries by event id.
+//!
+//! - `webhook`: POSTs a JSON array of events to `url`
+//! - `nats`: publishes each event to `<topic>.<kind>` on the NATS server at
+//!   `url` (`nats://host:port`)
+//! - `kafka`: produces to `topic` through the Kafka REST proxy at `url`,
+//!   keyed by session
+
+use crate::config::EventsConfig;
+use crate::persistence::outbox::OutboxEvent;
+use crate::persistence::Persistence;
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Port used when a NATS url does not name one
+const DEFAULT_NATS_PORT: u16 = 4222;
+
+/// Longest wait between retries while the sink is unreachable
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Start delivering the outbox of `persistence` to the configured sink.
+/// Returns `None` when no sink is configured or there is no async runtime.
+pub fn spawn_publisher(persistence: Persistence, config: &EventsConfig) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+    let sink = match Sink::from_config(config) {
+        Ok(sink) => sink,
+        Err(err) => {
+            warn!("Not publishing events: {:#}", err);
+            return None;
+        }
+    };
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let batch_size = config.batch_size.max(1);
+    let interval = Duration::from_millis(config.poll_interval_ms.max(1));
+    Some(runtime.spawn(publish_loop(persistence, sink, batch_size, interval)))
+}
+
+async fn publish_loop(
+    persistence: Persistence,
+    mut sink: Sink,
+    batch_size: usize,
+    interval: Duration,
+) {
+    let mut retry_delay = interval;
+    loop {
+        let events = match persistence.event_outbox_pending(batch_size) {
+            Ok(events) => events,
+            Err(err) => {
+                warn!("Failed to read the event outbox: {:#}", err);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+        let Some(last) = events.last() else {
+            tokio::time::sleep(interval).await;
+            continue;
+        };
+
+        match sink.publish(&events).await {
+            Ok(()) => {
+                debug!("Published {} events to {}", events.len(), sink.name());
+                if let Err(err) = persistence.event_outbox_ack(last.id) {
+                    warn!("Failed to acknowledge published events: {:#}", err);
+                }
+                retry_delay = interval;
+                // A full batch means more are probably waiting
+                if events.len() < batch_size {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to publish events to {}, retrying in {:?}: {:#}",
+                    sink.name(),
+                    retry_delay,
+                    err
+                );
+                tokio::time::sleep(retry_delay).await;
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Where events go
+enum Sink {
+    Webhook {
+        url: String,
+    },
+    Nats {
+        address: String,
+        topic: String,
+        connection: Option<BufReader<TcpStream>>,
+    },
+    Kafka {
+        url: String,
+        topic: String,
+    },
+}
+
+impl Sink {
+    fn from_config(config: &EventsConfig) -> Result<Self> {
+        let url = config
+            .url
+            .clone()
+            .context("[events] needs a url")?
+            .trim()
+            .to_string();
+        match config.sink.as_deref() {
+            Some("webhook") => Ok(Self::Webhook { url }),
+            Some("nats") => Ok(Self::Nats {
+                address: nats_address(&url)?,
+                topic: config.topic.clone(),
+                connection: None,
+            }),
+            Some("kafka") => Ok(Self::Kafka {
+                url: url.trim_end_matches('/').to_string(),
+                topic: config.topic.clone(),
+            }),
+            Some(other) => bail!("unknown [events] sink '{}'", other),
+            None => bail!("no [events] sink configured"),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::Nats { .. } => "NATS",
+            Self::Kafka { .. } => "Kafka",
+        }
+    }
+
+    async fn publish(&mut self, events: &[OutboxEvent]) -> Result<()> {
+        match self {
+            Self::Webhook { url } => post_json(url, "application/json", events).await,
+            Self::Kafka { url, topic } => {
+                let records: Vec<_> = events
+                    .iter()
+                    .map(|event| serde_json::json!({ "key": event.session_id, "value": event }))
+                    .collect();
+                post_json(
+                    &format!("{}/topics/{}", url, topic),
+                    "application/vnd.kafka.json.v2+json",
+                    &serde_json::json!({ "records": records }),
+                )
+                .await
+            }
+            Self::Nats {
+                address,
+                topic,
+                connection,
+            } => {
+                if connection.is_none() {
+                    *connection = Some(nats_connect(address).await?);
+                }
+                let stream = connection.as_mut().expect("connected above");
+                let result = nats_publish(stream, topic, events).await;
+                if result.is_err() {
+                    // Reconnect on the next attempt
+                    *connection = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+#[cfg(feature = "api")]
+async fn post_json<T: serde::Serialize + ?Sized>(
+    url: &str,
+    content_type: &str,
+    body: &T,
+) -> Result<()> {
+    let body = serde_json::to_vec(body)?;
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .with_context(|| format!("sending events to {}", url))?;
+    if !response.status().is_success() {
+        bail!("{} answered {}", url, response.status());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "api"))]
+async fn post_json<T: serde::Serialize + ?Sized>(
+    _url: &str,
+    _content_type: &str,
+    _body: &T,
+) -> Result<()> {
+    bail!("the webhook and kafka sinks need spec-ai built with the `api` feature")
+}
+
+/// `host:port` of a `nats://host[:port]` url
+fn nats_address(url: &str) -> Result<String> {
+    let rest = url.strip_prefix("nats://").unwrap_or(url);
+    let host_port = rest.split('/').next().unwrap_or_default();
+    if host_port.is_empty() {
+        bail!("NATS url '{}' has no host", url);
+    }
+    if host_port.contains(':') {
+        Ok(host_port.to_string())
+    } else {
+        Ok(format!("{}:{}", host_port, DEFAULT_NATS_PORT))
+    }
+}
+
+/// Subject of an event: the topic followed by the event kind
+fn nats_subject(topic: &str, event: &OutboxEvent) -> String {
+    format!("{}.{}", topic, event.kind)
+}
+
+/// `PUB` message for one payload
+fn nats_pub_frame(subject: &str, payload: &[u8]) -> Vec<u8> {
+    let mut frame = format!("PUB {} {}\r\n", subject, payload.len()).into_bytes();
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(b"\r\n");
+    frame
+}
+
+async fn nats_connect(address: &str) -> Result<BufReader<TcpStream>> {
+    let stream = TcpStream::connect(address)
+        .await
+        .with_context(|| format!("connecting to NATS at {}", address))?;
+    let mut stream = BufReader::new(stream);
+    // The server introduces itself with INFO before anything else
+    let info = read_nats_line(&mut stream).await?;
+    if !info.starts_with("INFO") {
+        bail!("unexpected NATS greeting: {}", info);
+    }
+    stream
+        .get_mut()
+        .write_all(b"CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"spec-ai\"}\r\n")
+        .await?;
+    Ok(stream)
+}
+
+/// Publish `events` and wait until the server has processed them
+async fn nats_publish(
+    stream: &mut BufReader<TcpStream>,
+    topic: &str,
+    events: &[OutboxEvent],
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    for event in events {
+        let payload = serde_json::to_vec(event)?;
+        buffer.extend(nats_pub_frame(&nats_subject(topic, event), &payload));
+    }
+    // The server answers PING only after everything sent before it
+    buffer.extend_from_slice(b"PING\r\n");
+    stream.get_mut().write_all(&buffer).await?;
+
+    loop {
+        let line = read_nats_line(stream).await?;
+        if line == "PONG" {
+            return Ok(());
+        } else if line == "PING" {
+            stream.get_mut().write_all(b"PONG\r\n").await?;
+        } else if line.starts_with("-ERR") {
+            bail!("NATS rejected the events: {}", line);
+        }
+    }
+}
+
+async fn read_nats_line(stream: &mut BufReader<TcpStream>) -> Result<String> {
+    let mut line = String::new();
+    let read = tokio::time::timeout(Duration::from_secs(30), stream.read_line(&mut line))
+        .await
+        .context("timed out waiting for NATS")??;
+    if read == 0 {
+        bail!("NATS closed the connection");
+    }
+    Ok(line.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    #[test]
+    fn nats_urls_default_the_port() {
+        assert_eq!(nats_address("nats://localhost").unwrap(), "localhost:4222");
+        assert_eq!(
+            nats_address("nats://bus.internal:4300/").unwrap(),
+            "bus.internal:4300"
+        );
+        assert!(nats_address("nats://").is_err());
+    }
+
+    #[test]
+    fn nats_frames_carry_the_payload_length() {
+        let event = OutboxEvent {
+            id: 1,
+            kind: "graph".to_string(),
+            session_id: "s".to_string(),
+            payload: json!({}),
+            created_at: Utc::now(),
+        };
+        let subject = nats_subject("spec-ai.events", &event);
+        assert_eq!(subject, "spec-ai.events.graph");
+        assert_eq!(
+            nats_pub_frame(&subject, b"{\"a\":1}"),
+            b"PUB spec-ai.events.graph 7\r\n{\"a\":1}\r\n"
+        );
+    }
+}
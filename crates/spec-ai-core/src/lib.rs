@@ -1,13 +1,25 @@
 pub mod agent;
 pub mod bootstrap_self;
 pub mod cli;
+pub mod clock;
+pub mod daemon;
 pub mod embeddings;
+pub mod events;
 #[cfg(feature = "api")]
 pub mod mesh;
+pub mod mesh_invite;
+pub mod panel;
+pub mod progress;
+pub mod quota;
 pub mod spec;
+pub mod spec_library;
+pub mod spec_source;
+pub mod spec_suggest;
+pub mod spec_vars;
 #[cfg(feature = "api")]
 pub mod sync;
 pub mod test_utils;
+pub mod tokenizer;
 pub mod tools;
 
 pub use spec_ai_config::{config, persistence, types};
@@ -1,13 +1,31 @@
 //! Shared mesh protocol types and client helpers.
+use crate::config::{AgentRegistry, AppConfig, MeshRole};
+use crate::persistence::Persistence;
+use crate::quota::{QuotaExceeded, QuotaTracker};
+use crate::tools::{RemoteToolExecutor, ToolRegistry, ToolResult};
 use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use hostname::get as get_hostname;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use uuid::{NoContext, Timestamp, Uuid};
 
+pub use crate::persistence::{InstanceStats, MESH_STATS_RETENTION_SECS};
+
+/// How long a remote tool call may take unless configured otherwise
+pub const DEFAULT_REMOTE_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often pending messages are polled for tool calls and their results
+const TOOL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Sessions changed within this window count as active in heartbeat stats
+const ACTIVE_SESSION_WINDOW_MINUTES: i64 = 15;
+
 /// Agent instance information in the mesh
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshInstance {
@@ -19,6 +37,154 @@ pub struct MeshInstance {
     pub last_heartbeat: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub agent_profiles: Vec<String>,
+    /// Tools this instance runs for other members as `remote:<tool>`
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Full catalog, when the instance sent one at registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<CapabilityCatalog>,
+    #[serde(default)]
+    pub role: MeshRole,
+}
+
+/// What an instance can do, served at `GET /v1/capabilities` and exchanged
+/// when instances register so peers can route and delegate work knowingly
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityCatalog {
+    pub instance_id: String,
+    /// spec-ai version the instance runs
+    pub version: String,
+    pub agents: Vec<AgentCapability>,
+    pub tools: Vec<ToolCapability>,
+    /// Model providers compiled into this build
+    pub providers: Vec<String>,
+    /// Default model as `provider/model`
+    pub default_model: Option<String>,
+    /// Optional features compiled into this build
+    pub features: Vec<String>,
+}
+
+/// An agent profile as advertised to peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentCapability {
+    pub name: String,
+    pub style: Option<String>,
+    /// `provider/model` when the profile overrides the default model
+    pub model: Option<String>,
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub denied_tools: Option<Vec<String>>,
+}
+
+/// A tool as advertised to peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCapability {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the tool's arguments
+    pub parameters: Value,
+    /// Whether other members may run it as `remote:<name>`
+    #[serde(default)]
+    pub shared: bool,
+}
+
+impl CapabilityCatalog {
+    /// Catalog of this build, its configuration, agents and tools
+    pub fn build(
+        instance_id: impl Into<String>,
+        config: &AppConfig,
+        agents: &AgentRegistry,
+        tools: &ToolRegistry,
+        shared_tools: &[String],
+    ) -> Self {
+        let agents = agents
+            .list()
+            .into_iter()
+            .filter_map(|name| {
+                let profile = agents.get(&name)?;
+                let model = profile.model_name.as_ref().map(|model| {
+                    let provider = profile
+                        .model_provider
+                        .as_deref()
+                        .unwrap_or(&config.model.provider);
+                    format!("{}/{}", provider, model)
+                });
+                Some(AgentCapability {
+                    name,
+                    style: profile.style,
+                    model,
+                    allowed_tools: profile.allowed_tools,
+                    denied_tools: profile.denied_tools,
+                })
+            })
+            .collect();
+
+        let mut names = tools.list();
+        names.sort_unstable();
+        let tools = names
+            .into_iter()
+            .filter_map(|name| {
+                let tool = tools.get(name)?;
+                Some(ToolCapability {
+                    name: name.to_string(),
+                    description: tools.describe(name)?,
+                    parameters: tool.parameters(),
+                    shared: shared_tools.iter().any(|shared| shared == name),
+                })
+            })
+            .collect();
+
+        let default_model = Some(match &config.model.model_name {
+            Some(model) => format!("{}/{}", config.model.provider, model),
+            None => config.model.provider.clone(),
+        });
+
+        Self {
+            instance_id: instance_id.into(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            agents,
+            tools,
+            providers: compiled_providers(),
+            default_model,
+            features: compiled_features(),
+        }
+    }
+
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|tool| tool.name == name)
+    }
+
+    pub fn has_agent(&self, name: &str) -> bool {
+        self.agents.iter().any(|agent| agent.name == name)
+    }
+}
+
+fn compiled_providers() -> Vec<String> {
+    [
+        ("mock", true),
+        ("openai", cfg!(feature = "openai")),
+        ("anthropic", cfg!(feature = "anthropic")),
+        ("ollama", cfg!(feature = "ollama")),
+        ("mlx", cfg!(feature = "mlx")),
+        ("lmstudio", cfg!(feature = "lmstudio")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+fn compiled_features() -> Vec<String> {
+    [
+        ("api", cfg!(feature = "api")),
+        ("vttrs", cfg!(feature = "vttrs")),
+        ("web-scraping", cfg!(feature = "web-scraping")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
 }
 
 /// Request to register a new instance
@@ -29,6 +195,17 @@ pub struct RegisterRequest {
     pub port: u16,
     pub capabilities: Vec<String>,
     pub agent_profiles: Vec<String>,
+    /// Tools offered for remote execution
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<CapabilityCatalog>,
+    /// Token from `spec-ai mesh invite`; limits the capabilities recorded
+    /// for this instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_token: Option<String>,
+    #[serde(default)]
+    pub role: MeshRole,
 }
 
 /// Response from registration
@@ -63,6 +240,129 @@ pub struct HeartbeatResponse {
     pub should_sync: bool,
 }
 
+/// This instance's stats for its next heartbeat; `heartbeat_latency` is the
+/// round trip of the previous one
+pub fn collect_instance_stats(
+    persistence: &Persistence,
+    heartbeat_latency: Option<Duration>,
+    quotas: Option<&QuotaTracker>,
+) -> InstanceStats {
+    let window = chrono::Duration::minutes(ACTIVE_SESSION_WINDOW_MINUTES);
+    let mut stats = persistence
+        .local_instance_stats(window)
+        .unwrap_or_else(|err| {
+            tracing::warn!("Failed to collect mesh stats: {}", err);
+            InstanceStats::default()
+        });
+    stats.tokens_used = crate::agent::model::tokens_used();
+    stats.heartbeat_latency_ms = heartbeat_latency.map(|d| d.as_secs_f64() * 1000.0);
+    stats.quota = quotas.map(QuotaTracker::usage);
+    stats
+}
+
+/// Health of one instance over the leader's stats window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceHealth {
+    pub instance_id: String,
+    pub hostname: String,
+    pub is_leader: bool,
+    /// Whether the instance heartbeated within the leader timeout
+    pub healthy: bool,
+    pub seconds_since_heartbeat: i64,
+    /// Most recent stats the instance reported
+    pub latest: Option<InstanceStats>,
+    /// Stats samples in the window
+    pub samples: usize,
+    pub tokens_in_window: u64,
+    pub tool_calls_in_window: u64,
+    pub avg_heartbeat_latency_ms: Option<f64>,
+    pub max_sync_lag_secs: Option<i64>,
+}
+
+impl InstanceHealth {
+    /// Summarize `samples` (oldest first) reported by an instance
+    pub fn from_samples(
+        instance_id: &str,
+        hostname: &str,
+        is_leader: bool,
+        last_heartbeat: DateTime<Utc>,
+        samples: &[InstanceStats],
+        stale_after_secs: u64,
+    ) -> Self {
+        let seconds_since_heartbeat = (Utc::now() - last_heartbeat).num_seconds().max(0);
+        let latencies: Vec<f64> = samples
+            .iter()
+            .filter_map(|s| s.heartbeat_latency_ms)
+            .collect();
+        Self {
+            instance_id: instance_id.to_string(),
+            hostname: hostname.to_string(),
+            is_leader,
+            healthy: seconds_since_heartbeat <= stale_after_secs as i64,
+            seconds_since_heartbeat,
+            latest: samples.last().cloned(),
+            samples: samples.len(),
+            tokens_in_window: counter_growth(samples.iter().map(|s| s.tokens_used)),
+            tool_calls_in_window: counter_growth(samples.iter().map(|s| s.tool_calls)),
+            avg_heartbeat_latency_ms: (!latencies.is_empty())
+                .then(|| latencies.iter().sum::<f64>() / latencies.len() as f64),
+            max_sync_lag_secs: samples.iter().filter_map(|s| s.sync_lag_secs).max(),
+        }
+    }
+}
+
+/// How much a cumulative counter grew across `values`. A drop means the
+/// instance restarted, so the new value counts as growth from zero.
+fn counter_growth(values: impl Iterator<Item = u64>) -> u64 {
+    let mut growth = 0;
+    let mut previous: Option<u64> = None;
+    for value in values {
+        growth += match previous {
+            Some(prev) if value >= prev => value - prev,
+            Some(_) => value,
+            None => 0,
+        };
+        previous = Some(value);
+    }
+    growth
+}
+
+/// Cluster health served by the leader at `GET /mesh/stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshStatsResponse {
+    pub leader_id: Option<String>,
+    /// Seconds of history the window covers
+    pub window_secs: u64,
+    pub instances: Vec<InstanceHealth>,
+    pub healthy: usize,
+    pub active_sessions: u64,
+    pub tokens_in_window: u64,
+    pub tool_calls_in_window: u64,
+}
+
+impl MeshStatsResponse {
+    pub fn new(leader_id: Option<String>, mut instances: Vec<InstanceHealth>) -> Self {
+        instances.sort_by(|a, b| {
+            b.is_leader
+                .cmp(&a.is_leader)
+                .then_with(|| a.instance_id.cmp(&b.instance_id))
+        });
+        Self {
+            leader_id,
+            window_secs: MESH_STATS_RETENTION_SECS as u64,
+            healthy: instances.iter().filter(|i| i.healthy).count(),
+            active_sessions: instances
+                .iter()
+                .filter_map(|i| i.latest.as_ref())
+                .map(|s| s.active_sessions)
+                .sum(),
+            tokens_in_window: instances.iter().map(|i| i.tokens_in_window).sum(),
+            tool_calls_in_window: instances.iter().map(|i| i.tool_calls_in_window).sum(),
+            instances,
+        }
+    }
+}
+
 /// Message types for inter-agent communication
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MessageType {
@@ -72,6 +372,8 @@ pub enum MessageType {
     TaskDelegation,
     TaskResult,
     GraphSync,
+    ToolCall,
+    ToolResult,
     Custom(String),
 }
 
@@ -84,6 +386,8 @@ impl MessageType {
             MessageType::TaskDelegation => "task_delegation".to_string(),
             MessageType::TaskResult => "task_result".to_string(),
             MessageType::GraphSync => "graph_sync".to_string(),
+            MessageType::ToolCall => "tool_call".to_string(),
+            MessageType::ToolResult => "tool_result".to_string(),
             MessageType::Custom(s) => s.clone(),
         }
     }
@@ -96,6 +400,8 @@ impl MessageType {
             "task_delegation" => MessageType::TaskDelegation,
             "task_result" => MessageType::TaskResult,
             "graph_sync" => MessageType::GraphSync,
+            "tool_call" => MessageType::ToolCall,
+            "tool_result" => MessageType::ToolResult,
             custom => MessageType::Custom(custom.to_string()),
         }
     }
@@ -161,23 +467,9 @@ impl MeshClient {
         format!("{}-{}", hostname, uuid)
     }
 
-    /// Register this instance with a mesh registry
-    pub async fn register(
-        &self,
-        instance_id: String,
-        hostname: String,
-        port: u16,
-        capabilities: Vec<String>,
-        agent_profiles: Vec<String>,
-    ) -> Result<RegisterResponse> {
-        let request = RegisterRequest {
-            instance_id,
-            hostname,
-            port,
-            capabilities,
-            agent_profiles,
-        };
-
+    /// Register this instance with a mesh registry. The response lists the
+    /// peers, with the catalog each of them registered.
+    pub async fn register(&self, request: RegisterRequest) -> Result<RegisterResponse> {
         let response = self
             .client
             .post(format!("{}/registry/register", self.base_url))
@@ -220,6 +512,36 @@ impl MeshClient {
         }
     }
 
+    /// Capability catalog of the instance this client points at
+    pub async fn capabilities(&self) -> Result<CapabilityCatalog> {
+        let response = self
+            .client
+            .get(format!("{}/v1/capabilities", self.base_url))
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            anyhow::bail!("Failed to fetch capabilities: {}", response.status())
+        }
+    }
+
+    /// Cluster health from the leader
+    pub async fn stats(&self) -> Result<MeshStatsResponse> {
+        let response = self
+            .client
+            .get(format!("{}/mesh/stats", self.base_url))
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            anyhow::bail!("Failed to fetch mesh stats: {}", response.status())
+        }
+    }
+
     /// List all instances in the mesh
     pub async fn list_instances(&self) -> Result<InstancesResponse> {
         let response = self
@@ -281,6 +603,13 @@ impl MeshClient {
 
         if response.status().is_success() {
             Ok(response.json().await?)
+        } else if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            // The leader refused the message; hand the quota to the caller
+            let body: Value = response.json().await.unwrap_or_default();
+            match serde_json::from_value::<QuotaExceeded>(body["details"].clone()) {
+                Ok(err) => Err(err.into()),
+                Err(_) => anyhow::bail!("Send message failed: quota exceeded"),
+            }
         } else {
             anyhow::bail!("Send message failed: {}", response.status())
         }
@@ -309,7 +638,7 @@ impl MeshClient {
     ) -> Result<()> {
         let response = self
             .client
-            .post(format!("{}/messages/{}/ack", self.base_url, instance_id))
+            .post(format!("{}/messages/ack/{}", self.base_url, instance_id))
             .json(&json!({ "message_ids": message_ids }))
             .send()
             .await?;
@@ -321,3 +650,363 @@ impl MeshClient {
         }
     }
 }
+
+/// Runs `remote:` tools on other instances over the mesh message bus.
+///
+/// A call is sent as a `tool_call` message to an instance advertising the
+/// tool; the caller then polls its own messages for the `tool_result` that
+/// carries the same correlation id, giving up after the timeout.
+pub struct MeshToolExecutor {
+    client: MeshClient,
+    instance_id: String,
+    timeout: Duration,
+    /// Instance id and advertised tools of every other member, as last seen
+    advertised: RwLock<Vec<(String, Vec<String>)>>,
+    /// Counts tool calls against the instance's hourly message quota
+    quotas: Option<Arc<QuotaTracker>>,
+}
+
+impl MeshToolExecutor {
+    pub fn new(client: MeshClient, instance_id: impl Into<String>) -> Self {
+        Self {
+            client,
+            instance_id: instance_id.into(),
+            timeout: DEFAULT_REMOTE_TOOL_TIMEOUT,
+            advertised: RwLock::new(Vec::new()),
+            quotas: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_quotas(mut self, quotas: Arc<QuotaTracker>) -> Self {
+        self.quotas = Some(quotas);
+        self
+    }
+
+    /// Re-read which members advertise which tools
+    pub async fn refresh(&self) -> Result<()> {
+        let instances = self.client.list_instances().await?.instances;
+        let advertised = instances
+            .into_iter()
+            .filter(|instance| instance.instance_id != self.instance_id)
+            .map(|instance| (instance.instance_id, instance.tools))
+            .collect();
+        *self
+            .advertised
+            .write()
+            .expect("advertised tools lock poisoned") = advertised;
+        Ok(())
+    }
+
+    /// Refresh the tool list every `period` so the agent's prompt shows
+    /// what other members offer. Runs until the task is dropped.
+    pub async fn keep_fresh(self: Arc<Self>, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.refresh().await {
+                tracing::debug!("Could not refresh mesh tool list: {}", err);
+            }
+        }
+    }
+
+    fn instance_offering(&self, tool: &str) -> Option<String> {
+        self.advertised
+            .read()
+            .expect("advertised tools lock poisoned")
+            .iter()
+            .find(|(_, tools)| tools.iter().any(|t| t == tool))
+            .map(|(instance, _)| instance.clone())
+    }
+}
+
+#[async_trait]
+impl RemoteToolExecutor for MeshToolExecutor {
+    async fn execute_remote(
+        &self,
+        instance: Option<&str>,
+        tool: &str,
+        args: Value,
+    ) -> Result<ToolResult> {
+        if let Err(err) = self.refresh().await {
+            tracing::warn!("Could not refresh mesh tool list: {}", err);
+        }
+        let target = match instance {
+            Some(instance) => instance.to_string(),
+            None => match self.instance_offering(tool) {
+                Some(instance) => instance,
+                None => {
+                    return Ok(ToolResult::failure(format!(
+                        "No mesh instance offers the '{}' tool",
+                        tool
+                    )))
+                }
+            },
+        };
+
+        if let Some(err) = self.quotas.as_ref().and_then(|q| q.record_message().err()) {
+            return Ok(ToolResult::failure(err.to_string()));
+        }
+        let call_id = Uuid::new_v7(Timestamp::now(NoContext)).to_string();
+        let sent = self
+            .client
+            .send_message(
+                self.instance_id.clone(),
+                Some(target.clone()),
+                MessageType::ToolCall,
+                json!({ "tool": tool, "args": args }),
+                Some(call_id.clone()),
+            )
+            .await;
+        if let Err(err) = sent {
+            return match err.downcast_ref::<QuotaExceeded>() {
+                Some(quota) => Ok(ToolResult::failure(quota.to_string())),
+                None => Err(err),
+            };
+        }
+        tracing::debug!("Sent '{}' tool call {} to {}", tool, call_id, target);
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let pending = self.client.get_messages(&self.instance_id).await?;
+            let reply = pending.messages.into_iter().find(|message| {
+                message.message_type == MessageType::ToolResult
+                    && message.correlation_id.as_deref() == Some(call_id.as_str())
+            });
+            if let Some(reply) = reply {
+                self.client
+                    .acknowledge_messages(&self.instance_id, vec![reply.message_id])
+                    .await?;
+                return Ok(serde_json::from_value(reply.payload).unwrap_or_else(|err| {
+                    ToolResult::failure(format!("Malformed tool result from {}: {}", target, err))
+                }));
+            }
+            if Instant::now() >= deadline {
+                return Ok(ToolResult::failure(format!(
+                    "Remote tool '{}' on {} did not answer within {}s",
+                    tool,
+                    target,
+                    self.timeout.as_secs()
+                )));
+            }
+            tokio::time::sleep(TOOL_POLL_INTERVAL).await;
+        }
+    }
+
+    fn advertised_tools(&self) -> Vec<String> {
+        self.advertised
+            .read()
+            .expect("advertised tools lock poisoned")
+            .iter()
+            .flat_map(|(_, tools)| tools.iter().cloned())
+            .collect()
+    }
+}
+
+/// Answer `tool_call` messages addressed to `instance_id` by running the
+/// requested tool from `registry`, as long as it is one of `shared`. Each
+/// call gets `timeout`. Runs until the task is dropped.
+pub async fn serve_tool_calls(
+    client: MeshClient,
+    instance_id: String,
+    registry: Arc<ToolRegistry>,
+    shared: Vec<String>,
+    timeout: Duration,
+) {
+    loop {
+        match client.get_messages(&instance_id).await {
+            Ok(pending) => {
+                let calls = pending.messages.into_iter().filter(|message| {
+                    message.message_type == MessageType::ToolCall
+                        && message.target_instance.as_deref() == Some(instance_id.as_str())
+                });
+                for call in calls {
+                    // Acknowledge first so a slow tool is not picked up twice
+                    if let Err(err) = client
+                        .acknowledge_messages(&instance_id, vec![call.message_id.clone()])
+                        .await
+                    {
+                        tracing::warn!("Could not acknowledge tool call: {}", err);
+                        continue;
+                    }
+                    let result = run_tool_call(&registry, &shared, &call.payload, timeout).await;
+                    let payload = serde_json::to_value(&result).unwrap_or(Value::Null);
+                    if let Err(err) = client
+                        .send_message(
+                            instance_id.clone(),
+                            Some(call.source_instance.clone()),
+                            MessageType::ToolResult,
+                            payload,
+                            call.correlation_id.clone(),
+                        )
+                        .await
+                    {
+                        tracing::warn!(
+                            "Could not return tool result to {}: {}",
+                            call.source_instance,
+                            err
+                        );
+                    }
+                }
+            }
+            Err(err) => tracing::debug!("Polling for tool calls failed: {}", err),
+        }
+        tokio::time::sleep(TOOL_POLL_INTERVAL).await;
+    }
+}
+
+/// Run one `tool_call` payload (`{"tool": ..., "args": ...}`)
+async fn run_tool_call(
+    registry: &ToolRegistry,
+    shared: &[String],
+    payload: &Value,
+    timeout: Duration,
+) -> ToolResult {
+    let Some(tool) = payload.get("tool").and_then(Value::as_str) else {
+        return ToolResult::failure("Tool call is missing the tool name");
+    };
+    if !shared.iter().any(|name| name == tool) {
+        return ToolResult::failure(format!("Tool '{}' is not shared with the mesh", tool));
+    }
+    let args = payload.get("args").cloned().unwrap_or(Value::Null);
+    match tokio::time::timeout(timeout, registry.execute(tool, args)).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => ToolResult::failure(err.to_string()),
+        Err(_) => ToolResult::failure(format!(
+            "Tool '{}' did not finish within {}s",
+            tool,
+            timeout.as_secs()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentProfile;
+    use crate::tools::builtin::{EchoTool, MathTool};
+
+    #[test]
+    fn catalog_lists_agents_tools_and_build() {
+        let mut config = AppConfig::default();
+        config.model.provider = "openai".into();
+        config.model.model_name = Some("gpt-4o".into());
+        let profiles = HashMap::from([(
+            "coder".to_string(),
+            AgentProfile {
+                model_provider: Some("anthropic".into()),
+                model_name: Some("claude-sonnet".into()),
+                allowed_tools: Some(vec!["echo".into()]),
+                ..AgentProfile::default()
+            },
+        )]);
+        let agents = AgentRegistry::new(profiles, Persistence::in_memory().unwrap());
+        let mut tools = ToolRegistry::new();
+        tools.register(Arc::new(MathTool::new()));
+        tools.register(Arc::new(EchoTool::new()));
+
+        let catalog =
+            CapabilityCatalog::build("node-1", &config, &agents, &tools, &["echo".into()]);
+        assert_eq!(catalog.instance_id, "node-1");
+        assert_eq!(catalog.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(catalog.default_model.as_deref(), Some("openai/gpt-4o"));
+        assert_eq!(
+            catalog.agents[0].model.as_deref(),
+            Some("anthropic/claude-sonnet")
+        );
+        assert!(catalog.has_agent("coder"));
+
+        let names: Vec<&str> = catalog.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["calculator", "echo"]);
+        assert!(!catalog.tools[0].shared && catalog.tools[1].shared);
+        assert!(catalog.tools[1].parameters["properties"]["message"].is_object());
+        assert!(catalog.providers.contains(&"mock".to_string()));
+        assert!(catalog.features.contains(&"api".to_string()));
+
+        // Registration without a catalog (older instances) still parses
+        let request: RegisterRequest = serde_json::from_value(json!({
+            "instance_id": "old", "hostname": "h", "port": 1,
+            "capabilities": [], "agent_profiles": []
+        }))
+        .unwrap();
+        assert!(request.catalog.is_none() && request.tools.is_empty());
+    }
+
+    #[test]
+    fn summarizes_heartbeat_stats() {
+        let sample = |tokens, tool_calls, lag, latency| InstanceStats {
+            active_sessions: 2,
+            tokens_used: tokens,
+            tool_calls,
+            sync_lag_secs: lag,
+            heartbeat_latency_ms: latency,
+            quota: None,
+        };
+        // The counters drop between the second and third sample: a restart
+        let samples = [
+            sample(100, 4, None, None),
+            sample(250, 6, Some(3), Some(10.0)),
+            sample(40, 1, Some(9), Some(20.0)),
+        ];
+        let health = InstanceHealth::from_samples(
+            "node-1",
+            "host",
+            false,
+            Utc::now() - chrono::Duration::seconds(2),
+            &samples,
+            15,
+        );
+        assert!(health.healthy);
+        assert_eq!(health.tokens_in_window, 190);
+        assert_eq!(health.tool_calls_in_window, 3);
+        assert_eq!(health.avg_heartbeat_latency_ms, Some(15.0));
+        assert_eq!(health.max_sync_lag_secs, Some(9));
+        assert_eq!(health.latest, Some(samples[2].clone()));
+
+        let stale = InstanceHealth::from_samples(
+            "leader",
+            "host",
+            true,
+            Utc::now() - chrono::Duration::seconds(60),
+            &[],
+            15,
+        );
+        assert!(!stale.healthy && stale.latest.is_none());
+
+        let stats = MeshStatsResponse::new(Some("leader".into()), vec![health, stale]);
+        assert_eq!(stats.instances[0].instance_id, "leader");
+        assert_eq!(stats.healthy, 1);
+        assert_eq!(stats.active_sessions, 2);
+        assert_eq!(stats.tokens_in_window, 190);
+
+        let metrics = samples[1].to_metrics();
+        assert_eq!(metrics["tokens_used"], json!(250));
+        assert_eq!(
+            InstanceStats::from_metrics(&metrics),
+            Some(samples[1].clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn runs_only_shared_tools_for_the_mesh() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new()));
+        let timeout = Duration::from_secs(5);
+
+        let call = json!({ "tool": "echo", "args": { "message": "hi" } });
+        let result = run_tool_call(&registry, &["echo".into()], &call, timeout).await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.output, "hi");
+
+        let result = run_tool_call(&registry, &[], &call, timeout).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("not shared"));
+
+        let result = run_tool_call(&registry, &["echo".into()], &json!({}), timeout).await;
+        assert!(!result.success);
+    }
+}
@@ -0,0 +1,243 @@
+//! Invite tokens for joining a mesh
+//!
+//! The leader mints join tokens with `spec-ai mesh invite` instead of every
+//! machine sharing one secret. A token is a small claims record (id,
+//! capabilities, expiry) sealed with ChaCha20-Poly1305 under the mesh key,
+//! which never leaves the leader: members cannot read, alter or extend the
+//! tokens they are given. When a member registers with a token, the leader
+//! only records the capabilities the token allows, so a member cannot claim
+//! `registry` or a sync ACL `tag:` it was not invited with.
+//!
+//! The mesh key lives in `~/.spec-ai/mesh.key` (created on first use)
+//! unless `[mesh] key_path` points elsewhere; `SPEC_AI_MESH_KEY` (base64)
+//! takes precedence.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::MeshConfig;
+use crate::spec_source::config_dir;
+
+/// Environment variable holding the mesh key as base64
+pub const KEY_ENV: &str = "SPEC_AI_MESH_KEY";
+
+/// Key file used when `[mesh] key_path` is not set
+const DEFAULT_KEY_PATH: &str = "~/.spec-ai/mesh.key";
+
+/// Marks a join token and its format version
+const TOKEN_PREFIX: &str = "sai-join1.";
+
+/// Capability that allows every other capability
+pub const ANY_CAPABILITY: &str = "*";
+
+/// What a join token lets its holder do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinClaims {
+    /// Identifies the token in logs
+    pub id: String,
+    /// Capabilities a member joining with the token may register
+    pub capabilities: Vec<String>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl JoinClaims {
+    pub fn allows(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|allowed| allowed == ANY_CAPABILITY || allowed == capability)
+    }
+
+    /// The requested capabilities this token allows
+    pub fn bind(&self, requested: Vec<String>) -> Vec<String> {
+        requested
+            .into_iter()
+            .filter(|capability| self.allows(capability))
+            .collect()
+    }
+}
+
+/// A join token the leader refuses
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum JoinTokenError {
+    #[error("a join token is required to join this mesh")]
+    Missing,
+    #[error("join token is malformed")]
+    Malformed,
+    #[error("join token was not issued by this mesh")]
+    Invalid,
+    #[error("join token expired at {0}")]
+    Expired(DateTime<Utc>),
+}
+
+/// The leader's mesh key, which mints and checks join tokens
+pub struct MeshKeyring {
+    key: LessSafeKey,
+}
+
+impl std::fmt::Debug for MeshKeyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MeshKeyring")
+    }
+}
+
+impl MeshKeyring {
+    /// The keyring at `[mesh] key_path`, creating its key on first use
+    pub fn from_config(config: &MeshConfig) -> Result<Self> {
+        let key = load_key(config.key_path.as_deref().unwrap_or(DEFAULT_KEY_PATH))?;
+        Ok(Self::with_key(key))
+    }
+
+    pub fn with_key(key: [u8; 32]) -> Self {
+        let sealing_key = blake3::derive_key("spec-ai mesh join token", &key);
+        Self {
+            key: LessSafeKey::new(
+                UnboundKey::new(&CHACHA20_POLY1305, &sealing_key).expect("32-byte ChaCha20 key"),
+            ),
+        }
+    }
+
+    /// Mint a token allowing `capabilities` that expires after `ttl`
+    pub fn mint(&self, capabilities: Vec<String>, ttl: Duration) -> Result<(String, JoinClaims)> {
+        let issued_at = Utc::now();
+        let claims = JoinClaims {
+            id: uuid::Uuid::new_v4().to_string(),
+            capabilities,
+            issued_at,
+            expires_at: issued_at + ttl,
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("no randomness available for a join token"))?;
+        let mut sealed = serde_json::to_vec(&claims)?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(TOKEN_PREFIX),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow!("sealing join token failed"))?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend(sealed);
+        let token = format!(
+            "{}{}",
+            TOKEN_PREFIX,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+        );
+        Ok((token, claims))
+    }
+
+    /// The claims of `token` if this keyring minted it and it has not
+    /// expired at `now`
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Result<JoinClaims, JoinTokenError> {
+        let encoded = token
+            .trim()
+            .strip_prefix(TOKEN_PREFIX)
+            .ok_or(JoinTokenError::Malformed)?;
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| JoinTokenError::Malformed)?;
+        if bytes.len() <= NONCE_LEN {
+            return Err(JoinTokenError::Malformed);
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let nonce =
+            Nonce::try_assume_unique_for_key(nonce).map_err(|_| JoinTokenError::Malformed)?;
+        let mut sealed = sealed.to_vec();
+        let opened = self
+            .key
+            .open_in_place(nonce, Aad::from(TOKEN_PREFIX), &mut sealed)
+            .map_err(|_| JoinTokenError::Invalid)?;
+        let claims: JoinClaims =
+            serde_json::from_slice(opened).map_err(|_| JoinTokenError::Malformed)?;
+        if claims.expires_at <= now {
+            return Err(JoinTokenError::Expired(claims.expires_at));
+        }
+        Ok(claims)
+    }
+}
+
+fn load_key(path: &str) -> Result<[u8; 32]> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let parse = |encoded: &str| -> Result<[u8; 32]> {
+        engine
+            .decode(encoded.trim())
+            .context("mesh key is not valid base64")?
+            .try_into()
+            .map_err(|_| anyhow!("mesh key must be 32 bytes"))
+    };
+    if let Ok(encoded) = std::env::var(KEY_ENV) {
+        return parse(&encoded).with_context(|| format!("reading {}", KEY_ENV));
+    }
+    let path = config_dir(path)?;
+    spec_ai_config::secret_key::load_or_create(&path, "mesh key", parse, |key| engine.encode(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_bind_capabilities_until_they_expire() {
+        let keyring = MeshKeyring::with_key([1u8; 32]);
+        let (token, claims) = keyring
+            .mint(vec!["query".to_string()], Duration::hours(1))
+            .unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+
+        let verified = keyring.verify(&token, Utc::now()).unwrap();
+        assert_eq!(verified, claims);
+        assert_eq!(
+            verified.bind(vec!["query".to_string(), "registry".to_string()]),
+            vec!["query".to_string()]
+        );
+
+        assert_eq!(
+            keyring.verify(&token, claims.expires_at),
+            Err(JoinTokenError::Expired(claims.expires_at))
+        );
+        assert_eq!(
+            MeshKeyring::with_key([2u8; 32]).verify(&token, Utc::now()),
+            Err(JoinTokenError::Invalid)
+        );
+        assert_eq!(
+            keyring.verify("not-a-token", Utc::now()),
+            Err(JoinTokenError::Malformed)
+        );
+
+        // Flipping any byte breaks the seal
+        let mut tampered = token.into_bytes();
+        let last = tampered.len() - 2;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert!(keyring.verify(&tampered, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn wildcard_tokens_allow_everything() {
+        let keyring = MeshKeyring::with_key([3u8; 32]);
+        let (token, _) = keyring
+            .mint(vec![ANY_CAPABILITY.to_string()], Duration::minutes(5))
+            .unwrap();
+        let claims = keyring.verify(&token, Utc::now()).unwrap();
+        assert!(claims.allows("registry"));
+        assert!(claims.allows("gpu"));
+    }
+
+    #[test]
+    fn keys_are_created_once_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("mesh.key");
+        let path = path.to_str().unwrap();
+        let first = load_key(path).unwrap();
+        assert_eq!(load_key(path).unwrap(), first);
+    }
+}
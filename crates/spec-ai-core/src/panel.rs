@@ -0,0 +1,170 @@
+//! Declarative UI panels that specs and tools can publish during a run
+//!
+//! A panel is a small JSON/TOML document describing what to show, never how:
+//! key-value summaries, tables and step progress. Specs declare initial panels
+//! under `[[panels]]`; tools update them by including a `"panel"` object (or a
+//! `"panels"` array) in their JSON output. Panels are matched by `id`, so a
+//! later update replaces the earlier content in place.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Status of a single step in a progress panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    Running,
+    Done,
+    Failed,
+    Skipped,
+}
+
+/// One step of a progress panel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelStep {
+    pub label: String,
+    #[serde(default)]
+    pub status: StepStatus,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Key/value row of a key-value panel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelEntry {
+    pub key: String,
+    pub value: String,
+}
+
+/// Panel content, tagged by `kind`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PanelContent {
+    KeyValue {
+        #[serde(default)]
+        entries: Vec<PanelEntry>,
+    },
+    Table {
+        columns: Vec<String>,
+        #[serde(default)]
+        rows: Vec<Vec<String>>,
+    },
+    Progress {
+        #[serde(default)]
+        steps: Vec<PanelStep>,
+    },
+}
+
+/// A declared panel: identity, title and content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelSpec {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub content: PanelContent,
+}
+
+impl PanelSpec {
+    /// Title to display, falling back to the id
+    pub fn display_title(&self) -> &str {
+        self.title
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .unwrap_or(&self.id)
+    }
+
+    /// Check structural invariants that serde cannot express
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.id.trim().is_empty() {
+            anyhow::bail!("panel id must not be empty");
+        }
+        if let PanelContent::Table { columns, rows } = &self.content {
+            if columns.is_empty() {
+                anyhow::bail!("table panel '{}' must declare columns", self.id);
+            }
+            if let Some(bad) = rows.iter().position(|r| r.len() != columns.len()) {
+                anyhow::bail!(
+                    "table panel '{}' row {} has {} cells, expected {}",
+                    self.id,
+                    bad + 1,
+                    rows[bad].len(),
+                    columns.len()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract panel declarations from a tool's JSON output, if any.
+    ///
+    /// Accepts `{"panel": {...}}` or `{"panels": [{...}, ...]}`; invalid
+    /// declarations are skipped so a malformed panel never fails a tool call.
+    pub fn from_tool_output(output: &str) -> Vec<PanelSpec> {
+        let Ok(value) = serde_json::from_str::<Value>(output) else {
+            return Vec::new();
+        };
+        let candidates: Vec<Value> = match (value.get("panel"), value.get("panels")) {
+            (Some(panel), _) => vec![panel.clone()],
+            (None, Some(Value::Array(panels))) => panels.clone(),
+            _ => Vec::new(),
+        };
+        candidates
+            .into_iter()
+            .filter_map(|v| serde_json::from_value::<PanelSpec>(v).ok())
+            .filter(|p| p.validate().is_ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_panels_from_tool_output() {
+        let output = json!({
+            "status": "ok",
+            "panel": {
+                "id": "deploy",
+                "title": "Deployment",
+                "kind": "progress",
+                "steps": [
+                    {"label": "build", "status": "done"},
+                    {"label": "push", "status": "running"}
+                ]
+            }
+        })
+        .to_string();
+
+        let panels = PanelSpec::from_tool_output(&output);
+        assert_eq!(panels.len(), 1);
+        assert_eq!(panels[0].display_title(), "Deployment");
+        match &panels[0].content {
+            PanelContent::Progress { steps } => {
+                assert_eq!(steps[1].status, StepStatus::Running);
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn skips_invalid_tables_and_non_json_output() {
+        let output = json!({
+            "panels": [
+                {"id": "bad", "kind": "table", "columns": ["a", "b"], "rows": [["1"]]},
+                {"id": "env", "kind": "key_value", "entries": [{"key": "region", "value": "eu"}]}
+            ]
+        })
+        .to_string();
+        let panels = PanelSpec::from_tool_output(&output);
+        assert_eq!(panels.len(), 1);
+        assert_eq!(panels[0].id, "env");
+
+        assert!(PanelSpec::from_tool_output("plain text").is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Progress reporting for long-running operations
+//!
+//! Bootstrap, ingestion, sync and model calls publish [`ProgressEvent`]s through a
+//! [`ProgressReporter`]. Front-ends attach a [`ProgressSink`] to decide how events
+//! are rendered; the REPL prints them as plain progress lines (see
+//! `cli::progress`). A reporter without a sink is a cheap no-op, so library code
+//! can report unconditionally. Panels declared by specs and tools travel the same
+//! stream as `panel` events, so front-ends can redraw them while a run is going.
+
+use crate::panel::PanelSpec;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Lifecycle state of a reported operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressState {
+    Started,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// A single progress update for a named operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    /// Operation identifier, e.g. "bootstrap" or "model"
+    pub operation: String,
+    /// Lifecycle state
+    pub state: ProgressState,
+    /// Human readable description of the current step
+    pub message: String,
+    /// Completed units of work, when known
+    pub current: Option<u64>,
+    /// Total units of work, when known (None renders as indeterminate)
+    pub total: Option<u64>,
+    /// Panels published with this event, replacing earlier ones by id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub panels: Vec<PanelSpec>,
+}
+
+impl ProgressEvent {
+    /// Fraction complete in `[0.0, 1.0]`, or None when the total is unknown
+    pub fn fraction(&self) -> Option<f32> {
+        match (self.current, self.total) {
+            (Some(current), Some(total)) if total > 0 => {
+                Some((current as f32 / total as f32).clamp(0.0, 1.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the operation has ended (successfully or not)
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.state, ProgressState::Finished | ProgressState::Failed)
+    }
+}
+
+/// Destination for progress events
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+}
+
+/// Sink that forwards events over an unbounded channel
+pub struct ChannelProgressSink {
+    tx: mpsc::UnboundedSender<ProgressEvent>,
+}
+
+impl ChannelProgressSink {
+    /// Create a sink and the receiver that observes its events
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<ProgressEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+}
+
+impl ProgressSink for ChannelProgressSink {
+    fn report(&self, event: ProgressEvent) {
+        // A dropped receiver just means nobody is watching anymore
+        let _ = self.tx.send(event);
+    }
+}
+
+/// Cloneable handle used by operations to publish progress
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    sink: Option<Arc<dyn ProgressSink>>,
+}
+
+impl std::fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("enabled", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl ProgressReporter {
+    /// Reporter that discards all events
+    pub fn noop() -> Self {
+        Self::default()
+    }
+
+    /// Reporter that forwards events to the given sink
+    pub fn new(sink: Arc<dyn ProgressSink>) -> Self {
+        Self { sink: Some(sink) }
+    }
+
+    /// Whether a sink is attached
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Publish a raw event
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(sink) = &self.sink {
+            sink.report(event);
+        }
+    }
+
+    /// Announce the start of an operation
+    pub fn started(&self, operation: &str, message: impl Into<String>, total: Option<u64>) {
+        self.emit(
+            operation,
+            ProgressState::Started,
+            message,
+            total.map(|_| 0),
+            total,
+        );
+    }
+
+    /// Report an intermediate step
+    pub fn step(
+        &self,
+        operation: &str,
+        message: impl Into<String>,
+        current: Option<u64>,
+        total: Option<u64>,
+    ) {
+        self.emit(operation, ProgressState::Running, message, current, total);
+    }
+
+    /// Announce successful completion
+    pub fn finished(&self, operation: &str, message: impl Into<String>) {
+        self.emit(operation, ProgressState::Finished, message, None, None);
+    }
+
+    /// Announce failure
+    pub fn failed(&self, operation: &str, message: impl Into<String>) {
+        self.emit(operation, ProgressState::Failed, message, None, None);
+    }
+
+    /// Publish panels declared by `source`, a spec or tool name
+    pub fn panels(&self, source: &str, panels: Vec<PanelSpec>) {
+        if self.sink.is_none() || panels.is_empty() {
+            return;
+        }
+        self.report(ProgressEvent {
+            operation: "panel".to_string(),
+            state: ProgressState::Running,
+            message: format!("{} updated {} panel(s)", source, panels.len()),
+            current: None,
+            total: None,
+            panels,
+        });
+    }
+
+    fn emit(
+        &self,
+        operation: &str,
+        state: ProgressState,
+        message: impl Into<String>,
+        current: Option<u64>,
+        total: Option<u64>,
+    ) {
+        if self.sink.is_none() {
+            return;
+        }
+        self.report(ProgressEvent {
+            operation: operation.to_string(),
+            state,
+            message: message.into(),
+            current,
+            total,
+            panels: Vec::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_sink_receives_events_in_order() {
+        let (sink, mut rx) = ChannelProgressSink::new();
+        let reporter = ProgressReporter::new(Arc::new(sink));
+
+        reporter.started("bootstrap", "scanning", Some(3));
+        reporter.step("bootstrap", "plugin rust-cargo", Some(1), Some(3));
+        reporter.finished("bootstrap", "done");
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.state, ProgressState::Started);
+        assert_eq!(first.fraction(), Some(0.0));
+
+        let second = rx.try_recv().unwrap();
+        assert_eq!(second.state, ProgressState::Running);
+        assert!((second.fraction().unwrap() - 1.0 / 3.0).abs() < f32::EPSILON);
+
+        let third = rx.try_recv().unwrap();
+        assert!(third.is_terminal());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn noop_reporter_is_disabled() {
+        let reporter = ProgressReporter::noop();
+        assert!(!reporter.is_enabled());
+        reporter.step("model", "ignored", None, None);
+    }
+}
@@ -0,0 +1,342 @@
+//! Usage quotas
+//!
+//! `[mesh.quotas]` caps how much work an instance takes on: concurrent agent
+//! runs, provider tokens per UTC day and mesh messages per hour, with
+//! optional per-agent caps on runs and tokens. [`QuotaTracker`] enforces
+//! them locally; its [`QuotaUsage`] travels in heartbeats so the mesh leader
+//! can refuse to delegate work to an instance that is already at a limit.
+//! Both sides report a refusal as a [`QuotaExceeded`] error.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+use crate::config::{QuotaConfig, QuotaLimits};
+pub use crate::persistence::QuotaUsage;
+
+/// Which limit was hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    ConcurrentRuns,
+    DailyTokens,
+    MessagesPerHour,
+}
+
+impl QuotaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaKind::ConcurrentRuns => "concurrent runs",
+            QuotaKind::DailyTokens => "daily token budget",
+            QuotaKind::MessagesPerHour => "messages per hour",
+        }
+    }
+}
+
+/// A request refused because it would exceed a quota
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[error("{scope} is at its {} quota ({used}/{limit})", kind.as_str())]
+pub struct QuotaExceeded {
+    pub kind: QuotaKind,
+    /// What the quota belongs to, e.g. `instance node-1` or `agent coder`
+    pub scope: String,
+    pub limit: u64,
+    pub used: u64,
+    /// Seconds until the quota frees up, when that is predictable
+    pub retry_after_secs: Option<u64>,
+}
+
+impl QuotaExceeded {
+    /// Error code used in API responses
+    pub const CODE: &'static str = "quota_exceeded";
+}
+
+/// Check `usage` against `limits` for starting one more run
+pub fn check_run(
+    scope: &str,
+    limits: &QuotaLimits,
+    running: usize,
+    tokens_today: u64,
+    now: DateTime<Utc>,
+) -> Result<(), QuotaExceeded> {
+    if let Some(max) = limits.max_concurrent_runs {
+        if running >= max {
+            return Err(QuotaExceeded {
+                kind: QuotaKind::ConcurrentRuns,
+                scope: scope.to_string(),
+                limit: max as u64,
+                used: running as u64,
+                retry_after_secs: None,
+            });
+        }
+    }
+    if let Some(budget) = limits.daily_token_budget {
+        if tokens_today >= budget {
+            return Err(QuotaExceeded {
+                kind: QuotaKind::DailyTokens,
+                scope: scope.to_string(),
+                limit: budget,
+                used: tokens_today,
+                retry_after_secs: Some(seconds_until_midnight(now)),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn seconds_until_midnight(now: DateTime<Utc>) -> u64 {
+    let tomorrow = now.date_naive() + Duration::days(1);
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (midnight - now).num_seconds().max(0) as u64
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    running: usize,
+    tokens_today: u64,
+}
+
+#[derive(Debug)]
+struct QuotaState {
+    day: NaiveDate,
+    instance: Usage,
+    agents: HashMap<String, Usage>,
+    /// Send times of mesh messages in the last hour, oldest first
+    messages: VecDeque<DateTime<Utc>>,
+}
+
+impl QuotaState {
+    /// Reset token counts when the UTC day changes
+    fn roll_day(&mut self, now: DateTime<Utc>) {
+        let today = now.date_naive();
+        if today != self.day {
+            self.day = today;
+            self.instance.tokens_today = 0;
+            for usage in self.agents.values_mut() {
+                usage.tokens_today = 0;
+            }
+        }
+    }
+
+    fn prune_messages(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::hours(1);
+        while self.messages.front().is_some_and(|at| *at <= cutoff) {
+            self.messages.pop_front();
+        }
+    }
+}
+
+/// Enforces this instance's quotas
+#[derive(Debug)]
+pub struct QuotaTracker {
+    instance_id: String,
+    config: QuotaConfig,
+    state: Mutex<QuotaState>,
+}
+
+impl QuotaTracker {
+    pub fn new(instance_id: impl Into<String>, config: QuotaConfig) -> Self {
+        Self {
+            instance_id: instance_id.into(),
+            config,
+            state: Mutex::new(QuotaState {
+                day: Utc::now().date_naive(),
+                instance: Usage::default(),
+                agents: HashMap::new(),
+                messages: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, QuotaState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Start a run of `agent`, or refuse it if the instance or the agent is
+    /// at a limit. The run counts as in progress until the permit is dropped.
+    pub fn begin_run(self: &Arc<Self>, agent: &str) -> Result<RunPermit, QuotaExceeded> {
+        let now = Utc::now();
+        let mut state = self.state();
+        state.roll_day(now);
+
+        let instance = state.instance;
+        check_run(
+            &format!("instance {}", self.instance_id),
+            &self.config.instance,
+            instance.running,
+            instance.tokens_today,
+            now,
+        )?;
+        if let Some(limits) = self.config.agents.get(agent) {
+            let usage = state.agents.get(agent).copied().unwrap_or_default();
+            check_run(
+                &format!("agent {}", agent),
+                limits,
+                usage.running,
+                usage.tokens_today,
+                now,
+            )?;
+        }
+
+        state.instance.running += 1;
+        state.agents.entry(agent.to_string()).or_default().running += 1;
+        Ok(RunPermit {
+            tracker: Arc::clone(self),
+            agent: agent.to_string(),
+        })
+    }
+
+    /// Count tokens a run of `agent` used
+    pub fn record_tokens(&self, agent: &str, tokens: u64) {
+        let mut state = self.state();
+        state.roll_day(Utc::now());
+        state.instance.tokens_today += tokens;
+        state
+            .agents
+            .entry(agent.to_string())
+            .or_default()
+            .tokens_today += tokens;
+    }
+
+    /// Count a mesh message about to be sent, or refuse it if the hourly
+    /// limit is reached
+    pub fn record_message(&self) -> Result<(), QuotaExceeded> {
+        let now = Utc::now();
+        let mut state = self.state();
+        state.prune_messages(now);
+        if let Some(max) = self.config.instance.max_messages_per_hour {
+            let sent = state.messages.len() as u64;
+            if sent >= u64::from(max) {
+                let retry_after = state
+                    .messages
+                    .front()
+                    .map(|oldest| (*oldest + Duration::hours(1) - now).num_seconds().max(0) as u64);
+                return Err(QuotaExceeded {
+                    kind: QuotaKind::MessagesPerHour,
+                    scope: format!("instance {}", self.instance_id),
+                    limit: u64::from(max),
+                    used: sent,
+                    retry_after_secs: retry_after,
+                });
+            }
+        }
+        state.messages.push_back(now);
+        Ok(())
+    }
+
+    /// Instance-wide usage against the instance limits
+    pub fn usage(&self) -> QuotaUsage {
+        let now = Utc::now();
+        let mut state = self.state();
+        state.roll_day(now);
+        state.prune_messages(now);
+        QuotaUsage {
+            running: state.instance.running,
+            tokens_today: state.instance.tokens_today,
+            messages_last_hour: state.messages.len() as u32,
+            limits: self.config.instance.clone(),
+        }
+    }
+
+    fn end_run(&self, agent: &str) {
+        let mut state = self.state();
+        state.instance.running = state.instance.running.saturating_sub(1);
+        if let Some(usage) = state.agents.get_mut(agent) {
+            usage.running = usage.running.saturating_sub(1);
+        }
+    }
+}
+
+/// An agent run admitted by [`QuotaTracker::begin_run`]
+#[derive(Debug)]
+pub struct RunPermit {
+    tracker: Arc<QuotaTracker>,
+    agent: String,
+}
+
+impl RunPermit {
+    /// Count tokens the run used
+    pub fn record_tokens(&self, tokens: u64) {
+        self.tracker.record_tokens(&self.agent, tokens);
+    }
+}
+
+impl Drop for RunPermit {
+    fn drop(&mut self) {
+        self.tracker.end_run(&self.agent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(config: QuotaConfig) -> Arc<QuotaTracker> {
+        Arc::new(QuotaTracker::new("node-1", config))
+    }
+
+    #[test]
+    fn limits_concurrent_runs_and_daily_tokens() {
+        let mut config = QuotaConfig::default();
+        config.instance.max_concurrent_runs = Some(2);
+        config.agents.insert(
+            "coder".into(),
+            QuotaLimits {
+                daily_token_budget: Some(100),
+                ..QuotaLimits::default()
+            },
+        );
+        let quotas = tracker(config);
+
+        let first = quotas.begin_run("coder").unwrap();
+        let _second = quotas.begin_run("writer").unwrap();
+        let err = quotas.begin_run("writer").unwrap_err();
+        assert_eq!(err.kind, QuotaKind::ConcurrentRuns);
+        assert_eq!(err.scope, "instance node-1");
+        assert_eq!((err.used, err.limit), (2, 2));
+
+        first.record_tokens(150);
+        drop(first);
+        assert_eq!(quotas.usage().running, 1);
+        assert_eq!(quotas.usage().tokens_today, 150);
+
+        let err = quotas.begin_run("coder").unwrap_err();
+        assert_eq!(err.kind, QuotaKind::DailyTokens);
+        assert_eq!(err.scope, "agent coder");
+        assert!(err.retry_after_secs.unwrap() <= 24 * 60 * 60);
+        assert!(err
+            .to_string()
+            .contains("daily token budget quota (150/100)"));
+        // Other agents still run
+        assert!(quotas.begin_run("writer").is_ok());
+    }
+
+    #[test]
+    fn limits_messages_per_hour() {
+        let mut config = QuotaConfig::default();
+        config.instance.max_messages_per_hour = Some(2);
+        let quotas = tracker(config);
+
+        quotas.record_message().unwrap();
+        quotas.record_message().unwrap();
+        let err = quotas.record_message().unwrap_err();
+        assert_eq!(err.kind, QuotaKind::MessagesPerHour);
+        assert!(err.retry_after_secs.unwrap() <= 3600);
+        assert_eq!(quotas.usage().messages_last_hour, 2);
+
+        let unlimited = tracker(QuotaConfig::default());
+        for _ in 0..100 {
+            unlimited.record_message().unwrap();
+        }
+        assert!(unlimited.usage().limits.is_unlimited());
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "messages_per_hour");
+        let back: QuotaExceeded = serde_json::from_value(json).unwrap();
+        assert_eq!(back, err);
+    }
+}
@@ -1,5 +1,10 @@
+use crate::agent::model::ProviderMetadata;
+use crate::panel::PanelSpec;
+use crate::spec_vars::{self, SpecVariable};
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -8,6 +13,13 @@ use std::path::{Path, PathBuf};
 pub struct AgentSpec {
     /// Optional friendly name for the spec.
     pub name: Option<String>,
+    /// One-line summary shown by `spec-ai specs list`.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Tools, provider features and context window the spec relies on;
+    /// checked before the spec runs.
+    #[serde(default)]
+    pub requires: SpecRequirements,
     /// Primary objective for the run (required).
     pub goal: String,
     /// Additional background/context for the task.
@@ -21,14 +33,244 @@ pub struct AgentSpec {
     /// Constraints/guardrails the agent should respect.
     #[serde(default)]
     pub constraints: Vec<String>,
+    /// Declarative UI panels shown while the spec runs.
+    #[serde(default)]
+    pub panels: Vec<PanelSpec>,
+    /// Language for the answer, overriding the agent profile (`auto`, a
+    /// language code or a language name).
+    #[serde(default)]
+    pub response_language: Option<String>,
+    /// Variables available as `{{name}}` in the spec's text.
+    #[serde(default)]
+    pub variables: BTreeMap<String, SpecVariable>,
+    /// Tool calls the spec asks to be allowed for its run only, beyond
+    /// what policy allows; granted by the operator or a trusted signature.
+    #[serde(default)]
+    pub permissions: Vec<SpecPermission>,
     /// Source path for this spec when loaded from disk.
     #[serde(skip)]
     source: Option<PathBuf>,
 }
 
+/// `[requires]` table of a spec: what the agent running it must provide.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SpecRequirements {
+    /// Tool names that must be registered and allowed for the agent.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Provider features the spec relies on: `function_calling`, `vision`
+    /// or `streaming`.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Smallest context window, in tokens, the spec can run in.
+    #[serde(default)]
+    pub min_context_window: Option<u32>,
+}
+
+impl SpecRequirements {
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty() && self.features.is_empty() && self.min_context_window.is_none()
+    }
+
+    /// Check the requirements against the agent that would run the spec.
+    /// `has_tool` reports whether a tool is available to the agent, and
+    /// `context_window` is the agent's effective window when known.
+    pub fn check(
+        &self,
+        has_tool: impl Fn(&str) -> bool,
+        provider: &ProviderMetadata,
+        context_window: Option<u32>,
+    ) -> Result<(), UnmetRequirements> {
+        let mut unmet = UnmetRequirements::default();
+        for tool in &self.tools {
+            if !has_tool(tool) {
+                unmet.tools.push(tool.clone());
+            }
+        }
+        for feature in &self.features {
+            match provider.supports_feature(feature) {
+                Some(true) => {}
+                Some(false) => unmet.features.push(feature.clone()),
+                None => unmet.unknown_features.push(feature.clone()),
+            }
+        }
+        if let (Some(required), Some(available)) = (self.min_context_window, context_window) {
+            if available < required {
+                unmet.context_window = Some((required, available));
+            }
+        }
+        unmet.provider = provider.name.clone();
+        if unmet.is_met() {
+            Ok(())
+        } else {
+            Err(unmet)
+        }
+    }
+}
+
+/// Requirements of a spec the current agent does not meet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnmetRequirements {
+    /// Provider the features were checked against.
+    pub provider: String,
+    pub tools: Vec<String>,
+    pub features: Vec<String>,
+    /// Feature names no provider reports on.
+    pub unknown_features: Vec<String>,
+    /// Required and available context window.
+    pub context_window: Option<(u32, u32)>,
+}
+
+impl UnmetRequirements {
+    fn is_met(&self) -> bool {
+        self.tools.is_empty()
+            && self.features.is_empty()
+            && self.unknown_features.is_empty()
+            && self.context_window.is_none()
+    }
+}
+
+impl fmt::Display for UnmetRequirements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "spec requirements are not met:")?;
+        if !self.tools.is_empty() {
+            write!(
+                f,
+                "\n  - tools not available to this agent: {}",
+                self.tools.join(", ")
+            )?;
+        }
+        if !self.features.is_empty() {
+            write!(
+                f,
+                "\n  - provider '{}' lacks: {}",
+                self.provider,
+                self.features.join(", ")
+            )?;
+        }
+        if !self.unknown_features.is_empty() {
+            write!(
+                f,
+                "\n  - unknown provider features: {} (expected function_calling, vision or streaming)",
+                self.unknown_features.join(", ")
+            )?;
+        }
+        if let Some((required, available)) = self.context_window {
+            write!(
+                f,
+                "\n  - context window of {} tokens is below the required {}",
+                available, required
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnmetRequirements {}
+
+/// `[[permissions]]` entry of a spec: a tool the spec needs allowed while it
+/// runs, optionally only for paths under some directories.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SpecPermission {
+    /// Tool name, e.g. `file_write`.
+    pub tool: String,
+    /// Directories the tool's path arguments must stay under, relative to
+    /// the directory the run starts in; empty allows any path.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Why the spec needs the tool, shown when asking for approval.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl SpecPermission {
+    /// Whether a call of `tool` touching `paths` (resolved against `base`)
+    /// is covered by this permission.
+    pub fn permits(&self, tool: &str, paths: &[PathBuf], base: &Path) -> bool {
+        if self.tool != tool {
+            return false;
+        }
+        if self.paths.is_empty() {
+            return true;
+        }
+        let allowed: Vec<PathBuf> = self
+            .paths
+            .iter()
+            .map(|dir| lexical_absolute(Path::new(dir), base))
+            .collect();
+        // A tool without path arguments cannot be kept inside the paths
+        !paths.is_empty()
+            && paths.iter().all(|path| {
+                let path = lexical_absolute(path, base);
+                allowed.iter().any(|dir| path.starts_with(dir))
+            })
+    }
+}
+
+impl fmt::Display for SpecPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tool)?;
+        if !self.paths.is_empty() {
+            write!(f, " under {}", self.paths.join(", "))?;
+        }
+        if let Some(reason) = self.reason.as_deref().map(str::trim) {
+            if !reason.is_empty() {
+                write!(f, " ({})", reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `path` joined to `base` when relative, with `.` and `..` folded away
+/// without touching the filesystem, so `out/../secret` is not under `out`.
+fn lexical_absolute(path: &Path, base: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
 impl AgentSpec {
-    /// Load a spec from a `.spec` TOML file.
+    /// Load a spec from a `.spec` TOML file, filling its variables from the
+    /// environment, its `.vars.toml` file and their defaults.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file_with_vars(path, &HashMap::new())
+    }
+
+    /// Load a spec, with `overrides` (from `--var`) taking precedence over
+    /// every other source of variable values.
+    pub fn from_file_with_vars(
+        path: impl AsRef<Path>,
+        overrides: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let mut spec = Self::from_file_unresolved(path)?;
+        if !spec.variables.is_empty() {
+            let file = spec_vars::load_vars_file(path)?;
+            let values = spec_vars::resolve(
+                &spec.variables,
+                overrides,
+                |key| std::env::var(key).ok(),
+                file.as_ref(),
+            )
+            .with_context(|| format!("spec '{}'", path.display()))?;
+            spec.apply_variables(&values);
+        }
+        Ok(spec)
+    }
+
+    /// Load a spec leaving its `{{name}}` placeholders as written.
+    pub fn from_file_unresolved(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         if !path.exists() {
             bail!("spec file '{}' was not found", path.display());
@@ -54,6 +296,38 @@ impl AgentSpec {
         Ok(spec)
     }
 
+    /// Substitute variable values into the spec's text.
+    pub fn apply_variables(&mut self, values: &BTreeMap<String, String>) {
+        for text in self.text_fields_mut() {
+            *text = spec_vars::render(text, values);
+        }
+    }
+
+    /// Fields that may contain `{{name}}` placeholders
+    fn text_fields(&self) -> impl Iterator<Item = &String> {
+        self.name
+            .iter()
+            .chain(self.description.iter())
+            .chain(std::iter::once(&self.goal))
+            .chain(self.context.iter())
+            .chain(self.tasks.iter())
+            .chain(self.deliverables.iter())
+            .chain(self.constraints.iter())
+            .chain(self.response_language.iter())
+    }
+
+    fn text_fields_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.name
+            .iter_mut()
+            .chain(self.description.iter_mut())
+            .chain(std::iter::once(&mut self.goal))
+            .chain(self.context.iter_mut())
+            .chain(self.tasks.iter_mut())
+            .chain(self.deliverables.iter_mut())
+            .chain(self.constraints.iter_mut())
+            .chain(self.response_language.iter_mut())
+    }
+
     /// Convert the structured spec into a model prompt.
     pub fn to_prompt(&self) -> String {
         let mut sections = Vec::new();
@@ -124,6 +398,23 @@ impl AgentSpec {
         self.goal.trim()
     }
 
+    /// The spec's own prose (goal, context and tasks), used to detect the
+    /// language it was written in.
+    pub fn language_sample(&self) -> String {
+        let mut sample = vec![self.goal.as_str()];
+        sample.extend(self.context.as_deref());
+        sample.extend(self.tasks.iter().map(String::as_str));
+        sample.join("\n")
+    }
+
+    /// Trimmed description, if the spec has one.
+    pub fn description(&self) -> Option<&str> {
+        self.description
+            .as_deref()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+    }
+
     /// Source path if loaded from disk.
     pub fn source_path(&self) -> Option<&Path> {
         self.source.as_deref()
@@ -217,6 +508,29 @@ impl AgentSpec {
             bail!("spec must include at least one task or deliverable");
         }
 
+        for permission in &self.permissions {
+            if permission.tool.trim().is_empty() {
+                bail!("spec permissions must name a tool");
+            }
+        }
+
+        for panel in &self.panels {
+            panel
+                .validate()
+                .with_context(|| format!("invalid panel '{}'", panel.id))?;
+        }
+
+        for text in self.text_fields() {
+            for name in spec_vars::placeholders(text) {
+                if !self.variables.contains_key(&name) {
+                    bail!(
+                        "spec uses `{{{{{}}}}}` but does not declare it under [variables]",
+                        name
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -251,6 +565,8 @@ deliverables = [
 
         let spec = AgentSpec::from_str(contents).expect("spec should parse");
         assert_eq!(spec.display_name(), "Docs refresh");
+        assert_eq!(spec.description(), None);
+        assert!(spec.requires.is_empty());
         assert!(spec.preview().contains("Goal: Update README"));
 
         let prompt = spec.to_prompt();
@@ -259,6 +575,153 @@ deliverables = [
         assert!(prompt.contains("Deliverables"));
     }
 
+    #[test]
+    fn parses_declared_panels() {
+        let contents = r#"
+goal = "Roll out the release"
+tasks = ["Build", "Deploy"]
+
+[[panels]]
+id = "rollout"
+title = "Rollout"
+kind = "progress"
+steps = [{ label = "build" }, { label = "deploy" }]
+
+[[panels]]
+id = "targets"
+kind = "table"
+columns = ["host", "status"]
+rows = [["web-1", "pending"]]
+        "#;
+        let spec = AgentSpec::from_str(contents).expect("spec should parse");
+        assert_eq!(spec.panels.len(), 2);
+        assert_eq!(spec.panels[0].display_title(), "Rollout");
+
+        let invalid = contents.replace(r#"rows = [["web-1", "pending"]]"#, r#"rows = [["web-1"]]"#);
+        assert!(AgentSpec::from_str(&invalid).is_err());
+    }
+
+    #[test]
+    fn parses_description_and_requirements() {
+        let contents = r#"
+name = "PR review"
+description = "Review the open pull request"
+goal = "Review the PR"
+tasks = ["Read the diff"]
+
+[requires]
+tools = ["git", "file_read"]
+features = ["function_calling"]
+min_context_window = 32000
+        "#;
+        let spec = AgentSpec::from_str(contents).expect("spec should parse");
+        assert_eq!(spec.description(), Some("Review the open pull request"));
+        assert_eq!(spec.requires.tools, vec!["git", "file_read"]);
+        assert_eq!(spec.requires.features, vec!["function_calling"]);
+        assert_eq!(spec.requires.min_context_window, Some(32000));
+    }
+
+    #[test]
+    fn reports_every_unmet_requirement() {
+        let requires = SpecRequirements {
+            tools: vec!["git".into(), "file_read".into()],
+            features: vec!["function_calling".into(), "telepathy".into()],
+            min_context_window: Some(32_000),
+        };
+        let mut provider = ProviderMetadata {
+            name: "Local".into(),
+            supported_models: vec![],
+            supports_streaming: true,
+            supports_function_calling: false,
+            supports_vision: false,
+            supports_json_mode: false,
+            context_window: None,
+        };
+
+        let unmet = requires
+            .check(|tool| tool == "file_read", &provider, Some(8_192))
+            .unwrap_err();
+        assert_eq!(unmet.tools, vec!["git"]);
+        assert_eq!(unmet.features, vec!["function_calling"]);
+        assert_eq!(unmet.unknown_features, vec!["telepathy"]);
+        assert_eq!(unmet.context_window, Some((32_000, 8_192)));
+        let report = unmet.to_string();
+        assert!(report.contains("tools not available to this agent: git"));
+        assert!(report.contains("provider 'Local' lacks: function_calling"));
+
+        provider.supports_function_calling = true;
+        let requires = SpecRequirements {
+            features: vec!["function_calling".into()],
+            ..requires
+        };
+        // An unknown context window is not held against the spec
+        assert!(requires.check(|_| true, &provider, None).is_ok());
+    }
+
+    #[test]
+    fn fills_variables_from_overrides_vars_file_and_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy.spec");
+        fs::write(
+            &path,
+            r#"
+name = "Deploy {{service}}"
+goal = "Deploy {{service}} to {{env}}"
+tasks = ["Roll out {{replicas}} replicas"]
+
+[variables]
+service = { description = "Service to deploy" }
+env = { default = "staging" }
+replicas = { type = "integer", default = 2 }
+"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("deploy.vars.toml"), "replicas = 4\n").unwrap();
+
+        assert!(AgentSpec::from_file(&path).is_err());
+        let overrides = HashMap::from([("service".to_string(), "api".to_string())]);
+        let spec = AgentSpec::from_file_with_vars(&path, &overrides).unwrap();
+        assert_eq!(spec.display_name(), "Deploy api");
+        assert_eq!(spec.goal, "Deploy api to staging");
+        assert_eq!(spec.tasks, vec!["Roll out 4 replicas"]);
+
+        let undeclared = r#"
+goal = "Deploy {{service}}"
+tasks = ["Ship it"]
+        "#;
+        let err = AgentSpec::from_str(undeclared).unwrap_err();
+        assert!(err.to_string().contains("`{{service}}`"));
+    }
+
+    #[test]
+    fn permissions_are_scoped_to_their_paths() {
+        let spec = AgentSpec::from_str(
+            r#"
+goal = "Write the report"
+tasks = ["Write it"]
+
+[[permissions]]
+tool = "file_write"
+paths = ["./out/"]
+reason = "stores the report"
+"#,
+        )
+        .unwrap();
+        let permission = &spec.permissions[0];
+        assert_eq!(
+            permission.to_string(),
+            "file_write under ./out/ (stores the report)"
+        );
+
+        let base = Path::new("/work");
+        let inside = [PathBuf::from("out/report.md")];
+        assert!(permission.permits("file_write", &inside, base));
+        assert!(permission.permits("file_write", &[PathBuf::from("/work/out/a/b.md")], base));
+        assert!(!permission.permits("file_write", &[PathBuf::from("out/../secret")], base));
+        assert!(!permission.permits("file_write", &[PathBuf::from("/etc/passwd")], base));
+        assert!(!permission.permits("bash", &inside, base));
+    }
+
     #[test]
     fn rejects_spec_without_goal() {
         let contents = r#"
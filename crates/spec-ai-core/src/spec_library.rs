@@ -0,0 +1,247 @@
+//! Local spec library
+//!
+//! `spec-ai specs add` copies specs into a library directory
+//! (`~/.spec-ai/specs` unless `[specs] library_dir` says otherwise) under a
+//! short name, so they can be run as `/spec <name>` from the REPL. Each
+//! entry is a `<name>.spec` file; specs added from a remote source keep
+//! their [`SpecProvenance`] next to it as `<name>.provenance.json`.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::config::SpecsConfig;
+use crate::spec::AgentSpec;
+use crate::spec_source::{config_dir, spec_ai_dir, SpecProvenance};
+
+const SPEC_EXTENSION: &str = "spec";
+const PROVENANCE_SUFFIX: &str = ".provenance.json";
+
+/// A spec stored in the library
+#[derive(Debug, Clone)]
+pub struct SpecEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub spec: AgentSpec,
+    /// Where the spec was fetched from, for specs added from a remote source
+    pub provenance: Option<SpecProvenance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecLibrary {
+    root: PathBuf,
+}
+
+impl SpecLibrary {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `~/.spec-ai/specs`
+    pub fn default_root() -> Result<PathBuf> {
+        spec_ai_dir("specs")
+    }
+
+    /// The library at `[specs] library_dir`, or at the default location
+    pub fn from_config(config: &SpecsConfig) -> Result<Self> {
+        let root = match config.library_dir.as_deref() {
+            Some(dir) => config_dir(dir)?,
+            None => Self::default_root()?,
+        };
+        Ok(Self::new(root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Library name for a spec file: its file stem
+    pub fn name_for(path: &Path) -> Option<String> {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string)
+            .filter(|name| validate_name(name).is_ok())
+    }
+
+    fn spec_path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", name, SPEC_EXTENSION))
+    }
+
+    fn provenance_path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}{}", name, PROVENANCE_SUFFIX))
+    }
+
+    /// Names of all specs in the library, sorted
+    pub fn names(&self) -> Result<Vec<String>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        let dir = std::fs::read_dir(&self.root)
+            .with_context(|| format!("reading spec library {}", self.root.display()))?;
+        for entry in dir {
+            let path = entry?.path();
+            let is_spec = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(SPEC_EXTENSION));
+            if is_spec && path.is_file() {
+                names.extend(Self::name_for(&path));
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Library names starting with `prefix`, for completing `/spec <prefix>`
+    pub fn complete(&self, prefix: &str) -> Vec<String> {
+        self.names()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect()
+    }
+
+    /// Path of the spec called `name`, if the library has one
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        validate_name(name).ok()?;
+        let path = self.spec_path(name);
+        path.is_file().then_some(path)
+    }
+
+    /// Load the spec called `name` with its metadata
+    pub fn get(&self, name: &str) -> Result<SpecEntry> {
+        validate_name(name)?;
+        let path = self.spec_path(name);
+        if !path.is_file() {
+            bail!("no spec named '{}' in {}", name, self.root.display());
+        }
+        let spec = AgentSpec::from_file_unresolved(&path)?;
+        let record = self.provenance_path(name);
+        let provenance = if record.exists() {
+            let bytes = std::fs::read(&record).context("reading spec provenance")?;
+            Some(serde_json::from_slice(&bytes).context("parsing spec provenance")?)
+        } else {
+            None
+        };
+        Ok(SpecEntry {
+            name: name.to_string(),
+            path,
+            spec,
+            provenance,
+        })
+    }
+
+    /// Add `contents` as `name`. The spec must parse; an existing entry is
+    /// only replaced when `force` is set.
+    pub fn add(
+        &self,
+        name: &str,
+        contents: &str,
+        provenance: Option<&SpecProvenance>,
+        force: bool,
+    ) -> Result<SpecEntry> {
+        validate_name(name)?;
+        AgentSpec::from_str(contents).with_context(|| format!("spec '{}' is invalid", name))?;
+        let path = self.spec_path(name);
+        if path.exists() && !force {
+            bail!(
+                "a spec named '{}' already exists; use --force to replace it",
+                name
+            );
+        }
+        std::fs::create_dir_all(&self.root)
+            .with_context(|| format!("creating spec library {}", self.root.display()))?;
+        std::fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+        let record = self.provenance_path(name);
+        match provenance {
+            Some(provenance) => std::fs::write(&record, serde_json::to_vec_pretty(provenance)?)
+                .context("writing spec provenance")?,
+            None if record.exists() => std::fs::remove_file(&record)?,
+            None => {}
+        }
+        self.get(name)
+    }
+
+    /// Remove the spec called `name`, returning whether it existed
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        validate_name(name)?;
+        let path = self.spec_path(name);
+        if !path.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+        let record = self.provenance_path(name);
+        if record.exists() {
+            std::fs::remove_file(&record)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Names become file names, so they are limited to a safe character set
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "invalid spec name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REVIEW: &str = r#"
+name = "PR review"
+description = "Review the open pull request"
+goal = "Review the PR"
+tasks = ["Read the diff"]
+
+[requires]
+tools = ["git"]
+"#;
+
+    #[test]
+    fn adds_lists_completes_and_removes_specs() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = SpecLibrary::new(dir.path().join("specs"));
+        assert!(library.names().unwrap().is_empty());
+
+        let entry = library.add("review", REVIEW, None, false).unwrap();
+        assert_eq!(
+            entry.spec.description(),
+            Some("Review the open pull request")
+        );
+        assert_eq!(entry.spec.requires.tools, vec!["git"]);
+        library
+            .add(
+                "release-notes",
+                &REVIEW.replace("PR review", "Notes"),
+                None,
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(library.names().unwrap(), vec!["release-notes", "review"]);
+        assert_eq!(library.complete("rev"), vec!["review"]);
+        assert_eq!(library.complete("re").len(), 2);
+        assert!(library.resolve("review").unwrap().ends_with("review.spec"));
+        assert!(library.resolve("missing").is_none());
+
+        assert!(library.add("review", REVIEW, None, false).is_err());
+        assert!(library.add("review", REVIEW, None, true).is_ok());
+        assert!(library.add("../escape", REVIEW, None, false).is_err());
+        assert!(library.add("broken", "goal = \"\"", None, false).is_err());
+
+        assert!(library.remove("review").unwrap());
+        assert!(!library.remove("review").unwrap());
+        assert_eq!(library.names().unwrap(), vec!["release-notes"]);
+    }
+}
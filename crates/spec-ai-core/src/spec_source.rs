@@ -0,0 +1,517 @@
+//! Remote spec sources
+//!
+//! `spec-ai run` accepts `github:org/repo/path/to.spec` (optionally
+//! `github:org/repo@ref/path/to.spec`) and `https://` URLs besides local
+//! paths. Fetched specs are checked against an optional checksum and, when
+//! `[specs] trusted_keys` is set, an Ed25519 signature published next to the
+//! spec as `<url>.sig`. Verified specs are cached under
+//! `~/.spec-ai/specs-cache` together with a [`SpecProvenance`] record that
+//! the run header shows. Downloading is left to the caller; this module only
+//! resolves, verifies and caches bytes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::SpecsConfig;
+
+/// Name of the provenance record stored next to a cached spec
+const PROVENANCE_FILE: &str = "provenance.json";
+
+/// Where a spec given on the command line comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecSource {
+    Local(PathBuf),
+    Remote(RemoteSpec),
+}
+
+/// A spec fetched over HTTPS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSpec {
+    /// The source as the user wrote it, without any checksum fragment
+    pub reference: String,
+    /// HTTPS URL the spec is downloaded from
+    pub url: String,
+    /// Checksum given as a `#sha256=<hex>` or `#blake3=<hex>` fragment
+    pub checksum: Option<Checksum>,
+}
+
+impl SpecSource {
+    /// Classify a command-line argument; anything that is not a `github:` or
+    /// URL source is a local path
+    pub fn parse(arg: &str) -> Result<Self> {
+        if let Some(rest) = arg.strip_prefix("github:") {
+            let (rest, checksum) = split_checksum(rest)?;
+            let url = github_raw_url(rest)?;
+            return Ok(Self::Remote(RemoteSpec {
+                reference: format!("github:{}", rest),
+                url,
+                checksum,
+            }));
+        }
+        if arg.starts_with("https://") {
+            let (url, checksum) = split_checksum(arg)?;
+            return Ok(Self::Remote(RemoteSpec {
+                reference: url.to_string(),
+                url: url.to_string(),
+                checksum,
+            }));
+        }
+        if arg.starts_with("http://") {
+            bail!("refusing to fetch spec over plain HTTP: {}", arg);
+        }
+        Ok(Self::Local(PathBuf::from(arg)))
+    }
+}
+
+/// `org/repo[@ref]/path/to.spec` as a raw.githubusercontent.com URL
+fn github_raw_url(rest: &str) -> Result<String> {
+    let mut parts = rest.splitn(3, '/');
+    let (Some(org), Some(repo), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("expected github:org/repo/path/to.spec, got github:{}", rest);
+    };
+    let (repo, git_ref) = repo.split_once('@').unwrap_or((repo, "HEAD"));
+    if org.is_empty() || repo.is_empty() || git_ref.is_empty() || path.is_empty() {
+        bail!("expected github:org/repo/path/to.spec, got github:{}", rest);
+    }
+    Ok(format!(
+        "https://raw.githubusercontent.com/{}/{}/{}/{}",
+        org, repo, git_ref, path
+    ))
+}
+
+fn split_checksum(source: &str) -> Result<(&str, Option<Checksum>)> {
+    match source.split_once('#') {
+        Some((base, fragment)) => {
+            let checksum = fragment.replacen('=', ":", 1).parse()?;
+            Ok((base, Some(checksum)))
+        }
+        None => Ok((source, None)),
+    }
+}
+
+/// Hash algorithms a spec checksum can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Hex digest of `bytes`
+    pub fn digest(&self, bytes: &[u8]) -> String {
+        match self {
+            ChecksumAlgorithm::Sha256 => {
+                to_hex(ring::digest::digest(&ring::digest::SHA256, bytes).as_ref())
+            }
+            ChecksumAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        }
+    }
+}
+
+/// Expected digest of a spec, written `sha256:<hex>` or `blake3:<hex>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub hex: String,
+}
+
+impl std::str::FromStr for Checksum {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (algorithm, hex) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow!("checksum must look like sha256:<hex>, got '{}'", value))?;
+        let algorithm = match algorithm.to_ascii_lowercase().as_str() {
+            "sha256" => ChecksumAlgorithm::Sha256,
+            "blake3" => ChecksumAlgorithm::Blake3,
+            other => bail!("unsupported checksum algorithm '{}'", other),
+        };
+        let hex = hex.trim().to_ascii_lowercase();
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("{} checksum must be 64 hex digits", algorithm.as_str());
+        }
+        Ok(Self { algorithm, hex })
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.as_str(), self.hex)
+    }
+}
+
+impl Checksum {
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        self.algorithm.digest(bytes) == self.hex
+    }
+
+    pub fn verify(&self, bytes: &[u8]) -> Result<()> {
+        let actual = self.algorithm.digest(bytes);
+        if actual != self.hex {
+            bail!(
+                "checksum mismatch: expected {}, got {}:{}",
+                self,
+                self.algorithm.as_str(),
+                actual
+            );
+        }
+        Ok(())
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check a base64 Ed25519 `signature` of `bytes` against base64 public
+/// keys, returning the key that verified it
+pub fn verify_signature(bytes: &[u8], signature: &str, trusted_keys: &[String]) -> Result<String> {
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let signature = engine
+        .decode(signature.trim())
+        .context("spec signature is not valid base64")?;
+    for key in trusted_keys {
+        let Ok(public_key) = engine.decode(key.trim()) else {
+            tracing::warn!(
+                "Ignoring trusted spec key that is not valid base64: {}",
+                key
+            );
+            continue;
+        };
+        if UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(bytes, &signature)
+            .is_ok()
+        {
+            return Ok(key.trim().to_string());
+        }
+    }
+    bail!("spec signature does not match any trusted key")
+}
+
+/// Trusted key that signed the local spec at `path`, with the signature
+/// read from `<path>.sig` next to it
+pub fn local_signer(path: &Path, trusted_keys: &[String]) -> Option<String> {
+    if trusted_keys.is_empty() {
+        return None;
+    }
+    let mut sig_path = path.as_os_str().to_owned();
+    sig_path.push(".sig");
+    let signature = std::fs::read_to_string(PathBuf::from(sig_path)).ok()?;
+    let bytes = std::fs::read(path).ok()?;
+    match verify_signature(&bytes, &signature, trusted_keys) {
+        Ok(key) => Some(key),
+        Err(err) => {
+            tracing::warn!("Ignoring signature of {}: {:#}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Where a fetched spec came from and how it was verified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecProvenance {
+    /// The source as given on the command line
+    pub source: String,
+    pub url: String,
+    /// SHA-256 of the spec contents
+    pub sha256: String,
+    /// Checksum the user required, if any
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Trusted key whose signature verified, if signatures are required
+    #[serde(default)]
+    pub signed_by: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl SpecProvenance {
+    /// One line for the run header
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!(
+            "sha256 {}",
+            &self.sha256[..12.min(self.sha256.len())]
+        )];
+        if self.checksum.is_some() {
+            parts.push("checksum verified".to_string());
+        }
+        if let Some(key) = &self.signed_by {
+            parts.push(format!("signed by {}", &key[..12.min(key.len())]));
+        }
+        parts.push(format!(
+            "fetched {}",
+            self.fetched_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+        format!("{} ({})", self.source, parts.join(", "))
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    let dirs = directories::BaseDirs::new()
+        .ok_or_else(|| anyhow!("could not determine home directory"))?;
+    Ok(dirs.home_dir().to_path_buf())
+}
+
+/// A configured directory, with a leading `~/` expanded
+pub(crate) fn config_dir(dir: &str) -> Result<PathBuf> {
+    match dir.strip_prefix("~/") {
+        Some(rest) => Ok(home_dir()?.join(rest)),
+        None => Ok(PathBuf::from(dir)),
+    }
+}
+
+/// `~/.spec-ai/<name>`
+pub(crate) fn spec_ai_dir(name: &str) -> Result<PathBuf> {
+    Ok(home_dir()?.join(".spec-ai").join(name))
+}
+
+/// Verified remote specs on disk, one directory per source URL
+#[derive(Debug, Clone)]
+pub struct SpecCache {
+    root: PathBuf,
+}
+
+impl SpecCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `~/.spec-ai/specs-cache`
+    pub fn default_root() -> Result<PathBuf> {
+        spec_ai_dir("specs-cache")
+    }
+
+    /// The cache at `[specs] cache_dir`, or at the default location
+    pub fn from_config(config: &SpecsConfig) -> Result<Self> {
+        let root = match config.cache_dir.as_deref() {
+            Some(dir) => config_dir(dir)?,
+            None => Self::default_root()?,
+        };
+        Ok(Self::new(root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_dir(&self, url: &str) -> PathBuf {
+        self.root
+            .join(&blake3::hash(url.as_bytes()).to_hex().as_str()[..16])
+    }
+
+    fn spec_file(&self, url: &str) -> PathBuf {
+        let name = url
+            .rsplit('/')
+            .next()
+            .filter(|name| name.ends_with(".spec"))
+            .unwrap_or("remote.spec");
+        self.entry_dir(url).join(name)
+    }
+
+    /// Store verified `bytes` for `spec`, returning the cached file
+    pub fn store(
+        &self,
+        spec: &RemoteSpec,
+        bytes: &[u8],
+        provenance: &SpecProvenance,
+    ) -> Result<PathBuf> {
+        let dir = self.entry_dir(&spec.url);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("creating spec cache directory {}", dir.display()))?;
+        let path = self.spec_file(&spec.url);
+        std::fs::write(&path, bytes)
+            .with_context(|| format!("writing cached spec {}", path.display()))?;
+        std::fs::write(
+            dir.join(PROVENANCE_FILE),
+            serde_json::to_vec_pretty(provenance)?,
+        )
+        .context("writing spec provenance")?;
+        Ok(path)
+    }
+
+    /// The cached copy of `spec` and its provenance, if present
+    pub fn load(&self, spec: &RemoteSpec) -> Result<Option<(PathBuf, SpecProvenance)>> {
+        let path = self.spec_file(&spec.url);
+        let record = self.entry_dir(&spec.url).join(PROVENANCE_FILE);
+        if !path.exists() || !record.exists() {
+            return Ok(None);
+        }
+        let provenance: SpecProvenance =
+            serde_json::from_slice(&std::fs::read(&record).context("reading spec provenance")?)
+                .context("parsing spec provenance")?;
+        Ok(Some((path, provenance)))
+    }
+
+    /// Verify downloaded `bytes` for `spec` and cache them. `signature` is
+    /// the contents of `<url>.sig`; it is required when `trusted_keys` is
+    /// not empty.
+    pub fn admit(
+        &self,
+        spec: &RemoteSpec,
+        bytes: &[u8],
+        signature: Option<&str>,
+        trusted_keys: &[String],
+    ) -> Result<(PathBuf, SpecProvenance)> {
+        if let Some(checksum) = &spec.checksum {
+            checksum
+                .verify(bytes)
+                .with_context(|| format!("verifying {}", spec.reference))?;
+        }
+        let signed_by = if trusted_keys.is_empty() {
+            None
+        } else {
+            let signature = signature.ok_or_else(|| {
+                anyhow!(
+                    "{} is not signed ({}.sig not found) and [specs] trusted_keys requires a signature",
+                    spec.reference,
+                    spec.url
+                )
+            })?;
+            Some(
+                verify_signature(bytes, signature, trusted_keys)
+                    .with_context(|| format!("verifying {}", spec.reference))?,
+            )
+        };
+        std::str::from_utf8(bytes)
+            .with_context(|| format!("{} is not UTF-8 text", spec.reference))?;
+
+        let provenance = SpecProvenance {
+            source: spec.reference.clone(),
+            url: spec.url.clone(),
+            sha256: ChecksumAlgorithm::Sha256.digest(bytes),
+            checksum: spec.checksum.as_ref().map(ToString::to_string),
+            signed_by,
+            fetched_at: Utc::now(),
+        };
+        let path = self.store(spec, bytes, &provenance)?;
+        Ok((path, provenance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_github_and_url_sources() {
+        let SpecSource::Remote(spec) =
+            SpecSource::parse("github:acme/specs/review/pr.spec").unwrap()
+        else {
+            panic!("expected a remote source");
+        };
+        assert_eq!(
+            spec.url,
+            "https://raw.githubusercontent.com/acme/specs/HEAD/review/pr.spec"
+        );
+        assert_eq!(spec.checksum, None);
+
+        let SpecSource::Remote(spec) = SpecSource::parse(&format!(
+            "github:acme/specs@v2/pr.spec#sha256={}",
+            "a".repeat(64)
+        ))
+        .unwrap() else {
+            panic!("expected a remote source");
+        };
+        assert_eq!(spec.reference, "github:acme/specs@v2/pr.spec");
+        assert!(spec.url.contains("/acme/specs/v2/pr.spec"));
+        assert_eq!(spec.checksum.unwrap().algorithm, ChecksumAlgorithm::Sha256);
+
+        assert!(matches!(
+            SpecSource::parse("https://example.com/a.spec").unwrap(),
+            SpecSource::Remote(_)
+        ));
+        assert_eq!(
+            SpecSource::parse("spec/smoke.spec").unwrap(),
+            SpecSource::Local(PathBuf::from("spec/smoke.spec"))
+        );
+        assert!(SpecSource::parse("http://example.com/a.spec").is_err());
+        assert!(SpecSource::parse("github:acme/specs").is_err());
+        assert!("md5:abc".parse::<Checksum>().is_err());
+    }
+
+    #[test]
+    fn verifies_checksums_and_signatures_before_caching() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let bytes = b"goal = \"Review the PR\"\n";
+        let sha = ChecksumAlgorithm::Sha256.digest(bytes);
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SpecCache::new(dir.path());
+        let mut spec =
+            match SpecSource::parse(&format!("https://example.com/review.spec#sha256={}", sha))
+                .unwrap()
+            {
+                SpecSource::Remote(spec) => spec,
+                SpecSource::Local(_) => unreachable!(),
+            };
+
+        let (path, provenance) = cache.admit(&spec, bytes, None, &[]).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        assert!(path.ends_with("review.spec"));
+        assert_eq!(provenance.sha256, sha);
+        assert_eq!(cache.load(&spec).unwrap().unwrap().1, provenance);
+        assert!(provenance.summary().contains("checksum verified"));
+
+        assert!(cache.admit(&spec, b"tampered", None, &[]).is_err());
+
+        // Signatures are required once keys are trusted
+        let engine = base64::engine::general_purpose::STANDARD;
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key = engine.encode(pair.public_key().as_ref());
+        let signature = engine.encode(pair.sign(bytes).as_ref());
+        spec.checksum = None;
+        assert!(cache.admit(&spec, bytes, None, &[key.clone()]).is_err());
+        let (_, provenance) = cache
+            .admit(&spec, bytes, Some(&signature), &[key.clone()])
+            .unwrap();
+        assert_eq!(provenance.signed_by.as_deref(), Some(key.as_str()));
+        let other = engine.encode([7u8; 32]);
+        assert!(cache
+            .admit(&spec, bytes, Some(&signature), &[other])
+            .is_err());
+    }
+
+    #[test]
+    fn local_specs_are_signed_by_a_sig_file() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key = engine.encode(pair.public_key().as_ref());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.spec");
+        let bytes = b"goal = \"Write the report\"\n";
+        std::fs::write(&path, bytes).unwrap();
+        assert_eq!(local_signer(&path, &[key.clone()]), None);
+
+        std::fs::write(
+            dir.path().join("report.spec.sig"),
+            engine.encode(pair.sign(bytes).as_ref()),
+        )
+        .unwrap();
+        assert_eq!(local_signer(&path, &[key.clone()]), Some(key.clone()));
+        assert_eq!(local_signer(&path, &[]), None);
+
+        std::fs::write(&path, b"goal = \"Delete everything\"\n").unwrap();
+        assert_eq!(local_signer(&path, &[key]), None);
+    }
+}
@@ -0,0 +1,392 @@
+//! Coverage-guided spec suggestions
+//!
+//! `spec-ai suggest-specs` looks for parts of a bootstrapped repository that
+//! nothing exercises: components without test files, linked test nodes or a
+//! spec that mentions them, weighted by how much they changed recently. For
+//! each gap the model drafts a `.spec` file, written to `specs/suggested/`
+//! for someone to review before it is run or moved into the library.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::persistence::Persistence;
+use crate::spec::AgentSpec;
+use crate::types::NodeType;
+
+/// Where drafts are written, relative to the repository root
+pub const DEFAULT_SUGGESTION_DIR: &str = "specs/suggested";
+/// Window for "recently changed" files
+const RECENT_DAYS: u32 = 30;
+/// Component kinds that hold no code to exercise
+const NON_CODE_KINDS: &[&str] = &[
+    "tests",
+    "docs",
+    "documentation",
+    "spec",
+    "specifications",
+    "examples",
+    "data",
+    "config",
+    "configuration",
+];
+
+/// A component nothing exercises
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageGap {
+    pub name: String,
+    /// Path relative to the repository root
+    pub path: String,
+    pub has_tests: bool,
+    /// Files under the component changed in the last [`RECENT_DAYS`] days
+    pub recent_files: Vec<String>,
+    pub sample_files: Vec<String>,
+}
+
+impl CoverageGap {
+    /// Why the component was picked, for display and for the prompt
+    pub fn reasons(&self) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if !self.has_tests {
+            reasons.push("no tests".to_string());
+        }
+        reasons.push("no spec mentions it".to_string());
+        if !self.recent_files.is_empty() {
+            reasons.push(format!(
+                "{} file(s) changed in the last {} days",
+                self.recent_files.len(),
+                RECENT_DAYS
+            ));
+        }
+        reasons
+    }
+}
+
+/// Components of the session's bootstrap graph that have no tests and no
+/// spec, or changed recently without a spec, most changed first
+pub fn find_coverage_gaps(
+    persistence: &Persistence,
+    session_id: &str,
+    repo_root: &Path,
+) -> Result<Vec<CoverageGap>> {
+    let nodes = persistence.list_graph_nodes(session_id, None, None)?;
+    let test_nodes: HashSet<i64> = nodes
+        .iter()
+        .filter(|node| node.label.contains("Test") || node.label.contains("Spec"))
+        .map(|node| node.id)
+        .collect();
+    let spec_texts = spec_texts(repo_root);
+    let recent = recently_changed_files(repo_root);
+
+    // Several plugins describe the same directory; merge them by path
+    let mut gaps: BTreeMap<String, CoverageGap> = BTreeMap::new();
+    let mut linked_tests: HashSet<String> = HashSet::new();
+    for node in nodes.iter().filter(|node| node.label == "Component") {
+        let props = &node.properties;
+        let Some(path) = props.get("path").and_then(|p| p.as_str()) else {
+            continue;
+        };
+        let path = path.trim_matches('/').to_string();
+        let kind = props
+            .get("component_type")
+            .or_else(|| props.get("type"))
+            .and_then(|kind| kind.as_str())
+            .unwrap_or("");
+        if path.is_empty() || Path::new(&path).is_absolute() || NON_CODE_KINDS.contains(&kind) {
+            continue;
+        }
+        let test_files = props
+            .pointer("/stats/test_files")
+            .and_then(|count| count.as_u64())
+            .unwrap_or(0);
+        let edges = persistence
+            .list_graph_edges(session_id, Some(node.id), None)?
+            .into_iter()
+            .chain(persistence.list_graph_edges(session_id, None, Some(node.id))?);
+        let linked = edges.into_iter().any(|edge| {
+            test_nodes.contains(&edge.source_id) || test_nodes.contains(&edge.target_id)
+        });
+        if linked {
+            linked_tests.insert(path.clone());
+        }
+        let samples: Vec<String> = props
+            .pointer("/stats/samples")
+            .and_then(|samples| samples.as_array())
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter_map(|s| s.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let gap = gaps.entry(path.clone()).or_insert_with(|| CoverageGap {
+            name: props
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or(path.as_str())
+                .to_string(),
+            path: path.clone(),
+            has_tests: false,
+            recent_files: recent
+                .iter()
+                .filter(|file| file.starts_with(&format!("{}/", path)))
+                .cloned()
+                .collect(),
+            sample_files: Vec::new(),
+        });
+        gap.has_tests |= test_files > 0;
+        if gap.sample_files.is_empty() {
+            gap.sample_files = samples;
+        }
+    }
+
+    let mut gaps: Vec<CoverageGap> = gaps
+        .into_values()
+        .map(|mut gap| {
+            gap.has_tests |= linked_tests.contains(&gap.path);
+            gap
+        })
+        .filter(|gap| !spec_texts.iter().any(|text| text.contains(&gap.path)))
+        .filter(|gap| !gap.has_tests || !gap.recent_files.is_empty())
+        .collect();
+    gaps.sort_by(|a, b| {
+        b.recent_files
+            .len()
+            .cmp(&a.recent_files.len())
+            .then(a.has_tests.cmp(&b.has_tests))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(gaps)
+}
+
+/// Contents of the repository's existing spec files
+fn spec_texts(repo_root: &Path) -> Vec<String> {
+    WalkDir::new(repo_root)
+        .max_depth(4)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(name.starts_with('.') && entry.depth() > 0)
+                && name != "target"
+                && name != "node_modules"
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.path().extension().is_some_and(|ext| ext == "spec")
+                && !entry
+                    .path()
+                    .starts_with(repo_root.join(DEFAULT_SUGGESTION_DIR))
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .collect()
+}
+
+/// Files changed in recent commits, relative to the repository root; empty
+/// outside a git checkout
+fn recently_changed_files(repo_root: &Path) -> Vec<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args([
+            "log",
+            &format!("--since={} days ago", RECENT_DAYS),
+            "--name-only",
+            "--pretty=format:",
+        ])
+        .output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    let mut seen = HashSet::new();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && seen.insert(line.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Prompt asking the model for a spec that exercises `gap`
+pub fn suggestion_prompt(repository: &str, gap: &CoverageGap) -> String {
+    let mut prompt = format!(
+        "You write spec files for spec-ai, an agent that runs them against a repository.\n\
+         Repository: {}\n\
+         Component: {} (path `{}`)\n\
+         Why it needs a spec: {}\n",
+        repository,
+        gap.name,
+        gap.path,
+        gap.reasons().join("; ")
+    );
+    let files: Vec<&String> = gap
+        .recent_files
+        .iter()
+        .chain(&gap.sample_files)
+        .take(10)
+        .collect();
+    if !files.is_empty() {
+        prompt.push_str("Files to focus on:\n");
+        for file in files {
+            prompt.push_str(&format!("- {}\n", file));
+        }
+    }
+    prompt.push_str(
+        "\nWrite a spec that has the agent exercise this component: read the code, \
+         write or run tests for its behaviour and report what is untested or broken.\n\
+         Answer with TOML only, using these keys:\n\
+         name = \"...\"\n\
+         description = \"...\"\n\
+         goal = \"...\"\n\
+         context = \"\"\"...\"\"\"\n\
+         tasks = [\"...\"]\n\
+         deliverables = [\"...\"]\n",
+    );
+    prompt
+}
+
+/// The spec in a model answer, with any Markdown fence removed. Fails when
+/// it is not a valid spec.
+pub fn extract_spec(answer: &str) -> Result<String> {
+    let trimmed = answer.trim();
+    let body = match trimmed.find("```") {
+        Some(start) => {
+            let after = &trimmed[start + 3..];
+            let after = after.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+            after.split("```").next().unwrap_or(after)
+        }
+        None => trimmed,
+    };
+    let body = body.trim();
+    if body.is_empty() {
+        bail!("the model returned no spec");
+    }
+    AgentSpec::from_str(body).context("the model's draft is not a valid spec")?;
+    Ok(format!("{}\n", body))
+}
+
+/// File name for a component's draft: `src/api` -> `src-api.spec`
+pub fn draft_file_name(gap: &CoverageGap) -> String {
+    let slug: String = gap
+        .path
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug.trim_matches('-');
+    format!("{}.spec", if slug.is_empty() { "component" } else { slug })
+}
+
+/// Write a draft into `dir`, refusing to replace an existing one unless
+/// `force` is set
+pub fn write_draft(dir: &Path, gap: &CoverageGap, spec: &str, force: bool) -> Result<PathBuf> {
+    let path = dir.join(draft_file_name(gap));
+    if path.exists() && !force {
+        bail!("{} exists; use --force to replace it", path.display());
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    let header = format!(
+        "# Draft suggested by `spec-ai suggest-specs` for `{}` ({}).\n\
+         # Review it before running it or adding it to the spec library.\n\n",
+        gap.path,
+        gap.reasons().join(", ")
+    );
+    std::fs::write(&path, header + spec).with_context(|| format!("writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Number of `Component` nodes in the session, to tell whether it was
+/// bootstrapped
+pub fn component_count(persistence: &Persistence, session_id: &str) -> Result<usize> {
+    Ok(persistence
+        .list_graph_nodes(session_id, Some(NodeType::Entity), None)?
+        .iter()
+        .filter(|node| node.label == "Component")
+        .count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EdgeType;
+    use serde_json::json;
+
+    #[test]
+    fn finds_components_without_tests_or_specs() {
+        let dir = tempfile::tempdir().unwrap();
+        let persistence = Persistence::in_memory().unwrap();
+        let session = "suggest";
+        let component = |name: &str, test_files: u64| {
+            persistence
+                .insert_graph_node(
+                    session,
+                    NodeType::Entity,
+                    "Component",
+                    &json!({
+                        "name": name,
+                        "path": name,
+                        "component_type": "other",
+                        "stats": {"test_files": test_files, "samples": [format!("{}/mod.rs", name)]},
+                    }),
+                    None,
+                )
+                .unwrap()
+        };
+        component("api", 0);
+        component("core", 3);
+        component("billing", 0);
+        let cli = component("cli", 0);
+        let failure = persistence
+            .insert_graph_node(session, NodeType::Fact, "TestFailure", &json!({}), None)
+            .unwrap();
+        persistence
+            .insert_graph_edge(session, failure, cli, EdgeType::RelatesTo, None, None, 1.0)
+            .unwrap();
+        std::fs::write(
+            dir.path().join("billing.spec"),
+            "goal = \"Check billing\"\ntasks = [\"Read billing/\"]\n",
+        )
+        .unwrap();
+
+        let gaps = find_coverage_gaps(&persistence, session, dir.path()).unwrap();
+        let paths: Vec<&str> = gaps.iter().map(|gap| gap.path.as_str()).collect();
+        assert_eq!(paths, vec!["api"]);
+        assert_eq!(gaps[0].sample_files, vec!["api/mod.rs"]);
+        assert!(suggestion_prompt("demo", &gaps[0]).contains("- api/mod.rs"));
+        assert_eq!(component_count(&persistence, session).unwrap(), 4);
+    }
+
+    #[test]
+    fn extracts_and_writes_drafts() {
+        let answer = "Here you go:\n```toml\nname = \"API\"\ngoal = \"Exercise the API\"\ntasks = [\"Call it\"]\n```\nEnjoy";
+        let spec = extract_spec(answer).unwrap();
+        assert!(spec.starts_with("name = \"API\""));
+        assert!(extract_spec("goal = \"\"").is_err());
+        assert!(extract_spec("  ").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let gap = CoverageGap {
+            name: "api".into(),
+            path: "crates/Api".into(),
+            has_tests: false,
+            recent_files: vec![],
+            sample_files: vec![],
+        };
+        let path = write_draft(dir.path(), &gap, &spec, false).unwrap();
+        assert!(path.ends_with("crates-api.spec"));
+        assert!(AgentSpec::from_file(&path).is_ok());
+        assert!(write_draft(dir.path(), &gap, &spec, false).is_err());
+        assert!(write_draft(dir.path(), &gap, &spec, true).is_ok());
+    }
+}
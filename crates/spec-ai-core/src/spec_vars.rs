@@ -0,0 +1,282 @@
+//! Spec variables
+//!
+//! A spec declares variables in a `[variables]` table and uses them as
+//! `{{name}}` in its text, so one spec can drive several environments:
+//!
+//! ```toml
+//! goal = "Deploy {{service}} to {{env}}"
+//! tasks = ["Roll out {{replicas}} replicas"]
+//!
+//! [variables]
+//! service = { description = "Service to deploy" }
+//! env = { default = "staging" }
+//! replicas = { type = "integer", default = 2 }
+//! ```
+//!
+//! Values come from, in order of precedence, `--var key=value`, a
+//! `SPEC_AI_VAR_<KEY>` environment variable, a `<spec>.vars.toml` file next
+//! to the spec, and the declared default. Every value is checked against the
+//! declared type; a variable without a value is an error.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Environment variable prefix for spec variable values
+pub const VAR_ENV_PREFIX: &str = "SPEC_AI_VAR_";
+
+/// Type of a declared variable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl VariableType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Integer => "integer",
+            VariableType::Float => "float",
+            VariableType::Boolean => "boolean",
+        }
+    }
+
+    /// Normalize a textual value, failing when it is not of this type
+    fn check(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            VariableType::String => Some(value.to_string()),
+            VariableType::Integer => value.parse::<i64>().ok().map(|v| v.to_string()),
+            VariableType::Float => value.parse::<f64>().ok().map(|v| v.to_string()),
+            VariableType::Boolean => value.parse::<bool>().ok().map(|v| v.to_string()),
+        }
+    }
+
+    /// Text of a TOML value, failing when it is not of this type
+    fn check_toml(&self, value: &toml::Value) -> Option<String> {
+        match (self, value) {
+            (VariableType::String, toml::Value::String(text)) => Some(text.clone()),
+            (VariableType::Integer, toml::Value::Integer(number)) => Some(number.to_string()),
+            (VariableType::Float, toml::Value::Float(number)) => Some(number.to_string()),
+            (VariableType::Float, toml::Value::Integer(number)) => Some(number.to_string()),
+            (VariableType::Boolean, toml::Value::Boolean(flag)) => Some(flag.to_string()),
+            (VariableType::String, _) => None,
+            (_, toml::Value::String(text)) => self.check(text),
+            _ => None,
+        }
+    }
+}
+
+/// A `[variables]` entry
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SpecVariable {
+    #[serde(default, rename = "type")]
+    pub kind: VariableType,
+    /// Value used when no other source provides one
+    #[serde(default)]
+    pub default: Option<toml::Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Parse a `key=value` command-line argument
+pub fn parse_var_arg(arg: &str) -> Result<(String, String)> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected KEY=VALUE, got '{}'", arg))?;
+    let key = key.trim();
+    if !is_identifier(key) {
+        bail!("invalid variable name '{}'", key);
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// `review.spec` -> `review.vars.toml`
+pub fn vars_file_for(spec_path: &Path) -> PathBuf {
+    let stem = spec_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("spec");
+    spec_path.with_file_name(format!("{}.vars.toml", stem))
+}
+
+/// Values from the `.vars.toml` file next to a spec, if there is one
+pub fn load_vars_file(spec_path: &Path) -> Result<Option<toml::Table>> {
+    let path = vars_file_for(spec_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let table = toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(Some(table))
+}
+
+/// Value of every declared variable, from the highest-precedence source
+/// that has one
+pub fn resolve(
+    declared: &BTreeMap<String, SpecVariable>,
+    overrides: &HashMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+    file: Option<&toml::Table>,
+) -> Result<BTreeMap<String, String>> {
+    let mut values = BTreeMap::new();
+    let mut missing = Vec::new();
+    for (name, variable) in declared {
+        let kind = variable.kind;
+        let invalid = |source: &str| {
+            anyhow!(
+                "variable '{}' from {} is not a valid {}",
+                name,
+                source,
+                kind.as_str()
+            )
+        };
+        let env_key = format!("{}{}", VAR_ENV_PREFIX, name.to_uppercase());
+        let value = if let Some(value) = overrides.get(name) {
+            kind.check(value).ok_or_else(|| invalid("--var"))?
+        } else if let Some(value) = env(&env_key) {
+            kind.check(&value).ok_or_else(|| invalid(&env_key))?
+        } else if let Some(value) = file.and_then(|table| table.get(name)) {
+            kind.check_toml(value)
+                .ok_or_else(|| invalid("the vars file"))?
+        } else if let Some(value) = &variable.default {
+            kind.check_toml(value)
+                .ok_or_else(|| invalid("its default"))?
+        } else {
+            missing.push(name.as_str());
+            continue;
+        };
+        values.insert(name.clone(), value);
+    }
+    if !missing.is_empty() {
+        bail!(
+            "no value for spec variable(s) {}; pass --var {}=<value> or set {}{}",
+            missing.join(", "),
+            missing[0],
+            VAR_ENV_PREFIX,
+            missing[0].to_uppercase()
+        );
+    }
+    Ok(values)
+}
+
+/// Names of the `{{name}}` placeholders in `text`
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for_each_placeholder(text, |name| {
+        names.push(name.to_string());
+        None
+    });
+    names
+}
+
+/// Replace `{{name}}` placeholders with their values, leaving unknown ones
+pub fn render(text: &str, values: &BTreeMap<String, String>) -> String {
+    for_each_placeholder(text, |name| values.get(name).cloned())
+}
+
+/// Walk the placeholders of `text`, substituting whatever `replace` returns
+fn for_each_placeholder(text: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = after[..end].trim();
+        let placeholder = &rest[start..start + 2 + end + 2];
+        match is_identifier(name).then(|| replace(name)).flatten() {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(placeholder),
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared() -> BTreeMap<String, SpecVariable> {
+        toml::from_str(
+            r#"
+service = {}
+env = { default = "staging" }
+replicas = { type = "integer", default = 2 }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_values_by_precedence_and_type() {
+        let overrides = HashMap::from([("service".to_string(), "api".to_string())]);
+        let file: toml::Table = toml::from_str("env = \"prod\"\nreplicas = 5").unwrap();
+        let env = |key: &str| (key == "SPEC_AI_VAR_REPLICAS").then(|| "3".to_string());
+
+        let values = resolve(&declared(), &overrides, env, Some(&file)).unwrap();
+        assert_eq!(values["service"], "api");
+        assert_eq!(values["env"], "prod");
+        assert_eq!(values["replicas"], "3");
+
+        let values = resolve(&declared(), &overrides, |_| None, None).unwrap();
+        assert_eq!(values["env"], "staging");
+        assert_eq!(values["replicas"], "2");
+
+        let err = resolve(&declared(), &HashMap::new(), |_| None, None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no value for spec variable(s) service"));
+        let bad = HashMap::from([
+            ("service".to_string(), "api".to_string()),
+            ("replicas".to_string(), "many".to_string()),
+        ]);
+        let err = resolve(&declared(), &bad, |_| None, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "variable 'replicas' from --var is not a valid integer"
+        );
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let values = BTreeMap::from([
+            ("service".to_string(), "api".to_string()),
+            ("env".to_string(), "prod".to_string()),
+        ]);
+        assert_eq!(
+            render("Deploy {{service}} to {{ env }}", &values),
+            "Deploy api to prod"
+        );
+        assert_eq!(
+            render("{{other}} {{not a var}} {{", &values),
+            "{{other}} {{not a var}} {{"
+        );
+        assert_eq!(
+            placeholders("{{a}} and {{ b }} but not {{c d}}"),
+            vec!["a", "b"]
+        );
+        assert_eq!(parse_var_arg("env=prod=1").unwrap().1, "prod=1");
+        assert!(parse_var_arg("env").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Access control for graph sync
+//!
+//! `[[sync.acl]]` rules decide which mesh peers may pull a graph (read) and
+//! whose changes to it are applied locally (write). A rule matches graphs by
+//! session ID and graph name, and lists peers by instance ID or by
+//! `tag:<capability>`. The first rule matching a graph decides; graphs that
+//! no rule mentions sync with everyone, as they did before ACLs existed.
+
+use thiserror::Error;
+
+use crate::config::{SyncAclRule, SyncConfig};
+
+/// Prefix marking a peer entry as a capability tag
+pub const TAG_PREFIX: &str = "tag:";
+
+/// Direction of a sync operation, seen from this instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAccess {
+    /// A peer pulls our graph
+    Read,
+    /// A peer's changes are applied to our graph
+    Write,
+}
+
+impl SyncAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncAccess::Read => "read",
+            SyncAccess::Write => "write",
+        }
+    }
+}
+
+/// The instance on the other end of a sync
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncPeer {
+    pub instance_id: String,
+    /// Capabilities the peer advertised when it registered
+    pub capabilities: Vec<String>,
+}
+
+impl SyncPeer {
+    pub fn new(instance_id: impl Into<String>, capabilities: Vec<String>) -> Self {
+        Self {
+            instance_id: instance_id.into(),
+            capabilities,
+        }
+    }
+
+    /// Whether an ACL peer entry names this peer
+    fn matches(&self, entry: &str) -> bool {
+        if entry == "*" {
+            return true;
+        }
+        match entry.strip_prefix(TAG_PREFIX) {
+            Some(tag) => self.capabilities.iter().any(|cap| cap == tag),
+            None => !self.instance_id.is_empty() && entry == self.instance_id,
+        }
+    }
+}
+
+/// A sync refused by the ACL
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("peer '{peer}' may not {} graph '{graph}' of session '{session}'", access.as_str())]
+pub struct SyncDenied {
+    pub peer: String,
+    pub access: SyncAccess,
+    pub session: String,
+    pub graph: String,
+}
+
+/// The `[[sync.acl]]` rules of this instance
+#[derive(Debug, Clone, Default)]
+pub struct SyncAcl {
+    rules: Vec<SyncAclRule>,
+}
+
+impl SyncAcl {
+    pub fn new(rules: Vec<SyncAclRule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn from_config(config: &SyncConfig) -> Self {
+        Self::new(config.acl.clone())
+    }
+
+    /// The rule governing a graph, if any
+    pub fn rule_for(&self, session: &str, graph: &str) -> Option<&SyncAclRule> {
+        self.rules.iter().find(|rule| {
+            pattern_matches(&rule.session, session) && pattern_matches(&rule.graph, graph)
+        })
+    }
+
+    pub fn allows(&self, peer: &SyncPeer, access: SyncAccess, session: &str, graph: &str) -> bool {
+        let Some(rule) = self.rule_for(session, graph) else {
+            return true;
+        };
+        let entries = match access {
+            SyncAccess::Read => &rule.read,
+            SyncAccess::Write => &rule.write,
+        };
+        entries.iter().any(|entry| peer.matches(entry))
+    }
+
+    /// `Ok` if `peer` may perform `access` on the graph
+    pub fn check(
+        &self,
+        peer: &SyncPeer,
+        access: SyncAccess,
+        session: &str,
+        graph: &str,
+    ) -> Result<(), SyncDenied> {
+        if self.allows(peer, access, session, graph) {
+            return Ok(());
+        }
+        Err(SyncDenied {
+            peer: if peer.instance_id.is_empty() {
+                "unknown".to_string()
+            } else {
+                peer.instance_id.clone()
+            },
+            access,
+            session: session.to_string(),
+            graph: graph.to_string(),
+        })
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn pattern_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(session: &str, graph: &str, read: &[&str], write: &[&str]) -> SyncAclRule {
+        SyncAclRule {
+            session: session.into(),
+            graph: graph.into(),
+            read: read.iter().map(|s| s.to_string()).collect(),
+            write: write.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn matches_wildcard_patterns() {
+        assert!(pattern_matches("*", "anything"));
+        assert!(pattern_matches("project-*", "project-secret"));
+        assert!(pattern_matches("*-secret", "project-secret"));
+        assert!(pattern_matches("a*c*e", "abcde"));
+        assert!(pattern_matches("default", "default"));
+        assert!(!pattern_matches("default", "default2"));
+        assert!(!pattern_matches("project-*", "other"));
+        assert!(!pattern_matches("a*c*e", "abcd"));
+    }
+
+    #[test]
+    fn first_matching_rule_decides_by_instance_or_tag() {
+        let acl = SyncAcl::new(vec![
+            rule("secret-*", "*", &["laptop", "tag:trusted"], &["laptop"]),
+            rule("*", "public", &["*"], &["*"]),
+        ]);
+        let laptop = SyncPeer::new("laptop", vec![]);
+        let trusted = SyncPeer::new("build-box", vec!["trusted".into()]);
+        let stranger = SyncPeer::new("stranger", vec!["graph".into()]);
+
+        assert!(acl.allows(&laptop, SyncAccess::Read, "secret-1", "default"));
+        assert!(acl.allows(&laptop, SyncAccess::Write, "secret-1", "default"));
+        assert!(acl.allows(&trusted, SyncAccess::Read, "secret-1", "public"));
+        assert!(!acl.allows(&trusted, SyncAccess::Write, "secret-1", "public"));
+        assert!(acl.allows(&stranger, SyncAccess::Write, "notes", "public"));
+
+        let err = acl
+            .check(&stranger, SyncAccess::Read, "secret-1", "default")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "peer 'stranger' may not read graph 'default' of session 'secret-1'"
+        );
+
+        // Graphs no rule mentions are open; a peer that did not identify
+        // itself only passes `*` entries
+        assert!(acl.allows(&stranger, SyncAccess::Read, "notes", "default"));
+        let unknown = SyncPeer::default();
+        assert!(!acl.allows(&unknown, SyncAccess::Write, "secret-1", "default"));
+        assert!(SyncAcl::default().allows(&unknown, SyncAccess::Write, "secret-1", "default"));
+    }
+}
@@ -1,5 +1,6 @@
+use super::acl::{SyncAccess, SyncAcl, SyncPeer};
 use super::protocol::{GraphSyncPayload, SyncType, SyncedEdge, SyncedNode, Tombstone};
-use super::{ConflictResolution, ConflictResolver, VectorClock};
+use super::{crdt, ConflictResolution, ConflictResolver, VectorClock};
 use crate::persistence::{ChangelogEntry, Persistence, SyncedEdgeRecord, SyncedNodeRecord};
 use anyhow::Result;
 
@@ -12,6 +13,10 @@ pub struct SyncEngine {
     persistence: Persistence,
     instance_id: String,
     resolver: ConflictResolver,
+    acl: SyncAcl,
+    /// Instance this engine serves or applies changes from; `None` for local
+    /// operations, which the ACL does not restrict
+    peer: Option<SyncPeer>,
 }
 
 #[derive(Debug, Clone)]
@@ -33,9 +38,32 @@ impl SyncEngine {
             persistence,
             instance_id: instance_id.clone(),
             resolver: ConflictResolver::new(instance_id),
+            acl: SyncAcl::default(),
+            peer: None,
         }
     }
 
+    /// Enforce `acl` on syncs with the peer set by [`Self::for_peer`]
+    pub fn with_acl(mut self, acl: SyncAcl) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// Serve or apply syncs on behalf of `peer`
+    pub fn for_peer(mut self, peer: SyncPeer) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Refuse with a [`super::SyncDenied`] error if the peer may not access
+    /// the graph
+    pub fn authorize(&self, access: SyncAccess, session_id: &str, graph_name: &str) -> Result<()> {
+        if let Some(peer) = &self.peer {
+            self.acl.check(peer, access, session_id, graph_name)?;
+        }
+        Ok(())
+    }
+
     /// Decide whether to use full or incremental sync based on changelog size
     pub async fn decide_sync_strategy(
         &self,
@@ -86,13 +114,22 @@ impl SyncEngine {
 
     /// Perform a full graph sync - send entire graph
     pub async fn sync_full(&self, session_id: &str, graph_name: &str) -> Result<GraphSyncPayload> {
-        // Get all synced nodes and edges
+        self.authorize(SyncAccess::Read, session_id, graph_name)?;
+
+        // Get all synced nodes and edges of the graph
+        let members = self
+            .persistence
+            .graph_node_ids_in_graph(session_id, graph_name)?;
         let nodes = self
             .persistence
-            .graph_list_nodes_with_sync(session_id, true, false)?;
+            .graph_list_nodes_with_sync(session_id, true, false)?
+            .into_iter()
+            .filter(|node| members.contains(&node.id));
         let edges = self
             .persistence
-            .graph_list_edges_with_sync(session_id, true, false)?;
+            .graph_list_edges_with_sync(session_id, true, false)?
+            .into_iter()
+            .filter(|edge| members.contains(&edge.source_id));
 
         // Get our current vector clock
         let vc_str = self
@@ -102,14 +139,8 @@ impl SyncEngine {
         let vector_clock = VectorClock::from_json(&vc_str)?;
 
         // Convert to sync protocol types
-        let synced_nodes: Vec<SyncedNode> = nodes
-            .into_iter()
-            .map(|n| self.node_record_to_synced(n))
-            .collect();
-        let synced_edges: Vec<SyncedEdge> = edges
-            .into_iter()
-            .map(|e| self.edge_record_to_synced(e))
-            .collect();
+        let synced_nodes: Vec<SyncedNode> = nodes.map(|n| self.node_record_to_synced(n)).collect();
+        let synced_edges: Vec<SyncedEdge> = edges.map(|e| self.edge_record_to_synced(e)).collect();
 
         Ok(GraphSyncPayload::response_full(
             session_id.to_string(),
@@ -119,7 +150,8 @@ impl SyncEngine {
             synced_edges,
             Vec::new(), // No tombstones in full sync
             None,
-        ))
+        )
+        .with_source(self.instance_id.clone()))
     }
 
     /// Perform incremental sync - send only changes since their vector clock
@@ -129,6 +161,8 @@ impl SyncEngine {
         graph_name: &str,
         their_vector_clock: &VectorClock,
     ) -> Result<GraphSyncPayload> {
+        self.authorize(SyncAccess::Read, session_id, graph_name)?;
+
         // Get our current vector clock
         let our_vc_str = self
             .persistence
@@ -197,9 +231,12 @@ impl SyncEngine {
             }
         }
 
-        // Fetch full entities for changed nodes/edges
+        // Fetch full entities for changed nodes/edges of the graph
+        let members = self
+            .persistence
+            .graph_node_ids_in_graph(session_id, graph_name)?;
         let mut synced_nodes = Vec::new();
-        for node_id in node_ids {
+        for node_id in node_ids.into_iter().filter(|id| members.contains(id)) {
             if let Some(node) = self.persistence.graph_get_node_with_sync(node_id)? {
                 if node.sync_enabled && !node.is_deleted {
                     synced_nodes.push(self.node_record_to_synced(node));
@@ -210,7 +247,7 @@ impl SyncEngine {
         let mut synced_edges = Vec::new();
         for edge_id in edge_ids {
             if let Some(edge) = self.persistence.graph_get_edge_with_sync(edge_id)? {
-                if edge.sync_enabled && !edge.is_deleted {
+                if edge.sync_enabled && !edge.is_deleted && members.contains(&edge.source_id) {
                     synced_edges.push(self.edge_record_to_synced(edge));
                 }
             }
@@ -224,7 +261,8 @@ impl SyncEngine {
             synced_edges,
             tombstones,
             None,
-        ))
+        )
+        .with_source(self.instance_id.clone()))
     }
 
     /// Apply incoming sync payload to local graph
@@ -233,6 +271,8 @@ impl SyncEngine {
         payload: &GraphSyncPayload,
         graph_name: &str,
     ) -> Result<SyncStats> {
+        self.authorize(SyncAccess::Write, &payload.session_id, graph_name)?;
+
         let mut stats = SyncStats {
             nodes_sent: 0,
             edges_sent: 0,
@@ -254,7 +294,10 @@ impl SyncEngine {
 
         // Apply nodes
         for node in &payload.nodes {
-            match self.apply_synced_node(node, &mut our_vector_clock).await {
+            match self
+                .apply_synced_node(node, graph_name, &mut our_vector_clock)
+                .await
+            {
                 Ok(applied) => {
                     if applied {
                         stats.nodes_applied += 1;
@@ -412,6 +455,7 @@ impl SyncEngine {
     async fn apply_synced_node(
         &self,
         node: &SyncedNode,
+        graph_name: &str,
         our_vector_clock: &mut VectorClock,
     ) -> Result<bool> {
         // Check if node exists locally
@@ -434,13 +478,34 @@ impl SyncEngine {
                     Ok(false)
                 }
                 crate::sync::ClockOrder::Concurrent => {
+                    // Concurrent updates to CRDT properties merge without
+                    // a conflict
+                    if existing_node.label == node.label
+                        && existing_node.node_type == node.node_type.as_str()
+                        && crdt::only_crdt_properties_differ(
+                            &existing_node.properties,
+                            &node.properties,
+                        )
+                    {
+                        let mut merged = node.clone();
+                        crdt::merge_crdt_properties(
+                            &existing_node.properties,
+                            &node.properties,
+                            &mut merged.properties,
+                        );
+                        merged.vector_clock.merge(&existing_vc);
+                        self.update_node_from_synced(&merged)?;
+                        our_vector_clock.merge(incoming_vc);
+                        return Ok(true);
+                    }
+
                     // Conflict - let resolver handle it
                     anyhow::bail!("conflict detected for node {}", node.id);
                 }
             }
         } else {
             // Node doesn't exist, insert it
-            self.insert_node_from_synced(node)?;
+            self.insert_node_from_synced(node, graph_name)?;
             our_vector_clock.merge(&node.vector_clock);
             Ok(true)
         }
@@ -579,10 +644,11 @@ impl SyncEngine {
         Ok(())
     }
 
-    fn insert_node_from_synced(&self, node: &SyncedNode) -> Result<()> {
+    fn insert_node_from_synced(&self, node: &SyncedNode, graph_name: &str) -> Result<()> {
         // Insert the node first
-        let node_id = self.persistence.insert_graph_node(
+        let node_id = self.persistence.insert_graph_node_in_graph(
             &node.session_id,
+            graph_name,
             node.node_type.clone(),
             &node.label,
             &node.properties,
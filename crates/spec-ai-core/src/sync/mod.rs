@@ -1,11 +1,14 @@
+pub mod acl;
 pub mod engine;
 pub mod protocol;
 pub mod resolver;
 
+pub use acl::{SyncAccess, SyncAcl, SyncDenied, SyncPeer};
 pub use engine::{SyncEngine, SyncStats};
 pub use protocol::{
     GraphSyncPayload, SyncAck, SyncConflict, SyncFullRequest, SyncIncrementalRequest, SyncResponse,
     SyncType, SyncedEdge, SyncedNode, Tombstone,
 };
 pub use resolver::{ConflictResolution, ConflictResolver};
+pub use spec_ai_config::sync::crdt::{self, CrdtValue, GCounter, OrSet};
 pub use spec_ai_config::sync::vector_clock::{ClockOrder, VectorClock};
@@ -45,6 +45,10 @@ pub struct GraphSyncPayload {
     pub correlation_id: Option<String>,
     /// For Conflict type: description of the conflict
     pub conflict_info: Option<String>,
+    /// Instance that produced the payload, checked against `[[sync.acl]]`
+    /// when the payload is applied
+    #[serde(default)]
+    pub source_instance: Option<String>,
 }
 
 /// Graph node with sync metadata
@@ -159,6 +163,12 @@ pub struct SyncConflict {
 }
 
 impl GraphSyncPayload {
+    /// Mark the payload as produced by `instance_id`
+    pub fn with_source(mut self, instance_id: impl Into<String>) -> Self {
+        self.source_instance = Some(instance_id.into());
+        self
+    }
+
     /// Create a full sync request
     pub fn request_full(
         session_id: String,
@@ -178,6 +188,7 @@ impl GraphSyncPayload {
             tombstones: Vec::new(),
             correlation_id: Some(uuid::Uuid::new_v4().to_string()),
             conflict_info: None,
+            source_instance: None,
         }
     }
 
@@ -201,6 +212,7 @@ impl GraphSyncPayload {
             tombstones: Vec::new(),
             correlation_id: Some(uuid::Uuid::new_v4().to_string()),
             conflict_info: None,
+            source_instance: None,
         }
     }
 
@@ -224,6 +236,7 @@ impl GraphSyncPayload {
             tombstones,
             correlation_id,
             conflict_info: None,
+            source_instance: None,
         }
     }
 
@@ -247,6 +260,7 @@ impl GraphSyncPayload {
             tombstones,
             correlation_id,
             conflict_info: None,
+            source_instance: None,
         }
     }
 
@@ -274,6 +288,7 @@ impl GraphSyncPayload {
                 "Applied {}/{}/{} (nodes/edges/tombstones), {} conflicts",
                 nodes_applied, edges_applied, tombstones_applied, conflicts_detected
             )),
+            source_instance: None,
         }
     }
 
@@ -300,6 +315,7 @@ impl GraphSyncPayload {
                 "Conflict detected for {} {}: local={}, remote={}",
                 entity_type, entity_id, local_vector_clock, remote_vector_clock
             )),
+            source_instance: None,
         }
     }
 }
@@ -1,5 +1,5 @@
 use super::protocol::{SyncedEdge, SyncedNode};
-use super::{ClockOrder, VectorClock};
+use super::{crdt, ClockOrder, VectorClock};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde_json::{json, Value as JsonValue};
@@ -108,11 +108,18 @@ impl ConflictResolver {
 
                     // Apply type-specific merge strategy
                     let merged_properties = if incoming.node_type == local_node.node_type {
-                        self.apply_type_specific_merge(
+                        let mut merged = self.apply_type_specific_merge(
                             incoming.node_type.as_str(),
                             &local_node.properties,
                             &incoming.properties,
-                        )
+                        );
+                        // CRDT properties merge exactly whatever else changed
+                        crdt::merge_crdt_properties(
+                            &local_node.properties,
+                            &incoming.properties,
+                            &mut merged,
+                        );
+                        merged
                     } else {
                         // Type mismatch - this is a serious conflict
                         warn!(
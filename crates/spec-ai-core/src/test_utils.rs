@@ -1,2 +1,3 @@
-// Re-export from spec-ai-config to maintain backwards compatibility
+// Re-export from spec-ai-config to maintain backwards compatibility; code
+// outside this workspace should use the `spec-ai-testing` crate
 pub use spec_ai_config::test_utils::{create_test_db, env_lock};
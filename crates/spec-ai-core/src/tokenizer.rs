@@ -0,0 +1,159 @@
+//! Token counting
+//!
+//! [`count_tokens`] counts `text` the way `model` would see it. OpenAI-family
+//! models (`gpt-*`, `o1`/`o3`/`o4`, OpenAI embeddings) are counted exactly
+//! with their BPE vocabulary via tiktoken; other providers do not publish a
+//! tokenizer that can run locally, so their counts come from a
+//! characters-per-token ratio measured for that model family. Model names
+//! may carry a provider prefix (`openai/gpt-4o`, `ollama/llama3.2`).
+
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+/// Characters per token assumed when nothing is known about the model
+pub const DEFAULT_CHARS_PER_TOKEN: f32 = 4.0;
+
+/// How tokens are counted for a model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tokenizer {
+    /// `o200k_base` (GPT-4o, GPT-4.1, o-series)
+    O200k,
+    /// `cl100k_base` (GPT-4, GPT-3.5, OpenAI embeddings)
+    Cl100k,
+    /// Estimate from the text length
+    Heuristic { chars_per_token: f32 },
+}
+
+impl Tokenizer {
+    /// Tokenizer for `model`, a model name with an optional `provider/` prefix
+    pub fn for_model(model: &str) -> Self {
+        let model = model.trim().to_lowercase();
+        let name = model.rsplit('/').next().unwrap_or(&model);
+
+        if name.starts_with("gpt-4o")
+            || name.starts_with("gpt-4.1")
+            || name.starts_with("gpt-5")
+            || name.starts_with("chatgpt-4o")
+            || ["o1", "o3", "o4"]
+                .iter()
+                .any(|series| name == *series || name.starts_with(&format!("{}-", series)))
+        {
+            return Tokenizer::O200k;
+        }
+        if name.starts_with("gpt-4")
+            || name.starts_with("gpt-3.5")
+            || name.starts_with("text-embedding-")
+        {
+            return Tokenizer::Cl100k;
+        }
+
+        // Ratios measured on mixed English prose and code
+        let chars_per_token = if name.contains("claude") {
+            3.5
+        } else if [
+            "llama", "mistral", "mixtral", "qwen", "gemma", "phi", "deepseek",
+        ]
+        .iter()
+        .any(|family| name.contains(family))
+        {
+            3.8
+        } else {
+            DEFAULT_CHARS_PER_TOKEN
+        };
+        Tokenizer::Heuristic { chars_per_token }
+    }
+
+    /// Whether counts are exact rather than estimated
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Tokenizer::Heuristic { .. })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tokenizer::O200k => "o200k_base",
+            Tokenizer::Cl100k => "cl100k_base",
+            Tokenizer::Heuristic { .. } => "estimate",
+        }
+    }
+
+    /// Tokens in `text`. Falls back to the default ratio if a BPE vocabulary
+    /// fails to load.
+    pub fn count(&self, text: &str) -> usize {
+        let bpe = match self {
+            Tokenizer::O200k => o200k(),
+            Tokenizer::Cl100k => cl100k(),
+            Tokenizer::Heuristic { chars_per_token } => {
+                return estimate(text, *chars_per_token);
+            }
+        };
+        match bpe {
+            Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+            None => estimate(text, DEFAULT_CHARS_PER_TOKEN),
+        }
+    }
+}
+
+/// Tokens in `text` for `model`
+pub fn count_tokens(text: &str, model: &str) -> usize {
+    Tokenizer::for_model(model).count(text)
+}
+
+/// Estimate from the character count at `chars_per_token`
+pub fn estimate(text: &str, chars_per_token: f32) -> usize {
+    (text.chars().count() as f32 / chars_per_token).ceil() as usize
+}
+
+fn o200k() -> Option<&'static CoreBPE> {
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    BPE.get_or_init(|| load("o200k_base", tiktoken_rs::o200k_base()))
+        .as_ref()
+}
+
+fn cl100k() -> Option<&'static CoreBPE> {
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    BPE.get_or_init(|| load("cl100k_base", tiktoken_rs::cl100k_base()))
+        .as_ref()
+}
+
+fn load(name: &str, bpe: anyhow::Result<CoreBPE>) -> Option<CoreBPE> {
+    bpe.map_err(|err| tracing::warn!("Failed to load {} tokenizer: {}", name, err))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_tokenizer_by_model_family() {
+        assert_eq!(Tokenizer::for_model("gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("openai/o3-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(
+            Tokenizer::for_model("text-embedding-3-small"),
+            Tokenizer::Cl100k
+        );
+        assert_eq!(
+            Tokenizer::for_model("claude-3-5-sonnet-latest"),
+            Tokenizer::Heuristic {
+                chars_per_token: 3.5
+            }
+        );
+        assert_eq!(
+            Tokenizer::for_model("ollama/llama3.2:3b"),
+            Tokenizer::Heuristic {
+                chars_per_token: 3.8
+            }
+        );
+        assert!(!Tokenizer::for_model("mock").is_exact());
+    }
+
+    #[test]
+    fn counts_tokens() {
+        assert_eq!(count_tokens("hello world", "gpt-4o"), 2);
+        assert_eq!(count_tokens("hello world", "gpt-4"), 2);
+        assert_eq!(count_tokens("", "gpt-4o"), 0);
+        // 11 characters at 4 per token
+        assert_eq!(count_tokens("hello world", "unknown-model"), 3);
+    }
+}
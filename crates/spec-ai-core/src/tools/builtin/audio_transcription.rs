@@ -1,3 +1,6 @@
+use crate::agent::audio_file;
+use crate::agent::transcription::{self, TranscriptionProvider};
+use crate::agent::transcription_providers::MockTranscriptionProvider;
 use crate::persistence::Persistence;
 use crate::tools::{Tool, ToolResult};
 use crate::types::MessageRole;
@@ -6,6 +9,7 @@ use async_trait::async_trait;
 use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
@@ -286,11 +290,15 @@ impl Default for TranscriptionConfig {
     }
 }
 
-/// Mock audio transcription tool
+/// Audio transcription tool: mock live scenarios, and recorded files run
+/// through a transcription provider
 pub struct AudioTranscriptionTool {
     scenarios: Vec<MockScenario>,
     active_sessions: Arc<Mutex<Vec<String>>>,
     persistence: Option<Arc<Persistence>>,
+    /// Transcribes `file` arguments
+    provider: Arc<dyn TranscriptionProvider>,
+    provider_config: transcription::TranscriptionConfig,
 }
 
 impl AudioTranscriptionTool {
@@ -305,6 +313,8 @@ impl AudioTranscriptionTool {
             ],
             active_sessions: Arc::new(Mutex::new(Vec::new())),
             persistence: None,
+            provider: Arc::new(MockTranscriptionProvider::new()),
+            provider_config: transcription::TranscriptionConfig::default(),
         }
     }
 
@@ -314,6 +324,17 @@ impl AudioTranscriptionTool {
         tool
     }
 
+    /// Transcribe `file` arguments with `provider` instead of the mock one
+    pub fn with_transcription_provider(
+        mut self,
+        provider: Arc<dyn TranscriptionProvider>,
+        config: transcription::TranscriptionConfig,
+    ) -> Self {
+        self.provider = provider;
+        self.provider_config = config;
+        self
+    }
+
     /// Get a scenario by name
     fn get_scenario(&self, name: &str) -> Option<&MockScenario> {
         self.scenarios.iter().find(|s| s.name == name)
@@ -407,6 +428,61 @@ impl AudioTranscriptionTool {
         }
     }
 
+    /// Transcribe a recorded file and, when `persist` is set, store its
+    /// chunks in the session's transcriptions with the file as their source
+    async fn transcribe_file(
+        &self,
+        path: &Path,
+        session_id: &str,
+        persist: bool,
+    ) -> Result<ToolResult> {
+        let chunks =
+            match audio_file::transcribe_file(self.provider.as_ref(), path, &self.provider_config)
+                .await
+            {
+                Ok(chunks) => chunks,
+                Err(err) => return Ok(ToolResult::failure(format!("{:#}", err))),
+            };
+
+        let source = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let source = source.display().to_string();
+        let mut stored = 0;
+        if persist {
+            if let Some(persistence) = &self.persistence {
+                let chunk_ms = (self.provider_config.chunk_duration_secs * 1000.0) as i64;
+                stored = audio_file::save_chunks(
+                    persistence,
+                    session_id,
+                    &chunks,
+                    Some(&source),
+                    chunk_ms,
+                )?
+                .len();
+            }
+        }
+
+        let segments: Vec<Value> = chunks
+            .iter()
+            .map(|chunk| {
+                json!({
+                    "start_ms": chunk.start_ms,
+                    "end_ms": chunk.end_ms,
+                    "text": chunk.text,
+                })
+            })
+            .collect();
+        let result = json!({
+            "session_id": session_id,
+            "mode": "file",
+            "file": source,
+            "provider": self.provider.metadata().name,
+            "segments": segments,
+            "count": chunks.len(),
+            "stored": stored,
+        });
+        Ok(ToolResult::success(result.to_string()))
+    }
+
     /// Store transcription event in database
     async fn persist_event(&self, session_id: &str, event: &TranscriptionEvent) -> Result<()> {
         if let Some(persistence) = &self.persistence {
@@ -446,7 +522,8 @@ impl Tool for AudioTranscriptionTool {
     fn description(&self) -> &str {
         "Mock audio transcription tool that simulates live audio input and converts it to text. \
          Supports multiple scenarios including conversations, commands, noisy environments, \
-         and multi-speaker sessions."
+         and multi-speaker sessions. Given a `file`, transcribes a recorded wav, mp3 or m4a \
+         file with the configured transcription provider instead."
     }
 
     fn parameters(&self) -> Value {
@@ -483,6 +560,14 @@ impl Tool for AudioTranscriptionTool {
                     "type": "boolean",
                     "description": "Whether to persist transcriptions to database",
                     "default": true
+                },
+                "file": {
+                    "type": "string",
+                    "description": "Path of a recorded wav, mp3 or m4a file to transcribe instead of a mock scenario"
+                },
+                "session_id": {
+                    "type": "string",
+                    "description": "Session whose transcriptions a file's chunks are stored in (default: a new audio_<timestamp> session)"
                 }
             },
             "required": []
@@ -504,6 +589,16 @@ impl Tool for AudioTranscriptionTool {
 
         let persist = args["persist"].as_bool().unwrap_or(true);
 
+        if let Some(file) = args["file"].as_str() {
+            let session_id = args["session_id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("audio_{}", chrono::Utc::now().timestamp_millis()));
+            return self
+                .transcribe_file(Path::new(file), &session_id, persist)
+                .await;
+        }
+
         // Generate session ID
         let session_id = format!("audio_{}", chrono::Utc::now().timestamp_millis());
 
@@ -708,6 +803,39 @@ mod tests {
         assert!(formatted.contains("Door closing"));
     }
 
+    #[tokio::test]
+    async fn test_file_transcription() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memo.wav");
+        let silence = vec![0.0f32; audio_file::SAMPLE_RATE as usize * 3];
+        std::fs::write(&path, audio_file::encode_wav(&silence)).unwrap();
+
+        let tool = AudioTranscriptionTool::new().with_transcription_provider(
+            Arc::new(MockTranscriptionProvider::with_transcriptions(vec![
+                "Dana: the demo moves to Thursday".to_string(),
+            ])),
+            transcription::TranscriptionConfig {
+                chunk_duration_secs: 2.0,
+                ..Default::default()
+            },
+        );
+        let result = tool
+            .execute(json!({ "file": path.to_str().unwrap(), "session_id": "standup" }))
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(output["mode"], "file");
+        assert_eq!(output["session_id"], "standup");
+        assert_eq!(output["count"], 2);
+        assert_eq!(output["segments"][1]["start_ms"], 2000);
+        assert_eq!(output["segments"][1]["end_ms"], 3000);
+
+        let missing = tool.execute(json!({ "file": "memo.ogg" })).await.unwrap();
+        assert!(!missing.success);
+    }
+
     #[tokio::test]
     async fn test_active_session_tracking() {
         let tool = AudioTranscriptionTool::new();
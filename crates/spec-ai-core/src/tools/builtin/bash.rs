@@ -1,12 +1,15 @@
-use crate::tools::{Tool, ToolResult};
+use crate::tools::{channel_stream, Tool, ToolChunk, ToolResult, ToolStream};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio::time;
 use tracing::info;
 
@@ -45,7 +48,7 @@ fn truncate_output(input: &[u8]) -> String {
     }
 }
 
-fn validate_command(command: &str) -> Result<()> {
+pub(crate) fn validate_command(command: &str) -> Result<()> {
     if command.trim().is_empty() {
         return Err(anyhow!("Command cannot be empty"));
     }
@@ -69,7 +72,8 @@ fn validate_command(command: &str) -> Result<()> {
     Ok(())
 }
 
-async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandOutput> {
+/// Check the command and prepare the process that runs it
+fn build_command(args: &BashArgs, shell_path: &Path) -> Result<Command> {
     if !shell_path.exists() {
         return Err(anyhow!(format!(
             "Shell path {} does not exist",
@@ -86,11 +90,6 @@ async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandO
         "Executing bash command"
     );
 
-    let timeout = args
-        .timeout_ms
-        .map(Duration::from_millis)
-        .unwrap_or(DEFAULT_TIMEOUT);
-
     let mut command = Command::new(shell_path);
     command.arg("-c").arg(&args.command);
     command.kill_on_drop(true);
@@ -104,6 +103,18 @@ async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandO
             command.env(key, value);
         }
     }
+    Ok(command)
+}
+
+fn command_timeout(args: &BashArgs) -> Duration {
+    args.timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TIMEOUT)
+}
+
+async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandOutput> {
+    let mut command = build_command(args, shell_path)?;
+    let timeout = command_timeout(args);
 
     let start = Instant::now();
     let output = match time::timeout(timeout, command.output()).await {
@@ -116,10 +127,103 @@ async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandO
         }
     };
 
+    Ok(finish_command(
+        args,
+        &output.stdout,
+        &output.stderr,
+        output.status.code().unwrap_or_default(),
+        start,
+    ))
+}
+
+/// Run the command, sending each line of stdout and stderr on `tx` as it
+/// is written
+async fn stream_bash_command(
+    args: &BashArgs,
+    shell_path: &Path,
+    tx: &mpsc::UnboundedSender<Result<ToolChunk>>,
+) -> Result<CommandOutput> {
+    let mut command = build_command(args, shell_path)?;
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let timeout = command_timeout(args);
+
+    let start = Instant::now();
+    let mut child = command.spawn().context("Failed to execute bash command")?;
+    let mut stdout = BufReader::new(child.stdout.take().context("stdout was not piped")?);
+    let mut stderr = BufReader::new(child.stderr.take().context("stderr was not piped")?);
+    let (mut stdout_all, mut stderr_all) = (Vec::new(), Vec::new());
+
+    let run = async {
+        // Lines are kept across polls, so a line cut short by the other
+        // stream's turn is finished on the next one
+        let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+        let (mut stdout_open, mut stderr_open) = (true, true);
+        while stdout_open || stderr_open {
+            tokio::select! {
+                read = stdout.read_until(b'\n', &mut stdout_line), if stdout_open => {
+                    let read = read.context("Failed to read bash output")?;
+                    stdout_open = forward_line(read, &mut stdout_line, &mut stdout_all, tx);
+                }
+                read = stderr.read_until(b'\n', &mut stderr_line), if stderr_open => {
+                    let read = read.context("Failed to read bash output")?;
+                    stderr_open = forward_line(read, &mut stderr_line, &mut stderr_all, tx);
+                }
+                // The caller gave up on the command (timed out or cancelled);
+                // returning drops the child, which kills it
+                _ = tx.closed() => {
+                    return Err(anyhow!("Command abandoned before it finished"));
+                }
+            }
+        }
+        child.wait().await.context("Failed to execute bash command")
+    };
+    let status = match time::timeout(timeout, run).await {
+        Ok(status) => status?,
+        Err(_) => {
+            return Err(anyhow!(format!(
+                "Command timed out after {} ms",
+                timeout.as_millis()
+            )));
+        }
+    };
+
+    Ok(finish_command(
+        args,
+        &stdout_all,
+        &stderr_all,
+        status.code().unwrap_or_default(),
+        start,
+    ))
+}
+
+/// Send a line the command wrote and keep it with the rest of its output;
+/// `false` once the stream has ended
+fn forward_line(
+    read: usize,
+    line: &mut Vec<u8>,
+    all: &mut Vec<u8>,
+    tx: &mpsc::UnboundedSender<Result<ToolChunk>>,
+) -> bool {
+    if read == 0 {
+        return false;
+    }
+    let _ = tx.send(Ok(ToolChunk::Output(
+        String::from_utf8_lossy(line).into_owned(),
+    )));
+    all.append(line);
+    true
+}
+
+fn finish_command(
+    args: &BashArgs,
+    stdout: &[u8],
+    stderr: &[u8],
+    exit_code: i32,
+    start: Instant,
+) -> CommandOutput {
     let duration = start.elapsed().as_millis();
-    let stdout = truncate_output(&output.stdout);
-    let stderr = truncate_output(&output.stderr);
-    let exit_code = output.status.code().unwrap_or_default();
+    let stdout = truncate_output(stdout);
+    let stderr = truncate_output(stderr);
 
     info!(
         target: "spec_ai::tools::bash",
@@ -129,13 +233,22 @@ async fn run_bash_command(args: &BashArgs, shell_path: &Path) -> Result<CommandO
         "Bash command finished"
     );
 
-    Ok(CommandOutput {
+    CommandOutput {
         command: args.command.clone(),
         stdout,
         stderr,
         exit_code,
         duration_ms: duration,
-    })
+    }
+}
+
+fn command_result(output: &CommandOutput) -> Result<ToolResult> {
+    let json = serde_json::to_string(output).context("Failed to serialize bash output")?;
+    if output.exit_code == 0 {
+        Ok(ToolResult::success(json))
+    } else {
+        Ok(ToolResult::failure(json))
+    }
 }
 
 /// Tool that executes bash commands with safety checks
@@ -198,28 +311,39 @@ impl Tool for BashTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["working_dir"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: BashArgs =
             serde_json::from_value(args).context("Failed to parse bash arguments")?;
         let shell_path = Path::new(&self.shell_path);
 
         let output = run_bash_command(&args, shell_path).await?;
+        command_result(&output)
+    }
 
-        if output.exit_code == 0 {
-            Ok(ToolResult::success(
-                serde_json::to_string(&output).context("Failed to serialize bash output")?,
-            ))
-        } else {
-            Ok(ToolResult::failure(
-                serde_json::to_string(&output).context("Failed to serialize bash output")?,
-            ))
-        }
+    async fn execute_streaming(&self, args: Value) -> Result<ToolStream> {
+        let args: BashArgs =
+            serde_json::from_value(args).context("Failed to parse bash arguments")?;
+        let shell_path = PathBuf::from(&self.shell_path);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let result = stream_bash_command(&args, &shell_path, &tx)
+                .await
+                .and_then(|output| command_result(&output));
+            let _ = tx.send(result.map(ToolChunk::Finished));
+        });
+        Ok(channel_stream(rx))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::collect_tool_stream;
 
     #[tokio::test]
     async fn test_bash_success() {
@@ -239,6 +363,29 @@ mod tests {
         assert!(!result.success);
     }
 
+    #[tokio::test]
+    async fn test_bash_streams_lines_as_they_are_written() {
+        let tool = BashTool::new();
+        let args = serde_json::json!({ "command": "echo one; echo two >&2; exit 3" });
+        let mut seen = Vec::new();
+        let result = collect_tool_stream(tool.execute_streaming(args).await.unwrap(), |text| {
+            seen.push(text.to_string())
+        })
+        .await
+        .unwrap();
+        seen.sort();
+        assert_eq!(seen, vec!["one\n", "two\n"]);
+        assert!(!result.success);
+        let payload: serde_json::Value = serde_json::from_str(&result.error.unwrap()).unwrap();
+        assert_eq!(payload["stdout"], "one\n");
+        assert_eq!(payload["stderr"], "two\n");
+        assert_eq!(payload["exit_code"], 3);
+
+        let args = serde_json::json!({ "command": "sleep 5", "timeout_ms": 1000 });
+        let stream = tool.execute_streaming(args).await.unwrap();
+        assert!(collect_tool_stream(stream, |_| {}).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_bash_timeout() {
         let tool = BashTool::new();
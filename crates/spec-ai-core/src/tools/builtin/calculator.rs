@@ -1,16 +1,26 @@
 use crate::tools::{Tool, ToolResult};
-use anyhow::{Context, Result};
-use async_trait::async_trait;
+use anyhow::Result;
 use serde::Deserialize;
-use serde_json::Value;
+use spec_ai_plugin::{typed_tool, ToolArgs};
 
 /// A calculator tool backed by a small standard math library
 pub struct MathTool;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolArgs)]
 struct MathArgs {
+    /// The operation to perform. Supports arithmetic (add, subtract, multiply, divide, power,
+    /// modulo or +, -, *, /, **, %), common unary functions (sqrt, abs, exp, ln, log10, log2,
+    /// sin, cos, tan, asin, acos, atan, sinh, cosh, tanh), and simple binary functions (min,
+    /// max, hypot, atan2). For unary functions, only `a` is used.
+    #[tool(values(
+        "add", "subtract", "multiply", "divide", "power", "modulo", "+", "-", "*", "/", "**", "%",
+        "sqrt", "abs", "exp", "ln", "log10", "log2", "sin", "cos", "tan", "asin", "acos", "atan",
+        "sinh", "cosh", "tanh", "min", "max", "hypot", "atan2"
+    ))]
     operation: String,
+    /// The first operand (or the sole operand for unary functions)
     a: f64,
+    /// The second operand (ignored for unary functions such as sqrt, ln, sin, etc.)
     b: f64,
 }
 
@@ -99,52 +109,16 @@ impl Default for MathTool {
     }
 }
 
-#[async_trait]
-impl Tool for MathTool {
-    fn name(&self) -> &str {
-        "calculator"
-    }
-
-    fn description(&self) -> &str {
-        "Calculator tool: performs mathematical operations using a small standard library (arithmetic, powers, modulo, roots, logs, trigonometric and hyperbolic functions, and simple two-argument operations like min/max)"
-    }
-
-    fn parameters(&self) -> Value {
-        serde_json::json!({
-            "type": "object",
-            "properties": {
-                "operation": {
-                    "type": "string",
-                    "description": "The operation to perform. Supports arithmetic (add, subtract, multiply, divide, power, modulo or +, -, *, /, **, %), common unary functions (sqrt, abs, exp, ln, log10, log2, sin, cos, tan, asin, acos, atan, sinh, cosh, tanh), and simple binary functions (min, max, hypot, atan2). For unary functions, only `a` is used.",
-                    "enum": [
-                        "add", "subtract", "multiply", "divide", "power", "modulo",
-                        "+", "-", "*", "/", "**", "%",
-                        "sqrt", "abs", "exp", "ln", "log10", "log2",
-                        "sin", "cos", "tan", "asin", "acos", "atan",
-                        "sinh", "cosh", "tanh",
-                        "min", "max", "hypot", "atan2"
-                    ]
-                },
-                "a": {
-                    "type": "number",
-                    "description": "The first operand (or the sole operand for unary functions)"
-                },
-                "b": {
-                    "type": "number",
-                    "description": "The second operand (ignored for unary functions such as sqrt, ln, sin, etc.)"
-                }
-            },
-            "required": ["operation", "a", "b"]
-        })
-    }
-
-    async fn execute(&self, args: Value) -> Result<ToolResult> {
-        let math_args: MathArgs =
-            serde_json::from_value(args).context("Failed to parse math arguments")?;
-
-        match self.evaluate(&math_args.operation, math_args.a, math_args.b) {
-            Ok(result) => Ok(ToolResult::success(result.to_string())),
-            Err(e) => Ok(ToolResult::failure(e.to_string())),
+typed_tool! {
+    impl Tool for MathTool {
+        name: "calculator",
+        description: "Calculator tool: performs mathematical operations using a small standard library (arithmetic, powers, modulo, roots, logs, trigonometric and hyperbolic functions, and simple two-argument operations like min/max)",
+        args: MathArgs,
+        async fn execute(&self, args) -> Result<ToolResult> {
+            match self.evaluate(&args.operation, args.a, args.b) {
+                Ok(result) => Ok(ToolResult::success(result.to_string())),
+                Err(e) => Ok(ToolResult::failure(e.to_string())),
+            }
         }
     }
 }
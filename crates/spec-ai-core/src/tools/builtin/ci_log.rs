@@ -0,0 +1,745 @@
+//! CI log ingestion
+//!
+//! `ingest_ci_log` parses the output of a test run (`cargo test`, pytest, or
+//! a JUnit XML report), records each failure as a `TestFailure` node under a
+//! `CiRun` event, and links failures to the code components bootstrap put in
+//! the graph, so a "fix the failing tests" spec starts from structured data
+//! rather than a wall of log text.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::graph_store::{KnowledgeGraphStore, GRAPH_POLICY_RESOURCE};
+use crate::persistence::Persistence;
+use crate::tools::{Tool, ToolResult};
+use crate::types::{EdgeType, NodeType};
+
+/// Longest failure message kept on a node
+const MAX_MESSAGE_CHARS: usize = 2000;
+/// Failures listed in the summary
+const SUMMARY_FAILURES: usize = 10;
+
+/// Test output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CiFormat {
+    CargoTest,
+    Pytest,
+    Junit,
+}
+
+impl CiFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CiFormat::CargoTest => "cargo_test",
+            CiFormat::Pytest => "pytest",
+            CiFormat::Junit => "junit",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cargo" | "cargo_test" | "cargo-test" => Some(CiFormat::CargoTest),
+            "pytest" => Some(CiFormat::Pytest),
+            "junit" | "junit_xml" | "xml" => Some(CiFormat::Junit),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the log contents
+    pub fn detect(log: &str) -> Option<Self> {
+        if log.contains("<testsuite") || log.contains("<testcase") {
+            Some(CiFormat::Junit)
+        } else if log.contains("test result:") || cargo_test_line().is_match(log) {
+            Some(CiFormat::CargoTest)
+        } else if log.contains("short test summary") || pytest_failed_line().is_match(log) {
+            Some(CiFormat::Pytest)
+        } else {
+            None
+        }
+    }
+}
+
+/// One failed test
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TestFailure {
+    pub test: String,
+    /// Test binary, module or class the test belongs to
+    pub suite: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+/// Failures and counts parsed from a test log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CiReport {
+    pub format: CiFormat,
+    pub passed: usize,
+    pub failed: usize,
+    pub failures: Vec<TestFailure>,
+}
+
+impl CiReport {
+    /// Human-readable summary listing the first few failures
+    pub fn summary(&self) -> String {
+        if self.failures.is_empty() {
+            return format!(
+                "{} log: {} passed, no failures.",
+                self.format.as_str(),
+                self.passed
+            );
+        }
+        let mut out = format!(
+            "{} log: {} passed, {} failed.",
+            self.format.as_str(),
+            self.passed,
+            self.failed
+        );
+        for failure in self.failures.iter().take(SUMMARY_FAILURES) {
+            let location = match (&failure.file, failure.line) {
+                (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+                (Some(file), None) => format!(" ({})", file),
+                _ => String::new(),
+            };
+            let first_line = failure.message.lines().next().unwrap_or("").trim();
+            out.push_str(&format!("\n- {}{}: {}", failure.test, location, first_line));
+        }
+        if self.failures.len() > SUMMARY_FAILURES {
+            out.push_str(&format!(
+                "\n… and {} more",
+                self.failures.len() - SUMMARY_FAILURES
+            ));
+        }
+        out
+    }
+}
+
+/// Parse a test log, detecting its format unless one is given
+pub fn parse_ci_log(log: &str, format: Option<CiFormat>) -> Result<CiReport> {
+    let format = match format.or_else(|| CiFormat::detect(log)) {
+        Some(format) => format,
+        None => bail!("unrecognized test output; pass format (cargo_test, pytest or junit)"),
+    };
+    let mut report = match format {
+        CiFormat::CargoTest => parse_cargo_test(log),
+        CiFormat::Pytest => parse_pytest(log),
+        CiFormat::Junit => parse_junit(log),
+    };
+    report.failed = report.failed.max(report.failures.len());
+    for failure in &mut report.failures {
+        if failure.message.chars().count() > MAX_MESSAGE_CHARS {
+            failure.message = failure.message.chars().take(MAX_MESSAGE_CHARS).collect();
+            failure.message.push('…');
+        }
+    }
+    Ok(report)
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+fn cargo_test_line() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?m)^test (\S+) \.\.\. (ok|FAILED|ignored)")
+}
+
+fn pytest_failed_line() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?m)^(?:FAILED|ERROR) (\S+?)::(\S+)(?: - (.*))?$")
+}
+
+/// `src/lib.rs:10:9`, as printed after `panicked at`
+fn split_location(location: &str) -> (Option<String>, Option<u32>) {
+    let mut parts = location.trim().trim_end_matches(':').rsplitn(3, ':');
+    let _column = parts.next();
+    let line = parts.next().and_then(|line| line.parse().ok());
+    match (parts.next(), line) {
+        (Some(file), Some(line)) => (Some(file.to_string()), Some(line)),
+        _ => (None, None),
+    }
+}
+
+fn parse_cargo_test(log: &str) -> CiReport {
+    static RUNNING: OnceLock<Regex> = OnceLock::new();
+    static STDOUT: OnceLock<Regex> = OnceLock::new();
+    static PANIC: OnceLock<Regex> = OnceLock::new();
+    static RESULT: OnceLock<Regex> = OnceLock::new();
+    let running = regex(&RUNNING, r"^\s*Running (?:unittests |tests )?(\S+)");
+    let stdout = regex(&STDOUT, r"^---- (\S+) stdout ----$");
+    // Current rustc: `panicked at src/lib.rs:3:5:` with the message on the
+    // next lines; older ones: `panicked at 'message', src/lib.rs:3:5`
+    let panic = regex(
+        &PANIC,
+        r"^thread '[^']*' panicked at (?:'(.*)', )?(\S+:\d+:\d+):?$",
+    );
+    let result = regex(&RESULT, r"^test result: \w+\. (\d+) passed; (\d+) failed");
+
+    let mut report = CiReport {
+        format: CiFormat::CargoTest,
+        passed: 0,
+        failed: 0,
+        failures: Vec::new(),
+    };
+    let mut suite: Option<String> = None;
+    let mut current: Option<TestFailure> = None;
+
+    for line in log.lines() {
+        let trimmed = line.trim_end();
+        if let Some(caps) = running.captures(trimmed) {
+            suite = Some(caps[1].to_string());
+            report.failures.extend(current.take());
+            continue;
+        }
+        if let Some(caps) = stdout.captures(trimmed) {
+            report.failures.extend(current.take());
+            current = Some(TestFailure {
+                test: caps[1].to_string(),
+                suite: suite.clone(),
+                ..Default::default()
+            });
+            continue;
+        }
+        if let Some(caps) = result.captures(trimmed) {
+            report.passed += caps[1].parse::<usize>().unwrap_or(0);
+            report.failed += caps[2].parse::<usize>().unwrap_or(0);
+            report.failures.extend(current.take());
+            continue;
+        }
+        if trimmed == "failures:" {
+            // The second `failures:` block only lists names again
+            report.failures.extend(current.take());
+            continue;
+        }
+        let Some(failure) = current.as_mut() else {
+            continue;
+        };
+        if trimmed.starts_with("note: run with `RUST_BACKTRACE") {
+            continue;
+        }
+        if let Some(caps) = panic.captures(trimmed) {
+            let (file, line) = split_location(&caps[2]);
+            failure.file = file;
+            failure.line = line;
+            if let Some(message) = caps.get(1) {
+                failure.message = message.as_str().to_string();
+            }
+        } else if !trimmed.is_empty() {
+            if !failure.message.is_empty() {
+                failure.message.push('\n');
+            }
+            failure.message.push_str(trimmed);
+        }
+    }
+    report.failures.extend(current.take());
+
+    // Failing tests whose output was not captured still show up as FAILED
+    for caps in cargo_test_line().captures_iter(log) {
+        let name = &caps[1];
+        if &caps[2] == "FAILED" && !report.failures.iter().any(|f| f.test == name) {
+            report.failures.push(TestFailure {
+                test: name.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+    for failure in &mut report.failures {
+        if failure.file.is_none() {
+            // Integration test binaries are named after their file
+            failure.file = failure
+                .suite
+                .as_ref()
+                .filter(|suite| suite.ends_with(".rs"))
+                .cloned();
+        }
+    }
+    report
+}
+
+fn parse_pytest(log: &str) -> CiReport {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    static COUNTS: OnceLock<Regex> = OnceLock::new();
+    let header = regex(&HEADER, r"^_{3,} (\S+) _{3,}$");
+    let location = regex(&LOCATION, r"^(\S+\.py):(\d+): ");
+    let counts = regex(&COUNTS, r"(\d+) (passed|failed|errors?)\b");
+
+    // Last `file.py:line:` reported inside each failure section
+    let mut section: Option<String> = None;
+    let mut lines = std::collections::HashMap::new();
+    let mut report = CiReport {
+        format: CiFormat::Pytest,
+        passed: 0,
+        failed: 0,
+        failures: Vec::new(),
+    };
+    for line in log.lines() {
+        let trimmed = line.trim_end();
+        if let Some(caps) = header.captures(trimmed) {
+            section = Some(caps[1].to_string());
+        } else if let (Some(name), Some(caps)) = (&section, location.captures(trimmed)) {
+            let line: u32 = caps[2].parse().unwrap_or(0);
+            lines.insert(name.clone(), (caps[1].to_string(), line));
+        }
+        if trimmed.starts_with('=') && trimmed.contains(" in ") {
+            for caps in counts.captures_iter(trimmed) {
+                let count: usize = caps[1].parse().unwrap_or(0);
+                match &caps[2] {
+                    "passed" => report.passed = count,
+                    _ => report.failed += count,
+                }
+            }
+        }
+    }
+
+    for caps in pytest_failed_line().captures_iter(log) {
+        let file = caps[1].to_string();
+        let id = caps[2].to_string();
+        let section_name = id.replace("::", ".");
+        let line = lines
+            .get(&section_name)
+            .filter(|(section_file, _)| *section_file == file)
+            .map(|(_, line)| *line);
+        let (suite, test) = match id.rsplit_once("::") {
+            Some((class, test)) => (Some(class.to_string()), test.to_string()),
+            None => (None, id.clone()),
+        };
+        report.failures.push(TestFailure {
+            test,
+            suite,
+            file: Some(file),
+            line,
+            message: caps
+                .get(3)
+                .map(|m| m.as_str().trim().to_string())
+                .unwrap_or_default(),
+        });
+    }
+    report
+}
+
+fn parse_junit(log: &str) -> CiReport {
+    static CASE: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    static FAILURE: OnceLock<Regex> = OnceLock::new();
+    let case = regex(&CASE, r"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)");
+    let attr = regex(&ATTR, r#"([\w:-]+)\s*=\s*"([^"]*)""#);
+    let failure = regex(
+        &FAILURE,
+        r"(?s)<(?:failure|error)\b([^>]*?)(?:/>|>(.*?)</(?:failure|error)>)",
+    );
+    let attributes = |text: &str| -> std::collections::HashMap<String, String> {
+        attr.captures_iter(text)
+            .map(|caps| {
+                (
+                    caps[1].to_string(),
+                    html_escape::decode_html_entities(&caps[2]).to_string(),
+                )
+            })
+            .collect()
+    };
+
+    let mut report = CiReport {
+        format: CiFormat::Junit,
+        passed: 0,
+        failed: 0,
+        failures: Vec::new(),
+    };
+    for caps in case.captures_iter(log) {
+        let attrs = attributes(&caps[1]);
+        let body = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        if body.contains("<skipped") {
+            continue;
+        }
+        let Some(failed) = failure.captures(body) else {
+            report.passed += 1;
+            continue;
+        };
+        let failure_attrs = attributes(&failed[1]);
+        let text = failed
+            .get(2)
+            .map(|m| {
+                let text = m.as_str().trim();
+                let text = text
+                    .strip_prefix("<![CDATA[")
+                    .and_then(|t| t.strip_suffix("]]>"))
+                    .unwrap_or(text);
+                html_escape::decode_html_entities(text).trim().to_string()
+            })
+            .unwrap_or_default();
+        let message = match failure_attrs.get("message") {
+            Some(message) if text.is_empty() => message.clone(),
+            Some(message) if !text.contains(message.as_str()) => {
+                format!("{}\n{}", message, text)
+            }
+            _ => text,
+        };
+        report.failures.push(TestFailure {
+            test: attrs.get("name").cloned().unwrap_or_default(),
+            suite: attrs.get("classname").cloned(),
+            file: attrs.get("file").cloned(),
+            line: attrs.get("line").and_then(|line| line.parse().ok()),
+            message,
+        });
+    }
+    report
+}
+
+/// Nodes written for one ingested log
+#[derive(Debug, Clone, Serialize)]
+pub struct CiIngestOutcome {
+    pub run_node_id: i64,
+    pub failure_node_ids: Vec<i64>,
+    /// Failures linked to a component or file node
+    pub linked: usize,
+}
+
+/// Graph node a failure in `file` belongs to: the node with the longest
+/// `path` property that is the file itself or one of its directories
+fn component_for<'a>(
+    file: &str,
+    components: &'a [(String, i64)],
+    roots: &[String],
+) -> Option<&'a (String, i64)> {
+    let relative = roots
+        .iter()
+        .find_map(|root| file.strip_prefix(root.as_str()))
+        .map(|rest| rest.trim_start_matches('/'))
+        .unwrap_or(file)
+        .trim_start_matches("./");
+    components
+        .iter()
+        .filter(|(path, _)| {
+            relative == path.as_str()
+                || relative
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|(path, _)| path.len())
+}
+
+/// Write a parsed report to the session graph
+pub fn record_ci_report(
+    persistence: &Persistence,
+    session_id: &str,
+    report: &CiReport,
+    source: &str,
+) -> Result<CiIngestOutcome> {
+    let nodes = persistence.list_graph_nodes(session_id, None, None)?;
+    let path_of = |properties: &Value| {
+        properties
+            .get("path")
+            .and_then(|path| path.as_str())
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+    };
+    let roots: Vec<String> = nodes
+        .iter()
+        .filter(|node| node.label == "Repository")
+        .filter_map(|node| path_of(&node.properties))
+        .filter(|path| Path::new(path).is_absolute())
+        .collect();
+    let components: Vec<(String, i64)> = nodes
+        .iter()
+        .filter(|node| node.label != "Repository" && node.label != "TestFailure")
+        .filter_map(|node| path_of(&node.properties).map(|path| (path, node.id)))
+        .filter(|(path, _)| !Path::new(path).is_absolute())
+        .collect();
+
+    let run_node_id = persistence.insert_graph_node(
+        session_id,
+        NodeType::Event,
+        "CiRun",
+        &json!({
+            "source": source,
+            "format": report.format.as_str(),
+            "passed": report.passed,
+            "failed": report.failed,
+            "ingested_at": Utc::now().to_rfc3339(),
+        }),
+        None,
+    )?;
+
+    let mut outcome = CiIngestOutcome {
+        run_node_id,
+        failure_node_ids: Vec::new(),
+        linked: 0,
+    };
+    for failure in &report.failures {
+        let failure_id = persistence.insert_graph_node(
+            session_id,
+            NodeType::Fact,
+            "TestFailure",
+            &json!({
+                "test": failure.test,
+                "suite": failure.suite,
+                "file": failure.file,
+                "line": failure.line,
+                "message": failure.message,
+                "framework": report.format.as_str(),
+            }),
+            None,
+        )?;
+        persistence.insert_graph_edge(
+            session_id,
+            failure_id,
+            run_node_id,
+            EdgeType::PartOf,
+            Some("failed_in"),
+            None,
+            1.0,
+        )?;
+        let component = failure
+            .file
+            .as_deref()
+            .and_then(|file| component_for(file, &components, &roots));
+        if let Some((_, component_id)) = component {
+            persistence.insert_graph_edge(
+                session_id,
+                failure_id,
+                *component_id,
+                EdgeType::RelatesTo,
+                Some("fails_in"),
+                None,
+                0.9,
+            )?;
+            outcome.linked += 1;
+        }
+        outcome.failure_node_ids.push(failure_id);
+    }
+    Ok(outcome)
+}
+
+/// Parses CI and test logs into `TestFailure` graph nodes
+pub struct IngestCiLogTool {
+    store: KnowledgeGraphStore,
+}
+
+impl IngestCiLogTool {
+    pub fn new(store: KnowledgeGraphStore) -> Self {
+        Self { store }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct IngestCiLogArgs {
+    /// Path of a log file or JUnit XML report
+    path: Option<String>,
+    /// Log text, when not reading a file
+    content: Option<String>,
+    /// cargo_test, pytest or junit; detected from the log when omitted
+    format: Option<String>,
+    /// Session ID for graph isolation (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for IngestCiLogTool {
+    fn name(&self) -> &str {
+        "ingest_ci_log"
+    }
+
+    fn description(&self) -> &str {
+        "Parse cargo test, pytest or JUnit XML output, record each failing test in the knowledge graph linked to its code component, and summarize the failures"
+    }
+
+    fn parameters(&self) -> Value {
+        IngestCiLogArgs::schema()
+    }
+
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args =
+            IngestCiLogArgs::from_args(args).context("Failed to parse ingest_ci_log arguments")?;
+        let session_id = args
+            .session_id
+            .filter(|id| !id.is_empty())
+            .context("session_id is required")?;
+        let format = match args.format.as_deref() {
+            Some(name) => match CiFormat::parse(name) {
+                Some(format) => Some(format),
+                None => {
+                    return Ok(ToolResult::failure(format!(
+                        "Unknown format '{}'; use cargo_test, pytest or junit",
+                        name
+                    )))
+                }
+            },
+            None => None,
+        };
+        let (log, source) = match (args.path, args.content) {
+            (Some(path), _) => {
+                let log = tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("reading {}", path))?;
+                (log, path)
+            }
+            (None, Some(content)) => (content, "inline".to_string()),
+            (None, None) => return Ok(ToolResult::failure("Provide path or content")),
+        };
+
+        let report = match parse_ci_log(&log, format) {
+            Ok(report) => report,
+            Err(err) => return Ok(ToolResult::failure(err.to_string())),
+        };
+        let recorded = report.clone();
+        let outcome = self
+            .store
+            .run(move |persistence| record_ci_report(persistence, &session_id, &recorded, &source))
+            .await?;
+
+        Ok(ToolResult::success(
+            json!({
+                "summary": report.summary(),
+                "format": report.format,
+                "passed": report.passed,
+                "failed": report.failed,
+                "failures": report.failures,
+                "run_node_id": outcome.run_node_id,
+                "linked_to_components": outcome.linked,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_LOG: &str = r#"
+     Running unittests src/lib.rs (target/debug/deps/demo-1234)
+
+running 3 tests
+test math::adds ... ok
+test math::subtracts ... FAILED
+test parse::old_style ... FAILED
+
+failures:
+
+---- math::subtracts stdout ----
+thread 'math::subtracts' panicked at src/math.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- parse::old_style stdout ----
+thread 'parse::old_style' panicked at 'bad input', src/parse.rs:4:5
+
+
+failures:
+    math::subtracts
+    parse::old_style
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out
+"#;
+
+    const PYTEST_LOG: &str = r#"
+============================= test session starts ==============================
+collected 3 items
+
+tests/test_math.py .F.                                                   [100%]
+
+=================================== FAILURES ===================================
+_________________________ TestMath.test_subtract ______________________________
+
+    def test_subtract(self):
+>       assert 1 - 1 == 1
+E       assert 0 == 1
+
+tests/test_math.py:9: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::TestMath::test_subtract - assert 0 == 1
+========================= 1 failed, 2 passed in 0.05s ==========================
+"#;
+
+    const JUNIT_LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="api" tests="3" failures="1">
+  <testcase classname="api.UserTest" name="creates_user" file="src/api/user.ts" line="42">
+    <failure message="expected 201 &amp; got 500">Error: expected 201 &amp; got 500
+    at user.ts:42</failure>
+  </testcase>
+  <testcase classname="api.UserTest" name="lists_users"/>
+  <testcase classname="api.UserTest" name="skips"><skipped/></testcase>
+</testsuite>"#;
+
+    #[test]
+    fn parses_cargo_test_output() {
+        let report = parse_ci_log(CARGO_LOG, None).unwrap();
+        assert_eq!(report.format, CiFormat::CargoTest);
+        assert_eq!((report.passed, report.failed), (1, 2));
+        assert_eq!(report.failures.len(), 2);
+
+        let subtracts = &report.failures[0];
+        assert_eq!(subtracts.test, "math::subtracts");
+        assert_eq!(subtracts.suite.as_deref(), Some("src/lib.rs"));
+        assert_eq!(subtracts.file.as_deref(), Some("src/math.rs"));
+        assert_eq!(subtracts.line, Some(12));
+        assert!(subtracts
+            .message
+            .starts_with("assertion `left == right` failed"));
+        assert!(!subtracts.message.contains("RUST_BACKTRACE"));
+
+        let old_style = &report.failures[1];
+        assert_eq!(old_style.message, "bad input");
+        assert_eq!(old_style.file.as_deref(), Some("src/parse.rs"));
+        assert!(report
+            .summary()
+            .contains("math::subtracts (src/math.rs:12)"));
+    }
+
+    #[test]
+    fn parses_pytest_and_junit_output() {
+        let report = parse_ci_log(PYTEST_LOG, None).unwrap();
+        assert_eq!(report.format, CiFormat::Pytest);
+        assert_eq!((report.passed, report.failed), (2, 1));
+        let failure = &report.failures[0];
+        assert_eq!(failure.test, "test_subtract");
+        assert_eq!(failure.suite.as_deref(), Some("TestMath"));
+        assert_eq!(failure.file.as_deref(), Some("tests/test_math.py"));
+        assert_eq!(failure.line, Some(9));
+        assert_eq!(failure.message, "assert 0 == 1");
+
+        let report = parse_ci_log(JUNIT_LOG, None).unwrap();
+        assert_eq!(report.format, CiFormat::Junit);
+        assert_eq!((report.passed, report.failed), (1, 1));
+        let failure = &report.failures[0];
+        assert_eq!(failure.test, "creates_user");
+        assert_eq!(failure.suite.as_deref(), Some("api.UserTest"));
+        assert_eq!(failure.line, Some(42));
+        assert!(failure.message.starts_with("Error: expected 201 & got 500"));
+
+        assert!(parse_ci_log("nothing to see", None).is_err());
+    }
+
+    #[test]
+    fn links_failures_to_the_most_specific_component() {
+        let components = vec![
+            ("src".to_string(), 1),
+            ("src/api".to_string(), 2),
+            ("tests".to_string(), 3),
+        ];
+        let roots = vec!["/work/repo".to_string()];
+        let find = |file: &str| component_for(file, &components, &roots).map(|(_, id)| *id);
+        assert_eq!(find("src/api/user.ts"), Some(2));
+        assert_eq!(find("/work/repo/src/math.rs"), Some(1));
+        assert_eq!(find("./tests/test_math.py"), Some(3));
+        assert_eq!(find("srcs/other.rs"), None);
+    }
+}
@@ -0,0 +1,390 @@
+//! Status of cloud providers and hosted services
+//!
+//! `cloud_status` reads a provider's public status page and reports whether
+//! anything is degraded: ongoing incidents and components not operational.
+//! Most providers publish an Atlassian Statuspage (`/api/v2/summary.json`),
+//! which also works for any page given by URL; Google Cloud publishes a
+//! list of incidents instead.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+use std::time::Duration;
+
+use crate::tools::{Tool, ToolResult};
+
+const GCP_INCIDENTS_URL: &str = "https://status.cloud.google.com/incidents.json";
+const MAX_UPDATE_CHARS: usize = 500;
+
+/// Providers known by name, and their Statuspage
+const STATUSPAGES: &[(&str, &str)] = &[
+    ("github", "https://www.githubstatus.com"),
+    ("cloudflare", "https://www.cloudflarestatus.com"),
+    ("digitalocean", "https://status.digitalocean.com"),
+    ("datadog", "https://status.datadoghq.com"),
+    ("openai", "https://status.openai.com"),
+    ("anthropic", "https://status.anthropic.com"),
+];
+
+/// An incident that has not been resolved
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Incident {
+    pub name: String,
+    /// e.g. `investigating`, `identified`, `monitoring`
+    pub status: String,
+    /// `minor`, `major`, `critical`, ...
+    pub impact: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_update: Option<String>,
+}
+
+/// A component that is not operational
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DegradedComponent {
+    pub name: String,
+    /// e.g. `degraded_performance`, `partial_outage`, `major_outage`
+    pub status: String,
+}
+
+/// What a status page says
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    pub healthy: bool,
+    /// The page's own summary, e.g. "All Systems Operational"
+    pub description: String,
+    pub incidents: Vec<Incident>,
+    pub degraded_components: Vec<DegradedComponent>,
+}
+
+fn text(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn shorten(update: &str) -> String {
+    let update = update.trim();
+    match update.char_indices().nth(MAX_UPDATE_CHARS) {
+        Some((end, _)) => format!("{}...", &update[..end]),
+        None => update.to_string(),
+    }
+}
+
+/// Read a Statuspage `summary.json`
+pub fn parse_statuspage(provider: &str, summary: &Value) -> ProviderStatus {
+    let incidents: Vec<Incident> = summary
+        .get("incidents")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|incident| Incident {
+            name: text(incident, "name"),
+            status: text(incident, "status"),
+            impact: text(incident, "impact"),
+            url: incident
+                .get("shortlink")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            latest_update: incident
+                .pointer("/incident_updates/0/body")
+                .and_then(Value::as_str)
+                .map(shorten),
+        })
+        .collect();
+    let degraded_components: Vec<DegradedComponent> = summary
+        .get("components")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|component| text(component, "status") != "operational")
+        .map(|component| DegradedComponent {
+            name: text(component, "name"),
+            status: text(component, "status"),
+        })
+        .collect();
+    let indicator = summary
+        .pointer("/status/indicator")
+        .and_then(Value::as_str)
+        .unwrap_or("none");
+    ProviderStatus {
+        provider: provider.to_string(),
+        healthy: indicator == "none" && incidents.is_empty() && degraded_components.is_empty(),
+        description: summary
+            .pointer("/status/description")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        incidents,
+        degraded_components,
+    }
+}
+
+/// Read Google Cloud's `incidents.json`; incidents without an end are ongoing
+pub fn parse_gcp_incidents(incidents: &Value) -> ProviderStatus {
+    let ongoing: Vec<Incident> = incidents
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|incident| incident.get("end").is_none_or(Value::is_null))
+        .map(|incident| {
+            let products: Vec<String> = incident
+                .get("affected_products")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|product| text(product, "title"))
+                .collect();
+            let mut name = text(incident, "external_desc");
+            if !products.is_empty() {
+                name = format!("{} ({})", name.trim(), products.join(", "));
+            }
+            Incident {
+                name,
+                status: text(incident, "status_impact"),
+                impact: text(incident, "severity"),
+                url: incident
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .map(|uri| format!("https://status.cloud.google.com/{}", uri)),
+                latest_update: incident
+                    .pointer("/most_recent_update/text")
+                    .and_then(Value::as_str)
+                    .map(shorten),
+            }
+        })
+        .collect();
+    ProviderStatus {
+        provider: "gcp".to_string(),
+        healthy: ongoing.is_empty(),
+        description: if ongoing.is_empty() {
+            "No ongoing incidents".to_string()
+        } else {
+            format!("{} ongoing incident(s)", ongoing.len())
+        },
+        incidents: ongoing,
+        degraded_components: Vec::new(),
+    }
+}
+
+/// Where to read a provider's status, and the format of the page
+enum StatusSource {
+    Statuspage(String),
+    GcpIncidents,
+}
+
+fn status_source(provider: Option<&str>, url: Option<&str>) -> Result<(String, StatusSource)> {
+    if let Some(url) = url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(anyhow!("url must be an http(s) URL"));
+        }
+        let url = url.trim_end_matches('/');
+        let summary = if url.ends_with(".json") {
+            url.to_string()
+        } else {
+            format!("{}/api/v2/summary.json", url)
+        };
+        let name = provider.unwrap_or(url).to_string();
+        return Ok((name, StatusSource::Statuspage(summary)));
+    }
+    let provider = provider
+        .map(str::to_lowercase)
+        .ok_or_else(|| anyhow!("cloud_status needs a provider or a url"))?;
+    if provider == "gcp" || provider == "google" {
+        return Ok(("gcp".to_string(), StatusSource::GcpIncidents));
+    }
+    match STATUSPAGES.iter().find(|(name, _)| *name == provider) {
+        Some((name, base)) => Ok((
+            name.to_string(),
+            StatusSource::Statuspage(format!("{}/api/v2/summary.json", base)),
+        )),
+        None => Err(anyhow!(
+            "Unknown provider '{}'. Known: gcp, {}; or pass the url of a Statuspage",
+            provider,
+            STATUSPAGES
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct CloudStatusArgs {
+    /// Provider name: gcp, github, cloudflare, digitalocean, datadog, openai or anthropic
+    provider: Option<String>,
+    /// Status page URL (Atlassian Statuspage) for a provider not listed
+    url: Option<String>,
+}
+
+/// Reports incidents and degraded components from public status pages
+pub struct CloudStatusTool {
+    client: Client,
+}
+
+impl CloudStatusTool {
+    pub fn new() -> Self {
+        static APP_USER_AGENT: &str =
+            concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
+        Self {
+            client: Client::builder()
+                .user_agent(APP_USER_AGENT)
+                .timeout(Duration::from_secs(15))
+                .build()
+                .expect("failed to construct cloud status client"),
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Value> {
+        self.client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .json()
+            .await
+            .with_context(|| format!("{} did not return JSON", url))
+    }
+}
+
+impl Default for CloudStatusTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for CloudStatusTool {
+    fn name(&self) -> &str {
+        "cloud_status"
+    }
+
+    fn description(&self) -> &str {
+        "Check a cloud provider's or hosted service's public status page for ongoing incidents and degraded components"
+    }
+
+    fn parameters(&self) -> Value {
+        CloudStatusArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args =
+            CloudStatusArgs::from_args(args).context("Failed to parse cloud_status arguments")?;
+        let (provider, source) = match status_source(args.provider.as_deref(), args.url.as_deref())
+        {
+            Ok(source) => source,
+            Err(err) => return Ok(ToolResult::failure(err.to_string())),
+        };
+        let status = match source {
+            StatusSource::Statuspage(url) => self
+                .fetch(&url)
+                .await
+                .map(|summary| parse_statuspage(&provider, &summary)),
+            StatusSource::GcpIncidents => self
+                .fetch(GCP_INCIDENTS_URL)
+                .await
+                .map(|incidents| parse_gcp_incidents(&incidents)),
+        };
+        match status {
+            Ok(status) => Ok(ToolResult::success(
+                serde_json::to_string(&status).context("Failed to serialize status")?,
+            )),
+            Err(err) => Ok(ToolResult::failure(format!("{:#}", err))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn statuspage_summary_is_parsed() {
+        let summary = json!({
+            "status": {"indicator": "minor", "description": "Partially Degraded Service"},
+            "components": [
+                {"name": "API Requests", "status": "operational"},
+                {"name": "Actions", "status": "degraded_performance"}
+            ],
+            "incidents": [{
+                "name": "Delayed Actions runs",
+                "status": "investigating",
+                "impact": "minor",
+                "shortlink": "https://stspg.io/abc",
+                "incident_updates": [{"body": "We are investigating delays."}]
+            }]
+        });
+        let status = parse_statuspage("github", &summary);
+        assert!(!status.healthy);
+        assert_eq!(status.description, "Partially Degraded Service");
+        assert_eq!(
+            status.degraded_components,
+            vec![DegradedComponent {
+                name: "Actions".to_string(),
+                status: "degraded_performance".to_string()
+            }]
+        );
+        assert_eq!(status.incidents[0].impact, "minor");
+        assert_eq!(
+            status.incidents[0].latest_update.as_deref(),
+            Some("We are investigating delays.")
+        );
+
+        let calm = json!({
+            "status": {"indicator": "none", "description": "All Systems Operational"},
+            "components": [{"name": "API Requests", "status": "operational"}],
+            "incidents": []
+        });
+        assert!(parse_statuspage("github", &calm).healthy);
+    }
+
+    #[test]
+    fn gcp_reports_only_ongoing_incidents() {
+        let incidents = json!([
+            {
+                "external_desc": "Elevated errors",
+                "severity": "medium",
+                "status_impact": "SERVICE_DISRUPTION",
+                "uri": "incidents/abc",
+                "affected_products": [{"title": "Cloud Run"}],
+                "most_recent_update": {"text": "Mitigation in progress."}
+            },
+            {"external_desc": "Past outage", "end": "2024-01-01T00:00:00Z"}
+        ]);
+        let status = parse_gcp_incidents(&incidents);
+        assert!(!status.healthy);
+        assert_eq!(status.incidents.len(), 1);
+        assert_eq!(status.incidents[0].name, "Elevated errors (Cloud Run)");
+        assert_eq!(
+            status.incidents[0].url.as_deref(),
+            Some("https://status.cloud.google.com/incidents/abc")
+        );
+    }
+
+    #[test]
+    fn providers_resolve_to_status_pages() {
+        let (name, source) = status_source(Some("GitHub"), None).unwrap();
+        assert_eq!(name, "github");
+        assert!(matches!(
+            source,
+            StatusSource::Statuspage(url) if url == "https://www.githubstatus.com/api/v2/summary.json"
+        ));
+        let (_, source) = status_source(None, Some("https://status.example.com/")).unwrap();
+        assert!(matches!(
+            source,
+            StatusSource::Statuspage(url) if url == "https://status.example.com/api/v2/summary.json"
+        ));
+        assert!(status_source(Some("aws"), None).is_err());
+        assert!(status_source(None, Some("file:///etc/passwd")).is_err());
+    }
+}
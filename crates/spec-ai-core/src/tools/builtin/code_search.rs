@@ -127,6 +127,10 @@ impl Tool for CodeSearchTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["root"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: CodeSearchArgs =
             serde_json::from_value(args).context("Failed to parse code_search arguments")?;
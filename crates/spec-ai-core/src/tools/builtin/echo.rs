@@ -1,14 +1,14 @@
 use crate::tools::{Tool, ToolResult};
-use anyhow::{Context, Result};
-use async_trait::async_trait;
+use anyhow::Result;
 use serde::Deserialize;
-use serde_json::Value;
+use spec_ai_plugin::{typed_tool, ToolArgs};
 
 /// A simple echo tool that returns its input
 pub struct EchoTool;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolArgs)]
 struct EchoArgs {
+    /// The message to echo back
     message: String,
 }
 
@@ -24,34 +24,14 @@ impl Default for EchoTool {
     }
 }
 
-#[async_trait]
-impl Tool for EchoTool {
-    fn name(&self) -> &str {
-        "echo"
-    }
-
-    fn description(&self) -> &str {
-        "Echoes back the provided message"
-    }
-
-    fn parameters(&self) -> Value {
-        serde_json::json!({
-            "type": "object",
-            "properties": {
-                "message": {
-                    "type": "string",
-                    "description": "The message to echo back"
-                }
-            },
-            "required": ["message"]
-        })
-    }
-
-    async fn execute(&self, args: Value) -> Result<ToolResult> {
-        let echo_args: EchoArgs =
-            serde_json::from_value(args).context("Failed to parse echo arguments")?;
-
-        Ok(ToolResult::success(echo_args.message))
+typed_tool! {
+    impl Tool for EchoTool {
+        name: "echo",
+        description: "Echoes back the provided message",
+        args: EchoArgs,
+        async fn execute(&self, args) -> Result<ToolResult> {
+            Ok(ToolResult::success(args.message))
+        }
     }
 }
 
@@ -375,6 +375,10 @@ impl Tool for FileExtractTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["path"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: FileExtractArgs =
             serde_json::from_value(args).context("Failed to parse file_extract arguments")?;
@@ -168,6 +168,10 @@ impl Tool for FileReadTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["path"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: FileReadArgs =
             serde_json::from_value(args).context("Failed to parse file_read arguments")?;
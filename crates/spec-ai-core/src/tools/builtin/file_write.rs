@@ -213,6 +213,10 @@ impl Tool for FileWriteTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["path"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: FileWriteArgs =
             serde_json::from_value(args).context("Failed to parse file_write arguments")?;
@@ -4,17 +4,23 @@ use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::graph_store::{KnowledgeGraphStore, GRAPH_POLICY_RESOURCE};
 use crate::persistence::Persistence;
 use crate::tools::{Tool, ToolResult};
 use crate::types::{EdgeType, NodeType, TraversalDirection};
 
 pub struct GraphTool {
-    persistence: Arc<Persistence>,
+    store: KnowledgeGraphStore,
 }
 
 impl GraphTool {
     pub fn new(persistence: Arc<Persistence>) -> Self {
-        Self { persistence }
+        Self::with_store(KnowledgeGraphStore::new(persistence))
+    }
+
+    /// Share a store with the focused graph tools
+    pub fn with_store(store: KnowledgeGraphStore) -> Self {
+        Self { store }
     }
 }
 
@@ -27,7 +33,9 @@ impl Tool for GraphTool {
     fn description(&self) -> &str {
         "Create, query, traverse, and synchronize knowledge graphs. Supports operations: \
          create_node, create_edge, delete_node, delete_edge, get_node, get_edge, \
-         list_nodes, list_edges, find_path, traverse_neighbors, update_node, \
+         list_nodes, list_edges, search_nodes, subgraph, find_path, traverse_neighbors, \
+         update_node, \
+         increment_counter, add_to_set, remove_from_set, \
          node_degree, list_hubs, enable_sync, disable_sync, sync_status, force_sync, \
          list_sync_configs"
     }
@@ -41,7 +49,9 @@ impl Tool for GraphTool {
                     "enum": [
                         "create_node", "create_edge", "delete_node", "delete_edge",
                         "get_node", "get_edge", "list_nodes", "list_edges",
-                        "find_path", "traverse_neighbors", "update_node",
+                        "search_nodes", "subgraph", "find_path", "traverse_neighbors",
+                        "update_node",
+                        "increment_counter", "add_to_set", "remove_from_set",
                         "node_degree", "list_hubs",
                         "enable_sync", "disable_sync", "sync_status", "force_sync",
                         "list_sync_configs"
@@ -62,7 +72,7 @@ impl Tool for GraphTool {
                 },
                 "node_type": {
                     "type": "string",
-                    "enum": ["entity", "concept", "fact", "message", "tool_result", "event"],
+                    "enum": self.store.types().node_type_names(),
                     "description": "Type of node to create or filter by"
                 },
                 "label": {
@@ -73,6 +83,28 @@ impl Tool for GraphTool {
                     "type": "object",
                     "description": "JSON properties for the node or edge"
                 },
+                "query": {
+                    "type": "string",
+                    "description": "Text to find nodes by meaning (for search_nodes, and the topic of a subgraph); searches all of the session's graphs"
+                },
+                "mermaid": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Also return the subgraph as a Mermaid flowchart (for subgraph)"
+                },
+                "property": {
+                    "type": "string",
+                    "description": "Node property holding a counter or set (for increment_counter, add_to_set, remove_from_set); these merge across synced instances without conflicts"
+                },
+                "value": {
+                    "description": "Element to add or remove (for add_to_set, remove_from_set)"
+                },
+                "amount": {
+                    "type": "integer",
+                    "default": 1,
+                    "minimum": 1,
+                    "description": "How much to add (for increment_counter)"
+                },
                 "source_id": {
                     "type": "integer",
                     "description": "Source node ID for edge creation or path finding"
@@ -83,10 +115,7 @@ impl Tool for GraphTool {
                 },
                 "edge_type": {
                     "type": "string",
-                    "enum": [
-                        "RELATES_TO", "CAUSED_BY", "PART_OF", "MENTIONS",
-                        "FOLLOWS_FROM", "USES", "PRODUCES", "DEPENDS_ON"
-                    ],
+                    "enum": self.store.types().edge_type_names(),
                     "description": "Type of edge relationship"
                 },
                 "custom_edge_type": {
@@ -113,7 +142,7 @@ impl Tool for GraphTool {
                     "default": 1,
                     "minimum": 1,
                     "maximum": 10,
-                    "description": "Depth for traversal operations"
+                    "description": "Depth for traversal operations and subgraph expansion"
                 },
                 "max_hops": {
                     "type": "integer",
@@ -127,7 +156,7 @@ impl Tool for GraphTool {
                     "default": 100,
                     "minimum": 1,
                     "maximum": 1000,
-                    "description": "Limit for list operations"
+                    "description": "Limit for list operations; for subgraph, how many matching nodes to expand from (default 5)"
                 },
                 "min_degree": {
                     "type": "integer",
@@ -137,8 +166,7 @@ impl Tool for GraphTool {
                 },
                 "graph_name": {
                     "type": "string",
-                    "default": "default",
-                    "description": "Graph name for sync operations"
+                    "description": "Named graph to work in; defaults to the session's active graph ('default' unless changed with /graph use). Edges only join nodes of the same graph"
                 },
                 "peer_instance_id": {
                     "type": "string",
@@ -153,6 +181,14 @@ impl Tool for GraphTool {
         })
     }
 
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let operation = args["operation"]
             .as_str()
@@ -162,8 +198,13 @@ impl Tool for GraphTool {
             .as_str()
             .context("session_id must be a string")?;
 
+        let graph_name = self
+            .store
+            .graph_name(session_id, args["graph_name"].as_str())
+            .await?;
+
         // Clone persistence for use in spawn_blocking
-        let persistence = Arc::clone(&self.persistence);
+        let persistence = self.store.persistence();
 
         match operation {
             "create_node" => {
@@ -175,15 +216,27 @@ impl Tool for GraphTool {
                     .context("label is required for create_node")?;
                 let properties = args["properties"].clone();
 
-                let node_type = NodeType::from_str(node_type);
+                let node_type = match self.store.types().node_type(node_type) {
+                    Ok(node_type) => node_type,
+                    Err(err) => return Ok(ToolResult::failure(err.to_string())),
+                };
+                let session = session_id.to_string();
                 let session_id = session_id.to_string();
                 let label = label.to_string();
 
                 let result = tokio::task::spawn_blocking(move || {
-                    persistence.insert_graph_node(&session_id, node_type, &label, &properties, None)
+                    persistence.insert_graph_node_in_graph(
+                        &session_id,
+                        &graph_name,
+                        node_type,
+                        &label,
+                        &properties,
+                        None,
+                    )
                 })
                 .await
                 .context("task join error")??;
+                self.store.embed_new_nodes(&session).await;
 
                 Ok(ToolResult::success(
                     json!({
@@ -280,7 +333,12 @@ impl Tool for GraphTool {
                 let session_id = session_id.to_string();
 
                 let result = tokio::task::spawn_blocking(move || {
-                    persistence.list_graph_nodes(&session_id, node_type, limit)
+                    persistence.list_graph_nodes_in_graph(
+                        &session_id,
+                        &graph_name,
+                        node_type,
+                        limit,
+                    )
                 })
                 .await
                 .context("task join error")??;
@@ -300,7 +358,12 @@ impl Tool for GraphTool {
                 let session_id = session_id.to_string();
 
                 let result = tokio::task::spawn_blocking(move || {
-                    persistence.list_graph_edges(&session_id, source_id, target_id)
+                    persistence.list_graph_edges_in_graph(
+                        &session_id,
+                        &graph_name,
+                        source_id,
+                        target_id,
+                    )
                 })
                 .await
                 .context("task join error")??;
@@ -314,6 +377,79 @@ impl Tool for GraphTool {
                 ))
             }
 
+            "search_nodes" => {
+                let query = args["query"]
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|query| !query.is_empty())
+                    .context("query is required for search_nodes")?;
+                let Some(client) = self.store.embeddings() else {
+                    return Ok(ToolResult::failure(
+                        "search_nodes needs an embeddings model (model.embeddings_model)",
+                    ));
+                };
+                let limit = args["limit"].as_u64().unwrap_or(10).clamp(1, 1000) as usize;
+                let query_embedding = client
+                    .embed(query)
+                    .await
+                    .context("embedding search_nodes query")?;
+                let hits = self
+                    .store
+                    .search_nodes_semantic(session_id, query_embedding, limit)
+                    .await?;
+                let nodes: Vec<Value> = hits
+                    .into_iter()
+                    .map(|(node, score)| {
+                        json!({
+                            "id": node.id,
+                            "node_type": node.node_type.as_str(),
+                            "label": node.label,
+                            "properties": node.properties,
+                            "score": score,
+                        })
+                    })
+                    .collect();
+
+                Ok(ToolResult::success(
+                    json!({
+                        "count": nodes.len(),
+                        "nodes": nodes
+                    })
+                    .to_string(),
+                ))
+            }
+
+            "subgraph" => {
+                let query = args["query"]
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|query| !query.is_empty())
+                    .context("query is required for subgraph")?;
+                let Some(client) = self.store.embeddings() else {
+                    return Ok(ToolResult::failure(
+                        "subgraph needs an embeddings model (model.embeddings_model)",
+                    ));
+                };
+                let seeds = args["limit"].as_u64().unwrap_or(5).clamp(1, 100) as usize;
+                let depth = args["depth"].as_u64().unwrap_or(1).clamp(1, 10) as usize;
+                let query_embedding = client
+                    .embed(query)
+                    .await
+                    .context("embedding subgraph query")?;
+                let subgraph = self
+                    .store
+                    .subgraph(session_id, query_embedding, seeds, depth)
+                    .await?;
+
+                let mut result = subgraph.to_json();
+                result["query"] = json!(query);
+                result["depth"] = json!(depth);
+                if args["mermaid"].as_bool().unwrap_or(false) {
+                    result["mermaid"] = json!(subgraph.to_mermaid(self.store.types()));
+                }
+                Ok(ToolResult::success(result.to_string()))
+            }
+
             "delete_node" => {
                 let node_id = args["node_id"]
                     .as_i64()
@@ -349,10 +485,61 @@ impl Tool for GraphTool {
                 })
                 .await
                 .context("task join error")??;
+                self.store.embed_new_nodes(session_id).await;
 
                 Ok(ToolResult::success(format!("Updated node {}", node_id)))
             }
 
+            "increment_counter" => {
+                let node_id = args["node_id"]
+                    .as_i64()
+                    .context("node_id is required for increment_counter")?;
+                let property = args["property"]
+                    .as_str()
+                    .context("property is required for increment_counter")?
+                    .to_string();
+                let amount = args["amount"].as_u64().unwrap_or(1);
+
+                let total = tokio::task::spawn_blocking(move || {
+                    persistence.graph_counter_increment(node_id, &property, amount)
+                })
+                .await
+                .context("task join error")??;
+
+                Ok(ToolResult::success(
+                    json!({ "node_id": node_id, "value": total }).to_string(),
+                ))
+            }
+
+            "add_to_set" | "remove_from_set" => {
+                let node_id = args["node_id"]
+                    .as_i64()
+                    .with_context(|| format!("node_id is required for {}", operation))?;
+                let property = args["property"]
+                    .as_str()
+                    .with_context(|| format!("property is required for {}", operation))?
+                    .to_string();
+                let value = args
+                    .get("value")
+                    .cloned()
+                    .with_context(|| format!("value is required for {}", operation))?;
+                let add = operation == "add_to_set";
+
+                let elements = tokio::task::spawn_blocking(move || {
+                    if add {
+                        persistence.graph_set_add(node_id, &property, &value)
+                    } else {
+                        persistence.graph_set_remove(node_id, &property, &value)
+                    }
+                })
+                .await
+                .context("task join error")??;
+
+                Ok(ToolResult::success(
+                    json!({ "node_id": node_id, "value": elements }).to_string(),
+                ))
+            }
+
             "node_degree" => {
                 let node_id = args["node_id"]
                     .as_i64()
@@ -361,7 +548,12 @@ impl Tool for GraphTool {
                 let session_id = session_id.to_string();
 
                 let (in_degree, out_degree, by_type) = tokio::task::spawn_blocking(move || {
-                    let edges = persistence.list_graph_edges(&session_id, None, None)?;
+                    let edges = persistence.list_graph_edges_in_graph(
+                        &session_id,
+                        &graph_name,
+                        None,
+                        None,
+                    )?;
                     let mut in_degree: i64 = 0;
                     let mut out_degree: i64 = 0;
                     let mut by_type: HashMap<String, (i64, i64)> = HashMap::new();
@@ -499,7 +691,12 @@ impl Tool for GraphTool {
                 let session_id = session_id.to_string();
 
                 let hubs = tokio::task::spawn_blocking(move || {
-                    let edges = persistence.list_graph_edges(&session_id, None, None)?;
+                    let edges = persistence.list_graph_edges_in_graph(
+                        &session_id,
+                        &graph_name,
+                        None,
+                        None,
+                    )?;
                     let mut degrees: HashMap<i64, (i64, i64)> = HashMap::new();
 
                     for edge in edges {
@@ -597,8 +794,6 @@ impl Tool for GraphTool {
             }
 
             "enable_sync" => {
-                let graph_name = args["graph_name"].as_str().unwrap_or("default");
-                let graph_name = graph_name.to_string();
                 let graph_name_display = graph_name.clone();
                 let session_id = session_id.to_string();
 
@@ -619,8 +814,6 @@ impl Tool for GraphTool {
             }
 
             "disable_sync" => {
-                let graph_name = args["graph_name"].as_str().unwrap_or("default");
-                let graph_name = graph_name.to_string();
                 let graph_name_display = graph_name.clone();
                 let session_id = session_id.to_string();
 
@@ -641,8 +834,6 @@ impl Tool for GraphTool {
             }
 
             "sync_status" => {
-                let graph_name = args["graph_name"].as_str().unwrap_or("default");
-                let graph_name = graph_name.to_string();
                 let graph_name_display = graph_name.clone();
                 let session_id = session_id.to_string();
                 let instance_id = persistence.instance_id().to_string();
@@ -678,12 +869,10 @@ impl Tool for GraphTool {
 
             #[cfg(feature = "api")]
             "force_sync" => {
-                let graph_name = args["graph_name"].as_str().unwrap_or("default");
                 let peer_instance_id = args["peer_instance_id"]
                     .as_str()
                     .context("peer_instance_id is required for force_sync")?;
 
-                let graph_name = graph_name.to_string();
                 let graph_name_display = graph_name.clone();
                 let session_id = session_id.to_string();
                 let peer_instance_id = peer_instance_id.to_string();
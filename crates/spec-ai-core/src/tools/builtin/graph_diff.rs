@@ -0,0 +1,380 @@
+//! Comparing two knowledge graph snapshots
+//!
+//! A [`GraphSnapshot`] names a session's graph, optionally as it stood at an
+//! earlier moment. Diffing two of them shows what an agent learned during a
+//! run, or how two bootstrap snapshots differ after a refactor.
+//!
+//! Nodes of the same session are matched by id. Across sessions they are
+//! matched by type, label and name, and edges by their endpoints, type and
+//! predicate. Deleted rows are not kept, so a snapshot taken in the past
+//! cannot show nodes that have since been removed.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use super::graph_store::node_summary;
+use crate::types::{GraphEdge, GraphNode};
+
+/// A session's graph, now or as of a point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphSnapshot {
+    pub session_id: String,
+    /// Only rows created at or before this moment; `None` for the current graph
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+impl GraphSnapshot {
+    pub fn current(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            as_of: None,
+        }
+    }
+
+    pub fn as_of(session_id: impl Into<String>, as_of: DateTime<Utc>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            as_of: Some(as_of),
+        }
+    }
+
+    /// Whether a row created at `created_at` is part of the snapshot
+    pub fn contains(&self, created_at: DateTime<Utc>) -> bool {
+        self.as_of.is_none_or(|as_of| created_at <= as_of)
+    }
+}
+
+impl fmt::Display for GraphSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_of {
+            Some(as_of) => write!(f, "{}@{}", self.session_id, as_of.to_rfc3339()),
+            None => f.write_str(&self.session_id),
+        }
+    }
+}
+
+/// Edge together with summaries of the nodes it joins
+#[derive(Debug, Clone)]
+pub struct DiffEdge {
+    pub edge: GraphEdge,
+    pub source: String,
+    pub target: String,
+}
+
+/// The same node or edge in both snapshots, with different contents
+#[derive(Debug, Clone)]
+pub struct Changed<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// Nodes and edges that differ between two snapshots
+#[derive(Debug, Clone, Default)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<GraphNode>,
+    pub removed_nodes: Vec<GraphNode>,
+    pub changed_nodes: Vec<Changed<GraphNode>>,
+    pub added_edges: Vec<DiffEdge>,
+    pub removed_edges: Vec<DiffEdge>,
+    pub changed_edges: Vec<Changed<DiffEdge>>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.changed_edges.is_empty()
+    }
+}
+
+/// Rows of one snapshot
+pub(crate) struct SnapshotRows {
+    pub snapshot: GraphSnapshot,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl SnapshotRows {
+    /// Keep the rows that existed at the snapshot's moment
+    pub fn new(snapshot: GraphSnapshot, nodes: Vec<GraphNode>, edges: Vec<GraphEdge>) -> Self {
+        let nodes: Vec<GraphNode> = nodes
+            .into_iter()
+            .filter(|node| snapshot.contains(node.created_at))
+            .collect();
+        let ids: HashSet<i64> = nodes.iter().map(|node| node.id).collect();
+        let edges = edges
+            .into_iter()
+            .filter(|edge| {
+                snapshot.contains(edge.created_at)
+                    && ids.contains(&edge.source_id)
+                    && ids.contains(&edge.target_id)
+            })
+            .collect();
+        Self {
+            snapshot,
+            nodes,
+            edges,
+        }
+    }
+}
+
+/// Compare the `from` snapshot against the later `to` snapshot
+pub(crate) fn diff_snapshots(from: SnapshotRows, to: SnapshotRows) -> GraphDiff {
+    let same_session = from.snapshot.session_id == to.snapshot.session_id;
+    let node_key = |node: &GraphNode| {
+        if same_session {
+            node.id.to_string()
+        } else {
+            format!(
+                "{}|{}|{}",
+                node.node_type.as_str(),
+                node.label.to_lowercase(),
+                node_summary(node).to_lowercase()
+            )
+        }
+    };
+    // A row updated between the two moments looks identical in both
+    // snapshots, so its timestamp is the only sign of the change
+    let updated_between = |before: &GraphNode, after: &GraphNode| {
+        before.id == after.id
+            && from
+                .snapshot
+                .as_of
+                .is_some_and(|as_of| after.updated_at > as_of)
+            && to.snapshot.contains(after.updated_at)
+    };
+
+    let mut diff = GraphDiff::default();
+    let from_keys: HashMap<i64, String> = from
+        .nodes
+        .iter()
+        .map(|node| (node.id, node_key(node)))
+        .collect();
+    let to_keys: HashMap<i64, String> = to
+        .nodes
+        .iter()
+        .map(|node| (node.id, node_key(node)))
+        .collect();
+
+    let from_nodes = from
+        .nodes
+        .iter()
+        .map(|node| (from_keys[&node.id].clone(), node))
+        .collect();
+    let to_nodes = to
+        .nodes
+        .iter()
+        .map(|node| (to_keys[&node.id].clone(), node))
+        .collect();
+    for pair in pair_by_key(from_nodes, to_nodes) {
+        match pair {
+            (Some(before), None) => diff.removed_nodes.push(before.clone()),
+            (None, Some(after)) => diff.added_nodes.push(after.clone()),
+            (Some(before), Some(after)) => {
+                if before.label != after.label
+                    || before.properties != after.properties
+                    || updated_between(before, after)
+                {
+                    diff.changed_nodes.push(Changed {
+                        before: before.clone(),
+                        after: after.clone(),
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let from_edges = diff_edges(&from, &from_keys);
+    let to_edges = diff_edges(&to, &to_keys);
+    for pair in pair_by_key(
+        from_edges
+            .iter()
+            .map(|(key, edge)| (key.clone(), edge))
+            .collect(),
+        to_edges
+            .iter()
+            .map(|(key, edge)| (key.clone(), edge))
+            .collect(),
+    ) {
+        match pair {
+            (Some(before), None) => diff.removed_edges.push(before.clone()),
+            (None, Some(after)) => diff.added_edges.push(after.clone()),
+            (Some(before), Some(after)) => {
+                if before.edge.weight != after.edge.weight
+                    || before.edge.properties != after.edge.properties
+                {
+                    diff.changed_edges.push(Changed {
+                        before: before.clone(),
+                        after: after.clone(),
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    diff
+}
+
+/// Edges of a snapshot keyed by their endpoints' keys, type and predicate
+fn diff_edges(rows: &SnapshotRows, node_keys: &HashMap<i64, String>) -> Vec<(String, DiffEdge)> {
+    let nodes: HashMap<i64, &GraphNode> = rows.nodes.iter().map(|node| (node.id, node)).collect();
+    rows.edges
+        .iter()
+        .map(|edge| {
+            let key = format!(
+                "{}|{}|{}|{}",
+                node_keys[&edge.source_id],
+                edge.edge_type.as_str(),
+                edge.predicate.as_deref().unwrap_or(""),
+                node_keys[&edge.target_id]
+            );
+            let edge = DiffEdge {
+                edge: edge.clone(),
+                source: node_summary(nodes[&edge.source_id]),
+                target: node_summary(nodes[&edge.target_id]),
+            };
+            (key, edge)
+        })
+        .collect()
+}
+
+/// Pair items of two collections that share a key, in key order. Items
+/// whose key repeats are paired in the order given; leftovers stay unpaired.
+fn pair_by_key<'a, T>(
+    from: Vec<(String, &'a T)>,
+    to: Vec<(String, &'a T)>,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+    let mut groups: BTreeMap<String, (Vec<&'a T>, Vec<&'a T>)> = BTreeMap::new();
+    for (key, item) in from {
+        groups.entry(key).or_default().0.push(item);
+    }
+    for (key, item) in to {
+        groups.entry(key).or_default().1.push(item);
+    }
+
+    let mut pairs = Vec::new();
+    for (before, after) in groups.into_values() {
+        let len = before.len().max(after.len());
+        for index in 0..len {
+            pairs.push((before.get(index).copied(), after.get(index).copied()));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EdgeType, NodeType};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn node(id: i64, session: &str, name: &str, created: u32, updated: u32) -> GraphNode {
+        GraphNode {
+            id,
+            session_id: session.to_string(),
+            node_type: NodeType::Entity,
+            label: "Service".to_string(),
+            properties: json!({ "name": name }),
+            embedding_id: None,
+            created_at: at(created),
+            updated_at: at(updated),
+        }
+    }
+
+    fn edge(id: i64, session: &str, source: i64, target: i64, created: u32) -> GraphEdge {
+        GraphEdge {
+            id,
+            session_id: session.to_string(),
+            source_id: source,
+            target_id: target,
+            edge_type: EdgeType::DependsOn,
+            predicate: None,
+            properties: None,
+            weight: 1.0,
+            temporal_start: None,
+            temporal_end: None,
+            created_at: at(created),
+        }
+    }
+
+    #[test]
+    fn sessions_are_matched_by_name() {
+        let before = SnapshotRows::new(
+            GraphSnapshot::current("run-1"),
+            vec![node(1, "run-1", "api", 1, 1), node(2, "run-1", "db", 1, 1)],
+            vec![edge(1, "run-1", 1, 2, 1)],
+        );
+        let mut cache = node(13, "run-2", "Cache", 2, 2);
+        cache.properties = json!({ "name": "cache" });
+        let mut db = node(12, "run-2", "db", 2, 2);
+        db.properties = json!({ "name": "db", "engine": "duckdb" });
+        let after = SnapshotRows::new(
+            GraphSnapshot::current("run-2"),
+            vec![node(11, "run-2", "API", 2, 2), db, cache],
+            vec![edge(21, "run-2", 11, 13, 2)],
+        );
+
+        let diff = diff_snapshots(before, after);
+        assert_eq!(diff.added_nodes.len(), 1);
+        assert_eq!(diff.added_nodes[0].id, 13);
+        assert!(diff.removed_nodes.is_empty());
+        assert_eq!(
+            diff.changed_nodes.len(),
+            2,
+            "api renamed, db gained a property"
+        );
+        assert_eq!(diff.removed_edges[0].target, "db");
+        assert_eq!(diff.added_edges[0].target, "cache");
+        assert!(diff.changed_edges.is_empty());
+    }
+
+    #[test]
+    fn one_session_at_two_moments() {
+        let nodes = vec![
+            node(1, "s", "api", 1, 1),
+            node(2, "s", "db", 1, 5),
+            node(3, "s", "cache", 6, 6),
+        ];
+        let edges = vec![edge(1, "s", 1, 2, 1), edge(2, "s", 1, 3, 6)];
+        let diff = diff_snapshots(
+            SnapshotRows::new(
+                GraphSnapshot::as_of("s", at(3)),
+                nodes.clone(),
+                edges.clone(),
+            ),
+            SnapshotRows::new(GraphSnapshot::current("s"), nodes, edges),
+        );
+
+        assert_eq!(
+            diff.added_nodes.iter().map(|n| n.id).collect::<Vec<_>>(),
+            [3]
+        );
+        assert_eq!(diff.changed_nodes.len(), 1);
+        assert_eq!(diff.changed_nodes[0].after.id, 2);
+        assert_eq!(diff.added_edges.len(), 1);
+        assert!(diff.removed_nodes.is_empty() && diff.removed_edges.is_empty());
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_diff() {
+        let rows = || {
+            SnapshotRows::new(
+                GraphSnapshot::current("s"),
+                vec![node(1, "s", "api", 1, 1)],
+                vec![],
+            )
+        };
+        assert!(diff_snapshots(rows(), rows()).is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+//! Shared backend for the knowledge graph tools
+//!
+//! The generic `graph` tool and the focused entity/fact tools all go through
+//! [`KnowledgeGraphStore`], which moves persistence calls onto the blocking
+//! pool and adds the name-based entity lookups that the narrow tools need.
+//! With an embeddings client, nodes are embedded from their label and
+//! properties as they are added, so they can be found by meaning with
+//! [`KnowledgeGraphStore::search_nodes_semantic`]. The store also carries the
+//! `[graph]` node and edge types the tools accept and show.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::graph_diff::{diff_snapshots, GraphDiff, GraphSnapshot, SnapshotRows};
+use super::graph_subgraph::{Subgraph, MAX_SUBGRAPH_NODES};
+use crate::config::GraphConfig;
+use crate::embeddings::EmbeddingsClient;
+use crate::persistence::Persistence;
+use crate::types::{GraphEdge, GraphNode, NodeType, TraversalDirection};
+
+/// Policy resource shared by every knowledge graph tool, so a single
+/// `tool_call` rule on `graph` covers all of them
+pub const GRAPH_POLICY_RESOURCE: &str = "graph";
+
+/// Entity nodes scanned when resolving a name
+const ENTITY_SCAN_LIMIT: i64 = 1000;
+
+/// Nodes loaded per snapshot when diffing graphs
+const SNAPSHOT_NODE_LIMIT: i64 = 100_000;
+
+/// Nodes embedded per request
+const EMBED_BATCH_SIZE: usize = 64;
+
+/// Edge touching a node, paired with the node on the other end
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub edge: GraphEdge,
+    pub node: GraphNode,
+    /// `true` when the edge points away from the node being inspected
+    pub outgoing: bool,
+}
+
+#[derive(Clone)]
+pub struct KnowledgeGraphStore {
+    persistence: Arc<Persistence>,
+    embeddings: Option<EmbeddingsClient>,
+    types: Arc<GraphConfig>,
+}
+
+impl KnowledgeGraphStore {
+    pub fn new(persistence: Arc<Persistence>) -> Self {
+        Self {
+            persistence,
+            embeddings: None,
+            types: Arc::new(GraphConfig::default()),
+        }
+    }
+
+    /// Embed nodes as they are added, and allow searching them by text
+    pub fn with_embeddings(mut self, embeddings: Option<EmbeddingsClient>) -> Self {
+        self.embeddings = embeddings;
+        self
+    }
+
+    pub fn embeddings(&self) -> Option<&EmbeddingsClient> {
+        self.embeddings.as_ref()
+    }
+
+    /// Accept and show the node and edge types declared in `[graph]`
+    pub fn with_types(mut self, types: GraphConfig) -> Self {
+        self.types = Arc::new(types);
+        self
+    }
+
+    pub fn types(&self) -> &GraphConfig {
+        &self.types
+    }
+
+    pub fn persistence(&self) -> Arc<Persistence> {
+        Arc::clone(&self.persistence)
+    }
+
+    /// Run a persistence call on the blocking thread pool
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Persistence) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let persistence = self.persistence();
+        tokio::task::spawn_blocking(move || f(&persistence))
+            .await
+            .context("task join error")?
+    }
+
+    /// The named graph a call should work in: `requested` when given,
+    /// otherwise the graph chosen for the session with `/graph use`
+    pub async fn graph_name(&self, session_id: &str, requested: Option<&str>) -> Result<String> {
+        if let Some(name) = requested.map(str::trim).filter(|name| !name.is_empty()) {
+            return Ok(name.to_string());
+        }
+        let session_id = session_id.to_string();
+        self.run(move |persistence| persistence.graph_get_active(&session_id))
+            .await
+    }
+
+    /// Entity nodes of a graph whose name matches `name` case-insensitively,
+    /// newest first
+    pub async fn find_entities(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+        name: &str,
+    ) -> Result<Vec<GraphNode>> {
+        let session_id = session_id.to_string();
+        let graph_name = graph_name.to_string();
+        let wanted = name.trim().to_lowercase();
+        self.run(move |persistence| {
+            let nodes = persistence.list_graph_nodes_in_graph(
+                &session_id,
+                &graph_name,
+                Some(NodeType::Entity),
+                Some(ENTITY_SCAN_LIMIT),
+            )?;
+            Ok(nodes
+                .into_iter()
+                .filter(|node| entity_name(node).to_lowercase() == wanted)
+                .collect())
+        })
+        .await
+    }
+
+    /// Id of the newest entity called `name`, creating it when none exists.
+    /// The flag reports whether a node was created.
+    pub async fn find_or_create_entity(
+        &self,
+        session_id: &str,
+        graph_name: &str,
+        name: &str,
+        entity_type: Option<&str>,
+    ) -> Result<(i64, bool)> {
+        if let Some(existing) = self
+            .find_entities(session_id, graph_name, name)
+            .await?
+            .first()
+        {
+            return Ok((existing.id, false));
+        }
+
+        let session = session_id.to_string();
+        let graph_name = graph_name.to_string();
+        let name = name.trim().to_string();
+        let label = entity_type
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .unwrap_or("Entity")
+            .to_string();
+        let id = self
+            .run(move |persistence| {
+                let properties = json!({
+                    "name": name,
+                    "type": label,
+                    "extracted_from": "tool",
+                });
+                persistence.insert_graph_node_in_graph(
+                    &session,
+                    &graph_name,
+                    NodeType::Entity,
+                    &label,
+                    &properties,
+                    None,
+                )
+            })
+            .await?;
+        self.embed_new_nodes(session_id).await;
+        Ok((id, true))
+    }
+
+    /// Every edge touching `node_id` together with the node on its other end.
+    /// Edges never cross graphs, so these all share the node's graph.
+    pub async fn connections(&self, session_id: &str, node_id: i64) -> Result<Vec<Connection>> {
+        let session_id = session_id.to_string();
+        self.run(move |persistence| {
+            let outgoing = persistence.list_graph_edges(&session_id, Some(node_id), None)?;
+            let incoming = persistence.list_graph_edges(&session_id, None, Some(node_id))?;
+
+            let mut connections = Vec::with_capacity(outgoing.len() + incoming.len());
+            for (edge, is_outgoing) in outgoing
+                .into_iter()
+                .map(|edge| (edge, true))
+                .chain(incoming.into_iter().map(|edge| (edge, false)))
+            {
+                let other = if is_outgoing {
+                    edge.target_id
+                } else {
+                    edge.source_id
+                };
+                if let Some(node) = persistence.get_graph_node(other)? {
+                    connections.push(Connection {
+                        edge,
+                        node,
+                        outgoing: is_outgoing,
+                    });
+                }
+            }
+            Ok(connections)
+        })
+        .await
+    }
+
+    /// Embed the session's nodes that have no embedding yet, including
+    /// nodes added outside the store; returns how many were embedded.
+    /// Does nothing without an embeddings client.
+    pub async fn embed_pending_nodes(&self, session_id: &str) -> Result<usize> {
+        let Some(client) = self.embeddings.clone() else {
+            return Ok(0);
+        };
+        let mut embedded = 0;
+        loop {
+            let session = session_id.to_string();
+            let nodes = self
+                .run(move |persistence| {
+                    persistence.graph_nodes_without_embedding(&session, EMBED_BATCH_SIZE)
+                })
+                .await?;
+            if nodes.is_empty() {
+                return Ok(embedded);
+            }
+            let texts: Vec<String> = nodes.iter().map(GraphNode::embedding_text).collect();
+            let embeddings = client
+                .embed_batch(&texts)
+                .await
+                .context("embedding graph nodes")?;
+            if embeddings.len() != nodes.len() || embeddings.iter().any(Vec::is_empty) {
+                anyhow::bail!(
+                    "expected {} node embeddings, got {} usable",
+                    nodes.len(),
+                    embeddings.iter().filter(|e| !e.is_empty()).count()
+                );
+            }
+            let count = nodes.len();
+            self.run(move |persistence| {
+                for (node, embedding) in nodes.iter().zip(&embeddings) {
+                    persistence.insert_graph_node_embedding(
+                        node.id,
+                        &node.session_id,
+                        embedding,
+                    )?;
+                }
+                Ok(())
+            })
+            .await?;
+            embedded += count;
+            if count < EMBED_BATCH_SIZE {
+                return Ok(embedded);
+            }
+        }
+    }
+
+    /// Embed newly added nodes, logging rather than failing the write that
+    /// added them; unembedded nodes are picked up by the next search
+    pub async fn embed_new_nodes(&self, session_id: &str) {
+        if let Err(err) = self.embed_pending_nodes(session_id).await {
+            warn!("Failed to embed graph nodes of {}: {:#}", session_id, err);
+        }
+    }
+
+    /// The `k` nodes of the session most similar to `query_embedding`, with
+    /// their similarity, best first. Nodes still without an embedding are
+    /// embedded first when the store has an embeddings client.
+    pub async fn search_nodes_semantic(
+        &self,
+        session_id: &str,
+        query_embedding: Vec<f32>,
+        k: usize,
+    ) -> Result<Vec<(GraphNode, f32)>> {
+        self.embed_new_nodes(session_id).await;
+        let session_id = session_id.to_string();
+        self.run(move |persistence| {
+            persistence.search_graph_nodes_semantic(&session_id, &query_embedding, k)
+        })
+        .await
+    }
+
+    /// The `seeds` nodes most similar to `query_embedding` and every node
+    /// within `depth` hops of them, with the edges among those nodes. Only
+    /// nodes with a positive similarity become seeds.
+    pub async fn subgraph(
+        &self,
+        session_id: &str,
+        query_embedding: Vec<f32>,
+        seeds: usize,
+        depth: usize,
+    ) -> Result<Subgraph> {
+        let hits = self
+            .search_nodes_semantic(session_id, query_embedding, seeds)
+            .await?;
+        let session_id = session_id.to_string();
+        self.run(move |persistence| {
+            let mut subgraph = Subgraph::default();
+            let mut included = HashSet::new();
+            for (node, score) in hits.into_iter().filter(|(_, score)| *score > 0.0) {
+                subgraph.seeds.push((node.id, score));
+                included.insert(node.id);
+                subgraph.nodes.push(node);
+            }
+            let seed_ids: Vec<i64> = subgraph.seeds.iter().map(|(id, _)| *id).collect();
+            'expand: for seed in seed_ids {
+                let neighbors = persistence.traverse_neighbors_with_edges(
+                    &session_id,
+                    seed,
+                    TraversalDirection::Both,
+                    depth,
+                )?;
+                for neighbor in neighbors {
+                    if included.contains(&neighbor.node.id) {
+                        continue;
+                    }
+                    if included.len() >= MAX_SUBGRAPH_NODES {
+                        subgraph.truncated = true;
+                        break 'expand;
+                    }
+                    included.insert(neighbor.node.id);
+                    subgraph.nodes.push(neighbor.node);
+                }
+            }
+            if !included.is_empty() {
+                subgraph.edges = persistence
+                    .list_graph_edges(&session_id, None, None)?
+                    .into_iter()
+                    .filter(|edge| {
+                        included.contains(&edge.source_id) && included.contains(&edge.target_id)
+                    })
+                    .collect();
+            }
+            Ok(subgraph)
+        })
+        .await
+    }
+
+    /// Nodes and edges added, removed or changed between two snapshots,
+    /// e.g. two sessions or one session at two moments
+    pub async fn diff(&self, from: GraphSnapshot, to: GraphSnapshot) -> Result<GraphDiff> {
+        self.run(move |persistence| {
+            let load = |snapshot: GraphSnapshot| -> Result<SnapshotRows> {
+                let nodes = persistence.list_graph_nodes(
+                    &snapshot.session_id,
+                    None,
+                    Some(SNAPSHOT_NODE_LIMIT),
+                )?;
+                let edges = persistence.list_graph_edges(&snapshot.session_id, None, None)?;
+                Ok(SnapshotRows::new(snapshot, nodes, edges))
+            };
+            Ok(diff_snapshots(load(from)?, load(to)?))
+        })
+        .await
+    }
+}
+
+/// Display name of an entity node, falling back to its label
+pub fn entity_name(node: &GraphNode) -> &str {
+    node.properties
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(&node.label)
+}
+
+/// One-line summary of any node for tool output
+pub fn node_summary(node: &GraphNode) -> String {
+    ["text", "name", "content", "description"]
+        .iter()
+        .find_map(|key| node.properties.get(*key).and_then(Value::as_str))
+        .unwrap_or(&node.label)
+        .to_string()
+}
@@ -0,0 +1,271 @@
+//! Extracting the part of a knowledge graph that is about a topic
+//!
+//! A [`Subgraph`] starts from the nodes closest in meaning to a query and
+//! takes in everything within a few hops of them, so "everything we know
+//! about billing" can be pulled into a prompt or a document. It serializes
+//! to compact JSON and renders as a Mermaid flowchart, with node and edge
+//! types named and colored as configured in `[graph]`.
+
+use serde_json::{json, Value};
+
+use super::graph_store::node_summary;
+use crate::config::GraphConfig;
+use crate::types::{GraphEdge, GraphNode};
+
+/// Nodes a subgraph stops growing at
+pub const MAX_SUBGRAPH_NODES: usize = 200;
+
+/// Characters of a node summary shown in a Mermaid box
+const MERMAID_LABEL_CHARS: usize = 60;
+
+/// Seed nodes matching a query and their neighborhoods
+#[derive(Debug, Clone, Default)]
+pub struct Subgraph {
+    /// Ids of the nodes that matched the query, with their similarity,
+    /// best first
+    pub seeds: Vec<(i64, f32)>,
+    /// Seeds first, then the nodes reached from them
+    pub nodes: Vec<GraphNode>,
+    /// Edges between any two of the nodes
+    pub edges: Vec<GraphEdge>,
+    /// Whether expansion stopped at [`MAX_SUBGRAPH_NODES`]
+    pub truncated: bool,
+}
+
+impl Subgraph {
+    /// Similarity of a seed node to the query; `None` for reached nodes
+    pub fn seed_score(&self, node_id: i64) -> Option<f32> {
+        self.seeds
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, score)| *score)
+    }
+
+    /// Nodes and edges without timestamps or bookkeeping columns
+    pub fn to_json(&self) -> Value {
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut value = json!({
+                    "id": node.id,
+                    "node_type": node.node_type.as_str(),
+                    "label": node.label,
+                    "properties": node.properties,
+                });
+                if let Some(score) = self.seed_score(node.id) {
+                    value["score"] = json!(score);
+                }
+                value
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let mut value = json!({
+                    "source_id": edge.source_id,
+                    "target_id": edge.target_id,
+                    "edge_type": edge.edge_type.as_str(),
+                });
+                if let Some(predicate) = &edge.predicate {
+                    value["predicate"] = json!(predicate);
+                }
+                value
+            })
+            .collect();
+        json!({
+            "seeds": self.seeds.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            "nodes": nodes,
+            "edges": edges,
+            "truncated": self.truncated,
+        })
+    }
+
+    /// Mermaid flowchart of the subgraph, seeds drawn with a heavier border
+    /// and types filled with their configured colors
+    pub fn to_mermaid(&self, types: &GraphConfig) -> String {
+        let mut out = String::from("flowchart LR\n");
+        // Colored node types, in order of first appearance, with their nodes
+        let mut colored: Vec<(&str, &str, Vec<String>)> = Vec::new();
+        for node in &self.nodes {
+            let class = if self.seed_score(node.id).is_some() {
+                ":::seed"
+            } else {
+                ""
+            };
+            if let Some(color) = types
+                .node_style(&node.node_type)
+                .and_then(|style| style.color.as_deref())
+            {
+                let name = node.node_type.as_str();
+                match colored.iter_mut().find(|(n, _, _)| *n == name) {
+                    Some((_, _, ids)) => ids.push(format!("n{}", node.id)),
+                    None => colored.push((name, color, vec![format!("n{}", node.id)])),
+                }
+            }
+            let text = format!(
+                "{}: {}",
+                types.node_label(&node.node_type),
+                node_summary(node)
+            );
+            out.push_str(&format!(
+                "    n{}[\"{}\"]{}\n",
+                node.id,
+                mermaid_text(&text, MERMAID_LABEL_CHARS),
+                class
+            ));
+        }
+        let mut edge_colors = Vec::new();
+        for (index, edge) in self.edges.iter().enumerate() {
+            let label = edge
+                .predicate
+                .clone()
+                .unwrap_or_else(|| types.edge_label(&edge.edge_type));
+            out.push_str(&format!(
+                "    n{} -->|\"{}\"| n{}\n",
+                edge.source_id,
+                mermaid_text(&label, MERMAID_LABEL_CHARS),
+                edge.target_id
+            ));
+            if let Some(color) = types
+                .edge_style(&edge.edge_type)
+                .and_then(|style| style.color.as_deref())
+            {
+                edge_colors.push((index, color));
+            }
+        }
+        for (name, color, ids) in &colored {
+            out.push_str(&format!("    classDef type_{} fill:{}\n", name, color));
+            out.push_str(&format!("    class {} type_{}\n", ids.join(","), name));
+        }
+        for (index, color) in edge_colors {
+            out.push_str(&format!("    linkStyle {} stroke:{}\n", index, color));
+        }
+        if !self.seeds.is_empty() {
+            out.push_str("    classDef seed stroke-width:3px\n");
+        }
+        out
+    }
+}
+
+/// `text` on one line, cut to `max_chars`, with the characters that end a
+/// Mermaid label escaped
+fn mermaid_text(text: &str, max_chars: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut cut: String = flat.chars().take(max_chars).collect();
+    if flat.chars().count() > max_chars {
+        cut.push('…');
+    }
+    cut.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GraphTypeStyle;
+    use crate::types::{EdgeType, NodeType};
+    use chrono::Utc;
+
+    fn node(id: i64, node_type: NodeType, properties: Value) -> GraphNode {
+        GraphNode {
+            id,
+            session_id: "s".to_string(),
+            node_type,
+            label: "Label".to_string(),
+            properties,
+            embedding_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn edge(source_id: i64, target_id: i64, predicate: Option<&str>) -> GraphEdge {
+        GraphEdge {
+            id: source_id * 100 + target_id,
+            session_id: "s".to_string(),
+            source_id,
+            target_id,
+            edge_type: EdgeType::RelatesTo,
+            predicate: predicate.map(str::to_string),
+            properties: None,
+            weight: 1.0,
+            temporal_start: None,
+            temporal_end: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn billing() -> Subgraph {
+        Subgraph {
+            seeds: vec![(1, 0.9)],
+            nodes: vec![
+                node(1, NodeType::Entity, json!({"name": "billing"})),
+                node(
+                    2,
+                    NodeType::Fact,
+                    json!({"text": "invoices go out \"monthly\""}),
+                ),
+            ],
+            edges: vec![edge(1, 2, None), edge(2, 1, Some("describes"))],
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn json_marks_seeds_and_drops_bookkeeping() {
+        let value = billing().to_json();
+        assert_eq!(value["seeds"], json!([1]));
+        assert!((value["nodes"][0]["score"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert!(value["nodes"][1].get("score").is_none());
+        assert!(value["nodes"][0].get("created_at").is_none());
+        assert_eq!(value["edges"][0]["edge_type"], "RELATES_TO");
+        assert_eq!(value["edges"][1]["predicate"], "describes");
+    }
+
+    #[test]
+    fn mermaid_draws_nodes_and_edges() {
+        let mermaid = billing().to_mermaid(&GraphConfig::default());
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("    n1[\"entity: billing\"]:::seed\n"));
+        assert!(mermaid.contains("n2[\"fact: invoices go out #quot;monthly#quot;\"]\n"));
+        assert!(mermaid.contains("    n1 -->|\"RELATES_TO\"| n2\n"));
+        assert!(mermaid.contains("    n2 -->|\"describes\"| n1\n"));
+        assert!(mermaid.ends_with("classDef seed stroke-width:3px\n"));
+        assert_eq!(mermaid_text("a  b\nc", 3), "a b…");
+    }
+
+    #[test]
+    fn mermaid_names_and_colors_configured_types() {
+        let mut subgraph = billing();
+        subgraph.nodes.push(node(
+            3,
+            NodeType::Other("incident".to_string()),
+            json!({"name": "outage"}),
+        ));
+        let mut types = GraphConfig::default();
+        types.node_types.insert(
+            "incident".to_string(),
+            GraphTypeStyle {
+                label: Some("Incident".to_string()),
+                color: Some("#d62728".to_string()),
+            },
+        );
+        types.edge_types.insert(
+            "RELATES_TO".to_string(),
+            GraphTypeStyle {
+                label: Some("related".to_string()),
+                color: Some("#888888".to_string()),
+            },
+        );
+        let mermaid = subgraph.to_mermaid(&types);
+        assert!(mermaid.contains("    n3[\"Incident: outage\"]\n"));
+        assert!(mermaid.contains("    n1[\"entity: billing\"]:::seed\n"));
+        assert!(mermaid.contains("    n1 -->|\"related\"| n2\n"));
+        assert!(mermaid.contains("    n2 -->|\"describes\"| n1\n"));
+        assert!(mermaid.contains("    classDef type_incident fill:#d62728\n"));
+        assert!(mermaid.contains("    class n3 type_incident\n"));
+        assert!(mermaid.contains("    linkStyle 1 stroke:#888888\n"));
+        assert!(mermaid.ends_with("classDef seed stroke-width:3px\n"));
+    }
+}
@@ -0,0 +1,492 @@
+//! Focused knowledge graph tools
+//!
+//! Models handle a handful of small, single-purpose schemas much better than
+//! the generic `graph` tool with its twenty operations. These tools cover
+//! the common cases (recording facts, looking up entities, reading their
+//! history, linking them) on top of the same [`KnowledgeGraphStore`] and
+//! share the `graph` policy resource.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+
+use super::graph_store::{
+    entity_name, node_summary, Connection, KnowledgeGraphStore, GRAPH_POLICY_RESOURCE,
+};
+use crate::tools::{Tool, ToolResult};
+use crate::types::{EdgeType, NodeType};
+
+const DEFAULT_LIMIT: usize = 20;
+
+fn session_id(session_id: &Option<String>) -> Result<&str> {
+    session_id
+        .as_deref()
+        .filter(|id| !id.is_empty())
+        .context("session_id is required")
+}
+
+fn parse_args<T: ToolArgs>(tool: &str, args: Value) -> Result<T> {
+    T::from_args(args).with_context(|| format!("Failed to parse {} arguments", tool))
+}
+
+fn relation_name(connection: &Connection) -> String {
+    connection
+        .edge
+        .predicate
+        .clone()
+        .unwrap_or_else(|| connection.edge.edge_type.as_str().to_lowercase())
+}
+
+/// Records a fact about an entity as a `Fact` node linked to the entity
+pub struct RememberFactTool {
+    store: KnowledgeGraphStore,
+}
+
+impl RememberFactTool {
+    pub fn new(store: KnowledgeGraphStore) -> Self {
+        Self { store }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct RememberFactArgs {
+    /// Entity the fact is about; created if it does not exist yet
+    entity: String,
+    /// The fact, stated as a complete sentence
+    fact: String,
+    /// Kind of entity when it is new, e.g. Person, Project, Service
+    entity_type: Option<String>,
+    /// Where the fact came from (file, URL, user)
+    source: Option<String>,
+    /// Named graph to use; defaults to the session's active graph
+    graph: Option<String>,
+    /// Session ID for graph isolation (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for RememberFactTool {
+    fn name(&self) -> &str {
+        "remember_fact"
+    }
+
+    fn description(&self) -> &str {
+        "Store a fact about a named entity in the knowledge graph so it can be recalled later"
+    }
+
+    fn parameters(&self) -> Value {
+        RememberFactArgs::schema()
+    }
+
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: RememberFactArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?.to_string();
+        if args.entity.trim().is_empty() || args.fact.trim().is_empty() {
+            return Ok(ToolResult::failure("entity and fact must not be empty"));
+        }
+
+        let graph_name = self
+            .store
+            .graph_name(&session_id, args.graph.as_deref())
+            .await?;
+        let (entity_id, entity_created) = self
+            .store
+            .find_or_create_entity(
+                &session_id,
+                &graph_name,
+                &args.entity,
+                args.entity_type.as_deref(),
+            )
+            .await?;
+
+        let properties = json!({
+            "text": args.fact.trim(),
+            "entity": args.entity.trim(),
+            "source": args.source,
+            "recorded_at": Utc::now().to_rfc3339(),
+        });
+        let session = session_id.clone();
+        let fact_id = self
+            .store
+            .run(move |persistence| {
+                let fact_id = persistence.insert_graph_node_in_graph(
+                    &session,
+                    &graph_name,
+                    NodeType::Fact,
+                    "Fact",
+                    &properties,
+                    None,
+                )?;
+                persistence.insert_graph_edge(
+                    &session,
+                    fact_id,
+                    entity_id,
+                    EdgeType::Mentions,
+                    Some("about"),
+                    None,
+                    1.0,
+                )?;
+                Ok(fact_id)
+            })
+            .await?;
+        self.store.embed_new_nodes(&session_id).await;
+
+        Ok(ToolResult::success(
+            json!({
+                "fact_id": fact_id,
+                "entity_id": entity_id,
+                "entity_created": entity_created,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+/// Looks up an entity by name with its facts and relations
+pub struct LookupEntityTool {
+    store: KnowledgeGraphStore,
+}
+
+impl LookupEntityTool {
+    pub fn new(store: KnowledgeGraphStore) -> Self {
+        Self { store }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct LookupEntityArgs {
+    /// Name of the entity (case-insensitive)
+    name: String,
+    /// Maximum facts and relations returned per entity (default 20)
+    limit: Option<usize>,
+    /// Named graph to use; defaults to the session's active graph
+    graph: Option<String>,
+    /// Session ID for graph isolation (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for LookupEntityTool {
+    fn name(&self) -> &str {
+        "lookup_entity"
+    }
+
+    fn description(&self) -> &str {
+        "Look up what the knowledge graph knows about a named entity: its facts and related entities"
+    }
+
+    fn parameters(&self) -> Value {
+        LookupEntityArgs::schema()
+    }
+
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: LookupEntityArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?;
+        let limit = args.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+        let graph_name = self
+            .store
+            .graph_name(session_id, args.graph.as_deref())
+            .await?;
+        let entities = self
+            .store
+            .find_entities(session_id, &graph_name, &args.name)
+            .await?;
+        if entities.is_empty() {
+            return Ok(ToolResult::failure(format!(
+                "No entity named '{}'",
+                args.name.trim()
+            )));
+        }
+
+        let mut results = Vec::with_capacity(entities.len());
+        for entity in &entities {
+            let mut connections = self.store.connections(session_id, entity.id).await?;
+            // Newest first; ids break ties between rows stored in the same instant
+            connections.sort_by(|a, b| {
+                (b.node.created_at, b.node.id).cmp(&(a.node.created_at, a.node.id))
+            });
+
+            let facts: Vec<Value> = connections
+                .iter()
+                .filter(|c| c.node.node_type == NodeType::Fact)
+                .take(limit)
+                .map(|c| json!({"id": c.node.id, "text": node_summary(&c.node)}))
+                .collect();
+            let relations: Vec<Value> = connections
+                .iter()
+                .filter(|c| c.node.node_type != NodeType::Fact)
+                .take(limit)
+                .map(|c| {
+                    json!({
+                        "relation": relation_name(c),
+                        "direction": if c.outgoing { "outgoing" } else { "incoming" },
+                        "node_id": c.node.id,
+                        "node_type": c.node.node_type.as_str(),
+                        "name": node_summary(&c.node),
+                    })
+                })
+                .collect();
+
+            results.push(json!({
+                "id": entity.id,
+                "name": entity_name(entity),
+                "type": entity.label,
+                "properties": entity.properties,
+                "facts": facts,
+                "relations": relations,
+            }));
+        }
+
+        Ok(ToolResult::success(
+            json!({ "entities": results }).to_string(),
+        ))
+    }
+}
+
+/// Lists everything connected to an entity in chronological order
+pub struct EntityTimelineTool {
+    store: KnowledgeGraphStore,
+}
+
+impl EntityTimelineTool {
+    pub fn new(store: KnowledgeGraphStore) -> Self {
+        Self { store }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct EntityTimelineArgs {
+    /// Name of the entity (case-insensitive)
+    name: String,
+    /// Number of most recent events to return (default 20)
+    limit: Option<usize>,
+    /// Named graph to use; defaults to the session's active graph
+    graph: Option<String>,
+    /// Session ID for graph isolation (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for EntityTimelineTool {
+    fn name(&self) -> &str {
+        "entity_timeline"
+    }
+
+    fn description(&self) -> &str {
+        "Show the facts, events and relations recorded for a named entity, oldest first"
+    }
+
+    fn parameters(&self) -> Value {
+        EntityTimelineArgs::schema()
+    }
+
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: EntityTimelineArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?;
+        let limit = args.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+        let graph_name = self
+            .store
+            .graph_name(session_id, args.graph.as_deref())
+            .await?;
+        let entities = self
+            .store
+            .find_entities(session_id, &graph_name, &args.name)
+            .await?;
+        if entities.is_empty() {
+            return Ok(ToolResult::failure(format!(
+                "No entity named '{}'",
+                args.name.trim()
+            )));
+        }
+
+        let mut connections = Vec::new();
+        for entity in &entities {
+            connections.extend(self.store.connections(session_id, entity.id).await?);
+        }
+        // Facts and events are dated by their own creation; links between
+        // existing entities by the moment the edge was added.
+        let at = |c: &Connection| match c.node.node_type {
+            NodeType::Entity => c.edge.created_at,
+            _ => c.node.created_at,
+        };
+        connections.sort_by_key(|c| (at(c), c.edge.id));
+        let skip = connections.len().saturating_sub(limit);
+
+        let events: Vec<Value> = connections
+            .iter()
+            .skip(skip)
+            .map(|c| {
+                json!({
+                    "at": at(c).to_rfc3339(),
+                    "kind": c.node.node_type.as_str(),
+                    "relation": relation_name(c),
+                    "node_id": c.node.id,
+                    "summary": node_summary(&c.node),
+                })
+            })
+            .collect();
+
+        Ok(ToolResult::success(
+            json!({
+                "entity": entity_name(&entities[0]),
+                "total": connections.len(),
+                "events": events,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+/// Links two named entities with a typed relation
+pub struct RelateEntitiesTool {
+    store: KnowledgeGraphStore,
+}
+
+impl RelateEntitiesTool {
+    pub fn new(store: KnowledgeGraphStore) -> Self {
+        Self { store }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct RelateEntitiesArgs {
+    /// Entity the relation starts from; created if missing
+    source: String,
+    /// Relation, e.g. depends_on, part_of, uses, or any verb phrase like works_on
+    relation: String,
+    /// Entity the relation points to; created if missing
+    target: String,
+    /// Kind of the source entity when it is new
+    source_type: Option<String>,
+    /// Kind of the target entity when it is new
+    target_type: Option<String>,
+    /// Named graph to use; defaults to the session's active graph
+    graph: Option<String>,
+    /// Session ID for graph isolation (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for RelateEntitiesTool {
+    fn name(&self) -> &str {
+        "relate_entities"
+    }
+
+    fn description(&self) -> &str {
+        "Record a relation between two named entities in the knowledge graph"
+    }
+
+    fn parameters(&self) -> Value {
+        RelateEntitiesArgs::schema()
+    }
+
+    fn policy_resource(&self) -> &str {
+        GRAPH_POLICY_RESOURCE
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: RelateEntitiesArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?.to_string();
+        let relation = args.relation.trim().to_lowercase().replace([' ', '-'], "_");
+        if args.source.trim().is_empty() || args.target.trim().is_empty() || relation.is_empty() {
+            return Ok(ToolResult::failure(
+                "source, relation and target must not be empty",
+            ));
+        }
+
+        let graph_name = self
+            .store
+            .graph_name(&session_id, args.graph.as_deref())
+            .await?;
+        let (source_id, _) = self
+            .store
+            .find_or_create_entity(
+                &session_id,
+                &graph_name,
+                &args.source,
+                args.source_type.as_deref(),
+            )
+            .await?;
+        let (target_id, _) = self
+            .store
+            .find_or_create_entity(
+                &session_id,
+                &graph_name,
+                &args.target,
+                args.target_type.as_deref(),
+            )
+            .await?;
+
+        let edge_type = EdgeType::from_str(&relation);
+        let predicate = relation.clone();
+        let (edge_id, existing) = self
+            .store
+            .run(move |persistence| {
+                let duplicate = persistence
+                    .list_graph_edges(&session_id, Some(source_id), Some(target_id))?
+                    .into_iter()
+                    .find(|edge| {
+                        edge.edge_type == edge_type
+                            && edge.predicate.as_deref() == Some(predicate.as_str())
+                    });
+                if let Some(edge) = duplicate {
+                    return Ok((edge.id, true));
+                }
+                let id = persistence.insert_graph_edge(
+                    &session_id,
+                    source_id,
+                    target_id,
+                    edge_type,
+                    Some(predicate.as_str()),
+                    None,
+                    1.0,
+                )?;
+                Ok((id, false))
+            })
+            .await?;
+
+        Ok(ToolResult::success(
+            json!({
+                "edge_id": edge_id,
+                "source_id": source_id,
+                "target_id": target_id,
+                "relation": relation,
+                "existing": existing,
+            })
+            .to_string(),
+        ))
+    }
+}
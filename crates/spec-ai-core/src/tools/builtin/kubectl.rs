@@ -0,0 +1,767 @@
+//! Read-only kubectl tools for triaging a cluster
+//!
+//! `kubectl_get`, `kubectl_describe` and `kubectl_logs` run the matching
+//! kubectl subcommand and nothing else. The command line is assembled here
+//! and never goes through a shell, and values that would be read as flags
+//! are refused, so the tools cannot change the cluster. Output is parsed
+//! into what a triage needs first: which objects are unhealthy and why,
+//! warning events, and error lines in logs.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time;
+
+use crate::tools::{Tool, ToolResult};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_OUTPUT_CHARS: usize = 16_384;
+const MAX_VALUE_CHARS: usize = 253;
+const DEFAULT_LOG_LINES: usize = 100;
+const MAX_LOG_LINES: usize = 2000;
+const MAX_ERROR_LINES: usize = 20;
+const ERROR_MARKERS: &[&str] = &["error", "exception", "panic", "fatal", "traceback"];
+
+/// The kubectl binary and how long a call may take
+#[derive(Debug, Clone)]
+pub struct Kubectl {
+    binary: PathBuf,
+    timeout: Duration,
+}
+
+impl Kubectl {
+    /// `kubectl` from `PATH`, using the current kubeconfig
+    pub fn new() -> Self {
+        Self {
+            binary: PathBuf::from("kubectl"),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run kubectl with `args`, returning stdout, or stderr as the error
+    async fn run(&self, args: &[String]) -> Result<String> {
+        let mut command = Command::new(&self.binary);
+        command.args(args).stdin(Stdio::null()).kill_on_drop(true);
+        let output = time::timeout(self.timeout, command.output())
+            .await
+            .map_err(|_| anyhow!("kubectl timed out after {:?}", self.timeout))?
+            .with_context(|| format!("Failed to run {}", self.binary.display()))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("kubectl {} failed: {}", args[0], stderr.trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Default for Kubectl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Refuse values kubectl would take for a flag, and anything that is not a
+/// plausible name, namespace, selector or duration
+fn check_value(field: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(anyhow!("{} cannot be empty", field));
+    }
+    if value.starts_with('-') {
+        return Err(anyhow!("{} cannot start with '-'", field));
+    }
+    if value.len() > MAX_VALUE_CHARS || value.chars().any(char::is_control) {
+        return Err(anyhow!("{} is not a valid value", field));
+    }
+    Ok(())
+}
+
+/// Flags selecting the context and namespace, checked
+fn scope_args(
+    context: &Option<String>,
+    namespace: &Option<String>,
+    all_namespaces: bool,
+) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    if let Some(context) = context {
+        check_value("context", context)?;
+        args.push(format!("--context={}", context));
+    }
+    if all_namespaces {
+        args.push("--all-namespaces".to_string());
+    } else if let Some(namespace) = namespace {
+        check_value("namespace", namespace)?;
+        args.push(format!("--namespace={}", namespace));
+    }
+    Ok(args)
+}
+
+/// Keep the end of `text`, where the latest lines are
+fn truncate_head(text: &str) -> String {
+    let count = text.chars().count();
+    if count <= MAX_OUTPUT_CHARS {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(count - MAX_OUTPUT_CHARS).collect();
+    format!("<truncated>...{}", tail)
+}
+
+fn json_result(value: &impl Serialize) -> Result<ToolResult> {
+    Ok(ToolResult::success(
+        serde_json::to_string(value).context("Failed to serialize kubectl output")?,
+    ))
+}
+
+fn failure(err: anyhow::Error) -> Result<ToolResult> {
+    Ok(ToolResult::failure(format!("{:#}", err)))
+}
+
+/// Health of one object from `kubectl get -o json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ObjectHealth {
+    pub kind: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Phase, readiness or the reason a container is stuck
+    pub status: String,
+    /// `None` for kinds whose health cannot be told from their status
+    pub healthy: Option<bool>,
+    /// Restarts, last termination reasons, node pressure and the like
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(Value::as_str)
+}
+
+fn int_at(value: &Value, pointer: &str) -> Option<i64> {
+    value.pointer(pointer).and_then(Value::as_i64)
+}
+
+fn conditions(object: &Value) -> impl Iterator<Item = (&str, &str)> {
+    object
+        .pointer("/status/conditions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|c| Some((c.get("type")?.as_str()?, c.get("status")?.as_str()?)))
+}
+
+fn pod_health(object: &Value, health: &mut ObjectHealth) {
+    let phase = str_at(object, "/status/phase").unwrap_or("Unknown");
+    let containers = object
+        .pointer("/status/containerStatuses")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let ready = containers
+        .iter()
+        .filter(|c| c.get("ready").and_then(Value::as_bool) == Some(true))
+        .count();
+    let restarts: i64 = containers
+        .iter()
+        .filter_map(|c| c.get("restartCount").and_then(Value::as_i64))
+        .sum();
+    let waiting = containers
+        .iter()
+        .find_map(|c| str_at(c, "/state/waiting/reason"));
+    for container in &containers {
+        if let Some(reason) = str_at(container, "/lastState/terminated/reason") {
+            let name = str_at(container, "/name").unwrap_or("container");
+            health
+                .notes
+                .push(format!("{} last terminated: {}", name, reason));
+        }
+    }
+    if restarts > 0 {
+        health.notes.push(format!("{} restart(s)", restarts));
+    }
+    health.status = match waiting {
+        Some(reason) => reason.to_string(),
+        None if containers.is_empty() => phase.to_string(),
+        None => format!("{} ({}/{} ready)", phase, ready, containers.len()),
+    };
+    health.healthy = Some(match phase {
+        "Succeeded" => true,
+        "Running" => waiting.is_none() && ready == containers.len(),
+        _ => false,
+    });
+}
+
+fn workload_health(object: &Value, health: &mut ObjectHealth) {
+    let (desired, ready) = if health.kind == "DaemonSet" {
+        (
+            int_at(object, "/status/desiredNumberScheduled").unwrap_or(0),
+            int_at(object, "/status/numberReady").unwrap_or(0),
+        )
+    } else {
+        (
+            int_at(object, "/spec/replicas").unwrap_or(1),
+            int_at(object, "/status/readyReplicas").unwrap_or(0),
+        )
+    };
+    health.status = format!("{}/{} ready", ready, desired);
+    health.healthy = Some(ready >= desired);
+}
+
+fn node_health(object: &Value, health: &mut ObjectHealth) {
+    let mut ready = false;
+    for (kind, status) in conditions(object) {
+        if kind == "Ready" {
+            ready = status == "True";
+        } else if status == "True" {
+            health.notes.push(kind.to_string());
+        }
+    }
+    if object
+        .pointer("/spec/unschedulable")
+        .and_then(Value::as_bool)
+        == Some(true)
+    {
+        health.notes.push("cordoned".to_string());
+    }
+    health.status = if ready { "Ready" } else { "NotReady" }.to_string();
+    health.healthy = Some(ready && health.notes.is_empty());
+}
+
+fn job_health(object: &Value, health: &mut ObjectHealth) {
+    let succeeded = int_at(object, "/status/succeeded").unwrap_or(0);
+    let failed = int_at(object, "/status/failed").unwrap_or(0);
+    let active = int_at(object, "/status/active").unwrap_or(0);
+    health.status = format!(
+        "{} succeeded, {} failed, {} active",
+        succeeded, failed, active
+    );
+    health.healthy = Some(failed == 0);
+}
+
+/// Health of any other kind: its phase, or conditions that are not met
+fn generic_health(object: &Value, health: &mut ObjectHealth) {
+    if let Some(phase) = str_at(object, "/status/phase") {
+        health.status = phase.to_string();
+        health.healthy = Some(matches!(
+            phase,
+            "Active" | "Bound" | "Running" | "Succeeded"
+        ));
+        return;
+    }
+    let failing: Vec<&str> = conditions(object)
+        .filter(|(kind, status)| matches!(*kind, "Ready" | "Available") && *status != "True")
+        .map(|(kind, _)| kind)
+        .collect();
+    if !failing.is_empty() {
+        health.status = format!("not {}", failing.join(", "));
+        health.healthy = Some(false);
+    }
+}
+
+/// Summarize one object
+pub fn object_health(object: &Value) -> ObjectHealth {
+    let mut health = ObjectHealth {
+        kind: str_at(object, "/kind").unwrap_or("Unknown").to_string(),
+        name: str_at(object, "/metadata/name").unwrap_or("").to_string(),
+        namespace: str_at(object, "/metadata/namespace").map(str::to_string),
+        status: String::new(),
+        healthy: None,
+        notes: Vec::new(),
+    };
+    match health.kind.as_str() {
+        "Pod" => pod_health(object, &mut health),
+        "Deployment" | "StatefulSet" | "ReplicaSet" | "DaemonSet" => {
+            workload_health(object, &mut health)
+        }
+        "Node" => node_health(object, &mut health),
+        "Job" => job_health(object, &mut health),
+        _ => generic_health(object, &mut health),
+    }
+    health
+}
+
+/// Objects of `kubectl get -o json` output, a `List` or a single object
+pub fn parse_get_output(json: &str) -> Result<Vec<ObjectHealth>> {
+    let value: Value = serde_json::from_str(json).context("kubectl returned invalid JSON")?;
+    Ok(match value.get("items").and_then(Value::as_array) {
+        Some(items) => items.iter().map(object_health).collect(),
+        None => vec![object_health(&value)],
+    })
+}
+
+/// A row of the Events table printed by `kubectl describe`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KubeEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub reason: String,
+    pub age: String,
+    pub from: String,
+    pub message: String,
+}
+
+/// Events listed at the end of `kubectl describe` output. Columns are
+/// split where the header's column names start.
+pub fn parse_events(description: &str) -> Vec<KubeEvent> {
+    let mut lines = description
+        .lines()
+        .skip_while(|line| !line.starts_with("Events:"));
+    if lines.next().is_none_or(|line| line.contains("<none>")) {
+        return Vec::new();
+    }
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let starts: Option<Vec<usize>> = ["Type", "Reason", "Age", "From", "Message"]
+        .iter()
+        .map(|column| header.find(column))
+        .collect();
+    let Some(starts) = starts else {
+        return Vec::new();
+    };
+    let column = |line: &str, index: usize| {
+        let end = starts.get(index + 1).copied().unwrap_or(line.len());
+        line.get(starts[index]..end.min(line.len()))
+            .or_else(|| line.get(starts[index]..))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    lines
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with("----"))
+        .take_while(|line| line.starts_with(' '))
+        .map(|line| KubeEvent {
+            kind: column(line, 0),
+            reason: column(line, 1),
+            age: column(line, 2),
+            from: column(line, 3),
+            message: column(line, 4),
+        })
+        .collect()
+}
+
+/// Log lines that look like errors, at most [`MAX_ERROR_LINES`] of the latest
+pub fn error_lines(log: &str) -> Vec<String> {
+    let errors: Vec<&str> = log
+        .lines()
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            ERROR_MARKERS.iter().any(|marker| lower.contains(marker))
+        })
+        .collect();
+    errors[errors.len().saturating_sub(MAX_ERROR_LINES)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct GetArgs {
+    /// Resource type, e.g. `pods`, `deployments`, `nodes` or `events`
+    resource: String,
+    /// Name of one object; all of them when omitted
+    name: Option<String>,
+    /// Namespace; the context's default when omitted
+    namespace: Option<String>,
+    /// Look in every namespace
+    #[serde(default)]
+    all_namespaces: bool,
+    /// Label selector, e.g. `app=web,tier!=cache`
+    selector: Option<String>,
+    /// kubeconfig context; the current one when omitted
+    context: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetOutput {
+    resource: String,
+    count: usize,
+    unhealthy: usize,
+    items: Vec<ObjectHealth>,
+}
+
+/// Lists cluster objects with their health
+pub struct KubectlGetTool {
+    kubectl: Arc<Kubectl>,
+}
+
+impl KubectlGetTool {
+    pub fn new(kubectl: Arc<Kubectl>) -> Self {
+        Self { kubectl }
+    }
+
+    fn command(args: &GetArgs) -> Result<Vec<String>> {
+        check_value("resource", &args.resource)?;
+        let mut command = vec!["get".to_string(), args.resource.clone()];
+        if let Some(name) = &args.name {
+            check_value("name", name)?;
+            command.push(name.clone());
+        }
+        if let Some(selector) = &args.selector {
+            check_value("selector", selector)?;
+            command.push(format!("--selector={}", selector));
+        }
+        command.extend(scope_args(
+            &args.context,
+            &args.namespace,
+            args.all_namespaces,
+        )?);
+        command.push("--output=json".to_string());
+        Ok(command)
+    }
+}
+
+#[async_trait]
+impl Tool for KubectlGetTool {
+    fn name(&self) -> &str {
+        "kubectl_get"
+    }
+
+    fn description(&self) -> &str {
+        "List Kubernetes objects (kubectl get, read-only) with a health summary of each: pod phase, readiness and restarts, ready replicas, node conditions"
+    }
+
+    fn parameters(&self) -> Value {
+        GetArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: GetArgs =
+            GetArgs::from_args(args).context("Failed to parse kubectl_get arguments")?;
+        let command = match Self::command(&args) {
+            Ok(command) => command,
+            Err(err) => return failure(err),
+        };
+        let items = match self.kubectl.run(&command).await {
+            Ok(stdout) => parse_get_output(&stdout)?,
+            Err(err) => return failure(err),
+        };
+        json_result(&GetOutput {
+            resource: args.resource,
+            count: items.len(),
+            unhealthy: items
+                .iter()
+                .filter(|item| item.healthy == Some(false))
+                .count(),
+            items,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct DescribeArgs {
+    /// Resource type, e.g. `pod` or `deployment`
+    resource: String,
+    /// Name of the object
+    name: String,
+    /// Namespace; the context's default when omitted
+    namespace: Option<String>,
+    /// kubeconfig context; the current one when omitted
+    context: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DescribeOutput {
+    events: Vec<KubeEvent>,
+    warnings: usize,
+    description: String,
+}
+
+/// Describes one cluster object, with its events parsed out
+pub struct KubectlDescribeTool {
+    kubectl: Arc<Kubectl>,
+}
+
+impl KubectlDescribeTool {
+    pub fn new(kubectl: Arc<Kubectl>) -> Self {
+        Self { kubectl }
+    }
+}
+
+#[async_trait]
+impl Tool for KubectlDescribeTool {
+    fn name(&self) -> &str {
+        "kubectl_describe"
+    }
+
+    fn description(&self) -> &str {
+        "Describe a Kubernetes object (kubectl describe, read-only), with its recent events listed separately"
+    }
+
+    fn parameters(&self) -> Value {
+        DescribeArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: DescribeArgs =
+            DescribeArgs::from_args(args).context("Failed to parse kubectl_describe arguments")?;
+        let command = check_value("resource", &args.resource)
+            .and_then(|_| check_value("name", &args.name))
+            .and_then(|_| scope_args(&args.context, &args.namespace, false));
+        let command = match command {
+            Ok(scope) => [
+                vec!["describe".to_string(), args.resource, args.name],
+                scope,
+            ]
+            .concat(),
+            Err(err) => return failure(err),
+        };
+        let description = match self.kubectl.run(&command).await {
+            Ok(stdout) => stdout,
+            Err(err) => return failure(err),
+        };
+        let events = parse_events(&description);
+        json_result(&DescribeOutput {
+            warnings: events.iter().filter(|e| e.kind == "Warning").count(),
+            events,
+            description: truncate_head(&description),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct LogsArgs {
+    /// Pod name
+    pod: String,
+    /// Container; the pod's default container when omitted
+    container: Option<String>,
+    /// Namespace; the context's default when omitted
+    namespace: Option<String>,
+    /// Lines from the end of the log (default 100, at most 2000)
+    tail: Option<usize>,
+    /// Only lines newer than this, e.g. `10m` or `1h`
+    since: Option<String>,
+    /// Logs of the previous, crashed container
+    #[serde(default)]
+    previous: bool,
+    /// kubeconfig context; the current one when omitted
+    context: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LogsOutput {
+    pod: String,
+    lines: usize,
+    errors: Vec<String>,
+    log: String,
+}
+
+/// Reads the log of a pod's container
+pub struct KubectlLogsTool {
+    kubectl: Arc<Kubectl>,
+}
+
+impl KubectlLogsTool {
+    pub fn new(kubectl: Arc<Kubectl>) -> Self {
+        Self { kubectl }
+    }
+
+    fn command(args: &LogsArgs) -> Result<Vec<String>> {
+        check_value("pod", &args.pod)?;
+        let tail = args.tail.unwrap_or(DEFAULT_LOG_LINES).min(MAX_LOG_LINES);
+        let mut command = vec![
+            "logs".to_string(),
+            args.pod.clone(),
+            format!("--tail={}", tail),
+        ];
+        if let Some(container) = &args.container {
+            check_value("container", container)?;
+            command.push(format!("--container={}", container));
+        }
+        if let Some(since) = &args.since {
+            check_value("since", since)?;
+            command.push(format!("--since={}", since));
+        }
+        if args.previous {
+            command.push("--previous".to_string());
+        }
+        command.extend(scope_args(&args.context, &args.namespace, false)?);
+        Ok(command)
+    }
+}
+
+#[async_trait]
+impl Tool for KubectlLogsTool {
+    fn name(&self) -> &str {
+        "kubectl_logs"
+    }
+
+    fn description(&self) -> &str {
+        "Read the latest log lines of a pod's container (kubectl logs, read-only), with error lines picked out"
+    }
+
+    fn parameters(&self) -> Value {
+        LogsArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: LogsArgs =
+            LogsArgs::from_args(args).context("Failed to parse kubectl_logs arguments")?;
+        let command = match Self::command(&args) {
+            Ok(command) => command,
+            Err(err) => return failure(err),
+        };
+        let log = match self.kubectl.run(&command).await {
+            Ok(stdout) => stdout,
+            Err(err) => return failure(err),
+        };
+        json_result(&LogsOutput {
+            pod: args.pod,
+            lines: log.lines().count(),
+            errors: error_lines(&log),
+            log: truncate_head(&log),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn object_health_reads_pods_workloads_and_nodes() {
+        let crashing = json!({
+            "kind": "Pod",
+            "metadata": {"name": "web-1", "namespace": "prod"},
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [{
+                    "name": "web",
+                    "ready": false,
+                    "restartCount": 7,
+                    "state": {"waiting": {"reason": "CrashLoopBackOff"}},
+                    "lastState": {"terminated": {"reason": "OOMKilled"}}
+                }]
+            }
+        });
+        let health = object_health(&crashing);
+        assert_eq!(health.status, "CrashLoopBackOff");
+        assert_eq!(health.healthy, Some(false));
+        assert_eq!(
+            health.notes,
+            vec!["web last terminated: OOMKilled", "7 restart(s)"]
+        );
+
+        let deployment = json!({
+            "kind": "Deployment",
+            "metadata": {"name": "web"},
+            "spec": {"replicas": 3},
+            "status": {"readyReplicas": 2}
+        });
+        let health = object_health(&deployment);
+        assert_eq!(health.status, "2/3 ready");
+        assert_eq!(health.healthy, Some(false));
+
+        let node = json!({
+            "kind": "Node",
+            "metadata": {"name": "node-a"},
+            "status": {"conditions": [
+                {"type": "MemoryPressure", "status": "True"},
+                {"type": "Ready", "status": "True"}
+            ]}
+        });
+        let health = object_health(&node);
+        assert_eq!(health.status, "Ready");
+        assert_eq!(health.notes, vec!["MemoryPressure"]);
+        assert_eq!(health.healthy, Some(false));
+
+        let config_map = json!({"kind": "ConfigMap", "metadata": {"name": "settings"}});
+        assert_eq!(object_health(&config_map).healthy, None);
+    }
+
+    #[test]
+    fn describe_events_are_parsed() {
+        let description = "\
+Name:         web-1
+Namespace:    prod
+Events:
+  Type     Reason     Age                From               Message
+  ----     ------     ----               ----               -------
+  Normal   Pulled     3m (x5 over 10m)   kubelet            Container image already present
+  Warning  BackOff    2m (x40 over 10m)  kubelet            Back-off restarting failed container
+";
+        let events = parse_events(description);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].kind, "Warning");
+        assert_eq!(events[1].reason, "BackOff");
+        assert_eq!(events[1].age, "2m (x40 over 10m)");
+        assert_eq!(events[1].from, "kubelet");
+        assert_eq!(events[1].message, "Back-off restarting failed container");
+
+        assert!(parse_events("Name: web-1\nEvents:  <none>\n").is_empty());
+    }
+
+    #[test]
+    fn commands_stay_read_only() {
+        let args = GetArgs::from_args(json!({
+            "resource": "pods",
+            "namespace": "prod",
+            "selector": "app=web"
+        }))
+        .unwrap();
+        assert_eq!(
+            KubectlGetTool::command(&args).unwrap(),
+            vec![
+                "get",
+                "pods",
+                "--selector=app=web",
+                "--namespace=prod",
+                "--output=json"
+            ]
+        );
+
+        // Flags smuggled in as values are refused
+        let args = GetArgs::from_args(json!({"resource": "pods", "name": "--raw=/api"})).unwrap();
+        assert!(KubectlGetTool::command(&args).is_err());
+        let args = LogsArgs::from_args(json!({"pod": "web-1", "tail": 100_000})).unwrap();
+        assert!(KubectlLogsTool::command(&args)
+            .unwrap()
+            .contains(&"--tail=2000".to_string()));
+    }
+
+    #[tokio::test]
+    async fn kubectl_get_summarizes_the_listing() {
+        let dir = TempDir::new().unwrap();
+        let listing = json!({
+            "kind": "List",
+            "items": [
+                {"kind": "Pod", "metadata": {"name": "ok"},
+                 "status": {"phase": "Running", "containerStatuses": [{"name": "c", "ready": true}]}},
+                {"kind": "Pod", "metadata": {"name": "pending"}, "status": {"phase": "Pending"}}
+            ]
+        });
+        let script = dir.path().join("kubectl");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\ncat <<'EOF'\n{}\nEOF\n", listing),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let tool = KubectlGetTool::new(Arc::new(Kubectl::new().with_binary(&script)));
+        let result = tool.execute(json!({"resource": "pods"})).await.unwrap();
+        assert!(result.success);
+        let output: Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(output["count"], 2);
+        assert_eq!(output["unhealthy"], 1);
+        assert_eq!(output["items"][1]["status"], "Pending");
+    }
+}
@@ -1,15 +1,24 @@
 pub mod audio_transcription;
 pub mod bash;
 pub mod calculator;
+pub mod ci_log;
 pub mod code_search;
 pub mod echo;
 pub mod file_extract;
 pub mod file_read;
 pub mod file_write;
 pub mod graph;
+pub mod graph_diff;
+pub mod graph_store;
+pub mod graph_subgraph;
+pub mod graph_tools;
+pub mod process;
 pub mod prompt;
+pub mod reminder;
 pub mod search;
 pub mod shell;
+pub mod tail_file;
+pub mod time;
 
 #[cfg(feature = "api")]
 pub mod web_search;
@@ -20,18 +29,32 @@ pub mod web_scraper;
 #[cfg(feature = "api")]
 pub mod mesh_communication;
 
+#[cfg(feature = "ops")]
+pub mod cloud_status;
+#[cfg(feature = "ops")]
+pub mod kubectl;
+
 pub use audio_transcription::AudioTranscriptionTool;
 pub use bash::BashTool;
 pub use calculator::MathTool;
+pub use ci_log::IngestCiLogTool;
 pub use code_search::CodeSearchTool;
 pub use echo::EchoTool;
 pub use file_extract::FileExtractTool;
 pub use file_read::FileReadTool;
 pub use file_write::FileWriteTool;
 pub use graph::GraphTool;
+pub use graph_diff::{GraphDiff, GraphSnapshot};
+pub use graph_store::KnowledgeGraphStore;
+pub use graph_subgraph::Subgraph;
+pub use graph_tools::{EntityTimelineTool, LookupEntityTool, RelateEntitiesTool, RememberFactTool};
+pub use process::{ProcessManager, ProcessStartTool, ProcessStatusTool, ProcessStopTool};
 pub use prompt::PromptUserTool;
+pub use reminder::RemindMeTool;
 pub use search::SearchTool;
 pub use shell::ShellTool;
+pub use tail_file::TailFileTool;
+pub use time::{GetTimeTool, ScheduleLookupTool};
 
 #[cfg(feature = "api")]
 pub use web_search::WebSearchTool;
@@ -41,3 +64,8 @@ pub use web_scraper::WebScraperTool;
 
 #[cfg(feature = "api")]
 pub use mesh_communication::{GetMessagesTool, QueryMeshTool, SendMessageTool};
+
+#[cfg(feature = "ops")]
+pub use cloud_status::CloudStatusTool;
+#[cfg(feature = "ops")]
+pub use kubectl::{Kubectl, KubectlDescribeTool, KubectlGetTool, KubectlLogsTool};
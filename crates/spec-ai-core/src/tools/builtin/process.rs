@@ -0,0 +1,618 @@
+//! Long-lived child processes
+//!
+//! `process_start` runs a command that keeps going (a dev server, a file
+//! watcher) in the background and returns its PID at once;
+//! `process_status` reports whether it is still running with its latest
+//! output, and `process_stop` kills it. Processes belong to the session
+//! that started them: they are killed when the session ends, and with the
+//! [`ProcessManager`] when the program shuts down. On Unix each command runs
+//! in a process group of its own, and the whole group is killed, so children
+//! the shell started do not outlive it. The tools are checked against the
+//! `process` policy action rather than `tool_call`.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tracing::{debug, info};
+
+use super::bash::validate_command;
+use crate::tools::{Tool, ToolResult};
+
+/// Policy action the process tools are checked against
+pub const PROCESS_POLICY_ACTION: &str = "process";
+
+/// Lines of output kept per process
+const OUTPUT_LINES: usize = 200;
+/// Processes one session may have at a time
+const MAX_PROCESSES_PER_SESSION: usize = 8;
+const DEFAULT_STATUS_LINES: usize = 20;
+
+/// Latest lines a process wrote to stdout and stderr, oldest first
+type OutputBuffer = Arc<Mutex<VecDeque<String>>>;
+
+struct ManagedProcess {
+    session_id: String,
+    command: String,
+    working_dir: Option<String>,
+    started_at: DateTime<Utc>,
+    child: Child,
+    output: OutputBuffer,
+}
+
+impl ManagedProcess {
+    fn info(&mut self, pid: u32, lines: usize) -> ProcessInfo {
+        let status = self.child.try_wait().ok().flatten();
+        let output = match self.output.lock() {
+            Ok(output) => output
+                .iter()
+                .skip(output.len().saturating_sub(lines))
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        ProcessInfo {
+            pid,
+            command: self.command.clone(),
+            working_dir: self.working_dir.clone(),
+            started_at: self.started_at,
+            running: status.is_none(),
+            exit_code: status.and_then(|status| status.code()),
+            output,
+        }
+    }
+}
+
+impl Drop for ManagedProcess {
+    fn drop(&mut self) {
+        // `kill_on_drop` only reaches the shell
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            kill_group(pid);
+        }
+    }
+}
+
+/// A managed process as the tools report it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub command: String,
+    pub working_dir: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub running: bool,
+    /// Exit code once the process has ended on its own
+    pub exit_code: Option<i32>,
+    /// Latest output lines, oldest first
+    pub output: Vec<String>,
+}
+
+/// Registry of the processes started with `process_start`, by PID. Each
+/// child is killed when its entry is dropped.
+pub struct ProcessManager {
+    shell_path: String,
+    processes: Mutex<HashMap<u32, ManagedProcess>>,
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
+        Self {
+            shell_path: shell,
+            processes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_shell(mut self, path: impl Into<String>) -> Self {
+        self.shell_path = path.into();
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u32, ManagedProcess>> {
+        self.processes.lock().expect("process registry lock")
+    }
+
+    /// Run `command` in the background for `session_id`
+    pub fn start(
+        &self,
+        session_id: &str,
+        command: &str,
+        working_dir: Option<&str>,
+        env: &HashMap<String, String>,
+    ) -> Result<ProcessInfo> {
+        validate_command(command)?;
+        let mut processes = self.lock();
+        let owned = processes
+            .values()
+            .filter(|process| process.session_id == session_id)
+            .count();
+        if owned >= MAX_PROCESSES_PER_SESSION {
+            return Err(anyhow!(
+                "This session already has {} processes; stop one first",
+                owned
+            ));
+        }
+
+        let mut child_command = Command::new(&self.shell_path);
+        child_command
+            .arg("-c")
+            .arg(command)
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        child_command.process_group(0);
+        if let Some(dir) = working_dir {
+            child_command.current_dir(dir);
+        }
+        let mut child = child_command
+            .spawn()
+            .with_context(|| format!("Failed to start '{}'", command))?;
+        let pid = child
+            .id()
+            .context("Process exited before it could be tracked")?;
+        let output = OutputBuffer::default();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(capture(stdout, output.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(capture(stderr, output.clone()));
+        }
+        info!(
+            target: "spec_ai::tools::process",
+            pid,
+            command,
+            session_id,
+            "Started background process"
+        );
+
+        let mut process = ManagedProcess {
+            session_id: session_id.to_string(),
+            command: command.to_string(),
+            working_dir: working_dir.map(str::to_string),
+            started_at: Utc::now(),
+            child,
+            output,
+        };
+        let info = process.info(pid, DEFAULT_STATUS_LINES);
+        processes.insert(pid, process);
+        Ok(info)
+    }
+
+    /// The session's processes, or only `pid`, with the last `lines` lines
+    /// of their output
+    pub fn status(&self, session_id: &str, pid: Option<u32>, lines: usize) -> Vec<ProcessInfo> {
+        let mut infos: Vec<ProcessInfo> = self
+            .lock()
+            .iter_mut()
+            .filter(|(id, process)| {
+                process.session_id == session_id && pid.is_none_or(|pid| pid == **id)
+            })
+            .map(|(id, process)| process.info(*id, lines))
+            .collect();
+        infos.sort_by_key(|info| info.started_at);
+        infos
+    }
+
+    /// Kill `pid` and forget it. `None` if the session has no such process.
+    pub async fn stop(&self, session_id: &str, pid: u32) -> Option<ProcessInfo> {
+        let mut stopped = self.take(|id, process| id == pid && process.session_id == session_id);
+        let (pid, process) = stopped.pop()?;
+        Some(kill(pid, process).await)
+    }
+
+    /// Kill every process `session_id` started; returns how many there were
+    pub async fn stop_session(&self, session_id: &str) -> usize {
+        let stopped = self.take(|_, process| process.session_id == session_id);
+        let count = stopped.len();
+        for (pid, process) in stopped {
+            kill(pid, process).await;
+        }
+        count
+    }
+
+    /// Kill every process, e.g. before shutting down
+    pub async fn stop_all(&self) -> usize {
+        let stopped = self.take(|_, _| true);
+        let count = stopped.len();
+        for (pid, process) in stopped {
+            kill(pid, process).await;
+        }
+        count
+    }
+
+    fn take(&self, filter: impl Fn(u32, &ManagedProcess) -> bool) -> Vec<(u32, ManagedProcess)> {
+        let mut processes = self.lock();
+        let pids: Vec<u32> = processes
+            .iter()
+            .filter(|(pid, process)| filter(**pid, process))
+            .map(|(pid, _)| *pid)
+            .collect();
+        pids.into_iter()
+            .filter_map(|pid| processes.remove(&pid).map(|process| (pid, process)))
+            .collect()
+    }
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Kill a process and its process group unless it has already ended, and
+/// report its last state
+async fn kill(pid: u32, mut process: ManagedProcess) -> ProcessInfo {
+    // Also when the shell has exited: what it left running is still in the group
+    #[cfg(unix)]
+    kill_group(pid);
+    if matches!(process.child.try_wait(), Ok(None)) {
+        if let Err(err) = process.child.kill().await {
+            debug!("Failed to kill process {}: {}", pid, err);
+        }
+    }
+    info!(
+        target: "spec_ai::tools::process",
+        pid,
+        command = %process.command,
+        "Stopped background process"
+    );
+    process.info(pid, DEFAULT_STATUS_LINES)
+}
+
+/// Kill the process group `pid` leads, as `start` made each process do
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    let Ok(pgid) = libc::pid_t::try_from(pid) else {
+        return;
+    };
+    // SAFETY: killpg takes no pointers; it only sends a signal
+    if unsafe { libc::killpg(pgid, libc::SIGKILL) } != 0 {
+        debug!(
+            "Failed to kill process group {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Keep the latest lines read from `stream` in `output`
+async fn capture(stream: impl AsyncRead + Unpin, output: OutputBuffer) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(mut output) = output.lock() else {
+            return;
+        };
+        if output.len() == OUTPUT_LINES {
+            output.pop_front();
+        }
+        output.push_back(line);
+    }
+}
+
+fn session_id(session_id: &Option<String>) -> Result<&str> {
+    session_id
+        .as_deref()
+        .filter(|id| !id.is_empty())
+        .context("session_id is required")
+}
+
+fn parse_args<T: ToolArgs>(tool: &str, args: Value) -> Result<T> {
+    T::from_args(args).with_context(|| format!("Failed to parse {} arguments", tool))
+}
+
+fn info_result(value: &impl Serialize) -> Result<ToolResult> {
+    Ok(ToolResult::success(
+        serde_json::to_string(value).context("Failed to serialize process info")?,
+    ))
+}
+
+/// Starts a command in the background
+pub struct ProcessStartTool {
+    manager: Arc<ProcessManager>,
+}
+
+impl ProcessStartTool {
+    pub fn new(manager: Arc<ProcessManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct ProcessStartArgs {
+    /// Shell command that keeps running, e.g. `npm run dev`
+    command: String,
+    /// Working directory for the process
+    working_dir: Option<String>,
+    /// Environment variables for the process
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Session that owns the process (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ProcessStartTool {
+    fn name(&self) -> &str {
+        "process_start"
+    }
+
+    fn description(&self) -> &str {
+        "Start a long-running command (dev server, watcher) in the background and return its PID; read its output with process_status"
+    }
+
+    fn parameters(&self) -> Value {
+        ProcessStartArgs::schema()
+    }
+
+    fn policy_action(&self) -> &str {
+        PROCESS_POLICY_ACTION
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    fn workspace_args(&self) -> &[&'static str] {
+        &["working_dir"]
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: ProcessStartArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?;
+        match self.manager.start(
+            session_id,
+            &args.command,
+            args.working_dir.as_deref(),
+            &args.env,
+        ) {
+            Ok(info) => info_result(&info),
+            Err(err) => Ok(ToolResult::failure(format!("{:#}", err))),
+        }
+    }
+}
+
+/// Reports the session's background processes
+pub struct ProcessStatusTool {
+    manager: Arc<ProcessManager>,
+}
+
+impl ProcessStatusTool {
+    pub fn new(manager: Arc<ProcessManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct ProcessStatusArgs {
+    /// PID of one process; all of the session's processes when omitted
+    pid: Option<u32>,
+    /// Lines of recent output to include per process (default 20)
+    lines: Option<usize>,
+    /// Session that owns the processes (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ProcessStatusTool {
+    fn name(&self) -> &str {
+        "process_status"
+    }
+
+    fn description(&self) -> &str {
+        "List background processes started with process_start: whether each is running, its exit code and its latest output"
+    }
+
+    fn parameters(&self) -> Value {
+        ProcessStatusArgs::schema()
+    }
+
+    fn policy_action(&self) -> &str {
+        PROCESS_POLICY_ACTION
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: ProcessStatusArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?;
+        let lines = args.lines.unwrap_or(DEFAULT_STATUS_LINES).min(OUTPUT_LINES);
+        let infos = self.manager.status(session_id, args.pid, lines);
+        match args.pid {
+            Some(pid) if infos.is_empty() => Ok(ToolResult::failure(format!(
+                "No process {} in this session",
+                pid
+            ))),
+            _ => info_result(&infos),
+        }
+    }
+}
+
+/// Kills one of the session's background processes
+pub struct ProcessStopTool {
+    manager: Arc<ProcessManager>,
+}
+
+impl ProcessStopTool {
+    pub fn new(manager: Arc<ProcessManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct ProcessStopArgs {
+    /// PID returned by process_start
+    pid: u32,
+    /// Session that owns the process (filled in automatically)
+    session_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ProcessStopTool {
+    fn name(&self) -> &str {
+        "process_stop"
+    }
+
+    fn description(&self) -> &str {
+        "Kill a background process started with process_start and return its final output"
+    }
+
+    fn parameters(&self) -> Value {
+        ProcessStopArgs::schema()
+    }
+
+    fn policy_action(&self) -> &str {
+        PROCESS_POLICY_ACTION
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: ProcessStopArgs = parse_args(self.name(), args)?;
+        let session_id = session_id(&args.session_id)?;
+        match self.manager.stop(session_id, args.pid).await {
+            Some(info) => info_result(&info),
+            None => Ok(ToolResult::failure(format!(
+                "No process {} in this session",
+                args.pid
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    async fn wait_for_output(manager: &ProcessManager, pid: u32, line: &str) -> ProcessInfo {
+        for _ in 0..50 {
+            let info = manager.status("s1", Some(pid), 10).remove(0);
+            if info.output.iter().any(|output| output == line) {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("process {} never wrote '{}'", pid, line);
+    }
+
+    #[tokio::test]
+    async fn processes_run_in_the_background_until_stopped() {
+        let manager = Arc::new(ProcessManager::new().with_shell("/bin/sh"));
+        let start = ProcessStartTool::new(manager.clone());
+        let result = start
+            .execute(json!({
+                "command": "echo ready; echo oops >&2; sleep 30",
+                "session_id": "s1"
+            }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let started: Value = serde_json::from_str(&result.output).unwrap();
+        let pid = started["pid"].as_u64().unwrap() as u32;
+
+        let info = wait_for_output(&manager, pid, "ready").await;
+        assert!(info.running);
+        wait_for_output(&manager, pid, "oops").await;
+        // Other sessions neither see nor stop it
+        assert!(manager.status("s2", None, 10).is_empty());
+        assert!(manager.stop("s2", pid).await.is_none());
+
+        let stop = ProcessStopTool::new(manager.clone());
+        let result = stop
+            .execute(json!({"pid": pid, "session_id": "s1"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        let stopped: Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(stopped["running"], false);
+        assert!(manager.status("s1", None, 10).is_empty());
+
+        let status = ProcessStatusTool::new(manager);
+        let result = status
+            .execute(json!({"pid": pid, "session_id": "s1"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn ending_a_session_kills_its_processes() {
+        let manager = ProcessManager::new().with_shell("/bin/sh");
+        let none = HashMap::new();
+        let first = manager.start("s1", "sleep 30", None, &none).unwrap();
+        manager.start("s1", "sleep 30", None, &none).unwrap();
+        let other = manager.start("s2", "sleep 30", None, &none).unwrap();
+        assert!(first.running);
+
+        assert_eq!(manager.stop_session("s1").await, 2);
+        assert!(manager.status("s1", None, 0).is_empty());
+        assert_eq!(manager.status("s2", None, 0)[0].pid, other.pid);
+        assert_eq!(manager.stop_all().await, 1);
+
+        let exited = manager.start("s1", "exit 3", None, &none).unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let info = manager.status("s1", Some(exited.pid), 0).remove(0);
+        assert!(!info.running);
+        assert_eq!(info.exit_code, Some(3));
+        assert!(manager.start("s1", "sudo true", None, &none).is_err());
+    }
+
+    /// Whether `pid` is gone or only waiting to be reaped
+    #[cfg(target_os = "linux")]
+    fn is_dead(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat
+                .rsplit_once(')')
+                .is_some_and(|(_, rest)| rest.trim_start().starts_with(['Z', 'X'])),
+            Err(_) => true,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn stopping_a_process_kills_what_it_started() {
+        let manager = ProcessManager::new().with_shell("/bin/sh");
+        let none = HashMap::new();
+        let shell = manager
+            .start("s1", "sleep 30 & echo $!; wait", None, &none)
+            .unwrap();
+        let mut grandchild = None;
+        for _ in 0..50 {
+            let info = manager.status("s1", Some(shell.pid), 10).remove(0);
+            grandchild = info
+                .output
+                .first()
+                .and_then(|line| line.parse::<u32>().ok());
+            if grandchild.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let grandchild = grandchild.expect("the shell never printed its child's PID");
+        assert!(!is_dead(grandchild));
+
+        manager.stop("s1", shell.pid).await.unwrap();
+        for _ in 0..50 {
+            if is_dead(grandchild) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("process {} outlived the shell that started it", grandchild);
+    }
+}
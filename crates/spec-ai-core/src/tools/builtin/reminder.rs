@@ -0,0 +1,236 @@
+//! Follow-ups the agent schedules for itself
+//!
+//! `remind_me` stores a message to be posted into the session later, after
+//! a delay such as `30m` or at an RFC 3339 time. See
+//! [`crate::agent::reminders`] for delivery.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+use std::sync::Arc;
+
+use crate::persistence::Persistence;
+use crate::tools::{Tool, ToolResult};
+
+/// Furthest ahead a reminder can be set
+const MAX_DELAY_DAYS: i64 = 365;
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Parse a delay such as `30m`, `2h`, `1h30m`, `90 seconds` or `1 day`
+pub fn parse_delay(input: &str) -> Option<Duration> {
+    let input = input.trim().to_lowercase();
+    let mut rest = input.as_str();
+    let mut total = Duration::zero();
+    let mut parts = 0;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count: i64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..letters] {
+            "s" | "sec" | "secs" | "second" | "seconds" => Duration::seconds(1),
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::minutes(1),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Duration::hours(1),
+            "d" | "day" | "days" => Duration::days(1),
+            "w" | "week" | "weeks" => Duration::weeks(1),
+            _ => return None,
+        };
+        total = total.checked_add(&unit.checked_mul(count.try_into().ok()?)?)?;
+        rest = rest[letters..].trim_start_matches([' ', ',']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+        parts += 1;
+    }
+    (parts > 0).then_some(total)
+}
+
+/// When a reminder set at `now` falls due
+fn due_time(
+    delay: Option<&str>,
+    at: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, String> {
+    let due = match (delay, at) {
+        (Some(delay), None) => {
+            now + parse_delay(delay).ok_or_else(|| {
+                format!(
+                    "Could not read the delay '{}'; use e.g. 30m, 2h or 1d",
+                    delay
+                )
+            })?
+        }
+        (None, Some(at)) => DateTime::parse_from_rfc3339(at.trim())
+            .map_err(|_| {
+                format!(
+                    "'{}' is not an RFC 3339 time such as 2025-03-01T09:00:00+01:00",
+                    at
+                )
+            })?
+            .with_timezone(&Utc),
+        _ => return Err("Give exactly one of 'in' and 'at'".to_string()),
+    };
+    if due <= now {
+        return Err("The reminder time is in the past".to_string());
+    }
+    if due - now > Duration::days(MAX_DELAY_DAYS) {
+        return Err(format!(
+            "Reminders can be set at most {} days ahead",
+            MAX_DELAY_DAYS
+        ));
+    }
+    Ok(due)
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct RemindMeArgs {
+    /// What to be reminded of, e.g. "check that the deploy finished"
+    message: String,
+    /// Delay from now, e.g. 30m, 2h, 1h30m or 1d
+    #[serde(rename = "in")]
+    delay: Option<String>,
+    /// Time to post the reminder, in RFC 3339 with an offset
+    at: Option<String>,
+    /// Session to post the reminder into (filled in automatically)
+    session_id: Option<String>,
+}
+
+/// Schedules a follow-up message into the current session
+pub struct RemindMeTool {
+    persistence: Arc<Persistence>,
+}
+
+impl RemindMeTool {
+    pub fn new(persistence: Arc<Persistence>) -> Self {
+        Self { persistence }
+    }
+}
+
+#[async_trait]
+impl Tool for RemindMeTool {
+    fn name(&self) -> &str {
+        "remind_me"
+    }
+
+    fn description(&self) -> &str {
+        "Schedule a follow-up message that is posted into this conversation later, e.g. to check on a deploy in 30 minutes. Give either 'in' or 'at'."
+    }
+
+    fn parameters(&self) -> Value {
+        RemindMeArgs::schema()
+    }
+
+    fn session_scoped(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args = RemindMeArgs::from_args(args).context("Failed to parse remind_me arguments")?;
+        let session_id = args
+            .session_id
+            .filter(|id| !id.is_empty())
+            .context("session_id is required")?;
+        let message = args.message.trim().to_string();
+        if message.is_empty() {
+            return Ok(ToolResult::failure("message must not be empty"));
+        }
+        if message.chars().count() > MAX_MESSAGE_CHARS {
+            return Ok(ToolResult::failure(format!(
+                "message is longer than {} characters",
+                MAX_MESSAGE_CHARS
+            )));
+        }
+        let due_at = match due_time(args.delay.as_deref(), args.at.as_deref(), Utc::now()) {
+            Ok(due_at) => due_at,
+            Err(err) => return Ok(ToolResult::failure(err)),
+        };
+
+        let persistence = self.persistence.clone();
+        let reminder = tokio::task::spawn_blocking(move || {
+            persistence.add_reminder(&session_id, &message, due_at)
+        })
+        .await
+        .context("reminder task failed")??;
+        Ok(ToolResult::success(
+            json!({
+                "id": reminder.id,
+                "message": reminder.message,
+                "due_at": reminder.due_at.to_rfc3339(),
+            })
+            .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_are_parsed() {
+        assert_eq!(parse_delay("30m"), Some(Duration::minutes(30)));
+        assert_eq!(parse_delay("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(
+            parse_delay("2 hours and 15 minutes"),
+            Some(Duration::minutes(135))
+        );
+        assert_eq!(parse_delay(" 1 day "), Some(Duration::days(1)));
+        assert_eq!(parse_delay("90 seconds"), Some(Duration::seconds(90)));
+        assert_eq!(parse_delay(""), None);
+        assert_eq!(parse_delay("soon"), None);
+        assert_eq!(parse_delay("30"), None);
+        assert_eq!(parse_delay("5 fortnights"), None);
+    }
+
+    #[test]
+    fn due_time_needs_one_future_time() {
+        let now = DateTime::parse_from_rfc3339("2025-03-01T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            due_time(Some("30m"), None, now),
+            Ok(now + Duration::minutes(30))
+        );
+        assert_eq!(
+            due_time(None, Some("2025-03-01T09:00:00+01:00"), now),
+            Err("The reminder time is in the past".to_string())
+        );
+        assert_eq!(
+            due_time(None, Some("2025-03-01T10:00:00+01:00"), now),
+            Ok(now + Duration::hours(1))
+        );
+        assert!(due_time(Some("30m"), Some("2025-03-01T10:00:00Z"), now).is_err());
+        assert!(due_time(None, None, now).is_err());
+        assert!(due_time(Some("400d"), None, now).is_err());
+    }
+
+    #[tokio::test]
+    async fn remind_me_stores_a_pending_reminder() {
+        let persistence = Arc::new(crate::test_utils::create_test_db());
+        let tool = RemindMeTool::new(persistence.clone());
+        let result = tool
+            .execute(json!({
+                "message": "check the deploy",
+                "in": "30m",
+                "session_id": "s1"
+            }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let pending = persistence.pending_reminders(Some("s1")).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].message, "check the deploy");
+        assert!(pending[0].due_at > Utc::now() + Duration::minutes(29));
+
+        let result = tool
+            .execute(json!({"message": "x", "in": "later", "session_id": "s1"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+    }
+}
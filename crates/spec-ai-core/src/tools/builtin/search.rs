@@ -172,6 +172,10 @@ impl Tool for SearchTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["root"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: SearchArgs =
             serde_json::from_value(args).context("Failed to parse search arguments")?;
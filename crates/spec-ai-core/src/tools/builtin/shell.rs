@@ -208,6 +208,10 @@ impl Tool for ShellTool {
         })
     }
 
+    fn workspace_args(&self) -> &[&'static str] {
+        &["working_dir"]
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let args: ShellArgs =
             serde_json::from_value(args).context("Failed to parse shell arguments")?;
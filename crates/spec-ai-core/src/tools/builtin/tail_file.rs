@@ -0,0 +1,337 @@
+use crate::tools::{channel_stream, collect_tool_stream, Tool, ToolChunk, ToolResult, ToolStream};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+
+const DEFAULT_LINES: usize = 20;
+const MAX_LINES: usize = 1000;
+const DEFAULT_MAX_BYTES: usize = 65_536; // 64 KiB
+const MAX_FOLLOW_SECS: u64 = 300;
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Deserialize)]
+struct TailFileArgs {
+    path: String,
+    /// Lines to return from the end of the file
+    lines: Option<usize>,
+    /// Seconds to keep watching for appended lines
+    #[serde(default)]
+    follow_secs: u64,
+    max_bytes: Option<usize>,
+    /// Directory the file must be in; set by the agent, see [`Tool::confined`]
+    workspace_root: Option<String>,
+}
+
+/// Tool returning the last lines of a file, optionally watching it for lines
+/// appended afterwards, e.g. the log of a process started with `bash`
+pub struct TailFileTool {
+    max_bytes: usize,
+}
+
+impl TailFileTool {
+    pub fn new() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+impl Default for TailFileTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `path` with symlinks resolved, refused unless it is inside `root` (the
+/// working directory when `None`)
+fn confine(path: &str, root: Option<&str>) -> Result<PathBuf> {
+    if path.trim().is_empty() {
+        return Err(anyhow!("tail_file requires a valid path"));
+    }
+    let root = match root {
+        Some(root) => PathBuf::from(root),
+        None => std::env::current_dir().context("Failed to read the working directory")?,
+    };
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Workspace not found: {}", root.display()))?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .with_context(|| format!("File not found: {}", path))?;
+    if !resolved.starts_with(&root) {
+        return Err(anyhow!(
+            "{} is outside the workspace {}",
+            resolved.display(),
+            root.display()
+        ));
+    }
+    if !resolved.is_file() {
+        return Err(anyhow!("{} is not a regular file", resolved.display()));
+    }
+    Ok(resolved)
+}
+
+/// Read `path` from `offset`, at most `limit` bytes
+fn read_from(path: &Path, offset: u64, limit: usize) -> Result<Vec<u8>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open file {}", path.display()))?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(limit as u64)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read file {}", path.display()))?;
+    Ok(bytes)
+}
+
+/// Last `lines` lines among the final `max_bytes` of `path`, and the
+/// length of the file they were read from
+fn read_tail(path: &Path, lines: usize, max_bytes: usize) -> Result<(String, u64)> {
+    let len = path
+        .metadata()
+        .with_context(|| format!("File not found: {}", path.display()))?
+        .len();
+    let start = len.saturating_sub(max_bytes as u64);
+    let bytes = read_from(path, start, max_bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut all: Vec<&str> = text.split_inclusive('\n').collect();
+    // A window starting mid-file opens with the end of a cut-off line
+    if start > 0 && !all.is_empty() {
+        all.remove(0);
+    }
+    let tail = all[all.len().saturating_sub(lines)..].concat();
+    Ok((tail, start + bytes.len() as u64))
+}
+
+/// Send `tail`, then whole lines appended to `path` after `offset`, until
+/// `follow` has passed, `max_bytes` have been sent or nobody is listening.
+/// A file that shrinks (truncated or rotated) is read again from the start.
+async fn watch(
+    path: &Path,
+    tail: String,
+    mut offset: u64,
+    follow: Duration,
+    max_bytes: usize,
+    tx: &mpsc::UnboundedSender<Result<ToolChunk>>,
+) -> Result<ToolResult> {
+    if !tail.is_empty() {
+        let _ = tx.send(Ok(ToolChunk::Output(tail.clone())));
+    }
+    let mut output = tail;
+    let mut pending = Vec::new();
+    let mut capped = false;
+    let deadline = Instant::now() + follow;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() || tx.is_closed() {
+            break;
+        }
+        time::sleep(left.min(POLL_INTERVAL)).await;
+
+        let len = path
+            .metadata()
+            .with_context(|| format!("File not found: {}", path.display()))?
+            .len();
+        if len < offset {
+            offset = 0;
+            pending.clear();
+        }
+        let budget = max_bytes.saturating_sub(output.len() + pending.len());
+        if budget == 0 {
+            capped = true;
+            break;
+        }
+        if len == offset {
+            continue;
+        }
+        let bytes = read_from(path, offset, budget)?;
+        offset += bytes.len() as u64;
+        pending.extend_from_slice(&bytes);
+        if let Some(end) = pending.iter().rposition(|byte| *byte == b'\n') {
+            let lines: Vec<u8> = pending.drain(..=end).collect();
+            let text = String::from_utf8_lossy(&lines).into_owned();
+            let _ = tx.send(Ok(ToolChunk::Output(text.clone())));
+            output.push_str(&text);
+        }
+    }
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        let _ = tx.send(Ok(ToolChunk::Output(text.clone())));
+        output.push_str(&text);
+    }
+    if capped {
+        output.push_str(&format!("\n[stopped watching after {} bytes]", max_bytes));
+    }
+    Ok(ToolResult::success(output))
+}
+
+#[async_trait]
+impl Tool for TailFileTool {
+    fn name(&self) -> &str {
+        "tail_file"
+    }
+
+    fn description(&self) -> &str {
+        "Returns the last lines of a file, optionally watching it for new lines for a while (e.g. the log of a process started with bash)"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File to read, inside the workspace"
+                },
+                "lines": {
+                    "type": "integer",
+                    "description": format!("Lines to return from the end of the file (at most {})", MAX_LINES),
+                    "default": DEFAULT_LINES,
+                    "minimum": 0
+                },
+                "follow_secs": {
+                    "type": "integer",
+                    "description": format!(
+                        "Seconds to keep watching for appended lines (at most {}); 0 returns at once",
+                        MAX_FOLLOW_SECS
+                    ),
+                    "default": 0,
+                    "minimum": 0
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Cap on the returned output (bytes)",
+                    "minimum": 1
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn workspace_args(&self) -> &[&'static str] {
+        &["path"]
+    }
+
+    fn confined(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        collect_tool_stream(self.execute_streaming(args).await?, |_| {}).await
+    }
+
+    async fn execute_streaming(&self, args: Value) -> Result<ToolStream> {
+        let args: TailFileArgs =
+            serde_json::from_value(args).context("Failed to parse tail_file arguments")?;
+        let path = confine(&args.path, args.workspace_root.as_deref())?;
+        let lines = args.lines.unwrap_or(DEFAULT_LINES).min(MAX_LINES);
+        let max_bytes = args
+            .max_bytes
+            .map_or(self.max_bytes, |bytes| bytes.min(self.max_bytes));
+        let follow = Duration::from_secs(args.follow_secs.min(MAX_FOLLOW_SECS));
+        let (tail, offset) = read_tail(&path, lines, max_bytes)?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let result = watch(&path, tail, offset, follow, max_bytes, &tx).await;
+            let _ = tx.send(result.map(ToolChunk::Finished));
+        });
+        Ok(channel_stream(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn args(dir: &TempDir, extra: Value) -> Value {
+        let mut args = serde_json::json!({
+            "path": "app.log",
+            "workspace_root": dir.path().to_string_lossy(),
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        args
+    }
+
+    #[tokio::test]
+    async fn test_tail_file_last_lines() {
+        let dir = TempDir::new().unwrap();
+        let log: String = (1..=50).map(|n| format!("line {}\n", n)).collect();
+        fs::write(dir.path().join("app.log"), log).unwrap();
+
+        let tool = TailFileTool::new();
+        let result = tool
+            .execute(args(&dir, serde_json::json!({"lines": 3})))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "line 48\nline 49\nline 50\n");
+
+        // A byte cap drops the line it cuts through
+        let result = tool
+            .execute(args(&dir, serde_json::json!({"lines": 3, "max_bytes": 12})))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "line 50\n");
+    }
+
+    #[tokio::test]
+    async fn test_tail_file_follows_appended_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "started\n").unwrap();
+
+        let writer = tokio::spawn(async move {
+            time::sleep(Duration::from_millis(300)).await;
+            let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(b"ready\npartial").unwrap();
+        });
+        let tool = TailFileTool::new();
+        let mut seen = Vec::new();
+        let stream = tool
+            .execute_streaming(args(&dir, serde_json::json!({"follow_secs": 1})))
+            .await
+            .unwrap();
+        let result = collect_tool_stream(stream, |text| seen.push(text.to_string()))
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert_eq!(seen, vec!["started\n", "ready\n", "partial"]);
+        assert_eq!(result.output, "started\nready\npartial");
+    }
+
+    #[tokio::test]
+    async fn test_tail_file_stays_in_workspace() {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(dir.path().join("secret.log"), "secret\n").unwrap();
+
+        let tool = TailFileTool::new();
+        let err = tool
+            .execute(serde_json::json!({
+                "path": "../secret.log",
+                "workspace_root": workspace.to_string_lossy(),
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the workspace"));
+    }
+}
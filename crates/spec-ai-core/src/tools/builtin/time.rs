@@ -0,0 +1,251 @@
+//! Date, time and calendar tools
+//!
+//! `get_time` reports the current time and resolves relative dates so the
+//! model does not have to do calendar arithmetic in its head;
+//! `schedule_lookup` lists calendar events and free working hours for a
+//! range of days. Both read from the agent's [`Clock`].
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use spec_ai_plugin::{ToolArgs, ToolSchema};
+
+use crate::clock::{free_slots, resolve_date, CalendarEvent, Clock, Zone};
+use crate::tools::{Tool, ToolResult};
+
+const MAX_LOOKUP_DAYS: u32 = 31;
+
+fn parse_args<T: ToolArgs>(tool: &str, args: Value) -> Result<T> {
+    T::from_args(args).with_context(|| format!("Failed to parse {} arguments", tool))
+}
+
+fn time_of_day(time: &DateTime<FixedOffset>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+/// Reports the current date and time and resolves relative dates
+pub struct GetTimeTool {
+    clock: Clock,
+}
+
+impl GetTimeTool {
+    pub fn new(clock: Clock) -> Self {
+        Self { clock }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct GetTimeArgs {
+    /// Date to resolve, e.g. "next tuesday", "in 3 days", "2 weeks ago" or "2025-03-01"
+    date: Option<String>,
+    /// Report the time at another UTC offset instead, e.g. "UTC" or "+09:00"
+    timezone: Option<String>,
+}
+
+#[async_trait]
+impl Tool for GetTimeTool {
+    fn name(&self) -> &str {
+        "get_time"
+    }
+
+    fn description(&self) -> &str {
+        "Get the current date, time and timezone, and resolve relative dates like \"next tuesday\" to calendar dates"
+    }
+
+    fn parameters(&self) -> Value {
+        GetTimeArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: GetTimeArgs = parse_args(self.name(), args)?;
+        let zone = match args.timezone.as_deref() {
+            Some(timezone) => match Zone::parse(timezone) {
+                Ok(zone) => zone,
+                Err(err) => return Ok(ToolResult::failure(err.to_string())),
+            },
+            None => self.clock.zone(),
+        };
+        let now = zone.convert(chrono::Utc::now());
+        let today = now.date_naive();
+
+        let mut output = json!({
+            "now": now.to_rfc3339(),
+            "date": today.to_string(),
+            "time": time_of_day(&now),
+            "weekday": now.format("%A").to_string(),
+            "timezone": zone.label(&now),
+        });
+        if let Some(expression) = args.date {
+            let Some(date) = resolve_date(&expression, today) else {
+                return Ok(ToolResult::failure(format!(
+                    "Could not resolve date '{}'; try forms like 'next friday', 'in 3 days' or 'YYYY-MM-DD'",
+                    expression
+                )));
+            };
+            output["resolved"] = json!({
+                "expression": expression,
+                "date": date.to_string(),
+                "weekday": date.format("%A").to_string(),
+                "days_from_today": (date - today).num_days(),
+            });
+        }
+        Ok(ToolResult::success(output.to_string()))
+    }
+}
+
+/// Lists calendar events and free working hours from the configured calendar
+pub struct ScheduleLookupTool {
+    clock: Clock,
+}
+
+impl ScheduleLookupTool {
+    pub fn new(clock: Clock) -> Self {
+        Self { clock }
+    }
+}
+
+#[derive(Debug, Deserialize, ToolArgs)]
+struct ScheduleLookupArgs {
+    /// First day to look at, e.g. "today", "next monday" or "2025-03-01" (default: today)
+    date: Option<String>,
+    /// Number of days to cover, 1 to 31 (default: 1)
+    days: Option<u32>,
+}
+
+fn event_json(event: &CalendarEvent) -> Value {
+    json!({
+        "summary": event.summary,
+        "start": event.start.to_rfc3339(),
+        "end": event.end.to_rfc3339(),
+        "all_day": event.all_day,
+        "location": event.location,
+    })
+}
+
+#[async_trait]
+impl Tool for ScheduleLookupTool {
+    fn name(&self) -> &str {
+        "schedule_lookup"
+    }
+
+    fn description(&self) -> &str {
+        "Look up calendar events and free time (working hours) for a day or range of days"
+    }
+
+    fn parameters(&self) -> Value {
+        ScheduleLookupArgs::schema()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let args: ScheduleLookupArgs = parse_args(self.name(), args)?;
+        if self.clock.calendar_file().is_none() {
+            return Ok(ToolResult::failure(
+                "No calendar configured; set [time].calendar_file to an .ics file",
+            ));
+        }
+
+        let now = self.clock.now();
+        let today = now.date_naive();
+        let first = match args.date.as_deref() {
+            Some(expression) => match resolve_date(expression, today) {
+                Some(date) => date,
+                None => {
+                    return Ok(ToolResult::failure(format!(
+                        "Could not resolve date '{}'",
+                        expression
+                    )))
+                }
+            },
+            None => today,
+        };
+        let days = args.days.unwrap_or(1).clamp(1, MAX_LOOKUP_DAYS);
+        let last = first + Duration::days(days as i64 - 1);
+
+        let (Some((from, _)), Some((_, to))) =
+            (self.clock.day_bounds(first), self.clock.day_bounds(last))
+        else {
+            return Ok(ToolResult::failure("Date is out of range"));
+        };
+        let events = self.clock.events_between(from, to)?;
+
+        let zone = self.clock.zone();
+        let mut schedule = Vec::new();
+        for offset in 0..days {
+            let date = first + Duration::days(offset as i64);
+            let Some((start, end)) = self.clock.day_bounds(date) else {
+                continue;
+            };
+            let day_events: Vec<CalendarEvent> = events
+                .iter()
+                .filter(|event| event.overlaps(start, end))
+                .cloned()
+                .collect();
+            let free: Vec<Value> = free_slots(&zone, date, &day_events)
+                .iter()
+                .map(|(start, end)| json!({ "start": time_of_day(start), "end": time_of_day(end) }))
+                .collect();
+            schedule.push(json!({
+                "date": date.to_string(),
+                "weekday": date.format("%A").to_string(),
+                "events": day_events.iter().map(event_json).collect::<Vec<_>>(),
+                "free": free,
+            }));
+        }
+
+        Ok(ToolResult::success(
+            json!({
+                "timezone": zone.label(&now),
+                "days": schedule,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn get_time_resolves_relative_dates() {
+        let tool = GetTimeTool::new(Clock::new(Zone::parse("UTC").unwrap()));
+        let result = tool.execute(json!({ "date": "in 3 days" })).await.unwrap();
+        assert!(result.success);
+        let output: Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(output["timezone"], "UTC");
+        assert_eq!(output["resolved"]["days_from_today"], 3);
+
+        let result = tool.execute(json!({ "date": "whenever" })).await.unwrap();
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn schedule_lookup_lists_events_and_free_time() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Planning\nDTSTART:20250310T130000\nDTEND:20250310T140000\nEND:VEVENT\nEND:VCALENDAR\n"
+        )
+        .unwrap();
+        let clock = Clock::new(Zone::parse("UTC").unwrap()).with_calendar(file.path());
+        let tool = ScheduleLookupTool::new(clock);
+
+        let result = tool
+            .execute(json!({ "date": "2025-03-10", "days": 2 }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let output: Value = serde_json::from_str(&result.output).unwrap();
+        let days = output["days"].as_array().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0]["events"][0]["summary"], "Planning");
+        assert_eq!(days[0]["free"][1]["start"], "14:00");
+        assert_eq!(days[1]["events"].as_array().unwrap().len(), 0);
+
+        let unconfigured = ScheduleLookupTool::new(Clock::default());
+        assert!(!unconfigured.execute(json!({})).await.unwrap().success);
+    }
+}
@@ -0,0 +1,431 @@
+//! Composite tools defined in configuration
+//!
+//! A composite tool runs a fixed sequence of registered tools as a single
+//! call, so the model sees e.g. `fetch_and_summarize` instead of having to
+//! chain `web_scraper` and `summarize` itself. Step arguments are JSON
+//! templates filled from the composite's arguments and earlier outputs (see
+//! [`CompositeStep`]). Policy rules apply to the composite's own name; the
+//! steps it runs are not checked individually.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use super::{Tool, ToolRegistry, ToolResult};
+use crate::config::{CompositeStep, CompositeToolConfig};
+
+/// Argument name used to pass the previous output when the next tool's
+/// schema does not single out one required argument
+const PIPED_ARG: &str = "input";
+
+fn placeholder_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.\-]+)\s*\}\}").expect("valid regex"))
+}
+
+/// How a step's arguments are produced
+enum StepArgs {
+    /// The composite's own arguments, unchanged
+    Passthrough,
+    /// The previous step's raw output under the given argument name
+    Piped(String),
+    /// A configured template
+    Template(Value),
+}
+
+struct Step {
+    tool: Arc<dyn Tool>,
+    args: StepArgs,
+}
+
+/// Tool that runs other tools in sequence
+pub struct CompositeTool {
+    name: String,
+    description: String,
+    parameters: Value,
+    steps: Vec<Step>,
+}
+
+impl CompositeTool {
+    /// Build a composite from its configuration, resolving step tools in
+    /// `registry`
+    pub fn from_config(
+        name: &str,
+        config: &CompositeToolConfig,
+        registry: &ToolRegistry,
+    ) -> Result<Self> {
+        let configured = config.steps();
+        if configured.is_empty() {
+            bail!("composite tool '{}' has no steps", name);
+        }
+
+        let mut steps = Vec::with_capacity(configured.len());
+        for (index, CompositeStep { tool, args }) in configured.into_iter().enumerate() {
+            let resolved = registry
+                .get(&tool)
+                .ok_or_else(|| anyhow!("composite tool '{}' uses unknown tool '{}'", name, tool))?;
+            let args = match args {
+                Some(template) => StepArgs::Template(template),
+                None if index == 0 => StepArgs::Passthrough,
+                None => StepArgs::Piped(piped_arg_name(&resolved.parameters())),
+            };
+            steps.push(Step {
+                tool: resolved,
+                args,
+            });
+        }
+
+        let parameters = match config.parameters() {
+            Some(declared) if !declared.is_empty() => {
+                let properties: Map<String, Value> = declared
+                    .iter()
+                    .map(|(arg, description)| {
+                        (
+                            arg.clone(),
+                            json!({"type": "string", "description": description}),
+                        )
+                    })
+                    .collect();
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": declared.keys().collect::<Vec<_>>(),
+                })
+            }
+            _ if matches!(steps[0].args, StepArgs::Passthrough) => steps[0].tool.parameters(),
+            _ => json!({"type": "object", "properties": {}}),
+        };
+
+        let description = config.description().map(str::to_string).unwrap_or_else(|| {
+            let names: Vec<&str> = steps.iter().map(|step| step.tool.name()).collect();
+            format!(
+                "Runs {} in sequence, passing each output to the next step",
+                names.join(" then ")
+            )
+        });
+
+        Ok(Self {
+            name: name.to_string(),
+            description,
+            parameters,
+            steps,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for CompositeTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    fn session_scoped(&self) -> bool {
+        self.steps.iter().any(|step| step.tool.session_scoped())
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let session_id = args.get("session_id").cloned();
+        let mut outputs: Vec<Value> = Vec::with_capacity(self.steps.len());
+        let mut last = ToolResult::success(String::new());
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let mut step_args = match &step.args {
+                StepArgs::Passthrough => args.clone(),
+                StepArgs::Piped(name) => json!({ name.as_str(): last.output.as_str() }),
+                StepArgs::Template(template) => render(template, &args, &outputs)
+                    .with_context(|| format!("step {} ({})", index + 1, step.tool.name()))?,
+            };
+            if step.tool.session_scoped() {
+                if let (Some(object), Some(session_id)) = (step_args.as_object_mut(), &session_id) {
+                    object
+                        .entry("session_id")
+                        .or_insert_with(|| session_id.clone());
+                }
+            }
+
+            last = step.tool.execute(step_args).await?;
+            if !last.success {
+                return Ok(ToolResult::failure(format!(
+                    "step {} ({}) failed: {}",
+                    index + 1,
+                    step.tool.name(),
+                    last.error.as_deref().unwrap_or("unknown error")
+                )));
+            }
+            outputs.push(output_value(&last.output));
+        }
+
+        Ok(last)
+    }
+}
+
+/// Register every composite in `composites`. Composites may use each other,
+/// so they are added once all the tools they reference exist.
+pub fn register_composite_tools(
+    registry: &mut ToolRegistry,
+    composites: &HashMap<String, CompositeToolConfig>,
+) -> Result<()> {
+    let mut pending: Vec<(&String, &CompositeToolConfig)> = composites.iter().collect();
+    pending.sort_by_key(|(name, _)| name.as_str());
+    for (name, _) in &pending {
+        if registry.has(name) {
+            bail!(
+                "composite tool '{}' conflicts with an existing tool of the same name",
+                name
+            );
+        }
+    }
+
+    while !pending.is_empty() {
+        let (ready, waiting): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|(_, config)| config.steps().iter().all(|step| registry.has(&step.tool)));
+        if ready.is_empty() {
+            let (name, config) = waiting[0];
+            let missing = config
+                .steps()
+                .into_iter()
+                .find(|step| !registry.has(&step.tool))
+                .map(|step| step.tool)
+                .unwrap_or_default();
+            bail!(
+                "composite tool '{}' uses unknown tool '{}' (or composites reference each other in a cycle)",
+                name,
+                missing
+            );
+        }
+        for (name, config) in ready {
+            let tool = CompositeTool::from_config(name, config, registry)?;
+            registry.register(Arc::new(tool));
+        }
+        pending = waiting;
+    }
+    Ok(())
+}
+
+/// Argument that receives piped output: the tool's only required argument,
+/// or its only argument, falling back to `input`
+fn piped_arg_name(schema: &Value) -> String {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if let [only] = required.as_slice() {
+        return only.to_string();
+    }
+    match schema["properties"].as_object() {
+        Some(properties) if properties.len() == 1 => properties.keys().next().unwrap().clone(),
+        _ => PIPED_ARG.to_string(),
+    }
+}
+
+/// Step output as JSON when it parses, otherwise as a string
+fn output_value(output: &str) -> Value {
+    serde_json::from_str(output).unwrap_or_else(|_| Value::String(output.to_string()))
+}
+
+/// Fill placeholders in a template. A string consisting of a single
+/// placeholder takes the referenced value as-is, keeping its JSON type.
+fn render(template: &Value, args: &Value, outputs: &[Value]) -> Result<Value> {
+    match template {
+        Value::String(text) => {
+            let re = placeholder_re();
+            if let Some(captures) = re.captures(text.trim()) {
+                if captures[0].len() == text.trim().len() {
+                    return lookup(&captures[1], args, outputs);
+                }
+            }
+            let mut rendered = String::with_capacity(text.len());
+            let mut last = 0;
+            for captures in re.captures_iter(text) {
+                let whole = captures.get(0).unwrap();
+                rendered.push_str(&text[last..whole.start()]);
+                match lookup(&captures[1], args, outputs)? {
+                    Value::String(value) => rendered.push_str(&value),
+                    value => rendered.push_str(&value.to_string()),
+                }
+                last = whole.end();
+            }
+            rendered.push_str(&text[last..]);
+            Ok(Value::String(rendered))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| render(item, args, outputs))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), render(value, args, outputs)?)))
+            .collect::<Result<Map<_, _>>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+/// Resolve a dotted placeholder path
+fn lookup(path: &str, args: &Value, outputs: &[Value]) -> Result<Value> {
+    let mut segments = path.split('.');
+    let head = segments.next().unwrap_or_default();
+    let mut value = match head {
+        "previous" => outputs
+            .last()
+            .ok_or_else(|| anyhow!("{{{{previous}}}} used in the first step"))?,
+        "steps" => {
+            let index: usize = segments
+                .next()
+                .and_then(|index| index.parse().ok())
+                .ok_or_else(|| anyhow!("expected a step number in {{{{{}}}}}", path))?;
+            outputs
+                .get(index)
+                .ok_or_else(|| anyhow!("{{{{{}}}}} refers to a step that has not run", path))?
+        }
+        name => args
+            .get(name)
+            .ok_or_else(|| anyhow!("missing argument '{}'", name))?,
+    };
+    for segment in segments {
+        value = match value {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            other => other.get(segment),
+        }
+        .ok_or_else(|| anyhow!("{{{{{}}}}} not found in output", path))?;
+    }
+    Ok(value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CompositeToolDefinition;
+    use crate::tools::builtin::EchoTool;
+
+    struct UpperTool;
+
+    #[async_trait]
+    impl Tool for UpperTool {
+        fn name(&self) -> &str {
+            "upper"
+        }
+
+        fn description(&self) -> &str {
+            "Uppercases text"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {"text": {"type": "string"}},
+                "required": ["text"]
+            })
+        }
+
+        async fn execute(&self, args: Value) -> Result<ToolResult> {
+            let text = args["text"].as_str().unwrap_or_default();
+            Ok(ToolResult::success(
+                json!({"text": text.to_uppercase(), "length": text.len()}).to_string(),
+            ))
+        }
+    }
+
+    fn base_registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new()));
+        registry.register(Arc::new(UpperTool));
+        registry
+    }
+
+    #[tokio::test]
+    async fn pipeline_pipes_output_into_next_tool() {
+        let mut registry = base_registry();
+        let composites = HashMap::from([(
+            "shout".to_string(),
+            CompositeToolConfig::Pipeline(vec!["echo".into(), "upper".into()]),
+        )]);
+        register_composite_tools(&mut registry, &composites).unwrap();
+
+        let tool = registry.get("shout").unwrap();
+        // The first tool's schema is advertised
+        assert_eq!(tool.parameters()["required"][0], "message");
+        let result = tool.execute(json!({"message": "hi there"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(output_value(&result.output)["text"], "HI THERE");
+    }
+
+    #[tokio::test]
+    async fn templates_read_arguments_and_earlier_outputs() {
+        let mut registry = base_registry();
+        let definition = CompositeToolDefinition {
+            description: Some("Shout and report".into()),
+            parameters: [("word".to_string(), "Word to shout".to_string())].into(),
+            steps: vec![
+                CompositeStep {
+                    tool: "upper".into(),
+                    args: Some(json!({"text": "{{word}}!"})),
+                },
+                CompositeStep {
+                    tool: "echo".into(),
+                    args: Some(json!({
+                        "message": "{{steps.0.text}} has {{previous.length}} chars"
+                    })),
+                },
+            ],
+        };
+        let composites = HashMap::from([(
+            "report".to_string(),
+            CompositeToolConfig::Definition(definition),
+        )]);
+        register_composite_tools(&mut registry, &composites).unwrap();
+
+        let tool = registry.get("report").unwrap();
+        assert_eq!(tool.description(), "Shout and report");
+        assert_eq!(tool.parameters()["required"][0], "word");
+        let result = tool.execute(json!({"word": "hey"})).await.unwrap();
+        assert_eq!(result.output, "HEY! has 4 chars");
+
+        let missing = tool.execute(json!({})).await;
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn composites_may_build_on_each_other_but_not_unknown_tools() {
+        let mut registry = base_registry();
+        let composites = HashMap::from([
+            (
+                "outer".to_string(),
+                CompositeToolConfig::Pipeline(vec!["inner".into(), "echo".into()]),
+            ),
+            (
+                "inner".to_string(),
+                CompositeToolConfig::Pipeline(vec!["echo".into(), "upper".into()]),
+            ),
+        ]);
+        register_composite_tools(&mut registry, &composites).unwrap();
+        assert!(registry.has("outer"));
+
+        let mut registry = base_registry();
+        let unknown = HashMap::from([(
+            "broken".to_string(),
+            CompositeToolConfig::Pipeline(vec!["echo".into(), "nope".into()]),
+        )]);
+        let err = register_composite_tools(&mut registry, &unknown).unwrap_err();
+        assert!(err.to_string().contains("'nope'"));
+
+        let clash = HashMap::from([(
+            "echo".to_string(),
+            CompositeToolConfig::Pipeline(vec!["upper".into()]),
+        )]);
+        assert!(register_composite_tools(&mut registry, &clash).is_err());
+    }
+}
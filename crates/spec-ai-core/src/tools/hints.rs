@@ -0,0 +1,180 @@
+//! Latency and cost hints advertised alongside tool descriptions
+//!
+//! Tiers start from `[tools.<name>]` configuration. Once a tool has been run a
+//! few times its latency tier is derived from the measured average instead, so
+//! the hints track reality without manual tuning. Hints name tiers only: a
+//! description that changed with every measurement would defeat prompt
+//! caching. `/tools stats` shows the measured milliseconds. Deterministic runs
+//! advertise configured tiers only, since a measured tier can flip between runs.
+
+use crate::config::{ToolSettings, ToolTier};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Observations required before measured latency overrides configuration
+const MIN_SAMPLES: u32 = 3;
+
+/// Weight of the newest sample in the moving average
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Upper bounds (milliseconds) of the low and medium latency tiers
+const LOW_LATENCY_MS: f64 = 500.0;
+const MEDIUM_LATENCY_MS: f64 = 3_000.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct LatencyStats {
+    samples: u32,
+    average_ms: f64,
+}
+
+/// Configured tiers plus measured latencies for every tool
+#[derive(Debug, Default)]
+pub struct ToolHints {
+    configured: HashMap<String, (Option<ToolTier>, Option<ToolTier>)>,
+    observed: Mutex<HashMap<String, LatencyStats>>,
+}
+
+impl ToolHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take configured tiers from tool settings
+    pub fn configure(&mut self, name: &str, settings: &ToolSettings) {
+        if settings.latency_tier.is_some() || settings.cost_tier.is_some() {
+            self.configured.insert(
+                name.to_string(),
+                (settings.latency_tier, settings.cost_tier),
+            );
+        }
+    }
+
+    /// Record how long a real (non-cached) execution took
+    pub fn record_latency(&self, name: &str, elapsed: Duration) {
+        let Ok(mut observed) = self.observed.lock() else {
+            return;
+        };
+        let stats = observed.entry(name.to_string()).or_default();
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        stats.average_ms = if stats.samples == 0 {
+            ms
+        } else {
+            EWMA_ALPHA * ms + (1.0 - EWMA_ALPHA) * stats.average_ms
+        };
+        stats.samples = stats.samples.saturating_add(1);
+    }
+
+    /// Average measured latency, once enough samples exist
+    pub fn measured_latency(&self, name: &str) -> Option<Duration> {
+        let observed = self.observed.lock().ok()?;
+        observed
+            .get(name)
+            .filter(|s| s.samples >= MIN_SAMPLES)
+            .map(|s| Duration::from_secs_f64(s.average_ms / 1000.0))
+    }
+
+    /// Effective latency tier: measured when available, otherwise configured
+    pub fn latency_tier(&self, name: &str) -> Option<ToolTier> {
+        match self.measured_latency(name) {
+            Some(latency) => Some(tier_for_latency(latency)),
+            None => self.configured.get(name).and_then(|(latency, _)| *latency),
+        }
+    }
+
+    pub fn cost_tier(&self, name: &str) -> Option<ToolTier> {
+        self.configured.get(name).and_then(|(_, cost)| *cost)
+    }
+
+    /// Hint suffix such as "[latency: low, cost: high]"
+    pub fn hint(&self, name: &str) -> Option<String> {
+        format_hint(self.latency_tier(name), self.cost_tier(name))
+    }
+
+    /// Hint from configured tiers alone, ignoring measurements, so it stays
+    /// the same from one run to the next
+    pub fn configured_hint(&self, name: &str) -> Option<String> {
+        let (latency, cost) = self.configured.get(name).copied().unwrap_or_default();
+        format_hint(latency, cost)
+    }
+}
+
+fn format_hint(latency: Option<ToolTier>, cost: Option<ToolTier>) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(tier) = latency {
+        parts.push(format!("latency: {}", tier.as_str()));
+    }
+    if let Some(tier) = cost {
+        parts.push(format!("cost: {}", tier.as_str()));
+    }
+    (!parts.is_empty()).then(|| format!("[{}]", parts.join(", ")))
+}
+
+fn tier_for_latency(latency: Duration) -> ToolTier {
+    let ms = latency.as_secs_f64() * 1000.0;
+    if ms <= LOW_LATENCY_MS {
+        ToolTier::Low
+    } else if ms <= MEDIUM_LATENCY_MS {
+        ToolTier::Medium
+    } else {
+        ToolTier::High
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_tiers_until_measurements_exist() {
+        let mut hints = ToolHints::new();
+        hints.configure(
+            "web_search",
+            &ToolSettings {
+                latency_tier: Some(ToolTier::High),
+                cost_tier: Some(ToolTier::Medium),
+                ..ToolSettings::default()
+            },
+        );
+        assert_eq!(
+            hints.hint("web_search").as_deref(),
+            Some("[latency: high, cost: medium]")
+        );
+        assert!(hints.hint("echo").is_none());
+
+        for _ in 0..MIN_SAMPLES {
+            hints.record_latency("web_search", Duration::from_millis(100));
+        }
+        assert_eq!(hints.latency_tier("web_search"), Some(ToolTier::Low));
+        assert_eq!(
+            hints.hint("web_search").as_deref(),
+            Some("[latency: low, cost: medium]")
+        );
+
+        // Measurements within the tier leave the description unchanged
+        hints.record_latency("web_search", Duration::from_millis(400));
+        assert_eq!(
+            hints.hint("web_search").as_deref(),
+            Some("[latency: low, cost: medium]")
+        );
+
+        // Configured hints ignore measurements entirely
+        assert_eq!(
+            hints.configured_hint("web_search").as_deref(),
+            Some("[latency: high, cost: medium]")
+        );
+        assert!(hints.configured_hint("echo").is_none());
+    }
+
+    #[test]
+    fn measured_latency_follows_recent_calls() {
+        let hints = ToolHints::new();
+        for _ in 0..MIN_SAMPLES {
+            hints.record_latency("bash", Duration::from_millis(200));
+        }
+        for _ in 0..10 {
+            hints.record_latency("bash", Duration::from_secs(5));
+        }
+        assert_eq!(hints.latency_tier("bash"), Some(ToolTier::High));
+    }
+}
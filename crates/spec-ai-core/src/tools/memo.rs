@@ -0,0 +1,209 @@
+//! Opt-in memoization of tool results
+//!
+//! Tools enabled in `[tools.<name>]` with `memoize = true` have successful
+//! results cached by a hash of their canonical JSON arguments. Entries expire
+//! after the configured TTL; when an argument names an existing file (the
+//! `path`-like keys used by the file tools) the file's content hash is stored
+//! too, so editing the file invalidates the entry.
+
+use super::ToolResult;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Argument keys that may name a file whose contents affect the result
+const FILE_ARG_KEYS: [&str; 4] = ["path", "file", "file_path", "filename"];
+
+/// Entries kept per cache before the oldest are evicted
+const MAX_ENTRIES: usize = 512;
+
+/// Memoization settings for a single tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoPolicy {
+    pub ttl: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct MemoEntry {
+    result: ToolResult,
+    stored_at: Instant,
+    /// Content hashes of files referenced by the arguments (None = missing)
+    files: Vec<(String, Option<blake3::Hash>)>,
+}
+
+/// Cache of tool results keyed by tool name and argument hash
+#[derive(Debug, Default)]
+pub struct ToolMemoCache {
+    policies: HashMap<String, MemoPolicy>,
+    entries: Mutex<HashMap<(String, blake3::Hash), MemoEntry>>,
+}
+
+impl ToolMemoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable memoization for `tool` with the given policy
+    pub fn enable(&mut self, tool: impl Into<String>, policy: MemoPolicy) {
+        self.policies.insert(tool.into(), policy);
+    }
+
+    pub fn is_enabled(&self, tool: &str) -> bool {
+        self.policies.contains_key(tool)
+    }
+
+    /// Cached result for this call, if still fresh and its files are unchanged
+    pub fn lookup(&self, tool: &str, args: &Value) -> Option<ToolResult> {
+        let policy = self.policies.get(tool)?;
+        let key = (tool.to_string(), args_hash(args));
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries.get(&key)?;
+
+        let fresh = entry.stored_at.elapsed() <= policy.ttl
+            && entry
+                .files
+                .iter()
+                .all(|(path, hash)| file_hash(Path::new(path)) == *hash);
+        if fresh {
+            Some(entry.result.clone())
+        } else {
+            entries.remove(&key);
+            None
+        }
+    }
+
+    /// Remember a successful result; failures are never cached
+    pub fn store(&self, tool: &str, args: &Value, result: &ToolResult) {
+        if !result.success || !self.is_enabled(tool) {
+            return;
+        }
+        let files = referenced_files(args)
+            .into_iter()
+            .map(|path| {
+                let hash = file_hash(Path::new(&path));
+                (path, hash)
+            })
+            .collect();
+
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() >= MAX_ENTRIES {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            (tool.to_string(), args_hash(args)),
+            MemoEntry {
+                result: result.clone(),
+                stored_at: Instant::now(),
+                files,
+            },
+        );
+    }
+
+    /// Drop all cached results
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+/// Hash of the arguments in canonical form (object keys sorted)
+fn args_hash(args: &Value) -> blake3::Hash {
+    blake3::hash(canonical_json(args).as_bytes())
+}
+
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", Value::String(k.clone()), canonical_json(&map[k])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn referenced_files(args: &Value) -> Vec<String> {
+    FILE_ARG_KEYS
+        .iter()
+        .filter_map(|key| args.get(*key).and_then(Value::as_str))
+        .filter(|path| Path::new(path).is_file())
+        .map(str::to_string)
+        .collect()
+}
+
+fn file_hash(path: &Path) -> Option<blake3::Hash> {
+    std::fs::read(path).ok().map(|bytes| blake3::hash(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache_for(tool: &str, ttl: Duration) -> ToolMemoCache {
+        let mut cache = ToolMemoCache::new();
+        cache.enable(tool, MemoPolicy { ttl });
+        cache
+    }
+
+    #[test]
+    fn hits_regardless_of_argument_key_order() {
+        let cache = cache_for("web_search", Duration::from_secs(60));
+        let args = json!({"query": "rust", "count": 5});
+        cache.store("web_search", &args, &ToolResult::success("results"));
+
+        let reordered = json!({"count": 5, "query": "rust"});
+        let hit = cache.lookup("web_search", &reordered).unwrap();
+        assert_eq!(hit.output, "results");
+        assert!(cache
+            .lookup("web_search", &json!({"query": "go"}))
+            .is_none());
+        assert!(cache.lookup("echo", &args).is_none());
+    }
+
+    #[test]
+    fn expired_and_failed_results_are_not_served() {
+        let cache = cache_for("echo", Duration::ZERO);
+        cache.store("echo", &json!({}), &ToolResult::success("hi"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.lookup("echo", &json!({})).is_none());
+
+        let cache = cache_for("echo", Duration::from_secs(60));
+        cache.store("echo", &json!({}), &ToolResult::failure("boom"));
+        assert!(cache.lookup("echo", &json!({})).is_none());
+    }
+
+    #[test]
+    fn file_changes_invalidate_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "v1").unwrap();
+
+        let cache = cache_for("file_read", Duration::from_secs(60));
+        let args = json!({"path": path.to_string_lossy()});
+        cache.store("file_read", &args, &ToolResult::success("v1"));
+        assert!(cache.lookup("file_read", &args).is_some());
+
+        std::fs::write(&path, "v2").unwrap();
+        assert!(cache.lookup("file_read", &args).is_none());
+    }
+}
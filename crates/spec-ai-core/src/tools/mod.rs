@@ -1,17 +1,28 @@
 pub mod builtin;
+pub mod composite;
+pub mod hints;
+pub mod memo;
 pub mod plugin_adapter;
+pub mod remote;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 use tracing::debug;
 
 use self::builtin::{
-    AudioTranscriptionTool, BashTool, CodeSearchTool, EchoTool, FileExtractTool, FileReadTool,
-    FileWriteTool, GraphTool, MathTool, PromptUserTool, SearchTool, ShellTool,
+    AudioTranscriptionTool, BashTool, CodeSearchTool, EchoTool, EntityTimelineTool,
+    FileExtractTool, FileReadTool, FileWriteTool, GetTimeTool, GraphTool, IngestCiLogTool,
+    KnowledgeGraphStore, LookupEntityTool, MathTool, ProcessManager, ProcessStartTool,
+    ProcessStatusTool, ProcessStopTool, PromptUserTool, RelateEntitiesTool, RememberFactTool,
+    RemindMeTool, ScheduleLookupTool, SearchTool, ShellTool, TailFileTool,
 };
 
 #[cfg(feature = "api")]
@@ -19,10 +30,22 @@ use self::builtin::WebSearchTool;
 
 #[cfg(feature = "web-scraping")]
 use self::builtin::WebScraperTool;
+
+#[cfg(feature = "ops")]
+use self::builtin::{
+    CloudStatusTool, Kubectl, KubectlDescribeTool, KubectlGetTool, KubectlLogsTool,
+};
+use crate::clock::Clock;
+use crate::config::{CompositeToolConfig, GraphConfig, ToolSettings};
 use crate::embeddings::EmbeddingsClient;
 use crate::persistence::Persistence;
 
+pub use composite::CompositeTool;
+pub use hints::ToolHints;
+pub use memo::{MemoPolicy, ToolMemoCache};
 pub use plugin_adapter::PluginToolAdapter;
+pub use remote::{RemoteTool, RemoteToolExecutor, REMOTE_PREFIX};
+pub use tokio_util::sync::CancellationToken;
 
 #[cfg(feature = "openai")]
 use async_openai::types::ChatCompletionTool;
@@ -58,6 +81,120 @@ impl ToolResult {
     }
 }
 
+/// Bounds on a tool call. A call that outlives `timeout`, or is still
+/// running when `cancel` is cancelled, is dropped (killing any process it
+/// started) and reported as a failed [`ToolResult`].
+#[derive(Debug, Clone, Default)]
+pub struct ToolLimits {
+    /// How long the call may run; unbounded when `None`
+    pub timeout: Option<Duration>,
+    pub cancel: CancellationToken,
+}
+
+impl ToolLimits {
+    /// Run `call` within these limits
+    async fn bound(
+        &self,
+        name: &str,
+        call: impl Future<Output = Result<ToolResult>>,
+    ) -> Result<ToolResult> {
+        let timed = async {
+            let Some(timeout) = self.timeout else {
+                return call.await;
+            };
+            match tokio::time::timeout(timeout, call).await {
+                Ok(result) => result,
+                Err(_) => Ok(ToolResult::failure(format!(
+                    "Tool '{}' timed out after {:?}",
+                    name, timeout
+                ))),
+            }
+        };
+        tokio::select! {
+            result = timed => result,
+            _ = self.cancel.cancelled() => {
+                Ok(ToolResult::failure(format!("Tool '{}' was cancelled", name)))
+            }
+        }
+    }
+}
+
+/// A piece of a streaming tool's output
+#[derive(Debug, Clone)]
+pub enum ToolChunk {
+    /// Output written since the previous chunk; not necessarily whole lines
+    Output(String),
+    /// The tool's result, always the last item of the stream
+    Finished(ToolResult),
+}
+
+/// Output of a tool as it runs, ending with its result
+pub type ToolStream = BoxStream<'static, Result<ToolChunk>>;
+
+/// A [`ToolStream`] reading what a tool task sends on a channel
+pub fn channel_stream(rx: mpsc::UnboundedReceiver<Result<ToolChunk>>) -> ToolStream {
+    stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (item, rx))
+    })
+    .boxed()
+}
+
+/// Drain a tool stream into its result, passing partial output to
+/// `on_output`. A stream that ends without a result succeeded with all of
+/// its output.
+pub async fn collect_tool_stream(
+    mut stream: ToolStream,
+    mut on_output: impl FnMut(&str),
+) -> Result<ToolResult> {
+    let mut output = String::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk? {
+            ToolChunk::Output(text) => {
+                on_output(&text);
+                output.push_str(&text);
+            }
+            ToolChunk::Finished(result) => return Ok(result),
+        }
+    }
+    Ok(ToolResult::success(output))
+}
+
+/// Partial output of a running tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolOutputChunk {
+    pub tool: String,
+    pub text: String,
+}
+
+/// Destination for the partial output of tools while they run, e.g. a
+/// REPL printing it or an API stream forwarding it
+pub trait ToolOutputSink: Send + Sync {
+    fn output(&self, tool: &str, text: &str);
+}
+
+/// Sink that forwards output over an unbounded channel
+pub struct ChannelToolOutputSink {
+    tx: mpsc::UnboundedSender<ToolOutputChunk>,
+}
+
+impl ChannelToolOutputSink {
+    /// Create a sink and the receiver that observes its output
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<ToolOutputChunk>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+}
+
+impl ToolOutputSink for ChannelToolOutputSink {
+    fn output(&self, tool: &str, text: &str) {
+        // A dropped receiver just means nobody is watching anymore
+        let _ = self.tx.send(ToolOutputChunk {
+            tool: tool.to_string(),
+            text: text.to_string(),
+        });
+    }
+}
+
 /// Trait for all tools that can be executed by the agent
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -70,13 +207,61 @@ pub trait Tool: Send + Sync {
     /// JSON Schema describing the tool's parameters
     fn parameters(&self) -> Value;
 
+    /// Policy action calls of the tool are checked against. Tools with
+    /// effects beyond a single call (e.g. starting background processes)
+    /// use an action of their own so a `tool_call` rule does not cover them.
+    fn policy_action(&self) -> &str {
+        "tool_call"
+    }
+
+    /// Resource name checked against `tool_call` policy rules. Tools that
+    /// share a backend can report a common resource so one rule covers them.
+    fn policy_resource(&self) -> &str {
+        self.name()
+    }
+
+    /// Whether the agent should fill in `session_id` when the model omits it
+    fn session_scoped(&self) -> bool {
+        false
+    }
+
+    /// Path arguments resolved against the session's focused workspace:
+    /// relative paths are joined to its root, and a missing argument is set
+    /// to the root itself
+    fn workspace_args(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Whether the tool refuses paths outside a `workspace_root` argument,
+    /// which the agent always sets to the session's focused workspace (or
+    /// the working directory), replacing any the model supplied
+    fn confined(&self) -> bool {
+        false
+    }
+
     /// Execute the tool with the given arguments
     async fn execute(&self, args: Value) -> Result<ToolResult>;
+
+    /// Execute the tool, yielding output as it is produced. Long-running
+    /// tools override this; by default the result of
+    /// [`execute`](Self::execute) is the only item.
+    async fn execute_streaming(&self, args: Value) -> Result<ToolStream> {
+        let result = self.execute(args).await?;
+        Ok(stream::once(async move { Ok(ToolChunk::Finished(result)) }).boxed())
+    }
 }
 
 /// Registry for managing and executing tools
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    memo: ToolMemoCache,
+    hints: ToolHints,
+    /// Resolves `remote:` tool names; `None` outside a mesh
+    remote: Option<Arc<dyn RemoteToolExecutor>>,
+    /// Background processes started by the process tools
+    processes: Arc<ProcessManager>,
+    /// Store shared by the knowledge graph tools; `None` without persistence
+    graph: Option<KnowledgeGraphStore>,
 }
 
 impl ToolRegistry {
@@ -84,9 +269,113 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            memo: ToolMemoCache::new(),
+            hints: ToolHints::new(),
+            remote: None,
+            processes: Arc::new(ProcessManager::new()),
+            graph: None,
         }
     }
 
+    /// Resolve `remote:<tool>` names by running the tool on another mesh
+    /// instance through `executor`
+    pub fn with_remote_executor(mut self, executor: Arc<dyn RemoteToolExecutor>) -> Self {
+        self.remote = Some(executor);
+        self
+    }
+
+    /// `remote:` names of tools other mesh instances advertise and this
+    /// registry does not have locally
+    pub fn remote_tools(&self) -> Vec<String> {
+        let Some(remote) = &self.remote else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = remote
+            .advertised_tools()
+            .into_iter()
+            .filter(|tool| !self.tools.contains_key(tool))
+            .map(|tool| format!("{}{}", REMOTE_PREFIX, tool))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Apply per-tool settings from configuration (memoization, latency/cost tiers)
+    pub fn with_settings(mut self, settings: &HashMap<String, ToolSettings>) -> Self {
+        for (name, tool_settings) in settings {
+            self.hints.configure(name, tool_settings);
+            if tool_settings.memoize {
+                self.memo.enable(
+                    name.clone(),
+                    MemoPolicy {
+                        ttl: Duration::from_secs(tool_settings.memo_ttl_secs),
+                    },
+                );
+            }
+        }
+        self
+    }
+
+    /// Point `get_time` and `schedule_lookup` at a configured clock
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.register(Arc::new(GetTimeTool::new(clock.clone())));
+        self.register(Arc::new(ScheduleLookupTool::new(clock)));
+        self
+    }
+
+    /// Have the knowledge graph tools accept and show the node and edge
+    /// types declared in `[graph]`
+    pub fn with_graph_types(mut self, types: &GraphConfig) -> Self {
+        if let Some(graph) = self.graph.take() {
+            self.register_graph_tools(graph.with_types(types.clone()));
+        }
+        self
+    }
+
+    /// Register composite tools from configuration. Fails when a composite
+    /// references an unknown tool or shadows an existing one.
+    pub fn with_composite_tools(
+        mut self,
+        composites: &HashMap<String, CompositeToolConfig>,
+    ) -> Result<Self> {
+        composite::register_composite_tools(&mut self, composites)?;
+        Ok(self)
+    }
+
+    /// Enable result memoization for a single tool
+    pub fn enable_memoization(&mut self, name: impl Into<String>, policy: MemoPolicy) {
+        self.memo.enable(name, policy);
+    }
+
+    /// Drop all memoized tool results
+    pub fn clear_memoized(&self) {
+        self.memo.clear();
+    }
+
+    /// Latency and cost hints for registered tools
+    pub fn hints(&self) -> &ToolHints {
+        &self.hints
+    }
+
+    /// Tool description with its latency/cost hint appended, if any
+    pub fn describe(&self, name: &str) -> Option<String> {
+        let tool = self.get(name)?;
+        Some(match self.hints.hint(name) {
+            Some(hint) => format!("{} {}", tool.description(), hint),
+            None => tool.description().to_string(),
+        })
+    }
+
+    /// Like [`describe`](Self::describe), but with configured tiers only
+    pub fn describe_configured(&self, name: &str) -> Option<String> {
+        let tool = self.get(name)?;
+        Some(match self.hints.configured_hint(name) {
+            Some(hint) => format!("{} {}", tool.description(), hint),
+            None => tool.description().to_string(),
+        })
+    }
+
     /// Create a registry populated with all built-in tools.
     ///
     /// Tools that require persistence (e.g., `graph`) are only registered when
@@ -104,22 +393,44 @@ impl ToolRegistry {
         registry.register(Arc::new(FileReadTool::new()));
         registry.register(Arc::new(FileExtractTool::new()));
         registry.register(Arc::new(FileWriteTool::new()));
+        registry.register(Arc::new(TailFileTool::new()));
         registry.register(Arc::new(PromptUserTool::new()));
         registry.register(Arc::new(SearchTool::new()));
         registry.register(Arc::new(CodeSearchTool::new()));
         registry.register(Arc::new(BashTool::new()));
         registry.register(Arc::new(ShellTool::new()));
+        let processes = registry.processes.clone();
+        registry.register(Arc::new(ProcessStartTool::new(processes.clone())));
+        registry.register(Arc::new(ProcessStatusTool::new(processes.clone())));
+        registry.register(Arc::new(ProcessStopTool::new(processes)));
+        registry.register(Arc::new(GetTimeTool::new(Clock::default())));
+        registry.register(Arc::new(ScheduleLookupTool::new(Clock::default())));
 
         // Register web search if api feature is enabled
         #[cfg(feature = "api")]
-        registry.register(Arc::new(WebSearchTool::new().with_embeddings(embeddings)));
+        registry.register(Arc::new(
+            WebSearchTool::new().with_embeddings(embeddings.clone()),
+        ));
 
         // Register web scraper if feature is enabled
         #[cfg(feature = "web-scraping")]
         registry.register(Arc::new(WebScraperTool::new()));
 
+        // Register read-only ops tools if feature is enabled
+        #[cfg(feature = "ops")]
+        {
+            let kubectl = Arc::new(Kubectl::new());
+            registry.register(Arc::new(KubectlGetTool::new(kubectl.clone())));
+            registry.register(Arc::new(KubectlDescribeTool::new(kubectl.clone())));
+            registry.register(Arc::new(KubectlLogsTool::new(kubectl)));
+            registry.register(Arc::new(CloudStatusTool::new()));
+        }
+
         if let Some(persistence) = persistence {
-            registry.register(Arc::new(GraphTool::new(persistence.clone())));
+            registry.register_graph_tools(
+                KnowledgeGraphStore::new(persistence.clone()).with_embeddings(embeddings),
+            );
+            registry.register(Arc::new(RemindMeTool::new(persistence.clone())));
             registry.register(Arc::new(AudioTranscriptionTool::with_persistence(
                 persistence,
             )));
@@ -135,15 +446,35 @@ impl ToolRegistry {
         registry
     }
 
+    fn register_graph_tools(&mut self, graph: KnowledgeGraphStore) {
+        self.register(Arc::new(GraphTool::with_store(graph.clone())));
+        self.register(Arc::new(RememberFactTool::new(graph.clone())));
+        self.register(Arc::new(LookupEntityTool::new(graph.clone())));
+        self.register(Arc::new(EntityTimelineTool::new(graph.clone())));
+        self.register(Arc::new(RelateEntitiesTool::new(graph.clone())));
+        self.register(Arc::new(IngestCiLogTool::new(graph.clone())));
+        self.graph = Some(graph);
+    }
+
     /// Register a tool in the registry
     pub fn register(&mut self, tool: Arc<dyn Tool>) {
         let name = tool.name().to_string();
         self.tools.insert(name, tool);
     }
 
-    /// Get a tool by name
+    /// Background processes started through this registry's process tools
+    pub fn processes(&self) -> &ProcessManager {
+        &self.processes
+    }
+
+    /// Get a tool by name. `remote:` names resolve to a proxy for the tool
+    /// on another mesh instance when a remote executor is configured.
     pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
-        self.tools.get(name).cloned()
+        if let Some(tool) = self.tools.get(name) {
+            return Some(tool.clone());
+        }
+        let executor = self.remote.as_ref()?;
+        RemoteTool::new(name, executor.clone()).map(|tool| Arc::new(tool) as Arc<dyn Tool>)
     }
 
     /// List all registered tool names
@@ -151,19 +482,33 @@ impl ToolRegistry {
         self.tools.keys().map(|s| s.as_str()).collect()
     }
 
-    /// Check if a tool is registered
+    /// Check if a tool is registered (or resolvable as a remote tool)
     pub fn has(&self, name: &str) -> bool {
         self.tools.contains_key(name)
+            || (self.remote.is_some() && remote::RemoteTarget::parse(name).is_some())
     }
 
     /// Execute a tool by name with the given arguments
     pub async fn execute(&self, name: &str, args: Value) -> Result<ToolResult> {
+        self.execute_with_limits(name, args, &ToolLimits::default())
+            .await
+    }
+
+    /// Execute a tool, giving up on it once it outlives `limits`
+    pub async fn execute_with_limits(
+        &self,
+        name: &str,
+        args: Value,
+        limits: &ToolLimits,
+    ) -> Result<ToolResult> {
         let tool = self
             .get(name)
             .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
 
         debug!("Executing tool '{}'", name);
-        let result = tool.execute(args).await;
+        let started = Instant::now();
+        let result = limits.bound(name, tool.execute(args)).await;
+        self.hints.record_latency(name, started.elapsed());
         match &result {
             Ok(res) => {
                 debug!(
@@ -178,6 +523,59 @@ impl ToolRegistry {
         result
     }
 
+    /// Execute a tool within `limits`, serving identical calls from the
+    /// memoization cache when the tool has memoization enabled. The flag is
+    /// `true` on a cache hit.
+    pub async fn execute_memoized(
+        &self,
+        name: &str,
+        args: Value,
+        limits: &ToolLimits,
+    ) -> Result<(ToolResult, bool)> {
+        if let Some(cached) = self.memo.lookup(name, &args) {
+            debug!("Tool '{}' served from memoization cache", name);
+            return Ok((cached, true));
+        }
+        if !self.memo.is_enabled(name) {
+            return self
+                .execute_with_limits(name, args, limits)
+                .await
+                .map(|result| (result, false));
+        }
+
+        let result = self.execute_with_limits(name, args.clone(), limits).await?;
+        self.memo.store(name, &args, &result);
+        Ok((result, false))
+    }
+
+    /// Execute a tool like [`execute_memoized`](Self::execute_memoized),
+    /// passing its partial output to `on_output` as it is produced.
+    /// Memoized tools are not streamed.
+    pub async fn execute_streaming(
+        &self,
+        name: &str,
+        args: Value,
+        limits: &ToolLimits,
+        on_output: impl FnMut(&str),
+    ) -> Result<(ToolResult, bool)> {
+        if self.memo.is_enabled(name) {
+            return self.execute_memoized(name, args, limits).await;
+        }
+        let tool = self
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
+
+        debug!("Executing tool '{}' with streamed output", name);
+        let started = Instant::now();
+        let call = async {
+            let stream = tool.execute_streaming(args).await?;
+            collect_tool_stream(stream, on_output).await
+        };
+        let result = limits.bound(name, call).await;
+        self.hints.record_latency(name, started.elapsed());
+        result.map(|result| (result, false))
+    }
+
     /// Get the number of registered tools
     pub fn len(&self) -> usize {
         self.tools.len()
@@ -253,18 +651,26 @@ impl ToolRegistry {
         Ok(stats)
     }
 
-    /// Convert all tools in the registry to OpenAI ChatCompletionTool format.
+    /// Convert the tools in the registry, except `hidden`, to OpenAI
+    /// ChatCompletionTool format.
     ///
     /// Used by providers that support native function calling (OpenAI-compatible,
     /// including MLX and LM Studio when enabled).
     #[cfg(any(feature = "openai", feature = "mlx", feature = "lmstudio"))]
-    pub fn to_openai_tools(&self) -> Vec<ChatCompletionTool> {
+    pub fn to_openai_tools(
+        &self,
+        hidden: &std::collections::HashSet<String>,
+    ) -> Vec<ChatCompletionTool> {
         use crate::agent::function_calling::tool_to_openai_function;
 
         self.tools
             .values()
+            .filter(|tool| !hidden.contains(tool.name()))
             .map(|tool| {
-                tool_to_openai_function(tool.name(), tool.description(), &tool.parameters())
+                let description = self
+                    .describe(tool.name())
+                    .unwrap_or_else(|| tool.description().to_string());
+                tool_to_openai_function(tool.name(), &description, &tool.parameters())
             })
             .collect()
     }
@@ -336,6 +742,261 @@ mod tests {
         assert_eq!(result.output, "dummy output");
     }
 
+    struct TickerTool;
+
+    #[async_trait]
+    impl Tool for TickerTool {
+        fn name(&self) -> &str {
+            "ticker"
+        }
+
+        fn description(&self) -> &str {
+            "Writes three ticks"
+        }
+
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+
+        async fn execute(&self, args: Value) -> Result<ToolResult> {
+            collect_tool_stream(self.execute_streaming(args).await?, |_| {}).await
+        }
+
+        async fn execute_streaming(&self, _args: Value) -> Result<ToolStream> {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                for tick in 1..=3 {
+                    let _ = tx.send(Ok(ToolChunk::Output(format!("tick {}\n", tick))));
+                }
+                let _ = tx.send(Ok(ToolChunk::Finished(ToolResult::success("3 ticks"))));
+            });
+            Ok(channel_stream(rx))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_streaming_passes_partial_output() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(TickerTool));
+        registry.register(Arc::new(DummyTool));
+        let limits = ToolLimits::default();
+
+        let mut seen = Vec::new();
+        let (result, cached) = registry
+            .execute_streaming("ticker", Value::Null, &limits, |text| {
+                seen.push(text.to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(seen, vec!["tick 1\n", "tick 2\n", "tick 3\n"]);
+        assert_eq!(result.output, "3 ticks");
+        assert!(!cached);
+
+        // Tools that do not stream produce their result alone
+        let mut seen = 0;
+        let (result, _) = registry
+            .execute_streaming("dummy", Value::Null, &limits, |_| seen += 1)
+            .await
+            .unwrap();
+        assert_eq!(seen, 0);
+        assert_eq!(result.output, "dummy output");
+
+        // A stream without a result succeeds with what it wrote
+        let partial = stream::iter(vec![Ok(ToolChunk::Output("half".to_string()))]).boxed();
+        let result = collect_tool_stream(partial, |_| {}).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "half");
+    }
+
+    struct CountingTool(std::sync::atomic::AtomicUsize);
+
+    #[async_trait]
+    impl Tool for CountingTool {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn description(&self) -> &str {
+            "Counts its executions"
+        }
+
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+
+        async fn execute(&self, _args: Value) -> Result<ToolResult> {
+            let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(ToolResult::success(n.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_memoized_only_when_enabled() {
+        let mut settings = HashMap::new();
+        settings.insert(
+            "counter".to_string(),
+            ToolSettings {
+                memoize: true,
+                ..ToolSettings::default()
+            },
+        );
+        let mut registry = ToolRegistry::new().with_settings(&settings);
+        registry.register(Arc::new(CountingTool(Default::default())));
+        registry.register(Arc::new(DummyTool));
+        let limits = ToolLimits::default();
+
+        let args = serde_json::json!({"q": 1});
+        let (first, hit) = registry
+            .execute_memoized("counter", args.clone(), &limits)
+            .await
+            .unwrap();
+        assert!(!hit);
+        let (second, hit) = registry
+            .execute_memoized("counter", args, &limits)
+            .await
+            .unwrap();
+        assert!(hit);
+        assert_eq!(first.output, second.output);
+
+        let (_, hit) = registry
+            .execute_memoized("dummy", Value::Null, &limits)
+            .await
+            .unwrap();
+        let (_, hit_again) = registry
+            .execute_memoized("dummy", Value::Null, &limits)
+            .await
+            .unwrap();
+        assert!(!hit && !hit_again);
+    }
+
+    struct SleepyTool;
+
+    #[async_trait]
+    impl Tool for SleepyTool {
+        fn name(&self) -> &str {
+            "sleepy"
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps for a minute"
+        }
+
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+
+        async fn execute(&self, _args: Value) -> Result<ToolResult> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(ToolResult::success("rested"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hung_tools_time_out_or_are_cancelled() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(SleepyTool));
+        registry.register(Arc::new(DummyTool));
+
+        let limits = ToolLimits {
+            timeout: Some(Duration::from_millis(20)),
+            ..ToolLimits::default()
+        };
+        let result = registry
+            .execute_with_limits("sleepy", Value::Null, &limits)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Tool 'sleepy' timed out after 20ms")
+        );
+        let result = registry
+            .execute_with_limits("dummy", Value::Null, &limits)
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let limits = ToolLimits::default();
+        let cancel = limits.cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+        let (result, _) = registry
+            .execute_streaming("sleepy", Value::Null, &limits, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(result.error.as_deref(), Some("Tool 'sleepy' was cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_describe_appends_configured_hints() {
+        let mut settings = HashMap::new();
+        settings.insert(
+            "dummy".to_string(),
+            ToolSettings {
+                cost_tier: Some(crate::config::ToolTier::High),
+                ..ToolSettings::default()
+            },
+        );
+        let mut registry = ToolRegistry::new().with_settings(&settings);
+        registry.register(Arc::new(DummyTool));
+
+        assert_eq!(
+            registry.describe("dummy").as_deref(),
+            Some("A dummy tool for testing [cost: high]")
+        );
+        assert!(registry.describe("missing").is_none());
+    }
+
+    struct EchoRemote;
+
+    #[async_trait]
+    impl RemoteToolExecutor for EchoRemote {
+        async fn execute_remote(
+            &self,
+            instance: Option<&str>,
+            tool: &str,
+            args: Value,
+        ) -> Result<ToolResult> {
+            Ok(ToolResult::success(format!(
+                "{}@{} {}",
+                tool,
+                instance.unwrap_or("any"),
+                args
+            )))
+        }
+
+        fn advertised_tools(&self) -> Vec<String> {
+            vec!["dummy".into(), "transcribe".into()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_prefix_resolves_through_executor() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(DummyTool));
+        assert!(registry.get("remote:transcribe").is_none());
+        assert!(registry.remote_tools().is_empty());
+
+        let registry = registry.with_remote_executor(Arc::new(EchoRemote));
+        assert!(registry.has("remote:transcribe"));
+        assert!(!registry.has("transcribe"));
+        // Tools available locally are not offered remotely
+        assert_eq!(registry.remote_tools(), vec!["remote:transcribe"]);
+
+        let result = registry
+            .execute("remote:transcribe", serde_json::json!({"path": "a.wav"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, r#"transcribe@any {"path":"a.wav"}"#);
+        let result = registry
+            .execute("remote:gpu-1/transcribe", Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(result.output, "transcribe@gpu-1 null");
+    }
+
     #[tokio::test]
     async fn test_execute_nonexistent_tool() {
         let registry = ToolRegistry::new();
@@ -0,0 +1,140 @@
+//! Tools executed on other mesh instances
+//!
+//! A tool name of the form `remote:<tool>` runs `<tool>` on whichever mesh
+//! instance advertises it, and `remote:<instance>/<tool>` pins the call to
+//! one instance. This lets a single machine with a GPU transcriber or a
+//! particular plugin serve the whole mesh. The registry resolves these names
+//! through a [`RemoteToolExecutor`]; the mesh-backed executor lives in
+//! [`crate::mesh`].
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use super::{Tool, ToolResult};
+
+/// Prefix marking a tool name for remote execution
+pub const REMOTE_PREFIX: &str = "remote:";
+
+/// Carries tool calls to the instances that serve them
+#[async_trait]
+pub trait RemoteToolExecutor: Send + Sync {
+    /// Run `tool` with `args` on `instance`, or on any instance advertising
+    /// `tool` when `instance` is `None`
+    async fn execute_remote(
+        &self,
+        instance: Option<&str>,
+        tool: &str,
+        args: Value,
+    ) -> Result<ToolResult>;
+
+    /// Tools other instances currently advertise, as last seen
+    fn advertised_tools(&self) -> Vec<String>;
+}
+
+/// Target of a `remote:` tool name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTarget {
+    pub instance: Option<String>,
+    pub tool: String,
+}
+
+impl RemoteTarget {
+    /// Parse `remote:<tool>` or `remote:<instance>/<tool>`
+    pub fn parse(name: &str) -> Option<Self> {
+        let rest = name.strip_prefix(REMOTE_PREFIX)?;
+        let (instance, tool) = match rest.rsplit_once('/') {
+            Some((instance, tool)) => (Some(instance.to_string()), tool),
+            None => (None, rest),
+        };
+        if tool.is_empty() || instance.as_deref() == Some("") {
+            return None;
+        }
+        Some(Self {
+            instance,
+            tool: tool.to_string(),
+        })
+    }
+}
+
+/// A `remote:` tool resolved by the registry
+pub struct RemoteTool {
+    name: String,
+    description: String,
+    target: RemoteTarget,
+    executor: Arc<dyn RemoteToolExecutor>,
+}
+
+impl RemoteTool {
+    pub fn new(name: &str, executor: Arc<dyn RemoteToolExecutor>) -> Option<Self> {
+        let target = RemoteTarget::parse(name)?;
+        let description = match &target.instance {
+            Some(instance) => format!(
+                "Runs the '{}' tool on mesh instance {}; takes the same arguments as '{}'.",
+                target.tool, instance, target.tool
+            ),
+            None => format!(
+                "Runs the '{}' tool on a mesh instance that offers it; takes the same arguments as '{}'.",
+                target.tool, target.tool
+            ),
+        };
+        Some(Self {
+            name: name.to_string(),
+            description,
+            target,
+            executor,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for RemoteTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "description": format!("Arguments for the remote '{}' tool", self.target.tool),
+            "additionalProperties": true
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        self.executor
+            .execute_remote(self.target.instance.as_deref(), &self.target.tool, args)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_tool_names() {
+        assert_eq!(
+            RemoteTarget::parse("remote:audio_transcribe"),
+            Some(RemoteTarget {
+                instance: None,
+                tool: "audio_transcribe".into()
+            })
+        );
+        assert_eq!(
+            RemoteTarget::parse("remote:gpu-box-1/audio_transcribe"),
+            Some(RemoteTarget {
+                instance: Some("gpu-box-1".into()),
+                tool: "audio_transcribe".into()
+            })
+        );
+        assert_eq!(RemoteTarget::parse("audio_transcribe"), None);
+        assert_eq!(RemoteTarget::parse("remote:"), None);
+        assert_eq!(RemoteTarget::parse("remote:/echo"), None);
+    }
+}
@@ -0,0 +1,20 @@
+//! Regenerates `include/spec_ai.h` from the `extern "C"` items in `src/`
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("reading cbindgen.toml");
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(crate_dir.join("include/spec_ai.h"));
+        }
+        // A parse failure surfaces as a compile error with a better message
+        Err(err) => println!("cargo:warning=spec_ai.h not regenerated: {}", err),
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Embeds the engine in a C program with one host-implemented tool.
+ *
+ *   cargo build -p spec-ai-ffi --release --features bundled,openai
+ *   cc examples/query.c -Iinclude -L../../target/release -lspec_ai_ffi -o query
+ *   OPENAI_API_KEY=... ./query "What time is it?"
+ */
+
+#include <stdio.h>
+#include <time.h>
+
+#include "spec_ai.h"
+
+static void print_event(const char *event_json, void *user_data) {
+    (void)user_data;
+    fprintf(stderr, "event: %s\n", event_json);
+}
+
+static void current_time(const char *args_json, SpecAiToolReply *reply, void *user_data) {
+    (void)args_json;
+    (void)user_data;
+    char buffer[64];
+    time_t now = time(NULL);
+    strftime(buffer, sizeof buffer, "%Y-%m-%d %H:%M:%S", localtime(&now));
+    spec_ai_tool_reply_set(reply, buffer, true);
+}
+
+int main(int argc, char **argv) {
+    const char *input = argc > 1 ? argv[1] : "What time is it?";
+
+    SpecAiEngine *engine = spec_ai_engine_new(
+        "{\"provider\": \"openai\", \"model\": \"gpt-4o-mini\", \"system_prompt\": \"You are terse.\"}");
+    if (engine == NULL) {
+        fprintf(stderr, "spec_ai_engine_new: %s\n", spec_ai_last_error());
+        return 1;
+    }
+
+    if (spec_ai_engine_register_tool(engine, "current_time", "Local date and time", NULL,
+                                     current_time, NULL) != SPEC_AI_STATUS_OK) {
+        fprintf(stderr, "register_tool: %s\n", spec_ai_last_error());
+        spec_ai_engine_free(engine);
+        return 1;
+    }
+
+    char *output = NULL;
+    SpecAiStatus status = spec_ai_engine_query(engine, input, print_event, NULL, &output);
+    if (status == SPEC_AI_STATUS_OK) {
+        printf("%s\n", output);
+        spec_ai_string_free(output);
+    } else {
+        fprintf(stderr, "query: %s\n", spec_ai_last_error());
+    }
+
+    spec_ai_engine_free(engine);
+    return status == SPEC_AI_STATUS_OK ? 0 : 1;
+}
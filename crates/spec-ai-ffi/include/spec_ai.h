@@ -0,0 +1,139 @@
+/*
+ * C ABI for the spec-ai agent engine.
+ *
+ * Generated by cbindgen from crates/spec-ai-ffi; do not edit.
+ */
+
+#ifndef SPEC_AI_H
+#define SPEC_AI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call that can fail
+typedef enum SpecAiStatus {
+  SPEC_AI_STATUS_OK = 0,
+  // A required pointer was null, a string was not UTF-8, or options
+  // were malformed
+  SPEC_AI_STATUS_INVALID_ARGUMENT = 1,
+  // The engine reported an error, e.g. the model provider failed
+  SPEC_AI_STATUS_ENGINE = 2,
+  // The library panicked; the engine should not be used again
+  SPEC_AI_STATUS_PANIC = 3,
+} SpecAiStatus;
+
+// An embedded engine: one agent, its session, and the runtime it runs on
+typedef struct SpecAiEngine SpecAiEngine;
+
+// Where a tool callback leaves its result, with `spec_ai_tool_reply_set`.
+// Owned by the library and only valid during the callback.
+typedef struct SpecAiToolReply SpecAiToolReply;
+
+// Runs a host tool. `args_json` is the model's arguments as a JSON object
+// and is only valid during the call. The callback answers through
+// `spec_ai_tool_reply_set`; a callback that never does counts as failed.
+// It may be called on any thread, but never concurrently for one engine.
+typedef void (*SpecAiToolCallback)(const char *args_json,
+                                   struct SpecAiToolReply *reply,
+                                   void *user_data);
+
+// Receives one event of a running query as a JSON object with a `type` of
+// `started`, `progress`, `tool_output`, `tool_call`, `completed` or
+// `failed`. The string is only valid during the call.
+typedef void (*SpecAiEventCallback)(const char *event_json, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Description of the last failure on the calling thread, or null. Valid
+// until the next failing call on this thread; do not free it.
+const char *spec_ai_last_error(void);
+
+// Free a string returned by this library. Null is ignored.
+//
+// # Safety
+// `text` must be null or a string from this library not already freed
+void spec_ai_string_free(char *text);
+
+// Library version, e.g. "0.4.16". Static; do not free it.
+const char *spec_ai_version(void);
+
+// Create an engine from a JSON object of options:
+//
+// - `provider` (required): "openai", "anthropic", "ollama", "mock", ...
+// - `model`, `system_prompt`, `temperature`
+// - `db_path`: DuckDB file for messages, memory and the graph
+//   (default: in memory)
+// - `session`: session to continue (default: a new one)
+// - `builtin_tools`: offer the built-in tools, which act on the host
+//   machine (default: false)
+// - `api_key_env`: environment variable holding the provider's API key
+//
+// Returns null on failure; see `spec_ai_last_error`.
+//
+// # Safety
+// `options_json` must be null or a NUL-terminated string
+struct SpecAiEngine *spec_ai_engine_new(const char *options_json);
+
+// Destroy an engine. Null is ignored.
+//
+// # Safety
+// `engine` must be null or come from `spec_ai_engine_new`, not already
+// freed, and not in use on another thread
+void spec_ai_engine_free(struct SpecAiEngine *engine);
+
+// The session the engine continues. Free the result with
+// `spec_ai_string_free`; null if `engine` is null.
+//
+// # Safety
+// `engine` must be null or a live engine
+char *spec_ai_engine_session_id(const struct SpecAiEngine *engine);
+
+// Offer a host function to the model as the tool `name`, replacing any
+// tool of that name. `parameters_json` is the JSON Schema of its
+// arguments; null means none. `user_data` is passed back to `callback`
+// untouched.
+//
+// # Safety
+// `engine` must be a live engine; strings must be null or NUL-terminated;
+// `callback` and `user_data` must stay valid until the engine is freed
+enum SpecAiStatus spec_ai_engine_register_tool(struct SpecAiEngine *engine,
+                                               const char *name,
+                                               const char *description,
+                                               const char *parameters_json,
+                                               SpecAiToolCallback callback,
+                                               void *user_data);
+
+// Answer `input` as the next step of the session, blocking until it
+// finishes. `on_event`, if given, receives the step's events on the
+// calling thread as they happen. On success `*output_json`, if
+// `output_json` is not null, receives the run as a JSON object
+// (`response`, `run_id`, `tool_invocations`, `token_usage`, ...), to be
+// freed with `spec_ai_string_free`.
+//
+// # Safety
+// `engine` must be a live engine used by no other thread; `input` must be
+// a NUL-terminated string; `output_json` must be null or writable
+enum SpecAiStatus spec_ai_engine_query(struct SpecAiEngine *engine,
+                                       const char *input,
+                                       SpecAiEventCallback on_event,
+                                       void *user_data,
+                                       char **output_json);
+
+// Record a tool's result: its output on success, or an error message
+// when `success` is false. `output` is copied; null means empty.
+//
+// # Safety
+// `reply` must be the pointer passed to the running tool callback, and
+// `output` null or a NUL-terminated string
+void spec_ai_tool_reply_set(struct SpecAiToolReply *reply, const char *output, bool success);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SPEC_AI_H */
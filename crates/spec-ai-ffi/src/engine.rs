@@ -0,0 +1,373 @@
+//! Engine lifecycle and queries
+
+use crate::tool::{ExternalTool, SpecAiToolCallback, UserData};
+use crate::{borrow_opt_str, borrow_str, ffi_call, into_c_string, FfiError, SpecAiStatus};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use spec_ai_sdk::{Agent, AgentEvent, AgentOutput, AgentProfile, ModelConfig, Persistence, Tool};
+use std::ffi::{c_char, c_void, CString};
+use std::ptr;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// Messages reloaded into a rebuilt agent
+const HISTORY_LIMIT: i64 = 200;
+
+/// Receives one event of a running query as a JSON object with a `type` of
+/// `started`, `progress`, `tool_output`, `tool_call`, `completed` or
+/// `failed`. The string is only valid during the call.
+pub type SpecAiEventCallback =
+    Option<unsafe extern "C" fn(event_json: *const c_char, user_data: *mut c_void)>;
+
+/// Options accepted by `spec_ai_engine_new`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EngineOptions {
+    provider: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    system_prompt: Option<String>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    db_path: Option<String>,
+    #[serde(default)]
+    session: Option<String>,
+    #[serde(default)]
+    builtin_tools: bool,
+    #[serde(default)]
+    api_key_env: Option<String>,
+}
+
+/// An embedded engine: one agent, its session, and the runtime it runs on
+pub struct SpecAiEngine {
+    runtime: Runtime,
+    model: ModelConfig,
+    profile: AgentProfile,
+    tools: Vec<Arc<dyn Tool>>,
+    builtin_tools: bool,
+    persistence: Persistence,
+    session_id: String,
+    agent: Agent,
+}
+
+impl SpecAiEngine {
+    fn new(options: EngineOptions) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("spec-ai-ffi")
+            .build()
+            .context("starting the engine runtime")?;
+        let persistence = match &options.db_path {
+            Some(path) => Persistence::new(path)?,
+            None => Persistence::in_memory()?,
+        };
+        let model = ModelConfig {
+            provider: options.provider,
+            model_name: options.model,
+            api_key_source: options.api_key_env,
+            ..ModelConfig::default()
+        };
+        let profile = AgentProfile {
+            prompt: options.system_prompt,
+            temperature: options.temperature,
+            ..AgentProfile::default()
+        };
+
+        let mut builder = Agent::builder()
+            .model(model.clone())
+            .profile(profile.clone())
+            .builtin_tools(options.builtin_tools)
+            .persistence(persistence.clone());
+        if let Some(session) = options.session {
+            builder = builder.session(session);
+        }
+        let agent = {
+            let _entered = runtime.enter();
+            builder.build()?
+        };
+        Ok(Self {
+            session_id: agent.session_id().to_string(),
+            runtime,
+            model,
+            profile,
+            tools: Vec::new(),
+            builtin_tools: options.builtin_tools,
+            persistence,
+            agent,
+        })
+    }
+
+    /// Add a tool, replacing one of the same name. The agent's tool
+    /// registry is fixed once built, so a new agent takes over the session.
+    fn register(&mut self, tool: Arc<dyn Tool>) -> Result<()> {
+        let mut tools = self.tools.clone();
+        tools.retain(|existing| existing.name() != tool.name());
+        tools.push(tool);
+
+        let builder = Agent::builder()
+            .model(self.model.clone())
+            .profile(self.profile.clone())
+            .tools(tools.iter().cloned())
+            .builtin_tools(self.builtin_tools)
+            .persistence(self.persistence.clone())
+            .session(self.session_id.clone());
+        let mut agent = {
+            let _entered = self.runtime.enter();
+            builder.build()?
+        };
+        agent.core_mut().load_history(HISTORY_LIMIT)?;
+        self.tools = tools;
+        self.agent = agent;
+        Ok(())
+    }
+
+    /// Run one step, passing each event to `on_event` on the calling thread
+    fn query(&mut self, input: &str, mut on_event: impl FnMut(&AgentEvent)) -> Result<AgentOutput> {
+        let agent = &mut self.agent;
+        self.runtime.block_on(async {
+            let mut events = agent.subscribe();
+            let result = {
+                let step = agent.query(input);
+                tokio::pin!(step);
+                loop {
+                    tokio::select! {
+                        result = &mut step => break result,
+                        Ok(event) = events.recv() => on_event(&event),
+                    }
+                }
+            };
+            // Tool calls and the final event are published as the step ends
+            while let Ok(event) = events.try_recv() {
+                on_event(&event);
+            }
+            result
+        })
+    }
+}
+
+/// Create an engine from a JSON object of options:
+///
+/// - `provider` (required): "openai", "anthropic", "ollama", "mock", ...
+/// - `model`, `system_prompt`, `temperature`
+/// - `db_path`: DuckDB file for messages, memory and the graph
+///   (default: in memory)
+/// - `session`: session to continue (default: a new one)
+/// - `builtin_tools`: offer the built-in tools, which act on the host
+///   machine (default: false)
+/// - `api_key_env`: environment variable holding the provider's API key
+///
+/// Returns null on failure; see `spec_ai_last_error`.
+///
+/// # Safety
+/// `options_json` must be null or a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_engine_new(options_json: *const c_char) -> *mut SpecAiEngine {
+    let mut engine = ptr::null_mut();
+    ffi_call(|| {
+        let options = borrow_str(options_json, "options_json")?;
+        let options: EngineOptions = serde_json::from_str(options)
+            .map_err(|err| FfiError::invalid(format!("invalid options: {}", err)))?;
+        engine = Box::into_raw(Box::new(SpecAiEngine::new(options)?));
+        Ok(())
+    });
+    engine
+}
+
+/// Destroy an engine. Null is ignored.
+///
+/// # Safety
+/// `engine` must be null or come from `spec_ai_engine_new`, not already
+/// freed, and not in use on another thread
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_engine_free(engine: *mut SpecAiEngine) {
+    if !engine.is_null() {
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            drop(Box::from_raw(engine));
+        }));
+    }
+}
+
+/// The session the engine continues. Free the result with
+/// `spec_ai_string_free`; null if `engine` is null.
+///
+/// # Safety
+/// `engine` must be null or a live engine
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_engine_session_id(engine: *const SpecAiEngine) -> *mut c_char {
+    match engine.as_ref() {
+        Some(engine) => into_c_string(engine.session_id.clone()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Offer a host function to the model as the tool `name`, replacing any
+/// tool of that name. `parameters_json` is the JSON Schema of its
+/// arguments; null means none. `user_data` is passed back to `callback`
+/// untouched.
+///
+/// # Safety
+/// `engine` must be a live engine; strings must be null or NUL-terminated;
+/// `callback` and `user_data` must stay valid until the engine is freed
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_engine_register_tool(
+    engine: *mut SpecAiEngine,
+    name: *const c_char,
+    description: *const c_char,
+    parameters_json: *const c_char,
+    callback: SpecAiToolCallback,
+    user_data: *mut c_void,
+) -> SpecAiStatus {
+    ffi_call(|| {
+        let engine = engine
+            .as_mut()
+            .ok_or_else(|| FfiError::invalid("engine is null"))?;
+        let name = borrow_str(name, "name")?;
+        let description = borrow_opt_str(description, "description")?.unwrap_or_default();
+        let callback = callback.ok_or_else(|| FfiError::invalid("callback is null"))?;
+        let parameters = match borrow_opt_str(parameters_json, "parameters_json")? {
+            Some(schema) => serde_json::from_str::<Value>(schema)
+                .map_err(|err| FfiError::invalid(format!("invalid parameters_json: {}", err)))?,
+            None => json!({ "type": "object", "properties": {} }),
+        };
+
+        engine.register(Arc::new(ExternalTool {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+            callback,
+            user_data: UserData(user_data),
+        }))?;
+        Ok(())
+    })
+}
+
+/// Answer `input` as the next step of the session, blocking until it
+/// finishes. `on_event`, if given, receives the step's events on the
+/// calling thread as they happen. On success `*output_json`, if
+/// `output_json` is not null, receives the run as a JSON object
+/// (`response`, `run_id`, `tool_invocations`, `token_usage`, ...), to be
+/// freed with `spec_ai_string_free`.
+///
+/// # Safety
+/// `engine` must be a live engine used by no other thread; `input` must be
+/// a NUL-terminated string; `output_json` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_engine_query(
+    engine: *mut SpecAiEngine,
+    input: *const c_char,
+    on_event: SpecAiEventCallback,
+    user_data: *mut c_void,
+    output_json: *mut *mut c_char,
+) -> SpecAiStatus {
+    if !output_json.is_null() {
+        *output_json = ptr::null_mut();
+    }
+    ffi_call(|| {
+        let engine = engine
+            .as_mut()
+            .ok_or_else(|| FfiError::invalid("engine is null"))?;
+        let input = borrow_str(input, "input")?;
+
+        let output = engine.query(input, |event| {
+            let Some(on_event) = on_event else {
+                return;
+            };
+            let Ok(json) = serde_json::to_string(event) else {
+                return;
+            };
+            if let Ok(json) = CString::new(json) {
+                on_event(json.as_ptr(), user_data);
+            }
+        })?;
+
+        if !output_json.is_null() {
+            let json = serde_json::to_string(&output)
+                .map_err(|err| FfiError::from(anyhow::Error::from(err)))?;
+            *output_json = into_c_string(json);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::SpecAiToolReply;
+    use crate::{spec_ai_last_error, spec_ai_string_free, spec_ai_tool_reply_set};
+    use std::ffi::CStr;
+
+    unsafe extern "C" fn count_events(event_json: *const c_char, user_data: *mut c_void) {
+        let events = &mut *(user_data as *mut Vec<String>);
+        events.push(CStr::from_ptr(event_json).to_string_lossy().into_owned());
+    }
+
+    unsafe extern "C" fn echo(
+        args_json: *const c_char,
+        reply: *mut SpecAiToolReply,
+        _user_data: *mut c_void,
+    ) {
+        spec_ai_tool_reply_set(reply, args_json, true);
+    }
+
+    #[test]
+    fn query_streams_events_and_returns_the_run() {
+        unsafe {
+            let options = CString::new(r#"{"provider": "mock", "session": "ffi-test"}"#).unwrap();
+            let engine = spec_ai_engine_new(options.as_ptr());
+            assert!(!engine.is_null());
+
+            let name = CString::new("echo").unwrap();
+            let status = spec_ai_engine_register_tool(
+                engine,
+                name.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                Some(echo),
+                ptr::null_mut(),
+            );
+            assert_eq!(status, SpecAiStatus::Ok);
+
+            let mut events: Vec<String> = Vec::new();
+            let mut output: *mut c_char = ptr::null_mut();
+            let input = CString::new("Say hello").unwrap();
+            let status = spec_ai_engine_query(
+                engine,
+                input.as_ptr(),
+                Some(count_events),
+                &mut events as *mut Vec<String> as *mut c_void,
+                &mut output,
+            );
+            assert_eq!(status, SpecAiStatus::Ok);
+
+            let run: Value =
+                serde_json::from_str(CStr::from_ptr(output).to_str().unwrap()).unwrap();
+            assert_eq!(
+                run["response"],
+                "This is a mock response from the test provider."
+            );
+            assert!(events.first().unwrap().contains(r#""type":"started""#));
+            assert!(events.last().unwrap().contains(r#""type":"completed""#));
+
+            let session = spec_ai_engine_session_id(engine);
+            assert_eq!(CStr::from_ptr(session).to_str().unwrap(), "ffi-test");
+            spec_ai_string_free(session);
+            spec_ai_string_free(output);
+            spec_ai_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn bad_options_report_an_error() {
+        unsafe {
+            let options = CString::new(r#"{"model": "gpt-4o"}"#).unwrap();
+            assert!(spec_ai_engine_new(options.as_ptr()).is_null());
+            let error = CStr::from_ptr(spec_ai_last_error()).to_str().unwrap();
+            assert!(error.contains("provider"), "{}", error);
+
+            assert!(spec_ai_engine_new(ptr::null()).is_null());
+        }
+    }
+}
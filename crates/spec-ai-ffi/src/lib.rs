@@ -0,0 +1,156 @@
+//! C ABI for embedding the spec-ai agent engine
+//!
+//! Hosts written in C, C++, Swift or anything else with a C FFI link the
+//! `cdylib` or `staticlib` and include `include/spec_ai.h`, which the build
+//! script regenerates from this crate. The lifecycle is:
+//!
+//! 1. `spec_ai_engine_new` with a JSON object of options
+//! 2. `spec_ai_engine_register_tool` for each tool the host implements
+//! 3. `spec_ai_engine_query` for each step, with a callback that receives
+//!    the step's events as JSON while it runs
+//! 4. `spec_ai_engine_free`
+//!
+//! Functions that can fail return a [`SpecAiStatus`]; `spec_ai_last_error`
+//! then describes the failure. Strings the library hands out are freed with
+//! `spec_ai_string_free`. An engine may be used from any thread, but only
+//! from one thread at a time.
+
+mod engine;
+mod tool;
+
+pub use engine::{
+    spec_ai_engine_free, spec_ai_engine_new, spec_ai_engine_query, spec_ai_engine_register_tool,
+    spec_ai_engine_session_id, SpecAiEngine, SpecAiEventCallback,
+};
+pub use tool::{spec_ai_tool_reply_set, SpecAiToolCallback, SpecAiToolReply};
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Outcome of a call that can fail
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecAiStatus {
+    Ok = 0,
+    /// A required pointer was null, a string was not UTF-8, or options
+    /// were malformed
+    InvalidArgument = 1,
+    /// The engine reported an error, e.g. the model provider failed
+    Engine = 2,
+    /// The library panicked; the engine should not be used again
+    Panic = 3,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+/// An error to report through [`SpecAiStatus`] and `spec_ai_last_error`
+pub(crate) struct FfiError {
+    status: SpecAiStatus,
+    message: String,
+}
+
+impl FfiError {
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        Self {
+            status: SpecAiStatus::InvalidArgument,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for FfiError {
+    fn from(err: anyhow::Error) -> Self {
+        Self {
+            status: SpecAiStatus::Engine,
+            message: format!("{:#}", err),
+        }
+    }
+}
+
+/// Run an FFI body, recording its error and keeping panics from unwinding
+/// into the host
+pub(crate) fn ffi_call(body: impl FnOnce() -> Result<(), FfiError>) -> SpecAiStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => SpecAiStatus::Ok,
+        Ok(Err(err)) => {
+            set_last_error(err.message);
+            err.status
+        }
+        Err(_) => {
+            set_last_error("spec-ai panicked; see stderr for details");
+            SpecAiStatus::Panic
+        }
+    }
+}
+
+/// Borrow a host string, which must be non-null, NUL-terminated UTF-8
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`
+pub(crate) unsafe fn borrow_str<'a>(ptr: *const c_char, what: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid(format!("{} is null", what)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::invalid(format!("{} is not valid UTF-8", what)))
+}
+
+/// Like [`borrow_str`], but a null pointer means "not given"
+///
+/// # Safety
+/// As for [`borrow_str`]
+pub(crate) unsafe fn borrow_opt_str<'a>(
+    ptr: *const c_char,
+    what: &str,
+) -> Result<Option<&'a str>, FfiError> {
+    if ptr.is_null() {
+        Ok(None)
+    } else {
+        borrow_str(ptr, what).map(Some)
+    }
+}
+
+/// Hand a string to the host, which frees it with `spec_ai_string_free`
+pub(crate) fn into_c_string(text: String) -> *mut c_char {
+    CString::new(text.replace('\0', " "))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Description of the last failure on the calling thread, or null. Valid
+/// until the next failing call on this thread; do not free it.
+#[no_mangle]
+pub extern "C" fn spec_ai_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `text` must be null or a string from this library not already freed
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Library version, e.g. "0.4.16". Static; do not free it.
+#[no_mangle]
+pub extern "C" fn spec_ai_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
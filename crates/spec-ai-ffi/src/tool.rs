@@ -0,0 +1,104 @@
+//! Tools implemented by the host through function pointers
+
+use anyhow::Result;
+use serde_json::Value;
+use spec_ai_sdk::{async_trait, Tool, ToolResult};
+use std::ffi::{c_char, c_void, CString};
+
+use crate::borrow_opt_str;
+
+/// Where a tool callback leaves its result, with `spec_ai_tool_reply_set`.
+/// Owned by the library and only valid during the callback.
+pub struct SpecAiToolReply {
+    output: Option<String>,
+    success: bool,
+}
+
+/// Runs a host tool. `args_json` is the model's arguments as a JSON object
+/// and is only valid during the call. The callback answers through
+/// `spec_ai_tool_reply_set`; a callback that never does counts as failed.
+/// It may be called on any thread, but never concurrently for one engine.
+pub type SpecAiToolCallback = Option<
+    unsafe extern "C" fn(
+        args_json: *const c_char,
+        reply: *mut SpecAiToolReply,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Record a tool's result: its output on success, or an error message
+/// when `success` is false. `output` is copied; null means empty.
+///
+/// # Safety
+/// `reply` must be the pointer passed to the running tool callback, and
+/// `output` null or a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn spec_ai_tool_reply_set(
+    reply: *mut SpecAiToolReply,
+    output: *const c_char,
+    success: bool,
+) {
+    let Some(reply) = reply.as_mut() else {
+        return;
+    };
+    let output = borrow_opt_str(output, "output")
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    reply.output = Some(output.to_string());
+    reply.success = success;
+}
+
+/// The host's `user_data` pointer, which the host promises is usable from
+/// the threads its callbacks run on
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+pub(crate) struct ExternalTool {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+    pub callback: unsafe extern "C" fn(*const c_char, *mut SpecAiToolReply, *mut c_void),
+    pub user_data: UserData,
+}
+
+impl ExternalTool {
+    fn call(&self, args: &Value) -> ToolResult {
+        let Ok(args) = CString::new(args.to_string()) else {
+            return ToolResult::failure("tool arguments contain a NUL byte");
+        };
+        let mut reply = SpecAiToolReply {
+            output: None,
+            success: false,
+        };
+        unsafe { (self.callback)(args.as_ptr(), &mut reply, self.user_data.0) };
+        match reply.output {
+            Some(output) if reply.success => ToolResult::success(output),
+            Some(error) => ToolResult::failure(error),
+            None => ToolResult::failure(format!("{} returned no reply", self.name)),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ExternalTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        // Host code may block for as long as it likes
+        Ok(tokio::task::block_in_place(|| self.call(&args)))
+    }
+}
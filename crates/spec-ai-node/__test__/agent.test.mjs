@@ -0,0 +1,52 @@
+import assert from 'node:assert/strict'
+import { test } from 'node:test'
+import { createRequire } from 'node:module'
+
+const { Agent } = createRequire(import.meta.url)('../index.js')
+
+const mockAgent = (options = {}) =>
+  new Agent({ provider: 'mock', systemPrompt: 'You are terse.', ...options })
+
+test('query resolves to the run', async () => {
+  const agent = mockAgent({ session: 'node-test' })
+  const result = await agent.query('Say hello')
+
+  assert.equal(result.response, 'This is a mock response from the test provider.')
+  assert.ok(result.run_id)
+  assert.equal(agent.sessionId, 'node-test')
+  const roles = (await agent.history()).map((message) => message.role)
+  assert.deepEqual(roles, ['User', 'Assistant'])
+})
+
+test('stream yields the events of the run', async () => {
+  const agent = mockAgent()
+  const events = []
+  for await (const event of agent.stream('Say hello')) {
+    events.push(event)
+  }
+
+  assert.equal(events[0].type, 'started')
+  assert.equal(events[0].input, 'Say hello')
+  assert.equal(events.at(-1).type, 'completed')
+  assert.equal(events.at(-1).response, 'This is a mock response from the test provider.')
+})
+
+test('registerTool keeps the session', async () => {
+  const agent = mockAgent()
+  await agent.query('First question')
+  const session = agent.sessionId
+
+  agent.registerTool(
+    'echo',
+    'Repeat the text back',
+    { type: 'object', properties: { text: { type: 'string' } } },
+    async ({ text }) => text,
+  )
+
+  assert.equal(agent.sessionId, session)
+  assert.equal((await agent.history()).length, 2)
+})
+
+test('registerTool needs a function', () => {
+  assert.throws(() => mockAgent().registerTool('broken', 'Not callable', null, 'nope'), TypeError)
+})
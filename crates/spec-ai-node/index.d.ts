@@ -0,0 +1,76 @@
+export interface AgentOptions {
+  /** Provider name such as "openai", "anthropic", "ollama" or "mock" */
+  provider: string
+  model?: string
+  systemPrompt?: string
+  temperature?: number
+  /** DuckDB file for messages, memory and the graph (default: in memory) */
+  dbPath?: string
+  /** Session to continue (default: a new one) */
+  session?: string
+  /** Offer the built-in tools, which act on the host machine (default: false) */
+  builtinTools?: boolean
+  /** Environment variable holding the provider's API key */
+  apiKeyEnv?: string
+}
+
+export interface ToolInvocation {
+  name: string
+  arguments: unknown
+  success: boolean
+  output?: string
+  error?: string
+  /** Served from the memoization cache */
+  cached?: boolean
+}
+
+export interface TokenUsage {
+  prompt_tokens: number
+  completion_tokens: number
+  total_tokens: number
+}
+
+export interface AgentOutput {
+  response: string
+  run_id: string
+  response_message_id: number | null
+  tool_invocations: ToolInvocation[]
+  token_usage: TokenUsage | null
+  finish_reason: string | null
+  [field: string]: unknown
+}
+
+export type AgentEvent =
+  | { type: 'started'; session_id: string; input: string }
+  | ({ type: 'progress' } & Record<string, unknown>)
+  | { type: 'tool_output'; tool: string; text: string }
+  | ({ type: 'tool_call' } & ToolInvocation)
+  | { type: 'completed'; run_id: string; response: string; token_usage: TokenUsage | null }
+  | { type: 'failed'; error: string }
+
+export interface Message {
+  id: number
+  session_id: string
+  role: string
+  content: string
+  created_at: string
+}
+
+export declare class Agent {
+  constructor(options: AgentOptions)
+  /** Session whose history this agent continues */
+  readonly sessionId: string
+  /** Answer `input`; steps on one agent run one at a time */
+  query(input: string): Promise<AgentOutput>
+  /** Answer `input`, yielding each event of the run as it happens */
+  stream(input: string): AsyncIterableIterator<AgentEvent>
+  /** Offer a JavaScript function to the model as a tool */
+  registerTool(
+    name: string,
+    description: string,
+    parameters: object | null,
+    func: (args: any) => unknown,
+  ): void
+  /** Messages of the session, oldest first */
+  history(limit?: number): Promise<Message[]>
+}
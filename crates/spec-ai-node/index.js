@@ -0,0 +1,65 @@
+'use strict'
+
+// Loads the native addon (binding.js is generated by `napi build`) and adds
+// the parts of the API that are simpler to express in JavaScript.
+const { Agent } = require('./binding.js')
+
+/**
+ * Offer `func` to the model as the tool `name`. `func` receives the
+ * arguments as an object and may return a string, any JSON-compatible
+ * value, or a promise of either. A thrown error becomes a failed tool
+ * result.
+ */
+Agent.prototype.registerTool = function registerTool(name, description, parameters, func) {
+  if (typeof func !== 'function') {
+    throw new TypeError(`tool '${name}' needs a function`)
+  }
+  const call = async (args) => {
+    const output = await func(args)
+    if (output === undefined || output === null) return ''
+    return typeof output === 'string' ? output : JSON.stringify(output)
+  }
+  this.registerToolRaw(name, description, parameters ?? null, call)
+}
+
+/**
+ * Answer `input`, yielding each event of the run as it happens. The last
+ * event is `completed` (with the response) or `failed`.
+ */
+Agent.prototype.stream = async function* stream(input) {
+  const pending = []
+  let wake = null
+  let done = false
+  let failure = null
+
+  const finished = this.streamEvents(input, (event) => {
+    pending.push(event)
+    if (wake) wake()
+  })
+  finished.then(
+    () => {
+      done = true
+      if (wake) wake()
+    },
+    (error) => {
+      failure = error
+      done = true
+      if (wake) wake()
+    },
+  )
+
+  while (true) {
+    if (pending.length > 0) {
+      yield pending.shift()
+      continue
+    }
+    if (done) break
+    await new Promise((resolve) => {
+      wake = resolve
+    })
+    wake = null
+  }
+  if (failure) throw failure
+}
+
+module.exports = { Agent }
@@ -0,0 +1,250 @@
+//! The `Agent` class
+
+use crate::js_err;
+use crate::tool::JsTool;
+use anyhow::Result;
+use napi::bindgen_prelude::within_runtime_if_available;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject};
+use napi_derive::napi;
+use serde_json::{json, Value};
+use spec_ai_sdk::{Agent as EngineAgent, AgentEvent, AgentProfile, ModelConfig, Persistence, Tool};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Messages reloaded into a rebuilt agent
+const HISTORY_LIMIT: i64 = 200;
+
+/// Options for `new Agent(...)`
+#[napi(object)]
+pub struct AgentOptions {
+    /// Provider name such as `"openai"`, `"anthropic"`, `"ollama"` or `"mock"`
+    pub provider: String,
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f64>,
+    /// DuckDB file for messages, memory and the graph (default: in memory)
+    pub db_path: Option<String>,
+    /// Session to continue (default: a new one)
+    pub session: Option<String>,
+    /// Offer the built-in tools, which act on the host machine (default: false)
+    pub builtin_tools: Option<bool>,
+    /// Environment variable holding the provider's API key
+    pub api_key_env: Option<String>,
+}
+
+/// Everything needed to build the engine agent again after a tool is added
+#[derive(Clone)]
+struct Blueprint {
+    model: ModelConfig,
+    profile: AgentProfile,
+    tools: Vec<Arc<dyn Tool>>,
+    builtin_tools: bool,
+    persistence: Persistence,
+    session_id: Option<String>,
+}
+
+impl Blueprint {
+    fn build(&self) -> Result<EngineAgent> {
+        let mut builder = EngineAgent::builder()
+            .model(self.model.clone())
+            .profile(self.profile.clone())
+            .tools(self.tools.iter().cloned())
+            .builtin_tools(self.builtin_tools)
+            .persistence(self.persistence.clone());
+        if let Some(session_id) = &self.session_id {
+            builder = builder.session(session_id.clone());
+        }
+        // Some providers start background tasks while being created
+        within_runtime_if_available(|| builder.build())
+    }
+}
+
+type Slot = Arc<Mutex<EngineAgent>>;
+
+/// Send an event to the JS callback without waiting for it to run
+fn emit(on_event: &ThreadsafeFunction<Value, ErrorStrategy::Fatal>, event: &AgentEvent) {
+    if let Ok(event) = serde_json::to_value(event) {
+        on_event.call(event, ThreadsafeFunctionCallMode::NonBlocking);
+    }
+}
+
+/// Build the JS value passed to a threadsafe callback
+fn js_arg(ctx: ThreadSafeCallContext<Value>) -> napi::Result<Vec<napi::JsUnknown>> {
+    Ok(vec![ctx.env.to_js_value(&ctx.value)?])
+}
+
+/// An agent embedded in the Node.js process. Each query is one
+/// conversational step; steps on one agent run one at a time, in call order.
+#[napi]
+pub struct Agent {
+    blueprint: Blueprint,
+    engine: Slot,
+}
+
+impl Agent {
+    fn session(&self) -> String {
+        self.blueprint
+            .session_id
+            .clone()
+            .expect("session id is fixed when the agent is created")
+    }
+}
+
+#[napi]
+impl Agent {
+    #[napi(constructor)]
+    pub fn new(options: AgentOptions) -> napi::Result<Self> {
+        let persistence = match &options.db_path {
+            Some(path) => Persistence::new(path),
+            None => Persistence::in_memory(),
+        }
+        .map_err(js_err)?;
+        let model = ModelConfig {
+            provider: options.provider,
+            model_name: options.model,
+            api_key_source: options.api_key_env,
+            ..ModelConfig::default()
+        };
+        let profile = AgentProfile {
+            prompt: options.system_prompt,
+            temperature: options.temperature.map(|t| t as f32),
+            ..AgentProfile::default()
+        };
+        let mut blueprint = Blueprint {
+            model,
+            profile,
+            tools: Vec::new(),
+            builtin_tools: options.builtin_tools.unwrap_or(false),
+            persistence,
+            session_id: options.session,
+        };
+
+        let engine = blueprint.build().map_err(js_err)?;
+        // Pin the session so rebuilding after `registerTool` continues it
+        blueprint.session_id = Some(engine.session_id().to_string());
+        Ok(Self {
+            blueprint,
+            engine: Arc::new(Mutex::new(engine)),
+        })
+    }
+
+    /// Session whose history this agent continues
+    #[napi(getter)]
+    pub fn session_id(&self) -> String {
+        self.session()
+    }
+
+    /// Native half of `registerTool`: `func` must return a promise of a
+    /// string, which `index.js` ensures
+    #[napi(
+        js_name = "registerToolRaw",
+        ts_args_type = "name: string, description: string, parameters: object | null, func: (args: any) => Promise<string>"
+    )]
+    pub fn register_tool_raw(
+        &mut self,
+        env: Env,
+        name: String,
+        description: String,
+        parameters: Option<Value>,
+        func: JsFunction,
+    ) -> napi::Result<()> {
+        let mut func: ThreadsafeFunction<Value, ErrorStrategy::Fatal> =
+            func.create_threadsafe_function(0, js_arg)?;
+        // A registered tool must not keep the process alive on its own
+        func.unref(&env)?;
+        let parameters = parameters
+            .filter(|schema| !schema.is_null())
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+
+        let mut blueprint = self.blueprint.clone();
+        blueprint.tools.retain(|tool| tool.name() != name);
+        blueprint.tools.push(Arc::new(JsTool {
+            name,
+            description,
+            parameters,
+            func,
+        }));
+
+        // The tool registry is fixed once built, so swap in a new engine
+        // that picks the conversation up from the database
+        let mut engine = blueprint.build().map_err(js_err)?;
+        engine
+            .core_mut()
+            .load_history(HISTORY_LIMIT)
+            .map_err(js_err)?;
+        self.blueprint = blueprint;
+        self.engine = Arc::new(Mutex::new(engine));
+        Ok(())
+    }
+
+    /// Answer `input`. Resolves to the run: `response`, `run_id`,
+    /// `tool_invocations`, `token_usage` and the other fields of the HTTP
+    /// API's result.
+    #[napi(ts_return_type = "Promise<AgentOutput>")]
+    pub async fn query(&self, input: String) -> napi::Result<Value> {
+        let output = self
+            .engine
+            .lock()
+            .await
+            .query(&input)
+            .await
+            .map_err(js_err)?;
+        serde_json::to_value(output).map_err(|err| napi::Error::from_reason(err.to_string()))
+    }
+
+    /// Like `query`, also passing each event of the run (`started`,
+    /// `progress`, `tool_output`, `tool_call`, `completed`, `failed`) to
+    /// `onEvent` as it happens. `index.js` turns this into `agent.stream(input)`.
+    #[napi(
+        ts_args_type = "input: string, onEvent: (event: AgentEvent) => void",
+        ts_return_type = "Promise<AgentOutput>"
+    )]
+    pub fn stream_events(
+        &self,
+        env: Env,
+        input: String,
+        on_event: JsFunction,
+    ) -> napi::Result<JsObject> {
+        let on_event: ThreadsafeFunction<Value, ErrorStrategy::Fatal> =
+            on_event.create_threadsafe_function(0, js_arg)?;
+        let engine = self.engine.clone();
+
+        env.spawn_future(async move {
+            let mut agent = engine.lock().await;
+            let mut events = agent.subscribe();
+            let result = {
+                let step = agent.query(&input);
+                tokio::pin!(step);
+                loop {
+                    tokio::select! {
+                        result = &mut step => break result,
+                        Ok(event) = events.recv() => emit(&on_event, &event),
+                    }
+                }
+            };
+            // Tool calls and the final event are published as the step ends
+            while let Ok(event) = events.try_recv() {
+                emit(&on_event, &event);
+            }
+            let output = result.map_err(js_err)?;
+            serde_json::to_value(output).map_err(|err| napi::Error::from_reason(err.to_string()))
+        })
+    }
+
+    /// Messages of the session, oldest first
+    #[napi(ts_return_type = "Promise<Message[]>")]
+    pub async fn history(&self, limit: Option<i64>) -> napi::Result<Value> {
+        let persistence = self.blueprint.persistence.clone();
+        let session_id = self.session();
+        let messages = tokio::task::spawn_blocking(move || {
+            persistence.list_messages(&session_id, limit.unwrap_or(100))
+        })
+        .await
+        .map_err(|err| napi::Error::from_reason(err.to_string()))?
+        .map_err(js_err)?;
+        serde_json::to_value(messages).map_err(|err| napi::Error::from_reason(err.to_string()))
+    }
+}
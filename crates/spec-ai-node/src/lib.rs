@@ -0,0 +1,17 @@
+//! Node.js bindings for the spec-ai agent engine
+//!
+//! Built with napi-rs into a native addon. The package's `index.js` loads
+//! it and adds `Agent.prototype.stream`, an async iterator over the events
+//! of a run, on top of the callback-based `streamEvents` exported here.
+//!
+//! Results and events cross into JavaScript as plain objects, converted
+//! through serde, so they carry the same fields as the HTTP API's
+//! responses and the Rust SDK's `AgentEvent`.
+
+mod agent;
+mod tool;
+
+/// Turn an engine error into a JavaScript `Error`, keeping its context chain
+pub(crate) fn js_err(err: anyhow::Error) -> napi::Error {
+    napi::Error::from_reason(format!("{:#}", err))
+}
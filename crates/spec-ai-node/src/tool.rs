@@ -0,0 +1,52 @@
+//! JavaScript functions offered to the agent as tools
+
+use anyhow::{anyhow, Result};
+use napi::bindgen_prelude::Promise;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction};
+use serde_json::Value;
+use spec_ai_sdk::{async_trait, Tool, ToolResult};
+
+/// A tool whose body is a JavaScript function. `index.js` wraps the user's
+/// function so it always returns a promise of a string; a thrown error or
+/// rejected promise becomes a failed tool result carrying its message.
+pub(crate) struct JsTool {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+    pub func: ThreadsafeFunction<Value, ErrorStrategy::Fatal>,
+}
+
+impl JsTool {
+    async fn call(&self, args: Value) -> Result<String> {
+        // Resolves once the function has returned on the JS thread; the
+        // promise it returned is then awaited here
+        let pending: Promise<String> = self
+            .func
+            .call_async(args)
+            .await
+            .map_err(|err| anyhow!(err.reason))?;
+        pending.await.map_err(|err| anyhow!(err.reason))
+    }
+}
+
+#[async_trait]
+impl Tool for JsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        Ok(match self.call(args).await {
+            Ok(output) => ToolResult::success(output),
+            Err(err) => ToolResult::failure(format!("{} threw {}", self.name, err)),
+        })
+    }
+}
@@ -58,6 +58,32 @@
 //! }
 //! ```
 //!
+//! ## Typed tools
+//!
+//! Instead of writing the parameter schema and argument parsing by hand,
+//! derive [`ToolArgs`] on the arguments struct and let [`typed_tool!`] build
+//! the `PluginTool`:
+//!
+//! ```rust,ignore
+//! use serde::Deserialize;
+//! use spec_ai_plugin::{typed_tool, PluginToolResult, ToolArgs};
+//!
+//! #[derive(Deserialize, ToolArgs)]
+//! struct GreetingArgs {
+//!     /// Name of the person to greet
+//!     name: String,
+//! }
+//!
+//! typed_tool! {
+//!     static GREETING_TOOL = {
+//!         name: "greeting",
+//!         description: "Generates a personalized greeting",
+//!         args: GreetingArgs,
+//!         execute: |args| PluginToolResult::success(format!("Hello, {}!", args.name)),
+//!     };
+//! }
+//! ```
+//!
 //! # For Host Applications
 //!
 //! Use the [`loader::PluginLoader`] to discover and load plugins from a directory:
@@ -77,9 +103,13 @@
 //! }
 //! ```
 
+// Lets `#[derive(ToolArgs)]` refer to `::spec_ai_plugin` inside this crate too
+extern crate self as spec_ai_plugin;
+
 pub mod abi;
 pub mod error;
 pub mod loader;
+pub mod typed;
 
 // Re-export commonly used types
 pub use abi::{
@@ -88,3 +118,14 @@ pub use abi::{
 };
 pub use error::PluginError;
 pub use loader::{expand_tilde, LoadStats, LoadedPlugin, PluginLoader};
+pub use spec_ai_tool_macros::ToolArgs;
+pub use typed::{ToolArgs, ToolSchema};
+
+/// Re-exports used by code generated from `typed_tool!` and `#[derive(ToolArgs)]`
+#[doc(hidden)]
+pub mod __private {
+    pub use abi_stable::std_types::RStr;
+    pub use anyhow;
+    pub use async_trait::async_trait;
+    pub use serde_json;
+}
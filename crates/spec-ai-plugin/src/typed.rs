@@ -0,0 +1,283 @@
+//! Typed tool arguments
+//!
+//! [`ToolSchema`] maps Rust types to JSON Schema fragments and
+//! `#[derive(ToolArgs)]` (from `spec-ai-tool-macros`, re-exported here)
+//! combines them into the parameter schema of an arguments struct. The
+//! [`typed_tool!`](crate::typed_tool) macro then writes the rest of the tool:
+//! either a `Tool` impl for builtins or a static [`PluginTool`](crate::PluginTool)
+//! for plugins.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// JSON Schema fragment describing a Rust type
+pub trait ToolSchema {
+    fn schema() -> Value;
+}
+
+/// Argument struct of a typed tool, usually derived with `#[derive(ToolArgs)]`
+pub trait ToolArgs: ToolSchema + DeserializeOwned {
+    /// Deserialize the arguments supplied by the model
+    fn from_args(args: Value) -> serde_json::Result<Self> {
+        serde_json::from_value(args)
+    }
+}
+
+macro_rules! schema_type {
+    ($schema:expr => $($ty:ty),+) => {
+        $(impl ToolSchema for $ty {
+            fn schema() -> Value {
+                $schema
+            }
+        })+
+    };
+}
+
+schema_type!(json!({"type": "string"}) => String, char, PathBuf);
+schema_type!(json!({"type": "boolean"}) => bool);
+schema_type!(json!({"type": "integer"}) => i8, i16, i32, i64, i128, isize);
+schema_type!(json!({"type": "integer", "minimum": 0}) => u8, u16, u32, u64, u128, usize);
+schema_type!(json!({"type": "number"}) => f32, f64);
+
+impl ToolSchema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Option<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Box<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+macro_rules! schema_array {
+    ($($collection:ident),+) => {
+        $(impl<T: ToolSchema> ToolSchema for $collection<T> {
+            fn schema() -> Value {
+                json!({"type": "array", "items": T::schema()})
+            }
+        })+
+    };
+}
+
+schema_array!(Vec, HashSet, BTreeSet);
+
+macro_rules! schema_map {
+    ($($map:ident),+) => {
+        $(impl<T: ToolSchema> ToolSchema for $map<String, T> {
+            fn schema() -> Value {
+                json!({"type": "object", "additionalProperties": T::schema()})
+            }
+        })+
+    };
+}
+
+schema_map!(HashMap, BTreeMap);
+
+/// Generate the boilerplate of a tool whose arguments derive [`ToolArgs`].
+///
+/// For a builtin, name the `Tool` trait in scope and write only the body of
+/// `execute`; the arguments arrive already parsed:
+///
+/// ```rust,ignore
+/// typed_tool! {
+///     impl Tool for EchoTool {
+///         name: "echo",
+///         description: "Echoes back the provided message",
+///         args: EchoArgs,
+///         async fn execute(&self, args) -> Result<ToolResult> {
+///             Ok(ToolResult::success(args.message))
+///         }
+///     }
+/// }
+/// ```
+///
+/// For a plugin, declare a static [`PluginTool`](crate::PluginTool); invalid
+/// arguments are reported as a failed [`PluginToolResult`](crate::PluginToolResult):
+///
+/// ```rust,ignore
+/// typed_tool! {
+///     static GREETING_TOOL = {
+///         name: "greeting",
+///         description: "Generates a personalized greeting",
+///         args: GreetingArgs,
+///         execute: |args| PluginToolResult::success(format!("Hello, {}!", args.name)),
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! typed_tool {
+    (
+        impl $tool:ident for $ty:ty {
+            name: $name:expr,
+            description: $description:expr,
+            args: $args:ty,
+            async fn execute(&$self_:ident, $parsed:ident) -> $ret:ty $body:block
+        }
+    ) => {
+        #[$crate::__private::async_trait]
+        impl $tool for $ty {
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn description(&self) -> &str {
+                $description
+            }
+
+            fn parameters(&self) -> $crate::__private::serde_json::Value {
+                <$args as $crate::ToolSchema>::schema()
+            }
+
+            async fn execute(&$self_, args: $crate::__private::serde_json::Value) -> $ret {
+                let $parsed = <$args as $crate::ToolArgs>::from_args(args).map_err(|err| {
+                    $crate::__private::anyhow::anyhow!(
+                        "Failed to parse {} arguments: {}",
+                        $name,
+                        err
+                    )
+                })?;
+                $body
+            }
+        }
+    };
+    (
+        $vis:vis static $static_name:ident = {
+            name: $name:expr,
+            description: $description:expr,
+            args: $args:ty,
+            execute: |$parsed:ident| $body:expr $(,)?
+        };
+    ) => {
+        $vis static $static_name: $crate::PluginTool = {
+            extern "C" fn info() -> $crate::PluginToolInfo {
+                $crate::PluginToolInfo::new(
+                    $name,
+                    $description,
+                    <$args as $crate::ToolSchema>::schema().to_string(),
+                )
+            }
+
+            extern "C" fn execute(
+                args_json: $crate::__private::RStr<'_>,
+            ) -> $crate::PluginToolResult {
+                let parsed = $crate::__private::serde_json::from_str::<
+                    $crate::__private::serde_json::Value,
+                >(args_json.as_str())
+                .and_then(<$args as $crate::ToolArgs>::from_args);
+                let $parsed: $args = match parsed {
+                    Ok(parsed) => parsed,
+                    Err(err) => {
+                        return $crate::PluginToolResult::failure(format!(
+                            "Invalid arguments: {}",
+                            err
+                        ))
+                    }
+                };
+                $body
+            }
+
+            $crate::PluginTool {
+                info,
+                execute,
+                initialize: None,
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PluginToolResult, ToolArgs};
+    use serde::Deserialize;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolArgs)]
+    #[serde(rename_all = "camelCase")]
+    struct SearchArgs {
+        /// Text to look for
+        query: String,
+        /// Maximum number of results
+        #[serde(default)]
+        max_results: u32,
+        /// Result language
+        #[tool(values("en", "de"))]
+        language: Option<String>,
+        #[serde(rename = "filters")]
+        #[tool(description = "Extra key/value filters")]
+        extra: HashMap<String, String>,
+        tags: Vec<String>,
+        #[serde(skip)]
+        internal: bool,
+    }
+
+    #[test]
+    fn derived_schema_follows_serde_attributes() {
+        let schema = SearchArgs::schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["properties"]["query"],
+            json!({"type": "string", "description": "Text to look for"})
+        );
+        assert_eq!(
+            schema["properties"]["maxResults"]["minimum"],
+            json!(0),
+            "rename_all applies and unsigned ints are non-negative"
+        );
+        assert_eq!(
+            schema["properties"]["language"]["enum"],
+            json!(["en", "de"])
+        );
+        assert_eq!(
+            schema["properties"]["filters"],
+            json!({
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": "Extra key/value filters"
+            })
+        );
+        assert_eq!(schema["properties"]["tags"]["items"]["type"], "string");
+        assert!(schema["properties"].get("internal").is_none());
+        assert_eq!(schema["required"], json!(["query", "filters", "tags"]));
+    }
+
+    #[derive(Debug, Deserialize, ToolArgs)]
+    struct ShoutArgs {
+        text: String,
+    }
+
+    typed_tool! {
+        static SHOUT_TOOL = {
+            name: "shout",
+            description: "Upper-cases text",
+            args: ShoutArgs,
+            execute: |args| PluginToolResult::success(args.text.to_uppercase()),
+        };
+    }
+
+    #[test]
+    fn plugin_tool_parses_arguments() {
+        let info = (SHOUT_TOOL.info)();
+        assert_eq!(info.name.as_str(), "shout");
+        let schema: Value = serde_json::from_str(info.parameters_json.as_str()).unwrap();
+        assert_eq!(schema["required"], json!(["text"]));
+
+        let result = (SHOUT_TOOL.execute)(r#"{"text": "hi"}"#.into());
+        assert!(result.success);
+        assert_eq!(result.output.as_str(), "HI");
+
+        let result = (SHOUT_TOOL.execute)(r#"{"txt": "hi"}"#.into());
+        assert!(!result.success);
+        assert!(result.error.is_some());
+    }
+}
@@ -0,0 +1,47 @@
+//! Policy evaluation as the rule set grows
+//!
+//! `cargo bench -p spec-ai-policy`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use spec_ai_policy::policy::{PolicyEffect, PolicyEngine, PolicyRule};
+
+/// `rules` deny rules on unrelated paths, then the rule the check matches
+fn engine(rules: usize) -> PolicyEngine {
+    let mut engine = PolicyEngine::new();
+    for i in 0..rules {
+        engine.add_rule(PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: format!("/srv/project-{}/*/secrets/*", i),
+            effect: PolicyEffect::Deny,
+            alternative: None,
+        });
+    }
+    engine.add_rule(PolicyRule {
+        agent: "coder".to_string(),
+        action: "tool_call".to_string(),
+        resource: "/home/*/src/*".to_string(),
+        effect: PolicyEffect::Allow,
+        alternative: None,
+    });
+    engine
+}
+
+fn check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("policy_check");
+    for rules in [10, 100, 1_000, 10_000] {
+        let engine = engine(rules);
+        group.bench_with_input(
+            BenchmarkId::new("last_rule", rules),
+            &engine,
+            |b, engine| b.iter(|| engine.check("coder", "tool_call", "/home/dev/src/main.rs")),
+        );
+        group.bench_with_input(BenchmarkId::new("no_match", rules), &engine, |b, engine| {
+            b.iter(|| engine.check("reviewer", "bash", "rm -rf /"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, check);
+criterion_main!(benches);
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 use spec_ai_config::persistence::Persistence;
 
@@ -22,6 +23,10 @@ pub struct PolicyRule {
     pub resource: String,
     /// Effect to apply when rule matches
     pub effect: PolicyEffect,
+    /// What to do instead when this rule denies, shown to the model with
+    /// the denial (e.g., "ask the user to paste the page")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative: Option<String>,
 }
 
 impl PolicyRule {
@@ -33,19 +38,184 @@ impl PolicyRule {
     }
 }
 
+impl std::fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let effect = match self.effect {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+        };
+        write!(
+            f,
+            "{} {} on {} for {}",
+            effect, self.action, self.resource, self.agent
+        )
+    }
+}
+
 /// Container for all policy rules
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PolicySet {
+    #[serde(default)]
     pub rules: Vec<PolicyRule>,
 }
 
+/// Read a policy file: YAML for `.yaml` and `.yml`, TOML otherwise. Either
+/// way the file holds a `rules` list of [`PolicyRule`]s.
+pub fn load_policy_file(path: &Path) -> Result<PolicySet> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("reading policy file {}", path.display()))?;
+    let is_yaml = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+    if is_yaml {
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("parsing policy file {}", path.display()))
+    } else {
+        toml::from_str(&content).with_context(|| format!("parsing policy file {}", path.display()))
+    }
+}
+
+/// Built-in rule sets, selected per agent with `policy_preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyPreset {
+    /// Read-only tools; no shell, file writes, cluster changes or web access
+    Safe,
+    /// Read-only tools and the web; shell, file writes and cluster changes
+    /// need approval
+    Standard,
+    /// Everything
+    Yolo,
+}
+
+/// Tools that only read files, code, the knowledge graph or the clock
+const READ_ONLY_TOOLS: [&str; 11] = [
+    "file_read",
+    "tail_file",
+    "file_extract",
+    "search",
+    "code_search",
+    "graph",
+    "get_time",
+    "schedule_lookup",
+    "echo",
+    "calculator",
+    "prompt_user",
+];
+
+const WEB_TOOLS: [&str; 3] = ["web_search", "web_scraper", "cloud_status"];
+
+/// Tools that read cluster state (the `ops` feature)
+const CLUSTER_READ_TOOLS: [&str; 3] = ["kubectl_get", "kubectl_describe", "kubectl_logs"];
+
+/// Tools that run commands or change files
+const WRITE_TOOLS: [&str; 3] = ["bash", "shell", "file_write"];
+
+/// Any other cluster tool is taken to change the cluster
+const CLUSTER_WRITE_TOOLS: &str = "kubectl_*";
+
+impl PolicyPreset {
+    pub const ALL: [PolicyPreset; 3] = [Self::Safe, Self::Standard, Self::Yolo];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Safe => "safe",
+            Self::Standard => "standard",
+            Self::Yolo => "yolo",
+        }
+    }
+
+    /// The preset's rules. Tools a preset does not allow fall through to the
+    /// default deny, which asks the user before the tool runs.
+    pub fn rules(&self) -> Vec<PolicyRule> {
+        let tool_rule = |tool: &str, effect| PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: tool.to_string(),
+            effect,
+            alternative: (effect == PolicyEffect::Deny)
+                .then(|| preset_alternative(tool))
+                .flatten()
+                .map(str::to_string),
+        };
+        match self {
+            Self::Safe => WRITE_TOOLS
+                .iter()
+                .chain(&WEB_TOOLS)
+                .map(|tool| tool_rule(tool, PolicyEffect::Deny))
+                .chain(
+                    READ_ONLY_TOOLS
+                        .iter()
+                        .chain(&CLUSTER_READ_TOOLS)
+                        .map(|tool| tool_rule(tool, PolicyEffect::Allow)),
+                )
+                .chain([tool_rule(CLUSTER_WRITE_TOOLS, PolicyEffect::Deny)])
+                .collect(),
+            Self::Standard => WRITE_TOOLS
+                .iter()
+                .map(|tool| tool_rule(tool, PolicyEffect::Deny))
+                .chain(
+                    READ_ONLY_TOOLS
+                        .iter()
+                        .chain(&WEB_TOOLS)
+                        .chain(&CLUSTER_READ_TOOLS)
+                        .map(|tool| tool_rule(tool, PolicyEffect::Allow)),
+                )
+                .chain([tool_rule(CLUSTER_WRITE_TOOLS, PolicyEffect::Deny)])
+                .collect(),
+            Self::Yolo => vec![PolicyRule {
+                agent: "*".to_string(),
+                action: "*".to_string(),
+                resource: "*".to_string(),
+                effect: PolicyEffect::Allow,
+                alternative: None,
+            }],
+        }
+    }
+}
+
+/// What presets suggest in place of the tools they deny
+fn preset_alternative(tool: &str) -> Option<&'static str> {
+    match tool {
+        "bash" | "shell" => Some("ask the user to run the command and paste its output"),
+        "file_write" => Some("show the user the change and ask them to apply it"),
+        "web_search" | "web_scraper" => Some("ask the user to paste the page"),
+        "cloud_status" => Some("ask the user to check the provider's status page"),
+        CLUSTER_WRITE_TOOLS => Some("ask the user to make the change in the cluster"),
+        _ => None,
+    }
+}
+
 /// Result of policy evaluation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PolicyDecision {
     /// Action is allowed
     Allow,
     /// Action is denied with a reason
-    Deny(String),
+    Deny(PolicyDenial),
+}
+
+/// Why an action was denied, and what the rule suggests instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDenial {
+    pub reason: String,
+    pub alternative: Option<String>,
+}
+
+impl std::fmt::Display for PolicyDenial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.alternative {
+            Some(alternative) => write!(f, "{}; instead, {}", self.reason, alternative),
+            None => f.write_str(&self.reason),
+        }
+    }
 }
 
 /// Policy engine that evaluates actions against stored rules
@@ -101,23 +271,42 @@ impl PolicyEngine {
     /// Rules are evaluated in order, and the first matching rule determines the decision
     /// If no rules match, the default is to deny with a reason
     pub fn check(&self, agent: &str, action: &str, resource: &str) -> PolicyDecision {
-        for rule in &self.policy_set.rules {
-            if rule.matches(agent, action, resource) {
-                return match rule.effect {
-                    PolicyEffect::Allow => PolicyDecision::Allow,
-                    PolicyEffect::Deny => PolicyDecision::Deny(format!(
+        if let Some((_, rule)) = self.matching_rule(agent, action, resource) {
+            return match rule.effect {
+                PolicyEffect::Allow => PolicyDecision::Allow,
+                PolicyEffect::Deny => PolicyDecision::Deny(PolicyDenial {
+                    reason: format!(
                         "Policy denies {} action {} on resource {}",
                         agent, action, resource
-                    )),
-                };
-            }
+                    ),
+                    alternative: rule.alternative.clone(),
+                }),
+            };
         }
 
         // Default: deny if no rule matches
-        PolicyDecision::Deny(format!(
-            "No policy rule matches agent '{}', action '{}', resource '{}' (default deny)",
-            agent, action, resource
-        ))
+        PolicyDecision::Deny(PolicyDenial {
+            reason: format!(
+                "No policy rule matches agent '{}', action '{}', resource '{}' (default deny)",
+                agent, action, resource
+            ),
+            alternative: None,
+        })
+    }
+
+    /// The first rule matching the agent, action and resource, and its
+    /// position in the policy set; `None` means the default deny applies
+    pub fn matching_rule(
+        &self,
+        agent: &str,
+        action: &str,
+        resource: &str,
+    ) -> Option<(usize, &PolicyRule)> {
+        self.policy_set
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(agent, action, resource))
     }
 
     /// Get the number of rules in the policy set
@@ -130,10 +319,30 @@ impl PolicyEngine {
         self.policy_set.rules.push(rule);
     }
 
+    /// Remove the rule at `index` (0-based), if there is one
+    pub fn remove_rule(&mut self, index: usize) -> Option<PolicyRule> {
+        (index < self.policy_set.rules.len()).then(|| self.policy_set.rules.remove(index))
+    }
+
     /// Get a reference to the policy set
     pub fn policy_set(&self) -> &PolicySet {
         &self.policy_set
     }
+
+    /// Append the rules of a policy file after the current ones; returns how
+    /// many were added
+    pub fn merge_file(&mut self, path: &Path) -> Result<usize> {
+        let file = load_policy_file(path)?;
+        let added = file.rules.len();
+        self.policy_set.rules.extend(file.rules);
+        Ok(added)
+    }
+
+    /// Append a preset's rules after the current ones, so rules stored by
+    /// the user still take precedence
+    pub fn apply_preset(&mut self, preset: PolicyPreset) {
+        self.policy_set.rules.extend(preset.rules());
+    }
 }
 
 impl Default for PolicyEngine {
@@ -245,6 +454,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "echo".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         };
 
         assert!(rule.matches("coder", "tool_call", "echo"));
@@ -260,6 +470,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "echo".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         };
 
         assert!(rule.matches("coder", "tool_call", "echo"));
@@ -274,6 +485,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         };
 
         assert!(rule.matches("coder", "tool_call", "echo"));
@@ -289,6 +501,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "echo".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         });
 
         assert_eq!(
@@ -305,6 +518,7 @@ mod tests {
             action: "bash".to_string(),
             resource: "/etc/*".to_string(),
             effect: PolicyEffect::Deny,
+            alternative: None,
         });
 
         match engine.check("coder", "bash", "/etc/passwd") {
@@ -313,6 +527,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_policy_engine_deny_with_alternative() {
+        let mut engine = PolicyEngine::new();
+        engine.add_rule(PolicyRule {
+            agent: "*".to_string(),
+            action: "tool_call".to_string(),
+            resource: "bash".to_string(),
+            effect: PolicyEffect::Deny,
+            alternative: Some("use the sandboxed shell tool".to_string()),
+        });
+
+        match engine.check("coder", "tool_call", "bash") {
+            PolicyDecision::Deny(denial) => {
+                assert_eq!(
+                    denial.alternative.as_deref(),
+                    Some("use the sandboxed shell tool")
+                );
+                assert!(denial
+                    .to_string()
+                    .ends_with("; instead, use the sandboxed shell tool"));
+            }
+            _ => panic!("Expected deny decision"),
+        }
+
+        // Stored rules without the field still load
+        let rule: PolicyRule = serde_json::from_value(serde_json::json!({
+            "agent": "*", "action": "bash", "resource": "*", "effect": "deny"
+        }))
+        .unwrap();
+        assert_eq!(rule.alternative, None);
+    }
+
     #[test]
     fn test_policy_engine_first_match_wins() {
         let mut engine = PolicyEngine::new();
@@ -322,6 +568,7 @@ mod tests {
             action: "bash".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Deny,
+            alternative: None,
         });
         // Second rule: allow bash for coder (should never be reached)
         engine.add_rule(PolicyRule {
@@ -329,6 +576,7 @@ mod tests {
             action: "bash".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         });
 
         // First rule should win
@@ -336,6 +584,21 @@ mod tests {
             PolicyDecision::Deny(_) => {}
             _ => panic!("Expected deny decision from first rule"),
         }
+        let (index, rule) = engine
+            .matching_rule("coder", "bash", "/tmp/test.sh")
+            .unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(rule.to_string(), "deny bash on * for *");
+        assert!(engine.matching_rule("coder", "file_write", "x").is_none());
+
+        // Without the blanket deny, the rule for coder is reached
+        assert!(engine.remove_rule(2).is_none());
+        let removed = engine.remove_rule(0).unwrap();
+        assert_eq!(removed.effect, PolicyEffect::Deny);
+        assert_eq!(
+            engine.check("coder", "bash", "/tmp/test.sh"),
+            PolicyDecision::Allow
+        );
     }
 
     #[test]
@@ -344,8 +607,9 @@ mod tests {
 
         // No rules, should deny by default
         match engine.check("agent", "action", "resource") {
-            PolicyDecision::Deny(reason) => {
-                assert!(reason.contains("No policy rule matches"));
+            PolicyDecision::Deny(denial) => {
+                assert!(denial.reason.contains("No policy rule matches"));
+                assert_eq!(denial.alternative, None);
             }
             _ => panic!("Expected default deny"),
         }
@@ -361,6 +625,7 @@ mod tests {
             action: "*".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         });
         assert_eq!(engine.rule_count(), 1);
     }
@@ -374,12 +639,14 @@ mod tests {
                     action: "tool_call".to_string(),
                     resource: "echo".to_string(),
                     effect: PolicyEffect::Allow,
+                    alternative: None,
                 },
                 PolicyRule {
                     agent: "*".to_string(),
                     action: "bash".to_string(),
                     resource: "/etc/*".to_string(),
                     effect: PolicyEffect::Deny,
+                    alternative: None,
                 },
             ],
         };
@@ -406,12 +673,14 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "echo".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         });
         engine.add_rule(PolicyRule {
             agent: "*".to_string(),
             action: "bash".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Deny,
+            alternative: None,
         });
 
         // Save to persistence
@@ -445,6 +714,7 @@ mod tests {
             action: "tool_call".to_string(),
             resource: "echo".to_string(),
             effect: PolicyEffect::Allow,
+            alternative: None,
         });
         engine.save_to_persistence(&persistence).unwrap();
 
@@ -455,6 +725,7 @@ mod tests {
             action: "*".to_string(),
             resource: "*".to_string(),
             effect: PolicyEffect::Deny,
+            alternative: None,
         });
         engine2.save_to_persistence(&persistence).unwrap();
 
@@ -469,6 +740,149 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_presets_layer_under_user_rules() {
+        let mut safe = PolicyEngine::new();
+        safe.apply_preset(PolicyPreset::Safe);
+        assert_eq!(
+            safe.check("coder", "tool_call", "file_read"),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            safe.check("sre", "tool_call", "kubectl_logs"),
+            PolicyDecision::Allow
+        );
+        for tool in [
+            "bash",
+            "file_write",
+            "web_search",
+            "cloud_status",
+            "kubectl_apply",
+            "some_plugin",
+        ] {
+            assert!(matches!(
+                safe.check("coder", "tool_call", tool),
+                PolicyDecision::Deny(_)
+            ));
+        }
+
+        let mut standard = PolicyEngine::new();
+        standard.add_rule(PolicyRule {
+            agent: "coder".to_string(),
+            action: "tool_call".to_string(),
+            resource: "bash".to_string(),
+            effect: PolicyEffect::Allow,
+            alternative: None,
+        });
+        standard.apply_preset(PolicyPreset::Standard);
+        assert_eq!(
+            standard.check("coder", "tool_call", "bash"),
+            PolicyDecision::Allow
+        );
+        assert!(matches!(
+            standard.check("reviewer", "tool_call", "bash"),
+            PolicyDecision::Deny(_)
+        ));
+        assert_eq!(
+            standard.check("reviewer", "tool_call", "web_scraper"),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            standard.check("sre", "tool_call", "cloud_status"),
+            PolicyDecision::Allow
+        );
+        match standard.check("sre", "tool_call", "kubectl_delete") {
+            PolicyDecision::Deny(denial) => assert_eq!(
+                denial.alternative.as_deref(),
+                Some("ask the user to make the change in the cluster")
+            ),
+            other => panic!("Expected deny, got {:?}", other),
+        }
+
+        let mut yolo = PolicyEngine::new();
+        yolo.apply_preset(PolicyPreset::Yolo);
+        assert_eq!(
+            yolo.check("coder", "tool_call", "bash"),
+            PolicyDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_preset_names_match_config() {
+        use spec_ai_config::config::AgentProfile;
+
+        let names: Vec<&str> = PolicyPreset::ALL.iter().map(|p| p.as_str()).collect();
+        assert_eq!(names, AgentProfile::POLICY_PRESETS);
+        assert_eq!(PolicyPreset::parse(" Safe "), Some(PolicyPreset::Safe));
+        assert_eq!(PolicyPreset::parse("reckless"), None);
+    }
+
+    #[test]
+    fn test_policy_file_rules_follow_stored_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("spec-ai.policy.toml");
+        std::fs::write(
+            &toml_path,
+            r#"
+[[rules]]
+agent = "*"
+action = "tool_call"
+resource = "bash"
+effect = "deny"
+alternative = "ask the user to run it"
+
+[[rules]]
+agent = "*"
+action = "tool_call"
+resource = "*"
+effect = "allow"
+"#,
+        )
+        .unwrap();
+
+        let mut engine = PolicyEngine::new();
+        engine.add_rule(PolicyRule {
+            agent: "coder".to_string(),
+            action: "tool_call".to_string(),
+            resource: "bash".to_string(),
+            effect: PolicyEffect::Allow,
+            alternative: None,
+        });
+        assert_eq!(engine.merge_file(&toml_path).unwrap(), 2);
+        assert_eq!(engine.rule_count(), 3);
+        assert_eq!(
+            engine.check("coder", "tool_call", "bash"),
+            PolicyDecision::Allow
+        );
+        match engine.check("writer", "tool_call", "bash") {
+            PolicyDecision::Deny(denial) => assert_eq!(
+                denial.alternative.as_deref(),
+                Some("ask the user to run it")
+            ),
+            _ => panic!("Expected deny from the policy file"),
+        }
+        assert_eq!(
+            engine.check("writer", "tool_call", "echo"),
+            PolicyDecision::Allow
+        );
+
+        let yaml_path = dir.path().join("spec-ai.policy.yaml");
+        std::fs::write(
+            &yaml_path,
+            "rules:\n  - agent: \"*\"\n    action: tool_call\n    resource: file_write\n    effect: deny\n",
+        )
+        .unwrap();
+        let set = load_policy_file(&yaml_path).unwrap();
+        assert_eq!(set.rules.len(), 1);
+        assert_eq!(set.rules[0].effect, PolicyEffect::Deny);
+        assert_eq!(set.rules[0].alternative, None);
+
+        std::fs::write(&toml_path, "[[rules]]\nagent = \"*\"\neffect = \"maybe\"\n").unwrap();
+        let err = load_policy_file(&toml_path).unwrap_err();
+        assert!(format!("{:#}", err).contains("spec-ai.policy.toml"));
+        assert!(load_policy_file(&dir.path().join("missing.toml")).is_err());
+    }
+
     #[test]
     fn test_load_empty_persistence() {
         use spec_ai_config::test_utils::create_test_db;
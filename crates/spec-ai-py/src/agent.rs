@@ -0,0 +1,305 @@
+//! The `spec_ai.Agent` class
+
+use crate::tool::PyTool;
+use crate::{from_py, py_err, to_py};
+use anyhow::Result;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3_async_runtimes::tokio::{future_into_py, get_runtime};
+use serde_json::json;
+use spec_ai_sdk::{
+    Agent as EngineAgent, AgentOutput, AgentProfile, ModelConfig, NodeType, Persistence, Tool,
+    TraversalDirection,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Messages reloaded into a rebuilt agent
+const HISTORY_LIMIT: i64 = 200;
+
+/// Everything needed to build the engine agent again after a tool is added
+#[derive(Clone)]
+struct Blueprint {
+    model: ModelConfig,
+    profile: AgentProfile,
+    tools: Vec<Arc<dyn Tool>>,
+    builtin_tools: bool,
+    persistence: Persistence,
+    session_id: Option<String>,
+}
+
+impl Blueprint {
+    fn build(&self) -> Result<EngineAgent> {
+        let mut builder = EngineAgent::builder()
+            .model(self.model.clone())
+            .profile(self.profile.clone())
+            .tools(self.tools.iter().cloned())
+            .builtin_tools(self.builtin_tools)
+            .persistence(self.persistence.clone());
+        if let Some(session_id) = &self.session_id {
+            builder = builder.session(session_id.clone());
+        }
+        // Some providers start background tasks while being created
+        let _runtime = get_runtime().enter();
+        builder.build()
+    }
+}
+
+type Slot = Arc<Mutex<EngineAgent>>;
+
+/// An agent embedded in the Python process.
+///
+/// `Agent(provider, model=None, *, system_prompt=None, temperature=None,
+/// db_path=None, session=None, builtin_tools=False, api_key_env=None)`
+///
+/// `provider` is a provider name such as `"openai"`, `"anthropic"`,
+/// `"ollama"` or `"mock"`. Without `db_path` the conversation and knowledge
+/// graph live in memory and disappear with the agent. Built-in tools act on
+/// the host machine and are off unless `builtin_tools=True`.
+#[pyclass(module = "spec_ai")]
+pub struct Agent {
+    blueprint: Blueprint,
+    engine: Slot,
+}
+
+impl Agent {
+    /// Run one step on the engine, outside the GIL's reach
+    async fn step(engine: Slot, input: String) -> Result<AgentOutput> {
+        engine.lock().await.query(&input).await
+    }
+
+    fn session(&self) -> &str {
+        self.blueprint
+            .session_id
+            .as_deref()
+            .expect("session id is fixed when the agent is created")
+    }
+}
+
+#[pymethods]
+impl Agent {
+    #[new]
+    #[pyo3(signature = (
+        provider,
+        model = None,
+        *,
+        system_prompt = None,
+        temperature = None,
+        db_path = None,
+        session = None,
+        builtin_tools = false,
+        api_key_env = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        provider: String,
+        model: Option<String>,
+        system_prompt: Option<String>,
+        temperature: Option<f32>,
+        db_path: Option<String>,
+        session: Option<String>,
+        builtin_tools: bool,
+        api_key_env: Option<String>,
+    ) -> PyResult<Self> {
+        let persistence = match db_path {
+            Some(path) => Persistence::new(path),
+            None => Persistence::in_memory(),
+        }
+        .map_err(py_err)?;
+        let model = ModelConfig {
+            provider,
+            model_name: model,
+            api_key_source: api_key_env,
+            ..ModelConfig::default()
+        };
+        let profile = AgentProfile {
+            prompt: system_prompt,
+            temperature,
+            ..AgentProfile::default()
+        };
+        let mut blueprint = Blueprint {
+            model,
+            profile,
+            tools: Vec::new(),
+            builtin_tools,
+            persistence,
+            session_id: session,
+        };
+
+        let engine = blueprint.build().map_err(py_err)?;
+        // Pin the session so rebuilding after `register_tool` continues it
+        blueprint.session_id = Some(engine.session_id().to_string());
+        Ok(Self {
+            blueprint,
+            engine: Arc::new(Mutex::new(engine)),
+        })
+    }
+
+    /// Session whose history this agent continues
+    #[getter]
+    fn session_id(&self) -> String {
+        self.session().to_string()
+    }
+
+    /// Offer `func` to the model as the tool `name`. `parameters` is the
+    /// JSON Schema of its arguments (default: no arguments). `func` is
+    /// called with the arguments as a dict and may return a string or any
+    /// JSON-compatible value.
+    #[pyo3(signature = (name, description, parameters, func))]
+    fn register_tool(
+        &mut self,
+        py: Python<'_>,
+        name: String,
+        description: String,
+        parameters: Option<Bound<'_, PyAny>>,
+        func: Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        if !func.is_callable() {
+            return Err(PyValueError::new_err(format!(
+                "tool '{}' needs a callable",
+                name
+            )));
+        }
+        let parameters = match parameters {
+            Some(schema) if !schema.is_none() => from_py(&schema)?,
+            _ => json!({ "type": "object", "properties": {} }),
+        };
+
+        let mut blueprint = self.blueprint.clone();
+        blueprint.tools.retain(|tool| tool.name() != name);
+        blueprint.tools.push(Arc::new(PyTool {
+            name,
+            description,
+            parameters,
+            callable: func.unbind(),
+        }));
+
+        // The tool registry is fixed once built, so swap in a new engine
+        // that picks the conversation up from the database
+        let mut engine = py.allow_threads(|| blueprint.build()).map_err(py_err)?;
+        engine
+            .core_mut()
+            .load_history(HISTORY_LIMIT)
+            .map_err(py_err)?;
+        self.blueprint = blueprint;
+        self.engine = Arc::new(Mutex::new(engine));
+        Ok(())
+    }
+
+    /// Answer `input`, blocking until the step finishes. Returns the run as
+    /// a dict with `response`, `run_id`, `tool_invocations`, `token_usage`
+    /// and the other fields of the HTTP API's result.
+    fn query(&self, py: Python<'_>, input: String) -> PyResult<PyObject> {
+        let engine = self.engine.clone();
+        let output = py
+            .allow_threads(|| get_runtime().block_on(Self::step(engine, input)))
+            .map_err(py_err)?;
+        to_py(py, &output)
+    }
+
+    /// Like `query`, but returns an awaitable for use inside asyncio.
+    /// Steps on one agent run one at a time, in call order.
+    fn query_async<'py>(&self, py: Python<'py>, input: String) -> PyResult<Bound<'py, PyAny>> {
+        let engine = self.engine.clone();
+        future_into_py(py, async move {
+            let output = Self::step(engine, input).await.map_err(py_err)?;
+            Python::with_gil(|py| to_py(py, &output))
+        })
+    }
+
+    /// Messages of the session, oldest first
+    #[pyo3(signature = (limit = 100))]
+    fn history(&self, py: Python<'_>, limit: i64) -> PyResult<PyObject> {
+        let messages = py
+            .allow_threads(|| {
+                self.blueprint
+                    .persistence
+                    .list_messages(self.session(), limit)
+            })
+            .map_err(py_err)?;
+        to_py(py, &messages)
+    }
+
+    /// Knowledge graph nodes of the session, optionally of one type
+    /// (`"entity"`, `"concept"`, `"fact"`, ...)
+    #[pyo3(signature = (node_type = None, limit = None))]
+    fn graph_nodes(
+        &self,
+        py: Python<'_>,
+        node_type: Option<String>,
+        limit: Option<i64>,
+    ) -> PyResult<PyObject> {
+        let node_type = node_type.as_deref().map(NodeType::from_str);
+        let nodes = py
+            .allow_threads(|| {
+                self.blueprint
+                    .persistence
+                    .list_graph_nodes(self.session(), node_type, limit)
+            })
+            .map_err(py_err)?;
+        to_py(py, &nodes)
+    }
+
+    /// Edges of the session's graph, optionally only those leaving
+    /// `source_id` or entering `target_id`
+    #[pyo3(signature = (source_id = None, target_id = None))]
+    fn graph_edges(
+        &self,
+        py: Python<'_>,
+        source_id: Option<i64>,
+        target_id: Option<i64>,
+    ) -> PyResult<PyObject> {
+        let edges = py
+            .allow_threads(|| {
+                self.blueprint
+                    .persistence
+                    .list_graph_edges(self.session(), source_id, target_id)
+            })
+            .map_err(py_err)?;
+        to_py(py, &edges)
+    }
+
+    /// Nodes within `depth` hops of `node_id`, each with its depth and the
+    /// edge that reached it. `direction` is `"outgoing"`, `"incoming"` or
+    /// `"both"`.
+    #[pyo3(signature = (node_id, depth = 1, direction = "both"))]
+    fn graph_neighbors(
+        &self,
+        py: Python<'_>,
+        node_id: i64,
+        depth: usize,
+        direction: &str,
+    ) -> PyResult<PyObject> {
+        let direction = match direction.to_ascii_lowercase().as_str() {
+            "outgoing" => TraversalDirection::Outgoing,
+            "incoming" => TraversalDirection::Incoming,
+            "both" => TraversalDirection::Both,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown direction '{}': use outgoing, incoming or both",
+                    other
+                )))
+            }
+        };
+        let neighbors = py
+            .allow_threads(|| {
+                self.blueprint.persistence.traverse_neighbors_with_edges(
+                    self.session(),
+                    node_id,
+                    direction,
+                    depth,
+                )
+            })
+            .map_err(py_err)?;
+        to_py(py, &neighbors)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Agent(provider={:?}, model={:?}, session={:?})",
+            self.blueprint.model.provider,
+            self.blueprint.model.model_name.as_deref().unwrap_or(""),
+            self.session()
+        )
+    }
+}
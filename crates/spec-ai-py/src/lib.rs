@@ -0,0 +1,64 @@
+//! Python bindings for the spec-ai agent engine
+//!
+//! Built with maturin into the `spec_ai` Python module:
+//!
+//! ```python
+//! import spec_ai
+//!
+//! agent = spec_ai.Agent("openai", "gpt-4o-mini", system_prompt="You are terse.")
+//!
+//! def weather(args):
+//!     return f"Sunny in {args['city']}"
+//!
+//! agent.register_tool(
+//!     "weather",
+//!     "Current weather for a city",
+//!     {"type": "object", "properties": {"city": {"type": "string"}}},
+//!     weather,
+//! )
+//! print(agent.query("What's the weather in Oslo?")["response"])
+//! ```
+//!
+//! Results cross into Python as plain dicts and lists, converted through
+//! JSON, so they carry the same fields as the HTTP API's responses.
+
+mod agent;
+mod tool;
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Turn an engine error into a Python `RuntimeError`, keeping its context chain
+pub(crate) fn py_err(err: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:#}", err))
+}
+
+/// Serialize `value` into the equivalent Python dicts, lists and scalars
+pub(crate) fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let text =
+        serde_json::to_string(value).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    Ok(py
+        .import_bound("json")?
+        .call_method1("loads", (text,))?
+        .unbind())
+}
+
+/// Read a JSON-compatible Python object (dicts, lists, scalars) as a `Value`
+pub(crate) fn from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let text: String = value
+        .py()
+        .import_bound("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|err| PyRuntimeError::new_err(err.to_string()))
+}
+
+#[pymodule]
+#[pyo3(name = "spec_ai")]
+fn spec_ai_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<agent::Agent>()?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Python callables offered to the agent as tools
+
+use crate::{from_py, to_py};
+use anyhow::Result;
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use serde_json::Value;
+use spec_ai_sdk::{async_trait, Tool, ToolResult};
+
+/// A tool whose body is a Python callable. The callable receives the
+/// model's arguments as a dict and returns a string, or any
+/// JSON-compatible value, which is sent back to the model as JSON.
+/// An exception becomes a failed tool result carrying its message.
+pub(crate) struct PyTool {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+    pub callable: Py<PyAny>,
+}
+
+impl PyTool {
+    fn call(&self, args: &Value) -> PyResult<String> {
+        Python::with_gil(|py| {
+            let args = to_py(py, args)?;
+            let output = self.callable.bind(py).call1((args,))?;
+            if output.is_none() {
+                Ok(String::new())
+            } else if let Ok(text) = output.downcast::<PyString>() {
+                Ok(text.to_string())
+            } else {
+                Ok(from_py(&output)?.to_string())
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for PyTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        // Python code holds the GIL for as long as it runs, so keep it off
+        // the async workers
+        let result = tokio::task::block_in_place(|| self.call(&args));
+        Ok(match result {
+            Ok(output) => ToolResult::success(output),
+            Err(err) => ToolResult::failure(format!("{} raised {}", self.name, err)),
+        })
+    }
+}
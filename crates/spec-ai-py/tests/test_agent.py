@@ -0,0 +1,59 @@
+import asyncio
+
+import pytest
+
+import spec_ai
+
+
+def mock_agent(**kwargs):
+    return spec_ai.Agent("mock", system_prompt="You are terse.", **kwargs)
+
+
+def test_query_returns_the_run():
+    agent = mock_agent(session="py-test")
+    result = agent.query("Say hello")
+
+    assert result["response"] == "This is a mock response from the test provider."
+    assert result["run_id"]
+    assert agent.session_id == "py-test"
+    roles = [message["role"] for message in agent.history()]
+    assert roles == ["User", "Assistant"]
+
+
+def test_query_async_is_awaitable():
+    agent = mock_agent()
+    result = asyncio.run(agent.query_async("Say hello"))
+    assert result["response"]
+
+
+def test_register_tool_keeps_the_session():
+    agent = mock_agent()
+    agent.query("First question")
+    session = agent.session_id
+
+    agent.register_tool(
+        "echo",
+        "Repeat the text back",
+        {"type": "object", "properties": {"text": {"type": "string"}}},
+        lambda args: args["text"],
+    )
+
+    assert agent.session_id == session
+    assert len(agent.history()) == 2
+
+
+def test_register_tool_needs_a_callable():
+    with pytest.raises(ValueError):
+        mock_agent().register_tool("broken", "Not callable", None, "nope")
+
+
+def test_graph_queries_return_lists():
+    agent = mock_agent()
+    agent.query("Alice works at Acme")
+    nodes = agent.graph_nodes(limit=10)
+    assert isinstance(nodes, list)
+    assert isinstance(agent.graph_edges(), list)
+    for node in nodes:
+        assert node["session_id"] == agent.session_id
+    with pytest.raises(ValueError):
+        agent.graph_neighbors(1, direction="sideways")
@@ -0,0 +1,290 @@
+//! The embeddable agent and its builder
+
+use crate::events::{AgentEvent, EventSink};
+use anyhow::{anyhow, Context, Result};
+use spec_ai_core::agent::model::ModelProvider;
+use spec_ai_core::agent::{create_provider, AgentBuilder as CoreBuilder, AgentCore, AgentOutput};
+use spec_ai_core::config::{AgentProfile, ModelConfig};
+use spec_ai_core::persistence::Persistence;
+use spec_ai_core::policy::PolicyEngine;
+use spec_ai_core::progress::ProgressReporter;
+use spec_ai_core::spec::AgentSpec;
+use spec_ai_core::tools::{Tool, ToolRegistry};
+use spec_ai_core::types::Message;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Events kept for a subscriber that falls behind
+const EVENT_BUFFER: usize = 256;
+
+/// Builder for [`Agent`]. Only a model is required: set one with
+/// [`provider`](Self::provider) or [`model`](Self::model).
+pub struct AgentBuilder {
+    provider: Option<Arc<dyn ModelProvider>>,
+    model: Option<ModelConfig>,
+    profile: AgentProfile,
+    tools: Vec<Arc<dyn Tool>>,
+    builtin_tools: bool,
+    persistence: Option<Persistence>,
+    session_id: Option<String>,
+    policy: Option<PolicyEngine>,
+    name: Option<String>,
+}
+
+impl AgentBuilder {
+    fn new() -> Self {
+        Self {
+            provider: None,
+            model: None,
+            profile: AgentProfile::default(),
+            tools: Vec::new(),
+            builtin_tools: false,
+            persistence: None,
+            session_id: None,
+            policy: None,
+            name: None,
+        }
+    }
+
+    /// Model provider that answers the agent's prompts
+    pub fn provider(mut self, provider: Arc<dyn ModelProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Create the provider from a model description, e.g. provider
+    /// `"openai"` and a model name. API keys come from
+    /// `api_key_source` or the provider's usual environment variable.
+    pub fn model(mut self, model: ModelConfig) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Agent settings: system prompt, temperature, memory and graph options
+    pub fn profile(mut self, profile: AgentProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.profile.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.profile.temperature = Some(temperature);
+        self
+    }
+
+    /// Tools the agent may call, in addition to any added before
+    pub fn tools(mut self, tools: impl IntoIterator<Item = Arc<dyn Tool>>) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
+    pub fn tool(mut self, tool: Arc<dyn Tool>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Also offer spec-ai's built-in tools (files, shell, search, graph).
+    /// Off by default, since they act on the host machine.
+    pub fn builtin_tools(mut self, enabled: bool) -> Self {
+        self.builtin_tools = enabled;
+        self
+    }
+
+    /// Where messages, memory and the knowledge graph are stored (default:
+    /// an in-memory database discarded with the agent)
+    pub fn persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Continue or name a session; history is kept per session
+    pub fn session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Rules deciding which tools may run (default: every tool is allowed)
+    pub fn policy(mut self, policy: PolicyEngine) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Name used in logs and telemetry
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Agent> {
+        let provider = match (self.provider, &self.model) {
+            (Some(provider), _) => provider,
+            (None, Some(model)) => create_provider(model).context("creating model provider")?,
+            (None, None) => return Err(anyhow!("an agent needs a provider or a model")),
+        };
+        let persistence = match self.persistence {
+            Some(persistence) => persistence,
+            None => Persistence::in_memory()?,
+        };
+
+        let mut registry = if self.builtin_tools {
+            ToolRegistry::with_builtin_tools(Some(Arc::new(persistence.clone())), None)
+        } else {
+            ToolRegistry::new()
+        };
+        for tool in self.tools {
+            registry.register(tool);
+        }
+
+        let mut builder = CoreBuilder::new()
+            .with_profile(self.profile)
+            .with_provider(provider)
+            .with_persistence(persistence)
+            .with_tool_registry(Arc::new(registry));
+        if let Some(session_id) = self.session_id {
+            builder = builder.with_session_id(session_id);
+        }
+        if let Some(policy) = self.policy {
+            builder = builder.with_policy_engine(Arc::new(policy));
+        }
+        if let Some(name) = self.name {
+            builder = builder.with_agent_name(name);
+        }
+        let mut core = builder.build()?;
+
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        core.set_progress_reporter(ProgressReporter::new(Arc::new(EventSink(events.clone()))));
+        core.set_tool_output_sink(Arc::new(EventSink(events.clone())));
+        Ok(Agent { core, events })
+    }
+}
+
+/// An agent embedded in the host program. Each [`query`](Self::query)
+/// is one conversational step: the agent recalls memory, calls tools as
+/// needed and stores the exchange in its session.
+pub struct Agent {
+    core: AgentCore,
+    events: broadcast::Sender<AgentEvent>,
+}
+
+impl Agent {
+    pub fn builder() -> AgentBuilder {
+        AgentBuilder::new()
+    }
+
+    /// Receive the events of every later run
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+
+    /// Answer `input`, continuing the session's conversation
+    pub async fn query(&mut self, input: &str) -> Result<AgentOutput> {
+        self.started(input);
+        let result = self.core.run_step(input).await;
+        self.finished(&result);
+        result
+    }
+
+    /// Run a spec: its goal, tasks, deliverables and constraints become the
+    /// prompt, and its requirements are checked first
+    pub async fn run_spec(&mut self, spec: &AgentSpec) -> Result<AgentOutput> {
+        self.started(spec.display_name());
+        let result = self.core.run_spec(spec).await;
+        self.finished(&result);
+        result
+    }
+
+    pub fn session_id(&self) -> &str {
+        self.core.session_id()
+    }
+
+    /// Messages of the session loaded into the agent
+    pub fn history(&self) -> &[Message] {
+        self.core.conversation_history()
+    }
+
+    /// The underlying engine, for settings the builder does not cover
+    pub fn core(&self) -> &AgentCore {
+        &self.core
+    }
+
+    pub fn core_mut(&mut self) -> &mut AgentCore {
+        &mut self.core
+    }
+
+    pub fn into_core(self) -> AgentCore {
+        self.core
+    }
+
+    fn emit(&self, event: AgentEvent) {
+        let _ = self.events.send(event);
+    }
+
+    fn started(&self, input: &str) {
+        self.emit(AgentEvent::Started {
+            session_id: self.core.session_id().to_string(),
+            input: input.to_string(),
+        });
+    }
+
+    fn finished(&self, result: &Result<AgentOutput>) {
+        match result {
+            Ok(output) => {
+                for invocation in &output.tool_invocations {
+                    self.emit(AgentEvent::ToolCall(invocation.clone()));
+                }
+                self.emit(AgentEvent::Completed {
+                    run_id: output.run_id.clone(),
+                    response: output.response.clone(),
+                    token_usage: output.token_usage.clone(),
+                });
+            }
+            Err(err) => self.emit(AgentEvent::Failed {
+                error: format!("{:#}", err),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_ai_core::agent::providers::MockProvider;
+
+    #[tokio::test]
+    async fn query_answers_and_publishes_events() {
+        let mut agent = Agent::builder()
+            .provider(Arc::new(MockProvider::new("Hello from the engine")))
+            .system_prompt("You are terse.")
+            .session("sdk-test")
+            .build()
+            .unwrap();
+        let mut events = agent.subscribe();
+
+        let output = agent.query("Say hello").await.unwrap();
+        assert_eq!(output.response, "Hello from the engine");
+        assert_eq!(agent.session_id(), "sdk-test");
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert!(matches!(
+            seen.first(),
+            Some(AgentEvent::Started { input, .. }) if input == "Say hello"
+        ));
+        assert!(matches!(
+            seen.last(),
+            Some(AgentEvent::Completed { response, .. }) if response == "Hello from the engine"
+        ));
+    }
+
+    #[test]
+    fn build_requires_a_model() {
+        let err = Agent::builder().build().err().unwrap();
+        assert!(err.to_string().contains("provider or a model"));
+    }
+}
@@ -0,0 +1,53 @@
+//! Typed events published while an agent runs
+
+use serde::Serialize;
+use spec_ai_core::agent::model::TokenUsage;
+use spec_ai_core::agent::output::ToolInvocation;
+use spec_ai_core::progress::{ProgressEvent, ProgressSink};
+use spec_ai_core::tools::ToolOutputSink;
+use tokio::sync::broadcast;
+
+/// Something that happened during a query or spec run.
+///
+/// Progress events and tool output arrive while the step runs. Tool calls
+/// are published when the step finishes, in the order the tools ran.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A query or spec run began
+    Started { session_id: String, input: String },
+    /// A stage of the run moved on, e.g. waiting for the model
+    Progress(ProgressEvent),
+    /// Output a tool wrote while running, e.g. lines of a bash command
+    ToolOutput { tool: String, text: String },
+    /// A tool the agent ran, with its outcome
+    ToolCall(ToolInvocation),
+    /// The run produced its answer
+    Completed {
+        run_id: String,
+        response: String,
+        token_usage: Option<TokenUsage>,
+    },
+    /// The run failed
+    Failed { error: String },
+}
+
+/// Forwards the core's progress reports as [`AgentEvent::Progress`] and
+/// tools' partial output as [`AgentEvent::ToolOutput`]
+pub(crate) struct EventSink(pub(crate) broadcast::Sender<AgentEvent>);
+
+impl ProgressSink for EventSink {
+    fn report(&self, event: ProgressEvent) {
+        // No subscribers just means nobody is listening
+        let _ = self.0.send(AgentEvent::Progress(event));
+    }
+}
+
+impl ToolOutputSink for EventSink {
+    fn output(&self, tool: &str, text: &str) {
+        let _ = self.0.send(AgentEvent::ToolOutput {
+            tool: tool.to_string(),
+            text: text.to_string(),
+        });
+    }
+}
@@ -0,0 +1,56 @@
+//! Embeddable spec-ai agent engine
+//!
+//! `spec-ai-sdk` runs the spec-ai agent inside another Rust program. It reads
+//! no configuration file: the model, tools, policy and storage are exactly
+//! what the builder is given, and storage defaults to an in-memory database.
+//!
+//! ```no_run
+//! use spec_ai_sdk::{Agent, AgentEvent, MockProvider};
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let mut agent = Agent::builder()
+//!     .provider(Arc::new(MockProvider::new("Hello!")))
+//!     .system_prompt("You are terse.")
+//!     .build()?;
+//!
+//! let mut events = agent.subscribe();
+//! let output = agent.query("Say hello").await?;
+//! println!("{}", output.response);
+//!
+//! while let Ok(event) = events.try_recv() {
+//!     if let AgentEvent::ToolCall(call) = event {
+//!         println!("ran {}", call.name);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Custom tools implement [`Tool`] and are added with
+//! [`AgentBuilder::tool`]; specs are parsed with [`AgentSpec::from_str`] or
+//! [`AgentSpec::from_file`] and run with [`Agent::run_spec`].
+
+pub mod agent;
+pub mod events;
+
+pub use agent::{Agent, AgentBuilder};
+pub use events::AgentEvent;
+
+pub use async_trait::async_trait;
+pub use spec_ai_core::agent::model::TokenUsage;
+pub use spec_ai_core::agent::output::ToolInvocation;
+pub use spec_ai_core::agent::providers::MockProvider;
+pub use spec_ai_core::agent::{
+    AgentCore, AgentOutput, GenerationConfig, ModelProvider, ModelResponse, ProviderKind,
+    ProviderMetadata,
+};
+pub use spec_ai_core::config::{AgentProfile, ModelConfig};
+pub use spec_ai_core::persistence::Persistence;
+pub use spec_ai_core::policy::{PolicyEffect, PolicyEngine, PolicyRule};
+pub use spec_ai_core::progress::{ProgressEvent, ProgressState};
+pub use spec_ai_core::spec::AgentSpec;
+pub use spec_ai_core::tools::{Tool, ToolResult};
+pub use spec_ai_core::types::{
+    GraphEdge, GraphNeighbor, GraphNode, Message, MessageRole, NodeType, TraversalDirection,
+};
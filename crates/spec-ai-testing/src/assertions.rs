@@ -0,0 +1,119 @@
+//! Assertions on what an agent step did
+//!
+//! Each assertion panics with the step's response and tool calls, so a
+//! failing test shows what the agent did instead.
+
+use spec_ai_core::agent::output::ToolInvocation;
+use spec_ai_core::agent::AgentOutput;
+
+/// Assertions on an [`AgentOutput`], chainable:
+///
+/// ```ignore
+/// output
+///     .assert_tool_called("file_read")
+///     .assert_tools_succeeded()
+///     .assert_response_contains("42");
+/// ```
+pub trait AgentOutputAssertions {
+    /// Calls of the tool `name`, in order
+    fn tool_calls(&self, name: &str) -> Vec<&ToolInvocation>;
+
+    fn assert_response_contains(&self, needle: &str) -> &Self;
+
+    fn assert_tool_called(&self, name: &str) -> &Self;
+
+    fn assert_tool_not_called(&self, name: &str) -> &Self;
+
+    /// Every tool call of the step succeeded
+    fn assert_tools_succeeded(&self) -> &Self;
+}
+
+impl AgentOutputAssertions for AgentOutput {
+    fn tool_calls(&self, name: &str) -> Vec<&ToolInvocation> {
+        self.tool_invocations
+            .iter()
+            .filter(|call| call.name == name)
+            .collect()
+    }
+
+    #[track_caller]
+    fn assert_response_contains(&self, needle: &str) -> &Self {
+        if !self.response.contains(needle) {
+            panic!("response does not contain {:?}\n{}", needle, describe(self));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_tool_called(&self, name: &str) -> &Self {
+        if self.tool_calls(name).is_empty() {
+            panic!("tool '{}' was not called\n{}", name, describe(self));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_tool_not_called(&self, name: &str) -> &Self {
+        if !self.tool_calls(name).is_empty() {
+            panic!("tool '{}' was called\n{}", name, describe(self));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_tools_succeeded(&self) -> &Self {
+        if let Some(failed) = self.tool_invocations.iter().find(|call| !call.success) {
+            panic!(
+                "tool '{}' failed: {}\n{}",
+                failed.name,
+                failed.error.as_deref().unwrap_or("no error message"),
+                describe(self)
+            );
+        }
+        self
+    }
+}
+
+/// The step's tool calls and response, for failure messages
+fn describe(output: &AgentOutput) -> String {
+    let mut description = String::from("tool calls:");
+    if output.tool_invocations.is_empty() {
+        description.push_str(" none");
+    }
+    for call in &output.tool_invocations {
+        description.push_str(&format!(
+            "\n  {} {} -> {}",
+            call.name,
+            call.arguments,
+            if call.success { "ok" } else { "failed" }
+        ));
+    }
+    description.push_str(&format!("\nresponse: {}", output.response));
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::agent;
+    use spec_ai_core::agent::providers::MockProvider;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn assertions_pass_on_a_plain_answer() {
+        let mut agent = agent(Arc::new(MockProvider::new("The answer is 42."))).unwrap();
+        let output = agent.run_step("What is the answer?").await.unwrap();
+        output
+            .assert_response_contains("42")
+            .assert_tool_not_called("file_read")
+            .assert_tools_succeeded();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "tool 'file_read' was not called")]
+    async fn missing_tool_calls_fail_with_the_response() {
+        let mut agent = agent(Arc::new(MockProvider::new("No tools needed."))).unwrap();
+        let output = agent.run_step("Read the file").await.unwrap();
+        output.assert_tool_called("file_read");
+    }
+}
@@ -0,0 +1,225 @@
+//! Builders for agents and graph data backed by an in-memory database
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use spec_ai_config::config::AgentProfile;
+use spec_ai_config::persistence::Persistence;
+use spec_ai_config::types::{EdgeType, NodeType};
+use spec_ai_core::agent::{AgentBuilder, AgentCore, ModelProvider};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Session used by fixtures that are not given one
+pub const TEST_SESSION: &str = "test-session";
+
+/// A fresh in-memory database with every migration applied
+pub fn persistence() -> Persistence {
+    Persistence::in_memory().expect("in-memory database")
+}
+
+/// An agent with the default profile and built-in tools, answering with
+/// `provider` and storing into `persistence`
+pub fn agent_with(provider: Arc<dyn ModelProvider>, persistence: Persistence) -> Result<AgentCore> {
+    AgentBuilder::new()
+        .with_profile(AgentProfile::default())
+        .with_provider(provider)
+        .with_persistence(persistence)
+        .with_session_id(TEST_SESSION)
+        .build()
+}
+
+/// [`agent_with`] on a fresh in-memory database
+pub fn agent(provider: Arc<dyn ModelProvider>) -> Result<AgentCore> {
+    agent_with(provider, persistence())
+}
+
+struct NodeSpec {
+    key: String,
+    node_type: NodeType,
+    properties: Value,
+}
+
+struct EdgeSpec {
+    source: String,
+    target: String,
+    edge_type: EdgeType,
+    weight: f32,
+}
+
+/// Knowledge graph data for one session, referred to by key until stored
+///
+/// ```no_run
+/// use spec_ai_testing::fixtures::{persistence, GraphFixture};
+/// use spec_ai_testing::{EdgeType, NodeType};
+///
+/// let db = persistence();
+/// let graph = GraphFixture::new("s")
+///     .node("alice", NodeType::Entity)
+///     .node("rust", NodeType::Concept)
+///     .edge("alice", "rust", EdgeType::Uses)
+///     .insert(&db)
+///     .unwrap();
+/// let alice = graph.id("alice");
+/// # let _ = alice;
+/// ```
+pub struct GraphFixture {
+    session_id: String,
+    nodes: Vec<NodeSpec>,
+    edges: Vec<EdgeSpec>,
+}
+
+impl GraphFixture {
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// A node labelled with its key
+    pub fn node(self, key: impl Into<String>, node_type: NodeType) -> Self {
+        self.node_with(key, node_type, Value::Object(Default::default()))
+    }
+
+    pub fn node_with(
+        mut self,
+        key: impl Into<String>,
+        node_type: NodeType,
+        properties: Value,
+    ) -> Self {
+        self.nodes.push(NodeSpec {
+            key: key.into(),
+            node_type,
+            properties,
+        });
+        self
+    }
+
+    /// An edge of weight 1.0 between two nodes added earlier
+    pub fn edge(
+        self,
+        source: impl Into<String>,
+        target: impl Into<String>,
+        edge_type: EdgeType,
+    ) -> Self {
+        self.weighted_edge(source, target, edge_type, 1.0)
+    }
+
+    pub fn weighted_edge(
+        mut self,
+        source: impl Into<String>,
+        target: impl Into<String>,
+        edge_type: EdgeType,
+        weight: f32,
+    ) -> Self {
+        self.edges.push(EdgeSpec {
+            source: source.into(),
+            target: target.into(),
+            edge_type,
+            weight,
+        });
+        self
+    }
+
+    /// Store the nodes, then the edges
+    pub fn insert(self, persistence: &Persistence) -> Result<GraphIds> {
+        let mut nodes = HashMap::new();
+        for node in self.nodes {
+            let id = persistence.insert_graph_node(
+                &self.session_id,
+                node.node_type,
+                &node.key,
+                &node.properties,
+                None,
+            )?;
+            nodes.insert(node.key, id);
+        }
+
+        let mut edges = Vec::new();
+        for edge in self.edges {
+            let id_of = |key: &str| {
+                nodes
+                    .get(key)
+                    .copied()
+                    .ok_or_else(|| anyhow!("edge refers to unknown node '{}'", key))
+            };
+            let id = persistence.insert_graph_edge(
+                &self.session_id,
+                id_of(&edge.source)?,
+                id_of(&edge.target)?,
+                edge.edge_type,
+                None,
+                None,
+                edge.weight,
+            )?;
+            edges.push(id);
+        }
+
+        Ok(GraphIds {
+            session_id: self.session_id,
+            nodes,
+            edges,
+        })
+    }
+}
+
+/// Ids given to the nodes and edges of a stored [`GraphFixture`]
+#[derive(Debug, Clone)]
+pub struct GraphIds {
+    pub session_id: String,
+    nodes: HashMap<String, i64>,
+    /// In the order the edges were added
+    pub edges: Vec<i64>,
+}
+
+impl GraphIds {
+    /// Id of the node added as `key`
+    #[track_caller]
+    pub fn id(&self, key: &str) -> i64 {
+        match self.nodes.get(key) {
+            Some(id) => *id,
+            None => panic!("graph fixture has no node '{}'", key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_store_nodes_and_edges() {
+        let db = persistence();
+        let graph = GraphFixture::new("s")
+            .node("alice", NodeType::Entity)
+            .node_with(
+                "rust",
+                NodeType::Concept,
+                serde_json::json!({"kind": "language"}),
+            )
+            .edge("alice", "rust", EdgeType::Uses)
+            .insert(&db)
+            .unwrap();
+
+        let rust = db.get_graph_node(graph.id("rust")).unwrap().unwrap();
+        assert_eq!(rust.label, "rust");
+        assert_eq!(rust.properties["kind"], "language");
+
+        let edges = db
+            .list_graph_edges("s", Some(graph.id("alice")), None)
+            .unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].target_id, graph.id("rust"));
+    }
+
+    #[test]
+    fn edges_to_unknown_nodes_are_refused() {
+        let err = GraphFixture::new("s")
+            .node("alice", NodeType::Entity)
+            .edge("alice", "bob", EdgeType::RelatesTo)
+            .insert(&persistence())
+            .unwrap_err();
+        assert!(err.to_string().contains("bob"));
+    }
+}
@@ -0,0 +1,34 @@
+//! Test fixtures for code built on the spec-ai agent engine
+//!
+//! Plugins and programs embedding the engine can test against it without a
+//! model API or a database file:
+//!
+//! - [`RecordingProvider`] records a real provider's responses to a cassette
+//!   file once and replays them in later runs.
+//! - [`fixtures`] builds in-memory databases, agents and graph data.
+//! - [`AgentOutputAssertions`] checks what an agent step answered and which
+//!   tools it called.
+//!
+//! ```no_run
+//! use spec_ai_testing::{fixtures, AgentOutputAssertions, MockProvider};
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let mut agent = fixtures::agent(Arc::new(MockProvider::new("It is 42.")))?;
+//! let output = agent.run_step("What is the answer?").await?;
+//! output.assert_response_contains("42").assert_tools_succeeded();
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod assertions;
+pub mod fixtures;
+pub mod recording;
+
+pub use assertions::AgentOutputAssertions;
+pub use fixtures::{GraphFixture, GraphIds};
+pub use recording::{Cassette, RecordingProvider, RECORD_ENV};
+
+pub use spec_ai_config::test_utils::{create_test_db, env_lock};
+pub use spec_ai_core::agent::providers::MockProvider;
+pub use spec_ai_core::types::{EdgeType, NodeType};
@@ -0,0 +1,269 @@
+//! Record provider responses once, replay them in every later test run
+//!
+//! A [`RecordingProvider`] either wraps a real provider and writes each
+//! response it returns to a cassette file, or replays a cassette without
+//! touching the network. Responses are looked up by a hash of the prompt,
+//! so a test keeps passing as long as the engine sends the same prompts;
+//! a prompt that changes fails with the hash it was looking for, and
+//! rerunning with `SPEC_AI_RECORD=1` refreshes the cassette.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use spec_ai_core::agent::model::{
+    GenerationConfig, ModelProvider, ModelResponse, ProviderKind, ProviderMetadata,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Set to re-record cassettes that already exist
+pub const RECORD_ENV: &str = "SPEC_AI_RECORD";
+
+/// Characters of each prompt kept in the cassette to help reviewing diffs
+const PROMPT_PREVIEW_CHARS: usize = 200;
+
+/// Recorded responses of one test, stored as JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    /// Metadata of the provider the responses were recorded from
+    #[serde(default)]
+    pub metadata: Option<ProviderMetadata>,
+    pub recordings: Vec<Recording>,
+}
+
+/// One prompt and the response it got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub prompt_hash: String,
+    pub prompt_preview: String,
+    pub response: ModelResponse,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("reading cassette {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("parsing cassette {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing cassette {}", path.display()))
+    }
+}
+
+enum Mode {
+    Record(Arc<dyn ModelProvider>),
+    /// Responses already replayed, per prompt hash
+    Replay(Mutex<HashMap<String, usize>>),
+}
+
+/// Provider that records a real provider's responses or replays them
+pub struct RecordingProvider {
+    path: PathBuf,
+    mode: Mode,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingProvider {
+    /// Record every response of `inner` to `path`, replacing what was there
+    pub fn record(inner: Arc<dyn ModelProvider>, path: impl Into<PathBuf>) -> Self {
+        let cassette = Cassette {
+            metadata: Some(inner.metadata()),
+            recordings: Vec::new(),
+        };
+        Self {
+            path: path.into(),
+            mode: Mode::Record(inner),
+            cassette: Mutex::new(cassette),
+        }
+    }
+
+    /// Replay the cassette at `path`
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let cassette = Cassette::load(&path)?;
+        Ok(Self {
+            path,
+            mode: Mode::Replay(Mutex::new(HashMap::new())),
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    /// Replay the cassette at `path`, or record one from the provider
+    /// `inner` returns when the cassette does not exist yet or
+    /// `SPEC_AI_RECORD` is set. `inner` is only called when recording, so
+    /// replaying needs no API key.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        inner: impl FnOnce() -> Result<Arc<dyn ModelProvider>>,
+    ) -> Result<Self> {
+        let path = path.into();
+        if path.exists() && std::env::var_os(RECORD_ENV).is_none() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(inner()?, path))
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record(_))
+    }
+
+    /// Responses recorded or loaded so far
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    async fn respond(&self, prompt: &str, config: &GenerationConfig) -> Result<ModelResponse> {
+        let prompt_hash = blake3::hash(prompt.as_bytes()).to_hex().to_string();
+        match &self.mode {
+            Mode::Record(inner) => {
+                let response = inner.generate(prompt, config).await?;
+                let mut cassette = self.cassette.lock().unwrap();
+                cassette.recordings.push(Recording {
+                    prompt_hash,
+                    prompt_preview: prompt.chars().take(PROMPT_PREVIEW_CHARS).collect(),
+                    response: response.clone(),
+                });
+                // Saved after every response, so a failing test keeps what
+                // it recorded
+                cassette.save(&self.path)?;
+                Ok(response)
+            }
+            Mode::Replay(replayed) => {
+                let mut replayed = replayed.lock().unwrap();
+                let seen = replayed.entry(prompt_hash.clone()).or_default();
+                let cassette = self.cassette.lock().unwrap();
+                let response = cassette
+                    .recordings
+                    .iter()
+                    .filter(|recording| recording.prompt_hash == prompt_hash)
+                    .nth(*seen)
+                    .map(|recording| recording.response.clone())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "{} has no recording for prompt {} (call {}); rerun with {}=1 to record it",
+                            self.path.display(),
+                            prompt_hash,
+                            *seen + 1,
+                            RECORD_ENV
+                        )
+                    })?;
+                *seen += 1;
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ModelProvider for RecordingProvider {
+    async fn generate(&self, prompt: &str, config: &GenerationConfig) -> Result<ModelResponse> {
+        self.respond(prompt, config).await
+    }
+
+    /// Streams the recorded response as a single chunk
+    async fn stream(
+        &self,
+        prompt: &str,
+        config: &GenerationConfig,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String>> + Send>>> {
+        let response = self.respond(prompt, config).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(response.content)
+        })))
+    }
+
+    fn metadata(&self) -> ProviderMetadata {
+        match &self.mode {
+            Mode::Record(inner) => inner.metadata(),
+            Mode::Replay(_) => {
+                let cassette = self.cassette.lock().unwrap();
+                cassette.metadata.clone().unwrap_or(ProviderMetadata {
+                    name: "Recording Provider".to_string(),
+                    supported_models: Vec::new(),
+                    supports_streaming: true,
+                    supports_function_calling: false,
+                    supports_vision: false,
+                    supports_json_mode: false,
+                    context_window: None,
+                })
+            }
+        }
+    }
+
+    fn kind(&self) -> ProviderKind {
+        match &self.mode {
+            Mode::Record(inner) => inner.kind(),
+            Mode::Replay(_) => ProviderKind::Mock,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_ai_core::agent::providers::MockProvider;
+
+    #[tokio::test]
+    async fn recorded_responses_replay_by_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassettes").join("greeting.json");
+        let config = GenerationConfig::default();
+
+        let inner = Arc::new(MockProvider::with_responses(vec![
+            "first".to_string(),
+            "second".to_string(),
+        ]));
+        let recorder = RecordingProvider::record(inner, &path);
+        assert_eq!(
+            recorder.generate("a", &config).await.unwrap().content,
+            "first"
+        );
+        assert_eq!(
+            recorder.generate("b", &config).await.unwrap().content,
+            "second"
+        );
+
+        let replay = RecordingProvider::replay(&path).unwrap();
+        assert!(!replay.is_recording());
+        assert_eq!(replay.metadata().name, "Mock Provider");
+        assert_eq!(
+            replay.generate("b", &config).await.unwrap().content,
+            "second"
+        );
+        assert_eq!(
+            replay.generate("a", &config).await.unwrap().content,
+            "first"
+        );
+
+        let err = replay.generate("a", &config).await.unwrap_err();
+        assert!(err.to_string().contains(RECORD_ENV));
+    }
+
+    #[tokio::test]
+    async fn open_replays_without_building_the_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+        let config = GenerationConfig::default();
+
+        let recorder =
+            RecordingProvider::open(&path, || Ok(Arc::new(MockProvider::new("hello")))).unwrap();
+        assert!(recorder.is_recording());
+        recorder.generate("hi", &config).await.unwrap();
+
+        let replay =
+            RecordingProvider::open(&path, || Err(anyhow!("no API key in tests"))).unwrap();
+        assert_eq!(
+            replay.generate("hi", &config).await.unwrap().content,
+            "hello"
+        );
+    }
+}
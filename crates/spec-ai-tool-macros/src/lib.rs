@@ -0,0 +1,320 @@
+//! Derive macro for typed spec-ai tool arguments
+//!
+//! `#[derive(ToolArgs)]` builds the JSON schema a tool advertises from the
+//! struct its arguments deserialize into, so the schema and the parsing code
+//! cannot drift apart. Use it through `spec_ai_plugin` (which re-exports the
+//! derive next to the `ToolArgs` and `ToolSchema` traits) rather than
+//! depending on this crate directly:
+//!
+//! ```rust,ignore
+//! use serde::Deserialize;
+//! use spec_ai_plugin::ToolArgs;
+//!
+//! #[derive(Deserialize, ToolArgs)]
+//! struct GreetingArgs {
+//!     /// Name of the person to greet
+//!     name: String,
+//!     /// Language for the greeting
+//!     #[tool(values("en", "es", "fr"))]
+//!     language: Option<String>,
+//! }
+//! ```
+//!
+//! Field doc comments become property descriptions, `Option` fields and
+//! fields with `#[serde(default)]` are optional, and `#[serde(rename)]`,
+//! `#[serde(rename_all)]`, `#[serde(skip)]` and `#[serde(deny_unknown_fields)]`
+//! are honored. `#[tool(description = "...")]` overrides the doc comment and
+//! `#[tool(values(...))]` restricts a string field to the listed values.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, Meta,
+    Token, Type,
+};
+
+#[proc_macro_derive(ToolArgs, attributes(tool))]
+pub fn derive_tool_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Container-level serde settings that change the schema
+#[derive(Default)]
+struct ContainerAttrs {
+    rename_all: Option<String>,
+    default: bool,
+    deny_unknown_fields: bool,
+}
+
+/// Field-level settings gathered from doc comments, serde and tool attributes
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    default: bool,
+    skip: bool,
+    description: Option<String>,
+    values: Vec<String>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "ToolArgs cannot be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ToolArgs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ToolArgs can only be derived for structs",
+            ))
+        }
+    };
+
+    let container = container_attrs(&input.attrs)?;
+    let mut properties = Vec::new();
+    for field in fields {
+        let attrs = field_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field
+            .ident
+            .as_ref()
+            .expect("named field")
+            .unraw()
+            .to_string();
+        let name = match (&attrs.rename, &container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => apply_rename_rule(&ident, rule, &field.ty)?,
+            (None, None) => ident,
+        };
+        let required = !(attrs.default || container.default || is_option(&field.ty));
+        let ty = &field.ty;
+
+        let description = attrs.description.map(|text| {
+            quote! {
+                object.insert(
+                    "description".to_string(),
+                    ::spec_ai_plugin::__private::serde_json::Value::from(#text),
+                );
+            }
+        });
+        let values = (!attrs.values.is_empty()).then(|| {
+            let values = &attrs.values;
+            quote! {
+                object.insert(
+                    "enum".to_string(),
+                    ::spec_ai_plugin::__private::serde_json::Value::from(
+                        ::std::vec![#(#values),*],
+                    ),
+                );
+            }
+        });
+        let annotate = (description.is_some() || values.is_some()).then(|| {
+            quote! {
+                if let ::std::option::Option::Some(object) = schema.as_object_mut() {
+                    #description
+                    #values
+                }
+            }
+        });
+        let push_required = required.then(|| quote! { required.push(#name); });
+
+        properties.push(quote! {
+            {
+                #[allow(unused_mut)]
+                let mut schema = <#ty as ::spec_ai_plugin::ToolSchema>::schema();
+                #annotate
+                properties.insert(#name.to_string(), schema);
+                #push_required
+            }
+        });
+    }
+
+    let additional = container.deny_unknown_fields.then(|| {
+        quote! {
+            object.insert(
+                "additionalProperties".to_string(),
+                ::spec_ai_plugin::__private::serde_json::Value::Bool(false),
+            );
+        }
+    });
+    let ident = &input.ident;
+    Ok(quote! {
+        impl ::spec_ai_plugin::ToolSchema for #ident {
+            #[allow(unused_mut)]
+            fn schema() -> ::spec_ai_plugin::__private::serde_json::Value {
+                let mut properties = ::spec_ai_plugin::__private::serde_json::Map::new();
+                let mut required: ::std::vec::Vec<&str> = ::std::vec::Vec::new();
+                #(#properties)*
+
+                let mut object = ::spec_ai_plugin::__private::serde_json::Map::new();
+                object.insert(
+                    "type".to_string(),
+                    ::spec_ai_plugin::__private::serde_json::Value::from("object"),
+                );
+                object.insert(
+                    "properties".to_string(),
+                    ::spec_ai_plugin::__private::serde_json::Value::Object(properties),
+                );
+                object.insert(
+                    "required".to_string(),
+                    ::spec_ai_plugin::__private::serde_json::Value::from(required),
+                );
+                #additional
+                ::spec_ai_plugin::__private::serde_json::Value::Object(object)
+            }
+        }
+
+        impl ::spec_ai_plugin::ToolArgs for #ident {}
+    })
+}
+
+fn container_attrs(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+    let mut container = ContainerAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") && meta.input.peek(Token![=]) {
+                let rule: LitStr = meta.value()?.parse()?;
+                container.rename_all = Some(rule.value());
+            } else if meta.path.is_ident("default") {
+                container.default = true;
+                skip_meta_value(&meta)?;
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                container.deny_unknown_fields = true;
+            } else {
+                skip_meta_value(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(container)
+}
+
+fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut field = FieldAttrs::default();
+    let mut doc_lines = Vec::new();
+    for attr in attrs {
+        if attr.path().is_ident("doc") {
+            if let Meta::NameValue(doc) = &attr.meta {
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) = &doc.value
+                {
+                    let line = text.value().trim().to_string();
+                    if !line.is_empty() {
+                        doc_lines.push(line);
+                    }
+                }
+            }
+        } else if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                    let rename: LitStr = meta.value()?.parse()?;
+                    field.rename = Some(rename.value());
+                } else if meta.path.is_ident("default") {
+                    field.default = true;
+                    skip_meta_value(&meta)?;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    field.skip = true;
+                } else {
+                    skip_meta_value(&meta)?;
+                }
+                Ok(())
+            })?;
+        } else if attr.path().is_ident("tool") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("description") {
+                    let text: LitStr = meta.value()?.parse()?;
+                    field.description = Some(text.value());
+                    Ok(())
+                } else if meta.path.is_ident("values") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let values = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    field.values = values.iter().map(LitStr::value).collect();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `description = \"...\"` or `values(...)`"))
+                }
+            })?;
+        }
+    }
+    if field.description.is_none() && !doc_lines.is_empty() {
+        field.description = Some(doc_lines.join(" "));
+    }
+    Ok(field)
+}
+
+/// Consume `= value` or `(...)` after a serde key this macro does not use
+fn skip_meta_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        let _: Expr = meta.value()?.parse()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        let _: TokenStream2 = content.parse()?;
+    }
+    Ok(())
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Apply a serde `rename_all` rule to a snake_case field name
+fn apply_rename_rule(field: &str, rule: &str, span: &Type) -> syn::Result<String> {
+    let words: Vec<&str> = field.split('_').filter(|w| !w.is_empty()).collect();
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+    Ok(match rule {
+        "snake_case" => field.to_string(),
+        "lowercase" => field.replace('_', ""),
+        "UPPERCASE" => field.replace('_', "").to_uppercase(),
+        "SCREAMING_SNAKE_CASE" => field.to_uppercase(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_uppercase(),
+        "PascalCase" => words.iter().map(|w| capitalize(w)).collect(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_string() } else { capitalize(w) })
+            .collect(),
+        other => {
+            return Err(syn::Error::new_spanned(
+                span,
+                format!("unsupported serde rename_all rule \"{}\"", other),
+            ))
+        }
+    })
+}
@@ -1,5 +1,5 @@
 pub use spec_ai_config::{config, persistence, types};
-pub use spec_ai_core::{agent, bootstrap_self, cli, embeddings, spec, test_utils, tools};
+pub use spec_ai_core::{agent, bootstrap_self, cli, embeddings, progress, spec, test_utils, tools};
 pub use spec_ai_policy::{plugin, policy};
 
 #[cfg(feature = "api")]
@@ -3,6 +3,7 @@ use spec_ai::agent::factory::create_provider;
 use spec_ai::agent::{AgentBuilder, AgentCore};
 use spec_ai::config::{AgentProfile, AppConfig, ModelConfig};
 use spec_ai::persistence::Persistence;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio;
@@ -83,6 +84,28 @@ async fn main() -> Result<()> {
         enable_audio_transcription: false,
         audio_response_mode: "immediate".to_string(),
         audio_scenario: None,
+        memory_importance_weight: 0.0,
+        memory_decay_half_life_hours: 168.0,
+        enable_reflection: false,
+        max_tokens: None,
+        stop_sequences: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        top_k: None,
+        seed: None,
+        deterministic: false,
+        context_overflow_retries: 3,
+        verify_responses: false,
+        critic_profile: None,
+        post_processors: Vec::new(),
+        response_language: None,
+        translate_responses: false,
+        time_context: false,
+        context_providers: Vec::new(),
+        model_routes: HashMap::new(),
+        context_compression: None,
+        tool_calling: Default::default(),
+        advertise_failing_tools: false,
     };
 
     // Build agent with fast model provider
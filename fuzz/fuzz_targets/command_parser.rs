@@ -0,0 +1,11 @@
+//! REPL input parsing
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spec_ai_core::cli::parse_command;
+
+fuzz_target!(|input: &str| {
+    let _ = parse_command(input);
+    // Every input is also tried as a slash command
+    let _ = parse_command(&format!("/{}", input));
+});
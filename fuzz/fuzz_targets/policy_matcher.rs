@@ -0,0 +1,52 @@
+//! Wildcard matching of policy rules
+//!
+//! Besides not panicking, checks the properties rules rely on: `*` matches
+//! everything, a pattern without wildcards matches only itself, and
+//! replacing any part of the text with `*` still matches the text.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use spec_ai_policy::policy::{PolicyEffect, PolicyRule};
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    pattern: String,
+    text: String,
+    /// Byte range of `text` replaced by `*` for the generalization check
+    start: usize,
+    len: usize,
+}
+
+fn rule(resource: &str) -> PolicyRule {
+    PolicyRule {
+        agent: "*".to_string(),
+        action: "*".to_string(),
+        resource: resource.to_string(),
+        effect: PolicyEffect::Allow,
+        alternative: None,
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let matched = rule(&input.pattern).matches("agent", "action", &input.text);
+    if !input.pattern.contains('*') {
+        assert_eq!(matched, input.pattern == input.text);
+    }
+
+    assert!(rule("*").matches("agent", "action", &input.text));
+    let text = input.text.replace('*', "");
+    assert!(rule(&text).matches("agent", "action", &text));
+
+    let start = input.start % (text.len() + 1);
+    let end = (start + input.len % (text.len() - start + 1)).min(text.len());
+    if text.is_char_boundary(start) && text.is_char_boundary(end) {
+        let generalized = format!("{}*{}", &text[..start], &text[end..]);
+        assert!(
+            rule(&generalized).matches("agent", "action", &text),
+            "{:?} does not match {:?}",
+            generalized,
+            text
+        );
+    }
+});
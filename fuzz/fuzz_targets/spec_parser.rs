@@ -0,0 +1,24 @@
+//! `.spec` parsing and everything done with a parsed spec before it runs
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spec_ai_core::spec::AgentSpec;
+use std::collections::BTreeMap;
+
+fuzz_target!(|contents: &str| {
+    let Ok(mut spec) = AgentSpec::from_str(contents) else {
+        return;
+    };
+    let _ = spec.to_prompt();
+    let _ = spec.preview();
+    let _ = spec.display_name();
+    let _ = spec.language_sample();
+
+    let values: BTreeMap<String, String> = spec
+        .variables
+        .keys()
+        .map(|name| (name.clone(), "{{".repeat(3)))
+        .collect();
+    spec.apply_variables(&values);
+    let _ = spec.to_prompt();
+});
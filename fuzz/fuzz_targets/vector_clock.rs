@@ -0,0 +1,18 @@
+//! Vector clocks received from peers as JSON
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spec_ai_config::sync::VectorClock;
+
+fuzz_target!(|data: (&str, &str)| {
+    let (Ok(mut local), Ok(remote)) = (
+        VectorClock::from_json(data.0),
+        VectorClock::from_json(data.1),
+    ) else {
+        return;
+    };
+    let _ = local.compare(&remote);
+    local.merge(&remote);
+    let _ = local.to_json();
+    let _ = local.to_string();
+});
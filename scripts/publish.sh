@@ -9,6 +9,7 @@ set -euo pipefail
 #   spec-ai-plugin (no internal deps)
 #   spec-ai-core   -> spec-ai-config, spec-ai-policy, spec-ai-plugin
 #   spec-ai-api    -> spec-ai-core, spec-ai-config, spec-ai-policy
+#   spec-ai-testing -> spec-ai-core, spec-ai-config
 #   spec-ai        -> spec-ai-core, spec-ai-config, spec-ai-policy, spec-ai-api
 #   spec-ai-cli    -> spec-ai
 
@@ -18,6 +19,7 @@ CRATES=(
     "spec-ai-plugin"
     "spec-ai-core"
     "spec-ai-api"
+    "spec-ai-testing"
     "spec-ai-cli"
     "spec-ai"
 )
@@ -76,3 +76,84 @@ fn test_bootstrap_self_creates_graph_artifacts() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_bootstrap_records_locked_dependencies_and_advisories() -> Result<()> {
+    let temp = tempdir()?;
+    let repo_root = temp.path().join("repo");
+    create_sample_repo(&repo_root)?;
+    fs::write(
+        repo_root.join("Cargo.lock"),
+        r#"
+version = 3
+
+[[package]]
+name = "bootstrap-fixture"
+version = "0.2.0"
+
+[[package]]
+name = "serde"
+version = "1.0.100"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "smallvec"
+version = "0.6.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+    )?;
+
+    let advisory_db = temp.path().join("advisory-db");
+    fs::create_dir_all(advisory_db.join("crates/smallvec"))?;
+    fs::write(
+        advisory_db.join("crates/smallvec/RUSTSEC-2019-0009.md"),
+        r#"```toml
+[advisory]
+id = "RUSTSEC-2019-0009"
+package = "smallvec"
+date = "2019-06-06"
+aliases = ["CVE-2019-15551"]
+
+[versions]
+patched = [">= 0.6.10"]
+```
+
+# Double-free and use-after-free in SmallVec::grow()
+"#,
+    )?;
+    std::env::set_var("SPEC_AI_ADVISORY_DB", &advisory_db);
+    std::env::set_var("SPEC_AI_ADVISORY_OFFLINE", "1");
+
+    let persistence = Persistence::new(temp.path().join("bootstrap.db"))?;
+    let session = "audit-session";
+    BootstrapSelf::new(&persistence, session, repo_root.clone()).run()?;
+
+    let nodes = persistence.list_graph_nodes(session, None, None)?;
+    let dependencies: Vec<_> = nodes.iter().filter(|n| n.label == "Dependency").collect();
+    assert_eq!(dependencies.len(), 2, "workspace members are not dependencies");
+    let serde = dependencies
+        .iter()
+        .find(|n| n.properties["name"] == "serde")
+        .expect("serde dependency missing");
+    assert_eq!(serde.properties["direct"], json!(true));
+    assert_eq!(serde.properties["vulnerable"], json!(false));
+
+    let smallvec = dependencies
+        .iter()
+        .find(|n| n.properties["name"] == "smallvec")
+        .expect("smallvec dependency missing");
+    let vulnerability = nodes
+        .iter()
+        .find(|n| n.label == "Vulnerability")
+        .expect("vulnerability node missing");
+    assert_eq!(vulnerability.properties["aliases"], json!(["CVE-2019-15551"]));
+    let edges = persistence.list_graph_edges(session, Some(smallvec.id), None)?;
+    assert!(edges
+        .iter()
+        .any(|e| e.target_id == vulnerability.id && e.predicate.as_deref() == Some("vulnerable_to")));
+
+    let repo_node = nodes.iter().find(|n| n.label == "Repository").unwrap();
+    assert_eq!(repo_node.properties["vulnerability_count"], json!(1));
+    assert_eq!(repo_node.properties["advisory_db"]["status"], json!("local"));
+    Ok(())
+}
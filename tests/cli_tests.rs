@@ -352,6 +352,16 @@ async fn test_init_command_gating() {
     let _ = cli.handle_line("/session new fresh").await.unwrap();
     let second = cli.handle_line("/init").await.unwrap().unwrap();
     assert!(second.contains("Knowledge graph bootstrap complete"));
+
+    let _ = cli.handle_line("Hello again").await.unwrap();
+    let confirm = cli.handle_line("/init --force").await.unwrap().unwrap();
+    assert!(confirm.contains("/init --force --yes"));
+    let forced = cli
+        .handle_line("/init --force --yes")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(forced.contains("Knowledge graph bootstrap complete"));
 }
 
 /// Test agent switching preserves session but changes agent context
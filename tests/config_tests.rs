@@ -1,5 +1,6 @@
-use spec_ai::config::{AgentProfile, AppConfig};
+use spec_ai::config::{AgentProfile, AppConfig, TimeConfig};
 use spec_ai::test_utils::env_lock;
+use spec_ai::types::{EdgeType, NodeType};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
@@ -150,6 +151,45 @@ fn test_validate_default_agent_not_found() {
     );
 }
 
+#[test]
+fn test_validate_roundtable_participants() {
+    let mut config = AppConfig::default();
+    config.model.provider = "mock".to_string();
+    for name in ["skeptic", "optimist", "judge"] {
+        config
+            .agents
+            .insert(name.to_string(), AgentProfile::default());
+    }
+
+    config.roundtable.participants = vec!["skeptic".to_string()];
+    assert!(config.validate().is_err(), "one participant is too few");
+
+    config.roundtable.participants.push("optimist".to_string());
+    config.roundtable.moderator = Some("judge".to_string());
+    assert!(config.validate().is_ok());
+
+    config.roundtable.moderator = Some("missing".to_string());
+    assert!(config.validate().is_err(), "unknown moderator must fail");
+}
+
+#[test]
+fn test_validate_critic_profile_exists() {
+    let mut config = AppConfig::default();
+    config.model.provider = "mock".to_string();
+    let writer = AgentProfile {
+        verify_responses: true,
+        critic_profile: Some("critic".to_string()),
+        ..AgentProfile::default()
+    };
+    config.agents.insert("writer".to_string(), writer);
+    assert!(config.validate().is_err(), "missing critic must fail");
+
+    config
+        .agents
+        .insert("critic".to_string(), AgentProfile::default());
+    assert!(config.validate().is_ok());
+}
+
 #[test]
 fn test_agent_profile_tool_restrictions() {
     let mut profile = AgentProfile::default();
@@ -252,3 +292,205 @@ fn test_agent_profile_effective_values() {
         Some("gpt-4")
     );
 }
+
+#[test]
+fn test_composite_tools_short_and_long_form() {
+    let config: AppConfig = toml::from_str(
+        r#"
+[composite_tools]
+fetch_and_summarize = ["web_scraper", "summarize"]
+
+[composite_tools.save_page]
+description = "Fetch a page and save it"
+parameters = { url = "Page address", path = "Output file" }
+steps = [
+  { tool = "web_scraper", args = { url = "{{url}}" } },
+  { tool = "file_write", args = { path = "{{path}}", content = "{{previous}}" } },
+]
+"#,
+    )
+    .unwrap();
+
+    let pipeline = &config.composite_tools["fetch_and_summarize"];
+    let tools: Vec<String> = pipeline.steps().into_iter().map(|s| s.tool).collect();
+    assert_eq!(tools, vec!["web_scraper", "summarize"]);
+    assert!(pipeline.parameters().is_none());
+
+    let save = &config.composite_tools["save_page"];
+    assert_eq!(save.description(), Some("Fetch a page and save it"));
+    assert_eq!(save.parameters().unwrap().len(), 2);
+    assert_eq!(save.steps()[1].args.as_ref().unwrap()["path"], "{{path}}");
+}
+
+#[test]
+fn test_time_config_offsets() {
+    let config: AppConfig = toml::from_str(
+        r#"
+[time]
+timezone = "UTC-05:30"
+calendar_file = "calendar.ics"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.time.calendar_days, 7);
+    assert_eq!(
+        config.time.utc_offset_seconds().unwrap(),
+        Some(-(5 * 3600 + 30 * 60))
+    );
+
+    let mut time = TimeConfig::default();
+    assert_eq!(time.utc_offset_seconds().unwrap(), None);
+    time.timezone = "utc".into();
+    assert_eq!(time.utc_offset_seconds().unwrap(), Some(0));
+    time.timezone = "+0200".into();
+    assert_eq!(time.utc_offset_seconds().unwrap(), Some(7200));
+    time.timezone = "Europe/Berlin".into();
+    assert!(time.utc_offset_seconds().is_err());
+}
+
+#[test]
+fn test_mesh_quotas_with_agent_overrides() {
+    let config: AppConfig = toml::from_str(
+        r#"
+[mesh.quotas]
+max_concurrent_runs = 4
+daily_token_budget = 2000000
+
+[mesh.quotas.agents.researcher]
+max_concurrent_runs = 1
+"#,
+    )
+    .unwrap();
+    let quotas = &config.mesh.quotas;
+    assert_eq!(quotas.instance.max_concurrent_runs, Some(4));
+    assert_eq!(quotas.instance.daily_token_budget, Some(2_000_000));
+    assert_eq!(quotas.instance.max_messages_per_hour, None);
+    assert_eq!(quotas.agents["researcher"].max_concurrent_runs, Some(1));
+    assert!(AppConfig::default().mesh.quotas.instance.is_unlimited());
+}
+
+#[test]
+fn test_sync_acl_rules() {
+    let config: AppConfig = toml::from_str(
+        r#"
+[[sync.acl]]
+session = "project-x-*"
+read = ["laptop-1", "tag:trusted"]
+write = ["laptop-1"]
+
+[[sync.acl]]
+graph = "shared"
+read = ["*"]
+"#,
+    )
+    .unwrap();
+    let acl = &config.sync.acl;
+    assert_eq!(acl.len(), 2);
+    assert_eq!(acl[0].graph, "*");
+    assert_eq!(acl[0].read, vec!["laptop-1", "tag:trusted"]);
+    assert_eq!(acl[1].session, "*");
+    assert!(acl[1].write.is_empty());
+    assert!(AppConfig::default().sync.acl.is_empty());
+}
+
+#[test]
+fn test_sessions_lifecycle() {
+    let mut config: AppConfig = toml::from_str(
+        r#"
+[sessions]
+archive_after_days = 30
+delete_after_days = 365
+"#,
+    )
+    .unwrap();
+    assert!(config.sessions.is_enabled());
+    assert!(config.validate().is_ok());
+
+    config.sessions.delete_after_days = Some(30);
+    assert!(config.validate().is_err(), "deleting before archiving");
+    config.sessions.delete_after_days = None;
+    config.sessions.archive_after_days = Some(0);
+    assert!(config.validate().is_err(), "zero days");
+    assert!(!AppConfig::default().sessions.is_enabled());
+}
+
+#[test]
+fn test_graph_types() {
+    let mut config: AppConfig = toml::from_str(
+        r##"
+[graph.node_types.incident]
+label = "Incident"
+color = "#d62728"
+
+[graph.node_types.entity]
+color = "#1f77b4"
+
+[graph.edge_types.BLOCKS]
+label = "blocks"
+"##,
+    )
+    .unwrap();
+    assert!(config.validate().is_ok());
+    let graph = &config.graph;
+    assert_eq!(
+        graph.node_type("Incident").unwrap(),
+        NodeType::Other("incident".to_string())
+    );
+    assert_eq!(graph.node_type("entity").unwrap(), NodeType::Entity);
+    assert!(graph.node_type("runbook").is_err(), "undeclared type");
+    assert_eq!(
+        graph.node_label(&NodeType::Other("incident".to_string())),
+        "Incident"
+    );
+    assert_eq!(graph.node_label(&NodeType::Entity), "entity");
+    assert_eq!(
+        graph.node_style(&NodeType::Entity).unwrap().rgb(),
+        Some((0x1f, 0x77, 0xb4))
+    );
+    assert_eq!(graph.edge_label(&EdgeType::from_str("blocks")), "blocks");
+    let names = graph.node_type_names();
+    assert_eq!(names.last().map(String::as_str), Some("incident"));
+    assert_eq!(names.iter().filter(|name| *name == "entity").count(), 1);
+    assert_eq!(
+        graph.edge_type_names().last().map(String::as_str),
+        Some("BLOCKS")
+    );
+
+    config.graph.node_types.get_mut("incident").unwrap().color = Some("red".to_string());
+    assert!(config.validate().is_err(), "color not #rrggbb");
+    let mut config = AppConfig::default();
+    config
+        .graph
+        .node_types
+        .insert("Incident".to_string(), Default::default());
+    assert!(config.validate().is_err(), "node type not lowercase");
+    let mut config = AppConfig::default();
+    config
+        .graph
+        .edge_types
+        .insert("blocks".to_string(), Default::default());
+    assert!(config.validate().is_err(), "edge type not uppercase");
+}
+
+#[test]
+fn test_tool_timeouts() {
+    let mut config: AppConfig = toml::from_str(
+        r#"
+tool_timeout_secs = 120
+
+[agents.coder.tool_timeouts]
+bash = 600
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.tool_timeout_secs, Some(120));
+    assert_eq!(config.agents["coder"].tool_timeouts["bash"], 600);
+    assert!(config.validate().is_ok());
+    assert!(config.agents["coder"].validate().is_ok());
+
+    config.tool_timeout_secs = Some(0);
+    assert!(config.validate().is_err(), "zero seconds");
+    let coder = config.agents.get_mut("coder").unwrap();
+    coder.tool_timeouts.insert("web_search".to_string(), 0);
+    assert!(coder.validate().is_err(), "zero seconds for one tool");
+}
@@ -309,3 +309,238 @@ fn test_graph_isolation_by_session() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_graph_crdt_properties() -> Result<()> {
+    let persistence = Persistence::in_memory()?;
+    let node_id = persistence.insert_graph_node(
+        "crdt_session",
+        NodeType::Entity,
+        "Topic",
+        &json!({"name": "Rust"}),
+        None,
+    )?;
+
+    assert_eq!(persistence.graph_counter_increment(node_id, "mentions", 2)?, 2);
+    assert_eq!(persistence.graph_counter_increment(node_id, "mentions", 1)?, 3);
+    persistence.graph_set_add(node_id, "tags", &json!("lang"))?;
+    persistence.graph_set_add(node_id, "tags", &json!("systems"))?;
+    let tags = persistence.graph_set_remove(node_id, "tags", &json!("lang"))?;
+    assert_eq!(tags, vec![json!("systems")]);
+
+    let node = persistence.get_graph_node(node_id)?.unwrap();
+    assert_eq!(node.properties["name"], "Rust");
+    assert_eq!(node.properties["mentions"]["$crdt"], "g_counter");
+    assert_eq!(node.properties["tags"]["$crdt"], "or_set");
+    assert_eq!(node.properties["tags"]["removed"].as_array().unwrap().len(), 1);
+
+    // A property of another kind is not silently replaced
+    assert!(persistence.graph_set_add(node_id, "mentions", &json!(1)).is_err());
+    assert!(persistence.graph_counter_increment(node_id, "name", 1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_graph_node_pinning() -> Result<()> {
+    let persistence = Persistence::in_memory()?;
+    let first = persistence.insert_graph_node(
+        "pins",
+        NodeType::Concept,
+        "Architecture",
+        &json!({"name": "hexagonal"}),
+        None,
+    )?;
+    let second = persistence.insert_graph_node(
+        "pins",
+        NodeType::Fact,
+        "Invariant",
+        &json!({"text": "handlers never touch the database"}),
+        None,
+    )?;
+
+    assert!(persistence.list_pinned_graph_nodes("pins")?.is_empty());
+    assert!(persistence.pin_graph_node(first)?);
+    assert!(persistence.pin_graph_node(second)?);
+    // Pinning twice keeps a single pin
+    assert!(persistence.pin_graph_node(first)?);
+    assert!(!persistence.pin_graph_node(9_999)?);
+
+    let pinned = persistence.list_pinned_graph_nodes("pins")?;
+    assert_eq!(pinned.len(), 2);
+    assert!(persistence.list_pinned_graph_nodes("other")?.is_empty());
+
+    assert!(persistence.unpin_graph_node(second)?);
+    assert!(!persistence.unpin_graph_node(second)?);
+
+    // Deleting a node drops its pin
+    persistence.delete_graph_node(first)?;
+    assert!(persistence.list_pinned_graph_nodes("pins")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_named_graphs() -> Result<()> {
+    let persistence = Persistence::in_memory()?;
+    let session = "named";
+    let service = persistence.insert_graph_node(
+        session,
+        NodeType::Entity,
+        "Service",
+        &json!({"name": "billing"}),
+        None,
+    )?;
+    let outage = persistence.insert_graph_node_in_graph(
+        session,
+        "incidents",
+        NodeType::Event,
+        "Outage",
+        &json!({"name": "2026-03 outage"}),
+        None,
+    )?;
+    let cause = persistence.insert_graph_node_in_graph(
+        session,
+        "incidents",
+        NodeType::Fact,
+        "Cause",
+        &json!({"text": "expired certificate"}),
+        None,
+    )?;
+    persistence.insert_graph_edge(
+        session,
+        cause,
+        outage,
+        EdgeType::CausedBy,
+        None,
+        None,
+        1.0,
+    )?;
+
+    // Edges never join nodes of different graphs
+    assert!(persistence
+        .insert_graph_edge(session, service, outage, EdgeType::RelatesTo, None, None, 1.0)
+        .is_err());
+
+    assert_eq!(persistence.graph_list(session)?, ["default", "incidents"]);
+    assert_eq!(persistence.graph_node_graph_name(cause)?, "incidents");
+    assert_eq!(persistence.graph_node_graph_name(service)?, "default");
+
+    let default_nodes = persistence.list_graph_nodes_in_graph(session, "default", None, None)?;
+    assert_eq!(default_nodes.len(), 1);
+    let incident_nodes =
+        persistence.list_graph_nodes_in_graph(session, "incidents", Some(NodeType::Fact), None)?;
+    assert_eq!(incident_nodes.len(), 1);
+    assert_eq!(incident_nodes[0].id, cause);
+    // Unscoped listing still spans every graph of the session
+    assert_eq!(persistence.list_graph_nodes(session, None, None)?.len(), 3);
+    assert_eq!(
+        persistence
+            .list_graph_edges_in_graph(session, "incidents", None, None)?
+            .len(),
+        1
+    );
+    assert!(persistence
+        .list_graph_edges_in_graph(session, "default", None, None)?
+        .is_empty());
+
+    // The active graph starts as "default" and is remembered per session
+    assert_eq!(persistence.graph_get_active(session)?, "default");
+    persistence.graph_set_active(session, "drafts")?;
+    assert_eq!(persistence.graph_get_active(session)?, "drafts");
+    assert_eq!(persistence.graph_get_active("other")?, "default");
+    assert!(persistence.graph_list(session)?.contains(&"drafts".to_string()));
+
+    // Sync is configured per graph
+    persistence.graph_set_sync_enabled(session, "incidents", true)?;
+    assert!(persistence.graph_get_sync_enabled(session, "incidents")?);
+    assert!(!persistence.graph_get_sync_enabled(session, "default")?);
+    let fix = persistence.insert_graph_node_in_graph(
+        session,
+        "incidents",
+        NodeType::Fact,
+        "Fix",
+        &json!({"text": "certificate renewal is automated"}),
+        None,
+    )?;
+    persistence.insert_graph_node(
+        session,
+        NodeType::Entity,
+        "Service",
+        &json!({"name": "ledger"}),
+        None,
+    )?;
+    let changes = persistence.graph_changelog_get_since(session, "1970-01-01 00:00:00")?;
+    assert_eq!(changes.len(), 1);
+
+    // Deleting a node forgets which graph it was in
+    persistence.delete_graph_node(fix)?;
+    assert_eq!(persistence.graph_node_graph_name(fix)?, "default");
+    Ok(())
+}
+
+#[test]
+fn test_graph_node_semantic_search() -> Result<()> {
+    let persistence = Persistence::in_memory()?;
+    let rust = persistence.insert_graph_node(
+        "semantic",
+        NodeType::Concept,
+        "Language",
+        &json!({"name": "Rust"}),
+        None,
+    )?;
+    let coffee = persistence.insert_graph_node(
+        "semantic",
+        NodeType::Entity,
+        "Drink",
+        &json!({"name": "coffee"}),
+        None,
+    )?;
+    persistence.insert_graph_node(
+        "semantic",
+        NodeType::Message,
+        "Message",
+        &json!({"message_id": 1}),
+        None,
+    )?;
+
+    // Message nodes share their message's vector and are never pending
+    let pending = persistence.graph_nodes_without_embedding("semantic", 10)?;
+    assert_eq!(
+        pending.iter().map(|n| n.id).collect::<Vec<_>>(),
+        vec![rust, coffee]
+    );
+    assert_eq!(pending[0].embedding_text(), r#"Language {"name":"Rust"}"#);
+
+    persistence.insert_graph_node_embedding(rust, "semantic", &[1.0, 0.0])?;
+    persistence.insert_graph_node_embedding(coffee, "semantic", &[0.0, 1.0])?;
+    assert!(persistence
+        .graph_nodes_without_embedding("semantic", 10)?
+        .is_empty());
+
+    let hits = persistence.search_graph_nodes_semantic("semantic", &[0.9, 0.1], 2)?;
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].0.id, rust);
+    assert!(hits[0].1 > hits[1].1);
+    assert!(persistence
+        .search_graph_nodes_semantic("other", &[0.9, 0.1], 2)?
+        .is_empty());
+
+    // Node vectors stay out of message recall
+    assert!(persistence
+        .recall_top_k("semantic", &[1.0, 0.0], 5)?
+        .is_empty());
+
+    // A changed node is embedded again; a deleted one is no longer found
+    persistence.update_graph_node(rust, &json!({"name": "Rust", "edition": 2021}))?;
+    assert_eq!(
+        persistence.get_graph_node(rust)?.unwrap().embedding_id,
+        None
+    );
+    let pending = persistence.graph_nodes_without_embedding("semantic", 10)?;
+    assert_eq!(pending.len(), 1);
+    persistence.delete_graph_node(coffee)?;
+    assert!(persistence
+        .search_graph_nodes_semantic("semantic", &[0.0, 1.0], 2)?
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,426 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use spec_ai::config::GraphConfig;
+use spec_ai::persistence::Persistence;
+use spec_ai::tools::builtin::{
+    EntityTimelineTool, GraphSnapshot, GraphTool, IngestCiLogTool, KnowledgeGraphStore,
+    LookupEntityTool, RelateEntitiesTool, RememberFactTool,
+};
+use spec_ai::tools::{Tool, ToolRegistry};
+use spec_ai::types::{EdgeType, NodeType};
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_remember_lookup_and_timeline() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("knowledge.db"))?);
+    let store = KnowledgeGraphStore::new(persistence.clone());
+    let remember = RememberFactTool::new(store.clone());
+    let lookup = LookupEntityTool::new(store.clone());
+    let timeline = EntityTimelineTool::new(store.clone());
+    let relate = RelateEntitiesTool::new(store);
+    let session_id = "knowledge_session";
+
+    let result = remember
+        .execute(json!({
+            "entity": "Billing Service",
+            "entity_type": "Service",
+            "fact": "The billing service runs on port 8080.",
+            "session_id": session_id
+        }))
+        .await?;
+    assert!(result.success);
+    let first: Value = serde_json::from_str(&result.output)?;
+    assert_eq!(first["entity_created"], true);
+
+    // Entity names resolve case-insensitively, so the second fact reuses the node
+    let result = remember
+        .execute(json!({
+            "entity": "billing service",
+            "fact": "It is owned by the payments team.",
+            "session_id": session_id
+        }))
+        .await?;
+    let second: Value = serde_json::from_str(&result.output)?;
+    assert_eq!(second["entity_created"], false);
+    assert_eq!(second["entity_id"], first["entity_id"]);
+
+    let result = relate
+        .execute(json!({
+            "source": "Billing Service",
+            "relation": "depends on",
+            "target": "Postgres",
+            "session_id": session_id
+        }))
+        .await?;
+    assert!(result.success);
+    let edge: Value = serde_json::from_str(&result.output)?;
+    assert_eq!(edge["relation"], "depends_on");
+    assert_eq!(edge["existing"], false);
+    let edges = persistence.list_graph_edges(
+        session_id,
+        edge["source_id"].as_i64(),
+        edge["target_id"].as_i64(),
+    )?;
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].edge_type, EdgeType::DependsOn);
+
+    // Relating the same pair again does not duplicate the edge
+    let again: Value = serde_json::from_str(
+        &relate
+            .execute(json!({
+                "source": "billing service",
+                "relation": "depends_on",
+                "target": "postgres",
+                "session_id": session_id
+            }))
+            .await?
+            .output,
+    )?;
+    assert_eq!(again["existing"], true);
+    assert_eq!(again["edge_id"], edge["edge_id"]);
+
+    let result = lookup
+        .execute(json!({"name": "BILLING SERVICE", "session_id": session_id}))
+        .await?;
+    assert!(result.success);
+    let payload: Value = serde_json::from_str(&result.output)?;
+    let entity = &payload["entities"][0];
+    assert_eq!(entity["name"], "Billing Service");
+    assert_eq!(entity["type"], "Service");
+    assert_eq!(entity["facts"].as_array().unwrap().len(), 2);
+    assert_eq!(entity["relations"][0]["name"], "Postgres");
+    assert_eq!(entity["relations"][0]["direction"], "outgoing");
+
+    let result = timeline
+        .execute(json!({"name": "Billing Service", "session_id": session_id}))
+        .await?;
+    let payload: Value = serde_json::from_str(&result.output)?;
+    let events = payload["events"].as_array().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0]["summary"], "The billing service runs on port 8080.");
+    assert_eq!(events[2]["summary"], "Postgres");
+
+    let facts = persistence.list_graph_nodes(session_id, Some(NodeType::Fact), None)?;
+    assert_eq!(facts.len(), 2);
+
+    let missing = lookup
+        .execute(json!({"name": "Unknown", "session_id": session_id}))
+        .await?;
+    assert!(!missing.success);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timeline_follows_stored_timestamps() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("knowledge.db"))?);
+    let store = KnowledgeGraphStore::new(persistence.clone());
+    let remember = RememberFactTool::new(store.clone());
+    let lookup = LookupEntityTool::new(store.clone());
+    let timeline = EntityTimelineTool::new(store);
+    let session_id = "dated_session";
+
+    let mut fact_ids = Vec::new();
+    for fact in ["Deployed to staging.", "Designed in 2020."] {
+        let result = remember
+            .execute(json!({"entity": "Gateway", "fact": fact, "session_id": session_id}))
+            .await?;
+        let payload: Value = serde_json::from_str(&result.output)?;
+        fact_ids.push(payload["fact_id"].as_i64().unwrap());
+    }
+    // The fact recorded second happened first
+    persistence.conn().execute(
+        &format!(
+            "UPDATE graph_nodes SET created_at = TIMESTAMP '2020-03-01 09:30:00' WHERE id = {}",
+            fact_ids[1]
+        ),
+        [],
+    )?;
+
+    let payload: Value = serde_json::from_str(
+        &timeline
+            .execute(json!({"name": "Gateway", "session_id": session_id}))
+            .await?
+            .output,
+    )?;
+    let events = payload["events"].as_array().unwrap();
+    assert_eq!(events[0]["summary"], "Designed in 2020.");
+    assert!(events[0]["at"]
+        .as_str()
+        .unwrap()
+        .starts_with("2020-03-01T09:30:00"));
+    assert_eq!(events[1]["summary"], "Deployed to staging.");
+
+    let payload: Value = serde_json::from_str(
+        &lookup
+            .execute(json!({"name": "Gateway", "session_id": session_id}))
+            .await?
+            .output,
+    )?;
+    let facts = payload["entities"][0]["facts"].as_array().unwrap();
+    assert_eq!(facts[0]["text"], "Deployed to staging.");
+    assert_eq!(facts[1]["text"], "Designed in 2020.");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diff_as_of_follows_stored_timestamps() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("diff.db"))?);
+    let store = KnowledgeGraphStore::new(persistence.clone());
+    let session_id = "diff_session";
+
+    let old = persistence.insert_graph_node(
+        session_id,
+        NodeType::Concept,
+        "Concept",
+        &json!({"name": "Caching"}),
+        None,
+    )?;
+    let new = persistence.insert_graph_node(
+        session_id,
+        NodeType::Concept,
+        "Concept",
+        &json!({"name": "Sharding"}),
+        None,
+    )?;
+    let backdate = |id: i64, created: &str, updated: &str| {
+        persistence.conn().execute(
+            &format!(
+                "UPDATE graph_nodes SET created_at = TIMESTAMP '{}', updated_at = TIMESTAMP '{}' WHERE id = {}",
+                created, updated, id
+            ),
+            [],
+        )
+    };
+    backdate(old, "2020-01-01 00:00:00", "2020-01-01 00:00:00")?;
+    let as_of = "2021-01-01T00:00:00Z".parse()?;
+
+    // Only the node stored after the snapshot moment is new
+    let diff = store
+        .diff(
+            GraphSnapshot::as_of(session_id, as_of),
+            GraphSnapshot::current(session_id),
+        )
+        .await?;
+    let added: Vec<i64> = diff.added_nodes.iter().map(|node| node.id).collect();
+    assert_eq!(added, vec![new]);
+    assert!(diff.changed_nodes.is_empty());
+
+    // Updating the old node after that moment shows it as changed
+    backdate(old, "2020-01-01 00:00:00", "2022-06-01 12:00:00")?;
+    let diff = store
+        .diff(
+            GraphSnapshot::as_of(session_id, as_of),
+            GraphSnapshot::current(session_id),
+        )
+        .await?;
+    assert_eq!(diff.changed_nodes.len(), 1);
+    assert_eq!(diff.changed_nodes[0].after.id, old);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graph_tools_share_policy_resource() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("registry.db"))?);
+    let registry = ToolRegistry::with_builtin_tools(Some(persistence), None);
+
+    for name in [
+        "graph",
+        "remember_fact",
+        "lookup_entity",
+        "entity_timeline",
+        "relate_entities",
+        "ingest_ci_log",
+    ] {
+        let tool = registry.get(name).expect("graph tool registered");
+        assert_eq!(tool.policy_resource(), "graph");
+        assert!(tool.session_scoped());
+    }
+
+    let echo = registry.get("echo").unwrap();
+    assert_eq!(echo.policy_resource(), "echo");
+    assert!(!echo.session_scoped());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ingest_ci_log_links_failures_to_components() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("ci.db"))?);
+    let session_id = "ci_session";
+    let component_id = persistence.insert_graph_node(
+        session_id,
+        NodeType::Entity,
+        "Component",
+        &json!({"name": "src", "path": "src"}),
+        None,
+    )?;
+    let tool = IngestCiLogTool::new(KnowledgeGraphStore::new(persistence.clone()));
+
+    let log = "\
+test parser::rejects_empty ... FAILED
+
+failures:
+
+---- parser::rejects_empty stdout ----
+thread 'parser::rejects_empty' panicked at src/parser.rs:30:5:
+expected an error
+
+test result: FAILED. 4 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+";
+    let result = tool
+        .execute(json!({"content": log, "session_id": session_id}))
+        .await?;
+    assert!(result.success);
+    let output: Value = serde_json::from_str(&result.output)?;
+    assert_eq!(output["format"], "cargo_test");
+    assert_eq!(output["failed"], 1);
+    assert_eq!(output["linked_to_components"], 1);
+    assert!(output["summary"]
+        .as_str()
+        .unwrap()
+        .contains("parser::rejects_empty (src/parser.rs:30): expected an error"));
+
+    let failures = persistence.list_graph_nodes(session_id, Some(NodeType::Fact), None)?;
+    let failure = failures
+        .iter()
+        .find(|node| node.label == "TestFailure")
+        .expect("TestFailure node");
+    assert_eq!(failure.properties["line"], 30);
+    let edges = persistence.list_graph_edges(session_id, Some(failure.id), None)?;
+    assert!(edges
+        .iter()
+        .any(|edge| edge.target_id == component_id && edge.predicate.as_deref() == Some("fails_in")));
+
+    let unknown = tool
+        .execute(json!({"content": "all good", "session_id": session_id}))
+        .await?;
+    assert!(!unknown.success);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subgraph_expands_from_matching_nodes() -> Result<()> {
+    let persistence = Arc::new(Persistence::in_memory()?);
+    let session_id = "subgraph_session";
+    let node = |node_type, label: &str, name: &str, embedding: [f32; 2]| -> Result<i64> {
+        let id = persistence.insert_graph_node(
+            session_id,
+            node_type,
+            label,
+            &json!({ "name": name }),
+            None,
+        )?;
+        persistence.insert_graph_node_embedding(id, session_id, &embedding)?;
+        Ok(id)
+    };
+    let billing = node(NodeType::Entity, "Service", "billing", [1.0, 0.0])?;
+    let invoices = node(NodeType::Fact, "Fact", "invoices are monthly", [0.6, 0.8])?;
+    let postgres = node(NodeType::Entity, "Database", "postgres", [0.0, 1.0])?;
+    node(NodeType::Entity, "Drink", "coffee", [0.0, 1.0])?;
+    persistence.insert_graph_edge(
+        session_id,
+        billing,
+        invoices,
+        EdgeType::RelatesTo,
+        None,
+        None,
+        1.0,
+    )?;
+    persistence.insert_graph_edge(
+        session_id,
+        invoices,
+        postgres,
+        EdgeType::DependsOn,
+        None,
+        None,
+        1.0,
+    )?;
+
+    let store = KnowledgeGraphStore::new(persistence.clone());
+    // Nodes unrelated to the query are no seeds, however many are asked for
+    let subgraph = store.subgraph(session_id, vec![1.0, 0.0], 4, 1).await?;
+    let ids: Vec<i64> = subgraph.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![billing, invoices, postgres]);
+    assert_eq!(subgraph.seeds.len(), 2);
+    assert_eq!(subgraph.edges.len(), 2);
+
+    let subgraph = store.subgraph(session_id, vec![1.0, 0.0], 1, 1).await?;
+    let ids: Vec<i64> = subgraph.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![billing, invoices]);
+    assert_eq!(subgraph.edges.len(), 1);
+    assert!(subgraph.to_mermaid().contains(&format!("n{} -->", billing)));
+
+    let subgraph = store.subgraph(session_id, vec![1.0, 0.0], 1, 2).await?;
+    assert_eq!(subgraph.nodes.len(), 3);
+
+    // The tool needs an embeddings model to match the query
+    let result = GraphTool::with_store(store)
+        .execute(json!({
+            "operation": "subgraph",
+            "query": "billing",
+            "session_id": session_id
+        }))
+        .await?;
+    assert!(!result.success);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declared_node_types_survive_storage() -> Result<()> {
+    let dir = tempdir()?;
+    let persistence = Arc::new(Persistence::new(dir.path().join("types.db"))?);
+    let types: GraphConfig = toml::from_str(
+        r##"
+[node_types.incident]
+label = "Incident"
+color = "#d62728"
+"##,
+    )?;
+    let registry =
+        ToolRegistry::with_builtin_tools(Some(persistence.clone()), None).with_graph_types(&types);
+    let graph = registry.get("graph").expect("graph tool registered");
+    assert!(graph.parameters()["properties"]["node_type"]["enum"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("incident")));
+    let create = |node_type: &str| {
+        json!({
+            "operation": "create_node",
+            "session_id": "types_session",
+            "node_type": node_type,
+            "label": "Outage",
+            "properties": {"name": "database outage"}
+        })
+    };
+
+    // Undeclared types are refused rather than stored as entities
+    let result = graph.execute(create("runbook")).await?;
+    assert!(!result.success);
+    assert!(result
+        .error
+        .unwrap()
+        .contains("Unknown node type 'runbook'"));
+
+    let result = graph.execute(create("Incident")).await?;
+    assert!(result.success);
+    let node_id = serde_json::from_str::<Value>(&result.output)?["node_id"]
+        .as_i64()
+        .unwrap();
+    let node = persistence.get_graph_node(node_id)?.unwrap();
+    assert_eq!(node.node_type, NodeType::Other("incident".to_string()));
+    let incidents = persistence.list_graph_nodes(
+        "types_session",
+        Some(NodeType::from_str("incident")),
+        None,
+    )?;
+    assert_eq!(incidents.len(), 1);
+    Ok(())
+}
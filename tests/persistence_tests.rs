@@ -1,4 +1,5 @@
 use serde_json::json;
+use spec_ai::persistence::importance::MemoryImportanceParams;
 use spec_ai::persistence::Persistence;
 use spec_ai::types::MessageRole;
 use tempfile::tempdir;
@@ -95,6 +96,252 @@ fn memory_vectors_insert_and_recall() {
         .all(|(a, b)| (*a - *b).abs() < 1e-6));
 }
 
+#[test]
+fn recall_index_follows_stored_vectors() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let msg = p
+        .insert_message("sess", MessageRole::User, "hello")
+        .unwrap();
+    let east = p
+        .insert_memory_vector("sess", Some(msg), &[1.0, 0.0])
+        .unwrap();
+    let north = p.insert_memory_vector("sess", None, &[0.0, 1.0]).unwrap();
+    let ids = |k| -> Vec<i64> {
+        p.recall_top_k("sess", &[1.0, 0.1], k)
+            .unwrap()
+            .iter()
+            .map(|(m, _)| m.id)
+            .collect()
+    };
+    assert_eq!(ids(2), vec![east, north]);
+
+    // Vectors stored after the index was built are recalled
+    let close = p.insert_memory_vector("sess", None, &[1.0, 0.1]).unwrap();
+    assert_eq!(ids(1), vec![close]);
+
+    // So are changes made directly in the table
+    p.conn()
+        .execute(
+            &format!("DELETE FROM memory_vectors WHERE id = {}", close),
+            [],
+        )
+        .unwrap();
+    assert_eq!(ids(3), vec![east, north]);
+
+    p.update_message_embedding(msg, &[0.0, -1.0]).unwrap();
+    assert_eq!(ids(1), vec![north]);
+    p.delete_memory(north).unwrap();
+    assert_eq!(ids(3), vec![east]);
+}
+
+#[test]
+fn memory_summaries_replace_the_embeddings_they_cover() {
+    use spec_ai::persistence::forget::ForgetTarget;
+    use spec_ai::persistence::search::SemanticHitKind;
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let mut ids = Vec::new();
+    for text in [
+        "deploy on monday",
+        "use blue-green",
+        "rollback plan",
+        "latest",
+    ] {
+        let id = p.insert_message("sess", MessageRole::User, text).unwrap();
+        p.insert_memory_vector("sess", Some(id), &[1.0, 0.0])
+            .unwrap();
+        ids.push(id);
+    }
+    p.conn()
+        .execute(
+            "UPDATE messages SET created_at = TIMESTAMP '2020-01-01 07:00:00' \
+             WHERE session_id = 'sess'",
+            [],
+        )
+        .unwrap();
+
+    // The newest message stays, and so does everything from `before` on
+    let chunk = p
+        .messages_to_summarize("sess", 1, Some(ids[2]), 10)
+        .unwrap();
+    assert_eq!(chunk.iter().map(|m| m.id).collect::<Vec<_>>(), ids[..2]);
+    let chunk = p.messages_to_summarize("sess", 1, None, 10).unwrap();
+    assert_eq!(chunk.len(), 3);
+    assert_eq!(
+        chunk[0].created_at.to_rfc3339(),
+        "2020-01-01T07:00:00+00:00"
+    );
+
+    let summary = p
+        .store_memory_summary(
+            "sess",
+            &chunk,
+            "Deploys go out Monday, blue-green",
+            Some(&[0.0, 1.0]),
+        )
+        .unwrap()
+        .expect("summary stored");
+    assert_eq!(summary.message_count, 3);
+    assert_eq!(p.summarized_through("sess").unwrap(), ids[2]);
+    assert!(p
+        .store_memory_summary("sess", &chunk, "again", None)
+        .unwrap()
+        .is_none());
+    assert!(p
+        .messages_to_summarize("sess", 1, None, 10)
+        .unwrap()
+        .is_empty());
+
+    // Recall finds the summary and the newest message only
+    let recalled = p.recall_top_k("sess", &[1.0, 1.0], 10).unwrap();
+    assert_eq!(recalled.len(), 2);
+    let embedding_id = summary.embedding_id.unwrap();
+    assert_eq!(
+        p.memory_summary_by_embedding(embedding_id).unwrap(),
+        Some(summary)
+    );
+    p.conn()
+        .execute(
+            "UPDATE memory_summaries SET created_at = TIMESTAMP '2020-01-02 07:00:00'",
+            [],
+        )
+        .unwrap();
+    assert_eq!(
+        p.list_memory_summaries("sess").unwrap()[0]
+            .created_at
+            .to_rfc3339(),
+        "2020-01-02T07:00:00+00:00"
+    );
+    let memories = p.list_memories("sess").unwrap();
+    assert!(memories
+        .iter()
+        .any(|m| m.id == embedding_id && m.kind == Some(SemanticHitKind::Summary)));
+
+    // Forgetting a covered message takes the summary with it
+    let report = p
+        .forget(&ForgetTarget::Message(ids[1]), None, false)
+        .unwrap();
+    assert_eq!(report.memory_summaries, 1);
+    assert!(p.list_memory_summaries("sess").unwrap().is_empty());
+    assert_eq!(p.recall_top_k("sess", &[1.0, 1.0], 10).unwrap().len(), 1);
+}
+
+#[test]
+fn memory_importance_tracks_access_and_ranks_prune_candidates() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let cold = p.insert_memory_vector("sess", None, &[1.0, 0.0]).unwrap();
+    let hot = p.insert_memory_vector("sess", None, &[0.9, 0.1]).unwrap();
+
+    p.record_memory_access(&[hot]).unwrap();
+    p.record_memory_access(&[hot]).unwrap();
+    let stats = p.memory_access_stats("sess").unwrap();
+    assert_eq!(stats[&hot].access_count, 2);
+    assert!(stats[&hot].last_accessed_at.is_some());
+    assert!(!stats.contains_key(&cold));
+
+    let params = MemoryImportanceParams {
+        weight: 1.0,
+        half_life_hours: 168.0,
+    };
+    let candidates = p.memory_prune_candidates("sess", 1, &params).unwrap();
+    assert_eq!(candidates[0].0.id, cold);
+
+    // Similarity alone prefers the exact match; full importance weight prefers the recalled one
+    let by_similarity = p
+        .recall_top_k_weighted("sess", &[1.0, 0.0], 1, &MemoryImportanceParams::default())
+        .unwrap();
+    assert_eq!(by_similarity[0].0.id, cold);
+    let by_importance = p
+        .recall_top_k_weighted("sess", &[1.0, 0.0], 1, &params)
+        .unwrap();
+    assert_eq!(by_importance[0].0.id, hot);
+}
+
+#[test]
+fn memory_importance_decays_from_stored_timestamps() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let old = p.insert_memory_vector("sess", None, &[1.0, 0.0]).unwrap();
+    let fresh = p.insert_memory_vector("sess", None, &[1.0, 0.0]).unwrap();
+    p.record_memory_access(&[old]).unwrap();
+    p.conn()
+        .execute(
+            &format!(
+                "UPDATE memory_vectors SET created_at = created_at - INTERVAL 14 DAY WHERE id = {}",
+                old
+            ),
+            [],
+        )
+        .unwrap();
+    p.conn()
+        .execute(
+            &format!(
+                "UPDATE memory_access SET last_accessed_at = last_accessed_at - INTERVAL 7 DAY \
+                 WHERE memory_id = {}",
+                old
+            ),
+            [],
+        )
+        .unwrap();
+
+    let stats = p.memory_access_stats("sess").unwrap();
+    let age = chrono::Utc::now() - stats[&old].last_accessed_at.unwrap();
+    assert!((age.num_hours() - 7 * 24).abs() <= 1, "read back {:?}", age);
+
+    // Recency halves over the week since the last access: 0.5 * 0.5 from
+    // recency plus 0.3 * 0.5 from the single access
+    let params = MemoryImportanceParams {
+        weight: 1.0,
+        half_life_hours: 168.0,
+    };
+    let recalled = p
+        .recall_top_k_weighted("sess", &[1.0, 0.0], 2, &params)
+        .unwrap();
+    assert_eq!(recalled[0].0.id, fresh);
+    assert!((recalled[0].1 - 0.5).abs() < 0.01, "{}", recalled[0].1);
+    assert_eq!(recalled[1].0.id, old);
+    assert!((recalled[1].1 - 0.4).abs() < 0.01, "{}", recalled[1].1);
+}
+
+#[test]
+fn recall_follows_embeddings_replaced_behind_its_back() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let message = p
+        .insert_message("sess", MessageRole::User, "Deploy on Fridays")
+        .unwrap();
+    let east = p
+        .insert_memory_vector("sess", Some(message), &[1.0, 0.0])
+        .unwrap();
+    let north = p.insert_memory_vector("sess", None, &[0.0, 1.0]).unwrap();
+    assert_eq!(
+        p.recall_top_k("sess", &[1.0, 0.0], 1).unwrap()[0].0.id,
+        east
+    );
+
+    // Re-embedded through this handle: the index is updated in place
+    p.update_message_embedding(message, &[0.0, 1.0]).unwrap();
+    let recalled = p.recall_top_k("sess", &[1.0, 0.0], 2).unwrap();
+    assert!(recalled.iter().all(|(_, similarity)| *similarity < 0.5));
+
+    // Re-embedded by another writer: the index is rebuilt from the table
+    p.conn()
+        .execute(
+            &format!(
+                "UPDATE memory_vectors SET embedding = '[1.0, 0.0]', revision = revision + 1 WHERE id = {}",
+                north
+            ),
+            [],
+        )
+        .unwrap();
+    let recalled = p.recall_top_k("sess", &[1.0, 0.0], 1).unwrap();
+    assert_eq!(recalled[0].0.id, north);
+    assert!((recalled[0].1 - 1.0).abs() < 1e-6);
+}
+
 #[test]
 fn tool_log_insert() {
     let path = temp_db_path();
@@ -128,3 +375,1176 @@ fn policy_upsert_and_get() {
     let got2 = p.policy_get("policies").unwrap().expect("exists");
     assert_eq!(got2.value, v2);
 }
+
+#[test]
+fn roundtable_drafts_round_trip_in_order() {
+    let p = Persistence::new(temp_db_path()).unwrap();
+    p.insert_roundtable_draft("s1", "rt-1", "skeptic", false, "It depends.")
+        .unwrap();
+    p.insert_roundtable_draft("s1", "rt-1", "optimist", false, "Yes!")
+        .unwrap();
+    p.insert_roundtable_draft("s1", "rt-1", "judge", true, "Probably yes.")
+        .unwrap();
+    p.insert_roundtable_draft("s1", "rt-2", "skeptic", false, "No.")
+        .unwrap();
+    p.conn()
+        .execute(
+            "UPDATE roundtable_drafts SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+            [],
+        )
+        .unwrap();
+
+    let drafts = p.list_roundtable_drafts("s1", "rt-1").unwrap();
+    let agents: Vec<&str> = drafts.iter().map(|d| d.agent_name.as_str()).collect();
+    assert_eq!(agents, vec!["skeptic", "optimist", "judge"]);
+    assert!(drafts[2].is_synthesis && !drafts[0].is_synthesis);
+    assert_eq!(
+        drafts[0].created_at.to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+    assert!(p.list_roundtable_drafts("s2", "rt-1").unwrap().is_empty());
+}
+
+#[test]
+fn response_variants_keep_their_stored_time() {
+    let p = Persistence::new(temp_db_path()).unwrap();
+    let question = p
+        .insert_message("s1", MessageRole::User, "Name a colour")
+        .unwrap();
+    let answer = p
+        .insert_message("s1", MessageRole::Assistant, "Blue")
+        .unwrap();
+    p.add_response_variant("s1", question, answer, "Blue", None, None)
+        .unwrap();
+    p.add_response_variant("s1", question, answer, "Green", Some("m"), Some(1.2))
+        .unwrap();
+    p.conn()
+        .execute(
+            "UPDATE response_variants SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+            [],
+        )
+        .unwrap();
+
+    let variants = p.list_response_variants(answer).unwrap();
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[1].content, "Green");
+    assert!(variants
+        .iter()
+        .all(|v| v.created_at.to_rfc3339() == "2020-01-01T00:00:00+00:00"));
+}
+
+#[test]
+fn step_timings_aggregate_per_stage() {
+    let p = Persistence::new(temp_db_path()).unwrap();
+    p.insert_step_timings(
+        "s1",
+        "run-1",
+        Some("coder"),
+        &[
+            ("run_step.main_model_call", Some("model"), 900.0),
+            ("run_step.tool_execution.grep", Some("tool"), 40.0),
+            ("run_step.total", None, 1000.0),
+        ],
+    )
+    .unwrap();
+    p.insert_step_timings(
+        "s1",
+        "run-2",
+        Some("coder"),
+        &[("run_step.main_model_call", Some("model"), 500.0)],
+    )
+    .unwrap();
+    p.insert_step_timings("s2", "run-3", None, &[("run_step.main_model_call", Some("model"), 100.0)])
+        .unwrap();
+
+    let report = p.step_timing_report(Some("s1")).unwrap();
+    assert_eq!(report.len(), 3);
+    assert_eq!(report[0].stage, "run_step.main_model_call");
+    assert_eq!(report[0].category.as_deref(), Some("model"));
+    assert_eq!(report[0].count, 2);
+    assert_eq!(report[0].total_ms, 1400.0);
+    assert_eq!(report[0].avg_ms, 700.0);
+    assert_eq!(report[0].max_ms, 900.0);
+    assert_eq!(report[1].stage, "run_step.total");
+    assert_eq!(report[1].category, None);
+    assert_eq!(report[2].stage, "run_step.tool_execution.grep");
+
+    let all = p.step_timing_report(None).unwrap();
+    assert_eq!(all[0].count, 3);
+}
+
+#[test]
+fn model_log_round_trips_by_run() {
+    use spec_ai::persistence::ModelLogEntry;
+
+    let p = Persistence::new(temp_db_path()).unwrap();
+    let entry = |stage: &str, run_id: &str| ModelLogEntry {
+        session_id: "s1".to_string(),
+        run_id: Some(run_id.to_string()),
+        agent: Some("coder".to_string()),
+        stage: stage.to_string(),
+        provider: "mock".to_string(),
+        model: Some("mock-model".to_string()),
+        prompt_digest: "abc123".to_string(),
+        prompt_bytes: 42,
+        prompt: "hello".to_string(),
+        params: json!({"temperature": 0.7}),
+        response: Some("hi".to_string()),
+        error: None,
+        truncated: false,
+        prompt_tokens: Some(5),
+        completion_tokens: None,
+        latency_ms: 12.5,
+    };
+    p.insert_model_log(&entry("run_step.main_model_call", "run-1"))
+        .unwrap();
+    p.insert_model_log(&entry("summarize_reasoning.generate", "run-1"))
+        .unwrap();
+    p.insert_model_log(&entry("run_step.main_model_call", "run-2"))
+        .unwrap();
+    p.conn()
+        .execute(
+            "UPDATE model_log SET created_at = TIMESTAMP '2020-01-01 00:00:00' \
+             WHERE run_id = 'run-1'",
+            [],
+        )
+        .unwrap();
+
+    let records = p.list_model_log("run-1").unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].entry.stage, "run_step.main_model_call");
+    assert_eq!(records[1].entry.stage, "summarize_reasoning.generate");
+    assert_eq!(records[0].entry.params["temperature"], 0.7);
+    assert_eq!(records[0].entry.prompt_tokens, Some(5));
+    assert_eq!(records[0].entry.completion_tokens, None);
+    assert_eq!(records[0].entry.prompt_bytes, 42);
+    assert_eq!(
+        records[0].created_at.to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+    assert!(p.list_model_log("run-3").unwrap().is_empty());
+}
+
+#[test]
+fn session_metadata_tracks_messages_and_titles() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    assert!(p.get_session("s1").unwrap().is_none());
+
+    for content in ["hello", "hi there", "how are you?"] {
+        p.insert_message("s1", MessageRole::User, content).unwrap();
+    }
+    p.insert_message("s2", MessageRole::User, "other").unwrap();
+
+    let session = p.get_session("s1").unwrap().unwrap();
+    assert_eq!(session.message_count, 3);
+    assert!(session.title.is_none());
+    assert_eq!(session.display_title(), "s1");
+
+    p.set_session_title("s1", "Greetings").unwrap();
+    p.set_session_agent("s1", "coder").unwrap();
+    let session = p.get_session("s1").unwrap().unwrap();
+    assert_eq!(session.title.as_deref(), Some("Greetings"));
+    assert_eq!(session.agent.as_deref(), Some("coder"));
+    assert!(session.tags.is_empty());
+
+    // Pruning refreshes the cached count
+    p.prune_messages("s1", 1).unwrap();
+    assert_eq!(p.get_session("s1").unwrap().unwrap().message_count, 1);
+
+    // Metadata can exist before any message is stored
+    p.set_session_title("s3", "Planned").unwrap();
+    let records = p.list_session_records().unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().any(|r| r.session_id == "s3" && r.message_count == 0));
+}
+
+#[test]
+fn session_tags_and_search_rank_across_sessions() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+
+    p.insert_message(
+        "ops",
+        MessageRole::User,
+        "The DuckDB migration failed on the staging database",
+    )
+    .unwrap();
+    p.insert_message("ops", MessageRole::Assistant, "Retry after a checkpoint")
+        .unwrap();
+    p.insert_message("misc", MessageRole::User, "duckdb is an embedded database")
+        .unwrap();
+    p.insert_message("misc", MessageRole::User, "unrelated chatter")
+        .unwrap();
+
+    assert_eq!(
+        p.add_session_tag("misc", " Migration ").unwrap(),
+        vec!["migration".to_string()]
+    );
+    assert_eq!(p.add_session_tag("misc", "migration").unwrap().len(), 1);
+    p.set_session_title("ops", "Staging incident").unwrap();
+
+    let hits = p.search_sessions("duckdb migration", 10).unwrap();
+    assert_eq!(hits.len(), 3);
+    // The message containing both terms ranks first
+    assert_eq!(hits[0].session_id, "ops");
+    assert_eq!(hits[0].session_title.as_deref(), Some("Staging incident"));
+    assert!(hits[0].snippet.contains("DuckDB migration"));
+    // A tag match counts double, so it beats a message matching one term
+    assert_eq!(hits[1].session_id, "misc");
+    assert!(hits[1].message_id.is_none());
+    assert_eq!(hits[2].session_id, "misc");
+    assert!(hits[2].message_id.is_some());
+
+    assert!(p.search_sessions("   ", 10).unwrap().is_empty());
+    assert_eq!(p.search_sessions("database", 1).unwrap().len(), 1);
+
+    // Hits carry the time their message was stored
+    p.conn()
+        .execute(
+            "UPDATE messages SET created_at = TIMESTAMP '2020-01-01 08:00:00' WHERE session_id = 'ops'",
+            [],
+        )
+        .unwrap();
+    let hits = p.search_sessions("staging", 10).unwrap();
+    let message_hit = hits.iter().find(|hit| hit.message_id.is_some()).unwrap();
+    assert_eq!(
+        message_hit.created_at.to_rfc3339(),
+        "2020-01-01T08:00:00+00:00"
+    );
+
+    assert!(p.remove_session_tag("misc", "migration").unwrap().is_empty());
+}
+
+#[test]
+fn queued_messages_wait_for_their_embedding() {
+    let p = Persistence::new(temp_db_path()).unwrap();
+    let first = p.insert_message("s1", MessageRole::User, "first").unwrap();
+    let second = p.insert_message("s1", MessageRole::User, "second").unwrap();
+    p.embedding_queue_push("s1", first, None).unwrap();
+    p.embedding_queue_push("s1", second, None).unwrap();
+    p.conn()
+        .execute(
+            "UPDATE messages SET created_at = TIMESTAMP '2020-01-01 00:00:00' WHERE id = ?",
+            [first],
+        )
+        .unwrap();
+
+    let waiting = p.unembedded_messages("s1", 10).unwrap();
+    assert_eq!(
+        waiting.iter().map(|m| m.id).collect::<Vec<_>>(),
+        vec![first, second]
+    );
+    assert_eq!(
+        waiting[0].created_at.to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+
+    p.embedding_queue_complete(first).unwrap();
+    assert_eq!(p.unembedded_messages("s1", 10).unwrap()[0].id, second);
+}
+
+#[test]
+fn semantic_search_spans_sessions_with_filters() {
+    use spec_ai::persistence::search::{SemanticHitKind, SemanticSearchFilter};
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+
+    let near = p
+        .insert_message("proj-a-1", MessageRole::User, "deploy the api")
+        .unwrap();
+    p.insert_memory_vector("proj-a-1", Some(near), &[1.0, 0.0])
+        .unwrap();
+    let far = p
+        .insert_message("proj-a-2", MessageRole::Assistant, "lunch plans")
+        .unwrap();
+    p.insert_memory_vector("proj-a-2", Some(far), &[0.0, 1.0])
+        .unwrap();
+    let other = p
+        .insert_message("proj-b-1", MessageRole::User, "deploy the worker")
+        .unwrap();
+    p.insert_memory_vector("proj-b-1", Some(other), &[0.9, 0.1])
+        .unwrap();
+    p.set_session_agent("proj-b-1", "ops").unwrap();
+    p.set_session_title("proj-a-1", "API rollout").unwrap();
+
+    let all = p
+        .semantic_search(&[1.0, 0.0], &SemanticSearchFilter::default(), 10)
+        .unwrap();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].source_id, near);
+    assert_eq!(all[0].kind, SemanticHitKind::Message);
+    assert_eq!(all[0].session_title.as_deref(), Some("API rollout"));
+    assert_eq!(all[0].role.as_deref(), Some("user"));
+    assert_eq!(all[1].source_id, other);
+    assert!(all[0].score > all[2].score);
+
+    let namespaced = SemanticSearchFilter {
+        namespace: Some("proj-a-".into()),
+        ..Default::default()
+    };
+    let hits = p.semantic_search(&[1.0, 0.0], &namespaced, 10).unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|hit| hit.session_id.starts_with("proj-a-")));
+
+    let by_agent = SemanticSearchFilter {
+        agent: Some("ops".into()),
+        ..Default::default()
+    };
+    let hits = p.semantic_search(&[1.0, 0.0], &by_agent, 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, "proj-b-1");
+
+    let future = SemanticSearchFilter {
+        since: Some(chrono::Utc::now() + chrono::Duration::days(1)),
+        ..Default::default()
+    };
+    assert!(p
+        .semantic_search(&[1.0, 0.0], &future, 10)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        p.semantic_search(&[1.0, 0.0], &SemanticSearchFilter::default(), 1)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    p.conn()
+        .execute(
+            "UPDATE memory_vectors SET created_at = TIMESTAMP '2020-01-01 09:30:00' \
+             WHERE session_id = 'proj-a-1'",
+            [],
+        )
+        .unwrap();
+    let before_2021 = SemanticSearchFilter {
+        until: Some("2021-01-01T00:00:00Z".parse().unwrap()),
+        ..Default::default()
+    };
+    let hits = p.semantic_search(&[1.0, 0.0], &before_2021, 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].source_id, near);
+    assert_eq!(hits[0].created_at.to_rfc3339(), "2020-01-01T09:30:00+00:00");
+}
+
+#[test]
+fn large_payloads_go_to_blob_store_and_unreferenced_blobs_are_collected() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap().with_blob_threshold(1024);
+    let blob_dir = path.parent().unwrap().join("blobs");
+
+    let page = "scraped line\n".repeat(200);
+    let id = p
+        .insert_message("s1", MessageRole::User, &page)
+        .unwrap();
+    let stored = p.get_message(id).unwrap().unwrap().content;
+    assert!(stored.len() < page.len());
+    assert!(stored.starts_with("scraped line"));
+    assert_eq!(p.resolve_blob(&stored).unwrap(), page);
+    assert_eq!(p.resolve_blob("short").unwrap(), "short");
+
+    let output = json!({ "output": "x".repeat(4096), "success": true });
+    p.log_tool("s1", "agent", "run-1", "web_scraper", &json!({}), &output, true, None)
+        .unwrap();
+
+    let blobs = p.list_blobs().unwrap();
+    assert_eq!(blobs.len(), 2);
+    assert!(blobs.iter().all(|blob| blob_dir
+        .join(&blob.hash[..2])
+        .join(&blob.hash)
+        .exists()));
+    p.conn()
+        .execute(
+            "UPDATE blobs SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+            [],
+        )
+        .unwrap();
+    assert!(p
+        .list_blobs()
+        .unwrap()
+        .iter()
+        .all(|blob| blob.created_at.to_rfc3339() == "2020-01-01T00:00:00+00:00"));
+
+    // Nothing is unreferenced yet
+    let report = p.gc_blobs(false).unwrap();
+    assert_eq!((report.removed, report.kept), (0, 2));
+
+    p.prune_messages("s1", 0).unwrap();
+    let dry = p.gc_blobs(true).unwrap();
+    assert_eq!(dry.removed, 1);
+    assert_eq!(p.list_blobs().unwrap().len(), 2);
+
+    let report = p.gc_blobs(false).unwrap();
+    assert_eq!(report.removed, 1);
+    assert_eq!(report.bytes_freed, page.len() as u64);
+    assert_eq!(p.list_blobs().unwrap().len(), 1);
+    let page_hash = &blobs
+        .iter()
+        .find(|blob| blob.size == page.len() as u64)
+        .unwrap()
+        .hash;
+    assert!(p.get_blob(page_hash).unwrap().is_none());
+}
+
+#[test]
+fn in_memory_database_supports_messages_graph_and_vectors() {
+    use spec_ai::types::{EdgeType, NodeType};
+
+    let p = Persistence::new(":memory:").unwrap();
+    assert!(p.is_in_memory());
+
+    let id = p
+        .insert_message("mem", MessageRole::User, &"long ".repeat(20_000))
+        .unwrap();
+    // Payloads stay inline rather than going to blob files
+    assert_eq!(
+        p.get_message(id).unwrap().unwrap().content.len(),
+        "long ".len() * 20_000
+    );
+    assert!(p.list_blobs().unwrap().is_empty());
+
+    p.insert_memory_vector("mem", Some(id), &[1.0, 0.0]).unwrap();
+    let recalled = p.recall_top_k("mem", &[1.0, 0.0], 1).unwrap();
+    assert_eq!(recalled[0].0.message_id, Some(id));
+
+    let a = p
+        .insert_graph_node("mem", NodeType::Entity, "api", &json!({}), None)
+        .unwrap();
+    let b = p
+        .insert_graph_node("mem", NodeType::Entity, "db", &json!({}), None)
+        .unwrap();
+    p.insert_graph_edge("mem", a, b, EdgeType::DependsOn, None, None, 1.0)
+        .unwrap();
+    let path = p.find_shortest_path("mem", a, b, None).unwrap().unwrap();
+    assert_eq!(path.length, 1);
+
+    // Each in-memory database is separate and starts empty
+    let other = Persistence::in_memory().unwrap();
+    assert!(other.list_messages("mem", 10).unwrap().is_empty());
+}
+
+#[test]
+fn mesh_stats_are_collected_and_kept_per_instance() {
+    use spec_ai::persistence::InstanceStats;
+
+    let p = Persistence::in_memory().unwrap();
+    p.insert_message("s1", MessageRole::User, "hi").unwrap();
+    p.log_tool("s1", "agent", "run-1", "echo", &json!({}), &json!("hi"), true, None)
+        .unwrap();
+
+    let local = p
+        .local_instance_stats(chrono::Duration::minutes(15))
+        .unwrap();
+    assert_eq!(local.active_sessions, 1);
+    assert_eq!(local.tool_calls, 1);
+    assert_eq!(local.sync_lag_secs, None);
+
+    let sample = InstanceStats {
+        tokens_used: 1_200,
+        heartbeat_latency_ms: Some(4.5),
+        ..local
+    };
+    p.mesh_stats_record("node-a", &sample).unwrap();
+    p.mesh_stats_record("node-b", &InstanceStats::default())
+        .unwrap();
+
+    let window = p.mesh_stats_window(Some("node-a")).unwrap();
+    assert_eq!(window.len(), 1);
+    assert_eq!(window[0].stats, sample);
+    assert_eq!(p.mesh_stats_window(None).unwrap().len(), 2);
+}
+
+#[test]
+fn session_workspaces_track_focus() {
+    let p = Persistence::in_memory().unwrap();
+    let api = p
+        .workspace_add("s1", "api", std::path::Path::new("/src/api"), Some(7))
+        .unwrap();
+    assert!(api.focused);
+    assert_eq!(api.graph_node_id, Some(7));
+    let web = p
+        .workspace_add("s1", "web", std::path::Path::new("/src/web"), None)
+        .unwrap();
+    assert!(!web.focused);
+    assert!(p
+        .workspace_add("s1", "api", std::path::Path::new("/elsewhere"), None)
+        .is_err());
+    assert!(p.workspace_list("s2").unwrap().is_empty());
+
+    assert!(p.workspace_focus("s1", "web").unwrap());
+    assert!(!p.workspace_focus("s1", "missing").unwrap());
+    let focused = p.workspace_focused("s1").unwrap().unwrap();
+    assert_eq!(focused.name, "web");
+    assert_eq!(focused.root, std::path::PathBuf::from("/src/web"));
+    p.conn()
+        .execute(
+            "UPDATE session_workspaces SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+            [],
+        )
+        .unwrap();
+    assert_eq!(
+        p.workspace_focused("s1")
+            .unwrap()
+            .unwrap()
+            .created_at
+            .to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+    let names: Vec<_> = p
+        .workspace_list("s1")
+        .unwrap()
+        .into_iter()
+        .filter(|w| w.focused)
+        .map(|w| w.name)
+        .collect();
+    assert_eq!(names, vec!["web"]);
+
+    assert!(p.workspace_remove("s1", "web").unwrap());
+    assert!(!p.workspace_remove("s1", "web").unwrap());
+    assert!(p.workspace_focused("s1").unwrap().is_none());
+}
+
+#[test]
+fn user_profile_facts_are_shared_and_deduplicated() {
+    let p = Persistence::in_memory().unwrap();
+    let tabs = p
+        .user_profile_upsert("preference", "Prefers tabs over spaces", Some("s1"))
+        .unwrap();
+    p.user_profile_upsert("project", "Maintains the billing service", Some("s2"))
+        .unwrap();
+    assert_eq!(
+        p.user_profile_upsert("style", "prefers tabs over spaces", Some("s3"))
+            .unwrap(),
+        tabs
+    );
+    let facts = p.user_profile_list().unwrap();
+    assert_eq!(facts.len(), 2);
+    let tabs_fact = facts.iter().find(|f| f.id == tabs).unwrap();
+    assert_eq!(tabs_fact.category, "style");
+    assert_eq!(tabs_fact.source_session.as_deref(), Some("s1"));
+    p.conn()
+        .execute(
+            "UPDATE user_profile SET created_at = TIMESTAMP '2020-01-01 00:00:00' WHERE id = ?",
+            [tabs],
+        )
+        .unwrap();
+    let facts = p.user_profile_list().unwrap();
+    let tabs_fact = facts.iter().find(|f| f.id == tabs).unwrap();
+    assert_eq!(
+        tabs_fact.created_at.to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+    assert!(tabs_fact.updated_at > tabs_fact.created_at);
+
+    let forgotten = p.user_profile_forget("BILLING").unwrap();
+    assert_eq!(forgotten.len(), 1);
+    assert!(p.user_profile_forget(&tabs.to_string()).unwrap().len() == 1);
+    assert!(p.user_profile_list().unwrap().is_empty());
+
+    p.user_profile_replace(&[("style".into(), "Writes terse commit messages".into())])
+        .unwrap();
+    assert_eq!(p.user_profile_list().unwrap()[0].source_session, None);
+
+    assert_eq!(p.user_profile_extracted_through("s1").unwrap(), None);
+    p.user_profile_mark_extracted("s1", 4).unwrap();
+    p.user_profile_mark_extracted("s1", 9).unwrap();
+    assert_eq!(p.user_profile_extracted_through("s1").unwrap(), Some(9));
+}
+
+#[test]
+fn forget_erases_matching_data_and_records_synced_deletions() {
+    use spec_ai::persistence::forget::ForgetTarget;
+    use spec_ai::types::{EdgeType, NodeType};
+
+    let p = Persistence::in_memory().unwrap();
+    let told = p
+        .insert_message("s1", MessageRole::User, "My address is 12 Elm Street")
+        .unwrap();
+    p.insert_message("s1", MessageRole::Assistant, "Noted.")
+        .unwrap();
+    p.insert_message("s2", MessageRole::User, "Ship it from elm street")
+        .unwrap();
+    p.insert_memory_vector("s1", Some(told), &[1.0, 0.0]).unwrap();
+    let person = p
+        .insert_graph_node("s1", NodeType::Entity, "Person", &json!({"name": "user"}), None)
+        .unwrap();
+    let address = p
+        .insert_graph_node(
+            "s1",
+            NodeType::Fact,
+            "Address",
+            &json!({"message_id": told, "text": "lives somewhere"}),
+            None,
+        )
+        .unwrap();
+    p.insert_graph_edge("s1", person, address, EdgeType::RelatesTo, None, None, 1.0)
+        .unwrap();
+    p.log_tool(
+        "s1",
+        "tester",
+        "run-1",
+        "geocode",
+        &json!({"q": "12 Elm Street"}),
+        &json!({"ok": true}),
+        true,
+        None,
+    )
+    .unwrap();
+    p.user_profile_upsert("other", "Lives on Elm Street", Some("s1"))
+        .unwrap();
+
+    // The address node is synced, so its deletion must reach peers
+    p.conn()
+        .execute(
+            &format!(
+                "INSERT INTO mesh_registry (instance_id, hostname, port) VALUES ('{}', 'localhost', 0)",
+                p.instance_id()
+            ),
+            [],
+        )
+        .unwrap();
+    p.graph_update_node_sync_metadata(address, "{}", p.instance_id(), true)
+        .unwrap();
+
+    let target = ForgetTarget::parse("elm street").unwrap();
+    let preview = p.forget(&target, Some("s1"), true).unwrap();
+    assert_eq!(preview.messages.len(), 1);
+    assert_eq!(preview.graph_nodes.len(), 1);
+    assert_eq!(preview.graph_edges, 1);
+    assert_eq!(preview.memory_vectors, 1);
+    assert_eq!(preview.tool_calls, 1);
+    assert_eq!(preview.profile_facts.len(), 1);
+    assert_eq!(p.list_messages("s1", 10).unwrap().len(), 2);
+
+    let report = p.forget(&target, Some("s1"), false).unwrap();
+    assert_eq!(report.synced_deletions, 1);
+    let remaining = p.list_messages("s1", 10).unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].content, "Noted.");
+    assert_eq!(p.list_messages("s2", 10).unwrap().len(), 1);
+    let nodes = p.list_graph_nodes("s1", None, None).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].id, person);
+    assert!(p.user_profile_list().unwrap().is_empty());
+
+    let changelog: Vec<(String, Option<String>)> = {
+        let conn = p.conn();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT operation, data FROM graph_changelog WHERE entity_id = {}",
+                address
+            ))
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        rows.map(Result::unwrap).collect()
+    };
+    assert_eq!(changelog, vec![("delete".to_string(), None)]);
+
+    let by_id = p.forget(&ForgetTarget::Message(told), None, false).unwrap();
+    assert!(by_id.is_empty());
+}
+
+#[test]
+fn redact_at_rest_stores_tokens_and_detokenizes_prompts() {
+    use spec_ai::persistence::privacy::PiiVault;
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path)
+        .unwrap()
+        .with_vault(PiiVault::with_key([3u8; 32], Vec::new()));
+    let text = "My name is Ada Lovelace, write to ada@example.com or +44 20 7946 0958";
+    let id = p.insert_message("s1", MessageRole::User, text).unwrap();
+
+    let stored = p.get_message(id).unwrap().unwrap().content;
+    for secret in ["Ada Lovelace", "ada@example.com", "7946"] {
+        assert!(!stored.contains(secret), "{} stored in the clear", secret);
+    }
+    assert!(stored.contains("[[pii:email:"));
+    assert_eq!(p.detokenize(&stored).unwrap(), text);
+
+    let node = p
+        .insert_graph_node(
+            "s1",
+            spec_ai::types::NodeType::Entity,
+            "Ada Lovelace",
+            &json!({"contact": "ada@example.com", "count": 5551234567i64}),
+            None,
+        )
+        .unwrap();
+    let node = p.get_graph_node(node).unwrap().unwrap();
+    assert!(!node.label.contains("Ada"));
+    assert!(!node.properties.to_string().contains("example.com"));
+    assert_eq!(node.properties["count"], json!(5551234567i64));
+
+    // The same database opened without the key keeps the tokens
+    drop(p);
+    let without_key = Persistence::new(&path)
+        .unwrap()
+        .with_vault(PiiVault::with_key([4u8; 32], Vec::new()));
+    assert_eq!(without_key.detokenize(&stored).unwrap(), stored);
+}
+
+#[test]
+fn transcript_segments_keep_offsets_and_speakers() {
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    let now = chrono::Utc::now();
+
+    let first = p
+        .insert_transcription("s1", 0, "Alice: kickoff", now)
+        .unwrap();
+    p.update_transcription_segment(first, 0, 5000, Some("Alice"), Some("standup.m4a"))
+        .unwrap();
+    p.insert_transcription("s1", 1, "no timing yet", now).unwrap();
+    p.insert_transcription("s2", 0, "other session", now)
+        .unwrap();
+    p.conn()
+        .execute(
+            "UPDATE transcriptions SET timestamp = TIMESTAMP '2020-01-01 00:00:05' WHERE id = ?",
+            [first],
+        )
+        .unwrap();
+
+    let segments = p.list_transcript_segments("s1").unwrap();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].start_ms, Some(0));
+    assert_eq!(segments[0].end_ms, Some(5000));
+    assert_eq!(segments[0].speaker.as_deref(), Some("Alice"));
+    assert_eq!(segments[0].source_file.as_deref(), Some("standup.m4a"));
+    assert_eq!(
+        segments[0].timestamp.to_rfc3339(),
+        "2020-01-01T00:00:05+00:00"
+    );
+    assert_eq!(
+        p.list_transcriptions("s1", None).unwrap()[0].3,
+        segments[0].timestamp
+    );
+    assert_eq!(segments[1].text, "no timing yet");
+    assert_eq!(segments[1].start_ms, None);
+    assert_eq!(segments[1].speaker, None);
+    assert_eq!(segments[1].source_file, None);
+}
+
+#[test]
+fn memories_are_listed_searched_and_deleted_individually() {
+    use spec_ai::persistence::memories::MemoryDeletion;
+    use spec_ai::persistence::search::SemanticHitKind;
+    use spec_ai::types::NodeType;
+
+    let p = Persistence::in_memory().unwrap();
+    let deploy = p
+        .insert_message("s1", MessageRole::User, "The deploy window is Friday")
+        .unwrap();
+    let deploy_memory = p
+        .insert_memory_vector("s1", Some(deploy), &[1.0, 0.0])
+        .unwrap();
+    let lunch = p
+        .insert_message("s1", MessageRole::Assistant, "Lunch is at noon")
+        .unwrap();
+    let lunch_memory = p
+        .insert_memory_vector("s1", Some(lunch), &[0.0, 1.0])
+        .unwrap();
+    let other = p.insert_message("s2", MessageRole::User, "deploy").unwrap();
+    p.insert_memory_vector("s2", Some(other), &[1.0, 0.0])
+        .unwrap();
+    p.record_memory_access(&[deploy_memory]).unwrap();
+
+    let memories = p.list_memories("s1").unwrap();
+    assert_eq!(memories.len(), 2);
+    assert_eq!(memories[0].id, lunch_memory);
+    assert_eq!(memories[1].kind, Some(SemanticHitKind::Message));
+    assert_eq!(memories[1].source_id, Some(deploy));
+    assert_eq!(memories[1].text, "The deploy window is Friday");
+    assert_eq!(memories[1].access_count, 1);
+
+    let by_text = p.search_memories("s1", "deploy", None, 10).unwrap();
+    assert_eq!(by_text.len(), 1);
+    assert_eq!(by_text[0].id, deploy_memory);
+    let by_embedding = p
+        .search_memories("s1", "deploy", Some(&[0.1, 0.9]), 10)
+        .unwrap();
+    assert_eq!(by_embedding[0].id, lunch_memory);
+    assert!(by_embedding[0].score > by_embedding[1].score);
+
+    match p.delete_memory(deploy_memory).unwrap() {
+        MemoryDeletion::Deleted(entry) => assert_eq!(entry.source_id, Some(deploy)),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(
+        p.delete_memory(deploy_memory).unwrap(),
+        MemoryDeletion::NotFound
+    );
+    assert_eq!(p.list_memories("s1").unwrap().len(), 1);
+    // The message itself stays in the session
+    assert_eq!(p.list_messages("s1", 10).unwrap().len(), 2);
+
+    let node_memory = p.insert_memory_vector("s1", None, &[0.5, 0.5]).unwrap();
+    let node = p
+        .insert_graph_node(
+            "s1",
+            NodeType::Fact,
+            "Deploy",
+            &json!({"day": "Friday"}),
+            Some(node_memory),
+        )
+        .unwrap();
+    assert_eq!(
+        p.delete_memory(node_memory).unwrap(),
+        MemoryDeletion::LinkedToNode { node_id: node }
+    );
+}
+
+#[test]
+fn graph_changes_are_queued_for_the_event_sink() {
+    use spec_ai::config::EventsConfig;
+    use spec_ai::types::{EdgeType, NodeType};
+
+    let plain = Persistence::in_memory().unwrap();
+    plain
+        .insert_graph_node("s1", NodeType::Entity, "api", &json!({}), None)
+        .unwrap();
+    assert!(plain.event_outbox_pending(10).unwrap().is_empty());
+
+    let config = EventsConfig {
+        sink: Some("webhook".to_string()),
+        url: Some("http://localhost:9/events".to_string()),
+        include: vec!["graph".to_string()],
+        ..EventsConfig::default()
+    };
+    let p = Persistence::in_memory().unwrap().with_events(&config);
+    let api = p
+        .insert_graph_node("s1", NodeType::Entity, "api", &json!({}), None)
+        .unwrap();
+    let db = p
+        .insert_graph_node("s1", NodeType::Entity, "db", &json!({}), None)
+        .unwrap();
+    let edge = p
+        .insert_graph_edge("s1", api, db, EdgeType::DependsOn, None, None, 1.0)
+        .unwrap();
+    p.update_graph_node(db, &json!({"engine": "duckdb"})).unwrap();
+    p.delete_graph_edge(edge).unwrap();
+    // Run events are not included
+    assert_eq!(
+        p.event_outbox_append("run", "s1", &json!({"run_id": "r1"}))
+            .unwrap(),
+        None
+    );
+
+    let events = p.event_outbox_pending(10).unwrap();
+    let operations: Vec<(String, String)> = events
+        .iter()
+        .map(|event| {
+            (
+                event.payload["entity_type"].as_str().unwrap().to_string(),
+                event.payload["operation"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        operations,
+        [
+            ("node".to_string(), "create".to_string()),
+            ("node".to_string(), "create".to_string()),
+            ("edge".to_string(), "insert".to_string()),
+            ("node".to_string(), "update".to_string()),
+            ("edge".to_string(), "delete".to_string()),
+        ]
+    );
+    assert!(events.iter().all(|event| event.kind == "graph"));
+    assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert_eq!(events[3].payload["data"]["properties"]["engine"], "duckdb");
+
+    assert_eq!(p.event_outbox_ack(events[2].id).unwrap(), 3);
+    let pending = p.event_outbox_pending(10).unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[0].id, events[3].id);
+}
+
+#[test]
+fn sessions_copy_between_databases_with_remapped_ids() {
+    use spec_ai::types::{EdgeType, NodeType};
+
+    let source = Persistence::in_memory().unwrap();
+    let question = source
+        .insert_message("laptop", MessageRole::User, "Where is the config?")
+        .unwrap();
+    source
+        .insert_message("laptop", MessageRole::Assistant, "In ~/.spec-ai")
+        .unwrap();
+    source
+        .insert_memory_vector("laptop", Some(question), &[1.0, 0.0])
+        .unwrap();
+    let message_node = source
+        .insert_graph_node(
+            "laptop",
+            NodeType::Message,
+            "Message",
+            &json!({"message_id": question}),
+            None,
+        )
+        .unwrap();
+    let config_node = source
+        .insert_graph_node("laptop", NodeType::Entity, "config", &json!({}), None)
+        .unwrap();
+    source
+        .insert_graph_edge(
+            "laptop",
+            message_node,
+            config_node,
+            EdgeType::Mentions,
+            None,
+            None,
+            1.0,
+        )
+        .unwrap();
+    source.pin_graph_node(config_node).unwrap();
+    source
+        .log_tool(
+            "laptop",
+            "agent",
+            "run-1",
+            "file_read",
+            &json!({"path": "config.toml"}),
+            &json!({"content": "x"}),
+            true,
+            None,
+        )
+        .unwrap();
+
+    // The target's sequences are further along, so every ID changes
+    let target = Persistence::in_memory().unwrap();
+    for i in 0..3 {
+        let id = target
+            .insert_message("workstation", MessageRole::User, &format!("note {}", i))
+            .unwrap();
+        target
+            .insert_memory_vector("workstation", Some(id), &[0.0, 1.0])
+            .unwrap();
+        target
+            .insert_graph_node("workstation", NodeType::Entity, "note", &json!({}), None)
+            .unwrap();
+    }
+
+    let report = source.copy_session_to("laptop", &target, None).unwrap();
+    assert_eq!(report.session_id, "laptop");
+    assert_eq!(
+        (report.messages, report.memory_vectors, report.graph_nodes),
+        (2, 1, 2)
+    );
+    assert_eq!((report.graph_edges, report.tool_calls), (1, 1));
+
+    let messages = target.list_messages("laptop", 10).unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].content, "Where is the config?");
+    assert_ne!(messages[0].id, question);
+    assert_eq!(
+        target.get_session("laptop").unwrap().unwrap().message_count,
+        2
+    );
+
+    let recalled = target.recall_top_k("laptop", &[1.0, 0.0], 1).unwrap();
+    assert_eq!(recalled[0].0.message_id, Some(messages[0].id));
+
+    let nodes = target.list_graph_nodes("laptop", None, None).unwrap();
+    let copied_message = nodes
+        .iter()
+        .find(|n| n.node_type == NodeType::Message)
+        .unwrap();
+    assert_eq!(copied_message.properties["message_id"], json!(messages[0].id));
+    let edges = target.list_graph_edges("laptop", None, None).unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].source_id, copied_message.id);
+    let pinned = target.list_pinned_graph_nodes("laptop").unwrap();
+    assert_eq!(pinned.len(), 1);
+    assert_eq!(pinned[0].label, "config");
+
+    // The source is untouched, and copying again would clobber the copy
+    assert_eq!(source.list_messages("laptop", 10).unwrap().len(), 2);
+    let err = source.copy_session_to("laptop", &target, None).unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+    let again = source
+        .copy_session_to("laptop", &target, Some("laptop-2"))
+        .unwrap();
+    assert_eq!(again.messages, 2);
+    assert!(source.copy_session_to("missing", &target, None).is_err());
+}
+
+#[test]
+fn webhook_triggers_and_their_jobs_round_trip() {
+    use spec_ai::persistence::triggers::TriggerJobStatus;
+    use std::collections::BTreeMap;
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+
+    let variables = BTreeMap::from([("branch".to_string(), "/ref".to_string())]);
+    let trigger = p
+        .add_webhook_trigger(
+            "deploy",
+            "s3cret",
+            "specs/deploy.spec.toml",
+            None,
+            &variables,
+        )
+        .unwrap();
+    assert_eq!(trigger.name, "deploy");
+    assert_eq!(trigger.secret, "s3cret");
+    assert_eq!(trigger.variables, variables);
+    assert!(p
+        .add_webhook_trigger("deploy", "other", "x.spec.toml", None, &BTreeMap::new())
+        .is_err());
+    assert_eq!(p.list_webhook_triggers().unwrap().len(), 1);
+    assert_eq!(p.webhook_trigger("deploy").unwrap().unwrap().id, trigger.id);
+
+    let job_id = p.add_trigger_job(trigger.id, "trigger_deploy_1").unwrap();
+    let job = p.trigger_job(job_id).unwrap().unwrap();
+    assert_eq!(job.status, TriggerJobStatus::Queued);
+    assert!(job.finished_at.is_none());
+
+    p.set_trigger_job_status(job_id, TriggerJobStatus::Running, None, None)
+        .unwrap();
+    p.set_trigger_job_status(job_id, TriggerJobStatus::Succeeded, Some("run-1"), None)
+        .unwrap();
+    let job = p.trigger_job(job_id).unwrap().unwrap();
+    assert_eq!(job.status, TriggerJobStatus::Succeeded);
+    assert_eq!(job.run_id.as_deref(), Some("run-1"));
+    assert!(job.finished_at.is_some());
+
+    // Stored times are read back, not replaced by the time of reading
+    p.conn()
+        .execute(
+            &format!(
+                "UPDATE trigger_jobs SET created_at = TIMESTAMP '2020-01-01 00:00:00', finished_at = TIMESTAMP '2020-01-01 00:05:00' WHERE id = {}",
+                job_id
+            ),
+            [],
+        )
+        .unwrap();
+    let job = p.trigger_job(job_id).unwrap().unwrap();
+    assert_eq!(job.created_at.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+    assert_eq!(
+        job.finished_at.map(|at| at - job.created_at),
+        Some(chrono::Duration::minutes(5))
+    );
+
+    assert!(p.delete_webhook_trigger(trigger.id).unwrap());
+    assert!(!p.delete_webhook_trigger(trigger.id).unwrap());
+    assert!(p.webhook_trigger("deploy").unwrap().is_none());
+    assert!(p.trigger_job(job_id).unwrap().is_some());
+}
+
+#[test]
+fn archived_sessions_keep_their_summary_and_graph() {
+    use spec_ai::persistence::SessionArchive;
+    use spec_ai::types::NodeType;
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+
+    p.insert_message("old", MessageRole::User, "Which port?")
+        .unwrap();
+    let answer = p
+        .insert_message("old", MessageRole::Assistant, "Use 8080.")
+        .unwrap();
+    let vector = p
+        .insert_memory_vector("old", Some(answer), &[0.1, 0.2])
+        .unwrap();
+    let node = p
+        .insert_graph_node(
+            "old",
+            NodeType::Message,
+            "Message",
+            &json!({ "message_id": answer }),
+            Some(vector),
+        )
+        .unwrap();
+    p.insert_message("other", MessageRole::User, "Unrelated")
+        .unwrap();
+
+    let archive = p
+        .archive_session("old", Some("The user picked port 8080."))
+        .unwrap();
+    assert_eq!(
+        archive,
+        SessionArchive {
+            messages: 2,
+            memory_vectors: 1
+        }
+    );
+    assert!(p.list_messages("old", 10).unwrap().is_empty());
+    let anchor = p.context_anchor("old").unwrap().unwrap();
+    assert_eq!(anchor.through_message_id, answer);
+    assert_eq!(anchor.summary, "The user picked port 8080.");
+    p.conn()
+        .execute(
+            "UPDATE context_anchors SET created_at = TIMESTAMP '2020-01-01 00:00:00'",
+            [],
+        )
+        .unwrap();
+    assert_eq!(
+        p.context_anchor("old")
+            .unwrap()
+            .unwrap()
+            .created_at
+            .to_rfc3339(),
+        "2020-01-01T00:00:00+00:00"
+    );
+    assert_eq!(p.get_graph_node(node).unwrap().unwrap().embedding_id, None);
+    let session = p.get_session("old").unwrap().unwrap();
+    assert!(session.is_archived());
+    assert_eq!(session.message_count, 0);
+    assert_eq!(p.list_session_records().unwrap().len(), 2);
+
+    // Opening the session thaws it, and so does writing to it
+    assert!(p.thaw_session("old").unwrap());
+    assert!(!p.thaw_session("old").unwrap());
+    p.archive_session("old", None).unwrap();
+    p.insert_message("old", MessageRole::User, "And the host?")
+        .unwrap();
+    assert!(!p.get_session("old").unwrap().unwrap().is_archived());
+    assert_eq!(
+        p.context_anchor("old").unwrap().unwrap().summary,
+        "The user picked port 8080."
+    );
+
+    assert!(p.delete_session("old").unwrap());
+    assert!(p.get_session("old").unwrap().is_none());
+    assert!(p.context_anchor("old").unwrap().is_none());
+    assert!(p.get_graph_node(node).unwrap().is_none());
+    assert_eq!(p.list_messages("other", 10).unwrap().len(), 1);
+    assert!(!p.delete_session("old").unwrap());
+}
+
+#[test]
+fn policy_audit_keeps_the_time_of_each_decision() {
+    use spec_ai::persistence::policy_audit::PolicyDecisionRecord;
+
+    let path = temp_db_path();
+    let p = Persistence::new(&path).unwrap();
+    p.record_policy_decision(&PolicyDecisionRecord {
+        session_id: "sess",
+        run_id: "run-1",
+        agent: "coder",
+        action: "tool_call",
+        resource: "bash",
+        allowed: false,
+        rule: None,
+    })
+    .unwrap();
+    p.conn()
+        .execute(
+            "UPDATE policy_audit SET created_at = TIMESTAMP '2020-01-01 12:30:00'",
+            [],
+        )
+        .unwrap();
+
+    let entries = p.policy_audit(None, 10).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].created_at.to_rfc3339(),
+        "2020-01-01T12:30:00+00:00"
+    );
+}
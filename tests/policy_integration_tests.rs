@@ -83,6 +83,7 @@ fn test_policy_allows_specific_tool() {
         action: "tool_call".to_string(),
         resource: "echo".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
 
     let profile = AgentProfile {
@@ -125,6 +126,7 @@ fn test_policy_denies_specific_tool() {
         action: "tool_call".to_string(),
         resource: "calculator".to_string(),
         effect: PolicyEffect::Deny,
+        alternative: None,
     });
 
     let profile = AgentProfile::default();
@@ -136,8 +138,8 @@ fn test_policy_denies_specific_tool() {
         .policy_engine()
         .check("agent", "tool_call", "calculator");
     match decision {
-        PolicyDecision::Deny(reason) => {
-            assert!(reason.contains("calculator"));
+        PolicyDecision::Deny(denial) => {
+            assert!(denial.reason.contains("calculator"));
         }
         PolicyDecision::Allow => panic!("Expected deny for math"),
     }
@@ -153,6 +155,7 @@ fn test_policy_wildcard_allows_all_tools() {
         action: "tool_call".to_string(),
         resource: "*".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
 
     let profile = AgentProfile::default();
@@ -184,6 +187,7 @@ fn test_policy_first_match_wins() {
         action: "tool_call".to_string(),
         resource: "*".to_string(),
         effect: PolicyEffect::Deny,
+        alternative: None,
     });
 
     // Second rule: allow echo (this should never be reached)
@@ -192,6 +196,7 @@ fn test_policy_first_match_wins() {
         action: "tool_call".to_string(),
         resource: "echo".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
 
     let profile = AgentProfile::default();
@@ -219,12 +224,14 @@ fn test_policy_persistence_round_trip() {
         action: "tool_call".to_string(),
         resource: "echo".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
     engine.add_rule(PolicyRule {
         agent: "*".to_string(),
         action: "tool_call".to_string(),
         resource: "calculator".to_string(),
         effect: PolicyEffect::Deny,
+        alternative: None,
     });
 
     // Save to persistence
@@ -259,6 +266,7 @@ fn test_policy_reload_updates_agent() {
         action: "tool_call".to_string(),
         resource: "*".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
     engine.save_to_persistence(&persistence).unwrap();
 
@@ -290,6 +298,7 @@ fn test_policy_reload_updates_agent() {
         action: "tool_call".to_string(),
         resource: "*".to_string(),
         effect: PolicyEffect::Deny,
+        alternative: None,
     });
     new_engine.save_to_persistence(&persistence).unwrap();
 
@@ -315,12 +324,14 @@ fn test_policy_combined_with_profile_permissions() {
         action: "tool_call".to_string(),
         resource: "echo".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
     policy_engine.add_rule(PolicyRule {
         agent: "*".to_string(),
         action: "tool_call".to_string(),
         resource: "calculator".to_string(),
         effect: PolicyEffect::Allow,
+        alternative: None,
     });
 
     // Profile only allows echo (denies math)
@@ -356,3 +367,45 @@ fn test_policy_combined_with_profile_permissions() {
     assert!(agent.profile().is_tool_allowed("echo"));
     assert!(!agent.profile().is_tool_allowed("calculator")); // Profile denies
 }
+
+#[tokio::test]
+async fn test_policy_decisions_are_audited() {
+    let mut policy_engine = PolicyEngine::new();
+    policy_engine.add_rule(PolicyRule {
+        agent: "*".to_string(),
+        action: "tool_call".to_string(),
+        resource: "echo".to_string(),
+        effect: PolicyEffect::Allow,
+        alternative: None,
+    });
+    let (agent, _dir) =
+        create_test_agent_with_policy(Arc::new(policy_engine), AgentProfile::default());
+
+    agent
+        .run_tool("echo", serde_json::json!({"message": "hi"}))
+        .await
+        .unwrap();
+    assert!(agent
+        .run_tool("calculator", serde_json::json!({"expression": "1 + 1"}))
+        .await
+        .is_err());
+
+    let decisions = agent.persistence().policy_audit(None, 10).unwrap();
+    assert_eq!(decisions.len(), 2);
+    // Newest first
+    assert_eq!(decisions[0].resource, "calculator");
+    assert!(!decisions[0].allowed);
+    assert_eq!(decisions[0].rule, None);
+    assert_eq!(decisions[1].resource, "echo");
+    assert!(decisions[1].allowed);
+    assert_eq!(
+        decisions[1].rule.as_deref(),
+        Some("#1 allow tool_call on echo for *")
+    );
+    assert_eq!(decisions[1].agent, "policy-test");
+    assert_eq!(decisions[1].session_id, "test-session");
+    assert!(decisions[1].run_id.starts_with("repl-"));
+
+    let others = agent.persistence().policy_audit(Some("coder"), 10).unwrap();
+    assert!(others.is_empty());
+}